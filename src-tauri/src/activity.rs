@@ -0,0 +1,354 @@
+// Background activity tracking
+// One event stream for everything the backend is busy with (indexing, searching, archive
+// extraction, ...) so the UI can show a single progress center instead of listening to a
+// different event per feature. Backend code calls activity_start / activity_progress /
+// activity_finish; every change is emitted as "activity-updated" and the current list is
+// available through activity_list for windows that open mid-way.
+
+use crate::cancellation::{self, CancellationToken};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Progress updates within this interval are merged; the UI cannot show them anyway
+const PROGRESS_EMIT_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Finished activities kept for windows that mount after the work is done
+const MAX_FINISHED_ACTIVITIES: usize = 20;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum ActivityKind {
+    Indexing,
+    Search,
+    Archive,
+    Git,
+    Other,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub enum ActivityOutcome {
+    Succeeded,
+    Failed { message: String },
+    Cancelled,
+}
+
+impl ActivityOutcome {
+    pub fn from_result<T>(result: &Result<T, String>) -> Self {
+        match result {
+            Ok(_) => ActivityOutcome::Succeeded,
+            Err(message) => ActivityOutcome::Failed {
+                message: message.clone(),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Activity {
+    pub id: String,
+    pub kind: ActivityKind,
+    pub title: String,
+    pub cancellable: bool,
+    pub done: u64,
+    /// None while the amount of work is unknown
+    pub total: Option<u64>,
+    pub detail: Option<String>,
+    pub started_at: i64,
+    /// None while the activity is running
+    pub outcome: Option<ActivityOutcome>,
+    pub finished_at: Option<i64>,
+}
+
+struct TrackedActivity {
+    activity: Activity,
+    last_emit: Option<Instant>,
+}
+
+#[derive(Default)]
+pub struct ActivityRegistry {
+    activities: HashMap<String, TrackedActivity>,
+    /// Ids of finished activities, oldest first
+    finished: Vec<String>,
+}
+
+impl ActivityRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn start(
+        &mut self,
+        id: String,
+        kind: ActivityKind,
+        title: String,
+        cancellable: bool,
+    ) -> Activity {
+        let activity = Activity {
+            id: id.clone(),
+            kind,
+            title,
+            cancellable,
+            done: 0,
+            total: None,
+            detail: None,
+            started_at: chrono::Utc::now().timestamp_millis(),
+            outcome: None,
+            finished_at: None,
+        };
+        self.activities.insert(
+            id,
+            TrackedActivity {
+                activity: activity.clone(),
+                last_emit: Some(Instant::now()),
+            },
+        );
+        activity
+    }
+
+    /// Record progress. Returns the updated activity when it should be emitted: not more
+    /// than once per PROGRESS_EMIT_INTERVAL, except that completion is always emitted.
+    /// Progress for unknown or finished activities is ignored.
+    pub fn progress(
+        &mut self,
+        id: &str,
+        done: u64,
+        total: Option<u64>,
+        detail: Option<String>,
+    ) -> Option<Activity> {
+        let tracked = self.activities.get_mut(id)?;
+        if tracked.activity.outcome.is_some() {
+            return None;
+        }
+        tracked.activity.done = done;
+        tracked.activity.total = total;
+        if detail.is_some() {
+            tracked.activity.detail = detail;
+        }
+        let complete = total.is_some_and(|total| done >= total);
+        let due = tracked
+            .last_emit
+            .is_none_or(|last| last.elapsed() >= PROGRESS_EMIT_INTERVAL);
+        if !complete && !due {
+            return None;
+        }
+        tracked.last_emit = Some(Instant::now());
+        Some(tracked.activity.clone())
+    }
+
+    /// Mark an activity finished. Finishing twice is an error and keeps the first outcome.
+    pub fn finish(&mut self, id: &str, outcome: ActivityOutcome) -> Result<Activity, String> {
+        let tracked = self
+            .activities
+            .get_mut(id)
+            .ok_or_else(|| format!("Unknown activity: {}", id))?;
+        if tracked.activity.outcome.is_some() {
+            return Err(format!("Activity already finished: {}", id));
+        }
+        tracked.activity.outcome = Some(outcome);
+        tracked.activity.finished_at = Some(chrono::Utc::now().timestamp_millis());
+        let activity = tracked.activity.clone();
+
+        self.finished.push(id.to_string());
+        while self.finished.len() > MAX_FINISHED_ACTIVITIES {
+            let oldest = self.finished.remove(0);
+            self.activities.remove(&oldest);
+        }
+        Ok(activity)
+    }
+
+    /// Running activities and recently finished ones, oldest first
+    pub fn list(&self) -> Vec<Activity> {
+        let mut activities: Vec<Activity> = self
+            .activities
+            .values()
+            .map(|tracked| tracked.activity.clone())
+            .collect();
+        activities.sort_by(|a, b| a.started_at.cmp(&b.started_at).then(a.id.cmp(&b.id)));
+        activities
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref REGISTRY: Mutex<ActivityRegistry> = Mutex::new(ActivityRegistry::new());
+}
+
+static NEXT_ACTIVITY_ID: AtomicU64 = AtomicU64::new(1);
+
+fn emit(activity: &Activity) {
+    // No app handle in unit tests; the registry is still updated
+    let Some(app_handle) = crate::try_get_app_handle() else {
+        return;
+    };
+    if let Err(e) = crate::events::emit_activity_updated(app_handle, activity) {
+        log::error!("Failed to emit activity update: {}", e);
+    }
+}
+
+/// Start tracking an activity and return its id. Cancellable activities get a token in
+/// the cancellation registry under the same id (see `cancellation_token`).
+pub fn activity_start(kind: ActivityKind, title: impl Into<String>, cancellable: bool) -> String {
+    let id = format!(
+        "activity-{}",
+        NEXT_ACTIVITY_ID.fetch_add(1, Ordering::Relaxed)
+    );
+    if cancellable {
+        cancellation::register(&id);
+    }
+    let activity = REGISTRY
+        .lock()
+        .unwrap()
+        .start(id.clone(), kind, title.into(), cancellable);
+    log::info!("Activity {} started: {}", id, activity.title);
+    emit(&activity);
+    id
+}
+
+pub fn activity_progress(id: &str, done: u64, total: Option<u64>, detail: Option<String>) {
+    let update = REGISTRY.lock().unwrap().progress(id, done, total, detail);
+    if let Some(activity) = update {
+        emit(&activity);
+    }
+}
+
+pub fn activity_finish(id: &str, outcome: ActivityOutcome) {
+    cancellation::unregister(id);
+    let result = REGISTRY.lock().unwrap().finish(id, outcome);
+    match result {
+        Ok(activity) => {
+            log::info!("Activity {} finished: {:?}", id, activity.outcome);
+            emit(&activity);
+        }
+        Err(e) => log::warn!("{}", e),
+    }
+}
+
+/// Token a cancellable activity polls; None for activities started as not cancellable
+pub fn cancellation_token(id: &str) -> Option<CancellationToken> {
+    cancellation::token(id)
+}
+
+#[tauri::command]
+pub fn activity_list() -> Vec<Activity> {
+    REGISTRY.lock().unwrap().list()
+}
+
+/// Ask a cancellable activity to stop; it finishes as cancelled once the work notices.
+/// Async so it is not queued behind synchronous commands on the main thread.
+#[tauri::command]
+pub async fn activity_cancel(id: String) -> Result<bool, String> {
+    let cancelled = cancellation::cancel(&id);
+    if cancelled {
+        log::info!("Cancellation requested for activity {}", id);
+    }
+    Ok(cancelled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn start(registry: &mut ActivityRegistry, id: &str) -> Activity {
+        registry.start(
+            id.to_string(),
+            ActivityKind::Indexing,
+            "Indexing 3 files".to_string(),
+            false,
+        )
+    }
+
+    #[test]
+    fn test_activity_lifecycle() {
+        let mut registry = ActivityRegistry::new();
+        let started = start(&mut registry, "a");
+        assert_eq!(started.outcome, None);
+        assert_eq!(registry.list(), vec![started]);
+
+        // Started just now, so intermediate progress is merged until the interval passes
+        assert_eq!(registry.progress("a", 1, Some(3), None), None);
+        let listed = &registry.list()[0];
+        assert_eq!((listed.done, listed.total), (1, Some(3)));
+
+        // Completion is emitted immediately and keeps the last detail
+        let complete = registry
+            .progress("a", 3, Some(3), Some("src/lib.rs".to_string()))
+            .unwrap();
+        assert_eq!(complete.detail.as_deref(), Some("src/lib.rs"));
+        assert_eq!(
+            registry.progress("a", 3, Some(3), None).unwrap().detail,
+            complete.detail
+        );
+
+        let finished = registry.finish("a", ActivityOutcome::Succeeded).unwrap();
+        assert_eq!(finished.outcome, Some(ActivityOutcome::Succeeded));
+        assert!(finished.finished_at.is_some());
+        assert_eq!(registry.progress("a", 4, Some(3), None), None);
+        assert_eq!(registry.list()[0].done, 3);
+    }
+
+    #[test]
+    fn test_duplicate_and_unknown_finish() {
+        let mut registry = ActivityRegistry::new();
+        start(&mut registry, "a");
+        registry.finish("a", ActivityOutcome::Cancelled).unwrap();
+        let err = registry
+            .finish(
+                "a",
+                ActivityOutcome::Failed {
+                    message: "late".to_string(),
+                },
+            )
+            .unwrap_err();
+        assert!(err.contains("already finished"), "{}", err);
+        assert_eq!(registry.list()[0].outcome, Some(ActivityOutcome::Cancelled));
+        assert!(registry
+            .finish("missing", ActivityOutcome::Succeeded)
+            .is_err());
+        assert_eq!(registry.progress("missing", 1, None, None), None);
+    }
+
+    #[test]
+    fn test_finished_activities_are_pruned() {
+        let mut registry = ActivityRegistry::new();
+        start(&mut registry, "running");
+        for i in 0..MAX_FINISHED_ACTIVITIES + 5 {
+            let id = format!("done-{}", i);
+            start(&mut registry, &id);
+            registry.finish(&id, ActivityOutcome::Succeeded).unwrap();
+        }
+        let listed = registry.list();
+        assert_eq!(listed.len(), MAX_FINISHED_ACTIVITIES + 1);
+        assert!(listed.iter().any(|a| a.id == "running"));
+        assert!(!listed.iter().any(|a| a.id == "done-0"));
+    }
+
+    #[test]
+    fn test_cancellable_activity_uses_cancellation_registry() {
+        let id = activity_start(ActivityKind::Search, "Searching for \"todo\"", true);
+        let token = cancellation_token(&id).unwrap();
+        assert!(!token.is_cancelled());
+        assert!(cancellation::cancel(&id));
+        assert!(token.is_cancelled());
+
+        activity_finish(&id, ActivityOutcome::Cancelled);
+        assert!(cancellation_token(&id).is_none());
+        assert!(!cancellation::cancel(&id));
+
+        let plain = activity_start(ActivityKind::Archive, "Extracting skill", false);
+        assert!(cancellation_token(&plain).is_none());
+        activity_finish(&plain, ActivityOutcome::Succeeded);
+    }
+
+    #[test]
+    fn test_outcome_serialization() {
+        let failed = ActivityOutcome::from_result::<()>(&Err("boom".to_string()));
+        let json = serde_json::to_value(&failed).unwrap();
+        assert_eq!(json["status"], "failed");
+        assert_eq!(json["message"], "boom");
+    }
+}
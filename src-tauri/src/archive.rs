@@ -1,6 +1,7 @@
 // Archive operations for skill package management
 // Provides tar.gz creation and extraction functionality
 
+use crate::skill_manifest::{self, ManifestError, SkillManifest};
 use flate2::read::GzDecoder;
 use flate2::write::GzEncoder;
 use flate2::Compression;
@@ -36,6 +37,9 @@ pub struct ExtractTarballResult {
     pub dest_dir: String,
     pub files_extracted: usize,
     pub error: Option<String>,
+    /// Validated skill.json of the extracted skill, shown for consent before first run
+    pub manifest: Option<SkillManifest>,
+    pub manifest_error: Option<ManifestError>,
 }
 
 /// Create a tar.gz archive from a directory
@@ -120,6 +124,8 @@ pub fn extract_tarball(request: ExtractTarballRequest) -> Result<ExtractTarballR
             dest_dir: request.dest_dir,
             files_extracted: 0,
             error: Some(format!("Tarball does not exist: {}", request.tarball_path)),
+            manifest: None,
+            manifest_error: None,
         });
     }
 
@@ -184,6 +190,8 @@ pub fn extract_tarball(request: ExtractTarballRequest) -> Result<ExtractTarballR
                     "Security: Path traversal detected in archive entry: {}",
                     entry_path.display()
                 )),
+                manifest: None,
+                manifest_error: None,
             });
         }
 
@@ -195,11 +203,18 @@ pub fn extract_tarball(request: ExtractTarballRequest) -> Result<ExtractTarballR
         files_extracted += 1;
     }
 
+    let (manifest, manifest_error) = match skill_manifest::load_manifest(dest_dir) {
+        Ok(manifest) => (Some(manifest), None),
+        Err(e) => (None, Some(e)),
+    };
+
     Ok(ExtractTarballResult {
         success: true,
         dest_dir: request.dest_dir,
         files_extracted,
         error: None,
+        manifest,
+        manifest_error,
     })
 }
 
@@ -256,6 +271,47 @@ mod tests {
 
         let content1 = fs::read_to_string(dest_dir.join("file1.txt")).unwrap();
         assert_eq!(content1, "Hello, World!");
+
+        // No skill.json in the package
+        assert!(extract_result.manifest.is_none());
+        assert!(matches!(
+            extract_result.manifest_error,
+            Some(ManifestError::Missing { .. })
+        ));
+    }
+
+    #[test]
+    fn test_extract_surfaces_skill_manifest() {
+        let temp_dir = TempDir::new().unwrap();
+        let source_dir = temp_dir.path().join("source");
+        let tarball_path = temp_dir.path().join("skill.tar.gz");
+        fs::create_dir_all(&source_dir).unwrap();
+        fs::write(source_dir.join("run.sh"), b"echo hi\n").unwrap();
+        fs::write(
+            source_dir.join("skill.json"),
+            br#"{"name": "greeter", "version": "0.1.0", "entrypoint": "run.sh",
+                "runtime": "bash", "capabilities": ["network"]}"#,
+        )
+        .unwrap();
+        create_tarball(CreateTarballRequest {
+            source_dir: source_dir.to_string_lossy().to_string(),
+            output_path: tarball_path.to_string_lossy().to_string(),
+        })
+        .unwrap();
+
+        let result = extract_tarball(ExtractTarballRequest {
+            tarball_path: tarball_path.to_string_lossy().to_string(),
+            dest_dir: temp_dir.path().join("dest").to_string_lossy().to_string(),
+        })
+        .unwrap();
+        assert!(result.success);
+        assert!(result.manifest_error.is_none());
+        let manifest = result.manifest.unwrap();
+        assert_eq!(manifest.name, "greeter");
+        assert_eq!(
+            manifest.capabilities,
+            vec![skill_manifest::Capability::Network]
+        );
     }
 
     #[test]
@@ -0,0 +1,662 @@
+// Read-only archive browsing
+// Lets the editor show the contents of a .tar / .tar.gz as a virtual tree and open entries
+// without extracting anything to disk. A one-off read streams the archive up to the entry;
+// an open session scans the entry table once and remembers where each entry's data starts,
+// keeping small archives decompressed in memory so later reads are slices.
+
+use base64::Engine;
+use flate2::read::GzDecoder;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufReader, Read, Seek, SeekFrom};
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Instant, SystemTime};
+use tar::{Archive, EntryType};
+
+/// Default cap on bytes returned for one entry
+const DEFAULT_MAX_ENTRY_BYTES: u64 = 1024 * 1024;
+
+/// Archives whose decompressed tar stream fits are kept in memory by their session
+const SESSION_MEMORY_LIMIT: usize = 64 * 1024 * 1024;
+
+/// Open sessions beyond this evict the least recently opened one
+const MAX_SESSIONS: usize = 8;
+
+/// Bytes inspected for NUL when deciding whether content is binary
+const BINARY_SNIFF_BYTES: usize = 8192;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveEntryInfo {
+    /// Normalized path inside the archive ("logs/app.log", no leading "./")
+    pub path: String,
+    pub size: u64,
+    /// "file", "directory", "symlink" or "other"
+    pub kind: String,
+    pub link_target: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveSessionInfo {
+    pub session_id: String,
+    pub tarball_path: String,
+    pub entries: Vec<ArchiveEntryInfo>,
+    /// Entries skipped because their paths are absolute or contain ".."
+    pub rejected_entries: Vec<String>,
+    /// Whether the decompressed archive is held in memory
+    pub in_memory: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveEntryContent {
+    pub entry_path: String,
+    /// Full size of the entry in the archive
+    pub size: u64,
+    /// Text, or base64 when `encoding` is "base64"
+    pub content: String,
+    /// "utf-8", "utf-16le", "utf-16be", "latin1" or "base64"
+    pub encoding: String,
+    pub is_binary: bool,
+    /// True when only the first `max_bytes` of the entry were read
+    pub truncated: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Compression {
+    None,
+    Gzip,
+}
+
+#[derive(Debug, Clone)]
+struct EntryLocation {
+    info: ArchiveEntryInfo,
+    /// Offset of the entry's data in the (decompressed) tar stream
+    data_offset: u64,
+}
+
+struct ArchiveSession {
+    tarball_path: PathBuf,
+    compression: Compression,
+    /// Size and mtime at scan time; a changed archive invalidates the offsets
+    fingerprint: (u64, Option<SystemTime>),
+    entries: HashMap<String, EntryLocation>,
+    data: Option<Arc<Vec<u8>>>,
+    opened_at: Instant,
+}
+
+lazy_static::lazy_static! {
+    static ref SESSIONS: Mutex<HashMap<String, ArchiveSession>> = Mutex::new(HashMap::new());
+}
+
+static NEXT_SESSION_ID: AtomicU64 = AtomicU64::new(1);
+
+fn fingerprint(path: &Path) -> io::Result<(u64, Option<SystemTime>)> {
+    let metadata = std::fs::metadata(path)?;
+    Ok((metadata.len(), metadata.modified().ok()))
+}
+
+fn detect_compression(path: &Path) -> io::Result<Compression> {
+    let mut magic = [0u8; 2];
+    let read = File::open(path)?.read(&mut magic)?;
+    Ok(if read == 2 && magic == [0x1f, 0x8b] {
+        Compression::Gzip
+    } else {
+        Compression::None
+    })
+}
+
+fn open_stream(path: &Path, compression: Compression) -> io::Result<Box<dyn Read>> {
+    let file = BufReader::new(File::open(path)?);
+    Ok(match compression {
+        Compression::None => Box::new(file),
+        Compression::Gzip => Box::new(GzDecoder::new(file)),
+    })
+}
+
+/// Normalize an entry path: drop "." components and surrounding slashes. Returns None for
+/// absolute paths and paths containing "..", which could escape a destination directory.
+fn normalize_entry_path(path: &Path) -> Option<String> {
+    let mut parts = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => parts.push(part.to_string_lossy().to_string()),
+            Component::CurDir => {}
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+    Some(parts.join("/"))
+}
+
+fn entry_kind(entry_type: EntryType) -> &'static str {
+    match entry_type {
+        EntryType::Regular | EntryType::Continuous | EntryType::GNUSparse => "file",
+        EntryType::Directory => "directory",
+        EntryType::Symlink | EntryType::Link => "symlink",
+        _ => "other",
+    }
+}
+
+/// Passes reads through while keeping a copy, until the copy would exceed `limit`
+struct CapturingReader<R> {
+    inner: R,
+    captured: Option<Vec<u8>>,
+    limit: usize,
+}
+
+impl<R: Read> Read for CapturingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        if let Some(ref mut captured) = self.captured {
+            if captured.len() + read > self.limit {
+                self.captured = None;
+            } else {
+                captured.extend_from_slice(&buf[..read]);
+            }
+        }
+        Ok(read)
+    }
+}
+
+/// Decode entry bytes for display: BOM-marked UTF-16/UTF-8, plain UTF-8, base64 for
+/// binary content (NUL bytes), and Latin-1 for anything else
+fn decode_content(bytes: &[u8], truncated: bool) -> (String, &'static str, bool) {
+    if let Some(rest) = bytes.strip_prefix(&[0xEF, 0xBB, 0xBF]) {
+        return (String::from_utf8_lossy(rest).to_string(), "utf-8", false);
+    }
+    let utf16 = |rest: &[u8], little_endian: bool| -> String {
+        let units: Vec<u16> = rest
+            .chunks_exact(2)
+            .map(|pair| {
+                if little_endian {
+                    u16::from_le_bytes([pair[0], pair[1]])
+                } else {
+                    u16::from_be_bytes([pair[0], pair[1]])
+                }
+            })
+            .collect();
+        String::from_utf16_lossy(&units)
+    };
+    if let Some(rest) = bytes.strip_prefix(&[0xFF, 0xFE]) {
+        return (utf16(rest, true), "utf-16le", false);
+    }
+    if let Some(rest) = bytes.strip_prefix(&[0xFE, 0xFF]) {
+        return (utf16(rest, false), "utf-16be", false);
+    }
+
+    if bytes[..bytes.len().min(BINARY_SNIFF_BYTES)].contains(&0) {
+        let encoded = base64::engine::general_purpose::STANDARD.encode(bytes);
+        return (encoded, "base64", true);
+    }
+    match std::str::from_utf8(bytes) {
+        Ok(text) => (text.to_string(), "utf-8", false),
+        // A multi-byte character cut off by the byte cap is still UTF-8
+        Err(e) if truncated && e.error_len().is_none() => (
+            String::from_utf8_lossy(&bytes[..e.valid_up_to()]).to_string(),
+            "utf-8",
+            false,
+        ),
+        Err(_) => (bytes.iter().map(|&b| b as char).collect(), "latin1", false),
+    }
+}
+
+fn entry_content(entry_path: &str, size: u64, bytes: Vec<u8>) -> ArchiveEntryContent {
+    let truncated = size > bytes.len() as u64;
+    let (content, encoding, is_binary) = decode_content(&bytes, truncated);
+    ArchiveEntryContent {
+        entry_path: entry_path.to_string(),
+        size,
+        content,
+        encoding: encoding.to_string(),
+        is_binary,
+        truncated,
+    }
+}
+
+fn check_readable(info: &ArchiveEntryInfo) -> Result<(), String> {
+    match info.kind.as_str() {
+        "file" => Ok(()),
+        "symlink" => Err(format!(
+            "{} is a link to {}",
+            info.path,
+            info.link_target.as_deref().unwrap_or("?")
+        )),
+        kind => Err(format!("{} is a {}, not a file", info.path, kind)),
+    }
+}
+
+fn requested_path(entry_path: &str) -> Result<String, String> {
+    normalize_entry_path(Path::new(entry_path))
+        .filter(|path| !path.is_empty())
+        .ok_or_else(|| format!("Invalid archive entry path: {}", entry_path))
+}
+
+/// Read one entry by streaming the archive up to it
+pub fn read_entry_streaming(
+    tarball_path: &Path,
+    entry_path: &str,
+    max_bytes: u64,
+) -> Result<ArchiveEntryContent, String> {
+    let wanted = requested_path(entry_path)?;
+    let compression =
+        detect_compression(tarball_path).map_err(|e| format!("Failed to open archive: {}", e))?;
+    let stream = open_stream(tarball_path, compression)
+        .map_err(|e| format!("Failed to open archive: {}", e))?;
+    let mut archive = Archive::new(stream);
+
+    for entry in archive
+        .entries()
+        .map_err(|e| format!("Failed to read archive entries: {}", e))?
+    {
+        let entry = entry.map_err(|e| format!("Failed to read entry: {}", e))?;
+        let path = entry
+            .path()
+            .map_err(|e| format!("Failed to get entry path: {}", e))?;
+        if normalize_entry_path(&path).as_deref() != Some(wanted.as_str()) {
+            continue;
+        }
+        let info = entry_info(&entry, wanted.clone());
+        check_readable(&info)?;
+        let mut bytes = Vec::new();
+        entry
+            .take(max_bytes)
+            .read_to_end(&mut bytes)
+            .map_err(|e| format!("Failed to read {}: {}", wanted, e))?;
+        return Ok(entry_content(&wanted, info.size, bytes));
+    }
+    Err(format!("Entry not found in archive: {}", entry_path))
+}
+
+fn entry_info<R: Read>(entry: &tar::Entry<R>, path: String) -> ArchiveEntryInfo {
+    let link_target = entry
+        .link_name()
+        .ok()
+        .flatten()
+        .map(|target| target.to_string_lossy().to_string());
+    ArchiveEntryInfo {
+        path,
+        size: entry.size(),
+        kind: entry_kind(entry.header().entry_type()).to_string(),
+        link_target,
+    }
+}
+
+/// Scan the entry table. Entries whose paths could escape a destination are rejected.
+fn scan_archive(
+    tarball_path: &Path,
+    memory_limit: usize,
+) -> Result<(ArchiveSession, Vec<String>), String> {
+    let compression =
+        detect_compression(tarball_path).map_err(|e| format!("Failed to open archive: {}", e))?;
+    let fingerprint =
+        fingerprint(tarball_path).map_err(|e| format!("Failed to open archive: {}", e))?;
+    let stream = open_stream(tarball_path, compression)
+        .map_err(|e| format!("Failed to open archive: {}", e))?;
+    let mut archive = Archive::new(CapturingReader {
+        inner: stream,
+        captured: Some(Vec::new()),
+        limit: memory_limit,
+    });
+
+    let mut entries = HashMap::new();
+    let mut rejected = Vec::new();
+    for entry in archive
+        .entries()
+        .map_err(|e| format!("Failed to read archive entries: {}", e))?
+    {
+        let entry = entry.map_err(|e| format!("Failed to read entry: {}", e))?;
+        let raw_path = entry
+            .path()
+            .map_err(|e| format!("Failed to get entry path: {}", e))?
+            .to_path_buf();
+        let Some(path) = normalize_entry_path(&raw_path).filter(|p| !p.is_empty()) else {
+            if !raw_path.as_os_str().is_empty() && raw_path != Path::new(".") {
+                log::warn!("Rejected archive entry path: {}", raw_path.display());
+                rejected.push(raw_path.to_string_lossy().to_string());
+            }
+            continue;
+        };
+        let location = EntryLocation {
+            info: entry_info(&entry, path.clone()),
+            data_offset: entry.raw_file_position(),
+        };
+        entries.insert(path, location);
+    }
+
+    // Drain the remainder (end-of-archive padding) so the capture is the whole stream
+    let mut reader = archive.into_inner();
+    io::copy(&mut reader, &mut io::sink()).map_err(|e| format!("Failed to read archive: {}", e))?;
+
+    Ok((
+        ArchiveSession {
+            tarball_path: tarball_path.to_path_buf(),
+            compression,
+            fingerprint,
+            entries,
+            data: reader.captured.map(Arc::new),
+            opened_at: Instant::now(),
+        },
+        rejected,
+    ))
+}
+
+fn read_from_session(
+    session: &SessionReadPlan,
+    max_bytes: u64,
+) -> Result<ArchiveEntryContent, String> {
+    let location = &session.location;
+    let wanted = location.info.size.min(max_bytes);
+    let offset = location.data_offset;
+    let read_error = |e: io::Error| format!("Failed to read {}: {}", location.info.path, e);
+
+    let bytes = match (&session.data, session.compression) {
+        (Some(data), _) => {
+            let start = (offset as usize).min(data.len());
+            let end = (start + wanted as usize).min(data.len());
+            data[start..end].to_vec()
+        }
+        (None, Compression::None) => {
+            let mut file = File::open(&session.tarball_path).map_err(read_error)?;
+            file.seek(SeekFrom::Start(offset)).map_err(read_error)?;
+            let mut bytes = Vec::new();
+            file.take(wanted)
+                .read_to_end(&mut bytes)
+                .map_err(read_error)?;
+            bytes
+        }
+        (None, Compression::Gzip) => {
+            // Deflate streams cannot seek; decompress up to the entry and discard
+            let mut stream =
+                open_stream(&session.tarball_path, Compression::Gzip).map_err(read_error)?;
+            io::copy(&mut (&mut stream).take(offset), &mut io::sink()).map_err(read_error)?;
+            let mut bytes = Vec::new();
+            stream
+                .take(wanted)
+                .read_to_end(&mut bytes)
+                .map_err(read_error)?;
+            bytes
+        }
+    };
+    Ok(entry_content(
+        &location.info.path,
+        location.info.size,
+        bytes,
+    ))
+}
+
+/// What a read needs from a session, copied out so the lock is not held during I/O
+struct SessionReadPlan {
+    tarball_path: PathBuf,
+    compression: Compression,
+    data: Option<Arc<Vec<u8>>>,
+    location: EntryLocation,
+}
+
+fn canonical(path: &str) -> PathBuf {
+    Path::new(path)
+        .canonicalize()
+        .unwrap_or_else(|_| PathBuf::from(path))
+}
+
+/// Plan a read through an open session on the archive, if there is a current one.
+/// Sessions for archives that changed on disk since they were scanned are dropped.
+fn session_plan(tarball_path: &Path, entry_path: &str) -> Option<Result<SessionReadPlan, String>> {
+    let current = fingerprint(tarball_path).ok()?;
+    let mut sessions = SESSIONS.lock().unwrap();
+    let stale: Vec<String> = sessions
+        .iter()
+        .filter(|(_, s)| s.tarball_path == tarball_path && s.fingerprint != current)
+        .map(|(id, _)| id.clone())
+        .collect();
+    for id in stale {
+        log::info!("Archive changed on disk, dropping session {}", id);
+        sessions.remove(&id);
+    }
+    let session = sessions.values().find(|s| s.tarball_path == tarball_path)?;
+
+    let plan = requested_path(entry_path).and_then(|wanted| {
+        let location = session
+            .entries
+            .get(&wanted)
+            .ok_or_else(|| format!("Entry not found in archive: {}", entry_path))?;
+        check_readable(&location.info)?;
+        Ok(SessionReadPlan {
+            tarball_path: session.tarball_path.clone(),
+            compression: session.compression,
+            data: session.data.clone(),
+            location: location.clone(),
+        })
+    });
+    Some(plan)
+}
+
+/// Read an entry, through an open session on the archive when there is one
+pub fn read_entry(
+    tarball_path: &str,
+    entry_path: &str,
+    max_bytes: Option<u64>,
+) -> Result<ArchiveEntryContent, String> {
+    let max_bytes = max_bytes.unwrap_or(DEFAULT_MAX_ENTRY_BYTES);
+    let path = canonical(tarball_path);
+    match session_plan(&path, entry_path) {
+        Some(plan) => read_from_session(&plan?, max_bytes),
+        None => read_entry_streaming(&path, entry_path, max_bytes),
+    }
+}
+
+fn open_session_with_limit(
+    tarball_path: &str,
+    memory_limit: usize,
+) -> Result<ArchiveSessionInfo, String> {
+    let path = canonical(tarball_path);
+    let start = Instant::now();
+    let (session, rejected_entries) = scan_archive(&path, memory_limit)?;
+
+    let mut entries: Vec<ArchiveEntryInfo> =
+        session.entries.values().map(|l| l.info.clone()).collect();
+    entries.sort_by(|a, b| a.path.cmp(&b.path));
+    let in_memory = session.data.is_some();
+    let session_id = format!(
+        "archive-{}",
+        NEXT_SESSION_ID.fetch_add(1, Ordering::Relaxed)
+    );
+    log::info!(
+        "Opened archive session {} for {} ({} entries, in memory: {}) in {}ms",
+        session_id,
+        tarball_path,
+        entries.len(),
+        in_memory,
+        start.elapsed().as_millis()
+    );
+
+    let mut sessions = SESSIONS.lock().unwrap();
+    while sessions.len() >= MAX_SESSIONS {
+        let Some(oldest) = sessions
+            .iter()
+            .min_by_key(|(_, s)| s.opened_at)
+            .map(|(id, _)| id.clone())
+        else {
+            break;
+        };
+        sessions.remove(&oldest);
+    }
+    sessions.insert(session_id.clone(), session);
+
+    Ok(ArchiveSessionInfo {
+        session_id,
+        tarball_path: tarball_path.to_string(),
+        entries,
+        rejected_entries,
+        in_memory,
+    })
+}
+
+#[tauri::command]
+pub async fn archive_open_session(tarball_path: String) -> Result<ArchiveSessionInfo, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        open_session_with_limit(&tarball_path, SESSION_MEMORY_LIMIT)
+    })
+    .await
+    .map_err(|e| format!("Archive scan failed: {}", e))?
+}
+
+#[tauri::command]
+pub async fn archive_read_entry(
+    tarball_path: String,
+    entry_path: String,
+    max_bytes: Option<u64>,
+) -> Result<ArchiveEntryContent, String> {
+    tauri::async_runtime::spawn_blocking(move || read_entry(&tarball_path, &entry_path, max_bytes))
+        .await
+        .map_err(|e| format!("Archive read failed: {}", e))?
+}
+
+/// Returns false when the session was already closed or evicted
+#[tauri::command]
+pub fn archive_close_session(session_id: String) -> bool {
+    SESSIONS.lock().unwrap().remove(&session_id).is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use tempfile::TempDir;
+
+    const BINARY: &[u8] = b"\x89PNG\r\n\x1a\n\x00\x00\x00\rIHDR";
+
+    fn append(builder: &mut tar::Builder<impl io::Write>, path: &str, data: &[u8]) {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o644);
+        header.set_entry_type(EntryType::Regular);
+        // Written directly so test archives can carry paths tar::Builder refuses
+        let name = &mut header.as_old_mut().name;
+        name[..path.len()].copy_from_slice(path.as_bytes());
+        header.set_cksum();
+        builder.append(&header, data).unwrap();
+    }
+
+    fn build_archive<W: io::Write>(writer: W) -> W {
+        let mut builder = tar::Builder::new(writer);
+        append(&mut builder, "./README.md", b"# Bundle\n");
+        append(
+            &mut builder,
+            "./logs/app/2024/server.log",
+            "started\nerror: caf\u{e9} closed\n".as_bytes(),
+        );
+        append(&mut builder, "./bin/logo.png", BINARY);
+        append(&mut builder, "./config/legacy.ini", b"name=caf\xe9\n");
+        append(&mut builder, "../evil.txt", b"escaped");
+        builder.into_inner().unwrap()
+    }
+
+    fn write_gzip(dir: &Path) -> String {
+        let path = dir.join("bundle.tar.gz");
+        let encoder = build_archive(GzEncoder::new(
+            File::create(&path).unwrap(),
+            flate2::Compression::default(),
+        ));
+        encoder.finish().unwrap();
+        path.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn test_read_entries_without_session() {
+        let temp_dir = TempDir::new().unwrap();
+        let tarball = write_gzip(temp_dir.path());
+
+        let log = read_entry(&tarball, "logs/app/2024/server.log", None).unwrap();
+        assert_eq!(log.content, "started\nerror: caf\u{e9} closed\n");
+        assert_eq!(log.encoding, "utf-8");
+        assert!(!log.truncated);
+
+        let binary = read_entry(&tarball, "./bin/logo.png", None).unwrap();
+        assert!(binary.is_binary);
+        assert_eq!(binary.encoding, "base64");
+        assert_eq!(
+            base64::engine::general_purpose::STANDARD
+                .decode(&binary.content)
+                .unwrap(),
+            BINARY
+        );
+
+        let legacy = read_entry(&tarball, "config/legacy.ini", None).unwrap();
+        assert_eq!(
+            (legacy.encoding.as_str(), legacy.content.as_str()),
+            ("latin1", "name=caf\u{e9}\n")
+        );
+
+        // Cut inside the two-byte "é": still UTF-8, reported as truncated
+        let cut = read_entry(&tarball, "logs/app/2024/server.log", Some(19)).unwrap();
+        assert!(cut.truncated);
+        assert_eq!(
+            (cut.encoding.as_str(), cut.content.as_str()),
+            ("utf-8", "started\nerror: caf")
+        );
+        assert_eq!(cut.size, 28);
+
+        assert!(read_entry(&tarball, "../evil.txt", None)
+            .unwrap_err()
+            .contains("Invalid archive entry path"));
+        assert!(read_entry(&tarball, "evil.txt", None)
+            .unwrap_err()
+            .contains("not found"));
+    }
+
+    #[test]
+    fn test_sessions_read_from_memory_seek_and_gzip_skip() {
+        let temp_dir = TempDir::new().unwrap();
+        let gzip = write_gzip(temp_dir.path());
+        let plain_path = temp_dir.path().join("bundle.tar");
+        build_archive(File::create(&plain_path).unwrap());
+        let plain = plain_path.to_string_lossy().to_string();
+
+        for (tarball, limit, in_memory) in [
+            (&gzip, SESSION_MEMORY_LIMIT, true),
+            (&plain, 0, false),
+            (&gzip, 0, false),
+        ] {
+            let info = open_session_with_limit(tarball, limit).unwrap();
+            assert_eq!(info.in_memory, in_memory);
+            assert_eq!(info.rejected_entries, vec!["../evil.txt"]);
+            let paths: Vec<&str> = info.entries.iter().map(|e| e.path.as_str()).collect();
+            assert_eq!(
+                paths,
+                vec![
+                    "README.md",
+                    "bin/logo.png",
+                    "config/legacy.ini",
+                    "logs/app/2024/server.log"
+                ]
+            );
+
+            let log = read_entry(tarball, "logs/app/2024/server.log", None).unwrap();
+            assert_eq!(log.content, "started\nerror: caf\u{e9} closed\n");
+            let binary = read_entry(tarball, "bin/logo.png", Some(10)).unwrap();
+            assert!(binary.truncated);
+            assert_eq!(
+                base64::engine::general_purpose::STANDARD
+                    .decode(&binary.content)
+                    .unwrap(),
+                &BINARY[..10]
+            );
+            assert!(read_entry(tarball, "missing.txt", None).is_err());
+
+            assert!(archive_close_session(info.session_id.clone()));
+            assert!(!archive_close_session(info.session_id));
+        }
+    }
+
+    #[test]
+    fn test_decode_content_boms() {
+        let (text, encoding, _) = decode_content(b"\xff\xfeh\x00i\x00", false);
+        assert_eq!((text.as_str(), encoding), ("hi", "utf-16le"));
+        let (text, encoding, _) = decode_content(b"\xef\xbb\xbfhi", false);
+        assert_eq!((text.as_str(), encoding), ("hi", "utf-8"));
+    }
+}
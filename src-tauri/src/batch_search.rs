@@ -0,0 +1,559 @@
+// Batch search module
+// Answers several content, file-name and glob queries with one walk of the tree. The agent
+// explores in parallel and used to issue each query as its own command, walking and reading
+// the same files once per query. Here every file is visited once, applicable queries are
+// evaluated against it, and content queries share a single read of its bytes.
+//
+// One walk means one set of walk rules: hidden entries and .gitignore'd paths are skipped
+// for every query, so results can differ from the single-query commands in trees relying
+// on those (e.g. file-name search normally also looks inside .github).
+
+use crate::concurrency::{self, OperationClass};
+use crate::exclusions::LiveExclusions;
+use crate::file_search::{FileSearchResult, HighPerformanceFileSearch};
+use crate::glob::{self, GlobResult, HighPerformanceGlob};
+use crate::path_access;
+use crate::path_wire;
+use crate::search::{RipgrepSearch, SearchResult};
+use grep::regex::RegexMatcher;
+use ignore::WalkBuilder;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+
+/// Upper bound on queries per batch
+pub const MAX_BATCH_QUERIES: usize = 32;
+/// Default caps, matching the single-query commands
+const DEFAULT_CONTENT_RESULTS: usize = 50;
+const DEFAULT_MATCHES_PER_FILE: usize = 10;
+const DEFAULT_FILE_NAME_RESULTS: usize = 200;
+const DEFAULT_GLOB_RESULTS: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BatchQueryKind {
+    /// Regex over file contents, like search_file_content
+    Content,
+    /// Fuzzy file name match, like search_files_fast
+    FileName,
+    /// Glob over paths relative to the root, like search_files_by_glob
+    Glob,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchQuery {
+    pub kind: BatchQueryKind,
+    pub pattern: String,
+    /// Cap on results for this query alone (files for content queries)
+    pub max_results: Option<usize>,
+    /// Content queries only
+    pub max_matches_per_file: Option<usize>,
+    /// Content queries only: extensions to search instead of the code file defaults
+    pub file_types: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "results", rename_all = "camelCase")]
+pub enum BatchMatches {
+    Content(Vec<SearchResult>),
+    FileName(Vec<FileSearchResult>),
+    Glob(Vec<GlobResult>),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchQueryResult {
+    pub pattern: String,
+    #[serde(flatten)]
+    pub matches: BatchMatches,
+    /// The cap was reached with matches or unsearched files left over
+    pub truncated: bool,
+    /// Set when the query could not run, e.g. an invalid regex; other queries are unaffected
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchSearchResponse {
+    /// One entry per query, in request order
+    pub results: Vec<BatchQueryResult>,
+    pub elapsed_ms: u64,
+    pub files_scanned: usize,
+    /// Files whose contents were read; each at most once however many content queries ran
+    pub files_read: usize,
+}
+
+struct Capped<T> {
+    max: usize,
+    items: Vec<T>,
+    truncated: bool,
+}
+
+impl<T> Capped<T> {
+    fn new(max: usize) -> Self {
+        Self {
+            max,
+            items: Vec::new(),
+            truncated: false,
+        }
+    }
+
+    fn is_full(&self) -> bool {
+        self.items.len() >= self.max
+    }
+
+    fn push(&mut self, item: T) {
+        if self.is_full() {
+            self.truncated = true;
+        } else {
+            self.items.push(item);
+        }
+    }
+}
+
+struct ContentQuery {
+    index: usize,
+    pattern: String,
+    searcher: RipgrepSearch,
+    matcher: RegexMatcher,
+    found: Mutex<Capped<SearchResult>>,
+}
+
+enum PathQuery {
+    FileName {
+        keywords: Vec<String>,
+        found: Capped<FileSearchResult>,
+    },
+    Glob {
+        found: Capped<GlobResult>,
+    },
+}
+
+/// Walk `root_path` once and answer every query
+pub fn run_batch(root_path: &str, queries: &[BatchQuery]) -> BatchSearchResponse {
+    let start = Instant::now();
+    let mut results: Vec<Option<BatchQueryResult>> = vec![None; queries.len()];
+    let mut content_queries = Vec::new();
+    let mut path_queries: Vec<(usize, PathQuery)> = Vec::new();
+
+    for (index, query) in queries.iter().enumerate() {
+        let empty = BatchQueryResult {
+            pattern: query.pattern.clone(),
+            matches: match query.kind {
+                BatchQueryKind::Content => BatchMatches::Content(vec![]),
+                BatchQueryKind::FileName => BatchMatches::FileName(vec![]),
+                BatchQueryKind::Glob => BatchMatches::Glob(vec![]),
+            },
+            truncated: false,
+            error: None,
+        };
+        match query.kind {
+            BatchQueryKind::Content if !query.pattern.is_empty() => {
+                match RipgrepSearch::build_matcher(&query.pattern) {
+                    Ok(matcher) => {
+                        let searcher = RipgrepSearch::new()
+                            .with_max_results(query.max_results.unwrap_or(DEFAULT_CONTENT_RESULTS))
+                            .with_max_matches_per_file(
+                                query
+                                    .max_matches_per_file
+                                    .unwrap_or(DEFAULT_MATCHES_PER_FILE),
+                            )
+                            .with_file_types(query.file_types.clone());
+                        let found = Mutex::new(Capped::new(searcher.max_results()));
+                        content_queries.push(ContentQuery {
+                            index,
+                            pattern: query.pattern.clone(),
+                            searcher,
+                            matcher,
+                            found,
+                        });
+                    }
+                    Err(e) => {
+                        results[index] = Some(BatchQueryResult {
+                            error: Some(e),
+                            ..empty
+                        })
+                    }
+                }
+            }
+            BatchQueryKind::FileName
+                if !HighPerformanceFileSearch::parse_query(&query.pattern).is_empty() =>
+            {
+                path_queries.push((
+                    index,
+                    PathQuery::FileName {
+                        keywords: HighPerformanceFileSearch::parse_query(&query.pattern),
+                        found: Capped::new(query.max_results.unwrap_or(DEFAULT_FILE_NAME_RESULTS)),
+                    },
+                ));
+            }
+            BatchQueryKind::Glob if !query.pattern.trim().is_empty() => {
+                path_queries.push((
+                    index,
+                    PathQuery::Glob {
+                        found: Capped::new(query.max_results.unwrap_or(DEFAULT_GLOB_RESULTS)),
+                    },
+                ));
+            }
+            // Empty patterns match nothing, as with the single-query commands
+            _ => results[index] = Some(empty),
+        }
+    }
+
+    let file_search = HighPerformanceFileSearch::new();
+    let glob = HighPerformanceGlob::new();
+    let mut files_scanned = 0;
+    let mut content_candidates: Vec<PathBuf> = Vec::new();
+
+    if !content_queries.is_empty() || !path_queries.is_empty() {
+        for entry in walk(root_path) {
+            let path = entry.path();
+            let is_file = entry.file_type().is_some_and(|t| t.is_file());
+            if is_file {
+                files_scanned += 1;
+            }
+            let path_str = path_wire::encode(path);
+
+            for (index, query) in path_queries.iter_mut() {
+                match query {
+                    PathQuery::FileName { keywords, found } => {
+                        if !is_file || !file_search.is_code_file(path) {
+                            continue;
+                        }
+                        let Some(filename) = path.file_name().and_then(OsStr::to_str) else {
+                            continue;
+                        };
+                        if let Some(result) = file_search.match_filename(filename, path, keywords) {
+                            found.push(result);
+                        }
+                    }
+                    PathQuery::Glob { found } => {
+                        let pattern = &queries[*index].pattern;
+                        if glob.matches_glob_pattern(&path_str, pattern, root_path) {
+                            if found.is_full() {
+                                found.truncated = true;
+                            } else {
+                                // Describing an entry stats it, so only kept ones are
+                                found.push(glob::glob_result(path, path_str.clone()));
+                            }
+                        }
+                    }
+                }
+            }
+
+            if is_file
+                && content_queries
+                    .iter()
+                    .any(|query| query.searcher.is_valid_file(path))
+            {
+                content_candidates.push(entry.into_path());
+            }
+        }
+    }
+
+    let files_read = AtomicUsize::new(0);
+    content_candidates.par_iter().for_each(|path| {
+        let mut wanted = Vec::new();
+        for query in &content_queries {
+            if !query.searcher.is_valid_file(path) {
+                continue;
+            }
+            match query.found.lock() {
+                // A file left unsearched because of the cap may hold more matches
+                Ok(mut found) if found.is_full() => found.truncated = true,
+                Ok(_) => wanted.push(query),
+                Err(_) => {}
+            }
+        }
+        if wanted.is_empty() {
+            return;
+        }
+        let Ok(bytes) = std::fs::read(path) else {
+            return;
+        };
+        files_read.fetch_add(1, Ordering::Relaxed);
+        for query in wanted {
+            if let Some(result) = RipgrepSearch::search_in_bytes(
+                &query.matcher,
+                path,
+                &bytes,
+                query.searcher.max_matches_per_file(),
+                &query.pattern,
+            ) {
+                if let Ok(mut found) = query.found.lock() {
+                    found.push(result);
+                }
+            }
+        }
+    });
+
+    for query in content_queries {
+        let found = query
+            .found
+            .into_inner()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        results[query.index] = Some(BatchQueryResult {
+            pattern: query.pattern,
+            matches: BatchMatches::Content(found.items),
+            truncated: found.truncated,
+            error: None,
+        });
+    }
+    for (index, query) in path_queries {
+        let (matches, truncated) = match query {
+            PathQuery::FileName { mut found, .. } => {
+                HighPerformanceFileSearch::sort_results(&mut found.items);
+                (BatchMatches::FileName(found.items), found.truncated)
+            }
+            PathQuery::Glob { mut found } => {
+                glob::sort_by_modified_time(&mut found.items);
+                (BatchMatches::Glob(found.items), found.truncated)
+            }
+        };
+        results[index] = Some(BatchQueryResult {
+            pattern: queries[index].pattern.clone(),
+            matches,
+            truncated,
+            error: None,
+        });
+    }
+
+    BatchSearchResponse {
+        results: results.into_iter().flatten().collect(),
+        elapsed_ms: start.elapsed().as_millis() as u64,
+        files_scanned,
+        files_read: files_read.into_inner(),
+    }
+}
+
+fn walk(root_path: &str) -> impl Iterator<Item = ignore::DirEntry> {
+    let exclusions = LiveExclusions::new();
+    WalkBuilder::new(root_path)
+        .hidden(true)
+        .git_ignore(true)
+        .git_global(true)
+        .git_exclude(true)
+        .ignore(true)
+        .parents(true)
+        .max_depth(Some(crate::walk_depth::default_max_depth()))
+        .filter_entry(move |entry| {
+            let is_dir = entry.file_type().is_some_and(|t| t.is_dir());
+            !(is_dir && exclusions.excludes_dir(&entry.file_name().to_string_lossy()))
+        })
+        .build()
+        .filter_map(|entry| entry.ok())
+        // Skip the root itself
+        .filter(|entry| entry.depth() > 0)
+}
+
+/// Run several searches over one walk of `root_path`
+#[tauri::command]
+pub async fn search_batch(
+    root_path: String,
+    queries: Vec<BatchQuery>,
+) -> Result<BatchSearchResponse, String> {
+    if queries.len() > MAX_BATCH_QUERIES {
+        return Err(format!(
+            "At most {} queries can be batched, got {}",
+            MAX_BATCH_QUERIES,
+            queries.len()
+        ));
+    }
+    path_access::ensure_readable(Path::new(&root_path))?;
+    let response = tauri::async_runtime::spawn_blocking(move || {
+        let _permit = concurrency::governor().acquire(
+            OperationClass::Interactive,
+            &format!("Batch search ({} queries)", queries.len()),
+            None,
+        );
+        run_batch(&root_path, &queries)
+    })
+    .await
+    .map_err(|e| format!("Batch search failed: {}", e))?;
+    log::info!(
+        "Batch search of {} queries scanned {} files ({} read) in {}ms",
+        response.results.len(),
+        response.files_scanned,
+        response.files_read,
+        response.elapsed_ms
+    );
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn create_fixture() -> TempDir {
+        let temp_dir = TempDir::new().unwrap();
+        let files = [
+            (
+                "src/main.rs",
+                "fn main() {\n    println!(\"Hello, world!\");\n}\n",
+            ),
+            (
+                "src/lib.rs",
+                "pub fn greet() {\n    println!(\"Hello from lib!\");\n}\n",
+            ),
+            (
+                "src/util/helpers.ts",
+                "export function hello() {\n  return 'hi';\n}\n",
+            ),
+            (
+                "tests/test_lib.rs",
+                "fn test_hello() {\n    assert!(true);\n}\n",
+            ),
+            ("docs/guide.md", "# Guide\n\nTODO: say hello\n"),
+            ("README.md", "# Hello World\n"),
+        ];
+        for (path, content) in files {
+            let path = temp_dir.path().join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        temp_dir
+    }
+
+    fn query(kind: BatchQueryKind, pattern: &str) -> BatchQuery {
+        BatchQuery {
+            kind,
+            pattern: pattern.to_string(),
+            max_results: None,
+            max_matches_per_file: None,
+            file_types: None,
+        }
+    }
+
+    fn content_lines(results: &[SearchResult]) -> Vec<(String, Vec<(u64, String)>)> {
+        let mut lines: Vec<_> = results
+            .iter()
+            .map(|result| {
+                let matches = result.matches.iter();
+                let matches = matches.map(|m| (m.line_number, m.line_content.clone()));
+                (result.file_path.clone(), matches.collect())
+            })
+            .collect();
+        lines.sort();
+        lines
+    }
+
+    #[test]
+    fn test_batch_matches_individual_searches() {
+        let temp_dir = create_fixture();
+        let root = temp_dir.path().to_str().unwrap();
+        let markdown = BatchQuery {
+            file_types: Some(vec!["md".to_string()]),
+            ..query(BatchQueryKind::Content, "hello")
+        };
+        let queries = vec![
+            query(BatchQueryKind::Content, "hello"),
+            query(BatchQueryKind::Content, r"fn \w+"),
+            markdown,
+            query(BatchQueryKind::FileName, "lib"),
+            query(BatchQueryKind::Glob, "**/*.rs"),
+            query(BatchQueryKind::Glob, "src/*"),
+        ];
+        let response = run_batch(root, &queries);
+        assert_eq!(response.results.len(), queries.len());
+
+        for (query, result) in queries.iter().zip(&response.results) {
+            assert!(!result.truncated && result.error.is_none());
+            match &result.matches {
+                BatchMatches::Content(found) => {
+                    let single = RipgrepSearch::new()
+                        .with_max_results(DEFAULT_CONTENT_RESULTS)
+                        .with_file_types(query.file_types.clone())
+                        .search_content(&query.pattern, root)
+                        .unwrap();
+                    assert!(!found.is_empty());
+                    assert_eq!(content_lines(found), content_lines(&single));
+                }
+                BatchMatches::FileName(found) => {
+                    let single = HighPerformanceFileSearch::new()
+                        .search_files(root, &query.pattern)
+                        .unwrap();
+                    let paths = |r: &[FileSearchResult]| {
+                        let mut paths: Vec<_> = r.iter().map(|r| r.path.clone()).collect();
+                        paths.sort();
+                        paths
+                    };
+                    assert_eq!(found.len(), 2);
+                    assert_eq!(paths(found), paths(&single));
+                }
+                BatchMatches::Glob(found) => {
+                    let single = HighPerformanceGlob::new()
+                        .search_files_by_glob(&query.pattern, root, DEFAULT_GLOB_RESULTS)
+                        .unwrap();
+                    let paths = |r: &[GlobResult]| {
+                        let mut paths: Vec<_> = r.iter().map(|r| r.path.clone()).collect();
+                        paths.sort();
+                        paths
+                    };
+                    assert!(!found.is_empty());
+                    assert_eq!(paths(found), paths(&single));
+                }
+            }
+        }
+
+        // Each candidate file is read once, where separate searches read it once per query
+        let separate_reads: usize = queries
+            .iter()
+            .filter(|q| q.kind == BatchQueryKind::Content)
+            .map(|q| {
+                let searcher = RipgrepSearch::new().with_file_types(q.file_types.clone());
+                walk(root)
+                    .filter(|entry| entry.path().is_file())
+                    .filter(|entry| searcher.is_valid_file(entry.path()))
+                    .count()
+            })
+            .sum();
+        assert_eq!(response.files_scanned, 6);
+        assert_eq!(response.files_read, 6);
+        assert!(response.files_read < separate_reads);
+    }
+
+    #[test]
+    fn test_caps_are_per_query() {
+        let temp_dir = create_fixture();
+        let root = temp_dir.path().to_str().unwrap();
+        let capped = |kind, pattern: &str, max_results| BatchQuery {
+            max_results: Some(max_results),
+            ..query(kind, pattern)
+        };
+        let queries = vec![
+            capped(BatchQueryKind::Content, "hello", 1),
+            query(BatchQueryKind::Content, "hello"),
+            capped(BatchQueryKind::Glob, "**/*.rs", 1),
+            query(BatchQueryKind::Glob, "**/*.rs"),
+            query(BatchQueryKind::Content, "("),
+            query(BatchQueryKind::FileName, "  "),
+        ];
+        let response = run_batch(root, &queries);
+        let counts: Vec<(usize, bool)> = response
+            .results
+            .iter()
+            .map(|result| {
+                let len = match &result.matches {
+                    BatchMatches::Content(found) => found.len(),
+                    BatchMatches::FileName(found) => found.len(),
+                    BatchMatches::Glob(found) => found.len(),
+                };
+                (len, result.truncated)
+            })
+            .collect();
+        assert_eq!(counts[0], (1, true));
+        assert_eq!(counts[1], (6, false));
+        assert_eq!(counts[2], (1, true));
+        assert_eq!(counts[3], (3, false));
+        // An invalid regex fails only its own query
+        assert!(response.results[4].error.is_some());
+        assert_eq!(counts[5], (0, false));
+    }
+}
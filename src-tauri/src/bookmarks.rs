@@ -0,0 +1,504 @@
+// Bookmarks module
+// Project-level bookmarks persisted in the Database. Each bookmark stores an anchor (the
+// line's text, surrounding context and a content hash) so it can be re-resolved after
+// the file is edited instead of pointing at a stale line number.
+
+use crate::database::Database;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::State;
+
+/// Number of lines captured above and below the anchored line
+const CONTEXT_LINES: usize = 2;
+
+/// How far (in lines) from the stored position to search for a moved anchor
+const RESOLVE_WINDOW: usize = 500;
+
+/// Text and context captured when a bookmark is created
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BookmarkAnchor {
+    pub line: usize,
+    pub text: String,
+    pub context_before: Vec<String>,
+    pub context_after: Vec<String>,
+    pub content_hash: String,
+}
+
+/// Where an anchor resolves in the current file content
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnchorResolution {
+    /// The anchored line is still at its stored position
+    Exact(usize),
+    /// The anchored line was found at a different position
+    Moved(usize),
+    /// The anchored text no longer exists near the stored position
+    Orphaned,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Bookmark {
+    pub id: i64,
+    pub root_path: String,
+    pub file_path: String,
+    /// Current 1-based line; the stored line when the bookmark is orphaned
+    pub line: usize,
+    /// Line the bookmark was created on
+    pub original_line: usize,
+    pub note: Option<String>,
+    pub anchor_text: String,
+    /// True when the anchor moved away from its stored line
+    pub drifted: bool,
+    /// True when the anchored text can no longer be found
+    pub orphaned: bool,
+    pub created_at: i64,
+}
+
+fn hash_line(text: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(text.trim().as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+/// Capture an anchor for a 1-based line of `content`
+pub fn create_anchor(content: &str, line: usize) -> Result<BookmarkAnchor, String> {
+    let lines: Vec<&str> = content.lines().collect();
+    if line == 0 || line > lines.len() {
+        return Err(format!(
+            "Line {} is out of range (file has {} lines)",
+            line,
+            lines.len()
+        ));
+    }
+    let index = line - 1;
+    let text = lines[index].to_string();
+    let before_start = index.saturating_sub(CONTEXT_LINES);
+    let after_end = (index + 1 + CONTEXT_LINES).min(lines.len());
+
+    Ok(BookmarkAnchor {
+        line,
+        content_hash: hash_line(&text),
+        text,
+        context_before: lines[before_start..index]
+            .iter()
+            .map(|l| l.to_string())
+            .collect(),
+        context_after: lines[index + 1..after_end]
+            .iter()
+            .map(|l| l.to_string())
+            .collect(),
+    })
+}
+
+/// Count how many context lines still surround `index`
+fn context_score(lines: &[&str], index: usize, anchor: &BookmarkAnchor) -> usize {
+    let before = anchor
+        .context_before
+        .iter()
+        .rev()
+        .enumerate()
+        .filter(|(offset, text)| {
+            index
+                .checked_sub(offset + 1)
+                .and_then(|i| lines.get(i))
+                .is_some_and(|l| l.trim() == text.trim())
+        })
+        .count();
+    let after = anchor
+        .context_after
+        .iter()
+        .enumerate()
+        .filter(|(offset, text)| {
+            lines
+                .get(index + offset + 1)
+                .is_some_and(|l| l.trim() == text.trim())
+        })
+        .count();
+    before + after
+}
+
+/// Re-resolve an anchor against the current lines of its file.
+///
+/// Candidates within the window are ranked by how much surrounding context still
+/// matches, then by distance from the stored line.
+pub fn resolve_anchor(lines: &[&str], anchor: &BookmarkAnchor) -> AnchorResolution {
+    let stored_index = anchor.line.saturating_sub(1);
+    if lines
+        .get(stored_index)
+        .is_some_and(|l| hash_line(l) == anchor.content_hash)
+    {
+        return AnchorResolution::Exact(anchor.line);
+    }
+
+    let window_start = stored_index.saturating_sub(RESOLVE_WINDOW);
+    let window_end = (stored_index + RESOLVE_WINDOW + 1).min(lines.len());
+    let anchor_text = anchor.text.trim();
+
+    (window_start..window_end)
+        .filter(|&i| lines[i].trim() == anchor_text)
+        .max_by_key(|&i| {
+            (
+                context_score(lines, i, anchor),
+                std::cmp::Reverse(i.abs_diff(stored_index)),
+            )
+        })
+        .map(|i| AnchorResolution::Moved(i + 1))
+        .unwrap_or(AnchorResolution::Orphaned)
+}
+
+fn now_secs() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+fn resolve_file_path(root_path: &str, file_path: &str) -> std::path::PathBuf {
+    let path = Path::new(file_path);
+    if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        Path::new(root_path).join(path)
+    }
+}
+
+async fn ensure_schema(db: &Database) -> Result<(), String> {
+    db.execute(
+        "CREATE TABLE IF NOT EXISTS bookmarks (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            root_path TEXT NOT NULL,
+            file_path TEXT NOT NULL,
+            line INTEGER NOT NULL,
+            note TEXT,
+            anchor_text TEXT NOT NULL,
+            context_before TEXT NOT NULL,
+            context_after TEXT NOT NULL,
+            content_hash TEXT NOT NULL,
+            created_at INTEGER NOT NULL
+        )",
+        vec![],
+    )
+    .await?;
+    db.execute(
+        "CREATE INDEX IF NOT EXISTS idx_bookmarks_root_path ON bookmarks(root_path)",
+        vec![],
+    )
+    .await?;
+    Ok(())
+}
+
+fn parse_context(value: Option<&serde_json::Value>) -> Vec<String> {
+    value
+        .and_then(|v| v.as_str())
+        .and_then(|s| serde_json::from_str(s).ok())
+        .unwrap_or_default()
+}
+
+pub async fn add_bookmark(
+    db: &Database,
+    root_path: &str,
+    file_path: &str,
+    line: usize,
+    note: Option<String>,
+) -> Result<Bookmark, String> {
+    let full_path = resolve_file_path(root_path, file_path);
+    let content = std::fs::read_to_string(&full_path)
+        .map_err(|e| format!("Failed to read {}: {}", full_path.display(), e))?;
+    let anchor = create_anchor(&content, line)?;
+
+    ensure_schema(db).await?;
+    let created_at = now_secs();
+    db.execute(
+        "INSERT INTO bookmarks (root_path, file_path, line, note, anchor_text, context_before, context_after, content_hash, created_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        vec![
+            serde_json::Value::String(root_path.to_string()),
+            serde_json::Value::String(file_path.to_string()),
+            serde_json::Value::Number(line.into()),
+            note.clone()
+                .map(serde_json::Value::String)
+                .unwrap_or(serde_json::Value::Null),
+            serde_json::Value::String(anchor.text.clone()),
+            serde_json::Value::String(serde_json::to_string(&anchor.context_before).unwrap_or_default()),
+            serde_json::Value::String(serde_json::to_string(&anchor.context_after).unwrap_or_default()),
+            serde_json::Value::String(anchor.content_hash.clone()),
+            serde_json::Value::Number(created_at.into()),
+        ],
+    )
+    .await?;
+
+    let result = db.query("SELECT last_insert_rowid() AS id", vec![]).await?;
+    let id = result
+        .rows
+        .first()
+        .and_then(|row| row.get("id"))
+        .and_then(|v| v.as_i64())
+        .ok_or("Failed to read bookmark id")?;
+
+    Ok(Bookmark {
+        id,
+        root_path: root_path.to_string(),
+        file_path: file_path.to_string(),
+        line,
+        original_line: line,
+        note,
+        anchor_text: anchor.text,
+        drifted: false,
+        orphaned: false,
+        created_at,
+    })
+}
+
+/// List bookmarks for a project, re-resolving each anchor against the current files.
+/// Every file is read once regardless of how many bookmarks it holds.
+pub async fn list_bookmarks(db: &Database, root_path: &str) -> Result<Vec<Bookmark>, String> {
+    ensure_schema(db).await?;
+    let result = db
+        .query(
+            "SELECT id, root_path, file_path, line, note, anchor_text, context_before, context_after, content_hash, created_at FROM bookmarks WHERE root_path = ? ORDER BY file_path, line",
+            vec![serde_json::Value::String(root_path.to_string())],
+        )
+        .await?;
+
+    let mut by_file: HashMap<String, Vec<(Bookmark, BookmarkAnchor)>> = HashMap::new();
+    let mut file_order: Vec<String> = Vec::new();
+    for row in &result.rows {
+        let get_str = |key: &str| {
+            row.get(key)
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string()
+        };
+        let line = row.get("line").and_then(|v| v.as_i64()).unwrap_or(1) as usize;
+        let file_path = get_str("file_path");
+        let anchor = BookmarkAnchor {
+            line,
+            text: get_str("anchor_text"),
+            context_before: parse_context(row.get("context_before")),
+            context_after: parse_context(row.get("context_after")),
+            content_hash: get_str("content_hash"),
+        };
+        let bookmark = Bookmark {
+            id: row.get("id").and_then(|v| v.as_i64()).unwrap_or_default(),
+            root_path: get_str("root_path"),
+            file_path: file_path.clone(),
+            line,
+            original_line: line,
+            note: row.get("note").and_then(|v| v.as_str()).map(String::from),
+            anchor_text: anchor.text.clone(),
+            drifted: false,
+            orphaned: false,
+            created_at: row
+                .get("created_at")
+                .and_then(|v| v.as_i64())
+                .unwrap_or_default(),
+        };
+        if !by_file.contains_key(&file_path) {
+            file_order.push(file_path.clone());
+        }
+        by_file
+            .entry(file_path)
+            .or_default()
+            .push((bookmark, anchor));
+    }
+
+    let mut bookmarks = Vec::with_capacity(result.rows.len());
+    for file_path in file_order {
+        let entries = by_file.remove(&file_path).unwrap_or_default();
+        let content = std::fs::read_to_string(resolve_file_path(root_path, &file_path)).ok();
+        let lines: Vec<&str> = content
+            .as_deref()
+            .map(|c| c.lines().collect())
+            .unwrap_or_default();
+
+        for (mut bookmark, anchor) in entries {
+            match resolve_anchor(&lines, &anchor) {
+                AnchorResolution::Exact(line) => bookmark.line = line,
+                AnchorResolution::Moved(line) => {
+                    bookmark.line = line;
+                    bookmark.drifted = true;
+                }
+                AnchorResolution::Orphaned => bookmark.orphaned = true,
+            }
+            bookmarks.push(bookmark);
+        }
+    }
+
+    Ok(bookmarks)
+}
+
+pub async fn remove_bookmark(db: &Database, id: i64) -> Result<(), String> {
+    ensure_schema(db).await?;
+    db.execute(
+        "DELETE FROM bookmarks WHERE id = ?",
+        vec![serde_json::Value::Number(id.into())],
+    )
+    .await?;
+    Ok(())
+}
+
+pub async fn update_bookmark_note(
+    db: &Database,
+    id: i64,
+    note: Option<String>,
+) -> Result<(), String> {
+    ensure_schema(db).await?;
+    let result = db
+        .execute(
+            "UPDATE bookmarks SET note = ? WHERE id = ?",
+            vec![
+                note.map(serde_json::Value::String)
+                    .unwrap_or(serde_json::Value::Null),
+                serde_json::Value::Number(id.into()),
+            ],
+        )
+        .await?;
+    if result.rows_affected == 0 {
+        return Err(format!("Bookmark {} not found", id));
+    }
+    Ok(())
+}
+
+// Tauri commands
+#[tauri::command]
+pub async fn bookmark_add(
+    db: State<'_, Arc<Database>>,
+    root_path: String,
+    file_path: String,
+    line: usize,
+    note: Option<String>,
+) -> Result<Bookmark, String> {
+    add_bookmark(&db, &root_path, &file_path, line, note).await
+}
+
+#[tauri::command]
+pub async fn bookmark_list(
+    db: State<'_, Arc<Database>>,
+    root_path: String,
+) -> Result<Vec<Bookmark>, String> {
+    list_bookmarks(&db, &root_path).await
+}
+
+#[tauri::command]
+pub async fn bookmark_remove(db: State<'_, Arc<Database>>, id: i64) -> Result<(), String> {
+    remove_bookmark(&db, id).await
+}
+
+#[tauri::command]
+pub async fn bookmark_update_note(
+    db: State<'_, Arc<Database>>,
+    id: i64,
+    note: Option<String>,
+) -> Result<(), String> {
+    update_bookmark_note(&db, id, note).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const SOURCE: &str =
+        "fn main() {\n    let a = 1;\n    let b = 2;\n    println!(\"{}\", a + b);\n}\n";
+
+    #[test]
+    fn test_create_anchor_captures_context() {
+        let anchor = create_anchor(SOURCE, 3).unwrap();
+        assert_eq!(anchor.text, "    let b = 2;");
+        assert_eq!(anchor.context_before, vec!["fn main() {", "    let a = 1;"]);
+        assert_eq!(
+            anchor.context_after,
+            vec!["    println!(\"{}\", a + b);", "}"]
+        );
+        assert!(create_anchor(SOURCE, 0).is_err());
+        assert!(create_anchor(SOURCE, 99).is_err());
+    }
+
+    #[test]
+    fn test_resolve_unchanged_is_exact() {
+        let anchor = create_anchor(SOURCE, 3).unwrap();
+        let lines: Vec<&str> = SOURCE.lines().collect();
+        assert_eq!(resolve_anchor(&lines, &anchor), AnchorResolution::Exact(3));
+    }
+
+    #[test]
+    fn test_resolve_after_lines_inserted_above() {
+        let anchor = create_anchor(SOURCE, 3).unwrap();
+        let edited = format!("use std::io;\nuse std::fs;\n\n{}", SOURCE);
+        let lines: Vec<&str> = edited.lines().collect();
+        assert_eq!(resolve_anchor(&lines, &anchor), AnchorResolution::Moved(6));
+    }
+
+    #[test]
+    fn test_resolve_prefers_matching_context() {
+        let content = "let x = 0;\nfoo();\nlet y = 1;\nbar();\nfoo();\nbaz();\n";
+        let anchor = create_anchor(content, 5).unwrap();
+        // Remove the first line so both foo() calls shift up; the one between bar() and
+        // baz() is the original
+        let edited = "foo();\nlet y = 1;\nbar();\nfoo();\nbaz();\n";
+        let lines: Vec<&str> = edited.lines().collect();
+        assert_eq!(resolve_anchor(&lines, &anchor), AnchorResolution::Moved(4));
+    }
+
+    #[test]
+    fn test_resolve_deleted_line_is_orphaned() {
+        let anchor = create_anchor(SOURCE, 3).unwrap();
+        let edited = SOURCE.replace("    let b = 2;\n", "");
+        let lines: Vec<&str> = edited.lines().collect();
+        assert_eq!(resolve_anchor(&lines, &anchor), AnchorResolution::Orphaned);
+    }
+
+    #[tokio::test]
+    async fn test_bookmark_lifecycle() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("project");
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("main.rs"), SOURCE).unwrap();
+        let root_path = root.to_string_lossy().to_string();
+
+        let db = Database::new(
+            temp_dir
+                .path()
+                .join("test.db")
+                .to_string_lossy()
+                .to_string(),
+        );
+        db.connect().await.unwrap();
+
+        let kept = add_bookmark(&db, &root_path, "main.rs", 3, Some("b".to_string()))
+            .await
+            .unwrap();
+        let removed_line = add_bookmark(&db, &root_path, "main.rs", 2, None)
+            .await
+            .unwrap();
+        assert_ne!(kept.id, removed_line.id);
+
+        // Insert lines above and delete the line holding the second bookmark
+        let edited = format!("// header\n\n{}", SOURCE.replace("    let a = 1;\n", ""));
+        std::fs::write(root.join("main.rs"), edited).unwrap();
+
+        let bookmarks = list_bookmarks(&db, &root_path).await.unwrap();
+        assert_eq!(bookmarks.len(), 2);
+        let moved = bookmarks.iter().find(|b| b.id == kept.id).unwrap();
+        assert_eq!(moved.line, 4);
+        assert!(moved.drifted);
+        assert!(!moved.orphaned);
+        let orphaned = bookmarks.iter().find(|b| b.id == removed_line.id).unwrap();
+        assert!(orphaned.orphaned);
+        assert_eq!(orphaned.line, 2);
+
+        update_bookmark_note(&db, kept.id, Some("updated".to_string()))
+            .await
+            .unwrap();
+        remove_bookmark(&db, removed_line.id).await.unwrap();
+        let bookmarks = list_bookmarks(&db, &root_path).await.unwrap();
+        assert_eq!(bookmarks.len(), 1);
+        assert_eq!(bookmarks[0].note.as_deref(), Some("updated"));
+        assert!(update_bookmark_note(&db, removed_line.id, None)
+            .await
+            .is_err());
+    }
+}
@@ -0,0 +1,65 @@
+// Cancellation registry
+// Long-running backend work registers a token under an id; the UI cancels by id and the
+// work polls its token at convenient points. Cancellation is cooperative only.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref TOKENS: Mutex<HashMap<String, CancellationToken>> = Mutex::new(HashMap::new());
+}
+
+/// Register a token for `id`, replacing any previous one
+pub fn register(id: &str) -> CancellationToken {
+    let token = CancellationToken::new();
+    TOKENS.lock().unwrap().insert(id.to_string(), token.clone());
+    token
+}
+
+/// The token registered for `id`, if the work is still running
+pub fn token(id: &str) -> Option<CancellationToken> {
+    TOKENS.lock().unwrap().get(id).cloned()
+}
+
+/// Request cancellation. Returns false when nothing is registered under `id`.
+pub fn cancel(id: &str) -> bool {
+    match TOKENS.lock().unwrap().get(id) {
+        Some(token) => {
+            token.cancel();
+            true
+        }
+        None => false,
+    }
+}
+
+/// Request cancellation of everything registered, e.g. on shutdown. Returns how many
+/// tokens were cancelled.
+pub fn cancel_all() -> usize {
+    let tokens = TOKENS.lock().unwrap();
+    for token in tokens.values() {
+        token.cancel();
+    }
+    tokens.len()
+}
+
+pub fn unregister(id: &str) {
+    TOKENS.lock().unwrap().remove(id);
+}
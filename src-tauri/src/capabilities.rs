@@ -0,0 +1,1980 @@
+// Backend capabilities registry
+// A declarative table of every command registered with the invoke handler, so the command
+// palette, docs and the AI tool-calling layer can discover what the backend offers instead
+// of keeping their own lists. Add an entry here whenever a command is added to
+// generate_handler! in lib.rs; a unit test fails when the two disagree.
+
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Category {
+    Activity,
+    App,
+    Archive,
+    Audit,
+    Auth,
+    BackgroundTasks,
+    Bookmarks,
+    CodeNavigation,
+    Database,
+    Docs,
+    Edits,
+    Files,
+    Git,
+    History,
+    Http,
+    Lint,
+    Lsp,
+    Maintenance,
+    Process,
+    Project,
+    Remote,
+    Search,
+    Shell,
+    Skills,
+    Terminal,
+    Tokens,
+    Websocket,
+    Windows,
+}
+
+/// JSON type of a parameter as the frontend passes it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum ParamType {
+    #[serde(rename = "string")]
+    Str,
+    #[serde(rename = "number")]
+    Num,
+    #[serde(rename = "boolean")]
+    Bool,
+    #[serde(rename = "array")]
+    Array,
+    #[serde(rename = "object")]
+    Object,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ParamSpec {
+    /// Argument name as passed to invoke (camelCase)
+    pub name: &'static str,
+    #[serde(rename = "type")]
+    pub param_type: ParamType,
+    pub required: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandSpec {
+    pub name: &'static str,
+    pub category: Category,
+    pub description: &'static str,
+    pub params: &'static [ParamSpec],
+    /// May take seconds or more; callers should show progress rather than block
+    pub long_running: bool,
+    /// Can be stopped through activity_cancel
+    pub cancellable: bool,
+}
+
+impl CommandSpec {
+    const fn long_running(mut self) -> Self {
+        self.long_running = true;
+        self
+    }
+
+    const fn cancellable(mut self) -> Self {
+        self.cancellable = true;
+        self
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackendCapabilities {
+    pub version: &'static str,
+    pub commands: &'static [CommandSpec],
+}
+
+const fn cmd(
+    name: &'static str,
+    category: Category,
+    description: &'static str,
+    params: &'static [ParamSpec],
+) -> CommandSpec {
+    CommandSpec {
+        name,
+        category,
+        description,
+        params,
+        long_running: false,
+        cancellable: false,
+    }
+}
+
+const fn req(name: &'static str, param_type: ParamType) -> ParamSpec {
+    ParamSpec {
+        name,
+        param_type,
+        required: true,
+    }
+}
+
+const fn opt(name: &'static str, param_type: ParamType) -> ParamSpec {
+    ParamSpec {
+        name,
+        param_type,
+        required: false,
+    }
+}
+
+use Category::*;
+use ParamType::*;
+
+/// Every registered command, in generate_handler! order
+pub static COMMANDS: &[CommandSpec] = &[
+    cmd(
+        "start_file_watching",
+        Files,
+        "Watch a project directory and emit file change events",
+        &[req("path", Str)],
+    ),
+    cmd(
+        "stop_file_watching",
+        Files,
+        "Stop the main window file watcher",
+        &[],
+    ),
+    cmd(
+        "search_file_content",
+        Search,
+        "Regex search of file contents under a root or explicit paths",
+        &[
+            req("query", Str),
+            opt("rootPath", Str),
+            opt("fileTypes", Array),
+            opt("excludeDirs", Array),
+            opt("includePaths", Array),
+            opt("excludePaths", Array),
+            opt("maxDepth", Num),
+            opt("sortBy", Str),
+            opt("isRegex", Bool),
+            opt("caseSensitive", Bool),
+            opt("wholeWord", Bool),
+            opt("respectGitignore", Bool),
+            opt("maxFileSize", Num),
+            opt("includeFiles", Array),
+            opt("includeGlobs", Array),
+            opt("excludeGlobs", Array),
+            opt("includeHidden", Bool),
+            opt("followSymlinks", Bool),
+            opt("countOnly", Bool),
+            opt("searchId", Str),
+        ],
+    )
+    .long_running()
+    .cancellable(),
+    cmd(
+        "search_file_content_stream",
+        Search,
+        "Content search that emits each file's matches as they are found",
+        &[
+            req("requestId", Str),
+            req("query", Str),
+            opt("rootPath", Str),
+            opt("fileTypes", Array),
+            opt("excludeDirs", Array),
+            opt("includePaths", Array),
+            opt("excludePaths", Array),
+            opt("maxDepth", Num),
+            opt("sortBy", Str),
+            opt("isRegex", Bool),
+            opt("caseSensitive", Bool),
+            opt("wholeWord", Bool),
+            opt("respectGitignore", Bool),
+            opt("maxFileSize", Num),
+            opt("includeFiles", Array),
+            opt("includeGlobs", Array),
+            opt("excludeGlobs", Array),
+            opt("includeHidden", Bool),
+            opt("followSymlinks", Bool),
+            opt("countOnly", Bool),
+            opt("searchId", Str),
+        ],
+    )
+    .long_running()
+    .cancellable(),
+    cmd(
+        "cancel_search",
+        Search,
+        "Stop a content search started with a search id",
+        &[req("searchId", Str)],
+    ),
+    cmd(
+        "search_and_replace",
+        Search,
+        "Replace the matches of a content search across files, or preview them",
+        &[
+            req("query", Str),
+            req("replacement", Str),
+            opt("rootPath", Str),
+            opt("fileTypes", Array),
+            opt("excludeDirs", Array),
+            opt("includePaths", Array),
+            opt("excludePaths", Array),
+            opt("maxDepth", Num),
+            opt("isRegex", Bool),
+            opt("caseSensitive", Bool),
+            opt("wholeWord", Bool),
+            opt("respectGitignore", Bool),
+            opt("maxFileSize", Num),
+            opt("includeFiles", Array),
+            opt("includeGlobs", Array),
+            opt("excludeGlobs", Array),
+            opt("includeHidden", Bool),
+            opt("followSymlinks", Bool),
+            req("dryRun", Bool),
+        ],
+    )
+    .long_running(),
+    cmd(
+        "search_files_fast",
+        Search,
+        "Fuzzy file name search",
+        &[
+            req("query", Str),
+            req("rootPath", Str),
+            opt("maxResults", Num),
+        ],
+    ),
+    cmd(
+        "file_search_get_ranking",
+        Search,
+        "Get the lists and weights file search uses to rank matches",
+        &[],
+    ),
+    cmd(
+        "file_search_set_ranking",
+        Search,
+        "Replace the file search ranking lists and weights",
+        &[req("config", Object)],
+    ),
+    cmd(
+        "list_project_files",
+        Files,
+        "List project files as text, respecting ignore rules",
+        &[
+            req("directoryPath", Str),
+            opt("recursive", Bool),
+            opt("maxDepth", Num),
+            opt("maxFiles", Num),
+        ],
+    ),
+    cmd(
+        "build_directory_tree",
+        Files,
+        "Build the file tree for a project, optionally with a snapshot for diffs",
+        &[
+            req("rootPath", Str),
+            opt("maxImmediateDepth", Num),
+            opt("withSnapshot", Bool),
+            opt("sort", Object),
+        ],
+    ),
+    cmd(
+        "build_directory_tree_diff",
+        Files,
+        "Changes to the file tree since a snapshot",
+        &[req("rootPath", Str), req("previousSnapshotId", Str)],
+    ),
+    cmd(
+        "load_directory_children",
+        Files,
+        "Load the children of a lazily expanded directory",
+        &[req("dirPath", Str), opt("sort", Object)],
+    ),
+    cmd(
+        "clear_directory_cache",
+        Files,
+        "Clear the cached directory tree",
+        &[],
+    ),
+    cmd(
+        "invalidate_directory_path",
+        Files,
+        "Drop one path from the directory tree cache",
+        &[req("path", Str)],
+    ),
+    cmd(
+        "check_path_access",
+        Files,
+        "Whether a path can be read: ok, notFound, denied (e.g. macOS privacy settings) or error",
+        &[req("path", Str)],
+    ),
+    cmd(
+        "search_files_by_glob",
+        Search,
+        "Find files matching a glob pattern",
+        &[
+            req("pattern", Str),
+            req("rootPath", Str),
+            req("maxResults", Num),
+            opt("maxDepth", Num),
+            opt("includeHidden", Bool),
+            opt("followSymlinks", Bool),
+        ],
+    ),
+    cmd(
+        "search_batch",
+        Search,
+        "Run several content, file-name and glob queries over one walk, with per-query caps",
+        &[req("rootPath", Str), req("queries", Array)],
+    )
+    .long_running(),
+    cmd(
+        "exclusions_get",
+        Search,
+        "Directory names excluded from walks in addition to the built-in list",
+        &[],
+    ),
+    cmd(
+        "exclusions_set",
+        Search,
+        "Replace the user's excluded directory names; running walks pick them up",
+        &[req("dirs", Array)],
+    ),
+    cmd(
+        "walk_depth_get",
+        Search,
+        "Default depth limit of search and glob walks",
+        &[],
+    ),
+    cmd(
+        "walk_depth_set",
+        Search,
+        "Set and persist the default depth limit of search and glob walks",
+        &[req("depth", Num)],
+    ),
+    cmd(
+        "calculate_directory_sizes",
+        Files,
+        "Recursive total bytes and file count for directories, optionally skipping ignored ones",
+        &[
+            req("paths", Array),
+            req("respectIgnores", Bool),
+            opt("operationId", Str),
+        ],
+    )
+    .long_running()
+    .cancellable(),
+    cmd(
+        "create_project_window",
+        Windows,
+        "Open a project in a new or existing window",
+        &[
+            opt("projectId", Str),
+            opt("rootPath", Str),
+            req("isNewWindow", Bool),
+        ],
+    ),
+    cmd(
+        "infer_project_root",
+        Windows,
+        "Find the repository or workspace root enclosing a directory",
+        &[req("path", Str)],
+    ),
+    cmd(
+        "get_all_project_windows",
+        Windows,
+        "List open project windows",
+        &[],
+    ),
+    cmd(
+        "broadcast_request",
+        Windows,
+        "Send a request to project windows and collect their replies",
+        &[
+            opt("rootPathFilter", Str),
+            req("requestKind", Str),
+            req("payload", Object),
+            opt("timeoutMs", Num),
+        ],
+    )
+    .long_running(),
+    cmd(
+        "reply_to_broadcast",
+        Windows,
+        "Answer a broadcast request on behalf of a window",
+        &[
+            req("correlationId", Str),
+            req("windowLabel", Str),
+            req("payload", Object),
+        ],
+    ),
+    cmd(
+        "get_current_window_label",
+        Windows,
+        "Label of the calling window",
+        &[],
+    ),
+    cmd(
+        "get_window_info",
+        Windows,
+        "Project info for the calling window",
+        &[],
+    ),
+    cmd(
+        "window_registry_dump",
+        Windows,
+        "Window registry entries with liveness, for diagnostics",
+        &[],
+    ),
+    cmd(
+        "check_project_window_exists",
+        Windows,
+        "Label of the window showing a project, if any",
+        &[req("rootPath", Str)],
+    ),
+    cmd(
+        "focus_project_window",
+        Windows,
+        "Bring a window to the front",
+        &[req("label", Str)],
+    ),
+    cmd(
+        "close_project_window",
+        Windows,
+        "Close a project window",
+        &[req("label", Str)],
+    ),
+    cmd(
+        "update_window_project",
+        Windows,
+        "Change the project shown in a window",
+        &[
+            req("label", Str),
+            opt("projectId", Str),
+            opt("rootPath", Str),
+        ],
+    ),
+    cmd(
+        "start_window_file_watching",
+        Files,
+        "Watch a project directory for one window",
+        &[
+            req("windowLabel", Str),
+            req("path", Str),
+            opt("scaleHint", Str),
+        ],
+    ),
+    cmd(
+        "stop_window_file_watching",
+        Files,
+        "Stop a window's file watcher",
+        &[req("windowLabel", Str)],
+    ),
+    cmd(
+        "watch_condition_add",
+        Files,
+        "Emit an event when a file appears or changes, or a port starts listening",
+        &[req("spec", Object)],
+    ),
+    cmd(
+        "watch_condition_list",
+        Files,
+        "Active watch conditions",
+        &[],
+    ),
+    cmd(
+        "watch_condition_remove",
+        Files,
+        "Stop watching a condition",
+        &[req("id", Str)],
+    ),
+    cmd(
+        "clean_project_artifacts",
+        Project,
+        "Remove build artifacts and caches from a project",
+        &[
+            req("rootPath", Str),
+            req("categories", Array),
+            req("dryRun", Bool),
+            opt("permanent", Bool),
+            opt("windowLabel", Str),
+        ],
+    )
+    .long_running(),
+    cmd(
+        "duplicate_project",
+        Project,
+        "Duplicate a project folder, cloning files copy-on-write where supported",
+        &[
+            req("sourceRoot", Str),
+            req("destRoot", Str),
+            opt("mode", Str),
+            opt("includeArtifacts", Bool),
+        ],
+    )
+    .long_running()
+    .cancellable(),
+    cmd(
+        "list_process_tree",
+        Process,
+        "A process and its descendants",
+        &[req("rootPid", Num)],
+    ),
+    cmd(
+        "kill_process_tree",
+        Process,
+        "Signal a process and its descendants",
+        &[req("rootPid", Num), req("signal", Str), opt("force", Bool)],
+    ),
+    cmd(
+        "activity_list",
+        Activity,
+        "Running and recently finished background activities",
+        &[],
+    ),
+    cmd(
+        "activity_cancel",
+        Activity,
+        "Cancel a cancellable background activity",
+        &[req("id", Str)],
+    ),
+    cmd(
+        "get_concurrency_status",
+        Activity,
+        "Permits held and queued per interactive/background budget, and the rayon pool size",
+        &[],
+    ),
+    cmd(
+        "maintenance_list_jobs",
+        Maintenance,
+        "Scheduled maintenance jobs and their last runs",
+        &[],
+    ),
+    cmd(
+        "maintenance_run_now",
+        Maintenance,
+        "Run a maintenance job immediately",
+        &[req("name", Str)],
+    ),
+    cmd(
+        "get_storage_report",
+        Maintenance,
+        "Disk usage of each app cache and recent quota evictions",
+        &[],
+    )
+    .long_running(),
+    cmd(
+        "set_storage_quota",
+        Maintenance,
+        "Set or clear the disk quota of an app cache",
+        &[req("category", Str), opt("maxBytes", Num)],
+    ),
+    cmd(
+        "set_code_index_eviction",
+        Maintenance,
+        "Allow quota enforcement to remove persisted code indexes",
+        &[req("allowed", Bool)],
+    ),
+    cmd(
+        "activate_app",
+        App,
+        "Bring the application to the foreground",
+        &[],
+    ),
+    cmd("db_connect", Database, "Open the application database", &[]),
+    cmd(
+        "db_execute",
+        Database,
+        "Execute a SQL statement",
+        &[req("sql", Str), req("params", Array)],
+    ),
+    cmd(
+        "db_query",
+        Database,
+        "Run a SQL query and return rows",
+        &[req("sql", Str), req("params", Array)],
+    ),
+    cmd(
+        "db_batch",
+        Database,
+        "Execute SQL statements in one transaction",
+        &[req("statements", Array)],
+    ),
+    cmd(
+        "proxy_fetch",
+        Http,
+        "HTTP request through the backend",
+        &[req("request", Object)],
+    ),
+    cmd(
+        "proxy_fetch_stream",
+        Http,
+        "Streaming HTTP request through the backend",
+        &[req("request", Object)],
+    )
+    .long_running(),
+    cmd(
+        "stream_fetch",
+        Http,
+        "Streaming HTTP request delivered as events",
+        &[req("request", Object)],
+    )
+    .long_running(),
+    cmd(
+        "git_get_status",
+        Git,
+        "Branch and change summary of a repository",
+        &[req("repoPath", Str), opt("detectRenames", Bool)],
+    ),
+    cmd(
+        "git_is_repository",
+        Git,
+        "Whether a path is inside a git repository",
+        &[req("repoPath", Str)],
+    ),
+    cmd(
+        "git_get_repository_info",
+        Git,
+        "Repository root, git dir, sparse checkout and partial clone state",
+        &[req("repoPath", Str)],
+    ),
+    cmd(
+        "git_get_all_file_statuses",
+        Git,
+        "Status of every changed file",
+        &[req("repoPath", Str), opt("detectRenames", Bool)],
+    ),
+    cmd(
+        "git_get_directory_status_rollup",
+        Git,
+        "Changed-file counts and decoration status per directory",
+        &[req("repoPath", Str), opt("maxDepth", Num)],
+    ),
+    cmd(
+        "git_get_line_changes",
+        Git,
+        "Changed lines of a file for gutter markers",
+        &[req("repoPath", Str), req("filePath", Str)],
+    ),
+    cmd(
+        "git_get_line_changes_for_content",
+        Git,
+        "Changed lines of unsaved editor content",
+        &[
+            req("repoPath", Str),
+            req("filePath", Str),
+            req("content", Str),
+        ],
+    ),
+    cmd(
+        "git_get_all_file_diffs",
+        Git,
+        "Diffs of all changed files",
+        &[req("repoPath", Str)],
+    ),
+    cmd(
+        "git_get_raw_diff_text",
+        Git,
+        "Working tree diff as text",
+        &[req("repoPath", Str)],
+    ),
+    cmd(
+        "git_get_staged_summary",
+        Git,
+        "Size-bounded summary of the staged diff for commit messages",
+        &[
+            req("repoPath", Str),
+            opt("maxBytesPerFile", Num),
+            opt("maxFiles", Num),
+        ],
+    ),
+    cmd(
+        "git_get_default_worktree_root",
+        Git,
+        "Default directory for task worktrees",
+        &[],
+    ),
+    cmd(
+        "git_acquire_worktree",
+        Git,
+        "Check out a pooled worktree for a task",
+        &[
+            req("projectPath", Str),
+            req("poolIndex", Num),
+            req("taskId", Str),
+            opt("force", Bool),
+            opt("worktreeRoot", Str),
+        ],
+    )
+    .long_running(),
+    cmd(
+        "git_release_worktree",
+        Git,
+        "Return a pooled worktree",
+        &[req("projectPath", Str), req("poolIndex", Num)],
+    ),
+    cmd(
+        "git_remove_worktree",
+        Git,
+        "Delete a pooled worktree",
+        &[
+            req("projectPath", Str),
+            req("poolIndex", Num),
+            opt("worktreeRoot", Str),
+        ],
+    ),
+    cmd(
+        "git_list_worktrees",
+        Git,
+        "Pooled worktrees of a project",
+        &[req("projectPath", Str), opt("worktreeRoot", Str)],
+    ),
+    cmd(
+        "git_get_worktree_changes",
+        Git,
+        "Uncommitted changes in a worktree",
+        &[req("worktreePath", Str)],
+    ),
+    cmd(
+        "git_commit_worktree",
+        Git,
+        "Commit all changes in a worktree",
+        &[req("worktreePath", Str), req("message", Str)],
+    ),
+    cmd(
+        "git_merge_worktree",
+        Git,
+        "Merge a worktree's branch into the main branch",
+        &[
+            req("projectPath", Str),
+            req("poolIndex", Num),
+            opt("commitMessage", Str),
+            opt("worktreeRoot", Str),
+        ],
+    )
+    .long_running(),
+    cmd(
+        "git_abort_merge",
+        Git,
+        "Abort an in-progress merge",
+        &[req("projectPath", Str)],
+    ),
+    cmd(
+        "git_continue_merge",
+        Git,
+        "Finish a merge after resolving conflicts",
+        &[req("projectPath", Str), opt("message", Str)],
+    ),
+    cmd(
+        "git_cleanup_worktrees",
+        Git,
+        "Remove all pooled worktrees of a project",
+        &[req("projectPath", Str), opt("worktreeRoot", Str)],
+    ),
+    cmd(
+        "git_sync_worktree_from_main",
+        Git,
+        "Rebase a worktree onto the main branch",
+        &[
+            req("projectPath", Str),
+            req("poolIndex", Num),
+            opt("worktreeRoot", Str),
+        ],
+    )
+    .long_running(),
+    cmd(
+        "git_abort_rebase",
+        Git,
+        "Abort an in-progress rebase",
+        &[req("worktreePath", Str)],
+    ),
+    cmd(
+        "git_list_linked_worktrees",
+        Git,
+        "Worktrees linked to a repository",
+        &[req("repoPath", Str)],
+    ),
+    cmd(
+        "git_add_linked_worktree",
+        Git,
+        "Add a linked worktree",
+        &[
+            req("repoPath", Str),
+            req("newPath", Str),
+            req("branch", Str),
+            req("createBranch", Bool),
+        ],
+    ),
+    cmd(
+        "git_remove_linked_worktree",
+        Git,
+        "Remove a linked worktree",
+        &[
+            req("repoPath", Str),
+            req("worktreePath", Str),
+            req("force", Bool),
+        ],
+    ),
+    cmd(
+        "git_get_hooks",
+        Git,
+        "Configured git hooks",
+        &[req("repoPath", Str)],
+    ),
+    cmd(
+        "git_commit",
+        Git,
+        "Commit staged changes, optionally running commit hooks",
+        &[
+            req("repoPath", Str),
+            req("message", Str),
+            opt("runHooks", Bool),
+        ],
+    )
+    .long_running(),
+    cmd(
+        "git_get_file_history",
+        Git,
+        "Commits that changed a file, optionally following renames",
+        &[
+            req("repoPath", Str),
+            req("filePath", Str),
+            opt("limit", Num),
+            opt("followRenames", Bool),
+        ],
+    ),
+    cmd(
+        "git_get_file_at_commit",
+        Git,
+        "A file's content at a commit",
+        &[
+            req("repoPath", Str),
+            req("filePath", Str),
+            req("commitId", Str),
+        ],
+    ),
+    cmd(
+        "git_get_line_annotation",
+        Git,
+        "Blame of one line with commit summary and relative time, from a per-file cache",
+        &[
+            req("repoPath", Str),
+            req("filePath", Str),
+            req("line", Num),
+            opt("lineText", Str),
+        ],
+    ),
+    cmd(
+        "git_prefetch_blame",
+        Git,
+        "Blame a file ahead of line annotation queries",
+        &[req("repoPath", Str), req("filePath", Str)],
+    ),
+    cmd(
+        "git_get_repo_size_report",
+        Git,
+        "Size of the repository's git dir by objects, packfiles, refs and worktrees",
+        &[req("repoPath", Str)],
+    ),
+    cmd(
+        "git_run_housekeeping",
+        Git,
+        "Run git gc and report the bytes freed",
+        &[
+            req("repoPath", Str),
+            req("aggressive", Bool),
+            opt("operationId", Str),
+        ],
+    ),
+    cmd(
+        "ws_connect",
+        Websocket,
+        "Open the realtime WebSocket connection",
+        &[req("url", Str), req("apiKey", Str)],
+    ),
+    cmd(
+        "ws_send",
+        Websocket,
+        "Send a WebSocket message",
+        &[req("message", Str)],
+    ),
+    cmd(
+        "ws_disconnect",
+        Websocket,
+        "Close the WebSocket connection",
+        &[],
+    ),
+    cmd(
+        "ws_acquire",
+        Websocket,
+        "Get the pooled WebSocket for a purpose, connecting if needed",
+        &[req("purpose", Str), req("url", Str), opt("options", Object)],
+    ),
+    cmd(
+        "ws_release",
+        Websocket,
+        "Release a pooled WebSocket; it closes after the last release lingers",
+        &[req("purpose", Str)],
+    ),
+    cmd(
+        "ws_pool_send",
+        Websocket,
+        "Send a message on a pooled WebSocket",
+        &[req("purpose", Str), req("message", Str)],
+    ),
+    cmd(
+        "ws_pool_status",
+        Websocket,
+        "List pooled WebSockets with their health",
+        &[],
+    ),
+    cmd(
+        "ssh_save_secret",
+        Remote,
+        "Store an SSH password or key passphrase",
+        &[req("name", Str), req("value", Str)],
+    ),
+    cmd(
+        "ssh_connect",
+        Remote,
+        "Open an SSH session to a remote host",
+        &[
+            req("host", Str),
+            opt("port", Num),
+            req("user", Str),
+            req("auth", Object),
+        ],
+    )
+    .long_running(),
+    cmd(
+        "ssh_confirm_host_key",
+        Remote,
+        "Accept or reject a remote host key",
+        &[req("requestId", Str), req("accept", Bool)],
+    ),
+    cmd(
+        "ssh_list_dir",
+        Remote,
+        "List a remote directory over SFTP",
+        &[req("sessionId", Str), req("path", Str)],
+    ),
+    cmd(
+        "ssh_read_file",
+        Remote,
+        "Read a remote file over SFTP",
+        &[
+            req("sessionId", Str),
+            req("path", Str),
+            opt("maxBytes", Num),
+        ],
+    ),
+    cmd(
+        "ssh_write_file",
+        Remote,
+        "Write a remote file over SFTP",
+        &[req("sessionId", Str), req("path", Str), req("content", Str)],
+    ),
+    cmd(
+        "ssh_exec",
+        Remote,
+        "Run a command on the remote host",
+        &[
+            req("sessionId", Str),
+            req("command", Str),
+            opt("timeoutMs", Num),
+        ],
+    )
+    .long_running(),
+    cmd(
+        "ssh_search",
+        Remote,
+        "Search file contents on the remote host",
+        &[
+            req("sessionId", Str),
+            req("query", Str),
+            req("rootPath", Str),
+            opt("maxResults", Num),
+            opt("timeoutMs", Num),
+        ],
+    )
+    .long_running(),
+    cmd(
+        "ssh_disconnect",
+        Remote,
+        "Close an SSH session",
+        &[req("sessionId", Str)],
+    ),
+    cmd(
+        "execute_user_shell",
+        Shell,
+        "Run a command in the user's shell",
+        &[
+            req("command", Str),
+            opt("cwd", Str),
+            opt("timeoutMs", Num),
+            opt("idleTimeoutMs", Num),
+            opt("captureMetadata", Bool),
+            opt("rootPath", Str),
+            opt("env", Object),
+        ],
+    )
+    .long_running(),
+    cmd(
+        "task_save",
+        Shell,
+        "Create or update a project task; secret env values go to the secret store",
+        &[req("task", Object)],
+    ),
+    cmd(
+        "task_list",
+        Shell,
+        "Tasks defined for a project",
+        &[req("rootPath", Str)],
+    ),
+    cmd(
+        "task_delete",
+        Shell,
+        "Delete a task with its run history",
+        &[req("id", Str)],
+    ),
+    cmd(
+        "task_run",
+        Shell,
+        "Run a task, streaming task-output events, and record the run",
+        &[req("id", Str), opt("operationId", Str)],
+    )
+    .long_running()
+    .cancellable(),
+    cmd(
+        "task_run_history",
+        Shell,
+        "Past runs of a task, newest first",
+        &[req("id", Str), opt("limit", Num)],
+    ),
+    cmd(
+        "env_profile_save",
+        Shell,
+        "Create or update a project environment profile",
+        &[req("profile", Object)],
+    ),
+    cmd(
+        "env_profile_list",
+        Shell,
+        "Environment profiles of a project, marking the active one",
+        &[req("rootPath", Str)],
+    ),
+    cmd(
+        "env_profile_delete",
+        Shell,
+        "Delete an environment profile",
+        &[req("id", Str)],
+    ),
+    cmd(
+        "set_active_profile",
+        Shell,
+        "Activate an environment profile for a project, or deactivate with no profile",
+        &[req("rootPath", Str), opt("profileId", Str)],
+    ),
+    cmd(
+        "env_profile_save_secret",
+        Shell,
+        "Store a secret for environment profiles to reference",
+        &[req("name", Str), req("value", Str)],
+    ),
+    cmd(
+        "env_profile_export",
+        Shell,
+        "An environment profile as JSON with secrets replaced by placeholders",
+        &[req("id", Str)],
+    ),
+    cmd(
+        "env_profile_import",
+        Shell,
+        "Create an environment profile from exported JSON",
+        &[req("rootPath", Str), req("json", Str)],
+    ),
+    cmd(
+        "execute_skill_script",
+        Skills,
+        "Run a skill script",
+        &[req("request", Object), opt("rootPath", Str)],
+    )
+    .long_running(),
+    cmd(
+        "create_skill_tarball",
+        Skills,
+        "Package a skill directory as a tarball",
+        &[req("request", Object)],
+    )
+    .long_running(),
+    cmd(
+        "extract_skill_tarball",
+        Skills,
+        "Extract a skill tarball",
+        &[req("request", Object)],
+    )
+    .long_running(),
+    cmd(
+        "validate_skill_manifest",
+        Skills,
+        "Validate a skill's skill.json and list the capabilities it requests",
+        &[req("skillDir", Str)],
+    ),
+    cmd(
+        "archive_open_session",
+        Archive,
+        "Scan a tarball for browsing without extracting it",
+        &[req("tarballPath", Str)],
+    )
+    .long_running(),
+    cmd(
+        "archive_read_entry",
+        Archive,
+        "Read one entry of a tarball",
+        &[
+            req("tarballPath", Str),
+            req("entryPath", Str),
+            opt("maxBytes", Num),
+        ],
+    ),
+    cmd(
+        "archive_close_session",
+        Archive,
+        "Close an archive browsing session",
+        &[req("sessionId", Str)],
+    ),
+    cmd(
+        "pty_spawn",
+        Terminal,
+        "Start a terminal session",
+        &[
+            opt("cwd", Str),
+            opt("cols", Num),
+            opt("rows", Num),
+            opt("preferredShell", Str),
+            opt("rootPath", Str),
+            opt("env", Object),
+        ],
+    ),
+    cmd(
+        "pty_write",
+        Terminal,
+        "Write input to a terminal",
+        &[req("ptyId", Str), req("data", Str)],
+    ),
+    cmd(
+        "pty_resize",
+        Terminal,
+        "Resize a terminal",
+        &[req("ptyId", Str), req("cols", Num), req("rows", Num)],
+    ),
+    cmd(
+        "pty_kill",
+        Terminal,
+        "End a terminal session",
+        &[req("ptyId", Str)],
+    ),
+    cmd(
+        "history_search",
+        History,
+        "Search recorded terminal commands",
+        &[
+            req("query", Str),
+            opt("cwdFilter", Str),
+            opt("maxResults", Num),
+        ],
+    ),
+    cmd(
+        "history_delete",
+        History,
+        "Delete recorded commands",
+        &[req("ids", Array)],
+    ),
+    cmd(
+        "history_clear",
+        History,
+        "Delete all recorded commands",
+        &[],
+    ),
+    cmd(
+        "history_set_recording_enabled",
+        History,
+        "Turn command recording on or off",
+        &[req("enabled", Bool)],
+    ),
+    cmd(
+        "history_get_recording_enabled",
+        History,
+        "Whether command recording is on",
+        &[],
+    ),
+    cmd(
+        "search_history_add",
+        History,
+        "Record a project search run outside search_file_content",
+        &[req("params", Object), req("results", Array)],
+    ),
+    cmd(
+        "search_history_list",
+        History,
+        "List recorded project searches and replaces, newest first",
+        &[opt("rootPath", Str), opt("kind", Str), opt("limit", Num)],
+    ),
+    cmd(
+        "search_history_replay",
+        History,
+        "Re-run a recorded search with its original flags",
+        &[req("id", Num)],
+    )
+    .long_running(),
+    cmd(
+        "search_history_delete",
+        History,
+        "Delete recorded searches",
+        &[req("ids", Array)],
+    ),
+    cmd(
+        "search_history_clear",
+        History,
+        "Delete recorded searches, for one project or all",
+        &[opt("rootPath", Str)],
+    ),
+    cmd(
+        "search_history_set_recording_enabled",
+        History,
+        "Turn search recording on or off",
+        &[req("enabled", Bool)],
+    ),
+    cmd(
+        "search_history_get_recording_enabled",
+        History,
+        "Whether search recording is on",
+        &[],
+    ),
+    cmd(
+        "apply_workspace_edits",
+        Edits,
+        "Apply multi-file edits with crash recovery",
+        &[req("edits", Array)],
+    ),
+    cmd(
+        "pending_edit_recoveries",
+        Edits,
+        "Interrupted edit operations that can be recovered",
+        &[],
+    ),
+    cmd(
+        "recover_edit_operation",
+        Edits,
+        "Roll back or finish an interrupted edit operation",
+        &[req("id", Str), req("mode", Str)],
+    ),
+    cmd(
+        "undo_list_recent",
+        Edits,
+        "Most recent file changes made by the backend that can be undone",
+        &[opt("limit", Num)],
+    ),
+    cmd(
+        "undo_revert",
+        Edits,
+        "Restore the files changed by a backend operation, skipping ones edited since",
+        &[req("operationId", Str)],
+    ),
+    cmd(
+        "audit_set_enabled",
+        Audit,
+        "Start or stop recording sensitive backend calls, optionally choosing which commands",
+        &[req("enabled", Bool), opt("commands", Array)],
+    ),
+    cmd(
+        "audit_list_sessions",
+        Audit,
+        "Recorded audit sessions, newest first",
+        &[],
+    ),
+    cmd(
+        "audit_read_session",
+        Audit,
+        "A page of an audit session's recorded calls",
+        &[req("id", Str), opt("offset", Num), opt("limit", Num)],
+    ),
+    cmd(
+        "audit_export",
+        Audit,
+        "Copy an audit session to a JSONL file for a bug report",
+        &[req("id", Str), req("dest", Str)],
+    ),
+    cmd(
+        "extract_selection",
+        Edits,
+        "Plan moving whole top-level declarations into a new file",
+        &[
+            req("filePath", Str),
+            req("content", Str),
+            req("langId", Str),
+            req("startLine", Num),
+            req("endLine", Num),
+            req("newFilePath", Str),
+        ],
+    ),
+    cmd(
+        "write_file_atomic",
+        Files,
+        "Write a file atomically, optionally checking its prior hash",
+        &[
+            req("path", Str),
+            opt("content", Str),
+            opt("base64", Str),
+            opt("makeExecutable", Bool),
+            opt("expectedPriorHash", Str),
+        ],
+    ),
+    cmd(
+        "write_files_atomic",
+        Files,
+        "Write several files atomically, reporting per-file results",
+        &[req("files", Array)],
+    ),
+    cmd(
+        "read_file_by_raw_path",
+        Files,
+        "Read a text file by the raw bytes of a path that isn't valid UTF-8",
+        &[req("rawPathBytes", Str)],
+    ),
+    cmd(
+        "move_paths",
+        Files,
+        "Move files and folders, copying across devices",
+        &[req("moves", Array), opt("overwritePolicy", Str)],
+    ),
+    cmd(
+        "bookmark_add",
+        Bookmarks,
+        "Bookmark a line",
+        &[
+            req("rootPath", Str),
+            req("filePath", Str),
+            req("line", Num),
+            opt("note", Str),
+        ],
+    ),
+    cmd(
+        "bookmark_list",
+        Bookmarks,
+        "Bookmarks of a project",
+        &[req("rootPath", Str)],
+    ),
+    cmd(
+        "bookmark_remove",
+        Bookmarks,
+        "Delete a bookmark",
+        &[req("id", Num)],
+    ),
+    cmd(
+        "bookmark_update_note",
+        Bookmarks,
+        "Change a bookmark's note",
+        &[req("id", Num), opt("note", Str)],
+    ),
+    cmd(
+        "detect_project_profile",
+        Project,
+        "Detect languages, frameworks and tooling of a project",
+        &[req("rootPath", Str)],
+    ),
+    cmd(
+        "assess_project_scale",
+        Project,
+        "Estimate project size to pick indexing strategies",
+        &[req("rootPath", Str), opt("timeBudgetMs", Num)],
+    ),
+    cmd(
+        "code_nav_index_file",
+        CodeNavigation,
+        "Index the definitions in one file",
+        &[
+            req("filePath", Str),
+            req("content", Str),
+            req("langId", Str),
+        ],
+    ),
+    cmd(
+        "code_nav_index_files_batch",
+        CodeNavigation,
+        "Index the definitions in many files in parallel",
+        &[req("files", Array)],
+    )
+    .long_running(),
+    cmd(
+        "code_nav_find_definition",
+        CodeNavigation,
+        "Find definitions of a symbol",
+        &[
+            req("symbolName", Str),
+            req("langFamily", Str),
+            opt("contextFile", Str),
+            opt("samePackageOnly", Bool),
+            opt("includeDependencies", Bool),
+            opt("visibility", Str),
+        ],
+    ),
+    cmd(
+        "code_nav_index_dependency_types",
+        CodeNavigation,
+        "Index the public declarations of dependencies for definition lookups",
+        &[req("rootPath", Str), opt("packageNames", Array)],
+    )
+    .long_running(),
+    cmd(
+        "code_nav_find_definitions_bulk",
+        CodeNavigation,
+        "Find definitions of several symbols",
+        &[
+            req("symbolNames", Array),
+            req("langFamily", Str),
+            opt("contextFile", Str),
+            opt("samePackageOnly", Bool),
+            opt("visibility", Str),
+        ],
+    ),
+    cmd(
+        "code_nav_has_definitions",
+        CodeNavigation,
+        "Which symbols have indexed definitions",
+        &[req("symbolNames", Array), req("langFamily", Str)],
+    ),
+    cmd(
+        "code_nav_get_stats",
+        CodeNavigation,
+        "Index statistics",
+        &[],
+    ),
+    cmd(
+        "code_nav_get_language_health",
+        CodeNavigation,
+        "Whether each language's grammar and definition query loaded",
+        &[],
+    ),
+    cmd(
+        "trace_get_enabled",
+        CodeNavigation,
+        "Whether indexing and search timings are being traced",
+        &[],
+    ),
+    cmd(
+        "trace_set_enabled",
+        CodeNavigation,
+        "Turn tracing of indexing and search timings on or off",
+        &[req("enabled", Bool)],
+    ),
+    cmd(
+        "trace_get_recent",
+        CodeNavigation,
+        "Most recent trace records, optionally filtered by operation",
+        &[opt("limit", Num), opt("operationFilter", Str)],
+    ),
+    cmd(
+        "trace_export_chrome",
+        CodeNavigation,
+        "Write buffered trace records as Chrome trace-event JSON",
+        &[req("destPath", Str)],
+    ),
+    cmd(
+        "language_mappings_get",
+        CodeNavigation,
+        "Built-in and custom file-type to language mappings",
+        &[],
+    ),
+    cmd(
+        "language_mappings_set",
+        CodeNavigation,
+        "Replace the custom file-type to language mappings",
+        &[req("mappings", Array)],
+    ),
+    cmd(
+        "code_nav_get_markdown_fences",
+        CodeNavigation,
+        "Whether code fences in markdown files are indexed",
+        &[],
+    ),
+    cmd(
+        "code_nav_set_markdown_fences",
+        CodeNavigation,
+        "Turn indexing of code fences in markdown files on or off",
+        &[req("enabled", Bool)],
+    ),
+    cmd(
+        "format_code",
+        Edits,
+        "Format file content, or a byte range of it, with the language's external formatter",
+        &[
+            req("filePath", Str),
+            req("content", Str),
+            req("langId", Str),
+            opt("range", Object),
+        ],
+    )
+    .long_running(),
+    cmd(
+        "format_get_config",
+        Edits,
+        "Formatter command per language",
+        &[],
+    ),
+    cmd(
+        "format_set_config",
+        Edits,
+        "Replace the per-language formatter overrides",
+        &[req("overrides", Object)],
+    ),
+    cmd(
+        "format_check_formatters",
+        Edits,
+        "Which configured formatters are installed",
+        &[opt("refresh", Bool)],
+    ),
+    cmd(
+        "code_nav_reference_counts",
+        CodeNavigation,
+        "Reference counts for code lenses",
+        &[
+            req("symbols", Array),
+            req("rootPath", Str),
+            opt("budgetMs", Num),
+        ],
+    )
+    .long_running(),
+    cmd(
+        "code_nav_get_definition_body",
+        CodeNavigation,
+        "First lines of each symbol's definition for the peek popup, found near its stored position",
+        &[
+            req("symbols", Array),
+            opt("maxLines", Num),
+            opt("maxBytes", Num),
+            opt("budgetMs", Num),
+        ],
+    ),
+    cmd(
+        "code_nav_suggest_imports",
+        CodeNavigation,
+        "Where an unresolved symbol can be imported from, with the import statement to insert",
+        &[
+            req("symbolName", Str),
+            req("langFamily", Str),
+            req("currentFile", Str),
+            req("rootPath", Str),
+            opt("maxResults", Num),
+            opt("visibility", Str),
+        ],
+    ),
+    cmd(
+        "code_nav_find_references_hybrid",
+        CodeNavigation,
+        "Find references to a symbol, optionally within a scope",
+        &[
+            req("symbolName", Str),
+            req("langFamily", Str),
+            req("rootPath", Str),
+            opt("includePaths", Array),
+            opt("changedOnly", Bool),
+            opt("baseRef", Str),
+        ],
+    ),
+    cmd(
+        "compute_file_overview",
+        CodeNavigation,
+        "Per-bucket search, git change and definition counts for a file's minimap",
+        &[
+            req("filePath", Str),
+            opt("content", Str),
+            req("buckets", Num),
+            req("layers", Array),
+            opt("search", Object),
+        ],
+    ),
+    cmd(
+        "code_nav_occurrences_in_buffer",
+        CodeNavigation,
+        "Occurrences of a symbol name, or of the identifier at a position, in one buffer",
+        &[
+            req("content", Str),
+            req("langId", Str),
+            req("symbolNameOrPosition", Object),
+            opt("includeStringsAndComments", Bool),
+        ],
+    ),
+    cmd(
+        "code_nav_clear_file",
+        CodeNavigation,
+        "Remove a file from the index",
+        &[req("filePath", Str)],
+    ),
+    cmd("code_nav_clear_all", CodeNavigation, "Clear the index", &[]),
+    cmd(
+        "code_nav_save_index",
+        CodeNavigation,
+        "Persist the index for a project, reporting progress",
+        &[
+            req("rootPath", Str),
+            req("fileTimestamps", Object),
+            opt("operationId", Str),
+        ],
+    )
+    .long_running()
+    .cancellable(),
+    cmd(
+        "code_nav_load_index",
+        CodeNavigation,
+        "Load the persisted index for a project, reporting progress; gives up on stalled disks",
+        &[
+            req("rootPath", Str),
+            opt("operationId", Str),
+            opt("keepPartial", Bool),
+            opt("timeoutMs", Num),
+        ],
+    )
+    .long_running()
+    .cancellable(),
+    cmd(
+        "code_nav_get_index_metadata",
+        CodeNavigation,
+        "Metadata of the persisted index",
+        &[req("rootPath", Str)],
+    ),
+    cmd(
+        "code_nav_delete_index",
+        CodeNavigation,
+        "Delete the persisted index",
+        &[req("rootPath", Str)],
+    ),
+    cmd(
+        "code_nav_get_indexed_files",
+        CodeNavigation,
+        "Files in the index",
+        &[],
+    ),
+    cmd(
+        "code_nav_list_indexable_files",
+        CodeNavigation,
+        "Files worth indexing with their mtimes, from git when the project is a repository",
+        &[
+            req("rootPath", Str),
+            opt("respectGitignore", Bool),
+            opt("includeUntracked", Bool),
+        ],
+    )
+    .long_running(),
+    cmd(
+        "code_nav_bootstrap_index",
+        CodeNavigation,
+        "Build the project index in the backend, resuming an interrupted build and saving it",
+        &[
+            req("rootPath", Str),
+            opt("options", Object),
+            opt("operationId", Str),
+        ],
+    )
+    .long_running(),
+    cmd(
+        "reconcile_project_state",
+        CodeNavigation,
+        "Drop index and directory cache entries of files deleted from disk",
+        &[req("rootPath", Str)],
+    )
+    .long_running(),
+    cmd(
+        "summarize_code_content",
+        CodeNavigation,
+        "Reduce code to signatures and definitions",
+        &[
+            req("content", Str),
+            req("langId", Str),
+            req("filePath", Str),
+        ],
+    ),
+    cmd(
+        "build_docs_index",
+        Docs,
+        "Build or refresh the documentation search index",
+        &[req("rootPath", Str), opt("globs", Array)],
+    )
+    .long_running(),
+    cmd(
+        "query_docs_index",
+        Docs,
+        "Search project documentation",
+        &[
+            req("rootPath", Str),
+            req("query", Str),
+            opt("maxResults", Num),
+        ],
+    ),
+    cmd("docset_list", Docs, "Installed offline docsets", &[]),
+    cmd(
+        "docset_import",
+        Docs,
+        "Install a Dash/Zeal docset archive",
+        &[req("archivePath", Str)],
+    )
+    .long_running(),
+    cmd(
+        "docset_remove",
+        Docs,
+        "Uninstall a docset",
+        &[req("id", Str)],
+    ),
+    cmd(
+        "docset_search",
+        Docs,
+        "Search docset entries by name",
+        &[
+            req("query", Str),
+            opt("docsetIds", Array),
+            opt("maxResults", Num),
+        ],
+    ),
+    cmd(
+        "docset_get_entry",
+        Docs,
+        "Docset entry with the page to display",
+        &[req("id", Str)],
+    ),
+    cmd(
+        "docset_lookup_symbol",
+        Docs,
+        "Docset entries for a symbol without a local definition",
+        &[
+            req("symbolName", Str),
+            req("langFamily", Str),
+            opt("maxResults", Num),
+        ],
+    ),
+    cmd(
+        "estimate_tokens",
+        Tokens,
+        "Count tokens in a text",
+        &[req("text", Str)],
+    ),
+    cmd(
+        "estimate_tokens_batch",
+        Tokens,
+        "Count tokens in several texts and files",
+        &[
+            opt("texts", Array),
+            opt("filePaths", Array),
+            opt("encoding", Str),
+            opt("maxFileBytes", Num),
+        ],
+    ),
+    cmd(
+        "run_lint",
+        Lint,
+        "Lint a saved file; the result arrives as a lint-result event",
+        &[
+            req("filePath", Str),
+            req("rootPath", Str),
+            req("requestId", Str),
+        ],
+    ),
+    cmd(
+        "check_lint_runtime",
+        Lint,
+        "Whether the lint runtime is available",
+        &[],
+    ),
+    cmd(
+        "spawn_background_task",
+        BackgroundTasks,
+        "Start a command in the background",
+        &[req("request", Object)],
+    ),
+    cmd(
+        "get_background_task_status",
+        BackgroundTasks,
+        "Status of a background task",
+        &[req("taskId", Str)],
+    ),
+    cmd(
+        "get_background_task_output",
+        BackgroundTasks,
+        "New output of a background task",
+        &[
+            req("taskId", Str),
+            req("stdoutBytesRead", Num),
+            req("stderrBytesRead", Num),
+        ],
+    ),
+    cmd(
+        "kill_background_task",
+        BackgroundTasks,
+        "Stop a background task",
+        &[req("taskId", Str)],
+    ),
+    cmd(
+        "list_background_tasks",
+        BackgroundTasks,
+        "Background tasks and their status",
+        &[],
+    ),
+    cmd(
+        "cleanup_background_tasks",
+        BackgroundTasks,
+        "Delete old background task output directories",
+        &[],
+    ),
+    cmd(
+        "lsp_start_server",
+        Lsp,
+        "Start a language server",
+        &[req("language", Str), req("rootPath", Str)],
+    ),
+    cmd(
+        "lsp_send_message",
+        Lsp,
+        "Send a message to a language server",
+        &[req("serverId", Str), req("message", Str)],
+    ),
+    cmd(
+        "lsp_stop_server",
+        Lsp,
+        "Stop a language server",
+        &[req("serverId", Str)],
+    ),
+    cmd("lsp_list_servers", Lsp, "Running language servers", &[]),
+    cmd(
+        "lsp_check_server_available",
+        Lsp,
+        "Whether a language server is installed",
+        &[req("language", Str)],
+    ),
+    cmd(
+        "lsp_get_server_config",
+        Lsp,
+        "Language server configuration",
+        &[req("language", Str)],
+    ),
+    cmd(
+        "lsp_get_server_status",
+        Lsp,
+        "Install and run status of a language server",
+        &[req("language", Str)],
+    ),
+    cmd(
+        "lsp_download_server",
+        Lsp,
+        "Download and install a language server",
+        &[req("language", Str)],
+    )
+    .long_running(),
+    cmd(
+        "start_oauth_callback_server",
+        Auth,
+        "Start the local OAuth callback server",
+        &[opt("expectedState", Str)],
+    ),
+    cmd(
+        "get_backend_capabilities",
+        App,
+        "This registry of backend commands",
+        &[],
+    ),
+    cmd(
+        "get_event_schema",
+        App,
+        "JSON schema of every event payload the backend emits, keyed by event name",
+        &[],
+    ),
+];
+
+#[tauri::command]
+pub fn get_backend_capabilities() -> BackendCapabilities {
+    BackendCapabilities {
+        version: env!("CARGO_PKG_VERSION"),
+        commands: COMMANDS,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    /// Command names registered in lib.rs, read from the generate_handler! invocation
+    fn registered_commands() -> Vec<String> {
+        let source = include_str!("lib.rs");
+        let start = source
+            .find("generate_handler![")
+            .expect("generate_handler! not found in lib.rs")
+            + "generate_handler![".len();
+        let end = start + source[start..].find(']').unwrap();
+        source[start..end]
+            .lines()
+            .map(|line| line.split("//").next().unwrap().trim())
+            .flat_map(|line| line.split(','))
+            .map(|entry| entry.trim())
+            .filter(|entry| !entry.is_empty())
+            .map(|path| path.rsplit("::").next().unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_table_matches_registered_commands() {
+        let registered: HashSet<String> = registered_commands().into_iter().collect();
+        let declared: HashSet<String> = COMMANDS.iter().map(|c| c.name.to_string()).collect();
+        assert_eq!(
+            declared.len(),
+            COMMANDS.len(),
+            "duplicate entries in COMMANDS"
+        );
+
+        let mut missing: Vec<&String> = registered.difference(&declared).collect();
+        missing.sort();
+        assert!(
+            missing.is_empty(),
+            "commands registered in lib.rs but missing from capabilities::COMMANDS: {:?}",
+            missing
+        );
+        let mut stale: Vec<&String> = declared.difference(&registered).collect();
+        stale.sort();
+        assert!(
+            stale.is_empty(),
+            "capabilities::COMMANDS lists commands that are not registered: {:?}",
+            stale
+        );
+    }
+
+    #[test]
+    fn test_specs_are_well_formed() {
+        for command in COMMANDS {
+            assert!(!command.description.is_empty(), "{}", command.name);
+            assert!(
+                !command.cancellable || command.long_running,
+                "{} is cancellable but not long-running",
+                command.name
+            );
+            let mut names = HashSet::new();
+            for param in command.params {
+                // Tauri converts snake_case argument names to camelCase for invoke
+                assert!(!param.name.contains('_'), "{}.{}", command.name, param.name);
+                assert!(names.insert(param.name), "{}.{}", command.name, param.name);
+            }
+        }
+
+        let json = serde_json::to_value(get_backend_capabilities()).unwrap();
+        let commit = json["commands"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|c| c["name"] == "git_commit")
+            .unwrap();
+        assert_eq!(commit["category"], "git");
+        assert_eq!(commit["longRunning"], true);
+        assert_eq!(commit["params"][2]["type"], "boolean");
+        assert_eq!(commit["params"][2]["required"], false);
+    }
+}
@@ -0,0 +1,13 @@
+// Clock module
+// Wall-clock timestamps as stored in history tables, the audit log, the undo journal and
+// events sent to the frontend.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Milliseconds since the Unix epoch; 0 when the system clock is set before it
+pub fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
@@ -1,12 +1,24 @@
-use crate::search::RipgrepSearch;
+use crate::activity::{self, ActivityKind, ActivityOutcome};
+use crate::concurrency::{self, OperationClass};
+use crate::dependency_index::{self, DependencyIndexResult};
+use crate::docs_index::{self, DocsIndexStats};
+use crate::embedded_code;
+use crate::index_persist::{self, PersistControl, PersistError, PersistPhase, PhaseTiming};
+use crate::path_wire::{self, RawPath};
+use crate::perf_trace::{self, Phase};
+use crate::search::{RipgrepSearch, SearchResult};
 use rayon::prelude::*;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
+use std::borrow::Cow;
 use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::path::PathBuf;
-use std::sync::RwLock;
-use std::time::Instant;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::time::{Duration, Instant};
 use streaming_iterator::StreamingIterator;
 use tauri::{AppHandle, Manager, State};
 use tree_sitter::{Language, Parser, Point, Query, QueryCursor, Tree};
@@ -21,20 +33,970 @@ pub struct SymbolInfo {
     pub start_column: u32,
     pub end_line: u32,
     pub end_column: u32,
+    // Columns counted in UTF-16 code units, as Monaco expects; the columns above are bytes
+    #[serde(default)]
+    pub start_column_utf16: u32,
+    #[serde(default)]
+    pub end_column_utf16: u32,
+    /// Whether other modules can import the definition: exported in JS/TS, module-level in
+    /// Python, pub in Rust
+    #[serde(default)]
+    pub exported: bool,
+    /// Indexed from a dependency (node_modules type declarations, registry crate sources)
+    /// rather than the project. Only returned by lookups that ask for dependencies.
+    #[serde(default)]
+    pub dependency: bool,
+    /// Access level by the language's rules; unknown for C/C++ and for entries persisted
+    /// before it was indexed
+    #[serde(default)]
+    pub visibility: Visibility,
 }
 
+/// Access level of a definition
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Visibility {
+    /// `pub` in Rust, exported in JS/TS, public class members, capitalized in Go, public in
+    /// Java, no leading underscore in Python
+    Public,
+    /// `pub(crate)`, `pub(super)` and `pub(in ..)` in Rust, unexported in Go, package-private
+    /// in Java
+    Internal,
+    /// Protected class members in TS and Java
+    Protected,
+    /// Private to its file, module or class: no `pub` in Rust, not exported in JS/TS,
+    /// `private` or `#name` members, a leading underscore in Python
+    Private,
+    #[default]
+    Unknown,
+}
+
+/// Visibility restriction of definition lookups
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VisibilityFilter {
+    /// Public definitions only. Entries of unknown visibility fall back to `exported`.
+    ExportedOnly,
+    /// Everything but private definitions
+    NonPrivate,
+}
+
+impl VisibilityFilter {
+    pub fn accepts(&self, symbol: &SymbolInfo) -> bool {
+        match (self, symbol.visibility) {
+            (VisibilityFilter::ExportedOnly, Visibility::Unknown) => symbol.exported,
+            (VisibilityFilter::ExportedOnly, visibility) => visibility == Visibility::Public,
+            (VisibilityFilter::NonPrivate, visibility) => visibility != Visibility::Private,
+        }
+    }
+}
+
+/// Keep the definitions `filter` accepts; no filter keeps everything
+pub fn retain_visible(symbols: &mut Vec<SymbolInfo>, filter: Option<VisibilityFilter>) {
+    if let Some(filter) = filter {
+        symbols.retain(|symbol| filter.accepts(symbol));
+    }
+}
+
+/// Optional restriction of a reference search to part of the project. Scopes combine as a
+/// union and each reference reports the scopes it fell in. An empty scope searches the
+/// whole root.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReferenceScope {
+    /// Directories or files, absolute or relative to the root
+    pub include_paths: Option<Vec<String>>,
+    /// Files modified, staged or untracked in the root's git repository
+    pub changed_only: bool,
+    /// With `changed_only`, also files changed on this branch since `base_ref`
+    pub base_ref: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScopedReference {
+    #[serde(flatten)]
+    pub symbol: SymbolInfo,
+    /// Labels of the scopes the reference matched ("path:src/ui", "changed", "base:main")
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub matched_scopes: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReferenceSearchResult {
+    pub references: Vec<ScopedReference>,
+    /// The root is a sparse checkout: files outside the sparse cone are not on disk, so
+    /// references in them are missing
+    pub sparse_checkout_active: bool,
+}
+
+/// The symbol whose occurrences to find in a buffer: a name, or the identifier at a 1-based
+/// line and UTF-16 column as Monaco reports the cursor
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum OccurrenceTarget {
+    Name(String),
+    Position { line: u32, column: u32 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OccurrenceKind {
+    Definition,
+    Reference,
+    /// Assigned to or incremented
+    Write,
+    /// Inside a string or comment; only returned when asked for
+    Text,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BufferOccurrence {
+    pub kind: OccurrenceKind,
+    pub start_line: u32,
+    pub start_column: u32,
+    pub end_line: u32,
+    pub end_column: u32,
+    pub start_column_utf16: u32,
+    pub end_column_utf16: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BufferOccurrences {
+    pub symbol_name: String,
+    pub occurrences: Vec<BufferOccurrence>,
+}
+
+/// A resolved scope: everything under a directory or file, or an explicit set of files.
+/// Paths are canonical so scopes reached through different spellings still match.
+enum ScopePaths {
+    Under(PathBuf),
+    Files(HashSet<PathBuf>),
+}
+
+struct ResolvedScope {
+    label: String,
+    paths: ScopePaths,
+}
+
+impl ResolvedScope {
+    fn contains(&self, path: &Path) -> bool {
+        match &self.paths {
+            ScopePaths::Under(prefix) => path.starts_with(prefix),
+            ScopePaths::Files(files) => files.contains(path),
+        }
+    }
+
+    fn search_paths(&self) -> Vec<String> {
+        match &self.paths {
+            ScopePaths::Under(prefix) => vec![path_wire::encode(prefix)],
+            ScopePaths::Files(files) => files.iter().map(|file| path_wire::encode(file)).collect(),
+        }
+    }
+}
+
+/// Resolve a scope against the root. Returns None when nothing restricts the search.
+/// `changed_only` outside a git repository does not restrict it either.
+fn resolve_reference_scope(
+    root_path: &str,
+    scope: &ReferenceScope,
+) -> Result<Option<Vec<ResolvedScope>>, String> {
+    let root = Path::new(root_path);
+    let mut scopes = Vec::new();
+    let mut restricted = false;
+
+    for include in scope.include_paths.iter().flatten() {
+        restricted = true;
+        let path = root.join(include);
+        match path.canonicalize() {
+            Ok(path) => scopes.push(ResolvedScope {
+                label: format!("path:{}", include),
+                paths: ScopePaths::Under(path),
+            }),
+            Err(_) => log::warn!("Reference scope path does not exist: {}", path.display()),
+        }
+    }
+
+    if scope.changed_only {
+        match crate::git::repository::discover_repository(root) {
+            Ok(repo) => {
+                restricted = true;
+                let workdir = repo
+                    .workdir()
+                    .ok_or("Cannot list changed files in a bare repository")?
+                    .to_path_buf();
+                let existing_files = |paths: Vec<String>| -> HashSet<PathBuf> {
+                    paths
+                        .into_iter()
+                        .filter_map(|path| workdir.join(path).canonicalize().ok())
+                        .filter(|path| path.is_file())
+                        .collect()
+                };
+
+                let statuses = crate::git::status::get_all_file_statuses(&repo, false)
+                    .map_err(|e| format!("Failed to get git status: {}", e))?;
+                scopes.push(ResolvedScope {
+                    label: "changed".to_string(),
+                    paths: ScopePaths::Files(existing_files(statuses.into_keys().collect())),
+                });
+                if let Some(ref base_ref) = scope.base_ref {
+                    let changed = crate::git::diff::get_paths_changed_since(&repo, base_ref)
+                        .map_err(|e| format!("Failed to diff against {}: {}", base_ref, e))?;
+                    scopes.push(ResolvedScope {
+                        label: format!("base:{}", base_ref),
+                        paths: ScopePaths::Files(existing_files(changed)),
+                    });
+                }
+            }
+            Err(_) => log::warn!(
+                "changed_only ignored, {} is not in a git repository",
+                root_path
+            ),
+        }
+    }
+
+    Ok(restricted.then_some(scopes))
+}
+
+/// Converts tree-sitter byte columns to 1-based UTF-16 columns. Per-line offset tables are
+/// built on first use and cached, since many symbols often share a line.
+struct Utf16ColumnMap<'a> {
+    source: &'a [u8],
+    line_starts: Vec<usize>,
+    // Row -> UTF-16 offset for each byte offset in the line; None for ASCII-only lines
+    lines: HashMap<usize, Option<Vec<u32>>>,
+}
+
+impl<'a> Utf16ColumnMap<'a> {
+    fn new(source: &'a [u8]) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(
+                source
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, &b)| b == b'\n')
+                    .map(|(i, _)| i + 1),
+            )
+            .collect();
+        Self {
+            source,
+            line_starts,
+            lines: HashMap::new(),
+        }
+    }
+
+    fn column(&mut self, point: Point) -> u32 {
+        let (source, line_starts) = (self.source, &self.line_starts);
+        let offsets = self.lines.entry(point.row).or_insert_with(|| {
+            let start = line_starts.get(point.row).copied().unwrap_or(source.len());
+            let end = line_starts
+                .get(point.row + 1)
+                .copied()
+                .unwrap_or(source.len());
+            let line = &source[start..end];
+            if line.is_ascii() {
+                return None;
+            }
+            let mut offsets = vec![0u32; line.len() + 1];
+            let mut utf16 = 0;
+            let mut byte = 0;
+            for ch in String::from_utf8_lossy(line).chars() {
+                // Lossy decoding keeps byte lengths for valid UTF-8; clamp for the rest
+                let len = ch.len_utf8().min(line.len() - byte);
+                for i in 0..len {
+                    offsets[byte + i] = utf16;
+                }
+                byte += len;
+                utf16 += ch.len_utf16() as u32;
+                if byte >= line.len() {
+                    break;
+                }
+            }
+            offsets[byte..].fill(utf16);
+            Some(offsets)
+        });
+        match offsets {
+            Some(offsets) => offsets[point.column.min(offsets.len() - 1)] + 1,
+            None => point.column as u32 + 1,
+        }
+    }
+}
+
+/// How the node under a text match of a symbol relates to it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ReferenceMatch {
+    Reference,
+    StringOrComment,
+    Excluded,
+}
+
+/// Node kinds that can name a symbol in a language
+fn is_identifier_kind(kind: &str, lang_id: &str) -> bool {
+    let valid_kinds = match lang_id {
+        "typescript" | "javascript" => {
+            &["identifier", "type_identifier", "property_identifier"][..]
+        }
+        "go" => &["identifier", "type_identifier", "field_identifier"][..],
+        _ => &["identifier", "type_identifier"][..],
+    };
+    valid_kinds.contains(&kind)
+}
+
+/// Byte offsets of the whole-word occurrences of `symbol_name` in a line
+pub(crate) fn word_occurrences<'a>(
+    line: &'a str,
+    symbol_name: &'a str,
+) -> impl Iterator<Item = usize> + 'a {
+    let bytes = line.as_bytes();
+    let is_word = |b: u8| b.is_ascii_alphanumeric() || b == b'_';
+    line.match_indices(symbol_name)
+        .map(|(col, _)| col)
+        .filter(move |&col| {
+            let after = col + symbol_name.len();
+            (col == 0 || !is_word(bytes[col - 1]))
+                && (after >= bytes.len() || !is_word(bytes[after]))
+        })
+}
+
+/// Ids of the nodes the definition query captures
+fn definition_node_ids(tree: &Tree, query: &Query, source: &[u8]) -> HashSet<usize> {
+    let mut ids = HashSet::new();
+    let mut cursor = QueryCursor::new();
+    let mut matches = cursor.matches(query, tree.root_node(), source);
+    while let Some(m) = matches.next() {
+        ids.extend(m.captures.iter().map(|capture| capture.node.id()));
+    }
+    ids
+}
+
+fn is_field_of(parent: &tree_sitter::Node, field: &str, node: &tree_sitter::Node) -> bool {
+    let mut cursor = parent.walk();
+    let mut children = parent.children_by_field_name(field, &mut cursor);
+    children.any(|child| child.id() == node.id())
+}
+
+/// Whether an identifier is the name a declaration introduces: locals, parameters and
+/// nested declarations the definition query leaves out
+fn is_declaration_name(node: &tree_sitter::Node) -> bool {
+    let Some(parent) = node.parent() else {
+        return false;
+    };
+    let kind = parent.kind();
+    if kind == "parameters" || kind == "formal_parameters" {
+        return true;
+    }
+    // Go `a, b := ...`
+    if kind == "expression_list" {
+        return parent.parent().is_some_and(|declaration| {
+            declaration.kind() == "short_var_declaration"
+                && is_field_of(&declaration, "left", &parent)
+        });
+    }
+    let declares = [
+        "_declarator",
+        "_declaration",
+        "_definition",
+        "_item",
+        "_spec",
+        "parameter",
+    ]
+    .iter()
+    .any(|suffix| kind.ends_with(suffix));
+    declares
+        && ["name", "pattern", "declarator"]
+            .iter()
+            .any(|field| is_field_of(&parent, field, node))
+}
+
+/// Whether an identifier is assigned to, directly or by destructuring, or incremented
+fn is_write_target(node: &tree_sitter::Node) -> bool {
+    let mut child = *node;
+    while let Some(parent) = child.parent() {
+        match parent.kind() {
+            "pattern_list"
+            | "tuple_pattern"
+            | "list_pattern"
+            | "expression_list"
+            | "array_pattern"
+            | "parenthesized_expression"
+            | "tuple_expression" => {
+                child = parent;
+            }
+            "assignment"
+            | "augmented_assignment"
+            | "assignment_expression"
+            | "augmented_assignment_expression"
+            | "compound_assignment_expr"
+            | "assignment_statement" => return is_field_of(&parent, "left", &child),
+            "update_expression" | "inc_statement" | "dec_statement" => return true,
+            _ => return false,
+        }
+    }
+    false
+}
+
+fn occurrence_kind(node: &tree_sitter::Node, definitions: &HashSet<usize>) -> OccurrenceKind {
+    if definitions.contains(&node.id()) || is_declaration_name(node) {
+        OccurrenceKind::Definition
+    } else if is_write_target(node) {
+        OccurrenceKind::Write
+    } else {
+        OccurrenceKind::Reference
+    }
+}
+
+/// Text of the identifier at a 0-based row and 1-based UTF-16 column. A cursor just past
+/// the end of an identifier still selects it.
+fn identifier_at(
+    tree: &Tree,
+    source: &str,
+    row: usize,
+    column_utf16: u32,
+    lang_id: &str,
+) -> Option<String> {
+    let line = source.split('\n').nth(row)?;
+    let target = column_utf16.saturating_sub(1) as usize;
+    let mut utf16 = 0;
+    let mut byte = line.len();
+    for (offset, ch) in line.char_indices() {
+        if utf16 >= target {
+            byte = offset;
+            break;
+        }
+        utf16 += ch.len_utf16();
+    }
+    let candidates = [Some(byte), byte.checked_sub(1)];
+    candidates.into_iter().flatten().find_map(|column| {
+        let point = Point::new(row, column);
+        let node = tree.root_node().descendant_for_point_range(point, point)?;
+        if !is_identifier_kind(node.kind(), lang_id) {
+            return None;
+        }
+        node.utf8_text(source.as_bytes()).ok().map(str::to_string)
+    })
+}
+
+/// Code parsed as one tree: a whole file, or a region embedded in a container file
+struct SourceUnit<'a> {
+    lang_id: &'a str,
+    /// Lines of the file before the unit
+    line_offset: u32,
+    content: Cow<'a, str>,
+}
+
+impl SourceUnit<'_> {
+    /// Whether a 1-based line of the file falls inside the unit
+    fn contains_line(&self, line_number: u64) -> bool {
+        let first = self.line_offset as u64 + 1;
+        let last = self.line_offset as u64 + self.content.lines().count().max(1) as u64;
+        (first..=last).contains(&line_number)
+    }
+}
+
+/// The units to parse for a file: the file itself, or the regions of a container
+fn source_units<'a>(lang_id: &'a str, content: &'a str) -> Vec<SourceUnit<'a>> {
+    if !embedded_code::is_container(lang_id) {
+        return vec![SourceUnit {
+            lang_id,
+            line_offset: 0,
+            content: Cow::Borrowed(content),
+        }];
+    }
+    embedded_code::extract_regions(lang_id, content)
+        .into_iter()
+        .map(|region| SourceUnit {
+            lang_id: region.lang_id,
+            line_offset: region.line_offset,
+            content: Cow::Owned(region.content),
+        })
+        .collect()
+}
+
+/// Definitions captured by `query` in a parsed unit, with lines relative to the file
+fn collect_definitions(
+    tree: &Tree,
+    query: &Query,
+    unit: &SourceUnit,
+    file_path: &str,
+) -> Vec<SymbolInfo> {
+    let source_bytes = unit.content.as_bytes();
+    let lang_family = CodeNavigationService::get_lang_family(unit.lang_id);
+    let mut utf16_columns = Utf16ColumnMap::new(source_bytes);
+    let mut definitions: Vec<SymbolInfo> = Vec::new();
+    // Text of the `@visibility` capture (visibility or access modifiers) per definition
+    let mut modifiers: Vec<Option<&str>> = Vec::new();
+    // A node matched by both a plain and an `.exported` pattern is one definition
+    let mut by_node: HashMap<usize, usize> = HashMap::new();
+    let mut cursor = QueryCursor::new();
+    let mut matches = cursor.matches(query, tree.root_node(), source_bytes);
+    while let Some(m) = matches.next() {
+        let modifier = m
+            .captures
+            .iter()
+            .find(|capture| query.capture_names()[capture.index as usize] == "visibility")
+            .and_then(|capture| capture.node.utf8_text(source_bytes).ok());
+        for capture in m.captures {
+            let node = capture.node;
+            let capture_name = query.capture_names()[capture.index as usize];
+            if capture_name == "visibility" {
+                continue;
+            }
+            // Use continue instead of ? to avoid skipping the entire file on one bad capture
+            let name = match node.utf8_text(source_bytes) {
+                Ok(text) => text.to_string(),
+                Err(_) => continue,
+            };
+            let exported = capture_name.ends_with(".exported");
+            if let Some(&existing) = by_node.get(&node.id()) {
+                definitions[existing].exported |= exported;
+                modifiers[existing] = modifiers[existing].or(modifier);
+                continue;
+            }
+            by_node.insert(node.id(), definitions.len());
+            modifiers.push(modifier);
+            definitions.push(SymbolInfo {
+                name,
+                kind: CodeNavigationService::get_symbol_kind(capture_name),
+                file_path: file_path.to_string(),
+                lang_family: lang_family.to_string(),
+                start_line: unit.line_offset + node.start_position().row as u32 + 1,
+                start_column: node.start_position().column as u32 + 1,
+                end_line: unit.line_offset + node.end_position().row as u32 + 1,
+                end_column: node.end_position().column as u32 + 1,
+                start_column_utf16: utf16_columns.column(node.start_position()),
+                end_column_utf16: utf16_columns.column(node.end_position()),
+                exported,
+                dependency: false,
+                visibility: Visibility::Unknown,
+            });
+        }
+    }
+    for (definition, modifier) in definitions.iter_mut().zip(modifiers) {
+        definition.visibility = classify_visibility(unit.lang_id, definition, modifier);
+    }
+    definitions
+}
+
+/// Visibility of a definition by its language's rules. `modifier` is the text of the
+/// definition's `@visibility` capture, if its pattern has one.
+fn classify_visibility(
+    lang_id: &str,
+    definition: &SymbolInfo,
+    modifier: Option<&str>,
+) -> Visibility {
+    let name = definition.name.as_str();
+    match lang_id {
+        "rust" => match modifier.map(|m| m.split_whitespace().collect::<String>()) {
+            None => Visibility::Private,
+            Some(m) if m == "pub" => Visibility::Public,
+            Some(m) if m == "pub(self)" => Visibility::Private,
+            // pub(crate), pub(super), pub(in path) and the old `crate` shorthand
+            Some(_) => Visibility::Internal,
+        },
+        "typescript" | "javascript" if definition.kind == "method" => {
+            if name.starts_with('#') {
+                return Visibility::Private;
+            }
+            match modifier {
+                Some("private") => Visibility::Private,
+                Some("protected") => Visibility::Protected,
+                _ => Visibility::Public,
+            }
+        }
+        "typescript" | "javascript" if definition.exported => Visibility::Public,
+        "typescript" | "javascript" => Visibility::Private,
+        "python" => {
+            let dunder = name.len() > 4 && name.starts_with("__") && name.ends_with("__");
+            if name.starts_with('_') && !dunder {
+                Visibility::Private
+            } else {
+                Visibility::Public
+            }
+        }
+        "go" if name.starts_with(|c: char| c.is_uppercase()) => Visibility::Public,
+        "go" => Visibility::Internal,
+        "java" => {
+            let words: Vec<&str> = modifier
+                .map(|m| m.split_whitespace().collect())
+                .unwrap_or_default();
+            if words.contains(&"public") {
+                Visibility::Public
+            } else if words.contains(&"protected") {
+                Visibility::Protected
+            } else if words.contains(&"private") {
+                Visibility::Private
+            } else {
+                Visibility::Internal
+            }
+        }
+        _ => Visibility::Unknown,
+    }
+}
+
+/// Number of independently locked definition shards
+const SYMBOL_SHARDS: usize = 16;
+
+type DefinitionMap = HashMap<String, Vec<SymbolInfo>>;
+
 #[derive(Default)]
-struct SymbolIndex {
-    definitions: HashMap<String, Vec<SymbolInfo>>,
+struct FileTable {
     // Reverse index: file_path -> symbol names (for fast clear_file)
-    file_definitions: HashMap<String, HashSet<String>>,
+    definitions: HashMap<String, HashSet<String>>,
+    // Owning package of each indexed file (directory of the nearest package manifest)
+    packages: HashMap<String, String>,
+}
+
+/// Definitions sharded by symbol name, plus the per-file tables. Each lock is held for a
+/// single file's or name's update, so lookups keep running while a batch is merged.
+/// Writers are serialized by `write_lock`, which keeps the file tables in step with the
+/// shards; readers never take it.
+struct SymbolIndex {
+    shards: Vec<RwLock<DefinitionMap>>,
+    files: RwLock<FileTable>,
+    write_lock: Mutex<()>,
+}
+
+// A panic while holding an index lock leaves at worst a partially updated file, which the
+// next index of that file replaces, so poisoning is ignored
+fn read_lock<T>(lock: &RwLock<T>) -> RwLockReadGuard<'_, T> {
+    lock.read().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn write_lock<T>(lock: &RwLock<T>) -> RwLockWriteGuard<'_, T> {
+    lock.write().unwrap_or_else(PoisonError::into_inner)
+}
+
+impl Default for SymbolIndex {
+    fn default() -> Self {
+        Self {
+            shards: (0..SYMBOL_SHARDS).map(|_| RwLock::default()).collect(),
+            files: RwLock::default(),
+            write_lock: Mutex::new(()),
+        }
+    }
+}
+
+fn shard_index(name: &str) -> usize {
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    name.hash(&mut hasher);
+    hasher.finish() as usize % SYMBOL_SHARDS
+}
+
+impl SymbolIndex {
+    fn shard(&self, name: &str) -> &RwLock<DefinitionMap> {
+        &self.shards[shard_index(name)]
+    }
+
+    fn lock_writer(&self) -> MutexGuard<'_, ()> {
+        self.write_lock
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn definitions_where(
+        &self,
+        name: &str,
+        filter: impl Fn(&SymbolInfo) -> bool,
+    ) -> Vec<SymbolInfo> {
+        read_lock(self.shard(name))
+            .get(name)
+            .map(|symbols| symbols.iter().filter(|s| filter(s)).cloned().collect())
+            .unwrap_or_default()
+    }
+
+    fn has_definition_where(&self, name: &str, filter: impl Fn(&SymbolInfo) -> bool) -> bool {
+        read_lock(self.shard(name))
+            .get(name)
+            .is_some_and(|symbols| symbols.iter().any(filter))
+    }
+
+    fn package_of(&self, file_path: &str) -> Option<String> {
+        read_lock(&self.files).packages.get(file_path).cloned()
+    }
+
+    /// Packages of the given files, for those that have one
+    fn packages_of<'a>(
+        &self,
+        file_paths: impl Iterator<Item = &'a str>,
+    ) -> HashMap<String, String> {
+        let files = read_lock(&self.files);
+        file_paths
+            .filter_map(|path| {
+                files
+                    .packages
+                    .get(path)
+                    .map(|package| (path.to_string(), package.clone()))
+            })
+            .collect()
+    }
+
+    /// Files with definitions recorded, including those with none
+    fn indexed_files(&self) -> Vec<String> {
+        read_lock(&self.files).definitions.keys().cloned().collect()
+    }
+
+    /// Files with any entry, including ones only known by their package
+    fn known_files(&self) -> HashSet<String> {
+        let files = read_lock(&self.files);
+        files
+            .definitions
+            .keys()
+            .chain(files.packages.keys())
+            .cloned()
+            .collect()
+    }
+
+    #[cfg(test)]
+    fn names_in_file(&self, file_path: &str) -> Option<HashSet<String>> {
+        read_lock(&self.files).definitions.get(file_path).cloned()
+    }
+
+    fn file_count(&self) -> usize {
+        read_lock(&self.files).definitions.len()
+    }
+
+    fn symbol_count(&self) -> usize {
+        self.shards
+            .iter()
+            .map(|shard| read_lock(shard).values().map(Vec::len).sum::<usize>())
+            .sum()
+    }
+
+    /// Symbol count of every file that has definitions
+    fn symbols_per_file(&self) -> HashMap<String, usize> {
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for shard in &self.shards {
+            for symbol in read_lock(shard).values().flatten() {
+                *counts.entry(symbol.file_path.clone()).or_default() += 1;
+            }
+        }
+        counts
+    }
+
+    /// Replace everything recorded for a file
+    fn replace_file(
+        &self,
+        file_path: &str,
+        names: HashSet<String>,
+        package: Option<String>,
+        definitions: Vec<SymbolInfo>,
+    ) {
+        let _writer = self.lock_writer();
+        self.remove_file_locked(file_path);
+        {
+            let mut files = write_lock(&self.files);
+            files.definitions.insert(file_path.to_string(), names);
+            if let Some(package) = package {
+                files.packages.insert(file_path.to_string(), package);
+            }
+        }
+        for symbol in definitions {
+            write_lock(self.shard(&symbol.name))
+                .entry(symbol.name.clone())
+                .or_default()
+                .push(symbol);
+        }
+    }
+
+    fn remove_file(&self, file_path: &str) {
+        let _writer = self.lock_writer();
+        self.remove_file_locked(file_path);
+    }
+
+    fn remove_file_locked(&self, file_path: &str) {
+        let names = {
+            let mut files = write_lock(&self.files);
+            files.packages.remove(file_path);
+            files.definitions.remove(file_path)
+        };
+        // Use reverse index for O(file_symbols) instead of O(total_symbols)
+        for name in names.into_iter().flatten() {
+            let mut shard = write_lock(self.shard(&name));
+            if let Some(symbols) = shard.get_mut(&name) {
+                symbols.retain(|s| s.file_path != file_path);
+                if symbols.is_empty() {
+                    shard.remove(&name);
+                }
+            }
+        }
+    }
+
+    /// Replace the whole index, as when loading it from disk
+    fn load(
+        &self,
+        definitions: DefinitionMap,
+        file_definitions: HashMap<String, HashSet<String>>,
+        packages: HashMap<String, String>,
+    ) {
+        let _writer = self.lock_writer();
+        let mut sharded: Vec<DefinitionMap> = (0..SYMBOL_SHARDS).map(|_| HashMap::new()).collect();
+        for (name, symbols) in definitions {
+            sharded[shard_index(&name)].insert(name, symbols);
+        }
+        *write_lock(&self.files) = FileTable {
+            definitions: file_definitions,
+            packages,
+        };
+        for (shard, definitions) in self.shards.iter().zip(sharded) {
+            *write_lock(shard) = definitions;
+        }
+    }
+
+    fn clear(&self) {
+        self.load(HashMap::new(), HashMap::new(), HashMap::new());
+    }
+
+    /// Copies of the definitions and the reverse index, for persisting
+    fn export(&self) -> (DefinitionMap, HashMap<String, HashSet<String>>) {
+        let _writer = self.lock_writer();
+        let mut definitions = HashMap::new();
+        for shard in &self.shards {
+            definitions.extend(read_lock(shard).iter().map(|(k, v)| (k.clone(), v.clone())));
+        }
+        (definitions, read_lock(&self.files).definitions.clone())
+    }
+}
+
+/// Manifests that mark a package root in a monorepo
+const PACKAGE_MANIFESTS: &[&str] = &["package.json", "Cargo.toml", "go.mod"];
+
+/// Resolves the owning package of a file, caching the answer for every directory walked
+/// so batch indexing does not repeat the same fs checks
+#[derive(Default)]
+struct PackageResolver {
+    dir_packages: HashMap<PathBuf, Option<String>>,
+}
+
+impl PackageResolver {
+    fn package_for_file(&mut self, file_path: &str) -> Option<String> {
+        let mut visited = Vec::new();
+        let mut current = Path::new(file_path).parent();
+        let mut package = None;
+        while let Some(dir) = current {
+            if let Some(cached) = self.dir_packages.get(dir) {
+                package = cached.clone();
+                break;
+            }
+            visited.push(dir.to_path_buf());
+            if PACKAGE_MANIFESTS.iter().any(|m| dir.join(m).is_file()) {
+                package = Some(dir.to_string_lossy().to_string());
+                break;
+            }
+            current = dir.parent();
+        }
+        for dir in visited {
+            self.dir_packages.insert(dir, package.clone());
+        }
+        package
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackageStats {
+    /// Package root directory, or None for files outside any package
+    pub package_id: Option<String>,
+    pub files: usize,
+    pub symbols: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CodeNavStats {
+    pub total_files: usize,
+    pub total_symbols: usize,
+    pub packages: Vec<PackageStats>,
+}
+
+/// Whether a language's grammar loaded. Grammar crates are bumped independently, and a
+/// version mismatch shows up at runtime as a parser or query error for that language.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum LanguageStatus {
+    Ok,
+    /// The parser accepted the grammar but the definition query did not compile
+    QueryError {
+        message: String,
+    },
+    /// The parser rejected the grammar, typically an ABI version mismatch
+    ParserError {
+        message: String,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+pub struct LanguageHealth {
+    pub lang_id: String,
+    #[serde(flatten)]
+    pub status: LanguageStatus,
+}
+
+/// Why a file could not be indexed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IndexError {
+    /// The language is known but its grammar failed to load at startup
+    LanguageUnavailable {
+        lang_id: String,
+        status: LanguageStatus,
+    },
+    ParseFailed {
+        file_path: String,
+    },
+}
+
+impl std::fmt::Display for IndexError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IndexError::LanguageUnavailable { lang_id, status } => {
+                let reason = match status {
+                    LanguageStatus::QueryError { message } => message.as_str(),
+                    LanguageStatus::ParserError { message } => message.as_str(),
+                    LanguageStatus::Ok => "unknown error",
+                };
+                write!(
+                    f,
+                    "Code navigation is unavailable for {}: {}",
+                    lang_id, reason
+                )
+            }
+            IndexError::ParseFailed { file_path } => {
+                write!(f, "Failed to parse file: {}", file_path)
+            }
+        }
+    }
 }
 
 pub struct CodeNavigationService {
     parsers: HashMap<String, Parser>,
     languages: HashMap<String, Language>,
     queries: HashMap<String, Query>,
+    health: HashMap<String, LanguageStatus>,
     index: SymbolIndex,
+    /// Definitions changed since the index was last saved or loaded
+    dirty: AtomicBool,
+    /// Where the index was last saved or loaded, for flushing on shutdown
+    persist_target: Option<PersistTarget>,
+    package_resolver: Mutex<PackageResolver>,
+}
+
+/// Sort definitions so those closest to `context_file` come first: the same file, then the
+/// same package, then files sharing the longest directory prefix. The sort is stable, so
+/// ties keep index order.
+fn rank_by_proximity(
+    symbols: &mut [SymbolInfo],
+    context_file: &str,
+    context_package: Option<&str>,
+    file_packages: &HashMap<String, String>,
+) {
+    let context = Path::new(context_file);
+    symbols.sort_by_cached_key(|symbol| {
+        let path = Path::new(&symbol.file_path);
+        let same_file = path == context;
+        let same_package = context_package.is_some()
+            && file_packages.get(&symbol.file_path).map(String::as_str) == context_package;
+        let shared = path
+            .components()
+            .zip(context.components())
+            .take_while(|(a, b)| a == b)
+            .count();
+        std::cmp::Reverse((same_file, same_package, shared))
+    });
 }
 
 impl CodeNavigationService {
@@ -43,7 +1005,11 @@ impl CodeNavigationService {
             parsers: HashMap::new(),
             languages: HashMap::new(),
             queries: HashMap::new(),
+            health: HashMap::new(),
             index: SymbolIndex::default(),
+            dirty: AtomicBool::new(false),
+            persist_target: None,
+            package_resolver: Mutex::new(PackageResolver::default()),
         };
         service.init_languages();
         service
@@ -63,14 +1029,30 @@ impl CodeNavigationService {
     }
 
     fn register_language(&mut self, lang_id: &str, language: Language) {
+        let query_str = Self::get_definition_query(lang_id);
+        self.register_language_with_query(lang_id, language, query_str);
+    }
+
+    /// Load a grammar and its definition query, recording whether either failed
+    fn register_language_with_query(&mut self, lang_id: &str, language: Language, query_str: &str) {
+        self.parsers.remove(lang_id);
+        self.languages.remove(lang_id);
+        self.queries.remove(lang_id);
+
         let mut parser = Parser::new();
-        if parser.set_language(&language).is_err() {
-            log::error!("Failed to set language for {}", lang_id);
+        if let Err(e) = parser.set_language(&language) {
+            log::error!("Failed to set language for {}: {}", lang_id, e);
+            self.health.insert(
+                lang_id.to_string(),
+                LanguageStatus::ParserError {
+                    message: e.to_string(),
+                },
+            );
             return;
         }
 
         // Create definition query for this language
-        let query_str = Self::get_definition_query(lang_id);
+        let mut status = LanguageStatus::Ok;
         if !query_str.is_empty() {
             match Query::new(&language, query_str) {
                 Ok(query) => {
@@ -78,12 +1060,49 @@ impl CodeNavigationService {
                 }
                 Err(e) => {
                     log::error!("Failed to create definition query for {}: {:?}", lang_id, e);
+                    status = LanguageStatus::QueryError {
+                        message: e.to_string(),
+                    };
                 }
             }
         }
 
-        self.parsers.insert(lang_id.to_string(), parser);
-        self.languages.insert(lang_id.to_string(), language);
+        self.parsers.insert(lang_id.to_string(), parser);
+        self.languages.insert(lang_id.to_string(), language);
+        self.health.insert(lang_id.to_string(), status);
+    }
+
+    /// Load status of every known language, sorted by language id
+    pub fn language_health(&self) -> Vec<LanguageHealth> {
+        let mut health: Vec<LanguageHealth> = self
+            .health
+            .iter()
+            .map(|(lang_id, status)| LanguageHealth {
+                lang_id: lang_id.clone(),
+                status: status.clone(),
+            })
+            .collect();
+        health.sort_by(|a, b| a.lang_id.cmp(&b.lang_id));
+        health
+    }
+
+    /// Languages whose grammar or query failed to load
+    pub fn degraded_languages(&self) -> Vec<LanguageHealth> {
+        self.language_health()
+            .into_iter()
+            .filter(|health| health.status != LanguageStatus::Ok)
+            .collect()
+    }
+
+    /// The load failure for `lang_id`, if it is a known language that failed to load
+    fn language_error(&self, lang_id: &str) -> Option<IndexError> {
+        match self.health.get(lang_id) {
+            Some(LanguageStatus::Ok) | None => None,
+            Some(status) => Some(IndexError::LanguageUnavailable {
+                lang_id: lang_id.to_string(),
+                status: status.clone(),
+            }),
+        }
     }
 
     fn get_definition_query(lang_id: &str) -> &'static str {
@@ -92,6 +1111,10 @@ impl CodeNavigationService {
                 r#"
                 (function_definition name: (identifier) @function.definition)
                 (class_definition name: (identifier) @class.definition)
+                (module (function_definition name: (identifier) @function.definition.exported))
+                (module (class_definition name: (identifier) @class.definition.exported))
+                (module (decorated_definition definition: (function_definition name: (identifier) @function.definition.exported)))
+                (module (decorated_definition definition: (class_definition name: (identifier) @class.definition.exported)))
                 "#
             }
             "rust" => {
@@ -103,6 +1126,13 @@ impl CodeNavigationService {
                 (const_item name: (identifier) @const.definition)
                 (static_item name: (identifier) @static.definition)
                 (type_item name: (type_identifier) @type.definition)
+                (function_item (visibility_modifier) @visibility name: (identifier) @function.definition.exported)
+                (struct_item (visibility_modifier) @visibility name: (type_identifier) @struct.definition.exported)
+                (enum_item (visibility_modifier) @visibility name: (type_identifier) @enum.definition.exported)
+                (trait_item (visibility_modifier) @visibility name: (type_identifier) @trait.definition.exported)
+                (const_item (visibility_modifier) @visibility name: (identifier) @const.definition.exported)
+                (static_item (visibility_modifier) @visibility name: (identifier) @static.definition.exported)
+                (type_item (visibility_modifier) @visibility name: (type_identifier) @type.definition.exported)
                 "#
             }
             "go" => {
@@ -131,23 +1161,33 @@ impl CodeNavigationService {
                 (method_declaration name: (identifier) @method.definition)
                 (class_declaration name: (identifier) @class.definition)
                 (interface_declaration name: (identifier) @interface.definition)
+                (method_declaration (modifiers) @visibility name: (identifier) @method.definition)
+                (class_declaration (modifiers) @visibility name: (identifier) @class.definition)
+                (interface_declaration (modifiers) @visibility name: (identifier) @interface.definition)
                 "#
             }
             "typescript" | "javascript" => {
                 r#"
                 (function_declaration name: (identifier) @function.definition)
-                (export_statement (function_declaration name: (identifier) @function.definition))
+                (export_statement (function_declaration name: (identifier) @function.definition.exported))
                 (class_declaration name: (type_identifier) @class.definition)
-                (export_statement (class_declaration name: (type_identifier) @class.definition))
+                (export_statement (class_declaration name: (type_identifier) @class.definition.exported))
                 (interface_declaration name: (type_identifier) @interface.definition)
-                (export_statement (interface_declaration name: (type_identifier) @interface.definition))
+                (export_statement (interface_declaration name: (type_identifier) @interface.definition.exported))
                 (type_alias_declaration name: (type_identifier) @type.definition)
-                (export_statement (type_alias_declaration name: (type_identifier) @type.definition))
+                (export_statement (type_alias_declaration name: (type_identifier) @type.definition.exported))
                 (enum_declaration name: (identifier) @enum.definition)
-                (export_statement (enum_declaration name: (identifier) @enum.definition))
+                (export_statement (enum_declaration name: (identifier) @enum.definition.exported))
                 (method_definition name: (property_identifier) @method.definition)
+                (method_definition (accessibility_modifier) @visibility name: (property_identifier) @method.definition)
+                (method_definition name: (private_property_identifier) @method.definition)
                 (program (lexical_declaration (variable_declarator name: (identifier) @const.definition)))
-                (program (export_statement (lexical_declaration (variable_declarator name: (identifier) @const.definition))))
+                (program (export_statement (lexical_declaration (variable_declarator name: (identifier) @const.definition.exported))))
+                (function_signature name: (identifier) @function.definition)
+                (export_statement (function_signature name: (identifier) @function.definition.exported))
+                (export_statement (ambient_declaration (function_signature name: (identifier) @function.definition.exported)))
+                (export_statement (ambient_declaration (class_declaration name: (type_identifier) @class.definition.exported)))
+                (export_statement (ambient_declaration (lexical_declaration (variable_declarator name: (identifier) @const.definition.exported))))
                 "#
             }
             _ => "",
@@ -186,7 +1226,8 @@ impl CodeNavigationService {
     pub fn get_lang_family(lang_id: &str) -> &'static str {
         match lang_id {
             "c" | "cpp" => "c_family",
-            "typescript" | "javascript" => "js_family",
+            // Vue and Svelte components are indexed by their script blocks
+            "typescript" | "javascript" | "vue" | "svelte" => "js_family",
             "python" => "python",
             "rust" => "rust",
             "go" => "go",
@@ -195,78 +1236,61 @@ impl CodeNavigationService {
         }
     }
 
-    pub fn index_file(&mut self, file_path: &str, content: &str, lang_id: &str) {
+    /// Index the definitions in one file. Unsupported languages are a no-op; a supported
+    /// language whose grammar failed to load is an error rather than an empty result.
+    pub fn index_file(
+        &mut self,
+        file_path: &str,
+        content: &str,
+        lang_id: &str,
+    ) -> Result<(), IndexError> {
+        self.index_file_as(file_path, content, lang_id, false)
+    }
+
+    /// Index a file of a dependency. Its definitions are left out of project lookups and
+    /// only returned when dependencies are asked for.
+    pub fn index_dependency_file(
+        &mut self,
+        file_path: &str,
+        content: &str,
+        lang_id: &str,
+    ) -> Result<(), IndexError> {
+        self.index_file_as(file_path, content, lang_id, true)
+    }
+
+    fn index_file_as(
+        &mut self,
+        file_path: &str,
+        content: &str,
+        lang_id: &str,
+        dependency: bool,
+    ) -> Result<(), IndexError> {
         let start = Instant::now();
+        let key = path_wire::index_key(file_path);
+        let file_path: &str = &key;
+        let _span = perf_trace::span("index_file", Phase::Total, Some(file_path));
 
-        // First clear existing symbols for this file
+        // First clear existing symbols for this file, including every embedded region
         self.clear_file(file_path);
 
-        let parser = match self.parsers.get_mut(lang_id) {
-            Some(p) => p,
-            None => {
-                log::debug!("No parser for language: {}", lang_id);
-                return;
-            }
-        };
-
-        let tree = match parser.parse(content, None) {
-            Some(t) => t,
-            None => {
-                log::error!("Failed to parse file: {}", file_path);
-                return;
-            }
+        let Some(mut definitions) =
+            self.parse_definitions(file_path, content, lang_id, "index_file")?
+        else {
+            return Ok(());
         };
-
-        let source_bytes = content.as_bytes();
-        let lang_family = Self::get_lang_family(lang_id).to_string();
-
-        // Collect definitions only (references are searched on-demand via hybrid search)
-        let mut definitions: Vec<SymbolInfo> = Vec::new();
-        let mut defined_names: HashSet<String> = HashSet::new();
-
-        if let Some(query) = self.queries.get(lang_id) {
-            let mut cursor = QueryCursor::new();
-            let mut matches = cursor.matches(query, tree.root_node(), source_bytes);
-
-            while let Some(m) = matches.next() {
-                for capture in m.captures {
-                    let node = capture.node;
-                    let name = match node.utf8_text(source_bytes) {
-                        Ok(text) => text.to_string(),
-                        Err(_) => continue,
-                    };
-
-                    let capture_name = query.capture_names()[capture.index as usize];
-                    let kind = Self::get_symbol_kind(capture_name);
-
-                    definitions.push(SymbolInfo {
-                        name: name.clone(),
-                        kind,
-                        file_path: file_path.to_string(),
-                        lang_family: lang_family.clone(),
-                        start_line: node.start_position().row as u32 + 1,
-                        start_column: node.start_position().column as u32 + 1,
-                        end_line: node.end_position().row as u32 + 1,
-                        end_column: node.end_position().column as u32 + 1,
-                    });
-                    defined_names.insert(name);
-                }
-            }
+        if dependency {
+            definitions.iter_mut().for_each(|d| d.dependency = true);
         }
+        let defined_names: HashSet<String> = definitions.iter().map(|d| d.name.clone()).collect();
 
         // Add definitions to index and always track file as indexed
         // This ensures files like test files are marked as "indexed" even with 0 definitions
         let def_count = definitions.len();
+        let merge_span = perf_trace::span("index_file", Phase::Merge, Some(file_path));
+        let package = self.resolve_package(file_path);
         self.index
-            .file_definitions
-            .insert(file_path.to_string(), defined_names);
-        for symbol in definitions {
-            self.index
-                .definitions
-                .entry(symbol.name.clone())
-                .or_default()
-                .push(symbol);
-        }
+            .replace_file(file_path, defined_names, package, definitions);
+        drop(merge_span);
 
         let duration = start.elapsed();
         log::debug!(
@@ -275,20 +1299,204 @@ impl CodeNavigationService {
             def_count,
             duration.as_secs_f64() * 1000.0
         );
+        Ok(())
+    }
+
+    /// Parse `content` and collect its definitions, without touching the index. None when
+    /// there is no parser for the language.
+    fn parse_definitions(
+        &mut self,
+        file_path: &str,
+        content: &str,
+        lang_id: &str,
+        operation: &'static str,
+    ) -> Result<Option<Vec<SymbolInfo>>, IndexError> {
+        let mut definitions: Vec<SymbolInfo> = Vec::new();
+        for unit in source_units(lang_id, content) {
+            if let Some(error) = self.language_error(unit.lang_id) {
+                return Err(error);
+            }
+
+            let parser = match self.parsers.get_mut(unit.lang_id) {
+                Some(p) => p,
+                // A container with no indexable regions is still tracked as indexed
+                None if embedded_code::is_container(lang_id) => continue,
+                None => {
+                    log::debug!("No parser for language: {}", lang_id);
+                    return Ok(None);
+                }
+            };
+
+            let parse_span = perf_trace::span(operation, Phase::Parse, Some(file_path));
+            let tree = match parser.parse(unit.content.as_ref(), None) {
+                Some(t) => t,
+                None => {
+                    log::error!("Failed to parse file: {}", file_path);
+                    return Err(IndexError::ParseFailed {
+                        file_path: file_path.to_string(),
+                    });
+                }
+            };
+            drop(parse_span);
+
+            // Collect definitions only (references are searched on-demand via hybrid search)
+            if let Some(query) = self.queries.get(unit.lang_id) {
+                let _span = perf_trace::span(operation, Phase::Query, Some(file_path));
+                definitions.extend(collect_definitions(&tree, query, &unit, file_path));
+            }
+        }
+        Ok(Some(definitions))
+    }
+
+    /// Definitions in a file's content, for callers that need them without indexing the
+    /// file. Empty for languages without a parser.
+    pub fn definitions_in(
+        &mut self,
+        file_path: &str,
+        content: &str,
+        lang_id: &str,
+    ) -> Result<Vec<SymbolInfo>, IndexError> {
+        Ok(self
+            .parse_definitions(file_path, content, lang_id, "definitions_in")?
+            .unwrap_or_default())
     }
 
+    /// Project definitions of a symbol; dependency definitions are left out
     pub fn find_definition(&self, symbol_name: &str, lang_family: &str) -> Vec<SymbolInfo> {
-        self.index
-            .definitions
-            .get(symbol_name)
-            .map(|symbols| {
-                symbols
-                    .iter()
-                    .filter(|s| s.lang_family == lang_family)
-                    .cloned()
-                    .collect()
+        self.index.definitions_where(symbol_name, |s| {
+            s.lang_family == lang_family && !s.dependency
+        })
+    }
+
+    /// Definitions of a symbol in indexed dependencies, ordered by file
+    pub fn find_dependency_definition(
+        &self,
+        symbol_name: &str,
+        lang_family: &str,
+    ) -> Vec<SymbolInfo> {
+        let mut results = self.index.definitions_where(symbol_name, |s| {
+            s.lang_family == lang_family && s.dependency
+        });
+        results.sort_by(|a, b| (&a.file_path, a.start_line).cmp(&(&b.file_path, b.start_line)));
+        results
+    }
+
+    /// Find definitions ranked by proximity to `context_file` when one is given
+    pub fn find_definition_ranked(
+        &self,
+        symbol_name: &str,
+        lang_family: &str,
+        context_file: Option<&str>,
+    ) -> Vec<SymbolInfo> {
+        self.find_definition_scoped(symbol_name, lang_family, context_file, false)
+    }
+
+    /// Like `find_definition_ranked`, optionally keeping only definitions in the same
+    /// package as `context_file`
+    pub fn find_definition_scoped(
+        &self,
+        symbol_name: &str,
+        lang_family: &str,
+        context_file: Option<&str>,
+        same_package_only: bool,
+    ) -> Vec<SymbolInfo> {
+        let mut results = self.find_definition(symbol_name, lang_family);
+        if let Some(context_file) = context_file {
+            let context_package = self.package_of(context_file);
+            let file_packages = self
+                .index
+                .packages_of(results.iter().map(|s| s.file_path.as_str()));
+            if same_package_only {
+                results.retain(|s| file_packages.get(&s.file_path) == context_package.as_ref());
+            }
+            rank_by_proximity(
+                &mut results,
+                context_file,
+                context_package.as_deref(),
+                &file_packages,
+            );
+        }
+        results
+    }
+
+    /// Package of a file: from the index when it was indexed, otherwise resolved on disk
+    fn package_of(&self, file_path: &str) -> Option<String> {
+        if let Some(package) = self.index.package_of(file_path) {
+            return Some(package);
+        }
+        self.resolve_package(file_path)
+    }
+
+    /// Package of a file on disk
+    fn resolve_package(&self, file_path: &str) -> Option<String> {
+        self.package_resolver
+            .lock()
+            .ok()
+            .and_then(|mut resolver| resolver.package_for_file(file_path))
+    }
+
+    /// Index totals with per-package file and symbol counts, largest package first
+    pub fn get_stats(&self) -> CodeNavStats {
+        let symbol_counts = self.index.symbols_per_file();
+        let indexed_files = self.index.indexed_files();
+        let file_packages = self
+            .index
+            .packages_of(indexed_files.iter().map(String::as_str));
+        let mut packages: HashMap<Option<&String>, PackageStats> = HashMap::new();
+        for file_path in &indexed_files {
+            let package = file_packages.get(file_path);
+            let symbols = symbol_counts.get(file_path).copied().unwrap_or(0);
+            let stats = packages.entry(package).or_insert_with(|| PackageStats {
+                package_id: package.cloned(),
+                files: 0,
+                symbols: 0,
+            });
+            stats.files += 1;
+            stats.symbols += symbols;
+        }
+
+        let mut packages: Vec<PackageStats> = packages.into_values().collect();
+        packages.sort_by(|a, b| {
+            b.symbols
+                .cmp(&a.symbols)
+                .then_with(|| a.package_id.cmp(&b.package_id))
+        });
+        CodeNavStats {
+            total_files: indexed_files.len(),
+            total_symbols: symbol_counts.values().sum(),
+            packages,
+        }
+    }
+
+    /// Look up many symbols at once. Every requested name is present in the result,
+    /// mapped to an empty list when it has no definition.
+    pub fn find_definitions_bulk(
+        &self,
+        symbol_names: &[String],
+        lang_family: &str,
+        context_file: Option<&str>,
+        same_package_only: bool,
+    ) -> HashMap<String, Vec<SymbolInfo>> {
+        let mut results = HashMap::with_capacity(symbol_names.len());
+        for name in symbol_names {
+            if !results.contains_key(name) {
+                let definitions =
+                    self.find_definition_scoped(name, lang_family, context_file, same_package_only);
+                results.insert(name.clone(), definitions);
+            }
+        }
+        results
+    }
+
+    /// Whether each symbol has at least one definition, in the order requested
+    pub fn has_definitions(&self, symbol_names: &[String], lang_family: &str) -> Vec<bool> {
+        symbol_names
+            .iter()
+            .map(|name| {
+                self.index
+                    .has_definition_where(name, |s| s.lang_family == lang_family && !s.dependency)
             })
-            .unwrap_or_default()
+            .collect()
     }
 
     /// Hybrid reference search: text search + tree-sitter filtering
@@ -301,29 +1509,219 @@ impl CodeNavigationService {
         root_path: &str,
     ) -> Vec<SymbolInfo> {
         let start = Instant::now();
+        let _span = perf_trace::span("find_references_hybrid", Phase::Total, None);
 
         // 1. Use ripgrep for global text search with word boundary
-        let searcher = RipgrepSearch::new()
-            .with_max_results(500)
-            .with_max_matches_per_file(100);
-
-        // Use word boundary pattern to avoid partial matches
-        let pattern = format!(r"\b{}\b", regex::escape(symbol_name));
-        let search_results = match searcher.search_content(&pattern, root_path) {
+        let search_span = perf_trace::span("find_references_hybrid", Phase::Query, None);
+        let search_results = match Self::reference_searcher()
+            .search_content(&Self::reference_pattern(symbol_name), root_path)
+        {
             Ok(results) => results,
             Err(e) => {
                 log::error!("Ripgrep search failed: {}", e);
                 return Vec::new();
             }
         };
+        drop(search_span);
+
+        // 2. For each result, validate using tree-sitter
+        let references = Self::validate_search_results(search_results, symbol_name, lang_family);
+
+        let duration = start.elapsed();
+        log::debug!(
+            "find_references_hybrid for '{}' found {} references in {:.2}ms",
+            symbol_name,
+            references.len(),
+            duration.as_secs_f64() * 1000.0
+        );
+
+        references
+    }
+
+    /// Hybrid reference search restricted to a scope. The walk itself is limited to the
+    /// scope's paths rather than filtering project-wide results afterwards.
+    pub fn find_references_scoped(
+        &self,
+        symbol_name: &str,
+        lang_family: &str,
+        root_path: &str,
+        scope: &ReferenceScope,
+    ) -> Result<Vec<ScopedReference>, String> {
+        let Some(scopes) = resolve_reference_scope(root_path, scope)? else {
+            return Ok(self
+                .find_references_hybrid(symbol_name, lang_family, root_path)
+                .into_iter()
+                .map(|symbol| ScopedReference {
+                    symbol,
+                    matched_scopes: Vec::new(),
+                })
+                .collect());
+        };
+        let start = Instant::now();
+
+        let search_paths: Vec<String> = scopes.iter().flat_map(|s| s.search_paths()).collect();
+        if search_paths.is_empty() {
+            return Ok(Vec::new());
+        }
+        let search_results = Self::reference_searcher()
+            .search_content_in_paths(&Self::reference_pattern(symbol_name), &search_paths)?;
+        let references = Self::validate_search_results(search_results, symbol_name, lang_family);
+
+        let mut labels_by_file: HashMap<String, Vec<String>> = HashMap::new();
+        let scoped: Vec<ScopedReference> = references
+            .into_iter()
+            .map(|symbol| {
+                let matched_scopes = labels_by_file
+                    .entry(symbol.file_path.clone())
+                    .or_insert_with(|| {
+                        let path = Path::new(&symbol.file_path);
+                        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+                        scopes
+                            .iter()
+                            .filter(|s| s.contains(&path))
+                            .map(|s| s.label.clone())
+                            .collect()
+                    })
+                    .clone();
+                ScopedReference {
+                    symbol,
+                    matched_scopes,
+                }
+            })
+            .collect();
+
+        log::debug!(
+            "find_references_scoped for '{}' searched {} scope paths, found {} references in {:.2}ms",
+            symbol_name,
+            search_paths.len(),
+            scoped.len(),
+            start.elapsed().as_secs_f64() * 1000.0
+        );
+        Ok(scoped)
+    }
+
+    /// Every occurrence of a symbol in one buffer, for selecting all occurrences in the
+    /// editor. The buffer is parsed once and the index is not consulted; text matches are
+    /// filtered exactly like reference search results.
+    pub fn occurrences_in_buffer(
+        &self,
+        content: &str,
+        lang_id: &str,
+        target: &OccurrenceTarget,
+        include_strings_and_comments: bool,
+    ) -> Result<BufferOccurrences, String> {
+        let start = Instant::now();
+        let mut parsed = Vec::new();
+        for unit in source_units(lang_id, content) {
+            let Some(language) = language_for(unit.lang_id) else {
+                continue;
+            };
+            let mut parser = Parser::new();
+            if parser.set_language(&language).is_err() {
+                continue;
+            }
+            if let Some(tree) = parser.parse(unit.content.as_ref(), None) {
+                parsed.push((unit, tree));
+            }
+        }
+        if parsed.is_empty() {
+            return Err(format!("Unsupported language: {}", lang_id));
+        }
+
+        let symbol_name = match target {
+            OccurrenceTarget::Name(name) => name.clone(),
+            OccurrenceTarget::Position { line, column } => parsed
+                .iter()
+                .find(|(unit, _)| unit.contains_line(*line as u64))
+                .and_then(|(unit, tree)| {
+                    let row = line.saturating_sub(unit.line_offset + 1) as usize;
+                    identifier_at(tree, &unit.content, row, *column, unit.lang_id)
+                })
+                .ok_or_else(|| format!("No identifier at line {}, column {}", line, column))?,
+        };
+        if symbol_name.is_empty() {
+            return Err("Symbol name is empty".to_string());
+        }
+
+        let mut occurrences = Vec::new();
+        for (unit, tree) in &parsed {
+            let source = unit.content.as_bytes();
+            let definitions = self
+                .queries
+                .get(unit.lang_id)
+                .map(|query| definition_node_ids(tree, query, source))
+                .unwrap_or_default();
+            let mut utf16_columns = Utf16ColumnMap::new(source);
+            for (row, line) in unit.content.split('\n').enumerate() {
+                for col in word_occurrences(line, &symbol_name) {
+                    let start_point = Point::new(row, col);
+                    let end_point = Point::new(row, col + symbol_name.len());
+                    let Some(node) = tree
+                        .root_node()
+                        .descendant_for_point_range(start_point, start_point)
+                    else {
+                        continue;
+                    };
+                    let kind = match Self::classify_reference_node(
+                        &node,
+                        &symbol_name,
+                        source,
+                        unit.lang_id,
+                    ) {
+                        ReferenceMatch::Reference => occurrence_kind(&node, &definitions),
+                        ReferenceMatch::StringOrComment if include_strings_and_comments => {
+                            OccurrenceKind::Text
+                        }
+                        _ => continue,
+                    };
+                    let line_number = unit.line_offset + row as u32 + 1;
+                    occurrences.push(BufferOccurrence {
+                        kind,
+                        start_line: line_number,
+                        start_column: col as u32 + 1,
+                        end_line: line_number,
+                        end_column: (col + symbol_name.len()) as u32 + 1,
+                        start_column_utf16: utf16_columns.column(start_point),
+                        end_column_utf16: utf16_columns.column(end_point),
+                    });
+                }
+            }
+        }
+
+        log::debug!(
+            "occurrences_in_buffer for '{}' found {} occurrences in {:.2}ms",
+            symbol_name,
+            occurrences.len(),
+            start.elapsed().as_secs_f64() * 1000.0
+        );
+        Ok(BufferOccurrences {
+            symbol_name,
+            occurrences,
+        })
+    }
+
+    fn reference_searcher() -> RipgrepSearch {
+        RipgrepSearch::new()
+            .with_max_results(500)
+            .with_max_matches_per_file(100)
+    }
 
+    /// Word boundary pattern to avoid partial matches
+    fn reference_pattern(symbol_name: &str) -> String {
+        format!(r"\b{}\b", regex::escape(symbol_name))
+    }
+
+    /// Keep the text matches tree-sitter confirms are references in the language family
+    fn validate_search_results(
+        search_results: Vec<SearchResult>,
+        symbol_name: &str,
+        lang_family: &str,
+    ) -> Vec<SymbolInfo> {
         log::debug!(
             "Ripgrep found {} files with matches for '{}'",
             search_results.len(),
             symbol_name
         );
-
-        // 2. For each result, validate using tree-sitter
         let mut references: Vec<SymbolInfo> = Vec::new();
 
         for result in search_results {
@@ -333,81 +1731,90 @@ impl CodeNavigationService {
                 None => continue,
             };
 
-            // Check if this file belongs to the requested language family
-            if Self::get_lang_family(&lang_id) != lang_family {
+            // Check if this file belongs to the requested language family. Containers
+            // are candidates for every family they may embed.
+            let container = embedded_code::is_container(&lang_id);
+            let candidate = if container {
+                embedded_code::container_families(&lang_id).contains(&lang_family)
+            } else {
+                Self::get_lang_family(&lang_id) == lang_family
+            };
+            if !candidate {
                 continue;
             }
 
             // Read file content
-            let content = match fs::read_to_string(&result.file_path) {
+            let read_span = perf_trace::span(
+                "find_references_validate",
+                Phase::Read,
+                Some(&result.file_path),
+            );
+            let content = match fs::read_to_string(path_wire::resolve(&result.file_path)) {
                 Ok(c) => c,
                 Err(_) => continue,
             };
+            drop(read_span);
 
-            // Get language and create parser
-            let language: Language = match lang_id.as_str() {
-                "python" => tree_sitter_python::LANGUAGE.into(),
-                "rust" => tree_sitter_rust::LANGUAGE.into(),
-                "go" => tree_sitter_go::LANGUAGE.into(),
-                "c" => tree_sitter_c::LANGUAGE.into(),
-                "cpp" => tree_sitter_cpp::LANGUAGE.into(),
-                "java" => tree_sitter_java::LANGUAGE.into(),
-                "typescript" | "javascript" => tree_sitter_typescript::LANGUAGE_TSX.into(),
-                _ => continue,
-            };
-
-            let mut parser = Parser::new();
-            if parser.set_language(&language).is_err() {
-                continue;
-            }
+            for unit in source_units(&lang_id, &content) {
+                if Self::get_lang_family(unit.lang_id) != lang_family {
+                    continue;
+                }
+                let Some(language) = language_for(unit.lang_id) else {
+                    continue;
+                };
 
-            let tree = match parser.parse(&content, None) {
-                Some(t) => t,
-                None => continue,
-            };
+                let mut parser = Parser::new();
+                if parser.set_language(&language).is_err() {
+                    continue;
+                }
 
-            let source_bytes = content.as_bytes();
-
-            // Validate each match
-            for m in &result.matches {
-                let validated = Self::validate_reference_at_line(
-                    &tree,
-                    source_bytes,
-                    m.line_number,
-                    symbol_name,
-                    &lang_id,
-                    &result.file_path,
-                    lang_family,
+                let parse_span = perf_trace::span(
+                    "find_references_validate",
+                    Phase::Parse,
+                    Some(&result.file_path),
                 );
-                references.extend(validated);
+                let tree = match parser.parse(unit.content.as_ref(), None) {
+                    Some(t) => t,
+                    None => continue,
+                };
+                drop(parse_span);
+
+                let source_bytes = unit.content.as_bytes();
+                let _span = perf_trace::span(
+                    "find_references_validate",
+                    Phase::Query,
+                    Some(&result.file_path),
+                );
+
+                // Validate each match, in unit-relative lines
+                for m in result
+                    .matches
+                    .iter()
+                    .filter(|m| unit.contains_line(m.line_number))
+                {
+                    let validated = Self::validate_reference_at_line(
+                        &tree,
+                        source_bytes,
+                        m.line_number - unit.line_offset as u64,
+                        symbol_name,
+                        unit.lang_id,
+                        &result.file_path,
+                        lang_family,
+                    );
+                    references.extend(validated.into_iter().map(|mut reference| {
+                        reference.start_line += unit.line_offset;
+                        reference.end_line += unit.line_offset;
+                        reference
+                    }));
+                }
             }
         }
-
-        let duration = start.elapsed();
-        log::debug!(
-            "find_references_hybrid for '{}' found {} references in {:.2}ms",
-            symbol_name,
-            references.len(),
-            duration.as_secs_f64() * 1000.0
-        );
-
         references
     }
 
-    /// Get language ID from file path based on extension
-    fn get_lang_id_from_path(file_path: &str) -> Option<String> {
-        let ext = file_path.rsplit('.').next()?;
-        match ext.to_lowercase().as_str() {
-            "py" => Some("python".to_string()),
-            "rs" => Some("rust".to_string()),
-            "go" => Some("go".to_string()),
-            "c" | "h" => Some("c".to_string()),
-            "cpp" | "cc" | "cxx" | "hpp" | "hxx" => Some("cpp".to_string()),
-            "java" => Some("java".to_string()),
-            "ts" | "tsx" => Some("typescript".to_string()),
-            "js" | "jsx" | "mjs" | "cjs" => Some("javascript".to_string()),
-            _ => None,
-        }
+    /// Get language ID from file path, using the built-in and custom language mappings
+    pub(crate) fn get_lang_id_from_path(file_path: &str) -> Option<String> {
+        crate::language_mapping::lang_id_for_path(file_path)
     }
 
     /// Validate references at a specific line number
@@ -449,28 +1856,15 @@ impl CodeNavigationService {
             Err(_) => return results,
         };
 
-        // Find all occurrences of symbol_name in this line
-        for (col, _) in line_content.match_indices(symbol_name) {
-            // Check word boundaries
-            let before_ok = col == 0
-                || !line_content.as_bytes()[col - 1].is_ascii_alphanumeric()
-                    && line_content.as_bytes()[col - 1] != b'_';
-
-            let after_idx = col + symbol_name.len();
-            let after_ok = after_idx >= line_content.len()
-                || !line_content.as_bytes()[after_idx].is_ascii_alphanumeric()
-                    && line_content.as_bytes()[after_idx] != b'_';
-
-            if !before_ok || !after_ok {
-                continue;
-            }
-
+        // Find all whole-word occurrences of symbol_name in this line
+        for col in word_occurrences(line_content, symbol_name) {
             // Get AST node at this position
             let point = Point::new(line_idx, col);
             let node = tree.root_node().descendant_for_point_range(point, point);
 
             if let Some(node) = node {
                 if Self::is_valid_reference_node(&node, symbol_name, source, lang_id) {
+                    let utf16_col = line_content[..col].encode_utf16().count();
                     results.push(SymbolInfo {
                         name: symbol_name.to_string(),
                         kind: "reference".to_string(),
@@ -480,6 +1874,12 @@ impl CodeNavigationService {
                         start_column: (col + 1) as u32,
                         end_line: line_number as u32,
                         end_column: (col + 1 + symbol_name.len()) as u32,
+                        start_column_utf16: (utf16_col + 1) as u32,
+                        end_column_utf16: (utf16_col + 1 + symbol_name.encode_utf16().count())
+                            as u32,
+                        exported: false,
+                        dependency: false,
+                        visibility: Visibility::Unknown,
                     });
                 }
             }
@@ -496,31 +1896,30 @@ impl CodeNavigationService {
         source: &[u8],
         lang_id: &str,
     ) -> bool {
-        // 1. Node text must exactly match the symbol name
-        let node_text = match node.utf8_text(source) {
-            Ok(text) => text,
-            Err(_) => return false,
-        };
-        if node_text != symbol_name {
-            return false;
-        }
+        Self::classify_reference_node(node, symbol_name, source, lang_id)
+            == ReferenceMatch::Reference
+    }
 
+    /// Classify the node under a text match of `symbol_name`: a reference, text inside a
+    /// string or comment, or something else (property name, object key, ...)
+    fn classify_reference_node(
+        node: &tree_sitter::Node,
+        symbol_name: &str,
+        source: &[u8],
+        lang_id: &str,
+    ) -> ReferenceMatch {
+        // 1. Node text must exactly match the symbol name
         // 2. Must be an identifier or type_identifier
-        let node_kind = node.kind();
-        let valid_kinds = match lang_id {
-            "typescript" | "javascript" => {
-                &["identifier", "type_identifier", "property_identifier"][..]
-            }
-            "go" => &["identifier", "type_identifier", "field_identifier"][..],
-            _ => &["identifier", "type_identifier"][..],
-        };
-        if !valid_kinds.contains(&node_kind) {
-            return false;
-        }
+        let is_identifier = node.utf8_text(source).is_ok_and(|text| text == symbol_name)
+            && is_identifier_kind(node.kind(), lang_id);
 
-        // 3. Exclude if inside string or comment (check ancestors)
-        let mut parent = node.parent();
-        while let Some(p) = parent {
+        // 3. Exclude if inside string or comment (check the node and its ancestors)
+        let mut current = if is_identifier {
+            node.parent()
+        } else {
+            Some(*node)
+        };
+        while let Some(p) = current {
             let kind = p.kind();
             // String literals
             if kind == "string"
@@ -530,15 +1929,22 @@ impl CodeNavigationService {
                 || kind == "interpreted_string_literal"
                 || kind == "raw_string_literal"
             {
-                return false;
+                return ReferenceMatch::StringOrComment;
             }
             // Comments
             if kind == "comment" || kind == "line_comment" || kind == "block_comment" {
-                return false;
+                return ReferenceMatch::StringOrComment;
             }
-            parent = p.parent();
+            current = p.parent();
         }
+        if !is_identifier || Self::is_excluded_identifier(node, lang_id) {
+            return ReferenceMatch::Excluded;
+        }
+        ReferenceMatch::Reference
+    }
 
+    /// Identifiers that match the symbol but do not refer to it
+    fn is_excluded_identifier(node: &tree_sitter::Node, lang_id: &str) -> bool {
         // 4. Exclude property access property name (obj.prop - exclude prop)
         if let Some(p) = node.parent() {
             let parent_kind = p.kind();
@@ -547,7 +1953,7 @@ impl CodeNavigationService {
             if parent_kind == "member_expression" {
                 if let Some(prop) = p.child_by_field_name("property") {
                     if prop.id() == node.id() {
-                        return false;
+                        return true;
                     }
                 }
             }
@@ -556,7 +1962,7 @@ impl CodeNavigationService {
             if parent_kind == "attribute" && lang_id == "python" {
                 if let Some(attr) = p.child_by_field_name("attribute") {
                     if attr.id() == node.id() {
-                        return false;
+                        return true;
                     }
                 }
             }
@@ -565,7 +1971,7 @@ impl CodeNavigationService {
             if parent_kind == "field_expression" && lang_id == "rust" {
                 if let Some(field) = p.child_by_field_name("field") {
                     if field.id() == node.id() {
-                        return false;
+                        return true;
                     }
                 }
             }
@@ -574,7 +1980,7 @@ impl CodeNavigationService {
             if parent_kind == "selector_expression" && lang_id == "go" {
                 if let Some(field) = p.child_by_field_name("field") {
                     if field.id() == node.id() {
-                        return false;
+                        return true;
                     }
                 }
             }
@@ -583,7 +1989,7 @@ impl CodeNavigationService {
             if parent_kind == "field_access" {
                 if let Some(field) = p.child_by_field_name("field") {
                     if field.id() == node.id() {
-                        return false;
+                        return true;
                     }
                 }
             }
@@ -597,21 +2003,21 @@ impl CodeNavigationService {
             if parent_kind == "pair" {
                 if let Some(key) = p.child_by_field_name("key") {
                     if key.id() == node.id() {
-                        return false;
+                        return true;
                     }
                 }
             }
 
             // Shorthand property identifier (e.g., { config } in object literal)
             if parent_kind == "shorthand_property_identifier" {
-                return false;
+                return true;
             }
 
             // Python dict pair
             if parent_kind == "pair" && lang_id == "python" {
                 if let Some(key) = p.child_by_field_name("key") {
                     if key.id() == node.id() {
-                        return false;
+                        return true;
                     }
                 }
             }
@@ -621,7 +2027,7 @@ impl CodeNavigationService {
                 // First child is the key
                 if let Some(key) = p.child(0) {
                     if key.id() == node.id() {
-                        return false;
+                        return true;
                     }
                 }
             }
@@ -630,7 +2036,7 @@ impl CodeNavigationService {
             if parent_kind == "field_initializer" && lang_id == "rust" {
                 if let Some(name) = p.child_by_field_name("name") {
                     if name.id() == node.id() {
-                        return false;
+                        return true;
                     }
                 }
             }
@@ -643,36 +2049,114 @@ impl CodeNavigationService {
                 // We want to exclude 'original' but keep 'renamed'
                 if let Some(name) = p.child_by_field_name("name") {
                     if name.id() == node.id() {
-                        return false;
+                        return true;
                     }
                 }
             }
         }
 
-        true
+        false
+    }
+
+    fn snapshot(&self, root_path: &str, file_timestamps: HashMap<String, i64>) -> PersistedIndex {
+        let (definitions, file_definitions) = self.index.export();
+        PersistedIndex {
+            version: INDEX_VERSION,
+            root_path: root_path.to_string(),
+            last_updated: chrono::Utc::now().timestamp(),
+            file_timestamps,
+            definitions,
+            file_definitions,
+        }
+    }
+
+    /// Remember where the index lives on disk; it now matches that file
+    fn mark_persisted(&mut self, target: PersistTarget) {
+        self.persist_target = Some(target);
+        self.dirty.store(false, Ordering::Relaxed);
+    }
+
+    /// Save the index to where it was last saved or loaded, if it changed since. The
+    /// file timestamps from that save are reused; files indexed after it look stale and
+    /// are re-indexed on the next load. Returns whether anything was written.
+    pub fn flush_dirty(&mut self) -> Result<bool, String> {
+        let dirty = self.dirty.load(Ordering::Relaxed);
+        let Some(target) = self.persist_target.as_ref().filter(|_| dirty) else {
+            return Ok(false);
+        };
+        let persisted = self.snapshot(&target.root_path, target.file_timestamps.clone());
+        write_persisted_index(&target.index_path, &persisted)?;
+        self.dirty.store(false, Ordering::Relaxed);
+        Ok(true)
+    }
+
+    /// Files with index entries under `root_path`
+    pub fn indexed_files_under(&self, root_path: &str) -> Vec<String> {
+        let root = root_path.trim_end_matches(['/', '\\']);
+        self.index
+            .known_files()
+            .into_iter()
+            .filter(|file| {
+                file.strip_prefix(root)
+                    .is_some_and(|rest| rest.starts_with(['/', '\\']))
+            })
+            .collect()
     }
 
     pub fn clear_file(&mut self, file_path: &str) {
-        // Use reverse index for O(file_symbols) instead of O(total_symbols)
-        if let Some(def_names) = self.index.file_definitions.remove(file_path) {
-            for name in def_names {
-                if let Some(symbols) = self.index.definitions.get_mut(&name) {
-                    symbols.retain(|s| s.file_path != file_path);
-                    if symbols.is_empty() {
-                        self.index.definitions.remove(&name);
-                    }
-                }
-            }
-        }
+        self.dirty.store(true, Ordering::Relaxed);
+        self.index.remove_file(&path_wire::index_key(file_path));
     }
 
     pub fn clear_all(&mut self) {
-        self.index.definitions.clear();
-        self.index.file_definitions.clear();
+        self.dirty.store(true, Ordering::Relaxed);
+        self.index.clear();
+        if let Ok(resolver) = self.package_resolver.get_mut() {
+            resolver.dir_packages.clear();
+        }
+    }
+
+    /// Add a loaded index file by file, MERGE_CHUNK_FILES at a time. `proceed` is called
+    /// with the files merged so far and the total before each chunk; merging stops when it
+    /// returns false. Returns how many files were merged.
+    fn merge_persisted(
+        &self,
+        definitions: DefinitionMap,
+        file_definitions: HashMap<String, HashSet<String>>,
+        mut proceed: impl FnMut(usize, usize) -> bool,
+    ) -> usize {
+        let mut by_file: HashMap<String, Vec<SymbolInfo>> = HashMap::new();
+        for symbol in definitions.into_values().flatten() {
+            by_file
+                .entry(symbol.file_path.clone())
+                .or_default()
+                .push(symbol);
+        }
+        let total = file_definitions.len();
+        let mut merged = 0;
+        let files: Vec<_> = file_definitions.into_iter().collect();
+        for chunk in files.chunks(MERGE_CHUNK_FILES) {
+            if !proceed(merged, total) {
+                return merged;
+            }
+            for (file_path, names) in chunk {
+                let package = self.resolve_package(file_path);
+                let symbols = by_file.remove(file_path).unwrap_or_default();
+                self.index
+                    .replace_file(file_path, names.clone(), package, symbols);
+            }
+            merged += chunk.len();
+        }
+        proceed(merged, total);
+        merged
     }
 }
 
-// Tauri state wrapper using RwLock for better read concurrency
+/// Files merged between cancellation checks when loading an index
+const MERGE_CHUNK_FILES: usize = 500;
+
+// Tauri state wrapper using RwLock for better read concurrency. Batch merges only take
+// the read lock; the index synchronizes them internally (see SymbolIndex).
 pub struct CodeNavState(pub RwLock<CodeNavigationService>);
 
 // Tauri commands
@@ -687,8 +2171,9 @@ pub async fn code_nav_index_file(
         .0
         .write()
         .map_err(|e| format!("Failed to acquire write lock: {}", e))?;
-    service.index_file(&file_path, &content, &lang_id);
-    Ok(())
+    service
+        .index_file(&file_path, &content, &lang_id)
+        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
@@ -696,12 +2181,65 @@ pub async fn code_nav_find_definition(
     state: State<'_, CodeNavState>,
     symbol_name: String,
     lang_family: String,
+    context_file: Option<String>,
+    same_package_only: Option<bool>,
+    include_dependencies: Option<bool>,
+    visibility: Option<VisibilityFilter>,
 ) -> Result<Vec<SymbolInfo>, String> {
     let service = state
         .0
         .read()
         .map_err(|e| format!("Failed to acquire read lock: {}", e))?;
-    Ok(service.find_definition(&symbol_name, &lang_family))
+    let mut results = service.find_definition_scoped(
+        &symbol_name,
+        &lang_family,
+        context_file.as_deref(),
+        same_package_only.unwrap_or(false),
+    );
+    // Dependency definitions come after every project-local one
+    if include_dependencies.unwrap_or(false) {
+        results.extend(service.find_dependency_definition(&symbol_name, &lang_family));
+    }
+    retain_visible(&mut results, visibility);
+    Ok(results)
+}
+
+#[tauri::command]
+pub async fn code_nav_find_definitions_bulk(
+    state: State<'_, CodeNavState>,
+    symbol_names: Vec<String>,
+    lang_family: String,
+    context_file: Option<String>,
+    same_package_only: Option<bool>,
+    visibility: Option<VisibilityFilter>,
+) -> Result<HashMap<String, Vec<SymbolInfo>>, String> {
+    let service = state
+        .0
+        .read()
+        .map_err(|e| format!("Failed to acquire read lock: {}", e))?;
+    let mut results = service.find_definitions_bulk(
+        &symbol_names,
+        &lang_family,
+        context_file.as_deref(),
+        same_package_only.unwrap_or(false),
+    );
+    for definitions in results.values_mut() {
+        retain_visible(definitions, visibility);
+    }
+    Ok(results)
+}
+
+#[tauri::command]
+pub async fn code_nav_has_definitions(
+    state: State<'_, CodeNavState>,
+    symbol_names: Vec<String>,
+    lang_family: String,
+) -> Result<Vec<bool>, String> {
+    let service = state
+        .0
+        .read()
+        .map_err(|e| format!("Failed to acquire read lock: {}", e))?;
+    Ok(service.has_definitions(&symbol_names, &lang_family))
 }
 
 #[tauri::command]
@@ -710,12 +2248,60 @@ pub async fn code_nav_find_references_hybrid(
     symbol_name: String,
     lang_family: String,
     root_path: String,
-) -> Result<Vec<SymbolInfo>, String> {
+    include_paths: Option<Vec<String>>,
+    changed_only: Option<bool>,
+    base_ref: Option<String>,
+) -> Result<ReferenceSearchResult, String> {
+    let scope = ReferenceScope {
+        include_paths,
+        changed_only: changed_only.unwrap_or(false),
+        base_ref,
+    };
+    let _permit = concurrency::governor().acquire(
+        OperationClass::Interactive,
+        &format!("References to {}", symbol_name),
+        None,
+    );
+    let service = state
+        .0
+        .read()
+        .map_err(|e| format!("Failed to acquire read lock: {}", e))?;
+    let references =
+        service.find_references_scoped(&symbol_name, &lang_family, &root_path, &scope)?;
+    Ok(ReferenceSearchResult {
+        references,
+        sparse_checkout_active: crate::git::sparse::sparse_checkout_active(Path::new(&root_path)),
+    })
+}
+
+/// Occurrences of a symbol in an editor buffer, for multi-cursor selection
+#[tauri::command]
+pub async fn code_nav_occurrences_in_buffer(
+    state: State<'_, CodeNavState>,
+    content: String,
+    lang_id: String,
+    symbol_name_or_position: OccurrenceTarget,
+    include_strings_and_comments: Option<bool>,
+) -> Result<BufferOccurrences, String> {
+    let service = state
+        .0
+        .read()
+        .map_err(|e| format!("Failed to acquire read lock: {}", e))?;
+    service.occurrences_in_buffer(
+        &content,
+        &lang_id,
+        &symbol_name_or_position,
+        include_strings_and_comments.unwrap_or(false),
+    )
+}
+
+#[tauri::command]
+pub async fn code_nav_get_stats(state: State<'_, CodeNavState>) -> Result<CodeNavStats, String> {
     let service = state
         .0
         .read()
         .map_err(|e| format!("Failed to acquire read lock: {}", e))?;
-    Ok(service.find_references_hybrid(&symbol_name, &lang_family, &root_path))
+    Ok(service.get_stats())
 }
 
 #[tauri::command]
@@ -741,151 +2327,254 @@ pub async fn code_nav_clear_all(state: State<'_, CodeNavState>) -> Result<(), St
     Ok(())
 }
 
+/// Tree-sitter grammar for a language id, as used by the index
+pub(crate) fn language_for(lang_id: &str) -> Option<Language> {
+    match lang_id {
+        "python" => Some(tree_sitter_python::LANGUAGE.into()),
+        "rust" => Some(tree_sitter_rust::LANGUAGE.into()),
+        "go" => Some(tree_sitter_go::LANGUAGE.into()),
+        "c" => Some(tree_sitter_c::LANGUAGE.into()),
+        "cpp" => Some(tree_sitter_cpp::LANGUAGE.into()),
+        "java" => Some(tree_sitter_java::LANGUAGE.into()),
+        "typescript" | "javascript" => Some(tree_sitter_typescript::LANGUAGE_TSX.into()),
+        _ => None,
+    }
+}
+
+/// Outcome of a batch index
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BatchIndexResult {
+    pub indexed_files: usize,
+    pub definitions: usize,
+    /// Files that could not be indexed, per language id. Includes languages whose
+    /// grammar failed to load as well as individual parse failures.
+    pub failures: HashMap<String, usize>,
+}
+
 /// Batch index multiple files in parallel (definitions only)
 /// References are searched on-demand via hybrid search
 #[tauri::command]
 pub async fn code_nav_index_files_batch(
     state: State<'_, CodeNavState>,
     files: Vec<(String, String, String)>, // (file_path, content, lang_id)
-) -> Result<(), String> {
-    let start = Instant::now();
+) -> Result<BatchIndexResult, String> {
+    index_files_batch(&state.0, &files)
+}
 
-    // Log files being indexed for debugging
-    for (file_path, _, lang_id) in &files {
-        log::debug!("Batch indexing file: {} (lang: {})", file_path, lang_id);
-    }
+/// Definitions extracted from one file: its symbols, their names and the index key
+type ExtractedFile = (Vec<SymbolInfo>, HashSet<String>, String);
 
-    // Parallel extraction of definitions
-    let def_results: Vec<(Vec<SymbolInfo>, HashSet<String>, String)> = files
-        .par_iter()
-        .filter_map(|(file_path, content, lang_id)| {
-            let language: Language = match lang_id.as_str() {
-                "python" => tree_sitter_python::LANGUAGE.into(),
-                "rust" => tree_sitter_rust::LANGUAGE.into(),
-                "go" => tree_sitter_go::LANGUAGE.into(),
-                "c" => tree_sitter_c::LANGUAGE.into(),
-                "cpp" => tree_sitter_cpp::LANGUAGE.into(),
-                "java" => tree_sitter_java::LANGUAGE.into(),
-                "typescript" | "javascript" => tree_sitter_typescript::LANGUAGE_TSX.into(),
-                _ => {
-                    log::warn!(
-                        "Unsupported language for indexing: {} (file: {})",
-                        lang_id,
-                        file_path
-                    );
-                    return None;
-                }
+/// Languages whose grammar or query failed to load
+fn unavailable_languages(nav: &RwLock<CodeNavigationService>) -> Result<HashSet<String>, String> {
+    Ok(nav
+        .read()
+        .map_err(|e| format!("Failed to acquire read lock: {}", e))?
+        .degraded_languages()
+        .into_iter()
+        .map(|health| health.lang_id)
+        .collect())
+}
+
+/// Parse files and extract their definitions in parallel, BACKGROUND_CHUNK files at a time
+/// so interactive work can get ahead. `on_file` is called as each file is taken up.
+/// Returns the extracted files and the failures per language id.
+fn extract_definitions(
+    unavailable: &HashSet<String>,
+    files: &[(String, String, String)],
+    on_file: &(dyn Fn(&str) + Sync),
+) -> (Vec<ExtractedFile>, HashMap<String, usize>) {
+    let failures: Mutex<HashMap<String, usize>> = Mutex::new(HashMap::new());
+    let record_failure = |lang_id: &str| {
+        if let Ok(mut failures) = failures.lock() {
+            *failures.entry(lang_id.to_string()).or_default() += 1;
+        }
+    };
+    // Files queued before a directory was excluded are dropped once the change is seen
+    let exclusions = crate::exclusions::LiveExclusions::new();
+
+    let extract = |(file_path, content, lang_id): &(String, String, String)| {
+        let key = path_wire::index_key(file_path);
+        let file_path: &str = &key;
+        if exclusions.excludes_path(Path::new(file_path)) {
+            return None;
+        }
+        let mut definitions = Vec::new();
+        for unit in source_units(lang_id, content) {
+            if unavailable.contains(unit.lang_id) {
+                record_failure(unit.lang_id);
+                return None;
+            }
+            let Some(language) = language_for(unit.lang_id) else {
+                log::warn!(
+                    "Unsupported language for indexing: {} (file: {})",
+                    lang_id,
+                    file_path
+                );
+                return None;
             };
 
             let mut parser = Parser::new();
             if parser.set_language(&language).is_err() {
                 log::error!(
                     "Failed to set language for parser: {} (file: {})",
-                    lang_id,
+                    unit.lang_id,
                     file_path
                 );
+                record_failure(unit.lang_id);
                 return None;
             }
 
-            let tree = match parser.parse(content, None) {
+            let parse_span = perf_trace::span("index_files_batch", Phase::Parse, Some(file_path));
+            let tree = match parser.parse(unit.content.as_ref(), None) {
                 Some(t) => t,
                 None => {
                     log::error!("Failed to parse file: {}", file_path);
+                    record_failure(unit.lang_id);
                     return None;
                 }
             };
-            let source_bytes = content.as_bytes();
-            let lang_family = CodeNavigationService::get_lang_family(lang_id).to_string();
+            drop(parse_span);
 
-            let def_query_str = CodeNavigationService::get_definition_query(lang_id);
+            let _span = perf_trace::span("index_files_batch", Phase::Query, Some(file_path));
+            let def_query_str = CodeNavigationService::get_definition_query(unit.lang_id);
             let def_query = match Query::new(&language, def_query_str) {
                 Ok(q) => q,
                 Err(e) => {
                     log::error!("Failed to create query for {}: {:?}", file_path, e);
+                    record_failure(unit.lang_id);
                     return None;
                 }
             };
+            definitions.extend(collect_definitions(&tree, &def_query, &unit, file_path));
+        }
+        let defined_names: HashSet<String> = definitions.iter().map(|d| d.name.clone()).collect();
 
-            let mut definitions = Vec::new();
-            let mut defined_names = HashSet::new();
-            {
-                let mut cursor = QueryCursor::new();
-                let mut matches = cursor.matches(&def_query, tree.root_node(), source_bytes);
-                while let Some(m) = matches.next() {
-                    for capture in m.captures {
-                        let node = capture.node;
-                        // Use continue instead of ? to avoid skipping the entire file on one bad capture
-                        let name = match node.utf8_text(source_bytes) {
-                            Ok(text) => text.to_string(),
-                            Err(_) => continue,
-                        };
-                        let capture_name = def_query.capture_names()[capture.index as usize];
-                        let kind = CodeNavigationService::get_symbol_kind(capture_name);
-
-                        definitions.push(SymbolInfo {
-                            name: name.clone(),
-                            kind,
-                            file_path: file_path.clone(),
-                            lang_family: lang_family.clone(),
-                            start_line: node.start_position().row as u32 + 1,
-                            start_column: node.start_position().column as u32 + 1,
-                            end_line: node.end_position().row as u32 + 1,
-                            end_column: node.end_position().column as u32 + 1,
-                        });
-                        defined_names.insert(name);
-                    }
-                }
-            }
-
-            log::debug!(
-                "File {} parsed with {} definitions",
-                file_path,
-                definitions.len()
-            );
-            Some((definitions, defined_names, file_path.clone()))
-        })
-        .collect();
+        log::debug!(
+            "File {} parsed with {} definitions",
+            file_path,
+            definitions.len()
+        );
+        Some((definitions, defined_names, file_path.to_string()))
+    };
 
-    // Merge definitions into the index
-    let mut service = state
-        .0
-        .write()
-        .map_err(|e| format!("Failed to acquire write lock: {}", e))?;
+    let governor = concurrency::governor();
+    let mut def_results: Vec<ExtractedFile> = Vec::new();
+    for chunk in files.chunks(concurrency::BACKGROUND_CHUNK) {
+        governor.yield_to_interactive();
+        def_results.par_extend(
+            chunk
+                .par_iter()
+                .inspect(|(file_path, _, _)| on_file(file_path))
+                .filter_map(extract),
+        );
+    }
+    (def_results, failures.into_inner().unwrap_or_default())
+}
 
+/// Merge extracted files into the index one file at a time, so lookups are not held up
+/// for the whole batch. Returns how many definitions were added.
+fn merge_extracted(service: &CodeNavigationService, extracted: Vec<ExtractedFile>) -> usize {
+    let _span = perf_trace::span("index_files_batch", Phase::Merge, None);
     let mut total_defs = 0;
-
-    // Clear files and add definitions
-    for (definitions, defined_names, file_path) in &def_results {
-        service.clear_file(file_path);
+    for (definitions, defined_names, file_path) in extracted {
         total_defs += definitions.len();
-
         // Always track successfully parsed files, even if they have no definitions
         // This ensures files like test files are marked as "indexed"
+        let package = service.resolve_package(&file_path);
         service
             .index
-            .file_definitions
-            .insert(file_path.clone(), defined_names.clone());
+            .replace_file(&file_path, defined_names, package, definitions);
+        service.dirty.store(true, Ordering::Relaxed);
+    }
+    total_defs
+}
 
-        for symbol in definitions {
-            service
-                .index
-                .definitions
-                .entry(symbol.name.clone())
-                .or_default()
-                .push(symbol.clone());
-        }
+fn index_files_batch(
+    nav: &RwLock<CodeNavigationService>,
+    files: &[(String, String, String)],
+) -> Result<BatchIndexResult, String> {
+    let start = Instant::now();
+    let _span = perf_trace::span("index_files_batch", Phase::Total, None);
+
+    // Languages that failed to load are skipped up front, so their files count as failures
+    // instead of silently yielding no definitions
+    let unavailable = unavailable_languages(nav)?;
+
+    // Log files being indexed for debugging
+    for (file_path, _, lang_id) in files {
+        log::debug!("Batch indexing file: {} (lang: {})", file_path, lang_id);
     }
 
+    let total_files = files.len() as u64;
+    let activity_id = activity::activity_start(
+        ActivityKind::Indexing,
+        format!("Indexing {} files", total_files),
+        false,
+    );
+    let _permit = concurrency::governor().acquire(
+        OperationClass::Background,
+        &format!("Indexing {} files", total_files),
+        Some(&activity_id),
+    );
+    let processed = AtomicU64::new(0);
+
+    // Parallel extraction of definitions
+    let (def_results, failures) = extract_definitions(&unavailable, files, &|file_path| {
+        let done = processed.fetch_add(1, Ordering::Relaxed) + 1;
+        activity::activity_progress(
+            &activity_id,
+            done,
+            Some(total_files),
+            Some(file_path.to_string()),
+        );
+    });
+
+    // Merge under the read lock; the index synchronizes writers itself
+    let service = match nav.read() {
+        Ok(service) => service,
+        Err(e) => {
+            let message = format!("Failed to acquire read lock: {}", e);
+            activity::activity_finish(
+                &activity_id,
+                ActivityOutcome::Failed {
+                    message: message.clone(),
+                },
+            );
+            return Err(message);
+        }
+    };
+    let def_count = def_results.len();
+    let total_defs = merge_extracted(&service, def_results);
+
     let duration = start.elapsed();
     log::info!(
         "Batch indexed {} files ({} successfully parsed, {} definitions) in {:.2}ms",
         files.len(),
-        def_results.len(),
+        def_count,
         total_defs,
         duration.as_secs_f64() * 1000.0
     );
+    if !failures.is_empty() {
+        log::warn!("Batch indexing failures by language: {:?}", failures);
+    }
+    activity::activity_finish(&activity_id, ActivityOutcome::Succeeded);
 
-    Ok(())
+    Ok(BatchIndexResult {
+        indexed_files: def_count,
+        definitions: total_defs,
+        failures,
+    })
+}
+
+#[tauri::command]
+pub async fn code_nav_get_language_health(
+    state: State<'_, CodeNavState>,
+) -> Result<Vec<LanguageHealth>, String> {
+    let service = state
+        .0
+        .read()
+        .map_err(|e| format!("Failed to acquire read lock: {}", e))?;
+    Ok(service.language_health())
 }
 
 // ============================================================================
@@ -894,7 +2583,18 @@ pub async fn code_nav_index_files_batch(
 
 /// Current version of the persisted index format
 /// Version 2: Removed reference indexing (references are now searched on-demand via hybrid search)
-const INDEX_VERSION: u32 = 2;
+/// Version 3: Added UTF-16 columns to SymbolInfo
+/// Version 4: Added the exported flag to SymbolInfo
+/// Version 6: Added visibility to SymbolInfo
+const INDEX_VERSION: u32 = 6;
+
+/// Oldest persisted version that still loads. Version 5 entries lack visibility, which
+/// defaults to unknown until their files are indexed again.
+const MIN_COMPATIBLE_INDEX_VERSION: u32 = 5;
+
+fn is_compatible_index_version(version: u32) -> bool {
+    (MIN_COMPATIBLE_INDEX_VERSION..=INDEX_VERSION).contains(&version)
+}
 
 /// Persisted index data structure (definitions only, references are searched on-demand)
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -920,7 +2620,7 @@ pub struct IndexMetadata {
 }
 
 /// Generate a hash for the project path to use as filename
-fn get_project_hash(root_path: &str) -> String {
+pub(crate) fn get_project_hash(root_path: &str) -> String {
     let mut hasher = Sha256::new();
     hasher.update(root_path.as_bytes());
     let result = hasher.finalize();
@@ -928,13 +2628,16 @@ fn get_project_hash(root_path: &str) -> String {
     hex::encode(&result[..8])
 }
 
+/// Directory under the app data directory holding persisted indexes
+pub(crate) const INDEX_DIR_NAME: &str = "code-index";
+
 /// Get the index directory path
-fn get_index_dir(app_handle: &AppHandle) -> Result<PathBuf, String> {
+pub(crate) fn get_index_dir(app_handle: &AppHandle) -> Result<PathBuf, String> {
     let app_data_dir = app_handle
         .path()
         .app_data_dir()
         .map_err(|e| format!("Failed to get app data dir: {}", e))?;
-    Ok(app_data_dir.join("code-index"))
+    Ok(app_data_dir.join(INDEX_DIR_NAME))
 }
 
 /// Get the index file path for a project
@@ -944,123 +2647,295 @@ fn get_index_path(app_handle: &AppHandle, root_path: &str) -> Result<PathBuf, St
     Ok(index_dir.join(format!("{}.json", hash)))
 }
 
-/// Save the current index to disk
-#[tauri::command]
-pub async fn code_nav_save_index(
-    app_handle: AppHandle,
-    state: State<'_, CodeNavState>,
+struct PersistTarget {
+    index_path: PathBuf,
     root_path: String,
     file_timestamps: HashMap<String, i64>,
-) -> Result<(), String> {
-    let start = Instant::now();
-
-    let service = state
-        .0
-        .read()
-        .map_err(|e| format!("Failed to acquire read lock: {}", e))?;
-
-    let persisted = PersistedIndex {
-        version: INDEX_VERSION,
-        root_path: root_path.clone(),
-        last_updated: chrono::Utc::now().timestamp(),
-        file_timestamps,
-        definitions: service.index.definitions.clone(),
-        file_definitions: service.index.file_definitions.clone(),
-    };
-
-    // Release the lock before doing I/O
-    drop(service);
-
-    // Ensure index directory exists
-    let index_dir = get_index_dir(&app_handle)?;
-    fs::create_dir_all(&index_dir)
-        .map_err(|e| format!("Failed to create index directory: {}", e))?;
+}
 
-    // Serialize and write to file
-    let index_path = get_index_path(&app_handle, &root_path)?;
-    let json = serde_json::to_string(&persisted)
+fn write_persisted_index(index_path: &Path, persisted: &PersistedIndex) -> Result<(), String> {
+    if let Some(index_dir) = index_path.parent() {
+        fs::create_dir_all(index_dir)
+            .map_err(|e| format!("Failed to create index directory: {}", e))?;
+    }
+    let json = serde_json::to_string(persisted)
         .map_err(|e| format!("Failed to serialize index: {}", e))?;
-    fs::write(&index_path, json).map_err(|e| format!("Failed to write index file: {}", e))?;
-
-    let duration = start.elapsed();
-    log::info!(
-        "Saved index for {} ({} definitions) in {:.2}ms",
-        root_path,
-        persisted
-            .definitions
-            .values()
-            .map(|v| v.len())
-            .sum::<usize>(),
-        duration.as_secs_f64() * 1000.0
-    );
+    fs::write(index_path, json).map_err(|e| format!("Failed to write index file: {}", e))
+}
 
-    Ok(())
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexSaveResult {
+    /// False when the save was cancelled; the previous file is kept
+    pub saved: bool,
+    pub bytes_written: u64,
+    pub definition_count: usize,
+    pub timings: Vec<PhaseTiming>,
+    pub elapsed_ms: u64,
 }
 
-/// Load a persisted index from disk
-#[tauri::command]
-pub async fn code_nav_load_index(
-    app_handle: AppHandle,
-    state: State<'_, CodeNavState>,
-    root_path: String,
-) -> Result<bool, String> {
-    let start = Instant::now();
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum IndexLoadStatus {
+    Loaded,
+    /// No index saved for the project
+    Missing,
+    /// Saved by another index version or for another root; needs a rebuild
+    Incompatible,
+    Cancelled,
+    /// The disk was too slow; the index should be rebuilt
+    TimedOut,
+}
 
-    let index_path = get_index_path(&app_handle, &root_path)?;
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexLoadResult {
+    pub status: IndexLoadStatus,
+    pub bytes_read: u64,
+    pub files_merged: usize,
+    pub definition_count: usize,
+    pub timings: Vec<PhaseTiming>,
+    pub elapsed_ms: u64,
+}
 
-    if !index_path.exists() {
-        log::info!("No persisted index found for {}", root_path);
-        return Ok(false);
+impl IndexLoadResult {
+    fn new(status: IndexLoadStatus, control: &PersistControl) -> Self {
+        Self {
+            status,
+            bytes_read: 0,
+            files_merged: 0,
+            definition_count: 0,
+            timings: control.timings(),
+            elapsed_ms: control.elapsed_ms(),
+        }
     }
+}
 
-    // Read and deserialize
-    let json =
-        fs::read_to_string(&index_path).map_err(|e| format!("Failed to read index file: {}", e))?;
-    let persisted: PersistedIndex =
-        serde_json::from_str(&json).map_err(|e| format!("Failed to deserialize index: {}", e))?;
+/// Serialize and write a snapshot, cooperatively
+fn save_snapshot(
+    index_path: &Path,
+    persisted: &PersistedIndex,
+    control: &PersistControl,
+) -> Result<u64, PersistError> {
+    let bytes = control.timed(PersistPhase::Serializing, || {
+        index_persist::serialize(persisted, control)
+    })?;
+    control.timed(PersistPhase::Writing, || {
+        index_persist::write_file(index_path, &bytes, control)
+    })?;
+    Ok(bytes.len() as u64)
+}
+
+/// Read, parse and merge a persisted index. A cancelled load clears the index, or keeps
+/// the files merged so far with `keep_partial`; either way it is not marked as persisted.
+fn load_persisted<R: std::io::Read>(
+    nav: &RwLock<CodeNavigationService>,
+    reader: R,
+    total_bytes: Option<u64>,
+    index_path: &Path,
+    root_path: &str,
+    keep_partial: bool,
+    control: &PersistControl,
+) -> Result<IndexLoadResult, String> {
+    let cancelled = |control: &PersistControl| -> Result<IndexLoadResult, String> {
+        if !keep_partial {
+            nav.write()
+                .map_err(|e| format!("Failed to acquire write lock: {}", e))?
+                .clear_all();
+        }
+        log::info!("Loading index for {} was cancelled", root_path);
+        Ok(IndexLoadResult::new(IndexLoadStatus::Cancelled, control))
+    };
+
+    let bytes = match control.timed(PersistPhase::Reading, || {
+        index_persist::read_all(reader, total_bytes, control)
+    }) {
+        Ok(bytes) => bytes,
+        Err(PersistError::Cancelled) => return cancelled(control),
+        Err(e) => return Err(e.to_string()),
+    };
+    let persisted: PersistedIndex = match control.timed(PersistPhase::Parsing, || {
+        index_persist::parse(&bytes, control)
+    }) {
+        Ok(persisted) => persisted,
+        Err(PersistError::Cancelled) => return cancelled(control),
+        Err(e) => return Err(e.to_string()),
+    };
+    let bytes_read = bytes.len() as u64;
+    drop(bytes);
 
     // Check version compatibility
-    if persisted.version != INDEX_VERSION {
+    if !is_compatible_index_version(persisted.version) {
         log::warn!(
             "Index version mismatch: expected {}, got {}. Rebuilding index.",
             INDEX_VERSION,
             persisted.version
         );
         // Delete outdated index file
-        let _ = fs::remove_file(&index_path);
-        return Ok(false);
+        let _ = fs::remove_file(index_path);
+        return Ok(IndexLoadResult::new(IndexLoadStatus::Incompatible, control));
     }
 
     // Verify root path matches
     if persisted.root_path != root_path {
         log::warn!("Index root path mismatch. Rebuilding index.");
-        return Ok(false);
+        return Ok(IndexLoadResult::new(IndexLoadStatus::Incompatible, control));
     }
 
-    // Load into service
-    let mut service = state
-        .0
+    // Lookups wait for the merge, as they would see a half-loaded index otherwise
+    let mut service = nav
         .write()
         .map_err(|e| format!("Failed to acquire write lock: {}", e))?;
-
     service.clear_all();
-    service.index.definitions = persisted.definitions;
-    service.index.file_definitions = persisted.file_definitions;
+    let files_merged = control.timed(PersistPhase::Merging, || {
+        service.merge_persisted(
+            persisted.definitions,
+            persisted.file_definitions,
+            |done, total| {
+                control.report(PersistPhase::Merging, done as u64, Some(total as u64));
+                !control.is_cancelled()
+            },
+        )
+    });
+    if control.is_cancelled() {
+        drop(service);
+        return cancelled(control);
+    }
+    service.mark_persisted(PersistTarget {
+        index_path: index_path.to_path_buf(),
+        root_path: root_path.to_string(),
+        file_timestamps: persisted.file_timestamps,
+    });
+
+    Ok(IndexLoadResult {
+        bytes_read,
+        files_merged,
+        definition_count: service.index.symbol_count(),
+        ..IndexLoadResult::new(IndexLoadStatus::Loaded, control)
+    })
+}
+
+/// Save the current index to disk. With an `operation_id` the save can be cancelled
+/// through the cancellation registry, which keeps the previous file.
+#[tauri::command]
+pub async fn code_nav_save_index(
+    app_handle: AppHandle,
+    state: State<'_, CodeNavState>,
+    root_path: String,
+    file_timestamps: HashMap<String, i64>,
+    operation_id: Option<String>,
+) -> Result<IndexSaveResult, String> {
+    let index_path = get_index_path(&app_handle, &root_path)?;
+    let control = PersistControl::new(&root_path, operation_id);
+    // The lock is released before doing I/O
+    let persisted = {
+        let mut service = state
+            .0
+            .write()
+            .map_err(|e| format!("Failed to acquire write lock: {}", e))?;
+        let persisted = service.snapshot(&root_path, file_timestamps.clone());
+        service.mark_persisted(PersistTarget {
+            index_path: index_path.clone(),
+            root_path: root_path.clone(),
+            file_timestamps,
+        });
+        persisted
+    };
+
+    let definition_count = persisted
+        .definitions
+        .values()
+        .map(|v| v.len())
+        .sum::<usize>();
+    let (written, control) = tauri::async_runtime::spawn_blocking(move || {
+        (save_snapshot(&index_path, &persisted, &control), control)
+    })
+    .await
+    .map_err(|e| format!("Index save task failed: {}", e))?;
+
+    if written.is_err() {
+        if let Ok(service) = state.0.read() {
+            service.dirty.store(true, Ordering::Relaxed);
+        }
+    }
+    let bytes_written = match written {
+        Ok(bytes) => bytes,
+        Err(PersistError::Cancelled) => {
+            log::info!("Saving index for {} was cancelled", root_path);
+            0
+        }
+        Err(e) => return Err(e.to_string()),
+    };
+
+    log::info!(
+        "Saved index for {} ({} definitions, {} bytes) in {}ms",
+        root_path,
+        definition_count,
+        bytes_written,
+        control.elapsed_ms()
+    );
+    Ok(IndexSaveResult {
+        saved: !control.is_cancelled(),
+        bytes_written,
+        definition_count,
+        timings: control.timings(),
+        elapsed_ms: control.elapsed_ms(),
+    })
+}
+
+/// Load a persisted index from disk. Progress is emitted as "index-persist-progress"; with
+/// an `operation_id` the load can be cancelled through the cancellation registry. A load
+/// exceeding `timeout_ms` (default LOAD_TIMEOUT) gives up and reports that the index needs
+/// rebuilding.
+#[tauri::command]
+pub async fn code_nav_load_index(
+    app_handle: AppHandle,
+    root_path: String,
+    operation_id: Option<String>,
+    keep_partial: Option<bool>,
+    timeout_ms: Option<u64>,
+) -> Result<IndexLoadResult, String> {
+    let index_path = get_index_path(&app_handle, &root_path)?;
+    let control = Arc::new(PersistControl::new(&root_path, operation_id));
+
+    if !index_path.exists() {
+        log::info!("No persisted index found for {}", root_path);
+        return Ok(IndexLoadResult::new(IndexLoadStatus::Missing, &control));
+    }
+
+    let timeout = timeout_ms.map_or(index_persist::LOAD_TIMEOUT, Duration::from_millis);
+    let task_control = control.clone();
+    let task_root = root_path.clone();
+    let result = index_persist::with_timeout(timeout, control.clone(), move || {
+        let file =
+            fs::File::open(&index_path).map_err(|e| format!("Failed to read index file: {}", e))?;
+        let total_bytes = file.metadata().ok().map(|m| m.len());
+        let state = app_handle.state::<CodeNavState>();
+        load_persisted(
+            &state.0,
+            file,
+            total_bytes,
+            &index_path,
+            &task_root,
+            keep_partial.unwrap_or(false),
+            &task_control,
+        )
+    })
+    .await;
 
-    let duration = start.elapsed();
+    let result = match result {
+        Ok(result) => result?,
+        Err(PersistError::TimedOut) => IndexLoadResult::new(IndexLoadStatus::TimedOut, &control),
+        Err(e) => return Err(e.to_string()),
+    };
     log::info!(
-        "Loaded index for {} ({} definitions) in {:.2}ms",
+        "Loading index for {}: {:?} ({} definitions) in {}ms",
         root_path,
-        service
-            .index
-            .definitions
-            .values()
-            .map(|v| v.len())
-            .sum::<usize>(),
-        duration.as_secs_f64() * 1000.0
+        result.status,
+        result.definition_count,
+        result.elapsed_ms
     );
-
-    Ok(true)
+    Ok(result)
 }
 
 /// Get metadata about a persisted index without loading it
@@ -1082,7 +2957,7 @@ pub async fn code_nav_get_index_metadata(
         serde_json::from_str(&json).map_err(|e| format!("Failed to deserialize index: {}", e))?;
 
     // Check version compatibility
-    if persisted.version != INDEX_VERSION {
+    if !is_compatible_index_version(persisted.version) {
         return Ok(None);
     }
 
@@ -1119,7 +2994,679 @@ pub async fn code_nav_get_indexed_files(
         .read()
         .map_err(|e| format!("Failed to acquire read lock: {}", e))?;
 
-    Ok(service.index.file_definitions.keys().cloned().collect())
+    Ok(service.index.indexed_files())
+}
+
+/// A file worth indexing, with the mtime (seconds) used as its index timestamp
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexableFile {
+    pub path: String,
+    pub lang_id: String,
+    pub modified_time: i64,
+    #[serde(flatten)]
+    pub raw_path: RawPath,
+}
+
+fn modified_secs(metadata: &fs::Metadata) -> i64 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|m| m.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+/// Candidate paths from git: tracked files, plus untracked ones that are not ignored
+fn git_candidate_paths(
+    repo: &git2::Repository,
+    root: &Path,
+    include_untracked: bool,
+) -> Result<Vec<PathBuf>, String> {
+    let workdir = repo
+        .workdir()
+        .ok_or("Repository has no working directory")?
+        .to_path_buf();
+    let index = repo.index().map_err(|e| e.to_string())?;
+    let mut paths: Vec<PathBuf> = index
+        .iter()
+        // Submodules (gitlinks) are directories, not files
+        .filter(|entry| entry.mode != 0o160000)
+        .map(|entry| workdir.join(String::from_utf8_lossy(&entry.path).as_ref()))
+        .collect();
+
+    if include_untracked {
+        let mut opts = git2::StatusOptions::new();
+        opts.include_untracked(true)
+            .recurse_untracked_dirs(true)
+            .include_ignored(false);
+        let statuses = repo.statuses(Some(&mut opts)).map_err(|e| e.to_string())?;
+        paths.extend(
+            statuses
+                .iter()
+                .filter(|entry| entry.status().is_wt_new())
+                .filter_map(|entry| entry.path().map(|path| workdir.join(path))),
+        );
+    }
+
+    // The root may be a subdirectory of the repository
+    let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+    let workdir = workdir.canonicalize().unwrap_or(workdir);
+    Ok(paths
+        .into_iter()
+        .filter_map(|path| {
+            let relative = path.strip_prefix(&workdir).ok()?;
+            let path = workdir.join(relative);
+            path.starts_with(&root).then_some(path)
+        })
+        .collect())
+}
+
+/// Candidate paths from a directory walk with the shared exclusions
+fn walk_candidate_paths(root: &Path, respect_gitignore: bool) -> Vec<PathBuf> {
+    let exclusions = crate::exclusions::LiveExclusions::new();
+    let mut walker = ignore::WalkBuilder::new(root);
+    walker
+        .hidden(true)
+        .git_ignore(respect_gitignore)
+        .git_global(respect_gitignore)
+        .git_exclude(respect_gitignore)
+        .ignore(respect_gitignore)
+        .parents(respect_gitignore)
+        .filter_entry(move |entry| {
+            if entry.file_type().is_some_and(|t| t.is_dir()) {
+                if let Some(name) = entry.file_name().to_str() {
+                    return !exclusions.excludes_dir(name);
+                }
+            }
+            true
+        });
+    walker
+        .build()
+        .flatten()
+        .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()))
+        .map(|entry| entry.into_path())
+        .collect()
+}
+
+/// Files under `root_path` in a language code navigation knows, with their mtimes. In a
+/// git repository with `respect_gitignore`, the list comes from git (tracked files, plus
+/// untracked non-ignored ones with `include_untracked`) instead of a walk, which keeps
+/// vendored and generated files out of the index.
+pub fn list_indexable_files(
+    root_path: &str,
+    respect_gitignore: bool,
+    include_untracked: bool,
+) -> Result<Vec<IndexableFile>, String> {
+    let root = Path::new(root_path);
+    if !root.is_dir() {
+        return Err(format!("Not a directory: {}", root_path));
+    }
+    let repo = respect_gitignore
+        .then(|| git2::Repository::discover(root).ok())
+        .flatten()
+        .filter(|repo| !repo.is_bare());
+    let candidates = match repo {
+        Some(repo) => git_candidate_paths(&repo, root, include_untracked)?,
+        None => walk_candidate_paths(root, respect_gitignore),
+    };
+
+    let mut files: Vec<IndexableFile> = candidates
+        .par_iter()
+        .filter_map(|path| {
+            let path_str = path_wire::encode(path);
+            let lang_id = CodeNavigationService::get_lang_id_from_path(&path_str)
+                .filter(|lang_id| embedded_code::is_indexable(lang_id))?;
+            // Tracked files deleted from the working tree have no metadata
+            let metadata = fs::metadata(path).ok().filter(|m| m.is_file())?;
+            Some(IndexableFile {
+                path: path_str,
+                lang_id,
+                modified_time: modified_secs(&metadata),
+                raw_path: RawPath::of(path),
+            })
+        })
+        .collect();
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    files.dedup_by(|a, b| a.path == b.path);
+    Ok(files)
+}
+
+/// Files to feed into batch or incremental indexing, with mtimes so no second stat pass
+/// is needed
+#[tauri::command]
+pub async fn code_nav_list_indexable_files(
+    root_path: String,
+    respect_gitignore: Option<bool>,
+    include_untracked: Option<bool>,
+) -> Result<Vec<IndexableFile>, String> {
+    let start = std::time::Instant::now();
+    let files = tauri::async_runtime::spawn_blocking(move || {
+        list_indexable_files(
+            &root_path,
+            respect_gitignore.unwrap_or(true),
+            include_untracked.unwrap_or(false),
+        )
+    })
+    .await
+    .map_err(|e| format!("Listing indexable files failed: {}", e))??;
+    log::info!(
+        "Listed {} indexable files in {}ms",
+        files.len(),
+        start.elapsed().as_millis()
+    );
+    Ok(files)
+}
+
+// ============================================================================
+// Index Bootstrap
+// ============================================================================
+
+/// Files read, parsed and persisted per bootstrap shard
+const BOOTSTRAP_CHUNK_FILES: usize = 500;
+
+/// Files larger than this are skipped by a bootstrap unless the options say otherwise
+const DEFAULT_BOOTSTRAP_MAX_FILE_BYTES: u64 = 1024 * 1024;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BootstrapOptions {
+    /// Files larger than this many bytes are not indexed
+    pub max_file_size: u64,
+    pub respect_gitignore: bool,
+    pub include_untracked: bool,
+    /// Also build the docs index
+    pub include_docs: bool,
+    /// Also index the public declarations of the direct dependencies
+    pub index_dependency_types: bool,
+}
+
+impl Default for BootstrapOptions {
+    fn default() -> Self {
+        Self {
+            max_file_size: DEFAULT_BOOTSTRAP_MAX_FILE_BYTES,
+            respect_gitignore: true,
+            include_untracked: true,
+            include_docs: false,
+            index_dependency_types: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BootstrapStatus {
+    Completed,
+    /// Stopped early; the shards persisted so far are picked up by the next bootstrap
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BootstrapResult {
+    pub status: BootstrapStatus,
+    /// Indexable files found under the root
+    pub total_files: usize,
+    /// Files taken unchanged from the saved index or an interrupted bootstrap
+    pub reused_files: usize,
+    /// Files read and parsed by this bootstrap
+    pub indexed_files: usize,
+    /// Files dropped from the index because they no longer exist
+    pub removed_files: usize,
+    /// Files over the size limit or that could not be read as text
+    pub skipped_files: usize,
+    /// Shards of an interrupted bootstrap that were resumed from
+    pub resumed_shards: usize,
+    pub definition_count: usize,
+    pub failures: HashMap<String, usize>,
+    pub dependencies: Option<DependencyIndexResult>,
+    pub docs: Option<DocsIndexStats>,
+    pub elapsed_ms: u64,
+}
+
+/// A file as written to a bootstrap shard
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ShardFile {
+    path: String,
+    modified_time: i64,
+    definitions: Vec<SymbolInfo>,
+}
+
+/// The shards a bootstrap has completed, with the timestamps of the files in them. A
+/// shard is only listed once it is fully written, so a crash loses at most one chunk.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct BootstrapManifest {
+    version: u32,
+    root_path: String,
+    shards: Vec<String>,
+    file_timestamps: HashMap<String, i64>,
+}
+
+const BOOTSTRAP_MANIFEST: &str = "manifest.json";
+
+fn bootstrap_dir(index_dir: &Path, root_path: &str) -> PathBuf {
+    index_dir.join(format!("bootstrap-{}", get_project_hash(root_path)))
+}
+
+/// The manifest of an interrupted bootstrap of `root_path`. One from another index
+/// version is deleted with its shards.
+fn read_bootstrap_manifest(dir: &Path, root_path: &str) -> Option<BootstrapManifest> {
+    let json = fs::read_to_string(dir.join(BOOTSTRAP_MANIFEST)).ok()?;
+    match serde_json::from_str::<BootstrapManifest>(&json) {
+        Ok(manifest) if manifest.version == INDEX_VERSION && manifest.root_path == root_path => {
+            Some(manifest)
+        }
+        _ => {
+            log::warn!("Discarding outdated bootstrap shards in {}", dir.display());
+            let _ = fs::remove_dir_all(dir);
+            None
+        }
+    }
+}
+
+/// Write a shard, then list it in the manifest. The manifest is replaced by a rename, so
+/// it never names a shard that is not on disk.
+fn persist_shard(
+    dir: &Path,
+    manifest: &mut BootstrapManifest,
+    files: &[ShardFile],
+) -> Result<(), String> {
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create shard directory: {}", e))?;
+    let name = format!("shard-{}.json", manifest.shards.len());
+    let json =
+        serde_json::to_vec(files).map_err(|e| format!("Failed to serialize shard: {}", e))?;
+    fs::write(dir.join(&name), json).map_err(|e| format!("Failed to write shard: {}", e))?;
+
+    manifest.shards.push(name);
+    manifest.file_timestamps.extend(
+        files
+            .iter()
+            .map(|file| (file.path.clone(), file.modified_time)),
+    );
+    let json = serde_json::to_vec(manifest)
+        .map_err(|e| format!("Failed to serialize bootstrap manifest: {}", e))?;
+    let temp = dir.join(format!("{}.tmp", BOOTSTRAP_MANIFEST));
+    fs::write(&temp, json).map_err(|e| format!("Failed to write bootstrap manifest: {}", e))?;
+    fs::rename(&temp, dir.join(BOOTSTRAP_MANIFEST))
+        .map_err(|e| format!("Failed to write bootstrap manifest: {}", e))
+}
+
+/// Merge the shards listed in a manifest into the index. Returns how many were read and
+/// the files they held; files of an unreadable shard are parsed again.
+fn merge_shards(
+    service: &CodeNavigationService,
+    dir: &Path,
+    manifest: &BootstrapManifest,
+) -> (usize, HashSet<String>) {
+    let mut merged = 0;
+    let mut merged_files = HashSet::new();
+    for name in &manifest.shards {
+        let files: Vec<ShardFile> = match fs::read(dir.join(name))
+            .map_err(|e| e.to_string())
+            .and_then(|bytes| serde_json::from_slice(&bytes).map_err(|e| e.to_string()))
+        {
+            Ok(files) => files,
+            Err(e) => {
+                log::warn!("Skipping unreadable bootstrap shard {}: {}", name, e);
+                continue;
+            }
+        };
+        for file in files {
+            let names = file.definitions.iter().map(|d| d.name.clone()).collect();
+            let package = service.resolve_package(&file.path);
+            service
+                .index
+                .replace_file(&file.path, names, package, file.definitions);
+            merged_files.insert(file.path);
+        }
+        merged += 1;
+    }
+    service.dirty.store(true, Ordering::Relaxed);
+    (merged, merged_files)
+}
+
+/// Read a file for indexing; None when it is over `max_bytes` or not text
+fn read_indexable(file: &IndexableFile, max_bytes: u64) -> Option<String> {
+    let path = path_wire::resolve(&file.path);
+    let metadata = fs::metadata(&path).ok()?;
+    if metadata.len() > max_bytes {
+        log::debug!(
+            "Skipping {} ({} bytes) for the index",
+            file.path,
+            metadata.len()
+        );
+        return None;
+    }
+    fs::read_to_string(&path).ok()
+}
+
+/// Build the index of `root_path` from the files on disk and save it to `index_dir`.
+/// A saved index is loaded first and only files whose mtime changed are parsed again;
+/// shards of an interrupted bootstrap are merged the same way. Files are read and parsed
+/// `chunk_files` at a time, and each chunk is written as a shard before the next starts.
+/// `progress` is called with (done, total, file) as files are taken up. Cancelling
+/// through `control` keeps the shards for the next bootstrap.
+fn bootstrap_index(
+    nav: &RwLock<CodeNavigationService>,
+    index_dir: &Path,
+    root_path: &str,
+    options: &BootstrapOptions,
+    chunk_files: usize,
+    control: &PersistControl,
+    progress: &(dyn Fn(u64, u64, &str) + Sync),
+) -> Result<BootstrapResult, String> {
+    let files = list_indexable_files(
+        root_path,
+        options.respect_gitignore,
+        options.include_untracked,
+    )?;
+    let index_path = index_dir.join(format!("{}.json", get_project_hash(root_path)));
+    let shard_dir = bootstrap_dir(index_dir, root_path);
+    let mut result = BootstrapResult {
+        status: BootstrapStatus::Completed,
+        total_files: files.len(),
+        reused_files: 0,
+        indexed_files: 0,
+        removed_files: 0,
+        skipped_files: 0,
+        resumed_shards: 0,
+        definition_count: 0,
+        failures: HashMap::new(),
+        dependencies: None,
+        docs: None,
+        elapsed_ms: 0,
+    };
+    let cancelled = |mut result: BootstrapResult| -> Result<BootstrapResult, String> {
+        log::info!("Bootstrapping the index of {} was cancelled", root_path);
+        result.status = BootstrapStatus::Cancelled;
+        result.elapsed_ms = control.elapsed_ms();
+        Ok(result)
+    };
+
+    // Start from the saved index, if there is a usable one
+    let mut timestamps: HashMap<String, i64> = HashMap::new();
+    let loaded = match fs::File::open(&index_path) {
+        Ok(file) => {
+            let total_bytes = file.metadata().ok().map(|m| m.len());
+            load_persisted(
+                nav,
+                file,
+                total_bytes,
+                &index_path,
+                root_path,
+                false,
+                control,
+            )
+            .map(|load| load.status)
+            .unwrap_or_else(|e| {
+                log::warn!("Rebuilding the index of {}: {}", root_path, e);
+                IndexLoadStatus::Incompatible
+            })
+        }
+        Err(_) => IndexLoadStatus::Missing,
+    };
+    match loaded {
+        IndexLoadStatus::Cancelled => return cancelled(result),
+        IndexLoadStatus::Loaded => {
+            let service = nav
+                .read()
+                .map_err(|e| format!("Failed to acquire read lock: {}", e))?;
+            if let Some(target) = &service.persist_target {
+                timestamps = target.file_timestamps.clone();
+            }
+        }
+        _ => nav
+            .write()
+            .map_err(|e| format!("Failed to acquire write lock: {}", e))?
+            .clear_all(),
+    }
+
+    // Then from the shards of an interrupted bootstrap, which are newer
+    let mut manifest = match read_bootstrap_manifest(&shard_dir, root_path) {
+        Some(manifest) => {
+            let service = nav
+                .read()
+                .map_err(|e| format!("Failed to acquire read lock: {}", e))?;
+            let (shards, merged_files) = merge_shards(&service, &shard_dir, &manifest);
+            result.resumed_shards = shards;
+            timestamps.extend(
+                manifest
+                    .file_timestamps
+                    .iter()
+                    .filter(|(path, _)| merged_files.contains(*path))
+                    .map(|(path, modified)| (path.clone(), *modified)),
+            );
+            log::info!(
+                "Resuming the index bootstrap of {} from {} shards",
+                root_path,
+                result.resumed_shards
+            );
+            manifest
+        }
+        None => BootstrapManifest {
+            version: INDEX_VERSION,
+            root_path: root_path.to_string(),
+            ..Default::default()
+        },
+    };
+
+    // Files gone from disk leave the index; unchanged ones are kept as they are
+    let current: HashSet<&str> = files.iter().map(|f| f.path.as_str()).collect();
+    let removed: Vec<String> = timestamps
+        .keys()
+        .filter(|path| !current.contains(path.as_str()))
+        .cloned()
+        .collect();
+    {
+        let service = nav
+            .read()
+            .map_err(|e| format!("Failed to acquire read lock: {}", e))?;
+        for path in &removed {
+            service.index.remove_file(path);
+            timestamps.remove(path);
+        }
+        if !removed.is_empty() {
+            service.dirty.store(true, Ordering::Relaxed);
+        }
+    }
+    result.removed_files = removed.len();
+    let stale: Vec<&IndexableFile> = files
+        .iter()
+        .filter(|file| timestamps.get(&file.path) != Some(&file.modified_time))
+        .collect();
+    result.reused_files = files.len() - stale.len();
+
+    let unavailable = unavailable_languages(nav)?;
+    let total = stale.len() as u64;
+    let processed = AtomicU64::new(0);
+    let governor = concurrency::governor();
+    for chunk in stale.chunks(chunk_files.max(1)) {
+        if control.is_cancelled() {
+            return cancelled(result);
+        }
+        governor.yield_to_interactive();
+
+        let contents: Vec<Option<String>> = chunk
+            .par_iter()
+            .map(|file| read_indexable(file, options.max_file_size))
+            .collect();
+        let mut batch = Vec::with_capacity(chunk.len());
+        for (file, content) in chunk.iter().zip(contents) {
+            match content {
+                Some(content) => batch.push((file.path.clone(), content, file.lang_id.clone())),
+                None => result.skipped_files += 1,
+            }
+        }
+        let (extracted, failures) = extract_definitions(&unavailable, &batch, &|file_path| {
+            let done = processed.fetch_add(1, Ordering::Relaxed) + 1;
+            progress(done, total, file_path);
+        });
+        processed.fetch_add((chunk.len() - batch.len()) as u64, Ordering::Relaxed);
+        for (lang_id, count) in failures {
+            *result.failures.entry(lang_id).or_default() += count;
+        }
+
+        let modified: HashMap<&str, i64> = chunk
+            .iter()
+            .map(|file| (file.path.as_str(), file.modified_time))
+            .collect();
+        let shard: Vec<ShardFile> = extracted
+            .iter()
+            .map(|(definitions, _, path)| ShardFile {
+                path: path.clone(),
+                modified_time: modified.get(path.as_str()).copied().unwrap_or(0),
+                definitions: definitions.clone(),
+            })
+            .collect();
+        persist_shard(&shard_dir, &mut manifest, &shard)?;
+
+        let service = nav
+            .read()
+            .map_err(|e| format!("Failed to acquire read lock: {}", e))?;
+        // Files that no longer parse lose their old definitions, as after a clear_file
+        let parsed: HashSet<&str> = extracted.iter().map(|(_, _, path)| path.as_str()).collect();
+        for file in chunk {
+            if !parsed.contains(file.path.as_str()) {
+                service.index.remove_file(&file.path);
+                timestamps.remove(&file.path);
+            }
+        }
+        timestamps.extend(
+            shard
+                .into_iter()
+                .map(|file| (file.path, file.modified_time)),
+        );
+        result.indexed_files += extracted.len();
+        merge_extracted(&service, extracted);
+    }
+
+    if options.index_dependency_types {
+        let mut service = nav
+            .write()
+            .map_err(|e| format!("Failed to acquire write lock: {}", e))?;
+        result.dependencies = Some(dependency_index::index_dependencies(
+            &mut service,
+            root_path,
+            None,
+            dependency_index::cargo_home().as_deref(),
+        ));
+    }
+
+    // Save the whole index, then drop the shards it now includes
+    let persisted = {
+        let mut service = nav
+            .write()
+            .map_err(|e| format!("Failed to acquire write lock: {}", e))?;
+        let persisted = service.snapshot(root_path, timestamps.clone());
+        service.mark_persisted(PersistTarget {
+            index_path: index_path.clone(),
+            root_path: root_path.to_string(),
+            file_timestamps: timestamps,
+        });
+        result.definition_count = service.index.symbol_count();
+        persisted
+    };
+    match save_snapshot(&index_path, &persisted, control) {
+        Ok(_) => {}
+        Err(PersistError::Cancelled) => {
+            if let Ok(service) = nav.read() {
+                service.dirty.store(true, Ordering::Relaxed);
+            }
+            return cancelled(result);
+        }
+        Err(e) => return Err(e.to_string()),
+    }
+    if let Err(e) = fs::remove_dir_all(&shard_dir) {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            log::warn!("Failed to remove bootstrap shards: {}", e);
+        }
+    }
+
+    result.elapsed_ms = control.elapsed_ms();
+    Ok(result)
+}
+
+/// Build the project index entirely in the backend: list the files, load the saved index
+/// or resume an interrupted bootstrap, parse what changed under a background permit,
+/// and save the result. Progress shows in the activity center; with an `operation_id`
+/// the bootstrap can be cancelled through the cancellation registry.
+#[tauri::command]
+pub async fn code_nav_bootstrap_index(
+    app_handle: AppHandle,
+    root_path: String,
+    options: Option<BootstrapOptions>,
+    operation_id: Option<String>,
+) -> Result<BootstrapResult, String> {
+    let options = options.unwrap_or_default();
+    let index_dir = get_index_dir(&app_handle)?;
+    let control = PersistControl::new(&root_path, operation_id);
+    let activity_id = activity::activity_start(
+        ActivityKind::Indexing,
+        format!("Indexing {}", root_path),
+        false,
+    );
+
+    let task_activity = activity_id.clone();
+    let task_root = root_path.clone();
+    let task_app = app_handle.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        let _permit = concurrency::governor().acquire(
+            OperationClass::Background,
+            &format!("Indexing {}", task_root),
+            Some(&task_activity),
+        );
+        let state = task_app.state::<CodeNavState>();
+        bootstrap_index(
+            &state.0,
+            &index_dir,
+            &task_root,
+            &options,
+            BOOTSTRAP_CHUNK_FILES,
+            &control,
+            &|done, total, file_path| {
+                activity::activity_progress(
+                    &task_activity,
+                    done,
+                    Some(total),
+                    Some(file_path.to_string()),
+                )
+            },
+        )
+        .map(|result| (result, options.include_docs))
+    })
+    .await
+    .map_err(|e| format!("Index bootstrap task failed: {}", e))
+    .and_then(|result| result);
+
+    let result = match result {
+        Ok((mut result, true)) if result.status == BootstrapStatus::Completed => {
+            docs_index::build_docs_index(app_handle.clone(), root_path.clone(), None)
+                .await
+                .map(|docs| {
+                    result.docs = Some(docs);
+                    result
+                })
+        }
+        other => other.map(|(result, _)| result),
+    };
+    let outcome = match &result {
+        Ok(result) if result.status == BootstrapStatus::Cancelled => ActivityOutcome::Cancelled,
+        _ => ActivityOutcome::from_result(&result),
+    };
+    activity::activity_finish(&activity_id, outcome);
+    if let Ok(ref summary) = result {
+        log::info!(
+            "Bootstrapped index of {}: {} files ({} reused, {} indexed, {} removed, {} skipped), {} definitions in {}ms",
+            root_path,
+            summary.total_files,
+            summary.reused_files,
+            summary.indexed_files,
+            summary.removed_files,
+            summary.skipped_files,
+            summary.definition_count,
+            summary.elapsed_ms
+        );
+    }
+    result
 }
 
 // ============================================================================
@@ -1693,6 +4240,7 @@ fn limit_text(text: &str, max_lines: usize) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_support::git;
 
     #[test]
     fn test_new_service_has_languages() {
@@ -1729,6 +4277,91 @@ mod tests {
         assert!(service.queries.contains_key("go"));
     }
 
+    #[test]
+    fn test_bundled_languages_are_healthy() {
+        let service = CodeNavigationService::new();
+        let health = service.language_health();
+        assert_eq!(health.len(), 8);
+        assert!(health.iter().all(|h| h.status == LanguageStatus::Ok));
+        assert!(service.degraded_languages().is_empty());
+    }
+
+    /// A grammar bump that breaks the definition query, simulated with an invalid query
+    fn service_with_broken_go() -> CodeNavigationService {
+        let mut service = CodeNavigationService::new();
+        service.register_language_with_query(
+            "go",
+            tree_sitter_go::LANGUAGE.into(),
+            "(no_such_node name: (identifier) @function.definition)",
+        );
+        service
+    }
+
+    #[test]
+    fn test_invalid_query_marks_language_degraded() {
+        let mut service = service_with_broken_go();
+
+        let degraded = service.degraded_languages();
+        assert_eq!(degraded.len(), 1);
+        assert_eq!(degraded[0].lang_id, "go");
+        assert!(matches!(
+            degraded[0].status,
+            LanguageStatus::QueryError { .. }
+        ));
+
+        let json = serde_json::to_value(&degraded[0]).unwrap();
+        assert_eq!(json["lang_id"], "go");
+        assert_eq!(json["status"], "query_error");
+        assert!(json["message"].is_string());
+
+        let err = service
+            .index_file("main.go", "package main\nfunc main() {}", "go")
+            .unwrap_err();
+        assert!(
+            matches!(err, IndexError::LanguageUnavailable { ref lang_id, .. } if lang_id == "go")
+        );
+        assert!(err.to_string().contains("go"));
+
+        // Other languages are unaffected, and unsupported ones are still a no-op
+        service
+            .index_file("main.py", "def main(): pass", "python")
+            .unwrap();
+        assert_eq!(service.find_definition("main", "python").len(), 1);
+        service.index_file("main.cob", "", "cobol").unwrap();
+    }
+
+    #[test]
+    fn test_batch_index_counts_failures_per_language() {
+        let nav = RwLock::new(service_with_broken_go());
+        let files = vec![
+            (
+                "a.go".to_string(),
+                "package a\nfunc A() {}".to_string(),
+                "go".to_string(),
+            ),
+            (
+                "b.go".to_string(),
+                "package b\nfunc B() {}".to_string(),
+                "go".to_string(),
+            ),
+            (
+                "c.py".to_string(),
+                "def c(): pass".to_string(),
+                "python".to_string(),
+            ),
+        ];
+
+        let result = index_files_batch(&nav, &files).unwrap();
+        assert_eq!(result.indexed_files, 1);
+        assert_eq!(result.definitions, 1);
+        assert_eq!(result.failures.get("go"), Some(&2));
+        assert_eq!(result.failures.len(), 1);
+
+        let service = nav.read().unwrap();
+        assert!(service.find_definition("A", "go").is_empty());
+        assert_eq!(service.find_definition("c", "python").len(), 1);
+    }
+
     #[test]
     fn test_get_lang_family() {
         assert_eq!(CodeNavigationService::get_lang_family("c"), "c_family");
@@ -1857,6 +4490,36 @@ mod tests {
         assert_eq!(CodeNavigationService::get_symbol_kind("unknown"), "symbol");
     }
 
+    /// Check both column encodings of a definition against the line it was found on
+    fn assert_columns(symbol: &SymbolInfo, line: &str) {
+        let start = line.find(symbol.name.as_str()).unwrap();
+        let end = start + symbol.name.len();
+        assert_eq!(symbol.start_column as usize, start + 1);
+        assert_eq!(symbol.end_column as usize, end + 1);
+        assert_eq!(
+            symbol.start_column_utf16 as usize,
+            line[..start].encode_utf16().count() + 1
+        );
+        assert_eq!(
+            symbol.end_column_utf16 as usize,
+            line[..end].encode_utf16().count() + 1
+        );
+    }
+
+    #[test]
+    fn test_utf16_column_map() {
+        let source = "ab\n日本😀x\n".as_bytes();
+        let mut map = Utf16ColumnMap::new(source);
+        assert_eq!(map.column(Point::new(0, 1)), 2);
+        // 日 and 本 are 3 bytes / 1 unit each, 😀 is 4 bytes / 2 units
+        assert_eq!(map.column(Point::new(1, 3)), 2);
+        assert_eq!(map.column(Point::new(1, 6)), 3);
+        assert_eq!(map.column(Point::new(1, 10)), 5);
+        assert_eq!(map.column(Point::new(1, 11)), 6);
+        // Past the last line
+        assert_eq!(map.column(Point::new(5, 0)), 1);
+    }
+
     #[test]
     fn test_index_python_file() {
         let mut service = CodeNavigationService::new();
@@ -1870,7 +4533,9 @@ class MyClass:
         pass
 "#;
 
-        service.index_file("test.py", python_code, "python");
+        service
+            .index_file("test.py", python_code, "python")
+            .unwrap();
 
         // Check that function definition was indexed
         let func_defs = service.find_definition("my_function", "python");
@@ -1884,6 +4549,17 @@ class MyClass:
         assert!(!class_defs.is_empty(), "Should find MyClass definition");
         assert_eq!(class_defs[0].name, "MyClass");
         assert_eq!(class_defs[0].kind, "class");
+
+        // Multibyte identifier: end columns differ between encodings
+        let line = "def größe_ändern(): pass";
+        service.index_file("unicode.py", line, "python").unwrap();
+        let defs = service.find_definition("größe_ändern", "python");
+        assert_columns(&defs[0], line);
+        assert_eq!((defs[0].start_column, defs[0].end_column), (5, 20));
+        assert_eq!(
+            (defs[0].start_column_utf16, defs[0].end_column_utf16),
+            (5, 17)
+        );
     }
 
     #[test]
@@ -1913,7 +4589,7 @@ const MY_CONST: i32 = 42;
 type MyType = Vec<i32>;
 "#;
 
-        service.index_file("test.rs", rust_code, "rust");
+        service.index_file("test.rs", rust_code, "rust").unwrap();
 
         // Check function
         let func_defs = service.find_definition("my_function", "rust");
@@ -1944,6 +4620,14 @@ type MyType = Vec<i32>;
         let type_defs = service.find_definition("MyType", "rust");
         assert!(!type_defs.is_empty(), "Should find MyType");
         assert_eq!(type_defs[0].kind, "type");
+
+        // Multibyte prefix: start columns differ between encodings
+        let line = "/* 日本語 😀 */ fn after_cjk() {}";
+        service.index_file("unicode.rs", line, "rust").unwrap();
+        let defs = service.find_definition("after_cjk", "rust");
+        assert_columns(&defs[0], line);
+        assert_eq!(defs[0].start_column, 25);
+        assert_eq!(defs[0].start_column_utf16, 17);
     }
 
     #[test]
@@ -1975,7 +4659,9 @@ enum MyEnum {
 const MY_CONST = 42;
 "#;
 
-        service.index_file("test.ts", ts_code, "typescript");
+        service
+            .index_file("test.ts", ts_code, "typescript")
+            .unwrap();
 
         // Check function
         let func_defs = service.find_definition("myFunction", "js_family");
@@ -2001,6 +4687,13 @@ const MY_CONST = 42;
         let enum_defs = service.find_definition("MyEnum", "js_family");
         assert!(!enum_defs.is_empty(), "Should find MyEnum");
         assert_eq!(enum_defs[0].kind, "enum");
+
+        let line = "/* 日本語 😀 */ function afterCjk() {}";
+        service
+            .index_file("unicode.ts", line, "typescript")
+            .unwrap();
+        let defs = service.find_definition("afterCjk", "js_family");
+        assert_columns(&defs[0], line);
     }
 
     #[test]
@@ -2021,7 +4714,7 @@ type MyStruct struct {
 func (m *MyStruct) Method() {}
 "#;
 
-        service.index_file("test.go", go_code, "go");
+        service.index_file("test.go", go_code, "go").unwrap();
 
         // Check function
         let func_defs = service.find_definition("myFunction", "go");
@@ -2037,6 +4730,13 @@ func (m *MyStruct) Method() {}
         let method_defs = service.find_definition("Method", "go");
         assert!(!method_defs.is_empty(), "Should find Method");
         assert_eq!(method_defs[0].kind, "method");
+
+        let line = "/* 日本語 😀 */ func afterCjk() {}";
+        service
+            .index_file("unicode.go", &format!("package main\n{}", line), "go")
+            .unwrap();
+        let defs = service.find_definition("afterCjk", "go");
+        assert_columns(&defs[0], line);
     }
 
     #[test]
@@ -2047,8 +4747,10 @@ func (m *MyStruct) Method() {}
         let python_code = "def test_func(): pass";
         let rust_code = "fn test_func() {}";
 
-        service.index_file("test.py", python_code, "python");
-        service.index_file("test.rs", rust_code, "rust");
+        service
+            .index_file("test.py", python_code, "python")
+            .unwrap();
+        service.index_file("test.rs", rust_code, "rust").unwrap();
 
         // Should only find Python definition
         let python_defs = service.find_definition("test_func", "python");
@@ -2065,12 +4767,171 @@ func (m *MyStruct) Method() {}
         assert!(js_defs.is_empty());
     }
 
+    #[test]
+    fn test_find_definition_ranked_by_proximity() {
+        let mut service = CodeNavigationService::new();
+        service
+            .index_file("/repo/a/util.py", "def helper(): pass", "python")
+            .unwrap();
+        service
+            .index_file("/repo/b/deep/util.py", "def helper(): pass", "python")
+            .unwrap();
+        service
+            .index_file("/repo/b/main.py", "def helper(): pass", "python")
+            .unwrap();
+
+        let ranked = service.find_definition_ranked("helper", "python", Some("/repo/b/main.py"));
+        let files: Vec<&str> = ranked.iter().map(|s| s.file_path.as_str()).collect();
+        assert_eq!(
+            files,
+            vec!["/repo/b/main.py", "/repo/b/deep/util.py", "/repo/a/util.py"]
+        );
+
+        let ranked = service.find_definition_ranked("helper", "python", Some("/repo/a/other.py"));
+        assert_eq!(ranked[0].file_path, "/repo/a/util.py");
+    }
+
+    #[test]
+    fn test_package_boundaries_in_monorepo() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::write(root.join("package.json"), r#"{"private": true}"#).unwrap();
+        for package in ["ui", "admin"] {
+            fs::create_dir_all(root.join("packages").join(package).join("src")).unwrap();
+            fs::write(
+                root.join("packages").join(package).join("package.json"),
+                format!(r#"{{"name": "{}"}}"#, package),
+            )
+            .unwrap();
+        }
+        let path = |rel: &str| root.join(rel).to_string_lossy().to_string();
+        let ui_button = path("packages/ui/src/Button.tsx");
+        let ui_app = path("packages/ui/src/App.tsx");
+        let admin_button = path("packages/admin/src/Button.tsx");
+        let admin_page = path("packages/admin/src/pages/Page.tsx");
+
+        let mut service = CodeNavigationService::new();
+        // Index the other package's Button first so index order alone would pick it
+        service
+            .index_file(&admin_button, "export function Button() {}", "typescript")
+            .unwrap();
+        service
+            .index_file(&ui_button, "export function Button() {}", "typescript")
+            .unwrap();
+        service
+            .index_file(&ui_app, "export function App() {}", "typescript")
+            .unwrap();
+
+        let ranked = service.find_definition_ranked("Button", "js_family", Some(&ui_app));
+        assert_eq!(ranked[0].file_path, ui_button);
+        assert!(ranked.iter().any(|s| s.file_path == admin_button));
+
+        // Not yet indexed: the package is resolved from disk
+        let ranked = service.find_definition_ranked("Button", "js_family", Some(&admin_page));
+        assert_eq!(ranked[0].file_path, admin_button);
+
+        let scoped = service.find_definition_scoped("Button", "js_family", Some(&ui_app), true);
+        assert!(!scoped.is_empty());
+        assert!(scoped.iter().all(|s| s.file_path == ui_button));
+
+        let stats = service.get_stats();
+        assert_eq!(stats.total_files, 3);
+        let ui_stats = stats
+            .packages
+            .iter()
+            .find(|p| p.package_id.as_deref() == Some(path("packages/ui").as_str()))
+            .unwrap();
+        assert_eq!(ui_stats.files, 2);
+        assert_eq!(stats.packages.len(), 2);
+        assert_eq!(stats.packages[0].package_id, ui_stats.package_id);
+        assert_eq!(
+            stats.packages.iter().map(|p| p.symbols).sum::<usize>(),
+            stats.total_symbols
+        );
+    }
+
+    #[test]
+    fn test_package_resolver_caches_directories() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let crate_dir = temp_dir.path().join("crates").join("core");
+        fs::create_dir_all(crate_dir.join("src").join("nested")).unwrap();
+        fs::write(crate_dir.join("Cargo.toml"), "[package]\nname = \"core\"").unwrap();
+
+        let mut resolver = PackageResolver::default();
+        let file = crate_dir.join("src").join("nested").join("mod.rs");
+        let expected = Some(crate_dir.to_string_lossy().to_string());
+        assert_eq!(resolver.package_for_file(&file.to_string_lossy()), expected);
+        assert_eq!(
+            resolver.dir_packages.get(&crate_dir.join("src")),
+            Some(&expected)
+        );
+
+        // Answers come from the cache even once the manifest is gone
+        fs::remove_file(crate_dir.join("Cargo.toml")).unwrap();
+        let sibling = crate_dir.join("src").join("lib.rs");
+        assert_eq!(
+            resolver.package_for_file(&sibling.to_string_lossy()),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_find_definitions_bulk_matches_single_lookups() {
+        let mut service = CodeNavigationService::new();
+        service
+            .index_file(
+                "/repo/src/a.py",
+                "def alpha(): pass\ndef beta(): pass\nclass Gamma: pass",
+                "python",
+            )
+            .unwrap();
+        service
+            .index_file("/repo/lib/b.py", "def alpha(): pass", "python")
+            .unwrap();
+        service
+            .index_file("/repo/src/c.rs", "fn beta() {}", "rust")
+            .unwrap();
+
+        let mut names: Vec<String> = ["alpha", "beta", "Gamma", "missing", "alpha"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        // Thousands of names in one call, mostly misses
+        names.extend((0..5000).map(|i| format!("unknown_{}", i)));
+
+        let context = Some("/repo/lib/b.py");
+        let bulk = service.find_definitions_bulk(&names, "python", context, false);
+        assert_eq!(bulk.len(), 5004);
+        for name in &names {
+            let single = service.find_definition_ranked(name, "python", context);
+            let from_bulk = &bulk[name];
+            assert_eq!(from_bulk.len(), single.len(), "mismatch for {}", name);
+            for (a, b) in from_bulk.iter().zip(single.iter()) {
+                assert_eq!(a.file_path, b.file_path);
+                assert_eq!(a.start_line, b.start_line);
+                assert_eq!(a.kind, b.kind);
+            }
+        }
+        assert_eq!(bulk["alpha"][0].file_path, "/repo/lib/b.py");
+        assert!(bulk["missing"].is_empty());
+
+        let has = service.has_definitions(&names, "python");
+        assert_eq!(&has[..5], &[true, true, true, false, true]);
+        assert!(has[5..].iter().all(|h| !h));
+        for (name, has_definition) in names.iter().zip(has.iter()) {
+            assert_eq!(
+                *has_definition,
+                !service.find_definition(name, "python").is_empty()
+            );
+        }
+    }
+
     #[test]
     fn test_clear_file() {
         let mut service = CodeNavigationService::new();
 
         let code = "def my_function(): pass";
-        service.index_file("test.py", code, "python");
+        service.index_file("test.py", code, "python").unwrap();
 
         // Verify it was indexed
         let defs = service.find_definition("my_function", "python");
@@ -2088,8 +4949,12 @@ func (m *MyStruct) Method() {}
     fn test_clear_all() {
         let mut service = CodeNavigationService::new();
 
-        service.index_file("test1.py", "def func1(): pass", "python");
-        service.index_file("test2.py", "def func2(): pass", "python");
+        service
+            .index_file("test1.py", "def func1(): pass", "python")
+            .unwrap();
+        service
+            .index_file("test2.py", "def func2(): pass", "python")
+            .unwrap();
 
         // Verify both were indexed
         assert!(!service.find_definition("func1", "python").is_empty());
@@ -2108,12 +4973,16 @@ func (m *MyStruct) Method() {}
         let mut service = CodeNavigationService::new();
 
         // Index initial version
-        service.index_file("test.py", "def old_func(): pass", "python");
+        service
+            .index_file("test.py", "def old_func(): pass", "python")
+            .unwrap();
         assert!(!service.find_definition("old_func", "python").is_empty());
         assert!(service.find_definition("new_func", "python").is_empty());
 
         // Reindex with new content
-        service.index_file("test.py", "def new_func(): pass", "python");
+        service
+            .index_file("test.py", "def new_func(): pass", "python")
+            .unwrap();
 
         // Old symbol should be gone, new should exist
         assert!(service.find_definition("old_func", "python").is_empty());
@@ -2132,7 +5001,7 @@ def func_line_5():
     pass
 "#;
 
-        service.index_file("test.py", code, "python");
+        service.index_file("test.py", code, "python").unwrap();
 
         let defs = service.find_definition("func_line_2", "python");
         assert!(!defs.is_empty());
@@ -2154,6 +5023,11 @@ def func_line_5():
             start_column: 5,
             end_line: 10,
             end_column: 14,
+            start_column_utf16: 5,
+            end_column_utf16: 14,
+            exported: false,
+            dependency: false,
+            visibility: Visibility::Unknown,
         };
 
         let json = serde_json::to_string(&symbol).unwrap();
@@ -2182,7 +5056,7 @@ struct MyStruct {
 };
 "#;
 
-        service.index_file("test.c", c_code, "c");
+        service.index_file("test.c", c_code, "c").unwrap();
 
         // Check function
         let func_defs = service.find_definition("my_function", "c_family");
@@ -2193,6 +5067,11 @@ struct MyStruct {
         let struct_defs = service.find_definition("MyStruct", "c_family");
         assert!(!struct_defs.is_empty(), "Should find MyStruct in C");
         assert_eq!(struct_defs[0].kind, "struct");
+
+        let line = "/* 日本語 😀 */ void after_cjk() {}";
+        service.index_file("unicode.c", line, "c").unwrap();
+        let defs = service.find_definition("after_cjk", "c_family");
+        assert_columns(&defs[0], line);
     }
 
     #[test]
@@ -2211,7 +5090,7 @@ interface MyInterface {
 }
 "#;
 
-        service.index_file("Test.java", java_code, "java");
+        service.index_file("Test.java", java_code, "java").unwrap();
 
         // Check class
         let class_defs = service.find_definition("MyClass", "java");
@@ -2230,23 +5109,576 @@ interface MyInterface {
             "Should find MyInterface in Java"
         );
         assert_eq!(interface_defs[0].kind, "interface");
+
+        let line = "    /* 日本語 😀 */ void afterCjk() {}";
+        service
+            .index_file("Unicode.java", &format!("class U {{\n{}\n}}", line), "java")
+            .unwrap();
+        let defs = service.find_definition("afterCjk", "java");
+        assert_columns(&defs[0], line);
     }
 
     #[test]
     fn test_file_definitions_reverse_index() {
         let mut service = CodeNavigationService::new();
 
-        service.index_file("test.py", "def func1(): pass\ndef func2(): pass", "python");
+        service
+            .index_file("test.py", "def func1(): pass\ndef func2(): pass", "python")
+            .unwrap();
 
         // Check reverse index has the file
-        assert!(service.index.file_definitions.contains_key("test.py"));
+        let names = service.index.names_in_file("test.py");
+        assert!(names.is_some());
 
         // Check it contains both function names
-        let names = service.index.file_definitions.get("test.py").unwrap();
+        let names = names.unwrap();
         assert!(names.contains("func1"));
         assert!(names.contains("func2"));
     }
 
+    #[test]
+    fn test_lookups_run_during_batch_merge() {
+        let files: Vec<(String, String, String)> = (0..100)
+            .map(|i| {
+                let content: String = (0..20)
+                    .map(|j| format!("fn sym_{}_{}() {{}}\nstruct Shared{} {{}}\n", i, j, j))
+                    .collect();
+                (format!("/repo/src/f{}.rs", i), content, "rust".to_string())
+            })
+            .collect();
+
+        // Single-threaded reference run
+        let mut reference = CodeNavigationService::new();
+        for (file_path, content, lang_id) in &files {
+            reference.index_file(file_path, content, lang_id).unwrap();
+        }
+
+        let nav = std::sync::Arc::new(RwLock::new(CodeNavigationService::new()));
+        let done = std::sync::Arc::new(AtomicBool::new(false));
+        let readers: Vec<_> = (0..4)
+            .map(|r| {
+                let nav = nav.clone();
+                let done = done.clone();
+                std::thread::spawn(move || {
+                    let mut slowest = std::time::Duration::ZERO;
+                    let mut lookups = 0;
+                    while !done.load(Ordering::Relaxed) {
+                        let start = Instant::now();
+                        let service = nav.read().unwrap();
+                        service.find_definition(&format!("sym_{}_1", lookups % 100), "rust");
+                        service.find_definition(&format!("Shared{}", r), "rust");
+                        drop(service);
+                        slowest = slowest.max(start.elapsed());
+                        lookups += 1;
+                    }
+                    (slowest, lookups)
+                })
+            })
+            .collect();
+
+        let result = index_files_batch(&nav, &files).unwrap();
+        done.store(true, Ordering::Relaxed);
+        assert_eq!(result.indexed_files, 100);
+        assert_eq!(result.definitions, 4000);
+        for reader in readers {
+            let (slowest, lookups) = reader.join().unwrap();
+            assert!(lookups > 0);
+            assert!(
+                slowest < std::time::Duration::from_millis(100),
+                "lookup blocked for {:?}",
+                slowest
+            );
+        }
+
+        let service = nav.read().unwrap();
+        let stats = service.get_stats();
+        let expected = reference.get_stats();
+        assert_eq!(stats.total_files, expected.total_files);
+        assert_eq!(stats.total_symbols, expected.total_symbols);
+        let names = (0..100)
+            .flat_map(|i| (0..20).map(move |j| format!("sym_{}_{}", i, j)))
+            .chain((0..20).map(|j| format!("Shared{}", j)));
+        for name in names {
+            assert_eq!(
+                serde_json::to_value(service.find_definition(&name, "rust")).unwrap(),
+                serde_json::to_value(reference.find_definition(&name, "rust")).unwrap(),
+                "{}",
+                name
+            );
+        }
+
+        // Clearing keeps the reverse index and the shards in step
+        drop(service);
+        nav.write().unwrap().clear_file("/repo/src/f0.rs");
+        let service = nav.read().unwrap();
+        assert!(service.find_definition("sym_0_0", "rust").is_empty());
+        assert_eq!(service.find_definition("Shared0", "rust").len(), 99);
+        assert_eq!(service.get_stats().total_symbols, 4000 - 40);
+    }
+
+    /// A repository with a tracked source file, an ignored generated directory and an
+    /// untracked new source file
+    fn create_indexable_repo() -> tempfile::TempDir {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        git(root, &["init", "-q"]);
+        git(root, &["config", "user.email", "test@example.com"]);
+        git(root, &["config", "user.name", "Test"]);
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::create_dir_all(root.join("generated/lib")).unwrap();
+        fs::write(root.join(".gitignore"), "generated/\n").unwrap();
+        fs::write(root.join("src/main.py"), "def main():\n    pass\n").unwrap();
+        fs::write(root.join("README.md"), "# readme\n").unwrap();
+        git(root, &["add", "."]);
+        git(root, &["commit", "-q", "-m", "init"]);
+        fs::write(root.join("generated/lib/dep.py"), "def dep():\n    pass\n").unwrap();
+        fs::write(root.join("src/new.rs"), "fn new() {}\n").unwrap();
+        temp_dir
+    }
+
+    fn relative_paths(root: &Path, files: &[IndexableFile]) -> Vec<String> {
+        let root = root.canonicalize().unwrap();
+        files
+            .iter()
+            .map(|f| {
+                Path::new(&f.path)
+                    .strip_prefix(&root)
+                    .unwrap()
+                    .to_string_lossy()
+                    .replace('\\', "/")
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_list_indexable_files_from_git() {
+        let temp_dir = create_indexable_repo();
+        let root = temp_dir.path().canonicalize().unwrap();
+        let root_str = root.to_string_lossy().to_string();
+
+        let tracked = list_indexable_files(&root_str, true, false).unwrap();
+        assert_eq!(relative_paths(&root, &tracked), ["src/main.py"]);
+        assert_eq!(tracked[0].lang_id, "python");
+        assert!(tracked[0].modified_time > 0);
+
+        let with_untracked = list_indexable_files(&root_str, true, true).unwrap();
+        assert_eq!(
+            relative_paths(&root, &with_untracked),
+            ["src/main.py", "src/new.rs"]
+        );
+
+        // Without gitignore, the walk also finds the ignored file
+        let walked = list_indexable_files(&root_str, false, false).unwrap();
+        assert_eq!(
+            relative_paths(&root, &walked),
+            ["generated/lib/dep.py", "src/main.py", "src/new.rs"]
+        );
+    }
+
+    #[test]
+    fn test_list_indexable_files_in_subdirectory_and_non_repo() {
+        let temp_dir = create_indexable_repo();
+        let root = temp_dir.path().canonicalize().unwrap();
+        let src = root.join("src");
+        let files = list_indexable_files(&src.to_string_lossy(), true, true).unwrap();
+        assert_eq!(relative_paths(&src, &files), ["main.py", "new.rs"]);
+
+        let plain = tempfile::TempDir::new().unwrap();
+        fs::create_dir_all(plain.path().join("node_modules/pkg")).unwrap();
+        fs::write(plain.path().join("node_modules/pkg/index.js"), "x").unwrap();
+        fs::write(plain.path().join("app.ts"), "export const a = 1;").unwrap();
+        let plain_root = plain.path().canonicalize().unwrap();
+        let files = list_indexable_files(&plain_root.to_string_lossy(), true, false).unwrap();
+        assert_eq!(relative_paths(&plain_root, &files), ["app.ts"]);
+    }
+
+    /// A project outside git with one file per language and a file over the size limit
+    fn create_bootstrap_project() -> tempfile::TempDir {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let files = [
+            ("src/a.rs", "pub fn alpha() {}\nstruct Beta;\n"),
+            (
+                "src/b.py",
+                "def gamma():\n    pass\n\nclass Delta:\n    pass\n",
+            ),
+            ("web/c.ts", "export function epsilon() {}\nclass Zeta {}\n"),
+            ("web/d.js", "function eta() {}\n"),
+            ("go/e.go", "package e\n\nfunc Theta() {}\n"),
+            ("java/F.java", "public class F {\n    void iota() {}\n}\n"),
+        ];
+        for (path, content) in files {
+            fs::create_dir_all(root.join(path).parent().unwrap()).unwrap();
+            fs::write(root.join(path), content).unwrap();
+        }
+        fs::write(root.join("src/big.rs"), "fn huge() {}\n".repeat(1000)).unwrap();
+        temp_dir
+    }
+
+    fn bootstrap_options() -> BootstrapOptions {
+        BootstrapOptions {
+            max_file_size: 4096,
+            ..Default::default()
+        }
+    }
+
+    /// Definitions per name, in a stable order, and the reverse index
+    fn exported_index(
+        nav: &RwLock<CodeNavigationService>,
+    ) -> (
+        std::collections::BTreeMap<String, Vec<serde_json::Value>>,
+        HashMap<String, HashSet<String>>,
+    ) {
+        let (definitions, file_definitions) = nav.read().unwrap().index.export();
+        let definitions = definitions
+            .into_iter()
+            .map(|(name, symbols)| {
+                let mut symbols: Vec<_> = symbols
+                    .iter()
+                    .map(|s| serde_json::to_value(s).unwrap())
+                    .collect();
+                symbols.sort_by_key(|s| s.to_string());
+                (name, symbols)
+            })
+            .collect();
+        (definitions, file_definitions)
+    }
+
+    /// The index the frontend built by reading every file and calling index_files_batch
+    fn batch_indexed(root_path: &str, max_file_size: u64) -> RwLock<CodeNavigationService> {
+        let files: Vec<(String, String, String)> = list_indexable_files(root_path, true, true)
+            .unwrap()
+            .into_iter()
+            .filter(|f| fs::metadata(&f.path).unwrap().len() <= max_file_size)
+            .map(|f| {
+                (
+                    f.path.clone(),
+                    fs::read_to_string(&f.path).unwrap(),
+                    f.lang_id,
+                )
+            })
+            .collect();
+        let nav = RwLock::new(CodeNavigationService::new());
+        index_files_batch(&nav, &files).unwrap();
+        nav
+    }
+
+    fn run_bootstrap(
+        index_dir: &Path,
+        root_path: &str,
+        chunk_files: usize,
+        cancel_after: Option<u64>,
+    ) -> (RwLock<CodeNavigationService>, BootstrapResult) {
+        let nav = RwLock::new(CodeNavigationService::new());
+        let control = PersistControl::new(root_path, None);
+        let result = bootstrap_index(
+            &nav,
+            index_dir,
+            root_path,
+            &bootstrap_options(),
+            chunk_files,
+            &control,
+            &|done, _, _| {
+                if cancel_after.is_some_and(|limit| done >= limit) {
+                    control.cancel();
+                }
+            },
+        )
+        .unwrap();
+        (nav, result)
+    }
+
+    #[test]
+    fn test_bootstrap_matches_batch_index() {
+        let project = create_bootstrap_project();
+        let root_path = project.path().canonicalize().unwrap();
+        let root_path = root_path.to_string_lossy();
+        let index_dir = tempfile::TempDir::new().unwrap();
+
+        let (nav, result) = run_bootstrap(index_dir.path(), &root_path, 4, None);
+        assert_eq!(result.status, BootstrapStatus::Completed);
+        assert_eq!(result.total_files, 7);
+        assert_eq!(result.indexed_files, 6);
+        assert_eq!(result.skipped_files, 1);
+        assert_eq!(result.reused_files, 0);
+        assert!(result.failures.is_empty());
+        assert!(nav
+            .read()
+            .unwrap()
+            .find_definition("huge", "rust")
+            .is_empty());
+
+        let expected = batch_indexed(&root_path, 4096);
+        assert_eq!(exported_index(&nav), exported_index(&expected));
+        assert_eq!(
+            result.definition_count,
+            expected.read().unwrap().index.symbol_count()
+        );
+
+        // The index is saved and the shards are gone
+        let index_path = index_dir
+            .path()
+            .join(format!("{}.json", get_project_hash(&root_path)));
+        let persisted: PersistedIndex =
+            serde_json::from_str(&fs::read_to_string(index_path).unwrap()).unwrap();
+        assert_eq!(persisted.file_timestamps.len(), 6);
+        assert!(!bootstrap_dir(index_dir.path(), &root_path).exists());
+    }
+
+    #[test]
+    fn test_bootstrap_resumes_from_shards_and_saved_index() {
+        let project = create_bootstrap_project();
+        let root = project.path().canonicalize().unwrap();
+        let root_path = root.to_string_lossy();
+        let index_dir = tempfile::TempDir::new().unwrap();
+
+        // Interrupted after the first shard of two files
+        let (_, first) = run_bootstrap(index_dir.path(), &root_path, 2, Some(2));
+        assert_eq!(first.status, BootstrapStatus::Cancelled);
+        assert_eq!(first.indexed_files, 2);
+        let shard_dir = bootstrap_dir(index_dir.path(), &root_path);
+        let manifest = read_bootstrap_manifest(&shard_dir, &root_path).unwrap();
+        assert_eq!(manifest.shards.len(), 1);
+        assert_eq!(manifest.file_timestamps.len(), 2);
+
+        // A fresh start picks the shard up and parses only the rest
+        let (nav, second) = run_bootstrap(index_dir.path(), &root_path, 2, None);
+        assert_eq!(second.status, BootstrapStatus::Completed);
+        assert_eq!(second.resumed_shards, 1);
+        assert_eq!(second.reused_files, 2);
+        assert_eq!(second.indexed_files, 4);
+        assert!(!shard_dir.exists());
+        assert_eq!(
+            exported_index(&nav),
+            exported_index(&batch_indexed(&root_path, 4096))
+        );
+
+        // After a change and a deletion, the saved index supplies everything else
+        let changed = root.join("src/b.py");
+        fs::write(&changed, "def renamed():\n    pass\n").unwrap();
+        let later = std::time::SystemTime::now() + Duration::from_secs(10);
+        fs::File::options()
+            .write(true)
+            .open(&changed)
+            .unwrap()
+            .set_modified(later)
+            .unwrap();
+        fs::remove_file(root.join("web/d.js")).unwrap();
+
+        let (nav, third) = run_bootstrap(index_dir.path(), &root_path, 2, None);
+        assert_eq!(third.resumed_shards, 0);
+        assert_eq!(third.removed_files, 1);
+        assert_eq!(third.indexed_files, 1);
+        assert_eq!(third.reused_files, 4);
+        let service = nav.read().unwrap();
+        assert!(service.find_definition("gamma", "python").is_empty());
+        assert_eq!(service.find_definition("renamed", "python").len(), 1);
+        assert!(service.find_definition("eta", "js_family").is_empty());
+        drop(service);
+        assert_eq!(
+            exported_index(&nav),
+            exported_index(&batch_indexed(&root_path, 4096))
+        );
+    }
+
+    fn visibilities(file_path: &str, content: &str, lang_id: &str) -> Vec<(String, Visibility)> {
+        CodeNavigationService::new()
+            .definitions_in(file_path, content, lang_id)
+            .unwrap()
+            .into_iter()
+            .map(|d| (d.name, d.visibility))
+            .collect()
+    }
+
+    fn visibility_of(definitions: &[(String, Visibility)], name: &str) -> Visibility {
+        definitions
+            .iter()
+            .find(|(n, _)| n == name)
+            .unwrap_or_else(|| panic!("no definition of {}", name))
+            .1
+    }
+
+    #[test]
+    fn test_rust_visibility() {
+        let defs = visibilities(
+            "lib.rs",
+            "pub fn api() {}\npub(crate) struct Shared;\npub(super) enum Up {}\npub(in crate::a) const LIMIT: u32 = 1;\nfn helper() {}\nstruct Local;\nimpl Local {\n    pub fn method(&self) {}\n}\n",
+            "rust",
+        );
+        assert_eq!(visibility_of(&defs, "api"), Visibility::Public);
+        assert_eq!(visibility_of(&defs, "Shared"), Visibility::Internal);
+        assert_eq!(visibility_of(&defs, "Up"), Visibility::Internal);
+        assert_eq!(visibility_of(&defs, "LIMIT"), Visibility::Internal);
+        assert_eq!(visibility_of(&defs, "helper"), Visibility::Private);
+        assert_eq!(visibility_of(&defs, "Local"), Visibility::Private);
+        assert_eq!(visibility_of(&defs, "method"), Visibility::Public);
+    }
+
+    #[test]
+    fn test_typescript_visibility() {
+        let defs = visibilities(
+            "api.ts",
+            "export function api() {}\nfunction helper() {}\nexport class Service {\n  run() {}\n  private secret() {}\n  protected hook() {}\n  #hidden() {}\n}\nconst local = 1;\nexport const shared = 2;\n",
+            "typescript",
+        );
+        assert_eq!(visibility_of(&defs, "api"), Visibility::Public);
+        assert_eq!(visibility_of(&defs, "helper"), Visibility::Private);
+        assert_eq!(visibility_of(&defs, "Service"), Visibility::Public);
+        assert_eq!(visibility_of(&defs, "run"), Visibility::Public);
+        assert_eq!(visibility_of(&defs, "secret"), Visibility::Private);
+        assert_eq!(visibility_of(&defs, "hook"), Visibility::Protected);
+        assert_eq!(visibility_of(&defs, "#hidden"), Visibility::Private);
+        assert_eq!(visibility_of(&defs, "local"), Visibility::Private);
+        assert_eq!(visibility_of(&defs, "shared"), Visibility::Public);
+    }
+
+    #[test]
+    fn test_python_visibility() {
+        let defs = visibilities(
+            "app.py",
+            "def api():\n    pass\n\ndef _helper():\n    pass\n\nclass Model:\n    def __init__(self):\n        pass\n\n    def __secret(self):\n        pass\n",
+            "python",
+        );
+        assert_eq!(visibility_of(&defs, "api"), Visibility::Public);
+        assert_eq!(visibility_of(&defs, "_helper"), Visibility::Private);
+        assert_eq!(visibility_of(&defs, "Model"), Visibility::Public);
+        assert_eq!(visibility_of(&defs, "__init__"), Visibility::Public);
+        assert_eq!(visibility_of(&defs, "__secret"), Visibility::Private);
+    }
+
+    #[test]
+    fn test_go_visibility() {
+        let defs = visibilities(
+            "server.go",
+            "package server\n\nfunc Serve() {}\nfunc handle() {}\ntype Config struct{}\ntype state struct{}\nfunc (c Config) Load() {}\n",
+            "go",
+        );
+        assert_eq!(visibility_of(&defs, "Serve"), Visibility::Public);
+        assert_eq!(visibility_of(&defs, "handle"), Visibility::Internal);
+        assert_eq!(visibility_of(&defs, "Config"), Visibility::Public);
+        assert_eq!(visibility_of(&defs, "state"), Visibility::Internal);
+        assert_eq!(visibility_of(&defs, "Load"), Visibility::Public);
+    }
+
+    #[test]
+    fn test_java_visibility() {
+        let defs = visibilities(
+            "Service.java",
+            "public class Service {\n    public void run() {}\n    protected void hook() {}\n    private void secret() {}\n    void local() {}\n    @Override\n    public String toString() { return \"\"; }\n}\nclass Helper {}\n",
+            "java",
+        );
+        assert_eq!(visibility_of(&defs, "Service"), Visibility::Public);
+        assert_eq!(visibility_of(&defs, "run"), Visibility::Public);
+        assert_eq!(visibility_of(&defs, "hook"), Visibility::Protected);
+        assert_eq!(visibility_of(&defs, "secret"), Visibility::Private);
+        assert_eq!(visibility_of(&defs, "local"), Visibility::Internal);
+        assert_eq!(visibility_of(&defs, "toString"), Visibility::Public);
+        assert_eq!(visibility_of(&defs, "Helper"), Visibility::Internal);
+    }
+
+    #[test]
+    fn test_visibility_filter() {
+        let mut service = CodeNavigationService::new();
+        service
+            .index_file("a.rs", "pub fn shared() {}\n", "rust")
+            .unwrap();
+        service
+            .index_file("b.rs", "pub(crate) fn shared() {}\n", "rust")
+            .unwrap();
+        service
+            .index_file("c.rs", "fn shared() {}\n", "rust")
+            .unwrap();
+        let files = |filter: Option<VisibilityFilter>| {
+            let mut results = service.find_definition("shared", "rust");
+            retain_visible(&mut results, filter);
+            let mut files: Vec<String> = results.into_iter().map(|s| s.file_path).collect();
+            files.sort();
+            files
+        };
+        assert_eq!(files(None), ["a.rs", "b.rs", "c.rs"]);
+        assert_eq!(files(Some(VisibilityFilter::ExportedOnly)), ["a.rs"]);
+        assert_eq!(files(Some(VisibilityFilter::NonPrivate)), ["a.rs", "b.rs"]);
+    }
+
+    #[test]
+    fn test_previous_index_version_loads_with_unknown_visibility() {
+        assert!(is_compatible_index_version(INDEX_VERSION));
+        assert!(is_compatible_index_version(5));
+        assert!(!is_compatible_index_version(4));
+
+        let json = r#"{"name":"old","kind":"function","file_path":"old.py","lang_family":"python","start_line":1,"start_column":1,"end_line":1,"end_column":4,"exported":true}"#;
+        let symbol: SymbolInfo = serde_json::from_str(json).unwrap();
+        assert_eq!(symbol.visibility, Visibility::Unknown);
+        // Unknown entries fall back to the exported flag
+        assert!(VisibilityFilter::ExportedOnly.accepts(&symbol));
+        assert!(VisibilityFilter::NonPrivate.accepts(&symbol));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_non_utf8_paths_index_and_clear_by_their_own_keys() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path().canonicalize().unwrap();
+        // Lossy conversion would give both files the same name
+        let latin = root.join(OsStr::from_bytes(b"caf\xe9.py"));
+        let other = root.join(OsStr::from_bytes(b"caf\xe8.py"));
+        fs::write(&latin, "def latin_fn():\n    pass\n").unwrap();
+        fs::write(&other, "def other_fn():\n    pass\n").unwrap();
+
+        let files = list_indexable_files(root.to_str().unwrap(), false, false).unwrap();
+        assert_eq!(files.len(), 2);
+        let mut service = CodeNavigationService::new();
+        for file in &files {
+            assert!(file.raw_path.path_is_lossy);
+            let content = fs::read_to_string(path_wire::resolve(&file.path)).unwrap();
+            service
+                .index_file(&file.path, &content, &file.lang_id)
+                .unwrap();
+        }
+
+        let latin_key = path_wire::encode(&latin);
+        let found = service.find_definition("latin_fn", "python");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].file_path, latin_key);
+        assert_eq!(path_wire::resolve(&found[0].file_path), latin);
+
+        service.clear_file(&latin_key);
+        assert!(service.find_definition("latin_fn", "python").is_empty());
+        assert_eq!(service.find_definition("other_fn", "python").len(), 1);
+    }
+
+    #[test]
+    fn test_flush_dirty_writes_only_after_changes() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let index_path = temp_dir.path().join("index.json");
+        let mut service = CodeNavigationService::new();
+
+        // Nothing to flush before the index was ever saved or loaded
+        service
+            .index_file("test.py", "def func1():\n    pass\n", "python")
+            .unwrap();
+        assert!(!service.flush_dirty().unwrap());
+
+        service.mark_persisted(PersistTarget {
+            index_path: index_path.clone(),
+            root_path: "/project".to_string(),
+            file_timestamps: HashMap::new(),
+        });
+        assert!(!service.flush_dirty().unwrap());
+
+        service
+            .index_file("other.py", "def func2():\n    pass\n", "python")
+            .unwrap();
+        assert!(service.flush_dirty().unwrap());
+        let persisted: PersistedIndex =
+            serde_json::from_str(&fs::read_to_string(&index_path).unwrap()).unwrap();
+        assert!(persisted.definitions.contains_key("func2"));
+        assert!(!service.flush_dirty().unwrap());
+    }
+
     #[test]
     fn test_persisted_index_serialization() {
         let mut definitions = HashMap::new();
@@ -2261,6 +5693,11 @@ interface MyInterface {
                 start_column: 1,
                 end_line: 1,
                 end_column: 10,
+                start_column_utf16: 1,
+                end_column_utf16: 10,
+                exported: false,
+                dependency: false,
+                visibility: Visibility::Unknown,
             }],
         );
 
@@ -2586,4 +6023,485 @@ const MaxRetries = 3
             "Should include method name"
         );
     }
+
+    #[test]
+    fn test_find_references_scoped() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let git = |args: &[&str]| crate::test_support::git(root, args);
+        let write = |path: &str, content: &str| {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        };
+
+        git(&["init", "-b", "main"]);
+        git(&["config", "user.email", "test@test.com"]);
+        git(&["config", "user.name", "Test User"]);
+        write(
+            "src/util.ts",
+            "export function helper() {\n  return 1;\n}\n",
+        );
+        write("src/feature/a.ts", "const a = helper();\n");
+        write("src/other/b.ts", "const b = 1;\n");
+        git(&["add", "."]);
+        git(&["commit", "-m", "init"]);
+        git(&["checkout", "-b", "feature"]);
+        write("src/other/b.ts", "const b = helper();\n");
+        git(&["commit", "-am", "use helper"]);
+        write("src/other/c.ts", "const c = helper();\n");
+
+        let service = CodeNavigationService::new();
+        let root_path = root.to_str().unwrap();
+        let find = |scope: ReferenceScope| {
+            let mut found: Vec<(String, Vec<String>)> = service
+                .find_references_scoped("helper", "js_family", root_path, &scope)
+                .unwrap()
+                .into_iter()
+                .map(|r| {
+                    let name = Path::new(&r.symbol.file_path)
+                        .file_name()
+                        .unwrap()
+                        .to_string_lossy()
+                        .to_string();
+                    (name, r.matched_scopes)
+                })
+                .filter(|(name, _)| name != "util.ts")
+                .collect();
+            found.sort();
+            found
+        };
+        let labels = |labels: &[&str]| labels.iter().map(|l| l.to_string()).collect::<Vec<_>>();
+
+        // Unscoped searches the whole project without labels
+        assert_eq!(
+            find(ReferenceScope::default()),
+            vec![
+                ("a.ts".to_string(), vec![]),
+                ("b.ts".to_string(), vec![]),
+                ("c.ts".to_string(), vec![]),
+            ]
+        );
+
+        let directory = ReferenceScope {
+            include_paths: Some(vec!["src/feature".to_string()]),
+            ..Default::default()
+        };
+        assert_eq!(
+            find(directory),
+            vec![("a.ts".to_string(), labels(&["path:src/feature"]))]
+        );
+
+        // Working tree changes plus files changed on the branch since main
+        let changed = ReferenceScope {
+            changed_only: true,
+            base_ref: Some("main".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            find(changed),
+            vec![
+                ("b.ts".to_string(), labels(&["base:main"])),
+                ("c.ts".to_string(), labels(&["changed"])),
+            ]
+        );
+
+        let combined = ReferenceScope {
+            include_paths: Some(vec!["src/other".to_string()]),
+            changed_only: true,
+            base_ref: None,
+        };
+        assert_eq!(
+            find(combined),
+            vec![
+                ("b.ts".to_string(), labels(&["path:src/other"])),
+                ("c.ts".to_string(), labels(&["path:src/other", "changed"])),
+            ]
+        );
+
+        // A scope that matches nothing does not fall back to the whole project
+        let missing = ReferenceScope {
+            include_paths: Some(vec!["src/missing".to_string()]),
+            ..Default::default()
+        };
+        assert!(find(missing).is_empty());
+    }
+
+    const VUE_COMPONENT: &str = "<template>\n  <button @click=\"formatTotal(items)\">Total</button>\n</template>\n\n<script lang=\"ts\">\nimport { sum } from './math';\n\nexport function formatTotal(items: number[]): string {\n  return sum(items).toFixed(2);\n}\n</script>\n\n<script setup>const label = formatTotal([1]);</script>\n";
+
+    #[test]
+    fn test_index_vue_script_block() {
+        let mut service = CodeNavigationService::new();
+        service
+            .index_file("/app/Cart.vue", VUE_COMPONENT, "vue")
+            .unwrap();
+
+        let defs = service.find_definition("formatTotal", "js_family");
+        assert!(!defs.is_empty());
+        // Line 8 of the component, not of the script block
+        for def in &defs {
+            assert_eq!(def.file_path, "/app/Cart.vue");
+            assert_eq!((def.start_line, def.start_column), (8, 17));
+        }
+        // The setup block on the tag's own line keeps its columns too
+        let label = service.find_definition("label", "js_family");
+        assert_eq!(label.len(), 1);
+        assert_eq!((label[0].start_line, label[0].start_column), (13, 21));
+
+        // Both blocks are cleared together
+        service.clear_file("/app/Cart.vue");
+        assert!(service
+            .find_definition("formatTotal", "js_family")
+            .is_empty());
+        assert!(service.find_definition("label", "js_family").is_empty());
+
+        let nav = RwLock::new(CodeNavigationService::new());
+        let files = vec![(
+            "/app/Cart.vue".to_string(),
+            VUE_COMPONENT.to_string(),
+            "vue".to_string(),
+        )];
+        let result = index_files_batch(&nav, &files).unwrap();
+        assert_eq!(result.indexed_files, 1);
+        let defs = nav
+            .read()
+            .unwrap()
+            .find_definition("formatTotal", "js_family");
+        assert_eq!(defs[0].start_line, 8);
+    }
+
+    #[test]
+    fn test_references_in_vue_components() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::write(root.join("Cart.vue"), VUE_COMPONENT).unwrap();
+        fs::write(root.join("report.ts"), "const total = formatTotal([]);\n").unwrap();
+
+        let service = CodeNavigationService::new();
+        let mut found: Vec<(String, u32)> = service
+            .find_references_hybrid("formatTotal", "js_family", root.to_str().unwrap())
+            .into_iter()
+            .map(|r| {
+                let name = Path::new(&r.file_path).file_name().unwrap();
+                (name.to_string_lossy().to_string(), r.start_line)
+            })
+            .collect();
+        found.sort();
+        // The template's use is markup, so only script lines count
+        assert_eq!(
+            found,
+            vec![
+                ("Cart.vue".to_string(), 8),
+                ("Cart.vue".to_string(), 13),
+                ("report.ts".to_string(), 1),
+            ]
+        );
+    }
+
+    fn buffer_occurrences(
+        code: &str,
+        lang_id: &str,
+        symbol_name: &str,
+        include_strings_and_comments: bool,
+    ) -> Vec<(u32, u32, OccurrenceKind)> {
+        CodeNavigationService::new()
+            .occurrences_in_buffer(
+                code,
+                lang_id,
+                &OccurrenceTarget::Name(symbol_name.to_string()),
+                include_strings_and_comments,
+            )
+            .unwrap()
+            .occurrences
+            .into_iter()
+            .map(|o| (o.start_line, o.start_column, o.kind))
+            .collect()
+    }
+
+    #[test]
+    fn test_occurrences_in_buffer_python() {
+        let code = r#"count = 0
+
+def bump(count):
+    # count is shadowed here
+    total = count + 1
+    return total
+
+count = bump(count)
+count += 1
+label = "count"
+print(obj.count)
+"#;
+        use OccurrenceKind::*;
+        assert_eq!(
+            buffer_occurrences(code, "python", "count", false),
+            vec![
+                (1, 1, Write),
+                (3, 10, Definition),
+                (5, 13, Reference),
+                (8, 1, Write),
+                (8, 14, Reference),
+                (9, 1, Write),
+            ]
+        );
+
+        let with_text = buffer_occurrences(code, "python", "count", true);
+        assert_eq!(with_text.len(), 8);
+        assert!(with_text.contains(&(4, 7, Text)));
+        assert!(with_text.contains(&(10, 10, Text)));
+    }
+
+    #[test]
+    fn test_occurrences_in_buffer_typescript() {
+        let code = r#"export function render(items: string[]) {
+  let total = 0;
+  for (const item of items) {
+    total += item.length;
+  }
+  total++;
+  // total is logged below
+  console.log(total, { total: 1 }, "total");
+  return total;
+}
+"#;
+        use OccurrenceKind::*;
+        assert_eq!(
+            buffer_occurrences(code, "typescript", "total", false),
+            vec![
+                (2, 7, Definition),
+                (4, 5, Write),
+                (6, 3, Write),
+                (8, 15, Reference),
+                (9, 10, Reference),
+            ]
+        );
+        assert_eq!(
+            buffer_occurrences(code, "typescript", "render", false),
+            vec![(1, 17, Definition)]
+        );
+    }
+
+    #[test]
+    fn test_occurrences_in_buffer_rust() {
+        let code = r#"fn main() {
+    let mut count = 0;
+    count += 1;
+    count = count * 2;
+    let s = "count";
+    // count
+    println!("{}", count);
+}
+"#;
+        use OccurrenceKind::*;
+        assert_eq!(
+            buffer_occurrences(code, "rust", "count", false),
+            vec![
+                (2, 13, Definition),
+                (3, 5, Write),
+                (4, 5, Write),
+                (4, 13, Reference),
+                (7, 20, Reference),
+            ]
+        );
+        assert_eq!(buffer_occurrences(code, "rust", "count", true).len(), 7);
+    }
+
+    #[test]
+    fn test_occurrences_in_buffer_go() {
+        let code = "package main\n\nfunc run() int {\n\ttotal, err := compute()\n\ttotal = total + 1\n\ttotal++\n\t_ = err\n\treturn total // total\n}\n";
+        use OccurrenceKind::*;
+        assert_eq!(
+            buffer_occurrences(code, "go", "total", false),
+            vec![
+                (4, 2, Definition),
+                (5, 2, Write),
+                (5, 10, Reference),
+                (6, 2, Write),
+                (8, 9, Reference),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_occurrences_in_buffer_at_utf16_position() {
+        let code = "const title = \"h\u{e9}llo\"; const size = 1;\nconsole.log(size, title);\n";
+        let service = CodeNavigationService::new();
+
+        // "size" starts at UTF-16 column 30 but byte column 31, after the two-byte é
+        for column in [30, 34] {
+            let found = service
+                .occurrences_in_buffer(
+                    code,
+                    "typescript",
+                    &OccurrenceTarget::Position { line: 1, column },
+                    false,
+                )
+                .unwrap();
+            assert_eq!(found.symbol_name, "size");
+            assert_eq!(
+                found.occurrences,
+                vec![
+                    BufferOccurrence {
+                        kind: OccurrenceKind::Definition,
+                        start_line: 1,
+                        start_column: 31,
+                        end_line: 1,
+                        end_column: 35,
+                        start_column_utf16: 30,
+                        end_column_utf16: 34,
+                    },
+                    BufferOccurrence {
+                        kind: OccurrenceKind::Reference,
+                        start_line: 2,
+                        start_column: 13,
+                        end_line: 2,
+                        end_column: 17,
+                        start_column_utf16: 13,
+                        end_column_utf16: 17,
+                    },
+                ]
+            );
+        }
+
+        let in_string = OccurrenceTarget::Position {
+            line: 1,
+            column: 17,
+        };
+        assert!(service
+            .occurrences_in_buffer(code, "typescript", &in_string, false)
+            .is_err());
+        assert!(service
+            .occurrences_in_buffer(
+                code,
+                "plaintext",
+                &OccurrenceTarget::Name("size".into()),
+                false
+            )
+            .is_err());
+    }
+
+    fn persisted_bytes(files: &[(&str, &str)]) -> Vec<u8> {
+        let mut service = CodeNavigationService::new();
+        for (path, content) in files {
+            service.index_file(path, content, "python").unwrap();
+        }
+        serde_json::to_vec(&service.snapshot("/project", HashMap::new())).unwrap()
+    }
+
+    fn slow_reader(bytes: Vec<u8>, chunk: usize) -> crate::index_persist::tests::SlowReader {
+        crate::index_persist::tests::SlowReader {
+            bytes: std::io::Cursor::new(bytes),
+            chunk,
+            delay: Duration::from_millis(2),
+        }
+    }
+
+    #[test]
+    fn test_load_persisted_index_reports_phases() {
+        let bytes = persisted_bytes(&[
+            ("/project/a.py", "def alpha():\n    pass\n"),
+            ("/project/b.py", "def beta():\n    pass\n"),
+        ]);
+        let total = bytes.len() as u64;
+        let nav = RwLock::new(CodeNavigationService::new());
+        let control = PersistControl::new("/project", None);
+        let result = load_persisted(
+            &nav,
+            slow_reader(bytes, 64),
+            Some(total),
+            Path::new("/nonexistent/index.json"),
+            "/project",
+            false,
+            &control,
+        )
+        .unwrap();
+
+        assert_eq!(result.status, IndexLoadStatus::Loaded);
+        assert_eq!(result.bytes_read, total);
+        assert_eq!(result.files_merged, 2);
+        let phases: Vec<_> = result.timings.iter().map(|t| t.phase).collect();
+        assert_eq!(
+            phases,
+            [
+                PersistPhase::Reading,
+                PersistPhase::Parsing,
+                PersistPhase::Merging
+            ]
+        );
+        let service = nav.read().unwrap();
+        assert_eq!(service.index.definitions_where("beta", |_| true).len(), 1);
+        assert!(service.persist_target.is_some());
+    }
+
+    #[test]
+    fn test_cancelled_load_leaves_index_empty_unless_keeping_partial() {
+        let bytes = persisted_bytes(&[("/project/a.py", "def alpha():\n    pass\n")]);
+        for keep_partial in [false, true] {
+            let mut service = CodeNavigationService::new();
+            service
+                .index_file("/project/old.py", "def old():\n    pass\n", "python")
+                .unwrap();
+            let nav = RwLock::new(service);
+            let control = Arc::new(PersistControl::new("/project", None));
+            let canceller = {
+                let control = control.clone();
+                std::thread::spawn(move || {
+                    std::thread::sleep(Duration::from_millis(20));
+                    control.cancel();
+                })
+            };
+            // Eight bytes every 2ms would take over a second
+            let result = load_persisted(
+                &nav,
+                slow_reader(bytes.clone(), 8),
+                None,
+                Path::new("/nonexistent/index.json"),
+                "/project",
+                keep_partial,
+                &control,
+            )
+            .unwrap();
+            canceller.join().unwrap();
+
+            assert_eq!(result.status, IndexLoadStatus::Cancelled);
+            let service = nav.read().unwrap();
+            assert_eq!(service.index.symbol_count(), usize::from(keep_partial));
+            assert!(service.persist_target.is_none());
+        }
+    }
+
+    #[test]
+    fn test_merge_persisted_stops_between_chunks() {
+        let mut definitions: DefinitionMap = HashMap::new();
+        let mut file_definitions = HashMap::new();
+        for i in 0..MERGE_CHUNK_FILES + 10 {
+            let file_path = format!("/project/f{}.py", i);
+            let name = format!("func{}", i);
+            definitions.insert(
+                name.clone(),
+                vec![SymbolInfo {
+                    name: name.clone(),
+                    kind: "function".to_string(),
+                    file_path: file_path.clone(),
+                    lang_family: "python".to_string(),
+                    start_line: 1,
+                    start_column: 1,
+                    end_line: 1,
+                    end_column: 10,
+                    start_column_utf16: 1,
+                    end_column_utf16: 10,
+                    exported: false,
+                    dependency: false,
+                    visibility: Visibility::Unknown,
+                }],
+            );
+            file_definitions.insert(file_path, HashSet::from([name]));
+        }
+
+        let service = CodeNavigationService::new();
+        let merged = service.merge_persisted(definitions, file_definitions, |done, _| done == 0);
+        assert_eq!(merged, MERGE_CHUNK_FILES);
+        assert_eq!(service.index.file_count(), MERGE_CHUNK_FILES);
+        assert_eq!(service.index.symbol_count(), MERGE_CHUNK_FILES);
+    }
 }
@@ -0,0 +1,338 @@
+// Concurrency governor
+// Search, batch indexing and directory sizing all fan out on the global rayon pool. Run
+// together they used to take every core and starve the UI thread. Operations now take a
+// permit from one of two budgets before starting: interactive (search, reference finding)
+// or background (indexing, stats). The budgets are independent, so a search never queues
+// behind indexing; background work additionally splits its parallel loops into chunks and
+// pauses between them while interactive work is in flight. The rayon pool itself is sized
+// to leave a core free.
+
+use crate::activity;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// Concurrent interactive operations before further ones queue
+const INTERACTIVE_PERMITS: usize = 4;
+/// Concurrent background operations before further ones queue
+const BACKGROUND_PERMITS: usize = 2;
+/// How long background work pauses per check while interactive work runs
+const YIELD_SLICE: Duration = Duration::from_millis(5);
+/// Longest pause per chunk, so background work still advances during long searches
+const MAX_YIELD: Duration = Duration::from_millis(250);
+/// Files per parallel chunk in background batch operations
+pub const BACKGROUND_CHUNK: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OperationClass {
+    Interactive,
+    Background,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BudgetStatus {
+    pub limit: usize,
+    pub active: usize,
+    pub waiting: usize,
+    /// Labels of the operations holding a permit, oldest first
+    pub operations: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConcurrencyStatus {
+    pub interactive: BudgetStatus,
+    pub background: BudgetStatus,
+    pub rayon_threads: usize,
+    pub available_cores: usize,
+}
+
+#[derive(Default)]
+struct BudgetState {
+    /// Permit id and label of each holder
+    active: Vec<(u64, String)>,
+    /// Tickets of queued acquires, served in order
+    queue: VecDeque<u64>,
+}
+
+struct Budget {
+    limit: usize,
+    state: Mutex<BudgetState>,
+    released: Condvar,
+}
+
+impl Budget {
+    fn new(limit: usize) -> Self {
+        Self {
+            limit: limit.max(1),
+            state: Mutex::new(BudgetState::default()),
+            released: Condvar::new(),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, BudgetState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn status(&self) -> BudgetStatus {
+        let state = self.lock();
+        BudgetStatus {
+            limit: self.limit,
+            active: state.active.len(),
+            waiting: state.queue.len(),
+            operations: state.active.iter().map(|(_, l)| l.clone()).collect(),
+        }
+    }
+
+    fn release(&self, id: u64) {
+        self.lock().active.retain(|(held, _)| *held != id);
+        self.released.notify_all();
+    }
+}
+
+/// Held for the duration of an operation; dropping it frees the slot
+pub struct Permit<'a> {
+    budget: &'a Budget,
+    id: u64,
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        self.budget.release(self.id);
+    }
+}
+
+pub struct ConcurrencyGovernor {
+    interactive: Budget,
+    background: Budget,
+    next_id: AtomicU64,
+}
+
+impl Default for ConcurrencyGovernor {
+    fn default() -> Self {
+        Self::new(INTERACTIVE_PERMITS, BACKGROUND_PERMITS)
+    }
+}
+
+impl ConcurrencyGovernor {
+    pub fn new(interactive_limit: usize, background_limit: usize) -> Self {
+        Self {
+            interactive: Budget::new(interactive_limit),
+            background: Budget::new(background_limit),
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    fn budget(&self, class: OperationClass) -> &Budget {
+        match class {
+            OperationClass::Interactive => &self.interactive,
+            OperationClass::Background => &self.background,
+        }
+    }
+
+    /// Take a permit, waiting in line if the class's budget is used up. While waiting,
+    /// the activity (if any) shows that it is queued.
+    pub fn acquire(
+        &self,
+        class: OperationClass,
+        label: &str,
+        activity_id: Option<&str>,
+    ) -> Permit<'_> {
+        let budget = self.budget(class);
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let mut state = budget.lock();
+        if state.queue.is_empty() && state.active.len() < budget.limit {
+            state.active.push((id, label.to_string()));
+            return Permit { budget, id };
+        }
+
+        state.queue.push_back(id);
+        log::debug!("{} queued for a {:?} permit", label, class);
+        if let Some(activity_id) = activity_id {
+            activity::activity_progress(
+                activity_id,
+                0,
+                None,
+                Some("Waiting for other operations to finish".to_string()),
+            );
+        }
+        while state.queue.front() != Some(&id) || state.active.len() >= budget.limit {
+            state = budget
+                .released
+                .wait(state)
+                .unwrap_or_else(|e| e.into_inner());
+        }
+        state.queue.pop_front();
+        state.active.push((id, label.to_string()));
+        // The next in line may also fit
+        budget.released.notify_all();
+        Permit { budget, id }
+    }
+
+    /// Take a permit only if one is free right away
+    pub fn try_acquire(&self, class: OperationClass, label: &str) -> Option<Permit<'_>> {
+        let budget = self.budget(class);
+        let mut state = budget.lock();
+        if !state.queue.is_empty() || state.active.len() >= budget.limit {
+            return None;
+        }
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        state.active.push((id, label.to_string()));
+        Some(Permit { budget, id })
+    }
+
+    /// Whether interactive work holds or waits for a permit
+    pub fn interactive_pending(&self) -> bool {
+        let state = self.interactive.lock();
+        !state.active.is_empty() || !state.queue.is_empty()
+    }
+
+    /// Called by background work between chunks: pause while interactive work is in
+    /// flight, for at most MAX_YIELD. A no-op on rayon workers, where pausing would hold
+    /// a pool thread the interactive work needs; call it from the thread driving the chunks.
+    pub fn yield_to_interactive(&self) {
+        if rayon::current_thread_index().is_some() {
+            return;
+        }
+        let start = Instant::now();
+        while self.interactive_pending() && start.elapsed() < MAX_YIELD {
+            std::thread::sleep(YIELD_SLICE);
+        }
+    }
+
+    pub fn status(&self) -> ConcurrencyStatus {
+        ConcurrencyStatus {
+            interactive: self.interactive.status(),
+            background: self.background.status(),
+            rayon_threads: rayon::current_num_threads(),
+            available_cores: available_cores(),
+        }
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref GOVERNOR: ConcurrencyGovernor = ConcurrencyGovernor::default();
+}
+
+pub fn governor() -> &'static ConcurrencyGovernor {
+    &GOVERNOR
+}
+
+fn available_cores() -> usize {
+    std::thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+/// Size the global rayon pool to leave one core for the UI. Must run before anything
+/// uses rayon; later calls keep the existing pool.
+pub fn configure_thread_pool() {
+    let threads = available_cores().saturating_sub(1).max(1);
+    match rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .thread_name(|i| format!("rayon-worker-{}", i))
+        .build_global()
+    {
+        Ok(()) => log::info!("Rayon pool sized to {} threads", threads),
+        Err(e) => log::warn!("Rayon pool already initialized: {}", e),
+    }
+}
+
+#[tauri::command]
+pub fn get_concurrency_status() -> ConcurrencyStatus {
+    governor().status()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    fn wait_until(condition: impl Fn() -> bool) {
+        let start = Instant::now();
+        while !condition() {
+            assert!(start.elapsed() < Duration::from_secs(5), "timed out");
+            std::thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    #[test]
+    fn test_interactive_proceeds_while_background_is_exhausted() {
+        let governor = Arc::new(ConcurrencyGovernor::new(1, 2));
+        let held = [
+            governor.acquire(OperationClass::Background, "index a", None),
+            governor.acquire(OperationClass::Background, "index b", None),
+        ];
+        assert!(governor
+            .try_acquire(OperationClass::Background, "stats")
+            .is_none());
+
+        let queued = {
+            let governor = governor.clone();
+            std::thread::spawn(move || {
+                let _permit = governor.acquire(OperationClass::Background, "stats", None);
+            })
+        };
+        wait_until(|| governor.status().background.waiting == 1);
+
+        // Background is full and has a queue; interactive work is not held up by it
+        let search = governor
+            .try_acquire(OperationClass::Interactive, "search")
+            .expect("interactive permit");
+        assert!(governor.interactive_pending());
+        let status = governor.status();
+        assert_eq!(status.interactive.operations, ["search"]);
+        assert_eq!(status.background.operations, ["index a", "index b"]);
+
+        drop(search);
+        assert!(!governor.interactive_pending());
+        drop(held);
+        queued.join().unwrap();
+        assert_eq!(governor.status().background.active, 0);
+    }
+
+    #[test]
+    fn test_waiters_are_served_in_order() {
+        let governor = Arc::new(ConcurrencyGovernor::new(1, 1));
+        let first = governor.acquire(OperationClass::Interactive, "first", None);
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let mut waiters = Vec::new();
+        for i in 0..3 {
+            let (shared, order) = (governor.clone(), order.clone());
+            waiters.push(std::thread::spawn(move || {
+                let _permit = shared.acquire(OperationClass::Interactive, "waiter", None);
+                order.lock().unwrap().push(i);
+            }));
+            // Queue them one at a time so the expected order is known
+            wait_until(|| governor.status().interactive.waiting == i + 1);
+        }
+        drop(first);
+        for waiter in waiters {
+            waiter.join().unwrap();
+        }
+        assert_eq!(*order.lock().unwrap(), [0, 1, 2]);
+    }
+
+    #[test]
+    fn test_background_yields_while_interactive_runs() {
+        let governor = ConcurrencyGovernor::new(1, 1);
+        let search = governor.acquire(OperationClass::Interactive, "search", None);
+        let start = Instant::now();
+        governor.yield_to_interactive();
+        // Paused, but not indefinitely
+        assert!(start.elapsed() >= MAX_YIELD);
+
+        let start = Instant::now();
+        std::thread::scope(|scope| {
+            scope.spawn(move || {
+                std::thread::sleep(Duration::from_millis(20));
+                drop(search);
+            });
+            governor.yield_to_interactive();
+        });
+        assert!(start.elapsed() < MAX_YIELD);
+        assert!(!governor.interactive_pending());
+    }
+}
@@ -150,20 +150,21 @@ pub const BINARY_EXTENSIONS: &[&str] = &[
     "eot", "jar", "war", "ear", "class", "pyc", "pyo", "db", "sqlite", "sqlite3",
 ];
 
-/// Check if a directory should be excluded
+/// Check if a directory should be excluded, by the built-in list or the user's exclusions
 pub fn should_exclude_dir(dir_name: &str) -> bool {
-    EXCLUDED_DIRS.contains(&dir_name)
+    EXCLUDED_DIRS.contains(&dir_name) || crate::exclusions::is_custom_excluded(dir_name)
 }
 
-/// Check if a file extension indicates a code file
+/// Check if a file extension indicates a code file, including custom language mappings
 pub fn is_code_extension(extension: &str) -> bool {
-    CODE_EXTENSIONS.contains(&extension)
+    CODE_EXTENSIONS.contains(&extension) || crate::language_mapping::is_custom_extension(extension)
 }
 
-/// Check if a filename (without extension) is a code file
+/// Check if a filename (without extension) is a code file, including custom language mappings
 pub fn is_code_filename(filename: &str) -> bool {
     let lower = filename.to_lowercase();
     CODE_FILENAMES.contains(&lower.as_str())
+        || crate::language_mapping::is_custom_filename(filename)
 }
 
 /// Check if a file extension indicates a binary file
@@ -263,6 +263,17 @@ impl Database {
         Ok(())
     }
 
+    /// Fold the WAL back into the main database file, then close
+    pub async fn checkpoint_and_close(&self) -> Result<(), String> {
+        if self.conn.lock().await.is_none() {
+            return Ok(());
+        }
+        if let Err(e) = self.query("PRAGMA wal_checkpoint(TRUNCATE)", vec![]).await {
+            log::warn!("WAL checkpoint failed: {}", e);
+        }
+        self.close().await
+    }
+
     /// Synchronous close for use in Drop or sync contexts
     pub fn close_sync(&self) {
         // Try to acquire lock and clear connection
@@ -327,7 +338,33 @@ fn base64_encode(data: &[u8]) -> String {
 // Tauri commands
 #[tauri::command]
 pub async fn db_connect(db: State<'_, Arc<Database>>) -> Result<(), String> {
-    db.connect().await
+    db.connect().await?;
+    // Lookups of custom language mappings are synchronous, so load them up front
+    if let Err(e) = crate::language_mapping::load_from_settings(&db).await {
+        log::warn!("Failed to load language mappings: {}", e);
+    }
+    if let Err(e) = crate::embedded_code::load_from_settings(&db).await {
+        log::warn!("Failed to load embedded code settings: {}", e);
+    }
+    if let Err(e) = crate::storage::load_from_settings(&db).await {
+        log::warn!("Failed to load storage quotas: {}", e);
+    }
+    if let Err(e) = crate::exclusions::load_from_settings(&db).await {
+        log::warn!("Failed to load excluded directories: {}", e);
+    }
+    if let Err(e) = crate::walk_depth::load_from_settings(&db).await {
+        log::warn!("Failed to load walk depth: {}", e);
+    }
+    if let Err(e) = crate::file_search::load_from_settings(&db).await {
+        log::warn!("Failed to load file search ranking: {}", e);
+    }
+    if let Err(e) = crate::perf_trace::load_from_settings(&db).await {
+        log::warn!("Failed to load tracing setting: {}", e);
+    }
+    if let Err(e) = crate::search_history::load_from_settings(&db).await {
+        log::warn!("Failed to load search history setting: {}", e);
+    }
+    Ok(())
 }
 
 #[tauri::command]
@@ -0,0 +1,475 @@
+// Definition peek module
+// Body text for the peek-definition popup, so the frontend doesn't fetch and slice whole
+// files. A symbol's stored position may be stale after edits, so the definition is looked
+// up near it by name and the drift is reported. Long bodies are cut after the last whole
+// statement that fits the caps instead of mid-token. Several symbols can be peeked in one
+// call; each file is read and parsed once, and work stops when the time budget runs out.
+
+use crate::code_navigation::{language_for, word_occurrences, CodeNavigationService, SymbolInfo};
+use crate::path_wire;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tree_sitter::{Node, Parser, Tree};
+
+/// How far (in lines) from the stored position to look for a moved definition
+const DRIFT_LINES: u32 = 20;
+
+pub const DEFAULT_MAX_LINES: usize = 40;
+pub const DEFAULT_MAX_BYTES: usize = 4096;
+
+/// Node kinds that hold a definition, across the indexed languages
+const DEFINITION_KINDS: &[&str] = &[
+    // Rust
+    "function_item",
+    "function_signature_item",
+    "struct_item",
+    "enum_item",
+    "union_item",
+    "trait_item",
+    "const_item",
+    "static_item",
+    "type_item",
+    "mod_item",
+    // Python, C and C++
+    "function_definition",
+    "class_definition",
+    "struct_specifier",
+    "class_specifier",
+    // Go, Java and TypeScript
+    "function_declaration",
+    "method_declaration",
+    "type_declaration",
+    "class_declaration",
+    "abstract_class_declaration",
+    "interface_declaration",
+    "type_alias_declaration",
+    "enum_declaration",
+    "method_definition",
+    "lexical_declaration",
+    "function_signature",
+];
+
+/// Nodes whose children are statements or members, i.e. where a body may be cut
+const BLOCK_KINDS: &[&str] = &[
+    "block",
+    "statement_block",
+    "compound_statement",
+    "declaration_list",
+    "field_declaration_list",
+    "enum_variant_list",
+    "class_body",
+    "interface_body",
+    "enum_body",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DefinitionBody {
+    /// Whole lines of the definition, up to the caps
+    pub text: String,
+    /// Lines covered by `text`, 1-based and inclusive
+    pub start_line: u32,
+    pub end_line: u32,
+    /// Last line of the whole definition
+    pub definition_end_line: u32,
+    pub truncated: bool,
+    /// Lines the definition moved from the symbol's stored position
+    pub drift: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DefinitionPeek {
+    pub name: String,
+    pub file_path: String,
+    /// The symbol's stored line
+    pub start_line: u32,
+    /// None while pending, or when the file can't be read or the definition is no longer
+    /// within the drift window of the stored line
+    pub body: Option<DefinitionBody>,
+    /// Not looked at before the budget ran out; ask again
+    pub pending: bool,
+}
+
+/// A file read and parsed once per call; None when it can't be read or parsed
+type ParsedFile = Option<(String, Tree)>;
+
+fn parse_file(file_path: &str) -> ParsedFile {
+    let lang_id = CodeNavigationService::get_lang_id_from_path(file_path)?;
+    let language = language_for(&lang_id)?;
+    let content = std::fs::read_to_string(path_wire::resolve(file_path)).ok()?;
+    let mut parser = Parser::new();
+    parser.set_language(&language).ok()?;
+    let tree = parser.parse(&content, None)?;
+    Some((content, tree))
+}
+
+/// Byte offset where each line starts
+fn line_starts(content: &str) -> Vec<usize> {
+    std::iter::once(0)
+        .chain(content.match_indices('\n').map(|(i, _)| i + 1))
+        .collect()
+}
+
+/// Byte offset where a 0-based line ends, before its line break
+fn line_end(content: &str, starts: &[usize], row: usize) -> usize {
+    let end = starts.get(row + 1).map_or(content.len(), |&next| next - 1);
+    if content[..end].ends_with('\r') {
+        end - 1
+    } else {
+        end
+    }
+}
+
+/// The definition `name_node` names, widened over `export` and decorators
+fn definition_of_name<'t>(name_node: Node<'t>) -> Option<Node<'t>> {
+    let parent = name_node.parent()?;
+    let names = parent.child_by_field_name("name") == Some(name_node)
+        || parent.child_by_field_name("declarator") == Some(name_node);
+    if !names {
+        return None;
+    }
+    let mut node = parent;
+    loop {
+        // A parameter's declarator names the parameter, not the enclosing function
+        if node.kind().contains("parameter") {
+            return None;
+        }
+        if DEFINITION_KINDS.contains(&node.kind()) {
+            break;
+        }
+        node = node.parent()?;
+    }
+    // A name inside the body belongs to something nested, not to this definition
+    if node
+        .child_by_field_name("body")
+        .is_some_and(|body| body.start_byte() <= name_node.start_byte())
+    {
+        return None;
+    }
+    match node.parent() {
+        Some(parent) if matches!(parent.kind(), "export_statement" | "decorated_definition") => {
+            Some(parent)
+        }
+        _ => Some(node),
+    }
+}
+
+/// The definition of `symbol`, nearest its stored line first, with the drift in lines
+fn locate_definition<'t>(
+    tree: &'t Tree,
+    content: &str,
+    starts: &[usize],
+    symbol: &SymbolInfo,
+) -> Option<(Node<'t>, i32)> {
+    let stored = symbol.start_line.max(1) as i64 - 1;
+    let rows = (0..=DRIFT_LINES as i64).flat_map(|distance| [stored - distance, stored + distance]);
+    for row in rows {
+        if row < 0 || row as usize >= starts.len() {
+            continue;
+        }
+        let row = row as usize;
+        let line_start = starts[row];
+        let line = &content[line_start..line_end(content, starts, row)];
+        for column in word_occurrences(line, &symbol.name) {
+            let start = line_start + column;
+            let end = start + symbol.name.len();
+            let Some(node) = tree.root_node().descendant_for_byte_range(start, end) else {
+                continue;
+            };
+            if node.start_byte() != start || node.end_byte() != end {
+                continue;
+            }
+            if let Some(definition) = definition_of_name(node) {
+                return Some((definition, row as i32 - stored as i32));
+            }
+        }
+    }
+    None
+}
+
+/// Rows (0-based) that end a statement or member inside `definition`, with nothing but
+/// whitespace after it on the line
+fn statement_end_rows(definition: Node, content: &str, starts: &[usize]) -> Vec<usize> {
+    let mut rows = Vec::new();
+    let mut stack = vec![definition];
+    while let Some(node) = stack.pop() {
+        let mut cursor = node.walk();
+        let is_block = BLOCK_KINDS.contains(&node.kind())
+            || node
+                .parent()
+                .and_then(|parent| parent.child_by_field_name("body"))
+                == Some(node);
+        for child in node.named_children(&mut cursor) {
+            if is_block {
+                let row = child.end_position().row;
+                let rest = &content
+                    [child.end_byte()..line_end(content, starts, row).max(child.end_byte())];
+                if rest.trim().is_empty() {
+                    rows.push(row);
+                }
+            }
+            stack.push(child);
+        }
+    }
+    rows.sort_unstable();
+    rows.dedup();
+    rows
+}
+
+/// The definition's whole lines cut to the caps, the last row kept and whether it was cut
+fn extract_body(
+    definition: Node,
+    content: &str,
+    starts: &[usize],
+    max_lines: usize,
+    max_bytes: usize,
+) -> (String, usize, bool) {
+    let first = definition.start_position().row;
+    let last = definition.end_position().row;
+    let start = starts[first];
+    let fits =
+        |row: usize| row - first < max_lines && line_end(content, starts, row) - start <= max_bytes;
+    if fits(last) {
+        return (
+            content[start..line_end(content, starts, last)].to_string(),
+            last,
+            false,
+        );
+    }
+
+    let cut = statement_end_rows(definition, content, starts)
+        .into_iter()
+        .filter(|&row| row < last && fits(row))
+        .max()
+        // No statement fits: fall back to whole lines
+        .or_else(|| (first..last).rev().find(|&row| fits(row)));
+    match cut {
+        Some(row) => (
+            content[start..line_end(content, starts, row)].to_string(),
+            row,
+            true,
+        ),
+        None => {
+            // Not even the first line fits; stop at a word boundary
+            let line = &content[start..line_end(content, starts, first)];
+            let mut end = max_bytes.min(line.len());
+            while !line.is_char_boundary(end) {
+                end -= 1;
+            }
+            let end = line[..end].rfind(char::is_whitespace).unwrap_or(end);
+            (line[..end].trim_end().to_string(), first, true)
+        }
+    }
+}
+
+/// Peek `symbols` in order until `budget` runs out; the rest come back pending
+pub fn peek_definitions(
+    symbols: &[SymbolInfo],
+    max_lines: usize,
+    max_bytes: usize,
+    budget: Duration,
+) -> Vec<DefinitionPeek> {
+    let start = Instant::now();
+    let max_lines = max_lines.max(1);
+    let mut files: HashMap<String, ParsedFile> = HashMap::new();
+
+    symbols
+        .iter()
+        .map(|symbol| {
+            let mut peek = DefinitionPeek {
+                name: symbol.name.clone(),
+                file_path: symbol.file_path.clone(),
+                start_line: symbol.start_line,
+                body: None,
+                pending: false,
+            };
+            if start.elapsed() >= budget {
+                peek.pending = true;
+                return peek;
+            }
+            let Some((content, tree)) = files
+                .entry(symbol.file_path.clone())
+                .or_insert_with(|| parse_file(&symbol.file_path))
+                .as_ref()
+            else {
+                return peek;
+            };
+            let starts = line_starts(content);
+            peek.body =
+                locate_definition(tree, content, &starts, symbol).map(|(definition, drift)| {
+                    let (text, end_row, truncated) =
+                        extract_body(definition, content, &starts, max_lines, max_bytes);
+                    DefinitionBody {
+                        text,
+                        start_line: definition.start_position().row as u32 + 1,
+                        end_line: end_row as u32 + 1,
+                        definition_end_line: definition.end_position().row as u32 + 1,
+                        truncated,
+                        drift,
+                    }
+                });
+            peek
+        })
+        .collect()
+}
+
+/// The first lines of each symbol's definition for the peek popup, in request order.
+/// Symbols not reached within `budget_ms` come back pending.
+#[tauri::command]
+pub async fn code_nav_get_definition_body(
+    symbols: Vec<SymbolInfo>,
+    max_lines: Option<usize>,
+    max_bytes: Option<usize>,
+    budget_ms: Option<u64>,
+) -> Result<Vec<DefinitionPeek>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        peek_definitions(
+            &symbols,
+            max_lines.unwrap_or(DEFAULT_MAX_LINES),
+            max_bytes.unwrap_or(DEFAULT_MAX_BYTES),
+            Duration::from_millis(budget_ms.unwrap_or(200)),
+        )
+    })
+    .await
+    .map_err(|e| format!("Definition peek task failed: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    const BUDGET: Duration = Duration::from_secs(10);
+
+    /// A function whose statements each span three lines: line 1 is the signature and
+    /// statement `k` covers lines `3k + 2 ..= 3k + 4`
+    fn long_function(statements: usize) -> String {
+        let mut source = String::from("fn long_function() {\n");
+        for k in 0..statements {
+            source.push_str(&format!("    let v{} = vec![\n        {},\n    ];\n", k, k));
+        }
+        source.push_str("}\n");
+        source
+    }
+
+    fn symbol(name: &str, file_path: &str, start_line: u32) -> SymbolInfo {
+        SymbolInfo {
+            name: name.to_string(),
+            kind: "function".to_string(),
+            file_path: file_path.to_string(),
+            lang_family: "rust".to_string(),
+            start_line,
+            start_column: 4,
+            end_line: start_line,
+            end_column: 4 + name.len() as u32,
+            start_column_utf16: 4,
+            end_column_utf16: 4 + name.len() as u32,
+            exported: false,
+            dependency: false,
+            visibility: Default::default(),
+        }
+    }
+
+    fn peek_one(symbol: SymbolInfo, max_lines: usize, max_bytes: usize) -> Option<DefinitionBody> {
+        peek_definitions(&[symbol], max_lines, max_bytes, BUDGET)
+            .remove(0)
+            .body
+    }
+
+    #[test]
+    fn test_truncation_stops_at_statement_boundaries() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("lib.rs");
+        fs::write(&file, long_function(20)).unwrap();
+        let file = file.to_string_lossy().to_string();
+
+        // A naive cut at line 9 would split the third statement
+        let body = peek_one(symbol("long_function", &file, 1), 9, DEFAULT_MAX_BYTES).unwrap();
+        assert!(body.truncated);
+        assert_eq!((body.start_line, body.end_line), (1, 7));
+        assert_eq!(body.definition_end_line, 62);
+        assert_eq!(body.text.lines().count(), 7);
+        assert!(body.text.ends_with("    ];"), "{}", body.text);
+
+        // A cut that lands on a statement end keeps it
+        let body = peek_one(symbol("long_function", &file, 1), 10, DEFAULT_MAX_BYTES).unwrap();
+        assert_eq!(body.end_line, 10);
+
+        // The byte cap cuts at a statement too
+        let body = peek_one(symbol("long_function", &file, 1), 40, 100).unwrap();
+        assert!(body.truncated);
+        assert!(body.text.len() <= 100);
+        assert!(body.text.ends_with("    ];"), "{}", body.text);
+
+        // A definition within the caps comes back whole
+        let body = peek_one(symbol("long_function", &file, 1), 100, 100_000).unwrap();
+        assert!(!body.truncated);
+        assert_eq!(body.end_line, 62);
+        assert!(body.text.ends_with('}'));
+    }
+
+    #[test]
+    fn test_drift_is_recovered_within_the_window() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("lib.rs");
+        let target = "fn helper() -> u32 {\n    let value = 1;\n    value\n}\n";
+        fs::write(&path, format!("fn first() {{}}\n\n{}", target)).unwrap();
+        let file = path.to_string_lossy().to_string();
+        let stored = symbol("helper", &file, 3);
+
+        let body = peek_one(stored.clone(), 40, DEFAULT_MAX_BYTES).unwrap();
+        assert_eq!((body.start_line, body.drift), (3, 0));
+
+        // Lines inserted above the target move it down
+        let inserted: String = (0..7).map(|i| format!("// note {}\n", i)).collect();
+        fs::write(&path, format!("fn first() {{}}\n{}\n{}", inserted, target)).unwrap();
+        let body = peek_one(stored.clone(), 40, DEFAULT_MAX_BYTES).unwrap();
+        assert_eq!((body.start_line, body.drift), (10, 7));
+        assert_eq!(body.text, target.trim_end());
+
+        // Calls to the name are not mistaken for its definition
+        fs::write(
+            &path,
+            format!("fn caller() {{\n    helper();\n}}\n{}{}", inserted, target),
+        )
+        .unwrap();
+        let body = peek_one(stored.clone(), 40, DEFAULT_MAX_BYTES).unwrap();
+        assert_eq!(body.start_line, 11);
+
+        // Beyond the window the definition is not found
+        let far: String = (0..30).map(|i| format!("// note {}\n", i)).collect();
+        fs::write(&path, format!("{}{}", far, target)).unwrap();
+        assert!(peek_one(stored, 40, DEFAULT_MAX_BYTES).is_none());
+    }
+
+    #[test]
+    fn test_peek_list_in_one_call() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("lib.rs");
+        fs::write(
+            &path,
+            format!("{}\nstruct Point {{\n    x: i32,\n}}\n", long_function(2)),
+        )
+        .unwrap();
+        let file = path.to_string_lossy().to_string();
+        let symbols = [
+            symbol("Point", &file, 10),
+            symbol("long_function", &file, 1),
+            symbol("missing", "/nonexistent/lib.rs", 1),
+        ];
+
+        let peeks = peek_definitions(&symbols, 40, DEFAULT_MAX_BYTES, BUDGET);
+        assert_eq!(peeks.len(), 3);
+        assert_eq!(
+            peeks[0].body.as_ref().unwrap().text,
+            "struct Point {\n    x: i32,\n}"
+        );
+        assert_eq!(peeks[1].body.as_ref().unwrap().start_line, 1);
+        assert!(peeks[2].body.is_none() && !peeks[2].pending);
+
+        // Without budget nothing is looked at
+        let peeks = peek_definitions(&symbols, 40, DEFAULT_MAX_BYTES, Duration::ZERO);
+        assert!(peeks.iter().all(|p| p.pending && p.body.is_none()));
+    }
+}
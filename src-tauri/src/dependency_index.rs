@@ -0,0 +1,631 @@
+// Dependency index module
+// Dependencies are excluded from project indexing, so go-to-definition on a library symbol
+// used to dead-end. This indexes just the public surface of dependencies on demand: the
+// .d.ts entry points of node_modules packages (from package.json types/exports, then
+// @types) and the files behind `pub mod` declarations of Rust crates in the local cargo
+// registry. Their definitions carry the `dependency` flag and only show up in lookups that
+// ask for dependencies.
+
+use crate::code_navigation::{CodeNavState, CodeNavigationService};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::State;
+
+/// Files indexed per package, following re-exports or `pub mod` declarations
+const MAX_FILES_PER_PACKAGE: usize = 64;
+
+lazy_static::lazy_static! {
+    /// Relative specifiers in `export ... from './x'` and `import ... from './x'`
+    static ref RELATIVE_FROM: Regex = Regex::new(r#"\bfrom\s+['"](\.{1,2}/[^'"]+)['"]"#).unwrap();
+    static ref PUB_MOD: Regex = Regex::new(r"(?m)^\s*pub\s+mod\s+([A-Za-z_][A-Za-z0-9_]*)\s*;").unwrap();
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexedDependency {
+    pub name: String,
+    pub version: Option<String>,
+    pub lang_family: String,
+    /// Package directory the files were read from
+    pub location: String,
+    pub files: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DependencyIndexResult {
+    pub indexed: Vec<IndexedDependency>,
+    /// Requested or direct dependencies whose sources or declarations were not found
+    pub not_found: Vec<String>,
+}
+
+/// A dependency located on disk, with the files to index
+struct LocatedDependency {
+    name: String,
+    version: Option<String>,
+    location: PathBuf,
+    files: Vec<PathBuf>,
+}
+
+fn read_json(path: &Path) -> Option<Value> {
+    serde_json::from_str(&fs::read_to_string(path).ok()?).ok()
+}
+
+fn read_toml(path: &Path) -> Option<toml::Table> {
+    fs::read_to_string(path).ok()?.parse().ok()
+}
+
+/// Names in package.json dependencies and devDependencies
+fn direct_js_dependencies(root: &Path) -> Vec<String> {
+    let Some(manifest) = read_json(&root.join("package.json")) else {
+        return Vec::new();
+    };
+    let names: BTreeSet<String> = ["dependencies", "devDependencies"]
+        .iter()
+        .filter_map(|field| manifest.get(field)?.as_object())
+        .flat_map(|deps| deps.keys().cloned())
+        .collect();
+    names.into_iter().collect()
+}
+
+fn is_declaration_file(path: &str) -> bool {
+    [".d.ts", ".d.mts", ".d.cts"]
+        .iter()
+        .any(|ext| path.ends_with(ext))
+}
+
+/// Declaration file named by an `exports` entry, following conditions
+fn exports_types(entry: &Value) -> Option<String> {
+    match entry {
+        Value::String(path) if is_declaration_file(path) => Some(path.clone()),
+        Value::Array(entries) => entries.iter().find_map(exports_types),
+        Value::Object(conditions) => {
+            // `"exports": { ".": ... }` or conditions directly
+            if let Some(root) = conditions.get(".") {
+                return exports_types(root);
+            }
+            ["types", "import", "require", "node", "default"]
+                .iter()
+                .find_map(|condition| exports_types(conditions.get(*condition)?))
+        }
+        _ => None,
+    }
+}
+
+/// Entry .d.ts of a package directory: exports types, then types/typings, then the
+/// declaration next to main, then index.d.ts
+fn js_types_entry(package_dir: &Path) -> Option<PathBuf> {
+    let manifest = read_json(&package_dir.join("package.json"))?;
+    let declared = manifest
+        .get("exports")
+        .and_then(exports_types)
+        .into_iter()
+        .chain(
+            ["types", "typings"]
+                .iter()
+                .filter_map(|field| manifest.get(field)?.as_str().map(String::from)),
+        )
+        .chain(
+            manifest
+                .get("main")
+                .and_then(|main| main.as_str())
+                .map(|main| format!("{}.d.ts", main.strip_suffix(".js").unwrap_or(main))),
+        )
+        .chain(std::iter::once("index.d.ts".to_string()));
+    declared
+        .map(|path| package_dir.join(path.trim_start_matches("./")))
+        .find(|path| path.is_file())
+}
+
+/// `@scope/name` types live in `@types/scope__name`
+fn types_package_name(name: &str) -> String {
+    match name.strip_prefix('@') {
+        Some(scoped) => format!("@types/{}", scoped.replacen('/', "__", 1)),
+        None => format!("@types/{}", name),
+    }
+}
+
+/// Declaration file a relative specifier in `from_file` points to
+fn resolve_declaration(from_file: &Path, specifier: &str) -> Option<PathBuf> {
+    let base = from_file.parent()?.join(specifier);
+    let base_str = base.to_string_lossy().to_string();
+    let stem = base_str
+        .strip_suffix(".js")
+        .or_else(|| base_str.strip_suffix(".mjs"))
+        .or_else(|| base_str.strip_suffix(".cjs"))
+        .unwrap_or(&base_str);
+    [
+        base_str.clone(),
+        format!("{}.d.ts", stem),
+        format!("{}.d.mts", stem),
+        format!("{}.d.cts", stem),
+        base.join("index.d.ts").to_string_lossy().to_string(),
+    ]
+    .into_iter()
+    .map(PathBuf::from)
+    .find(|path| path.is_file() && is_declaration_file(&path.to_string_lossy()))
+}
+
+/// The entry file and the declaration files it re-exports from, within the package
+fn follow_declarations(entry: PathBuf, package_dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut seen = HashSet::new();
+    let mut queue = VecDeque::from([entry]);
+    while let Some(file) = queue.pop_front() {
+        if files.len() >= MAX_FILES_PER_PACKAGE || !seen.insert(file.clone()) {
+            continue;
+        }
+        if let Ok(content) = fs::read_to_string(&file) {
+            for capture in RELATIVE_FROM.captures_iter(&content) {
+                if let Some(next) = resolve_declaration(&file, &capture[1]) {
+                    if next.starts_with(package_dir) {
+                        queue.push_back(next);
+                    }
+                }
+            }
+        }
+        files.push(file);
+    }
+    files
+}
+
+fn locate_js_dependency(root: &Path, name: &str) -> Option<LocatedDependency> {
+    let node_modules = root.join("node_modules");
+    [name.to_string(), types_package_name(name)]
+        .iter()
+        .find_map(|package| {
+            let package_dir = node_modules.join(package);
+            let entry = js_types_entry(&package_dir)?;
+            let version = read_json(&package_dir.join("package.json"))
+                .and_then(|m| m.get("version")?.as_str().map(String::from));
+            Some(LocatedDependency {
+                name: name.to_string(),
+                version,
+                files: follow_declarations(entry, &package_dir),
+                location: package_dir,
+            })
+        })
+}
+
+/// Direct registry dependencies in Cargo.toml as (name in Cargo.toml, package name).
+/// Path and git dependencies are skipped: they are not in the registry.
+fn direct_rust_dependencies(root: &Path) -> Vec<(String, String)> {
+    let Some(manifest) = read_toml(&root.join("Cargo.toml")) else {
+        return Vec::new();
+    };
+    let Some(deps) = manifest.get("dependencies").and_then(|d| d.as_table()) else {
+        return Vec::new();
+    };
+    deps.iter()
+        .filter_map(|(name, spec)| match spec {
+            toml::Value::Table(table) => {
+                if table.contains_key("path") || table.contains_key("git") {
+                    return None;
+                }
+                let package = table
+                    .get("package")
+                    .and_then(|p| p.as_str())
+                    .unwrap_or(name);
+                Some((name.clone(), package.to_string()))
+            }
+            _ => Some((name.clone(), name.clone())),
+        })
+        .collect()
+}
+
+/// Versions of each package in the nearest Cargo.lock
+fn locked_versions(root: &Path) -> HashMap<String, Vec<String>> {
+    let mut versions: HashMap<String, Vec<String>> = HashMap::new();
+    let Some(lock) = root
+        .ancestors()
+        .map(|dir| dir.join("Cargo.lock"))
+        .find(|path| path.is_file())
+        .and_then(|path| read_toml(&path))
+    else {
+        return versions;
+    };
+    let packages = lock.get("package").and_then(|p| p.as_array());
+    for package in packages.into_iter().flatten() {
+        let name = package.get("name").and_then(|n| n.as_str());
+        let version = package.get("version").and_then(|v| v.as_str());
+        if let (Some(name), Some(version)) = (name, version) {
+            versions
+                .entry(name.to_string())
+                .or_default()
+                .push(version.to_string());
+        }
+    }
+    versions
+}
+
+fn version_key(version: &str) -> Vec<u64> {
+    version
+        .split(|c: char| !c.is_ascii_digit())
+        .map_while(|part| part.parse().ok())
+        .collect()
+}
+
+pub(crate) fn cargo_home() -> Option<PathBuf> {
+    std::env::var_os("CARGO_HOME")
+        .map(PathBuf::from)
+        .or_else(|| dirs::home_dir().map(|home| home.join(".cargo")))
+}
+
+/// Source directory of `package` in the registry: the locked version when known,
+/// otherwise the newest one unpacked
+fn locate_crate_dir(
+    cargo_home: &Path,
+    package: &str,
+    locked: Option<&Vec<String>>,
+) -> Option<(PathBuf, String)> {
+    let registries: Vec<PathBuf> = fs::read_dir(cargo_home.join("registry").join("src"))
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .collect();
+    let prefix = format!("{}-", package);
+    let mut candidates: Vec<(PathBuf, String)> = registries
+        .iter()
+        .flat_map(|registry| fs::read_dir(registry).into_iter().flatten().flatten())
+        .filter_map(|entry| {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            let version = file_name.strip_prefix(&prefix)?;
+            // `serde-json-1.0.0` is not a version of `serde`
+            version
+                .starts_with(|c: char| c.is_ascii_digit())
+                .then(|| (entry.path(), version.to_string()))
+        })
+        .collect();
+    if let Some(locked) = locked {
+        candidates.retain(|(_, version)| locked.contains(version));
+    }
+    candidates.into_iter().max_by_key(|(_, v)| version_key(v))
+}
+
+/// lib.rs and the files of modules it declares `pub`, recursively
+fn rust_public_files(crate_dir: &Path) -> Vec<PathBuf> {
+    let lib = read_toml(&crate_dir.join("Cargo.toml"))
+        .and_then(|m| m.get("lib")?.get("path")?.as_str().map(String::from))
+        .map(|path| crate_dir.join(path))
+        .unwrap_or_else(|| crate_dir.join("src").join("lib.rs"));
+    let mut files = Vec::new();
+    // (file, directory its child modules live in)
+    let mut queue = VecDeque::new();
+    if lib.is_file() {
+        let dir = lib.parent().unwrap_or(crate_dir).to_path_buf();
+        queue.push_back((lib, dir));
+    }
+    while let Some((file, module_dir)) = queue.pop_front() {
+        if files.len() >= MAX_FILES_PER_PACKAGE {
+            break;
+        }
+        if let Ok(content) = fs::read_to_string(&file) {
+            for capture in PUB_MOD.captures_iter(&content) {
+                let name = &capture[1];
+                let flat = module_dir.join(format!("{}.rs", name));
+                let nested = module_dir.join(name).join("mod.rs");
+                if flat.is_file() {
+                    queue.push_back((flat, module_dir.join(name)));
+                } else if nested.is_file() {
+                    queue.push_back((nested, module_dir.join(name)));
+                }
+            }
+        }
+        files.push(file);
+    }
+    files
+}
+
+fn locate_rust_dependency(
+    cargo_home: &Path,
+    locked: &HashMap<String, Vec<String>>,
+    name: &str,
+    package: &str,
+) -> Option<LocatedDependency> {
+    let (crate_dir, version) = locate_crate_dir(cargo_home, package, locked.get(package))?;
+    let files = rust_public_files(&crate_dir);
+    (!files.is_empty()).then(|| LocatedDependency {
+        name: name.to_string(),
+        version: Some(version),
+        location: crate_dir,
+        files,
+    })
+}
+
+/// Index the public declarations of the named dependencies of `root_path`, or of all its
+/// direct dependencies. JS/TS packages are looked up in node_modules when the project has
+/// a package.json, Rust crates in the cargo registry when it has a Cargo.toml.
+pub fn index_dependencies(
+    service: &mut CodeNavigationService,
+    root_path: &str,
+    package_names: Option<&[String]>,
+    cargo_home: Option<&Path>,
+) -> DependencyIndexResult {
+    let root = Path::new(root_path);
+    let mut located: Vec<(LocatedDependency, &str, &str)> = Vec::new();
+    let mut found: HashSet<String> = HashSet::new();
+    let mut wanted: BTreeSet<String> = BTreeSet::new();
+
+    if root.join("package.json").is_file() {
+        let names = match package_names {
+            Some(names) => names.to_vec(),
+            None => direct_js_dependencies(root),
+        };
+        for name in names {
+            if let Some(dependency) = locate_js_dependency(root, &name) {
+                found.insert(name.clone());
+                located.push((dependency, "typescript", "js_family"));
+            }
+            wanted.insert(name);
+        }
+    }
+
+    if let (true, Some(cargo_home)) = (root.join("Cargo.toml").is_file(), cargo_home) {
+        let locked = locked_versions(root);
+        let direct = direct_rust_dependencies(root);
+        let crates: Vec<(String, String)> = match package_names {
+            Some(names) => names
+                .iter()
+                .map(|name| {
+                    direct
+                        .iter()
+                        .find(|(dep, package)| dep == name || package == name)
+                        .cloned()
+                        .unwrap_or_else(|| (name.clone(), name.clone()))
+                })
+                .collect(),
+            None => direct,
+        };
+        for (name, package) in crates {
+            if found.contains(&name) {
+                continue;
+            }
+            if let Some(dependency) = locate_rust_dependency(cargo_home, &locked, &name, &package) {
+                found.insert(name.clone());
+                located.push((dependency, "rust", "rust"));
+            }
+            wanted.insert(name);
+        }
+    }
+
+    let mut result = DependencyIndexResult::default();
+    for (dependency, lang_id, lang_family) in located {
+        let mut files = 0;
+        for file in &dependency.files {
+            let Ok(content) = fs::read_to_string(file) else {
+                continue;
+            };
+            let path = file.to_string_lossy();
+            match service.index_dependency_file(&path, &content, lang_id) {
+                Ok(()) => files += 1,
+                Err(e) => log::warn!("Failed to index dependency file {}: {}", path, e),
+            }
+        }
+        log::info!(
+            "Indexed {} files of dependency {} from {}",
+            files,
+            dependency.name,
+            dependency.location.display()
+        );
+        result.indexed.push(IndexedDependency {
+            name: dependency.name,
+            version: dependency.version,
+            lang_family: lang_family.to_string(),
+            location: dependency.location.to_string_lossy().to_string(),
+            files,
+        });
+    }
+    result.not_found = wanted.into_iter().filter(|n| !found.contains(n)).collect();
+    result
+}
+
+#[tauri::command]
+pub async fn code_nav_index_dependency_types(
+    state: State<'_, CodeNavState>,
+    root_path: String,
+    package_names: Option<Vec<String>>,
+) -> Result<DependencyIndexResult, String> {
+    let mut service = state
+        .0
+        .write()
+        .map_err(|e| format!("Failed to acquire write lock: {}", e))?;
+    Ok(index_dependencies(
+        &mut service,
+        &root_path,
+        package_names.as_deref(),
+        cargo_home().as_deref(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write(root: &Path, rel: &str, content: &str) -> String {
+        let path = root.join(rel);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, content).unwrap();
+        path.to_string_lossy().to_string()
+    }
+
+    fn js_fixture() -> TempDir {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        write(
+            root,
+            "package.json",
+            r#"{"dependencies": {"tiny-debounce": "^1.0.0", "untyped": "^2.0.0"}}"#,
+        );
+        write(
+            root,
+            "node_modules/tiny-debounce/package.json",
+            r#"{"name": "tiny-debounce", "version": "1.2.0", "main": "dist/index.js",
+                "exports": {".": {"import": {"types": "./dist/index.d.mts"}, "require": "./dist/index.js"}}}"#,
+        );
+        write(
+            root,
+            "node_modules/tiny-debounce/dist/index.d.mts",
+            "export { throttle } from './throttle.js';\nexport declare function debounce(fn: () => void, wait?: number): () => void;\nexport interface DebounceOptions { leading: boolean }\n",
+        );
+        write(
+            root,
+            "node_modules/tiny-debounce/dist/throttle.d.ts",
+            "export declare function throttle(fn: () => void): () => void;\n",
+        );
+        write(
+            root,
+            "node_modules/untyped/package.json",
+            r#"{"name": "untyped", "main": "index.js"}"#,
+        );
+        write(
+            root,
+            "node_modules/untyped/index.js",
+            "module.exports = {};\n",
+        );
+        write(
+            root,
+            "node_modules/@types/untyped/package.json",
+            r#"{"name": "@types/untyped", "version": "2.0.3", "types": "index.d.ts"}"#,
+        );
+        write(
+            root,
+            "node_modules/@types/untyped/index.d.ts",
+            "export declare class Untyped {}\n",
+        );
+        temp
+    }
+
+    #[test]
+    fn test_index_js_dependency_declarations() {
+        let temp = js_fixture();
+        let root = temp.path();
+        let mut service = CodeNavigationService::new();
+        let content = "export function debounce() {}\n";
+        let app = write(root, "src/app.ts", content);
+        service.index_file(&app, content, "typescript").unwrap();
+
+        let result = index_dependencies(&mut service, root.to_str().unwrap(), None, None);
+        let indexed: Vec<_> = result
+            .indexed
+            .iter()
+            .map(|d| (d.name.as_str(), d.version.as_deref(), d.files))
+            .collect();
+        // The entry and the file it re-exports from; @types fills in for untyped packages
+        assert_eq!(
+            indexed,
+            [
+                ("tiny-debounce", Some("1.2.0"), 2),
+                ("untyped", Some("2.0.3"), 1)
+            ]
+        );
+        assert!(result.not_found.is_empty());
+
+        // Project lookups are unchanged; dependency lookups return the declarations
+        let local = service.find_definition("debounce", "js_family");
+        assert_eq!(local.len(), 1);
+        assert!(!local[0].dependency);
+        let dependency = service.find_dependency_definition("debounce", "js_family");
+        assert_eq!(dependency.len(), 1);
+        assert!(dependency[0].dependency && dependency[0].exported);
+        assert!(dependency[0].file_path.ends_with("index.d.mts"));
+        assert_eq!(
+            service.find_dependency_definition("throttle", "js_family")[0].kind,
+            "function"
+        );
+        assert_eq!(
+            service.find_dependency_definition("Untyped", "js_family")[0].kind,
+            "class"
+        );
+        assert!(service.find_definition("throttle", "js_family").is_empty());
+        assert_eq!(
+            service.has_definitions(&["throttle".to_string()], "js_family"),
+            [false]
+        );
+
+        let named = index_dependencies(
+            &mut service,
+            root.to_str().unwrap(),
+            Some(&["tiny-debounce".to_string(), "missing".to_string()]),
+            None,
+        );
+        assert_eq!(named.indexed.len(), 1);
+        assert_eq!(named.not_found, ["missing"]);
+    }
+
+    #[test]
+    fn test_index_rust_dependency_sources() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path().join("project");
+        let cargo_home = temp.path().join("cargo");
+        write(
+            &root,
+            "Cargo.toml",
+            "[package]\nname = \"app\"\n\n[dependencies]\ntiny-log = \"0.3\"\nlocal = { path = \"../local\" }\n",
+        );
+        write(
+            &root,
+            "Cargo.lock",
+            "[[package]]\nname = \"tiny-log\"\nversion = \"0.3.1\"\n",
+        );
+        let registry = "registry/src/index.crates.io-6f17d22bba15001f";
+        write(
+            &cargo_home,
+            &format!("{}/tiny-log-0.4.0/src/lib.rs", registry),
+            "pub struct NewerLogger;\n",
+        );
+        let crate_dir = format!("{}/tiny-log-0.3.1", registry);
+        write(
+            &cargo_home,
+            &format!("{}/src/lib.rs", crate_dir),
+            "pub mod level;\nmod internal;\npub fn init() {}\n",
+        );
+        write(
+            &cargo_home,
+            &format!("{}/src/level/mod.rs", crate_dir),
+            "pub mod filter;\npub enum Level { Info }\n",
+        );
+        write(
+            &cargo_home,
+            &format!("{}/src/level/filter.rs", crate_dir),
+            "pub struct Filter;\n",
+        );
+        write(
+            &cargo_home,
+            &format!("{}/src/internal.rs", crate_dir),
+            "pub struct Hidden;\n",
+        );
+
+        let mut service = CodeNavigationService::new();
+        let result = index_dependencies(
+            &mut service,
+            root.to_str().unwrap(),
+            None,
+            Some(&cargo_home),
+        );
+        assert_eq!(result.indexed.len(), 1);
+        // The locked version, not the newest one unpacked; private modules are skipped
+        assert_eq!(result.indexed[0].version.as_deref(), Some("0.3.1"));
+        assert_eq!(result.indexed[0].files, 3);
+        for name in ["init", "Level", "Filter"] {
+            assert_eq!(
+                service.find_dependency_definition(name, "rust").len(),
+                1,
+                "{}",
+                name
+            );
+        }
+        assert!(service
+            .find_dependency_definition("Hidden", "rust")
+            .is_empty());
+        assert!(service
+            .find_dependency_definition("NewerLogger", "rust")
+            .is_empty());
+        assert!(service.find_definition("init", "rust").is_empty());
+    }
+}
@@ -0,0 +1,333 @@
+// Directory size module
+// Recursive folder sizes for the explorer's hover cards and "how big is src/" questions.
+// Subtrees are summed in parallel and symlinked directories are followed at most once, so
+// link cycles terminate. Results are cached briefly per path and directory mtime.
+
+use crate::cancellation::{self, CancellationToken};
+use crate::concurrency::{self, OperationClass};
+use crate::constants::should_exclude_dir;
+use ignore::gitignore::Gitignore;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+
+/// How long a computed size is reused while the directory's mtime is unchanged
+const CACHE_TTL: Duration = Duration::from_secs(30);
+const MAX_CACHE_ENTRIES: usize = 512;
+/// Top-level subdirectories summed per parallel chunk
+const SUBDIR_CHUNK: usize = 8;
+
+lazy_static::lazy_static! {
+    static ref SIZE_CACHE: Mutex<HashMap<(String, bool), CachedSize>> = Mutex::new(HashMap::new());
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct DirectorySize {
+    pub path: String,
+    pub total_bytes: u64,
+    pub file_count: u64,
+    /// Cancelled before the walk finished; the totals only cover what was visited
+    pub truncated: bool,
+    /// Set when the path could not be read at all
+    pub error: Option<String>,
+}
+
+struct CachedSize {
+    modified: SystemTime,
+    computed_at: Instant,
+    size: DirectorySize,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct Totals {
+    bytes: u64,
+    files: u64,
+}
+
+impl Totals {
+    fn add(self, other: Totals) -> Totals {
+        Totals {
+            bytes: self.bytes + other.bytes,
+            files: self.files + other.files,
+        }
+    }
+}
+
+/// One size computation. Shared across the rayon workers walking its subtrees.
+struct SizeWalk<'a> {
+    /// Present when ignores are respected
+    gitignore: Option<Gitignore>,
+    /// Canonical directories already entered, so symlink cycles are walked once
+    visited: Mutex<HashSet<PathBuf>>,
+    token: Option<&'a CancellationToken>,
+    cancelled: AtomicBool,
+}
+
+impl SizeWalk<'_> {
+    fn is_cancelled(&self) -> bool {
+        if self.token.is_some_and(|token| token.is_cancelled()) {
+            self.cancelled.store(true, Ordering::Relaxed);
+        }
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Claim `dir` for this walk; false when it (or a link to it) was already entered
+    fn enter(&self, dir: &Path) -> bool {
+        let canonical = dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf());
+        self.visited.lock().unwrap().insert(canonical)
+    }
+
+    fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        let Some(gitignore) = &self.gitignore else {
+            return false;
+        };
+        if is_dir
+            && path
+                .file_name()
+                .is_some_and(|name| should_exclude_dir(&name.to_string_lossy()))
+        {
+            return true;
+        }
+        gitignore.matched(path, is_dir).is_ignore()
+    }
+
+    fn dir_totals(&self, dir: &Path) -> Totals {
+        if self.is_cancelled() || !self.enter(dir) {
+            return Totals::default();
+        }
+        let Ok(entries) = fs::read_dir(dir) else {
+            return Totals::default();
+        };
+
+        let mut totals = Totals::default();
+        let mut subdirs = Vec::new();
+        for entry in entries.flatten() {
+            let path = entry.path();
+            // fs::metadata follows symlinks; `enter` keeps linked directories from looping
+            let Ok(metadata) = fs::metadata(&path) else {
+                continue;
+            };
+            if metadata.is_dir() {
+                if !self.is_ignored(&path, true) {
+                    subdirs.push(path);
+                }
+            } else if metadata.is_file() && !self.is_ignored(&path, false) {
+                totals.bytes += metadata.len();
+                totals.files += 1;
+            }
+        }
+
+        let sum = |dirs: &[PathBuf]| {
+            dirs.par_iter()
+                .map(|subdir| self.dir_totals(subdir))
+                .reduce(Totals::default, Totals::add)
+        };
+        if rayon::current_thread_index().is_some() {
+            return sum(&subdirs).add(totals);
+        }
+        // The top of the walk runs outside the pool: hand it out in chunks, pausing between
+        // them while interactive work runs
+        subdirs.chunks(SUBDIR_CHUNK).fold(totals, |totals, chunk| {
+            concurrency::governor().yield_to_interactive();
+            totals.add(sum(chunk))
+        })
+    }
+}
+
+/// .gitignore of the repository containing `path`, or of `path` itself outside a repository
+fn enclosing_gitignore(path: &Path) -> Gitignore {
+    let root = path
+        .ancestors()
+        .find(|dir| dir.join(".git").exists())
+        .unwrap_or(path);
+    Gitignore::new(root.join(".gitignore")).0
+}
+
+fn cached_size(key: &(String, bool), modified: SystemTime) -> Option<DirectorySize> {
+    let cache = SIZE_CACHE.lock().unwrap();
+    cache
+        .get(key)
+        .filter(|cached| cached.modified == modified && cached.computed_at.elapsed() < CACHE_TTL)
+        .map(|cached| cached.size.clone())
+}
+
+fn store_size(key: (String, bool), modified: SystemTime, size: &DirectorySize) {
+    let mut cache = SIZE_CACHE.lock().unwrap();
+    cache.retain(|_, cached| cached.computed_at.elapsed() < CACHE_TTL);
+    if cache.len() >= MAX_CACHE_ENTRIES {
+        return;
+    }
+    cache.insert(
+        key,
+        CachedSize {
+            modified,
+            computed_at: Instant::now(),
+            size: size.clone(),
+        },
+    );
+}
+
+/// Total bytes and file count under `path`, optionally skipping the shared excluded
+/// directories and gitignored entries. The requested path itself is never skipped.
+pub fn calculate_directory_size(
+    path: &Path,
+    respect_ignores: bool,
+    token: Option<&CancellationToken>,
+) -> DirectorySize {
+    let path_str = path.to_string_lossy().to_string();
+    let mut size = DirectorySize {
+        path: path_str.clone(),
+        total_bytes: 0,
+        file_count: 0,
+        truncated: false,
+        error: None,
+    };
+
+    let metadata = match fs::metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) => {
+            size.error = Some(format!("Failed to read {}: {}", path_str, e));
+            return size;
+        }
+    };
+    if metadata.is_file() {
+        size.total_bytes = metadata.len();
+        size.file_count = 1;
+        return size;
+    }
+
+    let key = (path_str, respect_ignores);
+    let modified = metadata.modified().ok();
+    if let Some(cached) = modified.and_then(|modified| cached_size(&key, modified)) {
+        return cached;
+    }
+
+    let walk = SizeWalk {
+        gitignore: respect_ignores.then(|| enclosing_gitignore(path)),
+        visited: Mutex::new(HashSet::new()),
+        token,
+        cancelled: AtomicBool::new(false),
+    };
+    let totals = walk.dir_totals(path);
+    size.total_bytes = totals.bytes;
+    size.file_count = totals.files;
+    size.truncated = walk.is_cancelled();
+
+    if let (Some(modified), false) = (modified, size.truncated) {
+        store_size(key, modified, &size);
+    }
+    size
+}
+
+/// Recursive sizes for several paths. With an `operation_id`, the work registers a token
+/// in the cancellation registry and can be stopped through activity_cancel; results
+/// computed after that are marked truncated.
+#[tauri::command]
+pub async fn calculate_directory_sizes(
+    paths: Vec<String>,
+    respect_ignores: bool,
+    operation_id: Option<String>,
+) -> Result<Vec<DirectorySize>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let _permit = concurrency::governor().acquire(
+            OperationClass::Background,
+            &format!("Directory sizes ({} paths)", paths.len()),
+            None,
+        );
+        let token = operation_id.as_deref().map(cancellation::register);
+        let sizes = paths
+            .iter()
+            .map(|path| calculate_directory_size(Path::new(path), respect_ignores, token.as_ref()))
+            .collect();
+        if let Some(id) = &operation_id {
+            cancellation::unregister(id);
+        }
+        sizes
+    })
+    .await
+    .map_err(|e| format!("Directory size task failed: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write(dir: &Path, rel: &str, len: usize) {
+        let path = dir.join(rel);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, vec![b'x'; len]).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_loop_is_walked_once() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        write(root, "a/file.txt", 10);
+        write(root, "b.txt", 5);
+        std::os::unix::fs::symlink(root, root.join("a/loop")).unwrap();
+        std::os::unix::fs::symlink(root.join("a"), root.join("a-link")).unwrap();
+
+        let size = calculate_directory_size(root, false, None);
+        assert_eq!(size.file_count, 2);
+        assert_eq!(size.total_bytes, 15);
+        assert!(!size.truncated);
+        assert!(size.error.is_none());
+    }
+
+    #[test]
+    fn test_respecting_ignores_skips_heavy_directories() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        fs::create_dir(root.join(".git")).unwrap();
+        fs::write(root.join(".gitignore"), "generated/\n*.log\n").unwrap();
+        write(root, "src/main.rs", 3);
+        write(root, "src/debug.log", 50);
+        write(root, "node_modules/pkg/index.js", 1000);
+        write(root, "generated/out.bin", 500);
+
+        let all = calculate_directory_size(root, false, None);
+        assert_eq!(all.total_bytes, 3 + 50 + 1000 + 500 + 17);
+        assert_eq!(all.file_count, 5);
+
+        let respected = calculate_directory_size(root, true, None);
+        assert_eq!(respected.total_bytes, 3 + 17);
+        assert_eq!(respected.file_count, 2);
+
+        // A subdirectory still picks up the repository's .gitignore
+        let src = calculate_directory_size(&root.join("src"), true, None);
+        assert_eq!(src.file_count, 1);
+        assert_eq!(src.total_bytes, 3);
+    }
+
+    #[test]
+    fn test_cancelled_walk_is_truncated_and_not_cached() {
+        let temp = TempDir::new().unwrap();
+        write(temp.path(), "a/b/c.txt", 7);
+        let token = CancellationToken::new();
+        token.cancel();
+
+        let cancelled = calculate_directory_size(temp.path(), false, Some(&token));
+        assert!(cancelled.truncated);
+        assert_eq!(cancelled.file_count, 0);
+
+        let full = calculate_directory_size(temp.path(), false, None);
+        assert!(!full.truncated);
+        assert_eq!(full.total_bytes, 7);
+    }
+
+    #[test]
+    fn test_missing_path_reports_error() {
+        let temp = TempDir::new().unwrap();
+        let size = calculate_directory_size(&temp.path().join("missing"), true, None);
+        assert!(size.error.is_some());
+        assert_eq!(size.file_count, 0);
+    }
+}
@@ -1,10 +1,25 @@
+use crate::path_wire::{self, RawPath};
+use crate::walk_depth::{self, DepthReport, DepthTracker};
+use icu_collator::{Collator, CollatorOptions, Numeric, Strength};
+use icu_locid::Locale;
 use ignore::gitignore::{Gitignore, GitignoreBuilder};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::cmp::Ordering as CmpOrdering;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::iter::Peekable;
 use std::path::Path;
+use std::str::Chars;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
 use std::time::{SystemTime, UNIX_EPOCH};
 
+/// Maximum number of tree snapshots kept for diffing; the oldest is dropped first
+const MAX_SNAPSHOTS: usize = 16;
+/// Snapshot lifetime in seconds
+const SNAPSHOT_TTL: u64 = 600;
+
+static SNAPSHOT_COUNTER: AtomicU64 = AtomicU64::new(0);
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FileNode {
     pub name: String,
@@ -16,6 +31,240 @@ pub struct FileNode {
     pub modified_time: Option<u64>,
     pub size: Option<u64>,
     pub is_git_ignored: Option<bool>,
+    /// Set on the root node when the tree was built with a snapshot for later diffing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snapshot_id: Option<String>,
+    #[serde(flatten)]
+    pub raw_path: RawPath,
+    /// Why a directory could not be listed; its children are then unknown
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<NodeError>,
+    /// Set on the root node: every path that could not be read while building the tree
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub warnings: Option<Vec<TreeWarning>>,
+    /// Set on the root node: directories left lazy because they were at the depth limit
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub depth_report: Option<DepthReport>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NodeErrorKind {
+    PermissionDenied,
+    Io,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NodeError {
+    pub kind: NodeErrorKind,
+    pub message: String,
+}
+
+impl NodeError {
+    fn from_io(error: &std::io::Error) -> Self {
+        let kind = match error.kind() {
+            std::io::ErrorKind::PermissionDenied => NodeErrorKind::PermissionDenied,
+            _ => NodeErrorKind::Io,
+        };
+        Self {
+            kind,
+            message: error.to_string(),
+        }
+    }
+}
+
+/// A path left out of a tree or shown with an error
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TreeWarning {
+    pub path: String,
+    #[serde(flatten)]
+    pub error: NodeError,
+}
+
+/// The children of a lazily loaded directory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DirectoryChildren {
+    pub children: Vec<FileNode>,
+    /// Every path that could not be read while listing them
+    pub warnings: Vec<TreeWarning>,
+}
+
+/// What the children of a directory are ordered by
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TreeSortMode {
+    /// By name, with runs of digits compared by value so file2 comes before file10
+    #[default]
+    NameNatural,
+    NameLexicographic,
+    ModifiedTime,
+    Size,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortDirection {
+    #[default]
+    Asc,
+    Desc,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TreeSortOptions {
+    pub mode: TreeSortMode,
+    pub direction: SortDirection,
+    /// Keep directories above files whatever the mode and direction
+    pub directories_first: bool,
+    /// BCP 47 tag to collate names for, e.g. "sv"; the root collation when unset
+    pub locale: Option<String>,
+}
+
+impl Default for TreeSortOptions {
+    fn default() -> Self {
+        Self {
+            mode: TreeSortMode::default(),
+            direction: SortDirection::default(),
+            directories_first: true,
+            locale: None,
+        }
+    }
+}
+
+/// Compares siblings under one set of sort options. The collator is built once per
+/// listing; without one, names are compared by `natural_cmp` or by lowercased name.
+struct ChildOrder {
+    options: TreeSortOptions,
+    collator: Option<Collator>,
+}
+
+impl ChildOrder {
+    fn new(options: &TreeSortOptions) -> Self {
+        let numeric = options.mode != TreeSortMode::NameLexicographic;
+        Self {
+            options: options.clone(),
+            collator: Self::build_collator(options.locale.as_deref(), numeric),
+        }
+    }
+
+    fn build_collator(locale: Option<&str>, numeric: bool) -> Option<Collator> {
+        let locale = match locale.map(str::parse::<Locale>) {
+            Some(Ok(locale)) => locale,
+            Some(Err(e)) => {
+                log::debug!("Invalid collation locale {:?}: {}", locale, e);
+                return None;
+            }
+            None => Locale::UND,
+        };
+        let mut options = CollatorOptions::new();
+        // Case only decides ties, as in the fallback comparison
+        options.strength = Some(Strength::Secondary);
+        if numeric {
+            options.numeric = Some(Numeric::On);
+        }
+        Collator::try_new(&(&locale).into(), options)
+            .map_err(|e| log::debug!("Cannot build a collator for {}: {}", locale, e))
+            .ok()
+    }
+
+    fn compare_names(&self, a: &str, b: &str) -> CmpOrdering {
+        let fallback = || match self.options.mode {
+            TreeSortMode::NameLexicographic => a.to_lowercase().cmp(&b.to_lowercase()),
+            _ => natural_cmp(a, b),
+        };
+        match &self.collator {
+            Some(collator) => collator.compare(a, b).then_with(fallback),
+            None => fallback(),
+        }
+    }
+
+    fn compare(&self, a: &FileNode, b: &FileNode) -> CmpOrdering {
+        if self.options.directories_first && a.is_directory != b.is_directory {
+            return if a.is_directory {
+                CmpOrdering::Less
+            } else {
+                CmpOrdering::Greater
+            };
+        }
+        let ordering = match self.options.mode {
+            TreeSortMode::NameNatural | TreeSortMode::NameLexicographic => {
+                self.compare_names(&a.name, &b.name)
+            }
+            TreeSortMode::ModifiedTime => a
+                .modified_time
+                .cmp(&b.modified_time)
+                .then_with(|| self.compare_names(&a.name, &b.name)),
+            TreeSortMode::Size => a
+                .size
+                .cmp(&b.size)
+                .then_with(|| self.compare_names(&a.name, &b.name)),
+        };
+        match self.options.direction {
+            SortDirection::Asc => ordering,
+            SortDirection::Desc => ordering.reverse(),
+        }
+    }
+
+    /// Stable, so entries that compare equal keep the order they were listed in
+    fn sort(&self, children: &mut [FileNode]) {
+        children.sort_by(|a, b| self.compare(a, b));
+    }
+}
+
+/// Compare names case-insensitively, with runs of ASCII digits compared by value. Of two
+/// names that are otherwise equal, the one whose first differing number has fewer
+/// leading zeros comes first.
+fn natural_cmp(a: &str, b: &str) -> CmpOrdering {
+    fn digit_run(chars: &mut Peekable<Chars>) -> String {
+        let mut run = String::new();
+        while let Some(c) = chars.next_if(char::is_ascii_digit) {
+            run.push(c);
+        }
+        run
+    }
+
+    let mut a_chars = a.chars().peekable();
+    let mut b_chars = b.chars().peekable();
+    let mut zeros = CmpOrdering::Equal;
+    loop {
+        match (a_chars.peek().copied(), b_chars.peek().copied()) {
+            (None, None) => return zeros,
+            (None, Some(_)) => return CmpOrdering::Less,
+            (Some(_), None) => return CmpOrdering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let x_run = digit_run(&mut a_chars);
+                let y_run = digit_run(&mut b_chars);
+                let x_value = x_run.trim_start_matches('0');
+                let y_value = y_run.trim_start_matches('0');
+                let ordering = x_value
+                    .len()
+                    .cmp(&y_value.len())
+                    .then_with(|| x_value.cmp(y_value));
+                if ordering != CmpOrdering::Equal {
+                    return ordering;
+                }
+                zeros = zeros.then(x_run.len().cmp(&y_run.len()));
+            }
+            (Some(x), Some(y)) => {
+                let ordering = x.to_lowercase().cmp(y.to_lowercase());
+                if ordering != CmpOrdering::Equal {
+                    return ordering;
+                }
+                a_chars.next();
+                b_chars.next();
+            }
+        }
+    }
+}
+
+/// Trees and lazily loaded listings are cached per ordering, so changing the sort never
+/// serves a listing in the old order
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct CacheKey {
+    dir: String,
+    /// The children of `dir` from load_directory_children, rather than the tree rooted there
+    children: bool,
+    sort: TreeSortOptions,
 }
 
 #[derive(Debug, Clone)]
@@ -24,9 +273,45 @@ struct CachedEntry {
     cached_at: u64,
 }
 
+/// Metadata recorded per path in a snapshot
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct SnapshotEntry {
+    modified_ms: u64,
+    size: u64,
+    is_dir: bool,
+    /// Directory whose children were not loaded, so only its own mtime can show changes
+    is_lazy: bool,
+}
+
+/// Compact record of the paths a built tree contained
+struct TreeSnapshot {
+    root_path: String,
+    max_depth: usize,
+    entries: HashMap<String, SnapshotEntry>,
+    created_at: u64,
+}
+
+/// Changes under one directory since a snapshot
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DirectoryChangeGroup {
+    pub parent: String,
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub modified: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DirectoryTreeDiff {
+    /// Snapshot of the current state, to diff against next time
+    pub snapshot_id: String,
+    /// Changes grouped by parent directory, sorted by parent path
+    pub groups: Vec<DirectoryChangeGroup>,
+}
+
 pub struct DirectoryTreeBuilder {
-    cache: Arc<Mutex<HashMap<String, CachedEntry>>>,
+    cache: Arc<Mutex<HashMap<CacheKey, CachedEntry>>>,
     cache_ttl: u64, // Cache TTL in seconds
+    snapshots: Mutex<HashMap<String, TreeSnapshot>>,
 }
 
 impl DirectoryTreeBuilder {
@@ -34,6 +319,7 @@ impl DirectoryTreeBuilder {
         Self {
             cache: Arc::new(Mutex::new(HashMap::new())),
             cache_ttl: 30, // 30 seconds cache
+            snapshots: Mutex::new(HashMap::new()),
         }
     }
 
@@ -60,7 +346,7 @@ impl DirectoryTreeBuilder {
     }
 
     fn normalize_path(path: &Path) -> String {
-        path.to_string_lossy().replace('\\', "/")
+        path_wire::encode(path).replace('\\', "/")
     }
 
     /// Build a gitignore matcher for the given root path
@@ -78,14 +364,19 @@ impl DirectoryTreeBuilder {
         &self,
         root_path: &str,
         max_immediate_depth: usize,
+        sort: &TreeSortOptions,
     ) -> Result<FileNode, String> {
-        let root = Path::new(root_path);
+        let root = &path_wire::resolve(root_path);
         if !root.exists() {
             return Err("Directory does not exist".to_string());
         }
 
         let now = Self::get_current_timestamp();
-        let path_key = Self::normalize_path(root);
+        let path_key = CacheKey {
+            dir: Self::normalize_path(root),
+            children: false,
+            sort: sort.clone(),
+        };
 
         // Check cache first
         if let Ok(cache) = self.cache.lock() {
@@ -100,7 +391,20 @@ impl DirectoryTreeBuilder {
         let gitignore = Self::build_gitignore_matcher(root);
 
         // Build tree with immediate depth loading
-        let node = self.build_node_recursive(root, 0, max_immediate_depth, now, &gitignore)?;
+        let depth = DepthTracker::new(max_immediate_depth);
+        let mut warnings = Vec::new();
+        let mut node = self.build_node_recursive(
+            root,
+            0,
+            max_immediate_depth,
+            now,
+            &gitignore,
+            &ChildOrder::new(sort),
+            &depth,
+            &mut warnings,
+        )?;
+        node.warnings = (!warnings.is_empty()).then_some(warnings);
+        node.depth_report = Some(depth.report());
 
         // Cache the result
         if let Ok(mut cache) = self.cache.lock() {
@@ -116,6 +420,9 @@ impl DirectoryTreeBuilder {
         Ok(node)
     }
 
+    /// Build the node for `path`. Only an unreadable root fails; a directory below it that
+    /// can't be listed becomes a node with an error, and is recorded in `warnings` along
+    /// with entries that could not be read at all.
     fn build_node_recursive(
         &self,
         path: &Path,
@@ -123,12 +430,12 @@ impl DirectoryTreeBuilder {
         max_depth: usize,
         timestamp: u64,
         gitignore: &Option<Gitignore>,
+        order: &ChildOrder,
+        depth: &DepthTracker,
+        warnings: &mut Vec<TreeWarning>,
     ) -> Result<FileNode, String> {
-        let name = path
-            .file_name()
-            .unwrap_or_default()
-            .to_string_lossy()
-            .to_string();
+        let name = path_wire::encode_name(path.file_name().unwrap_or_default());
+        let raw_path = RawPath::of(path);
 
         let path_str = Self::normalize_path(path);
         let (modified_time, size) = Self::get_file_metadata(path).unwrap_or((timestamp, 0));
@@ -150,23 +457,49 @@ impl DirectoryTreeBuilder {
                 modified_time: Some(modified_time),
                 size: Some(size),
                 is_git_ignored: Some(is_ignored),
+                snapshot_id: None,
+                raw_path,
+                error: None,
+                warnings: None,
+                depth_report: None,
             });
         }
 
         // Handle directory
         let entries = match std::fs::read_dir(path) {
-            Ok(entries) => {
-                let mut items = Vec::new();
-                for entry in entries {
-                    if let Ok(entry) = entry {
-                        items.push(entry);
-                    }
-                }
-                items
+            Ok(entries) => Self::readable_entries(entries, &path_str, warnings),
+            // A denied project root would otherwise show up as an empty project
+            Err(e) if current_depth == 0 && e.kind() == std::io::ErrorKind::PermissionDenied => {
+                return Err(crate::path_access::permission_denied(path));
             }
-            Err(_) => {
+            Err(_) if current_depth == 0 => {
                 return Err(format!("Failed to read directory: {}", path_str));
             }
+            // Below the root the directory stays visible, marked with why it is empty
+            Err(e) => {
+                let error = NodeError::from_io(&e);
+                log::debug!("Cannot list {}: {}", path_str, error.message);
+                warnings.push(TreeWarning {
+                    path: path_str.clone(),
+                    error: error.clone(),
+                });
+                return Ok(FileNode {
+                    name,
+                    path: path_str,
+                    is_directory: true,
+                    children: None,
+                    is_lazy_loaded: None,
+                    has_children: None,
+                    modified_time: Some(modified_time),
+                    size: Some(size),
+                    is_git_ignored: Some(is_ignored),
+                    snapshot_id: None,
+                    raw_path,
+                    error: Some(error),
+                    warnings: None,
+                    depth_report: None,
+                });
+            }
         };
 
         // If we're at max depth or directory is too large, use lazy loading
@@ -174,6 +507,7 @@ impl DirectoryTreeBuilder {
 
         if should_lazy_load {
             let has_children = !entries.is_empty();
+            depth.observe_listed(current_depth, has_children);
 
             return Ok(FileNode {
                 name,
@@ -185,6 +519,11 @@ impl DirectoryTreeBuilder {
                 modified_time: Some(modified_time),
                 size: Some(size),
                 is_git_ignored: Some(is_ignored),
+                snapshot_id: None,
+                raw_path,
+                error: None,
+                warnings: None,
+                depth_report: None,
             });
         }
 
@@ -200,24 +539,21 @@ impl DirectoryTreeBuilder {
                 continue;
             }
 
-            match self.build_node_recursive(
+            if let Ok(child) = self.build_node_recursive(
                 &entry_path,
                 current_depth + 1,
                 max_depth,
                 timestamp,
                 gitignore,
+                order,
+                depth,
+                warnings,
             ) {
-                Ok(child) => children.push(child),
-                Err(_) => {} // Skip failed entries
+                children.push(child);
             }
         }
 
-        // Sort children: directories first, then files, both alphabetically
-        children.sort_by(|a, b| match (a.is_directory, b.is_directory) {
-            (true, false) => std::cmp::Ordering::Less,
-            (false, true) => std::cmp::Ordering::Greater,
-            _ => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
-        });
+        order.sort(&mut children);
 
         Ok(FileNode {
             name,
@@ -229,9 +565,273 @@ impl DirectoryTreeBuilder {
             modified_time: Some(modified_time),
             size: Some(size),
             is_git_ignored: Some(is_ignored),
+            snapshot_id: None,
+            raw_path,
+            error: None,
+            warnings: None,
+            depth_report: None,
         })
     }
 
+    /// The entries of a listing; entries that fail to read are recorded against the
+    /// directory instead of disappearing silently
+    fn readable_entries(
+        entries: std::fs::ReadDir,
+        dir_path: &str,
+        warnings: &mut Vec<TreeWarning>,
+    ) -> Vec<std::fs::DirEntry> {
+        let mut items = Vec::new();
+        for entry in entries {
+            match entry {
+                Ok(entry) => items.push(entry),
+                Err(e) => warnings.push(TreeWarning {
+                    path: dir_path.to_string(),
+                    error: NodeError::from_io(&e),
+                }),
+            }
+        }
+        items
+    }
+
+    /// Build a fresh tree (bypassing the cache, so it matches the disk) and record a snapshot
+    /// of it; the snapshot id is set on the returned root node
+    pub fn build_directory_tree_with_snapshot(
+        &self,
+        root_path: &str,
+        max_immediate_depth: usize,
+        sort: &TreeSortOptions,
+    ) -> Result<FileNode, String> {
+        let root = &path_wire::resolve(root_path);
+        if !root.exists() {
+            return Err("Directory does not exist".to_string());
+        }
+
+        let now = Self::get_current_timestamp();
+        let gitignore = Self::build_gitignore_matcher(root);
+        let depth = DepthTracker::new(max_immediate_depth);
+        let mut warnings = Vec::new();
+        let mut node = self.build_node_recursive(
+            root,
+            0,
+            max_immediate_depth,
+            now,
+            &gitignore,
+            &ChildOrder::new(sort),
+            &depth,
+            &mut warnings,
+        )?;
+        node.warnings = (!warnings.is_empty()).then_some(warnings);
+        node.depth_report = Some(depth.report());
+
+        if let Ok(mut cache) = self.cache.lock() {
+            cache.insert(
+                CacheKey {
+                    dir: Self::normalize_path(root),
+                    children: false,
+                    sort: sort.clone(),
+                },
+                CachedEntry {
+                    node: node.clone(),
+                    cached_at: now,
+                },
+            );
+        }
+
+        node.snapshot_id = Some(self.store_snapshot(root_path, max_immediate_depth, &node, now));
+        Ok(node)
+    }
+
+    /// Compare the disk against a stored snapshot. Paths under an added or removed
+    /// directory are implied by it and not listed, so a directory rename shows up as the
+    /// old directory removed and the new one added.
+    pub fn diff_directory_tree(
+        &self,
+        root_path: &str,
+        snapshot_id: &str,
+    ) -> Result<DirectoryTreeDiff, String> {
+        let now = Self::get_current_timestamp();
+        let (previous, max_depth) = {
+            let mut snapshots = self.snapshots.lock().map_err(|e| e.to_string())?;
+            snapshots.retain(|_, snapshot| now.saturating_sub(snapshot.created_at) <= SNAPSHOT_TTL);
+            let snapshot = snapshots
+                .get(snapshot_id)
+                .ok_or_else(|| format!("Unknown or expired tree snapshot: {}", snapshot_id))?;
+            if snapshot.root_path != root_path {
+                return Err(format!(
+                    "Snapshot {} belongs to {}, not {}",
+                    snapshot_id, snapshot.root_path, root_path
+                ));
+            }
+            (snapshot.entries.clone(), snapshot.max_depth)
+        };
+
+        // Order doesn't matter to the diff
+        let current = self.build_directory_tree_with_snapshot(
+            root_path,
+            max_depth,
+            &TreeSortOptions::default(),
+        )?;
+        let current_id = current.snapshot_id.clone().unwrap_or_default();
+        let mut current_entries = HashMap::new();
+        Self::collect_snapshot_entries(&current, &mut current_entries);
+
+        Ok(DirectoryTreeDiff {
+            snapshot_id: current_id,
+            groups: Self::diff_entries(&previous, &current_entries),
+        })
+    }
+
+    fn store_snapshot(
+        &self,
+        root_path: &str,
+        max_depth: usize,
+        node: &FileNode,
+        now: u64,
+    ) -> String {
+        let mut entries = HashMap::new();
+        Self::collect_snapshot_entries(node, &mut entries);
+        let id = format!(
+            "tree-{}-{}",
+            now,
+            SNAPSHOT_COUNTER.fetch_add(1, Ordering::Relaxed)
+        );
+
+        if let Ok(mut snapshots) = self.snapshots.lock() {
+            snapshots.retain(|_, snapshot| now.saturating_sub(snapshot.created_at) <= SNAPSHOT_TTL);
+            while snapshots.len() >= MAX_SNAPSHOTS {
+                let oldest = snapshots
+                    .iter()
+                    .min_by_key(|(id, snapshot)| (snapshot.created_at, id.to_string()))
+                    .map(|(id, _)| id.clone());
+                match oldest {
+                    Some(oldest) => snapshots.remove(&oldest),
+                    None => break,
+                };
+            }
+            snapshots.insert(
+                id.clone(),
+                TreeSnapshot {
+                    root_path: root_path.to_string(),
+                    max_depth,
+                    entries,
+                    created_at: now,
+                },
+            );
+        }
+        id
+    }
+
+    /// Record every node of a built tree with millisecond mtimes, since the node's own
+    /// modified_time only has second precision
+    fn collect_snapshot_entries(node: &FileNode, entries: &mut HashMap<String, SnapshotEntry>) {
+        let (modified_ms, size) = path_wire::resolve(&node.path)
+            .metadata()
+            .ok()
+            .map(|metadata| {
+                let modified_ms = metadata
+                    .modified()
+                    .ok()
+                    .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                    .map(|d| d.as_millis() as u64)
+                    .unwrap_or(0);
+                (modified_ms, metadata.len())
+            })
+            .unwrap_or((0, 0));
+        entries.insert(
+            node.path.clone(),
+            SnapshotEntry {
+                modified_ms,
+                size,
+                is_dir: node.is_directory,
+                is_lazy: node.is_lazy_loaded == Some(true),
+            },
+        );
+        for child in node.children.iter().flatten() {
+            Self::collect_snapshot_entries(child, entries);
+        }
+    }
+
+    fn diff_entries(
+        previous: &HashMap<String, SnapshotEntry>,
+        current: &HashMap<String, SnapshotEntry>,
+    ) -> Vec<DirectoryChangeGroup> {
+        let mut added = HashSet::new();
+        let mut removed = HashSet::new();
+        let mut modified = Vec::new();
+
+        for (path, entry) in current {
+            match previous.get(path) {
+                None => {
+                    added.insert(path.as_str());
+                }
+                // A file replaced by a directory (or the reverse) is a remove plus an add
+                Some(old) if old.is_dir != entry.is_dir => {
+                    removed.insert(path.as_str());
+                    added.insert(path.as_str());
+                }
+                Some(old) => {
+                    let changed = old.modified_ms != entry.modified_ms || old.size != entry.size;
+                    // Loaded directories report changes through their children instead
+                    if changed && (!entry.is_dir || (old.is_lazy && entry.is_lazy)) {
+                        modified.push(path.as_str());
+                    }
+                }
+            }
+        }
+        for path in previous.keys() {
+            if !current.contains_key(path) {
+                removed.insert(path.as_str());
+            }
+        }
+
+        let parent_of = |path: &str| -> String {
+            Path::new(path)
+                .parent()
+                .map(Self::normalize_path)
+                .unwrap_or_default()
+        };
+        let mut groups: BTreeMap<String, DirectoryChangeGroup> = BTreeMap::new();
+        fn group(
+            groups: &mut BTreeMap<String, DirectoryChangeGroup>,
+            parent: String,
+        ) -> &mut DirectoryChangeGroup {
+            groups
+                .entry(parent.clone())
+                .or_insert_with(|| DirectoryChangeGroup {
+                    parent,
+                    ..Default::default()
+                })
+        }
+
+        for path in &added {
+            let parent = parent_of(path);
+            if !added.contains(parent.as_str()) {
+                group(&mut groups, parent).added.push(path.to_string());
+            }
+        }
+        for path in &removed {
+            let parent = parent_of(path);
+            if !removed.contains(parent.as_str()) {
+                group(&mut groups, parent).removed.push(path.to_string());
+            }
+        }
+        for path in modified {
+            group(&mut groups, parent_of(path))
+                .modified
+                .push(path.to_string());
+        }
+
+        groups
+            .into_values()
+            .map(|mut group| {
+                group.added.sort();
+                group.removed.sort();
+                group.modified.sort();
+                group
+            })
+            .collect()
+    }
+
     /// Find the git root directory by looking for .git folder
     fn find_git_root(path: &Path) -> Option<&Path> {
         let mut current = path;
@@ -246,22 +846,34 @@ impl DirectoryTreeBuilder {
         }
     }
 
-    /// Load children for a lazy-loaded directory
-    pub fn load_directory_children(&self, dir_path: &str) -> Result<Vec<FileNode>, String> {
-        let path = Path::new(dir_path);
+    /// Load children for a lazy-loaded directory. Fails only when the directory itself
+    /// can't be listed; unreadable subdirectories come back as nodes with an error.
+    pub fn load_directory_children(
+        &self,
+        dir_path: &str,
+        sort: &TreeSortOptions,
+    ) -> Result<DirectoryChildren, String> {
+        let path = &path_wire::resolve(dir_path);
         if !path.exists() || !path.is_dir() {
             return Err("Invalid directory path".to_string());
         }
 
         let now = Self::get_current_timestamp();
-        let cache_key = format!("{}_children", Self::normalize_path(path));
+        let cache_key = CacheKey {
+            dir: Self::normalize_path(path),
+            children: true,
+            sort: sort.clone(),
+        };
 
         // Check cache
         if let Ok(cache) = self.cache.lock() {
             if let Some(cached) = cache.get(&cache_key) {
                 if now - cached.cached_at <= self.cache_ttl {
                     if let Some(children) = &cached.node.children {
-                        return Ok(children.clone());
+                        return Ok(DirectoryChildren {
+                            children: children.clone(),
+                            warnings: cached.node.warnings.clone().unwrap_or_default(),
+                        });
                     }
                 }
             }
@@ -272,21 +884,22 @@ impl DirectoryTreeBuilder {
             Self::find_git_root(path).and_then(|root| Self::build_gitignore_matcher(root));
 
         // Build children
+        let mut warnings = Vec::new();
         let entries = match std::fs::read_dir(path) {
             Ok(entries) => {
-                let mut items = Vec::new();
-                for entry in entries {
-                    if let Ok(entry) = entry {
-                        items.push(entry);
-                    }
-                }
-                items
+                Self::readable_entries(entries, &Self::normalize_path(path), &mut warnings)
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::PermissionDenied => {
+                return Err(crate::path_access::permission_denied(path));
             }
             Err(_) => {
                 return Err("Failed to read directory".to_string());
             }
         };
 
+        let order = ChildOrder::new(sort);
+        // Grandchildren stay lazy; they are expanded with another call
+        let depth = DepthTracker::new(2);
         let mut children = Vec::new();
 
         for entry in entries {
@@ -298,17 +911,21 @@ impl DirectoryTreeBuilder {
                 continue;
             }
 
-            match self.build_node_recursive(&entry_path, 1, 2, now, &gitignore) {
-                Ok(child) => children.push(child),
-                Err(_) => {} // Skip failed entries
+            if let Ok(child) = self.build_node_recursive(
+                &entry_path,
+                1,
+                2,
+                now,
+                &gitignore,
+                &order,
+                &depth,
+                &mut warnings,
+            ) {
+                children.push(child);
             }
         }
 
-        children.sort_by(|a, b| match (a.is_directory, b.is_directory) {
-            (true, false) => std::cmp::Ordering::Less,
-            (false, true) => std::cmp::Ordering::Greater,
-            _ => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
-        });
+        order.sort(&mut children);
 
         // Cache the result
         if let Ok(mut cache) = self.cache.lock() {
@@ -325,13 +942,18 @@ impl DirectoryTreeBuilder {
                         modified_time: None,
                         size: None,
                         is_git_ignored: None,
+                        snapshot_id: None,
+                        raw_path: RawPath::default(),
+                        error: None,
+                        warnings: Some(warnings.clone()),
+                        depth_report: None,
                     },
                     cached_at: now,
                 },
             );
         }
 
-        Ok(children)
+        Ok(DirectoryChildren { children, warnings })
     }
 
     /// Clear cache (useful for file system changes)
@@ -339,16 +961,86 @@ impl DirectoryTreeBuilder {
         if let Ok(mut cache) = self.cache.lock() {
             cache.clear();
         }
+        if let Ok(mut snapshots) = self.snapshots.lock() {
+            snapshots.clear();
+        }
     }
 
-    /// Invalidate specific path cache
+    /// Invalidate specific path cache, in every ordering
     pub fn invalidate_path(&self, path: &str) {
         if let Ok(mut cache) = self.cache.lock() {
             let normalized = Self::normalize_path(Path::new(path));
-            cache.remove(&normalized);
-            cache.remove(&format!("{}_children", normalized));
+            cache.retain(|key, _| key.dir != normalized);
+        }
+    }
+
+    /// Drop the cached trees of `root` and every directory beneath it
+    pub fn invalidate_under(&self, root: &str) {
+        let root = Self::normalize_path(Path::new(root));
+        if let Ok(mut cache) = self.cache.lock() {
+            cache.retain(|key, _| !is_same_or_descendant(&key.dir, &root));
         }
     }
+
+    /// Paths of every node in the cached trees under `root`
+    pub fn cached_paths_under(&self, root: &str) -> Vec<String> {
+        fn collect(node: &FileNode, paths: &mut HashSet<String>) {
+            if !node.path.is_empty() {
+                paths.insert(node.path.clone());
+            }
+            for child in node.children.iter().flatten() {
+                collect(child, paths);
+            }
+        }
+
+        let root = Self::normalize_path(Path::new(root));
+        let mut paths = HashSet::new();
+        if let Ok(cache) = self.cache.lock() {
+            for (key, entry) in cache.iter() {
+                if is_same_or_descendant(&key.dir, &root) {
+                    collect(&entry.node, &mut paths);
+                }
+            }
+        }
+        paths
+            .into_iter()
+            .filter(|path| is_same_or_descendant(path, &root))
+            .collect()
+    }
+
+    /// Drop every cached tree that contains one of `paths`. Returns the directories whose
+    /// entries were dropped, sorted.
+    pub fn invalidate_containing(&self, paths: &[String]) -> Vec<String> {
+        let paths: Vec<String> = paths
+            .iter()
+            .map(|path| Self::normalize_path(Path::new(path)))
+            .collect();
+        let mut invalidated = Vec::new();
+        if let Ok(mut cache) = self.cache.lock() {
+            cache.retain(|key, _| {
+                let dir = key.dir.as_str();
+                let affected = paths
+                    .iter()
+                    .any(|path| path != dir && is_same_or_descendant(path, dir));
+                if affected {
+                    invalidated.push(dir.to_string());
+                }
+                !affected
+            });
+        }
+        invalidated.sort();
+        invalidated.dedup();
+        invalidated
+    }
+}
+
+/// Whether `path` is `dir` or lies beneath it (normalized, '/'-separated paths)
+fn is_same_or_descendant(path: &str, dir: &str) -> bool {
+    let dir = dir.trim_end_matches('/');
+    path == dir
+        || path
+            .strip_prefix(dir)
+            .is_some_and(|rest| rest.starts_with('/'))
 }
 
 // Global instance
@@ -356,18 +1048,48 @@ lazy_static::lazy_static! {
     static ref DIRECTORY_TREE_BUILDER: DirectoryTreeBuilder = DirectoryTreeBuilder::new();
 }
 
+/// The builder behind the directory tree commands
+pub fn tree_builder() -> &'static DirectoryTreeBuilder {
+    &DIRECTORY_TREE_BUILDER
+}
+
+/// With `with_snapshot`, the tree is built fresh and its root carries a snapshot id for
+/// build_directory_tree_diff. Children are in natural name order, directories first,
+/// unless `sort` says otherwise.
 #[tauri::command]
 pub fn build_directory_tree(
     root_path: String,
     max_immediate_depth: Option<usize>,
+    with_snapshot: Option<bool>,
+    sort: Option<TreeSortOptions>,
 ) -> Result<FileNode, String> {
-    let depth = max_immediate_depth.unwrap_or(2); // Default to 2 levels deep
-    DIRECTORY_TREE_BUILDER.build_directory_tree_fast(&root_path, depth)
+    // Default to 2 levels deep
+    let depth = max_immediate_depth
+        .unwrap_or(2)
+        .min(walk_depth::MAX_ALLOWED_DEPTH);
+    let sort = sort.unwrap_or_default();
+    if with_snapshot.unwrap_or(false) {
+        return DIRECTORY_TREE_BUILDER.build_directory_tree_with_snapshot(&root_path, depth, &sort);
+    }
+    DIRECTORY_TREE_BUILDER.build_directory_tree_fast(&root_path, depth, &sort)
+}
+
+/// Paths added, removed or modified since a snapshot, grouped by parent directory
+#[tauri::command]
+pub fn build_directory_tree_diff(
+    root_path: String,
+    previous_snapshot_id: String,
+) -> Result<DirectoryTreeDiff, String> {
+    DIRECTORY_TREE_BUILDER.diff_directory_tree(&root_path, &previous_snapshot_id)
 }
 
+/// Children of a lazily loaded directory, with the paths among them that could not be read
 #[tauri::command]
-pub fn load_directory_children(dir_path: String) -> Result<Vec<FileNode>, String> {
-    DIRECTORY_TREE_BUILDER.load_directory_children(&dir_path)
+pub fn load_directory_children(
+    dir_path: String,
+    sort: Option<TreeSortOptions>,
+) -> Result<DirectoryChildren, String> {
+    DIRECTORY_TREE_BUILDER.load_directory_children(&dir_path, &sort.unwrap_or_default())
 }
 
 #[tauri::command]
@@ -379,3 +1101,432 @@ pub fn clear_directory_cache() {
 pub fn invalidate_directory_path(path: String) {
     DIRECTORY_TREE_BUILDER.invalidate_path(&path);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn create_tree() -> TempDir {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::create_dir_all(root.join("docs/old")).unwrap();
+        fs::write(root.join("src/a.rs"), "fn a() {}").unwrap();
+        fs::write(root.join("src/b.rs"), "fn b() {}").unwrap();
+        fs::write(root.join("docs/old/readme.md"), "# Old").unwrap();
+        fs::write(root.join("top.txt"), "top").unwrap();
+        temp_dir
+    }
+
+    fn path(root: &Path, relative: &str) -> String {
+        DirectoryTreeBuilder::normalize_path(&root.join(relative))
+    }
+
+    fn names(nodes: &[FileNode]) -> Vec<&str> {
+        nodes.iter().map(|n| n.name.as_str()).collect()
+    }
+
+    fn node(name: &str, is_directory: bool, size: u64) -> FileNode {
+        FileNode {
+            name: name.to_string(),
+            path: name.to_string(),
+            is_directory,
+            children: None,
+            is_lazy_loaded: None,
+            has_children: None,
+            modified_time: None,
+            size: Some(size),
+            is_git_ignored: None,
+            snapshot_id: None,
+            raw_path: RawPath::default(),
+            error: None,
+            warnings: None,
+            depth_report: None,
+        }
+    }
+
+    #[test]
+    fn test_natural_cmp_properties() {
+        for a in 0..120u32 {
+            for b in 0..120u32 {
+                // Numbers compare by value, with or without padding
+                assert_eq!(
+                    natural_cmp(&format!("file{}", a), &format!("file{}", b)),
+                    a.cmp(&b)
+                );
+                assert_eq!(
+                    natural_cmp(&format!("file{:04}.txt", a), &format!("FILE{}.txt", b)),
+                    a.cmp(&b).then(CmpOrdering::Greater)
+                );
+                // Antisymmetric
+                let (x, y) = (format!("v{}.{}", a % 7, b), format!("V{}.{}", b % 7, a));
+                assert_eq!(natural_cmp(&x, &y), natural_cmp(&y, &x).reverse());
+            }
+        }
+        assert_eq!(natural_cmp("file2", "file10"), CmpOrdering::Less);
+        // Case alone is a tie
+        assert_eq!(natural_cmp("Readme.md", "README.md"), CmpOrdering::Equal);
+        // Leading zeros only decide otherwise equal names
+        assert_eq!(natural_cmp("file2", "file02"), CmpOrdering::Less);
+        assert_eq!(natural_cmp("file02b", "file2c"), CmpOrdering::Less);
+        assert_eq!(natural_cmp("file", "file1"), CmpOrdering::Less);
+        assert_eq!(natural_cmp("a1b", "a1"), CmpOrdering::Greater);
+
+        // The collated order agrees, and ties keep insertion order
+        for options in [
+            TreeSortOptions::default(),
+            TreeSortOptions {
+                locale: Some("not a locale!".to_string()),
+                ..Default::default()
+            },
+        ] {
+            let order = ChildOrder::new(&options);
+            let mut nodes: Vec<FileNode> = ["file10", "b.md", "B.md", "file2", "file02", "src"]
+                .iter()
+                .map(|name| node(name, *name == "src", 0))
+                .collect();
+            order.sort(&mut nodes);
+            assert_eq!(
+                names(&nodes),
+                ["src", "b.md", "B.md", "file2", "file02", "file10"]
+            );
+        }
+        assert!(ChildOrder::new(&TreeSortOptions::default())
+            .collator
+            .is_some());
+    }
+
+    #[test]
+    fn test_sort_modes_and_directions() {
+        let nodes = vec![
+            node("b", false, 30),
+            node("dir", true, 0),
+            node("a10", false, 10),
+            node("a9", false, 10),
+        ];
+        let sorted = |options: TreeSortOptions| {
+            let mut nodes = nodes.clone();
+            ChildOrder::new(&options).sort(&mut nodes);
+            names(&nodes)
+                .iter()
+                .map(|n| n.to_string())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(sorted(Default::default()), ["dir", "a9", "a10", "b"]);
+        let lexicographic = TreeSortOptions {
+            mode: TreeSortMode::NameLexicographic,
+            ..Default::default()
+        };
+        assert_eq!(sorted(lexicographic), ["dir", "a10", "a9", "b"]);
+        let size_desc = TreeSortOptions {
+            mode: TreeSortMode::Size,
+            direction: SortDirection::Desc,
+            ..Default::default()
+        };
+        assert_eq!(sorted(size_desc.clone()), ["dir", "b", "a10", "a9"]);
+        let mixed = TreeSortOptions {
+            directories_first: false,
+            ..size_desc
+        };
+        assert_eq!(sorted(mixed), ["b", "a10", "a9", "dir"]);
+
+        let parsed: TreeSortOptions =
+            serde_json::from_value(serde_json::json!({ "mode": "modified-time" })).unwrap();
+        assert_eq!(parsed.mode, TreeSortMode::ModifiedTime);
+        assert!(parsed.directories_first);
+    }
+
+    #[test]
+    fn test_cache_is_keyed_by_sort_options() {
+        let temp_dir = create_tree();
+        let root = temp_dir.path();
+        let root_str = root.to_str().unwrap();
+        let builder = DirectoryTreeBuilder::new();
+        let by_name = TreeSortOptions::default();
+        let files_first = TreeSortOptions {
+            directories_first: false,
+            direction: SortDirection::Desc,
+            ..Default::default()
+        };
+
+        let tree = builder
+            .build_directory_tree_fast(root_str, 2, &by_name)
+            .unwrap();
+        assert_eq!(
+            names(tree.children.as_ref().unwrap()),
+            ["docs", "src", "top.txt"]
+        );
+        // A cached tree in another order is not served
+        let tree = builder
+            .build_directory_tree_fast(root_str, 2, &files_first)
+            .unwrap();
+        assert_eq!(
+            names(tree.children.as_ref().unwrap()),
+            ["top.txt", "src", "docs"]
+        );
+        let src = path(root, "src");
+        let children = builder.load_directory_children(&src, &by_name).unwrap();
+        assert_eq!(names(&children.children), ["a.rs", "b.rs"]);
+        let children = builder.load_directory_children(&src, &files_first).unwrap();
+        assert_eq!(names(&children.children), ["b.rs", "a.rs"]);
+        assert_eq!(builder.cache.lock().unwrap().len(), 4);
+
+        // Invalidation drops every ordering
+        builder.invalidate_path(&src);
+        assert_eq!(builder.cache.lock().unwrap().len(), 2);
+        builder.invalidate_under(root_str);
+        assert!(builder.cache.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_depth_limit_is_reported_on_the_root() {
+        let temp_dir = TempDir::new().unwrap();
+        let root_str = temp_dir.path().to_str().unwrap();
+        crate::walk_depth::tests::nested_tree(temp_dir.path(), 25, "deep.rs");
+        let builder = DirectoryTreeBuilder::new();
+        let sort = TreeSortOptions::default();
+
+        let tree = builder
+            .build_directory_tree_fast(root_str, 20, &sort)
+            .unwrap();
+        let report = tree.depth_report.unwrap();
+        assert!(report.depth_limited);
+        assert_eq!(report.truncated_subtrees, 1);
+
+        let tree = builder
+            .build_directory_tree_with_snapshot(root_str, 30, &sort)
+            .unwrap();
+        assert!(!tree.depth_report.unwrap().depth_limited);
+    }
+
+    #[test]
+    fn test_diff_reports_adds_deletes_modifications_and_renames() {
+        let temp_dir = create_tree();
+        let root = temp_dir.path();
+        let root_str = root.to_str().unwrap();
+        let builder = DirectoryTreeBuilder::new();
+
+        let tree = builder
+            .build_directory_tree_with_snapshot(root_str, 5, &TreeSortOptions::default())
+            .unwrap();
+        let snapshot_id = tree.snapshot_id.unwrap();
+
+        fs::write(root.join("src/c.rs"), "fn c() {}").unwrap();
+        fs::remove_file(root.join("src/b.rs")).unwrap();
+        fs::write(root.join("top.txt"), "top, now longer").unwrap();
+        fs::rename(root.join("docs/old"), root.join("docs/new")).unwrap();
+
+        let diff = builder.diff_directory_tree(root_str, &snapshot_id).unwrap();
+        assert_ne!(diff.snapshot_id, snapshot_id);
+        assert_eq!(
+            diff.groups,
+            vec![
+                DirectoryChangeGroup {
+                    parent: DirectoryTreeBuilder::normalize_path(root),
+                    modified: vec![path(root, "top.txt")],
+                    ..Default::default()
+                },
+                DirectoryChangeGroup {
+                    parent: path(root, "docs"),
+                    added: vec![path(root, "docs/new")],
+                    removed: vec![path(root, "docs/old")],
+                    ..Default::default()
+                },
+                DirectoryChangeGroup {
+                    parent: path(root, "src"),
+                    added: vec![path(root, "src/c.rs")],
+                    removed: vec![path(root, "src/b.rs")],
+                    ..Default::default()
+                },
+            ]
+        );
+
+        // Diffing against the returned snapshot starts from the new state
+        let diff = builder
+            .diff_directory_tree(root_str, &diff.snapshot_id)
+            .unwrap();
+        assert!(diff.groups.is_empty());
+    }
+
+    #[test]
+    fn test_snapshots_expire_are_bounded_and_cleared() {
+        let temp_dir = create_tree();
+        let root_str = temp_dir.path().to_str().unwrap();
+        let builder = DirectoryTreeBuilder::new();
+
+        let first = builder
+            .build_directory_tree_with_snapshot(root_str, 2, &TreeSortOptions::default())
+            .unwrap()
+            .snapshot_id
+            .unwrap();
+        assert!(builder.diff_directory_tree("/elsewhere", &first).is_err());
+
+        // Expired snapshots are gone
+        builder
+            .snapshots
+            .lock()
+            .unwrap()
+            .get_mut(&first)
+            .unwrap()
+            .created_at -= SNAPSHOT_TTL + 1;
+        let err = builder.diff_directory_tree(root_str, &first).unwrap_err();
+        assert!(err.contains("expired"), "{}", err);
+
+        // Only the most recent snapshots are kept
+        let ids: Vec<String> = (0..MAX_SNAPSHOTS + 2)
+            .map(|_| {
+                builder
+                    .build_directory_tree_with_snapshot(root_str, 2, &TreeSortOptions::default())
+                    .unwrap()
+                    .snapshot_id
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(builder.snapshots.lock().unwrap().len(), MAX_SNAPSHOTS);
+        assert!(builder
+            .diff_directory_tree(root_str, ids.last().unwrap())
+            .is_ok());
+
+        builder.clear_cache();
+        assert!(builder
+            .diff_directory_tree(root_str, ids.last().unwrap())
+            .is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_denied_directory_reports_permission_error() {
+        use crate::path_access::{tests as access, PERMISSION_DENIED_PREFIX};
+
+        let temp_dir = TempDir::new().unwrap();
+        let Some(locked) = access::unreadable_dir(temp_dir.path()) else {
+            return;
+        };
+        let builder = DirectoryTreeBuilder::new();
+        let locked_str = locked.to_string_lossy().to_string();
+
+        let err = builder
+            .build_directory_tree_fast(&locked_str, 2, &TreeSortOptions::default())
+            .unwrap_err();
+        assert!(err.starts_with(PERMISSION_DENIED_PREFIX), "{}", err);
+        let err = builder
+            .load_directory_children(&locked_str, &TreeSortOptions::default())
+            .unwrap_err();
+        assert!(err.starts_with(PERMISSION_DENIED_PREFIX), "{}", err);
+
+        access::restore(&locked);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_unreadable_subdirectory_becomes_error_node() {
+        use crate::path_access::tests as access;
+
+        let temp_dir = create_tree();
+        let root = temp_dir.path();
+        let Some(locked) = access::unreadable_dir(&root.join("src")) else {
+            return;
+        };
+        let builder = DirectoryTreeBuilder::new();
+
+        let tree = builder
+            .build_directory_tree_fast(root.to_str().unwrap(), 5, &TreeSortOptions::default())
+            .unwrap();
+        let src = tree
+            .children
+            .as_ref()
+            .unwrap()
+            .iter()
+            .find(|c| c.name == "src")
+            .unwrap();
+        let names: Vec<&str> = src
+            .children
+            .as_ref()
+            .unwrap()
+            .iter()
+            .map(|c| c.name.as_str())
+            .collect();
+        assert_eq!(names, ["locked", "a.rs", "b.rs"]);
+        let locked_node = &src.children.as_ref().unwrap()[0];
+        let error = locked_node.error.as_ref().unwrap();
+        assert_eq!(error.kind, NodeErrorKind::PermissionDenied);
+        assert_eq!(locked_node.has_children, None);
+        assert!(locked_node.children.is_none());
+        // Siblings elsewhere in the tree still load
+        assert!(tree
+            .children
+            .as_ref()
+            .unwrap()
+            .iter()
+            .any(|c| c.name == "docs" && c.children.as_ref().is_some_and(|d| d.len() == 1)));
+        assert_eq!(
+            tree.warnings,
+            Some(vec![TreeWarning {
+                path: path(root, "src/locked"),
+                error: error.clone(),
+            }])
+        );
+
+        // Lazy loading the parent reports the same
+        let loaded = builder
+            .load_directory_children(&path(root, "src"), &TreeSortOptions::default())
+            .unwrap();
+        assert_eq!(loaded.children.len(), 3);
+        assert!(loaded.children[0].error.is_some());
+        assert_eq!(loaded.warnings.len(), 1);
+        assert_eq!(loaded.warnings[0].path, path(root, "src/locked"));
+        // Including when served from the cache
+        let cached = builder
+            .load_directory_children(&path(root, "src"), &TreeSortOptions::default())
+            .unwrap();
+        assert_eq!(cached.warnings, loaded.warnings);
+
+        // The error is left out of payloads for readable nodes
+        let json = serde_json::to_value(&tree).unwrap();
+        assert!(json.get("error").is_none());
+        assert_eq!(json["warnings"][0]["kind"], "permission_denied");
+        access::restore(&locked);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_non_utf8_names_can_be_loaded_again() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let dir = root.join(OsStr::from_bytes(b"archiv\xe9"));
+        fs::create_dir_all(dir.join("nested")).unwrap();
+        fs::write(dir.join(OsStr::from_bytes(b"caf\xe9.txt")), "latin").unwrap();
+        fs::write(dir.join(OsStr::from_bytes(b"caf\xe8.txt")), "other").unwrap();
+
+        let builder = DirectoryTreeBuilder::new();
+        let tree = builder
+            .build_directory_tree_fast(root.to_str().unwrap(), 1, &TreeSortOptions::default())
+            .unwrap();
+        let children = tree.children.unwrap();
+        let archive = children.iter().find(|c| c.is_directory).unwrap();
+        assert_eq!(archive.name, "archiv%E9");
+        assert!(archive.raw_path.path_is_lossy);
+        assert_eq!(archive.is_lazy_loaded, Some(true));
+
+        // The lazy directory loads by the path the tree returned
+        let files = builder
+            .load_directory_children(&archive.path, &TreeSortOptions::default())
+            .unwrap()
+            .children;
+        let names: Vec<&str> = files.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, ["nested", "caf%E8.txt", "caf%E9.txt"]);
+        for file in files.iter().filter(|f| !f.is_directory) {
+            let content = fs::read_to_string(path_wire::resolve(&file.path)).unwrap();
+            let raw = path_wire::from_raw_bytes(file.raw_path.raw_path_bytes.as_ref().unwrap());
+            assert_eq!(fs::read_to_string(raw.unwrap()).unwrap(), content);
+        }
+        // Everything inside a lossy directory is lossy too
+        assert!(files[0].raw_path.path_is_lossy);
+    }
+}
@@ -0,0 +1,961 @@
+// Docs index module
+// Full-text retrieval over a project's documentation for the assistant: markdown files
+// split at headings plus doc comments pulled out of source files with the code index's
+// tree-sitter grammars. Chunks are ranked with BM25, with a boost for exact phrase matches.
+// The index lives in memory per project, is persisted next to the code index, and is
+// rebuilt incrementally from file mtimes and sizes.
+
+use crate::code_navigation::{
+    get_index_dir, get_project_hash, language_for, CodeNavigationService,
+};
+use crate::constants::should_exclude_dir;
+use crate::glob::HighPerformanceGlob;
+use ignore::WalkBuilder;
+use rayon::prelude::*;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Instant, UNIX_EPOCH};
+use tauri::AppHandle;
+use tree_sitter::{Node, Parser};
+
+/// Version of the persisted docs index format
+const DOCS_INDEX_VERSION: u32 = 1;
+const DEFAULT_GLOBS: [&str; 2] = ["**/*.md", "**/*.mdx"];
+const DEFAULT_MAX_RESULTS: usize = 10;
+/// Files larger than this are not documentation worth indexing
+const MAX_DOC_FILE_BYTES: u64 = 1024 * 1024;
+/// Sections longer than this are split at blank lines
+const MAX_CHUNK_BYTES: usize = 2000;
+const SNIPPET_BYTES: usize = 240;
+/// Emit a progress event every this many indexed files
+const PROGRESS_INTERVAL: usize = 50;
+/// BM25 term-frequency saturation and length normalization
+const BM25_K1: f64 = 1.2;
+const BM25_B: f64 = 0.75;
+/// Score multiplier for chunks containing the whole query as a phrase
+const PHRASE_BOOST: f64 = 2.0;
+
+lazy_static::lazy_static! {
+    static ref DOCS_INDEXES: Mutex<HashMap<String, Arc<DocsIndex>>> = Mutex::new(HashMap::new());
+}
+
+/// A searchable piece of documentation
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DocChunk {
+    /// Heading path ("Setup > macOS") or documented symbol name
+    pub anchor: String,
+    pub start_line: usize,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PersistedDocFile {
+    pub modified_ms: i64,
+    pub size: u64,
+    pub chunks: Vec<DocChunk>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PersistedDocsIndex {
+    pub version: u32,
+    pub root_path: String,
+    /// Globs the index was built with; None for the defaults
+    pub globs: Option<Vec<String>>,
+    pub last_updated: i64,
+    pub files: HashMap<String, PersistedDocFile>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DocsIndexStats {
+    pub root_path: String,
+    pub file_count: usize,
+    pub chunk_count: usize,
+    /// Files parsed in this build; unchanged files are reused
+    pub reindexed_files: usize,
+    pub removed_files: usize,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DocsSearchResult {
+    pub file_path: String,
+    pub anchor: String,
+    pub start_line: usize,
+    pub score: f64,
+    pub snippet: String,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DocsIndexProgress<'a> {
+    pub root_path: &'a str,
+    /// "indexing" while files are parsed, then "done"
+    pub phase: &'a str,
+    pub processed: usize,
+    pub total: usize,
+}
+
+// ============================================================================
+// Chunking
+// ============================================================================
+
+fn file_name(file_path: &str) -> String {
+    Path::new(file_path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| file_path.to_string())
+}
+
+/// Add a section as one chunk, or several split at blank lines when it is long
+fn push_chunks(chunks: &mut Vec<DocChunk>, anchor: &str, start_line: usize, text: &str) {
+    let mut current = String::new();
+    let mut current_start = start_line;
+    for (offset, line) in text.lines().enumerate() {
+        if line.trim().is_empty() && current.len() >= MAX_CHUNK_BYTES {
+            if !current.trim().is_empty() {
+                chunks.push(DocChunk {
+                    anchor: anchor.to_string(),
+                    start_line: current_start,
+                    text: current.trim_end().to_string(),
+                });
+            }
+            current.clear();
+            current_start = start_line + offset + 1;
+            continue;
+        }
+        current.push_str(line);
+        current.push('\n');
+    }
+    if !current.trim().is_empty() {
+        chunks.push(DocChunk {
+            anchor: anchor.to_string(),
+            start_line: current_start,
+            text: current.trim_end().to_string(),
+        });
+    }
+}
+
+/// `## Title` -> (2, "Title")
+fn parse_heading(line: &str) -> Option<(usize, String)> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    if !(1..=6).contains(&level) {
+        return None;
+    }
+    let rest = &line[level..];
+    if !rest.is_empty() && !rest.starts_with(' ') {
+        return None;
+    }
+    let title = rest.trim().trim_end_matches('#').trim();
+    Some((level, title.to_string()))
+}
+
+/// Split markdown at headings (outside code fences); each chunk is anchored at its
+/// heading path
+pub fn chunk_markdown(content: &str, file_path: &str) -> Vec<DocChunk> {
+    let mut chunks = Vec::new();
+    let mut headings: Vec<(usize, String)> = Vec::new();
+    let mut section = String::new();
+    let mut section_start = 1;
+    let mut in_fence = false;
+
+    let anchor_of = |headings: &[(usize, String)]| {
+        if headings.is_empty() {
+            file_name(file_path)
+        } else {
+            headings
+                .iter()
+                .map(|(_, title)| title.as_str())
+                .collect::<Vec<_>>()
+                .join(" > ")
+        }
+    };
+
+    for (index, line) in content.lines().enumerate() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+        } else if !in_fence {
+            if let Some((level, title)) = parse_heading(trimmed) {
+                push_chunks(&mut chunks, &anchor_of(&headings), section_start, &section);
+                section.clear();
+                section_start = index + 1;
+                headings.retain(|(l, _)| *l < level);
+                headings.push((level, title));
+            }
+        }
+        section.push_str(line);
+        section.push('\n');
+    }
+    push_chunks(&mut chunks, &anchor_of(&headings), section_start, &section);
+    chunks
+}
+
+/// Last row a node's text is on; Rust line comments include their newline, which would
+/// otherwise put their end on the following row
+fn last_row(node: &Node) -> usize {
+    let end = node.end_position();
+    if end.column == 0 && end.row > node.start_position().row {
+        end.row - 1
+    } else {
+        end.row
+    }
+}
+
+fn is_comment(node: &Node) -> bool {
+    matches!(node.kind(), "comment" | "line_comment" | "block_comment")
+}
+
+/// Whether a comment is documentation rather than an ordinary comment. Go has no doc
+/// syntax; its doc comments are recognized by position instead.
+fn is_doc_comment(text: &str, lang_id: &str) -> bool {
+    match lang_id {
+        "rust" => {
+            (text.starts_with("///") && !text.starts_with("////"))
+                || text.starts_with("//!")
+                || (text.starts_with("/**") && !text.starts_with("/**/"))
+                || text.starts_with("/*!")
+        }
+        "c" | "cpp" => text.starts_with("/**") || text.starts_with("///"),
+        "go" => text.starts_with("//"),
+        _ => text.starts_with("/**") && !text.starts_with("/**/"),
+    }
+}
+
+/// Strip comment markers and docstring quotes, keeping the prose
+fn clean_comment(text: &str) -> String {
+    text.lines()
+        .map(|line| {
+            let mut line = line.trim();
+            for prefix in ["///", "//!", "//", "/**", "/*!", "/*"] {
+                if let Some(rest) = line.strip_prefix(prefix) {
+                    line = rest;
+                    break;
+                }
+            }
+            line = line.trim_end().trim_end_matches("*/").trim();
+            line = line.strip_prefix('*').unwrap_or(line).trim();
+            line.trim_matches(|c| c == '"' || c == '\'').trim()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_string()
+}
+
+/// Name of a declaration node, looking through export wrappers and declarators
+fn node_name(node: &Node, source: &[u8]) -> Option<String> {
+    let text = |n: Node| n.utf8_text(source).ok().map(str::to_string);
+    if let Some(name) = node.child_by_field_name("name") {
+        return text(name);
+    }
+    if let Some(declaration) = node.child_by_field_name("declaration") {
+        return node_name(&declaration, source);
+    }
+    // Rust impl blocks are named by their type
+    if node.kind() == "impl_item" {
+        return node.child_by_field_name("type").and_then(text);
+    }
+    let mut cursor = node.walk();
+    let name = node
+        .named_children(&mut cursor)
+        .find_map(|child| child.child_by_field_name("name"))
+        .and_then(text);
+    name
+}
+
+/// Python docstring: a string as the first statement of a module, class or function body
+fn python_docstring(node: &Node, source: &[u8], file_path: &str, chunks: &mut Vec<DocChunk>) {
+    let Some(statement) = node.named_child(0) else {
+        return;
+    };
+    let Some(string) = statement.named_child(0) else {
+        return;
+    };
+    if statement.kind() != "expression_statement" || string.kind() != "string" {
+        return;
+    }
+    let anchor = match node.parent() {
+        Some(parent) if node.kind() == "block" => node_name(&parent, source),
+        _ => None,
+    }
+    .unwrap_or_else(|| file_name(file_path));
+    if let Ok(text) = string.utf8_text(source) {
+        push_chunks(
+            chunks,
+            &anchor,
+            string.start_position().row + 1,
+            &clean_comment(text),
+        );
+    }
+}
+
+fn collect_doc_comments(
+    node: Node,
+    source: &[u8],
+    lang_id: &str,
+    file_path: &str,
+    chunks: &mut Vec<DocChunk>,
+) {
+    if lang_id == "python" && matches!(node.kind(), "module" | "block") {
+        python_docstring(&node, source, file_path, chunks);
+    }
+
+    let mut cursor = node.walk();
+    let children: Vec<Node> = node.named_children(&mut cursor).collect();
+    let mut i = 0;
+    while i < children.len() {
+        if !is_comment(&children[i]) {
+            collect_doc_comments(children[i], source, lang_id, file_path, chunks);
+            i += 1;
+            continue;
+        }
+
+        // A run of comments on consecutive lines documents the same item
+        let mut end = i + 1;
+        while end < children.len()
+            && is_comment(&children[end])
+            && children[end].start_position().row <= last_row(&children[end - 1]) + 1
+        {
+            end += 1;
+        }
+        let run = &children[i..end];
+        let run_end_row = last_row(&run[run.len() - 1]);
+        let documented = children[end..]
+            .iter()
+            .find(|n| !matches!(n.kind(), "attribute_item" | "decorator"));
+        let adjacent = documented.is_some_and(|n| n.start_position().row <= run_end_row + 1);
+
+        let docs: Vec<&str> = run
+            .iter()
+            .filter_map(|n| n.utf8_text(source).ok())
+            .filter(|text| is_doc_comment(text, lang_id))
+            .collect();
+        if !docs.is_empty() && (lang_id != "go" || adjacent) {
+            let inner = docs[0].starts_with("//!") || docs[0].starts_with("/*!");
+            let anchor = if inner {
+                Some(file_name(file_path))
+            } else {
+                documented.and_then(|n| node_name(n, source))
+            }
+            .unwrap_or_else(|| format!("line {}", run[0].start_position().row + 1));
+            push_chunks(
+                chunks,
+                &anchor,
+                run[0].start_position().row + 1,
+                &clean_comment(&docs.join("\n")),
+            );
+        }
+        i = end;
+    }
+}
+
+/// Doc comments (and Python docstrings) of a source file, anchored at the symbol they
+/// document
+pub fn extract_doc_comments(content: &str, lang_id: &str, file_path: &str) -> Vec<DocChunk> {
+    let Some(language) = language_for(lang_id) else {
+        return Vec::new();
+    };
+    let mut parser = Parser::new();
+    if parser.set_language(&language).is_err() {
+        return Vec::new();
+    }
+    let Some(tree) = parser.parse(content, None) else {
+        return Vec::new();
+    };
+    let mut chunks = Vec::new();
+    collect_doc_comments(
+        tree.root_node(),
+        content.as_bytes(),
+        lang_id,
+        file_path,
+        &mut chunks,
+    );
+    chunks
+}
+
+fn is_markdown(file_path: &str) -> bool {
+    Path::new(file_path)
+        .extension()
+        .and_then(OsStr::to_str)
+        .is_some_and(|e| matches!(e.to_lowercase().as_str(), "md" | "mdx" | "markdown"))
+}
+
+fn chunk_file(file_path: &str, content: &str) -> Vec<DocChunk> {
+    if is_markdown(file_path) {
+        return chunk_markdown(content, file_path);
+    }
+    match CodeNavigationService::get_lang_id_from_path(file_path) {
+        Some(lang_id) => extract_doc_comments(content, &lang_id, file_path),
+        None => {
+            let mut chunks = Vec::new();
+            push_chunks(&mut chunks, &file_name(file_path), 1, content);
+            chunks
+        }
+    }
+}
+
+// ============================================================================
+// Index
+// ============================================================================
+
+/// Lowercased words; CJK ideographs are one term each since they are not space-separated
+fn tokenize(text: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut word = String::new();
+    for c in text.chars() {
+        let is_ideograph = matches!(c, '\u{3400}'..='\u{9FFF}' | '\u{F900}'..='\u{FAFF}');
+        if (c.is_alphanumeric() || c == '_') && !is_ideograph {
+            word.extend(c.to_lowercase());
+            continue;
+        }
+        if !word.is_empty() {
+            tokens.push(std::mem::take(&mut word));
+        }
+        if is_ideograph {
+            tokens.push(c.to_string());
+        }
+    }
+    if !word.is_empty() {
+        tokens.push(word);
+    }
+    tokens
+}
+
+struct IndexedChunk {
+    file_path: String,
+    chunk: DocChunk,
+    length: usize,
+    /// Tokens joined by spaces and padded, for phrase matching
+    token_text: String,
+}
+
+/// BM25 search structures derived from the persisted files
+pub struct DocsIndex {
+    persisted: PersistedDocsIndex,
+    entries: Vec<IndexedChunk>,
+    /// term -> (entry index, term frequency)
+    postings: HashMap<String, Vec<(usize, u32)>>,
+    average_length: f64,
+}
+
+struct FileState {
+    path: String,
+    modified_ms: i64,
+    size: u64,
+}
+
+impl DocsIndex {
+    pub fn from_persisted(persisted: PersistedDocsIndex) -> Self {
+        let mut entries = Vec::new();
+        let mut postings: HashMap<String, Vec<(usize, u32)>> = HashMap::new();
+        let mut paths: Vec<&String> = persisted.files.keys().collect();
+        paths.sort();
+        for path in paths {
+            for chunk in &persisted.files[path].chunks {
+                let tokens = tokenize(&format!("{}\n{}", chunk.anchor, chunk.text));
+                let mut frequencies: HashMap<&str, u32> = HashMap::new();
+                for token in &tokens {
+                    *frequencies.entry(token.as_str()).or_default() += 1;
+                }
+                let index = entries.len();
+                for (term, frequency) in frequencies {
+                    postings
+                        .entry(term.to_string())
+                        .or_default()
+                        .push((index, frequency));
+                }
+                entries.push(IndexedChunk {
+                    file_path: path.clone(),
+                    chunk: chunk.clone(),
+                    length: tokens.len(),
+                    token_text: format!(" {} ", tokens.join(" ")),
+                });
+            }
+        }
+        let average_length = if entries.is_empty() {
+            0.0
+        } else {
+            entries.iter().map(|e| e.length).sum::<usize>() as f64 / entries.len() as f64
+        };
+        Self {
+            persisted,
+            entries,
+            postings,
+            average_length,
+        }
+    }
+
+    /// Files the index should cover, with their mtimes and sizes
+    fn scan(root_path: &str, globs: Option<&[String]>) -> Vec<FileState> {
+        let matcher = HighPerformanceGlob::new();
+        let mut walker = WalkBuilder::new(root_path);
+        walker
+            .hidden(true)
+            .git_ignore(true)
+            .git_global(true)
+            .git_exclude(true)
+            .ignore(true)
+            .parents(true)
+            .filter_entry(|entry| {
+                if entry.path().is_dir() {
+                    if let Some(name) = entry.path().file_name().and_then(OsStr::to_str) {
+                        return !should_exclude_dir(name);
+                    }
+                }
+                true
+            });
+
+        let mut files = Vec::new();
+        for entry in walker.build().flatten() {
+            let path = entry.path();
+            let path_str = path.to_string_lossy().to_string();
+            let wanted = match globs {
+                Some(globs) => globs
+                    .iter()
+                    .any(|glob| matcher.matches_glob_pattern(&path_str, glob, root_path)),
+                None => {
+                    DEFAULT_GLOBS
+                        .iter()
+                        .any(|glob| matcher.matches_glob_pattern(&path_str, glob, root_path))
+                        || CodeNavigationService::get_lang_id_from_path(&path_str).is_some()
+                }
+            };
+            if !wanted {
+                continue;
+            }
+            let Ok(metadata) = path.metadata() else {
+                continue;
+            };
+            if !metadata.is_file() || metadata.len() > MAX_DOC_FILE_BYTES {
+                continue;
+            }
+            let modified_ms = metadata
+                .modified()
+                .ok()
+                .and_then(|m| m.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_millis() as i64)
+                .unwrap_or(0);
+            files.push(FileState {
+                path: path_str,
+                modified_ms,
+                size: metadata.len(),
+            });
+        }
+        files
+    }
+
+    /// Build the index for `root_path`, reusing files from `previous` whose mtime and size
+    /// are unchanged. `progress` is called with (processed, total) as files are parsed.
+    pub fn build(
+        root_path: &str,
+        globs: Option<Vec<String>>,
+        previous: Option<&DocsIndex>,
+        progress: &(dyn Fn(usize, usize) + Sync),
+    ) -> (Self, DocsIndexStats) {
+        let start = Instant::now();
+        let files = Self::scan(root_path, globs.as_deref());
+        // Different globs cover different files, so nothing carries over
+        let previous_files = previous
+            .map(|p| &p.persisted)
+            .filter(|p| p.globs == globs)
+            .map(|p| &p.files);
+
+        let (unchanged, changed): (Vec<&FileState>, Vec<&FileState>) =
+            files.iter().partition(|file| {
+                previous_files
+                    .and_then(|previous| previous.get(&file.path))
+                    .is_some_and(|p| p.modified_ms == file.modified_ms && p.size == file.size)
+            });
+
+        let processed = AtomicUsize::new(0);
+        let total = changed.len();
+        let parsed: Vec<(String, PersistedDocFile)> = changed
+            .par_iter()
+            .filter_map(|file| {
+                let content = fs::read(&file.path).ok()?;
+                let done = processed.fetch_add(1, Ordering::Relaxed) + 1;
+                if done.is_multiple_of(PROGRESS_INTERVAL) || done == total {
+                    progress(done, total);
+                }
+                // Binary files have no documentation to offer
+                if content.contains(&0) {
+                    return None;
+                }
+                let chunks = chunk_file(&file.path, &String::from_utf8_lossy(&content));
+                Some((
+                    file.path.clone(),
+                    PersistedDocFile {
+                        modified_ms: file.modified_ms,
+                        size: file.size,
+                        chunks,
+                    },
+                ))
+            })
+            .collect();
+
+        let mut indexed: HashMap<String, PersistedDocFile> = HashMap::new();
+        if let Some(previous) = previous_files {
+            for file in &unchanged {
+                if let Some(entry) = previous.get(&file.path) {
+                    indexed.insert(file.path.clone(), entry.clone());
+                }
+            }
+        }
+        let reindexed_files = parsed.len();
+        indexed.extend(parsed);
+        let removed_files = previous_files.map_or(0, |previous| {
+            previous
+                .keys()
+                .filter(|p| !indexed.contains_key(*p))
+                .count()
+        });
+
+        let index = Self::from_persisted(PersistedDocsIndex {
+            version: DOCS_INDEX_VERSION,
+            root_path: root_path.to_string(),
+            globs,
+            last_updated: chrono::Utc::now().timestamp(),
+            files: indexed,
+        });
+        let stats = DocsIndexStats {
+            root_path: root_path.to_string(),
+            file_count: index.persisted.files.len(),
+            chunk_count: index.entries.len(),
+            reindexed_files,
+            removed_files,
+            duration_ms: start.elapsed().as_millis() as u64,
+        };
+        (index, stats)
+    }
+
+    /// Rank chunks by BM25 over the query terms. Chunks containing all the terms as a
+    /// contiguous phrase are boosted above ones where they are scattered.
+    pub fn search(&self, query: &str, max_results: usize) -> Vec<DocsSearchResult> {
+        let mut terms = tokenize(query);
+        let phrase = format!(" {} ", terms.join(" "));
+        terms.sort();
+        terms.dedup();
+
+        let total = self.entries.len() as f64;
+        let mut scores: HashMap<usize, f64> = HashMap::new();
+        for term in &terms {
+            let Some(postings) = self.postings.get(term) else {
+                continue;
+            };
+            let df = postings.len() as f64;
+            let idf = (1.0 + (total - df + 0.5) / (df + 0.5)).ln();
+            for &(index, frequency) in postings {
+                let tf = frequency as f64;
+                let length_norm = 1.0 - BM25_B
+                    + BM25_B * self.entries[index].length as f64 / self.average_length.max(1.0);
+                *scores.entry(index).or_default() +=
+                    idf * tf * (BM25_K1 + 1.0) / (tf + BM25_K1 * length_norm);
+            }
+        }
+
+        let mut ranked: Vec<(usize, f64)> = scores
+            .into_iter()
+            .map(|(index, score)| {
+                let entry = &self.entries[index];
+                let is_phrase = terms.len() > 1 && entry.token_text.contains(&phrase);
+                (
+                    index,
+                    if is_phrase {
+                        score * PHRASE_BOOST
+                    } else {
+                        score
+                    },
+                )
+            })
+            .collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+        ranked.truncate(max_results);
+
+        ranked
+            .into_iter()
+            .map(|(index, score)| {
+                let entry = &self.entries[index];
+                DocsSearchResult {
+                    file_path: entry.file_path.clone(),
+                    anchor: entry.chunk.anchor.clone(),
+                    start_line: entry.chunk.start_line,
+                    score,
+                    snippet: snippet(&entry.chunk.text, &terms),
+                }
+            })
+            .collect()
+    }
+}
+
+/// A window of the chunk around the first query term, whitespace collapsed
+fn snippet(text: &str, terms: &[String]) -> String {
+    let lower = text.to_lowercase();
+    // Lowercasing can change byte offsets for a few scripts; fall back to the start then
+    let position = if lower.len() == text.len() {
+        terms.iter().filter_map(|t| lower.find(t.as_str())).min()
+    } else {
+        None
+    }
+    .unwrap_or(0);
+
+    let mut start = position.saturating_sub(SNIPPET_BYTES / 3);
+    while !text.is_char_boundary(start) {
+        start -= 1;
+    }
+    let mut end = (start + SNIPPET_BYTES).min(text.len());
+    while !text.is_char_boundary(end) {
+        end += 1;
+    }
+    let body = text[start..end]
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    format!(
+        "{}{}{}",
+        if start > 0 { "…" } else { "" },
+        body,
+        if end < text.len() { "…" } else { "" }
+    )
+}
+
+// ============================================================================
+// Persistence and commands
+// ============================================================================
+
+fn docs_index_path(app_handle: &AppHandle, root_path: &str) -> Result<PathBuf, String> {
+    Ok(get_index_dir(app_handle)?.join(format!("docs-{}.json", get_project_hash(root_path))))
+}
+
+fn load_persisted(path: &Path, root_path: &str) -> Option<PersistedDocsIndex> {
+    let json = fs::read_to_string(path).ok()?;
+    let persisted: PersistedDocsIndex = serde_json::from_str(&json).ok()?;
+    if persisted.version != DOCS_INDEX_VERSION || persisted.root_path != root_path {
+        log::warn!("Discarding outdated docs index at {}", path.display());
+        let _ = fs::remove_file(path);
+        return None;
+    }
+    Some(persisted)
+}
+
+fn save_persisted(path: &Path, persisted: &PersistedDocsIndex) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create index directory: {}", e))?;
+    }
+    let json = serde_json::to_string(persisted)
+        .map_err(|e| format!("Failed to serialize docs index: {}", e))?;
+    fs::write(path, json).map_err(|e| format!("Failed to write docs index: {}", e))
+}
+
+/// The in-memory index for a project, loading the persisted one on first use
+fn cached_index(app_handle: &AppHandle, root_path: &str) -> Result<Option<Arc<DocsIndex>>, String> {
+    if let Some(index) = DOCS_INDEXES
+        .lock()
+        .map_err(|e| e.to_string())?
+        .get(root_path)
+    {
+        return Ok(Some(index.clone()));
+    }
+    let path = docs_index_path(app_handle, root_path)?;
+    let Some(persisted) = load_persisted(&path, root_path) else {
+        return Ok(None);
+    };
+    let index = Arc::new(DocsIndex::from_persisted(persisted));
+    DOCS_INDEXES
+        .lock()
+        .map_err(|e| e.to_string())?
+        .insert(root_path.to_string(), index.clone());
+    Ok(Some(index))
+}
+
+/// Build or refresh the docs index for a project. Only files whose mtime or size changed
+/// since the last build are parsed. Emits `docs-index-progress` while parsing.
+#[tauri::command]
+pub async fn build_docs_index(
+    app_handle: AppHandle,
+    root_path: String,
+    globs: Option<Vec<String>>,
+) -> Result<DocsIndexStats, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let previous = cached_index(&app_handle, &root_path)?;
+        let emit = |phase: &str, processed: usize, total: usize| {
+            let event = DocsIndexProgress {
+                root_path: &root_path,
+                phase,
+                processed,
+                total,
+            };
+            if let Err(e) = crate::events::emit_docs_index_progress(&app_handle, &event) {
+                log::error!("Failed to emit docs index progress: {}", e);
+            }
+        };
+
+        let (index, stats) = DocsIndex::build(
+            &root_path,
+            globs.filter(|g| !g.is_empty()),
+            previous.as_deref(),
+            &|processed, total| emit("indexing", processed, total),
+        );
+        emit("done", stats.file_count, stats.file_count);
+
+        save_persisted(&docs_index_path(&app_handle, &root_path)?, &index.persisted)?;
+        DOCS_INDEXES
+            .lock()
+            .map_err(|e| e.to_string())?
+            .insert(root_path.clone(), Arc::new(index));
+        log::info!(
+            "Built docs index for {}: {} files ({} reindexed, {} removed), {} chunks in {}ms",
+            root_path,
+            stats.file_count,
+            stats.reindexed_files,
+            stats.removed_files,
+            stats.chunk_count,
+            stats.duration_ms
+        );
+        Ok(stats)
+    })
+    .await
+    .map_err(|e| format!("Docs indexing task failed: {}", e))?
+}
+
+/// Search the docs index of a project, best matches first
+#[tauri::command]
+pub async fn query_docs_index(
+    app_handle: AppHandle,
+    root_path: String,
+    query: String,
+    max_results: Option<usize>,
+) -> Result<Vec<DocsSearchResult>, String> {
+    let index = cached_index(&app_handle, &root_path)?
+        .ok_or_else(|| format!("No docs index for {}; build it first", root_path))?;
+    Ok(index.search(&query, max_results.unwrap_or(DEFAULT_MAX_RESULTS)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn create_docs_fixture() -> TempDir {
+        let temp_dir = TempDir::new().unwrap();
+        let docs = temp_dir.path().join("docs");
+        fs::create_dir(&docs).unwrap();
+        fs::write(
+            docs.join("caching.md"),
+            "# Caching\n\n## Invalidation\n\nEntries are dropped by cache invalidation when a \
+             file changes on disk.\n",
+        )
+        .unwrap();
+        fs::write(
+            docs.join("overview.md"),
+            "# Overview\n\nThe cache keeps parsed trees. Memory is bounded and the cache is \
+             trimmed on idle. Invalidation of watchers happens on window close, and the \
+             invalidation queue is flushed. More cache details are elsewhere.\n\n```md\n\
+             # Not a heading\n```\n",
+        )
+        .unwrap();
+        fs::write(
+            docs.join("unrelated.md"),
+            "# Theming\n\nColors and fonts.\n",
+        )
+        .unwrap();
+        fs::write(
+            temp_dir.path().join("lib.rs"),
+            "//! Crate docs\n\n/// Parses the project config\n#[derive(Debug)]\npub struct \
+             ConfigParser;\n\n// ordinary comment\nfn helper() {}\n",
+        )
+        .unwrap();
+        temp_dir
+    }
+
+    #[test]
+    fn test_chunking_markdown_and_doc_comments() {
+        let chunks = chunk_markdown(
+            "Intro\n# Guide\n## Setup\nstep\n```\n# not heading\n```\n# Next\n",
+            "/r/README.md",
+        );
+        let anchors: Vec<&str> = chunks.iter().map(|c| c.anchor.as_str()).collect();
+        assert_eq!(anchors, vec!["README.md", "Guide", "Guide > Setup", "Next"]);
+        assert_eq!(chunks[2].start_line, 3);
+        assert!(chunks[2].text.contains("# not heading"));
+
+        let source =
+            "/// Parses the config\n#[derive(Debug)]\npub struct Config;\n\n// plain\nfn f() {}\n";
+        let chunks = extract_doc_comments(source, "rust", "/r/lib.rs");
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].anchor, "Config");
+        assert_eq!(chunks[0].text, "Parses the config");
+
+        let source = "class Loader:\n    \"\"\"Loads plugins from disk.\"\"\"\n    pass\n";
+        let chunks = extract_doc_comments(source, "python", "/r/loader.py");
+        assert_eq!(chunks[0].anchor, "Loader");
+        assert_eq!(chunks[0].text, "Loads plugins from disk.");
+
+        let source = "/** Renders the tree */\nexport function renderTree() {}\n";
+        let chunks = extract_doc_comments(source, "typescript", "/r/tree.ts");
+        assert_eq!(chunks[0].anchor, "renderTree");
+    }
+
+    #[test]
+    fn test_exact_phrase_ranks_above_scattered_terms() {
+        let temp_dir = create_docs_fixture();
+        let root = temp_dir.path().to_str().unwrap();
+        let (index, stats) = DocsIndex::build(root, None, None, &|_, _| {});
+        assert_eq!(stats.file_count, 4);
+        assert_eq!(stats.reindexed_files, 4);
+
+        let results = index.search("cache invalidation", 5);
+        assert!(results.len() >= 2);
+        assert!(results[0].file_path.ends_with("caching.md"));
+        assert_eq!(results[0].anchor, "Caching > Invalidation");
+        assert!(results[0].snippet.contains("cache invalidation"));
+        assert!(results[1].file_path.ends_with("overview.md"));
+        assert!(results
+            .iter()
+            .all(|r| !r.file_path.ends_with("unrelated.md")));
+
+        // Doc comments are searchable and anchored at their symbol
+        let results = index.search("project config", 5);
+        assert_eq!(results[0].anchor, "ConfigParser");
+        assert!(index.search("ordinary", 5).is_empty());
+    }
+
+    #[test]
+    fn test_incremental_rebuild_and_persistence() {
+        let temp_dir = create_docs_fixture();
+        let root = temp_dir.path().to_str().unwrap();
+        let (index, _) = DocsIndex::build(root, None, None, &|_, _| {});
+
+        let docs = temp_dir.path().join("docs");
+        fs::write(
+            docs.join("unrelated.md"),
+            "# Theming\n\nColors, fonts and icon packs.\n",
+        )
+        .unwrap();
+        fs::remove_file(docs.join("overview.md")).unwrap();
+        let progress = Mutex::new(Vec::new());
+        let (index, stats) = DocsIndex::build(root, None, Some(&index), &|done, total| {
+            progress.lock().unwrap().push((done, total));
+        });
+        assert_eq!(stats.reindexed_files, 1);
+        assert_eq!(stats.removed_files, 1);
+        assert_eq!(*progress.lock().unwrap(), vec![(1, 1)]);
+        assert_eq!(index.search("icon packs", 1)[0].anchor, "Theming");
+
+        let path = temp_dir.path().join("index").join("docs.json");
+        save_persisted(&path, &index.persisted).unwrap();
+        let loaded = DocsIndex::from_persisted(load_persisted(&path, root).unwrap());
+        assert_eq!(loaded.entries.len(), index.entries.len());
+        assert!(load_persisted(&path, "/elsewhere").is_none());
+    }
+}
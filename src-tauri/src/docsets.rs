@@ -0,0 +1,793 @@
+// Docsets module
+// Offline documentation for standard libraries from Dash/Zeal docsets. The user imports a
+// docset archive, which is unpacked with the archive module's hardened extraction into
+// app_data_dir/docsets/<id>. Each docset's own sqlite search index is opened with a
+// separate Database connection. Hover falls back to a docset lookup when the code index
+// has no definition for the symbol.
+
+use crate::archive::{extract_tarball, ExtractTarballRequest};
+use crate::code_navigation::CodeNavState;
+use crate::database::Database;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager, State};
+use tokio::sync::Mutex;
+
+pub(crate) const DOCSETS_DIR_NAME: &str = "docsets";
+const INDEX_PATH: &str = "Contents/Resources/docSet.dsidx";
+const DOCUMENTS_PATH: &str = "Contents/Resources/Documents";
+const INFO_PLIST_PATH: &str = "Contents/Info.plist";
+const DEFAULT_MAX_RESULTS: usize = 20;
+/// Rows fetched from the index per requested result, before ranking
+const CANDIDATE_FACTOR: usize = 20;
+const MIN_CANDIDATES: usize = 200;
+/// Documentation pages larger than this are not summarized
+const MAX_SUMMARY_SOURCE_BYTES: u64 = 4 * 1024 * 1024;
+const SUMMARY_CHARS: usize = 600;
+
+lazy_static::lazy_static! {
+    /// Open search index connections, by index path
+    static ref CONNECTIONS: Mutex<HashMap<PathBuf, Arc<Database>>> = Mutex::new(HashMap::new());
+    static ref PLIST_NAME: Regex =
+        Regex::new(r"<key>CFBundleName</key>\s*<string>([^<]*)</string>").unwrap();
+    static ref PLIST_FAMILY: Regex =
+        Regex::new(r"<key>DocSetPlatformFamily</key>\s*<string>([^<]*)</string>").unwrap();
+    static ref DASH_ENTRY_PREFIX: Regex = Regex::new(r"^(?:<dash_entry_[^>]*>)+").unwrap();
+    static ref SCRIPT_OR_STYLE: Regex =
+        Regex::new(r"(?is)<(script|style)\b.*?</(script|style)>").unwrap();
+    static ref HTML_TAG: Regex = Regex::new(r"(?s)<[^>]*>").unwrap();
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DocsetInfo {
+    pub id: String,
+    pub name: String,
+    /// DocSetPlatformFamily from Info.plist, e.g. "rust" or "python"
+    pub platform: Option<String>,
+    pub path: String,
+    pub entry_count: u64,
+}
+
+/// How a search result matched the query, best first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchKind {
+    Exact,
+    /// The last segment of a qualified name, e.g. "push" in "Vec::push"
+    Segment,
+    Prefix,
+    Contains,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DocsetSearchResult {
+    /// "<docset id>/<row id>", as accepted by docset_get_entry
+    pub id: String,
+    pub docset_id: String,
+    pub name: String,
+    /// Dash entry type, e.g. "Method" or "Struct"
+    pub entry_type: String,
+    pub match_kind: MatchKind,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DocsetEntry {
+    pub id: String,
+    pub docset_id: String,
+    pub name: String,
+    pub entry_type: String,
+    /// The HTML or markdown page to display
+    pub file_path: String,
+    /// Fragment within the page, without '#'
+    pub anchor: Option<String>,
+    /// Plain text from the page starting at the anchor
+    pub summary: Option<String>,
+}
+
+/// Installed docsets under one directory
+pub struct DocsetLibrary {
+    root: PathBuf,
+}
+
+fn slugify(name: &str) -> String {
+    let slug: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    slug.split('-')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Name and platform family from a docset's Info.plist
+fn read_info(docset_dir: &Path) -> (Option<String>, Option<String>) {
+    let Ok(plist) = fs::read_to_string(docset_dir.join(INFO_PLIST_PATH)) else {
+        return (None, None);
+    };
+    let capture = |regex: &Regex| {
+        regex
+            .captures(&plist)
+            .map(|c| c[1].trim().to_string())
+            .filter(|value| !value.is_empty())
+    };
+    (capture(&PLIST_NAME), capture(&PLIST_FAMILY))
+}
+
+/// The `.docset` directory inside an extracted archive
+fn find_docset_dir(extracted: &Path) -> Option<PathBuf> {
+    if extracted.join(INDEX_PATH).is_file() {
+        return Some(extracted.to_path_buf());
+    }
+    let mut pending = vec![(extracted.to_path_buf(), 0)];
+    while let Some((dir, depth)) = pending.pop() {
+        for entry in fs::read_dir(&dir).ok()?.flatten() {
+            let path = entry.path();
+            if !path.is_dir() {
+                continue;
+            }
+            if path.join(INDEX_PATH).is_file() {
+                return Some(path);
+            }
+            if depth < 2 {
+                pending.push((path, depth + 1));
+            }
+        }
+    }
+    None
+}
+
+fn match_kind(name: &str, query: &str) -> Option<MatchKind> {
+    let name = name.to_lowercase();
+    let query = query.to_lowercase();
+    if name == query {
+        Some(MatchKind::Exact)
+    } else if name
+        .rsplit(['.', ':', '/', '#'])
+        .next()
+        .is_some_and(|segment| segment == query)
+    {
+        Some(MatchKind::Segment)
+    } else if name.starts_with(&query) {
+        Some(MatchKind::Prefix)
+    } else if name.contains(&query) {
+        Some(MatchKind::Contains)
+    } else {
+        None
+    }
+}
+
+/// Dash stores page paths with optional `<dash_entry_...>` metadata and a `#fragment`
+fn split_entry_path(raw: &str) -> (String, Option<String>) {
+    let path = DASH_ENTRY_PREFIX.replace(raw, "");
+    match path.split_once('#') {
+        Some((file, anchor)) => (
+            file.to_string(),
+            Some(anchor.to_string()).filter(|a| !a.is_empty()),
+        ),
+        None => (path.to_string(), None),
+    }
+}
+
+fn decode_entities(text: &str) -> String {
+    text.replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&#x27;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Plain text of a documentation page from the anchor onwards
+fn summarize_page(path: &Path, anchor: Option<&str>) -> Option<String> {
+    if fs::metadata(path).ok()?.len() > MAX_SUMMARY_SOURCE_BYTES {
+        return None;
+    }
+    let content = fs::read_to_string(path).ok()?;
+    let is_markdown = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("md"));
+
+    let text = if is_markdown {
+        content
+    } else {
+        let start = anchor
+            .and_then(|anchor| {
+                [format!("id=\"{}\"", anchor), format!("name=\"{}\"", anchor)]
+                    .iter()
+                    .filter_map(|attr| content.find(attr.as_str()))
+                    .min()
+            })
+            .and_then(|i| content[..i].rfind('<'))
+            .or_else(|| content.find("<body"))
+            .unwrap_or(0);
+        let html = SCRIPT_OR_STYLE.replace_all(&content[start..], " ");
+        decode_entities(&HTML_TAG.replace_all(&html, " "))
+    };
+
+    let collapsed = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if collapsed.is_empty() {
+        return None;
+    }
+    Some(match collapsed.char_indices().nth(SUMMARY_CHARS) {
+        Some((end, _)) => format!("{}…", &collapsed[..end]),
+        None => collapsed,
+    })
+}
+
+/// `relative` under `base`, refusing paths that climb out of it
+fn contained_path(base: &Path, relative: &str) -> Option<PathBuf> {
+    let relative = Path::new(relative);
+    if relative
+        .components()
+        .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir))
+    {
+        return None;
+    }
+    Some(base.join(relative))
+}
+
+fn row_str(row: &Value, column: &str) -> String {
+    row[column].as_str().unwrap_or_default().to_string()
+}
+
+impl DocsetLibrary {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    fn docset_dir(&self, id: &str) -> Result<PathBuf, String> {
+        if id.is_empty() || slugify(id) != id {
+            return Err(format!("Invalid docset id: {}", id));
+        }
+        let dir = self.root.join(id);
+        if !dir.join(INDEX_PATH).is_file() {
+            return Err(format!("Docset not found: {}", id));
+        }
+        Ok(dir)
+    }
+
+    /// Connection to a docset's search index, opened on first use
+    async fn connection(&self, docset_dir: &Path) -> Result<Arc<Database>, String> {
+        let index_path = docset_dir.join(INDEX_PATH);
+        let mut connections = CONNECTIONS.lock().await;
+        if let Some(db) = connections.get(&index_path) {
+            return Ok(db.clone());
+        }
+        let db = Arc::new(Database::new(index_path.to_string_lossy().to_string()));
+        db.connect().await?;
+        connections.insert(index_path, db.clone());
+        Ok(db)
+    }
+
+    async fn info(&self, id: &str, docset_dir: &Path) -> Result<DocsetInfo, String> {
+        let (name, platform) = read_info(docset_dir);
+        let db = self.connection(docset_dir).await?;
+        let count = db
+            .query("SELECT COUNT(*) AS count FROM searchIndex", vec![])
+            .await
+            .map_err(|e| format!("Unsupported docset index in {}: {}", id, e))?;
+        Ok(DocsetInfo {
+            id: id.to_string(),
+            name: name.unwrap_or_else(|| id.to_string()),
+            platform,
+            path: docset_dir.to_string_lossy().to_string(),
+            entry_count: count
+                .rows
+                .first()
+                .and_then(|r| r["count"].as_u64())
+                .unwrap_or(0),
+        })
+    }
+
+    pub async fn list(&self) -> Result<Vec<DocsetInfo>, String> {
+        let Ok(entries) = fs::read_dir(&self.root) else {
+            return Ok(Vec::new());
+        };
+        let mut ids: Vec<String> = entries
+            .flatten()
+            .filter(|entry| entry.path().join(INDEX_PATH).is_file())
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .filter(|id| !id.starts_with('.'))
+            .collect();
+        ids.sort();
+
+        let mut docsets = Vec::new();
+        for id in ids {
+            match self.info(&id, &self.root.join(&id)).await {
+                Ok(info) => docsets.push(info),
+                Err(e) => log::warn!("Skipping docset {}: {}", id, e),
+            }
+        }
+        Ok(docsets)
+    }
+
+    /// Install a docset from a .tgz / .tar.gz archive
+    pub async fn import(&self, archive_path: &Path) -> Result<DocsetInfo, String> {
+        fs::create_dir_all(&self.root)
+            .map_err(|e| format!("Failed to create docsets directory: {}", e))?;
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or(0);
+        let staging = self.root.join(format!(".import-{}", nanos));
+
+        let result = self.install_from(archive_path, &staging).await;
+        if staging.exists() {
+            let _ = fs::remove_dir_all(&staging);
+        }
+        result
+    }
+
+    async fn install_from(
+        &self,
+        archive_path: &Path,
+        staging: &Path,
+    ) -> Result<DocsetInfo, String> {
+        let extracted = extract_tarball(ExtractTarballRequest {
+            tarball_path: archive_path.to_string_lossy().to_string(),
+            dest_dir: staging.to_string_lossy().to_string(),
+        })?;
+        if !extracted.success {
+            return Err(extracted
+                .error
+                .unwrap_or_else(|| "Failed to extract docset archive".to_string()));
+        }
+
+        let docset_dir = find_docset_dir(staging)
+            .ok_or_else(|| format!("No docset found in {}", archive_path.display()))?;
+        let (name, _) = read_info(&docset_dir);
+        let stem = docset_dir
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let id = slugify(name.as_deref().unwrap_or(&stem));
+        if id.is_empty() {
+            return Err("Docset has no usable name".to_string());
+        }
+        let target = self.root.join(&id);
+        if target.exists() {
+            return Err(format!("Docset {} is already installed", id));
+        }
+
+        fs::rename(&docset_dir, &target).map_err(|e| format!("Failed to install docset: {}", e))?;
+        match self.info(&id, &target).await {
+            Ok(info) => {
+                log::info!("Imported docset {} ({} entries)", id, info.entry_count);
+                Ok(info)
+            }
+            Err(e) => {
+                self.close(&target).await;
+                let _ = fs::remove_dir_all(&target);
+                Err(e)
+            }
+        }
+    }
+
+    async fn close(&self, docset_dir: &Path) {
+        let db = CONNECTIONS
+            .lock()
+            .await
+            .remove(&docset_dir.join(INDEX_PATH));
+        if let Some(db) = db {
+            let _ = db.close().await;
+        }
+    }
+
+    /// Uninstall a docset. Returns false when it was not installed.
+    pub async fn remove(&self, id: &str) -> Result<bool, String> {
+        let Ok(dir) = self.docset_dir(id) else {
+            return Ok(false);
+        };
+        self.close(&dir).await;
+        fs::remove_dir_all(&dir).map_err(|e| format!("Failed to remove docset {}: {}", id, e))?;
+        log::info!("Removed docset {}", id);
+        Ok(true)
+    }
+
+    /// Entries whose name contains `query`, exact and qualified-name matches first.
+    /// Searches every installed docset when `docset_ids` is None.
+    pub async fn search(
+        &self,
+        query: &str,
+        docset_ids: Option<&[String]>,
+        max_results: usize,
+    ) -> Result<Vec<DocsetSearchResult>, String> {
+        let query = query.trim();
+        if query.is_empty() || max_results == 0 {
+            return Ok(Vec::new());
+        }
+        let ids: Vec<String> = match docset_ids {
+            Some(ids) => ids.to_vec(),
+            None => self.list().await?.into_iter().map(|info| info.id).collect(),
+        };
+
+        let escaped = query
+            .replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_");
+        let candidates = (max_results * CANDIDATE_FACTOR).max(MIN_CANDIDATES);
+        let mut results = Vec::new();
+        for id in ids {
+            let db = self.connection(&self.docset_dir(&id)?).await?;
+            let rows = db
+                .query(
+                    "SELECT id, name, type FROM searchIndex WHERE name LIKE ? ESCAPE '\\' \
+                     ORDER BY length(name), name LIMIT ?",
+                    vec![
+                        Value::from(format!("%{}%", escaped)),
+                        Value::from(candidates as i64),
+                    ],
+                )
+                .await?;
+            for row in rows.rows {
+                let name = row_str(&row, "name");
+                let Some(kind) = match_kind(&name, query) else {
+                    continue;
+                };
+                results.push(DocsetSearchResult {
+                    id: format!("{}/{}", id, row["id"].as_i64().unwrap_or_default()),
+                    docset_id: id.clone(),
+                    name,
+                    entry_type: row_str(&row, "type"),
+                    match_kind: kind,
+                });
+            }
+        }
+
+        results.sort_by(|a, b| {
+            a.match_kind
+                .cmp(&b.match_kind)
+                .then(a.name.len().cmp(&b.name.len()))
+                .then(a.name.cmp(&b.name))
+        });
+        results.truncate(max_results);
+        Ok(results)
+    }
+
+    pub async fn get_entry(&self, id: &str) -> Result<DocsetEntry, String> {
+        let (docset_id, row_id) = id
+            .rsplit_once('/')
+            .and_then(|(docset, row)| Some((docset, row.parse::<i64>().ok()?)))
+            .ok_or_else(|| format!("Invalid docset entry id: {}", id))?;
+        let docset_dir = self.docset_dir(docset_id)?;
+        let db = self.connection(&docset_dir).await?;
+        let rows = db
+            .query(
+                "SELECT name, type, path FROM searchIndex WHERE id = ?",
+                vec![Value::from(row_id)],
+            )
+            .await?;
+        let row = rows
+            .rows
+            .first()
+            .ok_or_else(|| format!("Docset entry not found: {}", id))?;
+
+        let (file, anchor) = split_entry_path(&row_str(row, "path"));
+        let file_path = contained_path(&docset_dir.join(DOCUMENTS_PATH), &file)
+            .ok_or_else(|| format!("Docset entry {} points outside the docset", id))?;
+        Ok(DocsetEntry {
+            id: id.to_string(),
+            docset_id: docset_id.to_string(),
+            name: row_str(row, "name"),
+            entry_type: row_str(row, "type"),
+            summary: summarize_page(&file_path, anchor.as_deref()),
+            file_path: file_path.to_string_lossy().to_string(),
+            anchor,
+        })
+    }
+}
+
+/// Whether a docset documents the language family (as used by the code index)
+fn covers_lang_family(info: &DocsetInfo, lang_family: &str) -> bool {
+    let keywords: &[&str] = match lang_family {
+        "rust" => &["rust"],
+        "python" => &["python", "python3"],
+        "js_family" => &["javascript", "typescript", "nodejs", "node", "js", "mdn"],
+        "go" => &["go", "golang"],
+        "java" => &["java"],
+        "c_family" => &["c", "cpp"],
+        _ => return false,
+    };
+    let text = format!(
+        "{} {}",
+        info.name.replace("C++", "cpp"),
+        info.platform.as_deref().unwrap_or_default()
+    )
+    .to_lowercase();
+    text.split(|c: char| !c.is_ascii_alphanumeric())
+        .any(|word| keywords.contains(&word))
+}
+
+fn get_library(app_handle: &AppHandle) -> Result<DocsetLibrary, String> {
+    let app_data_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+    Ok(DocsetLibrary::new(app_data_dir.join(DOCSETS_DIR_NAME)))
+}
+
+#[tauri::command]
+pub async fn docset_list(app_handle: AppHandle) -> Result<Vec<DocsetInfo>, String> {
+    get_library(&app_handle)?.list().await
+}
+
+#[tauri::command]
+pub async fn docset_import(
+    app_handle: AppHandle,
+    archive_path: String,
+) -> Result<DocsetInfo, String> {
+    get_library(&app_handle)?
+        .import(Path::new(&archive_path))
+        .await
+}
+
+#[tauri::command]
+pub async fn docset_remove(app_handle: AppHandle, id: String) -> Result<bool, String> {
+    get_library(&app_handle)?.remove(&id).await
+}
+
+#[tauri::command]
+pub async fn docset_search(
+    app_handle: AppHandle,
+    query: String,
+    docset_ids: Option<Vec<String>>,
+    max_results: Option<usize>,
+) -> Result<Vec<DocsetSearchResult>, String> {
+    get_library(&app_handle)?
+        .search(
+            &query,
+            docset_ids.as_deref(),
+            max_results.unwrap_or(DEFAULT_MAX_RESULTS),
+        )
+        .await
+}
+
+#[tauri::command]
+pub async fn docset_get_entry(app_handle: AppHandle, id: String) -> Result<DocsetEntry, String> {
+    get_library(&app_handle)?.get_entry(&id).await
+}
+
+/// Hover fallback: docset entries for a symbol the code index has no definition for.
+/// Only docsets for the language family are consulted, and only exact or
+/// qualified-name matches are returned.
+#[tauri::command]
+pub async fn docset_lookup_symbol(
+    app_handle: AppHandle,
+    state: State<'_, CodeNavState>,
+    symbol_name: String,
+    lang_family: String,
+    max_results: Option<usize>,
+) -> Result<Vec<DocsetSearchResult>, String> {
+    let has_local_definition = state
+        .0
+        .read()
+        .map_err(|e| format!("Failed to acquire read lock: {}", e))?
+        .has_definitions(std::slice::from_ref(&symbol_name), &lang_family)
+        .first()
+        .copied()
+        .unwrap_or(false);
+    if has_local_definition {
+        return Ok(Vec::new());
+    }
+    lookup_symbol(
+        &get_library(&app_handle)?,
+        &symbol_name,
+        &lang_family,
+        max_results.unwrap_or(5),
+    )
+    .await
+}
+
+async fn lookup_symbol(
+    library: &DocsetLibrary,
+    symbol_name: &str,
+    lang_family: &str,
+    max_results: usize,
+) -> Result<Vec<DocsetSearchResult>, String> {
+    let ids: Vec<String> = library
+        .list()
+        .await?
+        .into_iter()
+        .filter(|info| covers_lang_family(info, lang_family))
+        .map(|info| info.id)
+        .collect();
+    if ids.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut results = library.search(symbol_name, Some(&ids), max_results).await?;
+    results.retain(|result| result.match_kind <= MatchKind::Segment);
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::archive::{create_tarball, CreateTarballRequest};
+    use tempfile::TempDir;
+
+    /// A minimal Rust docset packed the way Dash feeds ship them
+    async fn fixture_archive(temp: &Path) -> PathBuf {
+        let docset = temp.join("src/Rust.docset");
+        let resources = docset.join("Contents/Resources");
+        fs::create_dir_all(resources.join("Documents/std/vec")).unwrap();
+        fs::write(
+            docset.join(INFO_PLIST_PATH),
+            "<plist><dict><key>CFBundleName</key><string>Rust</string>\
+             <key>DocSetPlatformFamily</key><string>rust</string></dict></plist>",
+        )
+        .unwrap();
+        fs::write(
+            resources.join("Documents/std/vec/struct.Vec.html"),
+            "<html><head><style>p { color: red }</style></head><body><h1>Struct Vec</h1>\
+             <p>A contiguous growable array type.</p>\
+             <div id=\"method.push\"><h4>pub fn push(&amp;mut self, value: T)</h4>\
+             <p>Appends an element to the back of a collection.</p></div></body></html>",
+        )
+        .unwrap();
+
+        let db = Database::new(resources.join("docSet.dsidx").to_string_lossy().to_string());
+        db.connect().await.unwrap();
+        db.execute(
+            "CREATE TABLE searchIndex(id INTEGER PRIMARY KEY, name TEXT, type TEXT, path TEXT)",
+            vec![],
+        )
+        .await
+        .unwrap();
+        for (name, kind, path) in [
+            ("std::vec::Vec", "Struct", "std/vec/struct.Vec.html"),
+            (
+                "std::vec::Vec::push",
+                "Method",
+                "<dash_entry_name=push>std/vec/struct.Vec.html#method.push",
+            ),
+            (
+                "push_str",
+                "Method",
+                "std/string/struct.String.html#method.push_str",
+            ),
+            ("escape", "Function", "../../../../outside.html"),
+        ] {
+            db.execute(
+                "INSERT INTO searchIndex(name, type, path) VALUES (?, ?, ?)",
+                vec![Value::from(name), Value::from(kind), Value::from(path)],
+            )
+            .await
+            .unwrap();
+        }
+        db.close().await.unwrap();
+
+        let archive = temp.join("Rust.tgz");
+        let created = create_tarball(CreateTarballRequest {
+            source_dir: temp.join("src").to_string_lossy().to_string(),
+            output_path: archive.to_string_lossy().to_string(),
+        })
+        .unwrap();
+        assert!(created.success);
+        archive
+    }
+
+    #[tokio::test]
+    async fn test_import_search_and_get_entry() {
+        let temp = TempDir::new().unwrap();
+        let archive = fixture_archive(temp.path()).await;
+        let library = DocsetLibrary::new(temp.path().join("docsets"));
+
+        let info = library.import(&archive).await.unwrap();
+        assert_eq!(info.id, "rust");
+        assert_eq!(info.name, "Rust");
+        assert_eq!(info.platform.as_deref(), Some("rust"));
+        assert_eq!(info.entry_count, 4);
+        assert_eq!(library.list().await.unwrap(), vec![info.clone()]);
+        // No staging directories are left behind
+        assert_eq!(
+            fs::read_dir(temp.path().join("docsets")).unwrap().count(),
+            1
+        );
+
+        let err = library.import(&archive).await.unwrap_err();
+        assert!(err.contains("already installed"), "{}", err);
+
+        let results = library.search("push", None, 10).await.unwrap();
+        let names: Vec<&str> = results.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, vec!["std::vec::Vec::push", "push_str"]);
+        assert_eq!(results[0].match_kind, MatchKind::Segment);
+        assert_eq!(results[1].match_kind, MatchKind::Prefix);
+        assert_eq!(results[0].entry_type, "Method");
+
+        let entry = library.get_entry(&results[0].id).await.unwrap();
+        assert_eq!(entry.anchor.as_deref(), Some("method.push"));
+        assert!(entry.file_path.ends_with("std/vec/struct.Vec.html"));
+        assert!(Path::new(&entry.file_path).is_file());
+        let summary = entry.summary.unwrap();
+        assert!(
+            summary.starts_with("pub fn push(&mut self, value: T) Appends an element"),
+            "{}",
+            summary
+        );
+
+        // Index paths may not reach outside the docset
+        let escape = library.search("escape", None, 1).await.unwrap();
+        assert!(library.get_entry(&escape[0].id).await.is_err());
+
+        assert!(library.remove("rust").await.unwrap());
+        assert!(!library.remove("rust").await.unwrap());
+        assert!(library.list().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_lookup_symbol_filters_by_language_and_match() {
+        let temp = TempDir::new().unwrap();
+        let archive = fixture_archive(temp.path()).await;
+        let library = DocsetLibrary::new(temp.path().join("docsets"));
+        library.import(&archive).await.unwrap();
+
+        let found = lookup_symbol(&library, "push", "rust", 5).await.unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].name, "std::vec::Vec::push");
+
+        assert!(lookup_symbol(&library, "push", "python", 5)
+            .await
+            .unwrap()
+            .is_empty());
+        // Prefix matches are not good enough for hover
+        assert!(lookup_symbol(&library, "push_s", "rust", 5)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_import_rejects_archive_without_docset() {
+        let temp = TempDir::new().unwrap();
+        fs::create_dir_all(temp.path().join("src")).unwrap();
+        fs::write(temp.path().join("src/readme.txt"), "not a docset").unwrap();
+        let archive = temp.path().join("bad.tgz");
+        create_tarball(CreateTarballRequest {
+            source_dir: temp.path().join("src").to_string_lossy().to_string(),
+            output_path: archive.to_string_lossy().to_string(),
+        })
+        .unwrap();
+
+        let library = DocsetLibrary::new(temp.path().join("docsets"));
+        let err = library.import(&archive).await.unwrap_err();
+        assert!(err.contains("No docset found"), "{}", err);
+        assert!(library.list().await.unwrap().is_empty());
+        assert_eq!(
+            fs::read_dir(temp.path().join("docsets")).unwrap().count(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_split_entry_path_and_slugify() {
+        assert_eq!(
+            split_entry_path("<dash_entry_name=a><dash_entry_menuDescription=b>x/y.html#frag"),
+            ("x/y.html".to_string(), Some("frag".to_string()))
+        );
+        assert_eq!(
+            split_entry_path("index.html"),
+            ("index.html".to_string(), None)
+        );
+        assert_eq!(slugify("Python 3"), "python-3");
+        assert_eq!(slugify("C++"), "c");
+        assert!(contained_path(Path::new("/docs"), "../etc/passwd").is_none());
+        assert!(contained_path(Path::new("/docs"), "/etc/passwd").is_none());
+    }
+}
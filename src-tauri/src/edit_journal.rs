@@ -0,0 +1,560 @@
+// Edit journal module
+// Write-ahead journal for multi-file workspace edits. Before any file is modified, the
+// original contents are backed up to app_data_dir/edit-journal/<id>/ together with a
+// manifest. The manifest is marked complete once every write succeeds, so journals left
+// pending after a crash can be rolled back (or kept) on the next start.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+
+pub(crate) const JOURNAL_DIR_NAME: &str = "edit-journal";
+const MANIFEST_FILE: &str = "manifest.json";
+const BACKUPS_DIR: &str = "backups";
+
+/// Journals older than this are removed on startup
+pub const JOURNAL_RETENTION_DAYS: u64 = 7;
+
+/// Extra free space required on top of the backup size
+const DISK_SPACE_MARGIN_BYTES: u64 = 10 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JournalStatus {
+    Pending,
+    Complete,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JournalFileEntry {
+    pub path: String,
+    /// Whether the file existed before the edit; new files are removed on rollback
+    pub existed: bool,
+    /// SHA-256 of the original content
+    pub original_hash: Option<String>,
+    /// Backup file name inside the journal's backups directory
+    pub backup_file: Option<String>,
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EditJournalManifest {
+    pub id: String,
+    /// Unix timestamp in seconds
+    pub created_at: i64,
+    pub status: JournalStatus,
+    pub files: Vec<JournalFileEntry>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RecoveryMode {
+    /// Restore every file to its original content
+    Rollback,
+    /// Keep the files as they are and discard the journal
+    Keep,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceFileEdit {
+    pub path: String,
+    /// Full new content of the file
+    pub content: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApplyWorkspaceEditsResult {
+    pub journal_id: String,
+    pub files_written: usize,
+    /// Undo journal operation that reverts the edits; None when undo is unavailable
+    pub undo_operation_id: Option<String>,
+}
+
+/// Writer that hashes everything passing through it
+struct HashingWriter<W: Write> {
+    inner: W,
+    hasher: Sha256,
+}
+
+impl<W: Write> Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hasher.update(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Stream `src` into `dest`, returning the SHA-256 of the copied bytes
+fn copy_with_hash(src: &Path, dest: &Path) -> io::Result<String> {
+    let mut reader = BufReader::new(File::open(src)?);
+    let file = File::create(dest)?;
+    let mut writer = HashingWriter {
+        inner: BufWriter::new(file),
+        hasher: Sha256::new(),
+    };
+    io::copy(&mut reader, &mut writer)?;
+    writer.flush()?;
+    let HashingWriter { inner, hasher } = writer;
+    inner.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// Write a file via a temp file and rename so a crash never leaves it half-written
+pub(crate) fn write_file_atomically(path: &Path, content: &[u8]) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let file_name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let temp_path = path.with_file_name(format!(".{}.talkcody-tmp", file_name));
+    {
+        let mut file = File::create(&temp_path)?;
+        file.write_all(content)?;
+        file.sync_all()?;
+    }
+    fs::rename(&temp_path, path)
+}
+
+/// Restore a file from a backup by streaming into a temp file and renaming
+fn restore_file(backup: &Path, path: &Path) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let file_name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let temp_path = path.with_file_name(format!(".{}.talkcody-restore", file_name));
+    copy_with_hash(backup, &temp_path)?;
+    fs::rename(&temp_path, path)
+}
+
+fn ensure_disk_space(dir: &Path, required: u64) -> Result<(), String> {
+    let available = fs2::available_space(dir)
+        .map_err(|e| format!("Failed to check available disk space: {}", e))?;
+    if available < required.saturating_add(DISK_SPACE_MARGIN_BYTES) {
+        return Err(format!(
+            "Insufficient disk space for edit backups: {} bytes needed, {} bytes available",
+            required, available
+        ));
+    }
+    Ok(())
+}
+
+fn now_secs() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+pub struct EditJournal {
+    root: PathBuf,
+}
+
+impl EditJournal {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    fn journal_dir(&self, id: &str) -> Result<PathBuf, String> {
+        // Ids are generated UUIDs; reject anything that could escape the journal root
+        if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+            return Err(format!("Invalid edit journal id: {}", id));
+        }
+        Ok(self.root.join(id))
+    }
+
+    fn write_manifest(&self, manifest: &EditJournalManifest) -> Result<(), String> {
+        let path = self.journal_dir(&manifest.id)?.join(MANIFEST_FILE);
+        let json = serde_json::to_vec_pretty(manifest)
+            .map_err(|e| format!("Failed to serialize edit journal: {}", e))?;
+        write_file_atomically(&path, &json)
+            .map_err(|e| format!("Failed to write edit journal manifest: {}", e))
+    }
+
+    fn read_manifest(&self, id: &str) -> Result<EditJournalManifest, String> {
+        let path = self.journal_dir(id)?.join(MANIFEST_FILE);
+        let content = fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read edit journal {}: {}", id, e))?;
+        serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse edit journal {}: {}", id, e))
+    }
+
+    /// Back up every file that is about to be modified and record a pending manifest
+    pub fn begin(&self, paths: &[PathBuf]) -> Result<EditJournalManifest, String> {
+        let mut seen = HashSet::new();
+        let paths: Vec<&PathBuf> = paths.iter().filter(|p| seen.insert(*p)).collect();
+
+        let required: u64 = paths
+            .iter()
+            .filter_map(|p| fs::metadata(p).ok())
+            .filter(|m| m.is_file())
+            .map(|m| m.len())
+            .sum();
+
+        fs::create_dir_all(&self.root)
+            .map_err(|e| format!("Failed to create edit journal directory: {}", e))?;
+        ensure_disk_space(&self.root, required)?;
+
+        let id = uuid::Uuid::new_v4().to_string();
+        let journal_dir = self.journal_dir(&id)?;
+        let backups_dir = journal_dir.join(BACKUPS_DIR);
+        fs::create_dir_all(&backups_dir)
+            .map_err(|e| format!("Failed to create edit journal backups: {}", e))?;
+
+        let mut files = Vec::with_capacity(paths.len());
+        for (index, path) in paths.iter().enumerate() {
+            let path_str = path.to_string_lossy().to_string();
+            if path.is_file() {
+                let backup_file = format!("{}.bak", index);
+                let size = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+                let hash = copy_with_hash(path, &backups_dir.join(&backup_file))
+                    .map_err(|e| format!("Failed to back up {}: {}", path_str, e))?;
+                files.push(JournalFileEntry {
+                    path: path_str,
+                    existed: true,
+                    original_hash: Some(hash),
+                    backup_file: Some(backup_file),
+                    size,
+                });
+            } else if path.exists() {
+                return Err(format!("Cannot edit {}: not a regular file", path_str));
+            } else {
+                files.push(JournalFileEntry {
+                    path: path_str,
+                    existed: false,
+                    original_hash: None,
+                    backup_file: None,
+                    size: 0,
+                });
+            }
+        }
+
+        let manifest = EditJournalManifest {
+            id,
+            created_at: now_secs(),
+            status: JournalStatus::Pending,
+            files,
+        };
+        self.write_manifest(&manifest)?;
+        Ok(manifest)
+    }
+
+    /// Mark a journal as complete once all writes have succeeded
+    pub fn complete(&self, manifest: &mut EditJournalManifest) -> Result<(), String> {
+        manifest.status = JournalStatus::Complete;
+        self.write_manifest(manifest)
+    }
+
+    /// Journals that were started but never completed
+    pub fn pending(&self) -> Vec<EditJournalManifest> {
+        let Ok(entries) = fs::read_dir(&self.root) else {
+            return Vec::new();
+        };
+        let mut pending: Vec<EditJournalManifest> = entries
+            .flatten()
+            .filter(|e| e.path().is_dir())
+            .filter_map(|e| self.read_manifest(&e.file_name().to_string_lossy()).ok())
+            .filter(|m| m.status == JournalStatus::Pending)
+            .collect();
+        pending.sort_by_key(|m| std::cmp::Reverse(m.created_at));
+        pending
+    }
+
+    /// Resolve a journal, either restoring the original files or keeping the current state
+    pub fn recover(&self, id: &str, mode: RecoveryMode) -> Result<(), String> {
+        let manifest = self.read_manifest(id)?;
+        let journal_dir = self.journal_dir(id)?;
+
+        if mode == RecoveryMode::Rollback {
+            for file in &manifest.files {
+                let path = Path::new(&file.path);
+                match &file.backup_file {
+                    Some(backup_file) => {
+                        restore_file(&journal_dir.join(BACKUPS_DIR).join(backup_file), path)
+                            .map_err(|e| format!("Failed to restore {}: {}", file.path, e))?;
+                    }
+                    None if !file.existed && path.exists() => {
+                        fs::remove_file(path)
+                            .map_err(|e| format!("Failed to remove {}: {}", file.path, e))?;
+                    }
+                    None => {}
+                }
+            }
+        }
+
+        fs::remove_dir_all(&journal_dir)
+            .map_err(|e| format!("Failed to remove edit journal {}: {}", id, e))?;
+        log::info!("Resolved edit journal {} ({:?})", id, mode);
+        Ok(())
+    }
+
+    /// Remove journals older than `max_age`, returning how many were removed
+    pub fn prune(&self, max_age: Duration) -> usize {
+        let cutoff = now_secs() - max_age.as_secs() as i64;
+        let Ok(entries) = fs::read_dir(&self.root) else {
+            return 0;
+        };
+        let mut removed = 0;
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if !path.is_dir() {
+                continue;
+            }
+            let created_at = self
+                .read_manifest(&entry.file_name().to_string_lossy())
+                .map(|m| m.created_at)
+                .ok()
+                .or_else(|| {
+                    // Journals without a readable manifest fall back to the directory mtime
+                    entry
+                        .metadata()
+                        .and_then(|m| m.modified())
+                        .ok()
+                        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                        .map(|d| d.as_secs() as i64)
+                });
+            if created_at.is_some_and(|t| t < cutoff) && fs::remove_dir_all(&path).is_ok() {
+                removed += 1;
+            }
+        }
+        removed
+    }
+
+    /// Journal the edits, then apply them with `write_file`
+    fn apply_with<F>(
+        &self,
+        edits: &[WorkspaceFileEdit],
+        mut write_file: F,
+    ) -> Result<ApplyWorkspaceEditsResult, String>
+    where
+        F: FnMut(&Path, &[u8]) -> io::Result<()>,
+    {
+        let paths: Vec<PathBuf> = edits.iter().map(|e| PathBuf::from(&e.path)).collect();
+        let mut manifest = self.begin(&paths)?;
+
+        for edit in edits {
+            write_file(Path::new(&edit.path), edit.content.as_bytes()).map_err(|e| {
+                format!(
+                    "Failed to write {}: {}. Edit journal {} can be used to roll back",
+                    edit.path, e, manifest.id
+                )
+            })?;
+        }
+
+        self.complete(&mut manifest)?;
+        Ok(ApplyWorkspaceEditsResult {
+            journal_id: manifest.id,
+            files_written: edits.len(),
+            undo_operation_id: None,
+        })
+    }
+
+    /// Apply the edits and record them in the undo journal when it is available
+    pub fn apply(&self, edits: &[WorkspaceFileEdit]) -> Result<ApplyWorkspaceEditsResult, String> {
+        let pending = crate::undo::journal().and_then(|undo| {
+            let paths: Vec<PathBuf> = edits.iter().map(|e| PathBuf::from(&e.path)).collect();
+            undo.track("Workspace edit", &paths)
+        });
+        let mut result = self.apply_with(edits, write_file_atomically);
+        let undo_operation_id = pending.and_then(|pending| pending.complete(result.is_ok()));
+        if let Ok(ref mut applied) = result {
+            applied.undo_operation_id = undo_operation_id;
+        }
+        result
+    }
+}
+
+fn get_edit_journal(app_handle: &AppHandle) -> Result<EditJournal, String> {
+    let app_data_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+    Ok(EditJournal::new(app_data_dir.join(JOURNAL_DIR_NAME)))
+}
+
+/// Prune old journals and report any left pending by a previous crash
+pub fn check_on_startup(app_data_dir: &Path) {
+    let journal = EditJournal::new(app_data_dir.join(JOURNAL_DIR_NAME));
+    let pruned = journal.prune(Duration::from_secs(JOURNAL_RETENTION_DAYS * 24 * 60 * 60));
+    if pruned > 0 {
+        log::info!("Pruned {} old edit journals", pruned);
+    }
+    let pending = journal.pending();
+    if !pending.is_empty() {
+        log::warn!(
+            "Found {} incomplete edit operations that can be recovered",
+            pending.len()
+        );
+    }
+}
+
+#[tauri::command]
+pub async fn apply_workspace_edits(
+    app_handle: AppHandle,
+    edits: Vec<WorkspaceFileEdit>,
+) -> Result<ApplyWorkspaceEditsResult, String> {
+    let journal = get_edit_journal(&app_handle)?;
+    log::info!("Applying workspace edits to {} files", edits.len());
+    let recording = crate::audit::begin(
+        "apply_workspace_edits",
+        || serde_json::json!({ "edits": edits }),
+    );
+    let result = tauri::async_runtime::spawn_blocking(move || journal.apply(&edits))
+        .await
+        .map_err(|e| format!("Edit task failed: {}", e))
+        .and_then(|result| result);
+    crate::audit::finish(recording, &result);
+    result
+}
+
+#[tauri::command]
+pub fn pending_edit_recoveries(app_handle: AppHandle) -> Result<Vec<EditJournalManifest>, String> {
+    Ok(get_edit_journal(&app_handle)?.pending())
+}
+
+#[tauri::command]
+pub fn recover_edit_operation(
+    app_handle: AppHandle,
+    id: String,
+    mode: RecoveryMode,
+) -> Result<(), String> {
+    get_edit_journal(&app_handle)?.recover(&id, mode)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn setup() -> (TempDir, EditJournal, Vec<WorkspaceFileEdit>) {
+        let temp_dir = TempDir::new().unwrap();
+        let workspace = temp_dir.path().join("workspace");
+        fs::create_dir_all(&workspace).unwrap();
+        for name in ["a.rs", "b.rs", "c.rs"] {
+            fs::write(workspace.join(name), format!("original {}", name)).unwrap();
+        }
+        let edits = ["a.rs", "b.rs", "c.rs", "new.rs"]
+            .iter()
+            .map(|name| WorkspaceFileEdit {
+                path: workspace.join(name).to_string_lossy().to_string(),
+                content: format!("edited {}", name),
+            })
+            .collect();
+        let journal = EditJournal::new(temp_dir.path().join(JOURNAL_DIR_NAME));
+        (temp_dir, journal, edits)
+    }
+
+    /// Apply edits but fail on the third write, as if the app crashed mid-refactor
+    fn apply_failing_on_third_write(
+        journal: &EditJournal,
+        edits: &[WorkspaceFileEdit],
+    ) -> Result<ApplyWorkspaceEditsResult, String> {
+        let mut writes = 0;
+        journal.apply_with(edits, |path, content| {
+            writes += 1;
+            if writes == 3 {
+                return Err(io::Error::other("simulated crash"));
+            }
+            write_file_atomically(path, content)
+        })
+    }
+
+    #[test]
+    fn test_apply_marks_journal_complete() {
+        let (_temp_dir, journal, edits) = setup();
+        let result = journal.apply(&edits).unwrap();
+        assert_eq!(result.files_written, 4);
+        for edit in &edits {
+            assert_eq!(fs::read_to_string(&edit.path).unwrap(), edit.content);
+        }
+        assert!(journal.pending().is_empty());
+        let manifest = journal.read_manifest(&result.journal_id).unwrap();
+        assert_eq!(manifest.status, JournalStatus::Complete);
+    }
+
+    #[test]
+    fn test_failure_leaves_pending_journal() {
+        let (_temp_dir, journal, edits) = setup();
+        let err = apply_failing_on_third_write(&journal, &edits).unwrap_err();
+        assert!(err.contains("simulated crash"));
+
+        // Half-applied: first two files edited, the rest untouched
+        assert_eq!(fs::read_to_string(&edits[0].path).unwrap(), "edited a.rs");
+        assert_eq!(fs::read_to_string(&edits[1].path).unwrap(), "edited b.rs");
+        assert_eq!(fs::read_to_string(&edits[2].path).unwrap(), "original c.rs");
+
+        let pending = journal.pending();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].files.len(), 4);
+        assert!(pending[0].files[0].original_hash.is_some());
+        assert!(!pending[0].files[3].existed);
+    }
+
+    #[test]
+    fn test_rollback_restores_originals() {
+        let (_temp_dir, journal, edits) = setup();
+        apply_failing_on_third_write(&journal, &edits).unwrap_err();
+        // Simulate the new file having been created before the crash
+        fs::write(&edits[3].path, "partial").unwrap();
+
+        let id = journal.pending()[0].id.clone();
+        journal.recover(&id, RecoveryMode::Rollback).unwrap();
+
+        assert_eq!(fs::read_to_string(&edits[0].path).unwrap(), "original a.rs");
+        assert_eq!(fs::read_to_string(&edits[1].path).unwrap(), "original b.rs");
+        assert_eq!(fs::read_to_string(&edits[2].path).unwrap(), "original c.rs");
+        assert!(!Path::new(&edits[3].path).exists());
+        assert!(journal.pending().is_empty());
+    }
+
+    #[test]
+    fn test_keep_discards_journal_without_touching_files() {
+        let (_temp_dir, journal, edits) = setup();
+        apply_failing_on_third_write(&journal, &edits).unwrap_err();
+
+        let id = journal.pending()[0].id.clone();
+        journal.recover(&id, RecoveryMode::Keep).unwrap();
+
+        assert_eq!(fs::read_to_string(&edits[0].path).unwrap(), "edited a.rs");
+        assert!(journal.pending().is_empty());
+        assert!(journal.recover(&id, RecoveryMode::Keep).is_err());
+    }
+
+    #[test]
+    fn test_recover_rejects_path_traversal_ids() {
+        let (_temp_dir, journal, _edits) = setup();
+        assert!(journal.recover("../outside", RecoveryMode::Keep).is_err());
+    }
+
+    #[test]
+    fn test_prune_removes_old_journals() {
+        let (_temp_dir, journal, edits) = setup();
+        let result = journal.apply(&edits).unwrap();
+
+        let mut manifest = journal.read_manifest(&result.journal_id).unwrap();
+        manifest.created_at -= 30 * 24 * 60 * 60;
+        journal.write_manifest(&manifest).unwrap();
+        apply_failing_on_third_write(&journal, &edits).unwrap_err();
+
+        let removed = journal.prune(Duration::from_secs(JOURNAL_RETENTION_DAYS * 24 * 60 * 60));
+        assert_eq!(removed, 1);
+        assert_eq!(journal.pending().len(), 1);
+    }
+}
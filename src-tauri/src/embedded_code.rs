@@ -0,0 +1,294 @@
+// Embedded code module
+// Finds code embedded in other files so code navigation can index it: the <script> blocks
+// of Vue and Svelte single-file components, and, when enabled, TypeScript, JavaScript and
+// Python fences in markdown. Regions keep their position in the container file, so symbols
+// found in them point into the real file.
+
+use crate::database::Database;
+use crate::settings;
+use regex::Regex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tauri::State;
+
+/// Settings key for indexing fenced code blocks in markdown
+pub const MARKDOWN_FENCES_SETTING: &str = "code_nav_markdown_fences";
+
+static MARKDOWN_FENCES: AtomicBool = AtomicBool::new(false);
+
+lazy_static::lazy_static! {
+    static ref LANG_ATTR: Regex =
+        Regex::new(r#"(?i)\blang\s*=\s*["']?([a-z]+)"#).expect("valid lang attribute regex");
+    static ref TYPE_ATTR: Regex =
+        Regex::new(r#"(?i)\btype\s*=\s*["']?([^"'\s>]+)"#).expect("valid type attribute regex");
+}
+
+/// A block of code inside a container file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmbeddedRegion {
+    /// Language of the block: typescript, javascript or python
+    pub lang_id: &'static str,
+    /// Lines of the container before the block's first line
+    pub line_offset: u32,
+    /// The block's code. Text preceding it on its first line is blanked out with spaces so
+    /// columns match the container.
+    pub content: String,
+}
+
+/// Whether a language id names a container of embedded code rather than a language
+pub fn is_container(lang_id: &str) -> bool {
+    matches!(lang_id, "vue" | "svelte" | "markdown")
+}
+
+/// Whether files of this language id are worth indexing at all
+pub fn is_indexable(lang_id: &str) -> bool {
+    lang_id != "markdown" || markdown_fences_enabled()
+}
+
+/// Language families a container can hold, for narrowing reference searches
+pub fn container_families(lang_id: &str) -> &'static [&'static str] {
+    match lang_id {
+        "vue" | "svelte" => &["js_family"],
+        "markdown" if markdown_fences_enabled() => &["js_family", "python"],
+        _ => &[],
+    }
+}
+
+/// Embedded code regions of a container file, in file order. Empty for other languages,
+/// and for markdown while fence indexing is off.
+pub fn extract_regions(lang_id: &str, content: &str) -> Vec<EmbeddedRegion> {
+    match lang_id {
+        "vue" | "svelte" => script_regions(content),
+        "markdown" if markdown_fences_enabled() => fenced_regions(content),
+        _ => Vec::new(),
+    }
+}
+
+fn region(content: &str, start: usize, end: usize, lang_id: &'static str) -> EmbeddedRegion {
+    let line_start = content[..start].rfind('\n').map_or(0, |i| i + 1);
+    EmbeddedRegion {
+        lang_id,
+        line_offset: content[..start].matches('\n').count() as u32,
+        content: format!("{}{}", " ".repeat(start - line_start), &content[start..end]),
+    }
+}
+
+/// Language of a <script> tag from its attributes; None for non-script types such as
+/// templates or JSON
+fn script_language(attributes: &str) -> Option<&'static str> {
+    if let Some(script_type) = TYPE_ATTR.captures(attributes) {
+        let script_type = script_type[1].to_ascii_lowercase();
+        if ![
+            "module",
+            "text/javascript",
+            "application/javascript",
+            "text/typescript",
+        ]
+        .contains(&script_type.as_str())
+        {
+            return None;
+        }
+    }
+    match LANG_ATTR.captures(attributes) {
+        None => Some("javascript"),
+        Some(lang) => match lang[1].to_ascii_lowercase().as_str() {
+            "ts" | "tsx" | "typescript" => Some("typescript"),
+            "js" | "jsx" | "javascript" => Some("javascript"),
+            _ => None,
+        },
+    }
+}
+
+/// End of a tag starting at `start`, skipping '>' inside quoted attribute values
+fn tag_end(content: &str, start: usize) -> Option<usize> {
+    let mut quote = None;
+    for (i, c) in content[start..].char_indices() {
+        match (quote, c) {
+            (Some(q), _) if c == q => quote = None,
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '>') => return Some(start + i),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// <script> blocks of a Vue or Svelte component. The scan is forgiving: HTML comments are
+/// skipped, and an unclosed block runs to the end of the file.
+fn script_regions(content: &str) -> Vec<EmbeddedRegion> {
+    // ASCII lowercasing keeps byte offsets
+    let lower = content.to_ascii_lowercase();
+    let mut regions = Vec::new();
+    let mut pos = 0;
+    while let Some(found) = lower[pos..].find('<') {
+        let start = pos + found;
+        let rest = &lower[start..];
+        if rest.starts_with("<!--") {
+            pos = lower[start..]
+                .find("-->")
+                .map_or(lower.len(), |end| start + end + 3);
+            continue;
+        }
+        let is_script = rest.starts_with("<script")
+            && rest[7..]
+                .chars()
+                .next()
+                .is_some_and(|c| c.is_ascii_whitespace() || c == '>' || c == '/');
+        if !is_script {
+            pos = start + 1;
+            continue;
+        }
+        let Some(open_end) = tag_end(content, start) else {
+            break;
+        };
+        let attributes = &content[start + 7..open_end];
+        pos = open_end + 1;
+        if attributes.trim_end().ends_with('/') {
+            continue;
+        }
+        let body_end = lower[pos..]
+            .find("</script")
+            .map_or(lower.len(), |end| pos + end);
+        if let Some(lang_id) = script_language(attributes) {
+            regions.push(region(content, pos, body_end, lang_id));
+        }
+        pos = body_end;
+    }
+    regions
+}
+
+fn fence_language(info: &str) -> Option<&'static str> {
+    let lang = info.split_whitespace().next()?.trim_start_matches('{');
+    match lang.to_ascii_lowercase().as_str() {
+        "ts" | "tsx" | "typescript" => Some("typescript"),
+        "js" | "jsx" | "mjs" | "javascript" => Some("javascript"),
+        "py" | "python" => Some("python"),
+        _ => None,
+    }
+}
+
+/// Opening or closing fence: up to three spaces, then three or more backticks or tildes
+fn fence(line: &str) -> Option<(char, usize, &str)> {
+    let trimmed = line.trim_start_matches(' ');
+    if line.len() - trimmed.len() > 3 {
+        return None;
+    }
+    let marker = trimmed.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let count = trimmed.chars().take_while(|c| *c == marker).count();
+    (count >= 3).then(|| (marker, count, &trimmed[count..]))
+}
+
+/// Fenced code blocks in markdown tagged with a language code navigation indexes
+fn fenced_regions(content: &str) -> Vec<EmbeddedRegion> {
+    let mut regions = Vec::new();
+    // Marker, length, language and byte offset of the block's first line
+    let mut open: Option<(char, usize, Option<&'static str>, usize)> = None;
+    let mut offset = 0;
+    for line in content.split_inclusive('\n') {
+        let text = line.trim_end_matches(['\n', '\r']);
+        match (open, fence(text)) {
+            (None, Some((marker, count, info))) => {
+                open = Some((marker, count, fence_language(info), offset + line.len()));
+            }
+            (Some((marker, count, lang_id, start)), Some((close, close_count, rest)))
+                if close == marker && close_count >= count && rest.trim().is_empty() =>
+            {
+                if let Some(lang_id) = lang_id {
+                    regions.push(region(content, start, offset, lang_id));
+                }
+                open = None;
+            }
+            _ => {}
+        }
+        offset += line.len();
+    }
+    if let Some((_, _, Some(lang_id), start)) = open {
+        regions.push(region(
+            content,
+            start.min(content.len()),
+            content.len(),
+            lang_id,
+        ));
+    }
+    regions
+}
+
+pub fn markdown_fences_enabled() -> bool {
+    MARKDOWN_FENCES.load(Ordering::Relaxed)
+}
+
+pub fn set_markdown_fences_enabled(enabled: bool) {
+    MARKDOWN_FENCES.store(enabled, Ordering::Relaxed);
+}
+
+/// Apply the persisted markdown fence setting
+pub async fn load_from_settings(db: &Database) -> Result<(), String> {
+    if let Some(enabled) = settings::get_json_setting::<bool>(db, MARKDOWN_FENCES_SETTING).await? {
+        set_markdown_fences_enabled(enabled);
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn code_nav_get_markdown_fences() -> bool {
+    markdown_fences_enabled()
+}
+
+/// Turn indexing of markdown code fences on or off. Markdown files need re-indexing for a
+/// change to show up.
+#[tauri::command]
+pub async fn code_nav_set_markdown_fences(
+    db: State<'_, Arc<Database>>,
+    enabled: bool,
+) -> Result<(), String> {
+    settings::set_json_setting(&db, MARKDOWN_FENCES_SETTING, &enabled).await?;
+    set_markdown_fences_enabled(enabled);
+    log::info!(
+        "Markdown code fence indexing {}",
+        if enabled { "enabled" } else { "disabled" }
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_script_regions() {
+        let content = "<template>\n  <div>{{ a }}</div>\n</template>\n<!-- <script>bad()</script> -->\n<script lang=\"ts\">\nexport function a() {}\n</script>\n<script setup>const b = 1</script>\n<script type=\"text/x-template\">\nnot code\n</script>\n";
+        let regions = script_regions(content);
+        assert_eq!(regions.len(), 2);
+        assert_eq!(regions[0].lang_id, "typescript");
+        assert_eq!(regions[0].line_offset, 4);
+        assert_eq!(
+            regions[0].content,
+            format!("{}\nexport function a() {{}}\n", " ".repeat(18))
+        );
+        // Columns on the tag's line are preserved
+        assert_eq!(regions[1].lang_id, "javascript");
+        assert_eq!(regions[1].line_offset, 7);
+        assert_eq!(regions[1].content, format!("{}const b = 1", " ".repeat(14)));
+    }
+
+    #[test]
+    fn test_unclosed_script_runs_to_end() {
+        let regions = script_regions("<SCRIPT>\nlet x = 1;\n");
+        assert_eq!(regions.len(), 1);
+        assert_eq!(regions[0].content, "        \nlet x = 1;\n");
+        assert!(script_regions("<scripts>\n<script src=\"a.js\" />").is_empty());
+    }
+
+    #[test]
+    fn test_fenced_regions() {
+        let content = "# Title\n\n```ts\nconst a = 1;\n```\n\n````md\n```python\nnot indexed\n```\n````\n\n~~~py\ndef f():\n    pass\n~~~\n```rust\nfn g() {}\n```\n```js\nopen()\n";
+        let regions = fenced_regions(content);
+        let langs: Vec<_> = regions.iter().map(|r| r.lang_id).collect();
+        assert_eq!(langs, ["typescript", "python", "javascript"]);
+        assert_eq!(regions[0].line_offset, 3);
+        assert_eq!(regions[0].content, "const a = 1;\n");
+        assert_eq!(regions[1].line_offset, 13);
+        assert_eq!(regions[1].content, "def f():\n    pass\n");
+        assert_eq!(regions[2].content, "open()\n");
+    }
+}
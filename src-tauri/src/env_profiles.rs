@@ -0,0 +1,785 @@
+// Environment profiles module
+// Named sets of environment variables per project (e.g. "staging", "production"),
+// persisted in the Database. One profile per project can be active; its variables are
+// added to everything the app spawns for that project (terminals, tasks, shell commands,
+// skill scripts) beneath any variables the call itself sets. Values can reference the
+// secret store instead of being stored in plain text; references are resolved at spawn
+// time. Profiles export to JSON with secrets replaced by placeholders for sharing.
+
+use crate::clock::now_ms;
+use crate::database::Database;
+use crate::secrets::{self, SecretStore};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tauri::State;
+
+const SECRET_PREFIX: &str = "env-profile:";
+const EXPORT_FORMAT: &str = "talkcody-env-profile";
+const EXPORT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvProfileVar {
+    pub name: String,
+    /// Plain text value; unset when the value comes from the secret store
+    pub value: Option<String>,
+    /// Name of a secret saved with env_profile_save_secret
+    #[serde(default)]
+    pub secret_ref: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvProfile {
+    pub id: String,
+    pub root_path: String,
+    pub name: String,
+    pub variables: Vec<EnvProfileVar>,
+    /// Whether this is the project's active profile
+    pub active: bool,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+/// A profile as the UI submits it for saving
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvProfileDraft {
+    /// Existing profile to update; a new profile is created when absent
+    pub id: Option<String>,
+    pub root_path: String,
+    pub name: String,
+    #[serde(default)]
+    pub variables: Vec<EnvProfileVar>,
+}
+
+/// Shareable form of a profile, without secret values
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ProfileExport {
+    format: String,
+    version: u32,
+    name: String,
+    variables: Vec<ExportedVar>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportedVar {
+    name: String,
+    /// The value, or a placeholder naming the secret
+    value: String,
+    #[serde(default)]
+    secret: bool,
+}
+
+fn secret_key(name: &str) -> String {
+    format!("{}{}", SECRET_PREFIX, name)
+}
+
+fn secret_placeholder(secret_ref: &str) -> String {
+    format!("${{secret:{}}}", secret_ref)
+}
+
+fn parse_secret_placeholder(value: &str) -> Option<&str> {
+    value
+        .strip_prefix("${secret:")?
+        .strip_suffix('}')
+        .filter(|name| !name.is_empty())
+}
+
+async fn ensure_schema(db: &Database) -> Result<(), String> {
+    db.execute(
+        "CREATE TABLE IF NOT EXISTS env_profiles (
+            id TEXT PRIMARY KEY,
+            root_path TEXT NOT NULL,
+            name TEXT NOT NULL,
+            variables TEXT NOT NULL,
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL
+        )",
+        vec![],
+    )
+    .await?;
+    db.execute(
+        "CREATE INDEX IF NOT EXISTS idx_env_profiles_root_path ON env_profiles(root_path)",
+        vec![],
+    )
+    .await?;
+    db.execute(
+        "CREATE TABLE IF NOT EXISTS env_profile_active (
+            root_path TEXT PRIMARY KEY,
+            profile_id TEXT NOT NULL
+        )",
+        vec![],
+    )
+    .await?;
+    Ok(())
+}
+
+fn profile_from_row(row: &Value) -> EnvProfile {
+    let get_str = |key: &str| row.get(key).and_then(|v| v.as_str()).map(String::from);
+    let get_i64 = |key: &str| row.get(key).and_then(|v| v.as_i64()).unwrap_or_default();
+    EnvProfile {
+        id: get_str("id").unwrap_or_default(),
+        root_path: get_str("root_path").unwrap_or_default(),
+        name: get_str("name").unwrap_or_default(),
+        variables: get_str("variables")
+            .and_then(|vars| serde_json::from_str(&vars).ok())
+            .unwrap_or_default(),
+        active: get_i64("active") != 0,
+        created_at: get_i64("created_at"),
+        updated_at: get_i64("updated_at"),
+    }
+}
+
+const SELECT_PROFILES: &str = "SELECT p.*, (a.profile_id IS NOT NULL) AS active FROM env_profiles p LEFT JOIN env_profile_active a ON a.root_path = p.root_path AND a.profile_id = p.id";
+
+pub async fn get_profile(db: &Database, id: &str) -> Result<Option<EnvProfile>, String> {
+    ensure_schema(db).await?;
+    let result = db
+        .query(
+            &format!("{} WHERE p.id = ?", SELECT_PROFILES),
+            vec![Value::String(id.to_string())],
+        )
+        .await?;
+    Ok(result.rows.first().map(profile_from_row))
+}
+
+pub async fn list_profiles(db: &Database, root_path: &str) -> Result<Vec<EnvProfile>, String> {
+    ensure_schema(db).await?;
+    let result = db
+        .query(
+            &format!("{} WHERE p.root_path = ? ORDER BY p.name", SELECT_PROFILES),
+            vec![Value::String(root_path.to_string())],
+        )
+        .await?;
+    Ok(result.rows.iter().map(profile_from_row).collect())
+}
+
+fn validate_variables(variables: &[EnvProfileVar]) -> Result<(), String> {
+    for var in variables {
+        if var.name.is_empty() || var.name.contains('=') || var.name.contains('\0') {
+            return Err(format!("Invalid environment variable name: {:?}", var.name));
+        }
+        match (&var.value, &var.secret_ref) {
+            (Some(_), Some(_)) => {
+                return Err(format!(
+                    "{} cannot have both a value and a secret reference",
+                    var.name
+                ))
+            }
+            (None, None) => return Err(format!("{} has no value", var.name)),
+            (None, Some(secret_ref)) if secret_ref.is_empty() => {
+                return Err(format!("{} has an empty secret reference", var.name))
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Create a profile, or update it when the draft names an existing one
+pub async fn save_profile(db: &Database, draft: EnvProfileDraft) -> Result<EnvProfile, String> {
+    let EnvProfileDraft {
+        id,
+        root_path,
+        name,
+        variables,
+    } = draft;
+    if name.trim().is_empty() {
+        return Err("Profile name cannot be empty".to_string());
+    }
+    validate_variables(&variables)?;
+    let existing = match id.as_deref() {
+        Some(id) => Some(
+            get_profile(db, id)
+                .await?
+                .ok_or_else(|| format!("Environment profile {} not found", id))?,
+        ),
+        None => {
+            ensure_schema(db).await?;
+            None
+        }
+    };
+    let id = id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+
+    let now = now_ms();
+    let created_at = existing.as_ref().map(|p| p.created_at).unwrap_or(now);
+    let variables_json = serde_json::to_string(&variables).map_err(|e| e.to_string())?;
+    db.execute(
+        "INSERT OR REPLACE INTO env_profiles (id, root_path, name, variables, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?)",
+        vec![
+            Value::String(id.clone()),
+            Value::String(root_path.clone()),
+            Value::String(name.clone()),
+            Value::String(variables_json),
+            Value::Number(created_at.into()),
+            Value::Number(now.into()),
+        ],
+    )
+    .await?;
+
+    Ok(EnvProfile {
+        id,
+        root_path,
+        name,
+        variables,
+        active: existing.is_some_and(|p| p.active),
+        created_at,
+        updated_at: now,
+    })
+}
+
+/// Delete a profile, deactivating it if it was active. Returns false if it did not exist.
+/// Referenced secrets are kept; other profiles may share them.
+pub async fn delete_profile(db: &Database, id: &str) -> Result<bool, String> {
+    ensure_schema(db).await?;
+    let result = db
+        .execute(
+            "DELETE FROM env_profiles WHERE id = ?",
+            vec![Value::String(id.to_string())],
+        )
+        .await?;
+    db.execute(
+        "DELETE FROM env_profile_active WHERE profile_id = ?",
+        vec![Value::String(id.to_string())],
+    )
+    .await?;
+    Ok(result.rows_affected > 0)
+}
+
+/// Make `profile_id` the project's active profile, or deactivate profiles with None
+pub async fn set_active(
+    db: &Database,
+    root_path: &str,
+    profile_id: Option<&str>,
+) -> Result<(), String> {
+    ensure_schema(db).await?;
+    let Some(profile_id) = profile_id else {
+        db.execute(
+            "DELETE FROM env_profile_active WHERE root_path = ?",
+            vec![Value::String(root_path.to_string())],
+        )
+        .await?;
+        return Ok(());
+    };
+    let profile = get_profile(db, profile_id)
+        .await?
+        .ok_or_else(|| format!("Environment profile {} not found", profile_id))?;
+    if profile.root_path != root_path {
+        return Err(format!(
+            "Environment profile {} belongs to {}",
+            profile.name, profile.root_path
+        ));
+    }
+    db.execute(
+        "INSERT OR REPLACE INTO env_profile_active (root_path, profile_id) VALUES (?, ?)",
+        vec![
+            Value::String(root_path.to_string()),
+            Value::String(profile_id.to_string()),
+        ],
+    )
+    .await?;
+    Ok(())
+}
+
+pub async fn active_profile(db: &Database, root_path: &str) -> Result<Option<EnvProfile>, String> {
+    ensure_schema(db).await?;
+    let result = db
+        .query(
+            &format!(
+                "{} WHERE p.root_path = ? AND a.profile_id IS NOT NULL",
+                SELECT_PROFILES
+            ),
+            vec![Value::String(root_path.to_string())],
+        )
+        .await?;
+    Ok(result.rows.first().map(profile_from_row))
+}
+
+/// Variables of a profile, with secret references read from the store
+fn resolve_variables(
+    secrets: &SecretStore,
+    profile: &EnvProfile,
+) -> Result<Vec<(String, String)>, String> {
+    profile
+        .variables
+        .iter()
+        .map(|var| {
+            let value = match (&var.value, &var.secret_ref) {
+                (_, Some(secret_ref)) => {
+                    secrets.get(&secret_key(secret_ref))?.ok_or_else(|| {
+                        format!(
+                            "Secret {} used by {} in environment profile {} is missing",
+                            secret_ref, var.name, profile.name
+                        )
+                    })?
+                }
+                (value, None) => value.clone().unwrap_or_default(),
+            };
+            Ok((var.name.clone(), value))
+        })
+        .collect()
+}
+
+/// Variables of the project's active profile, empty when none is active
+pub async fn active_env(
+    db: &Database,
+    secrets: &SecretStore,
+    root_path: &str,
+) -> Result<Vec<(String, String)>, String> {
+    match active_profile(db, root_path).await? {
+        Some(profile) => resolve_variables(secrets, &profile),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Layer call-site variables over profile variables. Whatever neither sets is inherited
+/// from the app's environment when the result is applied to a command.
+pub fn merge_env(
+    profile: Vec<(String, String)>,
+    call_site: impl IntoIterator<Item = (String, String)>,
+) -> Vec<(String, String)> {
+    let mut merged = profile;
+    for (name, value) in call_site {
+        match merged.iter_mut().find(|(existing, _)| *existing == name) {
+            Some(entry) => entry.1 = value,
+            None => merged.push((name, value)),
+        }
+    }
+    merged
+}
+
+/// Environment for a spawn: the active profile of `root_path` (if any) beneath `call_site`
+pub async fn spawn_env(
+    db: &Database,
+    secrets: &SecretStore,
+    root_path: Option<&str>,
+    call_site: impl IntoIterator<Item = (String, String)>,
+) -> Result<Vec<(String, String)>, String> {
+    let profile = match root_path {
+        Some(root_path) => active_env(db, secrets, root_path).await?,
+        None => Vec::new(),
+    };
+    Ok(merge_env(profile, call_site))
+}
+
+/// JSON for sharing a profile. Secret references become `${secret:name}` placeholders;
+/// secret values never leave the store.
+pub fn export_profile(profile: &EnvProfile) -> Result<String, String> {
+    let export = ProfileExport {
+        format: EXPORT_FORMAT.to_string(),
+        version: EXPORT_VERSION,
+        name: profile.name.clone(),
+        variables: profile
+            .variables
+            .iter()
+            .map(|var| match &var.secret_ref {
+                Some(secret_ref) => ExportedVar {
+                    name: var.name.clone(),
+                    value: secret_placeholder(secret_ref),
+                    secret: true,
+                },
+                None => ExportedVar {
+                    name: var.name.clone(),
+                    value: var.value.clone().unwrap_or_default(),
+                    secret: false,
+                },
+            })
+            .collect(),
+    };
+    serde_json::to_string_pretty(&export).map_err(|e| e.to_string())
+}
+
+/// Create a profile in `root_path` from exported JSON. Placeholders become secret
+/// references; the secrets themselves must be saved on this machine before spawning.
+pub async fn import_profile(
+    db: &Database,
+    root_path: &str,
+    json: &str,
+) -> Result<EnvProfile, String> {
+    let export: ProfileExport =
+        serde_json::from_str(json).map_err(|e| format!("Invalid profile export: {}", e))?;
+    if export.format != EXPORT_FORMAT {
+        return Err(format!(
+            "Not an environment profile export: {}",
+            export.format
+        ));
+    }
+    if export.version > EXPORT_VERSION {
+        return Err(format!(
+            "Profile export version {} is newer than supported",
+            export.version
+        ));
+    }
+    let variables = export
+        .variables
+        .into_iter()
+        .map(|var| {
+            if var.secret {
+                let secret_ref = parse_secret_placeholder(&var.value)
+                    .unwrap_or(&var.name)
+                    .to_string();
+                EnvProfileVar {
+                    name: var.name,
+                    value: None,
+                    secret_ref: Some(secret_ref),
+                }
+            } else {
+                EnvProfileVar {
+                    name: var.name,
+                    value: Some(var.value),
+                    secret_ref: None,
+                }
+            }
+        })
+        .collect();
+    save_profile(
+        db,
+        EnvProfileDraft {
+            id: None,
+            root_path: root_path.to_string(),
+            name: export.name,
+            variables,
+        },
+    )
+    .await
+}
+
+/// Environment for a spawn through the app's secret store, for the command handlers
+pub(crate) async fn spawn_env_for(
+    db: &Database,
+    root_path: Option<&str>,
+    call_site: Option<HashMap<String, String>>,
+) -> Result<Vec<(String, String)>, String> {
+    spawn_env(
+        db,
+        secrets::store(),
+        root_path,
+        call_site.unwrap_or_default(),
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn env_profile_save(
+    db: State<'_, Arc<Database>>,
+    profile: EnvProfileDraft,
+) -> Result<EnvProfile, String> {
+    save_profile(&db, profile).await
+}
+
+#[tauri::command]
+pub async fn env_profile_list(
+    db: State<'_, Arc<Database>>,
+    root_path: String,
+) -> Result<Vec<EnvProfile>, String> {
+    list_profiles(&db, &root_path).await
+}
+
+#[tauri::command]
+pub async fn env_profile_delete(db: State<'_, Arc<Database>>, id: String) -> Result<bool, String> {
+    delete_profile(&db, &id).await
+}
+
+/// Activate a profile for a project; pass no profile to deactivate
+#[tauri::command]
+pub async fn set_active_profile(
+    db: State<'_, Arc<Database>>,
+    root_path: String,
+    profile_id: Option<String>,
+) -> Result<(), String> {
+    set_active(&db, &root_path, profile_id.as_deref()).await
+}
+
+/// Store a value for profiles to reference by `name`
+#[tauri::command]
+pub fn env_profile_save_secret(name: String, value: String) -> Result<(), String> {
+    if name.is_empty() {
+        return Err("Secret name cannot be empty".to_string());
+    }
+    secrets::store().set(&secret_key(&name), &value)
+}
+
+#[tauri::command]
+pub async fn env_profile_export(
+    db: State<'_, Arc<Database>>,
+    id: String,
+) -> Result<String, String> {
+    let profile = get_profile(&db, &id)
+        .await?
+        .ok_or_else(|| format!("Environment profile {} not found", id))?;
+    export_profile(&profile)
+}
+
+#[tauri::command]
+pub async fn env_profile_import(
+    db: State<'_, Arc<Database>>,
+    root_path: String,
+    json: String,
+) -> Result<EnvProfile, String> {
+    import_profile(&db, &root_path, &json).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    async fn test_db(temp_dir: &TempDir) -> Database {
+        let db = Database::new(
+            temp_dir
+                .path()
+                .join("test.db")
+                .to_string_lossy()
+                .to_string(),
+        );
+        db.connect().await.unwrap();
+        db
+    }
+
+    fn plain(name: &str, value: &str) -> EnvProfileVar {
+        EnvProfileVar {
+            name: name.to_string(),
+            value: Some(value.to_string()),
+            secret_ref: None,
+        }
+    }
+
+    fn secret(name: &str, secret_ref: &str) -> EnvProfileVar {
+        EnvProfileVar {
+            name: name.to_string(),
+            value: None,
+            secret_ref: Some(secret_ref.to_string()),
+        }
+    }
+
+    fn draft(root_path: &str, name: &str, variables: Vec<EnvProfileVar>) -> EnvProfileDraft {
+        EnvProfileDraft {
+            id: None,
+            root_path: root_path.to_string(),
+            name: name.to_string(),
+            variables,
+        }
+    }
+
+    fn pairs(env: &[(&str, &str)]) -> Vec<(String, String)> {
+        env.iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_call_site_overrides_profile() {
+        let profile = pairs(&[("API_URL", "https://staging"), ("REGION", "eu")]);
+        let call_site = pairs(&[("API_URL", "http://localhost"), ("DEBUG", "1")]);
+        assert_eq!(
+            merge_env(profile, call_site),
+            pairs(&[
+                ("API_URL", "http://localhost"),
+                ("REGION", "eu"),
+                ("DEBUG", "1")
+            ])
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_spawn_precedence() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = test_db(&temp_dir).await;
+        let secrets = SecretStore::new(temp_dir.path().join("secrets.json"));
+        let profile = save_profile(
+            &db,
+            draft(
+                "/project",
+                "staging",
+                vec![
+                    plain("PROFILE_ONLY", "profile"),
+                    plain("SHARED", "profile"),
+                    plain("HOME", "/profile-home"),
+                ],
+            ),
+        )
+        .await
+        .unwrap();
+        set_active(&db, "/project", Some(&profile.id))
+            .await
+            .unwrap();
+
+        let env = spawn_env(
+            &db,
+            &secrets,
+            Some("/project"),
+            pairs(&[("SHARED", "call-site")]),
+        )
+        .await
+        .unwrap();
+        let output = tokio::process::Command::new("/bin/sh")
+            .arg("-c")
+            .arg("echo \"$PROFILE_ONLY|$SHARED|$HOME|${PATH:+inherited}\"")
+            .envs(env)
+            .output()
+            .await
+            .unwrap();
+        // call-site > profile > inherited
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout).trim(),
+            "profile|call-site|/profile-home|inherited"
+        );
+
+        // Other projects and calls without a project get no profile variables
+        let other = spawn_env(&db, &secrets, Some("/other"), pairs(&[("A", "1")]))
+            .await
+            .unwrap();
+        assert_eq!(other, pairs(&[("A", "1")]));
+        assert!(spawn_env(&db, &secrets, None, Vec::new())
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_secret_references_resolve_at_spawn() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = test_db(&temp_dir).await;
+        let secrets = SecretStore::new(temp_dir.path().join("secrets.json"));
+        let profile = save_profile(
+            &db,
+            draft(
+                "/project",
+                "production",
+                vec![
+                    plain("API_URL", "https://prod"),
+                    secret("API_TOKEN", "prod-token"),
+                ],
+            ),
+        )
+        .await
+        .unwrap();
+        set_active(&db, "/project", Some(&profile.id))
+            .await
+            .unwrap();
+
+        // Missing secrets fail the spawn rather than run without them
+        let error = active_env(&db, &secrets, "/project").await.unwrap_err();
+        assert!(error.contains("prod-token"), "{}", error);
+
+        secrets.set(&secret_key("prod-token"), "t0k3n").unwrap();
+        assert_eq!(
+            active_env(&db, &secrets, "/project").await.unwrap(),
+            pairs(&[("API_URL", "https://prod"), ("API_TOKEN", "t0k3n")])
+        );
+        // The stored profile has only the reference; a rotated secret is picked up
+        let raw = db
+            .query("SELECT variables FROM env_profiles", vec![])
+            .await
+            .unwrap();
+        assert!(!raw.rows[0]["variables"].as_str().unwrap().contains("t0k3n"));
+        secrets.set(&secret_key("prod-token"), "rotated").unwrap();
+        let env = active_env(&db, &secrets, "/project").await.unwrap();
+        assert_eq!(env[1].1, "rotated");
+    }
+
+    #[tokio::test]
+    async fn test_profile_crud_and_activation() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = test_db(&temp_dir).await;
+        let staging = save_profile(&db, draft("/project", "staging", vec![plain("A", "1")]))
+            .await
+            .unwrap();
+        let production = save_profile(&db, draft("/project", "production", vec![]))
+            .await
+            .unwrap();
+        let elsewhere = save_profile(&db, draft("/other", "local", vec![]))
+            .await
+            .unwrap();
+        assert!(set_active(&db, "/project", Some(&elsewhere.id))
+            .await
+            .is_err());
+
+        set_active(&db, "/project", Some(&staging.id))
+            .await
+            .unwrap();
+        set_active(&db, "/project", Some(&production.id))
+            .await
+            .unwrap();
+        let profiles = list_profiles(&db, "/project").await.unwrap();
+        let names: Vec<_> = profiles
+            .iter()
+            .map(|p| (p.name.as_str(), p.active))
+            .collect();
+        assert_eq!(names, [("production", true), ("staging", false)]);
+
+        let updated = save_profile(
+            &db,
+            EnvProfileDraft {
+                id: Some(production.id.clone()),
+                ..draft("/project", "prod", vec![plain("B", "2")])
+            },
+        )
+        .await
+        .unwrap();
+        assert!(updated.active);
+        assert_eq!(updated.created_at, production.created_at);
+
+        let invalid = [
+            vec![plain("A=B", "1")],
+            vec![EnvProfileVar {
+                name: "A".to_string(),
+                value: None,
+                secret_ref: None,
+            }],
+        ];
+        for variables in invalid {
+            assert!(save_profile(&db, draft("/project", "x", variables))
+                .await
+                .is_err());
+        }
+
+        assert!(delete_profile(&db, &production.id).await.unwrap());
+        assert!(!delete_profile(&db, &production.id).await.unwrap());
+        assert!(active_profile(&db, "/project").await.unwrap().is_none());
+        set_active(&db, "/project", Some(&staging.id))
+            .await
+            .unwrap();
+        set_active(&db, "/project", None).await.unwrap();
+        assert!(active_profile(&db, "/project").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_export_replaces_secrets_with_placeholders() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = test_db(&temp_dir).await;
+        let secrets = SecretStore::new(temp_dir.path().join("secrets.json"));
+        secrets.set(&secret_key("staging-token"), "t0k3n").unwrap();
+        let profile = save_profile(
+            &db,
+            draft(
+                "/project",
+                "staging",
+                vec![
+                    plain("API_URL", "https://staging"),
+                    secret("API_TOKEN", "staging-token"),
+                ],
+            ),
+        )
+        .await
+        .unwrap();
+
+        let json = export_profile(&profile).unwrap();
+        assert!(!json.contains("t0k3n"));
+        assert!(json.contains("${secret:staging-token}"));
+
+        let imported = import_profile(&db, "/teammate", &json).await.unwrap();
+        assert_ne!(imported.id, profile.id);
+        assert_eq!(imported.name, "staging");
+        assert_eq!(imported.variables, profile.variables);
+        assert!(import_profile(&db, "/teammate", "{\"format\":\"other\"}")
+            .await
+            .is_err());
+    }
+}
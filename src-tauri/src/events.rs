@@ -0,0 +1,752 @@
+// Event contract registry
+// Every event the backend sends to the frontend, with the type of its payload. Emit sites
+// go through the typed helpers below rather than calling `emit` with a string, so a name
+// or payload can't drift from what is declared here, and `get_event_schema` hands the
+// frontend a JSON schema per event to generate its listener types from. A unit test fails
+// when crate source emits a literal event name that is not in the table.
+
+use crate::activity::Activity;
+use crate::code_navigation::LanguageHealth;
+use crate::docs_index::DocsIndexProgress;
+use crate::git::refresh::InProgressOperation;
+use crate::http_proxy::{ChunkPayload, EndPayload, SsePayload};
+use crate::index_persist::PersistProgress;
+use crate::language_mapping::LanguageMappingsChanged;
+use crate::lint::LintResult;
+use crate::lsp::{LspDownloadProgress, LspMessageEvent};
+use crate::oauth_callback_server::OAuthCallbackResult;
+use crate::path_access::PathAccessDeniedPayload;
+use crate::project_reconcile::ReconcileSummary;
+use crate::project_root::InferredRoot;
+use crate::search::{SearchComplete, SearchResult};
+use crate::ssh::HostKeyPrompt;
+use crate::tasks::TaskOutputEvent;
+use crate::terminal::PtyOutput;
+use crate::watch_conditions::WatchConditionEvent;
+use crate::websocket::{WebSocketMessage, WsPoolEvent};
+use schemars::schema::RootSchema;
+use schemars::{schema_for, JsonSchema};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use tauri::{Emitter, Runtime};
+
+pub const ACTIVITY_UPDATED: &str = "activity-updated";
+pub const BROADCAST_REQUEST: &str = "broadcast-request";
+pub const CODE_NAV_LANGUAGE_DEGRADED: &str = "code-nav-language-degraded";
+pub const DOCS_INDEX_PROGRESS: &str = "docs-index-progress";
+pub const FILE_SYSTEM_CHANGED: &str = "file-system-changed";
+pub const GIT_OPERATION_COMPLETE: &str = "git-operation-complete";
+pub const GIT_STATUS_CHANGED: &str = "git-status-changed";
+pub const INDEX_PERSIST_PROGRESS: &str = "index-persist-progress";
+pub const LANGUAGE_MAPPINGS_CHANGED: &str = "language-mappings-changed";
+pub const LINT_RESULT: &str = "lint-result";
+pub const LSP_DOWNLOAD_PROGRESS: &str = "lsp-download-progress";
+pub const LSP_MESSAGE: &str = "lsp-message";
+pub const OPENAI_OAUTH_CALLBACK: &str = "openai-oauth-callback";
+pub const PATH_ACCESS_DENIED: &str = "path-access-denied";
+pub const PROJECT_ROOT_INFERRED: &str = "project-root-inferred";
+pub const PROJECT_STATE_RECONCILED: &str = "project-state-reconciled";
+pub const PTY_CLOSE: &str = "pty-close";
+pub const PTY_OUTPUT: &str = "pty-output";
+/// Suffixed with the request id
+pub const SEARCH_COMPLETE: &str = "search-complete";
+/// Suffixed with the request id
+pub const SEARCH_RESULT: &str = "search-result";
+pub const SINGLE_INSTANCE: &str = "single-instance";
+pub const SSH_HOST_KEY_VERIFICATION: &str = "ssh-host-key-verification";
+/// Suffixed with the request id
+pub const STREAM_RESPONSE: &str = "stream-response";
+pub const TASK_OUTPUT: &str = "task-output";
+pub const WATCH_CONDITION_MET: &str = "watch-condition-met";
+pub const WATCH_CONDITION_TIMEOUT: &str = "watch-condition-timeout";
+pub const WS_CLOSED: &str = "ws-closed";
+pub const WS_CONNECTED: &str = "ws-connected";
+pub const WS_ERROR: &str = "ws-error";
+pub const WS_MESSAGE: &str = "ws-message";
+pub const WS_POOL_EVENT: &str = "ws-pool-event";
+
+// ============================================================================
+// Payloads without a home module
+// ============================================================================
+
+/// Arguments of a second launch, forwarded to the running instance
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SingleInstancePayload {
+    pub args: Vec<String>,
+    pub cwd: String,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct PtyClose {
+    pub pty_id: String,
+}
+
+/// A request every targeted window answers with `reply_to_broadcast`
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BroadcastRequest<'a> {
+    pub correlation_id: &'a str,
+    /// What is asked, e.g. "save-dirty-editors"
+    pub request_kind: &'a str,
+    pub payload: &'a serde_json::Value,
+}
+
+/// Payload of a window-scoped file-system-changed event. Carries the label so listeners
+/// shared between windows can filter.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct WindowPathsChanged<'a> {
+    pub window_label: &'a str,
+    pub paths: &'a [PathBuf],
+}
+
+/// Payload of a window-scoped git-status-changed event
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct WindowGitStatusChanged<'a> {
+    pub window_label: &'a str,
+    /// A rebase, merge or similar is under way; listeners can skip expensive refreshes
+    /// until git-operation-complete
+    pub in_progress_operation: Option<InProgressOperation>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GitOperationComplete<'a> {
+    /// Absent on the global watcher's event
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub window_label: Option<&'a str>,
+    pub operation: InProgressOperation,
+}
+
+/// One message of a streamed HTTP response: raw bytes, or parsed server-sent events when
+/// the request asked for them, then a final end marker
+#[derive(Clone, Serialize, JsonSchema)]
+#[serde(untagged)]
+pub enum StreamResponsePayload {
+    Chunk(ChunkPayload),
+    Sse(SsePayload),
+    End(EndPayload),
+}
+
+// ============================================================================
+// Registry
+// ============================================================================
+
+#[derive(Debug, Clone, Copy)]
+pub struct EventSpec {
+    /// Event name, with a `{placeholder}` suffix for names scoped to a window or request
+    pub name: &'static str,
+    pub description: &'static str,
+    schema: fn() -> RootSchema,
+}
+
+fn schema<T: JsonSchema>() -> RootSchema {
+    schema_for!(T)
+}
+
+const fn event(
+    name: &'static str,
+    description: &'static str,
+    schema: fn() -> RootSchema,
+) -> EventSpec {
+    EventSpec {
+        name,
+        description,
+        schema,
+    }
+}
+
+pub const EVENTS: &[EventSpec] = &[
+    event(
+        ACTIVITY_UPDATED,
+        "An activity started, made progress or finished",
+        schema::<Activity>,
+    ),
+    event(
+        "broadcast-request-{windowLabel}",
+        "A request the window should answer with reply_to_broadcast",
+        schema::<BroadcastRequest<'static>>,
+    ),
+    event(
+        CODE_NAV_LANGUAGE_DEGRADED,
+        "Grammars that failed to load at startup",
+        schema::<Vec<LanguageHealth>>,
+    ),
+    event(
+        DOCS_INDEX_PROGRESS,
+        "Progress of a documentation index build",
+        schema::<DocsIndexProgress<'static>>,
+    ),
+    event(
+        FILE_SYSTEM_CHANGED,
+        "Files changed under the global watcher's root",
+        schema::<Vec<PathBuf>>,
+    ),
+    event(
+        "file-system-changed-{windowLabel}",
+        "Files changed under a window's project root",
+        schema::<WindowPathsChanged<'static>>,
+    ),
+    event(
+        GIT_STATUS_CHANGED,
+        "Git state changed under the global watcher's root",
+        schema::<()>,
+    ),
+    event(
+        "git-status-changed-{windowLabel}",
+        "Git state changed in a window's repository",
+        schema::<WindowGitStatusChanged<'static>>,
+    ),
+    event(
+        GIT_OPERATION_COMPLETE,
+        "The global watcher's repository finished a rebase, merge or similar",
+        schema::<GitOperationComplete<'static>>,
+    ),
+    event(
+        "git-operation-complete-{windowLabel}",
+        "A window's repository finished a rebase, merge or similar; refresh now",
+        schema::<GitOperationComplete<'static>>,
+    ),
+    event(
+        INDEX_PERSIST_PROGRESS,
+        "Progress of saving or loading the code navigation index",
+        schema::<PersistProgress>,
+    ),
+    event(
+        LANGUAGE_MAPPINGS_CHANGED,
+        "Custom language mappings were saved",
+        schema::<LanguageMappingsChanged>,
+    ),
+    event(
+        LINT_RESULT,
+        "Diagnostics for a lint request",
+        schema::<LintResult>,
+    ),
+    event(
+        LSP_DOWNLOAD_PROGRESS,
+        "Progress of a language server download",
+        schema::<LspDownloadProgress>,
+    ),
+    event(
+        LSP_MESSAGE,
+        "A message read from a language server",
+        schema::<LspMessageEvent>,
+    ),
+    event(
+        OPENAI_OAUTH_CALLBACK,
+        "The OAuth callback server received a redirect or gave up",
+        schema::<OAuthCallbackResult>,
+    ),
+    event(
+        PATH_ACCESS_DENIED,
+        "The OS refused access to a path",
+        schema::<PathAccessDeniedPayload>,
+    ),
+    event(
+        PROJECT_ROOT_INFERRED,
+        "A window opened on a subdirectory was rooted at its repository",
+        schema::<InferredRoot>,
+    ),
+    event(
+        PROJECT_STATE_RECONCILED,
+        "Summary of a reconcile that finished in the background",
+        schema::<ReconcileSummary>,
+    ),
+    event(PTY_CLOSE, "A terminal session ended", schema::<PtyClose>),
+    event(
+        PTY_OUTPUT,
+        "Output read from a terminal; empty data when it closed",
+        schema::<PtyOutput>,
+    ),
+    event(
+        "search-complete-{requestId}",
+        "A streamed content search finished, with its totals",
+        schema::<SearchComplete>,
+    ),
+    event(
+        "search-result-{requestId}",
+        "The matches of one file in a streamed content search",
+        schema::<SearchResult>,
+    ),
+    event(
+        SINGLE_INSTANCE,
+        "The app was launched again while running",
+        schema::<SingleInstancePayload>,
+    ),
+    event(
+        SSH_HOST_KEY_VERIFICATION,
+        "An unknown or changed host key needs confirming",
+        schema::<HostKeyPrompt>,
+    ),
+    event(
+        "stream-response-{requestId}",
+        "A chunk, server-sent event or end marker of a streamed HTTP response",
+        schema::<StreamResponsePayload>,
+    ),
+    event(
+        TASK_OUTPUT,
+        "A line of output from a running task",
+        schema::<TaskOutputEvent>,
+    ),
+    event(
+        WATCH_CONDITION_MET,
+        "A watch condition became true",
+        schema::<WatchConditionEvent>,
+    ),
+    event(
+        WATCH_CONDITION_TIMEOUT,
+        "A watch condition gave up",
+        schema::<WatchConditionEvent>,
+    ),
+    event(WS_CLOSED, "The WebSocket was closed", schema::<()>),
+    event(WS_CONNECTED, "The WebSocket connected", schema::<()>),
+    event(WS_ERROR, "The WebSocket failed", schema::<String>),
+    event(
+        WS_MESSAGE,
+        "A text message arrived on the WebSocket",
+        schema::<WebSocketMessage>,
+    ),
+    event(
+        WS_POOL_EVENT,
+        "A pooled WebSocket received a message or closed",
+        schema::<WsPoolEvent>,
+    ),
+];
+
+/// Whether `name` is declared, either exactly or as a scoped name
+pub fn is_registered(name: &str) -> bool {
+    EVENTS.iter().any(|spec| match spec.name.find('{') {
+        Some(brace) => name.len() > brace && name.starts_with(&spec.name[..brace]),
+        None => spec.name == name,
+    })
+}
+
+/// Name of an event sent to one window. Window watchers use `<event>-<label>`, since a
+/// global `listen` in the frontend also hears events sent with `emit_to` to other windows;
+/// the legacy global watcher keeps the bare name.
+pub fn scoped_event_name(event: &str, window_label: Option<&str>) -> String {
+    match window_label {
+        Some(label) => format!("{}-{}", event, label),
+        None => event.to_string(),
+    }
+}
+
+/// JSON schema of every event's payload, keyed by event name
+#[tauri::command]
+pub fn get_event_schema() -> BTreeMap<String, serde_json::Value> {
+    EVENTS
+        .iter()
+        .map(|spec| {
+            let mut schema = serde_json::to_value((spec.schema)()).unwrap_or_default();
+            if let Some(object) = schema.as_object_mut() {
+                object.insert("description".to_string(), spec.description.into());
+            }
+            (spec.name.to_string(), schema)
+        })
+        .collect()
+}
+
+// ============================================================================
+// Typed emit helpers
+// ============================================================================
+
+pub fn emit_activity_updated<R: Runtime>(
+    emitter: &impl Emitter<R>,
+    activity: &Activity,
+) -> tauri::Result<()> {
+    emitter.emit(ACTIVITY_UPDATED, activity)
+}
+
+/// To the window's scoped event
+pub fn emit_broadcast_request<R: Runtime>(
+    emitter: &impl Emitter<R>,
+    window_label: &str,
+    request: &BroadcastRequest,
+) -> tauri::Result<()> {
+    emitter.emit_to(
+        window_label,
+        &scoped_event_name(BROADCAST_REQUEST, Some(window_label)),
+        request,
+    )
+}
+
+pub fn emit_code_nav_language_degraded<R: Runtime>(
+    emitter: &impl Emitter<R>,
+    languages: &[LanguageHealth],
+) -> tauri::Result<()> {
+    emitter.emit(CODE_NAV_LANGUAGE_DEGRADED, languages)
+}
+
+pub fn emit_docs_index_progress<R: Runtime>(
+    emitter: &impl Emitter<R>,
+    progress: &DocsIndexProgress,
+) -> tauri::Result<()> {
+    emitter.emit(DOCS_INDEX_PROGRESS, progress)
+}
+
+/// To the window's scoped event when a label is given, otherwise to every window
+pub fn emit_file_system_changed<R: Runtime>(
+    emitter: &impl Emitter<R>,
+    window_label: Option<&str>,
+    paths: &[PathBuf],
+) -> tauri::Result<()> {
+    match window_label {
+        Some(label) => emitter.emit_to(
+            label,
+            &scoped_event_name(FILE_SYSTEM_CHANGED, Some(label)),
+            WindowPathsChanged {
+                window_label: label,
+                paths,
+            },
+        ),
+        None => emitter.emit(FILE_SYSTEM_CHANGED, paths),
+    }
+}
+
+/// To the window's scoped event when a label is given, otherwise to every window. The
+/// global event keeps its legacy empty payload.
+pub fn emit_git_status_changed<R: Runtime>(
+    emitter: &impl Emitter<R>,
+    window_label: Option<&str>,
+    in_progress_operation: Option<InProgressOperation>,
+) -> tauri::Result<()> {
+    match window_label {
+        Some(label) => emitter.emit_to(
+            label,
+            &scoped_event_name(GIT_STATUS_CHANGED, Some(label)),
+            WindowGitStatusChanged {
+                window_label: label,
+                in_progress_operation,
+            },
+        ),
+        None => emitter.emit(GIT_STATUS_CHANGED, ()),
+    }
+}
+
+/// To the window's scoped event when a label is given, otherwise to every window
+pub fn emit_git_operation_complete<R: Runtime>(
+    emitter: &impl Emitter<R>,
+    window_label: Option<&str>,
+    operation: InProgressOperation,
+) -> tauri::Result<()> {
+    let payload = GitOperationComplete {
+        window_label,
+        operation,
+    };
+    match window_label {
+        Some(label) => emitter.emit_to(
+            label,
+            &scoped_event_name(GIT_OPERATION_COMPLETE, Some(label)),
+            payload,
+        ),
+        None => emitter.emit(GIT_OPERATION_COMPLETE, payload),
+    }
+}
+
+pub fn emit_index_persist_progress<R: Runtime>(
+    emitter: &impl Emitter<R>,
+    progress: &PersistProgress,
+) -> tauri::Result<()> {
+    emitter.emit(INDEX_PERSIST_PROGRESS, progress)
+}
+
+pub fn emit_language_mappings_changed<R: Runtime>(
+    emitter: &impl Emitter<R>,
+    payload: &LanguageMappingsChanged,
+) -> tauri::Result<()> {
+    emitter.emit(LANGUAGE_MAPPINGS_CHANGED, payload)
+}
+
+pub fn emit_lint_result<R: Runtime>(
+    emitter: &impl Emitter<R>,
+    result: &LintResult,
+) -> tauri::Result<()> {
+    emitter.emit(LINT_RESULT, result)
+}
+
+pub fn emit_lsp_download_progress<R: Runtime>(
+    emitter: &impl Emitter<R>,
+    progress: &LspDownloadProgress,
+) -> tauri::Result<()> {
+    emitter.emit(LSP_DOWNLOAD_PROGRESS, progress)
+}
+
+pub fn emit_lsp_message<R: Runtime>(
+    emitter: &impl Emitter<R>,
+    message: &LspMessageEvent,
+) -> tauri::Result<()> {
+    emitter.emit(LSP_MESSAGE, message)
+}
+
+pub fn emit_openai_oauth_callback<R: Runtime>(
+    emitter: &impl Emitter<R>,
+    result: &OAuthCallbackResult,
+) -> tauri::Result<()> {
+    emitter.emit(OPENAI_OAUTH_CALLBACK, result)
+}
+
+pub fn emit_path_access_denied<R: Runtime>(
+    emitter: &impl Emitter<R>,
+    payload: &PathAccessDeniedPayload,
+) -> tauri::Result<()> {
+    emitter.emit(PATH_ACCESS_DENIED, payload)
+}
+
+/// Sent only to the window that was opened
+pub fn emit_project_root_inferred<R: Runtime>(
+    emitter: &impl Emitter<R>,
+    window_label: &str,
+    inferred: &InferredRoot,
+) -> tauri::Result<()> {
+    emitter.emit_to(window_label, PROJECT_ROOT_INFERRED, inferred)
+}
+
+pub fn emit_project_state_reconciled<R: Runtime>(
+    emitter: &impl Emitter<R>,
+    summary: &ReconcileSummary,
+) -> tauri::Result<()> {
+    emitter.emit(PROJECT_STATE_RECONCILED, summary)
+}
+
+pub fn emit_pty_close<R: Runtime>(emitter: &impl Emitter<R>, pty_id: &str) -> tauri::Result<()> {
+    emitter.emit(
+        PTY_CLOSE,
+        PtyClose {
+            pty_id: pty_id.to_string(),
+        },
+    )
+}
+
+pub fn emit_pty_output<R: Runtime>(
+    emitter: &impl Emitter<R>,
+    output: &PtyOutput,
+) -> tauri::Result<()> {
+    emitter.emit(PTY_OUTPUT, output)
+}
+
+/// To the search's own event, `search-complete-<request id>`
+pub fn emit_search_complete<R: Runtime>(
+    emitter: &impl Emitter<R>,
+    request_id: &str,
+    summary: &SearchComplete,
+) -> tauri::Result<()> {
+    emitter.emit(&format!("{}-{}", SEARCH_COMPLETE, request_id), summary)
+}
+
+/// To the search's own event, `search-result-<request id>`
+pub fn emit_search_result<R: Runtime>(
+    emitter: &impl Emitter<R>,
+    request_id: &str,
+    result: &SearchResult,
+) -> tauri::Result<()> {
+    emitter.emit(&format!("{}-{}", SEARCH_RESULT, request_id), result)
+}
+
+pub fn emit_single_instance<R: Runtime>(
+    emitter: &impl Emitter<R>,
+    payload: &SingleInstancePayload,
+) -> tauri::Result<()> {
+    emitter.emit(SINGLE_INSTANCE, payload)
+}
+
+pub fn emit_ssh_host_key_verification<R: Runtime>(
+    emitter: &impl Emitter<R>,
+    prompt: &HostKeyPrompt,
+) -> tauri::Result<()> {
+    emitter.emit(SSH_HOST_KEY_VERIFICATION, prompt)
+}
+
+/// To the request's own event, `stream-response-<request id>`
+pub fn emit_stream_response<R: Runtime>(
+    emitter: &impl Emitter<R>,
+    request_id: u32,
+    payload: StreamResponsePayload,
+) -> tauri::Result<()> {
+    emitter.emit(&format!("{}-{}", STREAM_RESPONSE, request_id), payload)
+}
+
+pub fn emit_task_output<R: Runtime>(
+    emitter: &impl Emitter<R>,
+    event: &TaskOutputEvent,
+) -> tauri::Result<()> {
+    emitter.emit(TASK_OUTPUT, event)
+}
+
+pub fn emit_watch_condition_met<R: Runtime>(
+    emitter: &impl Emitter<R>,
+    event: &WatchConditionEvent,
+) -> tauri::Result<()> {
+    emitter.emit(WATCH_CONDITION_MET, event)
+}
+
+pub fn emit_watch_condition_timeout<R: Runtime>(
+    emitter: &impl Emitter<R>,
+    event: &WatchConditionEvent,
+) -> tauri::Result<()> {
+    emitter.emit(WATCH_CONDITION_TIMEOUT, event)
+}
+
+pub fn emit_ws_closed<R: Runtime>(emitter: &impl Emitter<R>) -> tauri::Result<()> {
+    emitter.emit(WS_CLOSED, ())
+}
+
+pub fn emit_ws_connected<R: Runtime>(emitter: &impl Emitter<R>) -> tauri::Result<()> {
+    emitter.emit(WS_CONNECTED, ())
+}
+
+pub fn emit_ws_error<R: Runtime>(emitter: &impl Emitter<R>, message: &str) -> tauri::Result<()> {
+    emitter.emit(WS_ERROR, message)
+}
+
+pub fn emit_ws_message<R: Runtime>(
+    emitter: &impl Emitter<R>,
+    message: &WebSocketMessage,
+) -> tauri::Result<()> {
+    emitter.emit(WS_MESSAGE, message)
+}
+
+pub fn emit_ws_pool_event<R: Runtime>(
+    emitter: &impl Emitter<R>,
+    event: &WsPoolEvent,
+) -> tauri::Result<()> {
+    emitter.emit(WS_POOL_EVENT, event)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+    use std::path::Path;
+
+    fn rust_sources(dir: &Path, files: &mut Vec<PathBuf>) {
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                rust_sources(&path, files);
+            } else if path.extension().is_some_and(|ext| ext == "rs") {
+                files.push(path);
+            }
+        }
+    }
+
+    #[test]
+    fn test_registry_names_are_unique() {
+        let names: HashSet<&str> = EVENTS.iter().map(|spec| spec.name).collect();
+        assert_eq!(names.len(), EVENTS.len(), "duplicate entries in EVENTS");
+    }
+
+    #[test]
+    fn test_every_event_has_a_schema() {
+        let schemas = get_event_schema();
+        assert_eq!(schemas.len(), EVENTS.len());
+        for spec in EVENTS {
+            let schema = &schemas[spec.name];
+            assert!(schema.is_object(), "no schema for {}", spec.name);
+            assert_eq!(schema["description"], spec.description);
+        }
+        assert!(schemas[LINT_RESULT]["properties"]
+            .get("diagnostics")
+            .is_some());
+    }
+
+    #[test]
+    fn test_scoped_names_are_registered() {
+        assert!(is_registered(FILE_SYSTEM_CHANGED));
+        assert!(is_registered("file-system-changed-window-1"));
+        assert!(is_registered("stream-response-42"));
+        assert!(!is_registered("stream-response-"));
+        assert!(!is_registered("file-changed"));
+    }
+
+    #[test]
+    fn test_source_emits_only_registered_literals() {
+        let pattern =
+            regex::Regex::new(r#"\.emit(?:_to)?\(\s*(?:[^,()"]+,\s*)?"([^"]*)""#).unwrap();
+        let mut files = Vec::new();
+        rust_sources(
+            &Path::new(env!("CARGO_MANIFEST_DIR")).join("src"),
+            &mut files,
+        );
+        assert!(!files.is_empty());
+
+        let mut unregistered = Vec::new();
+        for file in files {
+            let source = std::fs::read_to_string(&file).unwrap();
+            for captures in pattern.captures_iter(&source) {
+                let name = &captures[1];
+                if !is_registered(name) {
+                    unregistered.push(format!("{} in {}", name, file.display()));
+                }
+            }
+        }
+        assert!(
+            unregistered.is_empty(),
+            "events emitted but missing from events::EVENTS: {:?}",
+            unregistered
+        );
+    }
+
+    #[test]
+    fn test_window_watcher_payloads_carry_the_label() {
+        let paths = vec![PathBuf::from("/repo/src/main.rs")];
+
+        assert_eq!(
+            scoped_event_name(FILE_SYSTEM_CHANGED, Some("window-1")),
+            "file-system-changed-window-1"
+        );
+        assert_eq!(
+            serde_json::to_value(WindowPathsChanged {
+                window_label: "window-1",
+                paths: &paths,
+            })
+            .unwrap(),
+            serde_json::json!({ "windowLabel": "window-1", "paths": ["/repo/src/main.rs"] })
+        );
+        assert_eq!(
+            scoped_event_name(GIT_STATUS_CHANGED, Some("window-1")),
+            "git-status-changed-window-1"
+        );
+        assert_eq!(
+            serde_json::to_value(WindowGitStatusChanged {
+                window_label: "window-1",
+                in_progress_operation: Some(InProgressOperation::CherryPick),
+            })
+            .unwrap(),
+            serde_json::json!({ "windowLabel": "window-1", "inProgressOperation": "cherry-pick" })
+        );
+        assert_eq!(
+            serde_json::to_value(GitOperationComplete {
+                window_label: None,
+                operation: InProgressOperation::Rebase,
+            })
+            .unwrap(),
+            serde_json::json!({ "operation": "rebase" })
+        );
+    }
+
+    #[test]
+    fn test_global_watcher_events_keep_legacy_names() {
+        assert_eq!(
+            scoped_event_name(FILE_SYSTEM_CHANGED, None),
+            "file-system-changed"
+        );
+        assert_eq!(
+            scoped_event_name(GIT_STATUS_CHANGED, None),
+            "git-status-changed"
+        );
+    }
+
+    #[test]
+    fn test_stream_response_payloads_are_untagged() {
+        let payload = StreamResponsePayload::End(EndPayload {
+            request_id: 7,
+            status: 0,
+        });
+        assert_eq!(
+            serde_json::to_value(payload).unwrap(),
+            serde_json::json!({ "request_id": 7, "status": 0 })
+        );
+    }
+}
@@ -0,0 +1,224 @@
+// Exclusions module
+// Directory names every walk skips: the built-in list in constants plus names the user
+// adds. Changes bump a generation counter, and long-running walks hold a LiveExclusions
+// handle that compares it on each check, so an exclusion added mid-walk applies to the
+// directories the walk reaches afterwards.
+
+use crate::constants::EXCLUDED_DIRS;
+use crate::database::Database;
+use crate::settings;
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use tauri::State;
+
+/// Settings key holding the user's excluded directory names as JSON
+pub const EXCLUDED_DIRS_SETTING: &str = "excluded_dir_names";
+
+#[derive(Debug, Default)]
+pub struct ExclusionConfig {
+    custom_dirs: HashSet<String>,
+}
+
+impl ExclusionConfig {
+    pub fn excludes_dir(&self, name: &str) -> bool {
+        EXCLUDED_DIRS.contains(&name) || self.custom_dirs.contains(name)
+    }
+
+    /// Whether any directory on `path` is excluded
+    pub fn excludes_path(&self, path: &Path) -> bool {
+        let mut dirs = path.components().rev().skip(1);
+        dirs.any(|component| self.excludes_dir(&component.as_os_str().to_string_lossy()))
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref CONFIG: RwLock<Arc<ExclusionConfig>> = RwLock::new(Arc::default());
+}
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+pub fn current() -> Arc<ExclusionConfig> {
+    CONFIG
+        .read()
+        .map(|config| config.clone())
+        .unwrap_or_default()
+}
+
+/// Whether the user excluded this directory name; the built-in list is checked separately
+pub fn is_custom_excluded(name: &str) -> bool {
+    CONFIG
+        .read()
+        .is_ok_and(|config| config.custom_dirs.contains(name))
+}
+
+pub fn custom_dirs() -> Vec<String> {
+    let mut dirs: Vec<String> = current().custom_dirs.iter().cloned().collect();
+    dirs.sort();
+    dirs
+}
+
+/// Replace the user's excluded directory names. Walks in progress apply the change to the
+/// directories they reach next.
+pub fn set_custom_dirs(dirs: &[String]) -> Result<(), String> {
+    let custom_dirs = dirs
+        .iter()
+        .map(|dir| dir.trim())
+        .filter(|dir| !dir.is_empty())
+        .map(|dir| {
+            if dir.contains(['/', '\\']) {
+                Err(format!("Excluded name '{}' must be a directory name", dir))
+            } else {
+                Ok(dir.to_string())
+            }
+        })
+        .collect::<Result<HashSet<_>, _>>()?;
+    *CONFIG.write().map_err(|e| e.to_string())? = Arc::new(ExclusionConfig { custom_dirs });
+    GENERATION.fetch_add(1, Ordering::SeqCst);
+    Ok(())
+}
+
+/// The exclusions as seen by one long-running walk. Checking for a change costs one atomic
+/// load, so it is cheap to consult per entry; the snapshot is only reloaded when the
+/// generation moved. Share it between walker threads with an Arc.
+pub struct LiveExclusions {
+    snapshot: RwLock<(u64, Arc<ExclusionConfig>)>,
+}
+
+impl Default for LiveExclusions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LiveExclusions {
+    pub fn new() -> Self {
+        let generation = GENERATION.load(Ordering::SeqCst);
+        Self {
+            snapshot: RwLock::new((generation, current())),
+        }
+    }
+
+    /// The configuration to apply to the next entry
+    pub fn config(&self) -> Arc<ExclusionConfig> {
+        let generation = GENERATION.load(Ordering::SeqCst);
+        if let Ok(snapshot) = self.snapshot.read() {
+            if snapshot.0 == generation {
+                return snapshot.1.clone();
+            }
+        }
+        let Ok(mut snapshot) = self.snapshot.write() else {
+            return current();
+        };
+        if snapshot.0 != generation {
+            log::debug!("Exclusions changed during a walk, reloading");
+            *snapshot = (generation, current());
+        }
+        snapshot.1.clone()
+    }
+
+    pub fn excludes_dir(&self, name: &str) -> bool {
+        self.config().excludes_dir(name)
+    }
+
+    pub fn excludes_path(&self, path: &Path) -> bool {
+        self.config().excludes_path(path)
+    }
+}
+
+/// Apply the excluded directory names persisted in the settings table
+pub async fn load_from_settings(db: &Database) -> Result<(), String> {
+    if let Some(dirs) = settings::get_json_setting::<Vec<String>>(db, EXCLUDED_DIRS_SETTING).await?
+    {
+        set_custom_dirs(&dirs)?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn exclusions_get() -> Vec<String> {
+    custom_dirs()
+}
+
+/// Replace the user's excluded directory names and persist them
+#[tauri::command]
+pub async fn exclusions_set(
+    db: State<'_, Arc<Database>>,
+    dirs: Vec<String>,
+) -> Result<Vec<String>, String> {
+    set_custom_dirs(&dirs)?;
+    let dirs = custom_dirs();
+    settings::set_json_setting(&db, EXCLUDED_DIRS_SETTING, &dirs).await?;
+    log::info!("Excluded directory names set to {:?}", dirs);
+    Ok(dirs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ignore::WalkBuilder;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_excludes_path() {
+        let config = ExclusionConfig {
+            custom_dirs: HashSet::from(["generated".to_string()]),
+        };
+        assert!(config.excludes_path(Path::new("/p/node_modules/a.js")));
+        assert!(config.excludes_path(Path::new("/p/src/generated/a.ts")));
+        // The file name itself is not a directory
+        assert!(!config.excludes_path(Path::new("/p/src/generated")));
+        assert!(!config.excludes_path(Path::new("/p/src/a.ts")));
+        assert!(set_custom_dirs(&["a/b".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_exclusion_added_mid_walk_applies_to_later_entries() {
+        // Unique name so concurrent tests relying on the global list are unaffected
+        const FLIPPED: &str = "zz-flipped-exclusion";
+        let temp_dir = TempDir::new().unwrap();
+        let write_files = |dir: &str, count: usize| {
+            let dir = temp_dir.path().join(dir);
+            std::fs::create_dir_all(&dir).unwrap();
+            for i in 0..count {
+                std::fs::write(dir.join(format!("f{:04}.txt", i)), "x").unwrap();
+            }
+            dir
+        };
+        write_files("aa-early", 10);
+        write_files("bb-middle", 20);
+        let late = write_files(&format!("cc-late/{}", FLIPPED), 1);
+
+        let walk = |flip_after: Option<usize>| {
+            let live = Arc::new(LiveExclusions::new());
+            let filter = live.clone();
+            let walker = WalkBuilder::new(temp_dir.path())
+                .sort_by_file_name(|a, b| a.cmp(b))
+                .filter_entry(move |entry| {
+                    let is_dir = entry.file_type().is_some_and(|t| t.is_dir());
+                    let name = entry.file_name().to_string_lossy();
+                    !(is_dir && filter.excludes_dir(&name))
+                })
+                .build();
+            let mut files = Vec::new();
+            for entry in walker.flatten() {
+                if entry.file_type().is_some_and(|t| t.is_file()) {
+                    files.push(entry.into_path());
+                }
+                if Some(files.len()) == flip_after {
+                    set_custom_dirs(&[FLIPPED.to_string()]).unwrap();
+                }
+            }
+            files
+        };
+
+        let files = walk(None);
+        assert!(files.iter().any(|f| f.starts_with(&late)));
+
+        let files = walk(Some(10));
+        set_custom_dirs(&[]).unwrap();
+        // Files seen before the change are kept; the directory reached afterwards is not
+        assert_eq!(files.len(), 30);
+        assert!(!files.iter().any(|f| f.starts_with(&late)));
+    }
+}
@@ -0,0 +1,608 @@
+// Extract selection module
+// Backend half of "move this selection into a new module": checks the selection is made of
+// whole top-level declarations, builds the new file and the trimmed source, and for
+// js_family and python adds an import of the moved names where the source still uses them.
+// Nothing is written here; the plan is applied through apply_workspace_edits. Imports the
+// moved code itself depends on are not carried over to the new file.
+
+use crate::code_navigation::{language_for, CodeNavigationService};
+use crate::edit_journal::WorkspaceFileEdit;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::ops::Range;
+use std::path::{Component, Path, PathBuf};
+use tree_sitter::{Node, Parser};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtractSelectionPlan {
+    /// The new file first, then the updated source file
+    pub edits: Vec<WorkspaceFileEdit>,
+    /// The selected text, as removed from the source
+    pub moved_text: String,
+    /// Top-level names declared by the moved code
+    pub moved_names: Vec<String>,
+    /// Import added to the source, if any of the moved names are still used there
+    pub import_statement: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ImportStyle {
+    JavaScript,
+    Python,
+    /// Languages without generated imports
+    None,
+}
+
+fn import_style(lang_id: &str) -> ImportStyle {
+    match CodeNavigationService::get_lang_family(lang_id) {
+        "js_family" => ImportStyle::JavaScript,
+        "python" => ImportStyle::Python,
+        _ => ImportStyle::None,
+    }
+}
+
+/// Byte range of lines `start_line..=end_line` (1-based), including the final line break
+fn line_range(content: &str, start_line: usize, end_line: usize) -> Result<Range<usize>, String> {
+    if start_line == 0 || end_line < start_line {
+        return Err(format!(
+            "Invalid selection: lines {}-{}",
+            start_line, end_line
+        ));
+    }
+    let mut line_starts = vec![0];
+    line_starts.extend(content.match_indices('\n').map(|(i, _)| i + 1));
+    if line_starts.last() == Some(&content.len()) && !content.is_empty() {
+        line_starts.pop();
+    }
+    if end_line > line_starts.len() {
+        return Err(format!(
+            "Selection ends at line {} but the file has {} lines",
+            end_line,
+            line_starts.len()
+        ));
+    }
+    let end = line_starts.get(end_line).copied().unwrap_or(content.len());
+    Ok(line_starts[start_line - 1]..end)
+}
+
+/// Widen a removed line range over the blank lines it would otherwise leave doubled up
+fn removal_range(content: &str, range: &Range<usize>) -> Range<usize> {
+    let is_blank = |line: &str| line.trim().is_empty();
+    let (mut start, mut end) = (range.start, range.end);
+    let preceded_by_blank = content[..start]
+        .strip_suffix('\n')
+        .is_none_or(|before| is_blank(before.rsplit('\n').next().unwrap_or("")));
+    if preceded_by_blank {
+        while end < content.len() {
+            let line_end = content[end..]
+                .find('\n')
+                .map_or(content.len(), |i| end + i + 1);
+            if !is_blank(&content[end..line_end]) {
+                break;
+            }
+            end = line_end;
+        }
+    }
+    if end == content.len() {
+        while let Some(before) = content[..start].strip_suffix('\n') {
+            let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+            if !is_blank(&before[line_start..]) {
+                break;
+            }
+            start = line_start;
+        }
+    }
+    start..end
+}
+
+fn node_text<'a>(node: Node, source: &'a str) -> &'a str {
+    &source[node.byte_range()]
+}
+
+/// The top-level nodes making up the selection. Fails when the selection cuts through a
+/// node, contains a syntax error or has no code at all.
+fn selected_nodes<'t>(
+    root: Node<'t>,
+    source: &str,
+    range: &Range<usize>,
+) -> Result<Vec<Node<'t>>, String> {
+    let selected = &source[range.clone()];
+    let start = range.start + (selected.len() - selected.trim_start().len());
+    let end = range.start + selected.trim_end().len();
+    if start >= end {
+        return Err("Selection is empty".to_string());
+    }
+
+    let mut cursor = root.walk();
+    let mut nodes = Vec::new();
+    for node in root.children(&mut cursor) {
+        if node.end_byte() <= start || node.start_byte() >= end {
+            continue;
+        }
+        if node.start_byte() < start || node.end_byte() > end {
+            return Err(format!(
+                "Selection splits a {} at line {}; select whole top-level declarations",
+                node.kind(),
+                node.start_position().row + 1
+            ));
+        }
+        if node.has_error() {
+            return Err(format!(
+                "Selection contains a syntax error at line {}",
+                node.start_position().row + 1
+            ));
+        }
+        nodes.push(node);
+    }
+    if nodes.iter().all(|node| node.kind() == "comment") {
+        return Err("Selection contains no code to extract".to_string());
+    }
+    Ok(nodes)
+}
+
+/// Names declared by a top-level node, and whether it is exported already
+fn declared_names(node: Node, source: &str, style: ImportStyle) -> (Vec<String>, bool) {
+    let mut names = Vec::new();
+    match style {
+        ImportStyle::JavaScript => {
+            let (declaration, exported) = if node.kind() == "export_statement" {
+                if node.child_by_field_name("value").is_some()
+                    || node_text(node, source).starts_with("export default")
+                {
+                    // Default exports are moved as-is and not imported by name
+                    return (names, true);
+                }
+                match node.child_by_field_name("declaration") {
+                    Some(declaration) => (declaration, true),
+                    None => return (names, true),
+                }
+            } else {
+                (node, false)
+            };
+            match declaration.kind() {
+                "function_declaration"
+                | "generator_function_declaration"
+                | "class_declaration"
+                | "abstract_class_declaration"
+                | "interface_declaration"
+                | "type_alias_declaration"
+                | "enum_declaration" => {
+                    if let Some(name) = declaration.child_by_field_name("name") {
+                        names.push(node_text(name, source).to_string());
+                    }
+                }
+                "lexical_declaration" | "variable_declaration" => {
+                    let mut cursor = declaration.walk();
+                    for declarator in declaration.named_children(&mut cursor) {
+                        if let Some(name) = declarator
+                            .child_by_field_name("name")
+                            .filter(|name| name.kind() == "identifier")
+                        {
+                            names.push(node_text(name, source).to_string());
+                        }
+                    }
+                }
+                _ => {}
+            }
+            (names, exported)
+        }
+        ImportStyle::Python => {
+            let definition = if node.kind() == "decorated_definition" {
+                node.child_by_field_name("definition")
+            } else {
+                Some(node)
+            };
+            if let Some(definition) = definition {
+                match definition.kind() {
+                    "function_definition" | "class_definition" => {
+                        if let Some(name) = definition.child_by_field_name("name") {
+                            names.push(node_text(name, source).to_string());
+                        }
+                    }
+                    "expression_statement" => {
+                        if let Some(left) = definition
+                            .named_child(0)
+                            .filter(|child| child.kind() == "assignment")
+                            .and_then(|assignment| assignment.child_by_field_name("left"))
+                            .filter(|left| left.kind() == "identifier")
+                        {
+                            names.push(node_text(left, source).to_string());
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            (names, true)
+        }
+        ImportStyle::None => (names, true),
+    }
+}
+
+fn is_import(node: Node, style: ImportStyle) -> bool {
+    match style {
+        ImportStyle::JavaScript => node.kind() == "import_statement",
+        ImportStyle::Python => matches!(
+            node.kind(),
+            "import_statement" | "import_from_statement" | "future_import_statement"
+        ),
+        ImportStyle::None => false,
+    }
+}
+
+/// Directives ("use client") and docstrings that must stay ahead of imports
+fn is_prologue(node: Node) -> bool {
+    node.kind() == "comment"
+        || (node.kind() == "expression_statement"
+            && node
+                .named_child(0)
+                .is_some_and(|child| child.kind() == "string"))
+}
+
+/// Byte offset where a new import goes: after the last top-level import, else after the
+/// leading comments, directives and docstring. The flag is set when it follows an import.
+fn import_offset(root: Node, source: &str, style: ImportStyle) -> (usize, bool) {
+    let mut cursor = root.walk();
+    let children: Vec<Node> = root.children(&mut cursor).collect();
+    let last_import = children.iter().rev().find(|node| is_import(**node, style));
+    let anchor = last_import.or_else(|| {
+        children
+            .iter()
+            .take_while(|node| is_prologue(**node))
+            .last()
+    });
+    let offset = match anchor {
+        Some(node) => source[node.end_byte()..]
+            .find('\n')
+            .map(|i| node.end_byte() + i + 1)
+            .unwrap_or(source.len()),
+        None => 0,
+    };
+    (offset, last_import.is_some())
+}
+
+/// Resolve `.` and `..` without touching the filesystem
+fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    normalized.push("..");
+                }
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+/// `to` relative to the directory `from_dir`, both absolute
+fn relative_path(from_dir: &Path, to: &Path) -> PathBuf {
+    let from: Vec<Component> = from_dir.components().collect();
+    let to: Vec<Component> = to.components().collect();
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+    let mut relative = PathBuf::new();
+    for _ in common..from.len() {
+        relative.push("..");
+    }
+    for component in &to[common..] {
+        relative.push(component);
+    }
+    relative
+}
+
+/// Module specifier for a JS/TS import: relative, forward slashes, no extension
+pub(crate) fn js_module_specifier(from_dir: &Path, new_file: &Path) -> String {
+    let relative = relative_path(from_dir, &new_file.with_extension(""));
+    let specifier = relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect::<Vec<_>>()
+        .join("/");
+    if specifier.starts_with("..") {
+        specifier
+    } else {
+        format!("./{}", specifier)
+    }
+}
+
+/// Dotted module for a Python import. Modules at or below the source's directory are
+/// imported by path from there; anything else uses a relative import.
+fn python_module(from_dir: &Path, new_file: &Path) -> String {
+    let relative = relative_path(from_dir, &new_file.with_extension(""));
+    let mut parents = 0;
+    let mut parts = Vec::new();
+    for component in relative.components() {
+        match component {
+            Component::ParentDir => parents += 1,
+            other => parts.push(other.as_os_str().to_string_lossy().to_string()),
+        }
+    }
+    if parents == 0 {
+        parts.join(".")
+    } else {
+        format!("{}{}", ".".repeat(parents + 1), parts.join("."))
+    }
+}
+
+/// Identifiers appearing anywhere in `text`
+fn identifiers(text: &str) -> HashSet<&str> {
+    text.split(|c: char| !(c.is_alphanumeric() || c == '_' || c == '$'))
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+/// Build the workspace edits that move lines `start_line..=end_line` (1-based) of
+/// `file_path` into `new_file_path`. A relative `new_file_path` is resolved against the
+/// source file's directory.
+pub fn plan_extract_selection(
+    file_path: &str,
+    content: &str,
+    lang_id: &str,
+    start_line: usize,
+    end_line: usize,
+    new_file_path: &str,
+) -> Result<ExtractSelectionPlan, String> {
+    let source_path = Path::new(file_path);
+    let source_dir = source_path.parent().unwrap_or(Path::new(""));
+    let new_path = normalize_path(&source_dir.join(new_file_path));
+    if new_path == source_path {
+        return Err("Cannot extract a selection into its own file".to_string());
+    }
+    if new_path.exists() {
+        return Err(format!("{} already exists", new_path.display()));
+    }
+
+    let language =
+        language_for(lang_id).ok_or_else(|| format!("Unsupported language: {}", lang_id))?;
+    let mut parser = Parser::new();
+    parser
+        .set_language(&language)
+        .map_err(|e| format!("Failed to set language for {}: {}", lang_id, e))?;
+    let tree = parser
+        .parse(content, None)
+        .ok_or_else(|| format!("Failed to parse file: {}", file_path))?;
+    let root = tree.root_node();
+
+    let range = line_range(content, start_line, end_line)?;
+    let nodes = selected_nodes(root, content, &range)?;
+    let style = import_style(lang_id);
+
+    // The new file: the selection, with JS/TS declarations exported so they can be imported
+    let mut moved_names = Vec::new();
+    let mut new_content = String::new();
+    let mut copied_to = range.start;
+    for node in &nodes {
+        let (names, exported) = declared_names(*node, content, style);
+        if !exported && !names.is_empty() {
+            new_content.push_str(&content[copied_to..node.start_byte()]);
+            new_content.push_str("export ");
+            copied_to = node.start_byte();
+        }
+        moved_names.extend(names);
+    }
+    new_content.push_str(&content[copied_to..range.end]);
+    let new_content = format!("{}\n", new_content.trim());
+
+    let removal = removal_range(content, &range);
+    let remaining = format!("{}{}", &content[..removal.start], &content[removal.end..]);
+    let used = identifiers(&remaining);
+    let imported: Vec<&str> = moved_names
+        .iter()
+        .map(String::as_str)
+        .filter(|name| used.contains(name))
+        .collect();
+
+    let import_statement = match style {
+        _ if imported.is_empty() => None,
+        ImportStyle::JavaScript => {
+            let quote = if content.contains("from \"") {
+                '"'
+            } else {
+                '\''
+            };
+            Some(format!(
+                "import {{ {} }} from {}{}{};",
+                imported.join(", "),
+                quote,
+                js_module_specifier(source_dir, &new_path),
+                quote
+            ))
+        }
+        ImportStyle::Python => Some(format!(
+            "from {} import {}",
+            python_module(source_dir, &new_path),
+            imported.join(", ")
+        )),
+        ImportStyle::None => None,
+    };
+
+    let mut updated = remaining;
+    if let Some(statement) = &import_statement {
+        let (offset, after_import) = import_offset(root, content, style);
+        // An anchor inside or after the removed range shifts with it
+        let offset = if offset > removal.start {
+            removal.start.max(offset.saturating_sub(removal.len()))
+        } else {
+            offset
+        };
+        let offset = offset.min(updated.len());
+        let mut line = format!("{}\n", statement);
+        if offset > 0 && !updated[..offset].ends_with('\n') {
+            line.insert(0, '\n');
+        }
+        if !after_import && !updated[offset..].starts_with('\n') {
+            line.push('\n');
+        }
+        updated.insert_str(offset, &line);
+    }
+
+    Ok(ExtractSelectionPlan {
+        edits: vec![
+            WorkspaceFileEdit {
+                path: new_path.to_string_lossy().to_string(),
+                content: new_content,
+            },
+            WorkspaceFileEdit {
+                path: file_path.to_string(),
+                content: updated,
+            },
+        ],
+        moved_text: content[range].to_string(),
+        moved_names,
+        import_statement,
+    })
+}
+
+/// Plan an extract-to-file refactoring. Returns edits for apply_workspace_edits instead of
+/// writing anything.
+#[tauri::command]
+pub async fn extract_selection(
+    file_path: String,
+    content: String,
+    lang_id: String,
+    start_line: usize,
+    end_line: usize,
+    new_file_path: String,
+) -> Result<ExtractSelectionPlan, String> {
+    plan_extract_selection(
+        &file_path,
+        &content,
+        &lang_id,
+        start_line,
+        end_line,
+        &new_file_path,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    const APP_TS: &str = concat!(
+        "import { useState } from 'react';\n",
+        "\n",
+        "export function App() {\n",
+        "  return formatTitle('x');\n",
+        "}\n",
+        "\n",
+        "function formatTitle(title: string): string {\n",
+        "  return title.toUpperCase();\n",
+        "}\n",
+        "\n",
+        "const unusedHelper = 1;\n",
+    );
+
+    #[test]
+    fn test_extract_typescript_function_adds_relative_import() {
+        let temp = TempDir::new().unwrap();
+        let file = temp.path().join("src/components/App.tsx");
+        let plan = plan_extract_selection(
+            &file.to_string_lossy(),
+            APP_TS,
+            "typescript",
+            7,
+            11,
+            "../utils/format.ts",
+        )
+        .unwrap();
+
+        assert_eq!(plan.moved_names, vec!["formatTitle", "unusedHelper"]);
+        assert_eq!(
+            plan.import_statement.as_deref(),
+            Some("import { formatTitle } from '../utils/format';")
+        );
+
+        let new_file = &plan.edits[0];
+        assert_eq!(
+            Path::new(&new_file.path),
+            temp.path().join("src/utils/format.ts")
+        );
+        assert_eq!(
+            new_file.content,
+            "export function formatTitle(title: string): string {\n  return title.toUpperCase();\n}\n\nexport const unusedHelper = 1;\n"
+        );
+
+        let source = &plan.edits[1];
+        assert_eq!(
+            source.content,
+            "import { useState } from 'react';\nimport { formatTitle } from '../utils/format';\n\nexport function App() {\n  return formatTitle('x');\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_extract_into_same_directory_keeps_existing_exports() {
+        let content = "export function a() {}\n\nexport function b() { return a(); }\n";
+        let plan = plan_extract_selection("/repo/src/lib.ts", content, "typescript", 1, 1, "a.ts")
+            .unwrap();
+        assert_eq!(plan.edits[0].content, "export function a() {}\n");
+        assert_eq!(
+            plan.edits[1].content,
+            "import { a } from './a';\n\nexport function b() { return a(); }\n"
+        );
+    }
+
+    #[test]
+    fn test_selection_splitting_a_function_is_rejected() {
+        let err = plan_extract_selection("/repo/src/App.tsx", APP_TS, "typescript", 3, 4, "x.ts")
+            .unwrap_err();
+        assert!(err.contains("splits"), "{}", err);
+
+        let err = plan_extract_selection("/repo/src/App.tsx", APP_TS, "typescript", 8, 8, "x.ts")
+            .unwrap_err();
+        assert!(err.contains("splits"), "{}", err);
+    }
+
+    #[test]
+    fn test_existing_target_and_bad_ranges_are_rejected() {
+        let temp = TempDir::new().unwrap();
+        fs::write(temp.path().join("taken.ts"), "").unwrap();
+        let file = temp.path().join("App.tsx").to_string_lossy().to_string();
+
+        let err =
+            plan_extract_selection(&file, APP_TS, "typescript", 7, 9, "taken.ts").unwrap_err();
+        assert!(err.contains("already exists"), "{}", err);
+
+        assert!(plan_extract_selection(&file, APP_TS, "typescript", 7, 99, "x.ts").is_err());
+        assert!(plan_extract_selection(&file, APP_TS, "typescript", 2, 2, "x.ts").is_err());
+        assert!(plan_extract_selection(&file, APP_TS, "cobol", 7, 9, "x.ts").is_err());
+    }
+
+    #[test]
+    fn test_extract_python_function_after_docstring_and_imports() {
+        let content = "\"\"\"Module doc.\"\"\"\nimport os\n\n\ndef helper(path):\n    return os.path.basename(path)\n\n\ndef main():\n    print(helper('/tmp/x'))\n";
+        let plan = plan_extract_selection(
+            "/repo/app/main.py",
+            content,
+            "python",
+            5,
+            6,
+            "util/paths.py",
+        )
+        .unwrap();
+        assert_eq!(
+            plan.import_statement.as_deref(),
+            Some("from util.paths import helper")
+        );
+        assert_eq!(
+            plan.edits[0].content,
+            "def helper(path):\n    return os.path.basename(path)\n"
+        );
+        assert!(plan.edits[1]
+            .content
+            .starts_with("\"\"\"Module doc.\"\"\"\nimport os\nfrom util.paths import helper\n"));
+        assert!(!plan.edits[1].content.contains("def helper"));
+
+        let parent =
+            plan_extract_selection("/repo/app/main.py", content, "python", 5, 6, "../shared.py")
+                .unwrap();
+        assert_eq!(
+            parent.import_statement.as_deref(),
+            Some("from ..shared import helper")
+        );
+    }
+}
@@ -0,0 +1,650 @@
+// File move module
+// Moves files and folders for drag-and-drop in the explorer. A plain rename fails when the
+// destination is on another device (EXDEV), so those moves fall back to copying the tree,
+// keeping modification times and permissions, and deleting the source. An existing
+// destination is never replaced silently: the overwrite policy decides per move. Once all
+// moves are done, the directory tree cache and the code index are updated in one pass, and
+// the moves are recorded in the undo journal.
+
+use crate::audit;
+use crate::code_navigation::{CodeNavState, CodeNavigationService};
+use crate::directory_tree::{self, DirectoryTreeBuilder};
+use crate::path_wire;
+use crate::project_duplicate::create_symlink;
+use crate::undo::{self, UndoJournal};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use std::time::SystemTime;
+use tauri::{AppHandle, Manager};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MoveRequest {
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OverwritePolicy {
+    /// Leave an existing destination alone and fail the move
+    #[default]
+    Fail,
+    /// Replace the existing destination
+    Overwrite,
+    /// Move to a free "name 2", "name 3", ... next to the destination
+    Rename,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct MoveResult {
+    pub from: String,
+    /// Destination actually used; differs from the requested one under the rename policy.
+    /// None when the move failed.
+    pub to: Option<String>,
+    /// The source was on another device and was copied, then deleted
+    pub copied: bool,
+    pub error: Option<String>,
+}
+
+pub(crate) type RenameFn = fn(&Path, &Path) -> io::Result<()>;
+
+/// `dest` with " 2", " 3", ... added to its name until it is free. Files keep their
+/// extension after the number; folders are numbered at the end.
+fn renamed_variant(dest: &Path, is_dir: bool) -> PathBuf {
+    let parent = dest.parent().unwrap_or(Path::new(""));
+    let name = dest
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let (stem, extension) = match dest.extension() {
+        Some(ext) if !is_dir => (
+            dest.file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            format!(".{}", ext.to_string_lossy()),
+        ),
+        _ => (name, String::new()),
+    };
+    (2..)
+        .map(|n| parent.join(format!("{} {}{}", stem, n, extension)))
+        .find(|candidate| fs::symlink_metadata(candidate).is_err())
+        .unwrap()
+}
+
+fn remove_path(path: &Path) -> io::Result<()> {
+    if fs::symlink_metadata(path)?.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
+
+/// Set a file's or directory's modification time without needing write access to it
+fn set_mtime(path: &Path, mtime: SystemTime) -> io::Result<()> {
+    #[cfg(windows)]
+    let file = {
+        use std::os::windows::fs::OpenOptionsExt;
+        const FILE_WRITE_ATTRIBUTES: u32 = 0x100;
+        // Needed to open directories
+        const FILE_FLAG_BACKUP_SEMANTICS: u32 = 0x0200_0000;
+        fs::OpenOptions::new()
+            .access_mode(FILE_WRITE_ATTRIBUTES)
+            .custom_flags(FILE_FLAG_BACKUP_SEMANTICS)
+            .open(path)?
+    };
+    #[cfg(not(windows))]
+    let file = fs::File::open(path)?;
+    file.set_modified(mtime)
+}
+
+/// Copy `source` to `dest`, which must not exist, keeping permissions and modification
+/// times. Symlinks are recreated, not followed.
+fn copy_tree(source: &Path, dest: &Path) -> io::Result<()> {
+    let metadata = fs::symlink_metadata(source)?;
+    if metadata.file_type().is_symlink() {
+        return create_symlink(&fs::read_link(source)?, dest);
+    }
+    if metadata.is_dir() {
+        fs::create_dir(dest)?;
+        for entry in fs::read_dir(source)? {
+            let entry = entry?;
+            copy_tree(&entry.path(), &dest.join(entry.file_name()))?;
+        }
+        // After the contents, which would otherwise bump the modification time and need
+        // a writable directory
+        fs::set_permissions(dest, metadata.permissions())?;
+    } else {
+        // fs::copy carries the permission bits over
+        fs::copy(source, dest)?;
+    }
+    set_mtime(dest, metadata.modified()?)
+}
+
+/// Rename, or copy and delete when the destination is on another device
+pub(crate) fn rename_or_copy(source: &Path, dest: &Path, rename: RenameFn) -> Result<bool, String> {
+    match rename(source, dest) {
+        Ok(()) => Ok(false),
+        Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+            log::info!(
+                "{} is on another device than {}, copying",
+                dest.display(),
+                source.display()
+            );
+            if let Err(e) = copy_tree(source, dest) {
+                // Leave the source as it was and no half-made copy behind
+                if let Err(cleanup) = remove_path(dest) {
+                    log::warn!(
+                        "Failed to remove partial copy {}: {}",
+                        dest.display(),
+                        cleanup
+                    );
+                }
+                return Err(format!("Failed to copy {}: {}", source.display(), e));
+            }
+            remove_path(source).map_err(|e| {
+                format!(
+                    "Copied to {} but failed to delete {}: {}",
+                    dest.display(),
+                    source.display(),
+                    e
+                )
+            })?;
+            Ok(true)
+        }
+        Err(e) => Err(format!(
+            "Failed to move {} to {}: {}",
+            source.display(),
+            dest.display(),
+            e
+        )),
+    }
+}
+
+/// `path` with its parent directory resolved; the last component is kept as is, so a
+/// symlink names the link rather than its target
+fn resolve(path: &Path) -> Result<PathBuf, String> {
+    let name = path
+        .file_name()
+        .ok_or_else(|| format!("Invalid path: {}", path.display()))?;
+    let parent = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let parent = parent
+        .canonicalize()
+        .map_err(|e| format!("Folder {} is not accessible: {}", parent.display(), e))?;
+    Ok(parent.join(name))
+}
+
+/// Move one path, applying the overwrite policy. Returns the destination used and
+/// whether the source was copied.
+fn move_one(
+    from: &Path,
+    to: &Path,
+    policy: OverwritePolicy,
+    rename: RenameFn,
+) -> Result<(PathBuf, bool), String> {
+    let metadata = fs::symlink_metadata(from)
+        .map_err(|e| format!("Failed to read {}: {}", from.display(), e))?;
+    let source = resolve(from)?;
+    let resolved = resolve(to)?;
+    if resolved == source {
+        return Ok((to.to_path_buf(), false));
+    }
+    if metadata.is_dir() && resolved.starts_with(&source) {
+        return Err(format!("Can't move {} into itself", from.display()));
+    }
+
+    let mut dest = to.to_path_buf();
+    // On a case-insensitive filesystem a destination differing only in case is the source
+    // itself; renaming to it is how the case is changed
+    let same_file = resolved.to_string_lossy().to_lowercase()
+        == source.to_string_lossy().to_lowercase()
+        && to.canonicalize().ok() == from.canonicalize().ok();
+    if fs::symlink_metadata(to).is_ok() && !same_file {
+        match policy {
+            OverwritePolicy::Fail => {
+                return Err(format!("{} already exists", to.display()));
+            }
+            OverwritePolicy::Overwrite => {
+                if source.starts_with(&resolved) {
+                    return Err(format!(
+                        "Can't replace {}, which contains {}",
+                        to.display(),
+                        from.display()
+                    ));
+                }
+                remove_path(to)
+                    .map_err(|e| format!("Failed to replace {}: {}", to.display(), e))?;
+            }
+            OverwritePolicy::Rename => dest = renamed_variant(to, metadata.is_dir()),
+        }
+    }
+
+    let copied = rename_or_copy(from, &dest, rename)?;
+    Ok((dest, copied))
+}
+
+fn move_paths_with(
+    moves: &[MoveRequest],
+    policy: OverwritePolicy,
+    rename: RenameFn,
+) -> Vec<MoveResult> {
+    moves
+        .iter()
+        .map(|request| {
+            match move_one(
+                &path_wire::resolve(&request.from),
+                &path_wire::resolve(&request.to),
+                policy,
+                rename,
+            ) {
+                Ok((dest, copied)) => MoveResult {
+                    from: request.from.clone(),
+                    to: Some(path_wire::encode(&dest)),
+                    copied,
+                    error: None,
+                },
+                Err(e) => MoveResult {
+                    from: request.from.clone(),
+                    to: None,
+                    copied: false,
+                    error: Some(e),
+                },
+            }
+        })
+        .collect()
+}
+
+/// Path of `file` after `from` moved to `to`, if `file` is `from` or lies beneath it
+fn moved_path(file: &str, from: &str, to: &str) -> Option<String> {
+    let rest = file.strip_prefix(from)?;
+    (rest.is_empty() || rest.starts_with(['/', '\\'])).then(|| format!("{}{}", to, rest))
+}
+
+/// Re-index moved source files under their new paths. Only files that were indexed
+/// before the move are touched. Returns the number re-indexed.
+fn update_index(
+    nav: &RwLock<CodeNavigationService>,
+    moved: &[(String, String)],
+) -> Result<usize, String> {
+    let mut service = nav
+        .write()
+        .map_err(|e| format!("Failed to acquire write lock: {}", e))?;
+    let mut reindexed = 0;
+    for (from, to) in moved {
+        let parent = Path::new(from)
+            .parent()
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let mut indexed = service.indexed_files_under(from);
+        indexed.extend(
+            service
+                .indexed_files_under(&parent)
+                .into_iter()
+                .filter(|file| file == from),
+        );
+        for file in indexed {
+            let Some(new_path) = moved_path(&file, from, to) else {
+                continue;
+            };
+            service.clear_file(&file);
+            let Some(lang_id) = CodeNavigationService::get_lang_id_from_path(&new_path) else {
+                continue;
+            };
+            let Ok(content) = fs::read_to_string(&new_path) else {
+                continue;
+            };
+            match service.index_file(&new_path, &content, &lang_id) {
+                Ok(()) => reindexed += 1,
+                Err(e) => log::warn!("Failed to re-index moved file {}: {}", new_path, e),
+            }
+        }
+    }
+    Ok(reindexed)
+}
+
+/// Drop cached trees affected by the moves: those of the moved folders themselves and of
+/// every folder that gained or lost an entry
+fn invalidate_tree(tree: &DirectoryTreeBuilder, moved: &[(String, String)]) {
+    let mut paths = Vec::with_capacity(moved.len() * 2);
+    for (from, to) in moved {
+        tree.invalidate_under(from);
+        tree.invalidate_under(to);
+        paths.push(from.clone());
+        paths.push(to.clone());
+    }
+    tree.invalidate_containing(&paths);
+}
+
+/// Files the moves would replace under the overwrite policy, captured so that undoing
+/// can restore them. Replaced folders are not kept.
+fn replaced_files(moves: &[MoveRequest], policy: OverwritePolicy) -> Vec<PathBuf> {
+    if policy != OverwritePolicy::Overwrite {
+        return Vec::new();
+    }
+    moves
+        .iter()
+        .map(|request| path_wire::resolve(&request.to))
+        .filter(|to| fs::symlink_metadata(to).is_ok_and(|m| m.is_file()))
+        .collect()
+}
+
+/// Move files and folders. Each move succeeds or fails on its own; the results are in
+/// the order of `moves`. The moves made are recorded in `undo` as one operation.
+pub fn move_paths_blocking(
+    nav: Option<&RwLock<CodeNavigationService>>,
+    undo: Option<&UndoJournal>,
+    moves: &[MoveRequest],
+    policy: OverwritePolicy,
+) -> Vec<MoveResult> {
+    let pending = undo.and_then(|undo| undo.track("Move", &replaced_files(moves, policy)));
+    let results = move_paths_with(moves, policy, |from, to| fs::rename(from, to));
+    let moved: Vec<(String, String)> = results
+        .iter()
+        .filter_map(|result| Some((result.from.clone(), result.to.clone()?)))
+        .filter(|(from, to)| from != to)
+        .collect();
+    if let Some(mut pending) = pending {
+        for (from, to) in &moved {
+            pending.record_move(&path_wire::resolve(from), &path_wire::resolve(to));
+        }
+        pending.complete(!moved.is_empty());
+    }
+    if moved.is_empty() {
+        return results;
+    }
+
+    invalidate_tree(directory_tree::tree_builder(), &moved);
+    if let Some(nav) = nav {
+        match update_index(nav, &moved) {
+            Ok(reindexed) => log::info!(
+                "Moved {} paths, re-indexed {} files",
+                moved.len(),
+                reindexed
+            ),
+            Err(e) => log::error!("Failed to update the code index after moves: {}", e),
+        }
+    }
+    results
+}
+
+/// Move files and folders, as for drag-and-drop in the file tree. Cross-device moves
+/// copy and delete. `overwrite_policy` defaults to fail.
+#[tauri::command]
+pub async fn move_paths(
+    app_handle: AppHandle,
+    moves: Vec<MoveRequest>,
+    overwrite_policy: Option<OverwritePolicy>,
+) -> Result<Vec<MoveResult>, String> {
+    let recording = audit::begin(
+        "move_paths",
+        || json!({ "moves": moves, "overwritePolicy": overwrite_policy }),
+    );
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        let state = app_handle.try_state::<CodeNavState>();
+        move_paths_blocking(
+            state.as_ref().map(|state| &state.0),
+            undo::journal(),
+            &moves,
+            overwrite_policy.unwrap_or_default(),
+        )
+    })
+    .await
+    .map_err(|e| format!("Move task failed: {}", e));
+    audit::finish(recording, &result);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    fn cross_device(_from: &Path, _to: &Path) -> io::Result<()> {
+        Err(io::Error::from(io::ErrorKind::CrossesDevices))
+    }
+
+    fn request(from: &Path, to: &Path) -> MoveRequest {
+        MoveRequest {
+            from: from.to_string_lossy().to_string(),
+            to: to.to_string_lossy().to_string(),
+        }
+    }
+
+    fn sample_tree(root: &Path) -> PathBuf {
+        let dir = root.join("src");
+        fs::create_dir_all(dir.join("nested")).unwrap();
+        fs::write(dir.join("main.rs"), "fn main() {}\n").unwrap();
+        fs::write(dir.join("nested/util.rs"), "pub fn util() {}\n").unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_cross_device_move_copies_and_deletes() {
+        let temp = TempDir::new().unwrap();
+        let source = sample_tree(temp.path());
+        let old = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+        set_mtime(&source.join("main.rs"), old).unwrap();
+        set_mtime(&source.join("nested"), old).unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(source.join("main.rs"), fs::Permissions::from_mode(0o750)).unwrap();
+        }
+        let dest = temp.path().join("moved");
+
+        let results = move_paths_with(
+            &[request(&source, &dest)],
+            OverwritePolicy::Fail,
+            cross_device,
+        );
+        assert_eq!(results[0].error, None);
+        assert!(results[0].copied);
+        assert!(!source.exists());
+        assert_eq!(
+            fs::read_to_string(dest.join("nested/util.rs")).unwrap(),
+            "pub fn util() {}\n"
+        );
+        let main = fs::metadata(dest.join("main.rs")).unwrap();
+        assert_eq!(main.modified().unwrap(), old);
+        assert_eq!(
+            fs::metadata(dest.join("nested"))
+                .unwrap()
+                .modified()
+                .unwrap(),
+            old
+        );
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(main.permissions().mode() & 0o777, 0o750);
+        }
+    }
+
+    #[test]
+    fn test_other_rename_errors_are_not_copied() {
+        fn denied(_from: &Path, _to: &Path) -> io::Result<()> {
+            Err(io::Error::from(io::ErrorKind::PermissionDenied))
+        }
+        let temp = TempDir::new().unwrap();
+        let source = sample_tree(temp.path());
+        let dest = temp.path().join("moved");
+
+        let results = move_paths_with(&[request(&source, &dest)], OverwritePolicy::Fail, denied);
+        assert!(results[0].error.is_some());
+        assert!(source.join("main.rs").exists());
+        assert!(!dest.exists());
+    }
+
+    #[test]
+    fn test_overwrite_policies() {
+        let temp = TempDir::new().unwrap();
+        let dir = temp.path();
+        fs::write(dir.join("a.txt"), "new").unwrap();
+        fs::write(dir.join("b.txt"), "existing").unwrap();
+        fs::write(dir.join("b 2.txt"), "also existing").unwrap();
+
+        let failed = move_paths_with(
+            &[request(&dir.join("a.txt"), &dir.join("b.txt"))],
+            OverwritePolicy::Fail,
+            |from, to| fs::rename(from, to),
+        );
+        assert!(failed[0]
+            .error
+            .as_deref()
+            .unwrap()
+            .contains("already exists"));
+        assert_eq!(fs::read_to_string(dir.join("b.txt")).unwrap(), "existing");
+
+        let renamed = move_paths_with(
+            &[request(&dir.join("a.txt"), &dir.join("b.txt"))],
+            OverwritePolicy::Rename,
+            |from, to| fs::rename(from, to),
+        );
+        let used = dir.join("b 3.txt");
+        assert_eq!(renamed[0].to, Some(used.to_string_lossy().to_string()));
+        assert_eq!(fs::read_to_string(&used).unwrap(), "new");
+        assert_eq!(fs::read_to_string(dir.join("b.txt")).unwrap(), "existing");
+
+        let replaced = move_paths_with(
+            &[request(&used, &dir.join("b.txt"))],
+            OverwritePolicy::Overwrite,
+            |from, to| fs::rename(from, to),
+        );
+        assert_eq!(replaced[0].error, None);
+        assert_eq!(fs::read_to_string(dir.join("b.txt")).unwrap(), "new");
+    }
+
+    #[test]
+    fn test_moves_are_undoable() {
+        let temp = TempDir::new().unwrap();
+        let source = sample_tree(temp.path());
+        let dir = temp.path();
+        fs::write(dir.join("a.txt"), "new").unwrap();
+        fs::write(dir.join("b.txt"), "existing").unwrap();
+        let journal = UndoJournal::new(dir.join(undo::UNDO_DIR_NAME));
+        let dest = dir.join("lib");
+
+        let results = move_paths_blocking(
+            None,
+            Some(&journal),
+            &[
+                request(&source, &dest),
+                request(&dir.join("a.txt"), &dir.join("b.txt")),
+            ],
+            OverwritePolicy::Overwrite,
+        );
+        assert!(results.iter().all(|r| r.error.is_none()));
+        let operation = &journal.recent(1)[0];
+        assert_eq!(operation.label, "Move");
+        assert_eq!(operation.moves.len(), 2);
+        assert_eq!(operation.files.len(), 1);
+
+        let reverted = journal.revert(&operation.id).unwrap();
+        assert!(reverted.conflicted.is_empty(), "{:?}", reverted);
+        assert_eq!(reverted.restored.len(), 3);
+        assert!(source.join("nested/util.rs").exists());
+        assert!(!dest.exists());
+        assert_eq!(fs::read_to_string(dir.join("a.txt")).unwrap(), "new");
+        assert_eq!(fs::read_to_string(dir.join("b.txt")).unwrap(), "existing");
+
+        // Nothing moved, nothing recorded
+        let failed = move_paths_blocking(
+            None,
+            Some(&journal),
+            &[request(&dir.join("missing"), &dest)],
+            OverwritePolicy::Fail,
+        );
+        assert!(failed[0].error.is_some());
+        assert!(journal.recent(1).is_empty());
+    }
+
+    #[test]
+    fn test_renamed_variant_names() {
+        let temp = TempDir::new().unwrap();
+        let dir = temp.path();
+        assert_eq!(
+            renamed_variant(&dir.join("report.final.pdf"), false),
+            dir.join("report.final 2.pdf")
+        );
+        assert_eq!(
+            renamed_variant(&dir.join(".env"), false),
+            dir.join(".env 2")
+        );
+        fs::create_dir(dir.join("v1.0 2")).unwrap();
+        assert_eq!(renamed_variant(&dir.join("v1.0"), true), dir.join("v1.0 3"));
+    }
+
+    #[test]
+    fn test_rejects_moving_a_folder_into_itself() {
+        let temp = TempDir::new().unwrap();
+        let source = sample_tree(temp.path());
+
+        for dest in [source.join("nested/src"), source.join("inner")] {
+            let results = move_paths_with(
+                &[request(&source, &dest)],
+                OverwritePolicy::Rename,
+                |from, to| fs::rename(from, to),
+            );
+            assert!(
+                results[0].error.as_deref().unwrap().contains("into itself"),
+                "{:?}",
+                results[0]
+            );
+        }
+        // Replacing an ancestor of the source would delete the source
+        let results = move_paths_with(
+            &[request(&source.join("nested/util.rs"), &source)],
+            OverwritePolicy::Overwrite,
+            |from, to| fs::rename(from, to),
+        );
+        assert!(results[0].error.as_deref().unwrap().contains("contains"));
+        assert!(source.join("nested/util.rs").exists());
+    }
+
+    #[test]
+    fn test_index_follows_moved_files() {
+        let temp = TempDir::new().unwrap();
+        let source = sample_tree(temp.path());
+        let path = |p: &Path| p.to_string_lossy().to_string();
+        let nav = RwLock::new(CodeNavigationService::new());
+        for file in ["main.rs", "nested/util.rs"] {
+            let file = source.join(file);
+            let content = fs::read_to_string(&file).unwrap();
+            nav.write()
+                .unwrap()
+                .index_file(&path(&file), &content, "rust")
+                .unwrap();
+        }
+        let dest = temp.path().join("lib");
+
+        let results = move_paths_blocking(
+            Some(&nav),
+            None,
+            &[request(&source, &dest)],
+            OverwritePolicy::Fail,
+        );
+        assert_eq!(results[0].error, None);
+        let service = nav.read().unwrap();
+        assert!(service.indexed_files_under(&path(&source)).is_empty());
+        let mut indexed = service.indexed_files_under(&path(&dest));
+        indexed.sort();
+        assert_eq!(
+            indexed,
+            vec![
+                path(&dest.join("main.rs")),
+                path(&dest.join("nested/util.rs"))
+            ]
+        );
+        let found = service.find_definition("util", "rust");
+        assert_eq!(found[0].file_path, path(&dest.join("nested/util.rs")));
+    }
+}
@@ -0,0 +1,364 @@
+// File overview module
+// Minimap and scrollbar heatmap data for large files. The file's lines are divided into a
+// fixed number of buckets and each requested layer is reduced to one count per bucket:
+// lines matching a search, lines changed against git HEAD, and symbol definitions. The
+// frontend draws the arrays straight onto a canvas instead of walking thousands of
+// decorations in JS. The content is read once and shared by every layer.
+
+use crate::code_navigation::{CodeNavState, CodeNavigationService};
+use crate::git::{diff, repository};
+use crate::path_wire;
+use crate::search::RipgrepSearch;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::RwLock;
+use tauri::State;
+
+/// Upper bound on buckets; more than a tall screen has pixels
+pub const MAX_BUCKETS: usize = 4096;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OverviewLayer {
+    SearchMatches,
+    GitChanges,
+    SymbolDefinitions,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OverviewSearch {
+    pub query: String,
+    /// Treat the query as a regular expression rather than literal text
+    #[serde(default)]
+    pub is_regex: bool,
+    #[serde(default)]
+    pub whole_word: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct FileOverview {
+    pub total_lines: u32,
+    pub buckets: usize,
+    /// Lines matching the search, per bucket
+    pub search_matches: Option<Vec<u32>>,
+    /// Lines added, modified or next to a deletion against HEAD, per bucket. None outside
+    /// a repository.
+    pub git_changes: Option<Vec<u32>>,
+    /// Symbol definitions starting in each bucket. None for languages without a parser.
+    pub symbol_definitions: Option<Vec<u32>>,
+}
+
+/// Maps 1-based line numbers to buckets. Bucket `i` covers lines
+/// `i * total / buckets + 1 ..= (i + 1) * total / buckets`.
+struct Buckets {
+    total_lines: u32,
+    count: usize,
+}
+
+impl Buckets {
+    fn new(total_lines: u32, count: usize) -> Self {
+        Self {
+            total_lines: total_lines.max(1),
+            count: count.clamp(1, MAX_BUCKETS),
+        }
+    }
+
+    fn index(&self, line: u32) -> usize {
+        let line = line.clamp(1, self.total_lines) as u64 - 1;
+        (line * self.count as u64 / self.total_lines as u64) as usize
+    }
+
+    fn histogram(&self, lines: impl IntoIterator<Item = u32>) -> Vec<u32> {
+        let mut counts = vec![0; self.count];
+        for line in lines {
+            counts[self.index(line)] += 1;
+        }
+        counts
+    }
+}
+
+fn search_pattern(search: &OverviewSearch) -> String {
+    let pattern = if search.is_regex {
+        search.query.clone()
+    } else {
+        regex::escape(&search.query)
+    };
+    if search.whole_word {
+        format!(r"\b(?:{})\b", pattern)
+    } else {
+        pattern
+    }
+}
+
+fn search_lines(
+    file_path: &str,
+    content: &str,
+    search: &OverviewSearch,
+) -> Result<Vec<u32>, String> {
+    if search.query.is_empty() {
+        return Ok(Vec::new());
+    }
+    let matcher = RipgrepSearch::build_matcher(&search_pattern(search))?;
+    let result = RipgrepSearch::search_in_bytes(
+        &matcher,
+        Path::new(file_path),
+        content.as_bytes(),
+        usize::MAX,
+        &search.query,
+    );
+    Ok(result
+        .map(|r| r.matches.iter().map(|m| m.line_number as u32).collect())
+        .unwrap_or_default())
+}
+
+/// Changed lines, from the buffer when given, otherwise from the working tree. None when
+/// the file is not in a repository.
+fn git_changed_lines(file_path: &str, buffer: Option<&str>) -> Option<Vec<u32>> {
+    let path = Path::new(file_path);
+    let repo = repository::discover_repository(path.parent()?).ok()?;
+    let root = Path::new(&repository::get_repository_root(&repo)?)
+        .canonicalize()
+        .ok()?;
+    let absolute = path.parent()?.canonicalize().ok()?.join(path.file_name()?);
+    let relative = absolute
+        .strip_prefix(&root)
+        .ok()?
+        .to_string_lossy()
+        .replace('\\', "/");
+
+    let changes = match buffer {
+        Some(content) => diff::get_line_changes_for_content(&repo, &relative, content),
+        None => diff::get_line_changes(&repo, &relative),
+    };
+    match changes {
+        Ok(changes) => {
+            // A modified line is reported as both a deletion and an addition
+            let mut lines: Vec<u32> = changes.into_iter().map(|(line, _)| line).collect();
+            lines.dedup();
+            Some(lines)
+        }
+        Err(e) => {
+            log::debug!("No line changes for {}: {}", file_path, e);
+            None
+        }
+    }
+}
+
+fn definition_lines(
+    nav: &RwLock<CodeNavigationService>,
+    file_path: &str,
+    content: &str,
+) -> Result<Option<Vec<u32>>, String> {
+    let Some(lang_id) = CodeNavigationService::get_lang_id_from_path(file_path) else {
+        return Ok(None);
+    };
+    let definitions = nav
+        .write()
+        .map_err(|e| format!("Failed to acquire write lock: {}", e))?
+        .definitions_in(file_path, content, &lang_id)
+        .map_err(|e| e.to_string())?;
+    Ok(Some(definitions.iter().map(|d| d.start_line).collect()))
+}
+
+/// Compute the requested layers. `buffer` is unsaved editor content; without it the file
+/// is read from disk.
+pub fn compute_overview(
+    nav: &RwLock<CodeNavigationService>,
+    file_path: &str,
+    buffer: Option<&str>,
+    buckets: usize,
+    layers: &[OverviewLayer],
+    search: Option<&OverviewSearch>,
+) -> Result<FileOverview, String> {
+    let read;
+    let content = match buffer {
+        Some(content) => content,
+        None => {
+            read = std::fs::read_to_string(path_wire::resolve(file_path))
+                .map_err(|e| format!("Failed to read {}: {}", file_path, e))?;
+            read.as_str()
+        }
+    };
+    let total_lines = content.lines().count() as u32;
+    let buckets = Buckets::new(total_lines, buckets);
+    let mut overview = FileOverview {
+        total_lines,
+        buckets: buckets.count,
+        ..Default::default()
+    };
+
+    for layer in layers {
+        match layer {
+            OverviewLayer::SearchMatches => {
+                let lines = match search {
+                    Some(search) => search_lines(file_path, content, search)?,
+                    None => Vec::new(),
+                };
+                overview.search_matches = Some(buckets.histogram(lines));
+            }
+            OverviewLayer::GitChanges => {
+                overview.git_changes =
+                    git_changed_lines(file_path, buffer).map(|lines| buckets.histogram(lines));
+            }
+            OverviewLayer::SymbolDefinitions => {
+                overview.symbol_definitions = definition_lines(nav, file_path, content)?
+                    .map(|lines| buckets.histogram(lines));
+            }
+        }
+    }
+    Ok(overview)
+}
+
+/// Per-bucket counts for a file's minimap. `content` is the editor buffer when it has
+/// unsaved changes; `search` is needed for the search layer.
+#[tauri::command]
+pub async fn compute_file_overview(
+    state: State<'_, CodeNavState>,
+    file_path: String,
+    content: Option<String>,
+    buckets: usize,
+    layers: Vec<OverviewLayer>,
+    search: Option<OverviewSearch>,
+) -> Result<FileOverview, String> {
+    compute_overview(
+        &state.0,
+        &file_path,
+        content.as_deref(),
+        buckets,
+        &layers,
+        search.as_ref(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+    use std::fs;
+    use tempfile::TempDir;
+
+    const ALL_LAYERS: [OverviewLayer; 3] = [
+        OverviewLayer::SearchMatches,
+        OverviewLayer::GitChanges,
+        OverviewLayer::SymbolDefinitions,
+    ];
+
+    /// 100 lines: a function definition on lines 1, 41 and 81, and "needle" on lines 5,
+    /// 10, 55 and 100
+    fn sample_source() -> String {
+        (1..=100)
+            .map(|line| match line {
+                1 | 41 | 81 => format!("fn f{}() {{}}\n", line),
+                5 | 10 | 55 | 100 => format!("// needle {}\n", line),
+                _ => format!("// line {}\n", line),
+            })
+            .collect()
+    }
+
+    fn search(query: &str) -> OverviewSearch {
+        OverviewSearch {
+            query: query.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_bucket_boundaries() {
+        let buckets = Buckets::new(100, 4);
+        assert_eq!(buckets.index(1), 0);
+        assert_eq!(buckets.index(25), 0);
+        assert_eq!(buckets.index(26), 1);
+        assert_eq!(buckets.index(75), 2);
+        assert_eq!(buckets.index(76), 3);
+        assert_eq!(buckets.index(100), 3);
+        // Out-of-range lines land in the first or last bucket
+        assert_eq!(buckets.index(0), 0);
+        assert_eq!(buckets.index(250), 3);
+
+        // More buckets than lines leaves some empty
+        let sparse = Buckets::new(3, 6);
+        assert_eq!(sparse.histogram([1, 2, 3]), vec![1, 0, 1, 0, 1, 0]);
+    }
+
+    #[test]
+    fn test_layers_of_a_constructed_file() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("lib.rs");
+        fs::write(&file, sample_source()).unwrap();
+        let nav = RwLock::new(CodeNavigationService::new());
+
+        let overview = compute_overview(
+            &nav,
+            &file.to_string_lossy(),
+            None,
+            4,
+            &ALL_LAYERS,
+            Some(&search("NEEDLE")),
+        )
+        .unwrap();
+        assert_eq!(overview.total_lines, 100);
+        assert_eq!(overview.buckets, 4);
+        assert_eq!(overview.search_matches, Some(vec![2, 0, 1, 1]));
+        assert_eq!(overview.symbol_definitions, Some(vec![1, 1, 0, 1]));
+        // Not in a repository
+        assert_eq!(overview.git_changes, None);
+        // Nothing was added to the index
+        assert!(nav
+            .read()
+            .unwrap()
+            .find_definition("f41", "rust")
+            .is_empty());
+    }
+
+    #[test]
+    fn test_search_options() {
+        let content = "needle\nneedles\nneed.e\n";
+        let count = |search: OverviewSearch| search_lines("a.txt", content, &search).unwrap().len();
+        assert_eq!(count(search("need.e")), 1);
+        assert_eq!(
+            count(OverviewSearch {
+                is_regex: true,
+                ..search("need.e")
+            }),
+            3
+        );
+        assert_eq!(
+            count(OverviewSearch {
+                whole_word: true,
+                ..search("needle")
+            }),
+            1
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_git_changes_from_buffer() {
+        let dir = TempDir::new().unwrap();
+        let git = |args: &[&str]| test_support::git(dir.path(), args);
+        git(&["init", "-b", "main"]);
+        git(&["config", "user.email", "test@test.com"]);
+        git(&["config", "user.name", "Test User"]);
+        let file = dir.path().join("notes.txt");
+        fs::write(&file, sample_source()).unwrap();
+        git(&["add", "-A"]);
+        git(&["commit", "-m", "Initial"]);
+
+        let edited = sample_source().replace("// line 30\n", "// edited 30\n");
+        let nav = RwLock::new(CodeNavigationService::new());
+        let overview = compute_overview(
+            &nav,
+            &file.to_string_lossy(),
+            Some(&edited),
+            4,
+            &[OverviewLayer::GitChanges],
+            None,
+        )
+        .unwrap();
+        assert_eq!(overview.git_changes, Some(vec![0, 1, 0, 0]));
+        assert_eq!(overview.search_matches, None);
+        assert_eq!(overview.symbol_definitions, None);
+    }
+}
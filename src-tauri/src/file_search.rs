@@ -1,9 +1,18 @@
 use crate::constants::{is_code_extension, is_code_filename, should_exclude_dir};
+use crate::database::Database;
+use crate::path_wire::{self, RawPath};
+use crate::settings;
 use ignore::WalkBuilder;
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::ffi::OsStr;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use tauri::State;
+
+/// Settings key holding the file search ranking configuration as JSON
+pub const FILE_SEARCH_RANKING_SETTING: &str = "file_search_ranking";
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FileSearchResult {
@@ -11,15 +20,190 @@ pub struct FileSearchResult {
     pub path: String,
     pub is_directory: bool,
     pub score: f64,
+    /// Lockfile or generated file, sorted below every other match
+    #[serde(default)]
+    pub deprioritized: bool,
+    #[serde(flatten)]
+    pub raw_path: RawPath,
+}
+
+/// How matches are reordered after name scoring. Deprioritized files are never dropped,
+/// only sorted after the rest.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RankingConfig {
+    /// Exact file names, compared case-insensitively
+    pub deprioritized_names: Vec<String>,
+    /// File name endings of minified or generated files
+    pub deprioritized_suffixes: Vec<String>,
+    /// Directory names that hold source code
+    pub source_dirs: Vec<String>,
+    /// Added to matches inside one of `source_dirs`
+    pub source_dir_boost: f64,
+    /// Added to matches git tracks, when the tracked set is known
+    pub tracked_boost: f64,
+}
+
+impl Default for RankingConfig {
+    fn default() -> Self {
+        let strings = |items: &[&str]| items.iter().map(|item| item.to_string()).collect();
+        Self {
+            deprioritized_names: strings(&[
+                "package-lock.json",
+                "npm-shrinkwrap.json",
+                "yarn.lock",
+                "pnpm-lock.yaml",
+                "bun.lock",
+                "Cargo.lock",
+                "Gemfile.lock",
+                "Pipfile.lock",
+                "poetry.lock",
+                "uv.lock",
+                "composer.lock",
+                "flake.lock",
+                "go.sum",
+            ]),
+            deprioritized_suffixes: strings(&[
+                ".min.js",
+                ".min.mjs",
+                ".min.css",
+                ".bundle.js",
+                ".chunk.js",
+                ".map",
+                ".generated.ts",
+                ".g.dart",
+                ".pb.go",
+                "_pb2.py",
+                ".snap",
+            ]),
+            source_dirs: strings(&["src", "lib", "app", "crates", "packages", "cmd", "pkg"]),
+            source_dir_boost: 30.0,
+            tracked_boost: 60.0,
+        }
+    }
+}
+
+impl RankingConfig {
+    pub fn is_deprioritized(&self, filename: &str) -> bool {
+        let lower = filename.to_lowercase();
+        self.deprioritized_names
+            .iter()
+            .any(|name| name.eq_ignore_ascii_case(filename))
+            || self
+                .deprioritized_suffixes
+                .iter()
+                .any(|suffix| lower.ends_with(&suffix.to_lowercase()))
+    }
+
+    fn in_source_dir(&self, relative: &Path) -> bool {
+        relative.parent().is_some_and(|dir| {
+            dir.components().any(|component| {
+                self.source_dirs
+                    .iter()
+                    .any(|source| component.as_os_str() == source.as_str())
+            })
+        })
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref RANKING: RwLock<Arc<RankingConfig>> = RwLock::new(Arc::default());
+}
+
+pub fn ranking_config() -> Arc<RankingConfig> {
+    RANKING
+        .read()
+        .map(|config| config.clone())
+        .unwrap_or_default()
+}
+
+fn set_ranking_config(config: RankingConfig) -> Result<(), String> {
+    *RANKING.write().map_err(|e| e.to_string())? = Arc::new(config);
+    Ok(())
+}
+
+/// Apply the ranking configuration persisted in the settings table
+pub async fn load_from_settings(db: &Database) -> Result<(), String> {
+    if let Some(config) =
+        settings::get_json_setting::<RankingConfig>(db, FILE_SEARCH_RANKING_SETTING).await?
+    {
+        set_ranking_config(config)?;
+    }
+    Ok(())
+}
+
+/// Files git tracks under a search root, relative to that root
+#[derive(Debug, Clone, Default)]
+pub struct TrackedFiles {
+    paths: HashSet<PathBuf>,
+}
+
+impl TrackedFiles {
+    pub fn from_paths<I: IntoIterator<Item = PathBuf>>(paths: I) -> Self {
+        Self {
+            paths: paths.into_iter().collect(),
+        }
+    }
+
+    /// Entries of the git index under `root`; None outside a repository
+    pub fn for_root(root: &Path) -> Option<Self> {
+        let repo = git2::Repository::discover(root).ok()?;
+        let workdir = repo.workdir()?.canonicalize().ok()?;
+        let prefix = root
+            .canonicalize()
+            .ok()?
+            .strip_prefix(&workdir)
+            .ok()?
+            .to_path_buf();
+        let index = repo.index().ok()?;
+        let paths = index.iter().filter_map(|entry| {
+            let path = PathBuf::from(String::from_utf8_lossy(&entry.path).to_string());
+            path.strip_prefix(&prefix).ok().map(Path::to_path_buf)
+        });
+        Some(Self::from_paths(paths))
+    }
+
+    pub fn contains(&self, relative: &Path) -> bool {
+        self.paths.contains(relative)
+    }
+}
+
+/// Adjust scores for source directories and tracked files, and flag deprioritized files
+pub fn rank_results(
+    results: &mut [FileSearchResult],
+    root: &Path,
+    config: &RankingConfig,
+    tracked: Option<&TrackedFiles>,
+) {
+    for result in results.iter_mut() {
+        if config.is_deprioritized(&result.name) {
+            result.deprioritized = true;
+            continue;
+        }
+        let path = Path::new(&result.path);
+        let relative = path.strip_prefix(root).unwrap_or(path);
+        if config.in_source_dir(relative) {
+            result.score += config.source_dir_boost;
+        }
+        if tracked.is_some_and(|tracked| tracked.contains(relative)) {
+            result.score += config.tracked_boost;
+        }
+    }
 }
 
 pub struct HighPerformanceFileSearch {
     max_results: usize,
+    ranking: Option<Arc<RankingConfig>>,
+    tracked: Option<TrackedFiles>,
 }
 
 impl Default for HighPerformanceFileSearch {
     fn default() -> Self {
-        Self { max_results: 200 }
+        Self {
+            max_results: 200,
+            ranking: None,
+            tracked: None,
+        }
     }
 }
 
@@ -33,6 +217,17 @@ impl HighPerformanceFileSearch {
         self
     }
 
+    /// Rank matches with `config`, boosting files in `tracked` when given
+    pub fn with_ranking(
+        mut self,
+        config: Arc<RankingConfig>,
+        tracked: Option<TrackedFiles>,
+    ) -> Self {
+        self.ranking = Some(config);
+        self.tracked = tracked;
+        self
+    }
+
     /// High-performance file search with fuzzy matching and scoring
     pub fn search_files(
         &self,
@@ -58,7 +253,7 @@ impl HighPerformanceFileSearch {
             .git_exclude(true)
             .ignore(true)
             .parents(true)
-            .max_depth(Some(20))
+            .max_depth(Some(crate::walk_depth::default_max_depth()))
             .filter_entry(|entry| {
                 if entry.path().is_dir() {
                     if let Some(name) = entry.path().file_name().and_then(OsStr::to_str) {
@@ -106,9 +301,27 @@ impl HighPerformanceFileSearch {
         }
 
         let mut final_results = results;
+        if let Some(ref config) = self.ranking {
+            rank_results(
+                &mut final_results,
+                Path::new(root_path),
+                config,
+                self.tracked.as_ref(),
+            );
+        }
+        Self::sort_results(&mut final_results);
+        final_results.truncate(self.max_results);
+        Ok(final_results)
+    }
 
-        // Sort by score (descending) and then by name length (ascending)
-        final_results.par_sort_unstable_by(|a, b| {
+    /// Sort deprioritized files last, then by score (descending) and then by name length
+    /// (ascending)
+    pub(crate) fn sort_results(results: &mut [FileSearchResult]) {
+        results.par_sort_unstable_by(|a, b| {
+            let deprioritized_cmp = a.deprioritized.cmp(&b.deprioritized);
+            if deprioritized_cmp != std::cmp::Ordering::Equal {
+                return deprioritized_cmp;
+            }
             let score_cmp = b
                 .score
                 .partial_cmp(&a.score)
@@ -119,13 +332,10 @@ impl HighPerformanceFileSearch {
                 a.name.len().cmp(&b.name.len())
             }
         });
-
-        final_results.truncate(self.max_results);
-        Ok(final_results)
     }
 
     /// Parse search query into keywords, splitting on spaces and non-alphanumeric chars
-    fn parse_query(query: &str) -> Vec<String> {
+    pub(crate) fn parse_query(query: &str) -> Vec<String> {
         query
             .to_lowercase()
             .split_whitespace()
@@ -135,7 +345,7 @@ impl HighPerformanceFileSearch {
     }
 
     /// Check if a file is a code file based on extension
-    fn is_code_file(&self, path: &Path) -> bool {
+    pub(crate) fn is_code_file(&self, path: &Path) -> bool {
         if let Some(ext) = path.extension().and_then(OsStr::to_str) {
             return is_code_extension(ext);
         }
@@ -149,7 +359,7 @@ impl HighPerformanceFileSearch {
     }
 
     /// Advanced filename matching with scoring
-    fn match_filename(
+    pub(crate) fn match_filename(
         &self,
         filename: &str,
         full_path: &Path,
@@ -170,9 +380,11 @@ impl HighPerformanceFileSearch {
 
         Some(FileSearchResult {
             name: filename.to_string(),
-            path: full_path.to_string_lossy().to_string(),
+            path: path_wire::encode(full_path),
             is_directory: false,
             score,
+            deprioritized: false,
+            raw_path: RawPath::of(full_path),
         })
     }
 
@@ -330,6 +542,23 @@ impl HighPerformanceFileSearch {
     }
 }
 
+#[tauri::command]
+pub fn file_search_get_ranking() -> RankingConfig {
+    ranking_config().as_ref().clone()
+}
+
+/// Replace the file search ranking configuration and persist it
+#[tauri::command]
+pub async fn file_search_set_ranking(
+    db: State<'_, Arc<Database>>,
+    config: RankingConfig,
+) -> Result<(), String> {
+    settings::set_json_setting(&db, FILE_SEARCH_RANKING_SETTING, &config).await?;
+    set_ranking_config(config)?;
+    log::info!("File search ranking configuration updated");
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -408,4 +637,84 @@ jobs:
         assert!(names.contains(&"release.yml"));
         assert!(names.contains(&"test.yml"));
     }
+
+    #[test]
+    fn test_lockfiles_rank_below_source_matches() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(root.join("src/lock.rs"), "pub struct Lock;").unwrap();
+        fs::write(root.join("package-lock.json"), "{}").unwrap();
+        fs::write(root.join("yarn.lock"), "").unwrap();
+
+        let search =
+            HighPerformanceFileSearch::new().with_ranking(Arc::new(RankingConfig::default()), None);
+        let results = search.search_files(root.to_str().unwrap(), "lock").unwrap();
+
+        // Lockfiles are kept but flagged and sorted last
+        let names: Vec<&str> = results.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names.len(), 3);
+        assert_eq!(names[0], "lock.rs");
+        assert!(!results[0].deprioritized);
+        assert!(names.contains(&"package-lock.json"));
+        assert!(results[1..].iter().all(|r| r.deprioritized));
+    }
+
+    #[test]
+    fn test_rank_results_with_injected_config_and_tracked_set() {
+        let root = Path::new("/project");
+        let result = |path: &str| FileSearchResult {
+            name: Path::new(path)
+                .file_name()
+                .unwrap()
+                .to_string_lossy()
+                .to_string(),
+            path: root.join(path).to_string_lossy().to_string(),
+            is_directory: false,
+            score: 100.0,
+            deprioritized: false,
+            raw_path: RawPath::default(),
+        };
+        let mut results = vec![
+            result("docs/util.ts"),
+            result("scratch/util.ts"),
+            result("source/util.ts"),
+            result("source/util.snapshot.ts"),
+        ];
+        let config = RankingConfig {
+            deprioritized_names: Vec::new(),
+            deprioritized_suffixes: vec![".SNAPSHOT.ts".to_string()],
+            source_dirs: vec!["source".to_string()],
+            source_dir_boost: 5.0,
+            tracked_boost: 10.0,
+        };
+        let tracked = TrackedFiles::from_paths([
+            PathBuf::from("docs/util.ts"),
+            PathBuf::from("source/util.ts"),
+        ]);
+
+        rank_results(&mut results, root, &config, Some(&tracked));
+        HighPerformanceFileSearch::sort_results(&mut results);
+
+        let ranked: Vec<(String, f64, bool)> = results
+            .iter()
+            .map(|r| {
+                let relative = Path::new(&r.path).strip_prefix(root).unwrap();
+                (
+                    relative.to_string_lossy().to_string(),
+                    r.score,
+                    r.deprioritized,
+                )
+            })
+            .collect();
+        assert_eq!(
+            ranked,
+            vec![
+                ("source/util.ts".to_string(), 115.0, false),
+                ("docs/util.ts".to_string(), 110.0, false),
+                ("scratch/util.ts".to_string(), 100.0, false),
+                ("source/util.snapshot.ts".to_string(), 100.0, true),
+            ]
+        );
+    }
 }
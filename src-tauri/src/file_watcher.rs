@@ -1,13 +1,15 @@
-use crate::constants::EXCLUDED_DIRS;
+use crate::constants::should_exclude_dir;
+use crate::events;
+use crate::git::refresh::{self, AdaptiveDebounce, OperationTransition};
 use notify::{Config, RecommendedWatcher, RecursiveMode, Watcher};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::{
     atomic::{AtomicBool, Ordering},
     mpsc, Arc,
 };
 use std::thread::{self, JoinHandle};
 use std::time::{Duration, Instant};
-use tauri::{AppHandle, Emitter};
+use tauri::AppHandle;
 
 pub struct FileWatcher {
     _watcher: RecommendedWatcher,
@@ -17,6 +19,8 @@ pub struct FileWatcher {
     _git_watcher: Option<RecommendedWatcher>,
     _git_thread_handle: Option<JoinHandle<()>>,
     _git_stop_flag: Arc<AtomicBool>,
+    // Root registered with watch_conditions while watching
+    _watched_root: Option<PathBuf>,
 }
 
 impl FileWatcher {
@@ -39,6 +43,7 @@ impl FileWatcher {
             _git_watcher: None,
             _git_thread_handle: None,
             _git_stop_flag: Arc::new(AtomicBool::new(false)),
+            _watched_root: None,
         })
     }
 
@@ -50,6 +55,18 @@ impl FileWatcher {
         path: P,
         app_handle: AppHandle,
         window_label: Option<String>,
+    ) -> notify::Result<()> {
+        self.watch_directory_with_mode(path, app_handle, window_label, RecursiveMode::Recursive)
+    }
+
+    /// Watch a directory with an explicit recursion mode
+    /// Very large roots use RecursiveMode::NonRecursive to avoid exhausting OS watch limits
+    pub fn watch_directory_with_mode<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        app_handle: AppHandle,
+        window_label: Option<String>,
+        mode: RecursiveMode,
     ) -> notify::Result<()> {
         // Stop any existing watcher first
         self.stop();
@@ -69,10 +86,12 @@ impl FileWatcher {
         )?;
 
         // Start watching
-        watcher.watch(path.as_ref(), RecursiveMode::Recursive)?;
+        watcher.watch(path.as_ref(), mode)?;
 
         // Replace the old watcher
         self._watcher = watcher;
+        crate::watch_conditions::register_watched_root(&repo_path);
+        self._watched_root = Some(repo_path.clone());
 
         // Create new stop flag
         self._stop_flag = Arc::new(AtomicBool::new(false));
@@ -90,7 +109,7 @@ impl FileWatcher {
             // Trailing-edge debounce state
             let mut pending_emit = false;
             let mut last_event_time = Instant::now();
-            let mut pending_paths: Vec<std::path::PathBuf> = Vec::new();
+            let mut pending_paths: Vec<PathBuf> = Vec::new();
 
             loop {
                 // Check stop flag first
@@ -150,12 +169,15 @@ impl FileWatcher {
                             file_window_label
                         );
 
+                        crate::reference_counts::invalidate_paths(&pending_paths);
+                        crate::watch_conditions::notify_paths(&pending_paths);
+
                         // Emit to specific window if label provided, otherwise broadcast
-                        let result = if let Some(ref label) = file_window_label {
-                            file_app_handle.emit_to(label, "file-system-changed", &pending_paths)
-                        } else {
-                            file_app_handle.emit("file-system-changed", &pending_paths)
-                        };
+                        let result = events::emit_file_system_changed(
+                            &file_app_handle,
+                            file_window_label.as_deref(),
+                            &pending_paths,
+                        );
 
                         if let Err(e) = result {
                             log::error!("Failed to emit file system change event: {}", e);
@@ -222,15 +244,15 @@ impl FileWatcher {
         // Create new stop flag for git watcher
         self._git_stop_flag = Arc::new(AtomicBool::new(false));
         let stop_flag = Arc::clone(&self._git_stop_flag);
+        let repo_root = repo_path.as_ref().to_string_lossy().replace('\\', "/");
 
-        // Spawn thread to handle git events with proper trailing-edge debounce
+        // Spawn thread to handle git events with an adaptive trailing-edge debounce
         let git_thread_handle = thread::spawn(move || {
-            let debounce_duration = Duration::from_millis(500);
             let check_interval = Duration::from_millis(100);
 
-            // Trailing-edge debounce state
-            let mut pending_emit = false;
-            let mut last_event_time = Instant::now();
+            let mut debounce = AdaptiveDebounce::new();
+            let mut head_changed = false;
+            let mut operation = refresh::detect_in_progress(&git_path);
 
             loop {
                 // Check stop flag first
@@ -242,17 +264,16 @@ impl FileWatcher {
                 // Use short timeout to allow checking for pending events
                 match receiver.recv_timeout(check_interval) {
                     Ok(Ok(event)) => {
-                        // Check if this is a git status-related file change
-                        let is_git_status_change = event
-                            .paths
-                            .iter()
-                            .any(|path| Self::is_git_status_file(path));
+                        // Check if this is a git status-related file change, or a rebase or
+                        // merge starting or ending
+                        let is_git_status_change = event.paths.iter().any(|path| {
+                            Self::is_git_status_file(path) || refresh::is_operation_marker(path)
+                        });
 
                         if is_git_status_change {
                             log::debug!("Git status change detected: {:?}", event.paths);
-                            // Mark pending and update last event time (trailing-edge debounce)
-                            pending_emit = true;
-                            last_event_time = Instant::now();
+                            head_changed |= event.paths.iter().any(|path| Self::is_head_file(path));
+                            debounce.record(Instant::now());
                         }
                     }
                     Ok(Err(e)) => {
@@ -267,27 +288,55 @@ impl FileWatcher {
                     }
                 }
 
-                // Check if we should emit the pending event (trailing-edge debounce)
-                // Emit after debounce_duration has passed since the last event
-                if pending_emit {
-                    let elapsed = Instant::now().duration_since(last_event_time);
-                    if elapsed >= debounce_duration {
-                        log::info!(
-                            "Emitting debounced git-status-changed event to {:?}",
-                            window_label
-                        );
-
-                        // Emit to specific window if label provided, otherwise broadcast
-                        let result = if let Some(ref label) = window_label {
-                            app_handle.emit_to(label, "git-status-changed", ())
-                        } else {
-                            app_handle.emit("git-status-changed", ())
-                        };
+                // Emit once the debounce has passed since the last event; it grows while
+                // events arrive in a storm (e.g. an interactive rebase)
+                let now = Instant::now();
+                if debounce.is_due(now) {
+                    debounce.flushed();
+                    let previous = operation;
+                    operation = refresh::detect_in_progress(&git_path);
+                    log::info!(
+                        "Emitting debounced git-status-changed event to {:?} (storm: {}, operation: {:?})",
+                        window_label,
+                        debounce.in_storm(now),
+                        operation
+                    );
+
+                    crate::reference_counts::bump_generation();
+
+                    // Emit to specific window if label provided, otherwise broadcast
+                    let result = events::emit_git_status_changed(
+                        &app_handle,
+                        window_label.as_deref(),
+                        operation,
+                    );
+
+                    if let Err(e) = result {
+                        log::error!("Failed to emit git-status-changed event: {}", e);
+                    }
 
+                    if let Some(OperationTransition::Completed(finished)) =
+                        refresh::operation_transition(previous, operation)
+                    {
+                        log::info!("Git {:?} finished in {}", finished, repo_root);
+                        let result = events::emit_git_operation_complete(
+                            &app_handle,
+                            window_label.as_deref(),
+                            finished,
+                        );
                         if let Err(e) = result {
-                            log::error!("Failed to emit git-status-changed event: {}", e);
+                            log::error!("Failed to emit git-operation-complete event: {}", e);
                         }
-                        pending_emit = false;
+                    }
+
+                    // A branch switch can delete or add many files at once. HEAD moves
+                    // with every step of a rebase, so reconcile once it has finished.
+                    if head_changed && operation.is_none() {
+                        crate::project_reconcile::schedule_after_head_change(
+                            &app_handle,
+                            &repo_root,
+                        );
+                        head_changed = false;
                     }
                 }
             }
@@ -319,6 +368,10 @@ impl FileWatcher {
         // Stop git watcher first
         self.stop_git_watcher();
 
+        if let Some(root) = self._watched_root.take() {
+            crate::watch_conditions::unregister_watched_root(&root);
+        }
+
         // Set stop flag to signal thread to exit
         self._stop_flag.store(true, Ordering::Relaxed);
 
@@ -331,11 +384,11 @@ impl FileWatcher {
     }
 
     /// Check if a path should be watched (not ignored)
-    fn should_watch_path(path: &Path) -> bool {
-        // Check if any component of the path is in EXCLUDED_DIRS
+    pub(crate) fn should_watch_path(path: &Path) -> bool {
+        // Check if any component of the path is an excluded directory
         for component in path.components() {
             if let Some(name) = component.as_os_str().to_str() {
-                if EXCLUDED_DIRS.contains(&name) {
+                if should_exclude_dir(name) {
                     return false;
                 }
             }
@@ -367,6 +420,13 @@ impl FileWatcher {
     }
 
     /// Check if a path is a git status-related file
+    /// Whether a path is .git/HEAD, which changes when the checked out branch does
+    fn is_head_file(path: &Path) -> bool {
+        path.to_string_lossy()
+            .replace('\\', "/")
+            .ends_with(".git/HEAD")
+    }
+
     fn is_git_status_file(path: &Path) -> bool {
         let path_str = path.to_string_lossy();
 
@@ -441,6 +501,20 @@ mod tests {
         )));
     }
 
+    #[test]
+    fn test_is_head_file() {
+        assert!(FileWatcher::is_head_file(Path::new("/repo/.git/HEAD")));
+        assert!(!FileWatcher::is_head_file(Path::new(
+            "/repo/.git/ORIG_HEAD"
+        )));
+        assert!(!FileWatcher::is_head_file(Path::new(
+            "/repo/.git/logs/HEAD"
+        )));
+        assert!(!FileWatcher::is_head_file(Path::new(
+            "/repo/.git/refs/heads/main"
+        )));
+    }
+
     #[test]
     fn test_is_git_status_file_matches_refs_heads() {
         assert!(FileWatcher::is_git_status_file(Path::new(
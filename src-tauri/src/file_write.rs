@@ -0,0 +1,486 @@
+// File write module
+// Crash-safe saves for the editor. Content goes to a temp file in the target's directory,
+// is fsynced and renamed over the target, so a crash leaves either the old file or the
+// new one, never a truncated mix. An optional prior-content hash guards against
+// overwriting changes made outside the editor. Saves through the commands are recorded in
+// the undo journal.
+
+use crate::audit;
+use crate::path_wire;
+use crate::undo::{self, UndoJournal};
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::io::{self, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileWriteRequest {
+    pub path: String,
+    /// Text content; exactly one of `content` and `base64` must be set
+    pub content: Option<String>,
+    pub base64: Option<String>,
+    /// Set or clear the executable bits (unix only); None keeps the current mode
+    pub make_executable: Option<bool>,
+    /// SHA-256 of the content the editor last read. The write is refused with a
+    /// conflict if the file on disk no longer matches.
+    pub expected_prior_hash: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct FileWriteResult {
+    pub path: String,
+    /// SHA-256 of the written content
+    pub hash: String,
+    pub size: u64,
+    pub modified_ms: i64,
+    /// Undo journal operation that reverts the write, when it was recorded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub undo_operation_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum FileWriteError {
+    /// The file changed on disk since it was read; `actual_hash` is None when it is gone
+    #[serde(rename_all = "camelCase")]
+    Conflict {
+        path: String,
+        expected_hash: String,
+        actual_hash: Option<String>,
+    },
+    #[serde(rename_all = "camelCase")]
+    Failed { path: String, message: String },
+}
+
+impl std::fmt::Display for FileWriteError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FileWriteError::Conflict { path, .. } => {
+                write!(f, "{} was modified on disk since it was read", path)
+            }
+            FileWriteError::Failed { path, message } => {
+                write!(f, "Failed to write {}: {}", path, message)
+            }
+        }
+    }
+}
+
+/// Outcome of one file in a batch write
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileWriteOutcome {
+    pub path: String,
+    pub result: Option<FileWriteResult>,
+    pub error: Option<FileWriteError>,
+}
+
+fn hash_file(path: &Path) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut BufReader::new(File::open(path)?), &mut hasher)?;
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// Permissions with the executable bits set wherever the matching read bit is set, or
+/// with all executable bits cleared
+#[cfg(unix)]
+fn with_executable(permissions: fs::Permissions, executable: bool) -> fs::Permissions {
+    use std::os::unix::fs::PermissionsExt;
+    let mode = permissions.mode();
+    let mode = if executable {
+        mode | ((mode & 0o444) >> 2)
+    } else {
+        mode & !0o111
+    };
+    fs::Permissions::from_mode(mode)
+}
+
+#[cfg(not(unix))]
+fn with_executable(permissions: fs::Permissions, _executable: bool) -> fs::Permissions {
+    permissions
+}
+
+/// Make a rename durable by syncing the directory entry (not possible on Windows)
+fn sync_dir(dir: &Path) {
+    #[cfg(unix)]
+    if let Err(e) = File::open(dir).and_then(|d| d.sync_all()) {
+        log::warn!("Failed to sync directory {}: {}", dir.display(), e);
+    }
+    #[cfg(not(unix))]
+    let _ = dir;
+}
+
+fn decode_content(request: &FileWriteRequest) -> Result<Vec<u8>, String> {
+    match (&request.content, &request.base64) {
+        (Some(text), None) => Ok(text.as_bytes().to_vec()),
+        (None, Some(encoded)) => base64::engine::general_purpose::STANDARD
+            .decode(encoded)
+            .map_err(|e| format!("Invalid base64 content: {}", e)),
+        _ => Err("Exactly one of content and base64 must be provided".to_string()),
+    }
+}
+
+/// The file a write to `path` replaces: symlinks are written through, not replaced
+pub(crate) fn write_target(path: &str) -> PathBuf {
+    let requested = path_wire::resolve(path);
+    fs::canonicalize(&requested).unwrap_or(requested)
+}
+
+/// Write one file atomically: check the prior hash, write and fsync a temp file next to
+/// the target, carry over the target's permissions, then rename it into place
+pub fn write_atomic(request: &FileWriteRequest) -> Result<FileWriteResult, FileWriteError> {
+    let failed = |message: String| FileWriteError::Failed {
+        path: request.path.clone(),
+        message,
+    };
+    let content = decode_content(request).map_err(failed)?;
+
+    let target = write_target(&request.path);
+    let existing = fs::metadata(&target).ok().filter(|m| m.is_file());
+
+    if let Some(ref expected_hash) = request.expected_prior_hash {
+        let actual_hash = match existing {
+            Some(_) => Some(hash_file(&target).map_err(|e| failed(e.to_string()))?),
+            None => None,
+        };
+        if actual_hash.as_deref() != Some(expected_hash.as_str()) {
+            log::warn!("Write conflict for {}", request.path);
+            return Err(FileWriteError::Conflict {
+                path: request.path.clone(),
+                expected_hash: expected_hash.clone(),
+                actual_hash,
+            });
+        }
+    }
+
+    let dir = target
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    fs::create_dir_all(dir).map_err(|e| failed(format!("Failed to create directory: {}", e)))?;
+    let file_name = target
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .ok_or_else(|| failed("Path has no file name".to_string()))?;
+    let temp_path = dir.join(format!(
+        ".{}.talkcody-write-{}",
+        file_name,
+        rand::random::<u32>()
+    ));
+
+    let write_temp = || -> io::Result<()> {
+        let mut file = File::create(&temp_path)?;
+        file.write_all(&content)?;
+        file.sync_all()?;
+        let permissions = match existing {
+            Some(ref metadata) => metadata.permissions(),
+            None => file.metadata()?.permissions(),
+        };
+        let permissions = match request.make_executable {
+            Some(executable) => with_executable(permissions, executable),
+            None => permissions,
+        };
+        fs::set_permissions(&temp_path, permissions)?;
+        fs::rename(&temp_path, &target)
+    };
+    if let Err(e) = write_temp() {
+        let _ = fs::remove_file(&temp_path);
+        return Err(failed(e.to_string()));
+    }
+    sync_dir(dir);
+
+    let modified_ms = fs::metadata(&target)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|m| m.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0);
+    log::info!("Wrote {} ({} bytes)", request.path, content.len());
+    Ok(FileWriteResult {
+        path: request.path.clone(),
+        hash: hex::encode(Sha256::digest(&content)),
+        size: content.len() as u64,
+        modified_ms,
+        undo_operation_id: None,
+    })
+}
+
+/// `write_atomic`, recorded in `undo` so the save can be reverted
+pub fn write_recorded(
+    undo: Option<&UndoJournal>,
+    request: &FileWriteRequest,
+) -> Result<FileWriteResult, FileWriteError> {
+    let pending = undo.and_then(|undo| undo.track("Save file", &[write_target(&request.path)]));
+    let mut result = write_atomic(request);
+    let undo_operation_id = pending.and_then(|pending| pending.complete(result.is_ok()));
+    if let Ok(ref mut written) = result {
+        written.undo_operation_id = undo_operation_id;
+    }
+    result
+}
+
+/// Write files in order. Each file is all-or-nothing on its own; a failed or conflicting
+/// file does not stop the others. The files written are recorded in `undo` as one
+/// operation.
+pub fn write_all_atomic(
+    undo: Option<&UndoJournal>,
+    requests: &[FileWriteRequest],
+) -> Vec<FileWriteOutcome> {
+    let paths: Vec<PathBuf> = requests.iter().map(|r| write_target(&r.path)).collect();
+    let pending = undo.and_then(|undo| undo.track("Save files", &paths));
+    let mut outcomes: Vec<FileWriteOutcome> = requests
+        .iter()
+        .map(|request| match write_atomic(request) {
+            Ok(result) => FileWriteOutcome {
+                path: request.path.clone(),
+                result: Some(result),
+                error: None,
+            },
+            Err(error) => FileWriteOutcome {
+                path: request.path.clone(),
+                result: None,
+                error: Some(error),
+            },
+        })
+        .collect();
+    let written = outcomes.iter().any(|outcome| outcome.result.is_some());
+    let undo_operation_id = pending.and_then(|pending| pending.complete(written));
+    for result in outcomes
+        .iter_mut()
+        .filter_map(|outcome| outcome.result.as_mut())
+    {
+        result.undo_operation_id = undo_operation_id.clone();
+    }
+    outcomes
+}
+
+#[tauri::command]
+pub async fn write_file_atomic(
+    path: String,
+    content: Option<String>,
+    base64: Option<String>,
+    make_executable: Option<bool>,
+    expected_prior_hash: Option<String>,
+) -> Result<FileWriteResult, FileWriteError> {
+    let request = FileWriteRequest {
+        path: path.clone(),
+        content,
+        base64,
+        make_executable,
+        expected_prior_hash,
+    };
+    let recording = audit::begin("write_file_atomic", || json!(request));
+    let result =
+        tauri::async_runtime::spawn_blocking(move || write_recorded(undo::journal(), &request))
+            .await
+            .map_err(|e| FileWriteError::Failed {
+                path,
+                message: e.to_string(),
+            })
+            .and_then(|result| result);
+    audit::finish(recording, &result);
+    result
+}
+
+#[tauri::command]
+pub async fn write_files_atomic(
+    files: Vec<FileWriteRequest>,
+) -> Result<Vec<FileWriteOutcome>, String> {
+    let recording = audit::begin("write_files_atomic", || json!({ "files": files }));
+    let result =
+        tauri::async_runtime::spawn_blocking(move || write_all_atomic(undo::journal(), &files))
+            .await
+            .map_err(|e| format!("File write task failed: {}", e));
+    audit::finish(recording, &result);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn request(path: &Path, content: &str) -> FileWriteRequest {
+        FileWriteRequest {
+            path: path.to_string_lossy().to_string(),
+            content: Some(content.to_string()),
+            base64: None,
+            make_executable: None,
+            expected_prior_hash: None,
+        }
+    }
+
+    #[test]
+    fn test_conflict_when_prior_hash_does_not_match() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("main.rs");
+        let first = write_atomic(&request(&path, "fn main() {}\n")).unwrap();
+        assert_eq!(first.size, 13);
+
+        // Someone else changed the file after the editor read it
+        fs::write(&path, "fn main() { edited() }\n").unwrap();
+        let mut save = request(&path, "fn main() { mine() }\n");
+        save.expected_prior_hash = Some(first.hash.clone());
+        match write_atomic(&save) {
+            Err(FileWriteError::Conflict {
+                expected_hash,
+                actual_hash,
+                ..
+            }) => {
+                assert_eq!(expected_hash, first.hash);
+                assert_eq!(actual_hash, Some(hash_file(&path).unwrap()));
+            }
+            other => panic!("expected conflict, got {:?}", other),
+        }
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "fn main() { edited() }\n"
+        );
+
+        save.expected_prior_hash = Some(hash_file(&path).unwrap());
+        let saved = write_atomic(&save).unwrap();
+        assert_eq!(saved.hash, hash_file(&path).unwrap());
+
+        // A deleted file is a conflict too
+        fs::remove_file(&path).unwrap();
+        assert!(matches!(
+            write_atomic(&save),
+            Err(FileWriteError::Conflict {
+                actual_hash: None,
+                ..
+            })
+        ));
+        // No temp files are left behind
+        assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_batch_reports_per_file_results() {
+        let temp_dir = TempDir::new().unwrap();
+        let existing = temp_dir.path().join("a.txt");
+        fs::write(&existing, "old").unwrap();
+        let mut conflicting = request(&existing, "new");
+        conflicting.expected_prior_hash = Some("0".repeat(64));
+        let binary = FileWriteRequest {
+            base64: Some("iVBORw0K".to_string()),
+            content: None,
+            ..request(&temp_dir.path().join("nested/b.png"), "")
+        };
+        let invalid = FileWriteRequest {
+            base64: Some("a".to_string()),
+            ..request(&temp_dir.path().join("c.txt"), "both")
+        };
+
+        let outcomes = write_all_atomic(None, &[conflicting, binary, invalid]);
+        assert!(matches!(
+            outcomes[0].error,
+            Some(FileWriteError::Conflict { .. })
+        ));
+        assert_eq!(outcomes[1].result.as_ref().unwrap().size, 6);
+        assert_eq!(
+            fs::read(temp_dir.path().join("nested/b.png")).unwrap(),
+            b"\x89PNG\r\n"
+        );
+        assert!(matches!(
+            outcomes[2].error,
+            Some(FileWriteError::Failed { .. })
+        ));
+        assert_eq!(fs::read_to_string(&existing).unwrap(), "old");
+        assert!(!temp_dir.path().join("c.txt").exists());
+
+        let json = serde_json::to_value(&outcomes[0].error).unwrap();
+        assert_eq!(json["kind"], "conflict");
+        assert!(json["expectedHash"].is_string());
+    }
+
+    #[test]
+    fn test_saves_are_undoable() {
+        let temp_dir = TempDir::new().unwrap();
+        let journal = UndoJournal::new(temp_dir.path().join(undo::UNDO_DIR_NAME));
+        let existing = temp_dir.path().join("a.txt");
+        let created = temp_dir.path().join("b.txt");
+        fs::write(&existing, "old").unwrap();
+
+        let saved = write_recorded(Some(&journal), &request(&existing, "new")).unwrap();
+        let id = saved.undo_operation_id.unwrap();
+        assert_eq!(journal.recent(1)[0].label, "Save file");
+        journal.revert(&id).unwrap();
+        assert_eq!(fs::read_to_string(&existing).unwrap(), "old");
+
+        let mut conflicting = request(&existing, "newer");
+        conflicting.expected_prior_hash = Some("0".repeat(64));
+        let outcomes = write_all_atomic(
+            Some(&journal),
+            &[
+                request(&existing, "new"),
+                request(&created, "created"),
+                conflicting,
+            ],
+        );
+        let id = outcomes[0]
+            .result
+            .as_ref()
+            .unwrap()
+            .undo_operation_id
+            .clone();
+        assert_eq!(id, outcomes[1].result.as_ref().unwrap().undo_operation_id);
+        let reverted = journal.revert(&id.unwrap()).unwrap();
+        assert!(reverted.conflicted.is_empty());
+        assert_eq!(fs::read_to_string(&existing).unwrap(), "old");
+        assert!(!created.exists());
+
+        // Secret files are recorded without their content
+        let env = temp_dir.path().join(".env");
+        fs::write(&env, "TOKEN=1").unwrap();
+        write_recorded(Some(&journal), &request(&env, "TOKEN=2")).unwrap();
+        assert_eq!(
+            journal.recent(1)[0].files[0].prior,
+            undo::PriorContent::Withheld
+        );
+        let id = journal.recent(1)[0].id.clone();
+        journal.revert(&id).unwrap();
+        assert_eq!(fs::read_to_string(&env).unwrap(), "TOKEN=2");
+
+        // A save that writes nothing is not recorded
+        let mut conflicting = request(&existing, "newer");
+        conflicting.expected_prior_hash = Some("0".repeat(64));
+        assert!(write_recorded(Some(&journal), &conflicting).is_err());
+        assert!(journal.recent(1).is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_permissions_are_preserved_and_symlinks_followed() {
+        use std::os::unix::fs::PermissionsExt;
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("deploy.sh");
+        fs::write(&path, "#!/bin/sh\n").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o640)).unwrap();
+        let mode = |p: &Path| fs::metadata(p).unwrap().permissions().mode() & 0o777;
+
+        write_atomic(&request(&path, "#!/bin/sh\necho hi\n")).unwrap();
+        assert_eq!(mode(&path), 0o640);
+
+        let mut executable = request(&path, "#!/bin/sh\necho hi\n");
+        executable.make_executable = Some(true);
+        write_atomic(&executable).unwrap();
+        assert_eq!(mode(&path), 0o750);
+        executable.make_executable = Some(false);
+        write_atomic(&executable).unwrap();
+        assert_eq!(mode(&path), 0o640);
+
+        let link = temp_dir.path().join("link.sh");
+        std::os::unix::fs::symlink(&path, &link).unwrap();
+        write_atomic(&request(&link, "via link\n")).unwrap();
+        assert!(fs::symlink_metadata(&link)
+            .unwrap()
+            .file_type()
+            .is_symlink());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "via link\n");
+    }
+}
@@ -0,0 +1,608 @@
+// Formatter module
+// Bridge to the formatters users already have installed (prettier, rustfmt, black, ...).
+// Nothing is bundled: each language maps to a command that reads the source on stdin and
+// writes the formatted source to stdout. Defaults can be overridden per language in the
+// settings table. Range formatting uses the formatter's own range flags where it has them,
+// and otherwise formats only the selected text and splices it back into the file.
+
+use crate::database::Database;
+use crate::process_info;
+use crate::settings;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::State;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+/// Settings key holding per-language overrides as JSON
+pub const FORMATTERS_SETTING: &str = "code_formatters";
+/// Timeout for formatters configured without one
+const DEFAULT_FORMAT_TIMEOUT_MS: u64 = 10_000;
+
+/// How to run a formatter. Arguments may contain `{file}` (the file path) and, in
+/// `range_args`, `{rangeStart}`/`{rangeEnd}` (UTF-16 offsets, as prettier expects).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FormatterConfig {
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Extra arguments restricting formatting to a range. Without them, range requests
+    /// format the selected text on its own.
+    #[serde(default)]
+    pub range_args: Option<Vec<String>>,
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+}
+
+impl FormatterConfig {
+    fn new(command: &str, args: &[&str], range_args: Option<&[&str]>) -> Self {
+        let strings = |args: &[&str]| args.iter().map(|a| a.to_string()).collect();
+        Self {
+            command: command.to_string(),
+            args: strings(args),
+            range_args: range_args.map(strings),
+            timeout_ms: None,
+        }
+    }
+}
+
+const PRETTIER_LANGUAGES: &[&str] = &[
+    "typescript",
+    "javascript",
+    "typescriptreact",
+    "javascriptreact",
+    "tsx",
+    "jsx",
+    "json",
+    "css",
+    "scss",
+    "less",
+    "html",
+    "vue",
+    "markdown",
+    "yaml",
+    "graphql",
+];
+
+fn default_formatters() -> BTreeMap<String, FormatterConfig> {
+    let mut formatters = BTreeMap::new();
+    formatters.insert(
+        "rust".to_string(),
+        FormatterConfig::new("rustfmt", &["--emit", "stdout", "--edition", "2021"], None),
+    );
+    formatters.insert(
+        "python".to_string(),
+        FormatterConfig::new("black", &["-q", "-"], None),
+    );
+    formatters.insert("go".to_string(), FormatterConfig::new("gofmt", &[], None));
+    for lang in PRETTIER_LANGUAGES {
+        formatters.insert(
+            lang.to_string(),
+            FormatterConfig::new(
+                "prettier",
+                &["--stdin-filepath", "{file}"],
+                Some(&["--range-start", "{rangeStart}", "--range-end", "{rangeEnd}"]),
+            ),
+        );
+    }
+    formatters
+}
+
+/// Byte offsets into the content, end exclusive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FormatRange {
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FormatResult {
+    /// The whole file after formatting
+    pub content: String,
+    pub changed: bool,
+    /// Formatter that produced it
+    pub formatter: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum FormatError {
+    #[serde(rename_all = "camelCase")]
+    NotConfigured {
+        lang_id: String,
+    },
+    /// The formatter command is not installed or not on PATH
+    NotFound {
+        command: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    Failed {
+        command: String,
+        exit_code: Option<i32>,
+        stderr: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    TimedOut {
+        command: String,
+        timeout_ms: u64,
+    },
+    InvalidRange {
+        message: String,
+    },
+    Io {
+        message: String,
+    },
+}
+
+impl std::fmt::Display for FormatError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FormatError::NotConfigured { lang_id } => {
+                write!(f, "No formatter configured for {}", lang_id)
+            }
+            FormatError::NotFound { command } => write!(f, "Formatter '{}' not found", command),
+            FormatError::Failed {
+                command,
+                exit_code,
+                stderr,
+            } => write!(
+                f,
+                "Formatter '{}' failed (exit code {:?}): {}",
+                command,
+                exit_code,
+                stderr.trim()
+            ),
+            FormatError::TimedOut {
+                command,
+                timeout_ms,
+            } => write!(
+                f,
+                "Formatter '{}' timed out after {}ms",
+                command, timeout_ms
+            ),
+            FormatError::InvalidRange { message } => write!(f, "Invalid range: {}", message),
+            FormatError::Io { message } => write!(f, "{}", message),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FormatterStatus {
+    pub lang_id: String,
+    pub command: String,
+    /// Resolved executable, None when the formatter is not installed
+    pub path: Option<String>,
+}
+
+lazy_static::lazy_static! {
+    /// Resolved executables by command. Installing a formatter later needs a refresh
+    /// through format_check_formatters, like the other runtime checks.
+    static ref RESOLVED_COMMANDS: std::sync::Mutex<HashMap<String, Option<PathBuf>>> =
+        std::sync::Mutex::new(HashMap::new());
+}
+
+fn resolve_command(command: &str) -> Option<PathBuf> {
+    if let Ok(cache) = RESOLVED_COMMANDS.lock() {
+        if let Some(resolved) = cache.get(command) {
+            return resolved.clone();
+        }
+    }
+    let resolved = if command.contains(['/', '\\']) {
+        Some(PathBuf::from(command)).filter(|path| path.is_file())
+    } else {
+        which::which(command).ok()
+    };
+    if let Ok(mut cache) = RESOLVED_COMMANDS.lock() {
+        cache.insert(command.to_string(), resolved.clone());
+    }
+    resolved
+}
+
+/// Defaults with the user's overrides applied
+async fn load_formatters(db: &Database) -> Result<BTreeMap<String, FormatterConfig>, String> {
+    let mut formatters = default_formatters();
+    if let Some(overrides) =
+        settings::get_json_setting::<BTreeMap<String, FormatterConfig>>(db, FORMATTERS_SETTING)
+            .await?
+    {
+        formatters.extend(overrides);
+    }
+    Ok(formatters)
+}
+
+fn utf16_offset(content: &str, byte_offset: usize) -> usize {
+    content[..byte_offset].encode_utf16().count()
+}
+
+fn expand_args(args: &[String], file_path: &str, range: Option<(usize, usize)>) -> Vec<String> {
+    args.iter()
+        .map(|arg| {
+            let mut arg = arg.replace("{file}", file_path);
+            if let Some((start, end)) = range {
+                arg = arg
+                    .replace("{rangeStart}", &start.to_string())
+                    .replace("{rangeEnd}", &end.to_string());
+            }
+            arg
+        })
+        .collect()
+}
+
+/// Run `program` with `input` on stdin and return its stdout
+async fn run_formatter(
+    command: &str,
+    program: &Path,
+    args: &[String],
+    cwd: Option<&Path>,
+    input: &str,
+    timeout: Duration,
+) -> Result<String, FormatError> {
+    let mut cmd = Command::new(program);
+    cmd.args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    if let Some(cwd) = cwd {
+        cmd.current_dir(cwd);
+    }
+    let mut child = cmd.spawn().map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => FormatError::NotFound {
+            command: command.to_string(),
+        },
+        _ => FormatError::Io {
+            message: format!("Failed to start formatter '{}': {}", command, e),
+        },
+    })?;
+    let pid = child.id();
+    if let Some(pid) = pid {
+        process_info::register_spawned_pid(pid);
+    }
+
+    // Write stdin concurrently, a formatter may start writing before it read everything
+    let mut stdin = child.stdin.take();
+    let input = input.to_string();
+    let writer = tokio::spawn(async move {
+        if let Some(ref mut stdin) = stdin {
+            let _ = stdin.write_all(input.as_bytes()).await;
+            let _ = stdin.shutdown().await;
+        }
+    });
+
+    let output = tokio::time::timeout(timeout, child.wait_with_output()).await;
+    writer.abort();
+    if let Some(pid) = pid {
+        if output.is_err() {
+            let _ = process_info::kill_tree(pid, process_info::KillSignal::Kill);
+        }
+        process_info::unregister_spawned_pid(pid);
+    }
+
+    let output = output
+        .map_err(|_| FormatError::TimedOut {
+            command: command.to_string(),
+            timeout_ms: timeout.as_millis() as u64,
+        })?
+        .map_err(|e| FormatError::Io {
+            message: format!("Formatter '{}' failed: {}", command, e),
+        })?;
+    if !output.status.success() {
+        return Err(FormatError::Failed {
+            command: command.to_string(),
+            exit_code: output.status.code(),
+            stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+        });
+    }
+    String::from_utf8(output.stdout).map_err(|_| FormatError::Io {
+        message: format!("Formatter '{}' produced invalid UTF-8", command),
+    })
+}
+
+/// Format `content` (or only `range` of it) with `config`
+pub async fn format_with(
+    config: &FormatterConfig,
+    file_path: &str,
+    content: &str,
+    range: Option<FormatRange>,
+) -> Result<FormatResult, FormatError> {
+    if let Some(range) = range {
+        if range.start > range.end
+            || range.end > content.len()
+            || !content.is_char_boundary(range.start)
+            || !content.is_char_boundary(range.end)
+        {
+            return Err(FormatError::InvalidRange {
+                message: format!(
+                    "{}..{} is not a valid range of {} bytes",
+                    range.start,
+                    range.end,
+                    content.len()
+                ),
+            });
+        }
+    }
+
+    let program = resolve_command(&config.command).ok_or_else(|| FormatError::NotFound {
+        command: config.command.clone(),
+    })?;
+    let cwd = Path::new(file_path).parent().filter(|dir| dir.is_dir());
+    let timeout = Duration::from_millis(config.timeout_ms.unwrap_or(DEFAULT_FORMAT_TIMEOUT_MS));
+
+    let formatted = match (range, &config.range_args) {
+        (None, _) => {
+            let args = expand_args(&config.args, file_path, None);
+            run_formatter(&config.command, &program, &args, cwd, content, timeout).await?
+        }
+        // The formatter formats the range in place and returns the whole file
+        (Some(range), Some(range_args)) => {
+            let offsets = (
+                utf16_offset(content, range.start),
+                utf16_offset(content, range.end),
+            );
+            let mut args = expand_args(&config.args, file_path, None);
+            args.extend(expand_args(range_args, file_path, Some(offsets)));
+            run_formatter(&config.command, &program, &args, cwd, content, timeout).await?
+        }
+        // Format the selection on its own and put it back
+        (Some(range), None) => {
+            let args = expand_args(&config.args, file_path, None);
+            let selection = &content[range.start..range.end];
+            let mut formatted =
+                run_formatter(&config.command, &program, &args, cwd, selection, timeout).await?;
+            // Formatters end output with a newline; keep the selection's own ending
+            if !selection.ends_with('\n') {
+                let trimmed = formatted.trim_end_matches(['\n', '\r']).len();
+                formatted.truncate(trimmed);
+            }
+            format!(
+                "{}{}{}",
+                &content[..range.start],
+                formatted,
+                &content[range.end..]
+            )
+        }
+    };
+
+    Ok(FormatResult {
+        changed: formatted != content,
+        content: formatted,
+        formatter: config.command.clone(),
+    })
+}
+
+/// Format a file's content with the formatter configured for its language
+#[tauri::command]
+pub async fn format_code(
+    db: State<'_, Arc<Database>>,
+    file_path: String,
+    content: String,
+    lang_id: String,
+    range: Option<FormatRange>,
+) -> Result<FormatResult, FormatError> {
+    let formatters = load_formatters(&db)
+        .await
+        .map_err(|message| FormatError::Io { message })?;
+    let config = formatters
+        .get(&lang_id)
+        .ok_or_else(|| FormatError::NotConfigured {
+            lang_id: lang_id.clone(),
+        })?;
+    let result = format_with(config, &file_path, &content, range).await;
+    match &result {
+        Ok(result) => log::info!(
+            "Formatted {} with {} (changed: {})",
+            file_path,
+            result.formatter,
+            result.changed
+        ),
+        Err(e) => log::warn!("Formatting {} failed: {}", file_path, e),
+    }
+    result
+}
+
+/// Formatter per language, defaults merged with overrides
+#[tauri::command]
+pub async fn format_get_config(
+    db: State<'_, Arc<Database>>,
+) -> Result<BTreeMap<String, FormatterConfig>, String> {
+    load_formatters(&db).await
+}
+
+/// Replace the per-language overrides of the default formatters
+#[tauri::command]
+pub async fn format_set_config(
+    db: State<'_, Arc<Database>>,
+    overrides: BTreeMap<String, FormatterConfig>,
+) -> Result<BTreeMap<String, FormatterConfig>, String> {
+    if let Some((lang, _)) = overrides
+        .iter()
+        .find(|(_, config)| config.command.trim().is_empty())
+    {
+        return Err(format!("Formatter command for {} cannot be empty", lang));
+    }
+    settings::set_json_setting(&db, FORMATTERS_SETTING, &overrides).await?;
+    load_formatters(&db).await
+}
+
+/// Which configured formatters are installed. `refresh` re-resolves commands, e.g.
+/// after installing a formatter.
+#[tauri::command]
+pub async fn format_check_formatters(
+    db: State<'_, Arc<Database>>,
+    refresh: Option<bool>,
+) -> Result<Vec<FormatterStatus>, String> {
+    if refresh.unwrap_or(false) {
+        if let Ok(mut cache) = RESOLVED_COMMANDS.lock() {
+            cache.clear();
+        }
+    }
+    let formatters = load_formatters(&db).await?;
+    tauri::async_runtime::spawn_blocking(move || {
+        formatters
+            .into_iter()
+            .map(|(lang_id, config)| FormatterStatus {
+                path: resolve_command(&config.command).map(|p| p.to_string_lossy().to_string()),
+                lang_id,
+                command: config.command,
+            })
+            .collect()
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+    use tempfile::TempDir;
+
+    fn script(dir: &TempDir, name: &str, body: &str) -> FormatterConfig {
+        let path = dir.path().join(name);
+        std::fs::write(&path, format!("#!/bin/sh\n{}\n", body)).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        FormatterConfig {
+            command: path.to_string_lossy().to_string(),
+            args: Vec::new(),
+            range_args: None,
+            timeout_ms: None,
+        }
+    }
+
+    fn uppercase(dir: &TempDir) -> FormatterConfig {
+        script(dir, "upper.sh", "tr '[:lower:]' '[:upper:]'")
+    }
+
+    #[tokio::test]
+    async fn test_formats_whole_file_through_stdin() {
+        let dir = TempDir::new().unwrap();
+        let result = format_with(&uppercase(&dir), "/p/a.txt", "let x = 1;\n", None)
+            .await
+            .unwrap();
+        assert_eq!(result.content, "LET X = 1;\n");
+        assert!(result.changed);
+    }
+
+    #[tokio::test]
+    async fn test_range_without_range_support_is_spliced_back() {
+        let dir = TempDir::new().unwrap();
+        let content = "keep\nformat me\nkeep\n";
+        let start = content.find("format").unwrap();
+        let range = FormatRange {
+            start,
+            end: start + "format me".len(),
+        };
+        let result = format_with(&uppercase(&dir), "/p/a.txt", content, Some(range))
+            .await
+            .unwrap();
+        assert_eq!(result.content, "keep\nFORMAT ME\nkeep\n");
+    }
+
+    #[tokio::test]
+    async fn test_range_args_get_utf16_offsets() {
+        let dir = TempDir::new().unwrap();
+        // Echo the arguments so the test can see what the formatter received
+        let mut config = script(&dir, "args.sh", "cat > /dev/null; echo \"$@\"");
+        config.args = vec!["--stdin-filepath".into(), "{file}".into()];
+        config.range_args = Some(vec!["{rangeStart}".into(), "{rangeEnd}".into()]);
+        let content = "é = 1\nx = 2\n";
+        let start = content.find('x').unwrap();
+        let range = FormatRange {
+            start,
+            end: content.len(),
+        };
+        let result = format_with(&config, "/p/a.py", content, Some(range))
+            .await
+            .unwrap();
+        assert_eq!(result.content, "--stdin-filepath /p/a.py 6 12\n");
+    }
+
+    #[tokio::test]
+    async fn test_nonzero_exit_reports_stderr() {
+        let dir = TempDir::new().unwrap();
+        let config = script(&dir, "fail.sh", "echo 'syntax error on line 1' >&2; exit 3");
+        let err = format_with(&config, "/p/a.txt", "x", None)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err,
+            FormatError::Failed {
+                command: config.command.clone(),
+                exit_code: Some(3),
+                stderr: "syntax error on line 1\n".to_string(),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_missing_formatter_and_bad_range() {
+        let config = FormatterConfig::new("talkcody-no-such-formatter", &[], None);
+        let err = format_with(&config, "/p/a.txt", "x", None)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err,
+            FormatError::NotFound {
+                command: "talkcody-no-such-formatter".to_string()
+            }
+        );
+
+        let dir = TempDir::new().unwrap();
+        let err = format_with(
+            &uppercase(&dir),
+            "/p/a.txt",
+            "abc",
+            Some(FormatRange { start: 2, end: 9 }),
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(err, FormatError::InvalidRange { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_slow_formatter_times_out() {
+        let dir = TempDir::new().unwrap();
+        let mut config = script(&dir, "slow.sh", "sleep 5");
+        config.timeout_ms = Some(100);
+        let err = format_with(&config, "/p/a.txt", "x", None)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            FormatError::TimedOut {
+                timeout_ms: 100,
+                ..
+            }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_overrides_replace_defaults_per_language() {
+        let dir = TempDir::new().unwrap();
+        let db = Database::new(dir.path().join("test.db").to_string_lossy().to_string());
+        db.connect().await.unwrap();
+
+        let defaults = load_formatters(&db).await.unwrap();
+        assert_eq!(defaults["python"].command, "black");
+        assert!(defaults["typescript"].range_args.is_some());
+
+        let mut overrides = BTreeMap::new();
+        overrides.insert(
+            "python".to_string(),
+            FormatterConfig::new("ruff", &["format", "-"], None),
+        );
+        settings::set_json_setting(&db, FORMATTERS_SETTING, &overrides)
+            .await
+            .unwrap();
+        let formatters = load_formatters(&db).await.unwrap();
+        assert_eq!(formatters["python"].command, "ruff");
+        assert_eq!(formatters["rust"].command, "rustfmt");
+    }
+}
@@ -0,0 +1,352 @@
+// Inline blame for the editor's current line ("Alice, 3 weeks ago - Fix login bug").
+// Cursor moves query one line at a time, so the whole file is blamed once and cached,
+// keyed by HEAD and a hash of the file on disk. Uncommitted lines are reported as such:
+// lines changed on disk come back from blaming the working copy without a commit, and
+// unsaved edits are caught by comparing the editor's line text with the blamed content.
+// Only a few recently focused files are kept.
+
+use super::history::relative_path;
+use git2::{BlameOptions, Oid, Repository};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::{DefaultHasher, Entry};
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Files whose blame is kept
+const MAX_CACHED_FILES: usize = 16;
+/// Blame for a file not queried for this long is dropped
+const CACHE_IDLE_TTL: Duration = Duration::from_secs(10 * 60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AnnotationStatus {
+    Committed,
+    /// Changed on disk or in the editor since the last commit
+    NotCommittedYet,
+    OutOfRange,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlameCommit {
+    pub commit_id: String,
+    pub short_id: String,
+    pub summary: String,
+    pub author_name: String,
+    pub author_email: String,
+    /// Commit time in seconds since the epoch
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LineAnnotation {
+    /// 1-based
+    pub line: u32,
+    pub status: AnnotationStatus,
+    /// Set when committed
+    pub commit: Option<BlameCommit>,
+    /// e.g. "3 weeks ago", set when committed
+    pub relative_time: Option<String>,
+}
+
+/// Blame of one file as it is on disk
+struct FileBlame {
+    head: Option<Oid>,
+    content_hash: u64,
+    /// Commit of each line; None for lines not committed yet
+    line_commits: Vec<Option<Oid>>,
+    line_texts: Vec<String>,
+    commits: HashMap<Oid, BlameCommit>,
+    last_used: Instant,
+}
+
+impl FileBlame {
+    fn annotate(&self, line: u32, line_text: Option<&str>) -> LineAnnotation {
+        let mut annotation = LineAnnotation {
+            line,
+            status: AnnotationStatus::OutOfRange,
+            commit: None,
+            relative_time: None,
+        };
+        let index = (line as usize).wrapping_sub(1);
+        let (Some(commit_id), Some(text)) =
+            (self.line_commits.get(index), self.line_texts.get(index))
+        else {
+            // A line past the end of the saved file can only be an unsaved addition
+            if line_text.is_some() && line > 0 {
+                annotation.status = AnnotationStatus::NotCommittedYet;
+            }
+            return annotation;
+        };
+        let edited = line_text.is_some_and(|current| current.trim_end_matches('\r') != text);
+        match commit_id.and_then(|id| self.commits.get(&id)) {
+            Some(commit) if !edited => {
+                annotation.status = AnnotationStatus::Committed;
+                annotation.relative_time = Some(relative_time(
+                    commit.timestamp,
+                    chrono::Utc::now().timestamp(),
+                ));
+                annotation.commit = Some(commit.clone());
+            }
+            _ => annotation.status = AnnotationStatus::NotCommittedYet,
+        }
+        annotation
+    }
+}
+
+#[derive(Default)]
+struct BlameCache {
+    files: HashMap<(PathBuf, String), FileBlame>,
+}
+
+impl BlameCache {
+    fn evict(&mut self) {
+        self.files
+            .retain(|_, blame| blame.last_used.elapsed() < CACHE_IDLE_TTL);
+        while self.files.len() > MAX_CACHED_FILES {
+            let oldest = self
+                .files
+                .iter()
+                .min_by_key(|(_, blame)| blame.last_used)
+                .map(|(key, _)| key.clone());
+            match oldest {
+                Some(key) => self.files.remove(&key),
+                None => break,
+            };
+        }
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref BLAME_CACHE: Mutex<BlameCache> = Mutex::new(BlameCache::default());
+}
+
+fn hash_content(content: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn blame_commit(repo: &Repository, id: Oid) -> Result<BlameCommit, git2::Error> {
+    let commit = repo.find_commit(id)?;
+    let author = commit.author();
+    let commit_id = id.to_string();
+    let blame_commit = BlameCommit {
+        short_id: commit_id[..7].to_string(),
+        commit_id,
+        summary: commit.summary().unwrap_or("").to_string(),
+        author_name: author.name().unwrap_or("").to_string(),
+        author_email: author.email().unwrap_or("").to_string(),
+        timestamp: commit.time().seconds(),
+    };
+    Ok(blame_commit)
+}
+
+/// Blame the working copy `content` of `path`. Files not in HEAD are entirely uncommitted.
+fn compute_blame(
+    repo: &Repository,
+    path: &str,
+    head: Option<Oid>,
+    content: &[u8],
+) -> Result<FileBlame, git2::Error> {
+    let text = String::from_utf8_lossy(content);
+    let line_texts: Vec<String> = text
+        .lines()
+        .map(|line| line.trim_end_matches('\r').to_string())
+        .collect();
+    let mut line_commits = vec![None; line_texts.len()];
+    let mut commits = HashMap::new();
+
+    let committed = head.is_some()
+        && repo
+            .head()
+            .and_then(|head| head.peel_to_tree())
+            .is_ok_and(|tree| tree.get_path(Path::new(path)).is_ok());
+    if committed {
+        let blame = repo.blame_file(Path::new(path), Some(&mut BlameOptions::new()))?;
+        let blame = blame.blame_buffer(content)?;
+        for hunk in blame.iter() {
+            let id = hunk.final_commit_id();
+            if id.is_zero() {
+                continue;
+            }
+            if let Entry::Vacant(entry) = commits.entry(id) {
+                entry.insert(blame_commit(repo, id)?);
+            }
+            let start = hunk.final_start_line().saturating_sub(1);
+            let end = (start + hunk.lines_in_hunk()).min(line_commits.len());
+            for line in line_commits.iter_mut().take(end).skip(start) {
+                *line = Some(id);
+            }
+        }
+    }
+
+    Ok(FileBlame {
+        head,
+        content_hash: hash_content(content),
+        line_commits,
+        line_texts,
+        commits,
+        last_used: Instant::now(),
+    })
+}
+
+/// Make sure the cached blame of `file_path` matches HEAD and the file on disk, then run
+/// `f` on it
+fn with_blame<T>(
+    repo: &Repository,
+    file_path: &str,
+    f: impl FnOnce(&FileBlame) -> T,
+) -> Result<T, String> {
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| "Blame needs a working directory".to_string())?;
+    let path = relative_path(repo, file_path);
+    let content = std::fs::read(workdir.join(&path))
+        .map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let head = repo.head().ok().and_then(|head| head.target());
+    let content_hash = hash_content(&content);
+    let key = (workdir.to_path_buf(), path.clone());
+
+    {
+        let mut cache = BLAME_CACHE.lock().map_err(|e| e.to_string())?;
+        if let Some(blame) = cache.files.get_mut(&key) {
+            if blame.head == head && blame.content_hash == content_hash {
+                blame.last_used = Instant::now();
+                return Ok(f(blame));
+            }
+        }
+    }
+
+    // Blame outside the lock; it can take a while on long histories
+    let blame = compute_blame(repo, &path, head, &content)
+        .map_err(|e| format!("Failed to blame {}: {}", path, e))?;
+    let result = f(&blame);
+    let mut cache = BLAME_CACHE.lock().map_err(|e| e.to_string())?;
+    cache.files.insert(key, blame);
+    cache.evict();
+    Ok(result)
+}
+
+/// Blame annotation of one line. With `line_text`, the editor's current text of the line,
+/// a line edited but not saved is reported as not committed yet.
+pub fn get_line_annotation(
+    repo: &Repository,
+    file_path: &str,
+    line: u32,
+    line_text: Option<&str>,
+) -> Result<LineAnnotation, String> {
+    with_blame(repo, file_path, |blame| blame.annotate(line, line_text))
+}
+
+/// Blame a file ahead of the first cursor move
+pub fn prefetch_blame(repo: &Repository, file_path: &str) -> Result<(), String> {
+    with_blame(repo, file_path, |_| ())
+}
+
+/// "3 weeks ago" style age of `timestamp` as of `now`, both in seconds
+pub fn relative_time(timestamp: i64, now: i64) -> String {
+    let seconds = (now - timestamp).max(0);
+    let (count, unit) = match seconds {
+        0..=44 => return "just now".to_string(),
+        45..=3_599 => ((seconds + 30) / 60, "minute"),
+        3_600..=86_399 => ((seconds + 1_800) / 3_600, "hour"),
+        _ => {
+            let days = seconds / 86_400;
+            match days {
+                0..=13 => (days, "day"),
+                14..=59 => (days / 7, "week"),
+                60..=364 => (days / 30, "month"),
+                _ => (days / 365, "year"),
+            }
+        }
+    };
+    if count == 1 {
+        format!("1 {} ago", unit)
+    } else {
+        format!("{} {}s ago", count, unit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::git;
+    use tempfile::TempDir;
+
+    fn commit_file(dir: &Path, path: &str, content: &str, message: &str) {
+        std::fs::write(dir.join(path), content).unwrap();
+        git(dir, &["add", "-A"]);
+        git(dir, &["commit", "-m", message]);
+    }
+
+    fn create_repo() -> TempDir {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        git(dir, &["init", "-b", "main"]);
+        git(dir, &["config", "user.email", "alice@test.com"]);
+        git(dir, &["config", "user.name", "Alice"]);
+        commit_file(dir, "login.ts", "a\nb\nc\n", "Add login");
+        commit_file(dir, "login.ts", "a\nfixed\nc\n", "Fix login bug");
+        temp_dir
+    }
+
+    #[test]
+    fn test_committed_and_dirty_lines() {
+        let temp_dir = create_repo();
+        let dir = temp_dir.path();
+        let repo = Repository::open(dir).unwrap();
+        let file = dir.join("login.ts").to_string_lossy().to_string();
+        prefetch_blame(&repo, &file).unwrap();
+
+        let annotation = get_line_annotation(&repo, &file, 2, Some("fixed")).unwrap();
+        assert_eq!(annotation.status, AnnotationStatus::Committed);
+        let commit = annotation.commit.unwrap();
+        assert_eq!(commit.summary, "Fix login bug");
+        assert_eq!(commit.author_name, "Alice");
+        assert_eq!(annotation.relative_time.as_deref(), Some("just now"));
+        let first = get_line_annotation(&repo, &file, 1, None).unwrap();
+        assert_eq!(first.commit.unwrap().summary, "Add login");
+
+        // Edited in the editor but not saved: the blamed commit would be wrong
+        let unsaved = get_line_annotation(&repo, &file, 2, Some("fixed!")).unwrap();
+        assert_eq!(unsaved.status, AnnotationStatus::NotCommittedYet);
+        assert!(unsaved.commit.is_none());
+        let added = get_line_annotation(&repo, &file, 4, Some("new line")).unwrap();
+        assert_eq!(added.status, AnnotationStatus::NotCommittedYet);
+        let past_end = get_line_annotation(&repo, &file, 4, None).unwrap();
+        assert_eq!(past_end.status, AnnotationStatus::OutOfRange);
+
+        // Saved but not committed: the cache notices the new content
+        std::fs::write(dir.join("login.ts"), "a\nfixed\nchanged\n").unwrap();
+        let saved = get_line_annotation(&repo, &file, 3, Some("changed")).unwrap();
+        assert_eq!(saved.status, AnnotationStatus::NotCommittedYet);
+        let untouched = get_line_annotation(&repo, &file, 2, Some("fixed")).unwrap();
+        assert_eq!(untouched.status, AnnotationStatus::Committed);
+
+        // Untracked files have no commits at all
+        std::fs::write(dir.join("new.ts"), "x\n").unwrap();
+        let new_file = dir.join("new.ts").to_string_lossy().to_string();
+        let untracked = get_line_annotation(&repo, &new_file, 1, None).unwrap();
+        assert_eq!(untracked.status, AnnotationStatus::NotCommittedYet);
+    }
+
+    #[test]
+    fn test_relative_time() {
+        let now = 1_700_000_000;
+        let day = 86_400;
+        assert_eq!(relative_time(now - 10, now), "just now");
+        assert_eq!(relative_time(now - 60, now), "1 minute ago");
+        assert_eq!(relative_time(now - 5 * 3_600, now), "5 hours ago");
+        assert_eq!(relative_time(now - day, now), "1 day ago");
+        assert_eq!(relative_time(now - 21 * day, now), "3 weeks ago");
+        assert_eq!(relative_time(now - 90 * day, now), "3 months ago");
+        assert_eq!(relative_time(now - 800 * day, now), "2 years ago");
+        // Clock skew between machines
+        assert_eq!(relative_time(now + 100, now), "just now");
+    }
+}
@@ -0,0 +1,283 @@
+// Committing staged changes through git2. git2 does not run hooks, so when asked to, the
+// pre-commit and commit-msg hooks are run first and a failing hook aborts the commit.
+// The repository is reopened after each hook rather than held across awaits.
+
+use super::hooks::{self, GitHooksInfo};
+use git2::{Oid, Repository};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitResult {
+    pub commit_hash: String,
+    /// Commit hooks the repository has that were not run, for a UI notice
+    pub hooks_skipped: Vec<String>,
+}
+
+struct CommitContext {
+    workdir: PathBuf,
+    git_dir: PathBuf,
+    index_path: PathBuf,
+    hooks: GitHooksInfo,
+}
+
+fn open(repo_path: &str) -> Result<Repository, String> {
+    Repository::discover(repo_path).map_err(|e| format!("Failed to open repository: {}", e))
+}
+
+fn head_tree_id(repo: &Repository) -> Option<Oid> {
+    repo.head()
+        .ok()
+        .and_then(|head| head.peel_to_tree().ok())
+        .map(|tree| tree.id())
+}
+
+/// Fail like `git commit` does when the index matches HEAD
+fn ensure_staged_changes(repo: &Repository) -> Result<(), String> {
+    let mut index = repo
+        .index()
+        .map_err(|e| format!("Failed to read index: {}", e))?;
+    index
+        .read(true)
+        .map_err(|e| format!("Failed to read index: {}", e))?;
+    let tree_id = index
+        .write_tree()
+        .map_err(|e| format!("Failed to write tree: {}", e))?;
+    if head_tree_id(repo) == Some(tree_id) || (repo.head().is_err() && index.is_empty()) {
+        return Err("Nothing to commit".to_string());
+    }
+    Ok(())
+}
+
+fn prepare(repo_path: &str) -> Result<CommitContext, String> {
+    let repo = open(repo_path)?;
+    let workdir = repo
+        .workdir()
+        .ok_or("Cannot commit in a bare repository")?
+        .to_path_buf();
+    ensure_staged_changes(&repo)?;
+    Ok(CommitContext {
+        workdir,
+        git_dir: repo.path().to_path_buf(),
+        index_path: repo.path().join("index"),
+        hooks: hooks::get_hooks(&repo)?,
+    })
+}
+
+/// Commit the index as it is now (hooks may have restaged files) on top of HEAD
+fn create_commit(repo_path: &str, message: &str) -> Result<String, String> {
+    let repo = open(repo_path)?;
+    ensure_staged_changes(&repo)?;
+    let mut index = repo
+        .index()
+        .map_err(|e| format!("Failed to read index: {}", e))?;
+    let tree = index
+        .write_tree()
+        .and_then(|id| repo.find_tree(id))
+        .map_err(|e| format!("Failed to write tree: {}", e))?;
+    let signature = repo
+        .signature()
+        .map_err(|e| format!("Failed to get commit author (is user.name set?): {}", e))?;
+    let parent = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+    let parents: Vec<&git2::Commit> = parent.iter().collect();
+
+    let oid = repo
+        .commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            message,
+            &tree,
+            &parents,
+        )
+        .map_err(|e| format!("Failed to commit: {}", e))?;
+    Ok(oid.to_string())
+}
+
+/// Commit the staged changes. With `run_hooks`, pre-commit and commit-msg run first (the
+/// latter may rewrite the message); otherwise the hooks git would have run are reported
+/// back as skipped. prepare-commit-msg and post-commit are never run.
+pub async fn commit_staged(
+    repo_path: &str,
+    message: &str,
+    run_hooks: bool,
+) -> Result<CommitResult, String> {
+    let mut message = git2::message_prettify(message, None)
+        .map_err(|e| format!("Invalid commit message: {}", e))?;
+    if message.trim().is_empty() {
+        return Err("Commit message is empty".to_string());
+    }
+    let context = prepare(repo_path)?;
+
+    let mut ran = Vec::new();
+    if run_hooks {
+        // Hooks such as lint-staged use these to find the index being committed
+        let environment = HashMap::from([
+            (
+                "GIT_INDEX_FILE".to_string(),
+                context.index_path.to_string_lossy().to_string(),
+            ),
+            ("GIT_EDITOR".to_string(), ":".to_string()),
+        ]);
+        if hooks::run_hook(
+            &context.hooks,
+            "pre-commit",
+            &[],
+            &context.workdir,
+            environment.clone(),
+        )
+        .await?
+        {
+            ran.push("pre-commit");
+        }
+
+        let message_path = context.git_dir.join("COMMIT_EDITMSG");
+        std::fs::write(&message_path, &message)
+            .map_err(|e| format!("Failed to write commit message: {}", e))?;
+        let args = [message_path.to_string_lossy().to_string()];
+        if hooks::run_hook(
+            &context.hooks,
+            "commit-msg",
+            &args,
+            &context.workdir,
+            environment,
+        )
+        .await?
+        {
+            ran.push("commit-msg");
+            let edited = std::fs::read_to_string(&message_path)
+                .map_err(|e| format!("Failed to read commit message: {}", e))?;
+            message = git2::message_prettify(edited, None)
+                .map_err(|e| format!("Invalid commit message: {}", e))?;
+            if message.trim().is_empty() {
+                return Err("commit-msg hook left an empty commit message".to_string());
+            }
+        }
+    }
+
+    let commit_hash = create_commit(repo_path, &message)?;
+    let hooks_skipped: Vec<String> = hooks::active_commit_hooks(&context.hooks)
+        .into_iter()
+        .filter(|name| !ran.contains(&name.as_str()))
+        .collect();
+    log::info!(
+        "Committed {} in {} (hooks run: {:?}, skipped: {:?})",
+        commit_hash,
+        repo_path,
+        ran,
+        hooks_skipped
+    );
+    Ok(CommitResult {
+        commit_hash,
+        hooks_skipped,
+    })
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::test_support::git;
+    use std::os::unix::fs::PermissionsExt;
+    use std::path::Path;
+    use tempfile::TempDir;
+
+    fn create_test_repo() -> TempDir {
+        let temp_dir = TempDir::new().unwrap();
+        git(temp_dir.path(), &["init", "-b", "main"]);
+        git(temp_dir.path(), &["config", "user.email", "test@test.com"]);
+        git(temp_dir.path(), &["config", "user.name", "Test User"]);
+        std::fs::write(temp_dir.path().join("README.md"), "# Test\n").unwrap();
+        git(temp_dir.path(), &["add", "."]);
+        temp_dir
+    }
+
+    fn write_hook(dir: &Path, name: &str, script: &str) {
+        std::fs::create_dir_all(dir).unwrap();
+        let path = dir.join(name);
+        std::fs::write(&path, script).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_failing_pre_commit_hook_aborts_commit() {
+        let temp_dir = create_test_repo();
+        let repo_path = temp_dir.path().to_str().unwrap();
+        let hooks_dir = temp_dir.path().join(".git").join("hooks");
+        write_hook(
+            &hooks_dir,
+            "pre-commit",
+            "#!/bin/sh\necho \"lint-staged: 2 problems\" >&2\nexit 1\n",
+        );
+        std::fs::write(hooks_dir.join("pre-push.sample"), "#!/bin/sh\n").unwrap();
+
+        let info = hooks::get_hooks(&open(repo_path).unwrap()).unwrap();
+        assert_eq!(info.hooks_path_override, None);
+        assert_eq!(info.hooks.len(), 1);
+        assert_eq!(info.hooks[0].name, "pre-commit");
+        assert!(info.hooks[0].executable);
+
+        let err = commit_staged(repo_path, "Add readme", true)
+            .await
+            .unwrap_err();
+        assert!(err.contains("pre-commit hook failed"), "{}", err);
+        assert!(err.contains("lint-staged: 2 problems"), "{}", err);
+        assert!(open(repo_path).unwrap().head().is_err());
+
+        // Skipping hooks commits and reports what was skipped
+        let result = commit_staged(repo_path, "Add readme", false).await.unwrap();
+        assert_eq!(result.hooks_skipped, vec!["pre-commit"]);
+        assert_eq!(
+            git(temp_dir.path(), &["rev-parse", "HEAD"]),
+            result.commit_hash
+        );
+        assert_eq!(
+            commit_staged(repo_path, "Again", false).await.unwrap_err(),
+            "Nothing to commit"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_hooks_path_override_and_commit_msg_rewrite() {
+        let temp_dir = create_test_repo();
+        let repo_path = temp_dir.path().to_str().unwrap();
+        git(temp_dir.path(), &["config", "core.hooksPath", ".husky"]);
+        let husky_dir = temp_dir.path().join(".husky");
+        write_hook(
+            &husky_dir,
+            "pre-commit",
+            "#!/bin/sh\necho formatted > formatted.txt\ngit add formatted.txt\n",
+        );
+        write_hook(
+            &husky_dir,
+            "commit-msg",
+            "#!/bin/sh\necho \"Refs: TC-1\" >> \"$1\"\n",
+        );
+        write_hook(&husky_dir, "post-commit", "#!/bin/sh\n");
+        // Not executable, so git would ignore it
+        std::fs::write(husky_dir.join("pre-push"), "#!/bin/sh\n").unwrap();
+
+        let info = hooks::get_hooks(&open(repo_path).unwrap()).unwrap();
+        assert_eq!(info.hooks_path_override.as_deref(), Some(".husky"));
+        assert!(Path::new(&info.hooks_dir).ends_with(".husky"));
+        assert!(
+            !info
+                .hooks
+                .iter()
+                .find(|h| h.name == "pre-push")
+                .unwrap()
+                .executable
+        );
+
+        let result = commit_staged(repo_path, "Add readme", true).await.unwrap();
+        assert_eq!(result.hooks_skipped, vec!["post-commit"]);
+        assert_eq!(
+            git(temp_dir.path(), &["log", "-1", "--format=%B"]),
+            "Add readme\nRefs: TC-1"
+        );
+        // The file the pre-commit hook staged is part of the commit
+        let files = git(temp_dir.path(), &["show", "--name-only", "--format="]);
+        assert!(files.contains("formatted.txt"), "{}", files);
+    }
+}
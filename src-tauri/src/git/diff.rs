@@ -1,5 +1,5 @@
 use super::types::{DiffHunk, DiffLine, DiffLineType, FileDiff, GitFileStatus};
-use git2::{Diff, DiffOptions, Error as GitError, Repository};
+use git2::{AttrCheckFlags, AttrValue, Diff, DiffOptions, Error as GitError, Patch, Repository};
 use lazy_static::lazy_static;
 use lru::LruCache;
 use std::num::NonZeroUsize;
@@ -150,10 +150,130 @@ pub fn get_line_changes(
 
     // Compute line changes
     let file_diff = get_file_diff(repo, file_path)?;
+    let changes = line_changes_from_hunks(file_diff.hunks);
 
+    // Store in cache
+    if let Ok(mut cache) = LINE_CHANGES_CACHE.lock() {
+        cache.put(cache_key, changes.clone());
+        log::debug!(
+            "Cached line changes for: {} ({} changes)",
+            file_path,
+            changes.len()
+        );
+    }
+
+    Ok(changes)
+}
+
+/// Gets line-level changes for an unsaved editor buffer, diffing `content` against the
+/// HEAD blob for `file_path` without reading the working tree. Not cached, since the
+/// buffer changes on every keystroke.
+pub fn get_line_changes_for_content(
+    repo: &Repository,
+    file_path: &str,
+    content: &str,
+) -> Result<Vec<(u32, DiffLineType)>, GitError> {
+    let head_blob = match repo.head().and_then(|head| head.peel_to_tree()) {
+        Ok(tree) => match tree.get_path(std::path::Path::new(file_path)) {
+            Ok(entry) => Some(repo.find_blob(entry.id())?),
+            Err(e) if e.code() == git2::ErrorCode::NotFound => None,
+            Err(e) => return Err(e),
+        },
+        // Unborn branch: nothing is in HEAD yet
+        Err(e) if e.code() == git2::ErrorCode::UnbornBranch => None,
+        Err(e) => return Err(e),
+    };
+
+    let content = if should_normalize_line_endings(repo, file_path, content) {
+        std::borrow::Cow::Owned(content.replace("\r\n", "\n"))
+    } else {
+        std::borrow::Cow::Borrowed(content)
+    };
+
+    let Some(head_blob) = head_blob else {
+        // Not in HEAD: every line is an addition
+        let line_count = content.lines().count() as u32;
+        return Ok((1..=line_count)
+            .map(|line| (line, DiffLineType::Addition))
+            .collect());
+    };
+
+    let mut opts = DiffOptions::new();
+    let patch = Patch::from_blob_and_buffer(
+        &head_blob,
+        Some(std::path::Path::new(file_path)),
+        content.as_bytes(),
+        Some(std::path::Path::new(file_path)),
+        Some(&mut opts),
+    )?;
+
+    let mut hunks = Vec::with_capacity(patch.num_hunks());
+    for hunk_idx in 0..patch.num_hunks() {
+        let (hunk, line_count) = patch.hunk(hunk_idx)?;
+        let mut lines = Vec::with_capacity(line_count);
+        for line_idx in 0..line_count {
+            let line = patch.line_in_hunk(hunk_idx, line_idx)?;
+            let line_type = match line.origin() {
+                '+' => DiffLineType::Addition,
+                '-' => DiffLineType::Deletion,
+                _ => DiffLineType::Context,
+            };
+            lines.push(DiffLine {
+                line_type,
+                old_line_number: line.old_lineno(),
+                new_line_number: line.new_lineno(),
+                content: String::from_utf8_lossy(line.content()).to_string(),
+            });
+        }
+        hunks.push(DiffHunk {
+            old_start: hunk.old_start(),
+            old_lines: hunk.old_lines(),
+            new_start: hunk.new_start(),
+            new_lines: hunk.new_lines(),
+            header: String::from_utf8_lossy(hunk.header()).to_string(),
+            lines,
+        });
+    }
+
+    Ok(line_changes_from_hunks(hunks))
+}
+
+/// Whether git would convert CRLF to LF when adding this content, following the `text`
+/// and `eol` attributes and falling back to core.autocrlf
+fn should_normalize_line_endings(repo: &Repository, file_path: &str, content: &str) -> bool {
+    let path = std::path::Path::new(file_path);
+    let attr = |name: &str| {
+        repo.get_attr(path, name, AttrCheckFlags::FILE_THEN_INDEX)
+            .ok()
+            .map(AttrValue::from_string)
+    };
+    // Like git's auto detection: NUL bytes mean binary
+    let looks_like_text = !content.contains('\0');
+
+    match attr("text") {
+        Some(AttrValue::True) => return true,
+        Some(AttrValue::False) => return false,
+        Some(AttrValue::String("auto")) => return looks_like_text,
+        _ => {}
+    }
+    if matches!(attr("eol"), Some(AttrValue::String(_))) {
+        return true;
+    }
+
+    let autocrlf = repo
+        .config()
+        .and_then(|config| config.get_string("core.autocrlf"))
+        .map(|value| value.to_lowercase())
+        .unwrap_or_default();
+    matches!(autocrlf.as_str(), "true" | "input" | "yes" | "on" | "1") && looks_like_text
+}
+
+/// Converts diff hunks into gutter markers: additions at their new line number and
+/// deletions at the position in the new file where the lines were removed
+fn line_changes_from_hunks(hunks: Vec<DiffHunk>) -> Vec<(u32, DiffLineType)> {
     let mut changes = Vec::new();
 
-    for hunk in file_diff.hunks {
+    for hunk in hunks {
         // Track the current line number in the new file
         let mut current_new_line = hunk.new_start;
 
@@ -181,17 +301,24 @@ pub fn get_line_changes(
         }
     }
 
-    // Store in cache
-    if let Ok(mut cache) = LINE_CHANGES_CACHE.lock() {
-        cache.put(cache_key, changes.clone());
-        log::debug!(
-            "Cached line changes for: {} ({} changes)",
-            file_path,
-            changes.len()
-        );
-    }
+    changes
+}
 
-    Ok(changes)
+/// Paths changed on the current branch relative to `base_ref`: the diff from the merge
+/// base of HEAD and `base_ref` to HEAD, like a pull request's file list. Deleted files are
+/// included, so callers that read the files should check they exist.
+pub fn get_paths_changed_since(repo: &Repository, base_ref: &str) -> Result<Vec<String>, GitError> {
+    let base = repo.revparse_single(base_ref)?.peel_to_commit()?;
+    let head = repo.head()?.peel_to_commit()?;
+    let merge_base = repo.merge_base(base.id(), head.id()).unwrap_or(base.id());
+    let base_tree = repo.find_commit(merge_base)?.tree()?;
+    let diff = repo.diff_tree_to_tree(Some(&base_tree), Some(&head.tree()?), None)?;
+
+    Ok(diff
+        .deltas()
+        .filter_map(|delta| delta.new_file().path())
+        .map(|path| path.to_string_lossy().to_string())
+        .collect())
 }
 
 /// Generates raw diff text for all changed files (working directory vs HEAD)
@@ -442,6 +569,72 @@ mod tests {
         assert_eq!(changes1.len(), changes2.len());
     }
 
+    fn assert_same_changes(a: &[(u32, DiffLineType)], b: &[(u32, DiffLineType)]) {
+        let simplify = |changes: &[(u32, DiffLineType)]| -> Vec<(u32, String)> {
+            changes
+                .iter()
+                .map(|(line, t)| (*line, format!("{:?}", t)))
+                .collect()
+        };
+        assert_eq!(simplify(a), simplify(b));
+    }
+
+    #[test]
+    fn test_line_changes_for_content_matches_on_disk() {
+        let temp_dir = create_temp_git_repo_with_commit();
+        let repo = Repository::open(temp_dir.path()).unwrap();
+        let content = "# Changed\nLine 2\nInserted\nLine 3\nTrailing\n";
+
+        let from_buffer = get_line_changes_for_content(&repo, "README.md", content).unwrap();
+        // The buffer diff does not read the working tree
+        assert_same_changes(
+            &get_line_changes_for_content(&repo, "README.md", "# Initial\nLine 2\nLine 3\n")
+                .unwrap(),
+            &[],
+        );
+
+        std::fs::write(temp_dir.path().join("README.md"), content).unwrap();
+        let on_disk = get_line_changes(&repo, "README.md").unwrap();
+        assert!(!from_buffer.is_empty());
+        assert_same_changes(&from_buffer, &on_disk);
+    }
+
+    #[test]
+    fn test_line_changes_for_content_new_file() {
+        let temp_dir = create_temp_git_repo_with_commit();
+        let repo = Repository::open(temp_dir.path()).unwrap();
+
+        let changes = get_line_changes_for_content(&repo, "src/new.rs", "a\nb\nc").unwrap();
+        let lines: Vec<u32> = changes.iter().map(|(line, _)| *line).collect();
+        assert_eq!(lines, vec![1, 2, 3]);
+        assert!(changes
+            .iter()
+            .all(|(_, t)| matches!(t, DiffLineType::Addition)));
+    }
+
+    #[test]
+    fn test_line_changes_for_content_crlf() {
+        let temp_dir = create_temp_git_repo_with_commit();
+        let repo = Repository::open(temp_dir.path()).unwrap();
+        let crlf = "# Initial\r\nLine 2\r\nLine 3 edited\r\n";
+
+        // Without autocrlf every CRLF line differs from HEAD
+        let raw = get_line_changes_for_content(&repo, "README.md", crlf).unwrap();
+        assert!(raw.len() > 2);
+
+        Command::new("git")
+            .args(["config", "core.autocrlf", "true"])
+            .current_dir(temp_dir.path())
+            .output()
+            .unwrap();
+        let repo = Repository::open(temp_dir.path()).unwrap();
+        let normalized = get_line_changes_for_content(&repo, "README.md", crlf).unwrap();
+        std::fs::write(temp_dir.path().join("README.md"), crlf).unwrap();
+        let on_disk = get_line_changes(&repo, "README.md").unwrap();
+        assert_same_changes(&normalized, &on_disk);
+        assert_eq!(normalized.len(), 2);
+    }
+
     #[test]
     fn test_file_diff_hunks() {
         let temp_dir = create_temp_git_repo_with_commit();
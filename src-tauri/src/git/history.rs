@@ -0,0 +1,354 @@
+// Per-file history for the "compare with version" view. Walks commits from HEAD and keeps
+// the ones that changed the file, optionally following it across renames like
+// `git log --follow`, and reads the file's content at any of those commits.
+
+use super::types::GitFileStatus;
+use git2::{Commit, DiffFindOptions, DiffOptions, Oid, Repository, Sort, Tree};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+const DEFAULT_HISTORY_LIMIT: usize = 50;
+
+/// Content larger than this is returned truncated
+const MAX_FILE_AT_COMMIT_BYTES: usize = 5 * 1024 * 1024;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileHistoryEntry {
+    pub commit_id: String,
+    pub short_id: String,
+    pub summary: String,
+    pub author_name: String,
+    pub author_email: String,
+    /// Commit time in seconds since the epoch
+    pub timestamp: i64,
+    /// Path of the file in this commit
+    pub path: String,
+    /// Path before this commit when the commit renamed the file
+    pub previous_path: Option<String>,
+    pub change_type: GitFileStatus,
+    pub additions: usize,
+    pub deletions: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileAtCommit {
+    pub commit_id: String,
+    pub path: String,
+    pub size: usize,
+    /// None for binary files
+    pub content: Option<String>,
+    pub is_binary: bool,
+    pub truncated: bool,
+}
+
+/// Path relative to the working directory with forward slashes, accepting absolute paths
+pub(super) fn relative_path(repo: &Repository, file_path: &str) -> String {
+    let path = Path::new(file_path);
+    let canonical = |p: &Path| p.canonicalize().unwrap_or_else(|_| p.to_path_buf());
+    let relative = match repo.workdir() {
+        Some(workdir) if path.is_absolute() => path
+            .strip_prefix(workdir)
+            .map(Path::to_path_buf)
+            .or_else(|_| {
+                canonical(path)
+                    .strip_prefix(canonical(workdir))
+                    .map(Path::to_path_buf)
+            })
+            .unwrap_or_else(|_| path.to_path_buf()),
+        _ => path.to_path_buf(),
+    };
+    relative.to_string_lossy().replace('\\', "/")
+}
+
+fn blob_id(tree: Option<&Tree>, path: &str) -> Option<Oid> {
+    tree?
+        .get_path(Path::new(path))
+        .ok()
+        .filter(|entry| entry.kind() == Some(git2::ObjectType::Blob))
+        .map(|entry| entry.id())
+}
+
+/// The path `path` had in `parent` when `commit` renamed it there
+fn renamed_from(
+    repo: &Repository,
+    parent: &Tree,
+    tree: &Tree,
+    path: &str,
+) -> Result<Option<String>, git2::Error> {
+    let mut diff = repo.diff_tree_to_tree(Some(parent), Some(tree), None)?;
+    diff.find_similar(Some(DiffFindOptions::new().renames(true)))?;
+    Ok(diff
+        .deltas()
+        .filter(|delta| delta.status() == git2::Delta::Renamed)
+        .find(|delta| delta.new_file().path() == Some(Path::new(path)))
+        .and_then(|delta| delta.old_file().path())
+        .map(|old| old.to_string_lossy().to_string()))
+}
+
+/// Lines added and removed in the file by this commit
+fn line_stats(
+    repo: &Repository,
+    parent: Option<&Tree>,
+    tree: &Tree,
+    path: &str,
+    previous_path: Option<&str>,
+) -> Result<(usize, usize), git2::Error> {
+    let mut options = DiffOptions::new();
+    options.pathspec(path).disable_pathspec_match(true);
+    if let Some(previous_path) = previous_path {
+        options.pathspec(previous_path);
+    }
+    let mut diff = repo.diff_tree_to_tree(parent, Some(tree), Some(&mut options))?;
+    if previous_path.is_some() {
+        diff.find_similar(Some(DiffFindOptions::new().renames(true)))?;
+    }
+    let stats = diff.stats()?;
+    Ok((stats.insertions(), stats.deletions()))
+}
+
+fn history_entry(
+    commit: &Commit,
+    path: &str,
+    previous_path: Option<String>,
+    change_type: GitFileStatus,
+    (additions, deletions): (usize, usize),
+) -> FileHistoryEntry {
+    let id = commit.id().to_string();
+    FileHistoryEntry {
+        short_id: id[..7].to_string(),
+        commit_id: id,
+        summary: commit.summary().unwrap_or("").to_string(),
+        author_name: commit.author().name().unwrap_or("").to_string(),
+        author_email: commit.author().email().unwrap_or("").to_string(),
+        timestamp: commit.time().seconds(),
+        path: path.to_string(),
+        previous_path,
+        change_type,
+        additions,
+        deletions,
+    }
+}
+
+/// Commits reachable from HEAD that changed the file, newest first. Merge commits count
+/// only when the file differs from every parent, as in `git log`. With `follow_renames`
+/// the walk continues under the old name past the commit that renamed the file.
+pub fn get_file_history(
+    repo: &Repository,
+    file_path: &str,
+    limit: Option<usize>,
+    follow_renames: bool,
+) -> Result<Vec<FileHistoryEntry>, git2::Error> {
+    let limit = limit.unwrap_or(DEFAULT_HISTORY_LIMIT);
+    let mut path = relative_path(repo, file_path);
+    let mut revwalk = repo.revwalk()?;
+    revwalk.set_sorting(Sort::TOPOLOGICAL | Sort::TIME)?;
+    revwalk.push_head()?;
+
+    let mut history = Vec::new();
+    for oid in revwalk {
+        if history.len() >= limit {
+            break;
+        }
+        let commit = repo.find_commit(oid?)?;
+        let tree = commit.tree()?;
+        let current = blob_id(Some(&tree), &path);
+        let parent_trees = commit
+            .parents()
+            .map(|parent| parent.tree())
+            .collect::<Result<Vec<_>, _>>()?;
+        let parent_blobs: Vec<Option<Oid>> = parent_trees
+            .iter()
+            .map(|parent| blob_id(Some(parent), &path))
+            .collect();
+        if parent_blobs.contains(&current) {
+            continue;
+        }
+        // Root commit: only relevant if it contains the file
+        if parent_blobs.is_empty() && current.is_none() {
+            continue;
+        }
+
+        let first_parent = parent_trees.first();
+        let was_present = parent_blobs.first().copied().flatten().is_some();
+        let (change_type, previous_path) = match (current.is_some(), was_present) {
+            (true, true) => (GitFileStatus::Modified, None),
+            (false, _) => (GitFileStatus::Deleted, None),
+            (true, false) => {
+                let renamed = match (follow_renames, first_parent) {
+                    (true, Some(parent)) => renamed_from(repo, parent, &tree, &path)?,
+                    _ => None,
+                };
+                match renamed {
+                    Some(old_path) => (GitFileStatus::Renamed, Some(old_path)),
+                    None => (GitFileStatus::Added, None),
+                }
+            }
+        };
+
+        let stats = line_stats(repo, first_parent, &tree, &path, previous_path.as_deref())?;
+        history.push(history_entry(
+            &commit,
+            &path,
+            previous_path.clone(),
+            change_type,
+            stats,
+        ));
+        if let Some(old_path) = previous_path {
+            path = old_path;
+        }
+    }
+    Ok(history)
+}
+
+/// The file's content at a commit. Binary files come back without content; large files
+/// are cut at MAX_FILE_AT_COMMIT_BYTES.
+pub fn get_file_at_commit(
+    repo: &Repository,
+    file_path: &str,
+    commit_id: &str,
+) -> Result<FileAtCommit, String> {
+    let path = relative_path(repo, file_path);
+    let commit = repo
+        .revparse_single(commit_id)
+        .and_then(|object| object.peel_to_commit())
+        .map_err(|e| format!("Commit not found: {}: {}", commit_id, e))?;
+    let entry = commit
+        .tree()
+        .and_then(|tree| tree.get_path(Path::new(&path)))
+        .map_err(|_| format!("{} does not exist at {}", path, commit_id))?;
+    let blob = repo
+        .find_blob(entry.id())
+        .map_err(|_| format!("{} is not a file at {}", path, commit_id))?;
+
+    let bytes = blob.content();
+    let is_binary = blob.is_binary();
+    let truncated = !is_binary && bytes.len() > MAX_FILE_AT_COMMIT_BYTES;
+    let content = (!is_binary).then(|| {
+        let mut end = bytes.len().min(MAX_FILE_AT_COMMIT_BYTES);
+        // Do not split a UTF-8 sequence at the cut
+        while end < bytes.len() && end > 0 && (bytes[end] & 0xC0) == 0x80 {
+            end -= 1;
+        }
+        String::from_utf8_lossy(&bytes[..end]).to_string()
+    });
+    Ok(FileAtCommit {
+        commit_id: commit.id().to_string(),
+        path,
+        size: bytes.len(),
+        content,
+        is_binary,
+        truncated,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::git;
+    use tempfile::TempDir;
+
+    fn commit_file(dir: &Path, path: &str, content: &str, message: &str) {
+        std::fs::write(dir.join(path), content).unwrap();
+        git(dir, &["add", "-A"]);
+        git(dir, &["commit", "-m", message]);
+    }
+
+    /// config.ts is created, modified twice, renamed to settings.ts, then modified again
+    fn create_history_repo() -> TempDir {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        git(dir, &["init", "-b", "main"]);
+        git(dir, &["config", "user.email", "test@test.com"]);
+        git(dir, &["config", "user.name", "Test User"]);
+        let base = "export const config = {\n  name: 'app',\n  port: 3000,\n  debug: false,\n};\n";
+        commit_file(dir, "config.ts", base, "Add config");
+        commit_file(dir, "README.md", "# App\n", "Add readme");
+        commit_file(
+            dir,
+            "config.ts",
+            &base.replace("3000", "8080"),
+            "Change port",
+        );
+        commit_file(
+            dir,
+            "config.ts",
+            &base.replace("3000", "8080").replace("false", "true"),
+            "Enable debug",
+        );
+        git(dir, &["mv", "config.ts", "settings.ts"]);
+        git(dir, &["commit", "-m", "Rename config"]);
+        commit_file(
+            dir,
+            "settings.ts",
+            &base
+                .replace("3000", "8080")
+                .replace("false", "true")
+                .replace("app", "talkcody"),
+            "Rename app",
+        );
+        temp_dir
+    }
+
+    #[test]
+    fn test_history_with_and_without_follow_renames() {
+        let temp_dir = create_history_repo();
+        let repo = Repository::open(temp_dir.path()).unwrap();
+
+        let history = get_file_history(&repo, "settings.ts", None, false).unwrap();
+        let summaries: Vec<&str> = history.iter().map(|e| e.summary.as_str()).collect();
+        assert_eq!(summaries, vec!["Rename app", "Rename config"]);
+        assert!(matches!(history[1].change_type, GitFileStatus::Added));
+
+        let absolute = temp_dir.path().join("settings.ts");
+        let history = get_file_history(&repo, absolute.to_str().unwrap(), None, true).unwrap();
+        let summaries: Vec<&str> = history.iter().map(|e| e.summary.as_str()).collect();
+        assert_eq!(
+            summaries,
+            vec![
+                "Rename app",
+                "Rename config",
+                "Enable debug",
+                "Change port",
+                "Add config"
+            ]
+        );
+        assert!(matches!(history[1].change_type, GitFileStatus::Renamed));
+        assert_eq!(history[1].previous_path.as_deref(), Some("config.ts"));
+        assert_eq!((history[1].additions, history[1].deletions), (0, 0));
+        assert_eq!(history[2].path, "config.ts");
+        assert_eq!((history[3].additions, history[3].deletions), (1, 1));
+        assert_eq!((history[4].additions, history[4].deletions), (5, 0));
+
+        let limited = get_file_history(&repo, "settings.ts", Some(3), true).unwrap();
+        assert_eq!(limited.len(), 3);
+    }
+
+    #[test]
+    fn test_file_at_commit() {
+        let temp_dir = create_history_repo();
+        let repo = Repository::open(temp_dir.path()).unwrap();
+        let history = get_file_history(&repo, "settings.ts", None, true).unwrap();
+
+        let change_port = &history[3];
+        let version = get_file_at_commit(&repo, &change_port.path, &change_port.commit_id).unwrap();
+        let content = version.content.unwrap();
+        assert!(content.contains("port: 8080") && content.contains("debug: false"));
+        assert!(!version.is_binary && !version.truncated);
+
+        let err = get_file_at_commit(&repo, "settings.ts", &change_port.short_id).unwrap_err();
+        assert!(err.contains("does not exist"), "{}", err);
+
+        std::fs::write(
+            temp_dir.path().join("logo.png"),
+            b"\x89PNG\r\n\x1a\n\x00\x00",
+        )
+        .unwrap();
+        git(temp_dir.path(), &["add", "logo.png"]);
+        git(temp_dir.path(), &["commit", "-m", "Add logo"]);
+        let binary = get_file_at_commit(&repo, "logo.png", "HEAD").unwrap();
+        assert!(binary.is_binary);
+        assert_eq!((binary.content, binary.size), (None, 10));
+    }
+}
@@ -0,0 +1,173 @@
+// Git hooks. git2 never runs hooks, so commits made through it skip husky, lint-staged and
+// friends. This module finds the hooks a repository has configured and runs the commit
+// hooks through the script executor when the caller asks for them.
+
+use super::linked_worktree::common_dir;
+use crate::script_executor::{ScriptExecutionRequest, ScriptExecutor};
+use git2::Repository;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Hooks git runs around `git commit`, in the order it runs them
+pub const COMMIT_HOOKS: [&str; 4] = [
+    "pre-commit",
+    "prepare-commit-msg",
+    "commit-msg",
+    "post-commit",
+];
+
+/// lint-staged on a large change set can legitimately take minutes
+const HOOK_TIMEOUT_MS: u64 = 5 * 60 * 1000;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct GitHookInfo {
+    pub name: String,
+    pub path: String,
+    /// git ignores hooks without the executable bit
+    pub executable: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitHooksInfo {
+    /// Directory hooks are read from
+    pub hooks_dir: String,
+    /// Raw `core.hooksPath` value when it overrides `.git/hooks` (e.g. ".husky")
+    pub hooks_path_override: Option<String>,
+    pub hooks: Vec<GitHookInfo>,
+}
+
+/// The hooks directory: `core.hooksPath` (relative paths resolve against the working
+/// directory, as git does) or `hooks` in the common git dir, shared by all worktrees
+pub fn hooks_dir(repo: &Repository) -> (PathBuf, Option<String>) {
+    let hooks_path = repo
+        .config()
+        .and_then(|config| config.get_string("core.hooksPath"))
+        .ok()
+        .filter(|path| !path.trim().is_empty());
+    match hooks_path {
+        Some(hooks_path) => {
+            let expanded = match hooks_path.strip_prefix("~/") {
+                Some(rest) => dirs::home_dir().unwrap_or_default().join(rest),
+                None => PathBuf::from(&hooks_path),
+            };
+            let dir = if expanded.is_absolute() {
+                expanded
+            } else {
+                repo.workdir().unwrap_or_else(|| repo.path()).join(expanded)
+            };
+            (dir, Some(hooks_path))
+        }
+        None => (common_dir(repo).join("hooks"), None),
+    }
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path)
+        .map(|m| m.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+fn is_executable(_path: &Path) -> bool {
+    // Git for Windows runs any hook file regardless of permissions
+    true
+}
+
+/// List the hook scripts in the repository's hooks directory, skipping `.sample` files
+pub fn get_hooks(repo: &Repository) -> Result<GitHooksInfo, String> {
+    let (dir, hooks_path_override) = hooks_dir(repo);
+    let mut hooks = Vec::new();
+    if let Ok(entries) = std::fs::read_dir(&dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().to_string();
+            if !path.is_file() || name.starts_with('.') || name.ends_with(".sample") {
+                continue;
+            }
+            hooks.push(GitHookInfo {
+                name,
+                executable: is_executable(&path),
+                path: path.to_string_lossy().to_string(),
+            });
+        }
+    }
+    hooks.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(GitHooksInfo {
+        hooks_dir: dir.to_string_lossy().to_string(),
+        hooks_path_override,
+        hooks,
+    })
+}
+
+/// Commit hooks git would have run: present and executable
+pub fn active_commit_hooks(info: &GitHooksInfo) -> Vec<String> {
+    COMMIT_HOOKS
+        .iter()
+        .filter(|name| info.hooks.iter().any(|h| h.name == **name && h.executable))
+        .map(|name| name.to_string())
+        .collect()
+}
+
+/// Interpreter for a hook from its shebang; shell scripts (husky's included) use bash
+fn script_type_for(path: &Path) -> &'static str {
+    let first_line = std::fs::read_to_string(path)
+        .ok()
+        .and_then(|content| content.lines().next().map(str::to_string))
+        .unwrap_or_default();
+    if !first_line.starts_with("#!") {
+        "bash"
+    } else if first_line.contains("node") {
+        "nodejs"
+    } else if first_line.contains("python") {
+        "python"
+    } else {
+        "bash"
+    }
+}
+
+/// Run one hook from the working directory. Returns Ok(false) when the hook does not
+/// exist, and an error carrying the hook's output when it exits non-zero or times out.
+pub async fn run_hook(
+    info: &GitHooksInfo,
+    name: &str,
+    args: &[String],
+    workdir: &Path,
+    environment: HashMap<String, String>,
+) -> Result<bool, String> {
+    let Some(hook) = info.hooks.iter().find(|h| h.name == name && h.executable) else {
+        return Ok(false);
+    };
+    log::info!("Running {} hook {}", name, hook.path);
+
+    // Hooks run where git would run them, with the user's login-shell PATH from startup
+    let result = ScriptExecutor::execute(ScriptExecutionRequest {
+        script_path: hook.path.clone(),
+        script_type: script_type_for(Path::new(&hook.path)).to_string(),
+        args: args.to_vec(),
+        working_dir: Some(workdir.to_string_lossy().to_string()),
+        timeout_ms: Some(HOOK_TIMEOUT_MS),
+        environment: Some(environment),
+        runtime_constraint: None,
+        skill_dir: None,
+    })
+    .await?;
+
+    if result.success {
+        return Ok(true);
+    }
+    let output = format!("{}{}", result.stdout, result.stderr);
+    let reason = result
+        .error
+        .unwrap_or_else(|| format!("exited with code {}", result.exit_code));
+    Err(format!(
+        "{} hook failed ({}):\n{}",
+        name,
+        reason,
+        output.trim_end()
+    ))
+}
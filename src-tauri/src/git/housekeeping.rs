@@ -0,0 +1,430 @@
+// Repository housekeeping. Months of commits, fetches and stashes leave loose objects and
+// many packfiles behind, which slows every libgit2 operation. This module reports how the
+// git dir's size breaks down and runs `git gc` through the user's shell (libgit2 has no
+// gc), turning the progress git writes to stderr into activity updates.
+
+use super::linked_worktree::common_dir;
+use crate::activity::{self, ActivityKind, ActivityOutcome};
+use crate::cancellation::{self, CancellationToken};
+use crate::process_info::{self, KillSignal};
+use git2::Repository;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+use tokio::io::AsyncReadExt;
+
+/// How often a running gc checks its cancellation token
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Bytes of gc output kept for the error message of a failed run
+const OUTPUT_TAIL_BYTES: usize = 4 * 1024;
+
+lazy_static::lazy_static! {
+    // "Counting objects:  45% (450/1000)" or "Enumerating objects: 1234, done."
+    static ref PROGRESS_LINE: Regex =
+        Regex::new(r"^([A-Za-z][A-Za-z ]*):\s+(?:\d+%\s+\((\d+)/(\d+)\)|(\d+))").unwrap();
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RepoSizeReport {
+    /// The common git dir; linked worktrees share it with the main repository
+    pub git_dir: String,
+    pub total_bytes: u64,
+    /// Everything under objects/, including pack indexes
+    pub objects_bytes: u64,
+    pub loose_objects: u64,
+    pub loose_objects_bytes: u64,
+    pub packfiles: u64,
+    pub packfile_bytes: u64,
+    /// refs/ plus packed-refs
+    pub refs_bytes: u64,
+    /// Linked worktree entries under worktrees/
+    pub worktrees: u64,
+    pub worktrees_bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HousekeepingResult {
+    pub aggressive: bool,
+    pub cancelled: bool,
+    pub before: RepoSizeReport,
+    pub after: RepoSizeReport,
+    /// Bytes the git dir shrank by; 0 when it grew
+    pub freed_bytes: u64,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct GcProgress {
+    pub phase: String,
+    pub done: u64,
+    pub total: Option<u64>,
+}
+
+/// Parse one progress update of git's stderr, e.g. "Compressing objects:  50% (5/10)"
+pub(crate) fn parse_progress(line: &str) -> Option<GcProgress> {
+    let captures = PROGRESS_LINE.captures(line.trim())?;
+    let number = |index: usize| captures.get(index)?.as_str().parse::<u64>().ok();
+    let (done, total) = match number(2) {
+        Some(done) => (done, number(3)),
+        None => (number(4)?, None),
+    };
+    Some(GcProgress {
+        phase: captures[1].trim().to_string(),
+        done,
+        total,
+    })
+}
+
+/// Size of a file, or of everything below a directory. Symlinks are not followed.
+fn path_size(path: &Path) -> u64 {
+    let Ok(metadata) = fs::symlink_metadata(path) else {
+        return 0;
+    };
+    if !metadata.is_dir() {
+        return metadata.len();
+    }
+    fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| path_size(&entry.path()))
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+fn is_loose_object_dir(name: &str) -> bool {
+    name.len() == 2 && name.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// Break down the size of a git dir
+pub fn size_report(git_dir: &Path) -> RepoSizeReport {
+    let objects = git_dir.join("objects");
+    let mut report = RepoSizeReport {
+        git_dir: git_dir.to_string_lossy().to_string(),
+        total_bytes: path_size(git_dir),
+        objects_bytes: path_size(&objects),
+        refs_bytes: path_size(&git_dir.join("refs")) + path_size(&git_dir.join("packed-refs")),
+        worktrees_bytes: path_size(&git_dir.join("worktrees")),
+        ..Default::default()
+    };
+
+    for entry in fs::read_dir(&objects).into_iter().flatten().flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if !is_loose_object_dir(&name) {
+            continue;
+        }
+        for object in fs::read_dir(entry.path()).into_iter().flatten().flatten() {
+            report.loose_objects += 1;
+            report.loose_objects_bytes += object.metadata().map(|m| m.len()).unwrap_or(0);
+        }
+    }
+    for entry in fs::read_dir(objects.join("pack"))
+        .into_iter()
+        .flatten()
+        .flatten()
+    {
+        let path = entry.path();
+        if path.extension().is_some_and(|ext| ext == "pack") {
+            report.packfiles += 1;
+            report.packfile_bytes += entry.metadata().map(|m| m.len()).unwrap_or(0);
+        }
+    }
+    report.worktrees = fs::read_dir(git_dir.join("worktrees"))
+        .map(|entries| entries.flatten().count() as u64)
+        .unwrap_or(0);
+    report
+}
+
+pub fn repo_size_report(repo: &Repository) -> RepoSizeReport {
+    size_report(&common_dir(repo))
+}
+
+/// The index.lock another git process holds in the repository, if any. A linked worktree
+/// has its own index, so both its git dir and the common dir are checked.
+pub fn index_lock(repo: &Repository) -> Option<PathBuf> {
+    [repo.path().to_path_buf(), common_dir(repo)]
+        .into_iter()
+        .map(|dir| dir.join("index.lock"))
+        .find(|lock| lock.exists())
+}
+
+/// Keep the end of the output for error messages
+fn push_tail(tail: &mut String, line: &str) {
+    tail.push_str(line);
+    tail.push('\n');
+    if tail.len() > OUTPUT_TAIL_BYTES {
+        let mut cut = tail.len() - OUTPUT_TAIL_BYTES;
+        while !tail.is_char_boundary(cut) {
+            cut += 1;
+        }
+        tail.drain(..cut);
+    }
+}
+
+/// Run `git gc` and report progress to the activity. Returns the exit code, or None when
+/// cancelled.
+async fn run_gc(
+    workdir: &Path,
+    aggressive: bool,
+    activity_id: &str,
+    token: Option<&CancellationToken>,
+) -> Result<Option<i32>, String> {
+    let command = if aggressive {
+        "git gc --aggressive --prune=now"
+    } else {
+        "git gc"
+    };
+    let mut cmd = crate::user_shell_command(command);
+    cmd.current_dir(workdir)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped());
+    let mut child = cmd
+        .spawn()
+        .map_err(|e| format!("Failed to spawn git gc: {}", e))?;
+    let pid = child.id();
+    if let Some(pid) = pid {
+        process_info::register_spawned_pid(pid);
+    }
+
+    // Progress lines are rewritten in place with \r, so split on both line endings
+    let mut stderr = child.stderr.take();
+    let mut buffer = [0u8; 4096];
+    let mut pending: Vec<u8> = Vec::new();
+    let mut tail = String::new();
+    let mut handle_line = |line: &[u8]| {
+        let line = String::from_utf8_lossy(line);
+        if line.trim().is_empty() {
+            return;
+        }
+        if let Some(progress) = parse_progress(&line) {
+            activity::activity_progress(
+                activity_id,
+                progress.done,
+                progress.total,
+                Some(progress.phase),
+            );
+        } else {
+            push_tail(&mut tail, line.trim_end());
+        }
+    };
+
+    let mut cancelled = false;
+    let status = loop {
+        tokio::select! {
+            read = async {
+                match stderr.as_mut() {
+                    Some(stderr) => stderr.read(&mut buffer).await,
+                    None => std::future::pending().await,
+                }
+            } => match read {
+                Ok(0) | Err(_) => stderr = None,
+                Ok(n) => {
+                    pending.extend_from_slice(&buffer[..n]);
+                    while let Some(end) = pending.iter().position(|&b| b == b'\r' || b == b'\n') {
+                        let line: Vec<u8> = pending.drain(..=end).collect();
+                        handle_line(&line[..line.len() - 1]);
+                    }
+                }
+            },
+            status = child.wait() => break status,
+            _ = tokio::time::sleep(CANCEL_POLL_INTERVAL), if token.is_some() && !cancelled => {
+                if token.is_some_and(|t| t.is_cancelled()) {
+                    cancelled = true;
+                    log::info!("Cancelling git gc in {} ({:?})", workdir.display(), pid);
+                    if let Some(pid) = pid {
+                        let killed = tokio::task::spawn_blocking(move || {
+                            process_info::kill_tree(pid, KillSignal::Kill)
+                        })
+                        .await;
+                        if let Ok(Err(e)) = killed {
+                            log::warn!("Failed to kill git gc process tree {}: {}", pid, e);
+                        }
+                    }
+                    let _ = child.start_kill();
+                }
+            }
+        }
+    };
+    if let Some(mut stderr) = stderr {
+        let mut rest = Vec::new();
+        let _ = stderr.read_to_end(&mut rest).await;
+        pending.extend(rest);
+    }
+    for line in pending.split(|&b| b == b'\r' || b == b'\n') {
+        handle_line(line);
+    }
+    if let Some(pid) = pid {
+        process_info::unregister_spawned_pid(pid);
+    }
+
+    let status = status.map_err(|e| format!("Failed to wait for git gc: {}", e))?;
+    if cancelled {
+        return Ok(None);
+    }
+    match status.code() {
+        Some(0) => Ok(Some(0)),
+        code => Err(format!(
+            "git gc failed (exit code {:?}): {}",
+            code,
+            tail.trim_end()
+        )),
+    }
+}
+
+/// Run `git gc` on the repository containing `repo_path`, refusing while another git
+/// process holds the index lock. With an `operation_id` the run can be stopped through
+/// activity_cancel.
+pub async fn run_housekeeping(
+    repo_path: &str,
+    aggressive: bool,
+    operation_id: Option<String>,
+) -> Result<HousekeepingResult, String> {
+    let (workdir, before) = {
+        let repo = Repository::discover(repo_path)
+            .map_err(|e| format!("Failed to open repository: {}", e))?;
+        if let Some(lock) = index_lock(&repo) {
+            return Err(format!(
+                "Another git process is running in this repository ({} exists)",
+                lock.display()
+            ));
+        }
+        let workdir = repo.workdir().unwrap_or_else(|| repo.path()).to_path_buf();
+        (workdir, repo_size_report(&repo))
+    };
+    log::info!(
+        "Running {}git gc in {}",
+        if aggressive { "aggressive " } else { "" },
+        workdir.display()
+    );
+
+    let activity_id = activity::activity_start(
+        ActivityKind::Git,
+        format!("Cleaning up {}", workdir.display()),
+        false,
+    );
+    let token = operation_id.as_deref().map(cancellation::register);
+    let start = Instant::now();
+    let result = run_gc(&workdir, aggressive, &activity_id, token.as_ref()).await;
+    if let Some(ref operation_id) = operation_id {
+        cancellation::unregister(operation_id);
+    }
+    let outcome = match result {
+        Ok(None) => ActivityOutcome::Cancelled,
+        _ => ActivityOutcome::from_result(&result),
+    };
+    activity::activity_finish(&activity_id, outcome);
+    let exit_code = result?;
+
+    let after = size_report(Path::new(&before.git_dir));
+    let result = HousekeepingResult {
+        aggressive,
+        cancelled: exit_code.is_none(),
+        freed_bytes: before.total_bytes.saturating_sub(after.total_bytes),
+        before,
+        after,
+        duration_ms: start.elapsed().as_millis() as u64,
+    };
+    log::info!(
+        "git gc in {} freed {} bytes in {}ms",
+        workdir.display(),
+        result.freed_bytes,
+        result.duration_ms
+    );
+    Ok(result)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::test_support::git;
+    use tempfile::TempDir;
+
+    fn create_test_repo() -> TempDir {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        git(dir, &["init", "-b", "main"]);
+        git(dir, &["config", "user.email", "test@test.com"]);
+        git(dir, &["config", "user.name", "Test User"]);
+        for i in 0..3 {
+            fs::write(
+                dir.join(format!("file{}.txt", i)),
+                format!("content {}\n", i),
+            )
+            .unwrap();
+            git(dir, &["add", "-A"]);
+            git(dir, &["commit", "-m", &format!("Commit {}", i)]);
+        }
+        temp_dir
+    }
+
+    #[test]
+    fn test_parse_progress() {
+        assert_eq!(
+            parse_progress("Counting objects:  45% (450/1000)"),
+            Some(GcProgress {
+                phase: "Counting objects".to_string(),
+                done: 450,
+                total: Some(1000),
+            })
+        );
+        assert_eq!(
+            parse_progress("Enumerating objects: 1234, done."),
+            Some(GcProgress {
+                phase: "Enumerating objects".to_string(),
+                done: 1234,
+                total: None,
+            })
+        );
+        assert_eq!(
+            parse_progress("Total 9 (delta 0), reused 0 (delta 0), pack-reused 0"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_size_report_counts_loose_objects_and_packs() {
+        let temp_dir = create_test_repo();
+        let repo = Repository::open(temp_dir.path()).unwrap();
+
+        // Three commits of one blob, one tree and one commit each
+        let loose = repo_size_report(&repo);
+        assert_eq!(loose.loose_objects, 9);
+        assert!(loose.loose_objects_bytes > 0);
+        assert_eq!(loose.packfiles, 0);
+        assert!(loose.refs_bytes > 0);
+        assert_eq!(loose.worktrees, 0);
+        assert!(loose.total_bytes >= loose.objects_bytes + loose.refs_bytes);
+
+        git(temp_dir.path(), &["gc", "--quiet", "--prune=now"]);
+        let packed = repo_size_report(&repo);
+        assert_eq!(packed.loose_objects, 0);
+        assert_eq!(packed.packfiles, 1);
+        assert!(packed.packfile_bytes > 0);
+    }
+
+    #[tokio::test]
+    async fn test_housekeeping_refuses_while_index_is_locked() {
+        let temp_dir = create_test_repo();
+        let repo = Repository::open(temp_dir.path()).unwrap();
+        assert_eq!(index_lock(&repo), None);
+
+        let lock = temp_dir.path().join(".git").join("index.lock");
+        fs::write(&lock, "").unwrap();
+        assert_eq!(index_lock(&repo), Some(repo.path().join("index.lock")));
+
+        let error = run_housekeeping(temp_dir.path().to_str().unwrap(), false, None)
+            .await
+            .unwrap_err();
+        assert!(error.contains("index.lock"), "{}", error);
+        // Nothing was packed
+        assert_eq!(repo_size_report(&repo).packfiles, 0);
+    }
+}
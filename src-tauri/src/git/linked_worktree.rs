@@ -0,0 +1,444 @@
+// Linked worktrees created by the user (`git worktree add`), as opposed to the
+// TalkCody-managed task pool in worktree.rs. Everything goes through git2's worktree API.
+
+use git2::{
+    BranchType, Repository, Status, StatusOptions, WorktreeAddOptions, WorktreeLockStatus,
+    WorktreePruneOptions,
+};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A working tree of a repository: the main one or a linked one
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LinkedWorktreeInfo {
+    /// Worktree name under .git/worktrees (None for the main worktree)
+    pub name: Option<String>,
+    /// Absolute path to the working directory
+    pub path: String,
+    /// Checked-out branch, None when HEAD is detached
+    pub branch: Option<String>,
+    /// HEAD commit id (None on an unborn branch)
+    pub head: Option<String>,
+    pub detached: bool,
+    pub is_main: bool,
+    pub locked: bool,
+    pub lock_reason: Option<String>,
+    /// The working directory is missing, so `git worktree prune` would remove the entry
+    pub prunable: bool,
+}
+
+/// The main repository's git dir. A linked worktree's git dir (`.git/worktrees/<name>`)
+/// holds a `commondir` file pointing back at it, usually as `../..`.
+pub(crate) fn common_dir(repo: &Repository) -> PathBuf {
+    let git_dir = repo.path();
+    match fs::read_to_string(git_dir.join("commondir")) {
+        Ok(contents) => {
+            let dir = git_dir.join(contents.trim());
+            dir.canonicalize().unwrap_or(dir)
+        }
+        Err(_) => git_dir.to_path_buf(),
+    }
+}
+
+/// Open the main repository that owns `repo_path`, which may itself be a linked worktree
+fn open_main_repository(repo_path: &str) -> Result<Repository, String> {
+    let repo =
+        Repository::discover(repo_path).map_err(|e| format!("Failed to open repository: {}", e))?;
+    if !repo.is_worktree() {
+        return Ok(repo);
+    }
+    Repository::open(common_dir(&repo))
+        .map_err(|e| format!("Failed to open main repository: {}", e))
+}
+
+fn path_to_string(path: &Path) -> String {
+    let path = path.to_string_lossy();
+    let trimmed = path.trim_end_matches(['/', '\\']);
+    if trimmed.is_empty() {
+        path.to_string()
+    } else {
+        trimmed.to_string()
+    }
+}
+
+fn same_path(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// git writes the lock reason with a trailing newline; an empty reason means none was given
+fn lock_reason_text(reason: Option<String>) -> Option<String> {
+    reason
+        .map(|r| r.trim().to_string())
+        .filter(|r| !r.is_empty())
+}
+
+/// (branch, head commit, detached) for a repository's HEAD
+fn head_state(repo: &Repository) -> (Option<String>, Option<String>, bool) {
+    let detached = repo.head_detached().unwrap_or(false);
+    match repo.head() {
+        Ok(head) => {
+            let branch = if head.is_branch() {
+                head.shorthand().map(|s| s.to_string())
+            } else {
+                None
+            };
+            (branch, head.target().map(|oid| oid.to_string()), detached)
+        }
+        // Unborn branch: HEAD is a symbolic ref to a branch with no commits yet
+        Err(_) => {
+            let branch = repo
+                .find_reference("HEAD")
+                .ok()
+                .and_then(|r| r.symbolic_target().map(|s| s.to_string()))
+                .map(|target| target.trim_start_matches("refs/heads/").to_string());
+            (branch, None, false)
+        }
+    }
+}
+
+fn main_worktree_info(repo: &Repository) -> Option<LinkedWorktreeInfo> {
+    let workdir = repo.workdir()?;
+    let (branch, head, detached) = head_state(repo);
+    Some(LinkedWorktreeInfo {
+        name: None,
+        path: path_to_string(workdir),
+        branch,
+        head,
+        detached,
+        is_main: true,
+        locked: false,
+        lock_reason: None,
+        prunable: false,
+    })
+}
+
+fn linked_worktree_info(repo: &Repository, name: &str) -> Result<LinkedWorktreeInfo, String> {
+    let worktree = repo
+        .find_worktree(name)
+        .map_err(|e| format!("Failed to find worktree '{}': {}", name, e))?;
+    let (locked, lock_reason) = match worktree.is_locked() {
+        Ok(WorktreeLockStatus::Locked(reason)) => (true, lock_reason_text(reason)),
+        _ => (false, None),
+    };
+    let prunable = worktree.validate().is_err();
+    let (branch, head, detached) = if prunable {
+        (None, None, false)
+    } else {
+        Repository::open_from_worktree(&worktree)
+            .map(|wt_repo| head_state(&wt_repo))
+            .unwrap_or((None, None, false))
+    };
+    Ok(LinkedWorktreeInfo {
+        name: Some(name.to_string()),
+        path: path_to_string(worktree.path()),
+        branch,
+        head,
+        detached,
+        is_main: false,
+        locked,
+        lock_reason,
+        prunable,
+    })
+}
+
+/// List the main worktree (unless the repository is bare) followed by all linked worktrees
+pub fn list_linked_worktrees(repo_path: &str) -> Result<Vec<LinkedWorktreeInfo>, String> {
+    let repo = open_main_repository(repo_path)?;
+    let mut worktrees: Vec<LinkedWorktreeInfo> = main_worktree_info(&repo).into_iter().collect();
+
+    let names = repo
+        .worktrees()
+        .map_err(|e| format!("Failed to list worktrees: {}", e))?;
+    for name in names.iter().flatten() {
+        match linked_worktree_info(&repo, name) {
+            Ok(info) => worktrees.push(info),
+            Err(e) => log::warn!("Skipping worktree '{}': {}", name, e),
+        }
+    }
+    Ok(worktrees)
+}
+
+/// Pick a worktree name from the directory name that isn't taken yet
+fn unique_worktree_name(repo: &Repository, new_path: &Path) -> Result<String, String> {
+    let base: String = new_path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .ok_or_else(|| format!("Invalid worktree path: {}", new_path.display()))?
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    let existing: Vec<String> = repo
+        .worktrees()
+        .map_err(|e| format!("Failed to list worktrees: {}", e))?
+        .iter()
+        .flatten()
+        .map(|s| s.to_string())
+        .collect();
+
+    let mut name = base.clone();
+    let mut suffix = 1;
+    while existing.contains(&name) {
+        name = format!("{}-{}", base, suffix);
+        suffix += 1;
+    }
+    Ok(name)
+}
+
+/// Create a linked worktree at `new_path` with `branch` checked out. With `create_branch`
+/// the branch is created from the current HEAD; otherwise it must already exist and not be
+/// checked out in another worktree.
+pub fn add_linked_worktree(
+    repo_path: &str,
+    new_path: &str,
+    branch: &str,
+    create_branch: bool,
+) -> Result<LinkedWorktreeInfo, String> {
+    let repo = open_main_repository(repo_path)?;
+    let target = PathBuf::from(new_path);
+
+    if target.exists()
+        && target
+            .read_dir()
+            .map(|mut entries| entries.next().is_some())
+            .unwrap_or(true)
+    {
+        return Err(format!(
+            "Path already exists and is not empty: {}",
+            new_path
+        ));
+    }
+
+    let branch_ref = if create_branch {
+        let head_commit = repo
+            .head()
+            .and_then(|head| head.peel_to_commit())
+            .map_err(|e| format!("Failed to resolve HEAD: {}", e))?;
+        repo.branch(branch, &head_commit, false)
+            .map_err(|e| format!("Failed to create branch '{}': {}", branch, e))?
+    } else {
+        let existing = repo
+            .find_branch(branch, BranchType::Local)
+            .map_err(|e| format!("Branch '{}' not found: {}", branch, e))?;
+        if let Some(other) = list_linked_worktrees(repo_path)?
+            .into_iter()
+            .find(|wt| wt.branch.as_deref() == Some(branch))
+        {
+            return Err(format!(
+                "Branch '{}' is already checked out at {}",
+                branch, other.path
+            ));
+        }
+        existing
+    };
+
+    let name = unique_worktree_name(&repo, &target)?;
+    let reference = branch_ref.into_reference();
+    let mut opts = WorktreeAddOptions::new();
+    opts.reference(Some(&reference));
+    repo.worktree(&name, &target, Some(&opts))
+        .map_err(|e| format!("Failed to add worktree: {}", e))?;
+
+    log::info!(
+        "Added worktree '{}' at {} on branch {}",
+        name,
+        new_path,
+        branch
+    );
+    linked_worktree_info(&repo, &name)
+}
+
+fn has_uncommitted_changes(repo: &Repository) -> Result<bool, String> {
+    let mut opts = StatusOptions::new();
+    opts.include_untracked(true).include_ignored(false);
+    let statuses = repo
+        .statuses(Some(&mut opts))
+        .map_err(|e| format!("Failed to get worktree status: {}", e))?;
+    Ok(statuses
+        .iter()
+        .any(|entry| entry.status() != Status::CURRENT))
+}
+
+/// Remove a linked worktree and its working directory. Without `force`, locked worktrees
+/// and worktrees with uncommitted changes are refused.
+pub fn remove_linked_worktree(
+    repo_path: &str,
+    worktree_path: &str,
+    force: bool,
+) -> Result<(), String> {
+    let repo = open_main_repository(repo_path)?;
+    let target = Path::new(worktree_path);
+
+    if repo.workdir().is_some_and(|main| same_path(main, target)) {
+        return Err("Cannot remove the main worktree".to_string());
+    }
+
+    let names = repo
+        .worktrees()
+        .map_err(|e| format!("Failed to list worktrees: {}", e))?;
+    let worktree = names
+        .iter()
+        .flatten()
+        .filter_map(|name| repo.find_worktree(name).ok())
+        .find(|wt| same_path(wt.path(), target))
+        .ok_or_else(|| format!("No linked worktree at {}", worktree_path))?;
+
+    if !force {
+        if let Ok(WorktreeLockStatus::Locked(reason)) = worktree.is_locked() {
+            return Err(match lock_reason_text(reason) {
+                Some(reason) => format!("Worktree is locked: {}", reason),
+                None => "Worktree is locked".to_string(),
+            });
+        }
+        if worktree.validate().is_ok() {
+            let wt_repo = Repository::open_from_worktree(&worktree)
+                .map_err(|e| format!("Failed to open worktree: {}", e))?;
+            if has_uncommitted_changes(&wt_repo)? {
+                return Err("Worktree has uncommitted changes".to_string());
+            }
+        }
+    }
+
+    let mut opts = WorktreePruneOptions::new();
+    opts.valid(true).working_tree(true).locked(force);
+    worktree
+        .prune(Some(&mut opts))
+        .map_err(|e| format!("Failed to remove worktree: {}", e))?;
+
+    log::info!("Removed worktree at {}", worktree_path);
+    Ok(())
+}
+
+/// If `path` is inside a linked worktree, the root of the main worktree it belongs to
+pub fn main_worktree_root(path: &str) -> Option<String> {
+    let repo = Repository::discover(path).ok()?;
+    if !repo.is_worktree() {
+        return None;
+    }
+    let main = Repository::open(common_dir(&repo)).ok()?;
+    main.workdir().map(path_to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::{diff, repository, status};
+    use crate::test_support::git;
+    use tempfile::TempDir;
+
+    fn create_test_repo() -> TempDir {
+        let temp_dir = TempDir::new().unwrap();
+        let repo_dir = temp_dir.path().join("main");
+        std::fs::create_dir(&repo_dir).unwrap();
+        git(&repo_dir, &["init", "-b", "main"]);
+        git(&repo_dir, &["config", "user.email", "test@test.com"]);
+        git(&repo_dir, &["config", "user.name", "Test User"]);
+        std::fs::write(repo_dir.join("README.md"), "# Test\nline two\n").unwrap();
+        git(&repo_dir, &["add", "."]);
+        git(&repo_dir, &["commit", "-m", "Initial commit"]);
+        temp_dir
+    }
+
+    #[test]
+    fn test_add_list_and_remove_linked_worktrees() {
+        let temp_dir = create_test_repo();
+        let main = temp_dir.path().join("main");
+        let main_str = main.to_str().unwrap();
+        let feature = temp_dir.path().join("feature wt");
+        let feature_str = feature.to_str().unwrap();
+
+        let added = add_linked_worktree(main_str, feature_str, "feature", true).unwrap();
+        assert_eq!(added.branch.as_deref(), Some("feature"));
+        assert!(feature.join("README.md").exists());
+        // The branch can't be checked out twice
+        let other = temp_dir.path().join("other");
+        assert!(add_linked_worktree(main_str, other.to_str().unwrap(), "feature", false).is_err());
+
+        git(&main, &["worktree", "add", "--detach", "../detached"]);
+        git(
+            &main,
+            &[
+                "worktree",
+                "lock",
+                "--reason",
+                "on usb drive",
+                "../detached",
+            ],
+        );
+
+        // Listing from inside a linked worktree gives the same view
+        let worktrees = list_linked_worktrees(feature_str).unwrap();
+        assert_eq!(worktrees.len(), 3);
+        assert!(worktrees[0].is_main);
+        assert_eq!(worktrees[0].branch.as_deref(), Some("main"));
+        let detached = worktrees.iter().find(|w| w.detached).unwrap();
+        assert_eq!(detached.branch, None);
+        assert_eq!(detached.head, worktrees[0].head);
+        assert!(detached.locked);
+        assert_eq!(detached.lock_reason.as_deref(), Some("on usb drive"));
+
+        // Dirty and locked worktrees need force
+        std::fs::write(feature.join("new.txt"), "draft").unwrap();
+        let err = remove_linked_worktree(main_str, feature_str, false).unwrap_err();
+        assert!(err.contains("uncommitted"), "{}", err);
+        remove_linked_worktree(main_str, feature_str, true).unwrap();
+        assert!(!feature.exists());
+
+        let detached_path = detached.path.clone();
+        let err = remove_linked_worktree(main_str, &detached_path, false).unwrap_err();
+        assert!(err.contains("on usb drive"), "{}", err);
+        remove_linked_worktree(main_str, &detached_path, true).unwrap();
+
+        assert!(remove_linked_worktree(main_str, main_str, true).is_err());
+        assert_eq!(list_linked_worktrees(main_str).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_status_and_diff_in_linked_worktree() {
+        let temp_dir = create_test_repo();
+        let main = temp_dir.path().join("main");
+        let linked = temp_dir.path().join("linked");
+        add_linked_worktree(
+            main.to_str().unwrap(),
+            linked.to_str().unwrap(),
+            "wip",
+            true,
+        )
+        .unwrap();
+
+        std::fs::write(linked.join("README.md"), "# Test\nline two\nline three\n").unwrap();
+
+        let wt_repo = repository::discover_repository(linked.join("README.md")).unwrap();
+        let wt_status = status::get_repository_status(&wt_repo, false).unwrap();
+        assert_eq!(wt_status.modified.len(), 1);
+        assert_eq!(wt_status.branch.unwrap().name, "wip");
+        let changes = diff::get_line_changes(&wt_repo, "README.md").unwrap();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].0, 3);
+
+        // The main worktree is unaffected
+        let main_repo = repository::discover_repository(&main).unwrap();
+        assert_eq!(
+            status::get_repository_status(&main_repo, false)
+                .unwrap()
+                .changes_count,
+            0
+        );
+
+        assert_eq!(
+            main_worktree_root(linked.to_str().unwrap()).map(PathBuf::from),
+            Some(main.canonicalize().unwrap())
+        );
+        assert_eq!(main_worktree_root(main.to_str().unwrap()), None);
+    }
+}
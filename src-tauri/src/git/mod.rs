@@ -1,19 +1,41 @@
+pub mod blame;
+pub mod commit;
 pub mod diff;
+pub mod history;
+pub mod hooks;
+pub mod housekeeping;
+pub mod linked_worktree;
+pub mod refresh;
 pub mod repository;
+pub mod sparse;
+pub mod staged_summary;
 pub mod status;
 pub mod types;
 pub mod worktree;
 
-use types::{DiffLineType, FileDiff, GitFileStatus, GitStatus};
+use crate::audit;
+use blame::LineAnnotation;
+use commit::CommitResult;
+use history::{FileAtCommit, FileHistoryEntry};
+use hooks::GitHooksInfo;
+use housekeeping::{HousekeepingResult, RepoSizeReport};
+use linked_worktree::LinkedWorktreeInfo;
+use serde_json::json;
+use sparse::RepositoryInfo;
+use staged_summary::StagedSummary;
+use types::{DiffLineType, DirectoryStatus, FileDiff, GitFileStatus, GitStatus};
 use worktree::{MergeResult, SyncResult, WorktreeChanges, WorktreeInfo, WorktreePoolStatus};
 
 /// Gets the Git status for a repository at the given path
 #[tauri::command]
-pub async fn git_get_status(repo_path: String) -> Result<GitStatus, String> {
+pub async fn git_get_status(
+    repo_path: String,
+    detect_renames: Option<bool>,
+) -> Result<GitStatus, String> {
     let repo = repository::discover_repository(&repo_path)
         .map_err(|e| format!("Failed to open repository: {}", e))?;
 
-    status::get_repository_status(&repo)
+    status::get_repository_status(&repo, detect_renames.unwrap_or(false))
         .map_err(|e| format!("Failed to get repository status: {}", e))
 }
 
@@ -23,18 +45,41 @@ pub async fn git_is_repository(repo_path: String) -> Result<bool, String> {
     Ok(repository::is_git_repository(&repo_path))
 }
 
+/// Where the repository lives and how it is checked out: sparse checkout and partial clone
+#[tauri::command]
+pub async fn git_get_repository_info(repo_path: String) -> Result<RepositoryInfo, String> {
+    let repo = repository::discover_repository(&repo_path)
+        .map_err(|e| format!("Failed to open repository: {}", e))?;
+    Ok(sparse::repository_info(&repo))
+}
+
 /// Gets all file statuses as a map
 #[tauri::command]
 pub async fn git_get_all_file_statuses(
     repo_path: String,
+    detect_renames: Option<bool>,
 ) -> Result<std::collections::HashMap<String, (GitFileStatus, bool)>, String> {
     let repo = repository::discover_repository(&repo_path)
         .map_err(|e| format!("Failed to open repository: {}", e))?;
 
-    status::get_all_file_statuses(&repo)
+    status::get_all_file_statuses(&repo, detect_renames.unwrap_or(false))
         .map_err(|e| format!("Failed to get all file statuses: {}", e))
 }
 
+/// Changed-file counts per directory (repo-relative, "" for the root) for explorer
+/// decorations. Directories deeper than `max_depth` roll into their ancestors.
+#[tauri::command]
+pub async fn git_get_directory_status_rollup(
+    repo_path: String,
+    max_depth: Option<usize>,
+) -> Result<std::collections::HashMap<String, DirectoryStatus>, String> {
+    let repo = repository::discover_repository(&repo_path)
+        .map_err(|e| format!("Failed to open repository: {}", e))?;
+
+    status::get_directory_rollup(&repo, max_depth)
+        .map_err(|e| format!("Failed to get directory statuses: {}", e))
+}
+
 /// Gets line-level changes for a file (for editor gutter indicators)
 #[tauri::command]
 pub async fn git_get_line_changes(
@@ -58,13 +103,37 @@ pub async fn git_get_line_changes(
         .map_err(|e| format!("Failed to get line changes: {}", e))
 }
 
+/// Gets line-level changes for unsaved editor content against HEAD
+#[tauri::command]
+pub async fn git_get_line_changes_for_content(
+    repo_path: String,
+    file_path: String,
+    content: String,
+) -> Result<Vec<(u32, DiffLineType)>, String> {
+    let repo = repository::discover_repository(&repo_path)
+        .map_err(|e| format!("Failed to open repository: {}", e))?;
+
+    // Convert absolute path to relative path from repo root
+    let repo_root = repository::get_repository_root(&repo)
+        .ok_or_else(|| "Failed to get repository root".to_string())?;
+
+    let relative_path = if file_path.starts_with(&repo_root) {
+        file_path[repo_root.len()..].trim_start_matches('/')
+    } else {
+        &file_path
+    };
+
+    diff::get_line_changes_for_content(&repo, relative_path, &content)
+        .map_err(|e| format!("Failed to get line changes: {}", e))
+}
+
 /// Gets full diff for all changed files in the repository
 #[tauri::command]
 pub async fn git_get_all_file_diffs(repo_path: String) -> Result<Vec<FileDiff>, String> {
     let repo = repository::discover_repository(&repo_path)
         .map_err(|e| format!("Failed to open repository: {}", e))?;
 
-    let git_status = status::get_repository_status(&repo)
+    let git_status = status::get_repository_status(&repo, false)
         .map_err(|e| format!("Failed to get repository status: {}", e))?;
 
     let mut diffs = Vec::new();
@@ -89,6 +158,27 @@ pub async fn git_get_raw_diff_text(repo_path: String) -> Result<String, String>
     diff::get_raw_diff_text(&repo).map_err(|e| format!("Failed to get raw diff text: {}", e))
 }
 
+/// Size-bounded summary of the staged changes, as input for commit message generation
+#[tauri::command]
+pub async fn git_get_staged_summary(
+    repo_path: String,
+    max_bytes_per_file: Option<usize>,
+    max_files: Option<usize>,
+) -> Result<StagedSummary, String> {
+    tokio::task::spawn_blocking(move || {
+        let repo = repository::discover_repository(&repo_path)
+            .map_err(|e| format!("Failed to open repository: {}", e))?;
+        staged_summary::staged_summary(
+            &repo,
+            max_bytes_per_file.unwrap_or(staged_summary::DEFAULT_MAX_BYTES_PER_FILE),
+            max_files.unwrap_or(staged_summary::DEFAULT_MAX_FILES),
+        )
+        .map_err(|e| format!("Failed to summarize staged changes: {}", e))
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+}
+
 // ============================================================================
 // Worktree Commands
 // ============================================================================
@@ -132,7 +222,13 @@ pub async fn git_remove_worktree(
     pool_index: u32,
     worktree_root: Option<String>,
 ) -> Result<(), String> {
-    worktree::remove_worktree(&project_path, pool_index, worktree_root.as_deref())
+    let recording = audit::begin(
+        "git_remove_worktree",
+        || json!({ "projectPath": project_path, "poolIndex": pool_index, "worktreeRoot": worktree_root }),
+    );
+    let result = worktree::remove_worktree(&project_path, pool_index, worktree_root.as_deref());
+    audit::finish(recording, &result);
+    result
 }
 
 /// List all worktrees in the pool for a project
@@ -153,7 +249,13 @@ pub async fn git_get_worktree_changes(worktree_path: String) -> Result<WorktreeC
 /// Commit all changes in a worktree
 #[tauri::command]
 pub async fn git_commit_worktree(worktree_path: String, message: String) -> Result<String, String> {
-    worktree::commit_worktree(&worktree_path, &message)
+    let recording = audit::begin(
+        "git_commit_worktree",
+        || json!({ "worktreePath": worktree_path, "message": message }),
+    );
+    let result = worktree::commit_worktree(&worktree_path, &message);
+    audit::finish(recording, &result);
+    result
 }
 
 /// Merge a worktree's changes back to the main branch
@@ -164,12 +266,22 @@ pub async fn git_merge_worktree(
     commit_message: Option<String>,
     worktree_root: Option<String>,
 ) -> Result<MergeResult, String> {
-    worktree::merge_worktree_to_main(
+    let recording = audit::begin("git_merge_worktree", || {
+        json!({
+            "projectPath": project_path,
+            "poolIndex": pool_index,
+            "commitMessage": commit_message,
+            "worktreeRoot": worktree_root,
+        })
+    });
+    let result = worktree::merge_worktree_to_main(
         &project_path,
         pool_index,
         commit_message.as_deref(),
         worktree_root.as_deref(),
-    )
+    );
+    audit::finish(recording, &result);
+    result
 }
 
 /// Abort an in-progress merge
@@ -203,7 +315,14 @@ pub async fn git_sync_worktree_from_main(
     pool_index: u32,
     worktree_root: Option<String>,
 ) -> Result<SyncResult, String> {
-    worktree::sync_worktree_from_main(&project_path, pool_index, worktree_root.as_deref())
+    let recording = audit::begin(
+        "git_sync_worktree_from_main",
+        || json!({ "projectPath": project_path, "poolIndex": pool_index, "worktreeRoot": worktree_root }),
+    );
+    let result =
+        worktree::sync_worktree_from_main(&project_path, pool_index, worktree_root.as_deref());
+    audit::finish(recording, &result);
+    result
 }
 
 /// Abort an in-progress rebase in a worktree
@@ -211,3 +330,139 @@ pub async fn git_sync_worktree_from_main(
 pub async fn git_abort_rebase(worktree_path: String) -> Result<(), String> {
     worktree::abort_rebase(&worktree_path)
 }
+
+/// List the main and linked worktrees of the repository containing `repo_path`.
+/// Unlike `git_list_worktrees`, this covers worktrees created outside the task pool.
+#[tauri::command]
+pub async fn git_list_linked_worktrees(
+    repo_path: String,
+) -> Result<Vec<LinkedWorktreeInfo>, String> {
+    linked_worktree::list_linked_worktrees(&repo_path)
+}
+
+/// Add a linked worktree at `new_path` checking out `branch`
+#[tauri::command]
+pub async fn git_add_linked_worktree(
+    repo_path: String,
+    new_path: String,
+    branch: String,
+    create_branch: bool,
+) -> Result<LinkedWorktreeInfo, String> {
+    linked_worktree::add_linked_worktree(&repo_path, &new_path, &branch, create_branch)
+}
+
+/// Remove a linked worktree and its working directory
+#[tauri::command]
+pub async fn git_remove_linked_worktree(
+    repo_path: String,
+    worktree_path: String,
+    force: bool,
+) -> Result<(), String> {
+    linked_worktree::remove_linked_worktree(&repo_path, &worktree_path, force)
+}
+
+/// List the hook scripts of a repository and whether `core.hooksPath` overrides `.git/hooks`
+#[tauri::command]
+pub async fn git_get_hooks(repo_path: String) -> Result<GitHooksInfo, String> {
+    let repo = repository::discover_repository(&repo_path)
+        .map_err(|e| format!("Failed to open repository: {}", e))?;
+    hooks::get_hooks(&repo)
+}
+
+/// Commit the staged changes. git2 skips hooks, so with `run_hooks` the pre-commit and
+/// commit-msg hooks are run first; otherwise the result lists the hooks that were skipped.
+#[tauri::command]
+pub async fn git_commit(
+    repo_path: String,
+    message: String,
+    run_hooks: Option<bool>,
+) -> Result<CommitResult, String> {
+    let recording = audit::begin(
+        "git_commit",
+        || json!({ "repoPath": repo_path, "message": message, "runHooks": run_hooks }),
+    );
+    let result = commit::commit_staged(&repo_path, &message, run_hooks.unwrap_or(false)).await;
+    audit::finish(recording, &result);
+    result
+}
+
+/// Commits that changed a file, newest first, with per-commit line counts for the file.
+/// With `follow_renames` the history continues across renames.
+#[tauri::command]
+pub async fn git_get_file_history(
+    repo_path: String,
+    file_path: String,
+    limit: Option<usize>,
+    follow_renames: Option<bool>,
+) -> Result<Vec<FileHistoryEntry>, String> {
+    let repo = repository::discover_repository(&repo_path)
+        .map_err(|e| format!("Failed to open repository: {}", e))?;
+    history::get_file_history(&repo, &file_path, limit, follow_renames.unwrap_or(false))
+        .map_err(|e| format!("Failed to get file history: {}", e))
+}
+
+/// A file's content at a commit, for comparing with the current version
+#[tauri::command]
+pub async fn git_get_file_at_commit(
+    repo_path: String,
+    file_path: String,
+    commit_id: String,
+) -> Result<FileAtCommit, String> {
+    let repo = repository::discover_repository(&repo_path)
+        .map_err(|e| format!("Failed to open repository: {}", e))?;
+    history::get_file_at_commit(&repo, &file_path, &commit_id)
+}
+
+/// Blame of one line with its commit's summary and age, for inline annotations. Pass
+/// `line_text`, the editor's current text of the line, to have unsaved edits reported as
+/// not committed yet.
+#[tauri::command]
+pub async fn git_get_line_annotation(
+    repo_path: String,
+    file_path: String,
+    line: u32,
+    line_text: Option<String>,
+) -> Result<LineAnnotation, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let repo = repository::discover_repository(&repo_path)
+            .map_err(|e| format!("Failed to open repository: {}", e))?;
+        blame::get_line_annotation(&repo, &file_path, line, line_text.as_deref())
+    })
+    .await
+    .map_err(|e| format!("Blame task failed: {}", e))?
+}
+
+/// Blame a file when it gains focus so the first line annotation is instant
+#[tauri::command]
+pub async fn git_prefetch_blame(repo_path: String, file_path: String) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let repo = repository::discover_repository(&repo_path)
+            .map_err(|e| format!("Failed to open repository: {}", e))?;
+        blame::prefetch_blame(&repo, &file_path)
+    })
+    .await
+    .map_err(|e| format!("Blame task failed: {}", e))?
+}
+
+/// How the repository's git dir size breaks down: objects, packfiles, refs and worktrees
+#[tauri::command]
+pub async fn git_get_repo_size_report(repo_path: String) -> Result<RepoSizeReport, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let repo = repository::discover_repository(&repo_path)
+            .map_err(|e| format!("Failed to open repository: {}", e))?;
+        Ok(housekeeping::repo_size_report(&repo))
+    })
+    .await
+    .map_err(|e| format!("Size report task failed: {}", e))?
+}
+
+/// Run `git gc` with progress in the activity center and report the bytes it freed.
+/// Refuses while another git process holds the index lock.
+#[tauri::command]
+pub async fn git_run_housekeeping(
+    repo_path: String,
+    aggressive: bool,
+    operation_id: Option<String>,
+) -> Result<HousekeepingResult, String> {
+    housekeeping::run_housekeeping(&repo_path, aggressive, operation_id).await
+}
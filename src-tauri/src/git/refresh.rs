@@ -0,0 +1,271 @@
+// Coalescing of git status refreshes
+// An interactive rebase or a large merge rewrites HEAD, the index and refs many times a
+// second, and every git-status-changed makes each window recompute status and line
+// changes. The git watcher feeds its classified events through `AdaptiveDebounce`, which
+// stretches the debounce while events arrive faster than a storm threshold, and reports
+// the operation in progress (from the marker files git leaves in the git directory) so
+// the frontend can pause expensive refreshes until `OperationTransition::Completed`.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// Debounce outside of storms
+pub const BASE_DEBOUNCE: Duration = Duration::from_millis(500);
+/// Debounce while events arrive faster than `STORM_EVENTS_PER_SECOND`
+pub const STORM_DEBOUNCE: Duration = Duration::from_secs(2);
+/// More classified events than this within a second start a storm
+pub const STORM_EVENTS_PER_SECOND: usize = 20;
+/// A storm lasts this long after the last second that exceeded the threshold
+pub const STORM_COOLDOWN: Duration = Duration::from_secs(3);
+/// A refresh is sent at least this often while events keep arriving, so a long rebase
+/// still updates the UI now and then
+pub const MAX_REFRESH_DELAY: Duration = Duration::from_secs(10);
+
+const RATE_WINDOW: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum InProgressOperation {
+    Rebase,
+    Merge,
+    CherryPick,
+    Revert,
+}
+
+/// The operation a git directory's marker files say is in progress. A rebase that stops
+/// on a conflict also leaves the directory behind, so it still counts.
+pub fn detect_in_progress(git_dir: &Path) -> Option<InProgressOperation> {
+    if git_dir.join("rebase-merge").is_dir() || git_dir.join("rebase-apply").is_dir() {
+        Some(InProgressOperation::Rebase)
+    } else if git_dir.join("MERGE_HEAD").is_file() {
+        Some(InProgressOperation::Merge)
+    } else if git_dir.join("CHERRY_PICK_HEAD").is_file() {
+        Some(InProgressOperation::CherryPick)
+    } else if git_dir.join("REVERT_HEAD").is_file() {
+        Some(InProgressOperation::Revert)
+    } else {
+        None
+    }
+}
+
+/// Whether a path under the git directory marks an operation starting or ending
+pub fn is_operation_marker(path: &Path) -> bool {
+    let path = path.to_string_lossy().replace('\\', "/");
+    path.contains(".git/rebase-merge")
+        || path.contains(".git/rebase-apply")
+        || ["MERGE_HEAD", "CHERRY_PICK_HEAD", "REVERT_HEAD"]
+            .iter()
+            .any(|marker| path.ends_with(&format!(".git/{}", marker)))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OperationTransition {
+    Started(InProgressOperation),
+    /// The markers disappeared; listeners that paused should refresh now
+    Completed(InProgressOperation),
+}
+
+/// What changed between two detections. Switching straight from one operation to
+/// another (a merge during a rebase) counts as the new one starting.
+pub fn operation_transition(
+    previous: Option<InProgressOperation>,
+    current: Option<InProgressOperation>,
+) -> Option<OperationTransition> {
+    match (previous, current) {
+        (Some(before), None) => Some(OperationTransition::Completed(before)),
+        (before, Some(now)) if before != Some(now) => Some(OperationTransition::Started(now)),
+        _ => None,
+    }
+}
+
+/// Trailing-edge debounce whose quiet period grows while events arrive in a storm
+#[derive(Debug, Default)]
+pub struct AdaptiveDebounce {
+    /// Times of classified events within the last `RATE_WINDOW`
+    recent: VecDeque<Instant>,
+    storm_until: Option<Instant>,
+    first_pending: Option<Instant>,
+    last_event: Option<Instant>,
+}
+
+impl AdaptiveDebounce {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A classified event arrived at `at`
+    pub fn record(&mut self, at: Instant) {
+        while self
+            .recent
+            .front()
+            .is_some_and(|&t| at.saturating_duration_since(t) >= RATE_WINDOW)
+        {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(at);
+        if self.recent.len() > STORM_EVENTS_PER_SECOND {
+            self.storm_until = Some(at + STORM_COOLDOWN);
+        }
+        self.first_pending.get_or_insert(at);
+        self.last_event = Some(at);
+    }
+
+    pub fn in_storm(&self, now: Instant) -> bool {
+        self.storm_until.is_some_and(|until| now < until)
+    }
+
+    pub fn debounce(&self, now: Instant) -> Duration {
+        if self.in_storm(now) {
+            STORM_DEBOUNCE
+        } else {
+            BASE_DEBOUNCE
+        }
+    }
+
+    /// Whether the pending refresh is due: the debounce passed since the last event, or
+    /// the first unflushed event is `MAX_REFRESH_DELAY` old
+    pub fn is_due(&self, now: Instant) -> bool {
+        let (Some(first), Some(last)) = (self.first_pending, self.last_event) else {
+            return false;
+        };
+        now.saturating_duration_since(last) >= self.debounce(now)
+            || now.saturating_duration_since(first) >= MAX_REFRESH_DELAY
+    }
+
+    /// The pending refresh was sent; the storm state is kept
+    pub fn flushed(&mut self) {
+        self.first_pending = None;
+        self.last_event = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    /// Feed events at the given millisecond offsets, then report the refreshes a
+    /// 100ms polling loop would send up to `until_ms`
+    fn simulate(offsets_ms: &[u64], until_ms: u64) -> Vec<u64> {
+        let start = Instant::now();
+        let mut debounce = AdaptiveDebounce::new();
+        let mut events = offsets_ms.iter().peekable();
+        let mut flushes = Vec::new();
+        let mut now_ms = 0;
+        while now_ms <= until_ms {
+            while events.peek().is_some_and(|&&t| t <= now_ms) {
+                debounce.record(start + Duration::from_millis(*events.next().unwrap()));
+            }
+            let now = start + Duration::from_millis(now_ms);
+            if debounce.is_due(now) {
+                flushes.push(now_ms);
+                debounce.flushed();
+            }
+            now_ms += 100;
+        }
+        flushes
+    }
+
+    #[test]
+    fn test_sparse_events_use_the_base_debounce() {
+        assert_eq!(simulate(&[0, 200], 2000), vec![700]);
+        assert_eq!(simulate(&[0, 1000], 3000), vec![500, 1500]);
+    }
+
+    #[test]
+    fn test_storm_stretches_the_debounce() {
+        // 30 events in 300ms
+        let burst: Vec<u64> = (0..30).map(|i| i * 10).collect();
+        assert_eq!(simulate(&burst, 5000), vec![2300]);
+    }
+
+    #[test]
+    fn test_continuous_storm_still_refreshes_periodically() {
+        // 40 events a second for 25 seconds
+        let storm: Vec<u64> = (0..1000).map(|i| i * 25).collect();
+        // Every 10s while it lasts, then once the storm debounce passes after the last
+        assert_eq!(simulate(&storm, 30_000), vec![10_000, 20_100, 27_000]);
+    }
+
+    #[test]
+    fn test_storm_ends_after_the_cooldown() {
+        let start = Instant::now();
+        let mut debounce = AdaptiveDebounce::new();
+        for i in 0..=STORM_EVENTS_PER_SECOND as u64 {
+            debounce.record(start + Duration::from_millis(i));
+        }
+        assert!(debounce.in_storm(start + Duration::from_secs(1)));
+        assert_eq!(
+            debounce.debounce(start + Duration::from_secs(1)),
+            STORM_DEBOUNCE
+        );
+        let after = start + Duration::from_millis(20) + STORM_COOLDOWN;
+        assert!(!debounce.in_storm(after));
+        assert_eq!(debounce.debounce(after), BASE_DEBOUNCE);
+    }
+
+    #[test]
+    fn test_events_spread_over_seconds_are_not_a_storm() {
+        let start = Instant::now();
+        let mut debounce = AdaptiveDebounce::new();
+        for i in 0..60 {
+            debounce.record(start + Duration::from_millis(i * 100));
+        }
+        assert!(!debounce.in_storm(start + Duration::from_secs(6)));
+    }
+
+    #[test]
+    fn test_operation_transitions() {
+        use InProgressOperation::*;
+        assert_eq!(operation_transition(None, None), None);
+        assert_eq!(
+            operation_transition(None, Some(Rebase)),
+            Some(OperationTransition::Started(Rebase))
+        );
+        assert_eq!(operation_transition(Some(Rebase), Some(Rebase)), None);
+        assert_eq!(
+            operation_transition(Some(Rebase), Some(Merge)),
+            Some(OperationTransition::Started(Merge))
+        );
+        assert_eq!(
+            operation_transition(Some(Merge), None),
+            Some(OperationTransition::Completed(Merge))
+        );
+    }
+
+    #[test]
+    fn test_operation_markers() {
+        assert!(is_operation_marker(Path::new(
+            "/repo/.git/rebase-merge/done"
+        )));
+        assert!(is_operation_marker(Path::new("/repo/.git/rebase-apply")));
+        assert!(is_operation_marker(Path::new("/repo/.git/MERGE_HEAD")));
+        assert!(is_operation_marker(Path::new(
+            "/repo/.git/CHERRY_PICK_HEAD"
+        )));
+        assert!(!is_operation_marker(Path::new("/repo/.git/ORIG_HEAD")));
+        assert!(!is_operation_marker(Path::new("/repo/.git/index")));
+    }
+
+    #[test]
+    fn test_detect_in_progress_from_markers() {
+        let dir = std::env::temp_dir().join(format!("git-refresh-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        assert_eq!(detect_in_progress(&dir), None);
+
+        fs::write(dir.join("CHERRY_PICK_HEAD"), "abc\n").unwrap();
+        assert_eq!(
+            detect_in_progress(&dir),
+            Some(InProgressOperation::CherryPick)
+        );
+        fs::write(dir.join("MERGE_HEAD"), "abc\n").unwrap();
+        assert_eq!(detect_in_progress(&dir), Some(InProgressOperation::Merge));
+        fs::create_dir(dir.join("rebase-merge")).unwrap();
+        assert_eq!(detect_in_progress(&dir), Some(InProgressOperation::Rebase));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
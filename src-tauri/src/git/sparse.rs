@@ -0,0 +1,216 @@
+// Sparse checkout and partial clone awareness
+// In a sparse checkout, paths outside the sparse cone are in the index with the
+// SKIP_WORKTREE bit set but absent from disk. Status must not report them as deleted, and
+// features that only see the disk (reference search) should say their results may be
+// limited. Partial clones are reported alongside, since their missing blobs are fetched
+// lazily by git and can make history operations slow.
+
+use git2::{IndexEntryExtendedFlag, Repository};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::Path;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SparseCheckoutInfo {
+    pub active: bool,
+    /// Patterns are directories (`git sparse-checkout --cone`) rather than gitignore-style
+    pub cone_mode: bool,
+    /// Patterns in the sparse-checkout file
+    pub pattern_count: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PartialCloneInfo {
+    /// Remote objects are fetched from on demand
+    pub remote: String,
+    /// Filter the clone was made with, e.g. "blob:none"
+    pub filter: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RepositoryInfo {
+    /// Working directory; None for a bare repository
+    pub root: Option<String>,
+    pub git_dir: String,
+    pub is_linked_worktree: bool,
+    pub sparse_checkout: SparseCheckoutInfo,
+    pub partial_clone: Option<PartialCloneInfo>,
+}
+
+/// Whether the repository uses a sparse checkout. `core.sparseCheckout` decides when set;
+/// otherwise a sparse-checkout file counts, as `git sparse-checkout disable` sets the
+/// config to false but leaves the file behind.
+pub fn detect_sparse_checkout(repo: &Repository) -> SparseCheckoutInfo {
+    let config = repo.config().ok();
+    let config_bool = |key: &str| config.as_ref().and_then(|c| c.get_bool(key).ok());
+
+    let patterns = std::fs::read_to_string(repo.path().join("info").join("sparse-checkout")).ok();
+    let active = config_bool("core.sparseCheckout").unwrap_or(patterns.is_some());
+    if !active {
+        return SparseCheckoutInfo::default();
+    }
+    SparseCheckoutInfo {
+        active,
+        cone_mode: config_bool("core.sparseCheckoutCone").unwrap_or(false),
+        pattern_count: patterns
+            .as_deref()
+            .unwrap_or_default()
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .count(),
+    }
+}
+
+/// Whether the repository containing `path` uses a sparse checkout
+pub fn sparse_checkout_active(path: &Path) -> bool {
+    Repository::discover(path).is_ok_and(|repo| detect_sparse_checkout(&repo).active)
+}
+
+/// The promisor remote of a partial clone
+pub fn detect_partial_clone(repo: &Repository) -> Option<PartialCloneInfo> {
+    let config = repo.config().ok()?;
+    let remote = config
+        .get_string("extensions.partialClone")
+        .ok()
+        .or_else(|| {
+            let remotes = repo.remotes().ok()?;
+            let promisor = remotes.iter().flatten().find(|name| {
+                config
+                    .get_bool(&format!("remote.{}.promisor", name))
+                    .unwrap_or(false)
+            });
+            promisor.map(str::to_string)
+        })?;
+    let filter = config
+        .get_string(&format!("remote.{}.partialclonefilter", remote))
+        .ok();
+    Some(PartialCloneInfo { remote, filter })
+}
+
+pub fn repository_info(repo: &Repository) -> RepositoryInfo {
+    RepositoryInfo {
+        root: super::repository::get_repository_root(repo),
+        git_dir: repo.path().to_string_lossy().to_string(),
+        is_linked_worktree: repo.is_worktree(),
+        sparse_checkout: detect_sparse_checkout(repo),
+        partial_clone: detect_partial_clone(repo),
+    }
+}
+
+/// Index paths with the SKIP_WORKTREE bit: outside the sparse cone, or marked with
+/// `git update-index --skip-worktree`. Git does not compare them with the disk.
+pub fn skip_worktree_paths(repo: &Repository) -> Result<HashSet<String>, git2::Error> {
+    let index = repo.index()?;
+    Ok(index
+        .iter()
+        .filter(|entry| entry.flags_extended & IndexEntryExtendedFlag::SKIP_WORKTREE.bits() != 0)
+        .map(|entry| String::from_utf8_lossy(&entry.path).to_string())
+        .collect())
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::git::status::{get_all_file_statuses, get_repository_status};
+    use crate::test_support::git;
+    use std::fs;
+    use tempfile::TempDir;
+
+    /// A repository with two packages, checked out sparsely to `packages/app`
+    fn create_sparse_repo() -> TempDir {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        git(dir, &["init", "-b", "main"]);
+        git(dir, &["config", "user.email", "test@test.com"]);
+        git(dir, &["config", "user.name", "Test User"]);
+        for path in [
+            "README.md",
+            "packages/app/src/main.ts",
+            "packages/lib/src/index.ts",
+            "packages/lib/src/util.ts",
+        ] {
+            let file = dir.join(path);
+            fs::create_dir_all(file.parent().unwrap()).unwrap();
+            fs::write(&file, format!("// {}\n", path)).unwrap();
+        }
+        git(dir, &["add", "-A"]);
+        git(dir, &["commit", "-m", "Initial"]);
+        git(dir, &["sparse-checkout", "set", "--cone", "packages/app"]);
+        temp_dir
+    }
+
+    #[test]
+    fn test_detects_sparse_checkout() {
+        let temp_dir = create_sparse_repo();
+        let repo = Repository::open(temp_dir.path()).unwrap();
+        let info = detect_sparse_checkout(&repo);
+        assert!(info.active);
+        assert!(info.cone_mode);
+        assert!(info.pattern_count > 0);
+        assert!(sparse_checkout_active(
+            &temp_dir.path().join("packages/app")
+        ));
+        assert!(!temp_dir.path().join("packages/lib").exists());
+
+        let skipped = skip_worktree_paths(&repo).unwrap();
+        assert!(skipped.contains("packages/lib/src/index.ts"));
+        assert!(!skipped.contains("packages/app/src/main.ts"));
+
+        git(temp_dir.path(), &["sparse-checkout", "disable"]);
+        let repo = Repository::open(temp_dir.path()).unwrap();
+        assert!(!detect_sparse_checkout(&repo).active);
+    }
+
+    #[test]
+    fn test_sparse_checkout_status_is_clean() {
+        let temp_dir = create_sparse_repo();
+        let repo = Repository::open(temp_dir.path()).unwrap();
+
+        let status = get_repository_status(&repo, false).unwrap();
+        assert!(status.modified.is_empty(), "{:?}", status.modified);
+        assert_eq!(status.changes_count, 0);
+        assert!(get_all_file_statuses(&repo, false).unwrap().is_empty());
+
+        // Real deletions inside the cone are still reported
+        fs::remove_file(temp_dir.path().join("packages/app/src/main.ts")).unwrap();
+        let status = get_repository_status(&repo, false).unwrap();
+        let paths: Vec<&str> = status.modified.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths, vec!["packages/app/src/main.ts"]);
+    }
+
+    #[test]
+    fn test_repository_info() {
+        let temp_dir = create_sparse_repo();
+        let repo = Repository::open(temp_dir.path()).unwrap();
+        let info = repository_info(&repo);
+        assert!(info.root.is_some());
+        assert!(!info.is_linked_worktree);
+        assert!(info.sparse_checkout.active);
+        assert_eq!(info.partial_clone, None);
+
+        git(
+            temp_dir.path(),
+            &["config", "remote.origin.url", "https://example.com/x.git"],
+        );
+        git(
+            temp_dir.path(),
+            &["config", "remote.origin.promisor", "true"],
+        );
+        git(
+            temp_dir.path(),
+            &["config", "remote.origin.partialclonefilter", "blob:none"],
+        );
+        let repo = Repository::open(temp_dir.path()).unwrap();
+        assert_eq!(
+            detect_partial_clone(&repo),
+            Some(PartialCloneInfo {
+                remote: "origin".to_string(),
+                filter: Some("blob:none".to_string()),
+            })
+        );
+    }
+}
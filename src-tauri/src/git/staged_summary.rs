@@ -0,0 +1,350 @@
+// Staged change summary for commit message generation
+// The staged diff (index vs HEAD) of a large commit does not fit in a prompt. This builds
+// a size-bounded summary instead: every staged file with its status and line counts, the
+// full hunks of small files, and only the first and last hunks of files over the per-file
+// byte cap. Binary files report their sizes. Paths are repository-relative.
+
+use super::types::GitFileStatus;
+use git2::{Delta, DiffFindOptions, DiffOptions, Error as GitError, Oid, Patch, Repository};
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_MAX_BYTES_PER_FILE: usize = 4 * 1024;
+pub const DEFAULT_MAX_FILES: usize = 50;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SummaryHunk {
+    /// "@@ -1,4 +1,6 @@ fn main"
+    pub header: String,
+    /// Lines prefixed with '+', '-' or ' ', as in a unified diff
+    pub patch: String,
+    /// The patch text was cut to fit the byte cap
+    pub truncated: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StagedFileSummary {
+    pub path: String,
+    /// Previous path of a renamed file
+    pub old_path: Option<String>,
+    pub status: GitFileStatus,
+    pub additions: usize,
+    pub deletions: usize,
+    pub binary: bool,
+    /// Blob sizes in bytes, reported for binary files
+    pub old_size: Option<u64>,
+    pub new_size: Option<u64>,
+    pub hunks: Vec<SummaryHunk>,
+    /// Hunks were left out or cut to fit the byte cap
+    pub truncated: bool,
+    /// Hunks left out between the first and the last
+    pub omitted_hunks: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StagedTotals {
+    pub files_changed: usize,
+    pub additions: usize,
+    pub deletions: usize,
+    pub binary_files: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StagedSummary {
+    /// Summarized files, in path order, at most `max_files`
+    pub files: Vec<StagedFileSummary>,
+    /// Totals over every staged file, including omitted ones
+    pub totals: StagedTotals,
+    /// Staged files beyond `max_files`
+    pub omitted_files: Vec<String>,
+}
+
+fn file_status(delta: Delta) -> GitFileStatus {
+    match delta {
+        Delta::Added | Delta::Copied => GitFileStatus::Added,
+        Delta::Deleted => GitFileStatus::Deleted,
+        Delta::Renamed => GitFileStatus::Renamed,
+        Delta::Conflicted => GitFileStatus::Conflicted,
+        _ => GitFileStatus::Modified,
+    }
+}
+
+fn blob_size(repo: &Repository, id: Oid) -> Option<u64> {
+    if id.is_zero() {
+        return None;
+    }
+    repo.find_blob(id).ok().map(|blob| blob.size() as u64)
+}
+
+fn hunks_of(patch: &Patch) -> Result<Vec<SummaryHunk>, GitError> {
+    let mut hunks = Vec::with_capacity(patch.num_hunks());
+    for hunk_idx in 0..patch.num_hunks() {
+        let (hunk, line_count) = patch.hunk(hunk_idx)?;
+        let mut text = String::new();
+        for line_idx in 0..line_count {
+            let line = patch.line_in_hunk(hunk_idx, line_idx)?;
+            let prefix = match line.origin() {
+                '+' | '-' | ' ' => line.origin(),
+                // End-of-file newline markers
+                _ => continue,
+            };
+            text.push(prefix);
+            text.push_str(&String::from_utf8_lossy(line.content()));
+            if !text.ends_with('\n') {
+                text.push('\n');
+            }
+        }
+        hunks.push(SummaryHunk {
+            header: String::from_utf8_lossy(hunk.header())
+                .trim_end()
+                .to_string(),
+            patch: text,
+            truncated: false,
+        });
+    }
+    Ok(hunks)
+}
+
+fn hunk_bytes(hunk: &SummaryHunk) -> usize {
+    hunk.header.len() + hunk.patch.len()
+}
+
+/// Cut a hunk's patch to at most `max_bytes`, at a line boundary
+fn truncate_hunk(hunk: &mut SummaryHunk, max_bytes: usize) {
+    let budget = max_bytes.saturating_sub(hunk.header.len());
+    if hunk.patch.len() <= budget {
+        return;
+    }
+    let cut = hunk.patch[..budget].rfind('\n').map_or(0, |i| i + 1);
+    hunk.patch.truncate(cut);
+    hunk.truncated = true;
+}
+
+/// Keep every hunk when they fit in `max_bytes`, otherwise the first and the last, cut
+/// to share the cap. Returns the hunks kept and how many were left out.
+fn select_hunks(mut hunks: Vec<SummaryHunk>, max_bytes: usize) -> (Vec<SummaryHunk>, usize) {
+    if hunks.iter().map(hunk_bytes).sum::<usize>() <= max_bytes {
+        return (hunks, 0);
+    }
+    let omitted = hunks.len().saturating_sub(2);
+    if hunks.len() > 2 {
+        let last = hunks.pop().unwrap();
+        hunks.truncate(1);
+        hunks.push(last);
+    }
+    let share = max_bytes / hunks.len().max(1);
+    for hunk in &mut hunks {
+        truncate_hunk(hunk, share);
+    }
+    (hunks, omitted)
+}
+
+/// Summarize the staged changes of a repository
+pub fn staged_summary(
+    repo: &Repository,
+    max_bytes_per_file: usize,
+    max_files: usize,
+) -> Result<StagedSummary, GitError> {
+    // An unborn HEAD diffs the index against the empty tree
+    let head_tree = match repo.head() {
+        Ok(head) => Some(head.peel_to_tree()?),
+        Err(_) => None,
+    };
+    let mut opts = DiffOptions::new();
+    let mut diff = repo.diff_tree_to_index(head_tree.as_ref(), None, Some(&mut opts))?;
+    diff.find_similar(Some(DiffFindOptions::new().renames(true)))?;
+
+    let mut files = Vec::new();
+    let mut totals = StagedTotals::default();
+    let mut omitted_files = Vec::new();
+
+    for (idx, delta) in diff.deltas().enumerate() {
+        let path_of =
+            |file: git2::DiffFile| file.path().map(|p| p.to_string_lossy().replace('\\', "/"));
+        let path = path_of(delta.new_file())
+            .or_else(|| path_of(delta.old_file()))
+            .unwrap_or_default();
+        let old_path = (delta.status() == Delta::Renamed)
+            .then(|| path_of(delta.old_file()))
+            .flatten();
+
+        let patch = Patch::from_diff(&diff, idx)?;
+        // Binary detection needs the content, which the patch has loaded
+        let binary = patch.as_ref().is_none_or(|p| p.delta().flags().is_binary());
+        let (additions, deletions) = match (&patch, binary) {
+            (Some(patch), false) => {
+                let (_, additions, deletions) = patch.line_stats()?;
+                (additions, deletions)
+            }
+            _ => (0, 0),
+        };
+        totals.files_changed += 1;
+        totals.additions += additions;
+        totals.deletions += deletions;
+        if binary {
+            totals.binary_files += 1;
+        }
+
+        if files.len() >= max_files {
+            omitted_files.push(path);
+            continue;
+        }
+
+        let mut summary = StagedFileSummary {
+            path,
+            old_path,
+            status: file_status(delta.status()),
+            additions,
+            deletions,
+            binary,
+            old_size: None,
+            new_size: None,
+            hunks: Vec::new(),
+            truncated: false,
+            omitted_hunks: 0,
+        };
+        match patch {
+            Some(patch) if !binary => {
+                let (hunks, omitted) = select_hunks(hunks_of(&patch)?, max_bytes_per_file);
+                summary.truncated = omitted > 0 || hunks.iter().any(|h| h.truncated);
+                summary.omitted_hunks = omitted;
+                summary.hunks = hunks;
+            }
+            _ => {
+                summary.old_size = blob_size(repo, delta.old_file().id());
+                summary.new_size = blob_size(repo, delta.new_file().id());
+            }
+        }
+        files.push(summary);
+    }
+
+    Ok(StagedSummary {
+        files,
+        totals,
+        omitted_files,
+    })
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::test_support::git;
+    use std::fs;
+    use std::path::Path;
+    use tempfile::TempDir;
+
+    /// Numbered lines, so edits to every tenth line make separate hunks
+    fn numbered(count: usize, edited: impl Fn(usize) -> bool) -> String {
+        (0..count)
+            .map(|i| {
+                if edited(i) {
+                    format!("edited line {}\n", i)
+                } else {
+                    format!("line {}\n", i)
+                }
+            })
+            .collect()
+    }
+
+    fn create_staged_repo() -> TempDir {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        git(dir, &["init", "-b", "main"]);
+        git(dir, &["config", "user.email", "test@test.com"]);
+        git(dir, &["config", "user.name", "Test User"]);
+        fs::write(dir.join("README.md"), "# Demo\n").unwrap();
+        fs::write(dir.join("generated.txt"), numbered(2000, |_| false)).unwrap();
+        fs::write(dir.join("logo.bin"), [0u8, 1, 2, 3]).unwrap();
+        fs::write(dir.join("old_name.txt"), numbered(40, |_| false)).unwrap();
+        git(dir, &["add", "-A"]);
+        git(dir, &["commit", "-m", "Initial"]);
+
+        // A small edit, a large regenerated file, a binary change and a rename
+        fs::write(dir.join("README.md"), "# Demo\n\nMore docs.\n").unwrap();
+        fs::write(dir.join("generated.txt"), numbered(2000, |i| i % 50 == 0)).unwrap();
+        fs::write(dir.join("logo.bin"), [0u8, 9, 9, 9, 9, 9, 9, 9]).unwrap();
+        git(dir, &["mv", "old_name.txt", "new_name.txt"]);
+        git(dir, &["add", "-A"]);
+        // Unstaged edits are not part of the summary
+        fs::write(dir.join("README.md"), "# Unstaged\n").unwrap();
+        temp_dir
+    }
+
+    fn file<'a>(summary: &'a StagedSummary, path: &str) -> &'a StagedFileSummary {
+        summary.files.iter().find(|f| f.path == path).unwrap()
+    }
+
+    #[test]
+    fn test_large_file_keeps_first_and_last_hunks() {
+        let temp_dir = create_staged_repo();
+        let repo = Repository::open(temp_dir.path()).unwrap();
+        let summary = staged_summary(&repo, 1024, DEFAULT_MAX_FILES).unwrap();
+
+        let generated = file(&summary, "generated.txt");
+        assert_eq!(generated.additions, 40);
+        assert_eq!(generated.deletions, 40);
+        assert!(generated.truncated);
+        assert_eq!(generated.hunks.len(), 2);
+        assert_eq!(generated.omitted_hunks, 38);
+        assert!(generated.hunks[0].patch.contains("+edited line 0\n"));
+        assert!(generated.hunks[1].patch.contains("+edited line 1950\n"));
+        let bytes: usize = generated.hunks.iter().map(hunk_bytes).sum();
+        assert!(bytes <= 1024, "{} bytes", bytes);
+
+        let readme = file(&summary, "README.md");
+        assert!(!readme.truncated);
+        assert_eq!(readme.additions, 2);
+        assert!(readme.hunks[0].patch.contains("+More docs.\n"));
+        assert!(!readme.hunks[0].patch.contains("Unstaged"));
+    }
+
+    #[test]
+    fn test_binary_and_renamed_files() {
+        let temp_dir = create_staged_repo();
+        let repo = Repository::open(temp_dir.path()).unwrap();
+        let summary = staged_summary(&repo, DEFAULT_MAX_BYTES_PER_FILE, DEFAULT_MAX_FILES).unwrap();
+
+        let logo = file(&summary, "logo.bin");
+        assert!(logo.binary);
+        assert!(logo.hunks.is_empty());
+        assert_eq!((logo.old_size, logo.new_size), (Some(4), Some(8)));
+
+        let renamed = file(&summary, "new_name.txt");
+        assert!(matches!(renamed.status, GitFileStatus::Renamed));
+        assert_eq!(renamed.old_path.as_deref(), Some("old_name.txt"));
+
+        assert_eq!(summary.totals.files_changed, 4);
+        assert_eq!(summary.totals.binary_files, 1);
+        assert!(summary
+            .files
+            .iter()
+            .all(|f| !Path::new(&f.path).is_absolute()));
+    }
+
+    #[test]
+    fn test_max_files_lists_omitted_files() {
+        let temp_dir = create_staged_repo();
+        let repo = Repository::open(temp_dir.path()).unwrap();
+        let summary = staged_summary(&repo, DEFAULT_MAX_BYTES_PER_FILE, 2).unwrap();
+        assert_eq!(summary.files.len(), 2);
+        assert_eq!(summary.omitted_files.len(), 2);
+        // Totals still cover every staged file
+        assert_eq!(summary.totals.files_changed, 4);
+        assert_eq!(summary.totals.additions, 42);
+    }
+
+    #[test]
+    fn test_single_oversized_hunk_is_cut_at_a_line() {
+        let mut hunk = SummaryHunk {
+            header: "@@ -1,3 +1,3 @@".to_string(),
+            patch: "+aaaa\n+bbbb\n+cccc\n".to_string(),
+            truncated: false,
+        };
+        truncate_hunk(&mut hunk, hunk.header.len() + 14);
+        assert_eq!(hunk.patch, "+aaaa\n+bbbb\n");
+        assert!(hunk.truncated);
+    }
+}
@@ -1,13 +1,78 @@
 use super::repository::get_current_branch;
-use super::types::{FileStatus, GitFileStatus, GitStatus};
-use git2::{Error as GitError, Repository, Status, StatusOptions};
+use super::sparse;
+use super::types::{DirectoryStatus, FileStatus, GitFileStatus, GitStatus};
+use git2::{DiffDelta, Error as GitError, Repository, Status, StatusEntry, StatusOptions};
+use std::collections::{HashMap, HashSet};
 
-/// Gets the Git status of the repository
-pub fn get_repository_status(repo: &Repository) -> Result<GitStatus, GitError> {
+/// Similarity (percent) for a deleted/added pair to count as a rename, as in git
+const RENAME_THRESHOLD: u16 = 50;
+
+fn status_options(detect_renames: bool) -> StatusOptions {
     let mut opts = StatusOptions::new();
     opts.include_untracked(true);
     opts.recurse_untracked_dirs(true);
+    if detect_renames {
+        opts.renames_head_to_index(true);
+        opts.renames_index_to_workdir(true);
+        opts.rename_threshold(RENAME_THRESHOLD);
+    }
+    opts
+}
+
+/// Paths with the SKIP_WORKTREE bit, read from the index only once a working-tree
+/// deletion shows up. Those entries are outside the sparse cone, not deleted.
+struct SkipWorktree<'r> {
+    repo: &'r Repository,
+    paths: Option<HashSet<String>>,
+}
+
+impl<'r> SkipWorktree<'r> {
+    fn new(repo: &'r Repository) -> Self {
+        Self { repo, paths: None }
+    }
+
+    /// The entry's status without a deletion git itself would not report
+    fn filter(&mut self, path: &str, mut status: Status) -> Status {
+        if status.is_wt_deleted() {
+            let repo = self.repo;
+            let skipped = self
+                .paths
+                .get_or_insert_with(|| sparse::skip_worktree_paths(repo).unwrap_or_default());
+            if skipped.contains(path) {
+                status.remove(Status::WT_DELETED);
+            }
+        }
+        status
+    }
+}
 
+/// Current path of an entry on the staged or unstaged side, and the path it was renamed
+/// from. `StatusEntry::path` reports the old path of a rename, so use the deltas.
+fn entry_paths(entry: &StatusEntry, staged: bool) -> (String, Option<String>) {
+    let (delta, renamed): (Option<DiffDelta>, bool) = if staged {
+        (entry.head_to_index(), entry.status().is_index_renamed())
+    } else {
+        (entry.index_to_workdir(), entry.status().is_wt_renamed())
+    };
+    let path_of = |file: git2::DiffFile| file.path().map(|p| p.to_string_lossy().to_string());
+    let new_path = delta
+        .as_ref()
+        .and_then(|d| path_of(d.new_file()))
+        .unwrap_or_else(|| entry.path().unwrap_or("").to_string());
+    let old_path = delta
+        .as_ref()
+        .filter(|_| renamed)
+        .and_then(|d| path_of(d.old_file()));
+    (new_path, old_path)
+}
+
+/// Gets the Git status of the repository. With `detect_renames`, deleted/added pairs in
+/// the index and in the working tree are reported as renames carrying `old_path`.
+pub fn get_repository_status(
+    repo: &Repository,
+    detect_renames: bool,
+) -> Result<GitStatus, GitError> {
+    let mut opts = status_options(detect_renames);
     let statuses = repo.statuses(Some(&mut opts))?;
 
     let mut modified = Vec::new();
@@ -15,9 +80,11 @@ pub fn get_repository_status(repo: &Repository) -> Result<GitStatus, GitError> {
     let mut untracked = Vec::new();
     let mut conflicted = Vec::new();
 
+    let mut skip_worktree = SkipWorktree::new(repo);
+
     for entry in statuses.iter() {
         let path = entry.path().unwrap_or("").to_string();
-        let status = entry.status();
+        let status = skip_worktree.filter(&path, entry.status());
 
         // Check for conflicts first
         if status.is_conflicted() {
@@ -34,10 +101,12 @@ pub fn get_repository_status(repo: &Repository) -> Result<GitStatus, GitError> {
                 | Status::INDEX_TYPECHANGE,
         ) {
             let git_status = status_to_git_file_status(status, true);
+            let (path, old_path) = entry_paths(&entry, true);
             staged.push(FileStatus {
-                path: path.clone(),
+                path,
                 status: git_status,
                 staged: true,
+                old_path,
             });
         }
 
@@ -46,10 +115,12 @@ pub fn get_repository_status(repo: &Repository) -> Result<GitStatus, GitError> {
             Status::WT_MODIFIED | Status::WT_DELETED | Status::WT_RENAMED | Status::WT_TYPECHANGE,
         ) {
             let git_status = status_to_git_file_status(status, false);
+            let (path, old_path) = entry_paths(&entry, false);
             modified.push(FileStatus {
-                path: path.clone(),
+                path,
                 status: git_status,
                 staged: false,
+                old_path,
             });
         }
 
@@ -75,25 +146,26 @@ pub fn get_repository_status(repo: &Repository) -> Result<GitStatus, GitError> {
 
 /// Converts git2::Status to GitFileStatus
 fn status_to_git_file_status(status: Status, is_staged: bool) -> GitFileStatus {
+    // Renames are checked first: a rename with edits also carries the modified flag
     if is_staged {
-        if status.is_index_new() {
+        if status.is_index_renamed() {
+            GitFileStatus::Renamed
+        } else if status.is_index_new() {
             GitFileStatus::Added
         } else if status.is_index_modified() {
             GitFileStatus::Modified
         } else if status.is_index_deleted() {
             GitFileStatus::Deleted
-        } else if status.is_index_renamed() {
-            GitFileStatus::Renamed
         } else {
             GitFileStatus::Modified
         }
     } else {
-        if status.is_wt_modified() {
+        if status.is_wt_renamed() {
+            GitFileStatus::Renamed
+        } else if status.is_wt_modified() {
             GitFileStatus::Modified
         } else if status.is_wt_deleted() {
             GitFileStatus::Deleted
-        } else if status.is_wt_renamed() {
-            GitFileStatus::Renamed
         } else if status.is_wt_new() {
             GitFileStatus::Untracked
         } else {
@@ -103,20 +175,20 @@ fn status_to_git_file_status(status: Status, is_staged: bool) -> GitFileStatus {
 }
 
 /// Gets a map of all file statuses in the repository
-/// Returns a map of file path to (status, is_staged)
+/// Returns a map of file path to (status, is_staged). Renamed files, with `detect_renames`,
+/// are keyed by their new path.
 pub fn get_all_file_statuses(
     repo: &Repository,
+    detect_renames: bool,
 ) -> Result<std::collections::HashMap<String, (GitFileStatus, bool)>, GitError> {
-    let mut opts = StatusOptions::new();
-    opts.include_untracked(true);
-    opts.recurse_untracked_dirs(true);
-
+    let mut opts = status_options(detect_renames);
     let statuses = repo.statuses(Some(&mut opts))?;
     let mut result = std::collections::HashMap::new();
+    let mut skip_worktree = SkipWorktree::new(repo);
 
     for entry in statuses.iter() {
         let path = entry.path().unwrap_or("").to_string();
-        let status = entry.status();
+        let status = skip_worktree.filter(&path, entry.status());
 
         if status.is_conflicted() {
             result.insert(path, (GitFileStatus::Conflicted, false));
@@ -131,10 +203,10 @@ pub fn get_all_file_statuses(
                 | Status::INDEX_RENAMED,
         ) {
             let git_status = status_to_git_file_status(status, true);
-            result.insert(path.clone(), (git_status, true));
+            result.insert(entry_paths(&entry, true).0, (git_status, true));
         } else if status.intersects(Status::WT_MODIFIED | Status::WT_DELETED | Status::WT_RENAMED) {
             let git_status = status_to_git_file_status(status, false);
-            result.insert(path.clone(), (git_status, false));
+            result.insert(entry_paths(&entry, false).0, (git_status, false));
         } else if status.is_wt_new() {
             result.insert(path, (GitFileStatus::Untracked, false));
         }
@@ -143,6 +215,74 @@ pub fn get_all_file_statuses(
     Ok(result)
 }
 
+/// Precedence of a status when decorating a directory; higher wins
+fn decoration_rank(status: &GitFileStatus) -> u8 {
+    match status {
+        GitFileStatus::Conflicted => 5,
+        GitFileStatus::Modified | GitFileStatus::Renamed => 4,
+        GitFileStatus::Deleted => 3,
+        GitFileStatus::Added => 2,
+        GitFileStatus::Untracked => 1,
+        GitFileStatus::Unmodified => 0,
+    }
+}
+
+impl DirectoryStatus {
+    fn add(&mut self, status: &GitFileStatus) {
+        match status {
+            GitFileStatus::Conflicted => self.conflicted += 1,
+            GitFileStatus::Modified | GitFileStatus::Renamed => self.modified += 1,
+            GitFileStatus::Deleted => self.deleted += 1,
+            GitFileStatus::Added => self.added += 1,
+            GitFileStatus::Untracked => self.untracked += 1,
+            GitFileStatus::Unmodified => return,
+        }
+        if decoration_rank(status) > decoration_rank(&self.dominant) {
+            self.dominant = status.clone();
+        }
+    }
+}
+
+/// Fold file statuses into their ancestor directories (repo-relative, "" for the root).
+/// Directories deeper than `max_depth` are left out; their files still count toward the
+/// ancestors that are kept.
+pub fn directory_rollup(
+    statuses: &HashMap<String, (GitFileStatus, bool)>,
+    max_depth: Option<usize>,
+) -> HashMap<String, DirectoryStatus> {
+    let max_depth = max_depth.unwrap_or(usize::MAX);
+    let mut rollup: HashMap<String, DirectoryStatus> = HashMap::new();
+    for (path, (status, _)) in statuses {
+        rollup.entry(String::new()).or_default().add(status);
+        // Every '/' ends an ancestor directory, except a trailing one on a directory entry
+        let ancestors = path
+            .trim_end_matches('/')
+            .match_indices('/')
+            .take(max_depth)
+            .map(|(end, _)| &path[..end]);
+        for dir in ancestors {
+            match rollup.get_mut(dir) {
+                Some(entry) => entry.add(status),
+                None => {
+                    let mut entry = DirectoryStatus::default();
+                    entry.add(status);
+                    rollup.insert(dir.to_string(), entry);
+                }
+            }
+        }
+    }
+    rollup
+}
+
+/// Per-directory rollup of `get_all_file_statuses`, from the same single status pass
+pub fn get_directory_rollup(
+    repo: &Repository,
+    max_depth: Option<usize>,
+) -> Result<HashMap<String, DirectoryStatus>, GitError> {
+    let statuses = get_all_file_statuses(repo, false)?;
+    Ok(directory_rollup(&statuses, max_depth))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -195,7 +335,7 @@ mod tests {
         let temp_dir = create_temp_git_repo_with_commit();
 
         let repo = Repository::open(temp_dir.path()).unwrap();
-        let status = get_repository_status(&repo).unwrap();
+        let status = get_repository_status(&repo, false).unwrap();
 
         assert!(status.modified.is_empty());
         assert!(status.staged.is_empty());
@@ -213,7 +353,7 @@ mod tests {
         std::fs::write(&readme, "# Modified content").unwrap();
 
         let repo = Repository::open(temp_dir.path()).unwrap();
-        let status = get_repository_status(&repo).unwrap();
+        let status = get_repository_status(&repo, false).unwrap();
 
         assert_eq!(status.modified.len(), 1);
         assert_eq!(status.modified[0].path, "README.md");
@@ -231,7 +371,7 @@ mod tests {
         std::fs::write(&new_file, "new content").unwrap();
 
         let repo = Repository::open(temp_dir.path()).unwrap();
-        let status = get_repository_status(&repo).unwrap();
+        let status = get_repository_status(&repo, false).unwrap();
 
         assert_eq!(status.untracked.len(), 1);
         assert_eq!(status.untracked[0], "new_file.txt");
@@ -253,7 +393,7 @@ mod tests {
             .unwrap();
 
         let repo = Repository::open(temp_dir.path()).unwrap();
-        let status = get_repository_status(&repo).unwrap();
+        let status = get_repository_status(&repo, false).unwrap();
 
         assert_eq!(status.staged.len(), 1);
         assert_eq!(status.staged[0].path, "staged.txt");
@@ -270,7 +410,7 @@ mod tests {
         std::fs::remove_file(&readme).unwrap();
 
         let repo = Repository::open(temp_dir.path()).unwrap();
-        let status = get_repository_status(&repo).unwrap();
+        let status = get_repository_status(&repo, false).unwrap();
 
         assert_eq!(status.modified.len(), 1);
         assert_eq!(status.modified[0].path, "README.md");
@@ -282,7 +422,7 @@ mod tests {
         let temp_dir = create_temp_git_repo_with_commit();
 
         let repo = Repository::open(temp_dir.path()).unwrap();
-        let statuses = get_all_file_statuses(&repo).unwrap();
+        let statuses = get_all_file_statuses(&repo, false).unwrap();
 
         assert!(statuses.is_empty());
     }
@@ -306,7 +446,7 @@ mod tests {
             .unwrap();
 
         let repo = Repository::open(temp_dir.path()).unwrap();
-        let statuses = get_all_file_statuses(&repo).unwrap();
+        let statuses = get_all_file_statuses(&repo, false).unwrap();
 
         assert_eq!(statuses.len(), 2);
 
@@ -321,6 +461,94 @@ mod tests {
         assert!(is_staged);
     }
 
+    /// README.md grown to a size where similarity scoring is meaningful, then committed
+    fn commit_longer_readme(dir: &std::path::Path) {
+        let content: String = (0..40).map(|i| format!("line {}\n", i)).collect();
+        std::fs::write(dir.join("README.md"), content).unwrap();
+        Command::new("git")
+            .args(["commit", "-am", "Longer readme"])
+            .current_dir(dir)
+            .output()
+            .unwrap();
+    }
+
+    #[test]
+    fn test_unstaged_rename_detection() {
+        let temp_dir = create_temp_git_repo_with_commit();
+        commit_longer_readme(temp_dir.path());
+        std::fs::rename(
+            temp_dir.path().join("README.md"),
+            temp_dir.path().join("GUIDE.md"),
+        )
+        .unwrap();
+        let repo = Repository::open(temp_dir.path()).unwrap();
+
+        let plain = get_repository_status(&repo, false).unwrap();
+        assert_eq!(plain.modified.len(), 1);
+        assert_eq!(plain.modified[0].path, "README.md");
+        assert!(matches!(plain.modified[0].status, GitFileStatus::Deleted));
+        assert!(plain.modified[0].old_path.is_none());
+        assert_eq!(plain.untracked, vec!["GUIDE.md".to_string()]);
+
+        let detected = get_repository_status(&repo, true).unwrap();
+        assert_eq!(detected.modified.len(), 1);
+        assert_eq!(detected.modified[0].path, "GUIDE.md");
+        assert!(matches!(
+            detected.modified[0].status,
+            GitFileStatus::Renamed
+        ));
+        assert_eq!(detected.modified[0].old_path.as_deref(), Some("README.md"));
+        assert!(detected.untracked.is_empty());
+        assert!(detected.staged.is_empty());
+
+        let statuses = get_all_file_statuses(&repo, true).unwrap();
+        assert_eq!(statuses.len(), 1);
+        let (status, is_staged) = statuses.get("GUIDE.md").unwrap();
+        assert!(matches!(status, GitFileStatus::Renamed));
+        assert!(!is_staged);
+    }
+
+    #[test]
+    fn test_staged_rename_detection() {
+        let temp_dir = create_temp_git_repo_with_commit();
+        commit_longer_readme(temp_dir.path());
+        Command::new("git")
+            .args(["mv", "README.md", "GUIDE.md"])
+            .current_dir(temp_dir.path())
+            .output()
+            .unwrap();
+        // Edit after the rename so the entry is renamed and modified
+        let guide = temp_dir.path().join("GUIDE.md");
+        let mut content = std::fs::read_to_string(&guide).unwrap();
+        content.push_str("one more line\n");
+        std::fs::write(&guide, content).unwrap();
+        Command::new("git")
+            .args(["add", "GUIDE.md"])
+            .current_dir(temp_dir.path())
+            .output()
+            .unwrap();
+        let repo = Repository::open(temp_dir.path()).unwrap();
+
+        let plain = get_repository_status(&repo, false).unwrap();
+        let mut plain_paths: Vec<&str> = plain.staged.iter().map(|f| f.path.as_str()).collect();
+        plain_paths.sort();
+        assert_eq!(plain_paths, vec!["GUIDE.md", "README.md"]);
+        assert!(plain.staged.iter().all(|f| f.old_path.is_none()));
+
+        let detected = get_repository_status(&repo, true).unwrap();
+        assert_eq!(detected.staged.len(), 1);
+        assert_eq!(detected.staged[0].path, "GUIDE.md");
+        assert!(matches!(detected.staged[0].status, GitFileStatus::Renamed));
+        assert_eq!(detected.staged[0].old_path.as_deref(), Some("README.md"));
+        assert!(detected.modified.is_empty());
+
+        let statuses = get_all_file_statuses(&repo, true).unwrap();
+        assert_eq!(statuses.len(), 1);
+        let (status, is_staged) = statuses.get("GUIDE.md").unwrap();
+        assert!(matches!(status, GitFileStatus::Renamed));
+        assert!(is_staged);
+    }
+
     #[test]
     fn test_status_to_git_file_status_staged() {
         // Test staged new file
@@ -360,10 +588,103 @@ mod tests {
         let temp_dir = create_temp_git_repo_with_commit();
 
         let repo = Repository::open(temp_dir.path()).unwrap();
-        let status = get_repository_status(&repo).unwrap();
+        let status = get_repository_status(&repo, false).unwrap();
 
         assert!(status.branch.is_some());
         let branch = status.branch.unwrap();
         assert!(branch.name == "main" || branch.name == "master");
     }
+
+    #[test]
+    fn test_directory_rollup_counts_and_depth() {
+        let statuses: HashMap<String, (GitFileStatus, bool)> = [
+            ("src/app/main.rs", GitFileStatus::Modified),
+            ("src/app/deep/nested/util.rs", GitFileStatus::Untracked),
+            ("src/app/deep/new.rs", GitFileStatus::Added),
+            ("src/lib.rs", GitFileStatus::Conflicted),
+            ("docs/guide.md", GitFileStatus::Untracked),
+            ("docs/old.md", GitFileStatus::Deleted),
+            ("README.md", GitFileStatus::Renamed),
+        ]
+        .into_iter()
+        .map(|(path, status)| (path.to_string(), (status, false)))
+        .collect();
+
+        let rollup = directory_rollup(&statuses, None);
+        let root = &rollup[""];
+        assert_eq!(
+            (
+                root.modified,
+                root.added,
+                root.deleted,
+                root.untracked,
+                root.conflicted
+            ),
+            (2, 1, 1, 2, 1)
+        );
+        assert_eq!(rollup["src"].modified, 1);
+        assert_eq!(rollup["src"].conflicted, 1);
+        assert_eq!(rollup["src/app"].untracked, 1);
+        assert_eq!(rollup["src/app/deep"].added, 1);
+        assert_eq!(rollup["src/app/deep/nested"].untracked, 1);
+        assert_eq!(rollup.len(), 6);
+
+        // Deeper directories are dropped but still counted by their ancestors
+        let shallow = directory_rollup(&statuses, Some(1));
+        let mut dirs: Vec<&str> = shallow.keys().map(String::as_str).collect();
+        dirs.sort();
+        assert_eq!(dirs, ["", "docs", "src"]);
+        assert_eq!(shallow["src"].untracked, 1);
+        assert_eq!(shallow["src"].added, 1);
+        assert_eq!(directory_rollup(&statuses, Some(0)).len(), 1);
+    }
+
+    #[test]
+    fn test_directory_rollup_dominant_status() {
+        let rollup = |files: &[(&str, GitFileStatus)]| {
+            let statuses: HashMap<String, (GitFileStatus, bool)> = files
+                .iter()
+                .map(|(path, status)| (path.to_string(), (status.clone(), false)))
+                .collect();
+            directory_rollup(&statuses, None)["dir"].dominant.clone()
+        };
+        use GitFileStatus::*;
+        assert_eq!(rollup(&[("dir/a", Untracked)]), Untracked);
+        assert_eq!(
+            rollup(&[("dir/a", Untracked), ("dir/b", Modified)]),
+            Modified
+        );
+        assert_eq!(
+            rollup(&[
+                ("dir/a", Modified),
+                ("dir/sub/b", Conflicted),
+                ("dir/c", Untracked)
+            ]),
+            Conflicted
+        );
+        assert_eq!(rollup(&[("dir/a", Added), ("dir/b", Deleted)]), Deleted);
+        assert_eq!(rollup(&[("dir/a", Renamed), ("dir/b", Added)]), Renamed);
+    }
+
+    #[test]
+    fn test_directory_rollup_matches_flat_statuses() {
+        let temp_dir = create_temp_git_repo_with_commit();
+        let root = temp_dir.path();
+        std::fs::create_dir_all(root.join("a/b")).unwrap();
+        std::fs::write(root.join("a/b/new.txt"), "new").unwrap();
+        std::fs::write(root.join("a/top.txt"), "top").unwrap();
+        std::fs::write(root.join("README.md"), "# Changed").unwrap();
+
+        let repo = Repository::open(root).unwrap();
+        let flat = get_all_file_statuses(&repo, false).unwrap();
+        let rollup = get_directory_rollup(&repo, None).unwrap();
+        assert_eq!(
+            rollup[""].modified + rollup[""].untracked,
+            flat.len() as u32
+        );
+        assert_eq!(rollup[""].dominant, GitFileStatus::Modified);
+        assert_eq!(rollup["a"].untracked, 2);
+        assert_eq!(rollup["a/b"].untracked, 1);
+        assert_eq!(rollup["a"].dominant, GitFileStatus::Untracked);
+    }
 }
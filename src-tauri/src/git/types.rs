@@ -1,10 +1,11 @@
 use serde::{Deserialize, Serialize};
 
 /// Represents the status of a file in Git
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub enum GitFileStatus {
     /// File is unmodified
+    #[default]
     Unmodified,
     /// File is modified in working directory
     Modified,
@@ -30,6 +31,36 @@ pub struct FileStatus {
     pub status: GitFileStatus,
     /// Whether the file is staged
     pub staged: bool,
+    /// Previous path of a renamed file. Only set when the status was requested with rename
+    /// detection, which is off by default: it compares the contents of every deleted and
+    /// added/untracked file pair, which gets slow on huge repositories.
+    #[serde(default)]
+    pub old_path: Option<String>,
+}
+
+fn is_zero(count: &u32) -> bool {
+    *count == 0
+}
+
+/// Changed files under a directory, for decorating it in the explorer. Zero counts are
+/// left out of the JSON.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DirectoryStatus {
+    /// Modified and renamed files
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub modified: u32,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub added: u32,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub deleted: u32,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub untracked: u32,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub conflicted: u32,
+    /// The status to color the directory with: conflicted, then modified, deleted, added
+    /// and untracked
+    pub dominant: GitFileStatus,
 }
 
 /// Represents information about a Git branch
@@ -204,6 +235,7 @@ mod tests {
             path: "src/main.rs".to_string(),
             status: GitFileStatus::Modified,
             staged: true,
+            old_path: None,
         };
 
         let json = serde_json::to_string(&file_status).unwrap();
@@ -252,6 +284,7 @@ mod tests {
                 path: "file.rs".to_string(),
                 status: GitFileStatus::Modified,
                 staged: false,
+                old_path: None,
             }],
             staged: vec![],
             untracked: vec!["new_file.txt".to_string()],
@@ -1,8 +1,10 @@
-use crate::constants::should_exclude_dir;
+use crate::exclusions::LiveExclusions;
+use crate::walk_depth::{self, DepthReport, DepthTracker};
 use ignore::WalkBuilder;
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::ffi::OsStr;
+use std::path::Path;
 use std::time::UNIX_EPOCH;
 
 /// Default maximum number of results to return from glob search
@@ -18,11 +20,62 @@ pub struct GlobResult {
     pub modified_time: u64,
 }
 
-pub struct HighPerformanceGlob {}
+/// Glob matches, and whether the walk stopped short of deeply nested paths
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GlobSearchResponse {
+    pub results: Vec<GlobResult>,
+    #[serde(flatten)]
+    pub depth: DepthReport,
+}
+
+pub struct HighPerformanceGlob {
+    /// Levels below the root to descend; the configured default when unset
+    max_depth: Option<usize>,
+    /// Also walk dotfiles and dot directories; .git stays excluded with the other
+    /// excluded directories
+    include_hidden: bool,
+    /// Descend into symlinked directories. Symlinked files match either way, and a link
+    /// back to an ancestor is never followed.
+    follow_symlinks: bool,
+}
+
+/// Describe a matched entry
+pub(crate) fn glob_result(path: &Path, path_str: String) -> GlobResult {
+    // Get canonical path (resolves symlinks) for security validation
+    // If canonicalize fails (e.g., broken symlink), use the original path
+    let canonical_path = path
+        .canonicalize()
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_else(|_| path_str.clone());
+
+    // Get modification time
+    let modified_time = path
+        .metadata()
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |duration| duration.as_secs());
+
+    GlobResult {
+        path: path_str,
+        canonical_path,
+        is_directory: path.is_dir(),
+        modified_time,
+    }
+}
+
+/// Sort by modification time (descending - most recent first)
+pub(crate) fn sort_by_modified_time(results: &mut [GlobResult]) {
+    results.par_sort_unstable_by(|a, b| b.modified_time.cmp(&a.modified_time));
+}
 
 impl Default for HighPerformanceGlob {
     fn default() -> Self {
-        Self {}
+        Self {
+            max_depth: None,
+            include_hidden: false,
+            follow_symlinks: false,
+        }
     }
 }
 
@@ -31,6 +84,21 @@ impl HighPerformanceGlob {
         Self::default()
     }
 
+    pub fn with_max_depth(mut self, max_depth: Option<usize>) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    pub fn with_include_hidden(mut self, include_hidden: bool) -> Self {
+        self.include_hidden = include_hidden;
+        self
+    }
+
+    pub fn with_follow_symlinks(mut self, follow_symlinks: bool) -> Self {
+        self.follow_symlinks = follow_symlinks;
+        self
+    }
+
     /// High-performance glob pattern matching with results sorted by modification time
     ///
     /// # Arguments
@@ -43,25 +111,39 @@ impl HighPerformanceGlob {
         root_path: &str,
         max_results: usize,
     ) -> Result<Vec<GlobResult>, String> {
+        self.search_files_by_glob_with_report(pattern, root_path, max_results)
+            .map(|response| response.results)
+    }
+
+    /// Like `search_files_by_glob`, also reporting whether the walk hit its depth limit
+    pub fn search_files_by_glob_with_report(
+        &self,
+        pattern: &str,
+        root_path: &str,
+        max_results: usize,
+    ) -> Result<GlobSearchResponse, String> {
         if pattern.trim().is_empty() {
-            return Ok(vec![]);
+            return Ok(GlobSearchResponse::default());
         }
 
         // Use sequential file collection with ignore crate for simplicity and correctness
         let mut walker_builder = WalkBuilder::new(root_path);
+        let exclusions = LiveExclusions::new();
+        let depth = DepthTracker::new(walk_depth::resolve(self.max_depth));
 
         walker_builder
-            .hidden(true)
+            .hidden(!self.include_hidden)
+            .follow_links(self.follow_symlinks)
             .git_ignore(true)
             .git_global(true)
             .git_exclude(true)
             .ignore(true)
             .parents(true)
-            .max_depth(Some(20))
-            .filter_entry(|entry| {
+            .max_depth(Some(depth.max_depth()))
+            .filter_entry(move |entry| {
                 if entry.path().is_dir() {
                     if let Some(name) = entry.path().file_name().and_then(OsStr::to_str) {
-                        return !should_exclude_dir(name);
+                        return !exclusions.excludes_dir(name);
                     }
                 }
                 true
@@ -83,53 +165,35 @@ impl HighPerformanceGlob {
                 }
 
                 let path = entry.path();
+                let is_dir = entry.file_type().is_some_and(|t| t.is_dir());
+                depth.observe(entry.depth(), path, is_dir);
                 let path_str = path.to_string_lossy().to_string();
 
                 // Use glob pattern matching
                 if self.matches_glob_pattern(&path_str, pattern, root_path) {
-                    // Get canonical path (resolves symlinks) for security validation
-                    // If canonicalize fails (e.g., broken symlink), use the original path
-                    let canonical_path = path
-                        .canonicalize()
-                        .map(|p| p.to_string_lossy().to_string())
-                        .unwrap_or_else(|_| path_str.clone());
-
-                    // Get modification time
-                    let modified_time = if let Ok(metadata) = path.metadata() {
-                        if let Ok(modified) = metadata.modified() {
-                            if let Ok(duration) = modified.duration_since(UNIX_EPOCH) {
-                                duration.as_secs()
-                            } else {
-                                0
-                            }
-                        } else {
-                            0
-                        }
-                    } else {
-                        0
-                    };
-
-                    results.push(GlobResult {
-                        path: path_str,
-                        canonical_path,
-                        is_directory: path.is_dir(),
-                        modified_time,
-                    });
+                    results.push(glob_result(path, path_str));
                 }
             }
         }
 
-        // Sort by modification time (descending - most recent first)
-        results.par_sort_unstable_by(|a, b| b.modified_time.cmp(&a.modified_time));
+        sort_by_modified_time(&mut results);
 
         // Ensure we don't exceed limit after sorting
         results.truncate(max_results);
 
-        Ok(results)
+        Ok(GlobSearchResponse {
+            results,
+            depth: depth.report(),
+        })
     }
 
     /// Match glob pattern against file path
-    fn matches_glob_pattern(&self, file_path: &str, pattern: &str, root_path: &str) -> bool {
+    pub(crate) fn matches_glob_pattern(
+        &self,
+        file_path: &str,
+        pattern: &str,
+        root_path: &str,
+    ) -> bool {
         // Convert absolute path to relative path for matching
         let relative_path = if file_path.starts_with(root_path) {
             let root_len = root_path.len();
@@ -308,17 +372,26 @@ impl HighPerformanceGlob {
     }
 }
 
+/// `depth_limited` in the response means directories below `max_depth` (or the
+/// configured default) were not searched. Dotfiles are only matched with `include_hidden`,
+/// and symlinked directories are only walked with `follow_symlinks`.
 #[tauri::command]
 pub fn search_files_by_glob(
     pattern: String,
     path: Option<String>,
     max_results: Option<usize>,
-) -> Result<Vec<GlobResult>, String> {
+    max_depth: Option<usize>,
+    include_hidden: Option<bool>,
+    follow_symlinks: Option<bool>,
+) -> Result<GlobSearchResponse, String> {
     let root_path = path.unwrap_or_else(|| ".".to_string());
     let limit = max_results.unwrap_or(DEFAULT_MAX_GLOB_RESULTS);
 
-    let glob = HighPerformanceGlob::new();
-    glob.search_files_by_glob(&pattern, &root_path, limit)
+    let glob = HighPerformanceGlob::new()
+        .with_max_depth(max_depth)
+        .with_include_hidden(include_hidden.unwrap_or(false))
+        .with_follow_symlinks(follow_symlinks.unwrap_or(false));
+    glob.search_files_by_glob_with_report(&pattern, &root_path, limit)
 }
 
 #[cfg(test)]
@@ -454,6 +527,92 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_include_hidden_matches_dot_directories_but_not_git() {
+        let temp_dir = create_test_directory();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join(".github/workflows")).unwrap();
+        fs::write(root.join(".github/workflows/ci.yml"), "on: push").unwrap();
+        fs::create_dir_all(root.join(".git")).unwrap();
+        fs::write(root.join(".git/hooks.yml"), "hooks").unwrap();
+        let paths = |glob: HighPerformanceGlob| -> Vec<String> {
+            glob.search_files_by_glob("**/*.yml", root.to_str().unwrap(), 1000)
+                .unwrap()
+                .into_iter()
+                .map(|result| result.path)
+                .collect()
+        };
+
+        assert!(paths(HighPerformanceGlob::new()).is_empty());
+        assert_eq!(
+            paths(HighPerformanceGlob::new().with_include_hidden(true)),
+            vec![root
+                .join(".github/workflows/ci.yml")
+                .to_string_lossy()
+                .to_string()]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_follow_symlinks_walks_linked_directories_without_looping() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("project");
+        let outside = temp_dir.path().join("outside");
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::create_dir_all(outside.join("pkg")).unwrap();
+        fs::write(root.join("src/main.rs"), "main").unwrap();
+        fs::write(outside.join("pkg/lib.rs"), "lib").unwrap();
+        std::os::unix::fs::symlink(&root, root.join("src/loop")).unwrap();
+        std::os::unix::fs::symlink(outside.join("pkg"), root.join("pkg")).unwrap();
+        let paths = |glob: HighPerformanceGlob| -> Vec<String> {
+            let mut paths: Vec<String> = glob
+                .search_files_by_glob("**/*.rs", root.to_str().unwrap(), 1000)
+                .unwrap()
+                .into_iter()
+                .map(|result| result.path)
+                .collect();
+            paths.sort();
+            paths
+        };
+        let under_root = |path: &str| root.join(path).to_string_lossy().to_string();
+
+        assert_eq!(
+            paths(HighPerformanceGlob::new()),
+            [under_root("src/main.rs")]
+        );
+        assert_eq!(
+            paths(HighPerformanceGlob::new().with_follow_symlinks(true)),
+            [under_root("pkg/lib.rs"), under_root("src/main.rs")]
+        );
+    }
+
+    #[test]
+    fn test_depth_limit_is_reported_and_configurable() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().to_str().unwrap();
+        crate::walk_depth::tests::nested_tree(temp_dir.path(), 25, "deep.ts");
+
+        // 20 levels, the old fixed limit, miss the file 26 levels down and say so
+        let limited = HighPerformanceGlob::new()
+            .with_max_depth(Some(20))
+            .search_files_by_glob_with_report("**/deep.ts", root, 100)
+            .unwrap();
+        assert!(limited.results.is_empty());
+        assert!(limited.depth.depth_limited);
+        assert_eq!(limited.depth.truncated_subtrees, 1);
+
+        let raised = HighPerformanceGlob::new()
+            .with_max_depth(Some(30))
+            .search_files_by_glob_with_report("**/deep.ts", root, 100)
+            .unwrap();
+        assert_eq!(raised.results.len(), 1);
+        assert!(!raised.depth.depth_limited);
+        let json = serde_json::to_value(&raised).unwrap();
+        assert_eq!(json["depth_limited"], false);
+        assert_eq!(json["truncated_subtrees"], 0);
+    }
+
     #[test]
     fn test_glob_result_serialization() {
         let result = GlobResult {
@@ -0,0 +1,413 @@
+// Multipart module
+// Builds multipart/form-data bodies for the HTTP proxy. File parts are streamed from disk
+// in chunks, so uploading a dataset or image never loads it into JS or Rust memory.
+
+use base64::Engine;
+use futures_util::stream::{self, Stream, StreamExt};
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use tokio::io::AsyncReadExt;
+
+/// Largest single part accepted
+pub const MAX_PART_BYTES: u64 = 512 * 1024 * 1024;
+/// Largest multipart body accepted, all parts included
+pub const MAX_TOTAL_BYTES: u64 = 1024 * 1024 * 1024;
+/// Read size when streaming file parts
+const FILE_CHUNK_SIZE: usize = 64 * 1024;
+
+/// One field of a multipart/form-data request. Exactly one of `text`, `base64` and
+/// `file_path` must be set.
+#[derive(Debug, Clone, Deserialize)]
+pub struct MultipartPart {
+    pub name: String,
+    pub filename: Option<String>,
+    pub content_type: Option<String>,
+    pub text: Option<String>,
+    pub base64: Option<String>,
+    /// Streamed from disk. Relative paths resolve against the request's root_path,
+    /// and with a root_path the file must be inside it.
+    pub file_path: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct MultipartLimits {
+    pub max_part_bytes: u64,
+    pub max_total_bytes: u64,
+}
+
+impl Default for MultipartLimits {
+    fn default() -> Self {
+        Self {
+            max_part_bytes: MAX_PART_BYTES,
+            max_total_bytes: MAX_TOTAL_BYTES,
+        }
+    }
+}
+
+enum PartData {
+    Bytes(Vec<u8>),
+    File { path: PathBuf, len: u64 },
+}
+
+struct PreparedPart {
+    header: String,
+    data: PartData,
+}
+
+impl PreparedPart {
+    fn data_len(&self) -> u64 {
+        match &self.data {
+            PartData::Bytes(bytes) => bytes.len() as u64,
+            PartData::File { len, .. } => *len,
+        }
+    }
+}
+
+/// A validated multipart body, ready to be streamed
+pub struct MultipartBody {
+    boundary: String,
+    parts: Vec<PreparedPart>,
+}
+
+impl MultipartBody {
+    /// Validate parts, resolve file paths and check size limits. Files are only opened
+    /// when the body is streamed.
+    pub fn prepare(
+        parts: Vec<MultipartPart>,
+        root_path: Option<&str>,
+        limits: MultipartLimits,
+    ) -> Result<Self, String> {
+        let boundary = format!(
+            "----TalkCodyBoundary{}",
+            rand::thread_rng()
+                .sample_iter(&Alphanumeric)
+                .take(24)
+                .map(char::from)
+                .collect::<String>()
+        );
+
+        let mut prepared = Vec::with_capacity(parts.len());
+        for part in parts {
+            let data = match (part.text, part.base64, part.file_path) {
+                (Some(text), None, None) => PartData::Bytes(text.into_bytes()),
+                (None, Some(encoded), None) => PartData::Bytes(
+                    base64::engine::general_purpose::STANDARD
+                        .decode(encoded.trim())
+                        .map_err(|e| format!("Part '{}' has invalid base64: {}", part.name, e))?,
+                ),
+                (None, None, Some(file_path)) => {
+                    let path = resolve_upload_path(&file_path, root_path)?;
+                    let metadata = std::fs::metadata(&path)
+                        .map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+                    if !metadata.is_file() {
+                        return Err(format!("Not a file: {}", path.display()));
+                    }
+                    PartData::File {
+                        path,
+                        len: metadata.len(),
+                    }
+                }
+                _ => {
+                    return Err(format!(
+                        "Part '{}' must have exactly one of text, base64 or file_path",
+                        part.name
+                    ))
+                }
+            };
+
+            let filename = part.filename.or_else(|| match &data {
+                PartData::File { path, .. } => path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string()),
+                PartData::Bytes(_) => None,
+            });
+            let mut header = format!(
+                "--{}\r\nContent-Disposition: form-data; name=\"{}\"",
+                boundary,
+                escape_header_value(&part.name)
+            );
+            if let Some(filename) = filename {
+                header.push_str(&format!(
+                    "; filename=\"{}\"",
+                    escape_header_value(&filename)
+                ));
+            }
+            header.push_str("\r\n");
+            if let Some(content_type) = part.content_type {
+                if content_type.contains(['\r', '\n']) {
+                    return Err(format!("Part '{}' has an invalid content type", part.name));
+                }
+                header.push_str(&format!("Content-Type: {}\r\n", content_type));
+            }
+            header.push_str("\r\n");
+
+            let part = PreparedPart { header, data };
+            if part.data_len() > limits.max_part_bytes {
+                return Err(format!(
+                    "Part '{}' is {} bytes, over the {} byte limit",
+                    part_name(&part.header),
+                    part.data_len(),
+                    limits.max_part_bytes
+                ));
+            }
+            prepared.push(part);
+        }
+
+        let body = Self {
+            boundary,
+            parts: prepared,
+        };
+        if body.content_length() > limits.max_total_bytes {
+            return Err(format!(
+                "Multipart body is {} bytes, over the {} byte limit",
+                body.content_length(),
+                limits.max_total_bytes
+            ));
+        }
+        Ok(body)
+    }
+
+    pub fn content_type(&self) -> String {
+        format!("multipart/form-data; boundary={}", self.boundary)
+    }
+
+    /// Exact encoded size, sent as Content-Length
+    pub fn content_length(&self) -> u64 {
+        let parts: u64 = self
+            .parts
+            .iter()
+            .map(|part| part.header.len() as u64 + part.data_len() + 2)
+            .sum();
+        parts + self.closing().len() as u64
+    }
+
+    fn closing(&self) -> String {
+        format!("--{}--\r\n", self.boundary)
+    }
+
+    /// Encode the body as a stream of chunks. A file that shrinks while it is being read
+    /// fails the upload rather than sending a body shorter than the declared length.
+    pub fn into_stream(self) -> impl Stream<Item = Result<Vec<u8>, std::io::Error>> + Send {
+        let closing = self.closing().into_bytes();
+        let parts = stream::iter(self.parts).flat_map(|part| {
+            let header = stream::once(async move { Ok(part.header.into_bytes()) });
+            let data = match part.data {
+                PartData::Bytes(bytes) => stream::once(async move { Ok(bytes) }).boxed(),
+                PartData::File { path, len } => file_chunks(path, len).boxed(),
+            };
+            let trailer = stream::once(async { Ok(b"\r\n".to_vec()) });
+            header.chain(data).chain(trailer)
+        });
+        parts.chain(stream::once(async move { Ok(closing) }))
+    }
+
+    pub fn into_body(self) -> reqwest::Body {
+        reqwest::Body::wrap_stream(self.into_stream())
+    }
+}
+
+fn file_chunks(
+    path: PathBuf,
+    len: u64,
+) -> impl Stream<Item = Result<Vec<u8>, std::io::Error>> + Send {
+    enum State {
+        Pending(PathBuf),
+        Reading(tokio::fs::File, u64),
+        Done,
+    }
+
+    stream::unfold(State::Pending(path), move |state| async move {
+        let (mut file, remaining) = match state {
+            State::Done => return None,
+            State::Pending(path) => match tokio::fs::File::open(&path).await {
+                Ok(file) => (file, len),
+                Err(e) => return Some((Err(e), State::Done)),
+            },
+            State::Reading(file, remaining) => (file, remaining),
+        };
+        if remaining == 0 {
+            return None;
+        }
+
+        let mut buf = vec![0u8; FILE_CHUNK_SIZE.min(remaining as usize)];
+        match file.read(&mut buf).await {
+            Ok(0) => Some((
+                Err(std::io::Error::new(
+                    std::io::ErrorKind::UnexpectedEof,
+                    "file shrank while uploading",
+                )),
+                State::Done,
+            )),
+            Ok(n) => {
+                buf.truncate(n);
+                Some((Ok(buf), State::Reading(file, remaining - n as u64)))
+            }
+            Err(e) => Some((Err(e), State::Done)),
+        }
+    })
+}
+
+/// Resolve a file part's path. With a root, relative paths are joined to it and the
+/// canonical result must stay inside the root; without one, only absolute paths are allowed.
+pub fn resolve_upload_path(file_path: &str, root_path: Option<&str>) -> Result<PathBuf, String> {
+    let path = Path::new(file_path);
+    match root_path {
+        Some(root) => {
+            let root = Path::new(root)
+                .canonicalize()
+                .map_err(|e| format!("Invalid root path {}: {}", root, e))?;
+            let candidate = if path.is_absolute() {
+                path.to_path_buf()
+            } else {
+                root.join(path)
+            };
+            let resolved = candidate
+                .canonicalize()
+                .map_err(|e| format!("Cannot read {}: {}", candidate.display(), e))?;
+            if !resolved.starts_with(&root) {
+                return Err(format!("File is outside the project: {}", file_path));
+            }
+            Ok(resolved)
+        }
+        None if path.is_absolute() => Ok(path.to_path_buf()),
+        None => Err(format!(
+            "Relative file path requires a root path: {}",
+            file_path
+        )),
+    }
+}
+
+/// Quote-safe value for Content-Disposition, following what browsers send
+fn escape_header_value(value: &str) -> String {
+    value
+        .replace('"', "%22")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+fn part_name(header: &str) -> &str {
+    header
+        .split("name=\"")
+        .nth(1)
+        .and_then(|rest| rest.split('"').next())
+        .unwrap_or("")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn part(name: &str) -> MultipartPart {
+        MultipartPart {
+            name: name.to_string(),
+            filename: None,
+            content_type: None,
+            text: None,
+            base64: None,
+            file_path: None,
+        }
+    }
+
+    async fn encode(body: MultipartBody) -> Vec<u8> {
+        let chunks: Vec<_> = body.into_stream().collect().await;
+        chunks.into_iter().flat_map(|c| c.unwrap()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_encodes_parts_with_exact_length() {
+        let temp_dir = TempDir::new().unwrap();
+        let data: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+        std::fs::write(temp_dir.path().join("data.bin"), &data).unwrap();
+
+        let parts = vec![
+            MultipartPart {
+                text: Some("fine-tune".to_string()),
+                ..part("purpose")
+            },
+            MultipartPart {
+                base64: Some("aGVsbG8=".to_string()),
+                filename: Some("hi \"there\".txt".to_string()),
+                ..part("inline")
+            },
+            MultipartPart {
+                file_path: Some("data.bin".to_string()),
+                content_type: Some("application/octet-stream".to_string()),
+                ..part("file")
+            },
+        ];
+        let body =
+            MultipartBody::prepare(parts, temp_dir.path().to_str(), MultipartLimits::default())
+                .unwrap();
+        let boundary = body.boundary.clone();
+        let length = body.content_length();
+        let encoded = encode(body).await;
+
+        assert_eq!(encoded.len() as u64, length);
+        let text = String::from_utf8_lossy(&encoded);
+        assert!(text.starts_with(&format!(
+            "--{}\r\nContent-Disposition: form-data; name=\"purpose\"\r\n\r\nfine-tune\r\n",
+            boundary
+        )));
+        assert!(text.contains("filename=\"hi %22there%22.txt\"\r\n\r\nhello\r\n"));
+        assert!(text.contains(
+            "name=\"file\"; filename=\"data.bin\"\r\nContent-Type: application/octet-stream\r\n\r\n"
+        ));
+        assert!(text.ends_with(&format!("\r\n--{}--\r\n", boundary)));
+        assert!(encoded.windows(data.len()).any(|w| w == data.as_slice()));
+    }
+
+    #[test]
+    fn test_rejects_invalid_parts_and_paths() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("project");
+        std::fs::create_dir(&root).unwrap();
+        std::fs::write(root.join("small.txt"), "12345").unwrap();
+        std::fs::write(temp_dir.path().join("secret.txt"), "shh").unwrap();
+        let root_str = root.to_str();
+        let limits = MultipartLimits::default();
+        let prepare =
+            |parts: Vec<MultipartPart>, limits| MultipartBody::prepare(parts, root_str, limits);
+
+        // Zero or several sources
+        assert!(prepare(vec![part("empty")], limits).is_err());
+        let both = MultipartPart {
+            text: Some("a".to_string()),
+            base64: Some("YQ==".to_string()),
+            ..part("both")
+        };
+        assert!(prepare(vec![both], limits).is_err());
+
+        // Paths escaping the root, relative or absolute
+        let escape = MultipartPart {
+            file_path: Some("../secret.txt".to_string()),
+            ..part("f")
+        };
+        let err = prepare(vec![escape], limits).err().unwrap();
+        assert!(err.contains("outside the project"), "{}", err);
+        let absolute = temp_dir.path().join("secret.txt");
+        assert!(resolve_upload_path(absolute.to_str().unwrap(), root_str).is_err());
+        assert!(resolve_upload_path(absolute.to_str().unwrap(), None).is_ok());
+        assert!(resolve_upload_path("small.txt", None).is_err());
+
+        // Per-part and total limits
+        let file = || MultipartPart {
+            file_path: Some("small.txt".to_string()),
+            ..part("f")
+        };
+        let tight_part = MultipartLimits {
+            max_part_bytes: 4,
+            max_total_bytes: MAX_TOTAL_BYTES,
+        };
+        let err = prepare(vec![file()], tight_part).err().unwrap();
+        assert!(err.contains("'f' is 5 bytes"), "{}", err);
+        let tight_total = MultipartLimits {
+            max_part_bytes: 5,
+            max_total_bytes: 200,
+        };
+        assert!(prepare(vec![file()], tight_total).is_ok());
+        assert!(prepare(vec![file(), file(), file()], tight_total).is_err());
+    }
+}
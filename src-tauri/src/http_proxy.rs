@@ -1,10 +1,14 @@
-use futures_util::StreamExt;
+use crate::events::{self, StreamResponsePayload};
+use crate::http_multipart::{MultipartBody, MultipartLimits, MultipartPart};
+use crate::http_sse::{SseMessage, SseParser};
+use futures_util::{Stream, StreamExt};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::net::{IpAddr, ToSocketAddrs};
 use std::sync::atomic::{AtomicU32, Ordering};
 use std::time::Duration;
-use tauri::Emitter;
 use tokio::time::timeout;
 use url::Url;
 
@@ -115,6 +119,14 @@ fn is_private_ip(ip: &IpAddr) -> bool {
     }
 }
 
+/// Per-chunk timeout for the streaming commands: a long stream stays open as long as
+/// data keeps arriving
+const STREAM_CHUNK_TIMEOUT: Duration = Duration::from_secs(300);
+/// Per-chunk timeout for proxy_fetch, whose callers expect a prompt answer
+const FETCH_CHUNK_TIMEOUT: Duration = Duration::from_secs(30);
+/// Cap on a body accumulated into a `ProxyResponse` when the request doesn't set one
+pub const DEFAULT_MAX_BODY_BYTES: usize = 64 * 1024 * 1024;
+
 #[derive(Debug, Deserialize)]
 pub struct ProxyRequest {
     pub url: String,
@@ -122,6 +134,18 @@ pub struct ProxyRequest {
     pub headers: HashMap<String, String>,
     pub body: Option<String>,
     pub request_id: Option<u32>,
+    /// multipart/form-data parts, sent instead of `body`
+    pub multipart: Option<Vec<MultipartPart>>,
+    /// Project root that multipart file parts must stay inside
+    pub root_path: Option<String>,
+    /// stream_fetch only: parse the body as server-sent events and emit framed events
+    /// instead of raw chunks
+    #[serde(default)]
+    pub parse_sse: bool,
+    /// proxy_fetch and proxy_fetch_stream: largest body to accumulate, in bytes.
+    /// Defaults to `DEFAULT_MAX_BODY_BYTES`.
+    #[serde(default)]
+    pub max_body_bytes: Option<usize>,
 }
 
 #[derive(Debug, Serialize)]
@@ -138,18 +162,277 @@ pub struct StreamResponse {
     pub headers: HashMap<String, String>,
 }
 
-#[derive(Clone, Serialize)]
+#[derive(Clone, Serialize, JsonSchema)]
 pub struct ChunkPayload {
     pub request_id: u32,
     pub chunk: Vec<u8>,
 }
 
-#[derive(Clone, Serialize)]
+/// A parsed server-sent event, or the `[DONE]` sentinel, when `parse_sse` is set
+#[derive(Clone, Serialize, JsonSchema)]
+pub struct SsePayload {
+    pub request_id: u32,
+    #[serde(flatten)]
+    pub message: SseMessage,
+}
+
+#[derive(Clone, Serialize, JsonSchema)]
 pub struct EndPayload {
     pub request_id: u32,
     pub status: u16,
 }
 
+/// Add the caller's headers and the request body: plain text, or a multipart form whose
+/// file parts are streamed from disk
+fn apply_headers_and_body(
+    mut req_builder: reqwest::RequestBuilder,
+    headers: HashMap<String, String>,
+    body: Option<String>,
+    multipart: Option<Vec<MultipartPart>>,
+    root_path: Option<&str>,
+) -> Result<reqwest::RequestBuilder, String> {
+    let multipart = match multipart {
+        Some(_) if body.is_some() => {
+            return Err("A request cannot have both a body and multipart parts".to_string())
+        }
+        Some(parts) => Some(MultipartBody::prepare(
+            parts,
+            root_path,
+            MultipartLimits::default(),
+        )?),
+        None => None,
+    };
+
+    for (key, value) in headers {
+        // The multipart encoder sets its own boundary and length
+        if multipart.is_some()
+            && (key.eq_ignore_ascii_case("content-type")
+                || key.eq_ignore_ascii_case("content-length"))
+        {
+            continue;
+        }
+        req_builder = req_builder.header(&key, &value);
+    }
+
+    if let Some(multipart) = multipart {
+        log::info!(
+            "Sending multipart body: {} bytes",
+            multipart.content_length()
+        );
+        req_builder = req_builder
+            .header(reqwest::header::CONTENT_TYPE, multipart.content_type())
+            .header(reqwest::header::CONTENT_LENGTH, multipart.content_length())
+            .body(multipart.into_body());
+    } else if let Some(body) = body {
+        req_builder = req_builder.body(body);
+    }
+    Ok(req_builder)
+}
+
+fn request_builder(
+    client: &reqwest::Client,
+    method: &str,
+    url: &str,
+) -> Result<reqwest::RequestBuilder, String> {
+    match method.to_uppercase().as_str() {
+        "GET" => Ok(client.get(url)),
+        "POST" => Ok(client.post(url)),
+        "PUT" => Ok(client.put(url)),
+        "DELETE" => Ok(client.delete(url)),
+        "PATCH" => Ok(client.patch(url)),
+        _ => Err(format!("Unsupported HTTP method: {}", method)),
+    }
+}
+
+fn response_headers(response: &reqwest::Response) -> HashMap<String, String> {
+    response
+        .headers()
+        .iter()
+        .filter_map(|(key, value)| Some((key.to_string(), value.to_str().ok()?.to_string())))
+        .collect()
+}
+
+/// Why reading a response body stopped before the end of the stream
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BodyError {
+    /// The accumulated body went over the cap
+    TooLarge {
+        limit: usize,
+        bytes_read: usize,
+    },
+    /// The body isn't UTF-8; `offset` is the first bad byte
+    InvalidUtf8 {
+        offset: usize,
+    },
+    /// No chunk arrived within the per-chunk timeout
+    Timeout {
+        secs: u64,
+        chunks: usize,
+    },
+    Read {
+        chunk: usize,
+        message: String,
+    },
+}
+
+impl std::fmt::Display for BodyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BodyError::TooLarge { limit, bytes_read } => write!(
+                f,
+                "Response body exceeded the {} byte limit ({} bytes read so far)",
+                limit, bytes_read
+            ),
+            BodyError::InvalidUtf8 { offset } => write!(
+                f,
+                "Failed to convert response to UTF-8: invalid data at byte {}",
+                offset
+            ),
+            BodyError::Timeout { secs, chunks } => write!(
+                f,
+                "Timeout: no data received for {} seconds after {} chunks",
+                secs, chunks
+            ),
+            BodyError::Read { chunk, message } => {
+                write!(f, "Error reading chunk {}: {}", chunk, message)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct BodyProgress {
+    chunks: usize,
+    bytes: usize,
+}
+
+/// The chunk loop shared by the proxy commands. Waits at most `chunk_timeout` for each
+/// chunk and hands it to `on_chunk`, which returns false to stop reading early.
+async fn read_body<S, B, E, F>(
+    stream: S,
+    chunk_timeout: Duration,
+    mut on_chunk: F,
+) -> Result<BodyProgress, BodyError>
+where
+    S: Stream<Item = Result<B, E>>,
+    B: AsRef<[u8]>,
+    E: std::fmt::Display,
+    F: FnMut(&[u8]) -> Result<bool, BodyError>,
+{
+    let mut stream = std::pin::pin!(stream);
+    let mut progress = BodyProgress::default();
+    loop {
+        match timeout(chunk_timeout, stream.next()).await {
+            Ok(Some(Ok(chunk))) => {
+                let chunk = chunk.as_ref();
+                progress.chunks += 1;
+                progress.bytes += chunk.len();
+                if !on_chunk(chunk)? {
+                    break;
+                }
+            }
+            Ok(Some(Err(e))) => {
+                return Err(BodyError::Read {
+                    chunk: progress.chunks + 1,
+                    message: e.to_string(),
+                })
+            }
+            Ok(None) => break,
+            Err(_) => {
+                return Err(BodyError::Timeout {
+                    secs: chunk_timeout.as_secs(),
+                    chunks: progress.chunks,
+                })
+            }
+        }
+    }
+    Ok(progress)
+}
+
+/// Builds a body as text up to a byte cap, validating UTF-8 chunk by chunk. The bytes
+/// of a character split across chunks wait in `carry` until the rest arrives.
+struct TextAccumulator {
+    text: String,
+    carry: Vec<u8>,
+    max_bytes: usize,
+    bytes_read: usize,
+}
+
+impl TextAccumulator {
+    fn new(max_bytes: usize) -> Self {
+        Self {
+            text: String::new(),
+            carry: Vec::new(),
+            max_bytes,
+            bytes_read: 0,
+        }
+    }
+
+    fn push(&mut self, chunk: &[u8]) -> Result<(), BodyError> {
+        self.bytes_read += chunk.len();
+        if self.bytes_read > self.max_bytes {
+            return Err(BodyError::TooLarge {
+                limit: self.max_bytes,
+                bytes_read: self.bytes_read,
+            });
+        }
+
+        let pending: Cow<[u8]> = if self.carry.is_empty() {
+            Cow::Borrowed(chunk)
+        } else {
+            let mut pending = std::mem::take(&mut self.carry);
+            pending.extend_from_slice(chunk);
+            Cow::Owned(pending)
+        };
+        match std::str::from_utf8(&pending) {
+            Ok(text) => self.text.push_str(text),
+            Err(e) => {
+                let (valid, rest) = pending.split_at(e.valid_up_to());
+                // Safe to unwrap: `valid_up_to` marks the end of the valid prefix
+                self.text.push_str(std::str::from_utf8(valid).unwrap());
+                if e.error_len().is_some() {
+                    return Err(BodyError::InvalidUtf8 {
+                        offset: self.bytes_read - rest.len(),
+                    });
+                }
+                // An incomplete character at the end of the chunk
+                self.carry = rest.to_vec();
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> Result<String, BodyError> {
+        if self.carry.is_empty() {
+            Ok(self.text)
+        } else {
+            Err(BodyError::InvalidUtf8 {
+                offset: self.bytes_read - self.carry.len(),
+            })
+        }
+    }
+}
+
+/// Read a whole body as text through the shared chunk loop
+async fn read_text_body<S, B, E>(
+    stream: S,
+    chunk_timeout: Duration,
+    max_bytes: usize,
+) -> Result<(String, BodyProgress), BodyError>
+where
+    S: Stream<Item = Result<B, E>>,
+    B: AsRef<[u8]>,
+    E: std::fmt::Display,
+{
+    let mut text = TextAccumulator::new(max_bytes);
+    let progress = read_body(stream, chunk_timeout, |chunk| {
+        text.push(chunk)?;
+        Ok(true)
+    })
+    .await?;
+    Ok((text.finish()?, progress))
+}
+
 #[tauri::command]
 pub async fn proxy_fetch(request: ProxyRequest) -> Result<ProxyResponse, String> {
     log::info!("Proxy fetch request to: {} {}", request.method, request.url);
@@ -158,26 +441,16 @@ pub async fn proxy_fetch(request: ProxyRequest) -> Result<ProxyResponse, String>
     validate_url(&request.url)?;
 
     let client = reqwest::Client::new();
+    let req_builder = request_builder(&client, &request.method, &request.url)?;
 
-    // Build the request
-    let mut req_builder = match request.method.to_uppercase().as_str() {
-        "GET" => client.get(&request.url),
-        "POST" => client.post(&request.url),
-        "PUT" => client.put(&request.url),
-        "DELETE" => client.delete(&request.url),
-        "PATCH" => client.patch(&request.url),
-        _ => return Err(format!("Unsupported HTTP method: {}", request.method)),
-    };
-
-    // Add headers
-    for (key, value) in request.headers {
-        req_builder = req_builder.header(&key, &value);
-    }
-
-    // Add body if present
-    if let Some(body) = request.body {
-        req_builder = req_builder.body(body);
-    }
+    // Add headers and body (plain or multipart)
+    let req_builder = apply_headers_and_body(
+        req_builder,
+        request.headers,
+        request.body,
+        request.multipart,
+        request.root_path.as_deref(),
+    )?;
 
     // Send request
     let response = req_builder.send().await.map_err(|e| {
@@ -195,48 +468,14 @@ pub async fn proxy_fetch(request: ProxyRequest) -> Result<ProxyResponse, String>
         );
     }
 
-    // Extract headers
-    let mut headers = HashMap::new();
-    for (key, value) in response.headers() {
-        if let Ok(value_str) = value.to_str() {
-            headers.insert(key.to_string(), value_str.to_string());
-        }
-    }
+    let headers = response_headers(&response);
+    let max_bytes = request.max_body_bytes.unwrap_or(DEFAULT_MAX_BODY_BYTES);
 
-    // Log critical response headers for debugging
-    let _content_type = response
-        .headers()
-        .get("content-type")
-        .and_then(|v| v.to_str().ok())
-        .unwrap_or("none");
-    let _transfer_encoding = response
-        .headers()
-        .get("transfer-encoding")
-        .and_then(|v| v.to_str().ok())
-        .unwrap_or("none");
-    let _content_length = response
-        .headers()
-        .get("content-length")
-        .and_then(|v| v.to_str().ok())
-        .unwrap_or("none");
-
-    let read_timeout = Duration::from_secs(30);
-
-    let body = timeout(read_timeout, response.text())
+    let (body, _) = read_text_body(response.bytes_stream(), FETCH_CHUNK_TIMEOUT, max_bytes)
         .await
-        .map_err(|_| {
-            log::error!(
-                "Timeout reading response body after {} seconds",
-                read_timeout.as_secs()
-            );
-            format!(
-                "Timeout reading response body after {} seconds",
-                read_timeout.as_secs()
-            )
-        })?
         .map_err(|e| {
             log::error!("Failed to read response body: {}", e);
-            format!("Failed to read response body: {}", e)
+            e.to_string()
         })?;
 
     Ok(ProxyResponse {
@@ -260,26 +499,16 @@ pub async fn proxy_fetch_stream(request: ProxyRequest) -> Result<ProxyResponse,
     validate_url(&request.url)?;
 
     let client = reqwest::Client::new();
+    let req_builder = request_builder(&client, &request.method, &request.url)?;
 
-    // Build the request
-    let mut req_builder = match request.method.to_uppercase().as_str() {
-        "GET" => client.get(&request.url),
-        "POST" => client.post(&request.url),
-        "PUT" => client.put(&request.url),
-        "DELETE" => client.delete(&request.url),
-        "PATCH" => client.patch(&request.url),
-        _ => return Err(format!("Unsupported HTTP method: {}", request.method)),
-    };
-
-    // Add headers
-    for (key, value) in request.headers {
-        req_builder = req_builder.header(&key, &value);
-    }
-
-    // Add body if present
-    if let Some(body) = request.body {
-        req_builder = req_builder.body(body);
-    }
+    // Add headers and body (plain or multipart)
+    let req_builder = apply_headers_and_body(
+        req_builder,
+        request.headers,
+        request.body,
+        request.multipart,
+        request.root_path.as_deref(),
+    )?;
 
     // Send request
     let response = req_builder.send().await.map_err(|e| {
@@ -290,91 +519,29 @@ pub async fn proxy_fetch_stream(request: ProxyRequest) -> Result<ProxyResponse,
     let status = response.status().as_u16();
     log::info!("Proxy fetch (streaming) response status: {}", status);
 
-    // Extract headers
-    let mut headers = HashMap::new();
-    for (key, value) in response.headers() {
-        if let Ok(value_str) = value.to_str() {
-            headers.insert(key.to_string(), value_str.to_string());
-        }
-    }
+    let headers = response_headers(&response);
 
     // Log critical response headers for debugging
-    let content_type = response
-        .headers()
-        .get("content-type")
-        .and_then(|v| v.to_str().ok())
-        .unwrap_or("none");
-    let transfer_encoding = response
-        .headers()
-        .get("transfer-encoding")
-        .and_then(|v| v.to_str().ok())
-        .unwrap_or("none");
-    let content_length = response
-        .headers()
-        .get("content-length")
-        .and_then(|v| v.to_str().ok())
-        .unwrap_or("none");
-
+    let header = |name: &str| headers.get(name).map(String::as_str).unwrap_or("none");
     log::info!(
         "Streaming response headers - Content-Type: {}, Transfer-Encoding: {}, Content-Length: {}",
-        content_type,
-        transfer_encoding,
-        content_length
+        header("content-type"),
+        header("transfer-encoding"),
+        header("content-length")
     );
 
-    // Get response body using bytes_stream for better streaming support
-    log::info!("Starting to read response body in chunks...");
-
-    // Use per-chunk timeout instead of total timeout
-    // This allows long-running streams as long as data keeps arriving
-    let chunk_timeout = Duration::from_secs(300);
-
-    let mut body_chunks = Vec::new();
-    let mut stream = response.bytes_stream();
-    let mut chunk_count = 0;
-
-    loop {
-        // Wait for next chunk with timeout
-        let chunk_result = timeout(chunk_timeout, stream.next()).await;
-
-        match chunk_result {
-            Ok(Some(Ok(chunk))) => {
-                chunk_count += 1;
-                // log::info!("Received chunk {}: {} bytes", chunk_count, chunk.len());
-                body_chunks.extend_from_slice(&chunk);
-            }
-            Ok(Some(Err(e))) => {
-                log::error!("Error reading chunk {}: {}", chunk_count + 1, e);
-                return Err(format!("Error reading chunk: {}", e));
-            }
-            Ok(None) => {
-                // Stream ended normally
-                log::info!("Stream ended after {} chunks", chunk_count);
-                break;
-            }
-            Err(_) => {
-                // Timeout waiting for next chunk
-                log::error!(
-                    "Timeout waiting for chunk {} after {} seconds (no data received)",
-                    chunk_count + 1,
-                    chunk_timeout.as_secs()
-                );
-                return Err(format!(
-                    "Timeout: no data received for {} seconds after {} chunks",
-                    chunk_timeout.as_secs(),
-                    chunk_count
-                ));
-            }
-        }
-    }
-
-    let body = String::from_utf8(body_chunks)
-        .map_err(|e| format!("Failed to convert response to UTF-8: {}", e))?;
+    let max_bytes = request.max_body_bytes.unwrap_or(DEFAULT_MAX_BODY_BYTES);
+    let (body, progress) = read_text_body(response.bytes_stream(), STREAM_CHUNK_TIMEOUT, max_bytes)
+        .await
+        .map_err(|e| {
+            log::error!("Proxy fetch (streaming) body error: {}", e);
+            e.to_string()
+        })?;
 
     log::info!(
         "Streaming response complete - Total chunks: {}, Total size: {} bytes",
-        chunk_count,
-        body.len()
+        progress.chunks,
+        progress.bytes
     );
 
     Ok(ProxyResponse {
@@ -394,14 +561,14 @@ pub async fn stream_fetch(
     let request_id = request
         .request_id
         .unwrap_or_else(|| REQUEST_COUNTER.fetch_add(1, Ordering::SeqCst));
-    // Use request-specific event name to avoid global event broadcasting
-    let event_name = format!("stream-response-{}", request_id);
+    let parse_sse = request.parse_sse;
 
     log::info!(
-        "Stream fetch request to: {} {} (request_id: {})",
+        "Stream fetch request to: {} {} (request_id: {}, parse_sse: {})",
         request.method,
         request.url,
-        request_id
+        request_id,
+        parse_sse
     );
 
     // Validate URL to prevent SSRF attacks
@@ -411,26 +578,16 @@ pub async fn stream_fetch(
         .connect_timeout(Duration::from_secs(10))
         .build()
         .map_err(|e| format!("Failed to build client: {}", e))?;
+    let req_builder = request_builder(&client, &request.method, &request.url)?;
 
-    // Build the request
-    let mut req_builder = match request.method.to_uppercase().as_str() {
-        "GET" => client.get(&request.url),
-        "POST" => client.post(&request.url),
-        "PUT" => client.put(&request.url),
-        "DELETE" => client.delete(&request.url),
-        "PATCH" => client.patch(&request.url),
-        _ => return Err(format!("Unsupported HTTP method: {}", request.method)),
-    };
-
-    // Add headers
-    for (key, value) in request.headers {
-        req_builder = req_builder.header(&key, &value);
-    }
-
-    // Add body if present
-    if let Some(body) = request.body {
-        req_builder = req_builder.body(body);
-    }
+    // Add headers and body (plain or multipart)
+    let req_builder = apply_headers_and_body(
+        req_builder,
+        request.headers,
+        request.body,
+        request.multipart,
+        request.root_path.as_deref(),
+    )?;
 
     // Send request
     let response = req_builder.send().await.map_err(|e| {
@@ -447,86 +604,76 @@ pub async fn stream_fetch(
         );
     }
 
-    // Extract headers
-    let mut headers = HashMap::new();
-    for (key, value) in response.headers() {
-        if let Ok(value_str) = value.to_str() {
-            headers.insert(key.to_string(), value_str.to_string());
-        }
-    }
-
-    // Log response headers
-    let _content_type = response
-        .headers()
-        .get("content-type")
-        .and_then(|v| v.to_str().ok())
-        .unwrap_or("none");
+    let headers = response_headers(&response);
 
     // Spawn async task to stream chunks
     let window_clone = window.clone();
-    let event_name_clone = event_name.clone();
     tauri::async_runtime::spawn(async move {
-        let mut stream = response.bytes_stream();
-        let chunk_timeout = Duration::from_secs(300);
-        let mut chunk_count = 0;
-
-        loop {
-            let chunk_result = timeout(chunk_timeout, stream.next()).await;
-
-            match chunk_result {
-                Ok(Some(Ok(chunk))) => {
-                    chunk_count += 1;
-                    let _chunk_size = chunk.len();
-
-                    // Emit chunk to frontend using request-specific event
-                    if let Err(e) = window_clone.emit(
-                        &event_name_clone,
-                        ChunkPayload {
-                            request_id,
-                            chunk: chunk.to_vec(),
-                        },
-                    ) {
-                        log::error!(
-                            "Failed to emit chunk {} (request_id: {}): {:?}",
-                            chunk_count,
-                            request_id,
-                            e
-                        );
-                        break;
-                    }
-                }
-                Ok(Some(Err(e))) => {
+        let mut sse_parser = parse_sse.then(SseParser::new);
+
+        // Emit parsed events; false when the window is gone
+        let emit_sse = |messages: Vec<SseMessage>| {
+            for message in messages {
+                if let Err(e) = events::emit_stream_response(
+                    &window_clone,
+                    request_id,
+                    StreamResponsePayload::Sse(SsePayload {
+                        request_id,
+                        message,
+                    }),
+                ) {
                     log::error!(
-                        "Error reading chunk {} (request_id: {}): {}",
-                        chunk_count + 1,
+                        "Failed to emit SSE event (request_id: {}): {:?}",
                         request_id,
                         e
                     );
-                    break;
-                }
-                Ok(None) => {
-                    break;
-                }
-                Err(_) => {
-                    // Timeout waiting for next chunk
-                    log::error!(
-                        "Timeout waiting for chunk {} after {} seconds (request_id: {})",
-                        chunk_count + 1,
-                        chunk_timeout.as_secs(),
-                        request_id
-                    );
-                    break;
+                    return false;
                 }
             }
+            true
+        };
+
+        let read = read_body(response.bytes_stream(), STREAM_CHUNK_TIMEOUT, |chunk| {
+            if let Some(parser) = sse_parser.as_mut() {
+                return Ok(emit_sse(parser.feed(chunk)));
+            }
+
+            // Emit chunk to frontend using request-specific event
+            if let Err(e) = events::emit_stream_response(
+                &window_clone,
+                request_id,
+                StreamResponsePayload::Chunk(ChunkPayload {
+                    request_id,
+                    chunk: chunk.to_vec(),
+                }),
+            ) {
+                log::error!("Failed to emit chunk (request_id: {}): {:?}", request_id, e);
+                return Ok(false);
+            }
+            Ok(true)
+        })
+        .await;
+        if let Err(e) = read {
+            log::error!(
+                "Stream fetch body error (request_id: {}): {}",
+                request_id,
+                e
+            );
+        }
+
+        // Flush an event the server did not terminate with a blank line
+        if let Some(parser) = sse_parser.as_mut() {
+            emit_sse(parser.finish());
         }
 
         // Emit end signal
-        if let Err(e) = window_clone.emit(
-            &event_name_clone,
-            EndPayload {
+        if let Err(e) = events::emit_stream_response(
+            &window_clone,
+            request_id,
+            StreamResponsePayload::End(EndPayload {
                 request_id,
                 status: 0,
-            },
+            }),
         ) {
             log::error!(
                 "Failed to emit end payload (request_id: {}): {:?}",
@@ -754,6 +901,231 @@ mod tests {
         assert_eq!(request.url, "https://api.example.com/data");
         assert_eq!(request.method, "GET");
         assert!(request.body.is_none());
+        assert!(!request.parse_sse);
+    }
+
+    fn header_value<'a>(headers: &'a str, name: &str) -> Option<&'a str> {
+        headers.lines().find_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            key.eq_ignore_ascii_case(name).then(|| value.trim())
+        })
+    }
+
+    /// Accept one request, reply 200 and hand back (headers, body)
+    fn spawn_capture_server() -> (String, std::sync::mpsc::Receiver<(String, Vec<u8>)>) {
+        use std::io::{Read, Write};
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/upload", listener.local_addr().unwrap());
+        let (tx, rx) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut data = Vec::new();
+            let mut buf = [0u8; 8192];
+            let header_end = loop {
+                let n = stream.read(&mut buf).unwrap();
+                data.extend_from_slice(&buf[..n]);
+                if let Some(pos) = data.windows(4).position(|w| w == b"\r\n\r\n") {
+                    break pos + 4;
+                }
+            };
+            let headers = String::from_utf8_lossy(&data[..header_end]).to_string();
+            let length: usize = header_value(&headers, "content-length")
+                .and_then(|v| v.trim().parse().ok())
+                .expect("request without content-length");
+            while data.len() < header_end + length {
+                let n = stream.read(&mut buf).unwrap();
+                data.extend_from_slice(&buf[..n]);
+            }
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok")
+                .unwrap();
+            tx.send((headers, data[header_end..].to_vec())).unwrap();
+        });
+        (url, rx)
+    }
+
+    #[tokio::test]
+    async fn test_proxy_fetch_multipart_upload() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("train.jsonl"), "{\"prompt\":\"hi\"}\n").unwrap();
+        let (url, rx) = spawn_capture_server();
+
+        let request: ProxyRequest = serde_json::from_value(serde_json::json!({
+            "url": url,
+            "method": "POST",
+            "headers": {"Content-Type": "application/json", "Authorization": "Bearer k"},
+            "root_path": temp_dir.path().to_str().unwrap(),
+            "multipart": [
+                {"name": "purpose", "text": "fine-tune"},
+                {"name": "image", "filename": "dot.png", "content_type": "image/png", "base64": "iVBORw=="},
+                {"name": "file", "file_path": "train.jsonl", "content_type": "application/jsonl"}
+            ]
+        }))
+        .unwrap();
+        let response = proxy_fetch(request).await.unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body, "ok");
+
+        let (headers, body) = rx.recv().unwrap();
+        assert_eq!(header_value(&headers, "authorization"), Some("Bearer k"));
+        let content_type = header_value(&headers, "content-type").unwrap();
+        let boundary = content_type
+            .strip_prefix("multipart/form-data; boundary=")
+            .unwrap();
+
+        // Split the received body into (headers, content) per part
+        let body = String::from_utf8_lossy(&body).to_string();
+        let delimiter = format!("--{}", boundary);
+        let parts: Vec<(String, String)> = body
+            .split(delimiter.as_str())
+            .filter(|s| !s.is_empty() && *s != "--\r\n")
+            .map(|s| {
+                let (head, content) = s.trim_start_matches("\r\n").split_once("\r\n\r\n").unwrap();
+                (
+                    head.to_string(),
+                    content.trim_end_matches("\r\n").to_string(),
+                )
+            })
+            .collect();
+        assert_eq!(parts.len(), 3);
+        assert_eq!(
+            parts[0],
+            (
+                "Content-Disposition: form-data; name=\"purpose\"".to_string(),
+                "fine-tune".to_string()
+            )
+        );
+        assert!(parts[1]
+            .0
+            .contains("filename=\"dot.png\"\r\nContent-Type: image/png"));
+        // 0x89 isn't UTF-8, so it comes back as a replacement character
+        assert_eq!(parts[1].1, "\u{FFFD}PNG");
+        assert!(parts[2]
+            .0
+            .contains("name=\"file\"; filename=\"train.jsonl\""));
+        assert_eq!(parts[2].1, "{\"prompt\":\"hi\"}\n");
+    }
+
+    #[tokio::test]
+    async fn test_proxy_fetch_rejects_body_with_multipart() {
+        let request: ProxyRequest = serde_json::from_value(serde_json::json!({
+            "url": "http://127.0.0.1:9/upload",
+            "method": "POST",
+            "headers": {},
+            "body": "raw",
+            "multipart": [{"name": "purpose", "text": "fine-tune"}]
+        }))
+        .unwrap();
+        let err = proxy_fetch(request).await.unwrap_err();
+        assert!(err.contains("both a body and multipart"), "{}", err);
+    }
+
+    #[test]
+    fn test_text_accumulator_joins_characters_split_across_chunks() {
+        let text = "aé€😀z";
+        let bytes = text.as_bytes();
+        // Every way of cutting the body in two, including mid-character
+        for cut in 0..=bytes.len() {
+            let mut acc = TextAccumulator::new(1024);
+            acc.push(&bytes[..cut]).unwrap();
+            acc.push(&bytes[cut..]).unwrap();
+            assert_eq!(acc.finish().unwrap(), text, "cut at {}", cut);
+        }
+
+        // One byte per chunk: the emoji's four bytes wait in the carry buffer
+        let mut acc = TextAccumulator::new(1024);
+        for byte in bytes {
+            acc.push(std::slice::from_ref(byte)).unwrap();
+        }
+        assert_eq!(acc.finish().unwrap(), text);
+    }
+
+    #[test]
+    fn test_text_accumulator_reports_invalid_utf8() {
+        let mut acc = TextAccumulator::new(1024);
+        acc.push(b"ok").unwrap();
+        assert_eq!(
+            acc.push(&[b'!', 0xFF, b'x']),
+            Err(BodyError::InvalidUtf8 { offset: 3 })
+        );
+
+        // A character cut off by the end of the body
+        let mut acc = TextAccumulator::new(1024);
+        acc.push(&"é".as_bytes()[..1]).unwrap();
+        assert_eq!(acc.finish(), Err(BodyError::InvalidUtf8 { offset: 0 }));
+    }
+
+    #[tokio::test]
+    async fn test_read_text_body_cap_error() {
+        let chunks: Vec<Result<Vec<u8>, String>> =
+            vec![Ok(vec![b'a'; 6]), Ok(vec![b'b'; 6]), Ok(vec![b'c'; 6])];
+        let err = read_text_body(
+            futures_util::stream::iter(chunks),
+            Duration::from_secs(1),
+            10,
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(
+            err,
+            BodyError::TooLarge {
+                limit: 10,
+                bytes_read: 12,
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "Response body exceeded the 10 byte limit (12 bytes read so far)"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_read_text_body_counts_chunks_and_surfaces_errors() {
+        let chunks: Vec<Result<&[u8], String>> = vec![Ok(&b"data: "[..]), Ok(&b"hi\n\n"[..])];
+        let (body, progress) = read_text_body(
+            futures_util::stream::iter(chunks),
+            Duration::from_secs(1),
+            DEFAULT_MAX_BODY_BYTES,
+        )
+        .await
+        .unwrap();
+        assert_eq!(body, "data: hi\n\n");
+        assert_eq!(
+            progress,
+            BodyProgress {
+                chunks: 2,
+                bytes: 10,
+            }
+        );
+
+        let chunks: Vec<Result<&[u8], String>> = vec![Ok(&b"x"[..]), Err("reset".to_string())];
+        let err = read_text_body(
+            futures_util::stream::iter(chunks),
+            Duration::from_secs(1),
+            DEFAULT_MAX_BODY_BYTES,
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.to_string(), "Error reading chunk 2: reset");
+    }
+
+    #[tokio::test]
+    async fn test_read_body_times_out_per_chunk() {
+        let stream = futures_util::stream::iter(vec![Ok::<_, String>(b"x".to_vec())])
+            .chain(futures_util::stream::pending());
+        let err = read_body(stream, Duration::from_millis(20), |_| Ok(true))
+            .await
+            .unwrap_err();
+        assert_eq!(err, BodyError::Timeout { secs: 0, chunks: 1 });
+    }
+
+    #[test]
+    fn test_proxy_request_max_body_bytes() {
+        let request: ProxyRequest = serde_json::from_str(
+            r#"{"url": "https://example.com", "method": "GET", "headers": {}, "max_body_bytes": 1024}"#,
+        )
+        .unwrap();
+        assert_eq!(request.max_body_bytes, Some(1024));
     }
 
     #[test]
@@ -800,6 +1172,18 @@ mod tests {
         assert!(json.contains("\"chunk\":[72,101,108,108,111]"));
     }
 
+    #[test]
+    fn test_sse_payload_serialization() {
+        let payload = SsePayload {
+            request_id: 5,
+            message: SseMessage::Done,
+        };
+
+        let json = serde_json::to_string(&payload).unwrap();
+        assert!(json.contains("\"request_id\":5"));
+        assert!(json.contains("\"kind\":\"done\""));
+    }
+
     #[test]
     fn test_end_payload_serialization() {
         let payload = EndPayload {
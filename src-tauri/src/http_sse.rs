@@ -0,0 +1,342 @@
+// Server-sent events module
+// Incremental text/event-stream parser for the streaming proxy, so frontend consumers get
+// framed events instead of re-implementing SSE over raw byte chunks. Lines are split on
+// bytes before decoding, which keeps UTF-8 characters split across chunks intact.
+
+use schemars::JsonSchema;
+use serde::Serialize;
+
+/// Data payload OpenAI-compatible APIs send to mark the end of a stream
+pub const DONE_SENTINEL: &str = "[DONE]";
+
+const DEFAULT_EVENT_TYPE: &str = "message";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct SseEvent {
+    /// Event type, "message" unless the server set one
+    pub event: String,
+    /// Data lines joined with "\n"
+    pub data: String,
+    /// Last event id seen on the stream, which carries over to later events
+    pub id: Option<String>,
+    /// Reconnection time in ms, when a retry field arrived since the previous event
+    pub retry: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SseMessage {
+    Event(SseEvent),
+    /// An event whose data is the `[DONE]` sentinel
+    Done,
+}
+
+/// Feed response bytes in whatever chunks they arrive; complete events come out.
+#[derive(Debug, Default)]
+pub struct SseParser {
+    /// Bytes of the current, unterminated line
+    buffer: Vec<u8>,
+    /// The previous chunk ended in CR, so a leading LF belongs to that line ending
+    skip_lf: bool,
+    /// A line has been processed, so a BOM is no longer expected
+    started: bool,
+    event_type: Option<String>,
+    data: Vec<String>,
+    last_id: Option<String>,
+    retry: Option<u64>,
+}
+
+impl SseParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn feed(&mut self, bytes: &[u8]) -> Vec<SseMessage> {
+        let mut messages = Vec::new();
+        for &byte in bytes {
+            if std::mem::take(&mut self.skip_lf) && byte == b'\n' {
+                continue;
+            }
+            match byte {
+                b'\n' | b'\r' => {
+                    let line = std::mem::take(&mut self.buffer);
+                    self.process_line(&line, &mut messages);
+                    self.skip_lf = byte == b'\r';
+                }
+                _ => self.buffer.push(byte),
+            }
+        }
+        messages
+    }
+
+    /// End of stream. Unlike the spec, which discards an event missing its closing
+    /// blank line, the pending event is dispatched: some providers close the connection
+    /// straight after the last data line.
+    pub fn finish(&mut self) -> Vec<SseMessage> {
+        let mut messages = Vec::new();
+        if !self.buffer.is_empty() {
+            let line = std::mem::take(&mut self.buffer);
+            self.process_line(&line, &mut messages);
+        }
+        self.dispatch(&mut messages);
+        messages
+    }
+
+    fn process_line(&mut self, line: &[u8], messages: &mut Vec<SseMessage>) {
+        let decoded = String::from_utf8_lossy(line);
+        let mut line: &str = &decoded;
+        if !std::mem::replace(&mut self.started, true) {
+            line = line.strip_prefix('\u{feff}').unwrap_or(line);
+        }
+
+        if line.is_empty() {
+            self.dispatch(messages);
+            return;
+        }
+        if line.starts_with(':') {
+            // Comment, e.g. ": keepalive"
+            return;
+        }
+
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line, ""),
+        };
+        match field {
+            "event" => self.event_type = Some(value.to_string()),
+            "data" => self.data.push(value.to_string()),
+            "id" if !value.contains('\0') => self.last_id = Some(value.to_string()),
+            "retry" if !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit()) => {
+                self.retry = value.parse().ok();
+            }
+            _ => {}
+        }
+    }
+
+    fn dispatch(&mut self, messages: &mut Vec<SseMessage>) {
+        let event_type = self.event_type.take();
+        if self.data.is_empty() {
+            return;
+        }
+        let data = std::mem::take(&mut self.data).join("\n");
+        if data.trim() == DONE_SENTINEL {
+            messages.push(SseMessage::Done);
+            return;
+        }
+        messages.push(SseMessage::Event(SseEvent {
+            event: event_type.unwrap_or_else(|| DEFAULT_EVENT_TYPE.to_string()),
+            data,
+            id: self.last_id.clone(),
+            retry: self.retry.take(),
+        }));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_all(input: &[u8]) -> Vec<SseMessage> {
+        let mut parser = SseParser::new();
+        let mut messages = parser.feed(input);
+        messages.extend(parser.finish());
+        messages
+    }
+
+    fn event(data: &str) -> SseMessage {
+        SseMessage::Event(SseEvent {
+            event: "message".to_string(),
+            data: data.to_string(),
+            id: None,
+            retry: None,
+        })
+    }
+
+    #[test]
+    fn test_single_event() {
+        assert_eq!(parse_all(b"data: hello\n\n"), vec![event("hello")]);
+    }
+
+    #[test]
+    fn test_event_is_emitted_once_blank_line_arrives() {
+        let mut parser = SseParser::new();
+        assert!(parser.feed(b"data: hello\n").is_empty());
+        assert_eq!(parser.feed(b"\n"), vec![event("hello")]);
+        assert!(parser.finish().is_empty());
+    }
+
+    #[test]
+    fn test_multiple_data_lines_are_joined() {
+        assert_eq!(
+            parse_all(b"data: first\ndata: second\ndata:\ndata: third\n\n"),
+            vec![event("first\nsecond\n\nthird")]
+        );
+    }
+
+    #[test]
+    fn test_comments_are_ignored() {
+        assert_eq!(
+            parse_all(b": keepalive\n\n:\ndata: a\n: inside an event\ndata: b\n\n"),
+            vec![event("a\nb")]
+        );
+    }
+
+    #[test]
+    fn test_event_type_id_and_retry() {
+        let messages = parse_all(b"event: delta\nid: 7\nretry: 3000\ndata: {\"x\":1}\n\n");
+        assert_eq!(
+            messages,
+            vec![SseMessage::Event(SseEvent {
+                event: "delta".to_string(),
+                data: "{\"x\":1}".to_string(),
+                id: Some("7".to_string()),
+                retry: Some(3000),
+            })]
+        );
+    }
+
+    #[test]
+    fn test_id_carries_over_and_event_type_and_retry_do_not() {
+        let messages = parse_all(b"event: a\nid: 1\nretry: 10\ndata: x\n\ndata: y\n\n");
+        let SseMessage::Event(second) = &messages[1] else {
+            panic!("expected an event");
+        };
+        assert_eq!(second.event, "message");
+        assert_eq!(second.id.as_deref(), Some("1"));
+        assert_eq!(second.retry, None);
+    }
+
+    #[test]
+    fn test_invalid_retry_and_id_with_nul_are_ignored() {
+        let messages = parse_all(b"id: ok\nretry: 1s\ndata: x\n\nid: bad\0id\ndata: y\n\n");
+        let SseMessage::Event(second) = &messages[1] else {
+            panic!("expected an event");
+        };
+        assert_eq!(second.id.as_deref(), Some("ok"));
+        assert_eq!(second.retry, None);
+    }
+
+    #[test]
+    fn test_blank_lines_without_data_do_not_dispatch() {
+        assert_eq!(
+            parse_all(b"\n\nevent: ping\n\n\ndata: z\n\n"),
+            vec![event("z")]
+        );
+    }
+
+    #[test]
+    fn test_field_without_colon_and_single_space_stripping() {
+        assert_eq!(parse_all(b"data\n\n"), vec![event("")]);
+        assert_eq!(
+            parse_all(b"data:  two spaces\n\n"),
+            vec![event(" two spaces")]
+        );
+        assert_eq!(parse_all(b"data:no space\n\n"), vec![event("no space")]);
+        assert_eq!(parse_all(b"data: a:b\n\n"), vec![event("a:b")]);
+    }
+
+    #[test]
+    fn test_unknown_fields_are_ignored() {
+        assert_eq!(parse_all(b"foo: bar\ndata: x\n\n"), vec![event("x")]);
+    }
+
+    #[test]
+    fn test_crlf_and_cr_line_endings() {
+        assert_eq!(
+            parse_all(b"data: a\r\ndata: b\r\n\r\n"),
+            vec![event("a\nb")]
+        );
+        assert_eq!(parse_all(b"data: a\rdata: b\r\r"), vec![event("a\nb")]);
+    }
+
+    #[test]
+    fn test_crlf_split_across_chunks() {
+        let mut parser = SseParser::new();
+        let mut messages = parser.feed(b"data: a\r");
+        messages.extend(parser.feed(b"\ndata: b\r"));
+        messages.extend(parser.feed(b"\n\r"));
+        messages.extend(parser.feed(b"\n"));
+        assert_eq!(messages, vec![event("a\nb")]);
+    }
+
+    #[test]
+    fn test_utf8_split_across_chunks() {
+        let input = "data: héllo 世界 🎉\n\n".as_bytes();
+        let split = input.iter().position(|&b| b == 0xF0).unwrap() + 2;
+        let mut parser = SseParser::new();
+        let mut messages = parser.feed(&input[..split]);
+        messages.extend(parser.feed(&input[split..]));
+        assert_eq!(messages, vec![event("héllo 世界 🎉")]);
+    }
+
+    #[test]
+    fn test_every_split_point_matches_unsplit_parse() {
+        let input = "\u{feff}: keepalive\r\nevent: delta\r\nid: 42\r\ndata: 你好\r\ndata: {\"a\": 1}\r\n\r\ndata: é\rretry: 5\r\r\ndata: [DONE]\n\n"
+            .as_bytes();
+        let expected = parse_all(input);
+        assert_eq!(expected.len(), 3);
+        assert_eq!(expected[2], SseMessage::Done);
+
+        for split in 0..=input.len() {
+            let mut parser = SseParser::new();
+            let mut messages = parser.feed(&input[..split]);
+            messages.extend(parser.feed(&input[split..]));
+            messages.extend(parser.finish());
+            assert_eq!(messages, expected, "split at byte {}", split);
+        }
+
+        let mut parser = SseParser::new();
+        let mut messages = Vec::new();
+        for byte in input {
+            messages.extend(parser.feed(std::slice::from_ref(byte)));
+        }
+        messages.extend(parser.finish());
+        assert_eq!(messages, expected);
+    }
+
+    #[test]
+    fn test_done_sentinel_is_a_distinct_signal() {
+        assert_eq!(
+            parse_all(b"data: {\"choices\":[]}\n\ndata: [DONE]\n\n"),
+            vec![event("{\"choices\":[]}"), SseMessage::Done]
+        );
+        // Only an event whose whole data is the sentinel counts
+        assert_eq!(
+            parse_all(b"data: [DONE] soon\n\n"),
+            vec![event("[DONE] soon")]
+        );
+    }
+
+    #[test]
+    fn test_bom_is_stripped_only_at_stream_start() {
+        assert_eq!(
+            parse_all("\u{feff}data: x\n\n".as_bytes()),
+            vec![event("x")]
+        );
+        assert_eq!(
+            parse_all("data: \u{feff}x\n\n".as_bytes()),
+            vec![event("\u{feff}x")]
+        );
+    }
+
+    #[test]
+    fn test_finish_dispatches_unterminated_event() {
+        let mut parser = SseParser::new();
+        assert!(parser.feed(b"data: tail").is_empty());
+        assert_eq!(parser.finish(), vec![event("tail")]);
+
+        let mut empty = SseParser::new();
+        assert!(empty.finish().is_empty());
+    }
+
+    #[test]
+    fn test_message_serialization() {
+        let json = serde_json::to_value(event("hi")).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({"kind": "event", "event": "message", "data": "hi", "id": null, "retry": null})
+        );
+        let done = serde_json::to_value(SseMessage::Done).unwrap();
+        assert_eq!(done, serde_json::json!({"kind": "done"}));
+    }
+}
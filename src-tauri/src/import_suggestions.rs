@@ -0,0 +1,350 @@
+// Import suggestions module
+// Suggests where a symbol the user typed without an import can be imported from. Candidates
+// are indexed definitions other modules can import (exported in JS/TS, module-level in
+// Python, pub in Rust), ranked by proximity to the current file like definition lookups,
+// each with the import statement ready to insert. Module paths are best-effort: Python
+// follows the __init__.py package chain and Rust maps files under src/ to `crate::` paths.
+
+use crate::code_navigation::{CodeNavState, CodeNavigationService, SymbolInfo, VisibilityFilter};
+use crate::extract_selection::js_module_specifier;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Component, Path};
+use tauri::State;
+
+const DEFAULT_MAX_SUGGESTIONS: usize = 10;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportSuggestion {
+    pub symbol: SymbolInfo,
+    /// Relative path (JS/TS), dotted module (Python) or module path (Rust)
+    pub module_specifier: String,
+    pub import_statement: String,
+}
+
+/// Importable definitions of `symbol_name`, closest to `current_file` first. `visibility`
+/// narrows them further, e.g. to leave out `pub(crate)` items.
+pub fn suggest_imports(
+    service: &CodeNavigationService,
+    symbol_name: &str,
+    lang_family: &str,
+    current_file: &str,
+    root_path: &str,
+    max_results: usize,
+    visibility: Option<VisibilityFilter>,
+) -> Result<Vec<ImportSuggestion>, String> {
+    if !matches!(lang_family, "js_family" | "python" | "rust") {
+        return Err(format!(
+            "Import suggestions are not supported for {}",
+            lang_family
+        ));
+    }
+    let current = Path::new(current_file);
+    let quote = if lang_family == "js_family" {
+        js_quote(current)
+    } else {
+        '\''
+    };
+    let mut seen = HashSet::new();
+    let suggestions = service
+        .find_definition_ranked(symbol_name, lang_family, Some(current_file))
+        .into_iter()
+        // Definitions in the current file need no import
+        .filter(|symbol| symbol.exported && Path::new(&symbol.file_path) != current)
+        .filter(|symbol| visibility.is_none_or(|filter| filter.accepts(symbol)))
+        .filter_map(|symbol| {
+            let file = Path::new(&symbol.file_path);
+            let (module_specifier, import_statement) = match lang_family {
+                "js_family" => {
+                    let specifier = js_specifier(current, file);
+                    let statement = format!(
+                        "import {{ {} }} from {}{}{};",
+                        symbol.name, quote, specifier, quote
+                    );
+                    (specifier, statement)
+                }
+                "python" => {
+                    let module = python_module_path(file, Path::new(root_path))?;
+                    let statement = format!("from {} import {}", module, symbol.name);
+                    (module, statement)
+                }
+                _ => {
+                    let module = rust_module_path(file, current)?;
+                    let statement = format!("use {}::{};", module, symbol.name);
+                    (module, statement)
+                }
+            };
+            Some(ImportSuggestion {
+                symbol,
+                module_specifier,
+                import_statement,
+            })
+        })
+        .filter(|suggestion| seen.insert(suggestion.import_statement.clone()))
+        .take(max_results)
+        .collect();
+    Ok(suggestions)
+}
+
+/// The quote the current file already uses in imports, single by default
+fn js_quote(current_file: &Path) -> char {
+    match std::fs::read_to_string(current_file) {
+        Ok(content) if content.contains("from \"") => '"',
+        _ => '\'',
+    }
+}
+
+/// Relative specifier without the extension, except for component files whose imports keep
+/// it. `dir/index` is imported as `dir`.
+fn js_specifier(current_file: &Path, definition_file: &Path) -> String {
+    let from_dir = current_file.parent().unwrap_or(Path::new(""));
+    let extension = definition_file.extension().and_then(|e| e.to_str());
+    if let Some(ext @ ("vue" | "svelte")) = extension {
+        let specifier = js_module_specifier(from_dir, definition_file);
+        return format!("{}.{}", specifier, ext);
+    }
+    let specifier = js_module_specifier(from_dir, definition_file);
+    match specifier.strip_suffix("/index") {
+        Some(dir) => dir.to_string(),
+        None => specifier,
+    }
+}
+
+/// Dotted module of a Python file. Inside a package (a directory with __init__.py) the path
+/// starts at the outermost package; otherwise it is relative to the project root.
+fn python_module_path(file: &Path, root: &Path) -> Option<String> {
+    let mut base = file.parent()?;
+    while base.join("__init__.py").is_file() && base != root {
+        match base.parent() {
+            Some(parent) if parent.starts_with(root) => base = parent,
+            _ => break,
+        }
+    }
+    let relative = file.with_extension("");
+    let relative = relative.strip_prefix(base).ok()?;
+    let mut parts: Vec<String> = relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect();
+    if parts.last().is_some_and(|last| last == "__init__") {
+        parts.pop();
+    }
+    (!parts.is_empty()).then(|| parts.join("."))
+}
+
+/// `crate::`-style path of a Rust file under its crate's src/. Other crates are named by
+/// their package name. Files outside src/ (tests, examples, build scripts) give None.
+fn rust_module_path(file: &Path, current_file: &Path) -> Option<String> {
+    let definition_crate = crate_root(file)?;
+    let relative = file.strip_prefix(definition_crate.join("src")).ok()?;
+    let mut parts: Vec<String> = relative
+        .with_extension("")
+        .components()
+        .filter_map(|c| match c {
+            Component::Normal(part) => Some(part.to_string_lossy().to_string()),
+            _ => None,
+        })
+        .collect();
+    if parts.len() == 1 && matches!(parts[0].as_str(), "lib" | "main") {
+        parts.clear();
+    } else if parts.last().is_some_and(|last| last == "mod") {
+        parts.pop();
+    }
+    let prefix = if crate_root(current_file).as_deref() == Some(definition_crate.as_path()) {
+        "crate".to_string()
+    } else {
+        crate_name(&definition_crate)?
+    };
+    Some(
+        std::iter::once(prefix)
+            .chain(parts)
+            .collect::<Vec<_>>()
+            .join("::"),
+    )
+}
+
+/// Nearest ancestor directory with a Cargo.toml
+fn crate_root(file: &Path) -> Option<std::path::PathBuf> {
+    file.ancestors()
+        .skip(1)
+        .find(|dir| dir.join("Cargo.toml").is_file())
+        .map(Path::to_path_buf)
+}
+
+/// Package name as used in paths, e.g. `my-lib` is `my_lib`
+fn crate_name(crate_root: &Path) -> Option<String> {
+    let content = std::fs::read_to_string(crate_root.join("Cargo.toml")).ok()?;
+    let manifest: toml::Table = content.parse().ok()?;
+    let name = manifest.get("package")?.get("name")?.as_str()?;
+    Some(name.replace('-', "_"))
+}
+
+#[tauri::command]
+pub async fn code_nav_suggest_imports(
+    state: State<'_, CodeNavState>,
+    symbol_name: String,
+    lang_family: String,
+    current_file: String,
+    root_path: String,
+    max_results: Option<usize>,
+    visibility: Option<VisibilityFilter>,
+) -> Result<Vec<ImportSuggestion>, String> {
+    let service = state
+        .0
+        .read()
+        .map_err(|e| format!("Failed to acquire read lock: {}", e))?;
+    suggest_imports(
+        &service,
+        &symbol_name,
+        &lang_family,
+        &current_file,
+        &root_path,
+        max_results.unwrap_or(DEFAULT_MAX_SUGGESTIONS),
+        visibility,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn write(root: &Path, rel: &str, content: &str) -> String {
+        let path = root.join(rel);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, content).unwrap();
+        path.to_string_lossy().to_string()
+    }
+
+    fn statements(
+        service: &CodeNavigationService,
+        name: &str,
+        lang_family: &str,
+        current_file: &str,
+        root: &Path,
+    ) -> Vec<String> {
+        suggest_imports(
+            service,
+            name,
+            lang_family,
+            current_file,
+            root.to_str().unwrap(),
+            10,
+            None,
+        )
+        .unwrap()
+        .into_iter()
+        .map(|s| s.import_statement)
+        .collect()
+    }
+
+    #[test]
+    fn test_js_family_suggestions() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        let mut service = CodeNavigationService::new();
+        let files = [
+            ("src/utils/format.ts", "export function formatDate() {}\n"),
+            ("src/utils/index.ts", "export const formatDate = () => 1;\n"),
+            ("src/legacy/format.js", "function formatDate() {}\n"),
+            (
+                "src/components/DatePicker.vue",
+                "<script lang=\"ts\">\nexport function formatDate() {}\n</script>\n",
+            ),
+        ];
+        for (rel, content) in files {
+            let path = write(root, rel, content);
+            let lang = match rel.rsplit('.').next().unwrap() {
+                "vue" => "vue",
+                "js" => "javascript",
+                _ => "typescript",
+            };
+            service.index_file(&path, content, lang).unwrap();
+        }
+        let current = write(root, "src/components/Calendar.tsx", "formatDate();\n");
+
+        // The unexported legacy function is not suggested; the nearest file comes first
+        assert_eq!(
+            statements(&service, "formatDate", "js_family", &current, root),
+            [
+                "import { formatDate } from './DatePicker.vue';",
+                "import { formatDate } from '../utils/format';",
+                "import { formatDate } from '../utils';",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_python_suggestions() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        let mut service = CodeNavigationService::new();
+        write(root, "src/app/__init__.py", "");
+        write(root, "src/app/core/__init__.py", "");
+        let files = [
+            ("src/app/core/models.py", "class User:\n    pass\n"),
+            (
+                "src/app/core/__init__.py",
+                "@dataclass\nclass User:\n    pass\n",
+            ),
+            (
+                "scripts/seed.py",
+                "def main():\n    class User:\n        pass\n",
+            ),
+        ];
+        for (rel, content) in files {
+            let path = write(root, rel, content);
+            service.index_file(&path, content, "python").unwrap();
+        }
+        let current = write(root, "src/app/views.py", "User()\n");
+
+        // Classes nested in functions are not importable
+        assert_eq!(
+            statements(&service, "User", "python", &current, root),
+            [
+                "from app.core.models import User",
+                "from app.core import User"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rust_suggestions() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        let mut service = CodeNavigationService::new();
+        write(root, "app/Cargo.toml", "[package]\nname = \"app\"\n");
+        write(
+            root,
+            "shared-types/Cargo.toml",
+            "[package]\nname = \"shared-types\"\n",
+        );
+        let files = [
+            ("app/src/config/mod.rs", "pub struct Settings {}\n"),
+            (
+                "app/src/config/loader.rs",
+                "pub(crate) struct Settings {}\n",
+            ),
+            ("app/src/legacy.rs", "struct Settings {}\n"),
+            ("shared-types/src/lib.rs", "pub struct Settings {}\n"),
+            ("shared-types/tests/fixtures.rs", "pub struct Settings {}\n"),
+        ];
+        for (rel, content) in files {
+            let path = write(root, rel, content);
+            service.index_file(&path, content, "rust").unwrap();
+        }
+        let current = write(root, "app/src/main.rs", "fn main() { Settings {}; }\n");
+
+        assert_eq!(
+            statements(&service, "Settings", "rust", &current, root),
+            [
+                "use crate::config::Settings;",
+                "use crate::config::loader::Settings;",
+                "use shared_types::Settings;",
+            ]
+        );
+        assert!(suggest_imports(&service, "Settings", "go", &current, "/", 10, None).is_err());
+    }
+}
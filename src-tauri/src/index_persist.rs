@@ -0,0 +1,438 @@
+// Index persistence module
+// Cooperative I/O for saving and loading the code navigation index. Reading, parsing,
+// serializing and writing run in chunks that report "index-persist-progress" events and
+// poll a cancellation token, so a slow disk (e.g. a network home directory) shows progress
+// and can be abandoned. Loads also run under an overall timeout: a stalled filesystem
+// falls back to rebuilding the index instead of hanging.
+
+use crate::cancellation::{self, CancellationToken};
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{self, BufReader, Read, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Loads taking longer than this are abandoned and the index is rebuilt
+pub const LOAD_TIMEOUT: Duration = Duration::from_secs(90);
+
+/// Bytes read or written between cancellation checks and progress reports
+const CHUNK_BYTES: usize = 256 * 1024;
+
+/// Progress updates within this interval are merged, as for activities
+const PROGRESS_EMIT_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum PersistPhase {
+    Serializing,
+    Writing,
+    Reading,
+    Parsing,
+    Merging,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PersistProgress {
+    pub operation_id: Option<String>,
+    pub root_path: String,
+    pub phase: PersistPhase,
+    /// Bytes processed, or files while merging
+    pub processed: u64,
+    pub total: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PhaseTiming {
+    pub phase: PersistPhase,
+    pub ms: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PersistError {
+    Cancelled,
+    TimedOut,
+    Failed(String),
+}
+
+impl std::fmt::Display for PersistError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PersistError::Cancelled => write!(f, "cancelled"),
+            PersistError::TimedOut => write!(f, "timed out"),
+            PersistError::Failed(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+/// Progress, cancellation and timings of one save or load. With an operation id the token
+/// is registered in the cancellation registry, so activity_cancel stops the work.
+pub struct PersistControl {
+    root_path: String,
+    operation_id: Option<String>,
+    token: CancellationToken,
+    started: Instant,
+    last_emit: Mutex<Option<(PersistPhase, Instant)>>,
+    timings: Mutex<Vec<PhaseTiming>>,
+}
+
+impl PersistControl {
+    pub fn new(root_path: &str, operation_id: Option<String>) -> Self {
+        let token = match &operation_id {
+            Some(id) => cancellation::register(id),
+            None => CancellationToken::new(),
+        };
+        Self {
+            root_path: root_path.to_string(),
+            operation_id,
+            token,
+            started: Instant::now(),
+            last_emit: Mutex::new(None),
+            timings: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn cancel(&self) {
+        self.token.cancel();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.token.is_cancelled()
+    }
+
+    /// Emit progress, at most once per interval unless the phase changed
+    pub fn report(&self, phase: PersistPhase, processed: u64, total: Option<u64>) {
+        {
+            let Ok(mut last_emit) = self.last_emit.lock() else {
+                return;
+            };
+            if let Some((last_phase, at)) = *last_emit {
+                if last_phase == phase && at.elapsed() < PROGRESS_EMIT_INTERVAL {
+                    return;
+                }
+            }
+            *last_emit = Some((phase, Instant::now()));
+        }
+        let Some(app_handle) = crate::try_get_app_handle() else {
+            return;
+        };
+        let progress = PersistProgress {
+            operation_id: self.operation_id.clone(),
+            root_path: self.root_path.clone(),
+            phase,
+            processed,
+            total,
+        };
+        if let Err(e) = crate::events::emit_index_persist_progress(app_handle, &progress) {
+            log::error!("Failed to emit index persist progress: {}", e);
+        }
+    }
+
+    /// Run one phase, recording how long it took
+    pub fn timed<T>(&self, phase: PersistPhase, f: impl FnOnce() -> T) -> T {
+        let start = Instant::now();
+        let result = f();
+        if let Ok(mut timings) = self.timings.lock() {
+            timings.push(PhaseTiming {
+                phase,
+                ms: start.elapsed().as_millis() as u64,
+            });
+        }
+        result
+    }
+
+    pub fn timings(&self) -> Vec<PhaseTiming> {
+        self.timings
+            .lock()
+            .map(|timings| timings.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn elapsed_ms(&self) -> u64 {
+        self.started.elapsed().as_millis() as u64
+    }
+
+    /// Map an I/O error, which is how a cancelled reader or writer surfaces
+    fn io_error(&self, context: &str, e: impl std::fmt::Display) -> PersistError {
+        if self.is_cancelled() {
+            PersistError::Cancelled
+        } else {
+            PersistError::Failed(format!("{}: {}", context, e))
+        }
+    }
+}
+
+impl Drop for PersistControl {
+    fn drop(&mut self) {
+        if let Some(id) = &self.operation_id {
+            cancellation::unregister(id);
+        }
+    }
+}
+
+fn cancelled_io_error() -> io::Error {
+    io::Error::other("cancelled")
+}
+
+/// Reader that reports progress and fails once the operation is cancelled
+struct ProgressReader<'a, R> {
+    inner: R,
+    control: &'a PersistControl,
+    phase: PersistPhase,
+    processed: u64,
+    total: Option<u64>,
+}
+
+impl<R: Read> Read for ProgressReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.control.is_cancelled() {
+            return Err(cancelled_io_error());
+        }
+        let len = buf.len().min(CHUNK_BYTES);
+        let read = self.inner.read(&mut buf[..len])?;
+        self.processed += read as u64;
+        self.control.report(self.phase, self.processed, self.total);
+        Ok(read)
+    }
+}
+
+/// Writer that reports progress and fails once the operation is cancelled
+struct ProgressWriter<'a, W> {
+    inner: W,
+    control: &'a PersistControl,
+    phase: PersistPhase,
+    processed: u64,
+    total: Option<u64>,
+}
+
+impl<W: Write> Write for ProgressWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.control.is_cancelled() {
+            return Err(cancelled_io_error());
+        }
+        let len = buf.len().min(CHUNK_BYTES);
+        let written = self.inner.write(&buf[..len])?;
+        self.processed += written as u64;
+        self.control.report(self.phase, self.processed, self.total);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Read everything from `reader` in chunks
+pub fn read_all<R: Read>(
+    reader: R,
+    total: Option<u64>,
+    control: &PersistControl,
+) -> Result<Vec<u8>, PersistError> {
+    let mut reader = ProgressReader {
+        inner: reader,
+        control,
+        phase: PersistPhase::Reading,
+        processed: 0,
+        total,
+    };
+    let mut bytes = Vec::with_capacity(total.unwrap_or(0) as usize);
+    reader
+        .read_to_end(&mut bytes)
+        .map_err(|e| control.io_error("Failed to read index file", e))?;
+    Ok(bytes)
+}
+
+pub fn parse<T: DeserializeOwned>(
+    bytes: &[u8],
+    control: &PersistControl,
+) -> Result<T, PersistError> {
+    let reader = ProgressReader {
+        inner: bytes,
+        control,
+        phase: PersistPhase::Parsing,
+        processed: 0,
+        total: Some(bytes.len() as u64),
+    };
+    // serde_json reads byte by byte; the buffer keeps the progress reader off that path
+    serde_json::from_reader(BufReader::with_capacity(CHUNK_BYTES, reader))
+        .map_err(|e| control.io_error("Failed to deserialize index", e))
+}
+
+pub fn serialize<T: Serialize>(
+    value: &T,
+    control: &PersistControl,
+) -> Result<Vec<u8>, PersistError> {
+    let mut writer = io::BufWriter::with_capacity(
+        CHUNK_BYTES,
+        ProgressWriter {
+            inner: Vec::new(),
+            control,
+            phase: PersistPhase::Serializing,
+            processed: 0,
+            total: None,
+        },
+    );
+    serde_json::to_writer(&mut writer, value)
+        .map_err(|e| control.io_error("Failed to serialize index", e))?;
+    let writer = writer
+        .into_inner()
+        .map_err(|e| control.io_error("Failed to serialize index", e.error()))?;
+    Ok(writer.inner)
+}
+
+/// Write `bytes` to a temp file next to `path` in chunks, then rename it into place. A
+/// cancelled write leaves the existing file untouched.
+pub fn write_file(path: &Path, bytes: &[u8], control: &PersistControl) -> Result<(), PersistError> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| {
+            PersistError::Failed(format!("Failed to create index directory: {}", e))
+        })?;
+    }
+    let temp_path = path.with_extension("json.tmp");
+    let write = || -> io::Result<()> {
+        let mut writer = ProgressWriter {
+            inner: File::create(&temp_path)?,
+            control,
+            phase: PersistPhase::Writing,
+            processed: 0,
+            total: Some(bytes.len() as u64),
+        };
+        writer.write_all(bytes)?;
+        writer.inner.sync_all()?;
+        fs::rename(&temp_path, path)
+    };
+    write().map_err(|e| {
+        let _ = fs::remove_file(&temp_path);
+        control.io_error("Failed to write index file", e)
+    })
+}
+
+/// Run blocking persistence work, giving up after `timeout`. The work is cancelled on
+/// timeout so it stops at its next check; a read stuck in the filesystem is left behind.
+pub async fn with_timeout<T, F>(
+    timeout: Duration,
+    control: Arc<PersistControl>,
+    work: F,
+) -> Result<T, PersistError>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    let task = tauri::async_runtime::spawn_blocking(work);
+    match tokio::time::timeout(timeout, task).await {
+        Ok(result) => result.map_err(|e| PersistError::Failed(format!("Index task failed: {}", e))),
+        Err(_) => {
+            control.cancel();
+            log::warn!(
+                "Index persistence for {} timed out after {:?}",
+                control.root_path,
+                timeout
+            );
+            Err(PersistError::TimedOut)
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::thread;
+
+    /// Reader that hands out a few bytes at a time, sleeping before each read
+    pub(crate) struct SlowReader {
+        pub bytes: io::Cursor<Vec<u8>>,
+        pub chunk: usize,
+        pub delay: Duration,
+    }
+
+    impl Read for SlowReader {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            thread::sleep(self.delay);
+            let len = buf.len().min(self.chunk);
+            self.bytes.read(&mut buf[..len])
+        }
+    }
+
+    #[test]
+    fn test_round_trip_records_timings() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("index.json");
+        let control = PersistControl::new("/project", None);
+        let value: Vec<u32> = (0..100_000).collect();
+
+        let bytes = control.timed(PersistPhase::Serializing, || {
+            serialize(&value, &control).unwrap()
+        });
+        control.timed(PersistPhase::Writing, || {
+            write_file(&path, &bytes, &control).unwrap()
+        });
+        let read = read_all(File::open(&path).unwrap(), None, &control).unwrap();
+        let parsed: Vec<u32> = parse(&read, &control).unwrap();
+
+        assert_eq!(parsed, value);
+        let phases: Vec<_> = control.timings().iter().map(|t| t.phase).collect();
+        assert_eq!(phases, [PersistPhase::Serializing, PersistPhase::Writing]);
+        assert!(!path.with_extension("json.tmp").exists());
+    }
+
+    #[test]
+    fn test_cancel_through_registry_stops_slow_read() {
+        let control = PersistControl::new("/project", Some("index-load-test".to_string()));
+        let reader = SlowReader {
+            bytes: io::Cursor::new(vec![b' '; 10_000]),
+            chunk: 16,
+            delay: Duration::from_millis(5),
+        };
+        let canceller = thread::spawn(|| {
+            thread::sleep(Duration::from_millis(30));
+            assert!(cancellation::cancel("index-load-test"));
+        });
+        let start = Instant::now();
+        assert_eq!(
+            read_all(reader, Some(10_000), &control),
+            Err(PersistError::Cancelled)
+        );
+        // 625 reads would take over three seconds
+        assert!(start.elapsed() < Duration::from_secs(1));
+        canceller.join().unwrap();
+
+        drop(control);
+        assert!(cancellation::token("index-load-test").is_none());
+    }
+
+    #[test]
+    fn test_cancelled_write_keeps_existing_file() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("index.json");
+        fs::write(&path, "old").unwrap();
+        let control = PersistControl::new("/project", None);
+        control.cancel();
+        assert_eq!(
+            write_file(&path, b"new", &control),
+            Err(PersistError::Cancelled)
+        );
+        assert_eq!(fs::read_to_string(&path).unwrap(), "old");
+        assert!(!path.with_extension("json.tmp").exists());
+    }
+
+    #[tokio::test]
+    async fn test_timeout_cancels_stalled_read() {
+        let control = Arc::new(PersistControl::new("/project", None));
+        let task_control = control.clone();
+        let result = with_timeout(Duration::from_millis(50), control.clone(), move || {
+            let reader = SlowReader {
+                bytes: io::Cursor::new(vec![b' '; 1000]),
+                chunk: 1,
+                delay: Duration::from_millis(10),
+            };
+            read_all(reader, None, &task_control)
+        })
+        .await;
+        assert!(matches!(result, Err(PersistError::TimedOut)));
+        assert!(control.is_cancelled());
+    }
+}
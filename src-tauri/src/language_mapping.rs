@@ -0,0 +1,412 @@
+// Language mapping module
+// Which language a file is, by extension or filename. The built-in table covers the
+// languages code navigation ships parsers for; users can add mappings (e.g. `.pyi` to
+// python, `*.gohtml` to go) or override built-in ones, but not remove them. Custom mappings
+// are persisted in the settings table and take effect without a restart.
+
+use crate::database::Database;
+use crate::settings;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::sync::{Arc, RwLock};
+use tauri::{AppHandle, State};
+
+/// Settings key holding the custom mappings as JSON
+pub const LANGUAGE_MAPPINGS_SETTING: &str = "file_language_mappings";
+
+/// Extension to language id, as understood by code navigation
+const BUILTIN_EXTENSIONS: &[(&str, &str)] = &[
+    ("py", "python"),
+    ("rs", "rust"),
+    ("go", "go"),
+    ("c", "c"),
+    ("h", "c"),
+    ("cpp", "cpp"),
+    ("cc", "cpp"),
+    ("cxx", "cpp"),
+    ("hpp", "cpp"),
+    ("hxx", "cpp"),
+    ("java", "java"),
+    ("ts", "typescript"),
+    ("tsx", "typescript"),
+    ("js", "javascript"),
+    ("jsx", "javascript"),
+    ("mjs", "javascript"),
+    ("cjs", "javascript"),
+    // Containers, indexed by the code embedded in them
+    ("vue", "vue"),
+    ("svelte", "svelte"),
+    ("md", "markdown"),
+    ("markdown", "markdown"),
+];
+
+/// A pattern starting with '.' is an extension (`.pyi`); anything else is matched against
+/// the file name, with `*` and `?` wildcards (`*.gohtml`, `Jenkinsfile`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LanguageMapping {
+    pub pattern: String,
+    pub lang_id: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LanguageMappingSet {
+    pub builtin: Vec<LanguageMapping>,
+    /// Applied on top of the built-in mappings, in order
+    pub custom: Vec<LanguageMapping>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LanguageMappingsChanged {
+    /// Patterns added, removed or mapped to a different language
+    pub affected_patterns: Vec<String>,
+}
+
+/// Built-in and custom mappings merged for lookups
+#[derive(Debug, Default)]
+struct LanguageTable {
+    /// Lowercase extension to language id
+    extensions: HashMap<String, String>,
+    /// Custom filename patterns, lowercase, checked before extensions
+    filename_patterns: Vec<(String, String)>,
+    /// Extensions with a custom mapping
+    custom_extensions: BTreeSet<String>,
+    custom: Vec<LanguageMapping>,
+}
+
+impl LanguageTable {
+    fn new(custom: Vec<LanguageMapping>) -> Self {
+        let mut table = Self {
+            extensions: BUILTIN_EXTENSIONS
+                .iter()
+                .map(|(ext, lang)| (ext.to_string(), lang.to_string()))
+                .collect(),
+            ..Default::default()
+        };
+        for mapping in &custom {
+            let pattern = mapping.pattern.to_lowercase();
+            match pattern.strip_prefix('.') {
+                Some(ext) => {
+                    table
+                        .extensions
+                        .insert(ext.to_string(), mapping.lang_id.clone());
+                    table.custom_extensions.insert(ext.to_string());
+                }
+                None => table
+                    .filename_patterns
+                    .push((pattern, mapping.lang_id.clone())),
+            }
+        }
+        table.custom = custom;
+        table
+    }
+
+    fn lang_id_for_path(&self, file_path: &str) -> Option<String> {
+        let file_name = file_path
+            .rsplit(['/', '\\'])
+            .next()
+            .unwrap_or(file_path)
+            .to_lowercase();
+        if let Some((_, lang)) = self
+            .filename_patterns
+            .iter()
+            .find(|(pattern, _)| wildcard_match(pattern, &file_name))
+        {
+            return Some(lang.clone());
+        }
+        let (_, ext) = file_name.rsplit_once('.')?;
+        self.extensions.get(ext).cloned()
+    }
+
+    fn matches_custom_filename(&self, file_name: &str) -> bool {
+        let file_name = file_name.to_lowercase();
+        self.filename_patterns
+            .iter()
+            .any(|(pattern, _)| wildcard_match(pattern, &file_name))
+    }
+}
+
+/// `*` matches any run of characters, `?` exactly one
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // Position after the last '*' and the text position it is currently matched up to
+    let mut backtrack: Option<(usize, usize)> = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p + 1, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = backtrack {
+            p = star_p;
+            t = star_t + 1;
+            backtrack = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+fn validate(mappings: &[LanguageMapping]) -> Result<Vec<LanguageMapping>, String> {
+    mappings
+        .iter()
+        .map(|mapping| {
+            let pattern = mapping.pattern.trim();
+            let lang_id = mapping.lang_id.trim();
+            if pattern.is_empty() || pattern == "." {
+                return Err("Language mapping pattern cannot be empty".to_string());
+            }
+            if pattern.contains(['/', '\\']) {
+                return Err(format!(
+                    "Language mapping pattern '{}' must be a file name, not a path",
+                    pattern
+                ));
+            }
+            if lang_id.is_empty() {
+                return Err(format!("No language given for pattern '{}'", pattern));
+            }
+            Ok(LanguageMapping {
+                pattern: pattern.to_string(),
+                lang_id: lang_id.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Patterns whose mapping differs between two custom lists
+fn affected_patterns(old: &[LanguageMapping], new: &[LanguageMapping]) -> Vec<String> {
+    let lookup = |mappings: &[LanguageMapping]| -> HashMap<String, String> {
+        mappings
+            .iter()
+            .map(|m| (m.pattern.to_lowercase(), m.lang_id.clone()))
+            .collect()
+    };
+    let (old, new) = (lookup(old), lookup(new));
+    let patterns: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+    patterns
+        .into_iter()
+        .filter(|pattern| old.get(*pattern) != new.get(*pattern))
+        .cloned()
+        .collect()
+}
+
+lazy_static::lazy_static! {
+    static ref LANGUAGE_TABLE: RwLock<LanguageTable> = RwLock::new(LanguageTable::new(Vec::new()));
+}
+
+/// Language id of a file, from the custom mappings first and then the built-in ones
+pub fn lang_id_for_path(file_path: &str) -> Option<String> {
+    LANGUAGE_TABLE
+        .read()
+        .ok()
+        .and_then(|table| table.lang_id_for_path(file_path))
+}
+
+/// Whether a custom mapping covers this extension (without the leading dot)
+pub fn is_custom_extension(extension: &str) -> bool {
+    LANGUAGE_TABLE
+        .read()
+        .is_ok_and(|table| table.custom_extensions.contains(&extension.to_lowercase()))
+}
+
+/// Whether a custom filename pattern matches this file name
+pub fn is_custom_filename(file_name: &str) -> bool {
+    LANGUAGE_TABLE
+        .read()
+        .is_ok_and(|table| table.matches_custom_filename(file_name))
+}
+
+pub fn get_mappings() -> LanguageMappingSet {
+    let custom = LANGUAGE_TABLE
+        .read()
+        .map(|table| table.custom.clone())
+        .unwrap_or_default();
+    LanguageMappingSet {
+        builtin: BUILTIN_EXTENSIONS
+            .iter()
+            .map(|(ext, lang)| LanguageMapping {
+                pattern: format!(".{}", ext),
+                lang_id: lang.to_string(),
+            })
+            .collect(),
+        custom,
+    }
+}
+
+/// Replace the custom mappings. Returns the affected patterns.
+pub fn set_mappings(mappings: &[LanguageMapping]) -> Result<Vec<String>, String> {
+    let custom = validate(mappings)?;
+    let mut table = LANGUAGE_TABLE
+        .write()
+        .map_err(|e| format!("Failed to acquire write lock: {}", e))?;
+    let affected = affected_patterns(&table.custom, &custom);
+    *table = LanguageTable::new(custom);
+    Ok(affected)
+}
+
+/// Apply the custom mappings persisted in the settings table
+pub async fn load_from_settings(db: &Database) -> Result<(), String> {
+    let Some(mappings) =
+        settings::get_json_setting::<Vec<LanguageMapping>>(db, LANGUAGE_MAPPINGS_SETTING).await?
+    else {
+        return Ok(());
+    };
+    set_mappings(&mappings)?;
+    log::info!("Loaded {} custom language mapping(s)", mappings.len());
+    Ok(())
+}
+
+async fn save_to_settings(db: &Database, mappings: &[LanguageMapping]) -> Result<(), String> {
+    settings::set_json_setting(db, LANGUAGE_MAPPINGS_SETTING, &mappings).await
+}
+
+#[tauri::command]
+pub fn language_mappings_get() -> LanguageMappingSet {
+    get_mappings()
+}
+
+/// Replace the custom mappings, persist them and announce the affected patterns
+#[tauri::command]
+pub async fn language_mappings_set(
+    app_handle: AppHandle,
+    db: State<'_, Arc<Database>>,
+    mappings: Vec<LanguageMapping>,
+) -> Result<LanguageMappingSet, String> {
+    let custom = validate(&mappings)?;
+    save_to_settings(&db, &custom).await?;
+    let affected_patterns = set_mappings(&custom)?;
+    if !affected_patterns.is_empty() {
+        log::info!(
+            "Language mappings changed for {} pattern(s)",
+            affected_patterns.len()
+        );
+        if let Err(e) = crate::events::emit_language_mappings_changed(
+            &app_handle,
+            &LanguageMappingsChanged { affected_patterns },
+        ) {
+            log::error!("Failed to emit language mapping change: {}", e);
+        }
+    }
+    Ok(get_mappings())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::code_navigation::CodeNavigationService;
+    use tempfile::TempDir;
+
+    fn mapping(pattern: &str, lang_id: &str) -> LanguageMapping {
+        LanguageMapping {
+            pattern: pattern.to_string(),
+            lang_id: lang_id.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_wildcard_match() {
+        assert!(wildcard_match("*.gohtml", "page.gohtml"));
+        assert!(wildcard_match("jenkinsfile", "jenkinsfile"));
+        assert!(wildcard_match("*.config.?s", "vite.config.ts"));
+        assert!(wildcard_match("*", "anything"));
+        assert!(!wildcard_match("*.gohtml", "page.html"));
+        assert!(!wildcard_match("jenkinsfile", "jenkinsfile.bak"));
+    }
+
+    #[test]
+    fn test_custom_mappings_override_builtin_ones() {
+        let table = LanguageTable::new(vec![
+            mapping(".ts", "javascript"),
+            mapping(".mts", "typescript"),
+            mapping("*.gohtml", "go"),
+        ]);
+        assert_eq!(
+            table.lang_id_for_path("/src/a.ts"),
+            Some("javascript".into())
+        );
+        assert_eq!(
+            table.lang_id_for_path("/src/a.MTS"),
+            Some("typescript".into())
+        );
+        assert_eq!(
+            table.lang_id_for_path("/tpl/page.gohtml"),
+            Some("go".into())
+        );
+        // Built-ins without an override are untouched
+        assert_eq!(table.lang_id_for_path("main.rs"), Some("rust".into()));
+        assert_eq!(table.lang_id_for_path("README"), None);
+        assert!(table.custom_extensions.contains("mts"));
+        assert!(table.matches_custom_filename("Page.gohtml"));
+    }
+
+    #[test]
+    fn test_validate_rejects_bad_mappings() {
+        assert!(validate(&[mapping("", "python")]).is_err());
+        assert!(validate(&[mapping(".", "python")]).is_err());
+        assert!(validate(&[mapping(".pyi", " ")]).is_err());
+        assert!(validate(&[mapping("src/*.py", "python")]).is_err());
+        assert_eq!(
+            validate(&[mapping(" .pyi ", "python")]).unwrap(),
+            [mapping(".pyi", "python")]
+        );
+    }
+
+    #[test]
+    fn test_affected_patterns() {
+        let old = [mapping(".pyi", "python"), mapping(".mts", "javascript")];
+        let new = [mapping(".mts", "typescript"), mapping("*.gohtml", "go")];
+        assert_eq!(affected_patterns(&old, &new), ["*.gohtml", ".mts", ".pyi"]);
+        assert!(affected_patterns(&old, &old).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_pyi_mapping_is_persisted_and_used_for_indexing() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = Database::new(
+            temp_dir
+                .path()
+                .join("test.db")
+                .to_string_lossy()
+                .to_string(),
+        );
+        db.connect().await.unwrap();
+
+        assert_eq!(
+            CodeNavigationService::get_lang_id_from_path("stub.pyi"),
+            None
+        );
+        save_to_settings(&db, &[mapping(".pyi", "python")])
+            .await
+            .unwrap();
+        load_from_settings(&db).await.unwrap();
+
+        let lang_id = CodeNavigationService::get_lang_id_from_path("/project/stub.pyi").unwrap();
+        assert_eq!(lang_id, "python");
+        assert!(crate::constants::is_code_extension("pyi"));
+
+        let mut service = CodeNavigationService::new();
+        service
+            .index_file(
+                "/project/stub.pyi",
+                "def typed(x: int) -> str: ...\n",
+                &lang_id,
+            )
+            .unwrap();
+        let found = service.find_definition("typed", "python");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].file_path, "/project/stub.pyi");
+
+        set_mappings(&[]).unwrap();
+        assert_eq!(
+            CodeNavigationService::get_lang_id_from_path("stub.pyi"),
+            None
+        );
+    }
+}
@@ -1,40 +1,101 @@
+mod activity;
 mod analytics;
 mod archive;
+mod archive_browse;
+mod audit;
 mod background_tasks;
+mod batch_search;
+mod bookmarks;
+mod cancellation;
+mod capabilities;
+mod clock;
 mod code_navigation;
+mod command_history;
+mod concurrency;
 mod constants;
 mod database;
+mod definition_peek;
+mod dependency_index;
+mod directory_size;
 mod directory_tree;
 mod dock_menu;
+mod docs_index;
+mod docsets;
+mod edit_journal;
+mod embedded_code;
+mod env_profiles;
+mod events;
+mod exclusions;
+mod extract_selection;
+mod file_move;
+mod file_overview;
 mod file_search;
 mod file_watcher;
+mod file_write;
+mod formatter;
 mod git;
 mod glob;
+mod http_multipart;
 mod http_proxy;
+mod http_sse;
+mod import_suggestions;
+mod index_persist;
+mod language_mapping;
 mod lint;
 mod list_files;
 mod lsp;
+mod maintenance;
 mod oauth_callback_server;
+mod path_access;
+mod path_wire;
+mod perf_trace;
+mod process_info;
+mod project_clean;
+mod project_duplicate;
+mod project_profile;
+mod project_reconcile;
+mod project_root;
+mod project_scale;
+mod reference_counts;
 mod script_executor;
 mod search;
+mod search_history;
+mod secrets;
+mod settings;
+mod shell_capture;
+mod shell_env;
+mod shutdown;
+mod skill_manifest;
+mod ssh;
+mod storage;
+mod tasks;
 mod terminal;
+#[cfg(test)]
+mod test_support;
+mod token_count;
+mod undo;
+mod walk_depth;
+mod watch_conditions;
 mod websocket;
+mod window_broadcast;
 mod window_manager;
 
+use activity::{ActivityKind, ActivityOutcome};
 use analytics::AnalyticsState;
 use archive::{
     CreateTarballRequest, CreateTarballResult, ExtractTarballRequest, ExtractTarballResult,
 };
 use code_navigation::{CodeNavState, CodeNavigationService};
+use concurrency::OperationClass;
 use database::Database;
 use file_watcher::FileWatcher;
 use script_executor::{ScriptExecutionRequest, ScriptExecutionResult, ScriptExecutor};
-use serde::{Deserialize, Serialize};
+use serde::Serialize;
 use std::process::Stdio;
 use std::sync::OnceLock;
 use std::sync::{Arc, Mutex, RwLock};
 use std::time::{Duration, Instant, SystemTime};
-use tauri::{AppHandle, Emitter, Manager, State, WindowEvent};
+use tauri::{AppHandle, Manager, State, WindowEvent};
 use tokio::io::BufReader;
 use tokio::process::Command as TokioCommand;
 use tokio::sync::Mutex as TokioMutex;
@@ -42,6 +103,12 @@ use tokio::time::Duration as TokioDuration;
 use websocket::WebSocketState;
 use window_manager::{create_window, WindowRegistry, WindowState};
 
+/// Load the user's login shell environment, PATH included, into this process.
+/// Call from main before anything else starts; on failure the inherited environment stays.
+pub fn fix_path_env() -> Result<(), String> {
+    shell_env::fix()
+}
+
 // Global app handle for dock menu and other cross-module access
 // This is initialized once during app setup and provides safe static access to the AppHandle
 //
@@ -78,10 +145,11 @@ pub fn get_app_handle() -> &'static tauri::AppHandle {
         .expect("FATAL: get_app_handle() called before set_app_handle(). This is a bug in initialization order.")
 }
 
-#[derive(Clone, Serialize, Deserialize)]
-struct Payload {
-    args: Vec<String>,
-    cwd: String,
+/// Get the global app handle if it has been initialized
+///
+/// Use this from code paths that may also run outside the app (e.g. unit tests).
+pub fn try_get_app_handle() -> Option<&'static tauri::AppHandle> {
+    APP_HANDLE.get()
 }
 
 // Legacy: Keep for backward compatibility with existing windows
@@ -107,6 +175,7 @@ fn start_file_watching(
         watcher.stop();
     }
 
+    path_access::ensure_readable(std::path::Path::new(&path))?;
     let mut watcher = FileWatcher::new().map_err(|e| e.to_string())?;
     watcher
         .watch_directory(&path, app_handle, None)
@@ -131,40 +200,214 @@ fn stop_file_watching(state: State<AppState>) -> Result<(), String> {
 }
 
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 fn search_file_content(
     query: String,
-    root_path: String,
+    root_path: Option<String>,
     file_types: Option<Vec<String>>,
     exclude_dirs: Option<Vec<String>>,
-) -> Result<Vec<search::SearchResult>, String> {
-    let start_time = Instant::now();
+    include_paths: Option<Vec<String>>,
+    exclude_paths: Option<Vec<String>>,
+    max_depth: Option<usize>,
+    sort_by: Option<search::ResultOrder>,
+    is_regex: Option<bool>,
+    case_sensitive: Option<bool>,
+    whole_word: Option<bool>,
+    respect_gitignore: Option<bool>,
+    max_file_size: Option<u64>,
+    include_files: Option<Vec<String>>,
+    include_globs: Option<Vec<String>>,
+    exclude_globs: Option<Vec<String>>,
+    include_hidden: Option<bool>,
+    follow_symlinks: Option<bool>,
+    count_only: Option<bool>,
+    search_id: Option<String>,
+) -> Result<search::ContentSearchResponse, search::SearchError> {
     log::info!(
-        "Starting search for query: '{}' in path: {}",
+        "Starting search for query: '{}' in path: {:?} (include_paths: {:?})",
         query,
-        root_path
+        root_path,
+        include_paths
     );
+    run_content_search(
+        search::ContentSearchParams {
+            query,
+            root_path,
+            file_types,
+            exclude_dirs,
+            include_paths,
+            exclude_paths,
+            max_depth,
+            sort_by,
+            is_regex,
+            case_sensitive,
+            whole_word,
+            respect_gitignore,
+            max_file_size,
+            include_files,
+            include_globs,
+            exclude_globs,
+            include_hidden,
+            follow_symlinks,
+            count_only,
+        },
+        search_id,
+        |_| {},
+    )
+}
 
-    let searcher = search::RipgrepSearch::new()
-        .with_max_results(50)
-        .with_max_matches_per_file(10)
-        .with_file_types(file_types)
-        .with_exclude_dirs(exclude_dirs);
+/// Like `search_file_content`, but emits `search-result-<request id>` for each file as
+/// soon as it matches, then `search-complete-<request id>` with the totals. Nothing is
+/// emitted when the search fails to start, e.g. on an invalid pattern.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+async fn search_file_content_stream(
+    app_handle: AppHandle,
+    request_id: String,
+    query: String,
+    root_path: Option<String>,
+    file_types: Option<Vec<String>>,
+    exclude_dirs: Option<Vec<String>>,
+    include_paths: Option<Vec<String>>,
+    exclude_paths: Option<Vec<String>>,
+    max_depth: Option<usize>,
+    sort_by: Option<search::ResultOrder>,
+    is_regex: Option<bool>,
+    case_sensitive: Option<bool>,
+    whole_word: Option<bool>,
+    respect_gitignore: Option<bool>,
+    max_file_size: Option<u64>,
+    include_files: Option<Vec<String>>,
+    include_globs: Option<Vec<String>>,
+    exclude_globs: Option<Vec<String>>,
+    include_hidden: Option<bool>,
+    follow_symlinks: Option<bool>,
+    count_only: Option<bool>,
+    search_id: Option<String>,
+) -> Result<search::SearchComplete, search::SearchError> {
+    log::info!(
+        "Starting streamed search {} for query: '{}' in path: {:?}",
+        request_id,
+        query,
+        root_path
+    );
+    let params = search::ContentSearchParams {
+        query,
+        root_path,
+        file_types,
+        exclude_dirs,
+        include_paths,
+        exclude_paths,
+        max_depth,
+        sort_by,
+        is_regex,
+        case_sensitive,
+        whole_word,
+        respect_gitignore,
+        max_file_size,
+        include_files,
+        include_globs,
+        exclude_globs,
+        include_hidden,
+        follow_symlinks,
+        count_only,
+    };
+    tauri::async_runtime::spawn_blocking(move || {
+        let start_time = Instant::now();
+        let response = run_content_search(params, search_id, |result| {
+            if let Err(e) = events::emit_search_result(&app_handle, &request_id, result) {
+                log::error!("Failed to emit search result: {}", e);
+            }
+        })?;
+        let summary = search::SearchComplete::new(&response, start_time.elapsed());
+        if let Err(e) = events::emit_search_complete(&app_handle, &request_id, &summary) {
+            log::error!("Failed to emit search completion: {}", e);
+        }
+        Ok(summary)
+    })
+    .await
+    .map_err(|e| search::SearchError::Failed {
+        message: format!("Search task failed: {}", e),
+    })?
+}
 
-    let result = searcher.search_content(&query, &root_path).map_err(|e| {
-        log::error!("Search error: {}", e);
-        format!("Search failed: {}", e)
-    });
+/// Run a content search as an activity and record it in the search history. A
+/// `search_id` is registered for `cancel_search` while the search runs. `on_result`
+/// sees each matching file as soon as it is found.
+fn run_content_search(
+    params: search::ContentSearchParams,
+    search_id: Option<String>,
+    on_result: impl FnMut(&search::SearchResult) + Send,
+) -> Result<search::ContentSearchResponse, search::SearchError> {
+    let start_time = Instant::now();
+    let activity_id = activity::activity_start(
+        ActivityKind::Search,
+        format!("Searching for \"{}\"", params.query),
+        true,
+    );
+    let cancellation = activity::cancellation_token(&activity_id);
+    let _permit = concurrency::governor().acquire(
+        OperationClass::Interactive,
+        &format!("Search \"{}\"", params.query),
+        Some(&activity_id),
+    );
+    if let Some(ref search_id) = search_id {
+        cancellation::register(search_id);
+    }
+    let searcher = params
+        .searcher()
+        .with_activity(Some(activity_id.clone()))
+        .with_search_id(search_id.clone());
+    let result = params.stream(&searcher, on_result);
+    if let Some(ref search_id) = search_id {
+        cancellation::unregister(search_id);
+    }
 
     let duration = start_time.elapsed();
-    if let Ok(ref results) = result {
+    if let Ok(ref response) = result {
         log::info!(
-            "Search completed successfully with {} results in {}ms",
-            results.len(),
+            "Search {} with {} results in {}ms",
+            if response.cancelled {
+                "cancelled"
+            } else {
+                "completed successfully"
+            },
+            response.results.len(),
             duration.as_millis()
         );
+        if response.depth.depth_limited {
+            log::warn!(
+                "Search for \"{}\" skipped {} directories below the depth limit",
+                params.query,
+                response.depth.truncated_subtrees
+            );
+        }
+        if response.stats.skipped_size > 0 || response.stats.skipped_binary > 0 {
+            log::info!(
+                "Search for \"{}\" skipped {} oversized and {} binary files",
+                params.query,
+                response.stats.skipped_size,
+                response.stats.skipped_binary
+            );
+        }
     } else {
         log::error!("Search failed after {}ms", duration.as_millis());
     }
+    let cancelled = result.as_ref().is_ok_and(|response| response.cancelled);
+    let outcome = if cancelled || cancellation.is_some_and(|t| t.is_cancelled()) {
+        ActivityOutcome::Cancelled
+    } else {
+        ActivityOutcome::from_result(&result.as_ref().map_err(ToString::to_string))
+    };
+    activity::activity_finish(&activity_id, outcome);
+    // Partial results from a cancelled search aren't worth replaying, and counts are
+    // refreshed too often to be worth recording
+    match result {
+        Ok(ref response) if !response.cancelled && response.counts.is_none() => {
+            search_history::record_search(&params, &response.results);
+        }
+        _ => {}
+    }
 
     result
 }
@@ -182,8 +425,10 @@ fn search_files_fast(
         root_path
     );
 
-    let searcher =
-        file_search::HighPerformanceFileSearch::new().with_max_results(max_results.unwrap_or(200));
+    let tracked = file_search::TrackedFiles::for_root(std::path::Path::new(&root_path));
+    let searcher = file_search::HighPerformanceFileSearch::new()
+        .with_max_results(max_results.unwrap_or(200))
+        .with_ranking(file_search::ranking_config(), tracked);
 
     let result = searcher.search_files(&root_path, &query).map_err(|e| {
         log::error!("File search error: {}", e);
@@ -234,6 +479,40 @@ fn get_all_project_windows(
     state.window_registry.get_all_windows()
 }
 
+/// Ask every window, or those with `root_path_filter` open, to handle a request and
+/// collect their replies. Windows that do not answer within `timeout_ms` are marked
+/// timed out.
+#[tauri::command]
+async fn broadcast_request(
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+    root_path_filter: Option<String>,
+    request_kind: String,
+    payload: serde_json::Value,
+    timeout_ms: Option<u64>,
+) -> Result<window_broadcast::BroadcastResult, String> {
+    window_broadcast::broadcast(
+        &app_handle,
+        &state.window_registry,
+        root_path_filter.as_deref(),
+        request_kind,
+        payload,
+        timeout_ms.map(Duration::from_millis),
+    )
+    .await
+}
+
+/// Raw window registry contents with whether each label has a live window, for
+/// diagnosing stale entries
+#[tauri::command]
+fn window_registry_dump(
+    app_handle: AppHandle,
+    state: State<AppState>,
+) -> Result<Vec<window_manager::WindowRegistryEntry>, String> {
+    let live_labels = window_manager::live_window_labels(&app_handle);
+    state.window_registry.dump(&live_labels)
+}
+
 #[tauri::command]
 fn get_current_window_label(window: tauri::Window) -> Result<String, String> {
     Ok(window.label().to_string())
@@ -336,17 +615,39 @@ fn update_window_project(
 fn start_window_file_watching(
     window_label: String,
     path: String,
+    scale_hint: Option<project_scale::ProjectScale>,
     app_handle: AppHandle,
     state: State<AppState>,
 ) -> Result<(), String> {
+    start_window_watcher(&state, app_handle, window_label, &path, scale_hint)
+}
+
+/// Start (or replace) the file watcher for a window, picking the watch mode from the
+/// project scale
+fn start_window_watcher(
+    state: &AppState,
+    app_handle: AppHandle,
+    window_label: String,
+    path: &str,
+    scale_hint: Option<project_scale::ProjectScale>,
+) -> Result<(), String> {
+    let scale = scale_hint.unwrap_or_else(|| project_scale::scale_for(path));
     log::info!(
-        "Starting file watching for window {} at path: {}",
+        "Starting file watching for window {} at path: {} (scale: {:?})",
         window_label,
-        path
+        path,
+        scale
     );
+    // Watching a denied directory succeeds but never reports anything
+    path_access::ensure_readable(std::path::Path::new(path))?;
+    let mode = if scale == project_scale::ProjectScale::VeryLarge {
+        notify::RecursiveMode::NonRecursive
+    } else {
+        notify::RecursiveMode::Recursive
+    };
     let mut watcher = FileWatcher::new().map_err(|e| e.to_string())?;
     watcher
-        .watch_directory(&path, app_handle, Some(window_label.clone()))
+        .watch_directory_with_mode(path, app_handle, Some(window_label.clone()), mode)
         .map_err(|e| e.to_string())?;
     state
         .window_registry
@@ -408,79 +709,205 @@ struct ShellResult {
     timed_out: bool,
     idle_timed_out: bool,
     pid: Option<u32>,
+    /// Stdout and stderr lines in the order they were read
+    combined_output: Vec<(shell_capture::OutputStream, String)>,
+    // Only filled in with capture_metadata
+    final_cwd: Option<String>,
+    shell_reported_exit: Option<i32>,
+    duration_ms: Option<u64>,
 }
 
 const DEFAULT_TIMEOUT_MS: u64 = 120_000;
 const DEFAULT_IDLE_TIMEOUT_MS: u64 = 5_000;
+/// How long a timed-out shell gets to exit before its metadata is given up on
+const SHELL_EXIT_GRACE: TokioDuration = TokioDuration::from_millis(250);
 
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 async fn execute_user_shell(
+    db: State<'_, Arc<Database>>,
     command: String,
     cwd: Option<String>,
     timeout_ms: Option<u64>,
     idle_timeout_ms: Option<u64>,
+    capture_metadata: Option<bool>,
+    root_path: Option<String>,
+    env: Option<std::collections::HashMap<String, String>>,
 ) -> Result<ShellResult, String> {
-    log::info!("Executing user shell command: {}", command);
-    let max_timeout = TokioDuration::from_millis(timeout_ms.unwrap_or(DEFAULT_TIMEOUT_MS));
-    let idle_timeout =
-        TokioDuration::from_millis(idle_timeout_ms.unwrap_or(DEFAULT_IDLE_TIMEOUT_MS));
+    let start_time = Instant::now();
+    let recording = audit::begin("execute_user_shell", || {
+        serde_json::json!({
+            "command": command,
+            "cwd": cwd,
+            "timeoutMs": timeout_ms,
+            "rootPath": root_path,
+            "env": env,
+        })
+    });
+    let env = match env_profiles::spawn_env_for(&db, root_path.as_deref(), env).await {
+        Ok(env) => env,
+        Err(e) => {
+            let result = Err(e);
+            audit::finish::<ShellResult, _>(recording, &result);
+            return result;
+        }
+    };
+    let result = run_user_shell(
+        command.clone(),
+        cwd.clone(),
+        timeout_ms,
+        idle_timeout_ms,
+        capture_metadata.unwrap_or(false),
+        env,
+    )
+    .await;
+    audit::finish(recording, &result);
+    command_history::record_command(
+        command,
+        cwd,
+        result.as_ref().ok().map(|r| r.code),
+        Some(start_time.elapsed()),
+        command_history::CommandSource::Shell,
+    );
+    result
+}
 
+/// Command that runs `command` through the user's shell, as the AI shell tool and tasks do:
+/// a login interactive shell on Unix so the user's PATH and aliases apply
+pub(crate) fn user_shell_command(command: &str) -> TokioCommand {
     #[cfg(unix)]
     {
-        let shell = std::env::var("SHELL").unwrap_or_else(|_| "/bin/sh".to_string());
+        let shell = user_shell_path();
         let mut cmd = TokioCommand::new(&shell);
-        cmd.arg("-l").arg("-i").arg("-c").arg(&command);
-        if let Some(ref dir) = cwd {
-            cmd.current_dir(dir);
-        }
-        cmd.stdout(Stdio::piped());
-        cmd.stderr(Stdio::piped());
-        let mut child = cmd
-            .spawn()
-            .map_err(|e| format!("Failed to spawn shell: {}", e))?;
-        let child_pid = child.id();
-        let stdout = child.stdout.take();
-        let stderr = child.stderr.take();
-        execute_with_idle_timeout(
-            &mut child,
-            stdout,
-            stderr,
-            max_timeout,
-            idle_timeout,
-            child_pid,
-        )
-        .await
+        cmd.arg("-l").arg("-i").arg("-c").arg(command);
+        cmd
     }
     #[cfg(windows)]
     {
         let shell = std::env::var("COMSPEC").unwrap_or_else(|_| "cmd.exe".to_string());
         let mut cmd = TokioCommand::new(&shell);
         if shell.to_lowercase().contains("powershell") {
-            cmd.arg("-Command").arg(&command);
+            cmd.arg("-Command").arg(command);
         } else {
-            cmd.arg("/C").arg(&command);
+            cmd.arg("/C").arg(command);
         }
-        if let Some(ref dir) = cwd {
-            cmd.current_dir(dir);
+        cmd
+    }
+}
+
+#[cfg(unix)]
+fn user_shell_path() -> String {
+    std::env::var("SHELL").unwrap_or_else(|_| "/bin/sh".to_string())
+}
+
+#[cfg(windows)]
+fn user_shell_path() -> String {
+    std::env::var("COMSPEC").unwrap_or_else(|_| "cmd.exe".to_string())
+}
+
+async fn run_user_shell(
+    command: String,
+    cwd: Option<String>,
+    timeout_ms: Option<u64>,
+    idle_timeout_ms: Option<u64>,
+    capture_metadata: bool,
+    env: Vec<(String, String)>,
+) -> Result<ShellResult, String> {
+    log::info!("Executing user shell command: {}", command);
+    let max_timeout = TokioDuration::from_millis(timeout_ms.unwrap_or(DEFAULT_TIMEOUT_MS));
+    let idle_timeout =
+        TokioDuration::from_millis(idle_timeout_ms.unwrap_or(DEFAULT_IDLE_TIMEOUT_MS));
+
+    let capture = capture_metadata
+        .then(|| shell_capture::ShellCapture::for_shell(&user_shell_path()))
+        .flatten();
+    let mut cmd = match capture {
+        Some(ref capture) => user_shell_command(&capture.wrap(&command)),
+        None => user_shell_command(&command),
+    };
+    cmd.envs(env);
+    run_shell_process(cmd, cwd, max_timeout, idle_timeout, capture).await
+}
+
+/// Run a prepared shell command, reading the shell's metadata afterwards when it was
+/// wrapped by `capture`
+async fn run_shell_process(
+    mut cmd: TokioCommand,
+    cwd: Option<String>,
+    max_timeout: TokioDuration,
+    idle_timeout: TokioDuration,
+    capture: Option<shell_capture::ShellCapture>,
+) -> Result<ShellResult, String> {
+    let start_time = Instant::now();
+    if let Some(ref dir) = cwd {
+        cmd.current_dir(dir);
+    }
+    cmd.stdout(Stdio::piped());
+    cmd.stderr(Stdio::piped());
+    let mut child = cmd
+        .spawn()
+        .map_err(|e| format!("Failed to spawn shell: {}", e))?;
+    let child_pid = child.id();
+    if let Some(pid) = child_pid {
+        process_info::register_spawned_pid(pid);
+    }
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+    let result = execute_with_idle_timeout(
+        &mut child,
+        stdout,
+        stderr,
+        max_timeout,
+        idle_timeout,
+        child_pid,
+    )
+    .await;
+    let mut result = match result {
+        Ok(result) => result,
+        Err(e) => {
+            if let Some(pid) = child_pid {
+                process_info::unregister_spawned_pid(pid);
+            }
+            return Err(e);
         }
-        cmd.stdout(Stdio::piped());
-        cmd.stderr(Stdio::piped());
-        let mut child = cmd
-            .spawn()
-            .map_err(|e| format!("Failed to spawn shell: {}", e))?;
-        let child_pid = child.id();
-        let stdout = child.stdout.take();
-        let stderr = child.stderr.take();
-        execute_with_idle_timeout(
-            &mut child,
-            stdout,
-            stderr,
-            max_timeout,
-            idle_timeout,
-            child_pid,
-        )
-        .await
+    };
+
+    // The trap only writes the file as the shell exits, so a timed-out shell gets a
+    // moment to finish before it is left running
+    let exited = if !(result.timed_out || result.idle_timed_out) {
+        true
+    } else if capture.is_some() {
+        tokio::time::timeout(SHELL_EXIT_GRACE, child.wait())
+            .await
+            .is_ok()
+    } else {
+        false
+    };
+    if let Some(ref capture) = capture {
+        let metadata = if exited { capture.read() } else { None }.unwrap_or_default();
+        result.final_cwd = metadata.final_cwd;
+        result.shell_reported_exit = metadata.exit_code;
+        result.duration_ms = metadata
+            .duration_ms
+            .or_else(|| exited.then(|| start_time.elapsed().as_millis() as u64));
     }
+    if exited {
+        // Once the shell is gone its PID can belong to an unrelated process
+        if let Some(pid) = child_pid {
+            process_info::unregister_spawned_pid(pid);
+        }
+    } else {
+        // A timed-out shell keeps running and stays killable until it finishes; then its
+        // PID is unregistered and the metadata file removed
+        tokio::spawn(async move {
+            let _ = child.wait().await;
+            if let Some(pid) = child_pid {
+                process_info::unregister_spawned_pid(pid);
+            }
+            drop(capture);
+        });
+    }
+    Ok(result)
 }
 
 async fn execute_with_idle_timeout(
@@ -491,15 +918,18 @@ async fn execute_with_idle_timeout(
     idle_timeout: TokioDuration,
     child_pid: Option<u32>,
 ) -> Result<ShellResult, String> {
+    use shell_capture::OutputStream;
     use tokio::io::AsyncBufReadExt;
     let start_time = Instant::now();
     let mut stdout_lines: Vec<String> = Vec::new();
     let mut stderr_lines: Vec<String> = Vec::new();
+    let mut combined_output = Vec::new();
     let mut last_output_time = Instant::now();
     let mut timed_out = false;
     let mut idle_timed_out = false;
     let mut stdout_reader = stdout.map(|s| BufReader::new(s).lines());
     let mut stderr_reader = stderr.map(|s| BufReader::new(s).lines());
+    let mut exit_code = None;
 
     loop {
         if start_time.elapsed() >= max_timeout {
@@ -518,81 +948,117 @@ async fn execute_with_idle_timeout(
             status = child.wait() => {
                 match status {
                     Ok(exit_status) => {
-                        if let Some(ref mut reader) = stdout_reader { while let Ok(Some(line)) = reader.next_line().await { stdout_lines.push(line); } }
-                        if let Some(ref mut reader) = stderr_reader { while let Ok(Some(line)) = reader.next_line().await { stderr_lines.push(line); } }
-                        return Ok(ShellResult {
-                            stdout: stdout_lines.join("\n"),
-                            stderr: stderr_lines.join("\n"),
-                            code: exit_status.code().unwrap_or(-1),
-                            timed_out: false,
-                            idle_timed_out: false,
-                            pid: child_pid,
-                        });
+                        if let Some(ref mut reader) = stdout_reader { while let Ok(Some(line)) = reader.next_line().await { combined_output.push((OutputStream::Stdout, line.clone())); stdout_lines.push(line); } }
+                        if let Some(ref mut reader) = stderr_reader { while let Ok(Some(line)) = reader.next_line().await { combined_output.push((OutputStream::Stderr, line.clone())); stderr_lines.push(line); } }
+                        exit_code = Some(exit_status.code().unwrap_or(-1));
+                        break;
                     }
                     Err(e) => return Err(format!("Failed to wait for process: {}", e)),
                 }
             }
             result = async { if let Some(ref mut reader) = stdout_reader { reader.next_line().await } else { std::future::pending().await } } => {
-                match result { Ok(Some(line)) => { stdout_lines.push(line); last_output_time = Instant::now(); } _ => stdout_reader = None }
+                match result { Ok(Some(line)) => { combined_output.push((OutputStream::Stdout, line.clone())); stdout_lines.push(line); last_output_time = Instant::now(); } _ => stdout_reader = None }
             }
             result = async { if let Some(ref mut reader) = stderr_reader { reader.next_line().await } else { std::future::pending().await } } => {
-                match result { Ok(Some(line)) => { stderr_lines.push(line); last_output_time = Instant::now(); } _ => stderr_reader = None }
+                match result { Ok(Some(line)) => { combined_output.push((OutputStream::Stderr, line.clone())); stderr_lines.push(line); last_output_time = Instant::now(); } _ => stderr_reader = None }
             }
             _ = tokio::time::sleep(wait_duration) => {}
         }
         if stdout_reader.is_none() && stderr_reader.is_none() {
+            // Both pipes closing usually means the shell is exiting; its status follows
+            // shortly unless it keeps running without output
+            let remaining_idle = idle_timeout.saturating_sub(last_output_time.elapsed());
+            let remaining_max = max_timeout.saturating_sub(start_time.elapsed());
+            match tokio::time::timeout(std::cmp::min(remaining_idle, remaining_max), child.wait())
+                .await
+            {
+                Ok(Ok(exit_status)) => exit_code = Some(exit_status.code().unwrap_or(-1)),
+                Ok(Err(e)) => return Err(format!("Failed to wait for process: {}", e)),
+                Err(_) if remaining_max <= remaining_idle => timed_out = true,
+                Err(_) => idle_timed_out = true,
+            }
             break;
         }
     }
     Ok(ShellResult {
         stdout: stdout_lines.join("\n"),
         stderr: stderr_lines.join("\n"),
-        code: -1,
+        code: exit_code.unwrap_or(-1),
         timed_out,
         idle_timed_out,
         pid: child_pid,
+        combined_output,
+        final_cwd: None,
+        shell_reported_exit: None,
+        duration_ms: None,
     })
 }
 
 #[tauri::command]
 async fn execute_skill_script(
-    request: ScriptExecutionRequest,
+    db: State<'_, Arc<Database>>,
+    mut request: ScriptExecutionRequest,
+    root_path: Option<String>,
 ) -> Result<ScriptExecutionResult, String> {
-    ScriptExecutor::execute(request).await
+    let recording = audit::begin(
+        "execute_skill_script",
+        || serde_json::json!({ "request": request, "rootPath": root_path }),
+    );
+    let result: Result<ScriptExecutionResult, String> = async {
+        // The request's environment overrides the project's active environment profile
+        if root_path.is_some() {
+            let env =
+                env_profiles::spawn_env_for(&db, root_path.as_deref(), request.environment.take())
+                    .await?;
+            request.environment = Some(env.into_iter().collect());
+        }
+        ScriptExecutor::execute(request).await
+    }
+    .await;
+    audit::finish(recording, &result);
+    result
 }
 
 #[tauri::command]
 fn create_skill_tarball(request: CreateTarballRequest) -> Result<CreateTarballResult, String> {
-    archive::create_tarball(request)
+    let activity_id = activity::activity_start(
+        ActivityKind::Archive,
+        format!("Packaging {}", request.source_dir),
+        false,
+    );
+    let result = archive::create_tarball(request);
+    let outcome = match result {
+        Ok(CreateTarballResult {
+            error: Some(ref message),
+            ..
+        }) => ActivityOutcome::Failed {
+            message: message.clone(),
+        },
+        _ => ActivityOutcome::from_result(&result),
+    };
+    activity::activity_finish(&activity_id, outcome);
+    result
 }
 
 #[tauri::command]
 fn extract_skill_tarball(request: ExtractTarballRequest) -> Result<ExtractTarballResult, String> {
-    archive::extract_tarball(request)
-}
-
-#[tauri::command]
-fn estimate_tokens(text: String) -> usize {
-    let mut cjk_count = 0;
-    let mut other_count = 0;
-    for c in text.chars() {
-        if is_cjk_char(c) {
-            cjk_count += 1;
-        } else {
-            other_count += 1;
-        }
-    }
-    let other_tokens = if other_count > 0 {
-        (other_count / 4).max(1)
-    } else {
-        0
+    let activity_id = activity::activity_start(
+        ActivityKind::Archive,
+        format!("Extracting {}", request.tarball_path),
+        false,
+    );
+    let result = archive::extract_tarball(request);
+    let outcome = match result {
+        Ok(ExtractTarballResult {
+            error: Some(ref message),
+            ..
+        }) => ActivityOutcome::Failed {
+            message: message.clone(),
+        },
+        _ => ActivityOutcome::from_result(&result),
     };
-    (cjk_count + other_tokens).max(1)
-}
-
-#[inline]
-fn is_cjk_char(c: char) -> bool {
-    matches!(c, '\u{4E00}'..='\u{9FFF}' | '\u{3400}'..='\u{4DBF}' | '\u{F900}'..='\u{FAFF}' | '\u{3040}'..='\u{309F}' | '\u{30A0}'..='\u{30FF}' | '\u{AC00}'..='\u{D7AF}')
+    activity::activity_finish(&activity_id, outcome);
+    result
 }
 
 fn cleanup_old_logs(log_dir: &std::path::Path, days_to_keep: u64) {
@@ -614,16 +1080,122 @@ fn cleanup_old_logs(log_dir: &std::path::Path, days_to_keep: u64) {
     }
 }
 
+/// Register the shutdown hooks of every subsystem, in the order they must be torn down
+fn build_shutdown_coordinator(app: &AppHandle) -> shutdown::ShutdownCoordinator {
+    use shutdown::{ShutdownPhase, DEFAULT_HOOK_TIMEOUT};
+
+    let coordinator = shutdown::ShutdownCoordinator::new();
+
+    let handle = app.clone();
+    coordinator.register(
+        "maintenance",
+        ShutdownPhase::StopAccepting,
+        DEFAULT_HOOK_TIMEOUT,
+        move || {
+            if let Some(scheduler) = handle.try_state::<maintenance::MaintenanceScheduler>() {
+                scheduler.stop_all();
+            }
+        },
+    );
+
+    coordinator.register(
+        "cancel-operations",
+        ShutdownPhase::Flush,
+        DEFAULT_HOOK_TIMEOUT,
+        || {
+            let cancelled = cancellation::cancel_all();
+            log::info!("Cancelled {} running operation(s)", cancelled);
+        },
+    );
+
+    let handle = app.clone();
+    coordinator.register(
+        "code-index",
+        ShutdownPhase::Flush,
+        Duration::from_secs(4),
+        move || {
+            if let Some(state) = handle.try_state::<CodeNavState>() {
+                let result = match state.0.write() {
+                    Ok(mut nav) => nav.flush_dirty(),
+                    Err(e) => Err(e.to_string()),
+                };
+                if let Err(e) = result {
+                    log::error!("Failed to flush code index: {}", e);
+                }
+            }
+        },
+    );
+
+    let handle = app.clone();
+    coordinator.register(
+        "analytics",
+        ShutdownPhase::Flush,
+        Duration::from_secs(3),
+        move || {
+            if let Some(analytics_state) = handle.try_state::<AnalyticsState>() {
+                analytics::send_session_end_sync(analytics_state.inner());
+            }
+        },
+    );
+
+    let handle = app.clone();
+    coordinator.register(
+        "file-watchers",
+        ShutdownPhase::Release,
+        DEFAULT_HOOK_TIMEOUT,
+        move || {
+            if let Some(app_state) = handle.try_state::<AppState>() {
+                if let Ok(mut watcher_guard) = app_state.file_watcher.lock() {
+                    if let Some(mut watcher) = watcher_guard.take() {
+                        watcher.stop();
+                    }
+                }
+                app_state.window_registry.cleanup_all_watchers();
+            }
+        },
+    );
+
+    coordinator.register(
+        "terminals",
+        ShutdownPhase::Release,
+        DEFAULT_HOOK_TIMEOUT,
+        || {
+            let killed = terminal::kill_all_sessions();
+            log::info!("Killed {} terminal session(s)", killed);
+        },
+    );
+
+    let handle = app.clone();
+    coordinator.register(
+        "database",
+        ShutdownPhase::Release,
+        DEFAULT_HOOK_TIMEOUT,
+        move || {
+            if let Some(db) = handle.try_state::<Arc<Database>>() {
+                let db = db.inner().clone();
+                if let Err(e) = tauri::async_runtime::block_on(db.checkpoint_and_close()) {
+                    log::error!("Failed to close database: {}", e);
+                }
+            }
+        },
+    );
+
+    coordinator
+}
+
 pub fn run() {
+    concurrency::configure_thread_pool();
     tauri::Builder::default()
         .manage(AppState {
             file_watcher: Mutex::new(None),
             window_registry: WindowRegistry::new(),
         })
         .manage(AnalyticsState::new())
+        .manage(Arc::new(process_info::SpawnedPids::default()))
         .plugin(tauri_plugin_deep_link::init())
         .plugin(tauri_plugin_single_instance::init(|app, argv, cwd| {
-            if let Err(e) = app.emit("single-instance", Payload { args: argv, cwd }) {
+            let payload = events::SingleInstancePayload { args: argv, cwd };
+            if let Err(e) = events::emit_single_instance(app, &payload) {
                 log::error!("Failed to emit single-instance event: {}", e);
             }
         }))
@@ -631,20 +1203,88 @@ pub fn run() {
             // Set global app handle first (used by dock menu and other modules)
             set_app_handle(app.handle().clone());
 
+            let scheduler = maintenance::MaintenanceScheduler::new();
             if let Ok(log_dir) = app.path().app_log_dir() {
-                cleanup_old_logs(&log_dir, 3);
+                scheduler.register(
+                    "log-cleanup",
+                    Duration::from_secs(24 * 60 * 60),
+                    Duration::from_secs(60),
+                    move || {
+                        let log_dir = log_dir.clone();
+                        async move {
+                            tauri::async_runtime::spawn_blocking(move || {
+                                cleanup_old_logs(&log_dir, 3)
+                            })
+                            .await
+                            .map_err(|e| e.to_string())
+                        }
+                    },
+                );
+            }
+            if let Ok(locations) = storage::StorageLocations::from_app(app.handle()) {
+                scheduler.register(
+                    "storage-quotas",
+                    storage::QUOTA_ENFORCEMENT_INTERVAL,
+                    Duration::from_secs(5 * 60),
+                    move || {
+                        let locations = locations.clone();
+                        async move {
+                            tauri::async_runtime::spawn_blocking(move || {
+                                storage::run_quota_enforcement(&locations)
+                            })
+                            .await
+                            .map_err(|e| e.to_string())
+                        }
+                    },
+                );
             }
+            let app_handle = app.handle().clone();
+            scheduler.register(
+                "window-registry-reconcile",
+                window_manager::REGISTRY_RECONCILE_INTERVAL,
+                Duration::from_secs(5),
+                move || {
+                    let app_handle = app_handle.clone();
+                    async move {
+                        let state = app_handle
+                            .try_state::<AppState>()
+                            .ok_or("App state is not available")?;
+                        window_manager::reconcile_window_registry(
+                            &app_handle,
+                            &state.window_registry,
+                        )
+                        .map(|_| ())
+                    }
+                },
+            );
+            app.manage(scheduler);
             let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+            edit_journal::check_on_startup(&app_data_dir);
+            undo::init(&app_data_dir);
+            audit::init(&app_data_dir);
             let db_path = app_data_dir.join("talkcody.db");
             let db_path_str = db_path.to_string_lossy().to_string();
             let database = Arc::new(Database::new(db_path_str));
             app.manage(database);
             let ws_state = Arc::new(TokioMutex::new(WebSocketState::new()));
             app.manage(ws_state);
-            let code_nav_state = CodeNavState(RwLock::new(CodeNavigationService::new()));
-            app.manage(code_nav_state);
+            let code_nav_service = CodeNavigationService::new();
+            let degraded_languages = code_nav_service.degraded_languages();
+            if !degraded_languages.is_empty() {
+                log::warn!(
+                    "Code navigation degraded for {} language(s)",
+                    degraded_languages.len()
+                );
+                if let Err(e) =
+                    events::emit_code_nav_language_degraded(app.handle(), &degraded_languages)
+                {
+                    log::error!("Failed to emit degraded languages: {}", e);
+                }
+            }
+            app.manage(CodeNavState(RwLock::new(code_nav_service)));
             let lsp_state = lsp::LspState(tokio::sync::Mutex::new(lsp::LspRegistry::new()));
             app.manage(lsp_state);
+            app.manage(build_shutdown_coordinator(app.handle()));
 
             // Start analytics session
             let app_version = app.package_info().version.to_string();
@@ -707,28 +1347,63 @@ pub fn run() {
         .on_menu_event(|app, event| {
             dock_menu::handle_dock_menu_event(app, event);
         })
+        // New commands also need an entry in capabilities::COMMANDS (checked by its tests)
         .invoke_handler(tauri::generate_handler![
             start_file_watching,
             stop_file_watching,
             search_file_content,
+            search_file_content_stream,
+            search::search_and_replace,
+            search::cancel_search,
             search_files_fast,
+            file_search::file_search_get_ranking,
+            file_search::file_search_set_ranking,
             list_files::list_project_files,
             directory_tree::build_directory_tree,
+            directory_tree::build_directory_tree_diff,
             directory_tree::load_directory_children,
             directory_tree::clear_directory_cache,
             directory_tree::invalidate_directory_path,
+            path_access::check_path_access,
             glob::search_files_by_glob,
+            batch_search::search_batch,
+            exclusions::exclusions_get,
+            exclusions::exclusions_set,
+            walk_depth::walk_depth_get,
+            walk_depth::walk_depth_set,
+            directory_size::calculate_directory_sizes,
             create_project_window,
+            project_root::infer_project_root,
             get_all_project_windows,
+            broadcast_request,
+            window_broadcast::reply_to_broadcast,
             get_current_window_label,
             get_window_info,
+            window_registry_dump,
             check_project_window_exists,
             focus_project_window,
             close_project_window,
             update_window_project,
             start_window_file_watching,
             stop_window_file_watching,
+            watch_conditions::watch_condition_add,
+            watch_conditions::watch_condition_list,
+            watch_conditions::watch_condition_remove,
+            project_clean::clean_project_artifacts,
+            project_duplicate::duplicate_project,
+            process_info::list_process_tree,
+            process_info::kill_process_tree,
+            activity::activity_list,
+            concurrency::get_concurrency_status,
+            activity::activity_cancel,
+            maintenance::maintenance_list_jobs,
+            maintenance::maintenance_run_now,
+            storage::get_storage_report,
+            storage::set_storage_quota,
+            storage::set_code_index_eviction,
             activate_app,
+            capabilities::get_backend_capabilities,
+            events::get_event_schema,
             database::db_connect,
             database::db_execute,
             database::db_query,
@@ -738,10 +1413,14 @@ pub fn run() {
             http_proxy::stream_fetch,
             git::git_get_status,
             git::git_is_repository,
+            git::git_get_repository_info,
             git::git_get_all_file_statuses,
+            git::git_get_directory_status_rollup,
             git::git_get_line_changes,
+            git::git_get_line_changes_for_content,
             git::git_get_all_file_diffs,
             git::git_get_raw_diff_text,
+            git::git_get_staged_summary,
             git::git_get_default_worktree_root,
             git::git_acquire_worktree,
             git::git_release_worktree,
@@ -755,21 +1434,115 @@ pub fn run() {
             git::git_cleanup_worktrees,
             git::git_sync_worktree_from_main,
             git::git_abort_rebase,
+            git::git_list_linked_worktrees,
+            git::git_add_linked_worktree,
+            git::git_remove_linked_worktree,
+            git::git_get_hooks,
+            git::git_commit,
+            git::git_get_file_history,
+            git::git_get_file_at_commit,
+            git::git_get_line_annotation,
+            git::git_prefetch_blame,
+            git::git_get_repo_size_report,
+            git::git_run_housekeeping,
             websocket::ws_connect,
             websocket::ws_send,
             websocket::ws_disconnect,
+            websocket::ws_acquire,
+            websocket::ws_release,
+            websocket::ws_pool_send,
+            websocket::ws_pool_status,
+            ssh::ssh_save_secret,
+            ssh::ssh_connect,
+            ssh::ssh_confirm_host_key,
+            ssh::ssh_list_dir,
+            ssh::ssh_read_file,
+            ssh::ssh_write_file,
+            ssh::ssh_exec,
+            ssh::ssh_search,
+            ssh::ssh_disconnect,
             execute_user_shell,
+            tasks::task_save,
+            tasks::task_list,
+            tasks::task_delete,
+            tasks::task_run,
+            tasks::task_run_history,
+            env_profiles::env_profile_save,
+            env_profiles::env_profile_list,
+            env_profiles::env_profile_delete,
+            env_profiles::set_active_profile,
+            env_profiles::env_profile_save_secret,
+            env_profiles::env_profile_export,
+            env_profiles::env_profile_import,
             execute_skill_script,
             create_skill_tarball,
             extract_skill_tarball,
+            skill_manifest::validate_skill_manifest,
+            archive_browse::archive_open_session,
+            archive_browse::archive_read_entry,
+            archive_browse::archive_close_session,
             terminal::pty_spawn,
             terminal::pty_write,
             terminal::pty_resize,
             terminal::pty_kill,
+            command_history::history_search,
+            command_history::history_delete,
+            command_history::history_clear,
+            command_history::history_set_recording_enabled,
+            command_history::history_get_recording_enabled,
+            search_history::search_history_add,
+            search_history::search_history_list,
+            search_history::search_history_replay,
+            search_history::search_history_delete,
+            search_history::search_history_clear,
+            search_history::search_history_set_recording_enabled,
+            search_history::search_history_get_recording_enabled,
+            edit_journal::apply_workspace_edits,
+            edit_journal::pending_edit_recoveries,
+            edit_journal::recover_edit_operation,
+            undo::undo_list_recent,
+            undo::undo_revert,
+            audit::audit_set_enabled,
+            audit::audit_list_sessions,
+            audit::audit_read_session,
+            audit::audit_export,
+            extract_selection::extract_selection,
+            file_write::write_file_atomic,
+            file_write::write_files_atomic,
+            file_move::move_paths,
+            path_wire::read_file_by_raw_path,
+            bookmarks::bookmark_add,
+            bookmarks::bookmark_list,
+            bookmarks::bookmark_remove,
+            bookmarks::bookmark_update_note,
+            project_profile::detect_project_profile,
+            project_scale::assess_project_scale,
             code_navigation::code_nav_index_file,
             code_navigation::code_nav_index_files_batch,
             code_navigation::code_nav_find_definition,
+            dependency_index::code_nav_index_dependency_types,
+            code_navigation::code_nav_find_definitions_bulk,
+            code_navigation::code_nav_has_definitions,
+            code_navigation::code_nav_get_stats,
+            code_navigation::code_nav_get_language_health,
+            perf_trace::trace_get_enabled,
+            perf_trace::trace_set_enabled,
+            perf_trace::trace_get_recent,
+            perf_trace::trace_export_chrome,
+            language_mapping::language_mappings_get,
+            language_mapping::language_mappings_set,
+            embedded_code::code_nav_get_markdown_fences,
+            embedded_code::code_nav_set_markdown_fences,
+            formatter::format_code,
+            formatter::format_get_config,
+            formatter::format_set_config,
+            formatter::format_check_formatters,
+            reference_counts::code_nav_reference_counts,
+            definition_peek::code_nav_get_definition_body,
+            import_suggestions::code_nav_suggest_imports,
             code_navigation::code_nav_find_references_hybrid,
+            file_overview::compute_file_overview,
+            code_navigation::code_nav_occurrences_in_buffer,
             code_navigation::code_nav_clear_file,
             code_navigation::code_nav_clear_all,
             code_navigation::code_nav_save_index,
@@ -777,8 +1550,20 @@ pub fn run() {
             code_navigation::code_nav_get_index_metadata,
             code_navigation::code_nav_delete_index,
             code_navigation::code_nav_get_indexed_files,
+            code_navigation::code_nav_list_indexable_files,
+            code_navigation::code_nav_bootstrap_index,
+            project_reconcile::reconcile_project_state,
             code_navigation::summarize_code_content,
-            estimate_tokens,
+            docs_index::build_docs_index,
+            docs_index::query_docs_index,
+            docsets::docset_list,
+            docsets::docset_import,
+            docsets::docset_remove,
+            docsets::docset_search,
+            docsets::docset_get_entry,
+            docsets::docset_lookup_symbol,
+            token_count::estimate_tokens,
+            token_count::estimate_tokens_batch,
             lint::run_lint,
             lint::check_lint_runtime,
             background_tasks::spawn_background_task,
@@ -822,23 +1607,30 @@ pub fn run() {
         .build(tauri::generate_context!())
         .expect("error while building tauri application")
         .run(|app_handle, event| {
-            // RunEvent::Exit always runs (unlike ExitRequested which is inconsistent on macOS)
+            // RunEvent::Exit always runs (unlike ExitRequested which is inconsistent on macOS),
+            // so both start the shutdown sequence; the coordinator only runs it once.
             // See: https://github.com/tauri-apps/tauri/issues/9198
-            if let tauri::RunEvent::Exit = event {
-                log::info!("App exiting, sending session_end");
-
-                // Send session_end synchronously before exit
-                if let Some(analytics_state) = app_handle.try_state::<AnalyticsState>() {
-                    analytics::send_session_end_sync(analytics_state.inner());
-                }
-
-                // Close database connection to release file handles
-                if let Some(db) = app_handle.try_state::<Arc<Database>>() {
-                    log::info!("Closing database connection on app exit");
-                    db.inner().close_sync();
+            if matches!(
+                event,
+                tauri::RunEvent::ExitRequested { .. } | tauri::RunEvent::Exit
+            ) {
+                if let Some(coordinator) = app_handle.try_state::<shutdown::ShutdownCoordinator>() {
+                    if coordinator.is_shutting_down() {
+                        return;
+                    }
+                    log::info!("App exiting, running shutdown hooks");
+                    // Force the process down if the sequence itself gets stuck
+                    let _ = std::thread::Builder::new()
+                        .name("shutdown-watchdog".to_string())
+                        .spawn(|| {
+                            std::thread::sleep(
+                                shutdown::SHUTDOWN_DEADLINE + Duration::from_secs(2),
+                            );
+                            log::error!("Shutdown did not finish in time, forcing exit");
+                            std::process::exit(0);
+                        });
+                    coordinator.run(shutdown::SHUTDOWN_DEADLINE);
                 }
-
-                log::info!("session_end sent, app will exit now");
             }
         });
 }
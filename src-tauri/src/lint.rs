@@ -1,7 +1,8 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::process::Command;
 use std::sync::OnceLock;
-use tauri::{AppHandle, Emitter};
+use tauri::AppHandle;
 
 /// Cached result of bun availability check.
 /// Note: This is a static cache that persists for the lifetime of the application.
@@ -19,7 +20,7 @@ pub struct RuntimeStatus {
     pub node_available: bool,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct LintDiagnostic {
     pub severity: String,
     pub message: String,
@@ -30,7 +31,7 @@ pub struct LintDiagnostic {
     pub code: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct LintResult {
     pub file_path: String,
     pub diagnostics: Vec<LintDiagnostic>,
@@ -403,7 +404,7 @@ pub async fn run_lint(
                 );
 
                 // Emit result to frontend
-                if let Err(e) = app_clone.emit("lint-result", &lint_result) {
+                if let Err(e) = crate::events::emit_lint_result(&app_clone, &lint_result) {
                     log::error!("Failed to emit lint result: {}", e);
                 }
             }
@@ -424,7 +425,7 @@ pub async fn run_lint(
                         .map(|d| d.as_millis() as u64)
                         .unwrap_or(0),
                 };
-                if let Err(e) = app_clone.emit("lint-result", &empty_result) {
+                if let Err(e) = crate::events::emit_lint_result(&app_clone, &empty_result) {
                     log::error!("Failed to emit empty lint result: {}", e);
                 }
             }
@@ -8,6 +8,7 @@
 
 use flate2::read::GzDecoder;
 use reqwest::Client;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 use std::io::Read;
@@ -15,7 +16,7 @@ use std::path::PathBuf;
 use std::process::Stdio;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use tauri::{AppHandle, Emitter};
+use tauri::AppHandle;
 use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
 use tokio::process::{Child, ChildStdin, ChildStdout, Command as TokioCommand};
 use tokio::sync::Mutex;
@@ -202,7 +203,7 @@ pub struct LspServerInfo {
 }
 
 /// LSP message event payload
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct LspMessageEvent {
     pub server_id: String,
@@ -210,7 +211,7 @@ pub struct LspMessageEvent {
 }
 
 /// Download progress event
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct LspDownloadProgress {
     pub language: String,
@@ -645,7 +646,7 @@ fn emit_download_progress(
         message: message.map(|s| s.to_string()),
     };
 
-    if let Err(e) = app.emit("lsp-download-progress", &event) {
+    if let Err(e) = crate::events::emit_lsp_download_progress(app, &event) {
         log::error!("Failed to emit download progress: {}", e);
     }
 }
@@ -967,7 +968,7 @@ pub async fn lsp_start_server(
                         server_id: server_id_clone.clone(),
                         message,
                     };
-                    if let Err(e) = app_handle.emit("lsp-message", &event) {
+                    if let Err(e) = crate::events::emit_lsp_message(&app_handle, &event) {
                         log::error!("Failed to emit LSP message: {}", e);
                     }
                 }
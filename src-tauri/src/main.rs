@@ -4,6 +4,6 @@
 fn main() {
     // Fix PATH environment variable for GUI apps
     // This ensures user's shell config (e.g., ~/.zshrc) is loaded
-    let _ = fix_path_env::fix();
+    let _ = tauri_app_lib::fix_path_env();
     tauri_app_lib::run()
 }
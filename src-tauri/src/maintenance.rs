@@ -0,0 +1,309 @@
+// Maintenance module
+// A small scheduler for periodic background work (log cleanup, cache pruning, ...).
+// Subsystems register named jobs; each job runs on the async runtime at its interval plus
+// random jitter, never overlaps with itself, and has panics caught and recorded.
+
+use crate::clock;
+use futures_util::FutureExt;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+use tauri::State;
+
+type JobFuture = Pin<Box<dyn Future<Output = Result<(), String>> + Send>>;
+type JobFn = Arc<dyn Fn() -> JobFuture + Send + Sync>;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MaintenanceJobStatus {
+    pub name: String,
+    pub interval_ms: u64,
+    pub running: bool,
+    pub run_count: u64,
+    pub last_run_at: Option<i64>,
+    pub last_duration_ms: Option<u64>,
+    pub last_error: Option<String>,
+}
+
+#[derive(Default)]
+struct JobRecord {
+    run_count: u64,
+    last_run_at: Option<i64>,
+    last_duration_ms: Option<u64>,
+    last_error: Option<String>,
+}
+
+struct Job {
+    name: String,
+    interval: Duration,
+    jitter: Duration,
+    run: JobFn,
+    running: AtomicBool,
+    record: Mutex<JobRecord>,
+}
+
+impl Job {
+    fn status(&self) -> MaintenanceJobStatus {
+        let record = self.record.lock().unwrap_or_else(|e| e.into_inner());
+        MaintenanceJobStatus {
+            name: self.name.clone(),
+            interval_ms: self.interval.as_millis() as u64,
+            running: self.running.load(Ordering::SeqCst),
+            run_count: record.run_count,
+            last_run_at: record.last_run_at,
+            last_duration_ms: record.last_duration_ms,
+            last_error: record.last_error.clone(),
+        }
+    }
+
+    fn next_delay(&self, base: Duration) -> Duration {
+        let jitter_ms = self.jitter.as_millis() as u64;
+        if jitter_ms == 0 {
+            return base;
+        }
+        base + Duration::from_millis(rand::thread_rng().gen_range(0..=jitter_ms))
+    }
+
+    /// Run the job once. Returns false without running if it is already in progress.
+    async fn run_once(&self) -> bool {
+        if self
+            .running
+            .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+            .is_err()
+        {
+            return false;
+        }
+
+        let started_at = clock::now_ms();
+        let start = Instant::now();
+        // The closure itself may panic before returning a future
+        let result = match std::panic::catch_unwind(AssertUnwindSafe(|| (self.run)())) {
+            Ok(future) => match AssertUnwindSafe(future).catch_unwind().await {
+                Ok(result) => result,
+                Err(panic) => Err(panic_message(panic)),
+            },
+            Err(panic) => Err(panic_message(panic)),
+        };
+        let duration = start.elapsed();
+
+        if let Err(ref e) = result {
+            log::error!("Maintenance job '{}' failed: {}", self.name, e);
+        } else {
+            log::info!(
+                "Maintenance job '{}' finished in {}ms",
+                self.name,
+                duration.as_millis()
+            );
+        }
+        {
+            let mut record = self.record.lock().unwrap_or_else(|e| e.into_inner());
+            record.run_count += 1;
+            record.last_run_at = Some(started_at);
+            record.last_duration_ms = Some(duration.as_millis() as u64);
+            record.last_error = result.err();
+        }
+        self.running.store(false, Ordering::SeqCst);
+        true
+    }
+}
+
+fn panic_message(panic: Box<dyn std::any::Any + Send>) -> String {
+    let message = panic
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| panic.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string());
+    format!("panicked: {}", message)
+}
+
+/// Managed in Tauri state; clones share the same jobs
+#[derive(Clone, Default)]
+pub struct MaintenanceScheduler {
+    jobs: Arc<Mutex<HashMap<String, Arc<Job>>>>,
+}
+
+impl MaintenanceScheduler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a job and start its timer. The first run happens after a random delay of up
+    /// to `jitter`, then every `interval` plus up to `jitter`. Registering an existing name
+    /// replaces the job.
+    pub fn register<F, Fut>(&self, name: &str, interval: Duration, jitter: Duration, job: F)
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), String>> + Send + 'static,
+    {
+        let job = Arc::new(Job {
+            name: name.to_string(),
+            interval,
+            jitter,
+            run: Arc::new(move || Box::pin(job()) as JobFuture),
+            running: AtomicBool::new(false),
+            record: Mutex::new(JobRecord::default()),
+        });
+        if let Ok(mut jobs) = self.jobs.lock() {
+            jobs.insert(name.to_string(), job.clone());
+        }
+        log::info!(
+            "Registered maintenance job '{}' (every {:?})",
+            name,
+            interval
+        );
+
+        let jobs = Arc::downgrade(&self.jobs);
+        tauri::async_runtime::spawn(async move {
+            let mut delay = job.next_delay(Duration::ZERO);
+            loop {
+                tokio::time::sleep(delay).await;
+                if !Self::is_current(&jobs, &job) {
+                    break;
+                }
+                job.run_once().await;
+                delay = job.next_delay(job.interval);
+            }
+        });
+    }
+
+    /// Whether the scheduler still exists and `job` has not been replaced
+    fn is_current(jobs: &Weak<Mutex<HashMap<String, Arc<Job>>>>, job: &Arc<Job>) -> bool {
+        jobs.upgrade()
+            .and_then(|jobs| {
+                jobs.lock()
+                    .ok()
+                    .map(|jobs| jobs.get(&job.name).is_some_and(|j| Arc::ptr_eq(j, job)))
+            })
+            .unwrap_or(false)
+    }
+
+    /// Remove every job. Timers stop at their next wake-up; runs in progress finish.
+    pub fn stop_all(&self) {
+        if let Ok(mut jobs) = self.jobs.lock() {
+            jobs.clear();
+        }
+    }
+
+    pub fn list(&self) -> Vec<MaintenanceJobStatus> {
+        let mut statuses: Vec<MaintenanceJobStatus> = self
+            .jobs
+            .lock()
+            .map(|jobs| jobs.values().map(|job| job.status()).collect())
+            .unwrap_or_default();
+        statuses.sort_by(|a, b| a.name.cmp(&b.name));
+        statuses
+    }
+
+    /// Run a job immediately, outside its schedule
+    pub async fn run_now(&self, name: &str) -> Result<MaintenanceJobStatus, String> {
+        let job = self
+            .jobs
+            .lock()
+            .map_err(|e| e.to_string())?
+            .get(name)
+            .cloned()
+            .ok_or_else(|| format!("Unknown maintenance job: {}", name))?;
+        if !job.run_once().await {
+            return Err(format!("Maintenance job '{}' is already running", name));
+        }
+        Ok(job.status())
+    }
+}
+
+#[tauri::command]
+pub fn maintenance_list_jobs(
+    scheduler: State<'_, MaintenanceScheduler>,
+) -> Result<Vec<MaintenanceJobStatus>, String> {
+    Ok(scheduler.list())
+}
+
+#[tauri::command]
+pub async fn maintenance_run_now(
+    name: String,
+    scheduler: State<'_, MaintenanceScheduler>,
+) -> Result<MaintenanceJobStatus, String> {
+    scheduler.run_now(&name).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+
+    #[tokio::test]
+    async fn test_job_never_overlaps_with_itself() {
+        let scheduler = MaintenanceScheduler::new();
+        let active = Arc::new(AtomicUsize::new(0));
+        let max_active = Arc::new(AtomicUsize::new(0));
+        let (active_clone, max_clone) = (active.clone(), max_active.clone());
+        scheduler.register(
+            "slow",
+            Duration::from_millis(5),
+            Duration::ZERO,
+            move || {
+                let active = active_clone.clone();
+                let max_active = max_clone.clone();
+                async move {
+                    let now = active.fetch_add(1, Ordering::SeqCst) + 1;
+                    max_active.fetch_max(now, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(40)).await;
+                    active.fetch_sub(1, Ordering::SeqCst);
+                    Ok(())
+                }
+            },
+        );
+
+        // Manual runs while the scheduled run is in progress are refused
+        tokio::time::sleep(Duration::from_millis(15)).await;
+        let err = scheduler.run_now("slow").await.unwrap_err();
+        assert!(err.contains("already running"));
+
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        let status = &scheduler.list()[0];
+        assert!(status.run_count >= 2, "{:?}", status);
+        assert_eq!(max_active.load(Ordering::SeqCst), 1);
+        assert!(status.last_duration_ms.unwrap() >= 40);
+        assert!(scheduler.run_now("missing").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_errors_and_panics_are_recorded() {
+        let scheduler = MaintenanceScheduler::new();
+        let calls = Arc::new(AtomicUsize::new(0));
+        let calls_clone = calls.clone();
+        // Long interval: only the manual runs below happen after the first scheduled one
+        scheduler.register(
+            "flaky",
+            Duration::from_secs(3600),
+            Duration::ZERO,
+            move || {
+                let call = calls_clone.fetch_add(1, Ordering::SeqCst);
+                async move {
+                    match call {
+                        0 => panic!("disk on fire"),
+                        1 => Err("cache locked".to_string()),
+                        _ => Ok(()),
+                    }
+                }
+            },
+        );
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let status = &scheduler.list()[0];
+        assert_eq!(status.run_count, 1);
+        assert_eq!(status.last_error.as_deref(), Some("panicked: disk on fire"));
+        assert!(!status.running);
+
+        let status = scheduler.run_now("flaky").await.unwrap();
+        assert_eq!(status.last_error.as_deref(), Some("cache locked"));
+        let status = scheduler.run_now("flaky").await.unwrap();
+        assert_eq!(status.last_error, None);
+        assert_eq!(status.run_count, 3);
+    }
+}
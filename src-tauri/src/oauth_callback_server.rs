@@ -1,16 +1,16 @@
 // OAuth callback HTTP server for automatic token capture
 // This module implements a temporary HTTP server to receive OAuth callbacks
 
+use schemars::JsonSchema;
 use serde::Serialize;
 use std::net::TcpListener;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::thread;
 use std::time::Duration;
-use tauri::Emitter;
 
 /// OAuth callback result sent to frontend via Tauri event
-#[derive(Clone, Serialize)]
+#[derive(Clone, Serialize, JsonSchema)]
 pub struct OAuthCallbackResult {
     pub success: bool,
     pub code: Option<String>,
@@ -266,7 +266,7 @@ pub async fn start_oauth_callback_server(
         let result = run_callback_server(port, expected_state, shutdown_flag_clone);
 
         // Emit result to frontend
-        if let Err(e) = window.emit("openai-oauth-callback", &result) {
+        if let Err(e) = crate::events::emit_openai_oauth_callback(&window, &result) {
             log::error!("Failed to emit OAuth callback event: {:?}", e);
         }
 
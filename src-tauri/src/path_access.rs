@@ -0,0 +1,157 @@
+// Path access module
+// Detects when the OS refuses to list a project directory (macOS TCC/sandbox denials for
+// ~/Documents, ~/Desktop and similar) so callers can report it instead of showing an
+// empty project.
+
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::io;
+use std::path::Path;
+
+/// Prefix of the error returned when a path cannot be read for lack of permission; the
+/// frontend matches on it to tell denials apart from other failures
+pub const PERMISSION_DENIED_PREFIX: &str = "PermissionDenied: ";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase", tag = "status")]
+pub enum PathAccess {
+    Ok,
+    NotFound,
+    Denied,
+    Error { message: String },
+}
+
+#[derive(Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PathAccessDeniedPayload {
+    pub path: String,
+}
+
+fn classify_error(error: &io::Error) -> PathAccess {
+    // EPERM (TCC) and EACCES (file mode) both map to PermissionDenied
+    match error.kind() {
+        io::ErrorKind::NotFound => PathAccess::NotFound,
+        io::ErrorKind::PermissionDenied => PathAccess::Denied,
+        _ => PathAccess::Error {
+            message: error.to_string(),
+        },
+    }
+}
+
+/// Whether `path` can actually be read: directories are listed, files opened. A stat
+/// alone succeeds under TCC, so it is not enough.
+pub fn classify_access(path: &Path) -> PathAccess {
+    let result = if path.is_file() {
+        std::fs::File::open(path).map(|_| ())
+    } else {
+        std::fs::read_dir(path).map(|_| ())
+    };
+    match result {
+        Ok(()) => PathAccess::Ok,
+        Err(e) => classify_error(&e),
+    }
+}
+
+/// The typed error for a denied path, emitting `path-access-denied` so the frontend can
+/// point the user at the system privacy settings
+pub fn permission_denied(path: &Path) -> String {
+    let path = path.to_string_lossy().to_string();
+    log::warn!("Permission denied reading {}", path);
+    if let Some(app_handle) = crate::try_get_app_handle() {
+        let payload = PathAccessDeniedPayload { path: path.clone() };
+        if let Err(e) = crate::events::emit_path_access_denied(app_handle, &payload) {
+            log::error!("Failed to emit path access denial: {}", e);
+        }
+    }
+    format!("{}{}", PERMISSION_DENIED_PREFIX, path)
+}
+
+/// Fail with the typed error when `path` exists but cannot be read for lack of
+/// permission; other failures are left to the caller's own handling
+pub fn ensure_readable(path: &Path) -> Result<(), String> {
+    match classify_access(path) {
+        PathAccess::Denied => Err(permission_denied(path)),
+        _ => Ok(()),
+    }
+}
+
+#[tauri::command]
+pub fn check_path_access(path: String) -> PathAccess {
+    let path = Path::new(&path);
+    let access = classify_access(path);
+    if access == PathAccess::Denied {
+        permission_denied(path);
+    }
+    access
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// A directory with mode 000. None when the test runs as root, which bypasses file
+    /// modes, so the denial cannot be simulated.
+    #[cfg(unix)]
+    pub(crate) fn unreadable_dir(parent: &Path) -> Option<std::path::PathBuf> {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = parent.join("locked");
+        std::fs::create_dir(&dir).unwrap();
+        std::fs::write(dir.join("secret.txt"), "hidden").unwrap();
+        std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o000)).unwrap();
+        if std::fs::read_dir(&dir).is_ok() {
+            restore(&dir);
+            return None;
+        }
+        Some(dir)
+    }
+
+    /// Make an unreadable_dir removable again
+    #[cfg(unix)]
+    pub(crate) fn restore(dir: &Path) {
+        use std::os::unix::fs::PermissionsExt;
+        let _ = std::fs::set_permissions(dir, std::fs::Permissions::from_mode(0o755));
+    }
+
+    #[test]
+    fn test_classify_error_kinds() {
+        let denied = io::Error::from(io::ErrorKind::PermissionDenied);
+        assert_eq!(classify_error(&denied), PathAccess::Denied);
+        let missing = io::Error::from(io::ErrorKind::NotFound);
+        assert_eq!(classify_error(&missing), PathAccess::NotFound);
+        #[cfg(unix)]
+        {
+            // EPERM, which is what TCC returns
+            let eperm = io::Error::from_raw_os_error(1);
+            assert_eq!(classify_error(&eperm), PathAccess::Denied);
+        }
+    }
+
+    #[test]
+    fn test_classify_access() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("a.txt");
+        std::fs::write(&file, "a").unwrap();
+        assert_eq!(classify_access(temp_dir.path()), PathAccess::Ok);
+        assert_eq!(classify_access(&file), PathAccess::Ok);
+        assert_eq!(
+            classify_access(&temp_dir.path().join("missing")),
+            PathAccess::NotFound
+        );
+        assert!(ensure_readable(&temp_dir.path().join("missing")).is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_denied_directory() {
+        let temp_dir = TempDir::new().unwrap();
+        let Some(locked) = unreadable_dir(temp_dir.path()) else {
+            return;
+        };
+        assert_eq!(classify_access(&locked), PathAccess::Denied);
+        let error = ensure_readable(&locked).unwrap_err();
+        assert!(error.starts_with(PERMISSION_DENIED_PREFIX));
+        assert!(error.ends_with("locked"));
+        restore(&locked);
+    }
+}
@@ -0,0 +1,196 @@
+// Path wire format module
+// Paths reach the frontend as strings, but on Unix a file name is any byte sequence and
+// old archives still carry latin-1 names that aren't valid UTF-8. to_string_lossy turns
+// those bytes into U+FFFD: the result can't be opened again and distinct files collapse
+// into one name (and one index key). Such paths are sent percent-encoded instead, with
+// every invalid byte and every literal '%' written as %XX, and structs carrying a path
+// flag it and add the raw bytes in base64. Valid UTF-8 paths are sent unchanged.
+// `resolve` maps a wire string back to the path on disk, so commands taking paths from
+// the frontend accept either form.
+
+use base64::Engine;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
+
+/// The wire form of a path that isn't valid UTF-8, flattened into structs next to the
+/// encoded path string. Both fields are left out for valid paths.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct RawPath {
+    /// The path string is percent-encoded because the path isn't valid UTF-8
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub path_is_lossy: bool,
+    /// The path's bytes in base64, for opening it exactly. Only set when lossy.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw_path_bytes: Option<String>,
+}
+
+impl RawPath {
+    pub fn of(path: &Path) -> Self {
+        match path.to_str() {
+            Some(_) => Self::default(),
+            None => Self {
+                path_is_lossy: true,
+                raw_path_bytes: Some(
+                    base64::engine::general_purpose::STANDARD.encode(path_bytes(path)),
+                ),
+            },
+        }
+    }
+}
+
+#[cfg(unix)]
+fn path_bytes(path: &Path) -> Vec<u8> {
+    use std::os::unix::ffi::OsStrExt;
+    path.as_os_str().as_bytes().to_vec()
+}
+
+#[cfg(not(unix))]
+fn path_bytes(path: &Path) -> Vec<u8> {
+    path.to_string_lossy().into_owned().into_bytes()
+}
+
+#[cfg(unix)]
+fn path_from_bytes(bytes: Vec<u8>) -> Option<PathBuf> {
+    use std::os::unix::ffi::OsStringExt;
+    Some(PathBuf::from(std::ffi::OsString::from_vec(bytes)))
+}
+
+#[cfg(not(unix))]
+fn path_from_bytes(bytes: Vec<u8>) -> Option<PathBuf> {
+    String::from_utf8(bytes).ok().map(PathBuf::from)
+}
+
+/// The string sent to the frontend for a path, also used as its index key. Distinct paths
+/// get distinct strings.
+pub fn encode(path: &Path) -> String {
+    if let Some(s) = path.to_str() {
+        return s.to_string();
+    }
+    let bytes = path_bytes(path);
+    let mut encoded = String::with_capacity(bytes.len() + 8);
+    for chunk in bytes.utf8_chunks() {
+        for c in chunk.valid().chars() {
+            if c == '%' {
+                encoded.push_str("%25");
+            } else {
+                encoded.push(c);
+            }
+        }
+        for byte in chunk.invalid() {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+/// `encode` for a file name
+pub fn encode_name(name: &std::ffi::OsStr) -> String {
+    encode(Path::new(name))
+}
+
+/// Undo `encode`; None unless the string decodes to bytes that aren't valid UTF-8
+fn decode(wire: &str) -> Option<PathBuf> {
+    let mut bytes = Vec::with_capacity(wire.len());
+    let mut rest = wire.as_bytes();
+    while let Some((&first, tail)) = rest.split_first() {
+        if first == b'%' {
+            let hex = tail.get(..2)?;
+            let value = u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok()?;
+            bytes.push(value);
+            rest = &tail[2..];
+        } else {
+            bytes.push(first);
+            rest = tail;
+        }
+    }
+    if std::str::from_utf8(&bytes).is_ok() {
+        return None;
+    }
+    path_from_bytes(bytes)
+}
+
+/// The path on disk for a string from the frontend. A string naming an existing path is
+/// taken as is; otherwise an encoded non-UTF-8 path is decoded.
+pub fn resolve(wire: &str) -> PathBuf {
+    let path = Path::new(wire);
+    if !wire.contains('%') || path.exists() {
+        return path.to_path_buf();
+    }
+    decode(wire).unwrap_or_else(|| path.to_path_buf())
+}
+
+/// The path for base64 raw bytes from a `RawPath`
+pub fn from_raw_bytes(raw_path_bytes: &str) -> Result<PathBuf, String> {
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(raw_path_bytes)
+        .map_err(|e| format!("Invalid raw path: {}", e))?;
+    path_from_bytes(bytes).ok_or_else(|| "Raw path is not valid on this platform".to_string())
+}
+
+/// Canonical index key for a path string from the frontend, so a file indexed under a
+/// path from a walk is cleared by the same path coming back from the frontend
+pub fn index_key(wire: &str) -> Cow<'_, str> {
+    if !wire.contains('%') {
+        return Cow::Borrowed(wire);
+    }
+    Cow::Owned(encode(&resolve(wire)))
+}
+
+/// Read a text file by the raw path bytes of a search result, tree node or indexable file,
+/// for paths the frontend's file APIs can't express
+#[tauri::command]
+pub async fn read_file_by_raw_path(raw_path_bytes: String) -> Result<String, String> {
+    let path = from_raw_bytes(&raw_path_bytes)?;
+    crate::path_access::ensure_readable(&path)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        std::fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read {}: {}", encode(&path), e))
+    })
+    .await
+    .map_err(|e| format!("Read task failed: {}", e))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_valid_paths_are_unchanged() {
+        for path in [
+            "/work/app/src/main.rs",
+            "/work/100%/done.txt",
+            "/work/ünï.rs",
+        ] {
+            assert_eq!(encode(Path::new(path)), path);
+            assert_eq!(RawPath::of(Path::new(path)), RawPath::default());
+            assert_eq!(resolve(path), PathBuf::from(path));
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_invalid_bytes_round_trip() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let path = Path::new(OsStr::from_bytes(b"/work/caf\xe9 100%/r\xe9sum\xe9.txt"));
+        let wire = encode(path);
+        assert_eq!(wire, "/work/caf%E9 100%25/r%E9sum%E9.txt");
+        assert_eq!(resolve(&wire), path);
+        assert_eq!(index_key(&wire), wire);
+
+        let raw = RawPath::of(path);
+        assert!(raw.path_is_lossy);
+        assert_eq!(
+            from_raw_bytes(raw.raw_path_bytes.as_deref().unwrap()).unwrap(),
+            path
+        );
+
+        // Names that differ only in their invalid bytes stay distinct
+        let latin = Path::new(OsStr::from_bytes(b"/work/\xe9"));
+        let other = Path::new(OsStr::from_bytes(b"/work/\xe8"));
+        assert_ne!(encode(latin), encode(other));
+    }
+}
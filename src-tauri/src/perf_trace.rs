@@ -0,0 +1,347 @@
+// Performance tracing module
+// Span-style timing records for code navigation and search, for when indexing or search
+// is reported slow. Spans are collected into a bounded ring buffer only while tracing is
+// enabled in settings; when it is off, starting a span is a relaxed atomic load. Records
+// can be read back raw or exported as Chrome trace-event JSON for about:tracing/Perfetto.
+
+use crate::database::Database;
+use crate::settings;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::State;
+
+/// Settings key for collecting trace records
+pub const TRACING_SETTING: &str = "perf_tracing_enabled";
+
+/// Records kept; the oldest are dropped first
+const BUFFER_CAPACITY: usize = 20_000;
+/// Records returned by `trace_get_recent` without a limit
+const DEFAULT_RECENT_LIMIT: usize = 500;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static NEXT_THREAD_ID: AtomicU64 = AtomicU64::new(1);
+
+lazy_static::lazy_static! {
+    static ref BUFFER: Mutex<VecDeque<TraceRecord>> = Mutex::new(VecDeque::new());
+    /// Timestamps are microseconds since this instant
+    static ref EPOCH: Instant = Instant::now();
+}
+
+thread_local! {
+    /// Small stable id per thread, so parallel work shows up as separate tracks
+    static THREAD_ID: u64 = NEXT_THREAD_ID.fetch_add(1, Ordering::Relaxed);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Phase {
+    /// The whole operation
+    Total,
+    /// Walking directories for candidate files
+    Walk,
+    Read,
+    Parse,
+    /// Running tree-sitter queries or matching text
+    Query,
+    /// Applying results to the index
+    Merge,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceRecord {
+    pub operation: String,
+    pub phase: Phase,
+    pub file: Option<String>,
+    /// Start, in microseconds since tracing's epoch
+    pub start_us: u64,
+    pub duration_us: u64,
+    pub thread_id: u64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceExportResult {
+    pub path: String,
+    pub events: usize,
+}
+
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+pub fn set_enabled(enabled: bool) {
+    if enabled {
+        // Pin the epoch before the first record
+        lazy_static::initialize(&EPOCH);
+    }
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// A timed section, recorded when dropped
+pub struct Span {
+    operation: &'static str,
+    phase: Phase,
+    file: Option<String>,
+    start: Instant,
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        record(
+            self.operation,
+            self.phase,
+            self.file.take(),
+            self.start,
+            self.start.elapsed(),
+        );
+    }
+}
+
+/// Start a span, or nothing while tracing is disabled. Hold the guard for the section:
+/// `let _span = perf_trace::span("index_file", Phase::Parse, Some(path));`
+#[inline]
+pub fn span(operation: &'static str, phase: Phase, file: Option<&str>) -> Option<Span> {
+    if !enabled() {
+        return None;
+    }
+    Some(Span {
+        operation,
+        phase,
+        file: file.map(str::to_string),
+        start: Instant::now(),
+    })
+}
+
+fn record(
+    operation: &'static str,
+    phase: Phase,
+    file: Option<String>,
+    start: Instant,
+    duration: Duration,
+) {
+    let record = TraceRecord {
+        operation: operation.to_string(),
+        phase,
+        file,
+        start_us: start.saturating_duration_since(*EPOCH).as_micros() as u64,
+        duration_us: duration.as_micros() as u64,
+        thread_id: THREAD_ID.with(|id| *id),
+    };
+    if let Ok(mut buffer) = BUFFER.lock() {
+        if buffer.len() >= BUFFER_CAPACITY {
+            buffer.pop_front();
+        }
+        buffer.push_back(record);
+    }
+}
+
+/// The most recent records, oldest first. The filter matches operations containing it.
+pub fn recent(limit: usize, operation_filter: Option<&str>) -> Vec<TraceRecord> {
+    let Ok(buffer) = BUFFER.lock() else {
+        return Vec::new();
+    };
+    let mut records: Vec<TraceRecord> = buffer
+        .iter()
+        .rev()
+        .filter(|r| operation_filter.is_none_or(|f| r.operation.contains(f)))
+        .take(limit)
+        .cloned()
+        .collect();
+    records.reverse();
+    records
+}
+
+/// Chrome trace-event JSON: one complete ("X") event per record
+pub fn chrome_trace(records: &[TraceRecord]) -> serde_json::Value {
+    let events: Vec<serde_json::Value> = records
+        .iter()
+        .map(|r| {
+            let mut args = serde_json::Map::new();
+            if let Some(ref file) = r.file {
+                args.insert("file".to_string(), file.clone().into());
+            }
+            serde_json::json!({
+                "name": r.operation,
+                "cat": r.phase,
+                "ph": "X",
+                "ts": r.start_us,
+                "dur": r.duration_us,
+                "pid": std::process::id(),
+                "tid": r.thread_id,
+                "args": args,
+            })
+        })
+        .collect();
+    serde_json::json!({
+        "traceEvents": events,
+        "displayTimeUnit": "ms",
+    })
+}
+
+/// Apply the persisted tracing setting
+pub async fn load_from_settings(db: &Database) -> Result<(), String> {
+    if let Some(enabled) = settings::get_json_setting::<bool>(db, TRACING_SETTING).await? {
+        set_enabled(enabled);
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn trace_get_enabled() -> bool {
+    enabled()
+}
+
+/// Turn trace collection on or off. Records already collected are kept.
+#[tauri::command]
+pub async fn trace_set_enabled(db: State<'_, Arc<Database>>, enabled: bool) -> Result<(), String> {
+    settings::set_json_setting(&db, TRACING_SETTING, &enabled).await?;
+    set_enabled(enabled);
+    log::info!(
+        "Performance tracing {}",
+        if enabled { "enabled" } else { "disabled" }
+    );
+    Ok(())
+}
+
+#[tauri::command]
+pub fn trace_get_recent(
+    limit: Option<usize>,
+    operation_filter: Option<String>,
+) -> Vec<TraceRecord> {
+    recent(
+        limit.unwrap_or(DEFAULT_RECENT_LIMIT),
+        operation_filter.as_deref(),
+    )
+}
+
+/// Write every buffered record to a Chrome trace-event JSON file
+#[tauri::command]
+pub fn trace_export_chrome(dest_path: String) -> Result<TraceExportResult, String> {
+    let records = recent(usize::MAX, None);
+    let json = serde_json::to_vec(&chrome_trace(&records)).map_err(|e| e.to_string())?;
+    std::fs::write(&dest_path, json)
+        .map_err(|e| format!("Failed to write trace to {}: {}", dest_path, e))?;
+    log::info!("Exported {} trace events to {}", records.len(), dest_path);
+    Ok(TraceExportResult {
+        path: dest_path,
+        events: records.len(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::RipgrepSearch;
+    use tempfile::TempDir;
+
+    lazy_static::lazy_static! {
+        // Tracing is global; tests that toggle it run one at a time
+        static ref TRACING: Mutex<()> = Mutex::new(());
+    }
+
+    #[test]
+    fn test_spans_recorded_only_when_enabled() {
+        let _guard = TRACING.lock().unwrap();
+
+        set_enabled(false);
+        drop(span("test_disabled_op", Phase::Parse, Some("a.rs")));
+        assert!(recent(usize::MAX, Some("test_disabled_op")).is_empty());
+
+        set_enabled(true);
+        {
+            let _total = span("test_enabled_op", Phase::Total, None);
+            drop(span("test_enabled_op", Phase::Parse, Some("a.rs")));
+        }
+        set_enabled(false);
+
+        let records = recent(usize::MAX, Some("test_enabled_op"));
+        assert_eq!(records.len(), 2);
+        // The inner span ends first
+        assert_eq!(records[0].phase, Phase::Parse);
+        assert_eq!(records[0].file.as_deref(), Some("a.rs"));
+        assert_eq!(records[1].phase, Phase::Total);
+        assert!(records[1].start_us <= records[0].start_us);
+        assert!(records[1].duration_us >= records[0].duration_us);
+    }
+
+    #[test]
+    fn test_recent_limit_and_filter() {
+        let _guard = TRACING.lock().unwrap();
+        set_enabled(true);
+        for _ in 0..5 {
+            drop(span("test_limit_a", Phase::Query, None));
+            drop(span("test_limit_b", Phase::Query, None));
+        }
+        set_enabled(false);
+
+        assert_eq!(
+            trace_get_recent(Some(3), Some("test_limit_a".into())).len(),
+            3
+        );
+        let both = recent(usize::MAX, Some("test_limit_"));
+        assert_eq!(both.len(), 10);
+        assert_eq!(both.last().unwrap().operation, "test_limit_b");
+    }
+
+    #[test]
+    fn test_search_content_is_instrumented() {
+        let _guard = TRACING.lock().unwrap();
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join("lib.rs"), "fn traced_needle() {}\n").unwrap();
+        let root = dir.path().to_string_lossy().to_string();
+
+        let before = recent(usize::MAX, Some("search_content")).len();
+        RipgrepSearch::new()
+            .search_content("traced_needle", &root)
+            .unwrap();
+        assert_eq!(recent(usize::MAX, Some("search_content")).len(), before);
+
+        set_enabled(true);
+        RipgrepSearch::new()
+            .search_content("traced_needle", &root)
+            .unwrap();
+        set_enabled(false);
+        let phases: Vec<Phase> = recent(usize::MAX, Some("search_content"))
+            .into_iter()
+            .skip(before)
+            .map(|r| r.phase)
+            .collect();
+        assert!(phases.contains(&Phase::Walk));
+        assert!(phases.contains(&Phase::Query));
+        assert!(phases.contains(&Phase::Total));
+    }
+
+    #[test]
+    fn test_chrome_export_is_well_formed() {
+        let dir = TempDir::new().unwrap();
+        let records = vec![TraceRecord {
+            operation: "index_file".to_string(),
+            phase: Phase::Parse,
+            file: Some("src/main.rs".to_string()),
+            start_us: 10,
+            duration_us: 250,
+            thread_id: 3,
+        }];
+        let trace = chrome_trace(&records);
+        let event = &trace["traceEvents"][0];
+        assert_eq!(event["name"], "index_file");
+        assert_eq!(event["cat"], "parse");
+        assert_eq!(event["ph"], "X");
+        assert_eq!(event["ts"], 10);
+        assert_eq!(event["dur"], 250);
+        assert_eq!(event["tid"], 3);
+        assert_eq!(event["args"]["file"], "src/main.rs");
+
+        let dest = dir.path().join("trace.json");
+        let result = trace_export_chrome(dest.to_string_lossy().to_string()).unwrap();
+        let written: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(&dest).unwrap()).unwrap();
+        let events = written["traceEvents"].as_array().unwrap();
+        assert_eq!(events.len(), result.events);
+        assert!(events.iter().all(|e| e["ph"] == "X" && e["ts"].is_u64()));
+    }
+}
@@ -0,0 +1,393 @@
+// Process info module
+// Inspects and terminates the process trees of commands we spawned (user shell commands
+// and PTY sessions), e.g. to find and stop a misbehaving dev server's children.
+//
+// The process table comes from sysinfo. The root pids the app spawned are kept in managed
+// state, and only their trees can be killed from the frontend without `force`.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use sysinfo::{
+    Pid, ProcessRefreshKind, ProcessStatus, ProcessesToUpdate, Signal, System, UpdateKind,
+};
+use tauri::{Manager, State};
+
+/// Root pids of processes spawned by the app
+#[derive(Debug, Default)]
+pub struct SpawnedPids(Mutex<HashSet<u32>>);
+
+impl SpawnedPids {
+    pub fn register(&self, pid: u32) {
+        if let Ok(mut pids) = self.0.lock() {
+            pids.insert(pid);
+        }
+    }
+
+    pub fn unregister(&self, pid: u32) {
+        if let Ok(mut pids) = self.0.lock() {
+            pids.remove(&pid);
+        }
+    }
+
+    /// Whether `pid` is a registered root or a descendant of one
+    fn owns(&self, pid: u32, entries: &[ProcessEntry]) -> bool {
+        let Ok(roots) = self.0.lock() else {
+            return false;
+        };
+        let parents: HashMap<u32, u32> = entries.iter().map(|e| (e.pid, e.parent_pid)).collect();
+        let mut current = pid;
+        let mut seen = HashSet::new();
+        while seen.insert(current) {
+            if roots.contains(&current) {
+                return true;
+            }
+            match parents.get(&current) {
+                Some(&parent) if parent != 0 => current = parent,
+                _ => break,
+            }
+        }
+        false
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProcessInfo {
+    pub pid: u32,
+    pub parent_pid: u32,
+    pub name: String,
+    /// Usage over a short sampling interval; can exceed 100 on several cores
+    pub cpu_percent: f32,
+    pub memory_bytes: u64,
+    pub command: String,
+    pub children: Vec<ProcessInfo>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KillSignal {
+    Term,
+    Kill,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KillResult {
+    pub pid: u32,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+struct ProcessEntry {
+    pid: u32,
+    parent_pid: u32,
+    name: String,
+    cpu_percent: f32,
+    memory_bytes: u64,
+    command: String,
+    zombie: bool,
+}
+
+fn spawned_pids() -> Option<State<'static, Arc<SpawnedPids>>> {
+    crate::try_get_app_handle()?.try_state::<Arc<SpawnedPids>>()
+}
+
+/// Record a root pid in the app's managed `SpawnedPids`; no-op before setup
+pub fn register_spawned_pid(pid: u32) {
+    if let Some(spawned) = spawned_pids() {
+        spawned.register(pid);
+    }
+}
+
+pub fn unregister_spawned_pid(pid: u32) {
+    if let Some(spawned) = spawned_pids() {
+        spawned.unregister(pid);
+    }
+}
+
+/// The process table with memory and command lines. sysinfo measures CPU usage between
+/// two refreshes, so with `measure_cpu` the table is sampled twice, a short interval apart.
+fn process_table(measure_cpu: bool) -> System {
+    let mut system = System::new();
+    let mut refresh = ProcessRefreshKind::new()
+        .with_memory()
+        .with_cmd(UpdateKind::OnlyIfNotSet);
+    if measure_cpu {
+        system.refresh_processes_specifics(
+            ProcessesToUpdate::All,
+            true,
+            ProcessRefreshKind::new().with_cpu(),
+        );
+        std::thread::sleep(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL);
+        refresh = refresh.with_cpu();
+    }
+    system.refresh_processes_specifics(ProcessesToUpdate::All, true, refresh);
+    system
+}
+
+/// Processes in the table; on Linux sysinfo also lists threads, which are left out
+fn process_entries(system: &System) -> Vec<ProcessEntry> {
+    system
+        .processes()
+        .values()
+        .filter(|process| process.thread_kind().is_none())
+        .map(|process| ProcessEntry {
+            pid: process.pid().as_u32(),
+            parent_pid: process.parent().map_or(0, Pid::as_u32),
+            name: process.name().to_string_lossy().into_owned(),
+            cpu_percent: process.cpu_usage(),
+            memory_bytes: process.memory(),
+            command: process
+                .cmd()
+                .iter()
+                .map(|arg| arg.to_string_lossy())
+                .collect::<Vec<_>>()
+                .join(" "),
+            zombie: process.status() == ProcessStatus::Zombie,
+        })
+        .collect()
+}
+
+fn children_by_parent(entries: &[ProcessEntry]) -> HashMap<u32, Vec<&ProcessEntry>> {
+    let mut children: HashMap<u32, Vec<&ProcessEntry>> = HashMap::new();
+    for entry in entries
+        .iter()
+        .filter(|e| !e.zombie && e.pid != e.parent_pid)
+    {
+        children.entry(entry.parent_pid).or_default().push(entry);
+    }
+    children
+}
+
+fn build_tree(
+    entry: &ProcessEntry,
+    children: &HashMap<u32, Vec<&ProcessEntry>>,
+    visited: &mut HashSet<u32>,
+) -> ProcessInfo {
+    visited.insert(entry.pid);
+    let mut child_nodes = Vec::new();
+    for kid in children.get(&entry.pid).into_iter().flatten() {
+        // Guard against pid reuse producing a cycle
+        if !visited.contains(&kid.pid) {
+            child_nodes.push(build_tree(kid, children, visited));
+        }
+    }
+    ProcessInfo {
+        pid: entry.pid,
+        parent_pid: entry.parent_pid,
+        name: entry.name.clone(),
+        cpu_percent: entry.cpu_percent,
+        memory_bytes: entry.memory_bytes,
+        command: entry.command.clone(),
+        children: child_nodes,
+    }
+}
+
+/// Pids in the tree, children before their parents
+fn post_order(tree: &ProcessInfo, out: &mut Vec<u32>) {
+    for child in &tree.children {
+        post_order(child, out);
+    }
+    out.push(tree.pid);
+}
+
+pub fn process_tree(root_pid: u32) -> Result<ProcessInfo, String> {
+    let entries = process_entries(&process_table(true));
+    let root = entries
+        .iter()
+        .find(|e| e.pid == root_pid && !e.zombie)
+        .ok_or_else(|| format!("Process {} not found", root_pid))?;
+    let children = children_by_parent(&entries);
+    Ok(build_tree(root, &children, &mut HashSet::new()))
+}
+
+/// Windows has no TERM, so there both signals terminate the process
+fn send_signal(system: &System, pid: u32, signal: KillSignal) -> Result<(), String> {
+    let process = system
+        .process(Pid::from_u32(pid))
+        .ok_or_else(|| format!("Process {} not found", pid))?;
+    let sent = match signal {
+        KillSignal::Term => process
+            .kill_with(Signal::Term)
+            .unwrap_or_else(|| process.kill()),
+        KillSignal::Kill => process.kill(),
+    };
+    if sent {
+        Ok(())
+    } else {
+        Err(format!("Failed to signal process {}", pid))
+    }
+}
+
+/// Signal every process in the tree rooted at `root_pid`, children before parents
+pub fn kill_tree(root_pid: u32, signal: KillSignal) -> Result<Vec<KillResult>, String> {
+    kill_tree_guarded(root_pid, signal, None)
+}
+
+/// Like `kill_tree`, refusing trees `guard` did not spawn
+fn kill_tree_guarded(
+    root_pid: u32,
+    signal: KillSignal,
+    guard: Option<&SpawnedPids>,
+) -> Result<Vec<KillResult>, String> {
+    let mut system = process_table(false);
+    let entries = process_entries(&system);
+    if guard.is_some_and(|spawned| !spawned.owns(root_pid, &entries)) {
+        return Err(format!(
+            "Process {} was not started by this app; pass force to kill it anyway",
+            root_pid
+        ));
+    }
+    let root = entries
+        .iter()
+        .find(|e| e.pid == root_pid && !e.zombie)
+        .ok_or_else(|| format!("Process {} not found", root_pid))?;
+    let tree = build_tree(root, &children_by_parent(&entries), &mut HashSet::new());
+
+    let mut pids = Vec::new();
+    post_order(&tree, &mut pids);
+    log::info!(
+        "Sending {:?} to process tree {} ({} processes)",
+        signal,
+        root_pid,
+        pids.len()
+    );
+
+    let mut results: Vec<KillResult> = pids
+        .into_iter()
+        .map(|pid| match send_signal(&system, pid, signal) {
+            Ok(()) => KillResult {
+                pid,
+                success: true,
+                error: None,
+            },
+            Err(e) => KillResult {
+                pid,
+                success: false,
+                error: Some(e),
+            },
+        })
+        .collect();
+
+    // A child may exit on its own once its parent or siblings go away; that still counts
+    if results.iter().any(|r| !r.success) {
+        system.refresh_processes_specifics(ProcessesToUpdate::All, true, ProcessRefreshKind::new());
+        let remaining: HashSet<u32> = process_entries(&system)
+            .iter()
+            .filter(|e| !e.zombie)
+            .map(|e| e.pid)
+            .collect();
+        for result in results.iter_mut().filter(|r| !r.success) {
+            if !remaining.contains(&result.pid) {
+                result.success = true;
+                result.error = None;
+            }
+        }
+    }
+    Ok(results)
+}
+
+#[tauri::command]
+pub async fn list_process_tree(root_pid: u32) -> Result<ProcessInfo, String> {
+    tauri::async_runtime::spawn_blocking(move || process_tree(root_pid))
+        .await
+        .map_err(|e| format!("Process inspection failed: {}", e))?
+}
+
+/// Refuses trees the app did not spawn unless `force` is set
+#[tauri::command]
+pub async fn kill_process_tree(
+    spawned: State<'_, Arc<SpawnedPids>>,
+    root_pid: u32,
+    signal: KillSignal,
+    force: Option<bool>,
+) -> Result<Vec<KillResult>, String> {
+    let spawned = spawned.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let guard = (!force.unwrap_or(false)).then_some(spawned.as_ref());
+        let results = kill_tree_guarded(root_pid, signal, guard)?;
+        spawned.unregister(root_pid);
+        Ok(results)
+    })
+    .await
+    .map_err(|e| format!("Process kill failed: {}", e))?
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::process::Command;
+    use std::time::{Duration, Instant};
+
+    fn tree_pids(tree: &ProcessInfo) -> Vec<u32> {
+        let mut pids = Vec::new();
+        post_order(tree, &mut pids);
+        pids
+    }
+
+    fn is_alive(pid: u32) -> bool {
+        process_entries(&process_table(false))
+            .iter()
+            .any(|e| e.pid == pid && !e.zombie)
+    }
+
+    #[test]
+    fn test_process_entries_describe_the_current_process() {
+        let entries = process_entries(&process_table(true));
+        let me = entries
+            .iter()
+            .find(|e| e.pid == std::process::id())
+            .unwrap();
+        assert!(me.memory_bytes > 0);
+        assert!(!me.command.is_empty());
+        assert!(me.cpu_percent >= 0.0);
+        // The test harness threads are not listed as children of this process
+        assert!(!entries
+            .iter()
+            .any(|e| e.parent_pid == me.pid && e.name == me.name));
+    }
+
+    #[test]
+    fn test_list_and_kill_process_tree() {
+        let mut child = Command::new("sh")
+            .args(["-c", "sleep 30 & sleep 30 & sh -c 'sleep 30; true' & wait"])
+            .spawn()
+            .unwrap();
+        let root_pid = child.id();
+
+        // Wait for the shell to start its children
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let tree = loop {
+            let tree = process_tree(root_pid).unwrap();
+            if tree_pids(&tree).len() >= 5 || Instant::now() > deadline {
+                break tree;
+            }
+            std::thread::sleep(Duration::from_millis(50));
+        };
+        let pids = tree_pids(&tree);
+        assert_eq!(pids.len(), 5, "unexpected tree: {:?}", tree);
+        assert_eq!(*pids.last().unwrap(), root_pid);
+        assert_eq!(tree.children.len(), 3);
+        assert!(tree.command.contains("sleep 30"));
+
+        // Not registered: refused without force
+        let spawned = SpawnedPids::default();
+        assert!(kill_tree_guarded(root_pid, KillSignal::Kill, Some(&spawned)).is_err());
+        assert!(is_alive(root_pid));
+
+        spawned.register(root_pid);
+        let results = kill_tree_guarded(root_pid, KillSignal::Kill, Some(&spawned)).unwrap();
+        assert_eq!(results.len(), 5);
+        assert!(results.iter().all(|r| r.success), "{:?}", results);
+        assert_eq!(results.last().unwrap().pid, root_pid);
+
+        child.wait().unwrap();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while pids.iter().any(|pid| is_alive(*pid)) && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        assert!(pids.iter().all(|pid| !is_alive(*pid)));
+    }
+}
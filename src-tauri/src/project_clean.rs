@@ -0,0 +1,500 @@
+// Project clean module
+// Finds and deletes build artifacts and caches (node_modules, target, dist, __pycache__,
+// .next, ...) to free disk space. Only runs on directories that look like projects.
+
+use crate::AppState;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, State};
+
+/// Time cap for computing directory sizes
+const SIZE_TIME_BUDGET: Duration = Duration::from_secs(5);
+
+/// Files whose presence marks a directory as a project root
+const PROJECT_MANIFESTS: &[&str] = &[
+    "package.json",
+    "Cargo.toml",
+    "pyproject.toml",
+    "setup.py",
+    "requirements.txt",
+    "go.mod",
+    "pom.xml",
+    "build.gradle",
+    "build.gradle.kts",
+    "composer.json",
+    "Gemfile",
+    "deno.json",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ArtifactCategory {
+    NodeModules,
+    RustTarget,
+    BuildOutput,
+    PythonCache,
+    FrameworkCache,
+}
+
+impl ArtifactCategory {
+    const ALL: [ArtifactCategory; 5] = [
+        ArtifactCategory::NodeModules,
+        ArtifactCategory::RustTarget,
+        ArtifactCategory::BuildOutput,
+        ArtifactCategory::PythonCache,
+        ArtifactCategory::FrameworkCache,
+    ];
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArtifactDirectory {
+    pub path: String,
+    pub size_bytes: u64,
+    /// False when the size calculation hit the time cap
+    pub size_complete: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CleanCategoryReport {
+    pub category: ArtifactCategory,
+    pub directories: Vec<ArtifactDirectory>,
+    pub total_bytes: u64,
+    pub freed_bytes: u64,
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CleanProjectResult {
+    pub dry_run: bool,
+    pub categories: Vec<CleanCategoryReport>,
+    pub freed_bytes: u64,
+}
+
+fn has_manifest(dir: &Path) -> bool {
+    PROJECT_MANIFESTS.iter().any(|m| dir.join(m).is_file())
+}
+
+/// Category of an artifact directory, judged by its name and, for generic names like
+/// `target` or `build`, by a manifest next to it
+pub fn category_for_dir(name: &str, parent: &Path) -> Option<ArtifactCategory> {
+    match name {
+        "node_modules" => Some(ArtifactCategory::NodeModules),
+        "target"
+            if parent.join("Cargo.toml").is_file()
+                || parent.join("target").join("CACHEDIR.TAG").is_file() =>
+        {
+            Some(ArtifactCategory::RustTarget)
+        }
+        "dist" | "build" | "out" if has_manifest(parent) => Some(ArtifactCategory::BuildOutput),
+        "__pycache__" | ".pytest_cache" | ".mypy_cache" | ".ruff_cache" => {
+            Some(ArtifactCategory::PythonCache)
+        }
+        ".next" | ".nuxt" | ".turbo" | ".svelte-kit" | ".parcel-cache" | ".angular" => {
+            Some(ArtifactCategory::FrameworkCache)
+        }
+        _ => None,
+    }
+}
+
+/// Refuse roots that are not projects, so the command can't be pointed at a home directory
+fn ensure_project_root(root: &Path) -> Result<(), String> {
+    if !root.is_dir() {
+        return Err(format!("Not a directory: {}", root.display()));
+    }
+    if dirs::home_dir().is_some_and(|home| home == root) || root.parent().is_none() {
+        return Err(format!(
+            "Refusing to clean {}: not a project directory",
+            root.display()
+        ));
+    }
+    if !has_manifest(root) {
+        return Err(format!(
+            "Refusing to clean {}: no project manifest (package.json, Cargo.toml, ...) found",
+            root.display()
+        ));
+    }
+    Ok(())
+}
+
+/// Find artifact directories of the selected categories. Recognized artifact directories are
+/// never descended into, so a `dist` inside `node_modules` is not reported separately.
+fn find_artifacts(
+    root: &Path,
+    categories: &[ArtifactCategory],
+) -> Vec<(ArtifactCategory, PathBuf)> {
+    let mut found = Vec::new();
+    let mut stack = vec![root.to_path_buf()];
+    while let Some(dir) = stack.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            // file_type does not follow symlinks
+            if !entry.file_type().is_ok_and(|t| t.is_dir()) {
+                continue;
+            }
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name == ".git" {
+                continue;
+            }
+            match category_for_dir(&name, &dir) {
+                Some(category) if categories.contains(&category) => {
+                    found.push((category, entry.path()))
+                }
+                Some(_) => {}
+                None => stack.push(entry.path()),
+            }
+        }
+    }
+    found.sort_by(|a, b| a.1.cmp(&b.1));
+    found
+}
+
+/// Size of a directory tree, stopping at `deadline`; the flag is false if it was cut short
+fn dir_size(path: &Path, deadline: Instant) -> (u64, bool) {
+    let mut total = 0;
+    let mut stack = vec![path.to_path_buf()];
+    while let Some(dir) = stack.pop() {
+        if Instant::now() >= deadline {
+            return (total, false);
+        }
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            if metadata.is_dir() && !entry.file_type().is_ok_and(|t| t.is_symlink()) {
+                stack.push(entry.path());
+            } else {
+                total += metadata.len();
+            }
+        }
+    }
+    (total, true)
+}
+
+#[cfg(target_os = "macos")]
+fn trash_dir() -> io::Result<PathBuf> {
+    dirs::home_dir()
+        .map(|home| home.join(".Trash"))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "No home directory"))
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn trash_dir() -> io::Result<PathBuf> {
+    // freedesktop.org trash: $XDG_DATA_HOME/Trash
+    dirs::data_dir()
+        .map(|data| data.join("Trash"))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "No data directory"))
+}
+
+/// Move a directory to the user's trash. This is a rename, so it fails for paths on a
+/// different filesystem than the trash; callers fall back to reporting the error.
+#[cfg(unix)]
+fn move_to_trash(path: &Path) -> io::Result<()> {
+    let trash = trash_dir()?;
+    let files_dir = if cfg!(target_os = "macos") {
+        trash.clone()
+    } else {
+        trash.join("files")
+    };
+    fs::create_dir_all(&files_dir)?;
+
+    let base = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "artifact".to_string());
+    let mut name = base.clone();
+    let mut counter = 1;
+    while files_dir.join(&name).exists() {
+        name = format!("{} {}", base, counter);
+        counter += 1;
+    }
+
+    if !cfg!(target_os = "macos") {
+        let info_dir = trash.join("info");
+        fs::create_dir_all(&info_dir)?;
+        let encoded_path = url::Url::from_file_path(path)
+            .map(|u| u.path().to_string())
+            .unwrap_or_else(|_| path.to_string_lossy().to_string());
+        let info = format!(
+            "[Trash Info]\nPath={}\nDeletionDate={}\n",
+            encoded_path,
+            chrono::Local::now().format("%Y-%m-%dT%H:%M:%S")
+        );
+        fs::write(info_dir.join(format!("{}.trashinfo", name)), info)?;
+        if let Err(e) = fs::rename(path, files_dir.join(&name)) {
+            let _ = fs::remove_file(info_dir.join(format!("{}.trashinfo", name)));
+            return Err(e);
+        }
+        return Ok(());
+    }
+
+    fs::rename(path, files_dir.join(&name))
+}
+
+#[cfg(not(unix))]
+fn move_to_trash(_path: &Path) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "Moving to the Recycle Bin is not supported",
+    ))
+}
+
+/// Scan `root` for artifacts of the given categories (all when empty), then either report
+/// them (dry run) or delete them. Deletion goes to the trash unless `permanent` is set.
+pub fn clean_artifacts(
+    root: &Path,
+    categories: &[ArtifactCategory],
+    dry_run: bool,
+    permanent: bool,
+) -> Result<CleanProjectResult, String> {
+    ensure_project_root(root)?;
+    let categories: Vec<ArtifactCategory> = if categories.is_empty() {
+        ArtifactCategory::ALL.to_vec()
+    } else {
+        categories.to_vec()
+    };
+
+    let artifacts = find_artifacts(root, &categories);
+    let deadline = Instant::now() + SIZE_TIME_BUDGET;
+    let sized: Vec<(ArtifactCategory, ArtifactDirectory)> = artifacts
+        .par_iter()
+        .map(|(category, path)| {
+            let (size_bytes, size_complete) = dir_size(path, deadline);
+            (
+                *category,
+                ArtifactDirectory {
+                    path: path.to_string_lossy().to_string(),
+                    size_bytes,
+                    size_complete,
+                },
+            )
+        })
+        .collect();
+
+    let mut reports: Vec<CleanCategoryReport> = categories
+        .iter()
+        .map(|category| CleanCategoryReport {
+            category: *category,
+            directories: Vec::new(),
+            total_bytes: 0,
+            freed_bytes: 0,
+            errors: Vec::new(),
+        })
+        .collect();
+
+    for (category, directory) in sized {
+        let Some(report) = reports.iter_mut().find(|r| r.category == category) else {
+            continue;
+        };
+        report.total_bytes += directory.size_bytes;
+        if !dry_run {
+            let path = Path::new(&directory.path);
+            let result = if permanent {
+                fs::remove_dir_all(path)
+            } else {
+                move_to_trash(path)
+            };
+            match result {
+                Ok(()) => report.freed_bytes += directory.size_bytes,
+                Err(e) if permanent => {
+                    report
+                        .errors
+                        .push(format!("Failed to delete {}: {}", directory.path, e));
+                }
+                Err(e) => report.errors.push(format!(
+                    "Failed to move {} to trash: {} (permanent deletion may be required)",
+                    directory.path, e
+                )),
+            }
+        }
+        report.directories.push(directory);
+    }
+
+    let freed_bytes = reports.iter().map(|r| r.freed_bytes).sum();
+    Ok(CleanProjectResult {
+        dry_run,
+        categories: reports,
+        freed_bytes,
+    })
+}
+
+#[tauri::command]
+pub async fn clean_project_artifacts(
+    root_path: String,
+    categories: Vec<ArtifactCategory>,
+    dry_run: bool,
+    permanent: Option<bool>,
+    window_label: Option<String>,
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<CleanProjectResult, String> {
+    let root = PathBuf::from(&root_path);
+    if dry_run {
+        return tauri::async_runtime::spawn_blocking(move || {
+            clean_artifacts(&root, &categories, true, false)
+        })
+        .await
+        .map_err(|e| format!("Clean task failed: {}", e))?;
+    }
+
+    ensure_project_root(&root)?;
+    log::info!(
+        "Cleaning project artifacts in {} ({:?}, permanent: {})",
+        root_path,
+        categories,
+        permanent.unwrap_or(false)
+    );
+
+    // Deleting thousands of files would flood the watcher with events
+    if let Some(ref label) = window_label {
+        state.window_registry.set_window_file_watcher(label, None)?;
+    }
+
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        clean_artifacts(&root, &categories, false, permanent.unwrap_or(false))
+    })
+    .await
+    .map_err(|e| format!("Clean task failed: {}", e));
+
+    crate::directory_tree::clear_directory_cache();
+    if let Some(label) = window_label {
+        if let Err(e) = crate::start_window_watcher(&state, app_handle, label, &root_path, None) {
+            log::error!("Failed to restart file watcher after clean: {}", e);
+        }
+    }
+
+    let result = result??;
+    log::info!("Cleaned {} bytes from {}", result.freed_bytes, root_path);
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write_file(path: &Path, size: usize) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, vec![b'x'; size]).unwrap();
+    }
+
+    #[test]
+    fn test_category_matcher() {
+        let temp_dir = TempDir::new().unwrap();
+        let project = temp_dir.path();
+        let plain = project.join("docs");
+        fs::create_dir_all(&plain).unwrap();
+
+        assert_eq!(
+            category_for_dir("node_modules", &plain),
+            Some(ArtifactCategory::NodeModules)
+        );
+        assert_eq!(
+            category_for_dir("__pycache__", &plain),
+            Some(ArtifactCategory::PythonCache)
+        );
+        assert_eq!(
+            category_for_dir(".next", &plain),
+            Some(ArtifactCategory::FrameworkCache)
+        );
+        assert_eq!(
+            category_for_dir(".turbo", &plain),
+            Some(ArtifactCategory::FrameworkCache)
+        );
+        assert_eq!(category_for_dir("src", &plain), None);
+
+        // Generic names only count next to a manifest
+        assert_eq!(category_for_dir("target", &plain), None);
+        assert_eq!(category_for_dir("build", &plain), None);
+        fs::write(project.join("Cargo.toml"), "[package]").unwrap();
+        fs::write(project.join("package.json"), "{}").unwrap();
+        assert_eq!(
+            category_for_dir("target", project),
+            Some(ArtifactCategory::RustTarget)
+        );
+        assert_eq!(
+            category_for_dir("dist", project),
+            Some(ArtifactCategory::BuildOutput)
+        );
+    }
+
+    #[test]
+    fn test_refuses_non_project_root() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        write_file(&root.join("node_modules").join("pkg").join("index.js"), 10);
+
+        let err = clean_artifacts(root, &[], true, true).unwrap_err();
+        assert!(err.contains("no project manifest"));
+        let err = clean_artifacts(root, &[], false, true).unwrap_err();
+        assert!(err.contains("no project manifest"));
+        assert!(root.join("node_modules").exists());
+
+        assert!(clean_artifacts(Path::new("/"), &[], true, true).is_err());
+    }
+
+    #[test]
+    fn test_dry_run_then_permanent_delete() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::write(root.join("package.json"), "{}").unwrap();
+        write_file(&root.join("node_modules").join("a").join("index.js"), 100);
+        // Nested dist inside node_modules is part of node_modules, not a build output
+        write_file(
+            &root
+                .join("node_modules")
+                .join("a")
+                .join("dist")
+                .join("a.js"),
+            50,
+        );
+        write_file(&root.join("dist").join("bundle.js"), 30);
+        write_file(&root.join("app").join("__pycache__").join("m.pyc"), 20);
+        write_file(&root.join("src").join("build").join("keep.ts"), 5);
+
+        let dry = clean_artifacts(root, &[], true, false).unwrap();
+        assert!(dry.dry_run);
+        assert_eq!(dry.freed_bytes, 0);
+        let report = |result: &CleanProjectResult, category| {
+            result
+                .categories
+                .iter()
+                .find(|r| r.category == category)
+                .cloned()
+                .unwrap()
+        };
+        let node_modules = report(&dry, ArtifactCategory::NodeModules);
+        assert_eq!(node_modules.directories.len(), 1);
+        assert_eq!(node_modules.total_bytes, 150);
+        assert!(node_modules.directories[0].size_complete);
+        let build = report(&dry, ArtifactCategory::BuildOutput);
+        assert_eq!(build.directories.len(), 1);
+        assert_eq!(build.total_bytes, 30);
+        assert!(root.join("node_modules").exists());
+
+        let cleaned = clean_artifacts(
+            root,
+            &[ArtifactCategory::NodeModules, ArtifactCategory::PythonCache],
+            false,
+            true,
+        )
+        .unwrap();
+        assert_eq!(cleaned.freed_bytes, 170);
+        assert_eq!(cleaned.categories.len(), 2);
+        assert!(!root.join("node_modules").exists());
+        assert!(!root.join("app").join("__pycache__").exists());
+        assert!(root.join("dist").exists());
+        assert!(root.join("src").join("build").join("keep.ts").exists());
+    }
+}
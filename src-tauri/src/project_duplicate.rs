@@ -0,0 +1,689 @@
+// Project duplication module
+// Copies a project folder so it can be experimented on. Where the filesystem supports
+// copy-on-write clones (APFS via clonefile, Btrfs/XFS via the FICLONE ioctl) files are
+// cloned, which is near instant and shares blocks until either copy is modified; other
+// files are copied. Excluded directories such as node_modules and target are left out
+// unless asked for, but .git is copied intact so the duplicate stays a working repository.
+
+use crate::activity::{self, ActivityKind, ActivityOutcome};
+use crate::cancellation::CancellationToken;
+use crate::exclusions::{self, ExclusionConfig};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DuplicateMode {
+    /// Clone where supported, copy otherwise
+    #[default]
+    Auto,
+    /// Clone every file; fail when the filesystem can't
+    Clone,
+    /// Always copy
+    Copy,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateResult {
+    pub dest_root: String,
+    /// Size of the regular files duplicated
+    pub total_bytes: u64,
+    pub files_cloned: u64,
+    pub files_copied: u64,
+    pub symlinks: u64,
+    /// Excluded directories left out, relative to the source root
+    pub skipped_dirs: Vec<String>,
+    pub elapsed_ms: u64,
+}
+
+enum EntryKind {
+    Dir(fs::Permissions),
+    File(u64),
+    Symlink(PathBuf),
+}
+
+struct Entry {
+    /// Relative to the source root
+    relative: PathBuf,
+    kind: EntryKind,
+}
+
+#[derive(Default)]
+struct Plan {
+    entries: Vec<Entry>,
+    total_bytes: u64,
+    skipped_dirs: Vec<String>,
+}
+
+/// Whether a directory is left out. Nothing inside .git is ever skipped, since git keeps
+/// directories there (logs, for one) whose names are on the exclusion list.
+fn is_skipped(relative: &Path, name: &str, exclusions: Option<&ExclusionConfig>) -> bool {
+    let Some(exclusions) = exclusions else {
+        return false;
+    };
+    let in_git_dir = relative
+        .components()
+        .next()
+        .is_some_and(|c| c.as_os_str() == ".git");
+    name != ".git" && !in_git_dir && exclusions.excludes_dir(name)
+}
+
+fn collect(
+    source_root: &Path,
+    relative: &Path,
+    exclusions: Option<&ExclusionConfig>,
+    cancel: Option<&CancellationToken>,
+    plan: &mut Plan,
+) -> Result<(), String> {
+    if cancel.is_some_and(|token| token.is_cancelled()) {
+        return Err("Duplication cancelled".to_string());
+    }
+    let dir = source_root.join(relative);
+    let mut entries = fs::read_dir(&dir)
+        .map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?;
+    entries.sort_by_key(|entry| entry.file_name());
+
+    for entry in entries {
+        let name = entry.file_name();
+        let child = relative.join(&name);
+        let path = entry.path();
+        let metadata = fs::symlink_metadata(&path)
+            .map_err(|e| format!("Failed to stat {}: {}", path.display(), e))?;
+        let file_type = metadata.file_type();
+        if file_type.is_symlink() {
+            let target = fs::read_link(&path)
+                .map_err(|e| format!("Failed to read link {}: {}", path.display(), e))?;
+            plan.entries.push(Entry {
+                relative: child,
+                kind: EntryKind::Symlink(target),
+            });
+        } else if file_type.is_dir() {
+            if is_skipped(relative, &name.to_string_lossy(), exclusions) {
+                plan.skipped_dirs
+                    .push(child.to_string_lossy().replace('\\', "/"));
+                continue;
+            }
+            plan.entries.push(Entry {
+                relative: child.clone(),
+                kind: EntryKind::Dir(metadata.permissions()),
+            });
+            collect(source_root, &child, exclusions, cancel, plan)?;
+        } else if file_type.is_file() {
+            plan.total_bytes += metadata.len();
+            plan.entries.push(Entry {
+                relative: child,
+                kind: EntryKind::File(metadata.len()),
+            });
+        }
+        // Sockets, fifos and devices are not project content
+    }
+    Ok(())
+}
+
+/// Clone `source` to the new file `dest`, sharing its blocks
+#[cfg(target_os = "macos")]
+fn clone_file(source: &Path, dest: &Path) -> io::Result<()> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let source = CString::new(source.as_os_str().as_bytes())?;
+    let dest = CString::new(dest.as_os_str().as_bytes())?;
+    // SAFETY: both pointers are NUL-terminated paths that outlive the call
+    let rc = unsafe { libc::clonefile(source.as_ptr(), dest.as_ptr(), libc::CLONE_NOFOLLOW) };
+    if rc == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+/// Clone `source` to the new file `dest`, sharing its blocks
+#[cfg(target_os = "linux")]
+fn clone_file(source: &Path, dest: &Path) -> io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+
+    // _IOW(0x94, 9, int), from linux/fs.h
+    const FICLONE: libc::c_ulong = 0x4004_9409;
+
+    let input = fs::File::open(source)?;
+    let output = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(dest)?;
+    // SAFETY: both descriptors are open for the duration of the call
+    let rc = unsafe { libc::ioctl(output.as_raw_fd(), FICLONE as _, input.as_raw_fd()) };
+    if rc != 0 {
+        let error = io::Error::last_os_error();
+        drop(output);
+        let _ = fs::remove_file(dest);
+        return Err(error);
+    }
+    output.set_permissions(input.metadata()?.permissions())
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
+fn clone_file(_source: &Path, _dest: &Path) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "copy-on-write clones are not available on this platform",
+    ))
+}
+
+#[cfg(unix)]
+pub(crate) fn create_symlink(target: &Path, link: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+#[cfg(windows)]
+pub(crate) fn create_symlink(target: &Path, link: &Path) -> io::Result<()> {
+    // Resolve relative targets against the link's directory to pick the link type
+    let resolved = link.parent().map(|dir| dir.join(target));
+    if resolved.is_some_and(|path| path.is_dir()) {
+        std::os::windows::fs::symlink_dir(target, link)
+    } else {
+        std::os::windows::fs::symlink_file(target, link)
+    }
+}
+
+type CloneFn = fn(&Path, &Path) -> io::Result<()>;
+
+fn validate_roots(source_root: &Path, dest_root: &Path) -> Result<(), String> {
+    if !source_root.is_dir() {
+        return Err(format!(
+            "Source is not a directory: {}",
+            source_root.display()
+        ));
+    }
+    if dest_root.exists() {
+        return Err(format!(
+            "Destination already exists: {}",
+            dest_root.display()
+        ));
+    }
+    let source = source_root
+        .canonicalize()
+        .map_err(|e| format!("Failed to resolve {}: {}", source_root.display(), e))?;
+    let dest_parent = dest_root
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let dest_parent = dest_parent.canonicalize().map_err(|e| {
+        format!(
+            "Destination folder {} is not accessible: {}",
+            dest_parent.display(),
+            e
+        )
+    })?;
+    if dest_parent.starts_with(&source) {
+        return Err("The destination can't be inside the project being duplicated".to_string());
+    }
+    Ok(())
+}
+
+fn execute(
+    source_root: &Path,
+    dest_root: &Path,
+    plan: &Plan,
+    mode: DuplicateMode,
+    clone: CloneFn,
+    cancel: Option<&CancellationToken>,
+    on_progress: &mut dyn FnMut(u64, &Path),
+) -> Result<DuplicateResult, String> {
+    let mut result = DuplicateResult::default();
+    fs::create_dir(dest_root)
+        .map_err(|e| format!("Failed to create {}: {}", dest_root.display(), e))?;
+    // Cleared after the first failed clone: the rest of the tree is on the same
+    // filesystem and would fail the same way
+    let mut try_clone = mode != DuplicateMode::Copy;
+    let mut done_bytes = 0;
+    let mut dir_permissions = Vec::new();
+
+    for entry in &plan.entries {
+        if cancel.is_some_and(|token| token.is_cancelled()) {
+            return Err("Duplication cancelled".to_string());
+        }
+        let source = source_root.join(&entry.relative);
+        let dest = dest_root.join(&entry.relative);
+        match &entry.kind {
+            EntryKind::Dir(permissions) => {
+                fs::create_dir(&dest)
+                    .map_err(|e| format!("Failed to create {}: {}", dest.display(), e))?;
+                dir_permissions.push((dest, permissions.clone()));
+            }
+            EntryKind::Symlink(target) => {
+                create_symlink(target, &dest)
+                    .map_err(|e| format!("Failed to link {}: {}", dest.display(), e))?;
+                result.symlinks += 1;
+            }
+            EntryKind::File(size) => {
+                let cloned = if try_clone {
+                    match clone(&source, &dest) {
+                        Ok(()) => true,
+                        Err(e) if mode == DuplicateMode::Clone => {
+                            return Err(format!(
+                                "Copy-on-write clone of {} failed: {}",
+                                source.display(),
+                                e
+                            ));
+                        }
+                        Err(e) => {
+                            log::info!(
+                                "Clones unavailable under {} ({}), copying instead",
+                                dest_root.display(),
+                                e
+                            );
+                            try_clone = false;
+                            false
+                        }
+                    }
+                } else {
+                    false
+                };
+                if cloned {
+                    result.files_cloned += 1;
+                } else {
+                    // fs::copy carries the permission bits over
+                    fs::copy(&source, &dest)
+                        .map_err(|e| format!("Failed to copy {}: {}", source.display(), e))?;
+                    result.files_copied += 1;
+                }
+                done_bytes += size;
+                on_progress(done_bytes, &entry.relative);
+            }
+        }
+    }
+
+    // Last, and innermost first, so read-only directories were still writable while
+    // their contents were created
+    for (dir, permissions) in dir_permissions.into_iter().rev() {
+        fs::set_permissions(&dir, permissions)
+            .map_err(|e| format!("Failed to set permissions on {}: {}", dir.display(), e))?;
+    }
+    if let Ok(metadata) = fs::metadata(source_root) {
+        let _ = fs::set_permissions(dest_root, metadata.permissions());
+    }
+    Ok(result)
+}
+
+fn duplicate_with(
+    source_root: &Path,
+    dest_root: &Path,
+    mode: DuplicateMode,
+    include_artifacts: bool,
+    clone: CloneFn,
+    cancel: Option<&CancellationToken>,
+    on_progress: &mut dyn FnMut(u64, u64, &Path),
+) -> Result<DuplicateResult, String> {
+    let start = Instant::now();
+    validate_roots(source_root, dest_root)?;
+
+    let exclusions = (!include_artifacts).then(exclusions::current);
+    let mut plan = Plan::default();
+    collect(
+        source_root,
+        Path::new(""),
+        exclusions.as_deref(),
+        cancel,
+        &mut plan,
+    )?;
+
+    let total_bytes = plan.total_bytes;
+    let executed = execute(
+        source_root,
+        dest_root,
+        &plan,
+        mode,
+        clone,
+        cancel,
+        &mut |done, path| on_progress(done, total_bytes, path),
+    );
+    match executed {
+        Ok(result) => Ok(DuplicateResult {
+            dest_root: dest_root.to_string_lossy().to_string(),
+            total_bytes,
+            skipped_dirs: plan.skipped_dirs,
+            elapsed_ms: start.elapsed().as_millis() as u64,
+            ..result
+        }),
+        Err(e) => {
+            // Don't leave a half-made project behind
+            if dest_root.exists() {
+                if let Err(cleanup) = remove_partial(dest_root) {
+                    log::warn!(
+                        "Failed to remove partial duplicate {}: {}",
+                        dest_root.display(),
+                        cleanup
+                    );
+                }
+            }
+            Err(e)
+        }
+    }
+}
+
+/// Remove a partial destination, making read-only directories writable first
+fn remove_partial(dest_root: &Path) -> io::Result<()> {
+    if fs::remove_dir_all(dest_root).is_ok() {
+        return Ok(());
+    }
+    make_writable(dest_root)?;
+    fs::remove_dir_all(dest_root)
+}
+
+fn make_writable(dir: &Path) -> io::Result<()> {
+    let metadata = fs::symlink_metadata(dir)?;
+    if !metadata.is_dir() {
+        return Ok(());
+    }
+    let mut permissions = metadata.permissions();
+    #[allow(clippy::permissions_set_readonly_false)]
+    permissions.set_readonly(false);
+    fs::set_permissions(dir, permissions)?;
+    for entry in fs::read_dir(dir)?.flatten() {
+        make_writable(&entry.path())?;
+    }
+    Ok(())
+}
+
+/// Duplicate `source_root` to `dest_root`, which must not exist yet
+pub fn duplicate(
+    source_root: &Path,
+    dest_root: &Path,
+    mode: DuplicateMode,
+    include_artifacts: bool,
+    cancel: Option<&CancellationToken>,
+    on_progress: &mut dyn FnMut(u64, u64, &Path),
+) -> Result<DuplicateResult, String> {
+    duplicate_with(
+        source_root,
+        dest_root,
+        mode,
+        include_artifacts,
+        clone_file,
+        cancel,
+        on_progress,
+    )
+}
+
+/// Duplicate a project folder, cloning files copy-on-write where the filesystem allows.
+/// Runs as a cancellable activity; a cancelled or failed run removes the partial copy.
+#[tauri::command]
+pub async fn duplicate_project(
+    source_root: String,
+    dest_root: String,
+    mode: Option<DuplicateMode>,
+    include_artifacts: Option<bool>,
+) -> Result<DuplicateResult, String> {
+    let activity_id = activity::activity_start(
+        ActivityKind::Other,
+        format!("Duplicating {}", source_root),
+        true,
+    );
+    let cancellation = activity::cancellation_token(&activity_id);
+    let id = activity_id.clone();
+    let token = cancellation.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        duplicate(
+            Path::new(&source_root),
+            Path::new(&dest_root),
+            mode.unwrap_or_default(),
+            include_artifacts.unwrap_or(false),
+            token.as_ref(),
+            &mut |done, total, path| {
+                activity::activity_progress(
+                    &id,
+                    done,
+                    Some(total),
+                    Some(path.to_string_lossy().to_string()),
+                )
+            },
+        )
+    })
+    .await
+    .map_err(|e| format!("Duplication task failed: {}", e))
+    .and_then(|result| result);
+
+    let outcome = if cancellation.is_some_and(|t| t.is_cancelled()) {
+        ActivityOutcome::Cancelled
+    } else {
+        ActivityOutcome::from_result(&result)
+    };
+    activity::activity_finish(&activity_id, outcome);
+    if let Ok(ref summary) = result {
+        log::info!(
+            "Duplicated {} bytes to {} ({} cloned, {} copied) in {}ms",
+            summary.total_bytes,
+            summary.dest_root,
+            summary.files_cloned,
+            summary.files_copied,
+            summary.elapsed_ms
+        );
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn unsupported(_source: &Path, _dest: &Path) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "no clones here"))
+    }
+
+    fn sample_project(dir: &Path) {
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::write(dir.join("src/main.rs"), "fn main() {}\n").unwrap();
+        fs::write(dir.join("README.md"), "# demo\n").unwrap();
+        fs::create_dir_all(dir.join("node_modules/left-pad")).unwrap();
+        fs::write(
+            dir.join("node_modules/left-pad/index.js"),
+            "module.exports = 1;\n",
+        )
+        .unwrap();
+        fs::create_dir_all(dir.join("target/debug")).unwrap();
+        fs::write(dir.join("target/debug/app"), vec![0u8; 4096]).unwrap();
+        // Git keeps directories whose names are excluded elsewhere
+        fs::create_dir_all(dir.join(".git/logs/refs")).unwrap();
+        fs::write(dir.join(".git/HEAD"), "ref: refs/heads/main\n").unwrap();
+        fs::write(dir.join(".git/logs/HEAD"), "0000 1111 init\n").unwrap();
+    }
+
+    fn run(
+        source: &Path,
+        dest: &Path,
+        mode: DuplicateMode,
+        include_artifacts: bool,
+        clone: CloneFn,
+    ) -> Result<DuplicateResult, String> {
+        duplicate_with(
+            source,
+            dest,
+            mode,
+            include_artifacts,
+            clone,
+            None,
+            &mut |_, _, _| {},
+        )
+    }
+
+    #[test]
+    fn test_auto_falls_back_to_copies() {
+        let temp = TempDir::new().unwrap();
+        let source = temp.path().join("project");
+        sample_project(&source);
+        let dest = temp.path().join("copy");
+
+        let result = run(&source, &dest, DuplicateMode::Auto, false, unsupported).unwrap();
+        assert_eq!(result.files_cloned, 0);
+        assert_eq!(result.files_copied, 4);
+        assert_eq!(
+            fs::read_to_string(dest.join("src/main.rs")).unwrap(),
+            "fn main() {}\n"
+        );
+        assert_eq!(
+            result.total_bytes,
+            [
+                "fn main() {}\n",
+                "# demo\n",
+                "ref: refs/heads/main\n",
+                "0000 1111 init\n"
+            ]
+            .iter()
+            .map(|s| s.len() as u64)
+            .sum::<u64>()
+        );
+    }
+
+    #[test]
+    fn test_clone_mode_fails_without_support_and_cleans_up() {
+        let temp = TempDir::new().unwrap();
+        let source = temp.path().join("project");
+        sample_project(&source);
+        let dest = temp.path().join("copy");
+
+        let error = run(&source, &dest, DuplicateMode::Clone, false, unsupported).unwrap_err();
+        assert!(error.contains("clone"), "{}", error);
+        assert!(!dest.exists());
+    }
+
+    #[test]
+    fn test_artifacts_are_skipped_but_git_is_kept() {
+        let temp = TempDir::new().unwrap();
+        let source = temp.path().join("project");
+        sample_project(&source);
+        let dest = temp.path().join("copy");
+
+        let result = run(&source, &dest, DuplicateMode::Copy, false, unsupported).unwrap();
+        assert_eq!(result.skipped_dirs, vec!["node_modules", "target"]);
+        assert!(!dest.join("node_modules").exists());
+        assert!(!dest.join("target").exists());
+        assert!(dest.join(".git/HEAD").is_file());
+        assert!(dest.join(".git/logs/HEAD").is_file());
+        assert!(dest.join(".git/logs/refs").is_dir());
+    }
+
+    #[test]
+    fn test_include_artifacts_copies_everything() {
+        let temp = TempDir::new().unwrap();
+        let source = temp.path().join("project");
+        sample_project(&source);
+        let dest = temp.path().join("copy");
+
+        let result = run(&source, &dest, DuplicateMode::Copy, true, unsupported).unwrap();
+        assert!(result.skipped_dirs.is_empty());
+        assert_eq!(result.files_copied, 6);
+        assert!(dest.join("node_modules/left-pad/index.js").is_file());
+        assert_eq!(
+            fs::metadata(dest.join("target/debug/app")).unwrap().len(),
+            4096
+        );
+    }
+
+    #[test]
+    fn test_rejects_existing_or_nested_destinations() {
+        let temp = TempDir::new().unwrap();
+        let source = temp.path().join("project");
+        sample_project(&source);
+
+        let error = run(
+            &source,
+            &source.join("src"),
+            DuplicateMode::Copy,
+            false,
+            unsupported,
+        )
+        .unwrap_err();
+        assert!(error.contains("already exists"), "{}", error);
+        let error = run(
+            &source,
+            &source.join("copy"),
+            DuplicateMode::Copy,
+            false,
+            unsupported,
+        )
+        .unwrap_err();
+        assert!(error.contains("inside"), "{}", error);
+    }
+
+    #[test]
+    fn test_cancelled_duplication_removes_the_destination() {
+        let temp = TempDir::new().unwrap();
+        let source = temp.path().join("project");
+        sample_project(&source);
+        let dest = temp.path().join("copy");
+        let token = CancellationToken::new();
+
+        let mut progress = |_: u64, _: u64, _: &Path| token.cancel();
+        let error = duplicate_with(
+            &source,
+            &dest,
+            DuplicateMode::Copy,
+            false,
+            unsupported,
+            Some(&token),
+            &mut progress,
+        )
+        .unwrap_err();
+        assert!(error.contains("cancelled"), "{}", error);
+        assert!(!dest.exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_preserves_symlinks_and_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp = TempDir::new().unwrap();
+        let source = temp.path().join("project");
+        sample_project(&source);
+        fs::write(source.join("run.sh"), "#!/bin/sh\n").unwrap();
+        fs::set_permissions(source.join("run.sh"), fs::Permissions::from_mode(0o755)).unwrap();
+        std::os::unix::fs::symlink("src/main.rs", source.join("entry.rs")).unwrap();
+        let dest = temp.path().join("copy");
+
+        let result = run(&source, &dest, DuplicateMode::Copy, false, unsupported).unwrap();
+        assert_eq!(result.symlinks, 1);
+        assert_eq!(
+            fs::read_link(dest.join("entry.rs")).unwrap(),
+            PathBuf::from("src/main.rs")
+        );
+        let mode = fs::metadata(dest.join("run.sh"))
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o755);
+    }
+
+    /// APFS, where the temp directory lives on every supported macOS, always clones
+    #[cfg(target_os = "macos")]
+    #[test]
+    fn test_apfs_clones_files() {
+        let temp = TempDir::new().unwrap();
+        let source = temp.path().join("project");
+        sample_project(&source);
+        let dest = temp.path().join("copy");
+
+        let result = duplicate(
+            &source,
+            &dest,
+            DuplicateMode::Clone,
+            false,
+            None,
+            &mut |_, _, _| {},
+        )
+        .unwrap();
+        assert_eq!(result.files_cloned, 4);
+        assert_eq!(result.files_copied, 0);
+        assert_eq!(
+            fs::read_to_string(dest.join("README.md")).unwrap(),
+            "# demo\n"
+        );
+    }
+}
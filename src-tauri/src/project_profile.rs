@@ -0,0 +1,820 @@
+// Project profile module
+// Scans a project once when it is opened and reports its languages, package managers,
+// runnable tasks, framework hints and likely entry points.
+
+use crate::constants::should_exclude_dir;
+use ignore::WalkBuilder;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Maximum number of files inspected for the language histogram
+const MAX_SCANNED_FILES: usize = 50_000;
+
+/// Maximum directory depth for the scan
+const MAX_SCAN_DEPTH: usize = 8;
+
+/// Manifest and marker files that are collected during the walk
+const MARKER_FILES: &[&str] = &[
+    "package.json",
+    "pnpm-lock.yaml",
+    "pnpm-workspace.yaml",
+    "yarn.lock",
+    "package-lock.json",
+    "bun.lockb",
+    "bun.lock",
+    "Cargo.toml",
+    "pyproject.toml",
+    "requirements.txt",
+    "Pipfile",
+    "uv.lock",
+    "poetry.lock",
+    "go.mod",
+    "pom.xml",
+    "build.gradle",
+    "build.gradle.kts",
+    "Gemfile",
+    "composer.json",
+    "Makefile",
+    "makefile",
+    "GNUmakefile",
+    "manage.py",
+    "tauri.conf.json",
+    "angular.json",
+];
+
+/// Config files that identify a framework, matched by file stem
+const FRAMEWORK_CONFIG_FILES: &[(&str, &str)] = &[
+    ("next.config", "Next.js"),
+    ("nuxt.config", "Nuxt"),
+    ("vite.config", "Vite"),
+    ("svelte.config", "SvelteKit"),
+    ("astro.config", "Astro"),
+    ("remix.config", "Remix"),
+    ("gatsby-config", "Gatsby"),
+    ("tailwind.config", "Tailwind CSS"),
+];
+
+/// Dependencies that identify a framework
+const FRAMEWORK_DEPENDENCIES: &[(&str, &str)] = &[
+    ("react", "React"),
+    ("vue", "Vue"),
+    ("svelte", "Svelte"),
+    ("@angular/core", "Angular"),
+    ("express", "Express"),
+    ("@nestjs/core", "NestJS"),
+    ("electron", "Electron"),
+    ("@tauri-apps/api", "Tauri"),
+    ("tauri", "Tauri"),
+    ("axum", "Axum"),
+    ("actix-web", "Actix Web"),
+    ("rocket", "Rocket"),
+    ("bevy", "Bevy"),
+    ("django", "Django"),
+    ("flask", "Flask"),
+    ("fastapi", "FastAPI"),
+];
+
+/// Entry point candidates checked relative to each project or package directory
+const ENTRY_POINT_CANDIDATES: &[&str] = &[
+    "src/main.rs",
+    "src/lib.rs",
+    "main.go",
+    "src/main.ts",
+    "src/main.tsx",
+    "src/main.js",
+    "src/index.ts",
+    "src/index.tsx",
+    "src/index.js",
+    "index.ts",
+    "index.js",
+    "app.py",
+    "main.py",
+    "manage.py",
+    "src/main/java/Main.java",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct LanguageStat {
+    pub language: String,
+    pub file_count: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct PackageManagerInfo {
+    pub name: String,
+    /// Relative path of the file that identified the package manager
+    pub manifest_path: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectTask {
+    pub name: String,
+    /// Shell command that runs the task from `cwd`
+    pub command: String,
+    /// Directory to run the command in, relative to the project root ("" for the root)
+    pub cwd: String,
+    /// Relative path of the file that defines the task
+    pub source: String,
+    /// "npm-script", "make" or "cargo-bin"
+    pub kind: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct FrameworkHint {
+    pub name: String,
+    /// Relative path of the file that suggested the framework
+    pub evidence: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestParseError {
+    pub path: String,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectProfile {
+    pub root_path: String,
+    pub languages: Vec<LanguageStat>,
+    pub package_managers: Vec<PackageManagerInfo>,
+    pub tasks: Vec<ProjectTask>,
+    pub frameworks: Vec<FrameworkHint>,
+    pub entry_points: Vec<String>,
+    pub parse_errors: Vec<ManifestParseError>,
+}
+
+fn language_for_extension(extension: &str) -> Option<&'static str> {
+    let language = match extension {
+        "rs" => "Rust",
+        "ts" | "tsx" | "mts" | "cts" => "TypeScript",
+        "js" | "jsx" | "mjs" | "cjs" => "JavaScript",
+        "py" => "Python",
+        "go" => "Go",
+        "java" => "Java",
+        "kt" | "kts" => "Kotlin",
+        "c" | "h" => "C",
+        "cpp" | "cc" | "cxx" | "hpp" | "hh" => "C++",
+        "cs" => "C#",
+        "rb" => "Ruby",
+        "php" => "PHP",
+        "swift" => "Swift",
+        "scala" => "Scala",
+        "dart" => "Dart",
+        "vue" => "Vue",
+        "svelte" => "Svelte",
+        "ex" | "exs" => "Elixir",
+        "lua" => "Lua",
+        "sh" | "bash" | "zsh" => "Shell",
+        _ => return None,
+    };
+    Some(language)
+}
+
+fn relative_path(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+/// Parse Makefile target names, skipping special targets (.PHONY), pattern rules and
+/// variable assignments
+pub fn parse_makefile_targets(content: &str) -> Vec<String> {
+    let mut targets = Vec::new();
+    let mut seen = HashSet::new();
+    for line in content.lines() {
+        if line.starts_with(['\t', ' ', '#', '.']) {
+            continue;
+        }
+        let Some((head, rest)) = line.split_once(':') else {
+            continue;
+        };
+        // `VAR := value` and `VAR ::= value` are assignments, not rules
+        if rest.starts_with('=') || rest.starts_with(":=") || head.contains('=') {
+            continue;
+        }
+        for target in head.split_whitespace() {
+            let valid = target
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '/'));
+            if valid && !target.contains('%') && seen.insert(target.to_string()) {
+                targets.push(target.to_string());
+            }
+        }
+    }
+    targets
+}
+
+/// JavaScript package manager used to run scripts, based on the lockfiles found
+fn js_package_manager(markers: &HashMap<String, Vec<PathBuf>>) -> &'static str {
+    if markers.contains_key("pnpm-lock.yaml") || markers.contains_key("pnpm-workspace.yaml") {
+        "pnpm"
+    } else if markers.contains_key("yarn.lock") {
+        "yarn"
+    } else if markers.contains_key("bun.lockb") || markers.contains_key("bun.lock") {
+        "bun"
+    } else {
+        "npm"
+    }
+}
+
+struct ProfileBuilder<'a> {
+    root: &'a Path,
+    profile: ProjectProfile,
+    frameworks_seen: HashSet<String>,
+    entry_points_seen: HashSet<String>,
+}
+
+impl<'a> ProfileBuilder<'a> {
+    fn add_package_manager(&mut self, name: &str, path: &Path) {
+        if !self.profile.package_managers.iter().any(|p| p.name == name) {
+            self.profile.package_managers.push(PackageManagerInfo {
+                name: name.to_string(),
+                manifest_path: relative_path(self.root, path),
+            });
+        }
+    }
+
+    fn add_framework(&mut self, name: &str, path: &Path) {
+        if self.frameworks_seen.insert(name.to_string()) {
+            self.profile.frameworks.push(FrameworkHint {
+                name: name.to_string(),
+                evidence: relative_path(self.root, path),
+            });
+        }
+    }
+
+    fn add_entry_point(&mut self, path: &Path) {
+        let relative = relative_path(self.root, path);
+        if self.entry_points_seen.insert(relative.clone()) {
+            self.profile.entry_points.push(relative);
+        }
+    }
+
+    fn add_parse_error(&mut self, path: &Path, error: String) {
+        log::warn!("Failed to parse {}: {}", path.display(), error);
+        self.profile.parse_errors.push(ManifestParseError {
+            path: relative_path(self.root, path),
+            error,
+        });
+    }
+
+    fn add_dependency_frameworks<'k>(&mut self, names: impl Iterator<Item = &'k str>, path: &Path) {
+        let names: HashSet<&str> = names.collect();
+        for (dependency, framework) in FRAMEWORK_DEPENDENCIES {
+            if names.contains(dependency) {
+                self.add_framework(framework, path);
+            }
+        }
+    }
+
+    fn add_entry_points_in(&mut self, dir: &Path) {
+        for candidate in ENTRY_POINT_CANDIDATES {
+            let path = dir.join(candidate);
+            if path.is_file() {
+                self.add_entry_point(&path);
+            }
+        }
+    }
+
+    fn process_package_json(&mut self, path: &Path, package_manager: &str) {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) => return self.add_parse_error(path, e.to_string()),
+        };
+        let json: serde_json::Value = match serde_json::from_str(&content) {
+            Ok(json) => json,
+            Err(e) => return self.add_parse_error(path, e.to_string()),
+        };
+        let dir = path.parent().unwrap_or(self.root);
+        let cwd = relative_path(self.root, dir);
+        let source = relative_path(self.root, path);
+
+        if let Some(scripts) = json.get("scripts").and_then(|s| s.as_object()) {
+            for name in scripts.keys() {
+                self.profile.tasks.push(ProjectTask {
+                    name: name.clone(),
+                    command: format!("{} run {}", package_manager, name),
+                    cwd: cwd.clone(),
+                    source: source.clone(),
+                    kind: "npm-script".to_string(),
+                });
+            }
+        }
+
+        let mut dependency_names: Vec<&str> = Vec::new();
+        for key in ["dependencies", "devDependencies", "peerDependencies"] {
+            if let Some(deps) = json.get(key).and_then(|d| d.as_object()) {
+                dependency_names.extend(deps.keys().map(|k| k.as_str()));
+            }
+        }
+        self.add_dependency_frameworks(dependency_names.into_iter(), path);
+
+        if let Some(main) = json.get("main").and_then(|m| m.as_str()) {
+            let main_path = dir.join(main);
+            if main_path.is_file() {
+                self.add_entry_point(&main_path);
+            }
+        }
+        self.add_entry_points_in(dir);
+    }
+
+    fn process_cargo_toml(&mut self, path: &Path) {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) => return self.add_parse_error(path, e.to_string()),
+        };
+        let manifest: toml::Table = match content.parse() {
+            Ok(manifest) => manifest,
+            Err(e) => return self.add_parse_error(path, e.to_string()),
+        };
+        let dir = path.parent().unwrap_or(self.root);
+        let cwd = relative_path(self.root, dir);
+        let source = relative_path(self.root, path);
+
+        let dependency_names: Vec<&str> = ["dependencies", "dev-dependencies"]
+            .iter()
+            .filter_map(|key| manifest.get(*key).and_then(|d| d.as_table()))
+            .flat_map(|deps| deps.keys().map(|k| k.as_str()))
+            .collect();
+        self.add_dependency_frameworks(dependency_names.into_iter(), path);
+
+        let Some(package_name) = manifest
+            .get("package")
+            .and_then(|p| p.get("name"))
+            .and_then(|n| n.as_str())
+        else {
+            // Virtual workspace manifest: members are handled through their own Cargo.toml
+            return;
+        };
+
+        let mut bins: Vec<String> = manifest
+            .get("bin")
+            .and_then(|b| b.as_array())
+            .map(|bins| {
+                bins.iter()
+                    .filter_map(|b| b.get("name").and_then(|n| n.as_str()))
+                    .map(String::from)
+                    .collect()
+            })
+            .unwrap_or_default();
+        if dir.join("src/main.rs").is_file() && !bins.iter().any(|b| b == package_name) {
+            bins.insert(0, package_name.to_string());
+        }
+        if let Ok(entries) = std::fs::read_dir(dir.join("src/bin")) {
+            let mut extra: Vec<String> = entries
+                .flatten()
+                .filter_map(|e| {
+                    let path = e.path();
+                    (path.extension().and_then(|x| x.to_str()) == Some("rs"))
+                        .then(|| path.file_stem()?.to_str().map(String::from))
+                        .flatten()
+                })
+                .filter(|name| !bins.contains(name))
+                .collect();
+            extra.sort();
+            bins.extend(extra);
+        }
+
+        for bin in bins {
+            self.profile.tasks.push(ProjectTask {
+                command: format!("cargo run -p {} --bin {}", package_name, bin),
+                name: bin,
+                cwd: cwd.clone(),
+                source: source.clone(),
+                kind: "cargo-bin".to_string(),
+            });
+        }
+        self.add_entry_points_in(dir);
+    }
+
+    fn process_pyproject(&mut self, path: &Path) {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) => return self.add_parse_error(path, e.to_string()),
+        };
+        let manifest: toml::Table = match content.parse() {
+            Ok(manifest) => manifest,
+            Err(e) => return self.add_parse_error(path, e.to_string()),
+        };
+
+        let poetry = manifest
+            .get("tool")
+            .and_then(|t| t.get("poetry"))
+            .and_then(|p| p.as_table());
+        if poetry.is_some() {
+            self.add_package_manager("poetry", path);
+        }
+
+        // PEP 621 dependencies are requirement strings such as "fastapi>=0.100"
+        let mut dependency_names: Vec<String> = manifest
+            .get("project")
+            .and_then(|p| p.get("dependencies"))
+            .and_then(|d| d.as_array())
+            .map(|deps| {
+                deps.iter()
+                    .filter_map(|d| d.as_str())
+                    .map(|d| {
+                        d.split(|c: char| !(c.is_alphanumeric() || c == '-' || c == '_'))
+                            .next()
+                            .unwrap_or_default()
+                            .to_lowercase()
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        if let Some(deps) = poetry
+            .and_then(|p| p.get("dependencies"))
+            .and_then(|d| d.as_table())
+        {
+            dependency_names.extend(deps.keys().map(|k| k.to_lowercase()));
+        }
+        self.add_dependency_frameworks(dependency_names.iter().map(|s| s.as_str()), path);
+        self.add_entry_points_in(path.parent().unwrap_or(self.root));
+    }
+
+    fn process_makefile(&mut self, path: &Path) {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) => return self.add_parse_error(path, e.to_string()),
+        };
+        let dir = path.parent().unwrap_or(self.root);
+        let cwd = relative_path(self.root, dir);
+        let source = relative_path(self.root, path);
+        for target in parse_makefile_targets(&content) {
+            self.profile.tasks.push(ProjectTask {
+                command: format!("make {}", target),
+                name: target,
+                cwd: cwd.clone(),
+                source: source.clone(),
+                kind: "make".to_string(),
+            });
+        }
+    }
+}
+
+/// Scan a project and build its profile
+pub fn detect_profile(root_path: &str) -> Result<ProjectProfile, String> {
+    let root = Path::new(root_path);
+    if !root.is_dir() {
+        return Err(format!("Not a directory: {}", root_path));
+    }
+
+    let mut language_counts: HashMap<&'static str, usize> = HashMap::new();
+    // Marker file name -> paths, in walk order
+    let mut markers: HashMap<String, Vec<PathBuf>> = HashMap::new();
+    let mut framework_configs: Vec<(PathBuf, &'static str)> = Vec::new();
+    let mut scanned = 0;
+
+    let walker = WalkBuilder::new(root)
+        .hidden(true)
+        .git_ignore(true)
+        .max_depth(Some(MAX_SCAN_DEPTH))
+        .sort_by_file_path(|a, b| a.cmp(b))
+        .filter_entry(|entry| {
+            entry.depth() == 0
+                || !entry.file_type().is_some_and(|ft| ft.is_dir())
+                || !should_exclude_dir(&entry.file_name().to_string_lossy())
+        })
+        .build();
+
+    for entry in walker.flatten() {
+        if !entry.file_type().is_some_and(|ft| ft.is_file()) {
+            continue;
+        }
+        scanned += 1;
+        if scanned > MAX_SCANNED_FILES {
+            break;
+        }
+        let path = entry.path();
+        let file_name = entry.file_name().to_string_lossy();
+
+        if let Some(language) = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .and_then(|e| language_for_extension(&e.to_lowercase()))
+        {
+            *language_counts.entry(language).or_insert(0) += 1;
+        }
+        if MARKER_FILES.contains(&file_name.as_ref()) {
+            markers
+                .entry(file_name.to_string())
+                .or_default()
+                .push(path.to_path_buf());
+        }
+        if let Some(stem) = file_name.rsplit_once('.').map(|(stem, _)| stem) {
+            if let Some((_, framework)) = FRAMEWORK_CONFIG_FILES.iter().find(|(s, _)| *s == stem) {
+                framework_configs.push((path.to_path_buf(), framework));
+            }
+        }
+    }
+
+    let mut builder = ProfileBuilder {
+        root,
+        profile: ProjectProfile {
+            root_path: root_path.to_string(),
+            ..Default::default()
+        },
+        frameworks_seen: HashSet::new(),
+        entry_points_seen: HashSet::new(),
+    };
+
+    // Languages, most common first
+    let mut languages: Vec<LanguageStat> = language_counts
+        .into_iter()
+        .map(|(language, file_count)| LanguageStat {
+            language: language.to_string(),
+            file_count,
+        })
+        .collect();
+    languages.sort_by(|a, b| {
+        b.file_count
+            .cmp(&a.file_count)
+            .then_with(|| a.language.cmp(&b.language))
+    });
+    builder.profile.languages = languages;
+
+    // Package managers, identified by the shallowest marker file
+    let package_manager_markers: &[(&str, &str)] = &[
+        ("pnpm-lock.yaml", "pnpm"),
+        ("pnpm-workspace.yaml", "pnpm"),
+        ("yarn.lock", "yarn"),
+        ("bun.lockb", "bun"),
+        ("bun.lock", "bun"),
+        ("package-lock.json", "npm"),
+        ("Cargo.toml", "cargo"),
+        ("uv.lock", "uv"),
+        ("poetry.lock", "poetry"),
+        ("Pipfile", "pipenv"),
+        ("requirements.txt", "pip"),
+        ("go.mod", "go"),
+        ("pom.xml", "maven"),
+        ("build.gradle", "gradle"),
+        ("build.gradle.kts", "gradle"),
+        ("Gemfile", "bundler"),
+        ("composer.json", "composer"),
+    ];
+    for (marker, name) in package_manager_markers {
+        if let Some(path) = markers.get(*marker).and_then(|paths| paths.first()) {
+            builder.add_package_manager(name, path);
+        }
+    }
+    let js_pm = js_package_manager(&markers);
+    if let Some(path) = markers.get("package.json").and_then(|p| p.first()) {
+        if js_pm == "npm" {
+            builder.add_package_manager("npm", path);
+        }
+    }
+
+    // Manifests: tasks, dependency-based framework hints, entry points
+    let marker_paths = |name: &str| markers.get(name).cloned().unwrap_or_default();
+    for path in marker_paths("package.json") {
+        builder.process_package_json(&path, js_pm);
+    }
+    for path in marker_paths("Cargo.toml") {
+        builder.process_cargo_toml(&path);
+    }
+    for path in marker_paths("pyproject.toml") {
+        builder.process_pyproject(&path);
+    }
+    for name in ["Makefile", "makefile", "GNUmakefile"] {
+        for path in marker_paths(name) {
+            builder.process_makefile(&path);
+        }
+    }
+    for path in marker_paths("go.mod") {
+        let dir = path.parent().unwrap_or(root).to_path_buf();
+        builder.add_entry_points_in(&dir);
+        if let Ok(entries) = std::fs::read_dir(dir.join("cmd")) {
+            let mut mains: Vec<PathBuf> = entries
+                .flatten()
+                .map(|e| e.path().join("main.go"))
+                .filter(|p| p.is_file())
+                .collect();
+            mains.sort();
+            for main in mains {
+                builder.add_entry_point(&main);
+            }
+        }
+    }
+
+    // Framework hints from marker and config files
+    for path in marker_paths("tauri.conf.json") {
+        builder.add_framework("Tauri", &path);
+    }
+    for path in marker_paths("manage.py") {
+        builder.add_framework("Django", &path);
+    }
+    for path in marker_paths("angular.json") {
+        builder.add_framework("Angular", &path);
+    }
+    for (path, framework) in &framework_configs {
+        builder.add_framework(framework, path);
+    }
+
+    if builder.profile.entry_points.is_empty() {
+        builder.add_entry_points_in(root);
+    }
+
+    Ok(builder.profile)
+}
+
+#[tauri::command]
+pub async fn detect_project_profile(root_path: String) -> Result<ProjectProfile, String> {
+    log::info!("Detecting project profile for {}", root_path);
+    tauri::async_runtime::spawn_blocking(move || detect_profile(&root_path))
+        .await
+        .map_err(|e| format!("Project scan failed: {}", e))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn task_kinds(profile: &ProjectProfile) -> BTreeMap<String, usize> {
+        let mut kinds = BTreeMap::new();
+        for task in &profile.tasks {
+            *kinds.entry(task.kind.clone()).or_insert(0) += 1;
+        }
+        kinds
+    }
+
+    fn write(root: &Path, relative: &str, content: &str) {
+        let path = root.join(relative);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    fn create_js_monorepo() -> TempDir {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        write(
+            root,
+            "package.json",
+            r#"{"name": "mono", "private": true, "scripts": {"build": "turbo build", "lint": "eslint ."}}"#,
+        );
+        write(root, "pnpm-lock.yaml", "lockfileVersion: '9.0'\n");
+        write(root, "pnpm-workspace.yaml", "packages:\n  - packages/*\n");
+        write(
+            root,
+            "packages/web/package.json",
+            r#"{"name": "web", "scripts": {"dev": "next dev"}, "dependencies": {"next": "14", "react": "18"}}"#,
+        );
+        write(root, "packages/web/next.config.mjs", "export default {};\n");
+        write(root, "packages/web/src/index.tsx", "export {};\n");
+        write(root, "packages/web/src/app.tsx", "export {};\n");
+        write(root, "packages/web/src/util.ts", "export {};\n");
+        write(
+            root,
+            "packages/api/package.json",
+            r#"{"name": "api", "scripts": {"start": "node index.js",}"#,
+        );
+        write(root, "packages/api/index.js", "module.exports = {};\n");
+        write(
+            root,
+            "Makefile",
+            ".PHONY: setup test\nVERSION := 1.0\nsetup:\n\tpnpm install\ntest: setup\n\tpnpm test\n%.o: %.c\n\tcc $<\n",
+        );
+        // Dependencies must not be scanned
+        write(
+            root,
+            "node_modules/left-pad/package.json",
+            r#"{"scripts": {"x": "y"}}"#,
+        );
+        temp_dir
+    }
+
+    fn create_rust_workspace() -> TempDir {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        write(
+            root,
+            "Cargo.toml",
+            "[workspace]\nmembers = [\"crates/*\"]\nresolver = \"2\"\n",
+        );
+        write(
+            root,
+            "crates/cli/Cargo.toml",
+            "[package]\nname = \"cli\"\nversion = \"0.1.0\"\n\n[[bin]]\nname = \"tool\"\npath = \"src/tool.rs\"\n\n[dependencies]\naxum = \"0.7\"\n",
+        );
+        write(root, "crates/cli/src/main.rs", "fn main() {}\n");
+        write(root, "crates/cli/src/tool.rs", "fn main() {}\n");
+        write(root, "crates/cli/src/bin/migrate.rs", "fn main() {}\n");
+        write(
+            root,
+            "crates/core/Cargo.toml",
+            "[package]\nname = \"core\"\nversion = \"0.1.0\"\n",
+        );
+        write(root, "crates/core/src/lib.rs", "pub fn f() {}\n");
+        write(
+            root,
+            "crates/broken/Cargo.toml",
+            "[package\nname = \"broken\"\n",
+        );
+        write(root, "app/tauri.conf.json", "{}\n");
+        temp_dir
+    }
+
+    #[test]
+    fn test_parse_makefile_targets() {
+        let content = ".PHONY: all\nCC = gcc\nFLAGS := -O2\nall: build test\nbuild:\n\tcc main.c\ntest: build\n%.o: %.c\n\t$(CC) $<\n";
+        assert_eq!(
+            parse_makefile_targets(content),
+            vec!["all", "build", "test"]
+        );
+    }
+
+    #[test]
+    fn test_js_monorepo_profile() {
+        let temp_dir = create_js_monorepo();
+        let profile = detect_profile(&temp_dir.path().to_string_lossy()).unwrap();
+
+        assert_eq!(profile.languages[0].language, "TypeScript");
+        assert_eq!(profile.languages[0].file_count, 3);
+
+        let managers: Vec<&str> = profile
+            .package_managers
+            .iter()
+            .map(|p| p.name.as_str())
+            .collect();
+        assert_eq!(managers, vec!["pnpm"]);
+
+        let dev = profile.tasks.iter().find(|t| t.name == "dev").unwrap();
+        assert_eq!(dev.command, "pnpm run dev");
+        assert_eq!(dev.cwd, "packages/web");
+        assert!(profile
+            .tasks
+            .iter()
+            .any(|t| t.name == "build" && t.cwd.is_empty()));
+        assert!(!profile.tasks.iter().any(|t| t.name == "x"));
+        let kinds = task_kinds(&profile);
+        assert_eq!(kinds.get("npm-script"), Some(&3));
+        assert_eq!(kinds.get("make"), Some(&2));
+
+        // The malformed package.json is reported but doesn't stop the scan
+        assert_eq!(profile.parse_errors.len(), 1);
+        assert_eq!(profile.parse_errors[0].path, "packages/api/package.json");
+
+        let frameworks: Vec<&str> = profile.frameworks.iter().map(|f| f.name.as_str()).collect();
+        assert!(frameworks.contains(&"React"));
+        assert!(frameworks.contains(&"Next.js"));
+
+        assert!(profile
+            .entry_points
+            .contains(&"packages/web/src/index.tsx".to_string()));
+    }
+
+    #[test]
+    fn test_rust_workspace_profile() {
+        let temp_dir = create_rust_workspace();
+        let profile = detect_profile(&temp_dir.path().to_string_lossy()).unwrap();
+
+        assert_eq!(profile.languages[0].language, "Rust");
+        assert_eq!(profile.package_managers[0].name, "cargo");
+        assert_eq!(profile.package_managers[0].manifest_path, "Cargo.toml");
+
+        let bins: Vec<(&str, &str)> = profile
+            .tasks
+            .iter()
+            .filter(|t| t.kind == "cargo-bin")
+            .map(|t| (t.name.as_str(), t.command.as_str()))
+            .collect();
+        assert_eq!(
+            bins,
+            vec![
+                ("cli", "cargo run -p cli --bin cli"),
+                ("tool", "cargo run -p cli --bin tool"),
+                ("migrate", "cargo run -p cli --bin migrate"),
+            ]
+        );
+
+        assert_eq!(profile.parse_errors.len(), 1);
+        assert_eq!(profile.parse_errors[0].path, "crates/broken/Cargo.toml");
+
+        let frameworks: Vec<&str> = profile.frameworks.iter().map(|f| f.name.as_str()).collect();
+        assert!(frameworks.contains(&"Axum"));
+        assert!(frameworks.contains(&"Tauri"));
+
+        assert!(profile
+            .entry_points
+            .contains(&"crates/cli/src/main.rs".to_string()));
+        assert!(profile
+            .entry_points
+            .contains(&"crates/core/src/lib.rs".to_string()));
+    }
+
+    #[test]
+    fn test_detect_profile_rejects_missing_directory() {
+        assert!(detect_profile("/nonexistent/project/path").is_err());
+    }
+}
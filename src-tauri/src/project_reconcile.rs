@@ -0,0 +1,373 @@
+// Project reconcile module
+// After bulk external changes (a branch switch deleting hundreds of files, a large `rm`)
+// the code index and the directory tree cache keep pointing at files that are gone.
+// Reconciling stats every path they know under a project root and drops the entries of the
+// missing ones. Branch switches detected by the git watcher trigger it automatically.
+
+use crate::code_navigation::{CodeNavState, CodeNavigationService};
+use crate::directory_tree::{self, DirectoryTreeBuilder};
+use rayon::prelude::*;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+/// Time the stat pass may take before the remaining paths are checked in the background
+pub const RECONCILE_BUDGET: Duration = Duration::from_secs(2);
+/// Quiet period after the last HEAD change before reconciling
+pub const HEAD_CHANGE_DEBOUNCE: Duration = Duration::from_secs(2);
+/// Paths checked between deadline checks
+const STAT_CHUNK_SIZE: usize = 256;
+
+#[derive(Debug, Clone, Default, Serialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ReconcileSummary {
+    pub root_path: String,
+    /// Files whose index entries were dropped, sorted
+    pub removed_from_index: Vec<String>,
+    /// Directories whose cached trees were dropped, sorted
+    pub invalidated_dirs: Vec<String>,
+    pub checked_paths: usize,
+    pub elapsed_ms: u64,
+    /// The budget ran out; the remaining paths are checked in the background and their
+    /// summary is sent as a project-state-reconciled event
+    pub continued_in_background: bool,
+}
+
+struct StatPass {
+    missing: Vec<String>,
+    /// Paths not reached before the deadline
+    unchecked: Vec<String>,
+}
+
+fn is_missing(path: &str) -> bool {
+    // Permission errors and the like are not proof the file is gone
+    matches!(
+        std::fs::symlink_metadata(path),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound
+    )
+}
+
+/// Stat `paths` in parallel, a chunk at a time, until done or `deadline` passes
+fn find_missing(paths: Vec<String>, deadline: Option<Instant>) -> StatPass {
+    let mut missing = Vec::new();
+    let mut offset = 0;
+    while offset < paths.len() {
+        if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            break;
+        }
+        let end = (offset + STAT_CHUNK_SIZE).min(paths.len());
+        missing.par_extend(
+            paths[offset..end]
+                .par_iter()
+                .filter(|path| is_missing(path))
+                .cloned(),
+        );
+        offset = end;
+    }
+    StatPass {
+        missing,
+        unchecked: paths[offset..].to_vec(),
+    }
+}
+
+/// Drop the index entries and cached trees of `missing` paths
+fn remove_missing(
+    nav: &RwLock<CodeNavigationService>,
+    tree: &DirectoryTreeBuilder,
+    missing: &[String],
+    summary: &mut ReconcileSummary,
+) -> Result<(), String> {
+    if missing.is_empty() {
+        return Ok(());
+    }
+    let mut service = nav
+        .write()
+        .map_err(|e| format!("Failed to acquire write lock: {}", e))?;
+    let indexed: HashSet<String> = service
+        .indexed_files_under(&summary.root_path)
+        .into_iter()
+        .collect();
+    for path in missing.iter().filter(|path| indexed.contains(*path)) {
+        service.clear_file(path);
+        summary.removed_from_index.push(path.clone());
+    }
+    drop(service);
+    summary.removed_from_index.sort();
+
+    summary.invalidated_dirs = tree.invalidate_containing(missing);
+    Ok(())
+}
+
+/// Check the paths under `root_path` known to the index or the tree cache and drop the
+/// entries of missing ones. With a budget, the paths not checked in time are returned.
+fn reconcile(
+    nav: &RwLock<CodeNavigationService>,
+    tree: &DirectoryTreeBuilder,
+    root_path: &str,
+    budget: Option<Duration>,
+) -> Result<(ReconcileSummary, Vec<String>), String> {
+    let start = Instant::now();
+    let mut paths: HashSet<String> = tree.cached_paths_under(root_path).into_iter().collect();
+    paths.extend(
+        nav.read()
+            .map_err(|e| format!("Failed to acquire read lock: {}", e))?
+            .indexed_files_under(root_path),
+    );
+    let total = paths.len();
+
+    let pass = find_missing(paths.into_iter().collect(), budget.map(|b| start + b));
+    let mut summary = ReconcileSummary {
+        root_path: root_path.to_string(),
+        checked_paths: total - pass.unchecked.len(),
+        ..Default::default()
+    };
+    remove_missing(nav, tree, &pass.missing, &mut summary)?;
+    summary.continued_in_background = !pass.unchecked.is_empty();
+    summary.elapsed_ms = start.elapsed().as_millis() as u64;
+    Ok((summary, pass.unchecked))
+}
+
+/// Reconcile within RECONCILE_BUDGET, leaving what is left to a background thread
+fn run_reconcile(app_handle: &AppHandle, root_path: &str) -> Result<ReconcileSummary, String> {
+    let state = app_handle
+        .try_state::<CodeNavState>()
+        .ok_or("Code navigation is not available")?;
+    let tree = directory_tree::tree_builder();
+    let (summary, unchecked) = reconcile(&state.0, tree, root_path, Some(RECONCILE_BUDGET))?;
+    log::info!(
+        "Reconciled {}: {} removed from index, {} directories invalidated, {} paths checked in {}ms",
+        root_path,
+        summary.removed_from_index.len(),
+        summary.invalidated_dirs.len(),
+        summary.checked_paths,
+        summary.elapsed_ms
+    );
+
+    if !unchecked.is_empty() {
+        log::info!(
+            "Reconcile budget exhausted, checking {} remaining paths in the background",
+            unchecked.len()
+        );
+        let app_handle = app_handle.clone();
+        let root_path = root_path.to_string();
+        std::thread::spawn(move || {
+            let Some(state) = app_handle.try_state::<CodeNavState>() else {
+                return;
+            };
+            let start = Instant::now();
+            let total = unchecked.len();
+            let pass = find_missing(unchecked, None);
+            let mut rest = ReconcileSummary {
+                root_path,
+                checked_paths: total,
+                ..Default::default()
+            };
+            if let Err(e) = remove_missing(&state.0, tree, &pass.missing, &mut rest) {
+                log::error!("Background reconcile failed: {}", e);
+                return;
+            }
+            rest.elapsed_ms = start.elapsed().as_millis() as u64;
+            if let Err(e) = crate::events::emit_project_state_reconciled(&app_handle, &rest) {
+                log::error!("Failed to emit reconcile summary: {}", e);
+            }
+        });
+    }
+    Ok(summary)
+}
+
+/// Trailing-edge debounce per project root
+struct Debouncer {
+    window: Duration,
+    last_request: Mutex<HashMap<String, Instant>>,
+}
+
+impl Debouncer {
+    fn new(window: Duration) -> Self {
+        Self {
+            window,
+            last_request: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Record a request. Returns true if none was pending, i.e. the caller should start
+    /// waiting for the quiet period.
+    fn request(&self, key: &str) -> bool {
+        self.last_request
+            .lock()
+            .map(|mut pending| pending.insert(key.to_string(), Instant::now()).is_none())
+            .unwrap_or(false)
+    }
+
+    /// None once the window has passed since the last request, which clears it; otherwise
+    /// how much longer to wait
+    fn remaining(&self, key: &str) -> Option<Duration> {
+        let mut pending = self.last_request.lock().ok()?;
+        let wait = self.window.saturating_sub(pending.get(key)?.elapsed());
+        if wait.is_zero() {
+            pending.remove(key);
+            return None;
+        }
+        Some(wait)
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref HEAD_CHANGES: Debouncer = Debouncer::new(HEAD_CHANGE_DEBOUNCE);
+}
+
+/// Reconcile `root_path` once HEAD has been quiet for HEAD_CHANGE_DEBOUNCE, so rapid ref
+/// updates during a checkout or rebase trigger a single scan
+pub fn schedule_after_head_change(app_handle: &AppHandle, root_path: &str) {
+    if !HEAD_CHANGES.request(root_path) {
+        return;
+    }
+    let app_handle = app_handle.clone();
+    let root_path = root_path.to_string();
+    std::thread::spawn(move || {
+        while let Some(wait) = HEAD_CHANGES.remaining(&root_path) {
+            std::thread::sleep(wait);
+        }
+        match run_reconcile(&app_handle, &root_path) {
+            Ok(summary) => {
+                if let Err(e) = crate::events::emit_project_state_reconciled(&app_handle, &summary)
+                {
+                    log::error!("Failed to emit reconcile summary: {}", e);
+                }
+            }
+            Err(e) => log::warn!("Reconcile after HEAD change failed: {}", e),
+        }
+    });
+}
+
+/// Drop index entries and cached directory trees of files under `root_path` that no
+/// longer exist on disk
+#[tauri::command]
+pub async fn reconcile_project_state(
+    app_handle: AppHandle,
+    root_path: String,
+) -> Result<ReconcileSummary, String> {
+    tauri::async_runtime::spawn_blocking(move || run_reconcile(&app_handle, &root_path))
+        .await
+        .map_err(|e| format!("Reconcile task failed: {}", e))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn path_str(path: &std::path::Path) -> String {
+        path.to_string_lossy().replace('\\', "/")
+    }
+
+    /// A project with three Python files, indexed and shown in a cached tree
+    fn setup() -> (TempDir, RwLock<CodeNavigationService>, DirectoryTreeBuilder) {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("src/old")).unwrap();
+        let mut service = CodeNavigationService::new();
+        for (file, name) in [
+            ("src/keep.py", "keep"),
+            ("src/old/gone.py", "gone"),
+            ("src/old/also_gone.py", "also_gone"),
+        ] {
+            let content = format!("def {}():\n    pass\n", name);
+            fs::write(root.join(file), &content).unwrap();
+            service
+                .index_file(&path_str(&root.join(file)), &content, "python")
+                .unwrap();
+        }
+        let tree = DirectoryTreeBuilder::new();
+        tree.build_directory_tree_fast(&path_str(root), 3, &Default::default())
+            .unwrap();
+        (temp_dir, RwLock::new(service), tree)
+    }
+
+    #[test]
+    fn test_reconcile_removes_deleted_files() {
+        let (temp_dir, nav, tree) = setup();
+        let root = path_str(temp_dir.path());
+        fs::remove_dir_all(temp_dir.path().join("src/old")).unwrap();
+
+        let (summary, unchecked) = reconcile(&nav, &tree, &root, None).unwrap();
+        assert!(unchecked.is_empty());
+        assert!(!summary.continued_in_background);
+        assert_eq!(
+            summary.removed_from_index,
+            [
+                format!("{}/src/old/also_gone.py", root),
+                format!("{}/src/old/gone.py", root),
+            ]
+        );
+        assert_eq!(summary.invalidated_dirs, std::slice::from_ref(&root));
+
+        let service = nav.read().unwrap();
+        assert_eq!(
+            service.indexed_files_under(&root),
+            [format!("{}/src/keep.py", root)]
+        );
+        assert!(tree.cached_paths_under(&root).is_empty());
+    }
+
+    #[test]
+    fn test_reconcile_without_changes_keeps_everything() {
+        let (temp_dir, nav, tree) = setup();
+        let root = path_str(temp_dir.path());
+
+        let (summary, _) = reconcile(&nav, &tree, &root, None).unwrap();
+        assert!(summary.removed_from_index.is_empty());
+        assert!(summary.invalidated_dirs.is_empty());
+        // root, src, src/old and the three files
+        assert_eq!(summary.checked_paths, 6);
+        assert_eq!(nav.read().unwrap().indexed_files_under(&root).len(), 3);
+        assert!(!tree.cached_paths_under(&root).is_empty());
+    }
+
+    #[test]
+    fn test_reconcile_ignores_other_roots() {
+        let (temp_dir, nav, tree) = setup();
+        let root = path_str(temp_dir.path());
+        fs::remove_dir_all(temp_dir.path().join("src/old")).unwrap();
+
+        let other = format!("{}-other", root);
+        let (summary, _) = reconcile(&nav, &tree, &other, None).unwrap();
+        assert_eq!(summary.checked_paths, 0);
+        assert_eq!(nav.read().unwrap().indexed_files_under(&root).len(), 3);
+    }
+
+    #[test]
+    fn test_exhausted_budget_leaves_paths_unchecked() {
+        let (temp_dir, nav, tree) = setup();
+        let root = path_str(temp_dir.path());
+        fs::remove_dir_all(temp_dir.path().join("src/old")).unwrap();
+
+        let (summary, unchecked) = reconcile(&nav, &tree, &root, Some(Duration::ZERO)).unwrap();
+        assert!(summary.continued_in_background);
+        assert_eq!(summary.checked_paths, 0);
+        assert_eq!(unchecked.len(), 6);
+        assert_eq!(nav.read().unwrap().indexed_files_under(&root).len(), 3);
+
+        // The background pass picks up where the budget ran out
+        let pass = find_missing(unchecked, None);
+        assert_eq!(pass.missing.len(), 3);
+        assert!(pass.unchecked.is_empty());
+    }
+
+    #[test]
+    fn test_debouncer_waits_for_quiet_period() {
+        let debouncer = Debouncer::new(Duration::from_millis(50));
+        assert!(debouncer.request("/repo"));
+        // Further requests while pending extend the window instead of scheduling again
+        assert!(!debouncer.request("/repo"));
+        assert!(debouncer.request("/other"));
+
+        assert!(debouncer.remaining("/repo").is_some());
+        std::thread::sleep(Duration::from_millis(60));
+        assert!(debouncer.remaining("/repo").is_none());
+        assert!(debouncer.request("/repo"));
+    }
+}
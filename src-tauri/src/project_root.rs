@@ -0,0 +1,259 @@
+// Project root module
+// People often open src/ or one package of a monorepo, and git features and the index then
+// work on a fragment of the repository. This walks upward from the opened path looking for
+// repository and workspace markers and reports the root they point to, so the UI can offer
+// to open the whole repository instead.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// What marks a directory as a project root
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RootMarker {
+    /// `.git` directory, or file for worktrees and submodules
+    Git,
+    /// package.json with `workspaces`
+    PackageWorkspaces,
+    /// Cargo.toml with a `[workspace]` table
+    CargoWorkspace,
+    GoWork,
+    PnpmWorkspace,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RootEvidence {
+    pub path: String,
+    pub marker: RootMarker,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct InferredRoot {
+    pub root: String,
+    pub requested_path: String,
+    /// Requested path relative to the root, with `/` separators; empty when they are the same
+    pub subpath: String,
+    /// Markers found between the requested path and the root, innermost first
+    pub evidence: Vec<RootEvidence>,
+}
+
+fn root_markers(dir: &Path) -> Vec<RootMarker> {
+    let mut markers = Vec::new();
+    if dir.join(".git").exists() {
+        markers.push(RootMarker::Git);
+    }
+    let package_workspaces = fs::read_to_string(dir.join("package.json"))
+        .ok()
+        .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+        .is_some_and(|manifest| manifest.get("workspaces").is_some());
+    if package_workspaces {
+        markers.push(RootMarker::PackageWorkspaces);
+    }
+    let cargo_workspace = fs::read_to_string(dir.join("Cargo.toml"))
+        .ok()
+        .and_then(|content| content.parse::<toml::Table>().ok())
+        .is_some_and(|manifest| manifest.contains_key("workspace"));
+    if cargo_workspace {
+        markers.push(RootMarker::CargoWorkspace);
+    }
+    if dir.join("go.work").is_file() {
+        markers.push(RootMarker::GoWork);
+    }
+    if dir.join("pnpm-workspace.yaml").is_file() {
+        markers.push(RootMarker::PnpmWorkspace);
+    }
+    markers
+}
+
+#[cfg(unix)]
+fn device_of(path: &Path) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    fs::metadata(path).ok().map(|metadata| metadata.dev())
+}
+
+#[cfg(not(unix))]
+fn device_of(_path: &Path) -> Option<u64> {
+    None
+}
+
+/// Best project root for `path`: the nearest enclosing git repository, otherwise the
+/// outermost workspace. None when no marker is found.
+pub fn infer_root(path: &Path) -> Option<InferredRoot> {
+    infer_root_within(path, dirs::home_dir().as_deref())
+}
+
+/// The walk never reaches `home` or the filesystem root and stops where the path crosses
+/// onto another filesystem, so a dotfiles repository in the home directory or a stray
+/// manifest under `/` is never suggested.
+fn infer_root_within(path: &Path, home: Option<&Path>) -> Option<InferredRoot> {
+    let mut start = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    if start.is_file() {
+        start.pop();
+    }
+    let home = home.map(|home| home.canonicalize().unwrap_or_else(|_| home.to_path_buf()));
+    let device = device_of(&start);
+
+    let mut evidence = Vec::new();
+    let mut root: Option<PathBuf> = None;
+    for dir in start.ancestors() {
+        if home.as_deref() == Some(dir) || dir.parent().is_none() || device_of(dir) != device {
+            break;
+        }
+        let markers = root_markers(dir);
+        if markers.is_empty() {
+            continue;
+        }
+        evidence.extend(markers.iter().map(|marker| RootEvidence {
+            path: dir.to_string_lossy().to_string(),
+            marker: *marker,
+        }));
+        root = Some(dir.to_path_buf());
+        if markers.contains(&RootMarker::Git) {
+            break;
+        }
+    }
+
+    let root = root?;
+    let subpath = start
+        .strip_prefix(&root)
+        .ok()?
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy().to_string())
+        .collect::<Vec<_>>()
+        .join("/");
+    Some(InferredRoot {
+        root: root.to_string_lossy().to_string(),
+        requested_path: path.to_string_lossy().to_string(),
+        subpath,
+        evidence,
+    })
+}
+
+#[tauri::command]
+pub fn infer_project_root(path: String) -> Option<InferredRoot> {
+    infer_root(Path::new(&path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write(root: &Path, relative: &str, content: &str) {
+        let path = root.join(relative);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    fn markers_of(inferred: &InferredRoot, root: &Path) -> Vec<(String, RootMarker)> {
+        inferred
+            .evidence
+            .iter()
+            .map(|evidence| {
+                let relative = Path::new(&evidence.path)
+                    .strip_prefix(root)
+                    .unwrap()
+                    .to_string_lossy()
+                    .to_string();
+                (relative, evidence.marker)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_workspace_in_monorepo_resolves_to_repository() {
+        let temp = TempDir::new().unwrap();
+        let base = temp.path().canonicalize().unwrap();
+        let repo = base.join("repo");
+        fs::create_dir_all(repo.join(".git")).unwrap();
+        write(&repo, "package.json", r#"{"workspaces": ["packages/*"]}"#);
+        write(&repo, "packages/app/package.json", r#"{"name": "app"}"#);
+        write(
+            &repo,
+            "native/Cargo.toml",
+            "[workspace]\nmembers = [\"crates/*\"]\n",
+        );
+        write(
+            &repo,
+            "native/crates/core/Cargo.toml",
+            "[package]\nname = \"core\"\n",
+        );
+        fs::create_dir_all(repo.join("native/crates/core/src")).unwrap();
+
+        let inferred =
+            infer_root_within(&repo.join("native/crates/core/src"), Some(&base)).unwrap();
+        assert_eq!(Path::new(&inferred.root), repo);
+        assert_eq!(inferred.subpath, "native/crates/core/src");
+        assert_eq!(
+            markers_of(&inferred, &repo),
+            vec![
+                ("native".to_string(), RootMarker::CargoWorkspace),
+                (String::new(), RootMarker::Git),
+                (String::new(), RootMarker::PackageWorkspaces),
+            ]
+        );
+
+        // A plain package inside the workspace is not a root of its own
+        let inferred = infer_root_within(&repo.join("packages/app"), Some(&base)).unwrap();
+        assert_eq!(Path::new(&inferred.root), repo);
+        assert_eq!(inferred.subpath, "packages/app");
+
+        let inferred = infer_root_within(&repo, Some(&base)).unwrap();
+        assert_eq!(inferred.subpath, "");
+    }
+
+    #[test]
+    fn test_nearest_repository_and_outermost_workspace() {
+        let temp = TempDir::new().unwrap();
+        let base = temp.path().canonicalize().unwrap();
+
+        // A submodule's `.git` file wins over the repository around it
+        fs::create_dir_all(base.join("outer/.git")).unwrap();
+        write(
+            &base,
+            "outer/vendor/lib/.git",
+            "gitdir: ../../.git/modules/lib\n",
+        );
+        fs::create_dir_all(base.join("outer/vendor/lib/src")).unwrap();
+        let inferred = infer_root_within(&base.join("outer/vendor/lib/src"), Some(&base)).unwrap();
+        assert_eq!(Path::new(&inferred.root), base.join("outer/vendor/lib"));
+
+        // Without git, nested workspaces resolve to the outermost one
+        write(&base, "ws/go.work", "go 1.22\n");
+        write(&base, "ws/tools/pnpm-workspace.yaml", "packages: []\n");
+        fs::create_dir_all(base.join("ws/tools/cli")).unwrap();
+        let inferred = infer_root_within(&base.join("ws/tools/cli"), Some(&base)).unwrap();
+        assert_eq!(Path::new(&inferred.root), base.join("ws"));
+        assert_eq!(inferred.subpath, "tools/cli");
+
+        fs::create_dir_all(base.join("plain/src")).unwrap();
+        assert!(infer_root_within(&base.join("plain/src"), Some(&base)).is_none());
+    }
+
+    #[test]
+    fn test_stops_at_home_directory() {
+        let temp = TempDir::new().unwrap();
+        let home = temp.path().canonicalize().unwrap();
+        // A dotfiles repository in the home directory is never suggested
+        fs::create_dir_all(home.join(".git")).unwrap();
+        fs::create_dir_all(home.join("notes/2024")).unwrap();
+        assert!(infer_root_within(&home.join("notes/2024"), Some(&home)).is_none());
+
+        write(
+            &home,
+            "code/mono/package.json",
+            r#"{"workspaces": {"packages": []}}"#,
+        );
+        fs::create_dir_all(home.join("code/mono/web")).unwrap();
+        let inferred = infer_root_within(&home.join("code/mono/web"), Some(&home)).unwrap();
+        assert_eq!(Path::new(&inferred.root), home.join("code/mono"));
+        assert_eq!(
+            markers_of(&inferred, &home),
+            vec![("code/mono".to_string(), RootMarker::PackageWorkspaces)]
+        );
+    }
+}
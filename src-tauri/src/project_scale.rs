@@ -0,0 +1,479 @@
+// Project scale module
+// Samples a project directory within a time budget to estimate how large it is, so the
+// file watcher and indexer can pick conservative defaults for huge roots (e.g. ~/).
+
+use crate::constants::should_exclude_dir;
+use ignore::gitignore::Gitignore;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Default time budget for a scale assessment
+pub const DEFAULT_TIME_BUDGET_MS: u64 = 300;
+
+/// Upper bound on files visited by one sample regardless of the time budget
+const MAX_SAMPLED_FILES: usize = 200_000;
+
+/// Thresholds on the estimated file count
+const MEDIUM_FILE_COUNT: u64 = 5_000;
+const LARGE_FILE_COUNT: u64 = 50_000;
+const VERY_LARGE_FILE_COUNT: u64 = 200_000;
+
+/// Indexing cap recommended for large projects
+const LARGE_PROJECT_INDEX_LIMIT: usize = 5_000;
+
+lazy_static::lazy_static! {
+    /// Latest assessment per root path, so window setup and the watcher share one scan
+    static ref SCALE_CACHE: Mutex<HashMap<String, ProjectScaleAssessment>> = Mutex::new(HashMap::new());
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ProjectScale {
+    Small,
+    Medium,
+    Large,
+    VeryLarge,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ScaleRecommendation {
+    /// Stable identifier: "lazy-indexing", "skip-watcher-recursion" or "raise-inotify-limit"
+    pub id: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectScaleAssessment {
+    pub root_path: String,
+    pub scale: ProjectScale,
+    pub estimated_file_count: u64,
+    pub estimated_dir_count: u64,
+    pub estimated_total_size: u64,
+    /// Files actually visited by the sampler
+    pub sampled_file_count: u64,
+    /// True when the walk finished, so the estimates are exact
+    pub complete: bool,
+    /// True when the time budget ran out before the walk finished
+    pub exceeded_time_budget: bool,
+    /// Suggested cap on files handed to the indexer, if any
+    pub recommended_max_indexed_files: Option<usize>,
+    pub recommendations: Vec<ScaleRecommendation>,
+    pub elapsed_ms: u64,
+}
+
+/// Limits for a single sample
+#[derive(Debug, Clone, Copy)]
+pub struct SampleLimits {
+    pub time_budget: Duration,
+    pub max_files: usize,
+}
+
+/// Raw counts gathered by the breadth-first sampler
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SampleStats {
+    pub files: u64,
+    pub dirs: u64,
+    pub bytes: u64,
+    pub complete: bool,
+    pub timed_out: bool,
+    /// Directories at the level being processed when the walk stopped
+    pub frontier_total: u64,
+    /// How many of those were processed
+    pub frontier_processed: u64,
+    /// Files, bytes and subdirectories found in the processed frontier directories
+    pub frontier_files: u64,
+    pub frontier_bytes: u64,
+    pub frontier_subdirs: u64,
+}
+
+/// Walk `root` breadth-first, honouring the shared exclusion list and the root .gitignore,
+/// until the walk completes or a limit is hit. Limits are checked between directories.
+pub fn sample_directory(root: &Path, limits: SampleLimits) -> SampleStats {
+    let start = Instant::now();
+    let (gitignore, _) = Gitignore::new(root.join(".gitignore"));
+    let mut stats = SampleStats::default();
+
+    let mut level: VecDeque<PathBuf> = VecDeque::from([root.to_path_buf()]);
+    while !level.is_empty() {
+        let level_size = level.len() as u64;
+        let mut next_level = VecDeque::new();
+        let mut processed = 0;
+        let mut level_files = 0;
+        let mut level_bytes = 0;
+
+        while let Some(dir) = level.pop_front() {
+            if start.elapsed() >= limits.time_budget {
+                stats.timed_out = true;
+            }
+            if stats.timed_out || stats.files as usize >= limits.max_files {
+                stats.frontier_total = level_size;
+                stats.frontier_processed = processed;
+                stats.frontier_files = level_files;
+                stats.frontier_bytes = level_bytes;
+                stats.frontier_subdirs = next_level.len() as u64;
+                return stats;
+            }
+
+            stats.dirs += 1;
+            processed += 1;
+            let Ok(entries) = std::fs::read_dir(&dir) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let Ok(file_type) = entry.file_type() else {
+                    continue;
+                };
+                let path = entry.path();
+                if file_type.is_dir() {
+                    let name = entry.file_name();
+                    if should_exclude_dir(&name.to_string_lossy())
+                        || gitignore.matched(&path, true).is_ignore()
+                    {
+                        continue;
+                    }
+                    next_level.push_back(path);
+                } else if file_type.is_file() {
+                    if gitignore.matched(&path, false).is_ignore() {
+                        continue;
+                    }
+                    let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+                    stats.files += 1;
+                    stats.bytes += size;
+                    level_files += 1;
+                    level_bytes += size;
+                }
+            }
+        }
+        level = next_level;
+    }
+
+    stats.complete = true;
+    stats
+}
+
+/// Estimate totals from a possibly incomplete sample.
+///
+/// Unprocessed directories on the frontier level are assumed to look like the processed
+/// ones (same files, bytes and subdirectories per directory), and their subdirectories
+/// are assumed to hold the same number of files again. Deeper levels are not
+/// extrapolated, so the result is a conservative estimate for very deep trees.
+/// Returns (files, dirs, bytes).
+pub fn extrapolate(stats: &SampleStats) -> (u64, u64, u64) {
+    if stats.complete || stats.frontier_processed == 0 {
+        // Nothing processed on the frontier: count the unvisited directories only
+        let pending = stats
+            .frontier_total
+            .saturating_sub(stats.frontier_processed);
+        return (stats.files, stats.dirs + pending, stats.bytes);
+    }
+
+    let processed = stats.frontier_processed as f64;
+    let remaining = (stats.frontier_total - stats.frontier_processed) as f64;
+    let files_per_dir = stats.frontier_files as f64 / processed;
+    let bytes_per_dir = stats.frontier_bytes as f64 / processed;
+    let subdirs_per_dir = stats.frontier_subdirs as f64 / processed;
+
+    // Subdirectories below the whole frontier level (found + expected from the rest)
+    let next_level_dirs = stats.frontier_subdirs as f64 + remaining * subdirs_per_dir;
+
+    let files = stats.files as f64 + (remaining + next_level_dirs) * files_per_dir;
+    let bytes = stats.bytes as f64 + (remaining + next_level_dirs) * bytes_per_dir;
+    let dirs = stats.dirs as f64 + remaining + next_level_dirs;
+    (
+        files.round() as u64,
+        dirs.round() as u64,
+        bytes.round() as u64,
+    )
+}
+
+fn classify(estimated_files: u64, timed_out: bool) -> ProjectScale {
+    if timed_out || estimated_files >= VERY_LARGE_FILE_COUNT {
+        ProjectScale::VeryLarge
+    } else if estimated_files >= LARGE_FILE_COUNT {
+        ProjectScale::Large
+    } else if estimated_files >= MEDIUM_FILE_COUNT {
+        ProjectScale::Medium
+    } else {
+        ProjectScale::Small
+    }
+}
+
+/// Current inotify watch limit (Linux only)
+fn inotify_watch_limit() -> Option<u64> {
+    if !cfg!(target_os = "linux") {
+        return None;
+    }
+    std::fs::read_to_string("/proc/sys/fs/inotify/max_user_watches")
+        .ok()
+        .and_then(|s| s.trim().parse().ok())
+}
+
+fn recommendations_for(
+    scale: ProjectScale,
+    estimated_dirs: u64,
+    watch_limit: Option<u64>,
+) -> Vec<ScaleRecommendation> {
+    let mut recommendations = Vec::new();
+    if scale >= ProjectScale::Large {
+        recommendations.push(ScaleRecommendation {
+            id: "lazy-indexing".to_string(),
+            message: "Index files lazily as they are opened instead of indexing the whole project"
+                .to_string(),
+        });
+    }
+    if scale == ProjectScale::VeryLarge {
+        recommendations.push(ScaleRecommendation {
+            id: "skip-watcher-recursion".to_string(),
+            message: "Only watch the top-level directory for changes".to_string(),
+        });
+    }
+    if let Some(limit) = watch_limit {
+        // inotify needs one watch per directory for recursive watching
+        if estimated_dirs > limit {
+            recommendations.push(ScaleRecommendation {
+                id: "raise-inotify-limit".to_string(),
+                message: format!(
+                    "The project has about {} directories but fs.inotify.max_user_watches is {}; raise it with sysctl",
+                    estimated_dirs, limit
+                ),
+            });
+        }
+    }
+    recommendations
+}
+
+/// Sample `root_path` and build a scale assessment
+pub fn assess(root_path: &str, limits: SampleLimits) -> Result<ProjectScaleAssessment, String> {
+    let root = Path::new(root_path);
+    if !root.is_dir() {
+        return Err(format!("Not a directory: {}", root_path));
+    }
+
+    let start = Instant::now();
+    let stats = sample_directory(root, limits);
+    let (files, dirs, bytes) = extrapolate(&stats);
+    let scale = classify(files, stats.timed_out);
+
+    let assessment = ProjectScaleAssessment {
+        root_path: root_path.to_string(),
+        scale,
+        estimated_file_count: files,
+        estimated_dir_count: dirs,
+        estimated_total_size: bytes,
+        sampled_file_count: stats.files,
+        complete: stats.complete,
+        exceeded_time_budget: stats.timed_out,
+        recommended_max_indexed_files: (scale >= ProjectScale::Large)
+            .then_some(LARGE_PROJECT_INDEX_LIMIT),
+        recommendations: recommendations_for(scale, dirs, inotify_watch_limit()),
+        elapsed_ms: start.elapsed().as_millis() as u64,
+    };
+
+    log::info!(
+        "Assessed project scale for {}: {:?} (~{} files, complete: {}, {}ms)",
+        root_path,
+        assessment.scale,
+        assessment.estimated_file_count,
+        assessment.complete,
+        assessment.elapsed_ms
+    );
+
+    if let Ok(mut cache) = SCALE_CACHE.lock() {
+        cache.insert(root_path.to_string(), assessment.clone());
+    }
+    Ok(assessment)
+}
+
+fn default_limits(time_budget_ms: Option<u64>) -> SampleLimits {
+    SampleLimits {
+        time_budget: Duration::from_millis(time_budget_ms.unwrap_or(DEFAULT_TIME_BUDGET_MS)),
+        max_files: MAX_SAMPLED_FILES,
+    }
+}
+
+/// Scale of a project from the cache, assessing it with the default budget on a miss
+pub fn scale_for(root_path: &str) -> ProjectScale {
+    if let Some(cached) = SCALE_CACHE
+        .lock()
+        .ok()
+        .and_then(|cache| cache.get(root_path).map(|a| a.scale))
+    {
+        return cached;
+    }
+    assess(root_path, default_limits(None))
+        .map(|a| a.scale)
+        .unwrap_or(ProjectScale::Small)
+}
+
+/// Assess a project in the background so later lookups hit the cache
+pub fn prefetch(root_path: String) {
+    std::thread::spawn(move || {
+        if let Err(e) = assess(&root_path, default_limits(None)) {
+            log::warn!("Failed to assess project scale for {}: {}", root_path, e);
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn assess_project_scale(
+    root_path: String,
+    time_budget_ms: Option<u64>,
+) -> Result<ProjectScaleAssessment, String> {
+    tauri::async_runtime::spawn_blocking(move || assess(&root_path, default_limits(time_budget_ms)))
+        .await
+        .map_err(|e| format!("Project scale assessment failed: {}", e))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn unlimited() -> SampleLimits {
+        SampleLimits {
+            time_budget: Duration::from_secs(60),
+            max_files: usize::MAX,
+        }
+    }
+
+    /// Build a tree where every directory at each level has the given number of
+    /// subdirectories, and leaf directories hold `files_per_leaf` files of 10 bytes
+    fn build_tree(root: &Path, fanout: &[usize], files_per_leaf: usize) {
+        if fanout.is_empty() {
+            for i in 0..files_per_leaf {
+                fs::write(root.join(format!("file{}.txt", i)), "0123456789").unwrap();
+            }
+            return;
+        }
+        for i in 0..fanout[0] {
+            let dir = root.join(format!("dir{}", i));
+            fs::create_dir_all(&dir).unwrap();
+            build_tree(&dir, &fanout[1..], files_per_leaf);
+        }
+    }
+
+    #[test]
+    fn test_complete_sample_is_exact() {
+        let temp_dir = TempDir::new().unwrap();
+        build_tree(temp_dir.path(), &[3, 4], 5);
+
+        let stats = sample_directory(temp_dir.path(), unlimited());
+        assert!(stats.complete);
+        assert!(!stats.timed_out);
+        assert_eq!(stats.files, 60);
+        assert_eq!(stats.dirs, 1 + 3 + 12);
+        assert_eq!(extrapolate(&stats), (60, 16, 600));
+    }
+
+    #[test]
+    fn test_early_exit_extrapolates_leaf_level() {
+        let temp_dir = TempDir::new().unwrap();
+        // 10 leaf directories with 10 files each
+        build_tree(temp_dir.path(), &[10], 10);
+
+        let stats = sample_directory(
+            temp_dir.path(),
+            SampleLimits {
+                time_budget: Duration::from_secs(60),
+                max_files: 40,
+            },
+        );
+        assert!(!stats.complete);
+        assert_eq!(stats.files, 40);
+        assert_eq!(stats.frontier_total, 10);
+        assert_eq!(stats.frontier_processed, 4);
+
+        let (files, dirs, bytes) = extrapolate(&stats);
+        assert_eq!(files, 100);
+        assert_eq!(dirs, 11);
+        assert_eq!(bytes, 1000);
+    }
+
+    #[test]
+    fn test_early_exit_on_deeper_level() {
+        let temp_dir = TempDir::new().unwrap();
+        // 5 dirs x 4 subdirs x 5 files = 100 files
+        build_tree(temp_dir.path(), &[5, 4], 5);
+
+        let stats = sample_directory(
+            temp_dir.path(),
+            SampleLimits {
+                time_budget: Duration::from_secs(60),
+                max_files: 30,
+            },
+        );
+        assert!(!stats.complete);
+        assert_eq!(stats.frontier_total, 20);
+        assert_eq!(stats.frontier_processed, 6);
+        assert_eq!(extrapolate(&stats).0, 100);
+    }
+
+    #[test]
+    fn test_zero_time_budget_times_out() {
+        let temp_dir = TempDir::new().unwrap();
+        build_tree(temp_dir.path(), &[2], 2);
+
+        let stats = sample_directory(
+            temp_dir.path(),
+            SampleLimits {
+                time_budget: Duration::ZERO,
+                max_files: usize::MAX,
+            },
+        );
+        assert!(stats.timed_out);
+        assert!(!stats.complete);
+        assert_eq!(stats.files, 0);
+        assert_eq!(
+            classify(extrapolate(&stats).0, stats.timed_out),
+            ProjectScale::VeryLarge
+        );
+    }
+
+    #[test]
+    fn test_sample_skips_excluded_and_gitignored_dirs() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        build_tree(root, &[2], 3);
+        build_tree(&root.join("node_modules"), &[5], 5);
+        build_tree(&root.join("generated"), &[5], 5);
+        fs::write(root.join(".gitignore"), "generated/\n*.log\n").unwrap();
+        fs::write(root.join("debug.log"), "x").unwrap();
+
+        let stats = sample_directory(root, unlimited());
+        // 6 tree files + .gitignore
+        assert_eq!(stats.files, 7);
+    }
+
+    #[test]
+    fn test_classify_and_recommendations() {
+        assert_eq!(classify(100, false), ProjectScale::Small);
+        assert_eq!(classify(10_000, false), ProjectScale::Medium);
+        assert_eq!(classify(60_000, false), ProjectScale::Large);
+        assert_eq!(classify(500_000, false), ProjectScale::VeryLarge);
+
+        let ids = |r: Vec<ScaleRecommendation>| r.into_iter().map(|r| r.id).collect::<Vec<_>>();
+        assert!(ids(recommendations_for(ProjectScale::Small, 10, Some(8192))).is_empty());
+        assert_eq!(
+            ids(recommendations_for(ProjectScale::Large, 10, None)),
+            vec!["lazy-indexing"]
+        );
+        assert_eq!(
+            ids(recommendations_for(
+                ProjectScale::VeryLarge,
+                100_000,
+                Some(8192)
+            )),
+            vec![
+                "lazy-indexing",
+                "skip-watcher-recursion",
+                "raise-inotify-limit"
+            ]
+        );
+    }
+}
@@ -0,0 +1,274 @@
+// Reference count module
+// Serves CodeLens-style "N references" counts for definitions. Counting runs the hybrid
+// reference search per symbol, so it is done within a time budget and cached; the cache is
+// keyed by a repo generation bumped on git changes and trimmed per file on file changes.
+
+use crate::code_navigation::{CodeNavState, CodeNavigationService, SymbolInfo};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::State;
+
+/// Upper bound on cached counts; the cache is cleared when it is reached
+const MAX_CACHE_ENTRIES: usize = 20_000;
+
+/// Bumped whenever the git watcher sees a status change (checkout, pull, reset, ...)
+static REPO_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+lazy_static::lazy_static! {
+    static ref REFERENCE_COUNT_CACHE: Mutex<ReferenceCountCache> = Mutex::new(ReferenceCountCache::default());
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ReferenceCount {
+    pub name: String,
+    pub lang_family: String,
+    pub file_path: String,
+    pub start_line: u32,
+    /// Exact count, or None while the count is still pending
+    pub count: Option<usize>,
+    pub pending: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct CacheKey {
+    root_path: String,
+    name: String,
+    lang_family: String,
+    generation: u64,
+}
+
+#[derive(Debug, Clone)]
+struct CachedCount {
+    count: usize,
+    /// Files the references were found in, for per-file invalidation
+    files: HashSet<String>,
+}
+
+#[derive(Default)]
+pub struct ReferenceCountCache {
+    entries: HashMap<CacheKey, CachedCount>,
+}
+
+impl ReferenceCountCache {
+    /// Return counts for `symbols`, computing uncached ones in the given order until
+    /// `budget` runs out. Callers pass the most recently requested symbols first (e.g. the
+    /// viewport just scrolled to); the rest come back pending and a follow-up call with the
+    /// same symbols picks up where this one stopped.
+    pub fn counts_within_budget<F>(
+        &mut self,
+        symbols: &[SymbolInfo],
+        root_path: &str,
+        generation: u64,
+        budget: Duration,
+        mut count_references: F,
+    ) -> Vec<ReferenceCount>
+    where
+        F: FnMut(&str, &str) -> (usize, HashSet<String>),
+    {
+        let start = Instant::now();
+        self.entries.retain(|key, _| key.generation == generation);
+
+        symbols
+            .iter()
+            .map(|symbol| {
+                // Dependency symbols are left out of reference counts
+                if symbol.dependency {
+                    return ReferenceCount {
+                        name: symbol.name.clone(),
+                        lang_family: symbol.lang_family.clone(),
+                        file_path: symbol.file_path.clone(),
+                        start_line: symbol.start_line,
+                        count: None,
+                        pending: false,
+                    };
+                }
+                let key = CacheKey {
+                    root_path: root_path.to_string(),
+                    name: symbol.name.clone(),
+                    lang_family: symbol.lang_family.clone(),
+                    generation,
+                };
+                let count = match self.entries.get(&key) {
+                    Some(cached) => Some(cached.count),
+                    None if start.elapsed() < budget => {
+                        let (count, files) = count_references(&symbol.name, &symbol.lang_family);
+                        if self.entries.len() >= MAX_CACHE_ENTRIES {
+                            self.entries.clear();
+                        }
+                        self.entries.insert(key, CachedCount { count, files });
+                        Some(count)
+                    }
+                    None => None,
+                };
+                ReferenceCount {
+                    name: symbol.name.clone(),
+                    lang_family: symbol.lang_family.clone(),
+                    file_path: symbol.file_path.clone(),
+                    start_line: symbol.start_line,
+                    count,
+                    pending: count.is_none(),
+                }
+            })
+            .collect()
+    }
+
+    /// Drop counts a changed file may affect: symbols referenced from it before the change
+    /// and symbols whose name appears in its new content
+    pub fn invalidate_file(&mut self, file_path: &str, content: Option<&str>) {
+        self.entries.retain(|key, cached| {
+            !cached.files.contains(file_path)
+                && !content.is_some_and(|content| content.contains(key.name.as_str()))
+        });
+    }
+}
+
+pub fn current_generation() -> u64 {
+    REPO_GENERATION.load(Ordering::Relaxed)
+}
+
+/// Invalidate every cached count; called by the git watcher
+pub fn bump_generation() {
+    REPO_GENERATION.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Invalidate counts affected by changed files; called by the file watcher
+pub fn invalidate_paths(paths: &[PathBuf]) {
+    let Ok(mut cache) = REFERENCE_COUNT_CACHE.lock() else {
+        return;
+    };
+    for path in paths {
+        let content = std::fs::read_to_string(path).ok();
+        cache.invalidate_file(&path.to_string_lossy(), content.as_deref());
+    }
+}
+
+#[tauri::command]
+pub async fn code_nav_reference_counts(
+    state: State<'_, CodeNavState>,
+    symbols: Vec<SymbolInfo>,
+    root_path: String,
+    budget_ms: Option<u64>,
+) -> Result<Vec<ReferenceCount>, String> {
+    let service = state
+        .0
+        .read()
+        .map_err(|e| format!("Failed to acquire read lock: {}", e))?;
+    let mut cache = REFERENCE_COUNT_CACHE
+        .lock()
+        .map_err(|e| format!("Failed to lock reference count cache: {}", e))?;
+
+    let counts = cache.counts_within_budget(
+        &symbols,
+        &root_path,
+        current_generation(),
+        Duration::from_millis(budget_ms.unwrap_or(200)),
+        |name, lang_family| count_with_service(&service, name, lang_family, &root_path),
+    );
+    log::info!(
+        "Reference counts for {} symbols, {} pending",
+        counts.len(),
+        counts.iter().filter(|c| c.pending).count()
+    );
+    Ok(counts)
+}
+
+fn count_with_service(
+    service: &CodeNavigationService,
+    name: &str,
+    lang_family: &str,
+    root_path: &str,
+) -> (usize, HashSet<String>) {
+    let references = service.find_references_hybrid(name, lang_family, root_path);
+    let files = references.iter().map(|r| r.file_path.clone()).collect();
+    (references.len(), files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    fn symbol(name: &str) -> SymbolInfo {
+        SymbolInfo {
+            name: name.to_string(),
+            kind: "function".to_string(),
+            file_path: "/repo/src/lib.rs".to_string(),
+            lang_family: "rust".to_string(),
+            start_line: 1,
+            start_column: 0,
+            end_line: 3,
+            end_column: 1,
+            start_column_utf16: 0,
+            end_column_utf16: 1,
+            exported: false,
+            dependency: false,
+            visibility: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_budget_cutoff_leaves_rest_pending_and_resumes() {
+        let mut cache = ReferenceCountCache::default();
+        let symbols = vec![symbol("alpha"), symbol("beta"), symbol("gamma")];
+        let computed = RefCell::new(Vec::new());
+        let slow_count = |name: &str, _: &str| {
+            std::thread::sleep(Duration::from_millis(40));
+            computed.borrow_mut().push(name.to_string());
+            (name.len(), HashSet::new())
+        };
+
+        let first =
+            cache.counts_within_budget(&symbols, "/repo", 0, Duration::from_millis(60), slow_count);
+        let done = first.iter().filter(|c| !c.pending).count();
+        assert!((1..3).contains(&done));
+        // Pending symbols are always the tail of the request order
+        assert!(first[..done].iter().all(|c| c.count.is_some()));
+        assert!(first[done..].iter().all(|c| c.pending && c.count.is_none()));
+        assert_eq!(first[0].count, Some(5));
+
+        let second =
+            cache.counts_within_budget(&symbols, "/repo", 0, Duration::from_secs(10), slow_count);
+        assert!(second.iter().all(|c| !c.pending));
+        assert_eq!(second[2].count, Some(5));
+        // Each symbol was only counted once across both calls
+        assert_eq!(*computed.borrow(), vec!["alpha", "beta", "gamma"]);
+    }
+
+    #[test]
+    fn test_cache_reuse_and_invalidation() {
+        let mut cache = ReferenceCountCache::default();
+        let symbols = vec![symbol("alpha"), symbol("beta")];
+        let calls = RefCell::new(0);
+        let count = |name: &str, _: &str| {
+            *calls.borrow_mut() += 1;
+            let files = match name {
+                "alpha" => HashSet::from(["/repo/src/a.rs".to_string()]),
+                _ => HashSet::from(["/repo/src/b.rs".to_string()]),
+            };
+            (2, files)
+        };
+        let budget = Duration::from_secs(10);
+
+        cache.counts_within_budget(&symbols, "/repo", 0, budget, count);
+        cache.counts_within_budget(&symbols, "/repo", 0, budget, count);
+        assert_eq!(*calls.borrow(), 2);
+
+        // A file that referenced alpha changed: only alpha is recounted
+        cache.invalidate_file("/repo/src/a.rs", Some("fn unrelated() {}"));
+        cache.counts_within_budget(&symbols, "/repo", 0, budget, count);
+        assert_eq!(*calls.borrow(), 3);
+
+        // A new file mentioning beta may add references to it
+        cache.invalidate_file("/repo/src/new.rs", Some("beta();"));
+        cache.counts_within_budget(&symbols, "/repo", 0, budget, count);
+        assert_eq!(*calls.borrow(), 4);
+
+        // A new repo generation recounts everything
+        cache.counts_within_budget(&symbols, "/repo", 1, budget, count);
+        assert_eq!(*calls.borrow(), 6);
+    }
+}
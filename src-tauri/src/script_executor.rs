@@ -1,12 +1,19 @@
 // src-tauri/src/script_executor.rs
 
+use crate::skill_manifest::{self, SkillPolicy};
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
 use std::process::Stdio;
 use std::time::Duration;
 use std::time::Instant;
 use tokio::io::{AsyncReadExt, BufReader};
 use tokio::process::Command;
 
+/// How long a `--version` probe may take before the interpreter is skipped
+const VERSION_PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ScriptExecutionRequest {
     pub script_path: String,
@@ -15,6 +22,13 @@ pub struct ScriptExecutionRequest {
     pub working_dir: Option<String>,
     pub timeout_ms: Option<u64>,
     pub environment: Option<std::collections::HashMap<String, String>>,
+    /// Optional version constraint such as "python>=3.10" or "node>=18"
+    #[serde(default)]
+    pub runtime_constraint: Option<String>,
+    /// Directory of the skill the script belongs to. Its skill.json must validate, and
+    /// capabilities it does not declare are restricted.
+    #[serde(default)]
+    pub skill_dir: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -25,42 +39,328 @@ pub struct ScriptExecutionResult {
     pub execution_time_ms: u64,
     pub success: bool,
     pub error: Option<String>,
+    /// Interpreter the script was run with
+    pub interpreter: Option<ResolvedRuntime>,
+    /// Set when no interpreter satisfied the runtime constraint
+    pub runtime_error: Option<RuntimeResolutionError>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Runtime {
+    Python,
+    Bash,
+    Node,
+}
+
+impl Runtime {
+    fn from_script_type(script_type: &str) -> Option<Self> {
+        match script_type {
+            "python" => Some(Runtime::Python),
+            "bash" | "sh" => Some(Runtime::Bash),
+            "nodejs" | "javascript" => Some(Runtime::Node),
+            _ => None,
+        }
+    }
+
+    fn from_constraint_name(name: &str) -> Option<Self> {
+        match name {
+            "python" | "python3" => Some(Runtime::Python),
+            "bash" => Some(Runtime::Bash),
+            "node" | "nodejs" => Some(Runtime::Node),
+            _ => None,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Runtime::Python => "python",
+            Runtime::Bash => "bash",
+            Runtime::Node => "node",
+        }
+    }
+
+    /// Executable names probed on PATH, in order of preference
+    fn candidate_names(&self) -> &'static [&'static str] {
+        match self {
+            #[cfg(windows)]
+            Runtime::Python => &["python3", "python", "py"],
+            #[cfg(not(windows))]
+            Runtime::Python => &["python3", "python"],
+            Runtime::Bash => &["bash"],
+            Runtime::Node => &["node"],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct RuntimeVersion {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+}
+
+impl std::fmt::Display for RuntimeVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref VERSION_PATTERN: regex::Regex = regex::Regex::new(r"(\d+)\.(\d+)(?:\.(\d+))?").unwrap();
+}
+
+/// Parse the version from `--version` output, e.g. "Python 3.11.4", "v18.17.1" or
+/// "GNU bash, version 5.2.15(1)-release"
+pub fn parse_version_output(output: &str) -> Option<RuntimeVersion> {
+    let captures = VERSION_PATTERN.captures(output)?;
+    let component = |i: usize| {
+        captures
+            .get(i)
+            .and_then(|m| m.as_str().parse().ok())
+            .unwrap_or(0)
+    };
+    Some(RuntimeVersion {
+        major: component(1),
+        minor: component(2),
+        patch: component(3),
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CompareOp {
+    Ge,
+    Gt,
+    Le,
+    Lt,
+    Eq,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct VersionRequirement {
+    op: CompareOp,
+    version: RuntimeVersion,
+    /// Number of components given (1-3); "==3.11" matches any 3.11.x
+    precision: usize,
+    text: String,
+}
+
+impl VersionRequirement {
+    fn matches(&self, version: &RuntimeVersion) -> bool {
+        match self.op {
+            CompareOp::Ge => *version >= self.version,
+            CompareOp::Gt => *version > self.version,
+            CompareOp::Le => *version <= self.version,
+            CompareOp::Lt => *version < self.version,
+            CompareOp::Eq => {
+                let components = [
+                    (version.major, self.version.major),
+                    (version.minor, self.version.minor),
+                    (version.patch, self.version.patch),
+                ];
+                components[..self.precision].iter().all(|(a, b)| a == b)
+            }
+        }
+    }
+}
+
+/// A runtime constraint such as "python>=3.10" or "node>=18,<23"
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuntimeConstraint {
+    pub runtime: Runtime,
+    requirements: Vec<VersionRequirement>,
+}
+
+impl RuntimeConstraint {
+    pub fn parse(constraint: &str) -> Result<Self, String> {
+        let constraint = constraint.trim();
+        let name_end = constraint
+            .find(|c: char| !(c.is_ascii_alphanumeric()))
+            .unwrap_or(constraint.len());
+        let name = &constraint[..name_end];
+        let runtime = Runtime::from_constraint_name(&name.to_lowercase())
+            .ok_or_else(|| format!("Unknown runtime in constraint: {}", constraint))?;
+
+        let mut requirements = Vec::new();
+        for part in constraint[name_end..]
+            .split(',')
+            .map(str::trim)
+            .filter(|p| !p.is_empty())
+        {
+            let (op, rest) = [
+                (">=", CompareOp::Ge),
+                ("<=", CompareOp::Le),
+                ("==", CompareOp::Eq),
+                (">", CompareOp::Gt),
+                ("<", CompareOp::Lt),
+                ("=", CompareOp::Eq),
+            ]
+            .iter()
+            .find_map(|(prefix, op)| part.strip_prefix(prefix).map(|rest| (*op, rest.trim())))
+            .ok_or_else(|| format!("Invalid version requirement: {}", part))?;
+
+            let components: Vec<u64> = rest
+                .split('.')
+                .map(|c| c.parse::<u64>())
+                .collect::<Result<_, _>>()
+                .map_err(|_| format!("Invalid version in requirement: {}", part))?;
+            if components.is_empty() || components.len() > 3 {
+                return Err(format!("Invalid version in requirement: {}", part));
+            }
+            requirements.push(VersionRequirement {
+                op,
+                version: RuntimeVersion {
+                    major: components[0],
+                    minor: components.get(1).copied().unwrap_or(0),
+                    patch: components.get(2).copied().unwrap_or(0),
+                },
+                precision: components.len(),
+                text: part.replace(' ', ""),
+            });
+        }
+
+        Ok(Self {
+            runtime,
+            requirements,
+        })
+    }
+
+    pub fn is_satisfied_by(&self, version: &RuntimeVersion) -> bool {
+        self.requirements.iter().all(|r| r.matches(version))
+    }
+
+    fn describe(&self) -> String {
+        let requirements: Vec<String> = self
+            .requirements
+            .iter()
+            .map(|r| {
+                let split = r.text.find(|c: char| c.is_ascii_digit()).unwrap_or(0);
+                format!("{} {}", &r.text[..split], &r.text[split..])
+            })
+            .collect();
+        if requirements.is_empty() {
+            self.runtime.name().to_string()
+        } else {
+            format!("{} {}", self.runtime.name(), requirements.join(", "))
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ResolvedRuntime {
+    pub path: String,
+    /// Detected version; only probed when a constraint is given
+    pub version: Option<String>,
+}
+
+/// No interpreter satisfied a runtime constraint
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RuntimeResolutionError {
+    pub runtime: String,
+    pub constraint: String,
+    /// Interpreters that were found but did not satisfy the constraint
+    pub found: Vec<ResolvedRuntime>,
+}
+
+impl std::fmt::Display for RuntimeResolutionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "no {} found; ", self.constraint)?;
+        if self.found.is_empty() {
+            return write!(f, "no {} interpreter found on PATH", self.runtime);
+        }
+        let found: Vec<String> = self
+            .found
+            .iter()
+            .map(|r| {
+                format!(
+                    "{} at {}",
+                    r.version.as_deref().unwrap_or("unknown version"),
+                    r.path
+                )
+            })
+            .collect();
+        write!(f, "found {}", found.join(", "))
+    }
+}
+
+/// Find a Python interpreter in a .venv/venv next to the script or working directory,
+/// falling back to VIRTUAL_ENV
+pub fn find_virtualenv_python(dirs: &[&Path], virtual_env: Option<&str>) -> Option<PathBuf> {
+    let interpreter_in = |env_dir: &Path| {
+        [
+            "bin/python3",
+            "bin/python",
+            "Scripts/python.exe",
+            "Scripts/python3.exe",
+        ]
+        .iter()
+        .map(|p| env_dir.join(p))
+        .find(|p| p.is_file())
+    };
+    dirs.iter()
+        .flat_map(|dir| [dir.join(".venv"), dir.join("venv")])
+        .find_map(|env_dir| interpreter_in(&env_dir))
+        .or_else(|| virtual_env.and_then(|v| interpreter_in(Path::new(v))))
 }
 
 pub struct ScriptExecutor;
 
 impl ScriptExecutor {
     /// Execute a script with the specified parameters
-    pub async fn execute(request: ScriptExecutionRequest) -> Result<ScriptExecutionResult, String> {
+    pub async fn execute(
+        mut request: ScriptExecutionRequest,
+    ) -> Result<ScriptExecutionResult, String> {
         let start_time = Instant::now();
-
-        // Determine the command based on script type
-        let mut cmd = match request.script_type.as_str() {
-            "python" => {
-                let mut c = Command::new("python3");
-                c.arg(&request.script_path);
-                c
-            }
-            "bash" | "sh" => {
-                let mut c = Command::new("bash");
-                c.arg(&request.script_path);
-                c
+        let policy = Self::skill_policy(&mut request)?;
+
+        // Determine the interpreter based on script type and runtime constraint
+        let runtime = Runtime::from_script_type(&request.script_type)
+            .ok_or_else(|| format!("Unsupported script type: {}", request.script_type))?;
+        let constraint = request
+            .runtime_constraint
+            .as_deref()
+            .map(RuntimeConstraint::parse)
+            .transpose()?;
+        if let Some(ref constraint) = constraint {
+            if constraint.runtime != runtime {
+                return Err(format!(
+                    "Runtime constraint is for {} but script type is {}",
+                    constraint.runtime.name(),
+                    request.script_type
+                ));
             }
-            "nodejs" | "javascript" => {
-                let mut c = Command::new("node");
-                c.arg(&request.script_path);
-                c
-            }
-            _ => {
-                return Err(format!("Unsupported script type: {}", request.script_type));
+        }
+
+        let interpreter = match Self::resolve_runtime(runtime, constraint.as_ref(), &request).await
+        {
+            Ok(interpreter) => interpreter,
+            Err(runtime_error) => {
+                return Ok(ScriptExecutionResult {
+                    stdout: String::new(),
+                    stderr: runtime_error.to_string(),
+                    exit_code: -1,
+                    execution_time_ms: start_time.elapsed().as_millis() as u64,
+                    success: false,
+                    error: Some(runtime_error.to_string()),
+                    interpreter: None,
+                    runtime_error: Some(runtime_error),
+                });
             }
         };
 
+        let mut cmd = Command::new(&interpreter.path);
+        cmd.arg(&request.script_path);
+
         // Add arguments
         cmd.args(&request.args);
 
-        // Set working directory
-        if let Some(working_dir) = &request.working_dir {
+        // Set working directory; skills that may not write to the project run in their
+        // own workspace
+        let working_dir = policy
+            .as_ref()
+            .and_then(|p| p.pinned_cwd.as_ref())
+            .map(|dir| dir.to_string_lossy().to_string())
+            .or_else(|| request.working_dir.clone());
+        if let Some(working_dir) = &working_dir {
             // Validate working directory exists
             if !std::path::Path::new(working_dir).is_dir() {
                 return Ok(ScriptExecutionResult {
@@ -70,15 +370,22 @@ impl ScriptExecutor {
                     execution_time_ms: start_time.elapsed().as_millis() as u64,
                     success: false,
                     error: Some(format!("Invalid working directory: {}", working_dir)),
+                    interpreter: Some(interpreter),
+                    runtime_error: None,
                 });
             }
             cmd.current_dir(working_dir);
         }
 
         // Set environment variables
-        if let Some(env) = &request.environment {
-            cmd.envs(env);
+        let mut env = request.environment.clone().unwrap_or_default();
+        if let Some(ref policy) = policy {
+            policy.apply_env(&mut env);
+            for var in policy.removed_env_vars() {
+                cmd.env_remove(var);
+            }
         }
+        cmd.envs(env);
 
         // Configure stdio
         cmd.stdout(Stdio::piped());
@@ -103,6 +410,8 @@ impl ScriptExecutor {
                             "Script execution timeout ({}ms)",
                             timeout.as_millis()
                         )),
+                        interpreter: Some(interpreter),
+                        runtime_error: None,
                     });
                 }
             }
@@ -129,6 +438,8 @@ impl ScriptExecutor {
                     } else {
                         None
                     },
+                    interpreter: Some(interpreter),
+                    runtime_error: None,
                 })
             }
             Err(e) => Ok(ScriptExecutionResult {
@@ -138,10 +449,140 @@ impl ScriptExecutor {
                 execution_time_ms: execution_time,
                 success: false,
                 error: Some(format!("Execution error: {}", e)),
+                interpreter: Some(interpreter),
+                runtime_error: None,
             }),
         }
     }
 
+    /// Validate the manifest of the skill a request runs, defaulting the runtime
+    /// constraint to the one it declares. Requests outside of a skill have no policy.
+    fn skill_policy(request: &mut ScriptExecutionRequest) -> Result<Option<SkillPolicy>, String> {
+        let Some(skill_dir) = request.skill_dir.as_deref() else {
+            return Ok(None);
+        };
+        let manifest = skill_manifest::load_manifest(Path::new(skill_dir))
+            .map_err(|e| format!("Refusing to run skill script: {}", e))?;
+        if request.runtime_constraint.is_none() {
+            request.runtime_constraint = Some(manifest.runtime.clone());
+        }
+        let workspace = skill_manifest::skill_workspace(&manifest)?;
+        Ok(Some(SkillPolicy::for_manifest(&manifest, &workspace)))
+    }
+
+    /// Pick the interpreter for a script.
+    ///
+    /// Python prefers a virtualenv next to the script or working directory. Without a
+    /// constraint the first candidate is used as-is; with one, each candidate is probed
+    /// with `--version` and the first satisfying interpreter wins.
+    async fn resolve_runtime(
+        runtime: Runtime,
+        constraint: Option<&RuntimeConstraint>,
+        request: &ScriptExecutionRequest,
+    ) -> Result<ResolvedRuntime, RuntimeResolutionError> {
+        let candidates = Self::candidate_interpreters(runtime, request);
+
+        let Some(constraint) = constraint else {
+            // Fall back to the bare name so spawn errors read the same as before
+            let path = candidates
+                .into_iter()
+                .next()
+                .map(|p| p.to_string_lossy().to_string())
+                .unwrap_or_else(|| runtime.candidate_names()[0].to_string());
+            return Ok(ResolvedRuntime {
+                path,
+                version: None,
+            });
+        };
+
+        let mut found = Vec::new();
+        for candidate in candidates {
+            let path = candidate.to_string_lossy().to_string();
+            let version = Self::probe_version(&candidate).await;
+            if let Some(version) = version {
+                if constraint.is_satisfied_by(&version) {
+                    log::info!(
+                        "Resolved {} to {} ({})",
+                        constraint.describe(),
+                        path,
+                        version
+                    );
+                    return Ok(ResolvedRuntime {
+                        path,
+                        version: Some(version.to_string()),
+                    });
+                }
+            }
+            found.push(ResolvedRuntime {
+                path,
+                version: version.map(|v| v.to_string()),
+            });
+        }
+
+        Err(RuntimeResolutionError {
+            runtime: runtime.name().to_string(),
+            constraint: constraint.describe(),
+            found,
+        })
+    }
+
+    /// Interpreter paths to try, most preferred first, without duplicates
+    fn candidate_interpreters(runtime: Runtime, request: &ScriptExecutionRequest) -> Vec<PathBuf> {
+        let env_var = |name: &str| {
+            request
+                .environment
+                .as_ref()
+                .and_then(|env| env.get(name))
+                .map(OsString::from)
+                .or_else(|| std::env::var_os(name))
+        };
+
+        let mut candidates = Vec::new();
+        if runtime == Runtime::Python {
+            let mut dirs: Vec<&Path> = Vec::new();
+            if let Some(dir) = Path::new(&request.script_path).parent() {
+                dirs.push(dir);
+            }
+            if let Some(ref working_dir) = request.working_dir {
+                dirs.push(Path::new(working_dir));
+            }
+            let virtual_env = env_var("VIRTUAL_ENV").map(|v| v.to_string_lossy().to_string());
+            if let Some(venv_python) = find_virtualenv_python(&dirs, virtual_env.as_deref()) {
+                candidates.push(venv_python);
+            }
+        }
+
+        // PATH comes from the request environment or the process, which is the login-shell
+        // PATH after startup
+        let path_var = env_var("PATH");
+        let cwd = std::env::current_dir().unwrap_or_default();
+        for name in runtime.candidate_names() {
+            if let Ok(matches) = which::which_in_all(name, path_var.as_ref(), &cwd) {
+                candidates.extend(matches);
+            }
+        }
+
+        let mut seen = HashSet::new();
+        candidates.retain(|p| seen.insert(std::fs::canonicalize(p).unwrap_or_else(|_| p.clone())));
+        candidates
+    }
+
+    async fn probe_version(interpreter: &Path) -> Option<RuntimeVersion> {
+        let output = tokio::time::timeout(
+            VERSION_PROBE_TIMEOUT,
+            Command::new(interpreter)
+                .arg("--version")
+                .stdin(Stdio::null())
+                .output(),
+        )
+        .await
+        .ok()?
+        .ok()?;
+        // Python 2 prints its version to stderr
+        parse_version_output(&String::from_utf8_lossy(&output.stdout))
+            .or_else(|| parse_version_output(&String::from_utf8_lossy(&output.stderr)))
+    }
+
     /// Helper function to run a command and capture output
     async fn run_command(
         mut cmd: Command,
@@ -228,6 +669,8 @@ mod tests {
             working_dir: None,
             timeout_ms: None,
             environment: None,
+            runtime_constraint: None,
+            skill_dir: None,
         };
 
         let result = ScriptExecutor::execute(request).await;
@@ -253,6 +696,8 @@ mod tests {
             working_dir: None,
             timeout_ms: Some(1000), // 1 second timeout
             environment: None,
+            runtime_constraint: None,
+            skill_dir: None,
         };
 
         let result = ScriptExecutor::execute(request).await;
@@ -283,6 +728,8 @@ mod tests {
             working_dir: None,
             timeout_ms: Some(5000),
             environment: None,
+            runtime_constraint: None,
+            skill_dir: None,
         };
 
         let result = ScriptExecutor::execute(request).await;
@@ -294,6 +741,139 @@ mod tests {
         assert_eq!(exec_result.exit_code, 0);
     }
 
+    #[test]
+    fn test_parse_version_output_formats() {
+        let v = |major, minor, patch| RuntimeVersion {
+            major,
+            minor,
+            patch,
+        };
+        assert_eq!(parse_version_output("Python 3.11.4\n"), Some(v(3, 11, 4)));
+        assert_eq!(parse_version_output("Python 3.13.0rc1"), Some(v(3, 13, 0)));
+        assert_eq!(parse_version_output("v18.17.1\n"), Some(v(18, 17, 1)));
+        assert_eq!(
+            parse_version_output(
+                "GNU bash, version 5.2.15(1)-release (x86_64-pc-linux-gnu)\nCopyright (C) 2022"
+            ),
+            Some(v(5, 2, 15))
+        );
+        assert_eq!(
+            parse_version_output("GNU bash, version 3.2.57(1)-release (arm64-apple-darwin23)"),
+            Some(v(3, 2, 57))
+        );
+        assert_eq!(parse_version_output("command not found"), None);
+    }
+
+    #[test]
+    fn test_runtime_constraint_parsing_and_matching() {
+        let version = |s: &str| parse_version_output(s).unwrap();
+
+        let python = RuntimeConstraint::parse("python>=3.10").unwrap();
+        assert_eq!(python.runtime, Runtime::Python);
+        assert!(python.is_satisfied_by(&version("3.10.0")));
+        assert!(python.is_satisfied_by(&version("3.12.1")));
+        assert!(!python.is_satisfied_by(&version("3.8.10")));
+
+        let node = RuntimeConstraint::parse("node >= 18, < 23").unwrap();
+        assert_eq!(node.runtime, Runtime::Node);
+        assert!(node.is_satisfied_by(&version("v20.11.0")));
+        assert!(!node.is_satisfied_by(&version("v16.20.2")));
+        assert!(!node.is_satisfied_by(&version("v23.0.0")));
+
+        let exact = RuntimeConstraint::parse("python==3.11").unwrap();
+        assert!(exact.is_satisfied_by(&version("3.11.9")));
+        assert!(!exact.is_satisfied_by(&version("3.12.0")));
+
+        let any_bash = RuntimeConstraint::parse("bash").unwrap();
+        assert!(any_bash.is_satisfied_by(&version("3.2.57")));
+
+        assert_eq!(python.describe(), "python >= 3.10");
+        assert!(RuntimeConstraint::parse("ruby>=3").is_err());
+        assert!(RuntimeConstraint::parse("python~3.10").is_err());
+        assert!(RuntimeConstraint::parse("python>=three").is_err());
+    }
+
+    #[test]
+    fn test_find_virtualenv_python() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let project = temp_dir.path().join("project");
+        let venv_bin = project.join(".venv").join("bin");
+        std::fs::create_dir_all(&venv_bin).unwrap();
+        std::fs::write(venv_bin.join("python3"), "").unwrap();
+
+        let other = temp_dir.path().join("other");
+        std::fs::create_dir_all(&other).unwrap();
+
+        assert_eq!(
+            find_virtualenv_python(&[other.as_path(), project.as_path()], None),
+            Some(venv_bin.join("python3"))
+        );
+        assert_eq!(find_virtualenv_python(&[other.as_path()], None), None);
+        assert_eq!(
+            find_virtualenv_python(
+                &[other.as_path()],
+                Some(&project.join(".venv").to_string_lossy())
+            ),
+            Some(venv_bin.join("python3"))
+        );
+    }
+
+    #[tokio::test]
+    #[cfg(not(target_os = "windows"))]
+    async fn test_unsatisfied_constraint_reports_found_interpreters() {
+        use std::os::unix::fs::PermissionsExt;
+
+        // A fake python3 that reports an old version
+        let bin_dir = tempfile::TempDir::new().unwrap();
+        let fake_python = bin_dir.path().join("python3");
+        std::fs::write(&fake_python, "#!/bin/sh\necho 'Python 3.8.10'\n").unwrap();
+        std::fs::set_permissions(&fake_python, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let mut environment = std::collections::HashMap::new();
+        environment.insert(
+            "PATH".to_string(),
+            bin_dir.path().to_string_lossy().to_string(),
+        );
+        let request = ScriptExecutionRequest {
+            script_path: "/nonexistent/script.py".to_string(),
+            script_type: "python".to_string(),
+            args: vec![],
+            working_dir: None,
+            timeout_ms: None,
+            environment: Some(environment),
+            runtime_constraint: Some("python>=3.10".to_string()),
+            skill_dir: None,
+        };
+
+        let result = ScriptExecutor::execute(request).await.unwrap();
+        assert!(!result.success);
+        let runtime_error = result.runtime_error.unwrap();
+        assert_eq!(runtime_error.found.len(), 1);
+        assert_eq!(runtime_error.found[0].version.as_deref(), Some("3.8.10"));
+        assert_eq!(
+            result.error.unwrap(),
+            format!(
+                "no python >= 3.10 found; found 3.8.10 at {}",
+                fake_python.to_string_lossy()
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn test_constraint_for_wrong_runtime_is_rejected() {
+        let request = ScriptExecutionRequest {
+            script_path: "test.sh".to_string(),
+            script_type: "bash".to_string(),
+            args: vec![],
+            working_dir: None,
+            timeout_ms: None,
+            environment: None,
+            runtime_constraint: Some("node>=18".to_string()),
+            skill_dir: None,
+        };
+        assert!(ScriptExecutor::execute(request).await.is_err());
+    }
+
     #[tokio::test]
     async fn test_invalid_working_directory() {
         use std::io::Write;
@@ -311,6 +891,8 @@ mod tests {
             working_dir: Some("/this/path/does/not/exist".to_string()),
             timeout_ms: None,
             environment: None,
+            runtime_constraint: None,
+            skill_dir: None,
         };
 
         let result = ScriptExecutor::execute(request).await;
@@ -1,38 +1,453 @@
-use crate::constants::{is_code_extension, is_code_filename, should_exclude_dir};
+use crate::activity;
+use crate::audit;
+use crate::cancellation::{self, CancellationToken};
+use crate::constants::{is_code_extension, is_code_filename};
+use crate::exclusions::LiveExclusions;
+use crate::file_write::{self, FileWriteRequest};
+use crate::path_access;
+use crate::path_wire::{self, RawPath};
+use crate::perf_trace::{self, Phase};
+use crate::search_history;
+use crate::undo::{self, UndoJournal};
+use crate::walk_depth::{self, DepthReport, DepthTracker};
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use grep::matcher::Matcher;
 use grep::regex::{RegexMatcher, RegexMatcherBuilder};
-use grep::searcher::sinks::UTF8;
-use grep::searcher::{BinaryDetection, SearcherBuilder};
+use grep::searcher::{BinaryDetection, Searcher, SearcherBuilder, Sink, SinkMatch};
 use ignore::WalkBuilder;
 use rayon::prelude::*;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::borrow::Cow;
+use std::cmp::Reverse;
 use std::collections::HashSet;
 use std::ffi::OsStr;
-use std::path::Path;
-use std::sync::{Arc, Mutex};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::time::SystemTime;
 
 /// Maximum line length before truncation (in characters)
 const MAX_LINE_LENGTH: usize = 200;
 /// Number of characters to keep around the match when truncating
 const CONTEXT_CHARS: usize = 80;
+/// Files larger than this are skipped unless a search sets its own limit
+pub const DEFAULT_MAX_FILE_SIZE: u64 = 10 * 1024 * 1024;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
 pub struct SearchMatch {
     pub line_number: u64,
     pub line_content: String,
+    /// Position of the first match on the line, in bytes from the start of the file
     pub byte_offset: u64,
+    /// Character columns of the first match in the full line, zero-based and end
+    /// exclusive. `line_content` may be truncated, so they can point past it.
+    #[serde(default)]
+    pub column_start: u32,
+    #[serde(default)]
+    pub column_end: u32,
+    /// Character columns of every match in the line, in order
+    #[serde(default)]
+    pub match_ranges: Vec<(u32, u32)>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl SearchMatch {
+    /// A match located by the character ranges of the matches on its line
+    pub fn new(
+        line_number: u64,
+        line_content: String,
+        byte_offset: u64,
+        match_ranges: Vec<(u32, u32)>,
+    ) -> Self {
+        let (column_start, column_end) = match_ranges.first().copied().unwrap_or_default();
+        Self {
+            line_number,
+            line_content,
+            byte_offset,
+            column_start,
+            column_end,
+            match_ranges,
+        }
+    }
+}
+
+/// Byte ranges within `line` as character columns. The ranges must be in order. An
+/// offset inside a character counts as that character's start.
+pub(crate) fn char_columns(
+    line: &str,
+    byte_ranges: impl IntoIterator<Item = (usize, usize)>,
+) -> Vec<(u32, u32)> {
+    let mut chars_before = 0;
+    let mut counted_to = 0;
+    let mut columns = |byte: usize| {
+        let mut byte = byte.clamp(counted_to, line.len());
+        while !line.is_char_boundary(byte) {
+            byte -= 1;
+        }
+        chars_before += line[counted_to..byte].chars().count();
+        counted_to = byte;
+        chars_before as u32
+    };
+    byte_ranges
+        .into_iter()
+        .map(|(start, end)| (columns(start), columns(end)))
+        .collect()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct SearchResult {
     pub file_path: String,
     pub matches: Vec<SearchMatch>,
+    #[serde(flatten)]
+    pub raw_path: RawPath,
+}
+
+/// How the files of a search result are ordered
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ResultOrder {
+    /// Best ranked first by `relevance_score`, ties by path
+    #[default]
+    Relevance,
+    /// File path ascending
+    Path,
+    /// Most matches first, ties by path
+    MatchCount,
+    /// Most recently modified first, ties by path
+    Modified,
+}
+
+impl ResultOrder {
+    /// Sort `results` of a search for `query`. Every order falls back to the path, so
+    /// repeating a search gives the same order.
+    pub fn sort(self, results: &mut [SearchResult], query: &str) {
+        match self {
+            ResultOrder::Relevance => {
+                let terms = query_terms(query);
+                let now = SystemTime::now();
+                results.sort_by_cached_key(|r| {
+                    (
+                        Reverse(relevance_score(r, &terms, now)),
+                        r.file_path.clone(),
+                    )
+                });
+            }
+            ResultOrder::Path => results.sort_by(|a, b| a.file_path.cmp(&b.file_path)),
+            ResultOrder::MatchCount => results.sort_by(|a, b| {
+                b.matches
+                    .len()
+                    .cmp(&a.matches.len())
+                    .then_with(|| a.file_path.cmp(&b.file_path))
+            }),
+            ResultOrder::Modified => results.sort_by_cached_key(|r| {
+                let path = path_wire::resolve(&r.file_path);
+                (Reverse(modified_time(&path)), r.file_path.clone())
+            }),
+        }
+    }
+}
+
+/// Lowercased words of the query, for spotting them in paths. Characters after a
+/// backslash are regex escapes like `\b`, not part of a word.
+fn query_terms(query: &str) -> Vec<String> {
+    let mut text = String::with_capacity(query.len());
+    let mut chars = query.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            chars.next();
+            text.push(' ');
+        } else {
+            text.push(c);
+        }
+    }
+    text.split(|c: char| !c.is_alphanumeric() && c != '_')
+        .filter(|term| term.chars().count() >= 2)
+        .map(str::to_lowercase)
+        .collect()
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// How well a file answers a search: more matches, query terms in its path, a shallower
+/// path and a recent modification all rank it higher. Scaled to an integer so that
+/// files scoring the same fall back to path order.
+fn relevance_score(result: &SearchResult, terms: &[String], now: SystemTime) -> i64 {
+    let path = path_wire::resolve(&result.file_path);
+    let matches = (result.matches.len() as f64).ln_1p();
+    let lowered = result.file_path.to_lowercase();
+    let named = terms
+        .iter()
+        .filter(|term| lowered.contains(term.as_str()))
+        .count() as f64;
+    let depth = path.components().count() as f64;
+    let recency = modified_time(&path).map_or(0.0, |modified| {
+        let age = now.duration_since(modified).unwrap_or_default();
+        1.0 / (1.0 + age.as_secs_f64() / 86_400.0)
+    });
+    let score = 2.0 * matches + 3.0 * named - 0.5 * depth + 2.0 * recency;
+    (score * 1000.0).round() as i64
+}
+
+/// Why a content search failed, in a shape the search box can show
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum SearchError {
+    /// The query isn't a valid regex; `message` points at the problem
+    InvalidPattern {
+        message: String,
+    },
+    /// One of the include or exclude globs doesn't parse
+    InvalidGlob {
+        pattern: String,
+        message: String,
+    },
+    Failed {
+        message: String,
+    },
+}
+
+impl std::fmt::Display for SearchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SearchError::InvalidPattern { message } => {
+                write!(f, "Invalid search pattern: {}", message)
+            }
+            SearchError::InvalidGlob { pattern, message } => {
+                write!(f, "Invalid glob pattern \"{}\": {}", pattern, message)
+            }
+            SearchError::Failed { message } => write!(f, "Search failed: {}", message),
+        }
+    }
+}
+
+/// The results of a content search, and whether its walk stopped short of deep files
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ContentSearchResponse {
+    pub results: Vec<SearchResult>,
+    #[serde(flatten)]
+    pub depth: DepthReport,
+    /// The search was cancelled and `results` only covers the files searched before that
+    #[serde(default)]
+    pub cancelled: bool,
+    #[serde(default)]
+    pub stats: SearchStats,
+    /// Listed files that don't exist or can't be read
+    #[serde(default)]
+    pub skipped: Vec<String>,
+    /// Set instead of `results` by a count-only search
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub counts: Option<MatchCounts>,
+}
+
+/// How many files a content search looked at, and how many it passed over and why, so
+/// the search panel can say when results may be incomplete
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct SearchStats {
+    pub files_scanned: usize,
+    /// Larger than the search's file size limit
+    pub skipped_size: usize,
+    /// Looked like binary data; any matches found before that are dropped
+    pub skipped_binary: usize,
+}
+
+/// How a parallel search over a list of files ended
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct FilesSearched {
+    pub cancelled: bool,
+    pub stats: SearchStats,
+}
+
+/// Why a file gave no result without being searched
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Skipped {
+    TooLarge,
+    Binary,
+}
+
+/// Counters the workers of a parallel search share
+#[derive(Debug, Default)]
+struct Tally {
+    searched: AtomicU64,
+    skipped_size: AtomicUsize,
+    skipped_binary: AtomicUsize,
+    stopped: AtomicBool,
+}
+
+impl Tally {
+    /// Count a file about to be searched; returns how many have been so far
+    fn searching(&self) -> u64 {
+        self.searched.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    fn skipped(&self, reason: Skipped) {
+        let counter = match reason {
+            Skipped::TooLarge => &self.skipped_size,
+            Skipped::Binary => &self.skipped_binary,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn finish(self) -> FilesSearched {
+        let skipped_size = self.skipped_size.into_inner();
+        let skipped_binary = self.skipped_binary.into_inner();
+        FilesSearched {
+            cancelled: self.stopped.into_inner(),
+            stats: SearchStats {
+                files_scanned: self.searched.into_inner() as usize - skipped_size - skipped_binary,
+                skipped_size,
+                skipped_binary,
+            },
+        }
+    }
+}
+
+/// How many matches a file has, from a count-only search
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct FileMatchCount {
+    pub file_path: String,
+    pub count: u64,
+    #[serde(flatten)]
+    pub raw_path: RawPath,
+}
+
+/// The outcome of a count-only search: every file with matches and their total
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct MatchCounts {
+    pub files: Vec<FileMatchCount>,
+    pub total: u64,
+}
+
+/// Sent after the last result of a streamed search
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct SearchComplete {
+    pub total_files: usize,
+    pub total_matches: usize,
+    pub elapsed_ms: u64,
+    pub cancelled: bool,
+    #[serde(flatten)]
+    pub depth: DepthReport,
+    pub stats: SearchStats,
+}
+
+impl SearchComplete {
+    pub fn new(response: &ContentSearchResponse, elapsed: std::time::Duration) -> Self {
+        let (total_files, total_matches) = match response.counts {
+            Some(ref counts) => (counts.files.len(), counts.total as usize),
+            None => (
+                response.results.len(),
+                response.results.iter().map(|r| r.matches.len()).sum(),
+            ),
+        };
+        Self {
+            total_files,
+            total_matches,
+            elapsed_ms: elapsed.as_millis() as u64,
+            cancelled: response.cancelled,
+            depth: response.depth,
+            stats: response.stats,
+        }
+    }
+}
+
+/// Include and exclude globs, matched against a file's path relative to the search root
+/// (or, without one, to the walked directory). A pattern without a slash matches the
+/// file name at any depth, as in .gitignore. Excludes win over includes.
+#[derive(Debug, Clone, Default)]
+struct PathGlobs {
+    include: Option<GlobSet>,
+    exclude: Option<GlobSet>,
+}
+
+impl PathGlobs {
+    fn new(include: Option<&[String]>, exclude: Option<&[String]>) -> Result<Self, SearchError> {
+        Ok(Self {
+            include: include.map(Self::build).transpose()?,
+            exclude: exclude.map(Self::build).transpose()?,
+        })
+    }
+
+    fn build(patterns: &[String]) -> Result<GlobSet, SearchError> {
+        let mut builder = GlobSetBuilder::new();
+        for pattern in patterns {
+            let source = if pattern.contains('/') {
+                pattern.clone()
+            } else {
+                format!("**/{}", pattern)
+            };
+            let glob = GlobBuilder::new(&source)
+                .literal_separator(true)
+                .build()
+                .map_err(|e| SearchError::InvalidGlob {
+                    pattern: pattern.clone(),
+                    message: e.kind().to_string(),
+                })?;
+            builder.add(glob);
+        }
+        builder.build().map_err(|e| SearchError::Failed {
+            message: e.to_string(),
+        })
+    }
+
+    /// Whether `path` passes, matched relative to `root` when it lies inside it and
+    /// relative to `fallback` otherwise
+    fn allows_under(&self, path: &Path, root: &Path, fallback: &Path) -> bool {
+        let relative = path
+            .strip_prefix(root)
+            .or_else(|_| path.strip_prefix(fallback))
+            .unwrap_or(path);
+        self.allows(relative)
+    }
+
+    fn allows(&self, relative: &Path) -> bool {
+        if self
+            .exclude
+            .as_ref()
+            .is_some_and(|set| set.is_match(relative))
+        {
+            return false;
+        }
+        self.include
+            .as_ref()
+            .map_or(true, |set| set.is_match(relative))
+    }
 }
 
+#[derive(Debug, Clone, PartialEq)]
 pub struct RipgrepSearch {
     max_results: usize,
     max_matches_per_file: usize,
     file_types: Option<HashSet<String>>,
     exclude_dirs: Option<HashSet<String>>,
+    exclude_paths: Option<Vec<PathBuf>>,
+    /// Levels below a walked directory to descend; the configured default when unset
+    max_depth: Option<usize>,
+    order: ResultOrder,
+    /// Match the query as plain text instead of a regex
+    literal: bool,
+    case_sensitive: bool,
+    /// Only match where the query starts and ends on word boundaries
+    whole_word: bool,
+    /// Skip files ignored by .gitignore, the global gitignore and .git/info/exclude
+    respect_gitignore: bool,
+    /// Also walk dotfiles and dot directories; .git stays excluded with the other
+    /// excluded directories
+    include_hidden: bool,
+    /// Descend into symlinked directories, each real file once. Symlinked files are
+    /// searched either way, and a link back to an ancestor is never followed.
+    follow_symlinks: bool,
+    /// Files larger than this many bytes are skipped
+    max_file_size: u64,
+    /// Globs over paths relative to the search root; see `PathGlobs`
+    include_globs: Option<Vec<String>>,
+    exclude_globs: Option<Vec<String>>,
+    /// Activity to report progress to; a cancellable activity can stop the search
+    activity_id: Option<String>,
+    /// Id the caller registered in the cancellation registry for `cancel_search`
+    search_id: Option<String>,
 }
 
 impl Default for RipgrepSearch {
@@ -42,6 +457,20 @@ impl Default for RipgrepSearch {
             max_matches_per_file: 10,
             file_types: None,
             exclude_dirs: None,
+            exclude_paths: None,
+            max_depth: None,
+            order: ResultOrder::default(),
+            literal: false,
+            case_sensitive: false,
+            whole_word: false,
+            respect_gitignore: false,
+            include_hidden: false,
+            follow_symlinks: false,
+            max_file_size: DEFAULT_MAX_FILE_SIZE,
+            include_globs: None,
+            exclude_globs: None,
+            activity_id: None,
+            search_id: None,
         }
     }
 }
@@ -72,8 +501,92 @@ impl RipgrepSearch {
         self
     }
 
+    /// Subtrees (absolute paths) to prune during the walk
+    pub fn with_exclude_paths(mut self, exclude_paths: Option<Vec<String>>) -> Self {
+        self.exclude_paths =
+            exclude_paths.map(|paths| paths.iter().map(|path| path_wire::resolve(path)).collect());
+        self
+    }
+
+    pub fn with_max_depth(mut self, max_depth: Option<usize>) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    pub fn with_order(mut self, order: ResultOrder) -> Self {
+        self.order = order;
+        self
+    }
+
+    pub fn with_literal(mut self, literal: bool) -> Self {
+        self.literal = literal;
+        self
+    }
+
+    pub fn with_case_sensitive(mut self, case_sensitive: bool) -> Self {
+        self.case_sensitive = case_sensitive;
+        self
+    }
+
+    pub fn with_whole_word(mut self, whole_word: bool) -> Self {
+        self.whole_word = whole_word;
+        self
+    }
+
+    pub fn with_respect_gitignore(mut self, respect_gitignore: bool) -> Self {
+        self.respect_gitignore = respect_gitignore;
+        self
+    }
+
+    pub fn with_include_hidden(mut self, include_hidden: bool) -> Self {
+        self.include_hidden = include_hidden;
+        self
+    }
+
+    pub fn with_follow_symlinks(mut self, follow_symlinks: bool) -> Self {
+        self.follow_symlinks = follow_symlinks;
+        self
+    }
+
+    pub fn with_max_file_size(mut self, max_file_size: u64) -> Self {
+        self.max_file_size = max_file_size;
+        self
+    }
+
+    /// Only search files matching one of these globs
+    pub fn with_include_globs(mut self, include_globs: Option<Vec<String>>) -> Self {
+        self.include_globs = include_globs;
+        self
+    }
+
+    /// Skip walked files matching one of these globs, even if an include glob matches
+    pub fn with_exclude_globs(mut self, exclude_globs: Option<Vec<String>>) -> Self {
+        self.exclude_globs = exclude_globs;
+        self
+    }
+
+    pub fn with_activity(mut self, activity_id: Option<String>) -> Self {
+        self.activity_id = activity_id;
+        self
+    }
+
+    pub fn with_search_id(mut self, search_id: Option<String>) -> Self {
+        self.search_id = search_id;
+        self
+    }
+
+    /// Tokens that stop this search: its activity's and its search id's
+    fn cancellation_tokens(&self) -> Vec<CancellationToken> {
+        let activity = self
+            .activity_id
+            .as_deref()
+            .and_then(activity::cancellation_token);
+        let search = self.search_id.as_deref().and_then(cancellation::token);
+        activity.into_iter().chain(search).collect()
+    }
+
     #[inline]
-    fn is_valid_file(&self, path: &Path) -> bool {
+    pub(crate) fn is_valid_file(&self, path: &Path) -> bool {
         // If file_types is specified, use it for filtering
         if let Some(ref file_types) = self.file_types {
             if let Some(ext) = path.extension().and_then(OsStr::to_str) {
@@ -172,30 +685,173 @@ impl RipgrepSearch {
         query: &str,
         root_path: &str,
     ) -> Result<Vec<SearchResult>, String> {
+        self.search_content_with_report(query, root_path)
+            .map(|response| response.results)
+            .map_err(|e| e.to_string())
+    }
+
+    /// Like `search_content`, also reporting whether the walk hit its depth limit
+    pub fn search_content_with_report(
+        &self,
+        query: &str,
+        root_path: &str,
+    ) -> Result<ContentSearchResponse, SearchError> {
+        if query.is_empty() {
+            return Ok(ContentSearchResponse::default());
+        }
+        let _span = perf_trace::span("search_content", Phase::Total, None);
+
+        let globs = self.path_globs()?;
+        let depth = self.depth_tracker();
+        let files = {
+            let _span = perf_trace::span("search_content", Phase::Walk, Some(root_path));
+            let root = path_wire::resolve(root_path);
+            self.collect_files(&root, &root, &depth, &globs)
+        };
+        let (results, searched) = self.search_files(query, &files)?;
+        Ok(ContentSearchResponse {
+            results,
+            depth: depth.report(),
+            cancelled: searched.cancelled,
+            stats: searched.stats,
+            ..Default::default()
+        })
+    }
+
+    /// Search an explicit list of files and directories.
+    /// Directories are walked with the usual rules, files are searched directly
+    /// even if they wouldn't pass the file type filter. Files reachable through
+    /// several entries are only searched once.
+    pub fn search_content_in_paths(
+        &self,
+        query: &str,
+        include_paths: &[String],
+    ) -> Result<Vec<SearchResult>, String> {
+        self.search_content_in_paths_with_report(query, include_paths)
+            .map(|response| response.results)
+            .map_err(|e| e.to_string())
+    }
+
+    /// Like `search_content_in_paths`, also reporting whether a walk hit its depth limit
+    pub fn search_content_in_paths_with_report(
+        &self,
+        query: &str,
+        include_paths: &[String],
+    ) -> Result<ContentSearchResponse, SearchError> {
         if query.is_empty() {
-            return Ok(vec![]);
+            return Ok(ContentSearchResponse::default());
         }
 
-        // Create regex matcher once with proper builder pattern
-        let matcher = Arc::new(
-            RegexMatcherBuilder::new()
-                .case_insensitive(true)
-                .line_terminator(Some(b'\n'))
-                .build(query)
-                .map_err(|e| format!("Failed to create regex matcher: {}", e))?,
-        );
+        let globs = self.path_globs()?;
+        let depth = self.depth_tracker();
+        let files = self.collect_included_files(include_paths, None, &depth, &globs);
+        let (results, searched) = self.search_files(query, &files)?;
+        Ok(ContentSearchResponse {
+            results,
+            depth: depth.report(),
+            cancelled: searched.cancelled,
+            stats: searched.stats,
+            ..Default::default()
+        })
+    }
+
+    /// Files to search for an explicit list of files and directories, each once. Globs
+    /// match relative to `glob_root` when given, else to each listed directory (or, for a
+    /// listed file, its parent).
+    fn collect_included_files(
+        &self,
+        include_paths: &[String],
+        glob_root: Option<&Path>,
+        depth: &DepthTracker,
+        globs: &PathGlobs,
+    ) -> Vec<PathBuf> {
+        let mut seen: HashSet<PathBuf> = HashSet::new();
+        let mut files: Vec<PathBuf> = Vec::new();
+
+        for include in include_paths {
+            let path = &path_wire::resolve(include);
+            if path.is_file() {
+                let parent = path.parent().unwrap_or(path);
+                if !self.is_excluded_path(path)
+                    && globs.allows_under(path, glob_root.unwrap_or(parent), parent)
+                    && seen.insert(Self::dedup_key(path))
+                {
+                    files.push(path.to_path_buf());
+                }
+            } else if path.is_dir() {
+                for file in self.collect_files(path, glob_root.unwrap_or(path), depth, globs) {
+                    if seen.insert(Self::dedup_key(&file)) {
+                        files.push(file);
+                    }
+                }
+            }
+        }
+        files
+    }
+
+    /// Exactly the listed files, each once, for a search that doesn't walk. Paths that
+    /// aren't files or can't be opened are returned separately.
+    fn listed_files(include_files: &[String]) -> (Vec<PathBuf>, Vec<String>) {
+        let mut seen: HashSet<PathBuf> = HashSet::new();
+        let mut files = Vec::new();
+        let mut skipped = Vec::new();
+        for include in include_files {
+            let path = path_wire::resolve(include);
+            if !path.is_file() || std::fs::File::open(&path).is_err() {
+                skipped.push(include.clone());
+            } else if seen.insert(Self::dedup_key(&path)) {
+                files.push(path);
+            }
+        }
+        (files, skipped)
+    }
+
+    /// Compiled include and exclude globs, so bad syntax fails before any walk
+    fn path_globs(&self) -> Result<PathGlobs, SearchError> {
+        PathGlobs::new(self.include_globs.as_deref(), self.exclude_globs.as_deref())
+    }
+
+    fn depth_tracker(&self) -> DepthTracker {
+        DepthTracker::new(walk_depth::resolve(self.max_depth))
+    }
 
+    /// Canonical form of a path used to detect files listed more than once
+    fn dedup_key(path: &Path) -> PathBuf {
+        path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+    }
+
+    #[inline]
+    fn is_excluded_path(&self, path: &Path) -> bool {
+        self.exclude_paths
+            .as_ref()
+            .map(|paths| paths.iter().any(|excluded| path.starts_with(excluded)))
+            .unwrap_or(false)
+    }
+
+    /// Walk a directory and collect all files that pass the search filters, matching globs
+    /// relative to `glob_root`. Directories left unvisited at the depth limit are counted
+    /// in `depth`.
+    fn collect_files(
+        &self,
+        root: &Path,
+        glob_root: &Path,
+        depth: &DepthTracker,
+        globs: &PathGlobs,
+    ) -> Vec<PathBuf> {
         // Build walker with proper gitignore support and optimizations
-        let mut walker_builder = WalkBuilder::new(root_path);
+        let mut walker_builder = WalkBuilder::new(root);
 
         walker_builder
-            .hidden(true) // Skip hidden files by default
-            .git_ignore(false) // Don't use .gitignore files (search all files)
-            .git_global(false) // Don't use global gitignore
-            .git_exclude(false) // Don't use .git/info/exclude
+            .hidden(!self.include_hidden)
+            // The walker reports a link back to an ancestor as an error instead of looping
+            .follow_links(self.follow_symlinks)
+            // Gitignored files are searched unless asked otherwise
+            .git_ignore(self.respect_gitignore)
+            .git_global(self.respect_gitignore)
+            .git_exclude(self.respect_gitignore)
             .ignore(true) // Use .ignore files
             .parents(true) // Search parent directories for ignore files
-            .max_depth(Some(20)); // Reasonable depth limit
+            .max_depth(Some(depth.max_depth()));
 
         // Add custom exclude directories as overrides if specified
         if let Some(ref exclude_dirs) = self.exclude_dirs {
@@ -206,10 +862,25 @@ impl RipgrepSearch {
         }
 
         let exclude_dirs_clone = self.exclude_dirs.clone();
+        let exclude_paths_clone = self.exclude_paths.clone();
+        let exclusions = LiveExclusions::new();
+        let globs = globs.clone();
+        let walk_root = root.to_path_buf();
+        let glob_root = glob_root.to_path_buf();
         let walker = walker_builder
             .filter_entry(move |entry| {
                 let path = entry.path();
 
+                // Prune explicitly excluded subtrees
+                if let Some(ref exclude_paths) = exclude_paths_clone {
+                    if exclude_paths
+                        .iter()
+                        .any(|excluded| path.starts_with(excluded))
+                    {
+                        return false;
+                    }
+                }
+
                 // Quick directory filtering
                 if path.is_dir() {
                     let dir_name = path.file_name().and_then(OsStr::to_str).unwrap_or("");
@@ -221,61 +892,213 @@ impl RipgrepSearch {
                         }
                     }
 
-                    // Check default excluded directories
-                    return !should_exclude_dir(dir_name);
+                    // Check default and user excluded directories, reloaded mid-walk
+                    return !exclusions.excludes_dir(dir_name);
                 }
 
-                true
+                globs.allows_under(path, &glob_root, &walk_root)
             })
             .build();
 
-        // Collect files in parallel batches
-        let files: Vec<_> = walker
+        // Followed links can reach the same file under several paths
+        let mut seen: HashSet<PathBuf> = HashSet::new();
+        walker
             .filter_map(|entry| entry.ok())
             .filter(|entry| {
                 let path = entry.path();
-                path.is_file() && self.is_valid_file(path)
+                let is_dir = entry.file_type().is_some_and(|t| t.is_dir());
+                depth.observe(entry.depth(), path, is_dir);
+                path.is_file()
+                    && self.is_valid_file(path)
+                    && (!self.follow_symlinks || seen.insert(Self::dedup_key(path)))
             })
-            .collect();
+            .map(|entry| entry.into_path())
+            .collect()
+    }
 
-        // Shared state for results
-        let results = Arc::new(Mutex::new(Vec::new()));
-        let total_results = Arc::new(Mutex::new(0usize));
-        let max_results = self.max_results;
-        let max_matches_per_file = self.max_matches_per_file;
-
-        // Process files in parallel
-        files.par_iter().for_each(|entry| {
-            // Early termination check
-            {
-                let count = total_results.lock().unwrap();
-                if *count >= max_results {
-                    return;
-                }
-            }
+    pub(crate) fn max_results(&self) -> usize {
+        self.max_results
+    }
+
+    pub(crate) fn max_matches_per_file(&self) -> usize {
+        self.max_matches_per_file
+    }
+
+    /// Case-insensitive regex line matcher, the default for content searches
+    pub(crate) fn build_matcher(query: &str) -> Result<RegexMatcher, String> {
+        Self::build_matcher_with(query, false, false, false)
+            .map_err(|e| format!("Failed to create regex matcher: {}", e))
+    }
+
+    /// Line matcher for `query`, escaped first when `literal` and then wrapped in word
+    /// boundaries when `whole_word`
+    fn build_matcher_with(
+        query: &str,
+        literal: bool,
+        case_sensitive: bool,
+        whole_word: bool,
+    ) -> Result<RegexMatcher, grep::regex::Error> {
+        RegexMatcherBuilder::new()
+            .case_insensitive(!case_sensitive)
+            .line_terminator(Some(b'\n'))
+            .build(&Self::pattern_source(query, literal, whole_word))
+    }
+
+    /// Literal text only gets a boundary on a side that ends in a word character, so
+    /// `foo()` as a whole word still matches `foo();`
+    fn pattern_source(query: &str, literal: bool, whole_word: bool) -> String {
+        if !literal {
+            return if whole_word {
+                format!(r"\b(?:{})\b", query)
+            } else {
+                query.to_string()
+            };
+        }
+        let escaped = regex::escape(query);
+        if !whole_word {
+            return escaped;
+        }
+        let is_word = |c: char| c.is_alphanumeric() || c == '_';
+        let before = if query.starts_with(is_word) {
+            r"\b"
+        } else {
+            ""
+        };
+        let after = if query.ends_with(is_word) { r"\b" } else { "" };
+        format!("{}{}{}", before, escaped, after)
+    }
 
-            let path = entry.path();
-            let matcher_clone = Arc::clone(&matcher);
+    /// The matcher this search's literal, case and whole word options give for `query`
+    pub(crate) fn matcher(&self, query: &str) -> Result<RegexMatcher, SearchError> {
+        Self::build_matcher_with(query, self.literal, self.case_sensitive, self.whole_word).map_err(
+            |e| SearchError::InvalidPattern {
+                message: e.to_string(),
+            },
+        )
+    }
+
+    /// Search the given files in parallel. Once `max_results` files have matched the
+    /// rest are skipped, so which files make the cut depends on completion order, but the
+    /// returned results are always sorted by `order`. Cancellation is checked per file;
+    /// a cancelled search returns what it found so far.
+    fn search_files(
+        &self,
+        query: &str,
+        files: &[PathBuf],
+    ) -> Result<(Vec<SearchResult>, FilesSearched), SearchError> {
+        let mut results = Vec::new();
+        let searched = self.search_files_into(query, files, |result| results.push(result))?;
+        self.order.sort(&mut results, query);
+        Ok((results, searched))
+    }
 
-            match self.search_in_file_fast(&*matcher_clone, path, max_matches_per_file, query) {
-                Ok(Some(result)) => {
-                    if !result.matches.is_empty() {
-                        let mut results_guard = results.lock().unwrap();
-                        let mut count_guard = total_results.lock().unwrap();
+    /// Search the given files in parallel, handing each matching file to `on_result` as
+    /// soon as it is found. Workers send results over a channel that a separate thread
+    /// drains into `on_result`, so a slow consumer never holds up the search.
+    pub(crate) fn search_files_into(
+        &self,
+        query: &str,
+        files: &[PathBuf],
+        on_result: impl FnMut(SearchResult) + Send,
+    ) -> Result<FilesSearched, SearchError> {
+        let matcher = self.matcher(query)?;
+        let total_results = AtomicUsize::new(0);
+        let cancellation = self.cancellation_tokens();
+        let tally = Tally::default();
+        let total_files = files.len() as u64;
+        let (sender, receiver) = mpsc::channel();
+
+        std::thread::scope(|scope| {
+            scope.spawn(move || receiver.into_iter().for_each(on_result));
+
+            // Each worker's sender is dropped when the loop ends, which ends the drain
+            files.par_iter().for_each_with(sender, |sender, path| {
+                // Early termination check
+                if total_results.load(Ordering::Relaxed) >= self.max_results {
+                    return;
+                }
+                if cancellation.iter().any(CancellationToken::is_cancelled) {
+                    tally.stopped.store(true, Ordering::Relaxed);
+                    return;
+                }
+                let done = tally.searching();
+                if let Some(ref activity_id) = self.activity_id {
+                    activity::activity_progress(activity_id, done, Some(total_files), None);
+                }
 
-                        if *count_guard < max_results {
-                            results_guard.push(result);
-                            *count_guard += 1;
+                let _span = perf_trace::span("search_content", Phase::Query, path.to_str());
+                match self.search_in_file_fast(&matcher, path, self.max_matches_per_file, query) {
+                    Ok(Some(result)) => {
+                        if !result.matches.is_empty()
+                            && total_results.fetch_add(1, Ordering::Relaxed) < self.max_results
+                        {
+                            // Only fails if the drain panicked, which the scope re-raises
+                            let _ = sender.send(result);
                         }
                     }
+                    Ok(None) => {} // No matches, or unreadable
+                    Err(reason) => tally.skipped(reason),
                 }
-                Ok(None) => {} // No matches
-                Err(_) => {}   // Skip errors silently for performance
-            }
+            });
         });
 
-        let final_results = results.lock().unwrap().clone();
-        Ok(final_results)
+        Ok(tally.finish())
+    }
+
+    /// Count the matches in each file in parallel, without building match lines. Every
+    /// file and every match on a line is counted, so unlike a search the totals are exact.
+    /// Files without matches are left out and the rest are sorted by path.
+    pub(crate) fn count_files(
+        &self,
+        query: &str,
+        files: &[PathBuf],
+    ) -> Result<(MatchCounts, FilesSearched), SearchError> {
+        let matcher = self.matcher(query)?;
+        let cancellation = self.cancellation_tokens();
+        let tally = Tally::default();
+        let total_files = files.len() as u64;
+
+        let mut counts: Vec<FileMatchCount> = files
+            .par_iter()
+            .filter_map(|path| {
+                if cancellation.iter().any(CancellationToken::is_cancelled) {
+                    tally.stopped.store(true, Ordering::Relaxed);
+                    return None;
+                }
+                let done = tally.searching();
+                if let Some(ref activity_id) = self.activity_id {
+                    activity::activity_progress(activity_id, done, Some(total_files), None);
+                }
+                let count = self
+                    .check_file_size(path)
+                    .and_then(|()| Self::count_in_file(&matcher, path))
+                    .map_err(|reason| tally.skipped(reason))
+                    .ok()?;
+                (count > 0).then(|| FileMatchCount {
+                    file_path: path_wire::encode(path),
+                    count,
+                    raw_path: RawPath::of(path),
+                })
+            })
+            .collect();
+        counts.sort_by(|a, b| a.file_path.cmp(&b.file_path));
+
+        let total = counts.iter().map(|c| c.count).sum();
+        Ok((
+            MatchCounts {
+                files: counts,
+                total,
+            },
+            tally.finish(),
+        ))
+    }
+
+    /// Checked up front so a huge file is never opened
+    fn check_file_size(&self, file_path: &Path) -> Result<(), Skipped> {
+        if std::fs::metadata(file_path).is_ok_and(|m| m.len() > self.max_file_size) {
+            return Err(Skipped::TooLarge);
+        }
+        Ok(())
     }
 
     fn search_in_file_fast(
@@ -284,66 +1107,684 @@ impl RipgrepSearch {
         file_path: &Path,
         max_matches: usize,
         query: &str,
-    ) -> Result<Option<SearchResult>, String> {
-        let mut matches = Vec::with_capacity(max_matches.min(10)); // Pre-allocate reasonable capacity
+    ) -> Result<Option<SearchResult>, Skipped> {
+        self.check_file_size(file_path)?;
+        Self::collect_matches(matcher, file_path, None, max_matches, query)
+    }
+
+    /// Matches in a file; unreadable files count none
+    fn count_in_file(matcher: &RegexMatcher, file_path: &Path) -> Result<u64, Skipped> {
+        let mut sink = CountSink {
+            matcher,
+            count: 0,
+            binary: false,
+        };
+        let _ = Self::file_searcher().search_path(matcher, file_path, &mut sink);
+        if sink.binary {
+            return Err(Skipped::Binary);
+        }
+        Ok(sink.count)
+    }
 
-        // Create searcher with optimized settings
-        let mut searcher = SearcherBuilder::new()
+    /// A searcher that stops at the first NUL byte, treating the file as binary
+    fn file_searcher() -> Searcher {
+        SearcherBuilder::new()
             .binary_detection(BinaryDetection::quit(b'\x00'))
             .line_number(true)
-            .build();
+            .build()
+    }
 
-        let mut match_count = 0;
+    /// Search file contents that were already read, e.g. when several queries share one read
+    pub(crate) fn search_in_bytes(
+        matcher: &RegexMatcher,
+        file_path: &Path,
+        bytes: &[u8],
+        max_matches: usize,
+        query: &str,
+    ) -> Option<SearchResult> {
+        Self::collect_matches(matcher, file_path, Some(bytes), max_matches, query)
+            .ok()
+            .flatten()
+    }
 
-        let result = searcher.search_path(
-            matcher,
-            file_path,
-            UTF8(|lnum, line| {
-                if match_count >= max_matches {
-                    return Ok(false); // Early termination
-                }
+    /// Search `bytes` when given, otherwise read the file
+    fn collect_matches(
+        matcher: &RegexMatcher,
+        file_path: &Path,
+        bytes: Option<&[u8]>,
+        max_matches: usize,
+        query: &str,
+    ) -> Result<Option<SearchResult>, Skipped> {
+        let mut matches = Vec::with_capacity(max_matches.min(10)); // Pre-allocate reasonable capacity
 
-                matches.push(SearchMatch {
-                    line_number: lnum,
-                    line_content: Self::truncate_line_with_context(line, query),
-                    byte_offset: 0,
-                });
+        let mut searcher = Self::file_searcher();
 
-                match_count += 1;
-                Ok(true)
-            }),
-        );
+        let mut sink = MatchSink {
+            matcher,
+            query,
+            max_matches,
+            matches: &mut matches,
+            binary: false,
+        };
 
-        match result {
-            Ok(_) => {
-                if matches.is_empty() {
-                    Ok(None)
-                } else {
-                    Ok(Some(SearchResult {
-                        file_path: file_path.to_string_lossy().to_string(),
-                        matches,
-                    }))
-                }
-            }
-            Err(_) => Ok(None), // Return None instead of error for better performance
+        // Errors yield no result instead of failing the search, for better performance
+        let result = match bytes {
+            Some(bytes) => searcher.search_slice(matcher, bytes, &mut sink),
+            None => searcher.search_path(matcher, file_path, &mut sink),
+        };
+        if sink.binary {
+            return Err(Skipped::Binary);
         }
+        Ok(match result {
+            Ok(_) if !matches.is_empty() => Some(SearchResult {
+                file_path: path_wire::encode(file_path),
+                matches,
+                raw_path: RawPath::of(file_path),
+            }),
+            _ => None,
+        })
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::fs;
-    use tempfile::TempDir;
-
-    fn create_test_search_directory() -> TempDir {
-        let temp_dir = TempDir::new().unwrap();
+/// Collects matching lines with the absolute offset of their first match
+struct MatchSink<'a> {
+    matcher: &'a RegexMatcher,
+    query: &'a str,
+    max_matches: usize,
+    matches: &'a mut Vec<SearchMatch>,
+    /// A NUL byte was found and the search stopped there
+    binary: bool,
+}
 
-        // Create directory structure
-        fs::create_dir_all(temp_dir.path().join("src")).unwrap();
-        fs::create_dir_all(temp_dir.path().join("tests")).unwrap();
+impl Sink for MatchSink<'_> {
+    type Error = std::io::Error;
 
-        // Create files with searchable content
+    fn matched(&mut self, _searcher: &Searcher, mat: &SinkMatch<'_>) -> Result<bool, Self::Error> {
+        if self.matches.len() >= self.max_matches {
+            return Ok(false); // Early termination
+        }
+        let line = std::str::from_utf8(mat.bytes())
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        let mut byte_ranges = Vec::new();
+        // The searcher already found a match on this line, so this can't fail. A pattern
+        // like `x*` also matches the empty string at every byte, even inside a character.
+        let _ = self.matcher.find_iter(mat.bytes(), |m| {
+            if m.start() < m.end() {
+                byte_ranges.push((m.start(), m.end()));
+            }
+            true
+        });
+        let first_start = byte_ranges.first().map_or(0, |&(start, _)| start);
+
+        self.matches.push(SearchMatch::new(
+            mat.line_number().unwrap_or(0),
+            RipgrepSearch::truncate_line_with_context(line, self.query),
+            mat.absolute_byte_offset() + first_start as u64,
+            char_columns(line, byte_ranges),
+        ));
+        Ok(true)
+    }
+
+    fn binary_data(
+        &mut self,
+        _searcher: &Searcher,
+        _binary_byte_offset: u64,
+    ) -> Result<bool, Self::Error> {
+        self.binary = true;
+        Ok(false)
+    }
+}
+
+/// Counts the matches on each matching line without keeping the lines
+struct CountSink<'a> {
+    matcher: &'a RegexMatcher,
+    count: u64,
+    binary: bool,
+}
+
+impl Sink for CountSink<'_> {
+    type Error = std::io::Error;
+
+    fn matched(&mut self, _searcher: &Searcher, mat: &SinkMatch<'_>) -> Result<bool, Self::Error> {
+        let mut on_line = 0;
+        // The searcher already found a match on this line, so this can't fail
+        let _ = self.matcher.find_iter(mat.bytes(), |m| {
+            if m.start() < m.end() {
+                on_line += 1;
+            }
+            true
+        });
+        // A multi-line or empty match still counts once
+        self.count += on_line.max(1);
+        Ok(true)
+    }
+
+    fn binary_data(
+        &mut self,
+        _searcher: &Searcher,
+        _binary_byte_offset: u64,
+    ) -> Result<bool, Self::Error> {
+        self.binary = true;
+        Ok(false)
+    }
+}
+
+/// The arguments of a project content search, as the search panel sends them
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContentSearchParams {
+    pub query: String,
+    pub root_path: Option<String>,
+    pub file_types: Option<Vec<String>>,
+    pub exclude_dirs: Option<Vec<String>>,
+    pub include_paths: Option<Vec<String>>,
+    pub exclude_paths: Option<Vec<String>>,
+    /// Levels below each walked directory; the configured default when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_depth: Option<usize>,
+    /// File order of the results: relevance, path, match-count or modified; by relevance
+    /// when unset
+    #[serde(default, alias = "order", skip_serializing_if = "Option::is_none")]
+    pub sort_by: Option<ResultOrder>,
+    /// Treat the query as a regex; true when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub is_regex: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub case_sensitive: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub whole_word: Option<bool>,
+    /// Skip gitignored files; false when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub respect_gitignore: Option<bool>,
+    /// Skip files larger than this many bytes; 10 MB when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_file_size: Option<u64>,
+    /// Search exactly these files without walking; wins over the include paths and root
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub include_files: Option<Vec<String>>,
+    /// Only search walked files matching one of these globs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub include_globs: Option<Vec<String>>,
+    /// Skip walked files matching one of these globs; wins over the include globs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exclude_globs: Option<Vec<String>>,
+    /// Only count the matches per file; see `RipgrepSearch::count_files`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub count_only: Option<bool>,
+    /// Search dotfiles and dot directories too; false when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub include_hidden: Option<bool>,
+    /// Descend into symlinked directories; false when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub follow_symlinks: Option<bool>,
+}
+
+fn search_error(e: SearchError) -> SearchError {
+    log::error!("Search error: {}", e);
+    e
+}
+
+fn ensure_readable(path: &str) -> Result<(), SearchError> {
+    path_access::ensure_readable(&path_wire::resolve(path))
+        .map_err(|message| SearchError::Failed { message })
+}
+
+fn missing_search_root() -> SearchError {
+    search_error(SearchError::Failed {
+        message: "Either root_path or include_paths must be provided".to_string(),
+    })
+}
+
+impl ContentSearchParams {
+    /// The searcher these arguments configure
+    pub fn searcher(&self) -> RipgrepSearch {
+        RipgrepSearch::new()
+            .with_max_results(50)
+            .with_max_matches_per_file(10)
+            .with_file_types(self.file_types.clone())
+            .with_exclude_dirs(self.exclude_dirs.clone())
+            .with_exclude_paths(self.exclude_paths.clone())
+            .with_max_depth(self.max_depth)
+            .with_order(self.sort_by.unwrap_or_default())
+            .with_literal(!self.is_regex.unwrap_or(true))
+            .with_case_sensitive(self.case_sensitive.unwrap_or(false))
+            .with_whole_word(self.whole_word.unwrap_or(false))
+            .with_respect_gitignore(self.respect_gitignore.unwrap_or(false))
+            .with_include_hidden(self.include_hidden.unwrap_or(false))
+            .with_follow_symlinks(self.follow_symlinks.unwrap_or(false))
+            .with_max_file_size(self.max_file_size.unwrap_or(DEFAULT_MAX_FILE_SIZE))
+            .with_include_globs(self.include_globs.clone())
+            .with_exclude_globs(self.exclude_globs.clone())
+    }
+
+    /// Search the include paths when given, otherwise the root
+    pub fn run(&self, searcher: &RipgrepSearch) -> Result<ContentSearchResponse, SearchError> {
+        self.stream(searcher, |_| {})
+    }
+
+    /// Like `run`, also handing each matching file to `on_result` as soon as it is found,
+    /// in completion order. The response holds the same results sorted by `order`.
+    pub fn stream(
+        &self,
+        searcher: &RipgrepSearch,
+        mut on_result: impl FnMut(&SearchResult) + Send,
+    ) -> Result<ContentSearchResponse, SearchError> {
+        if self.query.is_empty() {
+            return Ok(ContentSearchResponse::default());
+        }
+        let _span = perf_trace::span("search_content", Phase::Total, None);
+        let depth = searcher.depth_tracker();
+        let (files, skipped) = self.files(searcher, &depth)?;
+
+        if self.count_only.unwrap_or(false) {
+            let (counts, searched) = searcher
+                .count_files(&self.query, &files)
+                .map_err(search_error)?;
+            return Ok(ContentSearchResponse {
+                depth: depth.report(),
+                cancelled: searched.cancelled,
+                stats: searched.stats,
+                skipped,
+                counts: Some(counts),
+                ..Default::default()
+            });
+        }
+
+        let mut results = Vec::new();
+        let searched = searcher
+            .search_files_into(&self.query, &files, |result| {
+                on_result(&result);
+                results.push(result);
+            })
+            .map_err(search_error)?;
+        searcher.order.sort(&mut results, &self.query);
+        Ok(ContentSearchResponse {
+            results,
+            depth: depth.report(),
+            cancelled: searched.cancelled,
+            stats: searched.stats,
+            skipped,
+            counts: None,
+        })
+    }
+
+    /// The files to search: the listed files when given, else the include paths, else
+    /// the root's. Also returns the listed files that can't be searched.
+    fn files(
+        &self,
+        searcher: &RipgrepSearch,
+        depth: &DepthTracker,
+    ) -> Result<(Vec<PathBuf>, Vec<String>), SearchError> {
+        if let Some(ref include_files) = self.include_files {
+            return Ok(RipgrepSearch::listed_files(include_files));
+        }
+        let globs = searcher.path_globs().map_err(search_error)?;
+        let files = match (&self.include_paths, &self.root_path) {
+            (Some(paths), _) if !paths.is_empty() => {
+                paths.iter().try_for_each(|path| ensure_readable(path))?;
+                let root = self.root_path.as_deref().map(path_wire::resolve);
+                searcher.collect_included_files(paths, root.as_deref(), depth, &globs)
+            }
+            (_, Some(root)) => {
+                ensure_readable(root)?;
+                let _span = perf_trace::span("search_content", Phase::Walk, Some(root));
+                let root = path_wire::resolve(root);
+                searcher.collect_files(&root, &root, depth, &globs)
+            }
+            _ => return Err(missing_search_root()),
+        };
+        Ok((files, Vec::new()))
+    }
+
+    /// Replace every match of the search with `replacement`, or only preview the changes
+    /// when `dry_run`
+    pub fn replace(
+        &self,
+        searcher: &RipgrepSearch,
+        undo: Option<&UndoJournal>,
+        replacement: &str,
+        dry_run: bool,
+    ) -> Result<ReplaceResponse, SearchError> {
+        if self.query.is_empty() {
+            return Ok(ReplaceResponse {
+                dry_run,
+                ..Default::default()
+            });
+        }
+        let depth = searcher.depth_tracker();
+        let (files, skipped) = self.files(searcher, &depth)?;
+        let mut response = searcher
+            .replace_in_files(&self.query, replacement, &files, undo, dry_run)
+            .map_err(search_error)?;
+        response.skipped.extend(skipped);
+        Ok(response)
+    }
+}
+
+// ============================================================================
+// Replace
+// ============================================================================
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplaceLinePreview {
+    pub line_number: u64,
+    pub old_line: String,
+    pub new_line: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplaceFileResult {
+    pub path: String,
+    pub replacements: usize,
+    /// Changed lines, in a dry run only
+    pub lines: Vec<ReplaceLinePreview>,
+}
+
+/// A file with matches that could not be rewritten
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplaceFailure {
+    pub path: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplaceResponse {
+    pub dry_run: bool,
+    /// Files changed, or that would change in a dry run
+    pub files_changed: usize,
+    pub replacements: usize,
+    /// By path
+    pub files: Vec<ReplaceFileResult>,
+    pub failures: Vec<ReplaceFailure>,
+    /// Files larger than the size limit, left unchanged, and listed files that can't be
+    /// read
+    #[serde(default)]
+    pub skipped: Vec<String>,
+    /// Undo journal operation that reverts the written files
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub undo_operation_id: Option<String>,
+}
+
+/// Rewrites lines with the search's pattern. Regex replacements expand `$1` and `${name}`;
+/// literal ones are inserted as they are.
+struct Replacer {
+    regex: regex::Regex,
+    replacement: String,
+    literal: bool,
+}
+
+impl Replacer {
+    fn new(search: &RipgrepSearch, query: &str, replacement: &str) -> Result<Self, SearchError> {
+        let source = RipgrepSearch::pattern_source(query, search.literal, search.whole_word);
+        let regex = regex::RegexBuilder::new(&source)
+            .case_insensitive(!search.case_sensitive)
+            .build()
+            .map_err(|e| SearchError::InvalidPattern {
+                message: e.to_string(),
+            })?;
+        Ok(Self {
+            regex,
+            replacement: replacement.to_string(),
+            literal: search.literal,
+        })
+    }
+
+    /// The line with every match replaced, and the number of matches
+    fn replace_line<'a>(&self, line: &'a str) -> (Cow<'a, str>, usize) {
+        let count = self.regex.find_iter(line).count();
+        if count == 0 {
+            return (Cow::Borrowed(line), 0);
+        }
+        let replaced = if self.literal {
+            self.regex
+                .replace_all(line, regex::NoExpand(&self.replacement))
+        } else {
+            self.regex.replace_all(line, self.replacement.as_str())
+        };
+        (replaced, count)
+    }
+
+    /// The file with every match replaced, keeping the changed lines when `preview`.
+    /// None when the file has no matches or isn't text.
+    fn rewrite(&self, path: &Path, preview: bool) -> Option<Rewrite> {
+        let bytes = std::fs::read(path).ok()?;
+        if bytes.contains(&0) {
+            return None;
+        }
+        let content = std::str::from_utf8(&bytes).ok()?;
+
+        let mut new_content = String::with_capacity(content.len());
+        let mut replacements = 0;
+        let mut lines = Vec::new();
+        for (index, line) in content.split_inclusive('\n').enumerate() {
+            let text = line.trim_end_matches(['\n', '\r']);
+            let (new_text, count) = self.replace_line(text);
+            if count > 0 {
+                replacements += count;
+                if preview {
+                    lines.push(ReplaceLinePreview {
+                        line_number: index as u64 + 1,
+                        old_line: text.to_string(),
+                        new_line: new_text.to_string(),
+                    });
+                }
+            }
+            new_content.push_str(&new_text);
+            new_content.push_str(&line[text.len()..]);
+        }
+        if replacements == 0 {
+            return None;
+        }
+        Some(Rewrite {
+            prior_hash: hex::encode(Sha256::digest(&bytes)),
+            content: new_content,
+            result: ReplaceFileResult {
+                path: path_wire::encode(path),
+                replacements,
+                lines,
+            },
+        })
+    }
+}
+
+/// A file's replaced content, not yet written
+struct Rewrite {
+    /// SHA-256 of the content the replace was computed from
+    prior_hash: String,
+    content: String,
+    result: ReplaceFileResult,
+}
+
+impl Rewrite {
+    fn write(self) -> Result<ReplaceFileResult, ReplaceFailure> {
+        let request = FileWriteRequest {
+            path: self.result.path.clone(),
+            content: Some(self.content),
+            base64: None,
+            make_executable: None,
+            // Refuse to overwrite edits made since the file was read
+            expected_prior_hash: Some(self.prior_hash),
+        };
+        match file_write::write_atomic(&request) {
+            Ok(_) => Ok(self.result),
+            Err(e) => Err(ReplaceFailure {
+                path: self.result.path,
+                message: e.to_string(),
+            }),
+        }
+    }
+}
+
+impl RipgrepSearch {
+    /// Replace every match in `files`, which the search's filters already selected. Each
+    /// file is written atomically on its own; one that fails is reported and the others
+    /// still change. The files written are recorded in `undo` as one operation. Files over
+    /// the size limit are skipped, as in a search.
+    fn replace_in_files(
+        &self,
+        query: &str,
+        replacement: &str,
+        files: &[PathBuf],
+        undo: Option<&UndoJournal>,
+        dry_run: bool,
+    ) -> Result<ReplaceResponse, SearchError> {
+        let replacer = Replacer::new(self, query, replacement)?;
+        let (files, oversized): (Vec<&PathBuf>, Vec<&PathBuf>) = files
+            .iter()
+            .partition(|path| self.check_file_size(path).is_ok());
+        let rewrites: Vec<Rewrite> = files
+            .par_iter()
+            .filter_map(|path| replacer.rewrite(path, dry_run))
+            .collect();
+
+        let mut undo_operation_id = None;
+        let outcomes: Vec<Result<ReplaceFileResult, ReplaceFailure>> = if dry_run {
+            rewrites
+                .into_iter()
+                .map(|rewrite| Ok(rewrite.result))
+                .collect()
+        } else {
+            // Only the files about to change are captured
+            let paths: Vec<PathBuf> = rewrites
+                .iter()
+                .map(|rewrite| file_write::write_target(&rewrite.result.path))
+                .collect();
+            let pending = undo.and_then(|undo| undo.track("Replace in files", &paths));
+            let outcomes: Vec<_> = rewrites.into_par_iter().map(Rewrite::write).collect();
+            undo_operation_id =
+                pending.and_then(|pending| pending.complete(outcomes.iter().any(Result::is_ok)));
+            outcomes
+        };
+
+        let mut response = ReplaceResponse {
+            dry_run,
+            undo_operation_id,
+            skipped: oversized
+                .iter()
+                .map(|path| path_wire::encode(path))
+                .collect(),
+            ..Default::default()
+        };
+        for outcome in outcomes {
+            match outcome {
+                Ok(file) => {
+                    response.files_changed += 1;
+                    response.replacements += file.replacements;
+                    response.files.push(file);
+                }
+                Err(failure) => response.failures.push(failure),
+            }
+        }
+        response.files.sort_by(|a, b| a.path.cmp(&b.path));
+        response.failures.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(response)
+    }
+}
+
+/// Replace a project search's matches with `replacement`, taking the same filters as
+/// `search_file_content`. A dry run returns the changed lines per file and writes nothing.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn search_and_replace(
+    query: String,
+    replacement: String,
+    root_path: Option<String>,
+    file_types: Option<Vec<String>>,
+    exclude_dirs: Option<Vec<String>>,
+    include_paths: Option<Vec<String>>,
+    exclude_paths: Option<Vec<String>>,
+    max_depth: Option<usize>,
+    is_regex: Option<bool>,
+    case_sensitive: Option<bool>,
+    whole_word: Option<bool>,
+    respect_gitignore: Option<bool>,
+    max_file_size: Option<u64>,
+    include_files: Option<Vec<String>>,
+    include_globs: Option<Vec<String>>,
+    exclude_globs: Option<Vec<String>>,
+    include_hidden: Option<bool>,
+    follow_symlinks: Option<bool>,
+    dry_run: bool,
+) -> Result<ReplaceResponse, SearchError> {
+    let params = ContentSearchParams {
+        query,
+        root_path,
+        file_types,
+        exclude_dirs,
+        include_paths,
+        exclude_paths,
+        max_depth,
+        sort_by: None,
+        is_regex,
+        case_sensitive,
+        whole_word,
+        respect_gitignore,
+        max_file_size,
+        include_files,
+        include_globs,
+        exclude_globs,
+        include_hidden,
+        follow_symlinks,
+        count_only: None,
+    };
+    let recording = audit::begin(
+        "search_and_replace",
+        || json!({ "params": params, "replacement": replacement, "dryRun": dry_run }),
+    );
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        let result = params.replace(&params.searcher(), undo::journal(), &replacement, dry_run);
+        if let Ok(ref response) = result {
+            search_history::record_replace(&params, &replacement, response);
+        }
+        result
+    })
+    .await
+    .map_err(|e| SearchError::Failed {
+        message: format!("Replace task failed: {}", e),
+    })
+    .and_then(|result| result);
+    if let Ok(ref response) = result {
+        log::info!(
+            "Replace{} changed {} occurrences in {} files ({} failed)",
+            if dry_run { " (dry run)" } else { "" },
+            response.replacements,
+            response.files_changed,
+            response.failures.len()
+        );
+    }
+    audit::finish(recording, &result);
+    result
+}
+
+/// Stop the content search started with `search_id`; it returns the results found so far.
+/// Async so it is not queued behind the search itself.
+#[tauri::command]
+pub async fn cancel_search(search_id: String) -> Result<bool, String> {
+    let cancelled = cancellation::cancel(&search_id);
+    if cancelled {
+        log::info!("Cancellation requested for search {}", search_id);
+    }
+    Ok(cancelled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn create_test_search_directory() -> TempDir {
+        let temp_dir = TempDir::new().unwrap();
+
+        // Create directory structure
+        fs::create_dir_all(temp_dir.path().join("src")).unwrap();
+        fs::create_dir_all(temp_dir.path().join("tests")).unwrap();
+
+        // Create files with searchable content
         fs::write(
             temp_dir.path().join("src/main.rs"),
             "fn main() {\n    println!(\"Hello, world!\");\n}\n",
@@ -470,6 +1911,668 @@ mod tests {
         assert!(!results_upper.is_empty());
     }
 
+    #[test]
+    fn test_literal_and_case_sensitive_modes() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join("ops.rs"),
+            "let n = count++;\nfoo(bar);\nfooxbar;\nFOO(BAR);\n",
+        )
+        .unwrap();
+        let root = temp_dir.path().to_str().unwrap();
+        let lines = |search: RipgrepSearch, query: &str| -> Vec<u64> {
+            search
+                .search_content(query, root)
+                .unwrap()
+                .iter()
+                .flat_map(|r| r.matches.iter().map(|m| m.line_number))
+                .collect()
+        };
+
+        // As a regex `foo(bar)` only matches "foobar", which isn't there
+        assert!(lines(RipgrepSearch::new(), "foo(bar)").is_empty());
+        let literal = || RipgrepSearch::new().with_literal(true);
+        assert_eq!(lines(literal(), "foo(bar)"), vec![2, 4]);
+        assert_eq!(lines(literal(), "count++"), vec![1]);
+        assert_eq!(
+            lines(literal().with_case_sensitive(true), "foo(bar)"),
+            vec![2]
+        );
+        assert_eq!(
+            lines(RipgrepSearch::new().with_case_sensitive(true), "FOO"),
+            vec![4]
+        );
+    }
+
+    #[test]
+    fn test_whole_word_mode() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join("a.rs"),
+            "let foobar = 1;\nfoo.bar();\ncall(foo);\nfoo_bar;\n",
+        )
+        .unwrap();
+        fs::write(temp_dir.path().join("b.py"), "foo = 2\n").unwrap();
+        let root = temp_dir.path().to_str().unwrap();
+        let lines = |search: RipgrepSearch, query: &str| -> Vec<(String, u64)> {
+            let mut found: Vec<(String, u64)> = search
+                .with_whole_word(true)
+                .search_content(query, root)
+                .unwrap()
+                .iter()
+                .flat_map(|r| {
+                    let name = Path::new(&r.file_path).file_name().unwrap();
+                    let name = name.to_string_lossy().to_string();
+                    r.matches.iter().map(move |m| (name.clone(), m.line_number))
+                })
+                .collect();
+            found.sort();
+            found
+        };
+        let at = |file: &str, lines: &[u64]| -> Vec<(String, u64)> {
+            lines.iter().map(|line| (file.to_string(), *line)).collect()
+        };
+
+        // Not foobar or foo_bar, but foo.bar and (foo)
+        assert_eq!(lines(RipgrepSearch::new(), "foo"), {
+            let mut expected = at("a.rs", &[2, 3]);
+            expected.extend(at("b.py", &[1]));
+            expected
+        });
+        // Regex alternations are bounded as a whole
+        assert_eq!(
+            lines(RipgrepSearch::new(), "foo|bar"),
+            lines(RipgrepSearch::new(), "foo")
+        );
+        // Literal text is escaped before the boundaries go around it
+        assert_eq!(
+            lines(RipgrepSearch::new().with_literal(true), "foo.bar"),
+            at("a.rs", &[2])
+        );
+        assert_eq!(lines(RipgrepSearch::new(), "foo.bar"), at("a.rs", &[2, 4]));
+        // File type filters still apply
+        assert_eq!(
+            lines(
+                RipgrepSearch::new().with_file_types(Some(vec!["rs".to_string()])),
+                "foo"
+            ),
+            at("a.rs", &[2, 3])
+        );
+    }
+
+    #[test]
+    fn test_invalid_regex_is_a_structured_error() {
+        let temp_dir = create_test_search_directory();
+        let params = ContentSearchParams {
+            query: "count++".to_string(),
+            root_path: Some(temp_dir.path().to_str().unwrap().to_string()),
+            ..Default::default()
+        };
+        let err = params.run(&params.searcher()).unwrap_err();
+        let SearchError::InvalidPattern { ref message } = err else {
+            panic!("expected an invalid pattern error, got {:?}", err);
+        };
+        assert!(message.contains("repetition"), "{}", message);
+        let json = serde_json::to_value(&err).unwrap();
+        assert_eq!(json["kind"], "invalidPattern");
+        assert_eq!(json["message"], message.as_str());
+
+        // The same query is fine as literal text
+        let params = ContentSearchParams {
+            is_regex: Some(false),
+            ..params
+        };
+        assert!(params.run(&params.searcher()).unwrap().results.is_empty());
+    }
+
+    #[test]
+    fn test_include_and_exclude_globs() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        for file in ["src/a.ts", "src/nested/a.test.ts", "docs/b.ts"] {
+            fs::create_dir_all(root.join(file).parent().unwrap()).unwrap();
+            fs::write(root.join(file), "const needle = 1;\n").unwrap();
+        }
+        let files = |include: &[&str], exclude: &[&str]| {
+            let globs = |patterns: &[&str]| {
+                (!patterns.is_empty()).then(|| patterns.iter().map(|p| p.to_string()).collect())
+            };
+            let params = ContentSearchParams {
+                query: "needle".to_string(),
+                root_path: Some(root.to_str().unwrap().to_string()),
+                include_globs: globs(include),
+                exclude_globs: globs(exclude),
+                ..Default::default()
+            };
+            params.run(&params.searcher()).map(|response| {
+                response
+                    .results
+                    .iter()
+                    .map(|r| {
+                        let path = Path::new(&r.file_path).strip_prefix(root).unwrap();
+                        path.to_str().unwrap().replace('\\', "/")
+                    })
+                    .collect::<Vec<_>>()
+            })
+        };
+
+        assert_eq!(
+            files(&["src/**"], &[]).unwrap(),
+            vec!["src/a.ts", "src/nested/a.test.ts"]
+        );
+        // A pattern without a slash matches file names at any depth, and excludes win
+        assert_eq!(
+            files(&["src/**"], &["*.test.ts"]).unwrap(),
+            vec!["src/a.ts"]
+        );
+        assert_eq!(
+            files(&[], &["**/*.test.ts"]).unwrap(),
+            vec!["docs/b.ts", "src/a.ts"]
+        );
+        // `*` stops at a separator
+        assert_eq!(files(&["src/*.ts"], &[]).unwrap(), vec!["src/a.ts"]);
+
+        let err = files(&["src/[a"], &[]).unwrap_err();
+        assert!(
+            matches!(err, SearchError::InvalidGlob { ref pattern, .. } if pattern == "src/[a"),
+            "{:?}",
+            err
+        );
+        assert_eq!(serde_json::to_value(&err).unwrap()["kind"], "invalidGlob");
+    }
+
+    #[test]
+    fn test_globs_match_relative_to_the_root_for_include_paths() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        for file in ["src/a.rs", "src/nested/b.rs", "src/c.ts", "docs/d.rs"] {
+            fs::create_dir_all(root.join(file).parent().unwrap()).unwrap();
+            fs::write(root.join(file), "let needle = 1;\n").unwrap();
+        }
+        let files = |include_paths: &[&str], include: &str| {
+            let params = ContentSearchParams {
+                query: "needle".to_string(),
+                root_path: Some(root.to_str().unwrap().to_string()),
+                include_paths: Some(
+                    include_paths
+                        .iter()
+                        .map(|p| root.join(p).to_str().unwrap().to_string())
+                        .collect(),
+                ),
+                include_globs: Some(vec![include.to_string()]),
+                ..Default::default()
+            };
+            let mut files: Vec<String> = params
+                .run(&params.searcher())
+                .unwrap()
+                .results
+                .iter()
+                .map(|r| {
+                    let path = Path::new(&r.file_path).strip_prefix(root).unwrap();
+                    path.to_str().unwrap().replace('\\', "/")
+                })
+                .collect();
+            files.sort();
+            files
+        };
+
+        assert_eq!(
+            files(&["src"], "src/**/*.rs"),
+            vec!["src/a.rs", "src/nested/b.rs"]
+        );
+        // Listed files are checked against the globs too
+        assert_eq!(
+            files(&["src/a.rs", "docs/d.rs"], "src/**"),
+            vec!["src/a.rs"]
+        );
+        assert!(files(&["src/c.ts"], "*.rs").is_empty());
+    }
+
+    #[test]
+    fn test_include_files_searches_only_the_listed_files() {
+        let temp_dir = create_test_search_directory();
+        let root = temp_dir.path();
+        let listed = |name: &str| root.join(name).to_str().unwrap().to_string();
+        let params = ContentSearchParams {
+            query: "println".to_string(),
+            root_path: Some(root.to_str().unwrap().to_string()),
+            include_files: Some(vec![
+                listed("src/lib.rs"),
+                listed("src/missing.rs"),
+                listed("src"),
+                listed("src/lib.rs"),
+            ]),
+            ..Default::default()
+        };
+        let searcher = params.searcher().with_max_matches_per_file(1);
+        let response = params.run(&searcher).unwrap();
+
+        assert_eq!(response.results.len(), 1);
+        assert!(response.results[0].file_path.ends_with("lib.rs"));
+        assert_eq!(response.results[0].matches.len(), 1);
+        assert_eq!(
+            response.skipped,
+            vec![listed("src/missing.rs"), listed("src")]
+        );
+    }
+
+    #[test]
+    fn test_count_only_counts_every_match() {
+        let temp_dir = create_test_search_directory();
+        fs::write(
+            temp_dir.path().join("src/many.rs"),
+            "println!(); println!();\nprintln!();\n",
+        )
+        .unwrap();
+        let params = ContentSearchParams {
+            count_only: Some(true),
+            ..search_params(temp_dir.path(), "println")
+        };
+        let searcher = params.searcher().with_max_matches_per_file(1);
+        let response = params.run(&searcher).unwrap();
+
+        assert!(response.results.is_empty());
+        let counts = response.counts.unwrap();
+        let per_file: Vec<_> = counts
+            .files
+            .iter()
+            .map(|c| {
+                let name = Path::new(&c.file_path).file_name().unwrap();
+                (name.to_str().unwrap().to_string(), c.count)
+            })
+            .collect();
+        assert_eq!(
+            per_file,
+            vec![
+                ("lib.rs".to_string(), 2),
+                ("main.rs".to_string(), 1),
+                ("many.rs".to_string(), 3),
+            ]
+        );
+        assert_eq!(counts.total, 6);
+
+        let summary = SearchComplete::new(
+            &ContentSearchResponse {
+                counts: Some(counts),
+                ..Default::default()
+            },
+            std::time::Duration::ZERO,
+        );
+        assert_eq!((summary.total_files, summary.total_matches), (3, 6));
+    }
+
+    #[test]
+    fn test_oversized_and_binary_files_are_skipped_and_counted() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::write(root.join("small.rs"), "let needle = 1;\n").unwrap();
+        fs::write(root.join("large.rs"), "let needle = 2;\n".repeat(100)).unwrap();
+        fs::write(root.join("blob.rs"), b"needle\n\x00\x01\x02").unwrap();
+        fs::write(root.join("other.rs"), "fn unrelated() {}\n").unwrap();
+
+        let response = RipgrepSearch::new()
+            .with_max_file_size(1024)
+            .search_content_with_report("needle", root.to_str().unwrap())
+            .unwrap();
+        let files: Vec<_> = response
+            .results
+            .iter()
+            .map(|r| Path::new(&r.file_path).file_name().unwrap().to_owned())
+            .collect();
+        assert_eq!(files, vec![OsStr::new("small.rs")]);
+        assert_eq!(
+            response.stats,
+            SearchStats {
+                files_scanned: 2,
+                skipped_size: 1,
+                skipped_binary: 1,
+            }
+        );
+
+        // The default limit is far above the large file
+        let response = RipgrepSearch::new()
+            .search_content_with_report("needle", root.to_str().unwrap())
+            .unwrap();
+        assert_eq!(response.results.len(), 2);
+        assert_eq!(response.stats.skipped_size, 0);
+    }
+
+    #[test]
+    fn test_include_hidden_searches_dot_directories_but_not_git() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        for (file, body) in [
+            (".github/workflows/ci.yml", "run: cargo test # needle\n"),
+            (".git/needle.yml", "needle: true\n"),
+            ("src/app.js", "const needle = 1;\n"),
+        ] {
+            let path = root.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, body).unwrap();
+        }
+        let files = |include_hidden: bool| {
+            let params = ContentSearchParams {
+                include_hidden: Some(include_hidden),
+                ..search_params(root, "needle")
+            };
+            let mut paths: Vec<_> = params
+                .run(&params.searcher())
+                .unwrap()
+                .results
+                .into_iter()
+                .map(|r| {
+                    Path::new(&r.file_path)
+                        .strip_prefix(root)
+                        .unwrap()
+                        .to_path_buf()
+                })
+                .collect();
+            paths.sort();
+            paths
+        };
+
+        assert_eq!(files(false), vec![PathBuf::from("src/app.js")]);
+        assert_eq!(
+            files(true),
+            vec![
+                PathBuf::from(".github/workflows/ci.yml"),
+                PathBuf::from("src/app.js"),
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_follow_symlinks_walks_linked_directories_without_looping() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("project");
+        let outside = temp_dir.path().join("outside");
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::create_dir_all(outside.join("pkg")).unwrap();
+        fs::write(root.join("src/main.rs"), "fn needle() {}\n").unwrap();
+        fs::write(outside.join("pkg/lib.rs"), "fn needle() {}\n").unwrap();
+        fs::write(outside.join("single.rs"), "fn needle() {}\n").unwrap();
+        std::os::unix::fs::symlink(&root, root.join("src/loop")).unwrap();
+        std::os::unix::fs::symlink(root.join("src"), root.join("src-link")).unwrap();
+        std::os::unix::fs::symlink(outside.join("pkg"), root.join("pkg")).unwrap();
+        std::os::unix::fs::symlink(outside.join("single.rs"), root.join("single.rs")).unwrap();
+        let names = |search: RipgrepSearch| {
+            let mut names: Vec<String> = search
+                .search_content("needle", root.to_str().unwrap())
+                .unwrap()
+                .iter()
+                .map(|r| {
+                    let name = Path::new(&r.file_path).file_name().unwrap();
+                    name.to_str().unwrap().to_string()
+                })
+                .collect();
+            names.sort();
+            names
+        };
+
+        // Linked files are searched, linked directories are not entered
+        assert_eq!(names(RipgrepSearch::new()), ["main.rs", "single.rs"]);
+        // src/loop is skipped and src-link/main.rs is the same file as src/main.rs
+        assert_eq!(
+            names(RipgrepSearch::new().with_follow_symlinks(true)),
+            ["lib.rs", "main.rs", "single.rs"]
+        );
+    }
+
+    #[test]
+    fn test_respect_gitignore_skips_ignored_directories() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        // Git ignore rules only apply inside a repository
+        fs::create_dir_all(root.join(".git")).unwrap();
+        fs::write(root.join(".gitignore"), "generated/\n").unwrap();
+        for dir in ["src", "generated", "legacy"] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+            fs::write(root.join(dir).join("app.js"), "const needle = 1;\n").unwrap();
+        }
+        let files = |search: RipgrepSearch| {
+            let mut paths: Vec<_> = search
+                .search_content("needle", root.to_str().unwrap())
+                .unwrap()
+                .into_iter()
+                .map(|r| {
+                    Path::new(&r.file_path)
+                        .strip_prefix(root)
+                        .unwrap()
+                        .to_path_buf()
+                })
+                .collect();
+            paths.sort();
+            paths
+        };
+
+        assert_eq!(
+            files(RipgrepSearch::new()),
+            vec![
+                PathBuf::from("generated/app.js"),
+                PathBuf::from("legacy/app.js"),
+                PathBuf::from("src/app.js"),
+            ]
+        );
+        assert_eq!(
+            files(RipgrepSearch::new().with_respect_gitignore(true)),
+            vec![PathBuf::from("legacy/app.js"), PathBuf::from("src/app.js")]
+        );
+        assert_eq!(
+            files(
+                RipgrepSearch::new()
+                    .with_respect_gitignore(true)
+                    .with_exclude_dirs(Some(vec!["legacy".to_string()]))
+            ),
+            vec![PathBuf::from("src/app.js")]
+        );
+    }
+
+    #[test]
+    fn test_stream_hands_out_each_result() {
+        let temp_dir = create_test_search_directory();
+        let params = search_params(temp_dir.path(), "println");
+        let mut streamed = Vec::new();
+        let response = params
+            .stream(&params.searcher(), |result| {
+                streamed.push(result.file_path.clone())
+            })
+            .unwrap();
+
+        streamed.sort();
+        let returned: Vec<_> = response
+            .results
+            .iter()
+            .map(|r| r.file_path.clone())
+            .collect();
+        assert_eq!(streamed, returned);
+        assert_eq!(returned.len(), 2);
+
+        let summary = SearchComplete::new(&response, std::time::Duration::from_millis(5));
+        assert_eq!(summary.total_files, 2);
+        assert_eq!(summary.total_matches, 3);
+        assert!(!summary.cancelled);
+        let json = serde_json::to_value(&summary).unwrap();
+        assert_eq!(json["elapsed_ms"], 5);
+        assert_eq!(json["depth_limited"], false);
+    }
+
+    fn search_params(root: &Path, query: &str) -> ContentSearchParams {
+        ContentSearchParams {
+            query: query.to_string(),
+            root_path: Some(root.to_str().unwrap().to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_replace_skips_files_over_the_size_limit() {
+        let temp_dir = TempDir::new().unwrap();
+        let small = temp_dir.path().join("small.rs");
+        let large = temp_dir.path().join("large.rs");
+        fs::write(&small, "old_name();\n").unwrap();
+        let large_content = format!("old_name();\n{}\n", "x".repeat(200));
+        fs::write(&large, &large_content).unwrap();
+
+        let params = ContentSearchParams {
+            max_file_size: Some(100),
+            ..search_params(temp_dir.path(), "old_name")
+        };
+        let response = params
+            .replace(&params.searcher(), None, "new_name", false)
+            .unwrap();
+        assert_eq!(response.files_changed, 1);
+        assert_eq!(response.skipped, vec![path_wire::encode(&large)]);
+        assert_eq!(fs::read_to_string(&small).unwrap(), "new_name();\n");
+        assert_eq!(fs::read_to_string(&large).unwrap(), large_content);
+    }
+
+    #[test]
+    fn test_replace_dry_run_previews_without_writing() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("main.rs");
+        let content = "let a = old_name(1);\r\nkeep();\nold_name(old_name(2));\n";
+        fs::write(&file, content).unwrap();
+
+        let params = search_params(temp_dir.path(), "old_name");
+        let response = params
+            .replace(&params.searcher(), None, "new_name", true)
+            .unwrap();
+        assert!(response.dry_run);
+        assert_eq!(response.files_changed, 1);
+        assert_eq!(response.replacements, 3);
+        assert_eq!(
+            response.files[0].lines,
+            vec![
+                ReplaceLinePreview {
+                    line_number: 1,
+                    old_line: "let a = old_name(1);".to_string(),
+                    new_line: "let a = new_name(1);".to_string(),
+                },
+                ReplaceLinePreview {
+                    line_number: 3,
+                    old_line: "old_name(old_name(2));".to_string(),
+                    new_line: "new_name(new_name(2));".to_string(),
+                },
+            ]
+        );
+        assert_eq!(fs::read_to_string(&file).unwrap(), content);
+    }
+
+    #[test]
+    fn test_replace_rewrites_files_with_capture_groups() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join("a.rs"),
+            "fn get_user() {}\r\nfn get_item() {}\n",
+        )
+        .unwrap();
+        fs::write(temp_dir.path().join("b.rs"), "get_user();\n").unwrap();
+        fs::write(temp_dir.path().join("c.rs"), "unrelated\n").unwrap();
+
+        let params = search_params(temp_dir.path(), r"get_(\w+)");
+        let response = params
+            .replace(&params.searcher(), None, "fetch_${1}", false)
+            .unwrap();
+        assert!(!response.dry_run);
+        assert_eq!(response.files_changed, 2);
+        assert_eq!(response.replacements, 3);
+        assert!(response.files.iter().all(|file| file.lines.is_empty()));
+        assert!(response.failures.is_empty());
+        // Line endings are kept
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join("a.rs")).unwrap(),
+            "fn fetch_user() {}\r\nfn fetch_item() {}\n"
+        );
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join("b.rs")).unwrap(),
+            "fetch_user();\n"
+        );
+
+        // Literal mode escapes the query and inserts `$1` as it is
+        let params = ContentSearchParams {
+            is_regex: Some(false),
+            whole_word: Some(true),
+            ..search_params(temp_dir.path(), "fetch_user()")
+        };
+        let response = params
+            .replace(&params.searcher(), None, "$1", false)
+            .unwrap();
+        assert_eq!(response.replacements, 2);
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join("b.rs")).unwrap(),
+            "$1;\n"
+        );
+    }
+
+    #[test]
+    fn test_replace_is_undoable() {
+        let temp_dir = TempDir::new().unwrap();
+        let journal_dir = TempDir::new().unwrap();
+        let journal = UndoJournal::new(journal_dir.path().to_path_buf());
+        fs::write(temp_dir.path().join("a.rs"), "old_name();\n").unwrap();
+        fs::write(temp_dir.path().join("b.rs"), "old_name(old_name);\n").unwrap();
+        fs::write(temp_dir.path().join("c.rs"), "unrelated\n").unwrap();
+
+        let params = search_params(temp_dir.path(), "old_name");
+        let preview = params
+            .replace(&params.searcher(), Some(&journal), "new_name", true)
+            .unwrap();
+        assert_eq!(preview.undo_operation_id, None);
+        assert!(journal.recent(1).is_empty());
+
+        let response = params
+            .replace(&params.searcher(), Some(&journal), "new_name", false)
+            .unwrap();
+        let id = response.undo_operation_id.unwrap();
+        let recent = journal.recent(1);
+        assert_eq!(recent[0].label, "Replace in files");
+        // Only the changed files are captured
+        assert_eq!(recent[0].files.len(), 2);
+
+        journal.revert(&id).unwrap();
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join("a.rs")).unwrap(),
+            "old_name();\n"
+        );
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join("b.rs")).unwrap(),
+            "old_name(old_name);\n"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_replace_reports_files_that_fail_to_write() {
+        use std::os::unix::fs::PermissionsExt;
+        let temp_dir = TempDir::new().unwrap();
+        let locked = temp_dir.path().join("locked");
+        fs::create_dir(&locked).unwrap();
+        fs::write(locked.join("a.rs"), "needle\n").unwrap();
+        fs::write(temp_dir.path().join("b.rs"), "needle\n").unwrap();
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o555)).unwrap();
+        if fs::write(locked.join("probe"), "").is_ok() {
+            // Running as root: permissions don't stop the write
+            return;
+        }
+
+        let params = search_params(temp_dir.path(), "needle");
+        let response = params
+            .replace(&params.searcher(), None, "pin", false)
+            .unwrap();
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o755)).unwrap();
+
+        assert_eq!(response.files_changed, 1);
+        assert!(response.files[0].path.ends_with("b.rs"));
+        assert_eq!(response.failures.len(), 1);
+        assert!(response.failures[0].path.ends_with("a.rs"));
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join("b.rs")).unwrap(),
+            "pin\n"
+        );
+        assert_eq!(fs::read_to_string(locked.join("a.rs")).unwrap(), "needle\n");
+    }
+
     #[test]
     fn test_search_result_structure() {
         let temp_dir = create_test_search_directory();
@@ -499,6 +2602,249 @@ mod tests {
         assert!(results.len() <= 1);
     }
 
+    fn result_summary(results: &[SearchResult]) -> Vec<(String, Vec<(u64, u64)>)> {
+        results
+            .iter()
+            .map(|r| {
+                let matches = r.matches.iter().map(|m| (m.line_number, m.byte_offset));
+                (r.file_path.clone(), matches.collect())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_result_order_is_deterministic() {
+        let temp_dir = TempDir::new().unwrap();
+        for i in 0..40 {
+            let body = "fn needle() {}\n".repeat(i % 4 + 1);
+            fs::write(temp_dir.path().join(format!("f{:02}.rs", i)), body).unwrap();
+        }
+        let root = temp_dir.path().to_str().unwrap();
+
+        let search = RipgrepSearch::new().with_order(ResultOrder::Path);
+        let first = search.search_content("needle", root).unwrap();
+        assert_eq!(first.len(), 40);
+        let paths: Vec<&String> = first.iter().map(|r| &r.file_path).collect();
+        let mut sorted = paths.clone();
+        sorted.sort();
+        assert_eq!(paths, sorted);
+        for _ in 0..5 {
+            let again = search.search_content("needle", root).unwrap();
+            assert_eq!(result_summary(&again), result_summary(&first));
+        }
+
+        let by_count = RipgrepSearch::new()
+            .with_order(ResultOrder::MatchCount)
+            .search_content("needle", root)
+            .unwrap();
+        let counts: Vec<usize> = by_count.iter().map(|r| r.matches.len()).collect();
+        assert!(counts.windows(2).all(|w| w[0] >= w[1]), "{:?}", counts);
+        // Ties stay in path order
+        assert!(by_count[0].file_path.ends_with("f03.rs"));
+        assert!(by_count[1].file_path.ends_with("f07.rs"));
+    }
+
+    #[test]
+    fn test_relevance_and_modified_orders() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let now = SystemTime::now();
+        let write = |name: &str, body: &str, age_days: u64| {
+            let path = root.join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, body).unwrap();
+            let modified = now - std::time::Duration::from_secs(age_days * 86_400);
+            fs::File::options()
+                .write(true)
+                .open(&path)
+                .unwrap()
+                .set_modified(modified)
+                .unwrap();
+        };
+        write("third_party/pkg/src/inner/deep.rs", "needle\nneedle\n", 400);
+        write("src/plain.rs", "needle\n", 30);
+        write("src/recent.rs", "needle\n", 0);
+        write("src/needle.rs", "needle\n", 30);
+        let names = |order: ResultOrder| -> Vec<String> {
+            let results = RipgrepSearch::new()
+                .with_order(order)
+                .search_content(r"\bneedle\b", root.to_str().unwrap())
+                .unwrap();
+            let names = results.iter().map(|r| {
+                let name = Path::new(&r.file_path).file_name().unwrap();
+                name.to_str().unwrap().to_string()
+            });
+            names.collect()
+        };
+
+        // The query in the path beats recency, which beats more matches deeper down
+        let ranked = names(ResultOrder::Relevance);
+        assert_eq!(ranked, ["needle.rs", "recent.rs", "plain.rs", "deep.rs"]);
+        for _ in 0..3 {
+            assert_eq!(names(ResultOrder::default()), ranked);
+        }
+        // plain.rs and needle.rs share a modification time and fall back to path order
+        assert_eq!(
+            names(ResultOrder::Modified),
+            ["recent.rs", "needle.rs", "plain.rs", "deep.rs"]
+        );
+        assert_eq!(query_terms(r"\bfoo_bar\(x\)|Baz"), ["foo_bar", "baz"]);
+    }
+
+    #[test]
+    fn test_sort_by_param_orders_results() {
+        let temp_dir = TempDir::new().unwrap();
+        // needle.rs ranks first by relevance, a.rs first by path
+        fs::write(temp_dir.path().join("a.rs"), "needle\n").unwrap();
+        fs::write(temp_dir.path().join("needle.rs"), "needle\nneedle\n").unwrap();
+        let names = |sort_by: &str| -> Vec<String> {
+            let params: ContentSearchParams = serde_json::from_value(serde_json::json!({
+                "query": "needle",
+                "rootPath": temp_dir.path().to_str().unwrap(),
+                "sortBy": sort_by,
+            }))
+            .unwrap();
+            params
+                .run(&params.searcher())
+                .unwrap()
+                .results
+                .iter()
+                .map(|r| {
+                    Path::new(&r.file_path)
+                        .file_name()
+                        .unwrap()
+                        .to_str()
+                        .unwrap()
+                        .to_string()
+                })
+                .collect()
+        };
+        assert_eq!(names("relevance"), vec!["needle.rs", "a.rs"]);
+        assert_eq!(names("path"), vec!["a.rs", "needle.rs"]);
+        assert_eq!(names("modified").len(), 2);
+
+        let invalid = serde_json::from_value::<ContentSearchParams>(serde_json::json!({
+            "query": "needle",
+            "sortBy": "size",
+        }));
+        assert!(invalid.is_err());
+    }
+
+    #[test]
+    fn test_max_results_slice_is_sorted() {
+        let temp_dir = TempDir::new().unwrap();
+        for i in 0..30 {
+            fs::write(temp_dir.path().join(format!("f{:02}.rs", i)), "fn x() {}\n").unwrap();
+        }
+        let results = RipgrepSearch::new()
+            .with_max_results(7)
+            .with_order(ResultOrder::Path)
+            .search_content("fn", temp_dir.path().to_str().unwrap())
+            .unwrap();
+        assert_eq!(results.len(), 7);
+        assert!(results.windows(2).all(|w| w[0].file_path < w[1].file_path));
+    }
+
+    #[test]
+    fn test_byte_offset_points_at_match_start() {
+        let temp_dir = TempDir::new().unwrap();
+        let content = "// héllo\nfn first() {}\n\n    let value = needle();\nneedle at start\n";
+        let file = temp_dir.path().join("offsets.rs");
+        fs::write(&file, content).unwrap();
+
+        let results = RipgrepSearch::new()
+            .search_content("needle", temp_dir.path().to_str().unwrap())
+            .unwrap();
+        let matches = &results[0].matches;
+        let expected: Vec<(u64, u64)> = content
+            .match_indices("needle")
+            .map(|(offset, _)| {
+                let line = content[..offset].matches('\n').count() as u64 + 1;
+                (line, offset as u64)
+            })
+            .collect();
+        assert_eq!(
+            matches
+                .iter()
+                .map(|m| (m.line_number, m.byte_offset))
+                .collect::<Vec<_>>(),
+            expected
+        );
+        assert_eq!(&content[matches[0].byte_offset as usize..][..6], "needle");
+
+        // Searching bytes already read gives the same offsets
+        let matcher = RipgrepSearch::build_matcher("NEEDLE").unwrap();
+        let from_bytes =
+            RipgrepSearch::search_in_bytes(&matcher, &file, content.as_bytes(), 10, "NEEDLE")
+                .unwrap();
+        assert_eq!(from_bytes.matches[1].byte_offset, expected[1].1);
+    }
+
+    #[test]
+    fn test_columns_count_characters_before_the_match() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join("columns.rs"),
+            "let s = \"héllo wörld\"; // wörld again\nwörld\n",
+        )
+        .unwrap();
+
+        let results = RipgrepSearch::new()
+            .search_content("wörld", temp_dir.path().to_str().unwrap())
+            .unwrap();
+        let matches = &results[0].matches;
+        assert_eq!((matches[0].column_start, matches[0].column_end), (15, 20));
+        assert_eq!(matches[0].match_ranges, vec![(15, 20), (26, 31)]);
+        // é is two bytes, so the match starts one byte after its column
+        assert_eq!(matches[0].byte_offset, 16);
+        assert_eq!(matches[1].match_ranges, vec![(0, 5)]);
+
+        assert_eq!(char_columns("ab", []), vec![]);
+        assert_eq!(
+            char_columns("日本語", [(3, 6), (6, 9)]),
+            vec![(1, 2), (2, 3)]
+        );
+        assert_eq!(char_columns("日本", [(1, 2), (4, 9)]), vec![(0, 0), (1, 2)]);
+    }
+
+    #[test]
+    fn test_empty_matching_pattern_on_multibyte_line() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("cjk.rs"), "日本語 😀 x\n한국어\n").unwrap();
+        let root = temp_dir.path().to_str().unwrap();
+
+        for query in ["x*", "a?"] {
+            let results = RipgrepSearch::new().search_content(query, root).unwrap();
+            let matches = &results[0].matches;
+            assert_eq!(matches.len(), 2, "{}", query);
+            assert!(matches[1].match_ranges.is_empty());
+        }
+        let results = RipgrepSearch::new().search_content("x*", root).unwrap();
+        assert_eq!(results[0].matches[0].match_ranges, vec![(6, 7)]);
+    }
+
+    #[test]
+    fn test_cancelled_search_returns_partial_results() {
+        let temp_dir = create_test_search_directory();
+        let root = temp_dir.path().to_str().unwrap();
+        let search = RipgrepSearch::new().with_search_id(Some("search-cancel-test".to_string()));
+
+        cancellation::register("search-cancel-test");
+        let response = search.search_content_with_report("fn", root).unwrap();
+        assert!(!response.cancelled);
+        assert!(!response.results.is_empty());
+
+        assert!(cancellation::cancel("search-cancel-test"));
+        let response = search.search_content_with_report("fn", root).unwrap();
+        assert!(response.cancelled);
+        assert!(response.results.is_empty());
+        cancellation::unregister("search-cancel-test");
+
+        // Once unregistered the id no longer stops anything
+        let response = search.search_content_with_report("fn", root).unwrap();
+        assert!(!response.cancelled);
+    }
+
     #[test]
     fn test_max_matches_per_file_limit() {
         let temp_dir = create_test_search_directory();
@@ -533,21 +2879,61 @@ mod tests {
     }
 
     #[test]
-    fn test_search_match_serialization() {
-        let match_item = SearchMatch {
-            line_number: 42,
-            line_content: "fn test() {}".to_string(),
-            byte_offset: 100,
+    fn test_depth_limit_is_reported_and_configurable() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().to_str().unwrap();
+        crate::walk_depth::tests::nested_tree(temp_dir.path(), 25, "deep.rs");
+        let query = "deeply_nested_needle";
+
+        // 20 levels, the old fixed limit, miss the file 26 levels down and say so
+        let limited = RipgrepSearch::new()
+            .with_max_depth(Some(20))
+            .search_content_with_report(query, root)
+            .unwrap();
+        assert!(limited.results.is_empty());
+        assert!(limited.depth.depth_limited);
+        assert_eq!(limited.depth.truncated_subtrees, 1);
+
+        let params = ContentSearchParams {
+            query: query.to_string(),
+            root_path: Some(root.to_string()),
+            max_depth: Some(30),
+            ..Default::default()
         };
+        let raised = params.run(&params.searcher()).unwrap();
+        assert_eq!(raised.results.len(), 1);
+        assert!(!raised.depth.depth_limited);
+        // The default reaches it as well
+        assert_eq!(
+            RipgrepSearch::new()
+                .search_content(query, root)
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_search_match_serialization() {
+        let match_item = SearchMatch::new(42, "fn test() {}".to_string(), 100, vec![(3, 7)]);
 
         let json = serde_json::to_string(&match_item).unwrap();
         assert!(json.contains("\"line_number\":42"));
         assert!(json.contains("\"line_content\":\"fn test() {}\""));
         assert!(json.contains("\"byte_offset\":100"));
+        assert!(json.contains("\"column_start\":3,\"column_end\":7"));
+        assert!(json.contains("\"match_ranges\":[[3,7]]"));
 
         let parsed: SearchMatch = serde_json::from_str(&json).unwrap();
         assert_eq!(parsed.line_number, 42);
         assert_eq!(parsed.line_content, "fn test() {}");
+        assert_eq!(parsed.match_ranges, vec![(3, 7)]);
+
+        // Matches serialized before columns were added still parse
+        let parsed: SearchMatch =
+            serde_json::from_str(r#"{"line_number":1,"line_content":"x","byte_offset":0}"#)
+                .unwrap();
+        assert_eq!((parsed.column_start, parsed.column_end), (0, 0));
     }
 
     #[test]
@@ -557,8 +2943,9 @@ mod tests {
             matches: vec![SearchMatch {
                 line_number: 1,
                 line_content: "fn main() {}".to_string(),
-                byte_offset: 0,
+                ..Default::default()
             }],
+            raw_path: RawPath::default(),
         };
 
         let json = serde_json::to_string(&result).unwrap();
@@ -605,6 +2992,127 @@ mod tests {
         assert!(search.exclude_dirs.is_some());
     }
 
+    #[test]
+    fn test_search_content_in_paths_mixed_include_list() {
+        let temp_dir = create_test_search_directory();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("docs")).unwrap();
+        fs::write(root.join("docs/notes.txt"), "Hello from notes\n").unwrap();
+        fs::write(root.join("docs/data.bin"), "Hello from data\n").unwrap();
+
+        let search = RipgrepSearch::new();
+        let include_paths = vec![
+            root.join("src").to_string_lossy().to_string(),
+            root.join("tests").to_string_lossy().to_string(),
+            // Not a code file, but explicitly listed so it must be searched
+            root.join("docs/data.bin").to_string_lossy().to_string(),
+        ];
+
+        let results = search
+            .search_content_in_paths("hello", &include_paths)
+            .unwrap();
+        let file_paths: Vec<&str> = results.iter().map(|r| r.file_path.as_str()).collect();
+
+        assert!(file_paths.iter().any(|p| p.ends_with("main.rs")));
+        assert!(file_paths.iter().any(|p| p.ends_with("lib.rs")));
+        assert!(file_paths.iter().any(|p| p.ends_with("data.bin")));
+        // README.md and notes.txt are outside the include list
+        assert!(!file_paths.iter().any(|p| p.ends_with("README.md")));
+        assert!(!file_paths.iter().any(|p| p.ends_with("notes.txt")));
+    }
+
+    #[test]
+    fn test_search_content_in_paths_deduplicates() {
+        let temp_dir = create_test_search_directory();
+        let root = temp_dir.path();
+
+        let search = RipgrepSearch::new();
+        let include_paths = vec![
+            root.to_string_lossy().to_string(),
+            root.join("src").to_string_lossy().to_string(),
+            root.join("src/main.rs").to_string_lossy().to_string(),
+        ];
+
+        let results = search
+            .search_content_in_paths("println", &include_paths)
+            .unwrap();
+        let main_count = results
+            .iter()
+            .filter(|r| r.file_path.ends_with("main.rs"))
+            .count();
+        assert_eq!(main_count, 1);
+    }
+
+    #[test]
+    fn test_exclude_paths_prunes_subtree() {
+        let temp_dir = create_test_search_directory();
+        let root = temp_dir.path();
+
+        let search = RipgrepSearch::new()
+            .with_exclude_paths(Some(vec![root.join("src").to_string_lossy().to_string()]));
+
+        let results = search
+            .search_content("hello", root.to_str().unwrap())
+            .unwrap();
+        assert!(!results.is_empty());
+        for result in &results {
+            assert!(
+                !Path::new(&result.file_path).starts_with(root.join("src")),
+                "Excluded subtree should not be searched: {}",
+                result.file_path
+            );
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_non_utf8_paths_round_trip() {
+        use base64::Engine;
+        use std::os::unix::ffi::OsStrExt;
+
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path().join(OsStr::from_bytes(b"archiv\xe9"));
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join(OsStr::from_bytes(b"r\xe9sum\xe9.rs"));
+        fs::write(&file, "fn hello() {}\n").unwrap();
+
+        let search = RipgrepSearch::new();
+        let results = search
+            .search_content("hello", temp_dir.path().to_str().unwrap())
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        let result = &results[0];
+        assert!(!result.file_path.contains('\u{FFFD}'));
+        assert!(result.raw_path.path_is_lossy);
+        assert_eq!(path_wire::resolve(&result.file_path), file);
+        let raw = base64::engine::general_purpose::STANDARD
+            .decode(result.raw_path.raw_path_bytes.as_ref().unwrap())
+            .unwrap();
+        assert_eq!(raw, file.as_os_str().as_bytes());
+
+        // The encoded path works as an include or exclude path
+        let again = search
+            .search_content_in_paths("hello", &[result.file_path.clone()])
+            .unwrap();
+        assert_eq!(again.len(), 1);
+        let excluded = RipgrepSearch::new()
+            .with_exclude_paths(Some(vec![path_wire::encode(&dir)]))
+            .search_content("hello", temp_dir.path().to_str().unwrap())
+            .unwrap();
+        assert!(excluded.is_empty());
+
+        // Valid paths carry no raw form
+        let json = serde_json::to_string(&again[0]).unwrap();
+        assert!(json.contains("\"path_is_lossy\":true"));
+        let plain = serde_json::to_string(&SearchResult {
+            file_path: "/a.rs".to_string(),
+            matches: Vec::new(),
+            raw_path: RawPath::default(),
+        })
+        .unwrap();
+        assert!(!plain.contains("raw_path_bytes"));
+    }
+
     #[test]
     fn test_truncate_line_short_line_unchanged() {
         // Lines shorter than MAX_LINE_LENGTH should not be truncated
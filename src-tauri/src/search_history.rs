@@ -0,0 +1,703 @@
+// Search history module
+// Persists project searches and replaces so a past search can be re-run with the same
+// flags and a past replace can be audited: which files it changed, how often, and the undo
+// journal operation that reverts it. Queries that look like they contain secrets are
+// stored masked and can't be replayed. Recording can be turned off in settings. Running a
+// project's last search again refreshes its entry, and only the newest entries are kept.
+
+use crate::clock::now_ms;
+use crate::command_history::mask_secrets;
+use crate::database::Database;
+use crate::search::{ContentSearchParams, ContentSearchResponse, ReplaceResponse, SearchResult};
+use crate::settings;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tauri::{Manager, State};
+
+/// Settings key for recording searches and replaces
+pub const RECORDING_SETTING: &str = "search_history_recording_enabled";
+
+const DEFAULT_LIST_LIMIT: usize = 50;
+/// Entries kept; the oldest beyond this are pruned on insert
+const MAX_ENTRIES: usize = 500;
+
+/// Recording is on unless turned off in settings
+static RECORDING_ENABLED: AtomicBool = AtomicBool::new(true);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchKind {
+    Search,
+    Replace,
+}
+
+impl SearchKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            SearchKind::Search => "search",
+            SearchKind::Replace => "replace",
+        }
+    }
+
+    fn parse(kind: &str) -> Option<Self> {
+        match kind {
+            "search" => Some(SearchKind::Search),
+            "replace" => Some(SearchKind::Replace),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplaceFileChange {
+    pub path: String,
+    pub replacements: usize,
+}
+
+/// What a replace did: the files it wrote and how often each changed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplaceRecord {
+    pub replacement: String,
+    pub file_changes: Vec<ReplaceFileChange>,
+    /// Undo journal operation that reverts the replace
+    pub undo_operation_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchHistoryEntry {
+    pub id: i64,
+    pub kind: SearchKind,
+    /// The search as run; its query is masked when `query_masked` is set
+    pub params: ContentSearchParams,
+    pub query_masked: bool,
+    /// Unix timestamp in milliseconds
+    pub timestamp: i64,
+    /// Files with matches
+    pub result_files: usize,
+    pub result_matches: usize,
+    /// Replace only
+    pub replacement: Option<String>,
+    pub file_changes: Vec<ReplaceFileChange>,
+    pub undo_operation_id: Option<String>,
+}
+
+pub fn is_recording_enabled() -> bool {
+    RECORDING_ENABLED.load(Ordering::Relaxed)
+}
+
+/// The search with its query masked, and whether masking changed it
+fn masked_params(params: &ContentSearchParams) -> (ContentSearchParams, bool) {
+    let query = mask_secrets(&params.query);
+    let masked = query != params.query;
+    (
+        ContentSearchParams {
+            query,
+            ..params.clone()
+        },
+        masked,
+    )
+}
+
+/// The history is listed per project; searches of explicit paths go under the first one
+fn history_root(params: &ContentSearchParams) -> Option<String> {
+    params.root_path.clone().or_else(|| {
+        params
+            .include_paths
+            .as_ref()
+            .and_then(|paths| paths.first().cloned())
+    })
+}
+
+async fn ensure_schema(db: &Database) -> Result<(), String> {
+    db.execute(
+        "CREATE TABLE IF NOT EXISTS search_history (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            kind TEXT NOT NULL,
+            root_path TEXT,
+            params TEXT NOT NULL,
+            query_masked INTEGER NOT NULL,
+            timestamp INTEGER NOT NULL,
+            result_files INTEGER NOT NULL,
+            result_matches INTEGER NOT NULL,
+            replacement TEXT,
+            file_changes TEXT,
+            undo_operation_id TEXT
+        )",
+        vec![],
+    )
+    .await?;
+    db.execute(
+        "CREATE INDEX IF NOT EXISTS idx_search_history_root ON search_history(root_path, timestamp)",
+        vec![],
+    )
+    .await?;
+    Ok(())
+}
+
+fn optional_string(value: Option<&str>) -> serde_json::Value {
+    value
+        .map(|v| serde_json::Value::String(v.to_string()))
+        .unwrap_or(serde_json::Value::Null)
+}
+
+/// The newest entry of `root_path` when it is a search with exactly these params
+async fn repeated_search(
+    db: &Database,
+    root_path: Option<&str>,
+    params_json: &str,
+) -> Result<Option<i64>, String> {
+    let result = db
+        .query(
+            "SELECT id, kind, params FROM search_history WHERE root_path IS ? ORDER BY timestamp DESC, id DESC LIMIT 1",
+            vec![optional_string(root_path)],
+        )
+        .await?;
+    let Some(row) = result.rows.first() else {
+        return Ok(None);
+    };
+    let text = |key: &str| row.get(key).and_then(|v| v.as_str());
+    if text("kind") != Some(SearchKind::Search.as_str()) || text("params") != Some(params_json) {
+        return Ok(None);
+    }
+    Ok(row.get("id").and_then(|v| v.as_i64()))
+}
+
+/// Drop the oldest entries beyond `MAX_ENTRIES`
+async fn prune(db: &Database) -> Result<u64, String> {
+    let result = db
+        .execute(
+            "DELETE FROM search_history WHERE id NOT IN (SELECT id FROM search_history ORDER BY timestamp DESC, id DESC LIMIT ?)",
+            vec![serde_json::Value::Number((MAX_ENTRIES as i64).into())],
+        )
+        .await?;
+    Ok(result.rows_affected)
+}
+
+/// Insert a search, or a replace when `replace` is given; a replace counts the files it
+/// changed rather than `results`. Returns the entry's id. Running the project's last
+/// search again refreshes that entry instead of adding one.
+pub async fn insert_entry(
+    db: &Database,
+    params: &ContentSearchParams,
+    results: &[SearchResult],
+    replace: Option<&ReplaceRecord>,
+) -> Result<i64, String> {
+    ensure_schema(db).await?;
+    let (params, query_masked) = masked_params(params);
+    let kind = if replace.is_some() {
+        SearchKind::Replace
+    } else {
+        SearchKind::Search
+    };
+    let (result_files, result_matches) = match replace {
+        Some(r) => (
+            r.file_changes.len(),
+            r.file_changes.iter().map(|c| c.replacements).sum(),
+        ),
+        None => (
+            results.len(),
+            results.iter().map(|r| r.matches.len()).sum::<usize>(),
+        ),
+    };
+    let root_path = history_root(&params);
+    let params_json = serde_json::to_string(&params).map_err(|e| e.to_string())?;
+    if kind == SearchKind::Search {
+        if let Some(id) = repeated_search(db, root_path.as_deref(), &params_json).await? {
+            db.execute(
+                "UPDATE search_history SET timestamp = ?, result_files = ?, result_matches = ? WHERE id = ?",
+                vec![
+                    serde_json::Value::Number(now_ms().into()),
+                    serde_json::Value::Number((result_files as i64).into()),
+                    serde_json::Value::Number((result_matches as i64).into()),
+                    serde_json::Value::Number(id.into()),
+                ],
+            )
+            .await?;
+            return Ok(id);
+        }
+    }
+    let file_changes = replace
+        .map(|r| serde_json::to_string(&r.file_changes))
+        .transpose()
+        .map_err(|e| e.to_string())?;
+    db.execute(
+        "INSERT INTO search_history (kind, root_path, params, query_masked, timestamp, result_files, result_matches, replacement, file_changes, undo_operation_id) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        vec![
+            serde_json::Value::String(kind.as_str().to_string()),
+            optional_string(root_path.as_deref()),
+            serde_json::Value::String(params_json),
+            serde_json::Value::Number((query_masked as i64).into()),
+            serde_json::Value::Number(now_ms().into()),
+            serde_json::Value::Number((result_files as i64).into()),
+            serde_json::Value::Number((result_matches as i64).into()),
+            optional_string(replace.map(|r| mask_secrets(&r.replacement)).as_deref()),
+            optional_string(file_changes.as_deref()),
+            optional_string(replace.and_then(|r| r.undo_operation_id.as_deref())),
+        ],
+    )
+    .await?;
+    let result = db.query("SELECT last_insert_rowid() AS id", vec![]).await?;
+    let id = result
+        .rows
+        .first()
+        .and_then(|row| row.get("id"))
+        .and_then(|v| v.as_i64())
+        .ok_or_else(|| "Failed to read search history id".to_string())?;
+    prune(db).await?;
+    Ok(id)
+}
+
+/// Record a search in the background. No-op when recording is disabled or the database
+/// is not available yet.
+pub fn record_search(params: &ContentSearchParams, results: &[SearchResult]) {
+    if !is_recording_enabled() || params.query.is_empty() {
+        return;
+    }
+    let Some(app_handle) = crate::try_get_app_handle() else {
+        return;
+    };
+    let Some(db) = app_handle.try_state::<Arc<Database>>() else {
+        return;
+    };
+    let db = db.inner().clone();
+    let params = params.clone();
+    let results = results.to_vec();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = insert_entry(&db, &params, &results, None).await {
+            log::warn!("Failed to record search history: {}", e);
+        }
+    });
+}
+
+/// Record a replace that wrote files, in the background. No-op for a dry run, when
+/// recording is disabled or the database is not available yet.
+pub fn record_replace(params: &ContentSearchParams, replacement: &str, response: &ReplaceResponse) {
+    if !is_recording_enabled() || response.dry_run || response.files.is_empty() {
+        return;
+    }
+    let Some(app_handle) = crate::try_get_app_handle() else {
+        return;
+    };
+    let Some(db) = app_handle.try_state::<Arc<Database>>() else {
+        return;
+    };
+    let db = db.inner().clone();
+    let params = params.clone();
+    let replace = ReplaceRecord {
+        replacement: replacement.to_string(),
+        file_changes: response
+            .files
+            .iter()
+            .map(|file| ReplaceFileChange {
+                path: file.path.clone(),
+                replacements: file.replacements,
+            })
+            .collect(),
+        undo_operation_id: response.undo_operation_id.clone(),
+    };
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = insert_entry(&db, &params, &[], Some(&replace)).await {
+            log::warn!("Failed to record replace history: {}", e);
+        }
+    });
+}
+
+fn entry_from_row(row: &serde_json::Value) -> Option<SearchHistoryEntry> {
+    let text = |key: &str| row.get(key).and_then(|v| v.as_str());
+    let number = |key: &str| row.get(key).and_then(|v| v.as_i64());
+    Some(SearchHistoryEntry {
+        id: number("id")?,
+        kind: SearchKind::parse(text("kind")?)?,
+        params: serde_json::from_str(text("params")?).ok()?,
+        query_masked: number("query_masked")? != 0,
+        timestamp: number("timestamp").unwrap_or(0),
+        result_files: number("result_files").unwrap_or(0) as usize,
+        result_matches: number("result_matches").unwrap_or(0) as usize,
+        replacement: text("replacement").map(String::from),
+        file_changes: text("file_changes")
+            .and_then(|changes| serde_json::from_str(changes).ok())
+            .unwrap_or_default(),
+        undo_operation_id: text("undo_operation_id").map(String::from),
+    })
+}
+
+/// Entries newest first, optionally for one project and of one kind
+pub async fn list_entries(
+    db: &Database,
+    root_path: Option<&str>,
+    kind: Option<SearchKind>,
+    limit: usize,
+) -> Result<Vec<SearchHistoryEntry>, String> {
+    ensure_schema(db).await?;
+    let mut sql = String::from("SELECT * FROM search_history WHERE 1 = 1");
+    let mut params = Vec::new();
+    if let Some(root_path) = root_path {
+        sql.push_str(" AND root_path = ?");
+        params.push(serde_json::Value::String(root_path.to_string()));
+    }
+    if let Some(kind) = kind {
+        sql.push_str(" AND kind = ?");
+        params.push(serde_json::Value::String(kind.as_str().to_string()));
+    }
+    sql.push_str(" ORDER BY timestamp DESC, id DESC LIMIT ?");
+    params.push(serde_json::Value::Number((limit as i64).into()));
+
+    let result = db.query(&sql, params).await?;
+    Ok(result.rows.iter().filter_map(entry_from_row).collect())
+}
+
+pub async fn get_entry(db: &Database, id: i64) -> Result<SearchHistoryEntry, String> {
+    ensure_schema(db).await?;
+    let result = db
+        .query(
+            "SELECT * FROM search_history WHERE id = ?",
+            vec![serde_json::Value::Number(id.into())],
+        )
+        .await?;
+    result
+        .rows
+        .first()
+        .and_then(entry_from_row)
+        .ok_or_else(|| format!("Search history entry {} not found", id))
+}
+
+/// The search to run when replaying an entry. A replace replays only its search.
+pub fn replay_params(entry: &SearchHistoryEntry) -> Result<ContentSearchParams, String> {
+    if entry.query_masked {
+        return Err(
+            "The query was stored masked because it looked like a secret, so it can't be replayed"
+                .to_string(),
+        );
+    }
+    Ok(entry.params.clone())
+}
+
+pub async fn delete_entries(db: &Database, ids: &[i64]) -> Result<u64, String> {
+    if ids.is_empty() {
+        return Ok(0);
+    }
+    ensure_schema(db).await?;
+    let placeholders = vec!["?"; ids.len()].join(", ");
+    let sql = format!("DELETE FROM search_history WHERE id IN ({})", placeholders);
+    let params = ids
+        .iter()
+        .map(|id| serde_json::Value::Number((*id).into()))
+        .collect();
+    Ok(db.execute(&sql, params).await?.rows_affected)
+}
+
+/// Delete every entry, or those of one project
+pub async fn clear_entries(db: &Database, root_path: Option<&str>) -> Result<u64, String> {
+    ensure_schema(db).await?;
+    let result = match root_path {
+        Some(root_path) => {
+            db.execute(
+                "DELETE FROM search_history WHERE root_path = ?",
+                vec![serde_json::Value::String(root_path.to_string())],
+            )
+            .await?
+        }
+        None => db.execute("DELETE FROM search_history", vec![]).await?,
+    };
+    Ok(result.rows_affected)
+}
+
+/// Apply the persisted recording setting
+pub async fn load_from_settings(db: &Database) -> Result<(), String> {
+    if let Some(enabled) = settings::get_json_setting::<bool>(db, RECORDING_SETTING).await? {
+        RECORDING_ENABLED.store(enabled, Ordering::Relaxed);
+    }
+    Ok(())
+}
+
+// Tauri commands
+#[tauri::command]
+pub async fn search_history_list(
+    db: State<'_, Arc<Database>>,
+    root_path: Option<String>,
+    kind: Option<SearchKind>,
+    limit: Option<usize>,
+) -> Result<Vec<SearchHistoryEntry>, String> {
+    list_entries(
+        &db,
+        root_path.as_deref(),
+        kind,
+        limit.unwrap_or(DEFAULT_LIST_LIMIT),
+    )
+    .await
+}
+
+/// Run a recorded search again, with today's exclusions and path access rules, and
+/// return fresh results. Replaying a replace runs its search and applies nothing.
+#[tauri::command]
+pub async fn search_history_replay(
+    db: State<'_, Arc<Database>>,
+    id: i64,
+) -> Result<ContentSearchResponse, String> {
+    let params = replay_params(&get_entry(&db, id).await?)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        crate::run_content_search(params, None, |_| {}).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| format!("Search task failed: {}", e))?
+}
+
+/// Record a search run outside `search_file_content`, such as a remote one. Returns the
+/// entry's id, or None when recording is disabled.
+#[tauri::command]
+pub async fn search_history_add(
+    db: State<'_, Arc<Database>>,
+    params: ContentSearchParams,
+    results: Vec<SearchResult>,
+) -> Result<Option<i64>, String> {
+    if !is_recording_enabled() || params.query.is_empty() {
+        return Ok(None);
+    }
+    insert_entry(&db, &params, &results, None).await.map(Some)
+}
+
+#[tauri::command]
+pub async fn search_history_delete(
+    db: State<'_, Arc<Database>>,
+    ids: Vec<i64>,
+) -> Result<u64, String> {
+    delete_entries(&db, &ids).await
+}
+
+#[tauri::command]
+pub async fn search_history_clear(
+    db: State<'_, Arc<Database>>,
+    root_path: Option<String>,
+) -> Result<u64, String> {
+    log::info!("Clearing search history for {:?}", root_path);
+    clear_entries(&db, root_path.as_deref()).await
+}
+
+#[tauri::command]
+pub fn search_history_get_recording_enabled() -> bool {
+    is_recording_enabled()
+}
+
+/// Turn recording on or off. Entries already recorded are kept.
+#[tauri::command]
+pub async fn search_history_set_recording_enabled(
+    db: State<'_, Arc<Database>>,
+    enabled: bool,
+) -> Result<(), String> {
+    settings::set_json_setting(&db, RECORDING_SETTING, &enabled).await?;
+    RECORDING_ENABLED.store(enabled, Ordering::Relaxed);
+    log::info!("Search history recording enabled: {}", enabled);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::SearchMatch;
+    use tempfile::TempDir;
+
+    async fn test_db(dir: &TempDir) -> Database {
+        let db = Database::new(dir.path().join("search.db").to_string_lossy().to_string());
+        db.connect().await.unwrap();
+        db
+    }
+
+    fn params(query: &str) -> ContentSearchParams {
+        ContentSearchParams {
+            query: query.to_string(),
+            root_path: Some("/work/app".to_string()),
+            file_types: Some(vec!["rs".to_string(), "toml".to_string()]),
+            exclude_dirs: Some(vec!["target".to_string()]),
+            include_paths: None,
+            exclude_paths: Some(vec!["/work/app/vendor".to_string()]),
+            max_depth: None,
+            sort_by: None,
+            is_regex: None,
+            case_sensitive: None,
+            whole_word: None,
+            respect_gitignore: None,
+            max_file_size: None,
+            include_files: None,
+            include_globs: None,
+            exclude_globs: None,
+            include_hidden: None,
+            follow_symlinks: None,
+            count_only: None,
+        }
+    }
+
+    fn results(files: &[(&str, usize)]) -> Vec<SearchResult> {
+        files
+            .iter()
+            .map(|(path, matches)| SearchResult {
+                file_path: path.to_string(),
+                matches: (0..*matches)
+                    .map(|i| SearchMatch {
+                        line_number: i as u64 + 1,
+                        line_content: "match".to_string(),
+                        ..Default::default()
+                    })
+                    .collect(),
+                raw_path: Default::default(),
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_replay_uses_the_recorded_flags() {
+        let dir = TempDir::new().unwrap();
+        let db = test_db(&dir).await;
+        let original = params("fn\\s+main");
+        let id = insert_entry(
+            &db,
+            &original,
+            &results(&[("/work/app/src/main.rs", 2)]),
+            None,
+        )
+        .await
+        .unwrap();
+
+        let entry = get_entry(&db, id).await.unwrap();
+        assert_eq!(entry.kind, SearchKind::Search);
+        assert_eq!(entry.result_files, 1);
+        assert_eq!(entry.result_matches, 2);
+        let replayed = replay_params(&entry).unwrap();
+        assert_eq!(replayed, original);
+        assert_eq!(replayed.searcher(), original.searcher());
+    }
+
+    #[tokio::test]
+    async fn test_replace_is_recorded_and_replays_its_search() {
+        let dir = TempDir::new().unwrap();
+        let db = test_db(&dir).await;
+        let replace = ReplaceRecord {
+            replacement: "new_name".to_string(),
+            file_changes: vec![
+                ReplaceFileChange {
+                    path: "/work/app/src/a.rs".to_string(),
+                    replacements: 3,
+                },
+                ReplaceFileChange {
+                    path: "/work/app/src/b.rs".to_string(),
+                    replacements: 1,
+                },
+            ],
+            undo_operation_id: Some("op-1".to_string()),
+        };
+        insert_entry(&db, &params("old_name"), &[], None)
+            .await
+            .unwrap();
+        let id = insert_entry(&db, &params("old_name"), &[], Some(&replace))
+            .await
+            .unwrap();
+
+        let replaces = list_entries(&db, Some("/work/app"), Some(SearchKind::Replace), 10)
+            .await
+            .unwrap();
+        assert_eq!(replaces.len(), 1);
+        assert_eq!(replaces[0].id, id);
+        assert_eq!(replaces[0].replacement.as_deref(), Some("new_name"));
+        assert_eq!(replaces[0].file_changes, replace.file_changes);
+        assert_eq!(replaces[0].undo_operation_id.as_deref(), Some("op-1"));
+        assert_eq!(
+            (replaces[0].result_files, replaces[0].result_matches),
+            (2, 4)
+        );
+        assert_eq!(replay_params(&replaces[0]).unwrap(), params("old_name"));
+
+        assert_eq!(
+            list_entries(&db, Some("/work/app"), None, 10)
+                .await
+                .unwrap()
+                .len(),
+            2
+        );
+        assert!(list_entries(&db, Some("/work/other"), None, 10)
+            .await
+            .unwrap()
+            .is_empty());
+        assert_eq!(delete_entries(&db, &[id]).await.unwrap(), 1);
+        assert_eq!(clear_entries(&db, Some("/work/app")).await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_repeated_searches_are_merged_and_old_entries_pruned() {
+        let dir = TempDir::new().unwrap();
+        let db = test_db(&dir).await;
+        let first = insert_entry(&db, &params("needle"), &[], None)
+            .await
+            .unwrap();
+        let again = insert_entry(
+            &db,
+            &params("needle"),
+            &results(&[("/work/app/src/a.rs", 4)]),
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(again, first);
+        let entry = get_entry(&db, first).await.unwrap();
+        assert_eq!((entry.result_files, entry.result_matches), (1, 4));
+
+        // Only the latest entry is merged into, and only with identical params
+        insert_entry(&db, &params("other"), &[], None)
+            .await
+            .unwrap();
+        let later = insert_entry(&db, &params("needle"), &[], None)
+            .await
+            .unwrap();
+        assert_ne!(later, first);
+        let case_sensitive = ContentSearchParams {
+            case_sensitive: Some(true),
+            ..params("needle")
+        };
+        assert_ne!(
+            insert_entry(&db, &case_sensitive, &[], None).await.unwrap(),
+            later
+        );
+        assert_eq!(list_entries(&db, None, None, 10).await.unwrap().len(), 4);
+
+        for i in 0..MAX_ENTRIES {
+            insert_entry(&db, &params(&format!("query_{}", i)), &[], None)
+                .await
+                .unwrap();
+        }
+        let entries = list_entries(&db, None, None, MAX_ENTRIES * 2)
+            .await
+            .unwrap();
+        assert_eq!(entries.len(), MAX_ENTRIES);
+        assert_eq!(
+            entries.last().unwrap().params.query,
+            "query_0",
+            "the oldest entries are pruned first"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_secret_queries_are_masked() {
+        let dir = TempDir::new().unwrap();
+        let db = test_db(&dir).await;
+        let id = insert_entry(&db, &params("API_KEY=sk-live-1234"), &[], None)
+            .await
+            .unwrap();
+        let entry = get_entry(&db, id).await.unwrap();
+        assert!(entry.query_masked);
+        assert_eq!(entry.params.query, "API_KEY=****");
+        assert!(replay_params(&entry).is_err());
+
+        let id = insert_entry(&db, &params("ghp_abcdefghijklmnop"), &[], None)
+            .await
+            .unwrap();
+        let entry = get_entry(&db, id).await.unwrap();
+        assert_eq!(entry.params.query, "ghp_****");
+
+        // Ordinary queries, even about tokens, are kept as typed
+        let (unchanged, masked) = masked_params(&params("parse_token"));
+        assert!(!masked);
+        assert_eq!(unchanged.query, "parse_token");
+    }
+}
@@ -0,0 +1,143 @@
+// Secrets module
+// Sensitive values (secret task environment variables and the like) kept out of the main
+// database, in a separate JSON file only the current user can read. Callers store a value
+// under a key, persist just the key, and resolve it when the value is needed, e.g. at
+// process spawn time. Values are not encrypted; the file permissions are the protection.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+lazy_static::lazy_static! {
+    static ref DEFAULT_STORE: SecretStore = SecretStore::new(default_store_path());
+}
+
+fn default_store_path() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("com.talkcody")
+        .join("secrets.json")
+}
+
+/// The app's secret store
+pub fn store() -> &'static SecretStore {
+    &DEFAULT_STORE
+}
+
+pub struct SecretStore {
+    path: PathBuf,
+    /// Serializes read-modify-write cycles on the file
+    lock: Mutex<()>,
+}
+
+impl SecretStore {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            lock: Mutex::new(()),
+        }
+    }
+
+    fn load(&self) -> Result<BTreeMap<String, String>, String> {
+        match fs::read_to_string(&self.path) {
+            Ok(content) => serde_json::from_str(&content)
+                .map_err(|e| format!("Failed to parse secret store: {}", e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+            Err(e) => Err(format!("Failed to read secret store: {}", e)),
+        }
+    }
+
+    /// Replace the file atomically, readable by the owner only
+    fn save(&self, secrets: &BTreeMap<String, String>) -> Result<(), String> {
+        let dir = self.path.parent().unwrap_or(Path::new("."));
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create secret store: {}", e))?;
+        let content = serde_json::to_vec(secrets).map_err(|e| e.to_string())?;
+        let temp_path = self.path.with_extension("json.tmp");
+
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options
+            .open(&temp_path)
+            .map_err(|e| format!("Failed to write secret store: {}", e))?;
+        file.write_all(&content)
+            .and_then(|_| file.sync_all())
+            .map_err(|e| format!("Failed to write secret store: {}", e))?;
+        fs::rename(&temp_path, &self.path)
+            .map_err(|e| format!("Failed to write secret store: {}", e))
+    }
+
+    pub fn set(&self, key: &str, value: &str) -> Result<(), String> {
+        let _guard = self.lock.lock().map_err(|e| e.to_string())?;
+        let mut secrets = self.load()?;
+        secrets.insert(key.to_string(), value.to_string());
+        self.save(&secrets)
+    }
+
+    pub fn get(&self, key: &str) -> Result<Option<String>, String> {
+        let _guard = self.lock.lock().map_err(|e| e.to_string())?;
+        Ok(self.load()?.remove(key))
+    }
+
+    /// Remove every key starting with `prefix`. Returns how many were removed.
+    pub fn delete_prefix(&self, prefix: &str) -> Result<usize, String> {
+        let _guard = self.lock.lock().map_err(|e| e.to_string())?;
+        let mut secrets = self.load()?;
+        let before = secrets.len();
+        secrets.retain(|key, _| !key.starts_with(prefix));
+        let removed = before - secrets.len();
+        if removed > 0 {
+            self.save(&secrets)?;
+        }
+        Ok(removed)
+    }
+
+    pub fn delete(&self, key: &str) -> Result<bool, String> {
+        let _guard = self.lock.lock().map_err(|e| e.to_string())?;
+        let mut secrets = self.load()?;
+        let removed = secrets.remove(key).is_some();
+        if removed {
+            self.save(&secrets)?;
+        }
+        Ok(removed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_set_get_delete() {
+        let temp = TempDir::new().unwrap();
+        let store = SecretStore::new(temp.path().join("nested/secrets.json"));
+        assert_eq!(store.get("a").unwrap(), None);
+
+        store.set("task:1:TOKEN", "t0k3n").unwrap();
+        store.set("task:1:KEY", "k").unwrap();
+        store.set("task:2:KEY", "other").unwrap();
+        assert_eq!(store.get("task:1:TOKEN").unwrap().as_deref(), Some("t0k3n"));
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(temp.path().join("nested/secrets.json"))
+                .unwrap()
+                .permissions()
+                .mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        assert_eq!(store.delete_prefix("task:1:").unwrap(), 2);
+        assert_eq!(store.get("task:1:KEY").unwrap(), None);
+        assert!(store.delete("task:2:KEY").unwrap());
+        assert!(!store.delete("task:2:KEY").unwrap());
+    }
+}
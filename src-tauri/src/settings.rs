@@ -0,0 +1,110 @@
+// Settings module
+// Read and write backend-owned entries of the key/value settings table the frontend
+// settings store keeps in the app database.
+//
+// Settings cached in memory are eventually consistent with work already running. Long
+// directory walks and batch indexing compare the exclusions generation as they go, so a
+// change applies to the directories and files reached after it; entries already visited,
+// and results already returned, are not revisited.
+
+use crate::database::Database;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+async fn ensure_table(db: &Database) -> Result<(), String> {
+    // Same schema the frontend settings store creates
+    db.execute(
+        "CREATE TABLE IF NOT EXISTS settings (key TEXT PRIMARY KEY, value TEXT NOT NULL, updated_at INTEGER NOT NULL)",
+        vec![],
+    )
+    .await?;
+    Ok(())
+}
+
+pub async fn get_setting(db: &Database, key: &str) -> Result<Option<String>, String> {
+    ensure_table(db).await?;
+    let result = db
+        .query(
+            "SELECT value FROM settings WHERE key = ?",
+            vec![serde_json::Value::String(key.to_string())],
+        )
+        .await?;
+    Ok(result
+        .rows
+        .first()
+        .and_then(|row| row.get("value"))
+        .and_then(|v| v.as_str())
+        .map(|v| v.to_string()))
+}
+
+pub async fn set_setting(db: &Database, key: &str, value: &str) -> Result<(), String> {
+    ensure_table(db).await?;
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as i64;
+    db.execute(
+        "INSERT OR REPLACE INTO settings (key, value, updated_at) VALUES (?, ?, ?)",
+        vec![
+            serde_json::Value::String(key.to_string()),
+            serde_json::Value::String(value.to_string()),
+            serde_json::Value::Number(now.into()),
+        ],
+    )
+    .await?;
+    Ok(())
+}
+
+/// A JSON-encoded setting, None when unset
+pub async fn get_json_setting<T: serde::de::DeserializeOwned>(
+    db: &Database,
+    key: &str,
+) -> Result<Option<T>, String> {
+    match get_setting(db, key).await? {
+        Some(value) => serde_json::from_str(&value)
+            .map(Some)
+            .map_err(|e| format!("Invalid {} setting: {}", key, e)),
+        None => Ok(None),
+    }
+}
+
+pub async fn set_json_setting<T: serde::Serialize>(
+    db: &Database,
+    key: &str,
+    value: &T,
+) -> Result<(), String> {
+    let value = serde_json::to_string(value).map_err(|e| e.to_string())?;
+    set_setting(db, key, &value).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_settings_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = Database::new(
+            temp_dir
+                .path()
+                .join("test.db")
+                .to_string_lossy()
+                .to_string(),
+        );
+        db.connect().await.unwrap();
+
+        assert_eq!(get_setting(&db, "theme").await.unwrap(), None);
+        set_setting(&db, "theme", "dark").await.unwrap();
+        set_setting(&db, "theme", "light").await.unwrap();
+        assert_eq!(
+            get_setting(&db, "theme").await.unwrap(),
+            Some("light".to_string())
+        );
+
+        set_json_setting(&db, "sizes", &vec![1, 2]).await.unwrap();
+        let sizes: Option<Vec<i32>> = get_json_setting(&db, "sizes").await.unwrap();
+        assert_eq!(sizes, Some(vec![1, 2]));
+        set_setting(&db, "sizes", "not json").await.unwrap();
+        assert!(get_json_setting::<Vec<i32>>(&db, "sizes").await.is_err());
+    }
+}
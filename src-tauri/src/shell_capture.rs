@@ -0,0 +1,208 @@
+// Shell capture module
+// Wraps user shell commands so the shell itself reports its exit code, final working
+// directory and duration to a side file, keeping that metadata out of the visible output.
+
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+static CAPTURE_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Shells that understand the POSIX `trap ... EXIT` wrapper
+const POSIX_SHELLS: &[&str] = &["sh", "bash", "zsh", "dash", "ksh", "mksh", "ash"];
+
+/// Which pipe a line of output came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+/// What the shell reported about a finished command
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ShellMetadata {
+    pub exit_code: Option<i32>,
+    pub final_cwd: Option<String>,
+    pub duration_ms: Option<u64>,
+}
+
+/// Temp file the wrapped command writes its metadata to; removed on drop
+pub struct ShellCapture {
+    path: PathBuf,
+}
+
+impl ShellCapture {
+    /// A capture for commands run by `shell`, or None when the shell cannot be wrapped
+    pub fn for_shell(shell: &str) -> Option<Self> {
+        if !is_posix_shell(shell) {
+            return None;
+        }
+        let id = CAPTURE_COUNTER.fetch_add(1, Ordering::Relaxed);
+        let path =
+            std::env::temp_dir().join(format!("talkcody-shell-{}-{}.meta", std::process::id(), id));
+        // A stale file from an earlier run would be read as this command's metadata
+        let _ = std::fs::remove_file(&path);
+        Some(Self { path })
+    }
+
+    /// `command` with an EXIT trap that records the metadata. The trap also runs when the
+    /// command calls `exit` itself.
+    pub fn wrap(&self, command: &str) -> String {
+        let meta = shell_quote(&self.path.to_string_lossy());
+        format!(
+            "__talkcody_meta={meta}; __talkcody_start=$(date +%s%3N 2>/dev/null); \
+             trap '__talkcody_status=$?; printf \"%s\\n%s\\n%s\\n%s\\n\" \"$__talkcody_status\" \"$__talkcody_start\" \"$(date +%s%3N 2>/dev/null)\" \"$PWD\" > \"$__talkcody_meta\"' EXIT\n{command}"
+        )
+    }
+
+    /// Metadata written by the shell, if it got as far as its EXIT trap
+    pub fn read(&self) -> Option<ShellMetadata> {
+        let content = std::fs::read_to_string(&self.path).ok()?;
+        parse_metadata(&content)
+    }
+}
+
+impl Drop for ShellCapture {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+fn is_posix_shell(shell: &str) -> bool {
+    if cfg!(windows) {
+        return false;
+    }
+    Path::new(shell)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| POSIX_SHELLS.contains(&name))
+}
+
+pub(crate) fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// Millisecond timestamp from `date +%s%3N`; BSD date has no %N, so anything that is not
+/// a plain millisecond count is rejected
+fn parse_millis(value: &str) -> Option<u64> {
+    let value = value.trim();
+    if value.len() < 13 || !value.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    value.parse().ok()
+}
+
+fn parse_metadata(content: &str) -> Option<ShellMetadata> {
+    let mut lines = content.lines();
+    let exit_code = lines.next()?.trim().parse().ok();
+    let start = lines.next().and_then(parse_millis);
+    let end = lines.next().and_then(parse_millis);
+    // The cwd is last so a path containing newlines survives
+    let final_cwd = Some(lines.collect::<Vec<_>>().join("\n")).filter(|cwd| !cwd.is_empty());
+    let duration_ms = match (start, end) {
+        (Some(start), Some(end)) => end.checked_sub(start),
+        _ => None,
+    };
+    Some(ShellMetadata {
+        exit_code,
+        final_cwd,
+        duration_ms,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::process::Command as TokioCommand;
+    use tokio::time::Duration as TokioDuration;
+
+    #[test]
+    fn test_parse_metadata() {
+        let metadata = parse_metadata("3\n1700000000000\n1700000000250\n/tmp/a b\n").unwrap();
+        assert_eq!(metadata.exit_code, Some(3));
+        assert_eq!(metadata.duration_ms, Some(250));
+        assert_eq!(metadata.final_cwd.as_deref(), Some("/tmp/a b"));
+
+        // BSD date prints the format literally
+        let metadata = parse_metadata("0\n17000000003N\n17000000013N\n/\n").unwrap();
+        assert_eq!(metadata.exit_code, Some(0));
+        assert_eq!(metadata.duration_ms, None);
+
+        assert_eq!(parse_metadata(""), None);
+    }
+
+    #[test]
+    fn test_only_posix_shells_are_wrapped() {
+        assert!(ShellCapture::for_shell("/bin/fish").is_none());
+        assert!(ShellCapture::for_shell("pwsh").is_none());
+        if cfg!(unix) {
+            assert!(ShellCapture::for_shell("/bin/bash").is_some());
+            assert!(ShellCapture::for_shell("/usr/local/bin/zsh").is_some());
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_capture_cd_and_nonzero_exit() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir(temp_dir.path().join("sub")).unwrap();
+        let capture = ShellCapture::for_shell("/bin/sh").unwrap();
+        let mut cmd = TokioCommand::new("/bin/sh");
+        cmd.arg("-c")
+            .arg(capture.wrap("cd sub && echo out && echo err >&2; exit 3"));
+
+        let result = crate::run_shell_process(
+            cmd,
+            Some(temp_dir.path().to_string_lossy().to_string()),
+            TokioDuration::from_secs(10),
+            TokioDuration::from_secs(5),
+            Some(capture),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(result.code, 3);
+        assert_eq!(result.shell_reported_exit, Some(3));
+        let expected_cwd = temp_dir.path().join("sub").canonicalize().unwrap();
+        let final_cwd = PathBuf::from(result.final_cwd.unwrap());
+        assert_eq!(final_cwd.canonicalize().unwrap(), expected_cwd);
+        // Only the command's own output is visible
+        assert_eq!(result.stdout, "out");
+        assert_eq!(result.stderr, "err");
+        // Lines from the two pipes are ordered by arrival, so only membership is stable
+        assert_eq!(result.combined_output.len(), 2);
+        assert!(result
+            .combined_output
+            .contains(&(OutputStream::Stdout, "out".to_string())));
+        assert!(result
+            .combined_output
+            .contains(&(OutputStream::Stderr, "err".to_string())));
+        assert!(result.duration_ms.is_some());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_capture_after_idle_timeout() {
+        let capture = ShellCapture::for_shell("/bin/sh").unwrap();
+        let meta_path = capture.path.clone();
+        let mut cmd = TokioCommand::new("/bin/sh");
+        // Silent long enough to hit the idle timeout, then exits within the grace period
+        cmd.arg("-c").arg(capture.wrap("sleep 0.15; exit 4"));
+
+        let result = crate::run_shell_process(
+            cmd,
+            None,
+            TokioDuration::from_secs(10),
+            TokioDuration::from_millis(100),
+            Some(capture),
+        )
+        .await
+        .unwrap();
+
+        assert!(result.idle_timed_out);
+        assert_eq!(result.code, -1);
+        assert_eq!(result.shell_reported_exit, Some(4));
+        assert!(!meta_path.exists());
+    }
+}
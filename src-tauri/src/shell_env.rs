@@ -0,0 +1,135 @@
+// Shell environment module
+// Apps started from the Dock, Finder or a desktop launcher don't inherit the PATH set up
+// in the user's shell profile, so tools like git, node and rg go missing. At startup the
+// environment is read from a login shell and copied into this process.
+
+use std::io::Read;
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::time::Duration;
+
+/// Printed around the environment dump, so output from the profile itself is ignored
+const DELIMITER: &str = "_SHELL_ENV_DELIMITER_";
+/// How long a slow or interactive rc file may delay launch before it's given up on
+const SHELL_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Copy PATH and the rest of the login shell's environment into this process. Must run
+/// before any other thread starts. On failure or timeout the inherited environment is
+/// left as is. Does nothing on Windows, where GUI apps already get the user's environment.
+pub fn fix() -> Result<(), String> {
+    if cfg!(windows) {
+        return Ok(());
+    }
+    let shell = std::env::var("SHELL").unwrap_or_else(|_| "/bin/sh".to_string());
+    let output = login_shell_output(&shell, SHELL_TIMEOUT)?;
+    for (key, value) in parse_env(&output) {
+        std::env::set_var(key, value);
+    }
+    Ok(())
+}
+
+/// Stdout of `env` run by `shell` as an interactive login shell, killing it past `timeout`
+fn login_shell_output(shell: &str, timeout: Duration) -> Result<String, String> {
+    let mut child = Command::new(shell)
+        .arg("-ilc")
+        .arg(format!(
+            "echo -n \"{0}\"; env; echo -n \"{0}\"; exit",
+            DELIMITER
+        ))
+        // Keeps oh-my-zsh from prompting for an update
+        .env("DISABLE_AUTO_UPDATE", "true")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Failed to run {}: {}", shell, e))?;
+
+    // Read on a thread so the timeout holds even if the shell never closes stdout
+    let mut stdout = child.stdout.take().expect("stdout is piped");
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let mut output = Vec::new();
+        let _ = tx.send(stdout.read_to_end(&mut output).map(|_| output));
+    });
+    match rx.recv_timeout(timeout) {
+        Ok(Ok(output)) => {
+            let status = child.wait().map_err(|e| e.to_string())?;
+            if !status.success() {
+                return Err(format!("{} exited with {}", shell, status));
+            }
+            Ok(String::from_utf8_lossy(&output).to_string())
+        }
+        Ok(Err(e)) => {
+            let _ = child.kill();
+            let _ = child.wait();
+            Err(format!("Failed to read {} output: {}", shell, e))
+        }
+        Err(_) => {
+            let _ = child.kill();
+            let _ = child.wait();
+            Err(format!("{} did not finish within {:?}", shell, timeout))
+        }
+    }
+}
+
+/// `KEY=value` lines between the first and last delimiter. Shells set `$_` to the last
+/// argument of the previous command, so the dump itself holds the delimiter; `_` is
+/// dropped.
+fn parse_env(output: &str) -> Vec<(&str, &str)> {
+    let (Some(start), Some(end)) = (output.find(DELIMITER), output.rfind(DELIMITER)) else {
+        return Vec::new();
+    };
+    let Some(dump) = output.get(start + DELIMITER.len()..end) else {
+        return Vec::new();
+    };
+    dump.lines()
+        .filter_map(|line| line.split_once('='))
+        .filter(|(key, _)| !key.is_empty() && *key != "_" && !key.contains(char::is_whitespace))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    #[test]
+    fn test_parse_env_ignores_profile_output() {
+        let output = format!(
+            "Welcome!\nPATH=/wrong\n{0}PATH=/opt/homebrew/bin:/usr/bin\n_={0}\nEMPTY=\nA=b=c\nnot a var\n{0}bye\n",
+            DELIMITER
+        );
+        assert_eq!(
+            parse_env(&output),
+            vec![
+                ("PATH", "/opt/homebrew/bin:/usr/bin"),
+                ("EMPTY", ""),
+                ("A", "b=c"),
+            ]
+        );
+        assert!(parse_env("no delimiter").is_empty());
+        assert!(parse_env(DELIMITER).is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_hanging_shell_times_out() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let shell = temp_dir.path().join("slow-shell");
+        std::fs::write(&shell, "#!/bin/sh\nsleep 30\n").unwrap();
+        std::fs::set_permissions(&shell, std::os::unix::fs::PermissionsExt::from_mode(0o755))
+            .unwrap();
+
+        let start = Instant::now();
+        let result = login_shell_output(shell.to_str().unwrap(), Duration::from_millis(200));
+        assert!(result.unwrap_err().contains("did not finish"));
+        assert!(start.elapsed() < Duration::from_secs(10));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_login_shell_reports_its_environment() {
+        let output = login_shell_output("/bin/sh", SHELL_TIMEOUT).unwrap();
+        assert!(parse_env(&output).iter().any(|(key, _)| *key == "PATH"));
+    }
+}
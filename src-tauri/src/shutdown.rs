@@ -0,0 +1,323 @@
+// Shutdown module
+// Ordered, bounded shutdown of the backend's subsystems on quit. Subsystems register hooks
+// in one of three phases; phases run in order, the hooks of a phase run concurrently, and
+// each hook has its own timeout. Once the global deadline passes the remaining hooks are
+// skipped, so a stuck subsystem can delay quitting but never prevent it.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Upper bound on the whole sequence
+pub const SHUTDOWN_DEADLINE: Duration = Duration::from_secs(8);
+/// Timeout for hooks registered without a specific one
+pub const DEFAULT_HOOK_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ShutdownPhase {
+    /// Stop schedulers and refuse new work
+    StopAccepting,
+    /// Cancel running operations and persist in-memory state
+    Flush,
+    /// Kill child processes, stop watchers, close the database
+    Release,
+}
+
+impl ShutdownPhase {
+    const ALL: [ShutdownPhase; 3] = [
+        ShutdownPhase::StopAccepting,
+        ShutdownPhase::Flush,
+        ShutdownPhase::Release,
+    ];
+}
+
+type HookFn = Box<dyn FnOnce() + Send>;
+
+struct ShutdownHook {
+    name: String,
+    phase: ShutdownPhase,
+    timeout: Duration,
+    run: HookFn,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PhaseReport {
+    pub phase: ShutdownPhase,
+    pub duration: Duration,
+    pub completed: Vec<String>,
+    /// Hooks still running when their timeout or the deadline expired
+    pub timed_out: Vec<String>,
+    /// Hooks never started because the deadline had passed
+    pub skipped: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ShutdownReport {
+    pub phases: Vec<PhaseReport>,
+    /// The global deadline cut the sequence short
+    pub deadline_exceeded: bool,
+}
+
+/// Managed in Tauri state
+#[derive(Default)]
+pub struct ShutdownCoordinator {
+    hooks: Mutex<Vec<ShutdownHook>>,
+    started: AtomicBool,
+}
+
+impl ShutdownCoordinator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register<F>(&self, name: &str, phase: ShutdownPhase, timeout: Duration, hook: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        if let Ok(mut hooks) = self.hooks.lock() {
+            hooks.push(ShutdownHook {
+                name: name.to_string(),
+                phase,
+                timeout,
+                run: Box::new(hook),
+            });
+        }
+    }
+
+    pub fn is_shutting_down(&self) -> bool {
+        self.started.load(Ordering::SeqCst)
+    }
+
+    /// Run every hook, phase by phase, within `deadline`. Only the first call does any
+    /// work; later calls return None.
+    pub fn run(&self, deadline: Duration) -> Option<ShutdownReport> {
+        if self.started.swap(true, Ordering::SeqCst) {
+            return None;
+        }
+        let hooks = self
+            .hooks
+            .lock()
+            .map(|mut hooks| std::mem::take(&mut *hooks))
+            .unwrap_or_default();
+
+        let start = Instant::now();
+        let mut pending = hooks;
+        let mut report = ShutdownReport::default();
+        log::info!("Shutting down ({} hooks)", pending.len());
+
+        for phase in ShutdownPhase::ALL {
+            let (phase_hooks, rest): (Vec<_>, Vec<_>) =
+                pending.into_iter().partition(|hook| hook.phase == phase);
+            pending = rest;
+            let phase_report = run_phase(phase, phase_hooks, start + deadline);
+            log::info!(
+                "Shutdown phase {:?} finished in {}ms ({} completed, {} timed out, {} skipped)",
+                phase,
+                phase_report.duration.as_millis(),
+                phase_report.completed.len(),
+                phase_report.timed_out.len(),
+                phase_report.skipped.len()
+            );
+            report.deadline_exceeded |=
+                !phase_report.skipped.is_empty() || Instant::now() >= start + deadline;
+            report.phases.push(phase_report);
+        }
+
+        if report.deadline_exceeded {
+            log::warn!(
+                "Shutdown deadline of {}ms exceeded, exiting anyway",
+                deadline.as_millis()
+            );
+        }
+        log::info!("Shutdown finished in {}ms", start.elapsed().as_millis());
+        Some(report)
+    }
+}
+
+fn run_phase(phase: ShutdownPhase, hooks: Vec<ShutdownHook>, deadline: Instant) -> PhaseReport {
+    let start = Instant::now();
+    let mut report = PhaseReport {
+        phase,
+        duration: Duration::ZERO,
+        completed: Vec::new(),
+        timed_out: Vec::new(),
+        skipped: Vec::new(),
+    };
+
+    // Each hook gets a thread, so a hook stuck past its timeout is simply abandoned
+    let mut running = Vec::new();
+    for hook in hooks {
+        if Instant::now() >= deadline {
+            report.skipped.push(hook.name);
+            continue;
+        }
+        let (done_tx, done_rx) = mpsc::channel();
+        let run = hook.run;
+        let name = hook.name.clone();
+        let spawned = std::thread::Builder::new()
+            .name(format!("shutdown-{}", hook.name))
+            .spawn(move || {
+                let hook_start = Instant::now();
+                run();
+                log::info!(
+                    "Shutdown hook '{}' finished in {}ms",
+                    name,
+                    hook_start.elapsed().as_millis()
+                );
+                let _ = done_tx.send(());
+            });
+        match spawned {
+            Ok(_) => running.push((hook.name, start + hook.timeout, done_rx)),
+            Err(e) => {
+                log::error!("Failed to start shutdown hook '{}': {}", hook.name, e);
+                report.skipped.push(hook.name);
+            }
+        }
+    }
+
+    for (name, hook_deadline, done_rx) in running {
+        let wait = hook_deadline
+            .min(deadline)
+            .saturating_duration_since(Instant::now());
+        match done_rx.recv_timeout(wait) {
+            // A hook that panicked drops its sender; it is finished either way
+            Ok(()) | Err(mpsc::RecvTimeoutError::Disconnected) => report.completed.push(name),
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                log::warn!("Shutdown hook '{}' timed out", name);
+                report.timed_out.push(name);
+            }
+        }
+    }
+
+    report.duration = start.elapsed();
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    fn recorder() -> Arc<Mutex<Vec<String>>> {
+        Arc::new(Mutex::new(Vec::new()))
+    }
+
+    fn record(log: &Arc<Mutex<Vec<String>>>, name: &str) -> impl FnOnce() + Send + 'static {
+        let log = log.clone();
+        let name = name.to_string();
+        move || log.lock().unwrap().push(name)
+    }
+
+    #[test]
+    fn test_phases_run_in_order_regardless_of_registration() {
+        let coordinator = ShutdownCoordinator::new();
+        let log = recorder();
+        coordinator.register(
+            "db",
+            ShutdownPhase::Release,
+            DEFAULT_HOOK_TIMEOUT,
+            record(&log, "db"),
+        );
+        coordinator.register(
+            "index",
+            ShutdownPhase::Flush,
+            DEFAULT_HOOK_TIMEOUT,
+            record(&log, "index"),
+        );
+        coordinator.register(
+            "scheduler",
+            ShutdownPhase::StopAccepting,
+            DEFAULT_HOOK_TIMEOUT,
+            record(&log, "scheduler"),
+        );
+        coordinator.register(
+            "watchers",
+            ShutdownPhase::Release,
+            DEFAULT_HOOK_TIMEOUT,
+            record(&log, "watchers"),
+        );
+
+        let report = coordinator.run(SHUTDOWN_DEADLINE).unwrap();
+        let log = log.lock().unwrap();
+        assert_eq!(&log[..2], ["scheduler", "index"]);
+        let mut released = log[2..].to_vec();
+        released.sort();
+        assert_eq!(released, ["db", "watchers"]);
+
+        assert!(!report.deadline_exceeded);
+        let phases: Vec<ShutdownPhase> = report.phases.iter().map(|p| p.phase).collect();
+        assert_eq!(phases, ShutdownPhase::ALL);
+        assert_eq!(report.phases[2].completed.len(), 2);
+        assert!(coordinator.is_shutting_down());
+    }
+
+    #[test]
+    fn test_run_happens_once() {
+        let coordinator = ShutdownCoordinator::new();
+        let log = recorder();
+        coordinator.register(
+            "a",
+            ShutdownPhase::Flush,
+            DEFAULT_HOOK_TIMEOUT,
+            record(&log, "a"),
+        );
+        assert!(coordinator.run(SHUTDOWN_DEADLINE).is_some());
+        assert!(coordinator.run(SHUTDOWN_DEADLINE).is_none());
+        assert_eq!(log.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_slow_hook_times_out_without_blocking_later_phases() {
+        let coordinator = ShutdownCoordinator::new();
+        let log = recorder();
+        coordinator.register(
+            "stuck",
+            ShutdownPhase::Flush,
+            Duration::from_millis(50),
+            || std::thread::sleep(Duration::from_secs(5)),
+        );
+        coordinator.register("panics", ShutdownPhase::Flush, DEFAULT_HOOK_TIMEOUT, || {
+            panic!("hook failure")
+        });
+        coordinator.register(
+            "db",
+            ShutdownPhase::Release,
+            DEFAULT_HOOK_TIMEOUT,
+            record(&log, "db"),
+        );
+
+        let start = Instant::now();
+        let report = coordinator.run(SHUTDOWN_DEADLINE).unwrap();
+        assert!(start.elapsed() < Duration::from_secs(2));
+        assert_eq!(report.phases[1].timed_out, ["stuck"]);
+        assert_eq!(report.phases[1].completed, ["panics"]);
+        assert_eq!(*log.lock().unwrap(), ["db"]);
+        assert!(!report.deadline_exceeded);
+    }
+
+    #[test]
+    fn test_global_deadline_skips_remaining_phases() {
+        let coordinator = ShutdownCoordinator::new();
+        let log = recorder();
+        coordinator.register(
+            "stuck",
+            ShutdownPhase::StopAccepting,
+            Duration::from_secs(10),
+            || std::thread::sleep(Duration::from_secs(5)),
+        );
+        coordinator.register(
+            "db",
+            ShutdownPhase::Release,
+            DEFAULT_HOOK_TIMEOUT,
+            record(&log, "db"),
+        );
+
+        let start = Instant::now();
+        let report = coordinator.run(Duration::from_millis(100)).unwrap();
+        assert!(start.elapsed() < Duration::from_secs(1));
+        assert!(report.deadline_exceeded);
+        assert_eq!(report.phases[0].timed_out, ["stuck"]);
+        assert_eq!(report.phases[2].skipped, ["db"]);
+        assert!(log.lock().unwrap().is_empty());
+    }
+}
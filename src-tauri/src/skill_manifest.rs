@@ -0,0 +1,498 @@
+// Skill manifest module
+// A skill ships a skill.json declaring what it is and what it needs: its entrypoint, the
+// runtime it runs on and the capabilities it asks for. Installs surface the manifest so
+// the UI can ask for consent before the first run, and the script executor refuses to run
+// a skill whose manifest is missing or invalid and applies restrictions for capabilities
+// it did not declare.
+
+use crate::script_executor::RuntimeConstraint;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+
+pub const MANIFEST_FILE: &str = "skill.json";
+
+/// Set to "1" for skills that did not declare network access. Proxy settings are also
+/// stripped, so well-behaved HTTP clients fail fast instead of reaching the network.
+pub const NO_NETWORK_ENV: &str = "TALKCODY_SKILL_NO_NETWORK";
+
+/// Proxy variables removed from the environment of skills without network access
+const PROXY_ENV_VARS: &[&str] = &[
+    "HTTP_PROXY",
+    "HTTPS_PROXY",
+    "ALL_PROXY",
+    "NO_PROXY",
+    "http_proxy",
+    "https_proxy",
+    "all_proxy",
+    "no_proxy",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Capability {
+    Network,
+    /// Run in and write to the project directory
+    WriteProject,
+    WriteHome,
+}
+
+impl Capability {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "network" => Some(Capability::Network),
+            "write_project" => Some(Capability::WriteProject),
+            "write_home" => Some(Capability::WriteHome),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SkillManifest {
+    pub name: String,
+    pub version: String,
+    /// Script to run, relative to the skill directory
+    pub entrypoint: String,
+    /// Runtime constraint such as "python>=3.10" or "node>=18"
+    pub runtime: String,
+    pub capabilities: Vec<Capability>,
+    /// Environment variables the skill reads
+    pub env: Vec<String>,
+}
+
+impl SkillManifest {
+    pub fn has(&self, capability: Capability) -> bool {
+        self.capabilities.contains(&capability)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestFieldError {
+    pub field: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum ManifestError {
+    Missing {
+        path: String,
+    },
+    Unreadable {
+        message: String,
+    },
+    InvalidJson {
+        message: String,
+    },
+    /// Every field that failed validation, in field order
+    Invalid {
+        errors: Vec<ManifestFieldError>,
+    },
+}
+
+impl std::fmt::Display for ManifestError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ManifestError::Missing { path } => write!(f, "Skill manifest not found: {}", path),
+            ManifestError::Unreadable { message } => {
+                write!(f, "Skill manifest could not be read: {}", message)
+            }
+            ManifestError::InvalidJson { message } => {
+                write!(f, "Skill manifest is not valid JSON: {}", message)
+            }
+            ManifestError::Invalid { errors } => {
+                let fields: Vec<String> = errors
+                    .iter()
+                    .map(|e| format!("{}: {}", e.field, e.message))
+                    .collect();
+                write!(f, "Skill manifest is invalid ({})", fields.join("; "))
+            }
+        }
+    }
+}
+
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 64
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_')
+}
+
+/// "1.2.3", optionally followed by a pre-release or build suffix
+fn is_valid_version(version: &str) -> bool {
+    let core = version.split(['-', '+']).next().unwrap_or_default();
+    let parts: Vec<&str> = core.split('.').collect();
+    parts.len() == 3
+        && parts
+            .iter()
+            .all(|p| !p.is_empty() && p.chars().all(|c| c.is_ascii_digit()))
+}
+
+fn is_valid_env_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Relative and staying inside the skill directory
+fn is_contained_relative(path: &str) -> bool {
+    let path = Path::new(path);
+    !path.as_os_str().is_empty()
+        && path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+}
+
+/// Parse and validate manifest JSON. `skill_dir`, when given, is used to check that the
+/// entrypoint exists.
+pub fn parse_manifest(
+    json: &str,
+    skill_dir: Option<&Path>,
+) -> Result<SkillManifest, ManifestError> {
+    let value: Value = serde_json::from_str(json).map_err(|e| ManifestError::InvalidJson {
+        message: e.to_string(),
+    })?;
+    let Some(object) = value.as_object() else {
+        return Err(ManifestError::InvalidJson {
+            message: "expected an object".to_string(),
+        });
+    };
+
+    let mut errors = Vec::new();
+    let mut error = |field: &str, message: &str| {
+        errors.push(ManifestFieldError {
+            field: field.to_string(),
+            message: message.to_string(),
+        })
+    };
+    let string_field = |field: &str, error: &mut dyn FnMut(&str, &str)| {
+        match object.get(field) {
+            None => error(field, "is required"),
+            Some(Value::String(s)) if s.trim().is_empty() => error(field, "must not be empty"),
+            Some(Value::String(s)) => return Some(s.trim().to_string()),
+            Some(_) => error(field, "must be a string"),
+        }
+        None
+    };
+
+    let name = string_field("name", &mut error);
+    if name.as_deref().is_some_and(|n| !is_valid_name(n)) {
+        error(
+            "name",
+            "must be lowercase letters, digits, '-' or '_', at most 64 characters",
+        );
+    }
+    let version = string_field("version", &mut error);
+    if version.as_deref().is_some_and(|v| !is_valid_version(v)) {
+        error("version", "must be a semantic version such as 1.0.0");
+    }
+    let entrypoint = string_field("entrypoint", &mut error);
+    if let Some(ref entrypoint) = entrypoint {
+        if !is_contained_relative(entrypoint) {
+            error(
+                "entrypoint",
+                "must be a relative path inside the skill directory",
+            );
+        } else if skill_dir.is_some_and(|dir| !dir.join(entrypoint).is_file()) {
+            error("entrypoint", "file does not exist");
+        }
+    }
+    let runtime = string_field("runtime", &mut error);
+    if let Some(ref runtime) = runtime {
+        if let Err(e) = RuntimeConstraint::parse(runtime) {
+            error("runtime", &e);
+        }
+    }
+
+    let mut capabilities = Vec::new();
+    match object.get("capabilities") {
+        None => {}
+        Some(Value::Array(items)) => {
+            for (i, item) in items.iter().enumerate() {
+                let field = format!("capabilities[{}]", i);
+                match item.as_str().map(|s| (s, Capability::parse(s))) {
+                    Some((_, Some(capability))) if !capabilities.contains(&capability) => {
+                        capabilities.push(capability)
+                    }
+                    Some((_, Some(_))) => {}
+                    Some((s, None)) => error(&field, &format!("unknown capability \"{}\"", s)),
+                    None => error(&field, "must be a string"),
+                }
+            }
+        }
+        Some(_) => error("capabilities", "must be an array of strings"),
+    }
+
+    let mut env = Vec::new();
+    match object.get("env") {
+        None => {}
+        Some(Value::Array(items)) => {
+            for (i, item) in items.iter().enumerate() {
+                let field = format!("env[{}]", i);
+                match item.as_str() {
+                    Some(s) if is_valid_env_name(s) => env.push(s.to_string()),
+                    Some(_) => error(&field, "must be a valid environment variable name"),
+                    None => error(&field, "must be a string"),
+                }
+            }
+        }
+        Some(_) => error("env", "must be an array of strings"),
+    }
+
+    match (name, version, entrypoint, runtime) {
+        (Some(name), Some(version), Some(entrypoint), Some(runtime)) if errors.is_empty() => {
+            Ok(SkillManifest {
+                name,
+                version,
+                entrypoint,
+                runtime,
+                capabilities,
+                env,
+            })
+        }
+        _ => Err(ManifestError::Invalid { errors }),
+    }
+}
+
+/// Load and validate the manifest of a skill directory
+pub fn load_manifest(skill_dir: &Path) -> Result<SkillManifest, ManifestError> {
+    let path = skill_dir.join(MANIFEST_FILE);
+    let json = match std::fs::read_to_string(&path) {
+        Ok(json) => json,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Err(ManifestError::Missing {
+                path: path.to_string_lossy().to_string(),
+            })
+        }
+        Err(e) => {
+            return Err(ManifestError::Unreadable {
+                message: e.to_string(),
+            })
+        }
+    };
+    parse_manifest(&json, Some(skill_dir))
+}
+
+/// Restrictions for running a skill, derived from what its manifest leaves undeclared
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkillPolicy {
+    /// Strip proxy variables and set `NO_NETWORK_ENV`
+    pub no_network: bool,
+    /// Working directory the script is pinned to instead of the project
+    pub pinned_cwd: Option<PathBuf>,
+}
+
+impl SkillPolicy {
+    /// `workspace` is the skill's scratch directory, used as the working directory of
+    /// skills that may not write to the project
+    pub fn for_manifest(manifest: &SkillManifest, workspace: &Path) -> Self {
+        Self {
+            no_network: !manifest.has(Capability::Network),
+            pinned_cwd: (!manifest.has(Capability::WriteProject)).then(|| workspace.to_path_buf()),
+        }
+    }
+
+    /// Proxy variables to remove from the child's environment
+    pub fn removed_env_vars(&self) -> &'static [&'static str] {
+        if self.no_network {
+            PROXY_ENV_VARS
+        } else {
+            &[]
+        }
+    }
+
+    /// Adjust the environment passed to the script
+    pub fn apply_env(&self, env: &mut HashMap<String, String>) {
+        for var in self.removed_env_vars() {
+            env.remove(*var);
+        }
+        if self.no_network {
+            env.insert(NO_NETWORK_ENV.to_string(), "1".to_string());
+        }
+    }
+}
+
+/// Scratch directory for a skill's runs, created on first use
+pub fn skill_workspace(manifest: &SkillManifest) -> Result<PathBuf, String> {
+    let dir = std::env::temp_dir()
+        .join("talkcody-skills")
+        .join(&manifest.name);
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create skill workspace {}: {}", dir.display(), e))?;
+    Ok(dir)
+}
+
+/// Parse and validate a skill's skill.json, reporting every invalid field
+#[tauri::command]
+pub fn validate_skill_manifest(skill_dir: String) -> Result<SkillManifest, ManifestError> {
+    load_manifest(Path::new(&skill_dir))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const VALID: &str = r#"{
+        "name": "pdf-tools",
+        "version": "1.2.0",
+        "entrypoint": "scripts/main.py",
+        "runtime": "python>=3.10",
+        "capabilities": ["network", "write_project"],
+        "env": ["OPENAI_API_KEY"]
+    }"#;
+
+    fn fields(error: ManifestError) -> Vec<String> {
+        match error {
+            ManifestError::Invalid { errors } => errors.into_iter().map(|e| e.field).collect(),
+            other => panic!("expected field errors, got {:?}", other),
+        }
+    }
+
+    fn manifest(capabilities: &[Capability]) -> SkillManifest {
+        SkillManifest {
+            name: "demo".to_string(),
+            version: "1.0.0".to_string(),
+            entrypoint: "main.sh".to_string(),
+            runtime: "bash".to_string(),
+            capabilities: capabilities.to_vec(),
+            env: vec![],
+        }
+    }
+
+    #[test]
+    fn test_parses_valid_manifest() {
+        let manifest = parse_manifest(VALID, None).unwrap();
+        assert_eq!(manifest.name, "pdf-tools");
+        assert_eq!(manifest.entrypoint, "scripts/main.py");
+        assert_eq!(
+            manifest.capabilities,
+            vec![Capability::Network, Capability::WriteProject]
+        );
+        assert_eq!(manifest.env, vec!["OPENAI_API_KEY"]);
+    }
+
+    #[test]
+    fn test_optional_lists_default_to_empty() {
+        let json =
+            r#"{"name": "a", "version": "0.1.0", "entrypoint": "run.sh", "runtime": "bash"}"#;
+        let manifest = parse_manifest(json, None).unwrap();
+        assert!(manifest.capabilities.is_empty());
+        assert!(manifest.env.is_empty());
+    }
+
+    #[test]
+    fn test_reports_every_invalid_field() {
+        let json = r#"{
+            "name": "Bad Name",
+            "version": "1.0",
+            "entrypoint": "../escape.py",
+            "runtime": "ruby>=3",
+            "capabilities": ["network", "root", 3],
+            "env": ["OK_VAR", "1BAD"]
+        }"#;
+        assert_eq!(
+            fields(parse_manifest(json, None).unwrap_err()),
+            vec![
+                "name",
+                "version",
+                "entrypoint",
+                "runtime",
+                "capabilities[1]",
+                "capabilities[2]",
+                "env[1]"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_missing_and_mistyped_fields() {
+        let json = r#"{"name": "a", "version": 1, "runtime": "", "env": "PATH"}"#;
+        assert_eq!(
+            fields(parse_manifest(json, None).unwrap_err()),
+            vec!["version", "entrypoint", "runtime", "env"]
+        );
+        assert!(matches!(
+            parse_manifest("[]", None),
+            Err(ManifestError::InvalidJson { .. })
+        ));
+        assert!(matches!(
+            parse_manifest("{", None),
+            Err(ManifestError::InvalidJson { .. })
+        ));
+    }
+
+    #[test]
+    fn test_load_checks_the_entrypoint_exists() {
+        let dir = TempDir::new().unwrap();
+        assert!(matches!(
+            load_manifest(dir.path()),
+            Err(ManifestError::Missing { .. })
+        ));
+
+        std::fs::write(dir.path().join(MANIFEST_FILE), VALID).unwrap();
+        assert_eq!(
+            fields(load_manifest(dir.path()).unwrap_err()),
+            vec!["entrypoint"]
+        );
+
+        std::fs::create_dir_all(dir.path().join("scripts")).unwrap();
+        std::fs::write(dir.path().join("scripts/main.py"), "print(1)\n").unwrap();
+        assert_eq!(load_manifest(dir.path()).unwrap().name, "pdf-tools");
+    }
+
+    #[test]
+    fn test_enforcement_matrix() {
+        let workspace = Path::new("/tmp/talkcody-skills/demo");
+        let cases = [
+            (vec![], true, true),
+            (vec![Capability::Network], false, true),
+            (vec![Capability::WriteProject], true, false),
+            (
+                vec![Capability::Network, Capability::WriteProject],
+                false,
+                false,
+            ),
+            (vec![Capability::WriteHome], true, true),
+        ];
+        for (capabilities, no_network, pinned) in cases {
+            let policy = SkillPolicy::for_manifest(&manifest(&capabilities), workspace);
+            assert_eq!(policy.no_network, no_network, "{:?}", capabilities);
+            assert_eq!(
+                policy.pinned_cwd.as_deref(),
+                pinned.then_some(workspace),
+                "{:?}",
+                capabilities
+            );
+        }
+    }
+
+    #[test]
+    fn test_no_network_strips_proxies_and_sets_marker() {
+        let policy = SkillPolicy::for_manifest(&manifest(&[]), Path::new("/tmp/ws"));
+        let mut env = HashMap::from([
+            ("HTTPS_PROXY".to_string(), "http://proxy:8080".to_string()),
+            ("http_proxy".to_string(), "http://proxy:8080".to_string()),
+            ("API_KEY".to_string(), "secret".to_string()),
+        ]);
+        policy.apply_env(&mut env);
+        assert!(!env.contains_key("HTTPS_PROXY"));
+        assert!(!env.contains_key("http_proxy"));
+        assert_eq!(env.get("API_KEY").map(String::as_str), Some("secret"));
+        assert_eq!(env.get(NO_NETWORK_ENV).map(String::as_str), Some("1"));
+
+        let policy =
+            SkillPolicy::for_manifest(&manifest(&[Capability::Network]), Path::new("/tmp/ws"));
+        let mut env = HashMap::from([("HTTPS_PROXY".to_string(), "http://proxy".to_string())]);
+        policy.apply_env(&mut env);
+        assert!(env.contains_key("HTTPS_PROXY"));
+        assert!(!env.contains_key(NO_NETWORK_ENV));
+    }
+}
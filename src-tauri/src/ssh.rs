@@ -0,0 +1,1006 @@
+// SSH module
+// Remote development over SSH: sessions to a dev server with SFTP file access, command
+// execution and content search, so remote projects work without an sshfs mount. ssh2 is
+// blocking, so every command runs on the blocking pool.
+
+use crate::path_wire::RawPath;
+use crate::search::{self, SearchMatch, SearchResult};
+use crate::shell_capture::shell_quote;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use ssh2::{Channel, CheckResult, HashType, KnownHostFileKind, Session, Sftp};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(15);
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+/// How long a host key prompt waits for the user before the connection is refused
+const HOST_KEY_CONFIRM_TIMEOUT: Duration = Duration::from_secs(120);
+const DEFAULT_MAX_READ_BYTES: u64 = 10 * 1024 * 1024;
+const MAX_WRITE_BYTES: usize = 50 * 1024 * 1024;
+const MAX_EXEC_OUTPUT_BYTES: usize = 4 * 1024 * 1024;
+/// Files the SFTP search fallback skips, to bound the transfer
+const MAX_SEARCH_FILE_BYTES: u64 = 1024 * 1024;
+const MAX_SEARCH_FILES: usize = 5_000;
+/// Secret store prefix for passwords and key passphrases
+const SECRET_PREFIX: &str = "ssh:";
+
+/// How to authenticate. Passwords and passphrases are never passed directly; they are
+/// stored with `ssh_save_secret` and referenced by name.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "method", rename_all = "camelCase")]
+pub enum SshAuth {
+    Agent,
+    #[serde(rename_all = "camelCase")]
+    Key {
+        key_path: String,
+        passphrase_secret: Option<String>,
+    },
+    #[serde(rename_all = "camelCase")]
+    Password {
+        password_secret: String,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum HostKeyStatus {
+    /// Not in any known hosts file
+    Unknown,
+    /// Known with a different key, which may mean the connection is being intercepted
+    Changed,
+}
+
+/// Payload of the host key event; answered with `ssh_confirm_host_key`
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct HostKeyPrompt {
+    pub request_id: String,
+    pub host: String,
+    pub port: u16,
+    pub key_type: String,
+    /// OpenSSH style, e.g. SHA256:base64
+    pub fingerprint: String,
+    pub status: HostKeyStatus,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteEntry {
+    pub name: String,
+    pub path: String,
+    pub is_directory: bool,
+    pub is_symlink: bool,
+    pub size: u64,
+    /// Seconds since the epoch
+    pub modified_time: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteExecResult {
+    pub stdout: String,
+    pub stderr: String,
+    pub exit_code: i32,
+    /// Output beyond the size cap was dropped
+    pub truncated: bool,
+}
+
+struct SshConnection {
+    session: Mutex<Session>,
+    label: String,
+}
+
+lazy_static::lazy_static! {
+    static ref SESSIONS: Mutex<HashMap<String, Arc<SshConnection>>> = Mutex::new(HashMap::new());
+    static ref PENDING_HOST_KEYS: Mutex<HashMap<String, mpsc::Sender<bool>>> =
+        Mutex::new(HashMap::new());
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum HostKeyCheck {
+    Trusted,
+    Untrusted(HostKeyStatus),
+}
+
+/// Where host keys are trusted from, and how an untrusted key is confirmed
+struct HostKeyVerifier {
+    known_hosts_files: Vec<PathBuf>,
+    /// Accepted keys are added here, never to the user's own known_hosts
+    trusted_store: PathBuf,
+    confirm: Box<dyn Fn(&HostKeyPrompt) -> bool + Send>,
+}
+
+impl HostKeyVerifier {
+    fn for_app() -> Self {
+        let trusted_store = app_known_hosts_path();
+        let mut known_hosts_files = vec![trusted_store.clone()];
+        if let Some(home) = dirs::home_dir() {
+            known_hosts_files.push(home.join(".ssh").join("known_hosts"));
+        }
+        Self {
+            known_hosts_files,
+            trusted_store,
+            confirm: Box::new(confirm_with_user),
+        }
+    }
+
+    fn verify(&self, session: &Session, host: &str, port: u16) -> Result<(), String> {
+        let status = match check_host_key(session, host, port, &self.known_hosts_files)? {
+            HostKeyCheck::Trusted => return Ok(()),
+            HostKeyCheck::Untrusted(status) => status,
+        };
+        let (_, key_type) = session.host_key().ok_or("Server sent no host key")?;
+        let prompt = HostKeyPrompt {
+            request_id: uuid::Uuid::new_v4().to_string(),
+            host: host.to_string(),
+            port,
+            key_type: format!("{:?}", key_type),
+            fingerprint: host_key_fingerprint(session)?,
+            status,
+        };
+        if !(self.confirm)(&prompt) {
+            return Err(format!("Host key for {} was not accepted", host));
+        }
+        remember_host_key(session, host, port, &self.trusted_store)
+    }
+}
+
+fn app_known_hosts_path() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("com.talkcody")
+        .join("ssh_known_hosts")
+}
+
+/// Host as written in known_hosts files
+fn known_hosts_entry(host: &str, port: u16) -> String {
+    if port == 22 {
+        host.to_string()
+    } else {
+        format!("[{}]:{}", host, port)
+    }
+}
+
+fn host_key_fingerprint(session: &Session) -> Result<String, String> {
+    use base64::Engine;
+    let hash = session
+        .host_key_hash(HashType::Sha256)
+        .ok_or("Server sent no host key")?;
+    Ok(format!(
+        "SHA256:{}",
+        base64::engine::general_purpose::STANDARD_NO_PAD.encode(hash)
+    ))
+}
+
+fn check_host_key(
+    session: &Session,
+    host: &str,
+    port: u16,
+    files: &[PathBuf],
+) -> Result<HostKeyCheck, String> {
+    let (key, _) = session.host_key().ok_or("Server sent no host key")?;
+    let mut changed = false;
+    for file in files.iter().filter(|file| file.is_file()) {
+        let mut known_hosts = session.known_hosts().map_err(|e| e.to_string())?;
+        if let Err(e) = known_hosts.read_file(file, KnownHostFileKind::OpenSSH) {
+            log::warn!("Skipping unreadable known hosts file {:?}: {}", file, e);
+            continue;
+        }
+        match known_hosts.check_port(host, port, key) {
+            CheckResult::Match => return Ok(HostKeyCheck::Trusted),
+            CheckResult::Mismatch => changed = true,
+            CheckResult::NotFound | CheckResult::Failure => {}
+        }
+    }
+    Ok(HostKeyCheck::Untrusted(if changed {
+        HostKeyStatus::Changed
+    } else {
+        HostKeyStatus::Unknown
+    }))
+}
+
+fn remember_host_key(session: &Session, host: &str, port: u16, path: &Path) -> Result<(), String> {
+    let (key, key_type) = session.host_key().ok_or("Server sent no host key")?;
+    let mut known_hosts = session.known_hosts().map_err(|e| e.to_string())?;
+    if path.is_file() {
+        known_hosts
+            .read_file(path, KnownHostFileKind::OpenSSH)
+            .map_err(|e| format!("Failed to read {:?}: {}", path, e))?;
+    }
+    known_hosts
+        .add(
+            &known_hosts_entry(host, port),
+            key,
+            "added by TalkCody",
+            key_type.into(),
+        )
+        .map_err(|e| e.to_string())?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    known_hosts
+        .write_file(path, KnownHostFileKind::OpenSSH)
+        .map_err(|e| format!("Failed to write {:?}: {}", path, e))
+}
+
+/// Ask the frontend about a host key and wait for `ssh_confirm_host_key`. Anything but
+/// an explicit accept, including no answer, refuses the key.
+fn confirm_with_user(prompt: &HostKeyPrompt) -> bool {
+    let Some(app_handle) = crate::try_get_app_handle() else {
+        return false;
+    };
+    let (sender, receiver) = mpsc::channel();
+    if let Ok(mut pending) = PENDING_HOST_KEYS.lock() {
+        pending.insert(prompt.request_id.clone(), sender);
+    }
+    let accepted = match crate::events::emit_ssh_host_key_verification(app_handle, prompt) {
+        Ok(()) => receiver
+            .recv_timeout(HOST_KEY_CONFIRM_TIMEOUT)
+            .unwrap_or(false),
+        Err(e) => {
+            log::error!("Failed to emit host key prompt: {}", e);
+            false
+        }
+    };
+    if let Ok(mut pending) = PENDING_HOST_KEYS.lock() {
+        pending.remove(&prompt.request_id);
+    }
+    accepted
+}
+
+fn secret_key(name: &str) -> String {
+    format!("{}{}", SECRET_PREFIX, name)
+}
+
+fn read_secret(name: &str) -> Result<String, String> {
+    crate::secrets::store()
+        .get(&secret_key(name))?
+        .ok_or_else(|| format!("No SSH secret named {}", name))
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+fn authenticate(session: &Session, user: &str, auth: &SshAuth) -> Result<(), String> {
+    let result = match auth {
+        SshAuth::Agent => session.userauth_agent(user),
+        SshAuth::Key {
+            key_path,
+            passphrase_secret,
+        } => {
+            let passphrase = passphrase_secret.as_deref().map(read_secret).transpose()?;
+            session.userauth_pubkey_file(user, None, &expand_home(key_path), passphrase.as_deref())
+        }
+        SshAuth::Password { password_secret } => {
+            session.userauth_password(user, &read_secret(password_secret)?)
+        }
+    };
+    result.map_err(|e| format!("Authentication failed: {}", e))?;
+    if !session.authenticated() {
+        return Err("Authentication failed".to_string());
+    }
+    Ok(())
+}
+
+fn connect_blocking(
+    host: &str,
+    port: u16,
+    user: &str,
+    auth: &SshAuth,
+    verifier: &HostKeyVerifier,
+) -> Result<String, String> {
+    let addr = (host, port)
+        .to_socket_addrs()
+        .map_err(|e| format!("Failed to resolve {}: {}", host, e))?
+        .next()
+        .ok_or_else(|| format!("Failed to resolve {}", host))?;
+    let tcp = TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT)
+        .map_err(|e| format!("Failed to connect to {}:{}: {}", host, port, e))?;
+    let mut session = Session::new().map_err(|e| e.to_string())?;
+    session.set_timeout(CONNECT_TIMEOUT.as_millis() as u32);
+    session.set_tcp_stream(tcp);
+    session
+        .handshake()
+        .map_err(|e| format!("SSH handshake failed: {}", e))?;
+    verifier.verify(&session, host, port)?;
+    authenticate(&session, user, auth)?;
+    session.set_keepalive(true, 30);
+
+    let session_id = uuid::Uuid::new_v4().to_string();
+    let label = format!("{}@{}:{}", user, host, port);
+    log::info!("SSH session {} connected to {}", session_id, label);
+    SESSIONS.lock().map_err(|e| e.to_string())?.insert(
+        session_id.clone(),
+        Arc::new(SshConnection {
+            session: Mutex::new(session),
+            label,
+        }),
+    );
+    Ok(session_id)
+}
+
+/// Run `f` with exclusive use of a session and the given timeout for each blocking call
+fn with_session<T>(
+    session_id: &str,
+    timeout: Duration,
+    f: impl FnOnce(&Session) -> Result<T, String>,
+) -> Result<T, String> {
+    let connection = SESSIONS
+        .lock()
+        .map_err(|e| e.to_string())?
+        .get(session_id)
+        .cloned()
+        .ok_or_else(|| format!("No SSH session {}", session_id))?;
+    let session = connection.session.lock().map_err(|e| e.to_string())?;
+    session.set_timeout(timeout.as_millis() as u32);
+    f(&session)
+}
+
+fn sftp(session: &Session) -> Result<Sftp, String> {
+    session
+        .sftp()
+        .map_err(|e| format!("Failed to open SFTP channel: {}", e))
+}
+
+fn list_dir_blocking(session: &Session, path: &str) -> Result<Vec<RemoteEntry>, String> {
+    let entries = sftp(session)?
+        .readdir(Path::new(path))
+        .map_err(|e| format!("Failed to list {}: {}", path, e))?;
+    let mut entries: Vec<RemoteEntry> = entries
+        .into_iter()
+        .filter_map(|(entry_path, stat)| {
+            let name = entry_path.file_name()?.to_string_lossy().to_string();
+            if name == "." || name == ".." {
+                return None;
+            }
+            Some(RemoteEntry {
+                name,
+                path: entry_path.to_string_lossy().to_string(),
+                is_directory: stat.is_dir(),
+                is_symlink: stat.file_type().is_symlink(),
+                size: stat.size.unwrap_or(0),
+                modified_time: stat.mtime,
+            })
+        })
+        .collect();
+    // Directories first, then files, both alphabetically, as in the local tree
+    entries.sort_by(|a, b| {
+        b.is_directory
+            .cmp(&a.is_directory)
+            .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+    });
+    Ok(entries)
+}
+
+fn read_file_blocking(session: &Session, path: &str, max_bytes: u64) -> Result<String, String> {
+    let sftp = sftp(session)?;
+    let stat = sftp
+        .stat(Path::new(path))
+        .map_err(|e| format!("Failed to stat {}: {}", path, e))?;
+    if let Some(size) = stat.size.filter(|size| *size > max_bytes) {
+        return Err(format!(
+            "{} is too large to open remotely ({} bytes, limit {})",
+            path, size, max_bytes
+        ));
+    }
+    let mut file = sftp
+        .open(Path::new(path))
+        .map_err(|e| format!("Failed to open {}: {}", path, e))?;
+    let mut content = Vec::new();
+    // The size can change between stat and read
+    Read::by_ref(&mut file)
+        .take(max_bytes + 1)
+        .read_to_end(&mut content)
+        .map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    if content.len() as u64 > max_bytes {
+        return Err(format!("{} is too large to open remotely", path));
+    }
+    String::from_utf8(content).map_err(|_| format!("{} is not a text file", path))
+}
+
+fn write_file_blocking(session: &Session, path: &str, content: &str) -> Result<(), String> {
+    if content.len() > MAX_WRITE_BYTES {
+        return Err(format!(
+            "Content is too large to write remotely ({} bytes, limit {})",
+            content.len(),
+            MAX_WRITE_BYTES
+        ));
+    }
+    let mut file = sftp(session)?
+        .create(Path::new(path))
+        .map_err(|e| format!("Failed to create {}: {}", path, e))?;
+    file.write_all(content.as_bytes())
+        .and_then(|_| file.flush())
+        .map_err(|e| format!("Failed to write {}: {}", path, e))
+}
+
+/// Output of a remote command, each stream capped at `MAX_EXEC_OUTPUT_BYTES`
+struct CapturedOutput {
+    stdout: Vec<u8>,
+    stderr: Vec<u8>,
+    truncated: bool,
+}
+
+/// Read stdout and stderr together until both reach EOF, keeping at most `cap` bytes of
+/// each and failing past `deadline`. Reading one stream to EOF before the other deadlocks
+/// once the command fills the unread stream's window, so the session is non-blocking here.
+fn read_output(
+    session: &Session,
+    channel: &mut Channel,
+    cap: usize,
+    deadline: Instant,
+) -> Result<CapturedOutput, String> {
+    session.set_blocking(false);
+    let result = read_output_nonblocking(channel, cap, deadline);
+    session.set_blocking(true);
+    result
+}
+
+fn read_output_nonblocking(
+    channel: &mut Channel,
+    cap: usize,
+    deadline: Instant,
+) -> Result<CapturedOutput, String> {
+    let mut output = CapturedOutput {
+        stdout: Vec::new(),
+        stderr: Vec::new(),
+        truncated: false,
+    };
+    // Stream 0 is stdout, stream 1 is stderr
+    let mut open = [true, true];
+    let mut buf = [0u8; 16 * 1024];
+    while open.contains(&true) {
+        if Instant::now() >= deadline {
+            return Err("Remote command timed out".to_string());
+        }
+        let mut progressed = false;
+        for (stream_id, target) in [(0, &mut output.stdout), (1, &mut output.stderr)] {
+            if !open[stream_id] {
+                continue;
+            }
+            match channel.stream(stream_id as i32).read(&mut buf) {
+                Ok(0) => open[stream_id] = false,
+                Ok(n) => {
+                    progressed = true;
+                    // Past the cap, keep draining so the command can finish and report its status
+                    let keep = n.min(cap.saturating_sub(target.len()));
+                    target.extend_from_slice(&buf[..keep]);
+                    output.truncated |= keep < n;
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {}
+                Err(e) => return Err(format!("Failed to read command output: {}", e)),
+            }
+        }
+        if !progressed {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+    Ok(output)
+}
+
+fn exec_blocking(
+    session: &Session,
+    command: &str,
+    timeout: Duration,
+) -> Result<RemoteExecResult, String> {
+    let deadline = Instant::now() + timeout;
+    let mut channel = session
+        .channel_session()
+        .map_err(|e| format!("Failed to open exec channel: {}", e))?;
+    channel
+        .exec(command)
+        .map_err(|e| format!("Failed to run remote command: {}", e))?;
+    let output = read_output(session, &mut channel, MAX_EXEC_OUTPUT_BYTES, deadline)?;
+    let remaining = deadline.saturating_duration_since(Instant::now());
+    session.set_timeout(remaining.as_millis().max(1) as u32);
+    channel.wait_close().map_err(|e| e.to_string())?;
+    Ok(RemoteExecResult {
+        stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+        stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+        exit_code: channel.exit_status().map_err(|e| e.to_string())?,
+        truncated: output.truncated,
+    })
+}
+
+/// Parse `path\0line:offset:content` lines, as printed by rg and grep with
+/// `--null --line-number --byte-offset`
+fn parse_search_output(
+    output: &str,
+    max_results: usize,
+    max_matches_per_file: usize,
+) -> Vec<SearchResult> {
+    let mut results: Vec<SearchResult> = Vec::new();
+    for line in output.lines() {
+        let Some((path, rest)) = line.split_once('\0') else {
+            continue;
+        };
+        let mut fields = rest.splitn(3, ':');
+        let (Some(line_number), Some(offset), Some(content)) =
+            (fields.next(), fields.next(), fields.next())
+        else {
+            continue;
+        };
+        let (Ok(line_number), Ok(byte_offset)) = (line_number.parse(), offset.parse()) else {
+            continue;
+        };
+        if results.last().map(|r| r.file_path.as_str()) != Some(path) {
+            if results.len() == max_results {
+                break;
+            }
+            results.push(SearchResult {
+                file_path: path.to_string(),
+                matches: Vec::new(),
+                raw_path: RawPath::default(),
+            });
+        }
+        let result = results.last_mut().unwrap();
+        if result.matches.len() < max_matches_per_file {
+            // The output doesn't say where on the line the match is
+            result.matches.push(SearchMatch {
+                line_number,
+                line_content: content.to_string(),
+                byte_offset,
+                ..Default::default()
+            });
+        }
+    }
+    results
+}
+
+/// Matching lines of one file, for the search fallback
+fn search_lines(content: &str, pattern: &regex::Regex, max_matches: usize) -> Vec<SearchMatch> {
+    let mut offset = 0u64;
+    let mut matches = Vec::new();
+    for (index, line) in content.split_inclusive('\n').enumerate() {
+        let text = line.trim_end_matches(['\n', '\r']);
+        let byte_ranges: Vec<_> = pattern
+            .find_iter(text)
+            .map(|m| (m.start(), m.end()))
+            .collect();
+        if !byte_ranges.is_empty() {
+            matches.push(SearchMatch::new(
+                index as u64 + 1,
+                text.to_string(),
+                offset,
+                search::char_columns(text, byte_ranges),
+            ));
+            if matches.len() == max_matches {
+                break;
+            }
+        }
+        offset += line.len() as u64;
+    }
+    matches
+}
+
+/// Remote search ignores case, like the rg and grep commands it runs
+fn search_pattern(query: &str) -> Result<regex::Regex, String> {
+    regex::RegexBuilder::new(query)
+        .case_insensitive(true)
+        .build()
+        .map_err(|e| format!("Invalid pattern: {}", e))
+}
+
+/// Search by reading files over SFTP, for servers without rg or grep
+fn search_over_sftp(
+    session: &Session,
+    query: &str,
+    root_path: &str,
+    max_results: usize,
+    max_matches_per_file: usize,
+) -> Result<Vec<SearchResult>, String> {
+    let pattern = search_pattern(query)?;
+    let sftp = sftp(session)?;
+    let mut results = Vec::new();
+    let mut pending = vec![PathBuf::from(root_path)];
+    let mut scanned = 0;
+    while let Some(dir) = pending.pop() {
+        let Ok(mut entries) = sftp.readdir(&dir) else {
+            continue;
+        };
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        for (path, stat) in entries {
+            let name = path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default();
+            if stat.is_dir() {
+                if !name.starts_with('.') && !crate::constants::should_exclude_dir(&name) {
+                    pending.push(path);
+                }
+                continue;
+            }
+            if !stat.is_file() || stat.size.unwrap_or(0) > MAX_SEARCH_FILE_BYTES {
+                continue;
+            }
+            scanned += 1;
+            if scanned > MAX_SEARCH_FILES {
+                return Ok(results);
+            }
+            let mut content = Vec::new();
+            if sftp
+                .open(&path)
+                .map_err(std::io::Error::from)
+                .and_then(|mut file| file.read_to_end(&mut content))
+                .is_err()
+            {
+                continue;
+            }
+            // Binary files are skipped, as local search does
+            let Ok(content) = String::from_utf8(content) else {
+                continue;
+            };
+            let matches = search_lines(&content, &pattern, max_matches_per_file);
+            if !matches.is_empty() {
+                results.push(SearchResult {
+                    file_path: path.to_string_lossy().to_string(),
+                    matches,
+                    raw_path: RawPath::default(),
+                });
+                if results.len() == max_results {
+                    return Ok(results);
+                }
+            }
+        }
+    }
+    Ok(results)
+}
+
+fn search_blocking(
+    session: &Session,
+    query: &str,
+    root_path: &str,
+    max_results: usize,
+    max_matches_per_file: usize,
+    timeout: Duration,
+) -> Result<Vec<SearchResult>, String> {
+    let probe = exec_blocking(
+        session,
+        "command -v rg >/dev/null 2>&1 && echo rg || (command -v grep >/dev/null 2>&1 && echo grep)",
+        timeout,
+    )?;
+    let pattern = shell_quote(query);
+    let root = shell_quote(root_path);
+    let command = match probe.stdout.trim() {
+        "rg" => format!(
+            "rg --ignore-case --null --line-number --byte-offset --no-heading --color never --max-count {} -e {} {}",
+            max_matches_per_file, pattern, root
+        ),
+        "grep" => format!(
+            "grep -r -I -E -i --null --line-number --byte-offset --exclude-dir=.git --exclude-dir=node_modules -m {} -e {} {}",
+            max_matches_per_file, pattern, root
+        ),
+        _ => {
+            return search_over_sftp(session, query, root_path, max_results, max_matches_per_file)
+        }
+    };
+    let output = exec_blocking(session, &command, timeout)?;
+    // Both tools exit with 1 when nothing matched
+    if output.exit_code > 1 && output.stdout.is_empty() {
+        return Err(format!("Remote search failed: {}", output.stderr.trim()));
+    }
+    Ok(parse_search_output(
+        &output.stdout,
+        max_results,
+        max_matches_per_file,
+    ))
+}
+
+async fn run_blocking<T: Send + 'static>(
+    f: impl FnOnce() -> Result<T, String> + Send + 'static,
+) -> Result<T, String> {
+    tauri::async_runtime::spawn_blocking(f)
+        .await
+        .map_err(|e| format!("SSH task failed: {}", e))?
+}
+
+fn timeout_or_default(timeout_ms: Option<u64>) -> Duration {
+    timeout_ms
+        .map(Duration::from_millis)
+        .unwrap_or(DEFAULT_TIMEOUT)
+}
+
+/// Store a password or key passphrase for use in `SshAuth`
+#[tauri::command]
+pub fn ssh_save_secret(name: String, value: String) -> Result<(), String> {
+    crate::secrets::store().set(&secret_key(&name), &value)
+}
+
+/// Open a session. Unknown or changed host keys are confirmed through the host key
+/// event before authenticating.
+#[tauri::command]
+pub async fn ssh_connect(
+    host: String,
+    port: Option<u16>,
+    user: String,
+    auth: SshAuth,
+) -> Result<String, String> {
+    run_blocking(move || {
+        connect_blocking(
+            &host,
+            port.unwrap_or(22),
+            &user,
+            &auth,
+            &HostKeyVerifier::for_app(),
+        )
+    })
+    .await
+}
+
+/// Answer a host key prompt
+#[tauri::command]
+pub fn ssh_confirm_host_key(request_id: String, accept: bool) -> Result<(), String> {
+    let sender = PENDING_HOST_KEYS
+        .lock()
+        .map_err(|e| e.to_string())?
+        .remove(&request_id)
+        .ok_or_else(|| format!("No pending host key prompt {}", request_id))?;
+    sender
+        .send(accept)
+        .map_err(|_| "The connection is no longer waiting".to_string())
+}
+
+#[tauri::command]
+pub async fn ssh_list_dir(session_id: String, path: String) -> Result<Vec<RemoteEntry>, String> {
+    run_blocking(move || {
+        with_session(&session_id, DEFAULT_TIMEOUT, |session| {
+            list_dir_blocking(session, &path)
+        })
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn ssh_read_file(
+    session_id: String,
+    path: String,
+    max_bytes: Option<u64>,
+) -> Result<String, String> {
+    let max_bytes = max_bytes.unwrap_or(DEFAULT_MAX_READ_BYTES);
+    run_blocking(move || {
+        with_session(&session_id, DEFAULT_TIMEOUT, |session| {
+            read_file_blocking(session, &path, max_bytes)
+        })
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn ssh_write_file(
+    session_id: String,
+    path: String,
+    content: String,
+) -> Result<(), String> {
+    let recording = crate::audit::begin(
+        "ssh_write_file",
+        || serde_json::json!({ "sessionId": session_id, "path": path, "content": content }),
+    );
+    let result = run_blocking(move || {
+        with_session(&session_id, DEFAULT_TIMEOUT, |session| {
+            write_file_blocking(session, &path, &content)
+        })
+    })
+    .await;
+    crate::audit::finish(recording, &result);
+    result
+}
+
+#[tauri::command]
+pub async fn ssh_exec(
+    session_id: String,
+    command: String,
+    timeout_ms: Option<u64>,
+) -> Result<RemoteExecResult, String> {
+    let timeout = timeout_or_default(timeout_ms);
+    let recording = crate::audit::begin(
+        "ssh_exec",
+        || serde_json::json!({ "sessionId": session_id, "command": command, "timeoutMs": timeout_ms }),
+    );
+    let result = run_blocking(move || {
+        with_session(&session_id, timeout, |session| {
+            exec_blocking(session, &command, timeout)
+        })
+    })
+    .await;
+    crate::audit::finish(recording, &result);
+    result
+}
+
+/// Case-insensitive content search on the remote host, with results shaped like those of
+/// `search_file_content`
+#[tauri::command]
+pub async fn ssh_search(
+    session_id: String,
+    query: String,
+    root_path: String,
+    max_results: Option<usize>,
+    timeout_ms: Option<u64>,
+) -> Result<Vec<SearchResult>, String> {
+    let timeout = timeout_or_default(timeout_ms);
+    run_blocking(move || {
+        with_session(&session_id, timeout, |session| {
+            search_blocking(
+                session,
+                &query,
+                &root_path,
+                max_results.unwrap_or(50),
+                10,
+                timeout,
+            )
+        })
+    })
+    .await
+}
+
+#[tauri::command]
+pub fn ssh_disconnect(session_id: String) -> Result<(), String> {
+    let connection = SESSIONS
+        .lock()
+        .map_err(|e| e.to_string())?
+        .remove(&session_id)
+        .ok_or_else(|| format!("No SSH session {}", session_id))?;
+    // An operation still running keeps the session alive until it finishes
+    if let Ok(session) = connection.session.try_lock() {
+        let _ = session.disconnect(None, "closed by client", None);
+    }
+    log::info!("SSH session {} to {} closed", session_id, connection.label);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_auth_deserialization() {
+        let auth: SshAuth = serde_json::from_str(r#"{"method":"agent"}"#).unwrap();
+        assert!(matches!(auth, SshAuth::Agent));
+        let auth: SshAuth = serde_json::from_str(
+            r#"{"method":"key","keyPath":"~/.ssh/id_ed25519","passphraseSecret":"dev"}"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            auth,
+            SshAuth::Key { ref key_path, passphrase_secret: Some(ref secret) }
+                if key_path == "~/.ssh/id_ed25519" && secret == "dev"
+        ));
+    }
+
+    #[test]
+    fn test_known_hosts_entry() {
+        assert_eq!(known_hosts_entry("dev.example.com", 22), "dev.example.com");
+        assert_eq!(known_hosts_entry("10.0.0.5", 2222), "[10.0.0.5]:2222");
+    }
+
+    #[test]
+    fn test_parse_search_output() {
+        let output = "src/a.rs\x003:40:fn main() {\nsrc/a.rs\x007:90:    main(); // a:b\nsrc/b.rs\x001:0:main\nnot a match line\n";
+        let results = parse_search_output(output, 10, 10);
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].file_path, "src/a.rs");
+        assert_eq!(results[0].matches.len(), 2);
+        assert_eq!(results[0].matches[1].line_number, 7);
+        assert_eq!(results[0].matches[1].byte_offset, 90);
+        assert_eq!(results[0].matches[1].line_content, "    main(); // a:b");
+
+        let limited = parse_search_output(output, 1, 1);
+        assert_eq!(limited.len(), 1);
+        assert_eq!(limited[0].matches.len(), 1);
+    }
+
+    #[test]
+    fn test_search_lines_offsets() {
+        let pattern = regex::Regex::new("fo+").unwrap();
+        let matches = search_lines("bar\r\nfoo\nbaz\nfooo", &pattern, 10);
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[0].line_number, 2);
+        assert_eq!(matches[0].byte_offset, 5);
+        assert_eq!(matches[0].line_content, "foo");
+        assert_eq!(matches[1].byte_offset, 13);
+        let matches = search_lines("é foo fooo", &pattern, 10);
+        assert_eq!(matches[0].match_ranges, vec![(2, 5), (6, 10)]);
+        assert_eq!((matches[0].column_start, matches[0].column_end), (2, 5));
+        assert_eq!(search_lines("foo\nfoo", &pattern, 1).len(), 1);
+    }
+
+    #[test]
+    fn test_search_pattern_ignores_case() {
+        let pattern = search_pattern("NeeDle").unwrap();
+        let matches = search_lines("needle\nNEEDLE here\nneedless", &pattern, 10);
+        assert_eq!(matches.len(), 3);
+        assert_eq!(matches[1].line_content, "NEEDLE here");
+        assert_eq!(matches[1].match_ranges, vec![(0, 6)]);
+        assert!(search_pattern("(").is_err());
+    }
+
+    /// Needs a reachable sshd: set TALKCODY_SSH_TEST_HOST (and optionally
+    /// TALKCODY_SSH_TEST_PORT, TALKCODY_SSH_TEST_USER, TALKCODY_SSH_TEST_KEY; the agent is
+    /// used without a key). The host key is accepted into a temporary known hosts file.
+    #[test]
+    fn test_remote_round_trip() {
+        let Ok(host) = std::env::var("TALKCODY_SSH_TEST_HOST") else {
+            return;
+        };
+        let port = std::env::var("TALKCODY_SSH_TEST_PORT")
+            .ok()
+            .and_then(|p| p.parse().ok())
+            .unwrap_or(22);
+        let user = std::env::var("TALKCODY_SSH_TEST_USER").unwrap_or_else(|_| "root".into());
+        let auth = match std::env::var("TALKCODY_SSH_TEST_KEY") {
+            Ok(key_path) => SshAuth::Key {
+                key_path,
+                passphrase_secret: None,
+            },
+            Err(_) => SshAuth::Agent,
+        };
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let trusted_store = temp_dir.path().join("known_hosts");
+        let verifier = HostKeyVerifier {
+            known_hosts_files: vec![trusted_store.clone()],
+            trusted_store: trusted_store.clone(),
+            confirm: Box::new(|prompt| prompt.status == HostKeyStatus::Unknown),
+        };
+
+        let session_id = connect_blocking(&host, port, &user, &auth, &verifier).unwrap();
+        assert!(trusted_store.is_file());
+        let dir = format!("/tmp/talkcody-ssh-test-{}", std::process::id());
+        with_session(&session_id, DEFAULT_TIMEOUT, |session| {
+            let result = exec_blocking(session, &format!("mkdir -p {}", dir), DEFAULT_TIMEOUT)?;
+            assert_eq!(result.exit_code, 0);
+            let file = format!("{}/hello.txt", dir);
+            write_file_blocking(session, &file, "alpha\nneedle here\n")?;
+            assert_eq!(
+                read_file_blocking(session, &file, 1024)?,
+                "alpha\nneedle here\n"
+            );
+            assert!(read_file_blocking(session, &file, 4).is_err());
+            let entries = list_dir_blocking(session, &dir)?;
+            assert_eq!(entries.len(), 1);
+            assert_eq!(entries[0].name, "hello.txt");
+
+            let results = search_blocking(session, "needle", &dir, 10, 10, DEFAULT_TIMEOUT)?;
+            assert_eq!(results.len(), 1);
+            assert_eq!(results[0].matches[0].line_number, 2);
+            let fallback = search_over_sftp(session, "needle", &dir, 10, 10)?;
+            assert_eq!(fallback[0].matches[0].byte_offset, 6);
+            let results = search_blocking(session, "NeeDLE", &dir, 10, 10, DEFAULT_TIMEOUT)?;
+            assert_eq!(results.len(), 1);
+            let fallback = search_over_sftp(session, "NeeDLE", &dir, 10, 10)?;
+            assert_eq!(fallback.len(), 1);
+
+            // Filling stderr while stdout is still open must not stall the command
+            let result = exec_blocking(
+                session,
+                "head -c 1000000 /dev/zero | tr '\\0' e >&2; echo done",
+                DEFAULT_TIMEOUT,
+            )?;
+            assert_eq!(result.stdout, "done\n");
+            assert_eq!(result.stderr.len(), 1_000_000);
+
+            let result = exec_blocking(session, "exit 3", DEFAULT_TIMEOUT)?;
+            assert_eq!(result.exit_code, 3);
+            exec_blocking(session, &format!("rm -rf {}", dir), DEFAULT_TIMEOUT)?;
+            Ok(())
+        })
+        .unwrap();
+        ssh_disconnect(session_id).unwrap();
+
+        // The second connection trusts the stored key without asking
+        let verifier = HostKeyVerifier {
+            known_hosts_files: vec![trusted_store.clone()],
+            trusted_store,
+            confirm: Box::new(|_| false),
+        };
+        let session_id = connect_blocking(&host, port, &user, &auth, &verifier).unwrap();
+        ssh_disconnect(session_id).unwrap();
+    }
+}
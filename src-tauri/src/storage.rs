@@ -0,0 +1,591 @@
+// Storage module
+// Accounts for the disk space the app's caches use and keeps them under user-set quotas.
+// Each category is a directory whose top-level entries are evicted whole, with a strategy
+// suited to what the category holds. Quotas are opt-in; the code index is only evicted
+// when explicitly allowed, since rebuilding it is expensive.
+
+use crate::database::Database;
+use crate::settings;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager, State};
+
+/// Settings key holding the quotas as JSON
+pub const STORAGE_QUOTAS_SETTING: &str = "storage_quotas";
+/// How often the maintenance scheduler enforces the quotas
+pub const QUOTA_ENFORCEMENT_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+/// Evictions kept for the next report
+const MAX_PENDING_EVICTIONS: usize = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum StorageCategory {
+    CodeIndex,
+    Logs,
+    Backups,
+    HttpCache,
+    Docsets,
+    EditJournal,
+    TempWorkspaces,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum EvictionStrategy {
+    /// Least recently accessed first
+    LeastRecentlyUsed,
+    /// Least recently modified first
+    OldestFirst,
+    /// Never evicted automatically
+    Never,
+}
+
+impl StorageCategory {
+    pub const ALL: [StorageCategory; 7] = [
+        StorageCategory::CodeIndex,
+        StorageCategory::Logs,
+        StorageCategory::Backups,
+        StorageCategory::HttpCache,
+        StorageCategory::Docsets,
+        StorageCategory::EditJournal,
+        StorageCategory::TempWorkspaces,
+    ];
+
+    /// Directory name under the app data directory; logs live in the app log directory
+    fn dir_name(self) -> &'static str {
+        match self {
+            StorageCategory::CodeIndex => crate::code_navigation::INDEX_DIR_NAME,
+            StorageCategory::Logs => "logs",
+            StorageCategory::Backups => "backups",
+            StorageCategory::HttpCache => "http-cache",
+            StorageCategory::Docsets => crate::docsets::DOCSETS_DIR_NAME,
+            StorageCategory::EditJournal => crate::edit_journal::JOURNAL_DIR_NAME,
+            StorageCategory::TempWorkspaces => "workspaces",
+        }
+    }
+
+    pub fn strategy(self, allow_code_index_eviction: bool) -> EvictionStrategy {
+        match self {
+            StorageCategory::HttpCache | StorageCategory::Docsets => {
+                EvictionStrategy::LeastRecentlyUsed
+            }
+            StorageCategory::Logs
+            | StorageCategory::Backups
+            | StorageCategory::EditJournal
+            | StorageCategory::TempWorkspaces => EvictionStrategy::OldestFirst,
+            // Indexes of projects not opened for a while go first
+            StorageCategory::CodeIndex if allow_code_index_eviction => {
+                EvictionStrategy::LeastRecentlyUsed
+            }
+            StorageCategory::CodeIndex => EvictionStrategy::Never,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageQuotas {
+    /// Maximum bytes per category; categories without an entry are unlimited
+    pub max_bytes: HashMap<StorageCategory, u64>,
+    pub allow_code_index_eviction: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CategoryUsage {
+    pub category: StorageCategory,
+    pub path: String,
+    pub size_bytes: u64,
+    pub file_count: u64,
+    /// Modification times of the oldest and newest file, in seconds since the epoch
+    pub oldest_modified: Option<i64>,
+    pub newest_modified: Option<i64>,
+    pub quota_bytes: Option<u64>,
+    pub strategy: EvictionStrategy,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EvictionRecord {
+    pub category: StorageCategory,
+    pub path: String,
+    pub size_bytes: u64,
+    /// Seconds since the epoch
+    pub evicted_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageReport {
+    pub categories: Vec<CategoryUsage>,
+    pub total_bytes: u64,
+    /// Evictions since the previous report
+    pub evictions: Vec<EvictionRecord>,
+}
+
+lazy_static::lazy_static! {
+    static ref QUOTAS: RwLock<StorageQuotas> = RwLock::new(StorageQuotas::default());
+    static ref PENDING_EVICTIONS: Mutex<Vec<EvictionRecord>> = Mutex::new(Vec::new());
+}
+
+/// Where each category lives on disk
+#[derive(Debug, Clone)]
+pub struct StorageLocations {
+    pub app_data_dir: PathBuf,
+    pub log_dir: PathBuf,
+}
+
+impl StorageLocations {
+    pub fn from_app(app_handle: &AppHandle) -> Result<Self, String> {
+        let path = app_handle.path();
+        Ok(Self {
+            app_data_dir: path
+                .app_data_dir()
+                .map_err(|e| format!("Failed to get app data dir: {}", e))?,
+            log_dir: path
+                .app_log_dir()
+                .map_err(|e| format!("Failed to get app log dir: {}", e))?,
+        })
+    }
+
+    pub fn category_dir(&self, category: StorageCategory) -> PathBuf {
+        match category {
+            StorageCategory::Logs => self.log_dir.clone(),
+            _ => self.app_data_dir.join(category.dir_name()),
+        }
+    }
+}
+
+/// A top-level entry of a category directory, the unit of eviction
+#[derive(Debug, Clone)]
+struct StorageItem {
+    path: PathBuf,
+    size: u64,
+    file_count: u64,
+    /// Newest modification and access times of any file inside, in seconds
+    modified: i64,
+    accessed: i64,
+    oldest_modified: i64,
+    /// In use and never evicted
+    protected: bool,
+}
+
+fn epoch_secs(time: std::io::Result<SystemTime>) -> Option<i64> {
+    time.ok()?
+        .duration_since(UNIX_EPOCH)
+        .ok()
+        .map(|d| d.as_secs() as i64)
+}
+
+fn now_secs() -> i64 {
+    epoch_secs(Ok(SystemTime::now())).unwrap_or(0)
+}
+
+/// Size and times of a file or directory tree. Symlinks are counted but not followed.
+fn measure(path: &Path) -> Option<StorageItem> {
+    let metadata = fs::symlink_metadata(path).ok()?;
+    let modified = epoch_secs(metadata.modified()).unwrap_or(0);
+    let mut item = StorageItem {
+        path: path.to_path_buf(),
+        size: 0,
+        file_count: 0,
+        modified: i64::MIN,
+        accessed: i64::MIN,
+        oldest_modified: i64::MAX,
+        protected: false,
+    };
+    if metadata.is_dir() {
+        for entry in fs::read_dir(path).ok()?.flatten() {
+            if let Some(child) = measure(&entry.path()) {
+                item.size += child.size;
+                item.file_count += child.file_count;
+                item.modified = item.modified.max(child.modified);
+                item.accessed = item.accessed.max(child.accessed);
+                item.oldest_modified = item.oldest_modified.min(child.oldest_modified);
+            }
+        }
+        if item.file_count == 0 {
+            item.modified = modified;
+            item.accessed = modified;
+        }
+    } else {
+        item.size = metadata.len();
+        item.file_count = 1;
+        item.modified = modified;
+        // Access times are often not updated (noatime), so never older than the last write
+        item.accessed = epoch_secs(metadata.accessed())
+            .unwrap_or(modified)
+            .max(modified);
+        item.oldest_modified = modified;
+    }
+    Some(item)
+}
+
+/// Journals still pending recovery must survive until the user resolves them
+fn pending_journal_ids(dir: &Path) -> HashSet<String> {
+    crate::edit_journal::EditJournal::new(dir.to_path_buf())
+        .pending()
+        .into_iter()
+        .map(|manifest| manifest.id)
+        .collect()
+}
+
+fn scan_category(dir: &Path, category: StorageCategory) -> Vec<StorageItem> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let pending = if category == StorageCategory::EditJournal {
+        pending_journal_ids(dir)
+    } else {
+        HashSet::new()
+    };
+    let mut items: Vec<StorageItem> = entries
+        .flatten()
+        .filter_map(|entry| measure(&entry.path()))
+        .collect();
+    for item in &mut items {
+        let name = item
+            .path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string());
+        item.protected = name.is_some_and(|name| pending.contains(&name));
+    }
+    // The most recently written item may still be in use, e.g. the active log file
+    if let Some(newest) = items.iter_mut().max_by_key(|item| item.modified) {
+        newest.protected = true;
+    }
+    items
+}
+
+/// Items to remove, in order, to bring `items` within `quota` bytes
+fn plan_eviction(items: &[StorageItem], quota: u64, strategy: EvictionStrategy) -> Vec<usize> {
+    let mut total: u64 = items.iter().map(|item| item.size).sum();
+    if total <= quota || strategy == EvictionStrategy::Never {
+        return Vec::new();
+    }
+    let mut order: Vec<usize> = (0..items.len()).filter(|&i| !items[i].protected).collect();
+    order.sort_by_key(|&i| match strategy {
+        EvictionStrategy::LeastRecentlyUsed => (items[i].accessed, items[i].modified),
+        _ => (items[i].modified, items[i].accessed),
+    });
+    let mut evict = Vec::new();
+    for i in order {
+        if total <= quota {
+            break;
+        }
+        total -= items[i].size;
+        evict.push(i);
+    }
+    evict
+}
+
+fn remove_item(path: &Path) -> std::io::Result<()> {
+    if fs::symlink_metadata(path)?.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
+
+fn record_evictions(records: &[EvictionRecord]) {
+    if let Ok(mut pending) = PENDING_EVICTIONS.lock() {
+        pending.extend_from_slice(records);
+        let overflow = pending.len().saturating_sub(MAX_PENDING_EVICTIONS);
+        pending.drain(..overflow);
+    }
+}
+
+/// Evict items from every category over its quota. Returns what was removed.
+pub fn enforce_quotas(locations: &StorageLocations, quotas: &StorageQuotas) -> Vec<EvictionRecord> {
+    let mut evicted = Vec::new();
+    for (&category, &quota) in &quotas.max_bytes {
+        let strategy = category.strategy(quotas.allow_code_index_eviction);
+        if strategy == EvictionStrategy::Never {
+            continue;
+        }
+        let items = scan_category(&locations.category_dir(category), category);
+        for i in plan_eviction(&items, quota, strategy) {
+            let item = &items[i];
+            if let Err(e) = remove_item(&item.path) {
+                log::warn!("Failed to evict {}: {}", item.path.display(), e);
+                continue;
+            }
+            log::info!(
+                "Evicted {} ({} bytes) from {:?} to stay under its {} byte quota",
+                item.path.display(),
+                item.size,
+                category,
+                quota
+            );
+            evicted.push(EvictionRecord {
+                category,
+                path: item.path.to_string_lossy().to_string(),
+                size_bytes: item.size,
+                evicted_at: now_secs(),
+            });
+        }
+    }
+    record_evictions(&evicted);
+    evicted
+}
+
+/// Maintenance job: enforce the quotas currently in effect
+pub fn run_quota_enforcement(locations: &StorageLocations) {
+    let quotas = current_quotas();
+    if quotas.max_bytes.is_empty() {
+        return;
+    }
+    let evicted = enforce_quotas(locations, &quotas);
+    if !evicted.is_empty() {
+        let bytes: u64 = evicted.iter().map(|record| record.size_bytes).sum();
+        log::info!(
+            "Storage quota pass evicted {} item(s), {} bytes",
+            evicted.len(),
+            bytes
+        );
+    }
+}
+
+pub fn build_report(locations: &StorageLocations, quotas: &StorageQuotas) -> StorageReport {
+    let categories: Vec<CategoryUsage> = StorageCategory::ALL
+        .iter()
+        .map(|&category| {
+            let dir = locations.category_dir(category);
+            let items = scan_category(&dir, category);
+            let files = items.iter().filter(|item| item.file_count > 0);
+            CategoryUsage {
+                category,
+                path: dir.to_string_lossy().to_string(),
+                size_bytes: items.iter().map(|item| item.size).sum(),
+                file_count: items.iter().map(|item| item.file_count).sum(),
+                oldest_modified: files.clone().map(|item| item.oldest_modified).min(),
+                newest_modified: files.map(|item| item.modified).max(),
+                quota_bytes: quotas.max_bytes.get(&category).copied(),
+                strategy: category.strategy(quotas.allow_code_index_eviction),
+            }
+        })
+        .collect();
+    let evictions = PENDING_EVICTIONS
+        .lock()
+        .map(|mut pending| std::mem::take(&mut *pending))
+        .unwrap_or_default();
+    StorageReport {
+        total_bytes: categories.iter().map(|c| c.size_bytes).sum(),
+        categories,
+        evictions,
+    }
+}
+
+fn current_quotas() -> StorageQuotas {
+    QUOTAS.read().map(|q| q.clone()).unwrap_or_default()
+}
+
+/// Apply the quotas persisted in the settings table
+pub async fn load_from_settings(db: &Database) -> Result<(), String> {
+    if let Some(quotas) =
+        settings::get_json_setting::<StorageQuotas>(db, STORAGE_QUOTAS_SETTING).await?
+    {
+        *QUOTAS.write().map_err(|e| e.to_string())? = quotas;
+    }
+    Ok(())
+}
+
+async fn update_quotas(
+    db: &Database,
+    update: impl FnOnce(&mut StorageQuotas),
+) -> Result<StorageQuotas, String> {
+    let mut quotas = current_quotas();
+    update(&mut quotas);
+    settings::set_json_setting(db, STORAGE_QUOTAS_SETTING, &quotas).await?;
+    *QUOTAS.write().map_err(|e| e.to_string())? = quotas.clone();
+    Ok(quotas)
+}
+
+/// Per-category disk usage, plus the evictions made since the previous report
+#[tauri::command]
+pub async fn get_storage_report(app_handle: AppHandle) -> Result<StorageReport, String> {
+    let locations = StorageLocations::from_app(&app_handle)?;
+    tauri::async_runtime::spawn_blocking(move || build_report(&locations, &current_quotas()))
+        .await
+        .map_err(|e| format!("Storage scan failed: {}", e))
+}
+
+/// Cap a category at `max_bytes`, or remove its cap with None
+#[tauri::command]
+pub async fn set_storage_quota(
+    db: State<'_, Arc<Database>>,
+    category: StorageCategory,
+    max_bytes: Option<u64>,
+) -> Result<StorageQuotas, String> {
+    update_quotas(&db, |quotas| match max_bytes {
+        Some(max_bytes) => {
+            quotas.max_bytes.insert(category, max_bytes);
+        }
+        None => {
+            quotas.max_bytes.remove(&category);
+        }
+    })
+    .await
+}
+
+/// Let quota enforcement remove code indexes; they are rebuilt when a project is opened
+#[tauri::command]
+pub async fn set_code_index_eviction(
+    db: State<'_, Arc<Database>>,
+    allowed: bool,
+) -> Result<StorageQuotas, String> {
+    update_quotas(&db, |quotas| quotas.allow_code_index_eviction = allowed).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write_aged(path: &Path, bytes: usize, age_secs: u64) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, vec![b'x'; bytes]).unwrap();
+        let time = SystemTime::now() - Duration::from_secs(age_secs);
+        let file = fs::File::options().write(true).open(path).unwrap();
+        file.set_times(fs::FileTimes::new().set_modified(time).set_accessed(time))
+            .unwrap();
+    }
+
+    fn locations(temp_dir: &TempDir) -> StorageLocations {
+        StorageLocations {
+            app_data_dir: temp_dir.path().join("data"),
+            log_dir: temp_dir.path().join("logs"),
+        }
+    }
+
+    #[test]
+    fn test_strategy_selection() {
+        use EvictionStrategy::*;
+        assert_eq!(
+            StorageCategory::HttpCache.strategy(false),
+            LeastRecentlyUsed
+        );
+        assert_eq!(StorageCategory::Backups.strategy(false), OldestFirst);
+        assert_eq!(StorageCategory::Logs.strategy(false), OldestFirst);
+        assert_eq!(StorageCategory::CodeIndex.strategy(false), Never);
+        assert_eq!(StorageCategory::CodeIndex.strategy(true), LeastRecentlyUsed);
+    }
+
+    #[test]
+    fn test_plan_eviction_quota_math() {
+        let item = |size, modified, accessed, protected| StorageItem {
+            path: PathBuf::new(),
+            size,
+            file_count: 1,
+            modified,
+            accessed,
+            oldest_modified: modified,
+            protected,
+        };
+        let items = vec![
+            item(400, 10, 90, false),
+            item(300, 20, 30, false),
+            item(500, 5, 5, true),
+            item(200, 40, 40, false),
+        ];
+        // 1400 bytes against 1000: one item is enough
+        assert_eq!(
+            plan_eviction(&items, 1000, EvictionStrategy::OldestFirst),
+            [0]
+        );
+        assert_eq!(
+            plan_eviction(&items, 1000, EvictionStrategy::LeastRecentlyUsed),
+            [1, 3]
+        );
+        // Protected items are kept even when the quota cannot be met
+        assert_eq!(
+            plan_eviction(&items, 0, EvictionStrategy::OldestFirst),
+            [0, 1, 3]
+        );
+        assert!(plan_eviction(&items, 1400, EvictionStrategy::OldestFirst).is_empty());
+        assert!(plan_eviction(&items, 0, EvictionStrategy::Never).is_empty());
+    }
+
+    #[test]
+    fn test_enforce_quotas() {
+        let temp_dir = TempDir::new().unwrap();
+        let locations = locations(&temp_dir);
+        let backups = locations.category_dir(StorageCategory::Backups);
+        write_aged(&backups.join("old.bak"), 1000, 300);
+        write_aged(&backups.join("mid/a.bak"), 600, 200);
+        write_aged(&backups.join("new.bak"), 500, 10);
+        let logs = locations.category_dir(StorageCategory::Logs);
+        write_aged(&logs.join("app.log"), 5000, 0);
+        let index = locations.category_dir(StorageCategory::CodeIndex);
+        write_aged(&index.join("a.json"), 800, 500);
+        write_aged(&index.join("b.json"), 800, 5);
+
+        let mut quotas = StorageQuotas::default();
+        quotas.max_bytes.insert(StorageCategory::Backups, 1200);
+        quotas.max_bytes.insert(StorageCategory::Logs, 100);
+        quotas.max_bytes.insert(StorageCategory::CodeIndex, 1000);
+
+        let evicted = enforce_quotas(&locations, &quotas);
+        let paths: Vec<&str> = evicted
+            .iter()
+            .map(|r| r.path.rsplit('/').next().unwrap())
+            .collect();
+        assert_eq!(paths, ["old.bak"]);
+        assert_eq!(evicted[0].size_bytes, 1000);
+        assert!(backups.join("mid/a.bak").exists());
+        // The only log is the active one, and the index needs the flag
+        assert!(logs.join("app.log").exists());
+        assert!(index.join("a.json").exists());
+
+        quotas.allow_code_index_eviction = true;
+        let evicted = enforce_quotas(&locations, &quotas);
+        assert_eq!(evicted.len(), 1);
+        assert!(!index.join("a.json").exists());
+
+        let report = build_report(&locations, &quotas);
+        let usage = |category| {
+            report
+                .categories
+                .iter()
+                .find(|c| c.category == category)
+                .unwrap()
+        };
+        let backup_usage = usage(StorageCategory::Backups);
+        assert_eq!(
+            (backup_usage.size_bytes, backup_usage.file_count),
+            (1100, 2)
+        );
+        assert_eq!(backup_usage.quota_bytes, Some(1200));
+        assert!(backup_usage.oldest_modified < backup_usage.newest_modified);
+        assert_eq!(usage(StorageCategory::HttpCache).size_bytes, 0);
+        assert_eq!(usage(StorageCategory::HttpCache).oldest_modified, None);
+        assert_eq!(report.total_bytes, 1100 + 5000 + 800);
+        // Evictions are reported once; other tests may add their own concurrently
+        assert!(report.evictions.iter().any(|r| r.path.ends_with("old.bak")));
+        assert!(report.evictions.iter().any(|r| r.path.ends_with("a.json")));
+    }
+
+    #[test]
+    fn test_pending_journals_are_kept() {
+        let temp_dir = TempDir::new().unwrap();
+        let locations = locations(&temp_dir);
+        let journal = crate::edit_journal::EditJournal::new(
+            locations.category_dir(StorageCategory::EditJournal),
+        );
+        let target = temp_dir.path().join("file.txt");
+        fs::write(&target, "original").unwrap();
+        let pending = journal.begin(std::slice::from_ref(&target)).unwrap();
+        let mut done = journal.begin(&[target]).unwrap();
+        journal.complete(&mut done).unwrap();
+
+        let mut quotas = StorageQuotas::default();
+        quotas.max_bytes.insert(StorageCategory::EditJournal, 0);
+        enforce_quotas(&locations, &quotas);
+        let dir = locations.category_dir(StorageCategory::EditJournal);
+        assert!(dir.join(&pending.id).exists());
+    }
+}
@@ -0,0 +1,842 @@
+// Tasks module
+// Per-project task definitions (build, test, lint, ...) persisted in the Database, with a
+// history of runs. Tasks run through the user's shell like execute_user_shell, stream
+// their output as events, can be cancelled through the cancellation registry and have
+// their whole process tree cleaned up. Secret environment values live in the secret store
+// and are only resolved when the task is spawned. Task variables are layered over the
+// project's active environment profile.
+
+use crate::cancellation::{self, CancellationToken};
+use crate::clock::now_ms;
+use crate::database::Database;
+use crate::env_profiles;
+use crate::process_info::{self, KillSignal};
+use crate::secrets::{self, SecretStore};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+use std::path::Path;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tauri::State;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::sync::mpsc;
+
+/// Bytes of output kept with each run
+const OUTPUT_TAIL_BYTES: usize = 16 * 1024;
+const DEFAULT_HISTORY_LIMIT: usize = 20;
+/// How often a running task checks its cancellation token
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(100);
+/// How long to keep reading output after exit, for descendants still holding the pipes
+const OUTPUT_DRAIN_TIMEOUT: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskEnvVar {
+    pub name: String,
+    /// Never returned for secret variables. Saving a secret without a value keeps the
+    /// stored one.
+    pub value: Option<String>,
+    #[serde(default)]
+    pub secret: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskDefinition {
+    pub id: String,
+    pub root_path: String,
+    pub name: String,
+    pub command: String,
+    /// Working directory, relative to the project root unless absolute
+    pub cwd: Option<String>,
+    pub env: Vec<TaskEnvVar>,
+    pub group: Option<String>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskRun {
+    pub id: i64,
+    pub task_id: String,
+    pub started_at: i64,
+    pub duration_ms: i64,
+    /// None when the process was killed by a signal
+    pub exit_code: Option<i32>,
+    pub cancelled: bool,
+    /// Last lines of stdout and stderr, interleaved as they arrived
+    pub output_tail: String,
+    /// Earlier output was dropped from the tail
+    pub output_truncated: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskOutputEvent {
+    pub task_id: String,
+    pub operation_id: Option<String>,
+    pub stream: OutputStream,
+    pub line: String,
+}
+
+/// Most recent output lines within a byte budget
+struct OutputTail {
+    lines: VecDeque<String>,
+    bytes: usize,
+    max_bytes: usize,
+    truncated: bool,
+}
+
+impl OutputTail {
+    fn new(max_bytes: usize) -> Self {
+        Self {
+            lines: VecDeque::new(),
+            bytes: 0,
+            max_bytes,
+            truncated: false,
+        }
+    }
+
+    fn push(&mut self, line: &str) {
+        self.bytes += line.len() + 1;
+        self.lines.push_back(line.to_string());
+        while self.bytes > self.max_bytes && self.lines.len() > 1 {
+            if let Some(dropped) = self.lines.pop_front() {
+                self.bytes -= dropped.len() + 1;
+                self.truncated = true;
+            }
+        }
+    }
+
+    fn text(&self) -> String {
+        let text = Vec::from(self.lines.clone()).join("\n");
+        if text.len() <= self.max_bytes {
+            return text;
+        }
+        // A single line longer than the budget: keep its end
+        let mut start = text.len() - self.max_bytes;
+        while !text.is_char_boundary(start) {
+            start += 1;
+        }
+        text[start..].to_string()
+    }
+}
+
+fn secret_key(task_id: &str, name: &str) -> String {
+    format!("{}{}", secret_prefix(task_id), name)
+}
+
+fn secret_prefix(task_id: &str) -> String {
+    format!("task-env:{}:", task_id)
+}
+
+fn opt_string(value: Option<&str>) -> Value {
+    value
+        .map(|v| Value::String(v.to_string()))
+        .unwrap_or(Value::Null)
+}
+
+async fn ensure_schema(db: &Database) -> Result<(), String> {
+    db.execute(
+        "CREATE TABLE IF NOT EXISTS tasks (
+            id TEXT PRIMARY KEY,
+            root_path TEXT NOT NULL,
+            name TEXT NOT NULL,
+            command TEXT NOT NULL,
+            cwd TEXT,
+            env TEXT NOT NULL,
+            task_group TEXT,
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL
+        )",
+        vec![],
+    )
+    .await?;
+    db.execute(
+        "CREATE INDEX IF NOT EXISTS idx_tasks_root_path ON tasks(root_path)",
+        vec![],
+    )
+    .await?;
+    db.execute(
+        "CREATE TABLE IF NOT EXISTS task_runs (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            task_id TEXT NOT NULL,
+            started_at INTEGER NOT NULL,
+            duration_ms INTEGER NOT NULL,
+            exit_code INTEGER,
+            cancelled INTEGER NOT NULL,
+            output_tail TEXT NOT NULL,
+            output_truncated INTEGER NOT NULL
+        )",
+        vec![],
+    )
+    .await?;
+    db.execute(
+        "CREATE INDEX IF NOT EXISTS idx_task_runs_task_id ON task_runs(task_id, started_at)",
+        vec![],
+    )
+    .await?;
+    Ok(())
+}
+
+fn task_from_row(row: &Value) -> TaskDefinition {
+    let get_str = |key: &str| row.get(key).and_then(|v| v.as_str()).map(String::from);
+    let get_i64 = |key: &str| row.get(key).and_then(|v| v.as_i64()).unwrap_or_default();
+    TaskDefinition {
+        id: get_str("id").unwrap_or_default(),
+        root_path: get_str("root_path").unwrap_or_default(),
+        name: get_str("name").unwrap_or_default(),
+        command: get_str("command").unwrap_or_default(),
+        cwd: get_str("cwd"),
+        env: get_str("env")
+            .and_then(|env| serde_json::from_str(&env).ok())
+            .unwrap_or_default(),
+        group: get_str("task_group"),
+        created_at: get_i64("created_at"),
+        updated_at: get_i64("updated_at"),
+    }
+}
+
+fn run_from_row(row: &Value) -> TaskRun {
+    let get_i64 = |key: &str| row.get(key).and_then(|v| v.as_i64());
+    TaskRun {
+        id: get_i64("id").unwrap_or_default(),
+        task_id: row
+            .get("task_id")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string(),
+        started_at: get_i64("started_at").unwrap_or_default(),
+        duration_ms: get_i64("duration_ms").unwrap_or_default(),
+        exit_code: get_i64("exit_code").map(|code| code as i32),
+        cancelled: get_i64("cancelled").unwrap_or_default() != 0,
+        output_tail: row
+            .get("output_tail")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string(),
+        output_truncated: get_i64("output_truncated").unwrap_or_default() != 0,
+    }
+}
+
+pub async fn get_task(db: &Database, id: &str) -> Result<Option<TaskDefinition>, String> {
+    ensure_schema(db).await?;
+    let result = db
+        .query(
+            "SELECT * FROM tasks WHERE id = ?",
+            vec![Value::String(id.to_string())],
+        )
+        .await?;
+    Ok(result.rows.first().map(task_from_row))
+}
+
+/// A task as the UI submits it for saving
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskDraft {
+    /// Existing task to update; a new task is created when absent
+    pub id: Option<String>,
+    pub root_path: String,
+    pub name: String,
+    pub command: String,
+    pub cwd: Option<String>,
+    #[serde(default)]
+    pub env: Vec<TaskEnvVar>,
+    pub group: Option<String>,
+}
+
+/// Create a task, or update it when the draft names an existing one. Secret env values
+/// go to the secret store; the stored definition keeps only their names.
+pub async fn save_task(
+    db: &Database,
+    secrets: &SecretStore,
+    draft: TaskDraft,
+) -> Result<TaskDefinition, String> {
+    let TaskDraft {
+        id,
+        root_path,
+        name,
+        command,
+        cwd,
+        env,
+        group,
+    } = draft;
+    if name.trim().is_empty() {
+        return Err("Task name cannot be empty".to_string());
+    }
+    if command.trim().is_empty() {
+        return Err("Task command cannot be empty".to_string());
+    }
+    let existing = match id.as_deref() {
+        Some(id) => Some(
+            get_task(db, id)
+                .await?
+                .ok_or_else(|| format!("Task {} not found", id))?,
+        ),
+        None => {
+            ensure_schema(db).await?;
+            None
+        }
+    };
+    let id = id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+
+    let mut stored_env = Vec::with_capacity(env.len());
+    for var in env {
+        if var.name.is_empty() || var.name.contains('=') || var.name.contains('\0') {
+            return Err(format!("Invalid environment variable name: {:?}", var.name));
+        }
+        if var.secret {
+            match var.value {
+                Some(ref value) => secrets.set(&secret_key(&id, &var.name), value)?,
+                None if secrets.get(&secret_key(&id, &var.name))?.is_none() => {
+                    return Err(format!("Secret {} has no value", var.name));
+                }
+                None => {}
+            }
+            stored_env.push(TaskEnvVar { value: None, ..var });
+        } else {
+            stored_env.push(var);
+        }
+    }
+    // Drop secrets of variables that were removed or are no longer secret
+    if let Some(ref existing) = existing {
+        for old in existing.env.iter().filter(|v| v.secret) {
+            if !stored_env.iter().any(|v| v.secret && v.name == old.name) {
+                secrets.delete(&secret_key(&id, &old.name))?;
+            }
+        }
+    }
+
+    let now = now_ms();
+    let created_at = existing.as_ref().map(|t| t.created_at).unwrap_or(now);
+    let env_json = serde_json::to_string(&stored_env).map_err(|e| e.to_string())?;
+    db.execute(
+        "INSERT OR REPLACE INTO tasks (id, root_path, name, command, cwd, env, task_group, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        vec![
+            Value::String(id.clone()),
+            Value::String(root_path.clone()),
+            Value::String(name.clone()),
+            Value::String(command.clone()),
+            opt_string(cwd.as_deref()),
+            Value::String(env_json),
+            opt_string(group.as_deref()),
+            Value::Number(created_at.into()),
+            Value::Number(now.into()),
+        ],
+    )
+    .await?;
+
+    Ok(TaskDefinition {
+        id,
+        root_path,
+        name,
+        command,
+        cwd,
+        env: stored_env,
+        group,
+        created_at,
+        updated_at: now,
+    })
+}
+
+pub async fn list_tasks(db: &Database, root_path: &str) -> Result<Vec<TaskDefinition>, String> {
+    ensure_schema(db).await?;
+    let result = db
+        .query(
+            "SELECT * FROM tasks WHERE root_path = ? ORDER BY task_group, name",
+            vec![Value::String(root_path.to_string())],
+        )
+        .await?;
+    Ok(result.rows.iter().map(task_from_row).collect())
+}
+
+/// Delete a task with its run history and secrets. Returns false if it did not exist.
+pub async fn delete_task(db: &Database, secrets: &SecretStore, id: &str) -> Result<bool, String> {
+    ensure_schema(db).await?;
+    let result = db
+        .execute(
+            "DELETE FROM tasks WHERE id = ?",
+            vec![Value::String(id.to_string())],
+        )
+        .await?;
+    db.execute(
+        "DELETE FROM task_runs WHERE task_id = ?",
+        vec![Value::String(id.to_string())],
+    )
+    .await?;
+    secrets.delete_prefix(&secret_prefix(id))?;
+    Ok(result.rows_affected > 0)
+}
+
+pub async fn run_history(db: &Database, id: &str, limit: usize) -> Result<Vec<TaskRun>, String> {
+    ensure_schema(db).await?;
+    let result = db
+        .query(
+            "SELECT * FROM task_runs WHERE task_id = ? ORDER BY started_at DESC, id DESC LIMIT ?",
+            vec![
+                Value::String(id.to_string()),
+                Value::Number((limit as i64).into()),
+            ],
+        )
+        .await?;
+    Ok(result.rows.iter().map(run_from_row).collect())
+}
+
+/// Environment for a spawn, with secret values read from the store
+fn resolve_env(
+    secrets: &SecretStore,
+    task: &TaskDefinition,
+) -> Result<Vec<(String, String)>, String> {
+    task.env
+        .iter()
+        .map(|var| {
+            let value = if var.secret {
+                secrets
+                    .get(&secret_key(&task.id, &var.name))?
+                    .ok_or_else(|| {
+                        format!("Secret {} is missing from the secret store", var.name)
+                    })?
+            } else {
+                var.value.clone().unwrap_or_default()
+            };
+            Ok((var.name.clone(), value))
+        })
+        .collect()
+}
+
+fn spawn_line_reader(
+    reader: Option<impl AsyncRead + Unpin + Send + 'static>,
+    stream: OutputStream,
+    tx: mpsc::UnboundedSender<(OutputStream, String)>,
+) {
+    let Some(reader) = reader else {
+        return;
+    };
+    tokio::spawn(async move {
+        let mut lines = BufReader::new(reader).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            if tx.send((stream, line)).is_err() {
+                break;
+            }
+        }
+    });
+}
+
+struct ProcessOutcome {
+    exit_code: Option<i32>,
+    cancelled: bool,
+    tail: OutputTail,
+}
+
+async fn execute_task(
+    task: &TaskDefinition,
+    env: Vec<(String, String)>,
+    operation_id: Option<&str>,
+    token: Option<&CancellationToken>,
+) -> Result<ProcessOutcome, String> {
+    let cwd = match task.cwd.as_deref() {
+        Some(cwd) if Path::new(cwd).is_absolute() => Path::new(cwd).to_path_buf(),
+        Some(cwd) => Path::new(&task.root_path).join(cwd),
+        None => Path::new(&task.root_path).to_path_buf(),
+    };
+
+    let mut cmd = crate::user_shell_command(&task.command);
+    cmd.current_dir(&cwd)
+        .envs(env)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    let mut child = cmd
+        .spawn()
+        .map_err(|e| format!("Failed to spawn task {}: {}", task.name, e))?;
+    let pid = child.id();
+    if let Some(pid) = pid {
+        process_info::register_spawned_pid(pid);
+    }
+
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    spawn_line_reader(child.stdout.take(), OutputStream::Stdout, tx.clone());
+    spawn_line_reader(child.stderr.take(), OutputStream::Stderr, tx);
+
+    let app_handle = crate::try_get_app_handle();
+    let mut tail = OutputTail::new(OUTPUT_TAIL_BYTES);
+    let mut handle_line = |stream: OutputStream, line: String| {
+        tail.push(&line);
+        if let Some(app_handle) = app_handle {
+            let event = TaskOutputEvent {
+                task_id: task.id.clone(),
+                operation_id: operation_id.map(String::from),
+                stream,
+                line,
+            };
+            if let Err(e) = crate::events::emit_task_output(app_handle, &event) {
+                log::error!("Failed to emit task output: {}", e);
+            }
+        }
+    };
+
+    let mut cancelled = false;
+    let status = loop {
+        tokio::select! {
+            Some((stream, line)) = rx.recv() => handle_line(stream, line),
+            status = child.wait() => break status,
+            _ = tokio::time::sleep(CANCEL_POLL_INTERVAL), if token.is_some() && !cancelled => {
+                if token.is_some_and(|t| t.is_cancelled()) {
+                    cancelled = true;
+                    log::info!("Cancelling task {} ({:?})", task.name, pid);
+                    if let Some(pid) = pid {
+                        let killed = tokio::task::spawn_blocking(move || {
+                            process_info::kill_tree(pid, KillSignal::Kill)
+                        })
+                        .await;
+                        if let Ok(Err(e)) = killed {
+                            log::warn!("Failed to kill task process tree {}: {}", pid, e);
+                        }
+                    }
+                    let _ = child.start_kill();
+                }
+            }
+        }
+    };
+    while let Ok(Some((stream, line))) = tokio::time::timeout(OUTPUT_DRAIN_TIMEOUT, rx.recv()).await
+    {
+        handle_line(stream, line);
+    }
+    if let Some(pid) = pid {
+        process_info::unregister_spawned_pid(pid);
+    }
+
+    let status = status.map_err(|e| format!("Failed to wait for task {}: {}", task.name, e))?;
+    Ok(ProcessOutcome {
+        exit_code: if cancelled { None } else { status.code() },
+        cancelled,
+        tail,
+    })
+}
+
+/// Run a task, streaming its output as `task-output` events, and record the run. With an
+/// `operation_id` the run can be stopped through activity_cancel.
+pub async fn run_task(
+    db: &Database,
+    secrets: &SecretStore,
+    id: &str,
+    operation_id: Option<String>,
+) -> Result<TaskRun, String> {
+    let task = get_task(db, id)
+        .await?
+        .ok_or_else(|| format!("Task {} not found", id))?;
+    // The task's own variables override the project's active environment profile
+    let profile_env = env_profiles::active_env(db, secrets, &task.root_path).await?;
+    let env = env_profiles::merge_env(profile_env, resolve_env(secrets, &task)?);
+    log::info!(
+        "Running task {} in {}: {}",
+        task.name,
+        task.root_path,
+        task.command
+    );
+
+    let token = operation_id.as_deref().map(cancellation::register);
+    let started_at = now_ms();
+    let start = Instant::now();
+    let outcome = execute_task(&task, env, operation_id.as_deref(), token.as_ref()).await;
+    if let Some(ref operation_id) = operation_id {
+        cancellation::unregister(operation_id);
+    }
+    let outcome = outcome?;
+    let duration_ms = start.elapsed().as_millis() as i64;
+
+    let mut run = TaskRun {
+        id: 0,
+        task_id: task.id.clone(),
+        started_at,
+        duration_ms,
+        exit_code: outcome.exit_code,
+        cancelled: outcome.cancelled,
+        output_tail: outcome.tail.text(),
+        output_truncated: outcome.tail.truncated,
+    };
+    db.execute(
+            "INSERT INTO task_runs (task_id, started_at, duration_ms, exit_code, cancelled, output_tail, output_truncated) VALUES (?, ?, ?, ?, ?, ?, ?)",
+            vec![
+                Value::String(run.task_id.clone()),
+                Value::Number(run.started_at.into()),
+                Value::Number(run.duration_ms.into()),
+                run.exit_code
+                    .map(|code| Value::Number(code.into()))
+                    .unwrap_or(Value::Null),
+                Value::Number((run.cancelled as i64).into()),
+                Value::String(run.output_tail.clone()),
+                Value::Number((run.output_truncated as i64).into()),
+            ],
+        )
+        .await?;
+    let result = db.query("SELECT last_insert_rowid() AS id", vec![]).await?;
+    run.id = result
+        .rows
+        .first()
+        .and_then(|row| row.get("id"))
+        .and_then(|v| v.as_i64())
+        .ok_or("Failed to read task run id")?;
+    log::info!(
+        "Task {} finished in {}ms (exit code {:?}, cancelled: {})",
+        task.name,
+        run.duration_ms,
+        run.exit_code,
+        run.cancelled
+    );
+    Ok(run)
+}
+
+#[tauri::command]
+pub async fn task_save(
+    db: State<'_, Arc<Database>>,
+    task: TaskDraft,
+) -> Result<TaskDefinition, String> {
+    save_task(&db, secrets::store(), task).await
+}
+
+#[tauri::command]
+pub async fn task_list(
+    db: State<'_, Arc<Database>>,
+    root_path: String,
+) -> Result<Vec<TaskDefinition>, String> {
+    list_tasks(&db, &root_path).await
+}
+
+#[tauri::command]
+pub async fn task_delete(db: State<'_, Arc<Database>>, id: String) -> Result<bool, String> {
+    delete_task(&db, secrets::store(), &id).await
+}
+
+#[tauri::command]
+pub async fn task_run(
+    db: State<'_, Arc<Database>>,
+    id: String,
+    operation_id: Option<String>,
+) -> Result<TaskRun, String> {
+    run_task(&db, secrets::store(), &id, operation_id).await
+}
+
+#[tauri::command]
+pub async fn task_run_history(
+    db: State<'_, Arc<Database>>,
+    id: String,
+    limit: Option<usize>,
+) -> Result<Vec<TaskRun>, String> {
+    run_history(&db, &id, limit.unwrap_or(DEFAULT_HISTORY_LIMIT)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    async fn test_db(temp_dir: &TempDir) -> Database {
+        let db = Database::new(
+            temp_dir
+                .path()
+                .join("test.db")
+                .to_string_lossy()
+                .to_string(),
+        );
+        db.connect().await.unwrap();
+        db
+    }
+
+    fn draft(root_path: &str, name: &str, command: &str, env: Vec<TaskEnvVar>) -> TaskDraft {
+        TaskDraft {
+            id: None,
+            root_path: root_path.to_string(),
+            name: name.to_string(),
+            command: command.to_string(),
+            cwd: None,
+            env,
+            group: None,
+        }
+    }
+
+    fn var(name: &str, value: Option<&str>, secret: bool) -> TaskEnvVar {
+        TaskEnvVar {
+            name: name.to_string(),
+            value: value.map(String::from),
+            secret,
+        }
+    }
+
+    #[test]
+    fn test_output_tail_keeps_latest_lines() {
+        let mut tail = OutputTail::new(11);
+        for line in ["one", "two", "three", "four"] {
+            tail.push(line);
+        }
+        assert_eq!(tail.text(), "three\nfour");
+        assert!(tail.truncated);
+
+        let mut long = OutputTail::new(4);
+        long.push("abcdefgh");
+        assert_eq!(long.text(), "efgh");
+    }
+
+    #[tokio::test]
+    async fn test_task_crud_with_secret_env() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = test_db(&temp_dir).await;
+        let secrets = SecretStore::new(temp_dir.path().join("secrets.json"));
+
+        let task = save_task(
+            &db,
+            &secrets,
+            TaskDraft {
+                group: Some("test".to_string()),
+                ..draft(
+                    "/project",
+                    "test",
+                    "cargo test",
+                    vec![
+                        var("RUST_LOG", Some("debug"), false),
+                        var("API_TOKEN", Some("s3cret"), true),
+                    ],
+                )
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(task.env[1].value, None);
+        save_task(&db, &secrets, draft("/other", "lint", "eslint", vec![]))
+            .await
+            .unwrap();
+
+        let tasks = list_tasks(&db, "/project").await.unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].command, "cargo test");
+        assert_eq!(tasks[0].env, task.env);
+        let raw = db
+            .query(
+                "SELECT env FROM tasks WHERE id = ?",
+                vec![Value::String(task.id.clone())],
+            )
+            .await
+            .unwrap();
+        assert!(!raw.rows[0]["env"].as_str().unwrap().contains("s3cret"));
+        let env = resolve_env(&secrets, &tasks[0]).unwrap();
+        assert!(env.contains(&("API_TOKEN".to_string(), "s3cret".to_string())));
+
+        // Updating without the secret's value keeps it; the creation time is preserved
+        let updated = save_task(
+            &db,
+            &secrets,
+            TaskDraft {
+                id: Some(task.id.clone()),
+                cwd: Some("crates".to_string()),
+                ..draft(
+                    "/project",
+                    "test all",
+                    "cargo test --workspace",
+                    vec![var("API_TOKEN", None, true)],
+                )
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(updated.created_at, task.created_at);
+        let tasks = list_tasks(&db, "/project").await.unwrap();
+        assert_eq!(tasks[0].name, "test all");
+        assert_eq!(tasks[0].cwd.as_deref(), Some("crates"));
+        assert_eq!(
+            resolve_env(&secrets, &tasks[0]).unwrap(),
+            vec![("API_TOKEN".to_string(), "s3cret".to_string())]
+        );
+
+        let missing = TaskDraft {
+            id: Some("missing".to_string()),
+            ..draft("/project", "x", "y", vec![])
+        };
+        assert!(save_task(&db, &secrets, missing).await.is_err());
+        assert!(delete_task(&db, &secrets, &task.id).await.unwrap());
+        assert!(!delete_task(&db, &secrets, &task.id).await.unwrap());
+        assert!(list_tasks(&db, "/project").await.unwrap().is_empty());
+        assert_eq!(
+            secrets.get(&secret_key(&task.id, "API_TOKEN")).unwrap(),
+            None
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_run_records_history() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = test_db(&temp_dir).await;
+        let secrets = SecretStore::new(temp_dir.path().join("secrets.json"));
+        let root_path = temp_dir.path().to_string_lossy().to_string();
+
+        let task = save_task(
+            &db,
+            &secrets,
+            draft(
+                &root_path,
+                "greet",
+                "echo \"hello $GREETING_NAME\"; exit 3",
+                vec![var("GREETING_NAME", Some("tasks"), true)],
+            ),
+        )
+        .await
+        .unwrap();
+
+        let run = run_task(&db, &secrets, &task.id, None).await.unwrap();
+        assert_eq!(run.exit_code, Some(3));
+        assert!(!run.cancelled);
+        assert!(run.output_tail.contains("hello tasks"));
+
+        let second = run_task(&db, &secrets, &task.id, Some("task-test-op".to_string()))
+            .await
+            .unwrap();
+        let history = run_history(&db, &task.id, 10).await.unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].id, second.id);
+        assert_eq!(history[1].id, run.id);
+        assert_eq!(history[1].exit_code, Some(3));
+        assert!(history[1].output_tail.contains("hello tasks"));
+        assert_eq!(run_history(&db, &task.id, 1).await.unwrap().len(), 1);
+        assert!(cancellation::token("task-test-op").is_none());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_cancelled_run_is_recorded() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = test_db(&temp_dir).await;
+        let secrets = SecretStore::new(temp_dir.path().join("secrets.json"));
+        let root_path = temp_dir.path().to_string_lossy().to_string();
+        let task = save_task(
+            &db,
+            &secrets,
+            draft(&root_path, "sleep", "echo started; sleep 30", vec![]),
+        )
+        .await
+        .unwrap();
+
+        let canceller = tokio::spawn(async {
+            tokio::time::sleep(Duration::from_millis(500)).await;
+            cancellation::cancel("task-cancel-op")
+        });
+        let start = Instant::now();
+        let run = run_task(&db, &secrets, &task.id, Some("task-cancel-op".to_string()))
+            .await
+            .unwrap();
+        assert!(canceller.await.unwrap());
+        assert!(run.cancelled);
+        assert_eq!(run.exit_code, None);
+        assert!(start.elapsed() < Duration::from_secs(10));
+    }
+}
@@ -1,17 +1,23 @@
+use crate::database::Database;
+use crate::env_profiles;
+use crate::process_info::KillSignal;
 use log::{error, info, warn};
 use portable_pty::{native_pty_system, CommandBuilder, PtySize};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::io::{Read, Write};
 use std::sync::{Arc, Mutex};
-use tauri::{AppHandle, Emitter};
+use tauri::{AppHandle, State};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PtySpawnResult {
     pub pty_id: String,
+    /// Pid of the shell, for process tree inspection
+    pub pid: Option<u32>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct PtyOutput {
     pub pty_id: String,
     pub data: String,
@@ -27,8 +33,69 @@ struct PtySession {
 
 type PtyRegistry = Arc<Mutex<HashMap<String, PtySession>>>;
 
+/// Input typed into a PTY since the last Enter, tracked for command history
+#[derive(Debug, Default)]
+struct PtyInputLine {
+    cwd: Option<String>,
+    line: String,
+    /// Set when the line was edited in ways we can't reproduce (arrow keys, tab
+    /// completion, shell history recall), so the buffered text is not trustworthy
+    unreliable: bool,
+}
+
+impl PtyInputLine {
+    /// Feed raw terminal input, returning every command completed by Enter
+    fn push_input(&mut self, data: &str) -> Vec<String> {
+        let mut commands = Vec::new();
+        for ch in data.chars() {
+            match ch {
+                '\r' | '\n' => {
+                    let command = self.line.trim();
+                    if !self.unreliable && !command.is_empty() {
+                        commands.push(command.to_string());
+                    }
+                    self.line.clear();
+                    self.unreliable = false;
+                }
+                // Backspace / delete
+                '\u{7f}' | '\u{8}' => {
+                    self.line.pop();
+                }
+                // Ctrl-C / Ctrl-U discard the current line
+                '\u{3}' | '\u{15}' => {
+                    self.line.clear();
+                    self.unreliable = false;
+                }
+                c if c.is_control() => self.unreliable = true,
+                c => self.line.push(c),
+            }
+        }
+        commands
+    }
+}
+
 lazy_static::lazy_static! {
     static ref PTY_SESSIONS: PtyRegistry = Arc::new(Mutex::new(HashMap::new()));
+    static ref PTY_INPUT_LINES: Mutex<HashMap<String, PtyInputLine>> = Mutex::new(HashMap::new());
+}
+
+/// Record commands entered in a PTY session to command history.
+/// The cwd is the directory the session was spawned in.
+fn record_pty_input(pty_id: &str, data: &str) {
+    if !crate::command_history::is_recording_enabled() {
+        return;
+    }
+    let mut input_lines = PTY_INPUT_LINES.lock().unwrap();
+    let input = input_lines.entry(pty_id.to_string()).or_default();
+    for command in input.push_input(data) {
+        crate::command_history::record_command(
+            command,
+            input.cwd.clone(),
+            None,
+            None,
+            crate::command_history::CommandSource::Pty,
+        );
+    }
 }
 
 /// Windows shell configurations: (command, version_args, shell_args)
@@ -118,6 +185,7 @@ fn get_shell_args(shell: &str) -> Vec<&'static str> {
 fn spawn_with_fallback(
     slave: &Box<dyn portable_pty::SlavePty + Send>,
     cwd: Option<&str>,
+    env: &[(String, String)],
 ) -> Result<(String, Box<dyn portable_pty::Child + Send + Sync>), String> {
     let mut last_error = String::new();
 
@@ -138,6 +206,9 @@ fn spawn_with_fallback(
         // Set TERM environment variable to enable color support
         cmd.env("TERM", "xterm-256color");
         cmd.env("COLORTERM", "truecolor");
+        for (name, value) in env {
+            cmd.env(name, value);
+        }
 
         if !shell_args.is_empty() {
             cmd.args(*shell_args);
@@ -175,14 +246,21 @@ fn spawn_with_fallback(
 }
 
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn pty_spawn(
     app: AppHandle,
+    db: State<'_, Arc<Database>>,
     cwd: Option<String>,
     cols: Option<u16>,
     rows: Option<u16>,
     preferred_shell: Option<String>,
+    root_path: Option<String>,
+    env: Option<HashMap<String, String>>,
 ) -> Result<PtySpawnResult, String> {
     info!("Spawning new PTY session");
+    // Call-site variables override the project's active environment profile, which
+    // overrides the app's environment
+    let env = env_profiles::spawn_env_for(&db, root_path.as_deref(), env).await?;
 
     let pty_system = native_pty_system();
     let pty_size = PtySize {
@@ -212,6 +290,9 @@ pub async fn pty_spawn(
                 // Set TERM environment variable to enable color support
                 cmd.env("TERM", "xterm-256color");
                 cmd.env("COLORTERM", "truecolor");
+                for (name, value) in &env {
+                    cmd.env(name, value);
+                }
                 let args = get_shell_args(shell);
                 if !args.is_empty() {
                     cmd.args(&args);
@@ -224,11 +305,11 @@ pub async fn pty_spawn(
                 (shell.to_string(), child)
             } else {
                 // Auto mode: try shells in order with fallback
-                spawn_with_fallback(&pair.slave, cwd.as_deref())?
+                spawn_with_fallback(&pair.slave, cwd.as_deref(), &env)?
             }
         } else {
             // No preference: auto mode
-            spawn_with_fallback(&pair.slave, cwd.as_deref())?
+            spawn_with_fallback(&pair.slave, cwd.as_deref(), &env)?
         }
     };
 
@@ -247,6 +328,9 @@ pub async fn pty_spawn(
         // This is critical for production builds launched from GUI (not terminal)
         cmd.env("TERM", "xterm-256color");
         cmd.env("COLORTERM", "truecolor");
+        for (name, value) in &env {
+            cmd.env(name, value);
+        }
 
         // Check if shell is zsh and disable PROMPT_SP (partial line marker)
         if shell.contains("zsh") {
@@ -284,6 +368,11 @@ pub async fn pty_spawn(
         .try_clone_reader()
         .map_err(|e| format!("Failed to clone reader: {}", e))?;
 
+    let pid = child.process_id();
+    if let Some(pid) = pid {
+        crate::process_info::register_spawned_pid(pid);
+    }
+
     // Store the session - keeping child and master alive is critical on Windows
     {
         let mut sessions = PTY_SESSIONS.lock().unwrap();
@@ -296,6 +385,13 @@ pub async fn pty_spawn(
             },
         );
     }
+    PTY_INPUT_LINES.lock().unwrap().insert(
+        pty_id.clone(),
+        PtyInputLine {
+            cwd: cwd.clone(),
+            ..Default::default()
+        },
+    );
 
     // Spawn a blocking task to read output (blocking I/O needs spawn_blocking)
     let pty_id_clone = pty_id.clone();
@@ -309,9 +405,9 @@ pub async fn pty_spawn(
                 Ok(0) => {
                     info!("PTY {} closed (read returned 0)", pty_id_clone);
                     // PTY closed
-                    let _ = app_clone.emit(
-                        "pty-output",
-                        PtyOutput {
+                    let _ = crate::events::emit_pty_output(
+                        &app_clone,
+                        &PtyOutput {
                             pty_id: pty_id_clone.clone(),
                             data: String::new(),
                         },
@@ -321,9 +417,9 @@ pub async fn pty_spawn(
                 Ok(n) => {
                     let data = String::from_utf8_lossy(&buffer[..n]).to_string();
                     info!("PTY {} read {} bytes", pty_id_clone, n);
-                    let emit_result = app_clone.emit(
-                        "pty-output",
-                        PtyOutput {
+                    let emit_result = crate::events::emit_pty_output(
+                        &app_clone,
+                        &PtyOutput {
                             pty_id: pty_id_clone.clone(),
                             data,
                         },
@@ -342,14 +438,18 @@ pub async fn pty_spawn(
         // Clean up session
         let mut sessions = PTY_SESSIONS.lock().unwrap();
         sessions.remove(&pty_id_clone);
+        if let Some(pid) = pid {
+            crate::process_info::unregister_spawned_pid(pid);
+        }
+        PTY_INPUT_LINES.lock().unwrap().remove(&pty_id_clone);
 
         // Emit close event
-        let _ = app_clone.emit("pty-close", serde_json::json!({ "pty_id": pty_id_clone }));
+        let _ = crate::events::emit_pty_close(&app_clone, &pty_id_clone);
     });
 
     // Child is now stored in the session, not dropped here
 
-    Ok(PtySpawnResult { pty_id })
+    Ok(PtySpawnResult { pty_id, pid })
 }
 
 #[tauri::command]
@@ -370,6 +470,7 @@ pub fn pty_write(pty_id: String, data: String) -> Result<(), String> {
             error!("Failed to flush PTY {}: {}", pty_id, e);
             format!("Failed to flush PTY: {}", e)
         })?;
+        record_pty_input(&pty_id, &data);
         info!("pty_write successful for {}", pty_id);
         Ok(())
     } else {
@@ -411,6 +512,10 @@ pub fn pty_kill(pty_id: String) -> Result<(), String> {
     let mut sessions = PTY_SESSIONS.lock().unwrap();
 
     if let Some(mut session) = sessions.remove(&pty_id) {
+        PTY_INPUT_LINES.lock().unwrap().remove(&pty_id);
+        if let Some(pid) = session.child.process_id() {
+            crate::process_info::unregister_spawned_pid(pid);
+        }
         // Kill the child process if it's still running
         if let Err(e) = session.child.kill() {
             warn!("Failed to kill PTY child process {}: {}", pty_id, e);
@@ -424,6 +529,27 @@ pub fn pty_kill(pty_id: String) -> Result<(), String> {
     }
 }
 
+/// Kill every PTY session with its whole process tree, e.g. on shutdown. Returns how
+/// many sessions were killed.
+pub fn kill_all_sessions() -> usize {
+    let sessions: Vec<(String, PtySession)> = PTY_SESSIONS.lock().unwrap().drain().collect();
+    PTY_INPUT_LINES.lock().unwrap().clear();
+    let count = sessions.len();
+    for (pty_id, mut session) in sessions {
+        if let Some(pid) = session.child.process_id() {
+            // Children started from the shell outlive it unless the whole tree is killed
+            if let Err(e) = crate::process_info::kill_tree(pid, KillSignal::Kill) {
+                warn!("Failed to kill process tree of PTY {}: {}", pty_id, e);
+            }
+            crate::process_info::unregister_spawned_pid(pid);
+        }
+        if let Err(e) = session.child.kill() {
+            warn!("Failed to kill PTY child process {}: {}", pty_id, e);
+        }
+    }
+    count
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -473,6 +599,41 @@ mod tests {
     }
 
     /// Windows-specific tests
+    /// Test that typed input is split into commands on Enter
+    #[test]
+    fn test_pty_input_line_collects_commands() {
+        let mut input = PtyInputLine::default();
+        assert!(input.push_input("git sta").is_empty());
+        assert_eq!(input.push_input("tus\r"), vec!["git status".to_string()]);
+
+        // Backspace edits are applied
+        assert_eq!(
+            input.push_input("lss\u{7f} -la\r"),
+            vec!["ls -la".to_string()]
+        );
+
+        // Several commands in one write (e.g. a paste)
+        assert_eq!(
+            input.push_input("cd src\npwd\n"),
+            vec!["cd src".to_string(), "pwd".to_string()]
+        );
+    }
+
+    /// Test that lines edited with escape sequences or cancelled are not recorded
+    #[test]
+    fn test_pty_input_line_skips_unreliable_lines() {
+        let mut input = PtyInputLine::default();
+        // Arrow-up history recall
+        assert!(input.push_input("\u{1b}[A\r").is_empty());
+        // Tab completion
+        assert!(input.push_input("cat READ\t\r").is_empty());
+        // Ctrl-C clears the line
+        assert!(input.push_input("rm -rf build\u{3}").is_empty());
+        assert_eq!(input.push_input("make\r"), vec!["make".to_string()]);
+        // Empty Enter
+        assert!(input.push_input("\r").is_empty());
+    }
+
     #[cfg(target_os = "windows")]
     mod windows_tests {
         use super::*;
@@ -541,7 +702,7 @@ mod tests {
             let pair = pty_system.openpty(pty_size).expect("Failed to open PTY");
 
             // spawn_with_fallback should succeed with at least one shell
-            let result = spawn_with_fallback(&pair.slave, None);
+            let result = spawn_with_fallback(&pair.slave, None, &[]);
             assert!(
                 result.is_ok(),
                 "spawn_with_fallback should succeed: {:?}",
@@ -581,7 +742,7 @@ mod tests {
 
             // Spawn shell
             let (shell, child) =
-                spawn_with_fallback(&pair.slave, None).expect("Failed to spawn shell");
+                spawn_with_fallback(&pair.slave, None, &[]).expect("Failed to spawn shell");
             println!("Spawned shell: {}", shell);
 
             // Drop slave after spawn (as we do in pty_spawn)
@@ -660,7 +821,7 @@ mod tests {
 
             // Spawn shell
             let (_shell, child) =
-                spawn_with_fallback(&pair.slave, None).expect("Failed to spawn shell");
+                spawn_with_fallback(&pair.slave, None, &[]).expect("Failed to spawn shell");
 
             drop(pair.slave);
 
@@ -725,7 +886,7 @@ mod tests {
             let pair = pty_system.openpty(pty_size).expect("Failed to open PTY");
 
             let (_shell, child) =
-                spawn_with_fallback(&pair.slave, None).expect("Failed to spawn shell");
+                spawn_with_fallback(&pair.slave, None, &[]).expect("Failed to spawn shell");
 
             drop(pair.slave);
 
@@ -787,7 +948,7 @@ mod tests {
             let pair = pty_system.openpty(pty_size).expect("Failed to open PTY");
 
             let (_shell, child) =
-                spawn_with_fallback(&pair.slave, None).expect("Failed to spawn shell");
+                spawn_with_fallback(&pair.slave, None, &[]).expect("Failed to spawn shell");
 
             drop(pair.slave);
 
@@ -0,0 +1,22 @@
+// Test support module
+// Helpers shared by the unit tests of several modules.
+
+use std::path::Path;
+use std::process::Command;
+
+/// Run git in `dir`, failing the test when it exits unsuccessfully. Returns its stdout,
+/// trimmed.
+pub fn git(dir: &Path, args: &[&str]) -> String {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .expect("Failed to run git");
+    assert!(
+        output.status.success(),
+        "git {:?} failed: {:?}",
+        args,
+        output
+    );
+    String::from_utf8_lossy(&output.stdout).trim().to_string()
+}
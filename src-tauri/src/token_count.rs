@@ -0,0 +1,297 @@
+// Token count module
+// Counts tokens for AI context budgeting with OpenAI's BPE tokenizers (cl100k_base and
+// o200k_base) from tiktoken-rs, which bundles their vocabularies. Per-file counts are
+// cached by content hash, so budgeting the same context again is cheap.
+
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+use tiktoken_rs::CoreBPE;
+
+/// Files larger than this are skipped unless the caller raises the cap
+const DEFAULT_MAX_FILE_BYTES: u64 = 2 * 1024 * 1024;
+/// Upper bound on cached per-file counts; the cache is cleared when it is reached
+const MAX_CACHE_ENTRIES: usize = 10_000;
+/// Bytes inspected for NUL when deciding whether a file is binary
+const BINARY_SNIFF_BYTES: usize = 8192;
+
+/// Encoding and the SHA-256 of the file content
+type FileCacheKey = (Encoding, [u8; 32]);
+
+lazy_static::lazy_static! {
+    static ref CL100K: CoreBPE = tiktoken_rs::cl100k_base().expect("bundled cl100k_base vocabulary");
+    static ref O200K: CoreBPE = tiktoken_rs::o200k_base().expect("bundled o200k_base vocabulary");
+    static ref FILE_TOKEN_CACHE: Mutex<HashMap<FileCacheKey, usize>> = Mutex::new(HashMap::new());
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Encoding {
+    #[default]
+    #[serde(rename = "cl100k_base", alias = "cl100k")]
+    Cl100k,
+    #[serde(rename = "o200k_base", alias = "o200k")]
+    O200k,
+}
+
+impl Encoding {
+    /// The tokenizer, built from the bundled vocabulary on first use
+    fn tokenizer(self) -> &'static CoreBPE {
+        match self {
+            Encoding::Cl100k => &CL100K,
+            Encoding::O200k => &O200K,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenCountItem {
+    /// File path, or None for an inline text
+    pub path: Option<String>,
+    pub tokens: usize,
+    /// Why a file was not counted (too large, binary, unreadable)
+    pub skipped: Option<String>,
+    pub cached: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenEstimate {
+    pub encoding: Encoding,
+    /// Inline texts first, then files, each in request order
+    pub items: Vec<TokenCountItem>,
+    pub total: usize,
+}
+
+// ============================================================================
+// Counting
+// ============================================================================
+
+pub struct TokenCounter {
+    encoding: Encoding,
+}
+
+impl TokenCounter {
+    pub fn new(encoding: Encoding) -> Self {
+        Self { encoding }
+    }
+
+    /// Tokens in `text`. Special tokens like `<|endoftext|>` are counted as plain text,
+    /// since files and prompts are sent that way.
+    pub fn count(&self, text: &str) -> usize {
+        self.encoding.tokenizer().encode_ordinary(text).len()
+    }
+
+    /// Count a file's tokens, reusing the cached count for identical content
+    fn count_file(&self, path: &str, max_bytes: u64) -> TokenCountItem {
+        let skipped = |reason: String| TokenCountItem {
+            path: Some(path.to_string()),
+            tokens: 0,
+            skipped: Some(reason),
+            cached: false,
+        };
+
+        let file_path = Path::new(path);
+        let is_binary_extension = file_path
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| crate::constants::is_binary_extension(&e.to_lowercase()));
+        if is_binary_extension {
+            return skipped("binary file".to_string());
+        }
+        match std::fs::metadata(file_path) {
+            Ok(metadata) if !metadata.is_file() => return skipped("not a file".to_string()),
+            Ok(metadata) if metadata.len() > max_bytes => {
+                return skipped(format!(
+                    "file is {} bytes, over the {} byte limit",
+                    metadata.len(),
+                    max_bytes
+                ))
+            }
+            Ok(_) => {}
+            Err(e) => return skipped(format!("cannot read file: {}", e)),
+        }
+        let bytes = match std::fs::read(file_path) {
+            Ok(bytes) => bytes,
+            Err(e) => return skipped(format!("cannot read file: {}", e)),
+        };
+        if bytes[..bytes.len().min(BINARY_SNIFF_BYTES)].contains(&0) {
+            return skipped("binary file".to_string());
+        }
+
+        let key = (self.encoding, Sha256::digest(&bytes).into());
+        if let Some(tokens) = FILE_TOKEN_CACHE
+            .lock()
+            .ok()
+            .and_then(|c| c.get(&key).copied())
+        {
+            return TokenCountItem {
+                path: Some(path.to_string()),
+                tokens,
+                skipped: None,
+                cached: true,
+            };
+        }
+
+        let tokens = self.count(&String::from_utf8_lossy(&bytes));
+        if let Ok(mut cache) = FILE_TOKEN_CACHE.lock() {
+            if cache.len() >= MAX_CACHE_ENTRIES {
+                cache.clear();
+            }
+            cache.insert(key, tokens);
+        }
+        TokenCountItem {
+            path: Some(path.to_string()),
+            tokens,
+            skipped: None,
+            cached: false,
+        }
+    }
+
+    /// Count inline texts and files in parallel
+    pub fn count_batch(
+        &self,
+        texts: &[String],
+        file_paths: &[String],
+        max_file_bytes: u64,
+    ) -> TokenEstimate {
+        let mut items: Vec<TokenCountItem> = texts
+            .par_iter()
+            .map(|text| TokenCountItem {
+                path: None,
+                tokens: self.count(text),
+                skipped: None,
+                cached: false,
+            })
+            .collect();
+        items.extend(
+            file_paths
+                .par_iter()
+                .map(|path| self.count_file(path, max_file_bytes))
+                .collect::<Vec<_>>(),
+        );
+        TokenEstimate {
+            encoding: self.encoding,
+            total: items.iter().map(|item| item.tokens).sum(),
+            items,
+        }
+    }
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Token count for a single text with cl100k_base
+#[tauri::command]
+pub fn estimate_tokens(text: String) -> usize {
+    TokenCounter::new(Encoding::default()).count(&text).max(1)
+}
+
+/// Token counts for many texts and files at once, with a total
+#[tauri::command]
+pub async fn estimate_tokens_batch(
+    texts: Option<Vec<String>>,
+    file_paths: Option<Vec<String>>,
+    encoding: Option<Encoding>,
+    max_file_bytes: Option<u64>,
+) -> Result<TokenEstimate, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        TokenCounter::new(encoding.unwrap_or_default()).count_batch(
+            &texts.unwrap_or_default(),
+            &file_paths.unwrap_or_default(),
+            max_file_bytes.unwrap_or(DEFAULT_MAX_FILE_BYTES),
+        )
+    })
+    .await
+    .map_err(|e| format!("Token counting task failed: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_counts_match_tiktoken() {
+        // Counts published in OpenAI's "How to count tokens with tiktoken" guide
+        let cases = [
+            ("tiktoken is great!", 6, 6),
+            ("antidisestablishmentarianism", 6, 6),
+            ("2 + 2 = 4", 7, 7),
+            ("お誕生日おめでとう", 9, 8),
+            ("hello world", 2, 2),
+        ];
+        let cl100k = TokenCounter::new(Encoding::Cl100k);
+        let o200k = TokenCounter::new(Encoding::O200k);
+        for (text, cl100k_tokens, o200k_tokens) in cases {
+            assert_eq!(cl100k.count(text), cl100k_tokens, "cl100k_base: {:?}", text);
+            assert_eq!(o200k.count(text), o200k_tokens, "o200k_base: {:?}", text);
+        }
+        // Token ids, not just counts, come from the real vocabulary
+        assert_eq!(
+            Encoding::Cl100k
+                .tokenizer()
+                .encode_ordinary("tiktoken is great!"),
+            vec![83, 1609, 5963, 374, 2294, 0]
+        );
+        assert_eq!(cl100k.count(""), 0);
+    }
+
+    #[test]
+    fn test_batch_counts_files_with_cache_and_skips() {
+        let temp_dir = TempDir::new().unwrap();
+        let text_path = temp_dir.path().join("notes.md");
+        let binary_path = temp_dir.path().join("data.bin");
+        let large_path = temp_dir.path().join("large.txt");
+        std::fs::write(&text_path, "hello world").unwrap();
+        std::fs::write(&binary_path, [1u8, 0, 2, 3]).unwrap();
+        std::fs::write(&large_path, "x".repeat(200)).unwrap();
+        let paths: Vec<String> = [&text_path, &binary_path, &large_path]
+            .iter()
+            .map(|p| p.to_string_lossy().to_string())
+            .collect();
+
+        let counter = TokenCounter::new(Encoding::O200k);
+        let texts = vec!["Hello, world!".to_string()];
+        let first = counter.count_batch(&texts, &paths, 100);
+        assert_eq!(first.items.len(), 4);
+        assert_eq!(first.items[0].path, None);
+        assert_eq!(first.items[0].tokens, 4);
+        assert_eq!(first.items[1].tokens, 2);
+        assert_eq!(first.items[2].skipped.as_deref(), Some("binary file"));
+        assert!(first.items[3].skipped.as_ref().unwrap().contains("limit"));
+        assert_eq!(first.total, 6);
+
+        // Same content is served from the cache, changed content is recounted
+        let second = counter.count_batch(&[], &paths[..1], 100);
+        assert!(second.items[0].cached);
+        std::fs::write(&text_path, "hello there world").unwrap();
+        let third = counter.count_batch(&[], &paths[..1], 100);
+        assert!(!third.items[0].cached);
+        assert_eq!(third.items[0].tokens, 3);
+    }
+
+    #[test]
+    fn test_one_megabyte_file_counts_quickly() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("big.rs");
+        let line = "    let value = compute_something(index, &items[index]); // 说明\n";
+        let content = line.repeat(1024 * 1024 / line.len() + 1);
+        std::fs::write(&path, &content).unwrap();
+
+        let start = Instant::now();
+        let estimate = TokenCounter::new(Encoding::Cl100k).count_batch(
+            &[],
+            &[path.to_string_lossy().to_string()],
+            DEFAULT_MAX_FILE_BYTES,
+        );
+        let elapsed = start.elapsed();
+        assert!(estimate.total > content.len() / 8, "{}", estimate.total);
+        assert!(elapsed.as_secs() < 5, "took {:?}", elapsed);
+    }
+}
@@ -0,0 +1,645 @@
+// Undo module
+// Journal of file changes made by the backend, so the latest one can be undone
+// independently of editor undo stacks. Before files are written, their prior contents are
+// captured: gzip-compressed under app_data_dir/undo-journal/<id>/ up to a size cap, or as a
+// reference to the HEAD blob when a larger file is clean in git. Files that look like they
+// hold secrets are recorded without content. Moves are recorded as from/to pairs and undone
+// by moving back. Only the most recent operations are kept.
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use git2::{Repository, Status};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashSet, VecDeque};
+use std::fs::{self, File};
+use std::io::{self, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+use crate::clock::now_ms;
+use crate::edit_journal::write_file_atomically;
+
+pub(crate) const UNDO_DIR_NAME: &str = "undo-journal";
+const OPERATION_FILE: &str = "operation.json";
+
+/// Operations kept; older ones are dropped with their stored content
+pub const MAX_OPERATIONS: usize = 50;
+/// Largest file whose content is stored in the journal
+pub const MAX_STORED_BYTES: u64 = 1024 * 1024;
+
+static UNDO_JOURNAL: OnceLock<UndoJournal> = OnceLock::new();
+
+/// How a file's prior content can be restored
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum PriorContent {
+    /// The file did not exist; undoing removes it
+    Absent,
+    /// Compressed copy inside the operation's directory
+    #[serde(rename_all = "camelCase")]
+    Stored { blob_file: String },
+    /// Too large to store, but identical to the blob at HEAD
+    #[serde(rename_all = "camelCase")]
+    GitBlob { repo_path: String, oid: String },
+    /// Not stored because the file may contain secrets
+    Withheld,
+    /// Too large to store and not clean in git
+    TooLarge,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UndoFileEntry {
+    pub path: String,
+    /// SHA-256 before the operation; None when the file did not exist
+    pub before_hash: Option<String>,
+    /// SHA-256 the operation left behind; None when it left no file
+    pub after_hash: Option<String>,
+    pub prior: PriorContent,
+}
+
+/// A file or folder the operation moved; undoing moves it back
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UndoMove {
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UndoOperation {
+    pub id: String,
+    /// What made the change, e.g. "Workspace edit"
+    pub label: String,
+    /// Unix timestamp in milliseconds
+    pub created_at_ms: i64,
+    pub files: Vec<UndoFileEntry>,
+    /// In the order they were made
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub moves: Vec<UndoMove>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UndoSkippedFile {
+    pub path: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UndoRevertResult {
+    pub operation_id: String,
+    pub restored: Vec<String>,
+    /// Files changed again after the operation; left untouched
+    pub conflicted: Vec<String>,
+    /// Files whose prior content was not kept
+    pub skipped: Vec<UndoSkippedFile>,
+}
+
+/// Prior contents captured by `UndoJournal::begin`, turned into an operation by `finish`
+pub struct UndoCapture {
+    operation: UndoOperation,
+}
+
+impl UndoCapture {
+    pub fn id(&self) -> &str {
+        &self.operation.id
+    }
+
+    /// Note a move made by the operation. A destination it replaced must have been
+    /// captured by `begin` to be restored.
+    pub fn record_move(&mut self, from: &Path, to: &Path) {
+        self.operation.moves.push(UndoMove {
+            from: from.to_string_lossy().to_string(),
+            to: to.to_string_lossy().to_string(),
+        });
+    }
+}
+
+/// A capture tied to its journal, for writers that go ahead without undo when capturing
+/// fails; see `UndoJournal::track`
+pub struct PendingUndo<'a> {
+    journal: &'a UndoJournal,
+    capture: UndoCapture,
+}
+
+impl PendingUndo<'_> {
+    pub fn record_move(&mut self, from: &Path, to: &Path) {
+        self.capture.record_move(from, to);
+    }
+
+    /// Record the operation when it changed anything, else drop it. Returns the
+    /// operation id, or None when nothing was recorded.
+    pub fn complete(self, changed: bool) -> Option<String> {
+        if !changed {
+            self.journal.discard(self.capture);
+            return None;
+        }
+        let label = self.capture.operation.label.clone();
+        match self.journal.finish(self.capture) {
+            Ok(id) => Some(id),
+            Err(e) => {
+                log::warn!("Failed to record {} for undo: {}", label, e);
+                None
+            }
+        }
+    }
+}
+
+/// Whether a file name looks like it holds credentials (.env files, keys, secrets)
+pub fn is_secret_path(path: &Path) -> bool {
+    let Some(name) = path.file_name().map(|n| n.to_string_lossy().to_lowercase()) else {
+        return false;
+    };
+    name == ".env"
+        || name.starts_with(".env.")
+        || name.contains("key")
+        || name.contains("secret")
+        || name.ends_with(".pem")
+}
+
+/// SHA-256 of a file; None when there is no file at `path`
+fn hash_file(path: &Path) -> io::Result<Option<String>> {
+    if fs::metadata(path).is_ok_and(|m| m.is_dir()) {
+        return Ok(None);
+    }
+    match File::open(path) {
+        Ok(file) => {
+            let mut hasher = Sha256::new();
+            io::copy(&mut BufReader::new(file), &mut hasher)?;
+            Ok(Some(hex::encode(hasher.finalize())))
+        }
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Repository and blob id of a file identical to its HEAD version
+fn clean_git_blob(path: &Path) -> Option<(String, String)> {
+    let path = fs::canonicalize(path).ok()?;
+    let repo = Repository::discover(path.parent()?).ok()?;
+    let workdir = fs::canonicalize(repo.workdir()?).ok()?;
+    let relative = path.strip_prefix(&workdir).ok()?;
+    if repo.status_file(relative).ok()? != Status::CURRENT {
+        return None;
+    }
+    let entry = repo
+        .head()
+        .ok()?
+        .peel_to_tree()
+        .ok()?
+        .get_path(relative)
+        .ok()?;
+    Some((
+        workdir.to_string_lossy().to_string(),
+        entry.id().to_string(),
+    ))
+}
+
+pub struct UndoJournal {
+    root: PathBuf,
+    max_stored_bytes: u64,
+    /// Oldest first
+    operations: Mutex<VecDeque<UndoOperation>>,
+}
+
+impl UndoJournal {
+    /// Open the journal, loading the operations left on disk by earlier runs
+    pub fn new(root: PathBuf) -> Self {
+        Self::with_limit(root, MAX_STORED_BYTES)
+    }
+
+    fn with_limit(root: PathBuf, max_stored_bytes: u64) -> Self {
+        let mut operations: Vec<UndoOperation> = fs::read_dir(&root)
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .filter_map(|entry| fs::read_to_string(entry.path().join(OPERATION_FILE)).ok())
+                    .filter_map(|json| serde_json::from_str(&json).ok())
+                    .collect()
+            })
+            .unwrap_or_default();
+        operations.sort_by_key(|op| op.created_at_ms);
+        let journal = Self {
+            root,
+            max_stored_bytes,
+            operations: Mutex::new(operations.into()),
+        };
+        journal.evict();
+        journal
+    }
+
+    fn operation_dir(&self, id: &str) -> Result<PathBuf, String> {
+        // Ids are generated UUIDs; reject anything that could escape the journal root
+        if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+            return Err(format!("Invalid undo operation id: {}", id));
+        }
+        Ok(self.root.join(id))
+    }
+
+    fn capture_prior(&self, dir: &Path, index: usize, path: &Path) -> Result<PriorContent, String> {
+        let metadata = match fs::metadata(path) {
+            Ok(metadata) if metadata.is_file() => metadata,
+            Ok(_) => return Err(format!("{} is not a regular file", path.display())),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(PriorContent::Absent),
+            Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+        };
+        if is_secret_path(path) {
+            return Ok(PriorContent::Withheld);
+        }
+        if metadata.len() > self.max_stored_bytes {
+            return Ok(match clean_git_blob(path) {
+                Some((repo_path, oid)) => PriorContent::GitBlob { repo_path, oid },
+                None => PriorContent::TooLarge,
+            });
+        }
+        let blob_file = format!("{}.gz", index);
+        let store = || -> io::Result<()> {
+            fs::create_dir_all(dir)?;
+            let mut encoder =
+                GzEncoder::new(File::create(dir.join(&blob_file))?, Compression::default());
+            io::copy(&mut BufReader::new(File::open(path)?), &mut encoder)?;
+            encoder.finish()?.sync_all()
+        };
+        store()
+            .map_err(|e| format!("Failed to store prior content of {}: {}", path.display(), e))?;
+        Ok(PriorContent::Stored { blob_file })
+    }
+
+    /// Capture the prior content of files about to be written or replaced
+    pub fn begin(&self, label: &str, paths: &[PathBuf]) -> Result<UndoCapture, String> {
+        let id = uuid::Uuid::new_v4().to_string();
+        let dir = self.operation_dir(&id)?;
+        let mut files = Vec::with_capacity(paths.len());
+        // A path listed twice is captured once, before the first write to it
+        let mut seen: HashSet<&Path> = HashSet::new();
+        let unique = paths.iter().filter(|path| seen.insert(path.as_path()));
+        for (index, path) in unique.enumerate() {
+            let captured = hash_file(path)
+                .map_err(|e| format!("Failed to hash {}: {}", path.display(), e))
+                .and_then(|hash| Ok((hash, self.capture_prior(&dir, index, path)?)));
+            let (before_hash, prior) = match captured {
+                Ok(captured) => captured,
+                Err(e) => {
+                    let _ = fs::remove_dir_all(&dir);
+                    return Err(e);
+                }
+            };
+            files.push(UndoFileEntry {
+                path: path.to_string_lossy().to_string(),
+                before_hash,
+                after_hash: None,
+                prior,
+            });
+        }
+        Ok(UndoCapture {
+            operation: UndoOperation {
+                id,
+                label: label.to_string(),
+                created_at_ms: now_ms(),
+                files,
+                moves: Vec::new(),
+            },
+        })
+    }
+
+    /// `begin`, logging instead of failing: a write that can't be captured still goes
+    /// ahead, it just can't be undone
+    pub fn track(&self, label: &str, paths: &[PathBuf]) -> Option<PendingUndo<'_>> {
+        match self.begin(label, paths) {
+            Ok(capture) => Some(PendingUndo {
+                journal: self,
+                capture,
+            }),
+            Err(e) => {
+                log::warn!("{} will not be undoable: {}", label, e);
+                None
+            }
+        }
+    }
+
+    /// Record the operation once its writes succeeded, returning its id
+    pub fn finish(&self, capture: UndoCapture) -> Result<String, String> {
+        let mut operation = capture.operation;
+        for file in &mut operation.files {
+            file.after_hash = hash_file(Path::new(&file.path))
+                .map_err(|e| format!("Failed to hash {}: {}", file.path, e))?;
+        }
+        let dir = self.operation_dir(&operation.id)?;
+        let json = serde_json::to_vec_pretty(&operation)
+            .map_err(|e| format!("Failed to serialize undo operation: {}", e))?;
+        write_file_atomically(&dir.join(OPERATION_FILE), &json)
+            .map_err(|e| format!("Failed to write undo operation: {}", e))?;
+
+        let id = operation.id.clone();
+        self.operations
+            .lock()
+            .map_err(|e| e.to_string())?
+            .push_back(operation);
+        self.evict();
+        Ok(id)
+    }
+
+    /// Drop a capture whose operation failed
+    pub fn discard(&self, capture: UndoCapture) {
+        if let Ok(dir) = self.operation_dir(capture.id()) {
+            let _ = fs::remove_dir_all(dir);
+        }
+    }
+
+    fn evict(&self) {
+        let Ok(mut operations) = self.operations.lock() else {
+            return;
+        };
+        while operations.len() > MAX_OPERATIONS {
+            if let Some(oldest) = operations.pop_front() {
+                if let Ok(dir) = self.operation_dir(&oldest.id) {
+                    let _ = fs::remove_dir_all(dir);
+                }
+            }
+        }
+    }
+
+    /// Most recent operations first
+    pub fn recent(&self, limit: usize) -> Vec<UndoOperation> {
+        self.operations
+            .lock()
+            .map(|operations| operations.iter().rev().take(limit).cloned().collect())
+            .unwrap_or_default()
+    }
+
+    fn restore(&self, dir: &Path, file: &UndoFileEntry) -> Result<Option<String>, String> {
+        let path = Path::new(&file.path);
+        let content = match &file.prior {
+            PriorContent::Absent => {
+                if path.exists() {
+                    fs::remove_file(path)
+                        .map_err(|e| format!("Failed to remove {}: {}", file.path, e))?;
+                }
+                return Ok(None);
+            }
+            PriorContent::Withheld => {
+                return Ok(Some("content of secret files is not kept".to_string()))
+            }
+            PriorContent::TooLarge => {
+                return Ok(Some(format!(
+                    "file was larger than {} bytes and not clean in git",
+                    self.max_stored_bytes
+                )))
+            }
+            PriorContent::Stored { blob_file } => {
+                let mut content = Vec::new();
+                File::open(dir.join(blob_file))
+                    .and_then(|blob| GzDecoder::new(blob).read_to_end(&mut content))
+                    .map_err(|e| {
+                        format!("Failed to read stored content of {}: {}", file.path, e)
+                    })?;
+                content
+            }
+            PriorContent::GitBlob { repo_path, oid } => Repository::open(repo_path)
+                .and_then(|repo| {
+                    let oid = git2::Oid::from_str(oid)?;
+                    Ok(repo.find_blob(oid)?.content().to_vec())
+                })
+                .map_err(|e| format!("Failed to read git blob for {}: {}", file.path, e))?,
+        };
+        write_file_atomically(path, &content)
+            .map_err(|e| format!("Failed to restore {}: {}", file.path, e))?;
+        Ok(None)
+    }
+
+    /// Restore the files an operation changed. Files modified since are reported as
+    /// conflicted and left alone, as are moves whose destination is gone or whose source
+    /// is taken again. The operation is removed from the journal.
+    pub fn revert(&self, operation_id: &str) -> Result<UndoRevertResult, String> {
+        let dir = self.operation_dir(operation_id)?;
+        let operation = self
+            .recent(MAX_OPERATIONS)
+            .into_iter()
+            .find(|op| op.id == operation_id)
+            .ok_or_else(|| format!("Unknown undo operation: {}", operation_id))?;
+
+        let mut result = UndoRevertResult {
+            operation_id: operation_id.to_string(),
+            ..Default::default()
+        };
+        // Moves go back first, newest first, so replaced destinations are free again
+        let mut moved_back: HashSet<&str> = HashSet::new();
+        for moved in operation.moves.iter().rev() {
+            let (from, to) = (Path::new(&moved.from), Path::new(&moved.to));
+            if fs::symlink_metadata(to).is_err() || fs::symlink_metadata(from).is_ok() {
+                result.conflicted.push(moved.to.clone());
+                continue;
+            }
+            match crate::file_move::rename_or_copy(to, from, |a, b| fs::rename(a, b)) {
+                Ok(_) => {
+                    result.restored.push(moved.from.clone());
+                    moved_back.insert(&moved.to);
+                }
+                Err(reason) => result.skipped.push(UndoSkippedFile {
+                    path: moved.to.clone(),
+                    reason,
+                }),
+            }
+        }
+        for file in &operation.files {
+            let is_destination = operation.moves.iter().any(|m| m.to == file.path);
+            if is_destination && !moved_back.contains(file.path.as_str()) {
+                // Already reported with its move
+                continue;
+            }
+            let current = hash_file(Path::new(&file.path))
+                .map_err(|e| format!("Failed to hash {}: {}", file.path, e))?;
+            let expected = if is_destination {
+                None
+            } else {
+                file.after_hash.clone()
+            };
+            if current != expected {
+                result.conflicted.push(file.path.clone());
+                continue;
+            }
+            match self.restore(&dir, file)? {
+                None => result.restored.push(file.path.clone()),
+                Some(reason) => result.skipped.push(UndoSkippedFile {
+                    path: file.path.clone(),
+                    reason,
+                }),
+            }
+        }
+
+        if let Ok(mut operations) = self.operations.lock() {
+            operations.retain(|op| op.id != operation_id);
+        }
+        let _ = fs::remove_dir_all(&dir);
+        log::info!(
+            "Reverted {} ({}): {} restored, {} conflicted, {} skipped",
+            operation.label,
+            operation_id,
+            result.restored.len(),
+            result.conflicted.len(),
+            result.skipped.len()
+        );
+        Ok(result)
+    }
+}
+
+/// Open the journal under the app data dir; called once on startup
+pub fn init(app_data_dir: &Path) {
+    let journal = UndoJournal::new(app_data_dir.join(UNDO_DIR_NAME));
+    let count = journal.recent(MAX_OPERATIONS).len();
+    if UNDO_JOURNAL.set(journal).is_ok() && count > 0 {
+        log::info!("Loaded {} undoable operations", count);
+    }
+}
+
+/// The app's journal, once initialized
+pub fn journal() -> Option<&'static UndoJournal> {
+    UNDO_JOURNAL.get()
+}
+
+fn require_journal() -> Result<&'static UndoJournal, String> {
+    journal().ok_or_else(|| "Undo journal is not initialized".to_string())
+}
+
+#[tauri::command]
+pub fn undo_list_recent(limit: Option<usize>) -> Result<Vec<UndoOperation>, String> {
+    Ok(require_journal()?.recent(limit.unwrap_or(10)))
+}
+
+#[tauri::command]
+pub async fn undo_revert(operation_id: String) -> Result<UndoRevertResult, String> {
+    let journal = require_journal()?;
+    let recording = crate::audit::begin(
+        "undo_revert",
+        || serde_json::json!({ "operationId": operation_id }),
+    );
+    let result = tauri::async_runtime::spawn_blocking(move || journal.revert(&operation_id))
+        .await
+        .map_err(|e| format!("Undo task failed: {}", e))
+        .and_then(|result| result);
+    crate::audit::finish(recording, &result);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+    use tempfile::TempDir;
+
+    fn write_and_record(journal: &UndoJournal, edits: &[(&Path, &str)]) -> String {
+        let paths: Vec<PathBuf> = edits.iter().map(|(p, _)| p.to_path_buf()).collect();
+        let capture = journal.begin("Test edit", &paths).unwrap();
+        for (path, content) in edits {
+            fs::write(path, content).unwrap();
+        }
+        journal.finish(capture).unwrap()
+    }
+
+    #[test]
+    fn test_revert_restores_and_reports_conflicts() {
+        let temp_dir = TempDir::new().unwrap();
+        let journal = UndoJournal::new(temp_dir.path().join(UNDO_DIR_NAME));
+        let a = temp_dir.path().join("a.rs");
+        let b = temp_dir.path().join("b.rs");
+        let created = temp_dir.path().join("new.rs");
+        let env = temp_dir.path().join(".env");
+        fs::write(&a, "original a").unwrap();
+        fs::write(&b, "original b").unwrap();
+        fs::write(&env, "TOKEN=1").unwrap();
+
+        let id = write_and_record(
+            &journal,
+            &[
+                (&a, "edited a"),
+                (&b, "edited b"),
+                (&created, "new"),
+                (&env, "TOKEN=2"),
+            ],
+        );
+        // Secret content never reaches the journal
+        let operation = &journal.recent(1)[0];
+        assert_eq!(operation.files[3].prior, PriorContent::Withheld);
+        assert_eq!(
+            fs::read_dir(temp_dir.path().join(UNDO_DIR_NAME).join(&id))
+                .unwrap()
+                .count(),
+            3
+        );
+
+        // b changes again after the operation
+        fs::write(&b, "edited b again").unwrap();
+
+        let result = journal.revert(&id).unwrap();
+        assert_eq!(fs::read_to_string(&a).unwrap(), "original a");
+        assert!(!created.exists());
+        assert_eq!(result.restored.len(), 2);
+        assert_eq!(result.conflicted, vec![b.to_string_lossy().to_string()]);
+        assert_eq!(fs::read_to_string(&b).unwrap(), "edited b again");
+        assert_eq!(result.skipped.len(), 1);
+        assert_eq!(fs::read_to_string(&env).unwrap(), "TOKEN=2");
+
+        assert!(journal.recent(10).is_empty());
+        assert!(journal.revert(&id).is_err());
+    }
+
+    #[test]
+    fn test_large_files_fall_back_to_git_blob() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo = temp_dir.path().join("repo");
+        fs::create_dir(&repo).unwrap();
+        let git = |args: &[&str]| test_support::git(&repo, args);
+        git(&["init", "-b", "main"]);
+        git(&["config", "user.email", "test@test.com"]);
+        git(&["config", "user.name", "Test User"]);
+        let clean = repo.join("clean.txt");
+        let dirty = repo.join("dirty.txt");
+        fs::write(&clean, "committed content").unwrap();
+        fs::write(&dirty, "committed").unwrap();
+        git(&["add", "-A"]);
+        git(&["commit", "-m", "Initial"]);
+        fs::write(&dirty, "uncommitted content").unwrap();
+
+        let journal = UndoJournal::with_limit(temp_dir.path().join(UNDO_DIR_NAME), 8);
+        let id = write_and_record(&journal, &[(&clean, "rewritten"), (&dirty, "rewritten")]);
+
+        let operation = &journal.recent(1)[0];
+        assert!(matches!(
+            operation.files[0].prior,
+            PriorContent::GitBlob { .. }
+        ));
+        assert_eq!(operation.files[1].prior, PriorContent::TooLarge);
+
+        let result = journal.revert(&id).unwrap();
+        assert_eq!(fs::read_to_string(&clean).unwrap(), "committed content");
+        assert_eq!(result.skipped.len(), 1);
+        assert_eq!(fs::read_to_string(&dirty).unwrap(), "rewritten");
+    }
+
+    #[test]
+    fn test_journal_is_bounded_and_reloaded() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join(UNDO_DIR_NAME);
+        let journal = UndoJournal::new(root.clone());
+        let file = temp_dir.path().join("a.txt");
+        for i in 0..MAX_OPERATIONS + 2 {
+            write_and_record(&journal, &[(&file, &i.to_string())]);
+        }
+        assert_eq!(journal.recent(usize::MAX).len(), MAX_OPERATIONS);
+        assert_eq!(fs::read_dir(&root).unwrap().count(), MAX_OPERATIONS);
+
+        let newest = journal.recent(1)[0].id.clone();
+        let reloaded = UndoJournal::new(root);
+        assert_eq!(reloaded.recent(usize::MAX).len(), MAX_OPERATIONS);
+        assert_eq!(reloaded.revert(&newest).unwrap().restored.len(), 1);
+        assert_eq!(
+            fs::read_to_string(&file).unwrap(),
+            MAX_OPERATIONS.to_string()
+        );
+    }
+}
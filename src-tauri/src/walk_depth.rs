@@ -0,0 +1,181 @@
+// Walk depth module
+// How deep the search and glob walkers descend, and whether a walk was cut short by it.
+// The limit is per request with a persisted default. A walk records every directory it
+// reached at the limit that still had entries, so results can say they are incomplete
+// instead of silently missing deeply nested files.
+
+use crate::database::Database;
+use crate::settings;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tauri::State;
+
+/// Default depth when neither the request nor the settings give one. Walks used to stop
+/// at 20 levels.
+pub const DEFAULT_MAX_DEPTH: usize = 64;
+/// Upper bound for requested and configured depths
+pub const MAX_ALLOWED_DEPTH: usize = 512;
+/// Settings key holding the default depth as JSON
+pub const MAX_DEPTH_SETTING: &str = "walk_max_depth";
+
+static DEFAULT_DEPTH: AtomicUsize = AtomicUsize::new(DEFAULT_MAX_DEPTH);
+
+pub fn default_max_depth() -> usize {
+    DEFAULT_DEPTH.load(Ordering::Relaxed)
+}
+
+fn validate(depth: usize) -> Result<usize, String> {
+    if (1..=MAX_ALLOWED_DEPTH).contains(&depth) {
+        Ok(depth)
+    } else {
+        Err(format!(
+            "Walk depth must be between 1 and {}, got {}",
+            MAX_ALLOWED_DEPTH, depth
+        ))
+    }
+}
+
+/// The depth a walk uses: the requested one clamped to the allowed range, else the default
+pub fn resolve(requested: Option<usize>) -> usize {
+    requested
+        .map(|depth| depth.clamp(1, MAX_ALLOWED_DEPTH))
+        .unwrap_or_else(default_max_depth)
+}
+
+/// Whether a walk left subtrees unvisited because of its depth limit
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct DepthReport {
+    pub depth_limited: bool,
+    /// Directories at the limit that had entries below them
+    pub truncated_subtrees: usize,
+}
+
+/// Counts the directories a walk reached at its limit without descending into them.
+/// Shared between walker threads by reference.
+#[derive(Debug)]
+pub struct DepthTracker {
+    max_depth: usize,
+    truncated: AtomicUsize,
+}
+
+impl DepthTracker {
+    pub fn new(max_depth: usize) -> Self {
+        Self {
+            max_depth,
+            truncated: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    /// Call for each entry the walk yields, with its depth below the walk root
+    pub fn observe(&self, depth: usize, path: &Path, is_dir: bool) {
+        if depth == self.max_depth && is_dir && has_entries(path) {
+            self.truncated.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// For walks that list each directory anyway: a directory at `depth` whose
+    /// entries are known
+    pub fn observe_listed(&self, depth: usize, has_entries: bool) {
+        if depth >= self.max_depth && has_entries {
+            self.truncated.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn report(&self) -> DepthReport {
+        let truncated_subtrees = self.truncated.load(Ordering::Relaxed);
+        DepthReport {
+            depth_limited: truncated_subtrees > 0,
+            truncated_subtrees,
+        }
+    }
+}
+
+fn has_entries(path: &Path) -> bool {
+    std::fs::read_dir(path).is_ok_and(|mut entries| entries.next().is_some())
+}
+
+/// Apply the default depth persisted in the settings table
+pub async fn load_from_settings(db: &Database) -> Result<(), String> {
+    if let Some(depth) = settings::get_json_setting::<usize>(db, MAX_DEPTH_SETTING).await? {
+        DEFAULT_DEPTH.store(validate(depth)?, Ordering::Relaxed);
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn walk_depth_get() -> usize {
+    default_max_depth()
+}
+
+/// Set and persist the depth searches and globs use when a request doesn't give one
+#[tauri::command]
+pub async fn walk_depth_set(db: State<'_, Arc<Database>>, depth: usize) -> Result<usize, String> {
+    let depth = validate(depth)?;
+    settings::set_json_setting(&db, MAX_DEPTH_SETTING, &depth).await?;
+    DEFAULT_DEPTH.store(depth, Ordering::Relaxed);
+    log::info!("Default walk depth set to {}", depth);
+    Ok(depth)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// A chain of `levels` nested directories under `root` with `file_name` at the bottom,
+    /// so the file sits `levels + 1` entries below the root
+    pub(crate) fn nested_tree(root: &Path, levels: usize, file_name: &str) -> PathBuf {
+        let mut dir = root.to_path_buf();
+        for level in 0..levels {
+            dir.push(format!("d{}", level));
+        }
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join(file_name);
+        std::fs::write(&file, "fn deeply_nested_needle() {}\n").unwrap();
+        file
+    }
+
+    #[test]
+    fn test_resolve_and_validate() {
+        assert_eq!(resolve(Some(0)), 1);
+        assert_eq!(resolve(Some(30)), 30);
+        assert_eq!(resolve(Some(usize::MAX)), MAX_ALLOWED_DEPTH);
+        assert!(validate(0).is_err());
+        assert!(validate(MAX_ALLOWED_DEPTH + 1).is_err());
+        assert_eq!(validate(25), Ok(25));
+    }
+
+    #[test]
+    fn test_tracker_counts_only_non_empty_directories_at_the_limit() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        nested_tree(root, 3, "a.rs");
+        std::fs::create_dir_all(root.join("empty/inner")).unwrap();
+
+        let tracker = DepthTracker::new(2);
+        for entry in ignore::WalkBuilder::new(root)
+            .max_depth(Some(2))
+            .build()
+            .flatten()
+        {
+            let is_dir = entry.file_type().is_some_and(|t| t.is_dir());
+            tracker.observe(entry.depth(), entry.path(), is_dir);
+        }
+        // d0/d1 still has d2 below it; empty/inner has nothing
+        assert_eq!(
+            tracker.report(),
+            DepthReport {
+                depth_limited: true,
+                truncated_subtrees: 1,
+            }
+        );
+        assert!(!DepthTracker::new(5).report().depth_limited);
+    }
+}
@@ -0,0 +1,627 @@
+// Watched conditions
+// "Tell me when ./dist/app.wasm appears" or "when port 5173 starts listening" without
+// polling from the frontend. Each condition runs as a small task that re-checks it every
+// poll interval; file conditions under a directory a FileWatcher already covers are woken
+// by the watcher's change events instead and only stat the file as a slow fallback. Port
+// conditions attempt a TCP connect with a short timeout. When a condition is met
+// "watch-condition-met" is emitted (one-shot conditions are removed at that point), and
+// "watch-condition-timeout" when its timeout passes first.
+
+use crate::clock;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::AppHandle;
+use tokio::sync::Notify;
+
+/// Conditions that may be active at once
+pub const MAX_ACTIVE_CONDITIONS: usize = 32;
+
+/// Shorter poll intervals are raised to this
+pub const MIN_POLL_INTERVAL_MS: u64 = 250;
+
+const DEFAULT_POLL_INTERVAL_MS: u64 = 1000;
+
+/// Stat interval for file conditions the file watcher covers, in case an event is missed
+const WATCHED_FALLBACK_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Upper bound for one port probe; a closed port usually refuses well before this
+const CONNECT_TIMEOUT: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ConditionKind {
+    /// The path exists
+    FileExists { path: String },
+    /// The path's size or modification time differs from when the condition was added
+    FileChanged { path: String },
+    /// A TCP connection to host:port is accepted
+    PortOpen { host: String, port: u16 },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchConditionSpec {
+    pub condition: ConditionKind,
+    /// Base directory for relative file paths
+    #[serde(default)]
+    pub cwd: Option<String>,
+    #[serde(default)]
+    pub poll_interval_ms: Option<u64>,
+    /// Give up after this long and emit "watch-condition-timeout"
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+    /// Remove the condition the first time it is met (the default); otherwise it fires
+    /// again each time the condition becomes true anew
+    #[serde(default = "default_one_shot")]
+    pub one_shot: bool,
+    /// Also show a desktop notification when the condition is met
+    #[serde(default)]
+    pub notify: bool,
+    #[serde(default)]
+    pub label: Option<String>,
+}
+
+fn default_one_shot() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchCondition {
+    pub id: String,
+    pub condition: ConditionKind,
+    pub label: Option<String>,
+    pub poll_interval_ms: u64,
+    pub timeout_ms: Option<u64>,
+    pub one_shot: bool,
+    pub notify: bool,
+    pub created_at: u64,
+    pub met_count: u32,
+    pub last_met_at: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchConditionEvent {
+    pub id: String,
+    pub condition: ConditionKind,
+    pub label: Option<String>,
+    pub at: u64,
+}
+
+/// What a condition looked like at one check; compared with the previous check to decide
+/// whether the condition has just become true
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Observation {
+    Flag(bool),
+    Fingerprint(Option<(SystemTime, u64)>),
+}
+
+impl Observation {
+    fn is_met(previous: &Observation, current: &Observation) -> bool {
+        match (previous, current) {
+            (Observation::Flag(before), Observation::Flag(now)) => *now && !before,
+            (Observation::Fingerprint(before), Observation::Fingerprint(now)) => before != now,
+            _ => false,
+        }
+    }
+}
+
+/// A condition with file paths resolved
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Probe {
+    FileExists(PathBuf),
+    FileChanged(PathBuf),
+    PortOpen(String, u16),
+}
+
+impl Probe {
+    fn new(condition: &ConditionKind, cwd: Option<&str>) -> Result<Self, String> {
+        let resolve = |path: &str| -> Result<PathBuf, String> {
+            let path = Path::new(path);
+            if path.is_absolute() {
+                return Ok(path.to_path_buf());
+            }
+            match cwd {
+                Some(cwd) => Ok(Path::new(cwd).join(path)),
+                None => Err(format!(
+                    "Relative path {} needs a cwd to resolve against",
+                    path.display()
+                )),
+            }
+        };
+        match condition {
+            ConditionKind::FileExists { path } => Ok(Probe::FileExists(resolve(path)?)),
+            ConditionKind::FileChanged { path } => Ok(Probe::FileChanged(resolve(path)?)),
+            ConditionKind::PortOpen { host, port } => {
+                if *port == 0 {
+                    return Err("Port must be between 1 and 65535".to_string());
+                }
+                let host = if host.trim().is_empty() {
+                    "127.0.0.1".to_string()
+                } else {
+                    host.trim().to_string()
+                };
+                Ok(Probe::PortOpen(host, *port))
+            }
+        }
+    }
+
+    fn path(&self) -> Option<&Path> {
+        match self {
+            Probe::FileExists(path) | Probe::FileChanged(path) => Some(path),
+            Probe::PortOpen(..) => None,
+        }
+    }
+
+    async fn observe(&self) -> Observation {
+        match self {
+            Probe::FileExists(path) => {
+                Observation::Flag(tokio::fs::try_exists(path).await.unwrap_or(false))
+            }
+            Probe::FileChanged(path) => {
+                let fingerprint = tokio::fs::metadata(path)
+                    .await
+                    .ok()
+                    .map(|metadata| (metadata.modified().unwrap_or(UNIX_EPOCH), metadata.len()));
+                Observation::Fingerprint(fingerprint)
+            }
+            Probe::PortOpen(host, port) => {
+                let connect = tokio::net::TcpStream::connect((host.as_str(), *port));
+                let open = matches!(
+                    tokio::time::timeout(CONNECT_TIMEOUT, connect).await,
+                    Ok(Ok(_))
+                );
+                Observation::Flag(open)
+            }
+        }
+    }
+
+    /// Initial state: file and port conditions that already hold count as met on the
+    /// first check, while a changed-file condition compares against the file as it is now
+    async fn baseline(&self) -> Observation {
+        match self {
+            Probe::FileChanged(_) => self.observe().await,
+            _ => Observation::Flag(false),
+        }
+    }
+}
+
+struct ActiveCondition {
+    info: WatchCondition,
+    path: Option<PathBuf>,
+    wake: Arc<Notify>,
+    task: Option<tokio::task::AbortHandle>,
+}
+
+#[derive(Default)]
+struct ConditionRegistry {
+    conditions: HashMap<String, ActiveCondition>,
+    /// Roots FileWatchers currently watch, counted since windows may share a root
+    watched_roots: HashMap<PathBuf, usize>,
+}
+
+impl ConditionRegistry {
+    fn covers(&self, path: &Path) -> bool {
+        self.watched_roots.keys().any(|root| path.starts_with(root))
+            && crate::file_watcher::FileWatcher::should_watch_path(path)
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref REGISTRY: Mutex<ConditionRegistry> = Mutex::new(ConditionRegistry::default());
+}
+
+static NEXT_CONDITION_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Called by FileWatcher when it starts watching a root
+pub fn register_watched_root(root: &Path) {
+    let mut registry = REGISTRY.lock().unwrap();
+    *registry
+        .watched_roots
+        .entry(root.to_path_buf())
+        .or_insert(0) += 1;
+}
+
+/// Called by FileWatcher when it stops watching a root
+pub fn unregister_watched_root(root: &Path) {
+    let mut registry = REGISTRY.lock().unwrap();
+    if let Some(count) = registry.watched_roots.get_mut(root) {
+        *count -= 1;
+        if *count == 0 {
+            registry.watched_roots.remove(root);
+        }
+    }
+}
+
+/// Wake file conditions on paths the file watcher reported as changed. Creating a file in
+/// a new directory only reports the directory, so conditions below a reported path wake too.
+pub fn notify_paths(paths: &[PathBuf]) {
+    let registry = REGISTRY.lock().unwrap();
+    for condition in registry.conditions.values() {
+        let Some(ref target) = condition.path else {
+            continue;
+        };
+        if paths.iter().any(|changed| target.starts_with(changed)) {
+            condition.wake.notify_one();
+        }
+    }
+}
+
+fn is_covered(path: Option<&Path>) -> bool {
+    path.is_some_and(|path| REGISTRY.lock().unwrap().covers(path))
+}
+
+type EmitFn = fn(&AppHandle, &WatchConditionEvent) -> tauri::Result<()>;
+
+fn emit(emit_event: EmitFn, payload: &WatchConditionEvent) {
+    // No app handle in unit tests; the registry is still updated
+    let Some(app_handle) = crate::try_get_app_handle() else {
+        return;
+    };
+    if let Err(e) = emit_event(app_handle, payload) {
+        log::error!("Failed to emit watch condition {}: {}", payload.id, e);
+    }
+}
+
+fn describe(condition: &ConditionKind) -> String {
+    match condition {
+        ConditionKind::FileExists { path } => format!("{} exists", path),
+        ConditionKind::FileChanged { path } => format!("{} changed", path),
+        ConditionKind::PortOpen { host, port } => format!("{}:{} is listening", host, port),
+    }
+}
+
+fn show_notification(payload: &WatchConditionEvent) {
+    use tauri_plugin_notification::NotificationExt;
+
+    let Some(app_handle) = crate::try_get_app_handle() else {
+        return;
+    };
+    let title = payload
+        .label
+        .clone()
+        .unwrap_or_else(|| "Condition met".to_string());
+    if let Err(e) = app_handle
+        .notification()
+        .builder()
+        .title(title)
+        .body(describe(&payload.condition))
+        .show()
+    {
+        log::warn!("Failed to show watch condition notification: {}", e);
+    }
+}
+
+/// Record that a condition was met and return the event payload, or None when the
+/// condition was removed meanwhile. One-shot conditions are removed here.
+fn record_met(id: &str) -> Option<(WatchConditionEvent, bool, bool)> {
+    let mut registry = REGISTRY.lock().unwrap();
+    let condition = registry.conditions.get_mut(id)?;
+    let at = clock::now_ms() as u64;
+    condition.info.met_count += 1;
+    condition.info.last_met_at = Some(at);
+    let payload = WatchConditionEvent {
+        id: id.to_string(),
+        condition: condition.info.condition.clone(),
+        label: condition.info.label.clone(),
+        at,
+    };
+    let one_shot = condition.info.one_shot;
+    let notify = condition.info.notify;
+    if one_shot {
+        registry.conditions.remove(id);
+    }
+    Some((payload, one_shot, notify))
+}
+
+fn record_timeout(id: &str) -> Option<WatchConditionEvent> {
+    let condition = REGISTRY.lock().unwrap().conditions.remove(id)?;
+    Some(WatchConditionEvent {
+        id: id.to_string(),
+        condition: condition.info.condition,
+        label: condition.info.label,
+        at: clock::now_ms() as u64,
+    })
+}
+
+async fn run_condition(
+    id: String,
+    probe: Probe,
+    mut previous: Observation,
+    poll_interval: Duration,
+    deadline: Option<Instant>,
+    wake: Arc<Notify>,
+) {
+    loop {
+        let current = probe.observe().await;
+        if Observation::is_met(&previous, &current) {
+            let Some((payload, one_shot, notify)) = record_met(&id) else {
+                return;
+            };
+            log::info!(
+                "Watch condition {} met: {}",
+                id,
+                describe(&payload.condition)
+            );
+            emit(crate::events::emit_watch_condition_met, &payload);
+            if notify {
+                show_notification(&payload);
+            }
+            if one_shot {
+                return;
+            }
+        }
+        previous = current;
+
+        let interval = if is_covered(probe.path()) {
+            WATCHED_FALLBACK_INTERVAL.max(poll_interval)
+        } else {
+            poll_interval
+        };
+        let mut sleep_for = interval;
+        if let Some(deadline) = deadline {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                if let Some(payload) = record_timeout(&id) {
+                    log::info!("Watch condition {} timed out", id);
+                    emit(crate::events::emit_watch_condition_timeout, &payload);
+                }
+                return;
+            }
+            sleep_for = sleep_for.min(remaining);
+        }
+        tokio::select! {
+            _ = wake.notified() => {}
+            _ = tokio::time::sleep(sleep_for) => {}
+        }
+    }
+}
+
+/// Validate a spec, register it and start checking it. Returns the new condition.
+pub async fn add_condition(spec: WatchConditionSpec) -> Result<WatchCondition, String> {
+    let probe = Probe::new(&spec.condition, spec.cwd.as_deref())?;
+    let poll_interval_ms = spec
+        .poll_interval_ms
+        .unwrap_or(DEFAULT_POLL_INTERVAL_MS)
+        .max(MIN_POLL_INTERVAL_MS);
+    // Take the baseline before registering, so changes right after this call are seen
+    let baseline = probe.baseline().await;
+
+    let id = format!(
+        "watch-condition-{}",
+        NEXT_CONDITION_ID.fetch_add(1, Ordering::Relaxed)
+    );
+    let info = WatchCondition {
+        id: id.clone(),
+        condition: spec.condition,
+        label: spec.label,
+        poll_interval_ms,
+        timeout_ms: spec.timeout_ms,
+        one_shot: spec.one_shot,
+        notify: spec.notify,
+        created_at: clock::now_ms() as u64,
+        met_count: 0,
+        last_met_at: None,
+    };
+    let wake = Arc::new(Notify::new());
+    {
+        let mut registry = REGISTRY.lock().unwrap();
+        if registry.conditions.len() >= MAX_ACTIVE_CONDITIONS {
+            return Err(format!(
+                "Too many active watch conditions (limit {})",
+                MAX_ACTIVE_CONDITIONS
+            ));
+        }
+        registry.conditions.insert(
+            id.clone(),
+            ActiveCondition {
+                info: info.clone(),
+                path: probe.path().map(Path::to_path_buf),
+                wake: wake.clone(),
+                task: None,
+            },
+        );
+    }
+
+    let deadline = spec
+        .timeout_ms
+        .map(|timeout| Instant::now() + Duration::from_millis(timeout));
+    let task = tokio::spawn(run_condition(
+        id.clone(),
+        probe,
+        baseline,
+        Duration::from_millis(poll_interval_ms),
+        deadline,
+        wake,
+    ));
+    if let Some(condition) = REGISTRY.lock().unwrap().conditions.get_mut(&id) {
+        condition.task = Some(task.abort_handle());
+    }
+    log::info!(
+        "Watch condition {} added: {}",
+        id,
+        describe(&info.condition)
+    );
+    Ok(info)
+}
+
+pub fn list_conditions() -> Vec<WatchCondition> {
+    let registry = REGISTRY.lock().unwrap();
+    let mut conditions: Vec<WatchCondition> = registry
+        .conditions
+        .values()
+        .map(|condition| condition.info.clone())
+        .collect();
+    conditions.sort_by(|a, b| a.created_at.cmp(&b.created_at).then(a.id.cmp(&b.id)));
+    conditions
+}
+
+pub fn remove_condition(id: &str) -> bool {
+    let removed = REGISTRY.lock().unwrap().conditions.remove(id);
+    match removed {
+        Some(condition) => {
+            if let Some(task) = condition.task {
+                task.abort();
+            }
+            true
+        }
+        None => false,
+    }
+}
+
+/// Start watching for a file to appear or change, or a port to start listening
+#[tauri::command]
+pub async fn watch_condition_add(spec: WatchConditionSpec) -> Result<WatchCondition, String> {
+    add_condition(spec).await
+}
+
+#[tauri::command]
+pub fn watch_condition_list() -> Vec<WatchCondition> {
+    list_conditions()
+}
+
+#[tauri::command]
+pub fn watch_condition_remove(id: String) -> bool {
+    remove_condition(&id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn spec(condition: ConditionKind) -> WatchConditionSpec {
+        WatchConditionSpec {
+            condition,
+            cwd: None,
+            poll_interval_ms: None,
+            timeout_ms: Some(30_000),
+            one_shot: true,
+            notify: false,
+            label: None,
+        }
+    }
+
+    fn is_active(id: &str) -> bool {
+        list_conditions().iter().any(|condition| condition.id == id)
+    }
+
+    async fn wait_until_removed(id: &str, within: Duration) -> bool {
+        let deadline = Instant::now() + within;
+        while Instant::now() < deadline {
+            if !is_active(id) {
+                return true;
+            }
+            tokio::time::sleep(Duration::from_millis(25)).await;
+        }
+        false
+    }
+
+    #[test]
+    fn test_observation_transitions() {
+        let closed = Observation::Flag(false);
+        let open = Observation::Flag(true);
+        assert!(Observation::is_met(&closed, &open));
+        assert!(!Observation::is_met(&open, &open));
+        assert!(!Observation::is_met(&open, &closed));
+
+        let missing = Observation::Fingerprint(None);
+        let written = Observation::Fingerprint(Some((UNIX_EPOCH, 3)));
+        assert!(Observation::is_met(&missing, &written));
+        assert!(!Observation::is_met(&written, &written));
+    }
+
+    #[test]
+    fn test_relative_path_needs_cwd() {
+        let condition = ConditionKind::FileExists {
+            path: "dist/app.wasm".to_string(),
+        };
+        assert!(Probe::new(&condition, None).is_err());
+        assert_eq!(
+            Probe::new(&condition, Some("/project")).unwrap(),
+            Probe::FileExists(PathBuf::from("/project/dist/app.wasm"))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_port_condition_met_when_listener_opens() {
+        // Find a free port, then release it until the listener opens mid-test
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let mut port_spec = spec(ConditionKind::PortOpen {
+            host: "127.0.0.1".to_string(),
+            port,
+        });
+        port_spec.poll_interval_ms = Some(MIN_POLL_INTERVAL_MS);
+        let condition = add_condition(port_spec).await.unwrap();
+
+        tokio::time::sleep(Duration::from_millis(600)).await;
+        assert!(is_active(&condition.id), "Port is not listening yet");
+
+        let _listener = tokio::net::TcpListener::bind(("127.0.0.1", port))
+            .await
+            .unwrap();
+        assert!(wait_until_removed(&condition.id, Duration::from_secs(5)).await);
+    }
+
+    #[tokio::test]
+    async fn test_file_condition_woken_by_watcher() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().to_path_buf();
+        register_watched_root(&root);
+
+        let target = root.join("dist").join("app.wasm");
+        let mut file_spec = spec(ConditionKind::FileExists {
+            path: "dist/app.wasm".to_string(),
+        });
+        file_spec.cwd = Some(root.to_string_lossy().to_string());
+        file_spec.poll_interval_ms = Some(MIN_POLL_INTERVAL_MS);
+        let condition = add_condition(file_spec).await.unwrap();
+        assert!(is_covered(Some(&target)));
+
+        // Covered conditions only stat on the slow fallback interval, so an early
+        // removal means the watcher notification woke the check
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        std::fs::create_dir_all(target.parent().unwrap()).unwrap();
+        std::fs::write(&target, b"\0asm").unwrap();
+        assert!(is_active(&condition.id));
+        notify_paths(&[root.join("dist")]);
+        assert!(wait_until_removed(&condition.id, Duration::from_secs(2)).await);
+
+        unregister_watched_root(&root);
+        assert!(!is_covered(Some(&target)));
+    }
+
+    #[tokio::test]
+    async fn test_timeout_removes_condition() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut file_spec = spec(ConditionKind::FileExists {
+            path: temp_dir.path().join("never").to_string_lossy().to_string(),
+        });
+        file_spec.timeout_ms = Some(300);
+        let condition = add_condition(file_spec).await.unwrap();
+        assert!(wait_until_removed(&condition.id, Duration::from_secs(2)).await);
+    }
+
+    #[tokio::test]
+    async fn test_remove_condition() {
+        let condition = add_condition(spec(ConditionKind::PortOpen {
+            host: String::new(),
+            port: 9,
+        }))
+        .await
+        .unwrap();
+        assert!(remove_condition(&condition.id));
+        assert!(!remove_condition(&condition.id));
+        assert!(!is_active(&condition.id));
+    }
+}
@@ -1,42 +1,50 @@
 // WebSocket service for Eleven Labs real-time transcription
-// Handles WebSocket connections with custom headers that browser WebSocket doesn't support
+// Handles WebSocket connections with custom headers that browser WebSocket doesn't support.
+// Other features share connections through the pool below: a connection is keyed by what
+// it is for, reference-counted across acquisitions, lingers briefly after the last release
+// and keeps a short replay buffer, so a reloaded webview can re-acquire the same
+// connection and catch up on the messages it missed.
 
+use crate::clock::now_ms;
+use futures_util::stream::{SplitSink, SplitStream};
 use futures_util::{SinkExt, StreamExt};
 use log::{error, info};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
-use tauri::{AppHandle, Emitter, State};
+use std::time::Duration;
+use tauri::{AppHandle, State};
 use tokio::sync::Mutex;
 use tokio_tungstenite::{
     connect_async,
-    tungstenite::{client::IntoClientRequest, Message},
+    tungstenite::{
+        client::IntoClientRequest,
+        handshake::client::Request,
+        http::{HeaderName, HeaderValue},
+        Message,
+    },
+    MaybeTlsStream, WebSocketStream,
 };
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+type WsStream = WebSocketStream<MaybeTlsStream<tokio::net::TcpStream>>;
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct WebSocketMessage {
     pub data: String,
 }
 
 // WebSocket connection state
 pub struct WebSocketState {
-    sender: Arc<
-        Mutex<
-            Option<
-                futures_util::stream::SplitSink<
-                    tokio_tungstenite::WebSocketStream<
-                        tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>,
-                    >,
-                    Message,
-                >,
-            >,
-        >,
-    >,
+    sender: Arc<Mutex<Option<SplitSink<WsStream, Message>>>>,
+    pool: Arc<WsPool>,
 }
 
 impl WebSocketState {
     pub fn new() -> Self {
         Self {
             sender: Arc::new(Mutex::new(None)),
+            pool: Arc::new(WsPool::new(DEFAULT_LINGER, DEFAULT_REPLAY_CAPACITY)),
         }
     }
 }
@@ -87,7 +95,7 @@ pub async fn ws_connect(
     }
 
     // Emit connection success event
-    if let Err(e) = app_handle.emit("ws-connected", ()) {
+    if let Err(e) = crate::events::emit_ws_connected(&app_handle) {
         error!("[WebSocket] Failed to emit connection event: {}", e);
     }
 
@@ -101,13 +109,13 @@ pub async fn ws_connect(
                 Ok(Message::Text(text)) => {
                     info!("[WebSocket] Received text message: {}", text);
                     let message = WebSocketMessage { data: text };
-                    if let Err(e) = app_handle_clone.emit("ws-message", message) {
+                    if let Err(e) = crate::events::emit_ws_message(&app_handle_clone, &message) {
                         error!("[WebSocket] Failed to emit message: {}", e);
                     }
                 }
                 Ok(Message::Close(frame)) => {
                     info!("[WebSocket] Connection closed: {:?}", frame);
-                    if let Err(e) = app_handle_clone.emit("ws-closed", ()) {
+                    if let Err(e) = crate::events::emit_ws_closed(&app_handle_clone) {
                         error!("[WebSocket] Failed to emit close event: {}", e);
                     }
                     break;
@@ -115,7 +123,9 @@ pub async fn ws_connect(
                 Err(e) => {
                     error!("[WebSocket] Error receiving message: {}", e);
                     let error_msg = format!("WebSocket error: {}", e);
-                    if let Err(emit_err) = app_handle_clone.emit("ws-error", error_msg) {
+                    if let Err(emit_err) =
+                        crate::events::emit_ws_error(&app_handle_clone, &error_msg)
+                    {
                         error!("[WebSocket] Failed to emit error event: {}", emit_err);
                     }
                     break;
@@ -174,3 +184,653 @@ pub async fn ws_disconnect(state: State<'_, Arc<Mutex<WebSocketState>>>) -> Resu
         Ok(())
     }
 }
+
+// ============================================================================
+// Connection pool
+// ============================================================================
+
+/// How long a connection stays open after its last release, for a quick re-acquire
+pub const DEFAULT_LINGER: Duration = Duration::from_secs(30);
+/// Received messages kept per pooled connection for callers catching up
+pub const DEFAULT_REPLAY_CAPACITY: usize = 256;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct WsAcquireOptions {
+    /// Handshake headers, used when a new connection is opened
+    pub headers: HashMap<String, String>,
+    /// Last sequence number the caller has seen; buffered messages after it are replayed.
+    /// Unset replays the whole buffer.
+    pub after_seq: Option<u64>,
+    /// How long the connection stays open after its last release
+    pub linger_ms: Option<u64>,
+}
+
+/// A received text message, numbered per connection from 1
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct PooledMessage {
+    pub seq: u64,
+    pub data: String,
+}
+
+/// What a pooled connection reports to the frontend
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum WsPoolEvent {
+    Message {
+        purpose: String,
+        id: String,
+        seq: u64,
+        data: String,
+    },
+    /// The connection is gone; the next acquire for the purpose reconnects
+    Closed {
+        purpose: String,
+        id: String,
+        error: Option<String>,
+    },
+}
+
+pub type WsPoolEventSink = Arc<dyn Fn(WsPoolEvent) + Send + Sync>;
+
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct WsAcquired {
+    pub id: String,
+    /// An open connection was handed out rather than a new one
+    pub reused: bool,
+    /// Buffered messages after the caller's `afterSeq`
+    pub replay: Vec<PooledMessage>,
+    /// Messages after `afterSeq` that had already left the buffer
+    pub missed: u64,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct WsPoolEntryStatus {
+    pub purpose: String,
+    pub id: String,
+    pub url: String,
+    pub refs: usize,
+    pub healthy: bool,
+    /// Released by everyone and waiting out its linger
+    pub lingering: bool,
+    pub connected_at: i64,
+    pub last_message_at: Option<i64>,
+    pub messages_received: u64,
+    pub buffered: usize,
+    pub last_error: Option<String>,
+}
+
+struct ReplayBuffer {
+    capacity: usize,
+    messages: VecDeque<PooledMessage>,
+    last_seq: u64,
+}
+
+impl ReplayBuffer {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            messages: VecDeque::with_capacity(capacity.min(64)),
+            last_seq: 0,
+        }
+    }
+
+    fn push(&mut self, data: String) -> PooledMessage {
+        self.last_seq += 1;
+        let message = PooledMessage {
+            seq: self.last_seq,
+            data,
+        };
+        if self.capacity == 0 {
+            return message;
+        }
+        if self.messages.len() == self.capacity {
+            self.messages.pop_front();
+        }
+        self.messages.push_back(message.clone());
+        message
+    }
+
+    /// Buffered messages after `after_seq`, and how many after it were already dropped
+    fn since(&self, after_seq: Option<u64>) -> (Vec<PooledMessage>, u64) {
+        let after = after_seq.unwrap_or(0);
+        let first_kept = self
+            .messages
+            .front()
+            .map_or(self.last_seq + 1, |message| message.seq);
+        let replay = self
+            .messages
+            .iter()
+            .filter(|message| message.seq > after)
+            .cloned()
+            .collect();
+        (replay, first_kept.saturating_sub(after + 1))
+    }
+}
+
+/// Connection state the reader task updates
+struct ConnectionHealth {
+    healthy: bool,
+    last_message_at: Option<i64>,
+    last_error: Option<String>,
+    replay: ReplayBuffer,
+}
+
+struct PoolEntry {
+    id: String,
+    url: String,
+    refs: usize,
+    linger: Duration,
+    /// Bumped whenever the count drops to zero, so a stale linger timer does nothing
+    release_generation: u64,
+    connected_at: i64,
+    sender: Arc<Mutex<SplitSink<WsStream, Message>>>,
+    health: Arc<std::sync::Mutex<ConnectionHealth>>,
+}
+
+impl PoolEntry {
+    fn healthy(&self) -> bool {
+        self.health.lock().unwrap().healthy
+    }
+
+    fn acquired(&mut self, options: &WsAcquireOptions) -> WsAcquired {
+        self.refs += 1;
+        if let Some(ms) = options.linger_ms {
+            self.linger = Duration::from_millis(ms);
+        }
+        let (replay, missed) = self.health.lock().unwrap().replay.since(options.after_seq);
+        WsAcquired {
+            id: self.id.clone(),
+            reused: true,
+            replay,
+            missed,
+        }
+    }
+
+    /// Send a close frame in the background
+    fn close(self) {
+        self.health.lock().unwrap().healthy = false;
+        tokio::spawn(async move {
+            let _ = self.sender.lock().await.send(Message::Close(None)).await;
+        });
+    }
+}
+
+/// Shared WebSocket connections keyed by purpose, e.g. "collaboration" or "mcp:<server>"
+pub struct WsPool {
+    entries: std::sync::Mutex<HashMap<String, PoolEntry>>,
+    linger: Duration,
+    replay_capacity: usize,
+}
+
+impl WsPool {
+    pub fn new(linger: Duration, replay_capacity: usize) -> Self {
+        Self {
+            entries: std::sync::Mutex::new(HashMap::new()),
+            linger,
+            replay_capacity,
+        }
+    }
+
+    /// Hand out the purpose's open connection to `url`, or open one. A dead connection
+    /// is replaced and keeps its acquisitions.
+    pub async fn acquire(
+        self: &Arc<Self>,
+        purpose: &str,
+        url: &str,
+        options: WsAcquireOptions,
+        on_event: WsPoolEventSink,
+    ) -> Result<WsAcquired, String> {
+        if let Some(acquired) = self.reuse(purpose, url, &options)? {
+            return Ok(acquired);
+        }
+
+        let request = client_request(url, &options.headers)?;
+        let (stream, _) = connect_async(request).await.map_err(|e| {
+            error!("[WebSocket] Pool connection for {} failed: {}", purpose, e);
+            format!("Connection failed: {}", e)
+        })?;
+        let (write, read) = stream.split();
+
+        // Another caller may have connected while this one was in flight
+        if let Some(acquired) = self.reuse(purpose, url, &options)? {
+            tokio::spawn(async move {
+                let mut write = write;
+                let _ = write.send(Message::Close(None)).await;
+            });
+            return Ok(acquired);
+        }
+
+        let id = uuid::Uuid::new_v4().to_string();
+        let health = Arc::new(std::sync::Mutex::new(ConnectionHealth {
+            healthy: true,
+            last_message_at: None,
+            last_error: None,
+            replay: ReplayBuffer::new(self.replay_capacity),
+        }));
+        let mut entries = self.entries.lock().unwrap();
+        let refs = entries.get(purpose).map_or(0, |dead| dead.refs) + 1;
+        entries.insert(
+            purpose.to_string(),
+            PoolEntry {
+                id: id.clone(),
+                url: url.to_string(),
+                refs,
+                linger: options.linger_ms.map_or(self.linger, Duration::from_millis),
+                release_generation: 0,
+                connected_at: now_ms(),
+                sender: Arc::new(Mutex::new(write)),
+                health: Arc::clone(&health),
+            },
+        );
+        spawn_pool_reader(purpose.to_string(), id.clone(), read, health, on_event);
+        info!("[WebSocket] Pool opened {} for {} ({})", id, purpose, url);
+
+        Ok(WsAcquired {
+            id,
+            reused: false,
+            replay: Vec::new(),
+            missed: 0,
+        })
+    }
+
+    fn reuse(
+        &self,
+        purpose: &str,
+        url: &str,
+        options: &WsAcquireOptions,
+    ) -> Result<Option<WsAcquired>, String> {
+        let mut entries = self.entries.lock().unwrap();
+        let Some(entry) = entries.get_mut(purpose) else {
+            return Ok(None);
+        };
+        if entry.url != url {
+            if entry.refs > 0 {
+                return Err(format!(
+                    "WebSocket purpose '{}' is in use for {}",
+                    purpose, entry.url
+                ));
+            }
+            // Lingering for another URL: nobody holds it, so replace it
+            entries.remove(purpose).unwrap().close();
+            return Ok(None);
+        }
+        Ok(entry.healthy().then(|| entry.acquired(options)))
+    }
+
+    /// Drop one acquisition; the connection closes once the last one has been released
+    /// for its linger. Returns the acquisitions left.
+    pub fn release(self: &Arc<Self>, purpose: &str) -> Result<usize, String> {
+        let mut entries = self.entries.lock().unwrap();
+        let entry = entries
+            .get_mut(purpose)
+            .filter(|entry| entry.refs > 0)
+            .ok_or_else(|| format!("No acquired WebSocket for '{}'", purpose))?;
+        entry.refs -= 1;
+        if entry.refs == 0 {
+            entry.release_generation += 1;
+            let generation = entry.release_generation;
+            let linger = entry.linger;
+            let pool = Arc::clone(self);
+            let purpose = purpose.to_string();
+            tokio::spawn(async move {
+                tokio::time::sleep(linger).await;
+                pool.expire(&purpose, generation);
+            });
+        }
+        Ok(entry.refs)
+    }
+
+    fn expire(&self, purpose: &str, generation: u64) {
+        let mut entries = self.entries.lock().unwrap();
+        let idle = entries
+            .get(purpose)
+            .is_some_and(|entry| entry.refs == 0 && entry.release_generation == generation);
+        if idle {
+            let entry = entries.remove(purpose).unwrap();
+            info!("[WebSocket] Pool closed {} for {}", entry.id, purpose);
+            entry.close();
+        }
+    }
+
+    pub async fn send(&self, purpose: &str, message: String) -> Result<(), String> {
+        let sender = {
+            let entries = self.entries.lock().unwrap();
+            let entry = entries
+                .get(purpose)
+                .ok_or_else(|| format!("No pooled WebSocket for '{}'", purpose))?;
+            if !entry.healthy() {
+                return Err(format!("WebSocket for '{}' is closed", purpose));
+            }
+            Arc::clone(&entry.sender)
+        };
+        let mut sender = sender.lock().await;
+        sender.send(Message::Text(message)).await.map_err(|e| {
+            error!("[WebSocket] Failed to send on {}: {}", purpose, e);
+            format!("Failed to send message: {}", e)
+        })
+    }
+
+    /// Every pooled connection, by purpose
+    pub fn status(&self) -> Vec<WsPoolEntryStatus> {
+        let entries = self.entries.lock().unwrap();
+        let mut status: Vec<WsPoolEntryStatus> = entries
+            .iter()
+            .map(|(purpose, entry)| {
+                let health = entry.health.lock().unwrap();
+                WsPoolEntryStatus {
+                    purpose: purpose.clone(),
+                    id: entry.id.clone(),
+                    url: entry.url.clone(),
+                    refs: entry.refs,
+                    healthy: health.healthy,
+                    lingering: entry.refs == 0,
+                    connected_at: entry.connected_at,
+                    last_message_at: health.last_message_at,
+                    messages_received: health.replay.last_seq,
+                    buffered: health.replay.messages.len(),
+                    last_error: health.last_error.clone(),
+                }
+            })
+            .collect();
+        status.sort_by(|a, b| a.purpose.cmp(&b.purpose));
+        status
+    }
+}
+
+fn client_request(url: &str, headers: &HashMap<String, String>) -> Result<Request, String> {
+    let mut request = url
+        .into_client_request()
+        .map_err(|e| format!("Failed to create request: {}", e))?;
+    for (key, value) in headers {
+        let name = HeaderName::from_bytes(key.as_bytes())
+            .map_err(|e| format!("Invalid header name {}: {}", key, e))?;
+        let value = HeaderValue::from_str(value)
+            .map_err(|e| format!("Invalid value for header {}: {}", key, e))?;
+        request.headers_mut().insert(name, value);
+    }
+    Ok(request)
+}
+
+/// Buffer and forward incoming text until the connection ends, then mark it unhealthy
+fn spawn_pool_reader(
+    purpose: String,
+    id: String,
+    mut read: SplitStream<WsStream>,
+    health: Arc<std::sync::Mutex<ConnectionHealth>>,
+    on_event: WsPoolEventSink,
+) {
+    tokio::spawn(async move {
+        let error = loop {
+            match read.next().await {
+                Some(Ok(Message::Text(text))) => {
+                    let message = {
+                        let mut health = health.lock().unwrap();
+                        health.last_message_at = Some(now_ms());
+                        health.replay.push(text)
+                    };
+                    on_event(WsPoolEvent::Message {
+                        purpose: purpose.clone(),
+                        id: id.clone(),
+                        seq: message.seq,
+                        data: message.data,
+                    });
+                }
+                Some(Ok(Message::Close(_))) | None => break None,
+                Some(Err(e)) => break Some(e.to_string()),
+                Some(Ok(_)) => {}
+            }
+        };
+        {
+            let mut health = health.lock().unwrap();
+            health.healthy = false;
+            health.last_error = error.clone();
+        }
+        info!("[WebSocket] Pool connection {} for {} ended", id, purpose);
+        on_event(WsPoolEvent::Closed { purpose, id, error });
+    });
+}
+
+/// The purpose's pooled connection, opened if there is no healthy one
+#[tauri::command]
+pub async fn ws_acquire(
+    purpose: String,
+    url: String,
+    options: Option<WsAcquireOptions>,
+    app_handle: AppHandle,
+    state: State<'_, Arc<Mutex<WebSocketState>>>,
+) -> Result<WsAcquired, String> {
+    let pool = Arc::clone(&state.lock().await.pool);
+    let on_event: WsPoolEventSink = Arc::new(move |event| {
+        if let Err(e) = crate::events::emit_ws_pool_event(&app_handle, &event) {
+            error!("[WebSocket] Failed to emit pool event: {}", e);
+        }
+    });
+    pool.acquire(&purpose, &url, options.unwrap_or_default(), on_event)
+        .await
+}
+
+#[tauri::command]
+pub async fn ws_release(
+    purpose: String,
+    state: State<'_, Arc<Mutex<WebSocketState>>>,
+) -> Result<usize, String> {
+    let pool = Arc::clone(&state.lock().await.pool);
+    pool.release(&purpose)
+}
+
+#[tauri::command]
+pub async fn ws_pool_send(
+    purpose: String,
+    message: String,
+    state: State<'_, Arc<Mutex<WebSocketState>>>,
+) -> Result<(), String> {
+    let pool = Arc::clone(&state.lock().await.pool);
+    pool.send(&purpose, message).await
+}
+
+#[tauri::command]
+pub async fn ws_pool_status(
+    state: State<'_, Arc<Mutex<WebSocketState>>>,
+) -> Result<Vec<WsPoolEntryStatus>, String> {
+    Ok(state.lock().await.pool.status())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::net::TcpListener;
+
+    /// Echo server on a random port; counts connections and the close frames it receives
+    async fn echo_server() -> (String, Arc<AtomicUsize>, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        let connections = Arc::new(AtomicUsize::new(0));
+        let closes = Arc::new(AtomicUsize::new(0));
+        let (opened, closed) = (Arc::clone(&connections), Arc::clone(&closes));
+        tokio::spawn(async move {
+            while let Ok((tcp, _)) = listener.accept().await {
+                opened.fetch_add(1, Ordering::SeqCst);
+                let closed = Arc::clone(&closed);
+                tokio::spawn(async move {
+                    let mut ws = tokio_tungstenite::accept_async(tcp).await.unwrap();
+                    while let Some(Ok(message)) = ws.next().await {
+                        match message {
+                            Message::Text(_) => ws.send(message).await.unwrap(),
+                            Message::Close(_) => {
+                                closed.fetch_add(1, Ordering::SeqCst);
+                                break;
+                            }
+                            _ => {}
+                        }
+                    }
+                });
+            }
+        });
+        (url, connections, closes)
+    }
+
+    fn event_log() -> (WsPoolEventSink, Arc<std::sync::Mutex<Vec<WsPoolEvent>>>) {
+        let events = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let log = Arc::clone(&events);
+        (
+            Arc::new(move |event| log.lock().unwrap().push(event)),
+            events,
+        )
+    }
+
+    async fn wait_until(mut condition: impl FnMut() -> bool) {
+        for _ in 0..200 {
+            if condition() {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("condition not met in time");
+    }
+
+    #[test]
+    fn test_replay_buffer_is_bounded() {
+        let mut buffer = ReplayBuffer::new(3);
+        for i in 1..=5 {
+            assert_eq!(buffer.push(format!("m{}", i)).seq, i);
+        }
+        let seqs = |messages: Vec<PooledMessage>| -> Vec<u64> {
+            messages.iter().map(|message| message.seq).collect()
+        };
+
+        let (replay, missed) = buffer.since(None);
+        assert_eq!(seqs(replay), vec![3, 4, 5]);
+        assert_eq!(missed, 2);
+        let (replay, missed) = buffer.since(Some(1));
+        assert_eq!(seqs(replay), vec![3, 4, 5]);
+        assert_eq!(missed, 1);
+        let (replay, missed) = buffer.since(Some(4));
+        assert_eq!(seqs(replay), vec![5]);
+        assert_eq!(missed, 0);
+        assert_eq!(buffer.since(Some(5)), (Vec::new(), 0));
+
+        let mut unbuffered = ReplayBuffer::new(0);
+        unbuffered.push("x".to_string());
+        assert_eq!(unbuffered.since(None), (Vec::new(), 1));
+    }
+
+    #[tokio::test]
+    async fn test_pool_refcount_lifecycle() {
+        let (url, connections, closes) = echo_server().await;
+        let pool = Arc::new(WsPool::new(Duration::from_millis(50), 16));
+        let (sink, _) = event_log();
+
+        let first = pool
+            .acquire("collab", &url, WsAcquireOptions::default(), sink.clone())
+            .await
+            .unwrap();
+        assert!(!first.reused);
+        let second = pool
+            .acquire("collab", &url, WsAcquireOptions::default(), sink.clone())
+            .await
+            .unwrap();
+        assert!(second.reused);
+        assert_eq!(second.id, first.id);
+        assert_eq!(connections.load(Ordering::SeqCst), 1);
+        assert_eq!(pool.status()[0].refs, 2);
+
+        // Another URL can't take a purpose that is in use
+        assert!(pool
+            .acquire(
+                "collab",
+                "ws://127.0.0.1:9",
+                WsAcquireOptions::default(),
+                sink
+            )
+            .await
+            .unwrap_err()
+            .contains("in use"));
+
+        assert_eq!(pool.release("collab"), Ok(1));
+        tokio::time::sleep(Duration::from_millis(120)).await;
+        assert!(pool.status()[0].healthy, "closed while still acquired");
+
+        assert_eq!(pool.release("collab"), Ok(0));
+        assert!(pool.status()[0].lingering);
+        wait_until(|| pool.status().is_empty()).await;
+        wait_until(|| closes.load(Ordering::SeqCst) == 1).await;
+        assert!(pool.release("collab").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_reacquire_during_linger_keeps_the_connection() {
+        let (url, connections, closes) = echo_server().await;
+        let pool = Arc::new(WsPool::new(Duration::from_millis(100), 16));
+        let (sink, _) = event_log();
+
+        let first = pool
+            .acquire("hmr", &url, WsAcquireOptions::default(), sink.clone())
+            .await
+            .unwrap();
+        pool.release("hmr").unwrap();
+        tokio::time::sleep(Duration::from_millis(40)).await;
+        let again = pool
+            .acquire("hmr", &url, WsAcquireOptions::default(), sink)
+            .await
+            .unwrap();
+        assert_eq!(again.id, first.id);
+
+        // The first release's timer must not close the re-acquired connection
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        let status = pool.status();
+        assert_eq!(status.len(), 1);
+        assert!(status[0].healthy);
+        assert_eq!(status[0].refs, 1);
+        assert_eq!(connections.load(Ordering::SeqCst), 1);
+        assert_eq!(closes.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_reacquire_replays_missed_messages() {
+        let (url, _, _) = echo_server().await;
+        let pool = Arc::new(WsPool::new(Duration::from_secs(30), 3));
+        let (sink, events) = event_log();
+
+        let acquired = pool
+            .acquire("mcp:docs", &url, WsAcquireOptions::default(), sink.clone())
+            .await
+            .unwrap();
+        for i in 1..=5 {
+            pool.send("mcp:docs", format!("m{}", i)).await.unwrap();
+        }
+        wait_until(|| events.lock().unwrap().len() == 5).await;
+        assert_eq!(
+            events.lock().unwrap()[4],
+            WsPoolEvent::Message {
+                purpose: "mcp:docs".to_string(),
+                id: acquired.id.clone(),
+                seq: 5,
+                data: "m5".to_string(),
+            }
+        );
+
+        // A reloaded webview that saw up to seq 2
+        let options = WsAcquireOptions {
+            after_seq: Some(2),
+            ..Default::default()
+        };
+        let reacquired = pool.acquire("mcp:docs", &url, options, sink).await.unwrap();
+        assert_eq!(reacquired.id, acquired.id);
+        let data: Vec<&str> = reacquired
+            .replay
+            .iter()
+            .map(|message| message.data.as_str())
+            .collect();
+        assert_eq!(data, vec!["m3", "m4", "m5"]);
+        assert_eq!(reacquired.missed, 0);
+
+        let status = &pool.status()[0];
+        assert_eq!(status.messages_received, 5);
+        assert_eq!(status.buffered, 3);
+        assert!(status.last_message_at.is_some());
+    }
+}
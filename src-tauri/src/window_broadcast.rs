@@ -0,0 +1,373 @@
+// Window broadcast module
+// Ask every project window (or those with one project open) to do something and wait for
+// their answers, e.g. "save dirty editors" before a branch switch. The request goes to
+// each window's scoped broadcast-request event with a correlation id; windows answer with
+// `reply_to_broadcast`, and the broadcast resolves once every window answered or the
+// deadline passed. Windows that close mid-request count as not replying.
+
+use crate::events::{self, BroadcastRequest};
+use crate::window_manager::{self, WindowRegistry};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Runtime};
+use tokio::sync::oneshot;
+
+/// Deadline when the caller does not give one
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+const MAX_TIMEOUT: Duration = Duration::from_secs(120);
+
+lazy_static::lazy_static! {
+    static ref BROADCASTS: BroadcastRegistry = BroadcastRegistry::default();
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ReplyStatus {
+    Replied,
+    /// No reply before the deadline
+    TimedOut,
+    /// The window closed before replying
+    Closed,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WindowReply {
+    pub window_label: String,
+    pub status: ReplyStatus,
+    pub payload: Option<Value>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BroadcastResult {
+    pub correlation_id: String,
+    pub request_kind: String,
+    /// One entry per targeted window, ordered by label
+    pub replies: Vec<WindowReply>,
+    /// Every targeted window replied
+    pub all_replied: bool,
+    pub elapsed_ms: u64,
+}
+
+struct PendingBroadcast {
+    request_kind: String,
+    started: Instant,
+    /// Targeted windows; None until the window replied or closed
+    replies: BTreeMap<String, Option<(ReplyStatus, Option<Value>)>>,
+    /// Wakes the waiting broadcast once every window is settled
+    settled: Option<oneshot::Sender<()>>,
+}
+
+impl PendingBroadcast {
+    fn notify_if_settled(&mut self) {
+        if self.replies.values().all(Option::is_some) {
+            if let Some(settled) = self.settled.take() {
+                let _ = settled.send(());
+            }
+        }
+    }
+}
+
+/// Broadcasts waiting for replies, by correlation id
+#[derive(Default)]
+pub struct BroadcastRegistry {
+    pending: Mutex<HashMap<String, PendingBroadcast>>,
+}
+
+impl BroadcastRegistry {
+    /// Track a broadcast to `window_labels`. The receiver fires once every window replied
+    /// or closed; with no windows it fires right away.
+    pub fn start(
+        &self,
+        correlation_id: &str,
+        request_kind: &str,
+        window_labels: impl IntoIterator<Item = String>,
+    ) -> oneshot::Receiver<()> {
+        let (settled, receiver) = oneshot::channel();
+        let mut broadcast = PendingBroadcast {
+            request_kind: request_kind.to_string(),
+            started: Instant::now(),
+            replies: window_labels.into_iter().map(|l| (l, None)).collect(),
+            settled: Some(settled),
+        };
+        broadcast.notify_if_settled();
+        if let Ok(mut pending) = self.pending.lock() {
+            pending.insert(correlation_id.to_string(), broadcast);
+        }
+        receiver
+    }
+
+    /// Record a window's reply. Fails for broadcasts that already resolved, windows that
+    /// were not asked, and second replies.
+    pub fn reply(
+        &self,
+        correlation_id: &str,
+        window_label: &str,
+        payload: Value,
+    ) -> Result<(), String> {
+        let mut pending = self.pending.lock().map_err(|e| e.to_string())?;
+        let broadcast = pending
+            .get_mut(correlation_id)
+            .ok_or_else(|| format!("No pending broadcast {}", correlation_id))?;
+        match broadcast.replies.get_mut(window_label) {
+            None => Err(format!(
+                "Window {} was not asked by broadcast {}",
+                window_label, correlation_id
+            )),
+            Some(Some(_)) => Err(format!(
+                "Window {} already replied to broadcast {}",
+                window_label, correlation_id
+            )),
+            Some(reply) => {
+                *reply = Some((ReplyStatus::Replied, Some(payload)));
+                broadcast.notify_if_settled();
+                Ok(())
+            }
+        }
+    }
+
+    /// A window closed; pending broadcasts stop waiting for it
+    pub fn window_closed(&self, window_label: &str) {
+        let Ok(mut pending) = self.pending.lock() else {
+            return;
+        };
+        for broadcast in pending.values_mut() {
+            match broadcast.replies.get_mut(window_label) {
+                Some(reply) if reply.is_none() => {
+                    *reply = Some((ReplyStatus::Closed, None));
+                    broadcast.notify_if_settled();
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Stop tracking a broadcast and aggregate its replies; windows that have not replied
+    /// are marked timed out
+    pub fn finish(&self, correlation_id: &str) -> Option<BroadcastResult> {
+        let broadcast = self.pending.lock().ok()?.remove(correlation_id)?;
+        let replies: Vec<WindowReply> = broadcast
+            .replies
+            .into_iter()
+            .map(|(window_label, reply)| {
+                let (status, payload) = reply.unwrap_or((ReplyStatus::TimedOut, None));
+                WindowReply {
+                    window_label,
+                    status,
+                    payload,
+                }
+            })
+            .collect();
+        Some(BroadcastResult {
+            correlation_id: correlation_id.to_string(),
+            request_kind: broadcast.request_kind,
+            all_replied: replies.iter().all(|r| r.status == ReplyStatus::Replied),
+            replies,
+            elapsed_ms: broadcast.started.elapsed().as_millis() as u64,
+        })
+    }
+
+    pub fn pending_count(&self) -> usize {
+        self.pending.lock().map(|p| p.len()).unwrap_or(0)
+    }
+}
+
+/// Pending broadcasts stop waiting for a window that was destroyed
+pub fn window_closed(window_label: &str) {
+    BROADCASTS.window_closed(window_label);
+}
+
+/// Send a request to every live window, or those with `root_path_filter` open, and wait
+/// for their replies until `timeout`
+pub async fn broadcast<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    window_registry: &WindowRegistry,
+    root_path_filter: Option<&str>,
+    request_kind: String,
+    payload: Value,
+    timeout: Option<Duration>,
+) -> Result<BroadcastResult, String> {
+    let live = window_manager::live_window_labels(app_handle);
+    let targets: Vec<String> = window_registry
+        .get_all_windows()?
+        .into_iter()
+        .filter(|window| live.contains(&window.label))
+        .filter(|window| {
+            root_path_filter.is_none_or(|filter| {
+                window
+                    .root_path
+                    .as_deref()
+                    .is_some_and(|root| window_manager::same_root(root, filter))
+            })
+        })
+        .map(|window| window.label)
+        .collect();
+
+    let correlation_id = uuid::Uuid::new_v4().to_string();
+    let settled = BROADCASTS.start(&correlation_id, &request_kind, targets.clone());
+    let request = BroadcastRequest {
+        correlation_id: &correlation_id,
+        request_kind: &request_kind,
+        payload: &payload,
+    };
+    for label in &targets {
+        if let Err(e) = events::emit_broadcast_request(app_handle, label, &request) {
+            log::warn!("Failed to send broadcast to window {}: {}", label, e);
+            BROADCASTS.window_closed(label);
+        }
+    }
+    log::info!(
+        "Broadcast {} ({}) sent to {} windows",
+        request_kind,
+        correlation_id,
+        targets.len()
+    );
+
+    let timeout = timeout.unwrap_or(DEFAULT_TIMEOUT).min(MAX_TIMEOUT);
+    let _ = tokio::time::timeout(timeout, settled).await;
+    BROADCASTS
+        .finish(&correlation_id)
+        .ok_or_else(|| format!("Broadcast {} was lost", correlation_id))
+}
+
+/// A window's answer to a broadcast request
+#[tauri::command]
+pub fn reply_to_broadcast(
+    correlation_id: String,
+    window_label: String,
+    payload: Value,
+) -> Result<(), String> {
+    BROADCASTS.reply(&correlation_id, &window_label, payload)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn labels(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    fn statuses(result: &BroadcastResult) -> Vec<(&str, ReplyStatus)> {
+        result
+            .replies
+            .iter()
+            .map(|r| (r.window_label.as_str(), r.status))
+            .collect()
+    }
+
+    #[test]
+    fn test_resolves_when_every_window_replied() {
+        let registry = BroadcastRegistry::default();
+        let mut settled = registry.start("b1", "save-dirty", labels(&["w2", "w1"]));
+
+        registry.reply("b1", "w1", json!({"saved": 2})).unwrap();
+        assert!(settled.try_recv().is_err());
+        registry.reply("b1", "w2", json!({"saved": 0})).unwrap();
+        assert!(settled.try_recv().is_ok());
+
+        let result = registry.finish("b1").unwrap();
+        assert!(result.all_replied);
+        assert_eq!(result.request_kind, "save-dirty");
+        assert_eq!(
+            statuses(&result),
+            vec![("w1", ReplyStatus::Replied), ("w2", ReplyStatus::Replied)]
+        );
+        assert_eq!(result.replies[0].payload, Some(json!({"saved": 2})));
+        assert_eq!(registry.pending_count(), 0);
+    }
+
+    #[test]
+    fn test_unanswered_windows_time_out() {
+        let registry = BroadcastRegistry::default();
+        let mut settled = registry.start("b1", "save-dirty", labels(&["w1", "w2"]));
+        registry.reply("b1", "w1", json!(true)).unwrap();
+        assert!(settled.try_recv().is_err());
+
+        let result = registry.finish("b1").unwrap();
+        assert!(!result.all_replied);
+        assert_eq!(
+            statuses(&result),
+            vec![("w1", ReplyStatus::Replied), ("w2", ReplyStatus::TimedOut)]
+        );
+        assert_eq!(result.replies[1].payload, None);
+
+        // Late replies find nothing to answer
+        assert!(registry.reply("b1", "w2", json!(true)).is_err());
+        assert!(registry.finish("b1").is_none());
+    }
+
+    #[test]
+    fn test_closed_windows_count_as_no_reply() {
+        let registry = BroadcastRegistry::default();
+        let mut first = registry.start("b1", "save-dirty", labels(&["w1", "w2"]));
+        let mut second = registry.start("b2", "refresh", labels(&["w2"]));
+        registry.reply("b1", "w1", json!(null)).unwrap();
+
+        registry.window_closed("w2");
+        assert!(first.try_recv().is_ok());
+        assert!(second.try_recv().is_ok());
+        assert_eq!(
+            statuses(&registry.finish("b1").unwrap()),
+            vec![("w1", ReplyStatus::Replied), ("w2", ReplyStatus::Closed)]
+        );
+        let result = registry.finish("b2").unwrap();
+        assert!(!result.all_replied);
+        assert_eq!(statuses(&result), vec![("w2", ReplyStatus::Closed)]);
+
+        // Closing a window that already replied changes nothing
+        let _settled = registry.start("b3", "refresh", labels(&["w1"]));
+        registry.reply("b3", "w1", json!(1)).unwrap();
+        registry.window_closed("w1");
+        assert_eq!(
+            statuses(&registry.finish("b3").unwrap()),
+            vec![("w1", ReplyStatus::Replied)]
+        );
+    }
+
+    #[test]
+    fn test_rejects_unknown_and_duplicate_replies() {
+        let registry = BroadcastRegistry::default();
+        let _settled = registry.start("b1", "save-dirty", labels(&["w1"]));
+        assert!(registry.reply("missing", "w1", json!(1)).is_err());
+        assert!(registry.reply("b1", "w9", json!(1)).is_err());
+        registry.reply("b1", "w1", json!(1)).unwrap();
+        assert!(registry.reply("b1", "w1", json!(2)).is_err());
+        assert_eq!(
+            registry.finish("b1").unwrap().replies[0].payload,
+            Some(json!(1))
+        );
+    }
+
+    #[test]
+    fn test_no_windows_settles_immediately() {
+        let registry = BroadcastRegistry::default();
+        let mut settled = registry.start("b1", "save-dirty", Vec::new());
+        assert!(settled.try_recv().is_ok());
+        let result = registry.finish("b1").unwrap();
+        assert!(result.replies.is_empty());
+        assert!(result.all_replied);
+    }
+
+    #[tokio::test]
+    async fn test_waiting_broadcast_wakes_on_last_reply() {
+        let registry = std::sync::Arc::new(BroadcastRegistry::default());
+        let settled = registry.start("b1", "save-dirty", labels(&["w1"]));
+        let replier = std::sync::Arc::clone(&registry);
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            replier.reply("b1", "w1", json!("ok")).unwrap();
+        });
+        let started = Instant::now();
+        assert!(tokio::time::timeout(Duration::from_secs(5), settled)
+            .await
+            .is_ok());
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert!(registry.finish("b1").unwrap().all_replied);
+    }
+}
@@ -1,12 +1,14 @@
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
-use tauri::{AppHandle, Emitter, Manager, Runtime, WebviewUrl, WebviewWindowBuilder};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, Runtime, WebviewUrl, WebviewWindowBuilder};
 
 use crate::file_watcher::FileWatcher;
+use crate::project_root::InferredRoot;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WindowInfo {
@@ -14,6 +16,36 @@ pub struct WindowInfo {
     pub project_id: Option<String>,
     pub root_path: Option<String>,
     pub title: String,
+    /// Root of the main repository when `root_path` is one of its linked worktrees
+    #[serde(default)]
+    pub main_repo_path: Option<String>,
+    /// Label of the window that has `main_repo_path` open, if any
+    #[serde(default)]
+    pub main_repo_window: Option<String>,
+    /// Repository or workspace root above `root_path`, when a subdirectory was opened
+    #[serde(default)]
+    pub inferred_root: Option<String>,
+    /// `root_path` relative to `inferred_root`
+    #[serde(default)]
+    pub selected_subpath: Option<String>,
+}
+
+/// How often the registry is reconciled against the live windows
+pub const REGISTRY_RECONCILE_INTERVAL: Duration = Duration::from_secs(60);
+/// Entries younger than this are never reaped: their window may still be being built
+const STALE_ENTRY_GRACE: Duration = Duration::from_secs(30);
+
+/// A registry entry as stored, for diagnostics
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WindowRegistryEntry {
+    pub label: String,
+    pub project_id: Option<String>,
+    pub root_path: Option<String>,
+    pub has_file_watcher: bool,
+    /// A webview window with this label currently exists
+    pub live: bool,
+    pub registered_ms_ago: Option<u64>,
 }
 
 pub struct WindowState {
@@ -22,26 +54,110 @@ pub struct WindowState {
     pub file_watcher: Option<FileWatcher>,
 }
 
+/// Outcome of [`WindowRegistry::try_claim_project`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProjectClaim {
+    /// The caller owns the project and must confirm or release the claim
+    Claimed,
+    /// Another window (possibly still being built) already has the project
+    Existing(String),
+}
+
 #[derive(Clone)]
 pub struct WindowRegistry {
     windows: Arc<Mutex<HashMap<String, WindowState>>>,
+    /// Project root -> main repository root, for roots that are linked worktrees
+    main_repo_cache: Arc<Mutex<HashMap<String, Option<String>>>>,
+    /// Project root -> enclosing repository or workspace root, for subdirectories
+    inferred_roots: Arc<Mutex<HashMap<String, InferredRoot>>>,
+    /// When each label was registered, so reconciliation can spare windows being created
+    registered_at: Arc<Mutex<HashMap<String, Instant>>>,
+    /// Labels of claimed entries whose window has not been built yet
+    pending: Arc<Mutex<HashSet<String>>>,
 }
 
 impl WindowRegistry {
     pub fn new() -> Self {
         Self {
             windows: Arc::new(Mutex::new(HashMap::new())),
+            main_repo_cache: Arc::new(Mutex::new(HashMap::new())),
+            inferred_roots: Arc::new(Mutex::new(HashMap::new())),
+            registered_at: Arc::new(Mutex::new(HashMap::new())),
+            pending: Arc::new(Mutex::new(HashSet::new())),
         }
     }
 
     pub fn register_window(&self, label: String, state: WindowState) -> Result<(), String> {
         let mut windows = self.windows.lock().map_err(|e| e.to_string())?;
+        if let Ok(mut registered_at) = self.registered_at.lock() {
+            registered_at.insert(label.clone(), Instant::now());
+        }
         windows.insert(label, state);
         Ok(())
     }
 
+    /// Atomically check that no window has `root_path` open and, if so, register a
+    /// placeholder entry for it under `label`. The caller then builds the window and
+    /// either confirms the claim or releases it.
+    pub fn try_claim_project(
+        &self,
+        label: &str,
+        root_path: &str,
+        project_id: Option<String>,
+    ) -> Result<ProjectClaim, String> {
+        let mut windows = self.windows.lock().map_err(|e| e.to_string())?;
+        if let Some((existing, _)) = windows
+            .iter()
+            .find(|(_, state)| state.root_path.as_deref() == Some(root_path))
+        {
+            return Ok(ProjectClaim::Existing(existing.clone()));
+        }
+        if let Ok(mut registered_at) = self.registered_at.lock() {
+            registered_at.insert(label.to_string(), Instant::now());
+        }
+        if let Ok(mut pending) = self.pending.lock() {
+            pending.insert(label.to_string());
+        }
+        windows.insert(
+            label.to_string(),
+            WindowState {
+                project_id,
+                root_path: Some(root_path.to_string()),
+                file_watcher: None,
+            },
+        );
+        Ok(ProjectClaim::Claimed)
+    }
+
+    /// The claimed window was built; its placeholder is now the real registration
+    pub fn confirm_claim(&self, label: &str) -> Result<(), String> {
+        self.pending
+            .lock()
+            .map_err(|e| e.to_string())?
+            .remove(label);
+        Ok(())
+    }
+
+    /// The claimed window could not be built; drop the placeholder so a retry can claim
+    pub fn release_claim(&self, label: &str) -> Result<(), String> {
+        self.unregister_window(label)
+    }
+
+    /// Whether `label` is a claimed entry whose window is still being built
+    pub fn is_pending(&self, label: &str) -> bool {
+        self.pending
+            .lock()
+            .is_ok_and(|pending| pending.contains(label))
+    }
+
     pub fn unregister_window(&self, label: &str) -> Result<(), String> {
         let mut windows = self.windows.lock().map_err(|e| e.to_string())?;
+        if let Ok(mut registered_at) = self.registered_at.lock() {
+            registered_at.remove(label);
+        }
+        if let Ok(mut pending) = self.pending.lock() {
+            pending.remove(label);
+        }
         if let Some(mut state) = windows.remove(label) {
             // Stop file watcher if exists
             if let Some(mut watcher) = state.file_watcher.take() {
@@ -51,10 +167,83 @@ impl WindowRegistry {
         Ok(())
     }
 
+    fn registered_age(&self, label: &str) -> Option<Duration> {
+        self.registered_at
+            .lock()
+            .ok()
+            .and_then(|registered_at| registered_at.get(label).map(|at| at.elapsed()))
+    }
+
+    /// Unregister entries with no live window, stopping their file watchers. Entries
+    /// registered less than `min_age` ago are kept, since their window may still be in
+    /// the middle of being built. Returns the reaped labels.
+    pub fn reap_stale_windows(
+        &self,
+        live_labels: &HashSet<String>,
+        min_age: Duration,
+    ) -> Result<Vec<String>, String> {
+        let stale: Vec<String> = {
+            let windows = self.windows.lock().map_err(|e| e.to_string())?;
+            windows
+                .keys()
+                .filter(|label| !live_labels.contains(*label))
+                .filter(|label| self.registered_age(label).is_none_or(|age| age >= min_age))
+                .cloned()
+                .collect()
+        };
+        let mut reaped = Vec::new();
+        for label in stale {
+            let had_watcher = self
+                .windows
+                .lock()
+                .map_err(|e| e.to_string())?
+                .get(&label)
+                .is_some_and(|state| state.file_watcher.is_some());
+            self.unregister_window(&label)?;
+            log::warn!(
+                "Reaped stale window registry entry {} (file watcher running: {})",
+                label,
+                had_watcher
+            );
+            reaped.push(label);
+        }
+        reaped.sort();
+        Ok(reaped)
+    }
+
+    /// Registry contents as stored, marked with whether each label has a live window
+    pub fn dump(&self, live_labels: &HashSet<String>) -> Result<Vec<WindowRegistryEntry>, String> {
+        let windows = self.windows.lock().map_err(|e| e.to_string())?;
+        let mut entries: Vec<WindowRegistryEntry> = windows
+            .iter()
+            .map(|(label, state)| WindowRegistryEntry {
+                label: label.clone(),
+                project_id: state.project_id.clone(),
+                root_path: state.root_path.clone(),
+                has_file_watcher: state.file_watcher.is_some(),
+                live: live_labels.contains(label),
+                registered_ms_ago: self.registered_age(label).map(|age| age.as_millis() as u64),
+            })
+            .collect();
+        entries.sort_by(|a, b| a.label.cmp(&b.label));
+        Ok(entries)
+    }
+
     pub fn get_all_windows(&self) -> Result<Vec<WindowInfo>, String> {
         let windows = self.windows.lock().map_err(|e| e.to_string())?;
         let mut infos = Vec::new();
         for (label, state) in windows.iter() {
+            let main_repo_path = state
+                .root_path
+                .as_deref()
+                .and_then(|root| self.main_repo_of(root));
+            let main_repo_window = main_repo_path
+                .as_deref()
+                .and_then(|main| Self::window_with_root(&windows, main));
+            let inferred = state
+                .root_path
+                .as_deref()
+                .and_then(|root| self.inferred_root_of(root));
             infos.push(WindowInfo {
                 label: label.clone(),
                 project_id: state.project_id.clone(),
@@ -63,11 +252,52 @@ impl WindowRegistry {
                     .root_path
                     .clone()
                     .unwrap_or_else(|| "TalkCody".to_string()),
+                main_repo_path,
+                main_repo_window,
+                inferred_root: inferred.as_ref().map(|inferred| inferred.root.clone()),
+                selected_subpath: inferred.map(|inferred| inferred.subpath),
             });
         }
         Ok(infos)
     }
 
+    /// Main repository root if `root_path` is a linked git worktree
+    fn main_repo_of(&self, root_path: &str) -> Option<String> {
+        if let Ok(cache) = self.main_repo_cache.lock() {
+            if let Some(cached) = cache.get(root_path) {
+                return cached.clone();
+            }
+        }
+        let main = crate::git::linked_worktree::main_worktree_root(root_path);
+        if let Ok(mut cache) = self.main_repo_cache.lock() {
+            cache.insert(root_path.to_string(), main.clone());
+        }
+        main
+    }
+
+    /// Remember that `root_path` is a subdirectory of a larger project
+    pub fn record_inferred_root(&self, root_path: &str, inferred: InferredRoot) {
+        if let Ok(mut inferred_roots) = self.inferred_roots.lock() {
+            inferred_roots.insert(root_path.to_string(), inferred);
+        }
+    }
+
+    fn inferred_root_of(&self, root_path: &str) -> Option<InferredRoot> {
+        self.inferred_roots.lock().ok()?.get(root_path).cloned()
+    }
+
+    fn window_with_root(windows: &HashMap<String, WindowState>, root_path: &str) -> Option<String> {
+        windows
+            .iter()
+            .find(|(_, state)| {
+                state
+                    .root_path
+                    .as_deref()
+                    .is_some_and(|path| same_root(path, root_path))
+            })
+            .map(|(label, _)| label.clone())
+    }
+
     pub fn find_window_by_project(&self, root_path: &str) -> Result<Option<String>, String> {
         let windows = self.windows.lock().map_err(|e| e.to_string())?;
         for (label, state) in windows.iter() {
@@ -80,6 +310,16 @@ impl WindowRegistry {
         Ok(None)
     }
 
+    /// When `root_path` is a linked worktree whose main repository is already open,
+    /// the label of that window. The worktree still gets its own window.
+    pub fn find_main_repo_window(&self, root_path: &str) -> Result<Option<String>, String> {
+        let Some(main) = self.main_repo_of(root_path) else {
+            return Ok(None);
+        };
+        let windows = self.windows.lock().map_err(|e| e.to_string())?;
+        Ok(Self::window_with_root(&windows, &main))
+    }
+
     pub fn update_window_project(
         &self,
         label: &str,
@@ -127,39 +367,70 @@ impl WindowRegistry {
     }
 }
 
-/// Try to focus an existing window if the project is already open
-fn try_focus_existing_window<R: Runtime>(
+/// Compare project roots, tolerating trailing separators and symlinked paths
+pub(crate) fn same_root(a: &str, b: &str) -> bool {
+    let (a, b) = (std::path::Path::new(a), std::path::Path::new(b));
+    a == b || matches!((a.canonicalize(), b.canonicalize()), (Ok(a), Ok(b)) if a == b)
+}
+
+/// Labels of the webview windows that currently exist
+pub fn live_window_labels<R: Runtime>(app_handle: &AppHandle<R>) -> HashSet<String> {
+    app_handle.webview_windows().into_keys().collect()
+}
+
+/// Drop registry entries whose window no longer exists, e.g. one that crashed during
+/// creation and never sent its Destroyed event
+pub fn reconcile_window_registry<R: Runtime>(
     app_handle: &AppHandle<R>,
     window_registry: &WindowRegistry,
-    root_path: &str,
+) -> Result<Vec<String>, String> {
+    let reaped =
+        window_registry.reap_stale_windows(&live_window_labels(app_handle), STALE_ENTRY_GRACE)?;
+    if !reaped.is_empty() {
+        log::info!("Window registry reconciliation reaped: {:?}", reaped);
+    }
+    Ok(reaped)
+}
+
+/// Focus the window that already has the project open. Returns `None` when the entry
+/// turns out to be stale, after removing it.
+fn focus_existing_window<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    window_registry: &WindowRegistry,
+    existing_label: &str,
 ) -> Result<Option<String>, String> {
-    if let Some(existing_label) = window_registry.find_window_by_project(root_path)? {
+    log::info!(
+        "Project already open in window: {}, attempting to focus it",
+        existing_label
+    );
+    if let Some(window) = app_handle.get_webview_window(existing_label) {
+        window.set_focus().map_err(|e| e.to_string())?;
+        window.show().map_err(|e| e.to_string())?;
+        #[cfg(target_os = "macos")]
+        {
+            use cocoa::appkit::NSApplication;
+            unsafe {
+                let app = cocoa::appkit::NSApp();
+                app.activateIgnoringOtherApps_(cocoa::base::YES);
+            }
+        }
+        log::info!("Successfully focused existing window: {}", existing_label);
+        return Ok(Some(existing_label.to_string()));
+    }
+    if window_registry.is_pending(existing_label) {
+        // The winning call is still building the window, which takes focus once shown
         log::info!(
-            "Project already open in window: {}, attempting to focus it",
+            "Window {} for this project is still being created, not creating another",
             existing_label
         );
-        if let Some(window) = app_handle.get_webview_window(&existing_label) {
-            window.set_focus().map_err(|e| e.to_string())?;
-            window.show().map_err(|e| e.to_string())?;
-            #[cfg(target_os = "macos")]
-            {
-                use cocoa::appkit::NSApplication;
-                unsafe {
-                    let app = cocoa::appkit::NSApp();
-                    app.activateIgnoringOtherApps_(cocoa::base::YES);
-                }
-            }
-            log::info!("Successfully focused existing window: {}", existing_label);
-            return Ok(Some(existing_label));
-        } else {
-            log::warn!(
-                "Window {} is in registry but doesn't exist, cleaning up",
-                existing_label
-            );
-            window_registry.unregister_window(&existing_label)?;
-            log::info!("Cleaned up stale window registration, will create new window");
-        }
+        return Ok(Some(existing_label.to_string()));
     }
+    log::warn!(
+        "Window {} is in registry but doesn't exist, cleaning up",
+        existing_label
+    );
+    window_registry.unregister_window(existing_label)?;
+    log::info!("Cleaned up stale window registration, will create new window");
     Ok(None)
 }
 
@@ -246,14 +517,12 @@ fn remove_window_state_from_file<R: Runtime>(
     Ok(())
 }
 
-fn register_window_with_cleanup<R: Runtime>(
+/// Unregister the window and drop its saved state once it is destroyed
+fn attach_cleanup_handler<R: Runtime>(
     window: &tauri::WebviewWindow<R>,
     window_registry: &WindowRegistry,
     label: String,
-    state: WindowState,
-) -> Result<(), String> {
-    window_registry.register_window(label.clone(), state)?;
-
+) {
     let registry_clone = window_registry.clone();
     let label_clone = label.clone();
     let app_handle = window.app_handle().clone();
@@ -269,6 +538,7 @@ fn register_window_with_cleanup<R: Runtime>(
             if let Err(e) = registry_clone.unregister_window(&label_clone) {
                 log::error!("Failed to unregister window {}: {}", label_clone, e);
             }
+            crate::window_broadcast::window_closed(&label_clone);
 
             // Clean up windows-state.json
             if let Err(e) = remove_window_state_from_file(&app_handle, &label_clone) {
@@ -282,7 +552,6 @@ fn register_window_with_cleanup<R: Runtime>(
     });
 
     log::info!("Window created successfully: {}", label);
-    Ok(())
 }
 
 pub fn create_window<R: Runtime>(
@@ -292,20 +561,65 @@ pub fn create_window<R: Runtime>(
     root_path: Option<String>,
     is_new_window: bool,
 ) -> Result<String, String> {
+    // Generate unique window label
+    let label = generate_window_label()?;
+
     // Only try to reuse existing window if not explicitly requesting a new window
-    // When is_new_window is true, always create a new window even if project is already open
+    // When is_new_window is true, always create a new window even if project is already open.
+    // Otherwise claim the project atomically, so two rapid requests can't both build one
+    let mut claimed = false;
     if !is_new_window {
         if let Some(ref path) = root_path {
-            if let Some(existing_label) =
-                try_focus_existing_window(app_handle, window_registry, path)?
-            {
-                return Ok(existing_label);
+            // A second attempt covers a stale entry that was just cleaned up
+            for _ in 0..2 {
+                match window_registry.try_claim_project(&label, path, project_id.clone())? {
+                    ProjectClaim::Claimed => {
+                        claimed = true;
+                        break;
+                    }
+                    ProjectClaim::Existing(existing_label) => {
+                        if let Some(focused) =
+                            focus_existing_window(app_handle, window_registry, &existing_label)?
+                        {
+                            return Ok(focused);
+                        }
+                    }
+                }
             }
         }
     }
 
-    // Generate unique window label
-    let label = generate_window_label()?;
+    if let Some(ref path) = root_path {
+        if let Some(main_label) = window_registry.find_main_repo_window(path)? {
+            log::info!(
+                "Project {} is a worktree of the repository open in window {}",
+                path,
+                main_label
+            );
+        }
+    }
+
+    // Start sizing the project early so the file watcher can pick a safe mode
+    if let Some(ref path) = root_path {
+        crate::project_scale::prefetch(path.clone());
+    }
+
+    // The user may have opened a subdirectory of a repository or workspace
+    let inferred_root = root_path
+        .as_deref()
+        .and_then(|path| Some((path, crate::project_root::infer_root(Path::new(path))?)))
+        .filter(|(path, inferred)| !same_root(path, &inferred.root))
+        .map(|(path, inferred)| {
+            log::info!(
+                "Project {} is inside {} ({})",
+                path,
+                inferred.root,
+                inferred.subpath
+            );
+            window_registry.record_inferred_root(path, inferred.clone());
+            inferred
+        });
+
     let title = build_window_title(root_path.as_ref());
 
     log::info!(
@@ -322,19 +636,42 @@ pub fn create_window<R: Runtime>(
         "/"
     };
 
-    let window = WebviewWindowBuilder::new(app_handle, &label, WebviewUrl::App(url_path.into()))
-        .title(&title)
-        .inner_size(1200.0, 800.0)
-        .build()
-        .map_err(|e| format!("Failed to create window: {}", e))?;
-
-    // Register window in registry and set up cleanup handler
-    let state = WindowState {
-        project_id,
-        root_path,
-        file_watcher: None,
-    };
-    register_window_with_cleanup(&window, window_registry, label.clone(), state)?;
+    // Register before building so the new webview finds its project on first load, and
+    // roll the entry back if the window never comes up
+    if !claimed {
+        let state = WindowState {
+            project_id,
+            root_path,
+            file_watcher: None,
+        };
+        window_registry.register_window(label.clone(), state)?;
+    }
+
+    let window =
+        match WebviewWindowBuilder::new(app_handle, &label, WebviewUrl::App(url_path.into()))
+            .title(&title)
+            .inner_size(1200.0, 800.0)
+            .build()
+        {
+            Ok(window) => window,
+            Err(e) => {
+                log::error!(
+                    "Failed to build window {}, rolling back registration",
+                    label
+                );
+                window_registry.release_claim(&label)?;
+                return Err(format!("Failed to create window: {}", e));
+            }
+        };
+    window_registry.confirm_claim(&label)?;
+    attach_cleanup_handler(&window, window_registry, label.clone());
+
+    // A webview that loads after this event finds the same details in its WindowInfo
+    if let Some(inferred) = inferred_root {
+        if let Err(e) = crate::events::emit_project_root_inferred(app_handle, &label, &inferred) {
+            log::warn!("Failed to emit project-root-inferred for {}: {}", label, e);
+        }
+    }
 
     Ok(label)
 }
@@ -342,6 +679,7 @@ pub fn create_window<R: Runtime>(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_support;
 
     #[test]
     fn test_window_registry_new() {
@@ -370,6 +708,32 @@ mod tests {
         assert_eq!(windows[0].root_path, Some("/path/to/project".to_string()));
     }
 
+    #[test]
+    fn test_window_info_reports_inferred_root() {
+        let registry = WindowRegistry::new();
+        let state = WindowState {
+            project_id: None,
+            root_path: Some("/repo/packages/app".to_string()),
+            file_watcher: None,
+        };
+        registry
+            .register_window("window-1".to_string(), state)
+            .unwrap();
+        registry.record_inferred_root(
+            "/repo/packages/app",
+            InferredRoot {
+                root: "/repo".to_string(),
+                requested_path: "/repo/packages/app".to_string(),
+                subpath: "packages/app".to_string(),
+                evidence: Vec::new(),
+            },
+        );
+
+        let windows = registry.get_all_windows().unwrap();
+        assert_eq!(windows[0].inferred_root.as_deref(), Some("/repo"));
+        assert_eq!(windows[0].selected_subpath.as_deref(), Some("packages/app"));
+    }
+
     #[test]
     fn test_unregister_window() {
         let registry = WindowRegistry::new();
@@ -436,6 +800,59 @@ mod tests {
         assert!(not_found.is_none());
     }
 
+    #[test]
+    fn test_worktree_window_links_to_main_repo_window() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let main = temp_dir.path().join("repo");
+        let linked = temp_dir.path().join("repo-feature");
+        std::fs::create_dir(&main).unwrap();
+        let git = |args: &[&str]| test_support::git(&main, args);
+        git(&["init"]);
+        git(&["config", "user.email", "test@test.com"]);
+        git(&["config", "user.name", "Test User"]);
+        std::fs::write(main.join("README.md"), "# Test").unwrap();
+        git(&["add", "."]);
+        git(&["commit", "-m", "Initial commit"]);
+        git(&["worktree", "add", "-b", "feature", linked.to_str().unwrap()]);
+
+        let registry = WindowRegistry::new();
+        let state = |root: &std::path::Path| WindowState {
+            project_id: None,
+            root_path: Some(root.to_string_lossy().to_string()),
+            file_watcher: None,
+        };
+        registry
+            .register_window("main-window".to_string(), state(&main))
+            .unwrap();
+
+        let linked_root = linked.to_string_lossy().to_string();
+        // Not the same project, but related to the open main repository
+        assert_eq!(registry.find_window_by_project(&linked_root).unwrap(), None);
+        assert_eq!(
+            registry.find_main_repo_window(&linked_root).unwrap(),
+            Some("main-window".to_string())
+        );
+        assert_eq!(
+            registry
+                .find_main_repo_window(&main.to_string_lossy())
+                .unwrap(),
+            None
+        );
+
+        registry
+            .register_window("worktree-window".to_string(), state(&linked))
+            .unwrap();
+        let windows = registry.get_all_windows().unwrap();
+        let worktree = windows
+            .iter()
+            .find(|w| w.label == "worktree-window")
+            .unwrap();
+        assert_eq!(worktree.main_repo_window.as_deref(), Some("main-window"));
+        assert!(worktree.main_repo_path.is_some());
+        let main_info = windows.iter().find(|w| w.label == "main-window").unwrap();
+        assert_eq!(main_info.main_repo_path, None);
+    }
+
     #[test]
     fn test_update_window_project() {
         let registry = WindowRegistry::new();
@@ -503,6 +920,10 @@ mod tests {
             project_id: Some("project-1".to_string()),
             root_path: Some("/path/to/project".to_string()),
             title: "Project - TalkCody".to_string(),
+            main_repo_path: None,
+            main_repo_window: None,
+            inferred_root: None,
+            selected_subpath: None,
         };
 
         let json = serde_json::to_string(&info).unwrap();
@@ -523,6 +944,10 @@ mod tests {
             project_id: None,
             root_path: None,
             title: "TalkCody".to_string(),
+            main_repo_path: None,
+            main_repo_window: None,
+            inferred_root: None,
+            selected_subpath: None,
         };
 
         let json = serde_json::to_string(&info).unwrap();
@@ -602,6 +1027,72 @@ mod tests {
         assert_eq!(windows.len(), 10);
     }
 
+    #[test]
+    fn test_concurrent_project_claims_have_one_winner() {
+        use std::sync::Barrier;
+        use std::thread;
+
+        let registry = Arc::new(WindowRegistry::new());
+        let barrier = Arc::new(Barrier::new(2));
+        let handles: Vec<_> = (0..2)
+            .map(|i| {
+                let registry = Arc::clone(&registry);
+                let barrier = Arc::clone(&barrier);
+                thread::spawn(move || {
+                    let label = format!("window-{}", i);
+                    barrier.wait();
+                    let claim = registry
+                        .try_claim_project(&label, "/path/to/project", None)
+                        .unwrap();
+                    (label, claim)
+                })
+            })
+            .collect();
+        let results: Vec<(String, ProjectClaim)> =
+            handles.into_iter().map(|h| h.join().unwrap()).collect();
+
+        let winners: Vec<&String> = results
+            .iter()
+            .filter(|(_, claim)| *claim == ProjectClaim::Claimed)
+            .map(|(label, _)| label)
+            .collect();
+        assert_eq!(winners.len(), 1);
+        let winner = winners[0].clone();
+        let loser = results.iter().find(|(label, _)| *label != winner).unwrap();
+        assert_eq!(loser.1, ProjectClaim::Existing(winner.clone()));
+
+        // The loser sees the placeholder as a window still being built
+        assert!(registry.is_pending(&winner));
+        assert_eq!(registry.get_all_windows().unwrap().len(), 1);
+        registry.confirm_claim(&winner).unwrap();
+        assert!(!registry.is_pending(&winner));
+    }
+
+    #[test]
+    fn test_released_claim_can_be_claimed_again() {
+        let registry = WindowRegistry::new();
+        assert_eq!(
+            registry
+                .try_claim_project("window-1", "/path/to/project", Some("p".to_string()))
+                .unwrap(),
+            ProjectClaim::Claimed
+        );
+        assert_eq!(
+            registry.find_window_by_project("/path/to/project").unwrap(),
+            Some("window-1".to_string())
+        );
+
+        // Building window-1 failed
+        registry.release_claim("window-1").unwrap();
+        assert!(!registry.is_pending("window-1"));
+        assert_eq!(
+            registry
+                .try_claim_project("window-2", "/path/to/project", None)
+                .unwrap(),
+            ProjectClaim::Claimed
+        );
+    }
+
     #[test]
     fn test_cleanup_all_watchers_empty_registry() {
         // Test cleanup_all_watchers on empty registry doesn't panic
@@ -813,6 +1304,47 @@ mod tests {
         assert_eq!(windows.len(), 2);
     }
 
+    #[test]
+    fn test_reap_stale_windows_keeps_live_and_young_entries() {
+        let registry = WindowRegistry::new();
+        for label in ["main", "window-1", "window-2"] {
+            let state = WindowState {
+                project_id: None,
+                root_path: Some(format!("/path/{}", label)),
+                file_watcher: None,
+            };
+            registry.register_window(label.to_string(), state).unwrap();
+        }
+        registry
+            .set_window_file_watcher("window-2", Some(FileWatcher::new().unwrap()))
+            .unwrap();
+        let live: HashSet<String> = ["main".to_string()].into_iter().collect();
+
+        // Just-registered entries may belong to windows still being built
+        let reaped = registry
+            .reap_stale_windows(&live, Duration::from_secs(30))
+            .unwrap();
+        assert!(reaped.is_empty());
+
+        let dump = registry.dump(&live).unwrap();
+        assert_eq!(dump.len(), 3);
+        assert!(dump[0].live && dump[0].label == "main");
+        assert!(!dump[2].live && dump[2].has_file_watcher);
+
+        let reaped = registry.reap_stale_windows(&live, Duration::ZERO).unwrap();
+        assert_eq!(reaped, vec!["window-1", "window-2"]);
+        let windows = registry.get_all_windows().unwrap();
+        assert_eq!(windows.len(), 1);
+        assert_eq!(windows[0].label, "main");
+
+        // Reconciling again is a no-op
+        assert!(registry
+            .reap_stale_windows(&live, Duration::ZERO)
+            .unwrap()
+            .is_empty());
+        assert!(registry.dump(&live).unwrap()[0].registered_ms_ago.is_some());
+    }
+
     #[test]
     fn test_unregister_window_stops_its_watcher() {
         // Test that unregistering a window properly stops its file watcher
@@ -109,27 +109,27 @@ describe('useRepositoryWatcher', () => {
 
       // Wait for listeners to be set up
       await vi.waitFor(() => {
-        expect(eventListeners.has('git-status-changed')).toBe(true);
+        expect(eventListeners.has('git-status-changed-test-window-1')).toBe(true);
       });
 
       // Simulate rapid events (like git commit generating multiple .git file changes)
       act(() => {
-        emitEvent('git-status-changed', {});
+        emitEvent('git-status-changed-test-window-1', {});
       });
       vi.advanceTimersByTime(50);
 
       act(() => {
-        emitEvent('git-status-changed', {});
+        emitEvent('git-status-changed-test-window-1', {});
       });
       vi.advanceTimersByTime(50);
 
       act(() => {
-        emitEvent('git-status-changed', {});
+        emitEvent('git-status-changed-test-window-1', {});
       });
       vi.advanceTimersByTime(50);
 
       act(() => {
-        emitEvent('git-status-changed', {});
+        emitEvent('git-status-changed-test-window-1', {});
       });
 
       // Before debounce completes, refresh should not be called
@@ -148,11 +148,11 @@ describe('useRepositoryWatcher', () => {
       renderHook(() => useRepositoryWatcher());
 
       await vi.waitFor(() => {
-        expect(eventListeners.has('git-status-changed')).toBe(true);
+        expect(eventListeners.has('git-status-changed-test-window-1')).toBe(true);
       });
 
       act(() => {
-        emitEvent('git-status-changed', {});
+        emitEvent('git-status-changed-test-window-1', {});
       });
 
       // Not called immediately
@@ -169,12 +169,12 @@ describe('useRepositoryWatcher', () => {
       renderHook(() => useRepositoryWatcher());
 
       await vi.waitFor(() => {
-        expect(eventListeners.has('git-status-changed')).toBe(true);
+        expect(eventListeners.has('git-status-changed-test-window-1')).toBe(true);
       });
 
       // First event
       act(() => {
-        emitEvent('git-status-changed', {});
+        emitEvent('git-status-changed-test-window-1', {});
       });
 
       // Wait 200ms (not enough to trigger)
@@ -183,7 +183,7 @@ describe('useRepositoryWatcher', () => {
 
       // Second event resets timer
       act(() => {
-        emitEvent('git-status-changed', {});
+        emitEvent('git-status-changed-test-window-1', {});
       });
 
       // Another 200ms (still not enough from second event)
@@ -203,12 +203,12 @@ describe('useRepositoryWatcher', () => {
       const { unmount } = renderHook(() => useRepositoryWatcher());
 
       await vi.waitFor(() => {
-        expect(eventListeners.has('git-status-changed')).toBe(true);
+        expect(eventListeners.has('git-status-changed-test-window-1')).toBe(true);
       });
 
       // Emit event but unmount before debounce completes
       act(() => {
-        emitEvent('git-status-changed', {});
+        emitEvent('git-status-changed-test-window-1', {});
       });
       vi.advanceTimersByTime(100);
 
@@ -234,11 +234,11 @@ describe('useRepositoryWatcher', () => {
       renderHook(() => useRepositoryWatcher());
 
       await vi.waitFor(() => {
-        expect(eventListeners.has('file-system-changed')).toBe(true);
+        expect(eventListeners.has('file-system-changed-test-window-1')).toBe(true);
       });
 
       act(() => {
-        emitEvent('file-system-changed', [
+        emitEvent('file-system-changed-test-window-1', [
           '/test/repo/file1.ts',
           '/test/repo/file1.ts',
           '/test/repo/file3.ts',
@@ -261,11 +261,11 @@ describe('useRepositoryWatcher', () => {
       renderHook(() => useRepositoryWatcher());
 
       await vi.waitFor(() => {
-        expect(eventListeners.has('file-system-changed')).toBe(true);
+        expect(eventListeners.has('file-system-changed-test-window-1')).toBe(true);
       });
 
       act(() => {
-        emitEvent('file-system-changed', { path: '/test/repo/file1.ts' });
+        emitEvent('file-system-changed-test-window-1', { path: '/test/repo/file1.ts' });
       });
 
       vi.advanceTimersByTime(150);
@@ -284,11 +284,13 @@ describe('useRepositoryWatcher', () => {
       renderHook(() => useRepositoryWatcher());
 
       await vi.waitFor(() => {
-        expect(eventListeners.has('file-system-changed')).toBe(true);
+        expect(eventListeners.has('file-system-changed-test-window-1')).toBe(true);
       });
 
       act(() => {
-        emitEvent('file-system-changed', { paths: ['/test/repo/file2.ts', '/test/repo/file3.ts'] });
+        emitEvent('file-system-changed-test-window-1', {
+          paths: ['/test/repo/file2.ts', '/test/repo/file3.ts'],
+        });
       });
 
       vi.advanceTimersByTime(150);
@@ -303,18 +305,18 @@ describe('useRepositoryWatcher', () => {
       renderHook(() => useRepositoryWatcher());
 
       await vi.waitFor(() => {
-        expect(eventListeners.has('file-system-changed')).toBe(true);
+        expect(eventListeners.has('file-system-changed-test-window-1')).toBe(true);
       });
 
       // Multiple file changes
       act(() => {
-        emitEvent('file-system-changed', ['/test/repo/file1.ts']);
+        emitEvent('file-system-changed-test-window-1', ['/test/repo/file1.ts']);
       });
       act(() => {
-        emitEvent('file-system-changed', ['/test/repo/file2.ts']);
+        emitEvent('file-system-changed-test-window-1', ['/test/repo/file2.ts']);
       });
       act(() => {
-        emitEvent('file-system-changed', ['/test/repo/file3.ts']);
+        emitEvent('file-system-changed-test-window-1', ['/test/repo/file3.ts']);
       });
 
       // Before debounce completes
@@ -332,11 +334,11 @@ describe('useRepositoryWatcher', () => {
       renderHook(() => useRepositoryWatcher());
 
       await vi.waitFor(() => {
-        expect(eventListeners.has('file-system-changed')).toBe(true);
+        expect(eventListeners.has('file-system-changed-test-window-1')).toBe(true);
       });
 
       act(() => {
-        emitEvent('file-system-changed', ['/test/repo/file.ts']);
+        emitEvent('file-system-changed-test-window-1', ['/test/repo/file.ts']);
       });
 
       vi.advanceTimersByTime(300);
@@ -346,6 +348,43 @@ describe('useRepositoryWatcher', () => {
     });
   });
 
+  describe('window scoping', () => {
+    it('should ignore payloads labelled for another window', async () => {
+      const useRepositoryWatcher = await getHook();
+
+      mockRepositoryState.openFiles = [
+        { path: '/test/repo/file1.ts', content: 'a', isLoading: false, error: null },
+      ];
+
+      renderHook(() => useRepositoryWatcher());
+
+      await vi.waitFor(() => {
+        expect(eventListeners.has('file-system-changed-test-window-1')).toBe(true);
+      });
+      expect(eventListeners.has('file-system-changed')).toBe(false);
+
+      act(() => {
+        emitEvent('file-system-changed-test-window-1', {
+          windowLabel: 'other-window',
+          paths: ['/test/repo/file1.ts'],
+        });
+      });
+      vi.advanceTimersByTime(500);
+      expect(mockHandleExternalFileChange).not.toHaveBeenCalled();
+      expect(mockRefreshFileTree).not.toHaveBeenCalled();
+
+      act(() => {
+        emitEvent('file-system-changed-test-window-1', {
+          windowLabel: 'test-window-1',
+          paths: ['/test/repo/file1.ts'],
+        });
+      });
+      vi.advanceTimersByTime(500);
+      expect(mockHandleExternalFileChange).toHaveBeenCalledWith('/test/repo/file1.ts');
+      expect(mockRefreshFileTree).toHaveBeenCalled();
+    });
+  });
+
   describe('event listener setup', () => {
     it('should set up listeners for both events', async () => {
       const useRepositoryWatcher = await getHook();
@@ -353,8 +392,8 @@ describe('useRepositoryWatcher', () => {
       renderHook(() => useRepositoryWatcher());
 
       await vi.waitFor(() => {
-        expect(eventListeners.has('file-system-changed')).toBe(true);
-        expect(eventListeners.has('git-status-changed')).toBe(true);
+        expect(eventListeners.has('file-system-changed-test-window-1')).toBe(true);
+        expect(eventListeners.has('git-status-changed-test-window-1')).toBe(true);
       });
     });
 
@@ -1,4 +1,4 @@
-import { listen } from '@tauri-apps/api/event';
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
 import { useCallback, useEffect, useRef } from 'react';
 import { logger } from '@/lib/logger';
 import { fastDirectoryTreeService } from '@/services/fast-directory-tree-service';
@@ -34,6 +34,21 @@ const normalizeFileSystemPaths = (payload: unknown): string[] => {
   return [];
 };
 
+/**
+ * Watcher events are emitted per window as `<event>-<label>`, so a window never
+ * reacts to changes in another window's project
+ */
+const windowEventName = (event: string, windowLabel: string) => `${event}-${windowLabel}`;
+
+/** Ignore payloads labelled for another window */
+const isForOtherWindow = (payload: unknown, windowLabel: string): boolean => {
+  if (payload && typeof payload === 'object' && !Array.isArray(payload)) {
+    const label = (payload as { windowLabel?: unknown }).windowLabel;
+    return isNonEmptyString(label) && label !== windowLabel;
+  }
+  return false;
+};
+
 /**
  * Hook to set up file system watching for the currently open repository
  * This should be called in components that need to monitor file system changes
@@ -103,26 +118,11 @@ export function useRepositoryWatcher() {
       return;
     }
 
-    // Start file watching with window-specific watcher
-    const startWatching = async () => {
-      try {
-        // Get current window label for window-specific file watching
-        const windowLabel = await WindowManagerService.getCurrentWindowLabel();
-        windowLabelRef.current = windowLabel;
+    let disposed = false;
+    const unlisteners: UnlistenFn[] = [];
 
-        // Use window-specific file watching to support multiple windows
-        await WindowManagerService.startWindowFileWatching(windowLabel, rootPath);
-        logger.info(`File watching started for window ${windowLabel} at:`, rootPath);
-      } catch (error) {
-        logger.error('Failed to start file watching:', error);
-      }
-    };
-
-    startWatching();
-
-    // Listen for file system changes
-    const unlistenFileSystem = listen('file-system-changed', (event) => {
-      const changedPaths = normalizeFileSystemPaths(event.payload);
+    const handleFileSystemChanged = (payload: unknown) => {
+      const changedPaths = normalizeFileSystemPaths(payload);
 
       if (changedPaths.length > 0) {
         const uniquePaths = Array.from(new Set(changedPaths));
@@ -148,12 +148,50 @@ export function useRepositoryWatcher() {
       // This is needed because .git directory only changes on git add/commit,
       // but git status should reflect working directory changes immediately
       debouncedRefreshGitStatusForFileChange();
-    });
+    };
 
-    // Listen for git status changes (from .git directory watcher)
-    const unlistenGitStatus = listen('git-status-changed', () => {
-      debouncedRefreshGitStatus();
-    });
+    // Start file watching with window-specific watcher
+    const startWatching = async () => {
+      try {
+        // Get current window label for window-specific file watching
+        const windowLabel = await WindowManagerService.getCurrentWindowLabel();
+        windowLabelRef.current = windowLabel;
+
+        // Listen for file system changes in this window's project
+        const unlistenFileSystem = await listen(
+          windowEventName('file-system-changed', windowLabel),
+          (event) => {
+            if (!isForOtherWindow(event.payload, windowLabel)) {
+              handleFileSystemChanged(event.payload);
+            }
+          }
+        );
+        // Listen for git status changes (from .git directory watcher)
+        const unlistenGitStatus = await listen(
+          windowEventName('git-status-changed', windowLabel),
+          (event) => {
+            if (!isForOtherWindow(event.payload, windowLabel)) {
+              debouncedRefreshGitStatus();
+            }
+          }
+        );
+        unlisteners.push(unlistenFileSystem, unlistenGitStatus);
+        if (disposed) {
+          for (const unlisten of unlisteners) {
+            unlisten();
+          }
+          return;
+        }
+
+        // Use window-specific file watching to support multiple windows
+        await WindowManagerService.startWindowFileWatching(windowLabel, rootPath);
+        logger.info(`File watching started for window ${windowLabel} at:`, rootPath);
+      } catch (error) {
+        logger.error('Failed to start file watching:', error);
+      }
+    };
+
+    startWatching();
 
     return () => {
       // Clear all pending timeouts on cleanup
@@ -170,8 +208,10 @@ export function useRepositoryWatcher() {
         fileChangeGitTimeoutRef.current = null;
       }
 
-      unlistenFileSystem.then((fn) => fn());
-      unlistenGitStatus.then((fn) => fn());
+      disposed = true;
+      for (const unlisten of unlisteners.splice(0)) {
+        unlisten();
+      }
 
       // Stop window-specific file watching
       if (windowLabelRef.current) {
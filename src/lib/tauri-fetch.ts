@@ -40,12 +40,32 @@ function sleep(ms: number): Promise<void> {
   return new Promise((resolve) => setTimeout(resolve, ms));
 }
 
+/**
+ * One multipart/form-data field. Set exactly one of text, base64 or file_path;
+ * file parts are streamed from disk by the Rust side.
+ */
+export interface MultipartPart {
+  name: string;
+  filename?: string;
+  content_type?: string;
+  text?: string;
+  base64?: string;
+  file_path?: string;
+}
+
 export interface ProxyRequest {
   url: string;
   method: string;
   headers: Record<string, string>;
   body?: string;
   request_id?: number;
+  multipart?: MultipartPart[];
+  /** Project root that multipart file parts must stay inside */
+  root_path?: string;
+  /** stream_fetch only: emit parsed server-sent events instead of raw chunks */
+  parse_sse?: boolean;
+  /** proxy_fetch and proxy_fetch_stream: largest body to accumulate, in bytes (default 64 MiB) */
+  max_body_bytes?: number;
 }
 
 export interface ProxyResponse {
@@ -66,6 +86,22 @@ type StreamEvent = {
   status?: number;
 };
 
+/**
+ * Emitted on stream-response-{request_id} instead of chunks when parse_sse is set.
+ * `done` is the OpenAI-style `data: [DONE]` sentinel; the stream still ends with
+ * the usual `{ status: 0 }` payload.
+ */
+export type SseStreamEvent =
+  | {
+      request_id: number;
+      kind: 'event';
+      event: string;
+      data: string;
+      id: string | null;
+      retry: number | null;
+    }
+  | { request_id: number; kind: 'done' };
+
 /**
  * Tauri fetch function type that is compatible across different environments
  */
@@ -5,6 +5,7 @@ import { GenericToolDoing } from '@/components/tools/generic-tool-doing';
 import { GenericToolResult } from '@/components/tools/generic-tool-result';
 import { createTool } from '@/lib/create-tool';
 import { logger } from '@/lib/logger';
+import { searchErrorMessage } from '@/services/repository-service';
 import { getEffectiveWorkspaceRoot } from '@/services/workspace-root-service';
 
 export interface CodeSearchResult {
@@ -61,18 +62,29 @@ Use this to find code patterns, function definitions, variable usage, or any tex
       });
 
       // Use Rust RipgrepSearch via Tauri command with new optional parameters
-      const searchResults: Array<{
-        file_path: string;
-        matches: Array<{
-          line_number: number;
-          line_content: string;
-          byte_offset: number;
+      const {
+        results: searchResults,
+        depth_limited,
+        truncated_subtrees,
+      }: {
+        results: Array<{
+          file_path: string;
+          matches: Array<{
+            line_number: number;
+            line_content: string;
+            byte_offset: number;
+          }>;
         }>;
-      }> = await invoke('search_file_content', {
+        depth_limited: boolean;
+        truncated_subtrees: number;
+      } = await invoke('search_file_content', {
         query: pattern,
         rootPath: searchPath,
         fileTypes: file_types || null,
       });
+      const depthNote = depth_limited
+        ? `\n\nNote: ${truncated_subtrees} deeply nested director${truncated_subtrees === 1 ? 'y was' : 'ies were'} not searched (depth limit reached).`
+        : '';
 
       if (searchResults && searchResults.length > 0) {
         // Format results for better readability
@@ -92,13 +104,13 @@ Use this to find code patterns, function definitions, variable usage, or any tex
 
         return {
           success: true,
-          result: `Found ${totalMatches} matches:\n${formattedResults.trim()}`,
+          result: `Found ${totalMatches} matches:\n${formattedResults.trim()}${depthNote}`,
         };
       }
 
       return {
         success: true,
-        result: 'No matches found',
+        result: `No matches found${depthNote}`,
       };
     } catch (error) {
       logger.error('Error executing Rust code search:', error);
@@ -106,7 +118,7 @@ Use this to find code patterns, function definitions, variable usage, or any tex
       return {
         success: false,
         result: 'Error executing code search',
-        error: error instanceof Error ? error.message : String(error),
+        error: searchErrorMessage(error),
       };
     }
   },
@@ -17,13 +17,17 @@ function useRealisticIsAbsoluteMock() {
   mockIsAbsolute.mockImplementation(async (p: string) => path.isAbsolute(p));
 }
 
+function globResponse(results: unknown[]) {
+  return { results, depth_limited: false, truncated_subtrees: 0 };
+}
+
 describe('globTool', () => {
   const PROJECT_ROOT = '/test/root';
 
   beforeEach(() => {
     vi.clearAllMocks();
     mockGetEffectiveWorkspaceRoot.mockResolvedValue(PROJECT_ROOT);
-    mockInvoke.mockResolvedValue([]);
+    mockInvoke.mockResolvedValue(globResponse([]));
     // Use realistic isAbsolute behavior based on Node.js path module
     useRealisticIsAbsoluteMock();
     // Use realistic join behavior
@@ -198,7 +202,7 @@ describe('globTool', () => {
 
   describe('result formatting', () => {
     it('should return "No files found" message when results are empty', async () => {
-      mockInvoke.mockResolvedValue([]);
+      mockInvoke.mockResolvedValue(globResponse([]));
 
       const result = await globTool.execute({ pattern: '**/*.ts' });
 
@@ -210,7 +214,7 @@ describe('globTool', () => {
         { path: `${PROJECT_ROOT}/src/index.ts`, is_directory: false, modified_time: 1700000000 },
         { path: `${PROJECT_ROOT}/src/utils.ts`, is_directory: false, modified_time: 1700000000 },
       ];
-      mockInvoke.mockResolvedValue(mockResults);
+      mockInvoke.mockResolvedValue(globResponse(mockResults));
 
       const result = await globTool.execute({ pattern: '**/*.ts' });
 
@@ -223,11 +227,20 @@ describe('globTool', () => {
       const mockResults = [
         { path: `${PROJECT_ROOT}/src`, is_directory: true, modified_time: 1700000000 },
       ];
-      mockInvoke.mockResolvedValue(mockResults);
+      mockInvoke.mockResolvedValue(globResponse(mockResults));
 
       const result = await globTool.execute({ pattern: 'src' });
 
       expect(result).toContain('[DIR]');
     });
+
+    it('should say when deeply nested directories were not searched', async () => {
+      mockInvoke.mockResolvedValue({ results: [], depth_limited: true, truncated_subtrees: 2 });
+
+      const result = await globTool.execute({ pattern: '**/deep.ts' });
+
+      expect(result).toContain('No files found');
+      expect(result).toContain('2 deeply nested directories were not searched');
+    });
   });
 });
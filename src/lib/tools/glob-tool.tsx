@@ -28,6 +28,12 @@ interface GlobResultType {
   modified_time: number;
 }
 
+interface GlobResponse {
+  results: GlobResultType[];
+  depth_limited: boolean;
+  truncated_subtrees: number;
+}
+
 export const globTool = createTool({
   name: 'glob',
   description: DESCRIPTION,
@@ -60,14 +66,20 @@ export const globTool = createTool({
       }
       logger.info(`Searching files with pattern "${pattern}" in path: ${searchPath}`);
 
-      const results: GlobResultType[] = await invoke('search_files_by_glob', {
-        pattern,
-        path: searchPath,
-      });
+      const { results, depth_limited, truncated_subtrees }: GlobResponse = await invoke(
+        'search_files_by_glob',
+        {
+          pattern,
+          path: searchPath,
+        }
+      );
+      const depthNote = depth_limited
+        ? `\n\nNote: ${truncated_subtrees} deeply nested director${truncated_subtrees === 1 ? 'y was' : 'ies were'} not searched (depth limit reached).`
+        : '';
 
       // Format results for display
       if (results.length === 0) {
-        return `No files found matching pattern "${pattern}" in ${searchPath}`;
+        return `No files found matching pattern "${pattern}" in ${searchPath}${depthNote}`;
       }
 
       const formattedResults = results
@@ -78,7 +90,7 @@ export const globTool = createTool({
         })
         .join('\n');
 
-      return `Found ${results.length} file(s) matching "${pattern}":\n\n${formattedResults}`;
+      return `Found ${results.length} file(s) matching "${pattern}":\n\n${formattedResults}${depthNote}`;
     } catch (error) {
       logger.error('Error searching files with glob pattern:', error);
       return (
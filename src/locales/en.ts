@@ -1063,6 +1063,8 @@ const en: LocaleDefinition = {
     errors: {
       failedToLoadDirectory: 'Failed to load directory contents',
       failedToOpen: (message) => `Failed to open repository: ${message}`,
+      permissionDenied: (path) =>
+        `No permission to read ${path}. On macOS, allow access in System Settings > Privacy & Security > Files and Folders.`,
       failedToRead: (message) => `Failed to read file: ${message}`,
       failedToSave: (message) => `Failed to save file: ${message}`,
       searchFailed: 'Search failed',
@@ -1022,6 +1022,7 @@ export interface LocaleDefinition {
     errors: {
       failedToLoadDirectory: string;
       failedToOpen: (message: string) => string;
+      permissionDenied: (path: string) => string;
       failedToRead: (message: string) => string;
       failedToSave: (message: string) => string;
       searchFailed: string;
@@ -1035,6 +1035,8 @@ const zh: LocaleDefinition = {
     errors: {
       failedToLoadDirectory: '无法加载目录内容',
       failedToOpen: (message) => `无法打开仓库：${message}`,
+      permissionDenied: (path) =>
+        `没有读取 ${path} 的权限。在 macOS 上，请在“系统设置 > 隐私与安全性 > 文件和文件夹”中允许访问。`,
       failedToRead: (message) => `无法读取文件：${message}`,
       failedToSave: (message) => `无法保存文件：${message}`,
       searchFailed: '搜索失败',
@@ -0,0 +1,70 @@
+import { invoke } from '@tauri-apps/api/core';
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
+
+export type ActivityKind = 'indexing' | 'search' | 'archive' | 'git' | 'other';
+
+export type ActivityOutcome =
+  | { status: 'succeeded' }
+  | { status: 'failed'; message: string }
+  | { status: 'cancelled' };
+
+export interface Activity {
+  id: string;
+  kind: ActivityKind;
+  title: string;
+  cancellable: boolean;
+  done: number;
+  /** null while the amount of work is unknown */
+  total: number | null;
+  detail: string | null;
+  startedAt: number;
+  /** null while the activity is running */
+  outcome: ActivityOutcome | null;
+  finishedAt: number | null;
+}
+
+/**
+ * Running and recently finished backend activities, oldest first
+ */
+export async function listActivities(): Promise<Activity[]> {
+  return invoke('activity_list');
+}
+
+/**
+ * Ask a cancellable activity to stop.
+ * @returns false if the activity is not running or cannot be cancelled
+ */
+export async function cancelActivity(id: string): Promise<boolean> {
+  return invoke('activity_cancel', { id });
+}
+
+/**
+ * Subscribe to activity changes (start, throttled progress, finish)
+ */
+export async function onActivityUpdated(handler: (activity: Activity) => void): Promise<UnlistenFn> {
+  return listen<Activity>('activity-updated', (event) => handler(event.payload));
+}
+
+export interface ConcurrencyBudget {
+  limit: number;
+  active: number;
+  waiting: number;
+  /** Labels of the operations holding a permit, oldest first */
+  operations: string[];
+}
+
+export interface ConcurrencyStatus {
+  /** Search and reference finding */
+  interactive: ConcurrencyBudget;
+  /** Indexing and directory sizes */
+  background: ConcurrencyBudget;
+  rayonThreads: number;
+  availableCores: number;
+}
+
+/**
+ * Which operations hold or wait for a concurrency permit, for debugging UI lag
+ */
+export async function getConcurrencyStatus(): Promise<ConcurrencyStatus> {
+  return invoke('get_concurrency_status');
+}
@@ -0,0 +1,52 @@
+import { invoke } from '@tauri-apps/api/core';
+
+export interface ArchiveEntryInfo {
+  /** Path inside the archive, without a leading "./" */
+  path: string;
+  size: number;
+  kind: 'file' | 'directory' | 'symlink' | 'other';
+  linkTarget: string | null;
+}
+
+export interface ArchiveSessionInfo {
+  sessionId: string;
+  tarballPath: string;
+  entries: ArchiveEntryInfo[];
+  /** Entries hidden because their paths are absolute or contain ".." */
+  rejectedEntries: string[];
+  inMemory: boolean;
+}
+
+export interface ArchiveEntryContent {
+  entryPath: string;
+  size: number;
+  /** Text, or base64 when encoding is "base64" */
+  content: string;
+  encoding: 'utf-8' | 'utf-16le' | 'utf-16be' | 'latin1' | 'base64';
+  isBinary: boolean;
+  truncated: boolean;
+}
+
+/**
+ * Scan a .tar or .tar.gz once so its entries can be browsed and read quickly.
+ * Close the session when the archive view goes away.
+ */
+export async function openArchiveSession(tarballPath: string): Promise<ArchiveSessionInfo> {
+  return invoke('archive_open_session', { tarballPath });
+}
+
+/**
+ * Read one entry without extracting the archive (1 MB by default).
+ * Uses an open session on the same archive when there is one.
+ */
+export async function readArchiveEntry(
+  tarballPath: string,
+  entryPath: string,
+  maxBytes?: number
+): Promise<ArchiveEntryContent> {
+  return invoke('archive_read_entry', { tarballPath, entryPath, maxBytes: maxBytes ?? null });
+}
+
+export async function closeArchiveSession(sessionId: string): Promise<boolean> {
+  return invoke('archive_close_session', { sessionId });
+}
@@ -0,0 +1,63 @@
+import { invoke } from '@tauri-apps/api/core';
+
+/** One recorded backend call. Contents appear as `{ sha256, bytes }`, secrets as "[redacted]". */
+export interface AuditEntry {
+  timestamp: number;
+  command: string;
+  args: unknown;
+  durationMs: number;
+  outcome: 'ok' | 'error';
+  error?: string;
+}
+
+export interface AuditSession {
+  id: string;
+  startedAt: number;
+  bytes: number;
+  parts: number;
+  /** Earlier parts were dropped by rotation */
+  truncated: boolean;
+  active: boolean;
+}
+
+export interface AuditPage {
+  entries: AuditEntry[];
+  /** Null at the end of the session */
+  nextOffset: number | null;
+}
+
+export interface AuditStatus {
+  enabled: boolean;
+  sessionId: string | null;
+  commands: string[];
+}
+
+/**
+ * Start recording sensitive backend calls in a new session, or stop. `commands` replaces
+ * the set of recorded commands.
+ */
+export async function setAuditEnabled(enabled: boolean, commands?: string[]): Promise<AuditStatus> {
+  return invoke('audit_set_enabled', { enabled, commands: commands ?? null });
+}
+
+/**
+ * Recorded sessions, newest first
+ */
+export async function listAuditSessions(): Promise<AuditSession[]> {
+  return invoke('audit_list_sessions');
+}
+
+export async function readAuditSession(
+  id: string,
+  offset?: number,
+  limit?: number
+): Promise<AuditPage> {
+  return invoke('audit_read_session', { id, offset, limit });
+}
+
+/**
+ * Copy a session to `dest` as one JSONL file for a bug report; returns the bytes written
+ */
+export async function exportAuditSession(id: string, dest: string): Promise<number> {
+  return invoke('audit_export', { id, dest });
+}
@@ -0,0 +1,39 @@
+import { invoke } from '@tauri-apps/api/core';
+
+export interface BackendParamSpec {
+  /** Argument name as passed to invoke */
+  name: string;
+  type: 'string' | 'number' | 'boolean' | 'array' | 'object';
+  required: boolean;
+}
+
+export interface BackendCommandSpec {
+  name: string;
+  category: string;
+  description: string;
+  params: BackendParamSpec[];
+  longRunning: boolean;
+  /** Can be stopped through activity_cancel */
+  cancellable: boolean;
+}
+
+export interface BackendCapabilities {
+  version: string;
+  commands: BackendCommandSpec[];
+}
+
+let cached: Promise<BackendCapabilities> | null = null;
+
+/**
+ * Every command the backend registers, with parameter schemas.
+ * The registry is static for a build, so it is fetched once.
+ */
+export function getBackendCapabilities(): Promise<BackendCapabilities> {
+  if (!cached) {
+    cached = invoke<BackendCapabilities>('get_backend_capabilities').catch((error) => {
+      cached = null;
+      throw error;
+    });
+  }
+  return cached;
+}
@@ -56,6 +56,11 @@ function createMockShellResult(overrides: {
   };
 }
 
+// Helper to wrap glob matches the way search_files_by_glob returns them
+function globResponse(results: unknown[]) {
+  return { results, depth_limited: false, truncated_subtrees: 0 };
+}
+
 describe('BashExecutor', () => {
   beforeEach(() => {
     mockInvoke.mockClear();
@@ -1350,12 +1355,12 @@ rm /outside/path`);
           // Default: return files within workspace with canonical_path
           const pattern = args.pattern as string;
           if (pattern.includes('/test/root/')) {
-            return Promise.resolve([
+            return Promise.resolve(globResponse([
               { path: '/test/root/file1.txt', canonical_path: '/test/root/file1.txt', is_directory: false, modified_time: 123 },
               { path: '/test/root/file2.txt', canonical_path: '/test/root/file2.txt', is_directory: false, modified_time: 124 },
-            ]);
+            ]));
           }
-          return Promise.resolve([]);
+          return Promise.resolve(globResponse([]));
         }
         return Promise.resolve(createMockShellResult({ code: 0 }));
       });
@@ -1383,10 +1388,10 @@ rm /outside/path`);
             return Promise.resolve(createMockShellResult({ code: 0, stdout: 'true\n' }));
           }
           if (cmd === 'search_files_by_glob') {
-            return Promise.resolve([
+            return Promise.resolve(globResponse([
               { path: '/test/root/src/file1.ts', canonical_path: '/test/root/src/file1.ts', is_directory: false, modified_time: 123 },
               { path: '/test/root/src/file2.ts', canonical_path: '/test/root/src/file2.ts', is_directory: false, modified_time: 124 },
-            ]);
+            ]));
           }
           return Promise.resolve(createMockShellResult({ code: 0 }));
         });
@@ -1401,10 +1406,10 @@ rm /outside/path`);
             return Promise.resolve(createMockShellResult({ code: 0, stdout: 'true\n' }));
           }
           if (cmd === 'search_files_by_glob') {
-            return Promise.resolve([
+            return Promise.resolve(globResponse([
               { path: '/test/root/src/a.js', canonical_path: '/test/root/src/a.js', is_directory: false, modified_time: 123 },
               { path: '/test/root/lib/b.js', canonical_path: '/test/root/lib/b.js', is_directory: false, modified_time: 124 },
-            ]);
+            ]));
           }
           return Promise.resolve(createMockShellResult({ code: 0 }));
         });
@@ -1419,10 +1424,10 @@ rm /outside/path`);
             return Promise.resolve(createMockShellResult({ code: 0, stdout: 'true\n' }));
           }
           if (cmd === 'search_files_by_glob') {
-            return Promise.resolve([
+            return Promise.resolve(globResponse([
               { path: '/test/root/dist/a.test.js', canonical_path: '/test/root/dist/a.test.js', is_directory: false, modified_time: 123 },
               { path: '/test/root/dist/sub/b.test.ts', canonical_path: '/test/root/dist/sub/b.test.ts', is_directory: false, modified_time: 124 },
-            ]);
+            ]));
           }
           return Promise.resolve(createMockShellResult({ code: 0 }));
         });
@@ -1442,10 +1447,10 @@ rm /outside/path`);
             return Promise.resolve(createMockShellResult({ code: 0, stdout: 'true\n' }));
           }
           if (cmd === 'search_files_by_glob') {
-            return Promise.resolve([
+            return Promise.resolve(globResponse([
               { path: '/test/root/temp1', canonical_path: '/test/root/temp1', is_directory: true, modified_time: 123 },
               { path: '/test/root/temp2', canonical_path: '/test/root/temp2', is_directory: true, modified_time: 124 },
-            ]);
+            ]));
           }
           return Promise.resolve(createMockShellResult({ code: 0 }));
         });
@@ -1460,10 +1465,10 @@ rm /outside/path`);
             return Promise.resolve(createMockShellResult({ code: 0, stdout: 'true\n' }));
           }
           if (cmd === 'search_files_by_glob') {
-            return Promise.resolve([
+            return Promise.resolve(globResponse([
               { path: '/test/root/file1.txt', canonical_path: '/test/root/file1.txt', is_directory: false, modified_time: 123 },
               { path: '/test/root/file2.txt', canonical_path: '/test/root/file2.txt', is_directory: false, modified_time: 124 },
-            ]);
+            ]));
           }
           return Promise.resolve(createMockShellResult({ code: 0 }));
         });
@@ -1478,9 +1483,9 @@ rm /outside/path`);
             return Promise.resolve(createMockShellResult({ code: 0, stdout: 'true\n' }));
           }
           if (cmd === 'search_files_by_glob') {
-            return Promise.resolve([
+            return Promise.resolve(globResponse([
               { path: '/test/root/fileA.txt', canonical_path: '/test/root/fileA.txt', is_directory: false, modified_time: 123 },
-            ]);
+            ]));
           }
           return Promise.resolve(createMockShellResult({ code: 0 }));
         });
@@ -1495,10 +1500,10 @@ rm /outside/path`);
             return Promise.resolve(createMockShellResult({ code: 0, stdout: 'true\n' }));
           }
           if (cmd === 'search_files_by_glob') {
-            return Promise.resolve([
+            return Promise.resolve(globResponse([
               { path: '/test/root/file.ts', canonical_path: '/test/root/file.ts', is_directory: false, modified_time: 123 },
               { path: '/test/root/file.js', canonical_path: '/test/root/file.js', is_directory: false, modified_time: 124 },
-            ]);
+            ]));
           }
           return Promise.resolve(createMockShellResult({ code: 0 }));
         });
@@ -1518,9 +1523,9 @@ rm /outside/path`);
             return Promise.resolve(createMockShellResult({ code: 0, stdout: 'true\n' }));
           }
           if (cmd === 'search_files_by_glob') {
-            return Promise.resolve([
+            return Promise.resolve(globResponse([
               { path: '/test/root/file.txt', canonical_path: '/test/root/file.txt', is_directory: false, modified_time: 123 },
-            ]);
+            ]));
           }
           return Promise.resolve(createMockShellResult({ code: 0 }));
         });
@@ -1535,9 +1540,9 @@ rm /outside/path`);
             return Promise.resolve(createMockShellResult({ code: 0, stdout: 'true\n' }));
           }
           if (cmd === 'search_files_by_glob') {
-            return Promise.resolve([
+            return Promise.resolve(globResponse([
               { path: '/test/root/file.txt', canonical_path: '/test/root/file.txt', is_directory: false, modified_time: 123 },
-            ]);
+            ]));
           }
           return Promise.resolve(createMockShellResult({ code: 0 }));
         });
@@ -1552,7 +1557,7 @@ rm /outside/path`);
             return Promise.resolve(createMockShellResult({ code: 0, stdout: 'true\n' }));
           }
           if (cmd === 'search_files_by_glob') {
-            return Promise.resolve([]); // No matches
+            return Promise.resolve(globResponse([])); // No matches
           }
           return Promise.resolve(createMockShellResult({ code: 0 }));
         });
@@ -1607,10 +1612,10 @@ rm /outside/path`);
           }
           if (cmd === 'search_files_by_glob') {
             // Simulating symlink attack: path looks safe but canonical_path reveals it points outside
-            return Promise.resolve([
+            return Promise.resolve(globResponse([
               { path: '/test/root/file.txt', canonical_path: '/test/root/file.txt', is_directory: false, modified_time: 123 },
               { path: '/test/root/link/dangerous.txt', canonical_path: '/outside/dangerous.txt', is_directory: false, modified_time: 124 },
-            ]);
+            ]));
           }
           return Promise.resolve(createMockShellResult({ code: 0 }));
         });
@@ -1633,7 +1638,7 @@ rm /outside/path`);
             }
             // Add one that points outside workspace via symlink
             paths.push({ path: '/test/root/link/danger.txt', canonical_path: '/outside/danger.txt', is_directory: false, modified_time: 100 });
-            return Promise.resolve(paths);
+            return Promise.resolve(globResponse(paths));
           }
           return Promise.resolve(createMockShellResult({ code: 0 }));
         });
@@ -1741,7 +1746,7 @@ rm ../*.txt`);
             return Promise.resolve(createMockShellResult({ code: 0, stdout: 'true\n' }));
           }
           if (cmd === 'search_files_by_glob') {
-            return Promise.resolve([]);
+            return Promise.resolve(globResponse([]));
           }
           return Promise.resolve(createMockShellResult({ code: 0 }));
         });
@@ -1766,9 +1771,9 @@ rm ../*.txt`);
             return Promise.resolve(createMockShellResult({ code: 0, stdout: 'true\n' }));
           }
           if (cmd === 'search_files_by_glob') {
-            return Promise.resolve([
+            return Promise.resolve(globResponse([
               { path: '/test/root/file.txt', canonical_path: '/test/root/file.txt', is_directory: false, modified_time: 123 },
-            ]);
+            ]));
           }
           return Promise.resolve(createMockShellResult({ code: 0 }));
         });
@@ -1785,9 +1790,9 @@ rm ../*.txt`);
             return Promise.resolve(createMockShellResult({ code: 0, stdout: 'true\n' }));
           }
           if (cmd === 'search_files_by_glob') {
-            return Promise.resolve([
+            return Promise.resolve(globResponse([
               { path: '/test/root/a/b/c/file.txt', canonical_path: '/test/root/a/b/c/file.txt', is_directory: false, modified_time: 123 },
-            ]);
+            ]));
           }
           return Promise.resolve(createMockShellResult({ code: 0 }));
         });
@@ -1806,10 +1811,10 @@ rm ../*.txt`);
           }
           if (cmd === 'search_files_by_glob') {
             // path looks safe (inside /test/root/link/) but canonical_path reveals symlink target
-            return Promise.resolve([
+            return Promise.resolve(globResponse([
               { path: '/test/root/link/passwd', canonical_path: '/etc/passwd', is_directory: false, modified_time: 123 },
               { path: '/test/root/link/shadow', canonical_path: '/etc/shadow', is_directory: false, modified_time: 124 },
-            ]);
+            ]));
           }
           return Promise.resolve(createMockShellResult({ code: 0 }));
         });
@@ -1826,10 +1831,10 @@ rm ../*.txt`);
             return Promise.resolve(createMockShellResult({ code: 0, stdout: 'true\n' }));
           }
           if (cmd === 'search_files_by_glob') {
-            return Promise.resolve([
+            return Promise.resolve(globResponse([
               { path: '/test/root/normal.txt', canonical_path: '/test/root/normal.txt', is_directory: false, modified_time: 123 },
               { path: '/test/root/safe_looking.txt', canonical_path: '/etc/passwd', is_directory: false, modified_time: 124 },
-            ]);
+            ]));
           }
           return Promise.resolve(createMockShellResult({ code: 0 }));
         });
@@ -1846,10 +1851,10 @@ rm ../*.txt`);
           }
           if (cmd === 'search_files_by_glob') {
             // Symlinks that point within workspace are safe
-            return Promise.resolve([
+            return Promise.resolve(globResponse([
               { path: '/test/root/link/file.txt', canonical_path: '/test/root/actual/file.txt', is_directory: false, modified_time: 123 },
               { path: '/test/root/link/other.txt', canonical_path: '/test/root/deep/nested/other.txt', is_directory: false, modified_time: 124 },
-            ]);
+            ]));
           }
           return Promise.resolve(createMockShellResult({ code: 0 }));
         });
@@ -1865,9 +1870,9 @@ rm ../*.txt`);
             return Promise.resolve(createMockShellResult({ code: 0, stdout: 'true\n' }));
           }
           if (cmd === 'search_files_by_glob') {
-            return Promise.resolve([
+            return Promise.resolve(globResponse([
               { path: '/test/root/link1', canonical_path: '/outside/sensitive', is_directory: false, modified_time: 123 },
-            ]);
+            ]));
           }
           return Promise.resolve(createMockShellResult({ code: 0 }));
         });
@@ -1915,7 +1920,7 @@ rm ../*.txt`);
                 modified_time: i,
               });
             }
-            return Promise.resolve(paths);
+            return Promise.resolve(globResponse(paths));
           }
           return Promise.resolve(createMockShellResult({ code: 0 }));
         });
@@ -1946,7 +1951,7 @@ rm ../*.txt`);
               is_directory: false,
               modified_time: 999,
             });
-            return Promise.resolve(paths);
+            return Promise.resolve(globResponse(paths));
           }
           return Promise.resolve(createMockShellResult({ code: 0 }));
         });
@@ -1964,10 +1969,10 @@ rm ../*.txt`);
             return Promise.resolve(createMockShellResult({ code: 0, stdout: 'true\n' }));
           }
           if (cmd === 'search_files_by_glob') {
-            return Promise.resolve([
+            return Promise.resolve(globResponse([
               { path: '/test/root/file1.txt', canonical_path: '/test/root/file1.txt', is_directory: false, modified_time: 123 },
               { path: '/test/root/file2.txt', canonical_path: '/test/root/file2.txt', is_directory: false, modified_time: 124 },
-            ]);
+            ]));
           }
           return Promise.resolve(createMockShellResult({ code: 0 }));
         });
@@ -12,6 +12,12 @@ interface TauriShellResult {
   timed_out: boolean;
   idle_timed_out: boolean;
   pid: number | null;
+  /** Stdout and stderr lines in the order they were read */
+  combined_output?: Array<['stdout' | 'stderr', string]>;
+  // Only reported when captureMetadata is set and the shell supports it
+  final_cwd?: string | null;
+  shell_reported_exit?: number | null;
+  duration_ms?: number | null;
 }
 
 // Result from Rust backend search_files_by_glob command
@@ -23,6 +29,13 @@ interface GlobResult {
   modified_time: number;
 }
 
+interface GlobResponse {
+  results: GlobResult[];
+  /** Directories below the walk's depth limit were not searched */
+  depth_limited: boolean;
+  truncated_subtrees: number;
+}
+
 export interface BashResult {
   success: boolean;
   message: string;
@@ -427,11 +440,14 @@ export class BashExecutor {
    */
   private async expandWildcards(pattern: string, workspaceRoot: string): Promise<string[]> {
     try {
-      const results = await invoke<GlobResult[]>('search_files_by_glob', {
+      const { results, depth_limited } = await invoke<GlobResponse>('search_files_by_glob', {
         pattern,
         path: workspaceRoot,
         maxResults: 10000, // Safety limit
       });
+      if (depth_limited) {
+        this.logger.warn('Wildcard expansion stopped at the depth limit:', pattern);
+      }
 
       // Use canonical_path (resolved symlinks) for security validation
       // This prevents symlink attacks where a symlink inside workspace points to external files
@@ -662,12 +678,20 @@ export class BashExecutor {
     timeoutMs?: number,
     idleTimeoutMs?: number
   ): Promise<TauriShellResult> {
-    return await invoke<TauriShellResult>('execute_user_shell', {
+    const result = await invoke<TauriShellResult>('execute_user_shell', {
       command,
       cwd,
       timeoutMs,
       idleTimeoutMs,
+      captureMetadata: true,
+      // cwd is the project root; its active environment profile applies
+      rootPath: cwd,
     });
+    // The shell may have finished just as the idle timeout fired
+    if (result.idle_timed_out && result.shell_reported_exit != null) {
+      return { ...result, idle_timed_out: false, code: result.shell_reported_exit };
+    }
+    return result;
   }
 
   /**
@@ -0,0 +1,74 @@
+import { invoke } from '@tauri-apps/api/core';
+
+export type BatchQueryKind = 'content' | 'fileName' | 'glob';
+
+export interface BatchQuery {
+  kind: BatchQueryKind;
+  pattern: string;
+  /** Cap for this query alone; files for content queries */
+  maxResults?: number;
+  /** Content queries only */
+  maxMatchesPerFile?: number;
+  /** Content queries only: extensions to search instead of the code file defaults */
+  fileTypes?: string[];
+}
+
+export interface BatchContentResult {
+  file_path: string;
+  matches: {
+    line_number: number;
+    line_content: string;
+    byte_offset: number;
+    column_start: number;
+    column_end: number;
+    match_ranges: [number, number][];
+  }[];
+}
+
+export interface BatchFileNameResult {
+  name: string;
+  path: string;
+  is_directory: boolean;
+  score: number;
+}
+
+export interface BatchGlobResult {
+  path: string;
+  canonical_path: string;
+  is_directory: boolean;
+  modified_time: number;
+}
+
+interface BatchQueryResultBase {
+  pattern: string;
+  /** The cap was reached with matches or unsearched files left over */
+  truncated: boolean;
+  /** Set when this query could not run, e.g. an invalid regex */
+  error: string | null;
+}
+
+export type BatchQueryResult = BatchQueryResultBase &
+  (
+    | { kind: 'content'; results: BatchContentResult[] }
+    | { kind: 'fileName'; results: BatchFileNameResult[] }
+    | { kind: 'glob'; results: BatchGlobResult[] }
+  );
+
+export interface BatchSearchResponse {
+  /** One entry per query, in request order */
+  results: BatchQueryResult[];
+  elapsedMs: number;
+  filesScanned: number;
+  filesRead: number;
+}
+
+/**
+ * Run several searches with a single walk of `rootPath`. Each file is read at most once
+ * however many content queries there are. All queries skip hidden and gitignored paths.
+ */
+export async function searchBatch(
+  rootPath: string,
+  queries: BatchQuery[]
+): Promise<BatchSearchResponse> {
+  return invoke('search_batch', { rootPath, queries });
+}
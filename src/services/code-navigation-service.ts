@@ -1,4 +1,6 @@
 import { invoke } from '@tauri-apps/api/core';
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
+import { getCustomLangIdForPath } from './language-mapping-service';
 
 export interface SymbolInfo {
   name: string;
@@ -9,8 +11,25 @@ export interface SymbolInfo {
   start_column: number;
   end_line: number;
   end_column: number;
+  /** Columns in UTF-16 code units (Monaco columns); start_column/end_column count bytes */
+  start_column_utf16?: number;
+  end_column_utf16?: number;
+  /** Importable from other modules: exported (JS/TS), module-level (Python) or pub (Rust) */
+  exported?: boolean;
+  /** From a dependency's public declarations (node_modules .d.ts or a registry crate) */
+  dependency?: boolean;
+  /** Access level by the language's rules; unknown for C/C++ and older index entries */
+  visibility?: SymbolVisibility;
 }
 
+export type SymbolVisibility = 'public' | 'internal' | 'protected' | 'private' | 'unknown';
+
+/**
+ * exported_only keeps public definitions (unknown ones by their exported flag);
+ * non_private drops private ones
+ */
+export type VisibilityFilter = 'exported_only' | 'non_private';
+
 /**
  * Get language family for language isolation
  * C/C++ share references, TypeScript/JavaScript share references
@@ -22,6 +41,8 @@ export function getLangFamily(langId: string): string {
       return 'c_family';
     case 'typescript':
     case 'javascript':
+    case 'vue':
+    case 'svelte':
       return 'js_family';
     case 'python':
       return 'python';
@@ -48,13 +69,102 @@ export async function indexFile(filePath: string, content: string, langId: strin
 }
 
 /**
- * Find definition of a symbol with language family filtering
+ * Find definition of a symbol with language family filtering.
+ * With includeDependencies, indexed dependency definitions follow the project's own.
  */
 export async function findDefinition(
   symbolName: string,
-  langFamily: string
+  langFamily: string,
+  includeDependencies?: boolean,
+  visibility?: VisibilityFilter
 ): Promise<SymbolInfo[]> {
-  return invoke('code_nav_find_definition', { symbolName, langFamily });
+  return invoke('code_nav_find_definition', {
+    symbolName,
+    langFamily,
+    includeDependencies: includeDependencies ?? null,
+    visibility: visibility ?? null,
+  });
+}
+
+export interface IndexedDependency {
+  name: string;
+  version: string | null;
+  langFamily: string;
+  location: string;
+  files: number;
+}
+
+export interface DependencyIndexResult {
+  indexed: IndexedDependency[];
+  notFound: string[];
+}
+
+/**
+ * Index the public declarations of dependencies so definition lookups can reach them:
+ * node_modules .d.ts entry points (or @types) and registry crate sources. Defaults to the
+ * direct dependencies in package.json and Cargo.toml.
+ */
+export async function indexDependencyTypes(
+  rootPath: string,
+  packageNames?: string[]
+): Promise<DependencyIndexResult> {
+  return invoke('code_nav_index_dependency_types', {
+    rootPath,
+    packageNames: packageNames ?? null,
+  });
+}
+
+export interface ImportSuggestion {
+  symbol: SymbolInfo;
+  /** Relative path (JS/TS), dotted module (Python) or module path (Rust) */
+  moduleSpecifier: string;
+  /** Ready to insert, e.g. `import { formatDate } from '../utils/format';` */
+  importStatement: string;
+}
+
+/**
+ * Where a symbol without an import can be imported from, closest to currentFile first.
+ * Supported for js_family, python and rust.
+ */
+export async function suggestImports(
+  symbolName: string,
+  langFamily: string,
+  currentFile: string,
+  rootPath: string,
+  maxResults?: number,
+  visibility?: VisibilityFilter
+): Promise<ImportSuggestion[]> {
+  return invoke('code_nav_suggest_imports', {
+    symbolName,
+    langFamily,
+    currentFile,
+    rootPath,
+    maxResults: maxResults ?? null,
+    visibility: visibility ?? null,
+  });
+}
+
+/**
+ * Restricts a reference search. Scopes combine as a union; with none set the whole root is searched.
+ */
+export interface ReferenceScope {
+  /** Directories or files, absolute or relative to the root */
+  includePaths?: string[];
+  /** Files modified, staged or untracked in git */
+  changedOnly?: boolean;
+  /** With changedOnly, also files changed on this branch since this ref */
+  baseRef?: string;
+}
+
+export interface ScopedReference extends SymbolInfo {
+  /** Scopes the reference matched, e.g. "path:src/ui", "changed", "base:main" */
+  matched_scopes?: string[];
+}
+
+export interface ReferenceSearchResult {
+  references: ScopedReference[];
+  /** The root is a sparse checkout, so references outside the sparse cone are missing */
+  sparse_checkout_active: boolean;
 }
 
 /**
@@ -65,9 +175,95 @@ export async function findDefinition(
 export async function findReferencesHybrid(
   symbolName: string,
   langFamily: string,
-  rootPath: string
-): Promise<SymbolInfo[]> {
-  return invoke('code_nav_find_references_hybrid', { symbolName, langFamily, rootPath });
+  rootPath: string,
+  scope?: ReferenceScope
+): Promise<ReferenceSearchResult> {
+  return invoke('code_nav_find_references_hybrid', {
+    symbolName,
+    langFamily,
+    rootPath,
+    includePaths: scope?.includePaths ?? null,
+    changedOnly: scope?.changedOnly ?? null,
+    baseRef: scope?.baseRef ?? null,
+  });
+}
+
+export interface DefinitionBody {
+  /** Whole lines of the definition, up to the caps */
+  text: string;
+  /** Lines covered by text, 1-based and inclusive */
+  start_line: number;
+  end_line: number;
+  /** Last line of the whole definition */
+  definition_end_line: number;
+  truncated: boolean;
+  /** Lines the definition moved from the symbol's stored position */
+  drift: number;
+}
+
+export interface DefinitionPeek {
+  name: string;
+  file_path: string;
+  /** The symbol's stored line */
+  start_line: number;
+  /** Null while pending, or when the definition is no longer near its stored line */
+  body: DefinitionBody | null;
+  /** Not reached within the budget; ask again */
+  pending: boolean;
+}
+
+/**
+ * The first lines of each definition for the peek popup, in request order. Long bodies are
+ * cut after a whole statement.
+ */
+export async function getDefinitionBodies(
+  symbols: SymbolInfo[],
+  options?: { maxLines?: number; maxBytes?: number; budgetMs?: number }
+): Promise<DefinitionPeek[]> {
+  return invoke('code_nav_get_definition_body', {
+    symbols,
+    maxLines: options?.maxLines ?? null,
+    maxBytes: options?.maxBytes ?? null,
+    budgetMs: options?.budgetMs ?? null,
+  });
+}
+
+export type OverviewLayer = 'searchMatches' | 'gitChanges' | 'symbolDefinitions';
+
+export interface OverviewSearch {
+  query: string;
+  isRegex?: boolean;
+  wholeWord?: boolean;
+}
+
+/** Counts per bucket of lines; a layer is null when it was not requested or has no data */
+export interface FileOverview {
+  totalLines: number;
+  buckets: number;
+  searchMatches: number[] | null;
+  /** Null outside a git repository */
+  gitChanges: number[] | null;
+  /** Null for languages without a parser */
+  symbolDefinitions: number[] | null;
+}
+
+/**
+ * Minimap data for a large file: each requested layer as one count per bucket of lines.
+ * Pass the editor content when it has unsaved changes.
+ */
+export async function computeFileOverview(
+  filePath: string,
+  buckets: number,
+  layers: OverviewLayer[],
+  options?: { content?: string; search?: OverviewSearch }
+): Promise<FileOverview> {
+  return invoke('compute_file_overview', {
+    filePath,
+    content: options?.content ?? null,
+    buckets,
+    layers,
+    search: options?.search ?? null,
+  });
 }
 
 /**
@@ -84,13 +280,50 @@ export async function clearAllIndex(): Promise<void> {
   await invoke('code_nav_clear_all');
 }
 
+/**
+ * Whether TypeScript, JavaScript and Python fences in markdown files are indexed
+ */
+export async function getMarkdownFenceIndexing(): Promise<boolean> {
+  return invoke('code_nav_get_markdown_fences');
+}
+
+/**
+ * Markdown files must be re-indexed for a change to take effect
+ */
+export async function setMarkdownFenceIndexing(enabled: boolean): Promise<void> {
+  await invoke('code_nav_set_markdown_fences', { enabled });
+}
+
+export interface BatchIndexResult {
+  indexed_files: number;
+  definitions: number;
+  /** Files that could not be indexed, per language id */
+  failures: Record<string, number>;
+}
+
 /**
  * Batch index multiple files in parallel
  */
 export async function indexFilesBatch(
   files: Array<[string, string, string]> // [filePath, content, langId]
-): Promise<void> {
-  await invoke('code_nav_index_files_batch', { files });
+): Promise<BatchIndexResult> {
+  return invoke('code_nav_index_files_batch', { files });
+}
+
+export type LanguageStatus =
+  | { status: 'ok' }
+  | { status: 'query_error'; message: string }
+  | { status: 'parser_error'; message: string };
+
+export type LanguageHealth = { lang_id: string } & LanguageStatus;
+
+/**
+ * Whether each language's grammar and definition query loaded. Also emitted once at
+ * startup as 'code-nav-language-degraded' (broken languages only); listeners that
+ * subscribe late should call this instead.
+ */
+export async function getLanguageHealth(): Promise<LanguageHealth[]> {
+  return invoke('code_nav_get_language_health');
 }
 
 // ============================================================================
@@ -110,22 +343,75 @@ export interface IndexMetadata {
   file_timestamps: Record<string, number>;
 }
 
+export type IndexPersistPhase = 'serializing' | 'writing' | 'reading' | 'parsing' | 'merging';
+
+export interface IndexPersistProgress {
+  operationId: string | null;
+  rootPath: string;
+  phase: IndexPersistPhase;
+  /** Bytes processed, or files while merging */
+  processed: number;
+  total: number | null;
+}
+
+export interface PhaseTiming {
+  phase: IndexPersistPhase;
+  ms: number;
+}
+
+export interface IndexSaveResult {
+  /** False when the save was cancelled; the previous file is kept */
+  saved: boolean;
+  bytesWritten: number;
+  definitionCount: number;
+  timings: PhaseTiming[];
+  elapsedMs: number;
+}
+
+export type IndexLoadStatus = 'loaded' | 'missing' | 'incompatible' | 'cancelled' | 'timedOut';
+
+export interface IndexLoadResult {
+  status: IndexLoadStatus;
+  bytesRead: number;
+  filesMerged: number;
+  definitionCount: number;
+  timings: PhaseTiming[];
+  elapsedMs: number;
+}
+
+export interface IndexPersistOptions {
+  /** Id to cancel the operation with activity_cancel */
+  operationId?: string;
+}
+
 /**
  * Save the current index to disk
  */
 export async function saveIndex(
   rootPath: string,
-  fileTimestamps: Record<string, number>
-): Promise<void> {
-  await invoke('code_nav_save_index', { rootPath, fileTimestamps });
+  fileTimestamps: Record<string, number>,
+  options: IndexPersistOptions = {}
+): Promise<IndexSaveResult> {
+  return invoke('code_nav_save_index', { rootPath, fileTimestamps, ...options });
 }
 
 /**
- * Load a persisted index from disk
- * Returns true if index was loaded successfully, false if no index exists
+ * Load a persisted index from disk. Any status but 'loaded' means the index needs
+ * rebuilding; a load that stalls past the timeout reports 'timedOut'.
  */
-export async function loadIndex(rootPath: string): Promise<boolean> {
-  return invoke('code_nav_load_index', { rootPath });
+export async function loadIndex(
+  rootPath: string,
+  options: IndexPersistOptions & { keepPartial?: boolean; timeoutMs?: number } = {}
+): Promise<IndexLoadResult> {
+  return invoke('code_nav_load_index', { rootPath, ...options });
+}
+
+export async function onIndexPersistProgress(
+  handler: (progress: IndexPersistProgress) => void
+): Promise<UnlistenFn> {
+  return listen<IndexPersistProgress>('index-persist-progress', (event) =>
+    handler(event.payload)
+  );
 }
 
 /**
@@ -150,6 +436,125 @@ export async function getIndexedFiles(): Promise<string[]> {
   return invoke('code_nav_get_indexed_files');
 }
 
+export interface IndexableFile {
+  path: string;
+  lang_id: string;
+  /** Seconds since the epoch, usable as the file's index timestamp */
+  modified_time: number;
+}
+
+/**
+ * List files worth indexing. In a git repository this is the tracked files (plus
+ * untracked, non-ignored ones with includeUntracked) rather than a directory walk.
+ */
+export async function listIndexableFiles(
+  rootPath: string,
+  options: { respectGitignore?: boolean; includeUntracked?: boolean } = {}
+): Promise<IndexableFile[]> {
+  return invoke('code_nav_list_indexable_files', {
+    rootPath,
+    respectGitignore: options.respectGitignore ?? true,
+    includeUntracked: options.includeUntracked ?? false,
+  });
+}
+
+export interface BootstrapOptions {
+  /** Files larger than this many bytes are not indexed (default 1 MiB) */
+  maxFileSize?: number;
+  respectGitignore?: boolean;
+  includeUntracked?: boolean;
+  /** Also build the docs index */
+  includeDocs?: boolean;
+  /** Also index the public declarations of the direct dependencies */
+  indexDependencyTypes?: boolean;
+}
+
+export type BootstrapStatus = 'completed' | 'cancelled';
+
+export interface BootstrapResult {
+  status: BootstrapStatus;
+  totalFiles: number;
+  /** Files taken unchanged from the saved index or an interrupted bootstrap */
+  reusedFiles: number;
+  indexedFiles: number;
+  removedFiles: number;
+  /** Files over the size limit or that could not be read as text */
+  skippedFiles: number;
+  resumedShards: number;
+  definitionCount: number;
+  failures: Record<string, number>;
+  dependencies: DependencyIndexResult | null;
+  docs: DocsIndexStats | null;
+  elapsedMs: number;
+}
+
+/**
+ * Build the project index in the backend: list, read and parse the files, reuse the saved
+ * index or an interrupted bootstrap, and save the result. Progress shows in the activity
+ * center; resolves once the index is complete or the operation was cancelled.
+ */
+export async function bootstrapIndex(
+  rootPath: string,
+  options: BootstrapOptions = {},
+  persist: IndexPersistOptions = {}
+): Promise<BootstrapResult> {
+  return invoke('code_nav_bootstrap_index', {
+    rootPath,
+    options,
+    operationId: persist.operationId ?? null,
+  });
+}
+
+// ============================================================================
+// Documentation Index
+// ============================================================================
+
+export interface DocsIndexStats {
+  rootPath: string;
+  fileCount: number;
+  chunkCount: number;
+  /** Files parsed in this build; unchanged files are reused */
+  reindexedFiles: number;
+  removedFiles: number;
+  durationMs: number;
+}
+
+export interface DocsSearchResult {
+  filePath: string;
+  /** Heading path ("Setup > macOS") or documented symbol name */
+  anchor: string;
+  startLine: number;
+  score: number;
+  snippet: string;
+}
+
+/** Payload of the `docs-index-progress` event */
+export interface DocsIndexProgress {
+  rootPath: string;
+  phase: 'indexing' | 'done';
+  processed: number;
+  total: number;
+}
+
+/**
+ * Build or refresh the documentation index (markdown plus doc comments by default).
+ * Only files changed since the last build are parsed.
+ */
+export async function buildDocsIndex(rootPath: string, globs?: string[]): Promise<DocsIndexStats> {
+  return invoke('build_docs_index', { rootPath, globs: globs ?? null });
+}
+
+/**
+ * Search the documentation index, best matches first
+ */
+export async function queryDocsIndex(
+  rootPath: string,
+  query: string,
+  maxResults?: number
+): Promise<DocsSearchResult[]> {
+  return invoke('query_docs_index', { rootPath, query, maxResults: maxResults ?? null });
+}
+
 // ============================================================================
 // Code Summarization for Message Compaction
 // ============================================================================
@@ -192,9 +597,7 @@ export async function summarizeCodeContent(
 // ============================================================================
 
 /**
- * Estimate token count using character-based heuristics.
- * - CJK characters: 1 char ≈ 1 token
- * - Other characters: 4 chars ≈ 1 token
+ * Count tokens with the cl100k_base tokenizer.
  *
  * This is used to quickly check if tree-sitter compression has reduced
  * tokens enough to skip AI-based compression.
@@ -206,10 +609,49 @@ export async function estimateTokens(text: string): Promise<number> {
   return invoke('estimate_tokens', { text });
 }
 
+export type TokenEncoding = 'cl100k_base' | 'o200k_base';
+
+export interface TokenCountItem {
+  /** File path, or null for an inline text */
+  path: string | null;
+  tokens: number;
+  /** Why a file was not counted (too large, binary, unreadable) */
+  skipped: string | null;
+  cached: boolean;
+}
+
+export interface TokenEstimate {
+  encoding: TokenEncoding;
+  /** Inline texts first, then files, each in request order */
+  items: TokenCountItem[];
+  total: number;
+}
+
+/**
+ * Count tokens for several texts and files at once.
+ * File counts are cached by content hash, so repeated budgeting of the same context is cheap.
+ */
+export async function estimateTokensBatch(options: {
+  texts?: string[];
+  filePaths?: string[];
+  encoding?: TokenEncoding;
+  maxFileBytes?: number;
+}): Promise<TokenEstimate> {
+  return invoke('estimate_tokens_batch', {
+    texts: options.texts ?? null,
+    filePaths: options.filePaths ?? null,
+    encoding: options.encoding ?? null,
+    maxFileBytes: options.maxFileBytes ?? null,
+  });
+}
+
 /**
- * Get language ID from file extension
+ * Get language ID from the user's custom language mappings, then the file extension
  */
 export function getLangIdFromPath(filePath: string): string | null {
+  const custom = getCustomLangIdForPath(filePath);
+  if (custom) return custom;
+
   const ext = filePath.split('.').pop()?.toLowerCase();
   if (!ext) return null;
 
@@ -0,0 +1,82 @@
+import { invoke } from '@tauri-apps/api/core';
+
+export interface DocsetInfo {
+  id: string;
+  name: string;
+  /** DocSetPlatformFamily from the docset's Info.plist */
+  platform: string | null;
+  path: string;
+  entryCount: number;
+}
+
+export type DocsetMatchKind = 'exact' | 'segment' | 'prefix' | 'contains';
+
+export interface DocsetSearchResult {
+  /** "<docset id>/<row id>" */
+  id: string;
+  docsetId: string;
+  name: string;
+  entryType: string;
+  matchKind: DocsetMatchKind;
+}
+
+export interface DocsetEntry {
+  id: string;
+  docsetId: string;
+  name: string;
+  entryType: string;
+  /** HTML or markdown page to display */
+  filePath: string;
+  anchor: string | null;
+  /** Plain text from the page, starting at the anchor */
+  summary: string | null;
+}
+
+export async function listDocsets(): Promise<DocsetInfo[]> {
+  return invoke('docset_list');
+}
+
+/**
+ * Install a Dash/Zeal docset from a .tgz archive
+ */
+export async function importDocset(archivePath: string): Promise<DocsetInfo> {
+  return invoke('docset_import', { archivePath });
+}
+
+export async function removeDocset(id: string): Promise<boolean> {
+  return invoke('docset_remove', { id });
+}
+
+/**
+ * Search entries by name across docsets (all installed ones by default)
+ */
+export async function searchDocsets(
+  query: string,
+  docsetIds?: string[],
+  maxResults?: number
+): Promise<DocsetSearchResult[]> {
+  return invoke('docset_search', {
+    query,
+    docsetIds: docsetIds ?? null,
+    maxResults: maxResults ?? null,
+  });
+}
+
+export async function getDocsetEntry(id: string): Promise<DocsetEntry> {
+  return invoke('docset_get_entry', { id });
+}
+
+/**
+ * Docset entries for a symbol, only when the code index has no definition for it
+ */
+export async function lookupSymbolDocs(
+  symbolName: string,
+  langFamily: string,
+  maxResults?: number
+): Promise<DocsetSearchResult[]> {
+  return invoke('docset_lookup_symbol', {
+    symbolName,
+    langFamily,
+    maxResults: maxResults ?? null,
+  });
+}
@@ -0,0 +1,71 @@
+import { invoke } from '@tauri-apps/api/core';
+
+export interface EnvProfileVar {
+  name: string;
+  /** Plain text value; null when the value comes from a secret */
+  value: string | null;
+  /** Name of a secret saved with saveEnvProfileSecret */
+  secretRef?: string | null;
+}
+
+export interface EnvProfile {
+  id: string;
+  rootPath: string;
+  name: string;
+  variables: EnvProfileVar[];
+  /** Whether this is the project's active profile */
+  active: boolean;
+  createdAt: number;
+  updatedAt: number;
+}
+
+export interface EnvProfileDraft {
+  /** Omit to create a new profile */
+  id?: string;
+  rootPath: string;
+  name: string;
+  variables?: EnvProfileVar[];
+}
+
+export async function saveEnvProfile(profile: EnvProfileDraft): Promise<EnvProfile> {
+  return invoke('env_profile_save', { profile });
+}
+
+export async function listEnvProfiles(rootPath: string): Promise<EnvProfile[]> {
+  return invoke('env_profile_list', { rootPath });
+}
+
+export async function deleteEnvProfile(id: string): Promise<boolean> {
+  return invoke('env_profile_delete', { id });
+}
+
+/**
+ * Activate a profile for a project; pass null to deactivate. Terminals, tasks, shell
+ * commands and skill scripts spawned for the project get its variables, beneath any
+ * variables the call sets itself.
+ */
+export async function setActiveEnvProfile(rootPath: string, profileId: string | null) {
+  return invoke<void>('set_active_profile', { rootPath, profileId });
+}
+
+/**
+ * Store a secret value for profile variables to reference by name
+ */
+export async function saveEnvProfileSecret(name: string, value: string): Promise<void> {
+  return invoke('env_profile_save_secret', { name, value });
+}
+
+/**
+ * JSON for sharing a profile; secret values are replaced by `${secret:name}` placeholders
+ */
+export async function exportEnvProfile(id: string): Promise<string> {
+  return invoke('env_profile_export', { id });
+}
+
+/**
+ * Create a profile from exported JSON. Referenced secrets must be saved on this machine
+ * before the profile is used.
+ */
+export async function importEnvProfile(rootPath: string, json: string): Promise<EnvProfile> {
+  return invoke('env_profile_import', { rootPath, json });
+}
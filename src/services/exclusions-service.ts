@@ -0,0 +1,29 @@
+import { invoke } from '@tauri-apps/api/core';
+
+/**
+ * Directory names skipped by searches, globbing and indexing in addition to the built-in
+ * list (node_modules, target, ...)
+ */
+export async function getExcludedDirs(): Promise<string[]> {
+  return invoke('exclusions_get');
+}
+
+/**
+ * Replace the excluded directory names. Searches and indexing already running skip the
+ * directories they reach after the change.
+ */
+export async function setExcludedDirs(dirs: string[]): Promise<string[]> {
+  return invoke('exclusions_set', { dirs });
+}
+
+/**
+ * How many levels below the root searches and globs descend when a request doesn't say.
+ * Results report `depth_limited` when deeper directories were skipped.
+ */
+export async function getWalkDepth(): Promise<number> {
+  return invoke('walk_depth_get');
+}
+
+export async function setWalkDepth(depth: number): Promise<number> {
+  return invoke('walk_depth_set', { depth });
+}
@@ -1,11 +1,57 @@
 // src/services/fast-directory-tree-service.ts
 import { invoke } from '@tauri-apps/api/core';
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
 import { logger } from '@/lib/logger';
-import type { FileNode } from '@/types/file-system';
+import type { DirectoryChildren, FileNode } from '@/types/file-system';
+
+export type TreeSortMode = 'name-natural' | 'name-lexicographic' | 'modified-time' | 'size';
+
+/** Order of directory children; every field is optional on the wire */
+export interface TreeSortOptions {
+  mode?: TreeSortMode; // default: 'name-natural' (file2 before file10)
+  direction?: 'asc' | 'desc'; // default: 'asc'
+  directories_first?: boolean; // default: true
+  locale?: string; // BCP 47 tag used to collate names
+}
 
 export interface DirectoryTreeOptions {
   maxImmediateDepth?: number; // How deep to load immediately (default: 2)
   enableCache?: boolean; // Whether to use caching (default: true)
+  withSnapshot?: boolean; // Record a snapshot for diffTree (default: false)
+  sort?: TreeSortOptions;
+}
+
+export interface DirectoryChangeGroup {
+  parent: string;
+  added: string[];
+  removed: string[];
+  modified: string[];
+}
+
+export interface DirectoryTreeDiff {
+  snapshot_id: string;
+  groups: DirectoryChangeGroup[];
+}
+
+export interface DirectorySize {
+  path: string;
+  totalBytes: number;
+  fileCount: number;
+  /** Cancelled before the walk finished; totals only cover what was visited */
+  truncated: boolean;
+  error: string | null;
+}
+
+export interface ReconcileSummary {
+  rootPath: string;
+  /** Files whose code index entries were dropped */
+  removedFromIndex: string[];
+  /** Directories whose cached trees were dropped */
+  invalidatedDirs: string[];
+  checkedPaths: number;
+  elapsedMs: number;
+  /** The rest is checked in the background and reported via onProjectReconciled */
+  continuedInBackground: boolean;
 }
 
 export class FastDirectoryTreeService {
@@ -26,11 +72,13 @@ export class FastDirectoryTreeService {
     rootPath: string,
     options: DirectoryTreeOptions = {}
   ): Promise<FileNode> {
-    const { maxImmediateDepth = 2 } = options;
+    const { maxImmediateDepth = 2, withSnapshot = false, sort } = options;
     try {
       const result = await invoke<FileNode>('build_directory_tree', {
         rootPath,
         maxImmediateDepth,
+        withSnapshot,
+        sort: sort ?? null,
       });
       return result;
     } catch (error) {
@@ -39,19 +87,34 @@ export class FastDirectoryTreeService {
     }
   }
 
+  /**
+   * Get paths added, removed or modified since a tree snapshot, grouped by parent directory.
+   * Throws if the snapshot expired or was cleared; rebuild the tree in that case.
+   */
+  async diffTree(rootPath: string, previousSnapshotId: string): Promise<DirectoryTreeDiff> {
+    return invoke<DirectoryTreeDiff>('build_directory_tree_diff', {
+      rootPath,
+      previousSnapshotId,
+    });
+  }
+
   /**
    * Load children for a lazy-loaded directory
    * This is called when user expands a directory that was marked as lazy-loaded
    */
-  async loadDirectoryChildren(dirPath: string): Promise<FileNode[]> {
+  async loadDirectoryChildren(dirPath: string, sort?: TreeSortOptions): Promise<FileNode[]> {
     try {
       logger.info(`Starting Rust loadDirectoryChildren: ${dirPath}`);
-      const result = await invoke<FileNode[]>('load_directory_children', {
+      const result = await invoke<DirectoryChildren>('load_directory_children', {
         dirPath,
+        sort: sort ?? null,
       });
       logger.info(`Completed Rust loadDirectoryChildren: ${dirPath}`);
+      if (result.warnings.length > 0) {
+        logger.warn(`Skipped ${result.warnings.length} unreadable paths in ${dirPath}`);
+      }
 
-      return result;
+      return result.children;
     } catch (error) {
       logger.error('Failed to load directory children:', error);
       throw new Error(`Failed to load directory children: ${error}`);
@@ -84,6 +147,37 @@ export class FastDirectoryTreeService {
     }
   }
 
+  /**
+   * Drop code index entries and cached trees of files deleted from disk, e.g. after a
+   * branch switch. The backend also runs this by itself when HEAD changes.
+   */
+  async reconcileProjectState(rootPath: string): Promise<ReconcileSummary> {
+    return invoke<ReconcileSummary>('reconcile_project_state', { rootPath });
+  }
+
+  /**
+   * Subscribe to reconciles started by the backend or finished in the background
+   */
+  async onProjectReconciled(handler: (summary: ReconcileSummary) => void): Promise<UnlistenFn> {
+    return listen<ReconcileSummary>('project-state-reconciled', (event) => handler(event.payload));
+  }
+
+  /**
+   * Recursive on-disk sizes for directories, cached briefly by the backend.
+   * Pass an operationId to be able to stop it with cancelActivity(operationId).
+   */
+  async getDirectorySizes(
+    paths: string[],
+    respectIgnores = true,
+    operationId?: string
+  ): Promise<DirectorySize[]> {
+    return invoke<DirectorySize[]>('calculate_directory_sizes', {
+      paths,
+      respectIgnores,
+      operationId: operationId ?? null,
+    });
+  }
+
   /**
    * Get directory statistics (file count, etc.)
    * This can be used to show directory info in the UI
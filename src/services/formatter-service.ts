@@ -0,0 +1,74 @@
+import { invoke } from '@tauri-apps/api/core';
+
+/**
+ * How to run a formatter. Args may contain {file}; rangeArgs may also contain
+ * {rangeStart}/{rangeEnd} (UTF-16 offsets).
+ */
+export interface FormatterConfig {
+  command: string;
+  args: string[];
+  /** Without range args, range requests format the selected text on its own */
+  rangeArgs?: string[] | null;
+  timeoutMs?: number | null;
+}
+
+/** Byte offsets into the content, end exclusive */
+export interface FormatRange {
+  start: number;
+  end: number;
+}
+
+export interface FormatResult {
+  /** The whole file after formatting */
+  content: string;
+  changed: boolean;
+  formatter: string;
+}
+
+export type FormatError =
+  | { kind: 'notConfigured'; langId: string }
+  | { kind: 'notFound'; command: string }
+  | { kind: 'failed'; command: string; exitCode: number | null; stderr: string }
+  | { kind: 'timedOut'; command: string; timeoutMs: number }
+  | { kind: 'invalidRange'; message: string }
+  | { kind: 'io'; message: string };
+
+export interface FormatterStatus {
+  langId: string;
+  command: string;
+  /** Resolved executable, null when the formatter is not installed */
+  path: string | null;
+}
+
+/**
+ * Format content with the external formatter configured for its language.
+ * Rejects with a FormatError.
+ */
+export async function formatCode(
+  filePath: string,
+  content: string,
+  langId: string,
+  range?: FormatRange
+): Promise<FormatResult> {
+  return invoke('format_code', { filePath, content, langId, range: range ?? null });
+}
+
+export async function getFormatterConfig(): Promise<Record<string, FormatterConfig>> {
+  return invoke('format_get_config');
+}
+
+/**
+ * Replace the per-language overrides of the default formatters
+ */
+export async function setFormatterOverrides(
+  overrides: Record<string, FormatterConfig>
+): Promise<Record<string, FormatterConfig>> {
+  return invoke('format_set_config', { overrides });
+}
+
+/**
+ * Which configured formatters are installed. Pass refresh after installing one.
+ */
+export async function checkFormatters(refresh = false): Promise<FormatterStatus[]> {
+  return invoke('format_check_formatters', { refresh });
+}
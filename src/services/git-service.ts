@@ -1,5 +1,18 @@
 import { invoke } from '@tauri-apps/api/core';
-import type { FileDiff, FileStatusMap, GitStatus, LineChange } from '../types/git';
+import type {
+  CommitResult,
+  DirectoryStatusMap,
+  FileAtCommit,
+  FileDiff,
+  FileHistoryEntry,
+  FileStatusMap,
+  GitHooksInfo,
+  GitStatus,
+  LineAnnotation,
+  LineChange,
+  RepositoryInfo,
+  StagedSummary,
+} from '../types/git';
 
 /**
  * Service layer for Git operations using Tauri commands
@@ -8,8 +21,8 @@ export class GitService {
   /**
    * Gets the full Git status for a repository
    */
-  async getStatus(repoPath: string): Promise<GitStatus> {
-    return invoke<GitStatus>('git_get_status', { repoPath });
+  async getStatus(repoPath: string, detectRenames = false): Promise<GitStatus> {
+    return invoke<GitStatus>('git_get_status', { repoPath, detectRenames });
   }
 
   /**
@@ -19,11 +32,26 @@ export class GitService {
     return invoke<boolean>('git_is_repository', { repoPath });
   }
 
+  /**
+   * Gets the repository's root, git dir, and sparse checkout and partial clone state
+   */
+  async getRepositoryInfo(repoPath: string): Promise<RepositoryInfo> {
+    return invoke<RepositoryInfo>('git_get_repository_info', { repoPath });
+  }
+
   /**
    * Gets all file statuses as a map
    */
-  async getAllFileStatuses(repoPath: string): Promise<FileStatusMap> {
-    return invoke<FileStatusMap>('git_get_all_file_statuses', { repoPath });
+  async getAllFileStatuses(repoPath: string, detectRenames = false): Promise<FileStatusMap> {
+    return invoke<FileStatusMap>('git_get_all_file_statuses', { repoPath, detectRenames });
+  }
+
+  /**
+   * Gets changed-file counts per directory for explorer decorations. Directories deeper
+   * than maxDepth roll into their ancestors.
+   */
+  async getDirectoryStatusRollup(repoPath: string, maxDepth?: number): Promise<DirectoryStatusMap> {
+    return invoke<DirectoryStatusMap>('git_get_directory_status_rollup', { repoPath, maxDepth });
   }
 
   /**
@@ -50,6 +78,89 @@ export class GitService {
   async getRawDiffText(repoPath: string): Promise<string> {
     return invoke<string>('git_get_raw_diff_text', { repoPath });
   }
+
+  /**
+   * Size-bounded summary of the staged changes, for commit message generation
+   */
+  async getStagedSummary(
+    repoPath: string,
+    maxBytesPerFile?: number,
+    maxFiles?: number
+  ): Promise<StagedSummary> {
+    return invoke<StagedSummary>('git_get_staged_summary', {
+      repoPath,
+      maxBytesPerFile,
+      maxFiles,
+    });
+  }
+
+  /**
+   * Lists the repository's hook scripts and whether core.hooksPath overrides .git/hooks
+   */
+  async getHooks(repoPath: string): Promise<GitHooksInfo> {
+    return invoke<GitHooksInfo>('git_get_hooks', { repoPath });
+  }
+
+  /**
+   * Commits the staged changes. Hooks only run when runHooks is set (pre-commit and
+   * commit-msg); otherwise the result lists the hooks that were skipped.
+   */
+  async commit(repoPath: string, message: string, runHooks = false): Promise<CommitResult> {
+    return invoke<CommitResult>('git_commit', { repoPath, message, runHooks });
+  }
+
+  /**
+   * Commits that changed a file, newest first. With followRenames the history
+   * continues under the file's previous names.
+   */
+  async getFileHistory(
+    repoPath: string,
+    filePath: string,
+    options: { limit?: number; followRenames?: boolean } = {}
+  ): Promise<FileHistoryEntry[]> {
+    return invoke<FileHistoryEntry[]>('git_get_file_history', {
+      repoPath,
+      filePath,
+      limit: options.limit ?? null,
+      followRenames: options.followRenames ?? false,
+    });
+  }
+
+  /**
+   * Content of a file at a commit; pass the entry's path for commits before a rename
+   */
+  async getFileAtCommit(
+    repoPath: string,
+    filePath: string,
+    commitId: string
+  ): Promise<FileAtCommit> {
+    return invoke<FileAtCommit>('git_get_file_at_commit', { repoPath, filePath, commitId });
+  }
+
+  /**
+   * Blame of one line for inline annotations. Pass the line's current editor text so
+   * unsaved edits come back as notCommittedYet.
+   */
+  async getLineAnnotation(
+    repoPath: string,
+    filePath: string,
+    line: number,
+    lineText?: string
+  ): Promise<LineAnnotation> {
+    return invoke<LineAnnotation>('git_get_line_annotation', {
+      repoPath,
+      filePath,
+      line,
+      lineText: lineText ?? null,
+    });
+  }
+
+  /**
+   * Blame a file in the background when it gains focus
+   */
+  async prefetchBlame(repoPath: string, filePath: string): Promise<void> {
+    return invoke<void>('git_prefetch_blame', { repoPath, filePath });
+  }
 }
 
 // Export a singleton instance
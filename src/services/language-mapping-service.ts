@@ -0,0 +1,85 @@
+import { invoke } from '@tauri-apps/api/core';
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
+
+/**
+ * A pattern starting with '.' is an extension ('.pyi'); anything else is matched against
+ * the file name with '*' and '?' wildcards ('*.gohtml', 'Jenkinsfile')
+ */
+export interface LanguageMapping {
+  pattern: string;
+  langId: string;
+}
+
+export interface LanguageMappingSet {
+  builtin: LanguageMapping[];
+  /** Applied on top of the built-in mappings, in order */
+  custom: LanguageMapping[];
+}
+
+export interface LanguageMappingsChanged {
+  /** Patterns added, removed or mapped to a different language; files matching them may need re-indexing */
+  affectedPatterns: string[];
+}
+
+// Custom mappings as last loaded from or saved to the backend
+let customMappings: LanguageMapping[] = [];
+
+function wildcardToRegExp(pattern: string): RegExp {
+  const source = pattern
+    .toLowerCase()
+    .replace(/[.+^${}()|[\]\\]/g, '\\$&')
+    .replace(/\*/g, '.*')
+    .replace(/\?/g, '.');
+  return new RegExp(`^${source}$`);
+}
+
+export async function loadLanguageMappings(): Promise<LanguageMappingSet> {
+  const mappings = await invoke<LanguageMappingSet>('language_mappings_get');
+  customMappings = mappings.custom ?? [];
+  return mappings;
+}
+
+/**
+ * Replace the custom mappings. Built-in mappings can be overridden but not removed.
+ */
+export async function setLanguageMappings(
+  mappings: LanguageMapping[]
+): Promise<LanguageMappingSet> {
+  const result = await invoke<LanguageMappingSet>('language_mappings_set', { mappings });
+  customMappings = result.custom ?? [];
+  return result;
+}
+
+export async function onLanguageMappingsChanged(
+  handler: (change: LanguageMappingsChanged) => void
+): Promise<UnlistenFn> {
+  return listen<LanguageMappingsChanged>('language-mappings-changed', (event) =>
+    handler(event.payload)
+  );
+}
+
+/**
+ * Language of a file according to the custom mappings only
+ */
+export function getCustomLangIdForPath(filePath: string): string | null {
+  const fileName = (filePath.split(/[\\/]/).pop() ?? filePath).toLowerCase();
+  for (const mapping of customMappings) {
+    if (!mapping.pattern.startsWith('.') && wildcardToRegExp(mapping.pattern).test(fileName)) {
+      return mapping.langId;
+    }
+  }
+  const ext = fileName.includes('.') ? fileName.split('.').pop() : undefined;
+  const byExtension = customMappings.find(
+    (mapping) => mapping.pattern.toLowerCase() === `.${ext}`
+  );
+  return byExtension?.langId ?? null;
+}
+
+/**
+ * Extensions (without the dot) that have a custom mapping
+ */
+export function getCustomExtensions(): string[] {
+  return customMappings
+    .filter((mapping) => mapping.pattern.startsWith('.'))
+    .map((mapping) => mapping.pattern.slice(1).toLowerCase());
+}
@@ -3,6 +3,7 @@ import type * as Monaco from 'monaco-editor';
 import { logger } from '@/lib/logger';
 import { settingsManager } from '@/stores/settings-store';
 import { findDefinition, findReferencesHybrid, getLangFamily } from './code-navigation-service';
+import { getDocsetEntry, lookupSymbolDocs } from './docset-service';
 import { getLspCompletion } from './lsp/lsp-completion-provider';
 import {
   getLspDefinition,
@@ -450,7 +451,7 @@ export function registerDefinitionProviders(monaco: typeof Monaco) {
             definitions: filteredDefinitions.map((def) => ({
               file_path: def.file_path,
               start_line: def.start_line,
-              start_column: def.start_column,
+              start_column: def.start_column_utf16 || def.start_column,
             })),
             timestamp: Date.now(),
           };
@@ -459,7 +460,12 @@ export function registerDefinitionProviders(monaco: typeof Monaco) {
           // The actual navigation is handled by our onMouseDown handler
           const result = filteredDefinitions.map((def) => ({
             uri: monaco.Uri.file(def.file_path),
-            range: new monaco.Range(def.start_line, def.start_column, def.end_line, def.end_column),
+            range: new monaco.Range(
+              def.start_line,
+              def.start_column_utf16 || def.start_column,
+              def.end_line,
+              def.end_column_utf16 || def.end_column
+            ),
           }));
           logger.info('[CodeNav] Returning definition locations:', result);
           return result;
@@ -470,6 +476,38 @@ export function registerDefinitionProviders(monaco: typeof Monaco) {
       },
     });
 
+    // Hover docs from installed docsets, for symbols without a local definition
+    monaco.languages.registerHoverProvider(langId, {
+      provideHover: async (model, position) => {
+        const word = model.getWordAtPosition(position);
+        if (!word) return null;
+
+        try {
+          const langFamily = getLangFamily(model.getLanguageId());
+          const [match] = await lookupSymbolDocs(word.word, langFamily, 1);
+          if (!match) return null;
+
+          const entry = await getDocsetEntry(match.id);
+          const contents = [{ value: `**${entry.name}** — ${entry.entryType}` }];
+          if (entry.summary) {
+            contents.push({ value: entry.summary });
+          }
+          return {
+            range: new monaco.Range(
+              position.lineNumber,
+              word.startColumn,
+              position.lineNumber,
+              word.endColumn
+            ),
+            contents,
+          };
+        } catch (error) {
+          logger.warn('[CodeNav] Docset hover lookup failed:', error);
+          return null;
+        }
+      },
+    });
+
     // Find References (Shift+F12)
     monaco.languages.registerReferenceProvider(langId, {
       provideReferences: async (model, position, _context) => {
@@ -525,7 +563,14 @@ export function registerDefinitionProviders(monaco: typeof Monaco) {
           }
 
           // Use hybrid search: ripgrep text search + tree-sitter filtering
-          const references = await findReferencesHybrid(word.word, langFamily, rootPath);
+          const { references, sparse_checkout_active } = await findReferencesHybrid(
+            word.word,
+            langFamily,
+            rootPath
+          );
+          if (sparse_checkout_active) {
+            logger.info('[CodeNav] References limited by sparse checkout');
+          }
           logger.info(
             '[CodeNav] findReferencesHybrid (Tree-sitter) returned:',
             references.length,
@@ -537,7 +582,12 @@ export function registerDefinitionProviders(monaco: typeof Monaco) {
 
           return references.map((ref) => ({
             uri: monaco.Uri.file(ref.file_path),
-            range: new monaco.Range(ref.start_line, ref.start_column, ref.end_line, ref.end_column),
+            range: new monaco.Range(
+              ref.start_line,
+              ref.start_column_utf16 || ref.start_column,
+              ref.end_line,
+              ref.end_column_utf16 || ref.end_column
+            ),
           }));
         } catch (error) {
           logger.error('[CodeNav] Error finding references:', error);
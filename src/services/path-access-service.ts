@@ -0,0 +1,40 @@
+import { invoke } from '@tauri-apps/api/core';
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
+
+export type PathAccess =
+  | { status: 'ok' }
+  | { status: 'notFound' }
+  /** On macOS, usually missing Files and Folders / Full Disk Access permission */
+  | { status: 'denied' }
+  | { status: 'error'; message: string };
+
+export interface PathAccessDenied {
+  path: string;
+}
+
+// Must match path_access::PERMISSION_DENIED_PREFIX
+const PERMISSION_DENIED_PREFIX = 'PermissionDenied: ';
+
+export async function checkPathAccess(path: string): Promise<PathAccess> {
+  return invoke('check_path_access', { path });
+}
+
+/**
+ * Fired whenever the backend is refused access to a project path
+ */
+export async function onPathAccessDenied(
+  handler: (denial: PathAccessDenied) => void
+): Promise<UnlistenFn> {
+  return listen<PathAccessDenied>('path-access-denied', (event) => handler(event.payload));
+}
+
+/**
+ * Whether a rejected tree, search or watch command failed for lack of permission.
+ * Returns the denied path, or null for any other error.
+ */
+export function getPermissionDeniedPath(error: unknown): string | null {
+  const message = error instanceof Error ? error.message : String(error);
+  return message.startsWith(PERMISSION_DENIED_PREFIX)
+    ? message.slice(PERMISSION_DENIED_PREFIX.length)
+    : null;
+}
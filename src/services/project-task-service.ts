@@ -0,0 +1,92 @@
+import { invoke } from '@tauri-apps/api/core';
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
+
+export interface ProjectTaskEnvVar {
+  name: string;
+  /** Always null for secret variables read back from the backend */
+  value: string | null;
+  secret: boolean;
+}
+
+export interface ProjectTask {
+  id: string;
+  rootPath: string;
+  name: string;
+  command: string;
+  /** Relative to the project root unless absolute */
+  cwd: string | null;
+  env: ProjectTaskEnvVar[];
+  group: string | null;
+  createdAt: number;
+  updatedAt: number;
+}
+
+export interface ProjectTaskDraft {
+  /** Omit to create a new task */
+  id?: string;
+  rootPath: string;
+  name: string;
+  command: string;
+  cwd?: string | null;
+  /** A secret variable saved without a value keeps its stored value */
+  env?: ProjectTaskEnvVar[];
+  group?: string | null;
+}
+
+export interface ProjectTaskRun {
+  id: number;
+  taskId: string;
+  startedAt: number;
+  durationMs: number;
+  /** null when the process was killed by a signal */
+  exitCode: number | null;
+  cancelled: boolean;
+  outputTail: string;
+  outputTruncated: boolean;
+}
+
+export interface ProjectTaskOutput {
+  taskId: string;
+  operationId: string | null;
+  stream: 'stdout' | 'stderr';
+  line: string;
+}
+
+export async function saveProjectTask(task: ProjectTaskDraft): Promise<ProjectTask> {
+  return invoke('task_save', { task });
+}
+
+export async function listProjectTasks(rootPath: string): Promise<ProjectTask[]> {
+  return invoke('task_list', { rootPath });
+}
+
+export async function deleteProjectTask(id: string): Promise<boolean> {
+  return invoke('task_delete', { id });
+}
+
+/**
+ * Run a task to completion. Pass an operationId to make the run cancellable through
+ * activity_cancel.
+ */
+export async function runProjectTask(id: string, operationId?: string): Promise<ProjectTaskRun> {
+  return invoke('task_run', { id, operationId: operationId ?? null });
+}
+
+/**
+ * Recent runs of a task, newest first
+ */
+export async function getProjectTaskRunHistory(
+  id: string,
+  limit?: number
+): Promise<ProjectTaskRun[]> {
+  return invoke('task_run_history', { id, limit: limit ?? null });
+}
+
+/**
+ * Subscribe to output lines of running tasks
+ */
+export async function onProjectTaskOutput(
+  handler: (output: ProjectTaskOutput) => void
+): Promise<UnlistenFn> {
+  return listen<ProjectTaskOutput>('task-output', (event) => handler(event.payload));
+}
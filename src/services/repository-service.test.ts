@@ -21,10 +21,12 @@ vi.mock('@tauri-apps/plugin-fs', () => ({
 // Unmock repository-service so we can test the actual implementation
 vi.unmock('./repository-service');
 
+import { invoke } from '@tauri-apps/api/core';
 import { dirname, join } from '@tauri-apps/api/path';
 import { exists, rename } from '@tauri-apps/plugin-fs';
 import { repositoryService } from './repository-service';
 
+const mockInvoke = vi.mocked(invoke);
 const mockExists = vi.mocked(exists);
 const mockRename = vi.mocked(rename);
 const mockDirname = vi.mocked(dirname);
@@ -37,44 +39,57 @@ describe('repositoryService.moveFile', () => {
   });
 
   it('should move a file to a different directory', async () => {
-    mockExists.mockResolvedValueOnce(true); // source exists
-    mockExists.mockResolvedValueOnce(false); // destination does not exist
-    mockRename.mockResolvedValueOnce(undefined);
+    mockInvoke.mockResolvedValueOnce([
+      { from: '/project/src/file.ts', to: '/project/lib/file.ts', copied: false, error: null },
+    ]);
 
     await repositoryService.moveFile('/project/src/file.ts', '/project/lib/file.ts');
 
-    expect(mockRename).toHaveBeenCalledWith('/project/src/file.ts', '/project/lib/file.ts');
+    expect(mockInvoke).toHaveBeenCalledWith('move_paths', {
+      moves: [{ from: '/project/src/file.ts', to: '/project/lib/file.ts' }],
+      overwritePolicy: 'fail',
+    });
   });
 
   it('should throw error when source does not exist', async () => {
-    mockExists.mockResolvedValueOnce(false); // source does not exist
+    mockInvoke.mockResolvedValueOnce([
+      {
+        from: '/project/src/file.ts',
+        to: null,
+        copied: false,
+        error: 'Failed to read /project/src/file.ts: No such file or directory',
+      },
+    ]);
 
     await expect(
       repositoryService.moveFile('/project/src/file.ts', '/project/lib/file.ts')
-    ).rejects.toThrow('Source file/directory does not exist');
+    ).rejects.toThrow('Failed to read /project/src/file.ts');
   });
 
   it('should throw error when destination already exists', async () => {
-    mockExists.mockResolvedValueOnce(true); // source exists
-    mockExists.mockResolvedValueOnce(true); // destination exists
+    mockInvoke.mockResolvedValueOnce([
+      {
+        from: '/project/src/file.ts',
+        to: null,
+        copied: false,
+        error: '/project/lib/file.ts already exists',
+      },
+    ]);
 
     await expect(
       repositoryService.moveFile('/project/src/file.ts', '/project/lib/file.ts')
-    ).rejects.toThrow('A file or directory already exists');
+    ).rejects.toThrow('already exists');
   });
 
-  it('should move cache entry when moving a cached file', async () => {
-    // First, manually add a cache entry by reading through fileCache
-    // Since we can't directly access private fileCache, we test via behavior
-
-    mockExists.mockResolvedValueOnce(true); // source exists
-    mockExists.mockResolvedValueOnce(false); // destination does not exist
-    mockRename.mockResolvedValueOnce(undefined);
+  it('should not rename through the fs plugin', async () => {
+    mockInvoke.mockResolvedValueOnce([
+      { from: '/project/old/file.ts', to: '/project/new/file.ts', copied: true, error: null },
+    ]);
 
     await repositoryService.moveFile('/project/old/file.ts', '/project/new/file.ts');
 
-    expect(mockRename).toHaveBeenCalledWith('/project/old/file.ts', '/project/new/file.ts');
-    expect(mockRename).toHaveBeenCalledTimes(1);
+    expect(mockRename).not.toHaveBeenCalled();
+    expect(mockInvoke).toHaveBeenCalledTimes(1);
   });
 });
 
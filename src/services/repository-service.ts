@@ -1,6 +1,7 @@
 // src/services/repository-service.ts
 
 import { invoke } from '@tauri-apps/api/core';
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
 import { dirname, join } from '@tauri-apps/api/path';
 import { open } from '@tauri-apps/plugin-dialog';
 import {
@@ -26,15 +27,193 @@ import {
   shouldSkipDirectory,
 } from './repository-utils';
 
+export interface MoveRequest {
+  from: string;
+  to: string;
+}
+
+export type OverwritePolicy = 'fail' | 'overwrite' | 'rename';
+
+export interface MoveResult {
+  from: string;
+  /** Destination actually used; null when the move failed */
+  to: string | null;
+  /** The source was on another device and was copied, then deleted */
+  copied: boolean;
+  error: string | null;
+}
+
 interface SearchMatch {
   line_number: number;
   line_content: string;
   byte_offset: number;
+  /** Character columns of the first match in the full line, zero-based, end exclusive */
+  column_start: number;
+  column_end: number;
+  /** Character columns of every match in the line */
+  match_ranges: [number, number][];
 }
 
-interface SearchResult {
+export interface SearchResult {
   file_path: string;
   matches: SearchMatch[];
+  /** The path isn't valid UTF-8 and file_path is percent-encoded */
+  path_is_lossy?: boolean;
+  raw_path_bytes?: string;
+}
+
+export interface ContentSearchResponse {
+  results: SearchResult[];
+  /** Directories below the walk's depth limit were not searched */
+  depth_limited: boolean;
+  truncated_subtrees: number;
+  /** The search was cancelled; `results` covers only the files searched before that */
+  cancelled: boolean;
+  stats: SearchStats;
+  /** Listed files (`includeFiles`) that don't exist or can't be read */
+  skipped: string[];
+  /** Set by a `countOnly` search, which leaves `results` empty */
+  counts?: MatchCounts;
+}
+
+export interface FileMatchCount {
+  file_path: string;
+  count: number;
+  path_is_lossy?: boolean;
+  raw_path_bytes?: string;
+}
+
+/** Exact match counts per file, without the per-file or total result limits */
+export interface MatchCounts {
+  files: FileMatchCount[];
+  total: number;
+}
+
+/** Files a search looked at, and those it passed over */
+export interface SearchStats {
+  files_scanned: number;
+  /** Larger than the search's file size limit */
+  skipped_size: number;
+  /** Looked like binary data */
+  skipped_binary: number;
+}
+
+/** Sent after the last result of a streamed search */
+export interface SearchComplete {
+  total_files: number;
+  total_matches: number;
+  elapsed_ms: number;
+  cancelled: boolean;
+  depth_limited: boolean;
+  truncated_subtrees: number;
+  stats: SearchStats;
+}
+
+export type SearchResultOrder = 'relevance' | 'path' | 'match-count' | 'modified';
+
+export interface ContentSearchParams {
+  query: string;
+  rootPath?: string | null;
+  fileTypes?: string[] | null;
+  excludeDirs?: string[] | null;
+  includePaths?: string[] | null;
+  excludePaths?: string[] | null;
+  /** Levels below each walked directory; the configured default when unset */
+  maxDepth?: number | null;
+  /** File order of the results; by relevance when unset */
+  sortBy?: SearchResultOrder | null;
+  /** Treat the query as a regex; true when unset */
+  isRegex?: boolean | null;
+  caseSensitive?: boolean | null;
+  wholeWord?: boolean | null;
+  /** Skip gitignored files; false when unset */
+  respectGitignore?: boolean | null;
+  /** Skip files larger than this many bytes; 10 MB when unset */
+  maxFileSize?: number | null;
+  /** Search exactly these files without walking; wins over includePaths and rootPath */
+  includeFiles?: string[] | null;
+  /** Only search files matching one of these globs, relative to rootPath */
+  includeGlobs?: string[] | null;
+  /** Skip files matching one of these globs; wins over includeGlobs */
+  excludeGlobs?: string[] | null;
+  /** Only count matches per file instead of returning them */
+  countOnly?: boolean | null;
+  /** Also search dotfiles and dot directories such as .github; .git is never searched */
+  includeHidden?: boolean | null;
+  /** Descend into symlinked directories; linked files are searched either way */
+  followSymlinks?: boolean | null;
+}
+
+/** Why search_file_content failed; an invalid pattern is shown next to the search box */
+export type SearchError =
+  | { kind: 'invalidPattern'; message: string }
+  | { kind: 'invalidGlob'; pattern: string; message: string }
+  | { kind: 'failed'; message: string };
+
+export function isSearchError(error: unknown): error is SearchError {
+  return typeof error === 'object' && error !== null && 'kind' in error;
+}
+
+export function searchErrorMessage(error: unknown): string {
+  if (isSearchError(error)) {
+    switch (error.kind) {
+      case 'invalidPattern':
+        return `Invalid search pattern: ${error.message}`;
+      case 'invalidGlob':
+        return `Invalid glob pattern "${error.pattern}": ${error.message}`;
+      case 'failed':
+        return `Search failed: ${error.message}`;
+    }
+  }
+  return error instanceof Error ? error.message : String(error);
+}
+
+export interface ReplaceLinePreview {
+  lineNumber: number;
+  oldLine: string;
+  newLine: string;
+}
+
+export interface ReplaceFileResult {
+  path: string;
+  replacements: number;
+  /** Changed lines, in a dry run only */
+  lines: ReplaceLinePreview[];
+}
+
+export interface ReplaceResponse {
+  dryRun: boolean;
+  filesChanged: number;
+  replacements: number;
+  files: ReplaceFileResult[];
+  /** Files with matches that could not be written; the others were still changed */
+  failures: { path: string; message: string }[];
+  /** Files over `maxFileSize`, left unchanged, and listed files that can't be read */
+  skipped: string[];
+  /** Undo journal operation that reverts the written files */
+  undoOperationId?: string;
+}
+
+export type SearchHistoryKind = 'search' | 'replace';
+
+export interface ReplaceFileChange {
+  path: string;
+  replacements: number;
+}
+
+export interface SearchHistoryEntry {
+  id: number;
+  kind: SearchHistoryKind;
+  params: ContentSearchParams;
+  /** The query looked like a secret and was stored masked; it can't be replayed */
+  queryMasked: boolean;
+  timestamp: number;
+  resultFiles: number;
+  resultMatches: number;
+  replacement: string | null;
+  fileChanges: ReplaceFileChange[];
+  /** Undo journal operation that reverts the replace */
+  undoOperationId: string | null;
 }
 
 interface CachedFile {
@@ -79,6 +258,11 @@ export class RepositoryService {
     return this.readFileWithCache(filePath);
   }
 
+  /** Read a file whose path isn't valid UTF-8, by the raw bytes the backend returned */
+  async readFileByRawPath(rawPathBytes: string): Promise<string> {
+    return invoke<string>('read_file_by_raw_path', { rawPathBytes });
+  }
+
   getRelativePath(fullPath: string, repositoryPath: string): string {
     return getRelativePath(fullPath, repositoryPath);
   }
@@ -321,31 +505,36 @@ export class RepositoryService {
     }
   }
 
+  // Move files or directories to new locations (supports cross-directory and
+  // cross-device moves). Each move succeeds or fails on its own.
+  async movePaths(
+    moves: MoveRequest[],
+    overwritePolicy: OverwritePolicy = 'fail'
+  ): Promise<MoveResult[]> {
+    const results = await invoke<MoveResult[]>('move_paths', { moves, overwritePolicy });
+
+    // Update cache
+    for (const result of results) {
+      if (result.to && this.fileCache.has(result.from)) {
+        const cachedData = this.fileCache.get(result.from);
+        this.fileCache.delete(result.from);
+        if (cachedData) {
+          this.fileCache.set(result.to, cachedData);
+        }
+      }
+    }
+    return results;
+  }
+
   // Move a file or directory to a new location (supports cross-directory moves)
   async moveFile(sourcePath: string, destinationPath: string): Promise<void> {
     try {
-      // Check if source exists
-      if (!(await exists(sourcePath))) {
-        throw new Error('Source file/directory does not exist');
-      }
-
-      // Check if destination already exists
-      if (await exists(destinationPath)) {
-        throw new Error(`A file or directory already exists at "${destinationPath}"`);
+      const [result] = await this.movePaths([{ from: sourcePath, to: destinationPath }]);
+      if (result?.error) {
+        throw new Error(result.error);
       }
 
-      await rename(sourcePath, destinationPath);
-
-      // Update cache
-      if (this.fileCache.has(sourcePath)) {
-        const cachedData = this.fileCache.get(sourcePath);
-        this.fileCache.delete(sourcePath);
-        if (cachedData) {
-          this.fileCache.set(destinationPath, cachedData);
-        }
-      }
-
-      logger.info('File/directory moved:', sourcePath, '->', destinationPath);
+      logger.info('File/directory moved:', sourcePath, '->', result?.to);
     } catch (error) {
       logger.error('Error moving file/directory:', error);
       throw new Error(
@@ -363,19 +552,46 @@ export class RepositoryService {
     return this.fileCache.size;
   }
 
-  async searchFileContent(query: string, rootPath: string): Promise<any[]> {
+  /**
+   * Search file contents under `rootPath`. An invalid regex is rethrown as a
+   * `SearchError` so the search box can show it; other failures give no results.
+   */
+  async searchFileContent(
+    query: string,
+    rootPath: string,
+    options: {
+      isRegex?: boolean;
+      caseSensitive?: boolean;
+      wholeWord?: boolean;
+      respectGitignore?: boolean;
+      /** Id to stop the search with `cancelSearch` */
+      searchId?: string;
+    } = {}
+  ): Promise<any[]> {
     if (!query.trim()) {
       return [];
     }
 
     try {
       const startTime = Date.now();
-      const results: SearchResult[] = await invoke('search_file_content', {
-        query: query.trim(),
-        rootPath,
-      });
+      const { results, depth_limited, truncated_subtrees, cancelled }: ContentSearchResponse =
+        await invoke('search_file_content', {
+          query: query.trim(),
+          rootPath,
+          isRegex: options.isRegex ?? null,
+          caseSensitive: options.caseSensitive ?? null,
+          wholeWord: options.wholeWord ?? null,
+          respectGitignore: options.respectGitignore ?? null,
+          searchId: options.searchId ?? null,
+        });
       const endTime = Date.now();
       logger.info(`searchFileContent took ${endTime - startTime}ms`);
+      if (depth_limited) {
+        logger.warn(`searchFileContent skipped ${truncated_subtrees} directories at the depth limit`);
+      }
+      if (cancelled) {
+        logger.info(`searchFileContent cancelled with ${results.length} partial results`);
+      }
 
       // Transform results to match existing interface
       return results.map((result) => ({
@@ -386,6 +602,9 @@ export class RepositoryService {
         })),
       }));
     } catch (error) {
+      if (isSearchError(error) && error.kind === 'invalidPattern') {
+        throw error;
+      }
       logger.error('Ripgrep search failed, falling back to slow search:', error);
 
       // Fallback to the original slow method if ripgrep fails
@@ -393,6 +612,99 @@ export class RepositoryService {
     }
   }
 
+  /**
+   * Run a content search, handing each file's matches to `onResult` as soon as they are
+   * found (unsorted). Resolves with the totals once the search is done.
+   */
+  async streamSearch(
+    params: ContentSearchParams,
+    onResult: (result: SearchResult) => void,
+    searchId?: string
+  ): Promise<SearchComplete> {
+    const requestId = crypto.randomUUID();
+    const unlisteners: UnlistenFn[] = [];
+    try {
+      // Listen before invoking so no early result is missed
+      unlisteners.push(
+        await listen<SearchResult>(`search-result-${requestId}`, (event) => onResult(event.payload))
+      );
+      let resolveComplete: (summary: SearchComplete) => void = () => {};
+      const complete = new Promise<SearchComplete>((resolve) => {
+        resolveComplete = resolve;
+      });
+      unlisteners.push(
+        await listen<SearchComplete>(`search-complete-${requestId}`, (event) =>
+          resolveComplete(event.payload)
+        )
+      );
+      await invoke<SearchComplete>('search_file_content_stream', {
+        ...params,
+        requestId,
+        searchId: searchId ?? null,
+      });
+      // Results are delivered before the completion event
+      return await complete;
+    } finally {
+      for (const unlisten of unlisteners) {
+        unlisten();
+      }
+    }
+  }
+
+  /**
+   * Stop a search started with `searchId`; it resolves with the results found so far.
+   * Returns false when no such search is running.
+   */
+  async cancelSearch(searchId: string): Promise<boolean> {
+    return invoke<boolean>('cancel_search', { searchId });
+  }
+
+  async listSearchHistory(
+    rootPath?: string,
+    kind?: SearchHistoryKind,
+    limit?: number
+  ): Promise<SearchHistoryEntry[]> {
+    return invoke<SearchHistoryEntry[]>('search_history_list', { rootPath, kind, limit });
+  }
+
+  /**
+   * Record a search run elsewhere, such as on a remote host. Repeating the project's last
+   * search refreshes its entry. Returns null when recording is off.
+   */
+  async addSearchHistory(
+    params: ContentSearchParams,
+    results: SearchResult[]
+  ): Promise<number | null> {
+    return invoke<number | null>('search_history_add', { params, results });
+  }
+
+  /** Re-run a recorded search. Replaying a replace only searches again. */
+  async replaySearch(id: number): Promise<ContentSearchResponse> {
+    return invoke<ContentSearchResponse>('search_history_replay', { id });
+  }
+
+  /**
+   * Replace the matches of a content search across files. Regex replacements expand `$1`.
+   * With `dryRun` nothing is written and the changed lines come back per file. Otherwise
+   * the written files can be reverted with `undoOperationId` and the replace is recorded
+   * in search history.
+   */
+  async searchAndReplace(
+    params: Omit<ContentSearchParams, 'sortBy' | 'countOnly'>,
+    replacement: string,
+    dryRun: boolean
+  ): Promise<ReplaceResponse> {
+    return invoke<ReplaceResponse>('search_and_replace', { ...params, replacement, dryRun });
+  }
+
+  async deleteSearchHistory(ids: number[]): Promise<number> {
+    return invoke<number>('search_history_delete', { ids });
+  }
+
+  async clearSearchHistory(rootPath?: string): Promise<number> {
+    return invoke<number>('search_history_clear', { rootPath });
+  }
+
   async writeFile(filePath: string, content: string): Promise<void> {
     try {
       // Ensure directory exists
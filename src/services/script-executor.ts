@@ -30,9 +30,13 @@ export interface ScriptExecutionResult {
  */
 export class ScriptExecutor {
   /**
-   * Execute a skill script via Tauri backend
+   * Execute a skill script via Tauri backend. With rootPath, the project's active
+   * environment profile applies beneath request.environment.
    */
-  async execute(request: ScriptExecutionRequest): Promise<ScriptExecutionResult> {
+  async execute(
+    request: ScriptExecutionRequest,
+    rootPath?: string
+  ): Promise<ScriptExecutionResult> {
     try {
       logger.info('Executing skill script:', {
         script_path: request.script_path,
@@ -49,6 +53,7 @@ export class ScriptExecutor {
           timeout_ms: request.timeout_ms || null,
           environment: request.environment || null,
         },
+        rootPath: rootPath ?? null,
       });
 
       logger.info('Script execution result:', {
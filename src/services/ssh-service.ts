@@ -0,0 +1,116 @@
+import { invoke } from '@tauri-apps/api/core';
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
+
+/**
+ * Passwords and key passphrases are stored with saveSshSecret and referenced by name,
+ * so they never travel with connection settings
+ */
+export type SshAuth =
+  | { method: 'agent' }
+  | { method: 'key'; keyPath: string; passphraseSecret?: string }
+  | { method: 'password'; passwordSecret: string };
+
+export interface HostKeyPrompt {
+  requestId: string;
+  host: string;
+  port: number;
+  keyType: string;
+  /** OpenSSH style, e.g. SHA256:base64 */
+  fingerprint: string;
+  /** 'changed' means the host is known with a different key */
+  status: 'unknown' | 'changed';
+}
+
+export interface RemoteEntry {
+  name: string;
+  path: string;
+  isDirectory: boolean;
+  isSymlink: boolean;
+  size: number;
+  modifiedTime: number | null;
+}
+
+export interface RemoteExecResult {
+  stdout: string;
+  stderr: string;
+  exitCode: number;
+  truncated: boolean;
+}
+
+// Same shape as the results of search_file_content
+export interface RemoteSearchResult {
+  file_path: string;
+  matches: Array<{
+    line_number: number;
+    line_content: string;
+    byte_offset: number;
+    /** Zero when rg or grep found the match; they don't report columns */
+    column_start: number;
+    column_end: number;
+    match_ranges: [number, number][];
+  }>;
+}
+
+export async function saveSshSecret(name: string, value: string): Promise<void> {
+  return invoke('ssh_save_secret', { name, value });
+}
+
+/**
+ * Resolves with a session id. Unknown or changed host keys are sent to
+ * onHostKeyVerification and the connection waits for confirmHostKey.
+ */
+export async function connect(
+  host: string,
+  user: string,
+  auth: SshAuth,
+  port?: number
+): Promise<string> {
+  return invoke('ssh_connect', { host, port, user, auth });
+}
+
+export async function onHostKeyVerification(
+  handler: (prompt: HostKeyPrompt) => void
+): Promise<UnlistenFn> {
+  return listen<HostKeyPrompt>('ssh-host-key-verification', (event) => handler(event.payload));
+}
+
+export async function confirmHostKey(requestId: string, accept: boolean): Promise<void> {
+  return invoke('ssh_confirm_host_key', { requestId, accept });
+}
+
+export async function listDir(sessionId: string, path: string): Promise<RemoteEntry[]> {
+  return invoke('ssh_list_dir', { sessionId, path });
+}
+
+export async function readFile(
+  sessionId: string,
+  path: string,
+  maxBytes?: number
+): Promise<string> {
+  return invoke('ssh_read_file', { sessionId, path, maxBytes });
+}
+
+export async function writeFile(sessionId: string, path: string, content: string): Promise<void> {
+  return invoke('ssh_write_file', { sessionId, path, content });
+}
+
+export async function exec(
+  sessionId: string,
+  command: string,
+  timeoutMs?: number
+): Promise<RemoteExecResult> {
+  return invoke('ssh_exec', { sessionId, command, timeoutMs });
+}
+
+export async function search(
+  sessionId: string,
+  query: string,
+  rootPath: string,
+  options: { maxResults?: number; timeoutMs?: number } = {}
+): Promise<RemoteSearchResult[]> {
+  return invoke('ssh_search', { sessionId, query, rootPath, ...options });
+}
+
+export async function disconnect(sessionId: string): Promise<void> {
+  return invoke('ssh_disconnect', { sessionId });
+}
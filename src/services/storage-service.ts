@@ -0,0 +1,65 @@
+import { invoke } from '@tauri-apps/api/core';
+
+export type StorageCategory =
+  | 'codeIndex'
+  | 'logs'
+  | 'backups'
+  | 'httpCache'
+  | 'docsets'
+  | 'editJournal'
+  | 'tempWorkspaces';
+
+export type EvictionStrategy = 'leastRecentlyUsed' | 'oldestFirst' | 'never';
+
+export interface CategoryUsage {
+  category: StorageCategory;
+  path: string;
+  sizeBytes: number;
+  fileCount: number;
+  /** Seconds since the epoch */
+  oldestModified: number | null;
+  newestModified: number | null;
+  quotaBytes: number | null;
+  strategy: EvictionStrategy;
+}
+
+export interface EvictionRecord {
+  category: StorageCategory;
+  path: string;
+  sizeBytes: number;
+  /** Seconds since the epoch */
+  evictedAt: number;
+}
+
+export interface StorageReport {
+  categories: CategoryUsage[];
+  totalBytes: number;
+  /** Evictions since the previous report */
+  evictions: EvictionRecord[];
+}
+
+export interface StorageQuotas {
+  maxBytes: Partial<Record<StorageCategory, number>>;
+  allowCodeIndexEviction: boolean;
+}
+
+export async function getStorageReport(): Promise<StorageReport> {
+  return invoke('get_storage_report');
+}
+
+/**
+ * Cap a category, or remove its cap with null. Enforced periodically by the backend.
+ */
+export async function setStorageQuota(
+  category: StorageCategory,
+  maxBytes: number | null
+): Promise<StorageQuotas> {
+  return invoke('set_storage_quota', { category, maxBytes });
+}
+
+/**
+ * Code indexes are never evicted unless this is allowed
+ */
+export async function setCodeIndexEviction(allowed: boolean): Promise<StorageQuotas> {
+  return invoke('set_code_index_eviction', { allowed });
+}
@@ -100,6 +100,8 @@ class TerminalService {
         cols,
         rows,
         preferredShell: preferredShell === 'auto' ? null : preferredShell,
+        // Terminals open at the project root; its active environment profile applies
+        rootPath: cwd ?? null,
       });
 
       const session: TerminalSession = {
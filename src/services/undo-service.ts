@@ -0,0 +1,50 @@
+import { invoke } from '@tauri-apps/api/core';
+
+export type PriorContent =
+  | { kind: 'absent' }
+  | { kind: 'stored'; blobFile: string }
+  | { kind: 'gitBlob'; repoPath: string; oid: string }
+  | { kind: 'withheld' }
+  | { kind: 'tooLarge' };
+
+export interface UndoFileEntry {
+  path: string;
+  beforeHash: string | null;
+  afterHash: string | null;
+  prior: PriorContent;
+}
+
+/** A file or folder moved by the operation; undoing moves it back */
+export interface UndoMove {
+  from: string;
+  to: string;
+}
+
+/** A file change made by the backend, such as a workspace edit, save, move or replace */
+export interface UndoOperation {
+  id: string;
+  label: string;
+  createdAtMs: number;
+  files: UndoFileEntry[];
+  moves?: UndoMove[];
+}
+
+export interface UndoRevertResult {
+  operationId: string;
+  restored: string[];
+  /** Edited again after the operation; left untouched */
+  conflicted: string[];
+  /** Prior content was not kept (secret files, large files not clean in git) */
+  skipped: { path: string; reason: string }[];
+}
+
+/**
+ * Most recent backend operations first
+ */
+export async function listRecentUndoOperations(limit?: number): Promise<UndoOperation[]> {
+  return invoke('undo_list_recent', { limit });
+}
+
+export async function revertUndoOperation(operationId: string): Promise<UndoRevertResult> {
+  return invoke('undo_revert', { operationId });
+}
@@ -0,0 +1,85 @@
+import { invoke } from '@tauri-apps/api/core';
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
+
+export interface WsAcquireOptions {
+  /** Handshake headers, used when a new connection is opened */
+  headers?: Record<string, string>;
+  /** Last sequence number seen; buffered messages after it are replayed. Unset replays all. */
+  afterSeq?: number;
+  /** How long the connection stays open after its last release */
+  lingerMs?: number;
+}
+
+export interface PooledMessage {
+  seq: number;
+  data: string;
+}
+
+export interface WsAcquired {
+  id: string;
+  /** An open connection was handed out rather than a new one */
+  reused: boolean;
+  replay: PooledMessage[];
+  /** Messages after `afterSeq` that had already left the replay buffer */
+  missed: number;
+}
+
+export interface WsPoolEntryStatus {
+  purpose: string;
+  id: string;
+  url: string;
+  refs: number;
+  healthy: boolean;
+  /** Released by everyone and waiting out its linger */
+  lingering: boolean;
+  connectedAt: number;
+  lastMessageAt: number | null;
+  messagesReceived: number;
+  buffered: number;
+  lastError: string | null;
+}
+
+export type WsPoolEvent =
+  | { kind: 'message'; purpose: string; id: string; seq: number; data: string }
+  | { kind: 'closed'; purpose: string; id: string; error: string | null };
+
+/**
+ * The pooled connection for `purpose`, opened if there is no healthy one. After a webview
+ * reload, acquiring again returns the same id and the buffered messages after `afterSeq`.
+ */
+export async function acquireWebSocket(
+  purpose: string,
+  url: string,
+  options?: WsAcquireOptions
+): Promise<WsAcquired> {
+  return invoke('ws_acquire', { purpose, url, options: options ?? null });
+}
+
+/**
+ * Drop one acquisition; returns how many are left
+ */
+export async function releaseWebSocket(purpose: string): Promise<number> {
+  return invoke('ws_release', { purpose });
+}
+
+export async function sendPooledWebSocket(purpose: string, message: string): Promise<void> {
+  return invoke('ws_pool_send', { purpose, message });
+}
+
+export async function getWebSocketPoolStatus(): Promise<WsPoolEntryStatus[]> {
+  return invoke('ws_pool_status');
+}
+
+/**
+ * Listen to messages and closes of one purpose's pooled connection
+ */
+export async function onPooledWebSocketEvent(
+  purpose: string,
+  handler: (event: WsPoolEvent) => void
+): Promise<UnlistenFn> {
+  return listen<WsPoolEvent>('ws-pool-event', (event) => {
+    if (event.payload.purpose === purpose) {
+      handler(event.payload);
+    }
+  });
+}
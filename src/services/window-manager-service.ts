@@ -1,4 +1,5 @@
 import { invoke } from '@tauri-apps/api/core';
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
 import { logger } from '@/lib/logger';
 
 // Module-level state for new window flag
@@ -23,6 +24,55 @@ export interface WindowInfo {
   project_id?: string;
   root_path?: string;
   title: string;
+  /** Root of the main repository when root_path is one of its linked git worktrees */
+  main_repo_path?: string | null;
+  /** Label of the window that has the main repository open */
+  main_repo_window?: string | null;
+  /** Repository or workspace root above root_path, when a subdirectory was opened */
+  inferred_root?: string | null;
+  /** root_path relative to inferred_root */
+  selected_subpath?: string | null;
+}
+
+export type RootMarker =
+  | 'git'
+  | 'package_workspaces'
+  | 'cargo_workspace'
+  | 'go_work'
+  | 'pnpm_workspace';
+
+/**
+ * Payload of the "project-root-inferred" event, emitted to a window opened on a
+ * subdirectory so it can offer to open the whole repository instead
+ */
+export interface InferredRoot {
+  root: string;
+  requestedPath: string;
+  /** Requested path relative to root, '/'-separated */
+  subpath: string;
+  /** Markers found on the way up, innermost first */
+  evidence: { path: string; marker: RootMarker }[];
+}
+
+/**
+ * Payload of the window's "broadcast-request-<label>" event. Answer it with
+ * WindowManagerService.replyToBroadcast.
+ */
+export interface BroadcastRequest {
+  correlationId: string;
+  requestKind: string;
+  payload: unknown;
+}
+
+export type BroadcastReplyStatus = 'replied' | 'timedOut' | 'closed';
+
+export interface BroadcastResult {
+  correlationId: string;
+  requestKind: string;
+  /** One entry per targeted window, ordered by label */
+  replies: { windowLabel: string; status: BroadcastReplyStatus; payload: unknown | null }[];
+  allReplied: boolean;
+  elapsedMs: number;
 }
 
 export class WindowManagerService {
@@ -50,6 +100,13 @@ export class WindowManagerService {
     }
   }
 
+  /**
+   * The repository or workspace root enclosing a directory, or null when none is found
+   */
+  static async inferProjectRoot(path: string): Promise<InferredRoot | null> {
+    return invoke<InferredRoot | null>('infer_project_root', { path });
+  }
+
   /**
    * Check if this window was just created as a new window
    * Should be called once on window startup to determine if auto-loading should be skipped
@@ -89,6 +146,54 @@ export class WindowManagerService {
     }
   }
 
+  /**
+   * Ask every project window, or those with rootPathFilter open, to handle a request and
+   * wait for their replies. Windows that do not answer in time are marked timed out.
+   */
+  static async broadcastRequest(
+    requestKind: string,
+    payload: unknown,
+    options?: { rootPathFilter?: string; timeoutMs?: number }
+  ): Promise<BroadcastResult> {
+    return invoke<BroadcastResult>('broadcast_request', {
+      rootPathFilter: options?.rootPathFilter ?? null,
+      requestKind,
+      payload,
+      timeoutMs: options?.timeoutMs ?? null,
+    });
+  }
+
+  /**
+   * Handle broadcast requests sent to this window. The handler's result is sent back as
+   * the window's reply.
+   */
+  static async onBroadcastRequest(
+    windowLabel: string,
+    handler: (request: BroadcastRequest) => Promise<unknown> | unknown
+  ): Promise<UnlistenFn> {
+    return listen<BroadcastRequest>(`broadcast-request-${windowLabel}`, async (event) => {
+      const request = event.payload;
+      try {
+        const reply = await handler(request);
+        await WindowManagerService.replyToBroadcast(
+          request.correlationId,
+          windowLabel,
+          reply ?? null
+        );
+      } catch (error) {
+        logger.error(`Failed to answer broadcast ${request.requestKind}:`, error);
+      }
+    });
+  }
+
+  static async replyToBroadcast(
+    correlationId: string,
+    windowLabel: string,
+    payload: unknown
+  ): Promise<void> {
+    await invoke('reply_to_broadcast', { correlationId, windowLabel, payload });
+  }
+
   /**
    * Get current window label
    */
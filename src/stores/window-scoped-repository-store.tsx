@@ -6,6 +6,7 @@ import { logger } from '@/lib/logger';
 import { getLocale, type SupportedLocale } from '@/locales';
 import { databaseService } from '@/services/database-service';
 import { fastDirectoryTreeService } from '@/services/fast-directory-tree-service';
+import { getPermissionDeniedPath } from '@/services/path-access-service';
 import { repositoryService } from '@/services/repository-service';
 import { WindowManagerService } from '@/services/window-manager-service';
 import { WindowRestoreService } from '@/services/window-restore-service';
@@ -249,7 +250,12 @@ function createRepositoryStore() {
           isLoading: false,
         });
 
-        toast.error(getTranslations().RepositoryStore.errors.failedToOpen(errorMessage));
+        const deniedPath = getPermissionDeniedPath(error);
+        toast.error(
+          deniedPath
+            ? getTranslations().RepositoryStore.errors.permissionDenied(deniedPath)
+            : getTranslations().RepositoryStore.errors.failedToOpen(errorMessage)
+        );
         throw error;
       }
     },
@@ -77,6 +77,7 @@ describe('bashTool', () => {
       cwd: '/test/root',
       timeoutMs: 300000,
       idleTimeoutMs: 60000,
+      captureMetadata: true,
     });
   });
 
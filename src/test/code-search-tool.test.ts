@@ -28,6 +28,14 @@ import { codeSearch } from '@/lib/tools/code-search-tool';
 
 const PROJECT_ROOT = '/Users/test/project';
 
+// search_file_content wraps the results with the walk's depth report
+const searchResponse = (results: unknown[]) => ({
+  results,
+  depth_limited: false,
+  truncated_subtrees: 0,
+  cancelled: false,
+});
+
 describe('codeSearch Tool', () => {
   beforeEach(() => {
     vi.clearAllMocks();
@@ -68,7 +76,7 @@ describe('codeSearch Tool', () => {
       },
     ];
 
-    mockInvoke.mockResolvedValue(mockResult);
+    mockInvoke.mockResolvedValue(searchResponse(mockResult));
 
     const result = await codeSearch.execute?.({
       pattern: 'console.log',
@@ -86,6 +94,21 @@ describe('codeSearch Tool', () => {
     });
   });
 
+  it('should say when deeply nested directories were not searched', async () => {
+    mockInvoke.mockResolvedValue({ results: [], depth_limited: true, truncated_subtrees: 1 });
+
+    const result = await codeSearch.execute?.({
+      pattern: 'needle',
+      path: PROJECT_ROOT,
+    });
+
+    const actualResult = await normalizeResult(result);
+
+    expect(actualResult.success).toBe(true);
+    expect(actualResult.result).toContain('No matches found');
+    expect(actualResult.result).toContain('1 deeply nested directory was not searched');
+  });
+
   it('should handle search with file type filters', async () => {
     const mockResult = [
       {
@@ -100,7 +123,7 @@ describe('codeSearch Tool', () => {
       },
     ];
 
-    mockInvoke.mockResolvedValue(mockResult);
+    mockInvoke.mockResolvedValue(searchResponse(mockResult));
 
     const result = await codeSearch.execute?.({
       pattern: 'function',
@@ -133,7 +156,7 @@ describe('codeSearch Tool', () => {
       },
     ];
 
-    mockInvoke.mockResolvedValue(mockResult);
+    mockInvoke.mockResolvedValue(searchResponse(mockResult));
 
     const result = await codeSearch.execute?.({
       pattern: 'import',
@@ -165,7 +188,7 @@ describe('codeSearch Tool', () => {
       },
     ];
 
-    mockInvoke.mockResolvedValue(mockResult);
+    mockInvoke.mockResolvedValue(searchResponse(mockResult));
 
     const result = await codeSearch.execute?.({
       pattern: "don't match",
@@ -193,7 +216,7 @@ describe('codeSearch Tool', () => {
       }>;
     }> = []; // Empty array when no matches found
 
-    mockInvoke.mockResolvedValue(mockResult);
+    mockInvoke.mockResolvedValue(searchResponse(mockResult));
 
     const result = await codeSearch.execute?.({
       pattern: 'nonexistent',
@@ -238,6 +261,25 @@ describe('codeSearch Tool', () => {
     expect(actualResult.error).toBe('Permission denied');
   });
 
+  it('should report an invalid pattern from the structured search error', async () => {
+    mockInvoke.mockRejectedValue({
+      kind: 'invalidPattern',
+      message: 'regex parse error: repetition operator missing expression',
+    });
+
+    const result = await codeSearch.execute?.({
+      pattern: 'count++',
+      path: '/Users/test/project',
+    });
+
+    const actualResult = await normalizeResult(result);
+
+    expect(actualResult.success).toBe(false);
+    expect(actualResult.error).toBe(
+      'Invalid search pattern: regex parse error: repetition operator missing expression'
+    );
+  });
+
   it('should handle error with stderr', async () => {
     const error = new Error('Error: Invalid regex pattern');
     mockInvoke.mockRejectedValue(error);
@@ -278,7 +320,7 @@ describe('codeSearch Tool', () => {
       },
     ];
 
-    mockInvoke.mockResolvedValue(mockResult);
+    mockInvoke.mockResolvedValue(searchResponse(mockResult));
 
     const result = await codeSearch.execute?.({
       pattern: 'fragment.*pipeline.*driver',
@@ -321,7 +363,7 @@ describe('codeSearch Tool', () => {
       },
     ];
 
-    mockInvoke.mockResolvedValue(mockResult);
+    mockInvoke.mockResolvedValue(searchResponse(mockResult));
 
     const result = await codeSearch.execute?.({
       pattern: 'ForkJoinTask',
@@ -360,7 +402,7 @@ describe('codeSearch Tool', () => {
         },
       ];
 
-      mockInvoke.mockResolvedValue(mockResult);
+      mockInvoke.mockResolvedValue(searchResponse(mockResult));
 
       const result = await codeSearch.execute?.({
         pattern: 'console.log',
@@ -398,7 +440,7 @@ describe('codeSearch Tool', () => {
         },
       ];
 
-      mockInvoke.mockResolvedValue(mockResult);
+      mockInvoke.mockResolvedValue(searchResponse(mockResult));
 
       const result = await codeSearch.execute?.({
         pattern: 'helper',
@@ -419,7 +461,7 @@ describe('codeSearch Tool', () => {
     });
 
     it('should handle "./src" relative path correctly', async () => {
-      mockInvoke.mockResolvedValue([]);
+      mockInvoke.mockResolvedValue(searchResponse([]));
 
       const result = await codeSearch.execute?.({
         pattern: 'test',
@@ -440,7 +482,7 @@ describe('codeSearch Tool', () => {
     });
 
     it('should handle "../other" relative path correctly', async () => {
-      mockInvoke.mockResolvedValue([]);
+      mockInvoke.mockResolvedValue(searchResponse([]));
 
       const result = await codeSearch.execute?.({
         pattern: 'test',
@@ -462,7 +504,7 @@ describe('codeSearch Tool', () => {
 
     it('should use absolute path directly without joining', async () => {
       const absolutePath = '/absolute/path/to/search';
-      mockInvoke.mockResolvedValue([]);
+      mockInvoke.mockResolvedValue(searchResponse([]));
 
       const result = await codeSearch.execute?.({
         pattern: 'test',
@@ -135,9 +135,16 @@ export class TestIPCHandler {
       );
     }
     if (cmd === 'search_file_content') {
-      return this.fsAdapter.searchContent(
-        args as { query: string; rootPath: string; fileTypes?: string[]; excludeDirs?: string[] }
-      );
+      return {
+        results: this.fsAdapter.searchContent(
+          args as { query: string; rootPath: string; fileTypes?: string[]; excludeDirs?: string[] }
+        ),
+        depth_limited: false,
+        truncated_subtrees: 0,
+        cancelled: false,
+        stats: { files_scanned: 0, skipped_size: 0, skipped_binary: 0 },
+        skipped: [],
+      };
     }
 
     // Shell command
@@ -192,7 +199,7 @@ export class TestIPCHandler {
 
     // Glob search
     if (cmd === 'search_files_by_glob') {
-      return [];
+      return { results: [], depth_limited: false, truncated_subtrees: 0 };
     }
 
     // List files
@@ -261,7 +268,7 @@ export class TestIPCHandler {
       return [];
     }
     if (cmd === 'code_nav_find_references_hybrid') {
-      return [];
+      return { references: [], sparse_checkout_active: false };
     }
     if (cmd === 'code_nav_get_indexed_files') {
       return [];
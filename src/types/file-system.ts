@@ -8,6 +8,31 @@ export interface FileNode {
   modified_time?: number; // File modification timestamp
   size?: number; // File size in bytes
   is_git_ignored?: boolean; // Indicates if file/directory is git-ignored (starts with .)
+  snapshot_id?: string; // Set on the root when built with a snapshot for diffing
+  path_is_lossy?: boolean; // Path isn't valid UTF-8; `path` and `name` are percent-encoded
+  raw_path_bytes?: string; // Base64 path bytes, set when path_is_lossy
+  error?: FileNodeError; // Set when the directory couldn't be listed; children are unknown
+  warnings?: TreeWarning[]; // Set on the root: paths that couldn't be read while building
+  depth_report?: DepthReport; // Set on the root: directories left lazy at the depth limit
+}
+
+export interface DepthReport {
+  depth_limited: boolean;
+  truncated_subtrees: number;
+}
+
+export interface FileNodeError {
+  kind: 'permission_denied' | 'io';
+  message: string;
+}
+
+export interface TreeWarning extends FileNodeError {
+  path: string;
+}
+
+export interface DirectoryChildren {
+  children: FileNode[];
+  warnings: TreeWarning[];
 }
 
 export interface OpenFile {
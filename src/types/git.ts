@@ -15,6 +15,8 @@ export interface FileStatus {
   path: string;
   status: GitFileStatus;
   staged: boolean;
+  /** Previous path of a renamed file; only set when status was requested with detectRenames */
+  oldPath: string | null;
 }
 
 export interface BranchInfo {
@@ -75,9 +77,149 @@ export interface CommitInfo {
   timestamp: number;
 }
 
+export interface GitHookInfo {
+  name: string;
+  path: string;
+  /** git ignores hooks without the executable bit */
+  executable: boolean;
+}
+
+export interface GitHooksInfo {
+  hooksDir: string;
+  /** Raw core.hooksPath value when it overrides .git/hooks (e.g. ".husky") */
+  hooksPathOverride: string | null;
+  hooks: GitHookInfo[];
+}
+
+export interface SparseCheckoutInfo {
+  active: boolean;
+  /** Patterns are directories (`git sparse-checkout --cone`) */
+  coneMode: boolean;
+  patternCount: number;
+}
+
+export interface PartialCloneInfo {
+  /** Remote missing objects are fetched from on demand */
+  remote: string;
+  /** e.g. "blob:none" */
+  filter: string | null;
+}
+
+export interface RepositoryInfo {
+  /** Working directory; null for a bare repository */
+  root: string | null;
+  gitDir: string;
+  isLinkedWorktree: boolean;
+  sparseCheckout: SparseCheckoutInfo;
+  partialClone: PartialCloneInfo | null;
+}
+
+export interface SummaryHunk {
+  header: string;
+  /** Lines prefixed with '+', '-' or ' ' */
+  patch: string;
+  truncated: boolean;
+}
+
+export interface StagedFileSummary {
+  path: string;
+  oldPath: string | null;
+  status: GitFileStatus;
+  additions: number;
+  deletions: number;
+  binary: boolean;
+  /** Blob sizes in bytes, reported for binary files */
+  oldSize: number | null;
+  newSize: number | null;
+  hunks: SummaryHunk[];
+  truncated: boolean;
+  omittedHunks: number;
+}
+
+export interface StagedSummary {
+  files: StagedFileSummary[];
+  totals: {
+    filesChanged: number;
+    additions: number;
+    deletions: number;
+    binaryFiles: number;
+  };
+  /** Staged files beyond maxFiles */
+  omittedFiles: string[];
+}
+
+export interface CommitResult {
+  commitHash: string;
+  /** Commit hooks the repository has that were not run */
+  hooksSkipped: string[];
+}
+
+export interface FileHistoryEntry {
+  commitId: string;
+  shortId: string;
+  summary: string;
+  authorName: string;
+  authorEmail: string;
+  /** Seconds since the epoch */
+  timestamp: number;
+  /** Path of the file in this commit */
+  path: string;
+  /** Path before this commit when the commit renamed the file */
+  previousPath: string | null;
+  changeType: GitFileStatus;
+  additions: number;
+  deletions: number;
+}
+
+export interface FileAtCommit {
+  commitId: string;
+  path: string;
+  size: number;
+  /** null for binary files */
+  content: string | null;
+  isBinary: boolean;
+  truncated: boolean;
+}
+
+export interface BlameCommit {
+  commitId: string;
+  shortId: string;
+  summary: string;
+  authorName: string;
+  authorEmail: string;
+  /** Seconds since the epoch */
+  timestamp: number;
+}
+
+export type AnnotationStatus = 'committed' | 'notCommittedYet' | 'outOfRange';
+
+export interface LineAnnotation {
+  /** 1-based */
+  line: number;
+  status: AnnotationStatus;
+  commit: BlameCommit | null;
+  /** e.g. "3 weeks ago" */
+  relativeTime: string | null;
+}
+
 // Helper types for UI components
 export type LineChange = [number, DiffLineType];
 
 export interface FileStatusMap {
   [path: string]: [GitFileStatus, boolean]; // [status, isStaged]
 }
+
+/** Changed files under a directory; zero counts are omitted */
+export interface DirectoryStatus {
+  modified?: number; // Includes renames
+  added?: number;
+  deleted?: number;
+  untracked?: number;
+  conflicted?: number;
+  dominant: GitFileStatus;
+}
+
+/** Keyed by repo-relative directory, '' for the repository root */
+export interface DirectoryStatusMap {
+  [directory: string]: DirectoryStatus;
+}